@@ -0,0 +1,5 @@
+-- Remove processing run tracking
+DROP INDEX IF EXISTS idx_feed_items_processing_run;
+ALTER TABLE feed_items DROP COLUMN processing_run_id;
+DROP TABLE IF EXISTS processing_run_actions;
+DROP TABLE IF EXISTS processing_runs;
@@ -0,0 +1,33 @@
+-- Track each processing run so its results can be inspected or rolled back
+CREATE TABLE processing_runs (
+    id TEXT PRIMARY KEY,
+    imap_account_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running', -- 'running', 'completed', 'failed' or 'rolled_back'
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    emails_processed INTEGER NOT NULL DEFAULT 0,
+    items_created INTEGER NOT NULL DEFAULT 0,
+    error_message TEXT,
+    FOREIGN KEY (imap_account_id) REFERENCES imap_accounts(id) ON DELETE CASCADE
+);
+
+-- Post-processing actions applied to the mailbox during a run
+CREATE TABLE processing_run_actions (
+    id TEXT PRIMARY KEY,
+    processing_run_id TEXT NOT NULL,
+    feed_item_id TEXT,
+    folder TEXT NOT NULL,
+    uid BIGINT NOT NULL,
+    email_message_id TEXT,
+    action TEXT NOT NULL,
+    target_folder TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (processing_run_id) REFERENCES processing_runs(id) ON DELETE CASCADE
+);
+
+-- Remember which run created each feed item
+ALTER TABLE feed_items ADD COLUMN processing_run_id TEXT NULL REFERENCES processing_runs(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_processing_runs_account ON processing_runs(imap_account_id);
+CREATE INDEX idx_processing_run_actions_run ON processing_run_actions(processing_run_id);
+CREATE INDEX idx_feed_items_processing_run ON feed_items(processing_run_id);
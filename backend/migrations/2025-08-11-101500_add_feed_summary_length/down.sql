@@ -0,0 +1,2 @@
+-- Remove per-feed summary length
+ALTER TABLE feeds DROP COLUMN summary_length;
@@ -0,0 +1,2 @@
+-- Per-feed length of generated item descriptions (NULL uses the default of 500 characters)
+ALTER TABLE feeds ADD COLUMN summary_length INTEGER NULL;
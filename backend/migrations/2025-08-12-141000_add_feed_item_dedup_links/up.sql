@@ -0,0 +1,6 @@
+-- Cross-feed deduplication: items can link to a canonical copy instead of storing the body again
+ALTER TABLE feed_items ADD COLUMN content_hash TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN canonical_item_id TEXT NULL REFERENCES feed_items(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_feed_items_content_hash ON feed_items(content_hash);
+CREATE INDEX idx_feed_items_canonical ON feed_items(canonical_item_id);
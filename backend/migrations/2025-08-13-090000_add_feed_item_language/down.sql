@@ -0,0 +1,2 @@
+-- Remove detected language from feed items
+ALTER TABLE feed_items DROP COLUMN language;
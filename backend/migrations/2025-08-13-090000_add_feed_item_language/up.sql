@@ -0,0 +1,2 @@
+-- Detected language of each feed item (ISO 639-3 code, 'und' when undetermined)
+ALTER TABLE feed_items ADD COLUMN language TEXT NULL;
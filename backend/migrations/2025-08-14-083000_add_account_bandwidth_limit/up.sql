@@ -0,0 +1,6 @@
+-- Per-account bandwidth limit for IMAP connections (NULL = unlimited)
+ALTER TABLE imap_accounts ADD COLUMN max_bytes_per_second INTEGER NULL;
+
+-- Bytes transferred over IMAP during each processing run
+ALTER TABLE processing_runs ADD COLUMN bytes_received BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE processing_runs ADD COLUMN bytes_sent BIGINT NOT NULL DEFAULT 0;
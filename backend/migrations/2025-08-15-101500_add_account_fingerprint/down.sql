@@ -0,0 +1,2 @@
+-- Remove mailbox fingerprints
+ALTER TABLE imap_accounts DROP COLUMN fingerprint;
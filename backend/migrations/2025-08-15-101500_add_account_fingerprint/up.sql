@@ -0,0 +1,3 @@
+-- Fingerprint of the mailbox behind an account (server greeting + INBOX UIDVALIDITY),
+-- used to spot accounts that reach the same mailbox under different names
+ALTER TABLE imap_accounts ADD COLUMN fingerprint TEXT NULL;
@@ -0,0 +1,4 @@
+-- Per-feed locale and timezone for display dates, and an optional item title template
+ALTER TABLE feeds ADD COLUMN locale TEXT NULL;
+ALTER TABLE feeds ADD COLUMN timezone TEXT NULL;
+ALTER TABLE feeds ADD COLUMN title_template TEXT NULL;
@@ -0,0 +1,4 @@
+-- Remove observe-only rules
+DROP INDEX IF EXISTS idx_rule_matches_rule_message;
+DROP TABLE IF EXISTS rule_matches;
+ALTER TABLE email_rules DROP COLUMN observe_only;
@@ -0,0 +1,16 @@
+-- Observe-only rules record their matches for preview instead of creating feed items
+ALTER TABLE email_rules ADD COLUMN observe_only BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Emails matched by observe-only rules, one row per rule and message
+CREATE TABLE rule_matches (
+    id TEXT PRIMARY KEY,
+    email_rule_id TEXT NOT NULL,
+    email_message_id TEXT NOT NULL,
+    email_subject TEXT NOT NULL,
+    email_from TEXT NOT NULL,
+    email_date TEXT NOT NULL,
+    matched_at TEXT NOT NULL,
+    FOREIGN KEY (email_rule_id) REFERENCES email_rules(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_rule_matches_rule_message ON rule_matches(email_rule_id, email_message_id);
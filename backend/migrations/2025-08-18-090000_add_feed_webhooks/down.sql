@@ -0,0 +1,4 @@
+-- Remove feed webhooks
+ALTER TABLE feeds DROP COLUMN webhook_body;
+ALTER TABLE feeds DROP COLUMN webhook_method;
+ALTER TABLE feeds DROP COLUMN webhook_url;
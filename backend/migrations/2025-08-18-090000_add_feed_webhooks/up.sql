@@ -0,0 +1,4 @@
+-- Outgoing HTTP call fired for each new item of a feed
+ALTER TABLE feeds ADD COLUMN webhook_url TEXT NULL;
+ALTER TABLE feeds ADD COLUMN webhook_method TEXT NULL;
+ALTER TABLE feeds ADD COLUMN webhook_body TEXT NULL;
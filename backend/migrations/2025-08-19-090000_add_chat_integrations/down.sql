@@ -0,0 +1,3 @@
+-- Remove chat integrations
+DROP INDEX IF EXISTS idx_chat_integrations_feed;
+DROP TABLE IF EXISTS chat_integrations;
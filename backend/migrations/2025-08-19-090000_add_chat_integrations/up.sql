@@ -0,0 +1,18 @@
+-- Chat integrations posting new feed items to Slack, Discord or Matrix
+CREATE TABLE chat_integrations (
+    id TEXT PRIMARY KEY,
+    feed_id TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    webhook_url TEXT,
+    matrix_homeserver TEXT,
+    matrix_room_id TEXT,
+    matrix_access_token TEXT,
+    message_template TEXT,
+    rate_limit_per_minute INTEGER,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_chat_integrations_feed ON chat_integrations(feed_id);
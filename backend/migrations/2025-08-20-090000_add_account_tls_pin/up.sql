@@ -0,0 +1,3 @@
+-- Pinned TLS certificate or public key fingerprint of the IMAP server;
+-- connections fail when the server presents anything else
+ALTER TABLE imap_accounts ADD COLUMN tls_pin TEXT NULL;
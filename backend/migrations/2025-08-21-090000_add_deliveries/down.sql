@@ -0,0 +1,3 @@
+-- Remove the outbound delivery queue
+DROP INDEX IF EXISTS idx_deliveries_status;
+DROP TABLE IF EXISTS deliveries;
@@ -0,0 +1,24 @@
+-- Outbound delivery queue shared by webhooks and chat integrations; each row
+-- is a rendered HTTP request retried with backoff until it succeeds or fails
+-- for good
+CREATE TABLE deliveries (
+    id TEXT PRIMARY KEY,
+    feed_id TEXT NOT NULL,
+    item_id TEXT,
+    kind TEXT NOT NULL,
+    queue TEXT NOT NULL,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    headers TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    last_error TEXT,
+    delivered_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_deliveries_status ON deliveries(status, created_at);
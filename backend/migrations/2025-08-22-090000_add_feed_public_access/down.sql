@@ -0,0 +1,2 @@
+-- Remove per-feed public access overrides
+ALTER TABLE feeds DROP COLUMN public_access;
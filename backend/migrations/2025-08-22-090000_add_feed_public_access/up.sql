@@ -0,0 +1,3 @@
+-- Whether the feed's anonymous /feeds/* endpoints are served; NULL follows
+-- the FEED_PUBLIC_ENDPOINTS setting
+ALTER TABLE feeds ADD COLUMN public_access BOOLEAN NULL;
@@ -0,0 +1,7 @@
+-- Remove resource quotas
+DROP INDEX IF EXISTS idx_imap_accounts_quota_group;
+ALTER TABLE imap_accounts DROP COLUMN max_processing_minutes_per_day;
+ALTER TABLE imap_accounts DROP COLUMN max_items;
+ALTER TABLE imap_accounts DROP COLUMN max_feeds;
+ALTER TABLE imap_accounts DROP COLUMN quota_group_id;
+DROP TABLE IF EXISTS quota_groups;
@@ -0,0 +1,18 @@
+-- Resource quotas per IMAP account and per quota group of accounts
+CREATE TABLE quota_groups (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    max_feeds INTEGER,
+    max_items INTEGER,
+    max_processing_minutes_per_day INTEGER,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- Group membership is cleared when a group is deleted
+ALTER TABLE imap_accounts ADD COLUMN quota_group_id TEXT NULL;
+ALTER TABLE imap_accounts ADD COLUMN max_feeds INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN max_items INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN max_processing_minutes_per_day INTEGER NULL;
+
+CREATE INDEX idx_imap_accounts_quota_group ON imap_accounts(quota_group_id);
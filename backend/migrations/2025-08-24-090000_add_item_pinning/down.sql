@@ -0,0 +1,3 @@
+-- Remove item pinning
+ALTER TABLE feeds DROP COLUMN max_pinned;
+ALTER TABLE feed_items DROP COLUMN pinned;
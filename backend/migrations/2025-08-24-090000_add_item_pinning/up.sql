@@ -0,0 +1,5 @@
+-- Pinned items come first in generated feeds and are never removed by retention cleanup
+ALTER TABLE feed_items ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Most items a feed may pin; NULL uses the default cap
+ALTER TABLE feeds ADD COLUMN max_pinned INTEGER NULL;
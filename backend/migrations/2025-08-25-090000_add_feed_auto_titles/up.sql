@@ -0,0 +1,2 @@
+-- Whether items of emails without a subject are titled from their body; NULL enables it
+ALTER TABLE feeds ADD COLUMN auto_titles BOOLEAN NULL;
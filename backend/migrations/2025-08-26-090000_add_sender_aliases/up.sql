@@ -0,0 +1,8 @@
+-- Sending addresses and domains treated as one logical sender, one per line in addresses
+CREATE TABLE sender_aliases (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    addresses TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,23 @@
+-- Write-ahead log of what a run decided to do with each email, written before
+-- the feed item is created and the mailbox changed; status is 'pending' until
+-- resolved as 'applied', 'failed', 'reconciled' or 'recovered'
+CREATE TABLE processing_intents (
+    id TEXT PRIMARY KEY,
+    processing_run_id TEXT NOT NULL,
+    feed_id TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    uid BIGINT NOT NULL,
+    email_message_id TEXT,
+    action TEXT NOT NULL,
+    target_folder TEXT,
+    item_title TEXT NOT NULL,
+    email_snapshot TEXT,
+    feed_item_id TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TEXT NOT NULL,
+    resolved_at TEXT,
+    FOREIGN KEY (processing_run_id) REFERENCES processing_runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_processing_intents_run ON processing_intents(processing_run_id);
+CREATE INDEX idx_processing_intents_status ON processing_intents(status);
@@ -0,0 +1,5 @@
+-- Presentation of the feed's hosted item pages: custom CSS, header and footer HTML snippets and a logo
+ALTER TABLE feeds ADD COLUMN page_css TEXT NULL;
+ALTER TABLE feeds ADD COLUMN page_header_html TEXT NULL;
+ALTER TABLE feeds ADD COLUMN page_footer_html TEXT NULL;
+ALTER TABLE feeds ADD COLUMN page_logo_url TEXT NULL;
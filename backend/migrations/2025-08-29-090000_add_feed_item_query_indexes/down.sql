@@ -0,0 +1,4 @@
+-- Remove composite feed item indexes
+DROP INDEX IF EXISTS idx_feed_items_feed_title_from;
+DROP INDEX IF EXISTS idx_feed_items_feed_message_id;
+DROP INDEX IF EXISTS idx_feed_items_feed_pub_date;
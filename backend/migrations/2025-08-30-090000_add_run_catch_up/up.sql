@@ -0,0 +1,5 @@
+-- Gap since the account's last completed run that a processing run caught up on
+ALTER TABLE processing_runs ADD COLUMN catch_up_from TEXT NULL;
+ALTER TABLE processing_runs ADD COLUMN catch_up_to TEXT NULL;
+-- Whether the bounded backfill stopped short of the start of the gap
+ALTER TABLE processing_runs ADD COLUMN catch_up_truncated BOOLEAN NOT NULL DEFAULT FALSE;
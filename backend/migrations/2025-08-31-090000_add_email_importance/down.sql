@@ -0,0 +1,4 @@
+-- Remove email importance and categories
+ALTER TABLE email_rules DROP COLUMN importance;
+ALTER TABLE feed_items DROP COLUMN category;
+ALTER TABLE feed_items DROP COLUMN importance;
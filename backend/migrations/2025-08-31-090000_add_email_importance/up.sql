@@ -0,0 +1,6 @@
+-- Importance ('high', 'normal' or 'low') from X-Priority/Importance headers and the Gmail category tab of each item's email
+ALTER TABLE feed_items ADD COLUMN importance TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN category TEXT NULL;
+
+-- Rules may match only emails of one importance (NULL = any)
+ALTER TABLE email_rules ADD COLUMN importance TEXT NULL;
@@ -0,0 +1,2 @@
+-- Remove rule processing order
+ALTER TABLE email_rules DROP COLUMN processing_order;
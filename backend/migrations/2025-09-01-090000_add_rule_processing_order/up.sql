@@ -0,0 +1,2 @@
+-- Order in which a rule processes the emails of a run ('newest_first' or 'oldest_first')
+ALTER TABLE email_rules ADD COLUMN processing_order TEXT NOT NULL DEFAULT 'newest_first';
@@ -0,0 +1,3 @@
+-- Remove the per-rule UID high-water mark
+ALTER TABLE email_rules DROP COLUMN uid_validity;
+ALTER TABLE email_rules DROP COLUMN last_seen_uid;
@@ -0,0 +1,3 @@
+-- Highest UID of a rule's folder already handled, valid while the folder's UIDVALIDITY is unchanged
+ALTER TABLE email_rules ADD COLUMN last_seen_uid BIGINT NULL;
+ALTER TABLE email_rules ADD COLUMN uid_validity BIGINT NULL;
@@ -0,0 +1,2 @@
+-- Remove the sanitized HTML body of feed items
+ALTER TABLE feed_items DROP COLUMN email_body_html;
@@ -0,0 +1,2 @@
+-- Sanitized HTML part of the email, stored next to its plain text in email_body
+ALTER TABLE feed_items ADD COLUMN email_body_html TEXT NULL;
@@ -0,0 +1,2 @@
+-- Remove item attachments
+DROP TABLE IF EXISTS attachments;
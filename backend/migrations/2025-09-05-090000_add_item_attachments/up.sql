@@ -0,0 +1,15 @@
+-- Files attached to the email an item was made from, served as enclosures;
+-- position numbers an item's attachments from 1
+CREATE TABLE attachments (
+    id TEXT PRIMARY KEY,
+    feed_item_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    filename TEXT,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    content BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (feed_item_id) REFERENCES feed_items(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_attachments_item_position ON attachments(feed_item_id, position);
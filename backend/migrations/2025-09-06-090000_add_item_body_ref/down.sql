@@ -0,0 +1,2 @@
+-- Remove the blob store reference of feed items
+ALTER TABLE feed_items DROP COLUMN body_ref;
@@ -0,0 +1,2 @@
+-- Remove the TLS server name override of accounts
+ALTER TABLE imap_accounts DROP COLUMN tls_server_name;
@@ -0,0 +1,2 @@
+-- Name sent as SNI and verified against the certificate instead of the host
+ALTER TABLE imap_accounts ADD COLUMN tls_server_name TEXT NULL;
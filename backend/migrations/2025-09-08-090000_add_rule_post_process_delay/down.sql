@@ -0,0 +1,3 @@
+-- Remove rule post-process delays
+DROP TABLE IF EXISTS deferred_actions;
+ALTER TABLE email_rules DROP COLUMN post_process_delay_hours;
@@ -0,0 +1,28 @@
+-- Hours a rule leaves matched mail untouched before applying its action
+ALTER TABLE email_rules ADD COLUMN post_process_delay_hours INTEGER NULL;
+
+-- Mailbox actions held back by a rule's delay, applied by a background sweep
+-- once due; status is 'pending' until applied, when the row is removed, or
+-- 'failed' when the email could not be acted on
+CREATE TABLE deferred_actions (
+    id TEXT PRIMARY KEY,
+    email_rule_id TEXT NOT NULL,
+    imap_account_id TEXT NOT NULL,
+    processing_run_id TEXT NOT NULL,
+    processing_intent_id TEXT NOT NULL,
+    feed_item_id TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    uid BIGINT NOT NULL,
+    email_message_id TEXT,
+    action TEXT NOT NULL,
+    target_folder TEXT,
+    due_at TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    last_error TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (email_rule_id) REFERENCES email_rules(id) ON DELETE CASCADE,
+    FOREIGN KEY (processing_run_id) REFERENCES processing_runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_deferred_actions_due ON deferred_actions(status, due_at);
+CREATE INDEX idx_deferred_actions_run ON deferred_actions(processing_run_id);
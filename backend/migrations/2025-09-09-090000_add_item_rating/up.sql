@@ -0,0 +1,3 @@
+-- Reader's thumbs up or down on an item ('up' or 'down')
+ALTER TABLE feed_items ADD COLUMN rating TEXT NULL;
+CREATE INDEX idx_feed_items_rating ON feed_items(rating);
@@ -0,0 +1,19 @@
+-- Cost of evaluating each rule in a processing run: how long fetching its
+-- folder and matching the fetched emails took, and how many were matched
+CREATE TABLE rule_costs (
+    id TEXT PRIMARY KEY,
+    processing_run_id TEXT NOT NULL,
+    email_rule_id TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    emails_fetched INTEGER NOT NULL DEFAULT 0,
+    evaluations INTEGER NOT NULL DEFAULT 0,
+    matches INTEGER NOT NULL DEFAULT 0,
+    fetch_ms BIGINT NOT NULL DEFAULT 0,
+    match_us BIGINT NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (processing_run_id) REFERENCES processing_runs(id) ON DELETE CASCADE,
+    FOREIGN KEY (email_rule_id) REFERENCES email_rules(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_rule_costs_run ON rule_costs(processing_run_id);
+CREATE INDEX idx_rule_costs_created ON rule_costs(created_at);
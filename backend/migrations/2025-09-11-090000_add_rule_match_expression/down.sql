@@ -0,0 +1,2 @@
+-- Remove rule match expressions
+ALTER TABLE email_rules DROP COLUMN match_expression;
@@ -0,0 +1,3 @@
+-- Advanced matching: a JSON expression of regex patterns combined with
+-- all/any/not, checked along with the rule's substring criteria
+ALTER TABLE email_rules ADD COLUMN match_expression TEXT NULL;
@@ -0,0 +1,2 @@
+-- Remove retention policies
+DROP TABLE IF EXISTS retention_policies;
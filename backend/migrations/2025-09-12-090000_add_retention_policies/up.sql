@@ -0,0 +1,12 @@
+-- How long each history table keeps its rows, and how many the cleanup has
+-- purged from it so far
+CREATE TABLE retention_policies (
+    id TEXT PRIMARY KEY,
+    target TEXT NOT NULL UNIQUE,
+    max_rows INTEGER NULL,
+    max_age_days INTEGER NULL,
+    rows_purged BIGINT NOT NULL DEFAULT 0,
+    last_purged_at TEXT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
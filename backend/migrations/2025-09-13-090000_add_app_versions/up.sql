@@ -0,0 +1,8 @@
+-- Versions of the backend that have run against this database, so an
+-- upgrade can tell which version ran before it
+CREATE TABLE app_versions (
+    version TEXT PRIMARY KEY,
+    git_hash TEXT NULL,
+    first_run_at TEXT NOT NULL,
+    last_run_at TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Settings changed at runtime through /api/settings, overriding the
+-- environment variables of the same purpose
+CREATE TABLE app_settings (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
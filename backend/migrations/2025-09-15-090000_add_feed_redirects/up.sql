@@ -0,0 +1,11 @@
+-- Feeds merged into another one, so their URLs keep working
+CREATE TABLE feed_redirects (
+    old_feed_id TEXT PRIMARY KEY NOT NULL,
+    feed_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_feed_redirects_feed_id ON feed_redirects(feed_id);
+
+-- Feed an item was first published in, set once it moves to another feed
+ALTER TABLE feed_items ADD COLUMN origin_feed_id TEXT NULL;
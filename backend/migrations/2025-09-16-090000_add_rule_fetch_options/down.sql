@@ -0,0 +1,4 @@
+-- Remove rule fetch options
+ALTER TABLE email_rules DROP COLUMN include_subfolders;
+ALTER TABLE email_rules DROP COLUMN include_seen;
+ALTER TABLE email_rules DROP COLUMN fetch_limit;
@@ -0,0 +1,6 @@
+-- How much of its folder a rule fetches per run: at most fetch_limit emails
+-- (none: the default), seen mail too unless include_seen is off, and the
+-- folder's subfolders when include_subfolders is on
+ALTER TABLE email_rules ADD COLUMN fetch_limit INTEGER NULL;
+ALTER TABLE email_rules ADD COLUMN include_seen BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE email_rules ADD COLUMN include_subfolders BOOLEAN NOT NULL DEFAULT 0;
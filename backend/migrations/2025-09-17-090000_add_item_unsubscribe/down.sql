@@ -0,0 +1,4 @@
+-- Remove item unsubscribe links
+ALTER TABLE feed_items DROP COLUMN unsubscribe_one_click;
+ALTER TABLE feed_items DROP COLUMN unsubscribe_mailto;
+ALTER TABLE feed_items DROP COLUMN unsubscribe_url;
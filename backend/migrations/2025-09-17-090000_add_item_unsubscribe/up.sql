@@ -0,0 +1,5 @@
+-- Where the email of an item can be unsubscribed from: a web (or RFC 8058
+-- one-click) URL and a mailto: address from List-Unsubscribe, or a footer link
+ALTER TABLE feed_items ADD COLUMN unsubscribe_url TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN unsubscribe_mailto TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN unsubscribe_one_click BOOLEAN NOT NULL DEFAULT 0;
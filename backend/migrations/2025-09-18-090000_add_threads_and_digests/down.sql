@@ -0,0 +1,7 @@
+-- Remove threads and digests
+DROP INDEX IF EXISTS idx_feed_items_thread;
+ALTER TABLE feeds DROP COLUMN digest_mode;
+ALTER TABLE feed_items DROP COLUMN digest_message_ids;
+ALTER TABLE feed_items DROP COLUMN thread_id;
+ALTER TABLE feed_items DROP COLUMN email_references;
+ALTER TABLE feed_items DROP COLUMN in_reply_to;
@@ -0,0 +1,10 @@
+-- Thread headers of an item's email, the thread's root Message-ID, and the
+-- Message-IDs of further emails merged into the item by its feed's digest
+-- mode ('thread' or 'day'; none keeps one item per email)
+ALTER TABLE feed_items ADD COLUMN in_reply_to TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN email_references TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN thread_id TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN digest_message_ids TEXT NULL;
+ALTER TABLE feeds ADD COLUMN digest_mode TEXT NULL;
+
+CREATE INDEX idx_feed_items_thread ON feed_items(feed_id, thread_id);
@@ -0,0 +1,2 @@
+-- Remove the connection security mode of accounts
+ALTER TABLE imap_accounts DROP COLUMN security;
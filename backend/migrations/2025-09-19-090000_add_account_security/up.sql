@@ -0,0 +1,9 @@
+-- How an account's connection is secured: none, starttls or ssl_tls (implicit TLS)
+ALTER TABLE imap_accounts ADD COLUMN security TEXT NOT NULL DEFAULT 'starttls';
+
+-- TLS accounts on 993 expect implicit TLS, which STARTTLS never worked with
+UPDATE imap_accounts SET security = CASE
+    WHEN NOT use_tls THEN 'none'
+    WHEN port = 993 THEN 'ssl_tls'
+    ELSE 'starttls'
+END;
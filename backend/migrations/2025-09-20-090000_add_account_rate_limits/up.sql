@@ -0,0 +1,4 @@
+-- Politeness limits for an account's IMAP server (NULL = unlimited)
+ALTER TABLE imap_accounts ADD COLUMN max_connections_per_hour INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN min_fetch_delay_seconds INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN max_messages_per_fetch INTEGER NULL;
@@ -0,0 +1,18 @@
+-- Long-running operations started from the API, with their progress and outcome
+CREATE TABLE jobs (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL, -- e.g. 'backfill_rule', 'process_account', 'cleanup_feeds'
+    target_id TEXT, -- the account, rule or feed the job works on, if any
+    status TEXT NOT NULL DEFAULT 'queued', -- 'queued', 'running', 'completed' or 'failed'
+    processed INTEGER NOT NULL DEFAULT 0,
+    total INTEGER, -- NULL while unknown
+    message TEXT,
+    error_message TEXT,
+    result TEXT, -- JSON summary of a completed job
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT
+);
+
+CREATE INDEX idx_jobs_created_at ON jobs(created_at);
+CREATE INDEX idx_jobs_status ON jobs(status);
@@ -0,0 +1,2 @@
+-- Remove the item description template
+ALTER TABLE feeds DROP COLUMN description_template;
@@ -0,0 +1,2 @@
+-- Item description template of a feed, next to its title template
+ALTER TABLE feeds ADD COLUMN description_template TEXT NULL;
@@ -0,0 +1,5 @@
+-- Remove the content filters of rules
+ALTER TABLE email_rules DROP COLUMN unwrap_tracking_links;
+ALTER TABLE email_rules DROP COLUMN strip_tracking_pixels;
+ALTER TABLE email_rules DROP COLUMN strip_quoted_replies;
+ALTER TABLE email_rules DROP COLUMN strip_footers;
@@ -0,0 +1,7 @@
+-- Content filters a rule applies to emails before creating their items:
+-- newsletter footers, quoted replies, tracking pixels (on, as before) and
+-- click-tracking link wrappers
+ALTER TABLE email_rules ADD COLUMN strip_footers BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE email_rules ADD COLUMN strip_quoted_replies BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE email_rules ADD COLUMN strip_tracking_pixels BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE email_rules ADD COLUMN unwrap_tracking_links BOOLEAN NOT NULL DEFAULT 0;
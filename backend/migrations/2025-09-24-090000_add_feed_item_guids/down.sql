@@ -0,0 +1,3 @@
+-- Remove stable GUIDs of feed items
+DROP INDEX IF EXISTS idx_feed_items_feed_content_hash;
+ALTER TABLE feed_items DROP COLUMN guid;
@@ -0,0 +1,5 @@
+-- Stable RSS GUIDs derived from the content hash of an item's email, and an
+-- index to find an email's duplicates in a feed by that hash. Items stored
+-- before keep their GUIDs
+ALTER TABLE feed_items ADD COLUMN guid TEXT NULL;
+CREATE INDEX idx_feed_items_feed_content_hash ON feed_items(feed_id, content_hash);
@@ -0,0 +1,2 @@
+-- Remove post-processing chains of rules
+ALTER TABLE email_rules DROP COLUMN post_process_actions;
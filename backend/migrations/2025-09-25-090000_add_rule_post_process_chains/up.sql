@@ -0,0 +1,3 @@
+-- Ordered post-processing actions of a rule as a JSON array, e.g. mark read,
+-- copy to Archive, then delete after a week; replaces the single action when set
+ALTER TABLE email_rules ADD COLUMN post_process_actions TEXT NULL;
@@ -0,0 +1,3 @@
+-- Remove the detected server capabilities and fetch strategy of accounts
+ALTER TABLE imap_accounts DROP COLUMN fetch_strategy;
+ALTER TABLE imap_accounts DROP COLUMN server_capabilities;
@@ -0,0 +1,4 @@
+-- What an account's server was found to support: its capabilities and the
+-- fetch strategy that worked, tried first on later runs
+ALTER TABLE imap_accounts ADD COLUMN server_capabilities TEXT NULL;
+ALTER TABLE imap_accounts ADD COLUMN fetch_strategy TEXT NULL;
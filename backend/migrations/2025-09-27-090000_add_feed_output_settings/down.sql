@@ -0,0 +1,3 @@
+-- Remove the feed output settings
+ALTER TABLE feeds DROP COLUMN full_content;
+ALTER TABLE feeds DROP COLUMN item_limit;
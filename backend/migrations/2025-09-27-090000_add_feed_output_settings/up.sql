@@ -0,0 +1,3 @@
+-- Items in a feed's rendered documents and whether they carry the full HTML body
+ALTER TABLE feeds ADD COLUMN item_limit INTEGER NULL;
+ALTER TABLE feeds ADD COLUMN full_content BOOLEAN NULL;
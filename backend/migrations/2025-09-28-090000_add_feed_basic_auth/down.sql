@@ -0,0 +1,3 @@
+-- Remove the feed credentials
+ALTER TABLE feeds DROP COLUMN auth_password_hash;
+ALTER TABLE feeds DROP COLUMN auth_username;
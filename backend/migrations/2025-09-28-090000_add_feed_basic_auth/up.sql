@@ -0,0 +1,3 @@
+-- Basic auth credentials of password-protected feeds; the password is an Argon2 hash
+ALTER TABLE feeds ADD COLUMN auth_username TEXT NULL;
+ALTER TABLE feeds ADD COLUMN auth_password_hash TEXT NULL;
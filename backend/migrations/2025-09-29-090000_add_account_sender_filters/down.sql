@@ -0,0 +1,3 @@
+-- Remove account sender filters
+DROP INDEX IF EXISTS idx_account_sender_filters_pattern;
+DROP TABLE IF EXISTS account_sender_filters;
@@ -0,0 +1,11 @@
+-- Sender addresses and domains an account lets through to its rules or keeps out
+CREATE TABLE account_sender_filters (
+    id TEXT PRIMARY KEY,
+    imap_account_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (imap_account_id) REFERENCES imap_accounts(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_account_sender_filters_pattern ON account_sender_filters(imap_account_id, kind, pattern);
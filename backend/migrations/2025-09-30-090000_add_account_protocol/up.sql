@@ -0,0 +1,2 @@
+-- How an account's mail arrives: polled over IMAP, or delivered to the SMTP listener
+ALTER TABLE imap_accounts ADD COLUMN protocol TEXT NOT NULL DEFAULT 'imap';
@@ -0,0 +1,5 @@
+-- Remove the sync numbering of items
+DROP TRIGGER feed_items_sync_id;
+DROP TABLE feed_item_sync_sequence;
+DROP INDEX idx_feed_items_sync_id;
+ALTER TABLE feed_items DROP COLUMN sync_id;
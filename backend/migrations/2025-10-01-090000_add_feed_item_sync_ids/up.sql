@@ -0,0 +1,17 @@
+-- Number items in the order they arrive, for sync clients paging with
+-- `since_id`; numbers come from an AUTOINCREMENT sequence so they are never
+-- reused once items are deleted
+ALTER TABLE feed_items ADD COLUMN sync_id BIGINT;
+UPDATE feed_items SET sync_id = rowid;
+CREATE UNIQUE INDEX idx_feed_items_sync_id ON feed_items(sync_id);
+
+CREATE TABLE feed_item_sync_sequence (id INTEGER PRIMARY KEY AUTOINCREMENT);
+INSERT INTO feed_item_sync_sequence (id) SELECT COALESCE(MAX(sync_id), 0) FROM feed_items;
+DELETE FROM feed_item_sync_sequence;
+
+CREATE TRIGGER feed_items_sync_id AFTER INSERT ON feed_items WHEN NEW.sync_id IS NULL
+BEGIN
+    INSERT INTO feed_item_sync_sequence (id) VALUES (NULL);
+    UPDATE feed_items SET sync_id = last_insert_rowid() WHERE rowid = NEW.rowid;
+    DELETE FROM feed_item_sync_sequence;
+END;
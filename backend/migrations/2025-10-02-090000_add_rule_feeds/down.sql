@@ -0,0 +1,4 @@
+-- Remove feeds attached to rules
+DROP INDEX IF EXISTS idx_rule_feeds_feed_id;
+DROP INDEX IF EXISTS idx_rule_feeds_rule_feed;
+DROP TABLE IF EXISTS rule_feeds;
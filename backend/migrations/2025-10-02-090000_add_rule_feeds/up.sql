@@ -0,0 +1,13 @@
+-- Further feeds a rule delivers its matching emails to, besides its own
+CREATE TABLE rule_feeds (
+    id TEXT PRIMARY KEY,
+    email_rule_id TEXT NOT NULL,
+    feed_id TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (email_rule_id) REFERENCES email_rules(id) ON DELETE CASCADE,
+    FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_rule_feeds_rule_feed ON rule_feeds(email_rule_id, feed_id);
+CREATE INDEX idx_rule_feeds_feed_id ON rule_feeds(feed_id);
@@ -0,0 +1,4 @@
+-- Restore the single-column Message-ID index and remove the dedup scope
+DROP INDEX IF EXISTS idx_feed_items_message_id_feed;
+CREATE INDEX idx_feed_items_message_id ON feed_items(email_message_id);
+ALTER TABLE feeds DROP COLUMN dedup_scope;
@@ -0,0 +1,6 @@
+-- How far a feed looks for an email it already has: feed, account or global
+ALTER TABLE feeds ADD COLUMN dedup_scope TEXT NULL;
+
+-- Message-ID lookups across feeds, answered from the index alone
+DROP INDEX IF EXISTS idx_feed_items_message_id;
+CREATE INDEX idx_feed_items_message_id_feed ON feed_items(email_message_id, feed_id);
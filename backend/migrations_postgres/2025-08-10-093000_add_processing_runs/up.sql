@@ -0,0 +1,31 @@
+-- Track each processing run so its results can be inspected or rolled back (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS processing_runs (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    imap_account_id TEXT NOT NULL REFERENCES imap_accounts(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'running',
+    started_at TEXT NOT NULL DEFAULT now()::TEXT,
+    finished_at TEXT,
+    emails_processed INTEGER NOT NULL DEFAULT 0,
+    items_created INTEGER NOT NULL DEFAULT 0,
+    error_message TEXT
+);
+
+-- Post-processing actions applied to the mailbox during a run
+CREATE TABLE IF NOT EXISTS processing_run_actions (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    processing_run_id TEXT NOT NULL REFERENCES processing_runs(id) ON DELETE CASCADE,
+    feed_item_id TEXT,
+    folder TEXT NOT NULL,
+    uid BIGINT NOT NULL,
+    email_message_id TEXT,
+    action TEXT NOT NULL,
+    target_folder TEXT,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+-- Remember which run created each feed item
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS processing_run_id TEXT NULL REFERENCES processing_runs(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_processing_runs_account ON processing_runs(imap_account_id);
+CREATE INDEX IF NOT EXISTS idx_processing_run_actions_run ON processing_run_actions(processing_run_id);
+CREATE INDEX IF NOT EXISTS idx_feed_items_processing_run ON feed_items(processing_run_id);
@@ -0,0 +1,2 @@
+-- Per-feed length of generated item descriptions (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS summary_length INTEGER NULL;
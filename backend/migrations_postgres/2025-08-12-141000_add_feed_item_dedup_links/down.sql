@@ -0,0 +1,5 @@
+-- Remove cross-feed deduplication links
+DROP INDEX IF EXISTS idx_feed_items_canonical;
+DROP INDEX IF EXISTS idx_feed_items_content_hash;
+ALTER TABLE feed_items DROP COLUMN canonical_item_id;
+ALTER TABLE feed_items DROP COLUMN content_hash;
@@ -0,0 +1,6 @@
+-- Cross-feed deduplication: items can link to a canonical copy (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS content_hash TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS canonical_item_id TEXT NULL REFERENCES feed_items(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_feed_items_content_hash ON feed_items(content_hash);
+CREATE INDEX IF NOT EXISTS idx_feed_items_canonical ON feed_items(canonical_item_id);
@@ -0,0 +1,2 @@
+-- Detected language of each feed item (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS language TEXT NULL;
@@ -0,0 +1,4 @@
+-- Remove bandwidth limits and transfer counters
+ALTER TABLE processing_runs DROP COLUMN bytes_sent;
+ALTER TABLE processing_runs DROP COLUMN bytes_received;
+ALTER TABLE imap_accounts DROP COLUMN max_bytes_per_second;
@@ -0,0 +1,6 @@
+-- Per-account bandwidth limit for IMAP connections (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS max_bytes_per_second INTEGER NULL;
+
+-- Bytes transferred over IMAP during each processing run
+ALTER TABLE processing_runs ADD COLUMN IF NOT EXISTS bytes_received BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE processing_runs ADD COLUMN IF NOT EXISTS bytes_sent BIGINT NOT NULL DEFAULT 0;
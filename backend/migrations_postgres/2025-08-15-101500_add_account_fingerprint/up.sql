@@ -0,0 +1,2 @@
+-- Fingerprint of the mailbox behind an account (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS fingerprint TEXT NULL;
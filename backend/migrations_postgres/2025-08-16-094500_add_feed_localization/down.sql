@@ -0,0 +1,4 @@
+-- Remove feed localization settings
+ALTER TABLE feeds DROP COLUMN title_template;
+ALTER TABLE feeds DROP COLUMN timezone;
+ALTER TABLE feeds DROP COLUMN locale;
@@ -0,0 +1,4 @@
+-- Per-feed locale and timezone for display dates, and an optional item title template (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS locale TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS timezone TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS title_template TEXT NULL;
@@ -0,0 +1,15 @@
+-- Observe-only rules record their matches for preview instead of creating feed items (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS observe_only BOOLEAN NOT NULL DEFAULT false;
+
+-- Emails matched by observe-only rules, one row per rule and message
+CREATE TABLE IF NOT EXISTS rule_matches (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    email_rule_id TEXT NOT NULL REFERENCES email_rules(id) ON DELETE CASCADE,
+    email_message_id TEXT NOT NULL,
+    email_subject TEXT NOT NULL,
+    email_from TEXT NOT NULL,
+    email_date TEXT NOT NULL,
+    matched_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_rule_matches_rule_message ON rule_matches(email_rule_id, email_message_id);
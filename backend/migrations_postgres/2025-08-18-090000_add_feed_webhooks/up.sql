@@ -0,0 +1,4 @@
+-- Outgoing HTTP call fired for each new item of a feed (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS webhook_url TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS webhook_method TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS webhook_body TEXT NULL;
@@ -0,0 +1,17 @@
+-- Chat integrations posting new feed items to Slack, Discord or Matrix (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS chat_integrations (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    feed_id TEXT NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    platform TEXT NOT NULL,
+    webhook_url TEXT,
+    matrix_homeserver TEXT,
+    matrix_room_id TEXT,
+    matrix_access_token TEXT,
+    message_template TEXT,
+    rate_limit_per_minute INTEGER,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    updated_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_integrations_feed ON chat_integrations(feed_id);
@@ -0,0 +1,2 @@
+-- Remove TLS certificate pins
+ALTER TABLE imap_accounts DROP COLUMN tls_pin;
@@ -0,0 +1,2 @@
+-- Pinned TLS certificate or public key fingerprint (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS tls_pin TEXT NULL;
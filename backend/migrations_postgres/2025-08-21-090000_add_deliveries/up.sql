@@ -0,0 +1,21 @@
+-- Outbound delivery queue shared by webhooks and chat integrations (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS deliveries (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    feed_id TEXT NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    item_id TEXT,
+    kind TEXT NOT NULL,
+    queue TEXT NOT NULL,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    headers TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL DEFAULT now()::TEXT,
+    last_error TEXT,
+    delivered_at TEXT,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    updated_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_deliveries_status ON deliveries(status, created_at);
@@ -0,0 +1,2 @@
+-- Per-feed override of FEED_PUBLIC_ENDPOINTS (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS public_access BOOLEAN NULL;
@@ -0,0 +1,18 @@
+-- Resource quotas per IMAP account and per quota group of accounts (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS quota_groups (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    name TEXT NOT NULL,
+    max_feeds INTEGER,
+    max_items INTEGER,
+    max_processing_minutes_per_day INTEGER,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    updated_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+-- Group membership is cleared when a group is deleted
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS quota_group_id TEXT NULL;
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS max_feeds INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS max_items INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS max_processing_minutes_per_day INTEGER NULL;
+
+CREATE INDEX IF NOT EXISTS idx_imap_accounts_quota_group ON imap_accounts(quota_group_id);
@@ -0,0 +1,5 @@
+-- Pinned items come first in generated feeds and are never removed by retention cleanup (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS pinned BOOLEAN NOT NULL DEFAULT false;
+
+-- Most items a feed may pin; NULL uses the default cap
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS max_pinned INTEGER NULL;
@@ -0,0 +1,2 @@
+-- Remove per-feed automatic titles
+ALTER TABLE feeds DROP COLUMN auto_titles;
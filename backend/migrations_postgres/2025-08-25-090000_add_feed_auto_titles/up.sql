@@ -0,0 +1,2 @@
+-- Whether items of emails without a subject are titled from their body (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS auto_titles BOOLEAN NULL;
@@ -0,0 +1,2 @@
+-- Remove sender aliases
+DROP TABLE IF EXISTS sender_aliases;
@@ -0,0 +1,8 @@
+-- Sending addresses and domains treated as one logical sender, one per line in addresses (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS sender_aliases (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    name TEXT NOT NULL,
+    addresses TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    updated_at TEXT NOT NULL DEFAULT now()::TEXT
+);
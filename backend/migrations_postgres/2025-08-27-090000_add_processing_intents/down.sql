@@ -0,0 +1,2 @@
+-- Remove the processing intent log
+DROP TABLE IF EXISTS processing_intents;
@@ -0,0 +1,21 @@
+-- Write-ahead log of what a run decided to do with each email, written before
+-- the feed item is created and the mailbox changed (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS processing_intents (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    processing_run_id TEXT NOT NULL REFERENCES processing_runs(id) ON DELETE CASCADE,
+    feed_id TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    uid BIGINT NOT NULL,
+    email_message_id TEXT,
+    action TEXT NOT NULL,
+    target_folder TEXT,
+    item_title TEXT NOT NULL,
+    email_snapshot TEXT,
+    feed_item_id TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_processing_intents_run ON processing_intents(processing_run_id);
+CREATE INDEX IF NOT EXISTS idx_processing_intents_status ON processing_intents(status);
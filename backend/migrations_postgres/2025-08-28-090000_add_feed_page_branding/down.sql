@@ -0,0 +1,5 @@
+-- Remove per-feed item page branding
+ALTER TABLE feeds DROP COLUMN page_logo_url;
+ALTER TABLE feeds DROP COLUMN page_footer_html;
+ALTER TABLE feeds DROP COLUMN page_header_html;
+ALTER TABLE feeds DROP COLUMN page_css;
@@ -0,0 +1,5 @@
+-- Presentation of the feed's hosted item pages: custom CSS, header and footer HTML snippets and a logo (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS page_css TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS page_header_html TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS page_footer_html TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS page_logo_url TEXT NULL;
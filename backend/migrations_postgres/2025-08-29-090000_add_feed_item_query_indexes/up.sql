@@ -0,0 +1,4 @@
+-- Composite indexes for per-feed item listing and duplicate checks (PostgreSQL conditional syntax)
+CREATE INDEX IF NOT EXISTS idx_feed_items_feed_pub_date ON feed_items(feed_id, pub_date DESC);
+CREATE INDEX IF NOT EXISTS idx_feed_items_feed_message_id ON feed_items(feed_id, email_message_id);
+CREATE INDEX IF NOT EXISTS idx_feed_items_feed_title_from ON feed_items(feed_id, title, email_from);
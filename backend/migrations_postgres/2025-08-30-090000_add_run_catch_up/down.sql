@@ -0,0 +1,4 @@
+-- Remove catch-up ranges from processing runs
+ALTER TABLE processing_runs DROP COLUMN catch_up_truncated;
+ALTER TABLE processing_runs DROP COLUMN catch_up_to;
+ALTER TABLE processing_runs DROP COLUMN catch_up_from;
@@ -0,0 +1,5 @@
+-- Remove append-only feeds and item hash chains
+ALTER TABLE feed_items DROP COLUMN chain_hash;
+ALTER TABLE feed_items DROP COLUMN chain_previous;
+ALTER TABLE feeds DROP COLUMN chain_head;
+ALTER TABLE feeds DROP COLUMN append_only;
@@ -0,0 +1,8 @@
+-- Append-only feeds, whose items cannot be edited or deleted and are chained by hash (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS append_only BOOLEAN NOT NULL DEFAULT FALSE;
+-- Hash of the feed's latest chained item
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS chain_head TEXT NULL;
+
+-- Hash of the previous item in the chain (NULL for the first) and of this item's content together with it
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS chain_previous TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS chain_hash TEXT NULL;
@@ -0,0 +1,3 @@
+-- Highest UID of a rule's folder already handled, valid while the folder's UIDVALIDITY is unchanged (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS last_seen_uid BIGINT NULL;
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS uid_validity BIGINT NULL;
@@ -0,0 +1,2 @@
+-- Sanitized HTML part of the email, stored next to its plain text in email_body (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS email_body_html TEXT NULL;
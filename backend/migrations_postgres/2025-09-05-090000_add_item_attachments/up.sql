@@ -0,0 +1,13 @@
+-- Files attached to the email an item was made from, served as enclosures (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS attachments (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    feed_item_id TEXT NOT NULL REFERENCES feed_items(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    filename TEXT,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    content BYTEA NOT NULL,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_attachments_item_position ON attachments(feed_item_id, position);
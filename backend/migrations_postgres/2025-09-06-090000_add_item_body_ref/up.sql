@@ -0,0 +1,2 @@
+-- Key of the blob holding an item's body once it is moved out of the database (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS body_ref TEXT NULL;
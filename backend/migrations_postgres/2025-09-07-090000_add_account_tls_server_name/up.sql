@@ -0,0 +1,2 @@
+-- Name sent as SNI and verified against the certificate instead of the host (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS tls_server_name TEXT NULL;
@@ -0,0 +1,24 @@
+-- Hours a rule leaves matched mail untouched before applying its action (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS post_process_delay_hours INTEGER NULL;
+
+-- Mailbox actions held back by a rule's delay, applied by a background sweep once due
+CREATE TABLE IF NOT EXISTS deferred_actions (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    email_rule_id TEXT NOT NULL REFERENCES email_rules(id) ON DELETE CASCADE,
+    imap_account_id TEXT NOT NULL,
+    processing_run_id TEXT NOT NULL REFERENCES processing_runs(id) ON DELETE CASCADE,
+    processing_intent_id TEXT NOT NULL,
+    feed_item_id TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    uid BIGINT NOT NULL,
+    email_message_id TEXT,
+    action TEXT NOT NULL,
+    target_folder TEXT,
+    due_at TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    last_error TEXT,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_deferred_actions_due ON deferred_actions(status, due_at);
+CREATE INDEX IF NOT EXISTS idx_deferred_actions_run ON deferred_actions(processing_run_id);
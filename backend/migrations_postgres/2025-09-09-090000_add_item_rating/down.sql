@@ -0,0 +1,3 @@
+-- Remove item ratings
+DROP INDEX IF EXISTS idx_feed_items_rating;
+ALTER TABLE feed_items DROP COLUMN rating;
@@ -0,0 +1,3 @@
+-- Reader's thumbs up or down on an item ('up' or 'down') (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS rating TEXT NULL;
+CREATE INDEX IF NOT EXISTS idx_feed_items_rating ON feed_items(rating);
@@ -0,0 +1,2 @@
+-- Remove rule evaluation costs
+DROP TABLE IF EXISTS rule_costs;
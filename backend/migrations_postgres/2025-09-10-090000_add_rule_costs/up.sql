@@ -0,0 +1,16 @@
+-- Cost of evaluating each rule in a processing run (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS rule_costs (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    processing_run_id TEXT NOT NULL REFERENCES processing_runs(id) ON DELETE CASCADE,
+    email_rule_id TEXT NOT NULL REFERENCES email_rules(id) ON DELETE CASCADE,
+    folder TEXT NOT NULL,
+    emails_fetched INTEGER NOT NULL DEFAULT 0,
+    evaluations INTEGER NOT NULL DEFAULT 0,
+    matches INTEGER NOT NULL DEFAULT 0,
+    fetch_ms BIGINT NOT NULL DEFAULT 0,
+    match_us BIGINT NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_costs_run ON rule_costs(processing_run_id);
+CREATE INDEX IF NOT EXISTS idx_rule_costs_created ON rule_costs(created_at);
@@ -0,0 +1,2 @@
+-- Advanced matching expression of a rule, as JSON (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS match_expression TEXT NULL;
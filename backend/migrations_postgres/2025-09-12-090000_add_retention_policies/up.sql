@@ -0,0 +1,11 @@
+-- How long each history table keeps its rows (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS retention_policies (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    target TEXT NOT NULL UNIQUE,
+    max_rows INTEGER NULL,
+    max_age_days INTEGER NULL,
+    rows_purged BIGINT NOT NULL DEFAULT 0,
+    last_purged_at TEXT NULL,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    updated_at TEXT NOT NULL DEFAULT now()::TEXT
+);
@@ -0,0 +1,7 @@
+-- Versions of the backend that have run against this database (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS app_versions (
+    version TEXT PRIMARY KEY,
+    git_hash TEXT NULL,
+    first_run_at TEXT NOT NULL DEFAULT now()::TEXT,
+    last_run_at TEXT NOT NULL DEFAULT now()::TEXT
+);
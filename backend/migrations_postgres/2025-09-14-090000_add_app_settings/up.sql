@@ -0,0 +1,6 @@
+-- Settings changed at runtime through /api/settings (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT now()::TEXT
+);
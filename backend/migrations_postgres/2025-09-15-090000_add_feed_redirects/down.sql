@@ -0,0 +1,4 @@
+-- Remove feed redirects and item origins
+ALTER TABLE feed_items DROP COLUMN IF EXISTS origin_feed_id;
+DROP INDEX IF EXISTS idx_feed_redirects_feed_id;
+DROP TABLE IF EXISTS feed_redirects;
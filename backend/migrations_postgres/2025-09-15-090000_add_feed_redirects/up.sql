@@ -0,0 +1,10 @@
+-- Feeds merged into another one, so their URLs keep working (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS feed_redirects (
+    old_feed_id TEXT PRIMARY KEY NOT NULL,
+    feed_id TEXT NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_feed_redirects_feed_id ON feed_redirects(feed_id);
+
+-- Feed an item was first published in, set once it moves to another feed
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS origin_feed_id TEXT NULL;
@@ -0,0 +1,4 @@
+-- How much of its folder a rule fetches per run (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS fetch_limit INTEGER NULL;
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS include_seen BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS include_subfolders BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Where the email of an item can be unsubscribed from (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS unsubscribe_url TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS unsubscribe_mailto TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS unsubscribe_one_click BOOLEAN NOT NULL DEFAULT FALSE;
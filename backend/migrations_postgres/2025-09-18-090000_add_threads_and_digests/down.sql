@@ -0,0 +1,7 @@
+-- Remove threads and digests
+DROP INDEX IF EXISTS idx_feed_items_thread;
+ALTER TABLE feeds DROP COLUMN IF EXISTS digest_mode;
+ALTER TABLE feed_items DROP COLUMN IF EXISTS digest_message_ids;
+ALTER TABLE feed_items DROP COLUMN IF EXISTS thread_id;
+ALTER TABLE feed_items DROP COLUMN IF EXISTS email_references;
+ALTER TABLE feed_items DROP COLUMN IF EXISTS in_reply_to;
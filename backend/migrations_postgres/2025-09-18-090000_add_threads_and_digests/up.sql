@@ -0,0 +1,8 @@
+-- Thread headers of items and per-feed digest mode (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS in_reply_to TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS email_references TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS thread_id TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS digest_message_ids TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS digest_mode TEXT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_feed_items_thread ON feed_items(feed_id, thread_id);
@@ -0,0 +1,4 @@
+-- Remove the rate limits of accounts
+ALTER TABLE imap_accounts DROP COLUMN max_messages_per_fetch;
+ALTER TABLE imap_accounts DROP COLUMN min_fetch_delay_seconds;
+ALTER TABLE imap_accounts DROP COLUMN max_connections_per_hour;
@@ -0,0 +1,4 @@
+-- Politeness limits for an account's IMAP server (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS max_connections_per_hour INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS min_fetch_delay_seconds INTEGER NULL;
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS max_messages_per_fetch INTEGER NULL;
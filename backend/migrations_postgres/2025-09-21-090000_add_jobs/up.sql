@@ -0,0 +1,18 @@
+-- Long-running operations started from the API, with their progress and outcome (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    kind TEXT NOT NULL,
+    target_id TEXT,
+    status TEXT NOT NULL DEFAULT 'queued',
+    processed INTEGER NOT NULL DEFAULT 0,
+    total INTEGER,
+    message TEXT,
+    error_message TEXT,
+    result TEXT,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT,
+    started_at TEXT,
+    finished_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_created_at ON jobs(created_at);
+CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status);
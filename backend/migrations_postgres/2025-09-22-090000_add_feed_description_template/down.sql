@@ -0,0 +1,2 @@
+-- Remove the item description template (PostgreSQL conditional syntax)
+ALTER TABLE feeds DROP COLUMN IF EXISTS description_template;
@@ -0,0 +1,2 @@
+-- Item description template of a feed, next to its title template (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS description_template TEXT NULL;
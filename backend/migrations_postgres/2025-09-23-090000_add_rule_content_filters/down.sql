@@ -0,0 +1,5 @@
+-- Remove the content filters of rules (PostgreSQL conditional syntax)
+ALTER TABLE email_rules DROP COLUMN IF EXISTS unwrap_tracking_links;
+ALTER TABLE email_rules DROP COLUMN IF EXISTS strip_tracking_pixels;
+ALTER TABLE email_rules DROP COLUMN IF EXISTS strip_quoted_replies;
+ALTER TABLE email_rules DROP COLUMN IF EXISTS strip_footers;
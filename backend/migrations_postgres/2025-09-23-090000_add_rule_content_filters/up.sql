@@ -0,0 +1,5 @@
+-- Content filters a rule applies to emails before creating their items (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS strip_footers BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS strip_quoted_replies BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS strip_tracking_pixels BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS unwrap_tracking_links BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Remove stable GUIDs of feed items (PostgreSQL conditional syntax)
+DROP INDEX IF EXISTS idx_feed_items_feed_content_hash;
+ALTER TABLE feed_items DROP COLUMN IF EXISTS guid;
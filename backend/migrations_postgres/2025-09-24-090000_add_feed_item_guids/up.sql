@@ -0,0 +1,3 @@
+-- Stable RSS GUIDs and duplicate lookup by content hash per feed (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS guid TEXT NULL;
+CREATE INDEX IF NOT EXISTS idx_feed_items_feed_content_hash ON feed_items(feed_id, content_hash);
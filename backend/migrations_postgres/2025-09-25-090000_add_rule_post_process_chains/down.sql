@@ -0,0 +1,2 @@
+-- Remove post-processing chains of rules (PostgreSQL conditional syntax)
+ALTER TABLE email_rules DROP COLUMN IF EXISTS post_process_actions;
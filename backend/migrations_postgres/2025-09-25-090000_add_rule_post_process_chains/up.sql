@@ -0,0 +1,2 @@
+-- Ordered post-processing actions of a rule as a JSON array (PostgreSQL conditional syntax)
+ALTER TABLE email_rules ADD COLUMN IF NOT EXISTS post_process_actions TEXT NULL;
@@ -0,0 +1,3 @@
+-- Remove the detected server capabilities and fetch strategy of accounts (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts DROP COLUMN IF EXISTS fetch_strategy;
+ALTER TABLE imap_accounts DROP COLUMN IF EXISTS server_capabilities;
@@ -0,0 +1,3 @@
+-- Detected server capabilities and fetch strategy of accounts (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS server_capabilities TEXT NULL;
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS fetch_strategy TEXT NULL;
@@ -0,0 +1,3 @@
+-- Remove the feed output settings (PostgreSQL conditional syntax)
+ALTER TABLE feeds DROP COLUMN IF EXISTS full_content;
+ALTER TABLE feeds DROP COLUMN IF EXISTS item_limit;
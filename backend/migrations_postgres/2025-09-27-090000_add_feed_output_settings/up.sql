@@ -0,0 +1,3 @@
+-- Items in a feed's rendered documents and whether they carry the full HTML body (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS item_limit INTEGER NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS full_content BOOLEAN NULL;
@@ -0,0 +1,3 @@
+-- Remove the feed credentials (PostgreSQL conditional syntax)
+ALTER TABLE feeds DROP COLUMN IF EXISTS auth_password_hash;
+ALTER TABLE feeds DROP COLUMN IF EXISTS auth_username;
@@ -0,0 +1,3 @@
+-- Basic auth credentials of password-protected feeds; the password is an Argon2 hash (PostgreSQL conditional syntax)
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS auth_username TEXT NULL;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS auth_password_hash TEXT NULL;
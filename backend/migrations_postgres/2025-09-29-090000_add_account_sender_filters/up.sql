@@ -0,0 +1,10 @@
+-- Sender addresses and domains an account lets through to its rules or keeps out (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS account_sender_filters (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    imap_account_id TEXT NOT NULL REFERENCES imap_accounts(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_account_sender_filters_pattern ON account_sender_filters(imap_account_id, kind, pattern);
@@ -0,0 +1,2 @@
+-- Remove how accounts receive mail
+ALTER TABLE imap_accounts DROP COLUMN protocol;
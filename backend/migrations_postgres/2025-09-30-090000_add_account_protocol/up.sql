@@ -0,0 +1,2 @@
+-- How an account's mail arrives (PostgreSQL conditional syntax)
+ALTER TABLE imap_accounts ADD COLUMN IF NOT EXISTS protocol TEXT NOT NULL DEFAULT 'imap';
@@ -0,0 +1,3 @@
+-- Remove the sync numbering of items
+DROP INDEX IF EXISTS idx_feed_items_sync_id;
+ALTER TABLE feed_items DROP COLUMN sync_id;
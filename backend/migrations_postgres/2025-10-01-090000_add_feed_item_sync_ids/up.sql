@@ -0,0 +1,3 @@
+-- Number items in the order they arrive, for sync clients paging with `since_id` (PostgreSQL conditional syntax)
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS sync_id BIGSERIAL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_feed_items_sync_id ON feed_items(sync_id);
@@ -0,0 +1,11 @@
+-- Further feeds a rule delivers its matching emails to, besides its own (PostgreSQL conditional syntax)
+CREATE TABLE IF NOT EXISTS rule_feeds (
+    id TEXT PRIMARY KEY DEFAULT uuid_generate_v4()::TEXT,
+    email_rule_id TEXT NOT NULL REFERENCES email_rules(id) ON DELETE CASCADE,
+    feed_id TEXT NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    priority INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT now()::TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_rule_feeds_rule_feed ON rule_feeds(email_rule_id, feed_id);
+CREATE INDEX IF NOT EXISTS idx_rule_feeds_feed_id ON rule_feeds(feed_id);
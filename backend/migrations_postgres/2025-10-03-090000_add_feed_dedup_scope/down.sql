@@ -0,0 +1,4 @@
+-- Restore the single-column Message-ID index and remove the dedup scope (PostgreSQL conditional syntax)
+DROP INDEX IF EXISTS idx_feed_items_message_id_feed;
+CREATE INDEX IF NOT EXISTS idx_feed_items_email_message_id ON feed_items(email_message_id);
+ALTER TABLE feeds DROP COLUMN IF EXISTS dedup_scope;
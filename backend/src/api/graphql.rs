@@ -0,0 +1,459 @@
+//! GraphQL endpoint at `/api/graphql`
+//!
+//! Serves the accounts, rules, feeds and items of the REST API as one graph,
+//! so a client such as the dashboard can fetch accounts with their rules,
+//! feeds and item counts in a single request and select only the fields it
+//! shows. Queries are read-only; changes still go through the REST API.
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Result, Schema};
+use axum::{extract::State, routing::post, Json, Router};
+
+use crate::api::AppState;
+use crate::feed::{blob::BlobStore, bodies};
+use crate::db::{
+    connection::DatabasePool,
+    models::{EmailRule, Feed, FeedItem, ImapAccount},
+    operations_generic::{EmailRuleOpsGeneric, FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric},
+};
+
+/// Deepest nesting of fields a query may use
+pub const MAX_QUERY_DEPTH: usize = 8;
+
+pub type ApiSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn schema() -> ApiSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+pub fn routes() -> Router<AppState> {
+    let schema = schema();
+    Router::new().route(
+        "/api/graphql",
+        post(move |State(state): State<AppState>, Json(request): Json<async_graphql::Request>| async move {
+            Json(schema.execute(request.data(state.pool).data(state.body_store)).await)
+        }),
+    )
+}
+
+fn pool<'a>(ctx: &Context<'a>) -> Result<&'a DatabasePool> {
+    ctx.data::<DatabasePool>()
+}
+
+pub struct QueryRoot;
+
+#[Object(rename_fields = "snake_case")]
+impl QueryRoot {
+    /// All IMAP accounts
+    async fn accounts(&self, ctx: &Context<'_>) -> Result<Vec<AccountNode>> {
+        Ok(ImapAccountOpsGeneric::get_all(pool(ctx)?)?.into_iter().map(AccountNode).collect())
+    }
+
+    async fn account(&self, ctx: &Context<'_>, id: String) -> Result<AccountNode> {
+        Ok(AccountNode(ImapAccountOpsGeneric::get_by_id(pool(ctx)?, &id)?))
+    }
+
+    /// All email rules
+    async fn rules(&self, ctx: &Context<'_>) -> Result<Vec<RuleNode>> {
+        Ok(EmailRuleOpsGeneric::get_all(pool(ctx)?)?.into_iter().map(RuleNode).collect())
+    }
+
+    async fn rule(&self, ctx: &Context<'_>, id: String) -> Result<RuleNode> {
+        Ok(RuleNode(EmailRuleOpsGeneric::get_by_id(pool(ctx)?, &id)?))
+    }
+
+    /// All feeds
+    async fn feeds(&self, ctx: &Context<'_>) -> Result<Vec<FeedNode>> {
+        Ok(FeedOpsGeneric::get_all(pool(ctx)?)?.into_iter().map(FeedNode).collect())
+    }
+
+    async fn feed(&self, ctx: &Context<'_>, id: String) -> Result<FeedNode> {
+        Ok(FeedNode(FeedOpsGeneric::get_by_id(pool(ctx)?, &id)?))
+    }
+}
+
+/// An IMAP account; the password is not exposed
+pub struct AccountNode(ImapAccount);
+
+#[Object(name = "Account", rename_fields = "snake_case")]
+impl AccountNode {
+    async fn id(&self) -> Option<&str> {
+        self.0.id.as_deref()
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn host(&self) -> &str {
+        &self.0.host
+    }
+
+    async fn port(&self) -> i32 {
+        self.0.port
+    }
+
+    async fn username(&self) -> &str {
+        &self.0.username
+    }
+
+    async fn use_tls(&self) -> bool {
+        self.0.use_tls
+    }
+
+    /// `none`, `starttls` or `ssl_tls`
+    async fn security(&self) -> &str {
+        &self.0.security
+    }
+
+    async fn created_at(&self) -> &str {
+        &self.0.created_at
+    }
+
+    async fn updated_at(&self) -> &str {
+        &self.0.updated_at
+    }
+
+    async fn default_post_process_action(&self) -> &str {
+        &self.0.default_post_process_action
+    }
+
+    async fn default_move_to_folder(&self) -> Option<&str> {
+        self.0.default_move_to_folder.as_deref()
+    }
+
+    async fn quota_group_id(&self) -> Option<&str> {
+        self.0.quota_group_id.as_deref()
+    }
+
+    async fn max_feeds(&self) -> Option<i32> {
+        self.0.max_feeds
+    }
+
+    async fn max_items(&self) -> Option<i32> {
+        self.0.max_items
+    }
+
+    /// The account's email rules
+    async fn rules(&self, ctx: &Context<'_>) -> Result<Vec<RuleNode>> {
+        let Some(id) = &self.0.id else { return Ok(Vec::new()) };
+        Ok(EmailRuleOpsGeneric::get_by_account_id(pool(ctx)?, id)?.into_iter().map(RuleNode).collect())
+    }
+
+    /// Items stored across the feeds of the account's rules
+    async fn item_count(&self, ctx: &Context<'_>) -> Result<i64> {
+        let Some(id) = &self.0.id else { return Ok(0) };
+        Ok(FeedItemOpsGeneric::count_by_account_ids(pool(ctx)?, std::slice::from_ref(id))?)
+    }
+}
+
+/// An email rule
+pub struct RuleNode(EmailRule);
+
+#[Object(name = "Rule", rename_fields = "snake_case")]
+impl RuleNode {
+    async fn id(&self) -> Option<&str> {
+        self.0.id.as_deref()
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn imap_account_id(&self) -> &str {
+        &self.0.imap_account_id
+    }
+
+    async fn folder(&self) -> &str {
+        &self.0.folder
+    }
+
+    async fn to_address(&self) -> Option<&str> {
+        self.0.to_address.as_deref()
+    }
+
+    async fn from_address(&self) -> Option<&str> {
+        self.0.from_address.as_deref()
+    }
+
+    async fn subject_contains(&self) -> Option<&str> {
+        self.0.subject_contains.as_deref()
+    }
+
+    async fn label(&self) -> Option<&str> {
+        self.0.label.as_deref()
+    }
+
+    async fn is_active(&self) -> bool {
+        self.0.is_active
+    }
+
+    async fn created_at(&self) -> &str {
+        &self.0.created_at
+    }
+
+    async fn updated_at(&self) -> &str {
+        &self.0.updated_at
+    }
+
+    async fn post_process_action(&self) -> &str {
+        &self.0.post_process_action
+    }
+
+    async fn move_to_folder(&self) -> Option<&str> {
+        self.0.move_to_folder.as_deref()
+    }
+
+    async fn observe_only(&self) -> bool {
+        self.0.observe_only
+    }
+
+    async fn importance(&self) -> Option<&str> {
+        self.0.importance.as_deref()
+    }
+
+    async fn processing_order(&self) -> &str {
+        &self.0.processing_order
+    }
+
+    async fn post_process_delay_hours(&self) -> Option<i32> {
+        self.0.post_process_delay_hours
+    }
+
+    async fn match_expression(&self) -> Option<&str> {
+        self.0.match_expression.as_deref()
+    }
+
+    async fn fetch_limit(&self) -> Option<i32> {
+        self.0.fetch_limit
+    }
+
+    async fn include_seen(&self) -> bool {
+        self.0.include_seen
+    }
+
+    async fn include_subfolders(&self) -> bool {
+        self.0.include_subfolders
+    }
+
+    async fn strip_footers(&self) -> bool {
+        self.0.strip_footers
+    }
+
+    async fn strip_quoted_replies(&self) -> bool {
+        self.0.strip_quoted_replies
+    }
+
+    async fn strip_tracking_pixels(&self) -> bool {
+        self.0.strip_tracking_pixels
+    }
+
+    async fn unwrap_tracking_links(&self) -> bool {
+        self.0.unwrap_tracking_links
+    }
+
+    /// Post-processing chain as JSON, replacing the single action when set
+    async fn post_process_actions(&self) -> Option<&str> {
+        self.0.post_process_actions.as_deref()
+    }
+
+    /// The account the rule reads from
+    async fn account(&self, ctx: &Context<'_>) -> Result<AccountNode> {
+        Ok(AccountNode(ImapAccountOpsGeneric::get_by_id(pool(ctx)?, &self.0.imap_account_id)?))
+    }
+
+    /// The feeds fed by the rule
+    async fn feeds(&self, ctx: &Context<'_>) -> Result<Vec<FeedNode>> {
+        let Some(id) = &self.0.id else { return Ok(Vec::new()) };
+        Ok(FeedOpsGeneric::get_by_rule_id(pool(ctx)?, id)?.into_iter().map(FeedNode).collect())
+    }
+}
+
+/// A feed
+pub struct FeedNode(Feed);
+
+#[Object(name = "Feed", rename_fields = "snake_case")]
+impl FeedNode {
+    async fn id(&self) -> Option<&str> {
+        self.0.id.as_deref()
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn link(&self) -> Option<&str> {
+        self.0.link.as_deref()
+    }
+
+    async fn email_rule_id(&self) -> &str {
+        &self.0.email_rule_id
+    }
+
+    async fn feed_type(&self) -> &str {
+        &self.0.feed_type
+    }
+
+    async fn is_active(&self) -> bool {
+        self.0.is_active
+    }
+
+    async fn created_at(&self) -> &str {
+        &self.0.created_at
+    }
+
+    async fn updated_at(&self) -> &str {
+        &self.0.updated_at
+    }
+
+    async fn max_items(&self) -> Option<i32> {
+        self.0.max_items
+    }
+
+    async fn max_age_days(&self) -> Option<i32> {
+        self.0.max_age_days
+    }
+
+    async fn min_items(&self) -> Option<i32> {
+        self.0.min_items
+    }
+
+    /// Whether items are kept unaltered and chained by hash
+    async fn append_only(&self) -> bool {
+        self.0.append_only
+    }
+
+    /// 'thread' or 'day' when emails are merged into combined items
+    async fn digest_mode(&self) -> Option<&str> {
+        self.0.digest_mode.as_deref()
+    }
+
+    /// The rule that fills the feed
+    async fn rule(&self, ctx: &Context<'_>) -> Result<RuleNode> {
+        Ok(RuleNode(EmailRuleOpsGeneric::get_by_id(pool(ctx)?, &self.0.email_rule_id)?))
+    }
+
+    /// Items stored in the feed
+    async fn item_count(&self, ctx: &Context<'_>) -> Result<i64> {
+        let Some(id) = &self.0.id else { return Ok(0) };
+        Ok(FeedItemOpsGeneric::count_by_feed_id(pool(ctx)?, id)?)
+    }
+
+    /// The feed's items, pinned first and then newest first
+    async fn items(&self, ctx: &Context<'_>, limit: Option<i64>) -> Result<Vec<ItemNode>> {
+        let Some(id) = &self.0.id else { return Ok(Vec::new()) };
+        Ok(FeedItemOpsGeneric::get_by_feed_id(pool(ctx)?, id, limit)?.into_iter().map(ItemNode).collect())
+    }
+}
+
+/// A feed item
+pub struct ItemNode(FeedItem);
+
+impl ItemNode {
+    /// The item with its body read from the blob store, when it is kept there
+    async fn with_body(&self, ctx: &Context<'_>) -> FeedItem {
+        let mut item = self.0.clone();
+        let store = ctx.data_opt::<Option<BlobStore>>().and_then(Option::as_ref);
+        bodies::load(store, std::slice::from_mut(&mut item)).await;
+        item
+    }
+}
+
+#[Object(name = "FeedItem", rename_fields = "snake_case")]
+impl ItemNode {
+    async fn id(&self) -> Option<&str> {
+        self.0.id.as_deref()
+    }
+
+    async fn feed_id(&self) -> &str {
+        &self.0.feed_id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn link(&self) -> Option<&str> {
+        self.0.link.as_deref()
+    }
+
+    async fn author(&self) -> Option<&str> {
+        self.0.author.as_deref()
+    }
+
+    async fn pub_date(&self) -> &str {
+        &self.0.pub_date
+    }
+
+    async fn email_subject(&self) -> Option<&str> {
+        self.0.email_subject.as_deref()
+    }
+
+    async fn email_from(&self) -> Option<&str> {
+        self.0.email_from.as_deref()
+    }
+
+    async fn email_body(&self, ctx: &Context<'_>) -> Option<String> {
+        match self.0.body_ref {
+            Some(_) => self.with_body(ctx).await.email_body,
+            None => self.0.email_body.clone(),
+        }
+    }
+
+    /// Sanitized HTML part of the email, if it had one
+    async fn email_body_html(&self, ctx: &Context<'_>) -> Option<String> {
+        match self.0.body_ref {
+            Some(_) => self.with_body(ctx).await.email_body_html,
+            None => self.0.email_body_html.clone(),
+        }
+    }
+
+    async fn is_read(&self) -> Option<bool> {
+        self.0.is_read
+    }
+
+    async fn starred(&self) -> Option<bool> {
+        self.0.starred
+    }
+
+    async fn pinned(&self) -> bool {
+        self.0.pinned
+    }
+
+    async fn rating(&self) -> Option<&str> {
+        self.0.rating.as_deref()
+    }
+
+    async fn language(&self) -> Option<&str> {
+        self.0.language.as_deref()
+    }
+
+    async fn importance(&self) -> Option<&str> {
+        self.0.importance.as_deref()
+    }
+
+    async fn category(&self) -> Option<&str> {
+        self.0.category.as_deref()
+    }
+
+    async fn unsubscribe_url(&self) -> Option<&str> {
+        self.0.unsubscribe_url.as_deref()
+    }
+
+    async fn unsubscribe_mailto(&self) -> Option<&str> {
+        self.0.unsubscribe_mailto.as_deref()
+    }
+
+    async fn unsubscribe_one_click(&self) -> bool {
+        self.0.unsubscribe_one_click
+    }
+}
@@ -0,0 +1,108 @@
+//! Maintenance mode
+//!
+//! For backups and upgrades the API can be put into maintenance mode: the
+//! scheduler stops starting runs, cleanups and deliveries, and any request
+//! that could change data is refused with `503 Service Unavailable` and a
+//! `Retry-After` header. Reads, including the public RSS and Atom feeds, are
+//! served as usual. Only the maintenance endpoint itself stays writable, so
+//! the mode can be left again.
+
+use crate::api::{types::{ErrorResponse, MaintenanceStatus}, AppState};
+use axum::{
+    extract::State,
+    http::{header, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Path of the endpoint that turns maintenance mode on and off
+pub const MAINTENANCE_PATH: &str = "/api/admin/maintenance";
+
+/// Seconds refused clients are told to wait when none are given
+pub const DEFAULT_RETRY_AFTER_SECONDS: u64 = 300;
+
+/// A stretch of maintenance, from when it was turned on
+#[derive(Debug, Clone)]
+pub struct MaintenanceWindow {
+    pub since: DateTime<Utc>,
+    pub reason: Option<String>,
+    pub retry_after_seconds: u64,
+}
+
+impl MaintenanceWindow {
+    pub fn new(reason: Option<String>, retry_after_seconds: Option<u64>) -> Self {
+        Self {
+            since: Utc::now(),
+            reason,
+            retry_after_seconds: retry_after_seconds.unwrap_or(DEFAULT_RETRY_AFTER_SECONDS),
+        }
+    }
+}
+
+/// Whether the API is in maintenance mode, shared by all requests
+#[derive(Clone, Default)]
+pub struct MaintenanceMode {
+    window: Arc<RwLock<Option<MaintenanceWindow>>>,
+}
+
+impl MaintenanceMode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current window, if maintenance mode is on
+    pub async fn current(&self) -> Option<MaintenanceWindow> {
+        self.window.read().await.clone()
+    }
+
+    /// Turn maintenance mode on; the window already open is kept if it is on
+    pub async fn enter(&self, window: MaintenanceWindow) -> MaintenanceWindow {
+        self.window.write().await.get_or_insert(window).clone()
+    }
+
+    /// Turn maintenance mode off, returning the window that was open
+    pub async fn leave(&self) -> Option<MaintenanceWindow> {
+        self.window.write().await.take()
+    }
+
+    /// Status of the mode with `in_flight_runs` processing runs still going
+    pub async fn status(&self, in_flight_runs: usize) -> MaintenanceStatus {
+        let window = self.current().await;
+        MaintenanceStatus {
+            active: window.is_some(),
+            since: window.as_ref().map(|window| window.since.to_rfc3339()),
+            reason: window.as_ref().and_then(|window| window.reason.clone()),
+            retry_after_seconds: window.as_ref().map(|window| window.retry_after_seconds),
+            in_flight_runs,
+        }
+    }
+}
+
+/// Refuse requests that could change data while maintenance mode is on
+pub async fn refuse_writes<B>(
+    State(state): State<AppState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let read_only = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if read_only || request.uri().path() == MAINTENANCE_PATH {
+        return next.run(request).await;
+    }
+    let Some(window) = state.maintenance.current().await else {
+        return next.run(request).await;
+    };
+
+    let error = match &window.reason {
+        Some(reason) => format!("Down for maintenance: {}", reason),
+        None => "Down for maintenance".to_string(),
+    };
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, window.retry_after_seconds.to_string())],
+        Json(ErrorResponse { error }),
+    ).into_response()
+}
@@ -1,26 +1,68 @@
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod maintenance;
+pub mod openapi;
+pub mod request_id;
 pub mod routes;
+pub mod types;
+pub mod version;
 
-use crate::{background::BackgroundServiceHandle, db::connection::DatabasePool};
-use axum::Router;
+use crate::{
+    background::{jobs::JobRunner, tasks::TaskRegistry, BackgroundServiceHandle},
+    db::connection::DatabasePool,
+    feed::{blob::BlobStore, bodies},
+};
+use axum::{middleware, Router};
+use maintenance::MaintenanceMode;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: DatabasePool,
     pub background: BackgroundServiceHandle,
+    pub tasks: TaskRegistry,
+    pub jobs: JobRunner,
+    pub maintenance: MaintenanceMode,
+    /// Where item bodies moved out of the database are kept
+    pub body_store: Option<BlobStore>,
 }
 
 pub fn create_routes(pool: DatabasePool, background_handle: BackgroundServiceHandle) -> Router {
     let state = AppState {
+        jobs: JobRunner::new(pool.clone()),
         pool,
         background: background_handle,
+        tasks: TaskRegistry::new(),
+        maintenance: MaintenanceMode::new(),
+        body_store: bodies::store_from_env(),
     };
 
-    Router::new()
+    let router = Router::new()
         .merge(routes::health::routes())
+        .merge(routes::metrics::routes())
         .merge(routes::imap_accounts::routes())
         .merge(routes::email_rules::routes())
         .merge(routes::feeds::routes())
+        .merge(routes::fever::routes())
+        .merge(routes::timeline::routes())
+        .merge(routes::chat_integrations::routes())
+        .merge(routes::deliveries::routes())
+        .merge(routes::quotas::routes())
+        .merge(routes::senders::routes())
+        .merge(routes::sender_filters::routes())
         .merge(routes::imap_operations::routes())
+        .merge(routes::setup::routes())
+        .merge(routes::import::routes())
         .merge(routes::background::routes())
+        .merge(routes::admin::routes())
+        .merge(routes::jobs::routes())
+        .merge(routes::analysis::routes())
+        .merge(routes::settings::routes());
+    #[cfg(feature = "graphql")]
+    let router = router.merge(graphql::routes());
+
+    router
+        .layer(middleware::from_fn_with_state(state.clone(), maintenance::refuse_writes))
         .with_state(state)
+        .merge(openapi::routes())
+        .layer(middleware::from_fn(request_id::trace_requests))
 }
@@ -0,0 +1,322 @@
+//! OpenAPI specification of the HTTP API
+//!
+//! Generated from the `#[utoipa::path]` annotations on the route handlers and
+//! the `ToSchema` derives on the request, response and model types. Served as
+//! JSON at `/api/openapi.json` with Swagger UI at `/api/docs`.
+
+use axum::Router;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::api::{routes, types};
+use crate::background::config::{BackgroundConfig, ProcessingLimits, RetryConfig};
+use crate::background::service::ServiceState;
+use crate::db::models::{AccountSenderFilter, ChatIntegration, Delivery, EmailRule, Feed, FeedItem, ImapAccount, ProcessingIntent, ProcessingRun, QuotaGroup, RuleCost, RuleMatch};
+
+pub const OPENAPI_JSON_PATH: &str = "/api/openapi.json";
+pub const SWAGGER_UI_PATH: &str = "/api/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "mail2feed API", description = "Turn IMAP mailboxes into RSS and Atom feeds"),
+    paths(
+        routes::health::health_check,
+        routes::health::liveness,
+        routes::health::readiness,
+        routes::metrics::get_metrics,
+        routes::imap_accounts::list_accounts,
+        routes::imap_accounts::create_account,
+        routes::imap_accounts::get_account,
+        routes::imap_accounts::update_account,
+        routes::imap_accounts::delete_account,
+        routes::email_rules::list_rules,
+        routes::email_rules::create_rule,
+        routes::email_rules::get_rule,
+        routes::email_rules::update_rule,
+        routes::email_rules::delete_rule,
+        routes::email_rules::get_rule_stats,
+        routes::email_rules::get_rule_preview,
+        routes::email_rules::backfill_rule,
+        routes::email_rules::list_rule_feeds,
+        routes::email_rules::attach_rule_feed,
+        routes::email_rules::detach_rule_feed,
+        routes::email_rules::create_rule_with_feed,
+        routes::email_rules::validate_match_expression,
+        routes::feeds::list_feeds,
+        routes::feeds::create_feed,
+        routes::feeds::get_feed,
+        routes::feeds::update_feed,
+        routes::feeds::delete_feed,
+        routes::feeds::get_feed_items,
+        routes::feeds::get_feed_items_metadata,
+        routes::feeds::verify_feed_chain,
+        routes::feeds::cleanup_feed,
+        routes::feeds::cleanup_all_feeds,
+        routes::feeds::export_feed,
+        routes::feeds::download_export,
+        routes::feeds::test_feed_webhook,
+        routes::timeline::get_timeline,
+        routes::chat_integrations::list_integrations,
+        routes::chat_integrations::create_integration,
+        routes::chat_integrations::get_integration,
+        routes::chat_integrations::update_integration,
+        routes::chat_integrations::delete_integration,
+        routes::chat_integrations::test_integration,
+        routes::deliveries::list_failed,
+        routes::deliveries::retry_failed,
+        routes::deliveries::retry_delivery,
+        routes::deliveries::delete_delivery,
+        routes::quotas::list_groups,
+        routes::quotas::create_group,
+        routes::quotas::get_group,
+        routes::quotas::update_group,
+        routes::quotas::delete_group,
+        routes::quotas::get_group_usage,
+        routes::quotas::get_account_quota,
+        routes::senders::list_aliases,
+        routes::senders::create_alias,
+        routes::senders::get_alias,
+        routes::senders::update_alias,
+        routes::senders::delete_alias,
+        routes::senders::get_sender_stats,
+        routes::sender_filters::list_filters,
+        routes::sender_filters::create_filter,
+        routes::sender_filters::get_filter,
+        routes::sender_filters::update_filter,
+        routes::sender_filters::delete_filter,
+        routes::feeds::get_feed_item,
+        routes::feeds::update_feed_item,
+        routes::feeds::bulk_update_feed_items,
+        routes::feeds::share_feed_item,
+        routes::feeds::unsubscribe_feed_item,
+        routes::feeds::get_rss_feed,
+        routes::feeds::get_atom_feed,
+        routes::feeds::get_item_page,
+        routes::feeds::get_item_html,
+        routes::feeds::get_item_attachment,
+        routes::feeds::get_shared_item_page,
+        routes::imap_operations::test_connection,
+        routes::imap_operations::tls_fingerprint,
+        routes::imap_operations::list_folders,
+        routes::imap_operations::diagnose_account,
+        routes::imap_operations::diagnose_settings,
+        routes::imap_operations::process_account,
+        routes::imap_operations::process_all_accounts,
+        routes::setup::setup_status,
+        routes::setup::initial_setup,
+        routes::setup::validate_connection,
+        routes::setup::suggest_folders,
+        routes::setup::finalize,
+        routes::import::import,
+        routes::background::get_status,
+        routes::background::start_service,
+        routes::background::stop_service,
+        routes::background::restart_service,
+        routes::background::update_config,
+        routes::background::process_account,
+        routes::background::process_all_accounts,
+        routes::background::list_runs,
+        routes::background::get_run,
+        routes::background::get_run_intents,
+        routes::background::get_run_rule_costs,
+        routes::background::rollback_run,
+        routes::admin::get_maintenance,
+        routes::admin::enter_maintenance,
+        routes::admin::leave_maintenance,
+        routes::admin::backfill_metadata,
+        routes::admin::offload_bodies,
+        routes::admin::list_tasks,
+        routes::admin::get_task,
+        routes::admin::get_version,
+        routes::admin::merge_feeds,
+        routes::admin::split_feed,
+        routes::jobs::list_jobs,
+        routes::jobs::get_job,
+        routes::analysis::storage_forecast,
+        routes::analysis::rating_report,
+        routes::analysis::rule_cost_report,
+        routes::analysis::stats,
+        routes::fever::fever_api,
+        routes::settings::get_settings,
+        routes::settings::update_settings,
+        routes::settings::test_notifications,
+    ),
+    components(schemas(
+        ImapAccount,
+        EmailRule,
+        Feed,
+        FeedItem,
+        ProcessingRun,
+        ProcessingIntent,
+        RuleMatch,
+        RuleCost,
+        ChatIntegration,
+        AccountSenderFilter,
+        Delivery,
+        QuotaGroup,
+        BackgroundConfig,
+        RetryConfig,
+        ProcessingLimits,
+        ServiceState,
+        types::ErrorResponse,
+        types::HealthResponse,
+        types::LivenessResponse,
+        types::ReadinessResponse,
+        types::DependencyCheck,
+        types::DependencyStatus,
+        types::BackgroundCheck,
+        types::AccountFreshness,
+        types::CreateImapAccountRequest,
+        types::DuplicateAccountResponse,
+        types::UpdateImapAccountRequest,
+        types::CreateEmailRuleRequest,
+        types::UpdateEmailRuleRequest,
+        types::PostProcessStep,
+        types::RuleStatsResponse,
+        types::RuleFeedRequest,
+        types::CreateRuleWithFeedRequest,
+        types::RuleWithFeedResponse,
+        types::SampleEmail,
+        types::ValidateMatchExpressionRequest,
+        types::MatchExpressionValidation,
+        types::CreateFeedRequest,
+        types::UpdateFeedRequest,
+        types::FeedItemMetadata,
+        types::FeedItemMetadataPage,
+        types::FeedItemsPage,
+        types::ChainVerification,
+        types::UpdateFeedItemRequest,
+        types::BulkItemAction,
+        types::BulkFeedItemsRequest,
+        types::BulkFeedItemsResponse,
+        types::ShareFeedItemRequest,
+        types::SharedItemLink,
+        types::UnsubscribeResponse,
+        types::TimelineItem,
+        types::TimelineResponse,
+        types::WebhookTestResponse,
+        types::ChatIntegrationRequest,
+        types::QuotaGroupRequest,
+        types::QuotaReport,
+        types::QuotaUsage,
+        types::AccountQuotaResponse,
+        types::SenderAliasRequest,
+        types::SenderAliasResponse,
+        types::SenderStats,
+        types::SenderFilterRequest,
+        types::TestConnectionResponse,
+        types::TlsFingerprintResponse,
+        types::FolderNode,
+        types::ConnectionDiagnostics,
+        types::DiagnosticStep,
+        types::StepStatus,
+        types::ProcessAccountResponse,
+        types::SetupConnectionRequest,
+        types::SetupConnectionResponse,
+        types::FolderSuggestion,
+        types::SetupFeedRequest,
+        types::SetupFinalizeRequest,
+        types::SetupFinalizeResponse,
+        types::SetupDatabaseStatus,
+        types::SetupStatusResponse,
+        types::AdminUserRequest,
+        types::InitialSetupRequest,
+        types::InitialSetupResponse,
+        types::ImportRequest,
+        types::ImportResponse,
+        types::SkippedImport,
+        types::ImportFormat,
+        types::ImportMapping,
+        types::MatchOn,
+        types::BackgroundStatusResponse,
+        types::StartServiceRequest,
+        types::BackgroundProcessResponse,
+        types::ServiceActionResponse,
+        types::BackfillMetadataRequest,
+        types::BackfillRuleRequest,
+        types::AttachFeedRequest,
+        types::RuleFeedResponse,
+        types::OffloadBodiesRequest,
+        types::TaskStartedResponse,
+        types::JobStartedResponse,
+        types::ExportFormat,
+        types::ExportResult,
+        types::JobResponse,
+        types::JobStatus,
+        types::EnterMaintenanceRequest,
+        types::MaintenanceStatus,
+        types::MergeFeedsRequest,
+        types::SplitFeedRequest,
+        types::MovedFeedItem,
+        types::FeedReorganizationResponse,
+        types::VersionResponse,
+        types::VersionChange,
+        types::ChangeKind,
+        types::ServiceStatus,
+        types::RollbackResult,
+        types::TaskState,
+        types::TaskStatus,
+        types::StorageForecast,
+        types::FeedForecast,
+        types::RatingReport,
+        types::SenderRating,
+        types::RuleRating,
+        types::RuleSuggestion,
+        types::RuleCostReport,
+        types::RuleCostSummary,
+        types::FolderCostSummary,
+        types::StatsResponse,
+        types::StorageStatus,
+        types::Stats,
+        types::FeedStats,
+        types::DayCount,
+        types::TopSender,
+        types::AccountStats,
+        types::Safeguard,
+        types::SettingsResponse,
+        types::RuntimeSetting,
+        types::SettingSource,
+        types::SettingKind,
+        types::RetentionSetting,
+        types::FeedRetention,
+        types::NotificationSettings,
+        types::NotificationChannel,
+        types::ChannelKind,
+        types::SmtpSecurity,
+        types::NotificationTestResult,
+        types::CleanupResult,
+        types::FeedCleanup,
+        types::CleanupReport,
+        types::RetentionSettingRequest,
+        types::UpdateSettingsRequest,
+        types::FeverUserRequest,
+    )),
+    tags(
+        (name = "health", description = "Service health"),
+        (name = "metrics", description = "Prometheus metrics on feed and account freshness"),
+        (name = "imap-accounts", description = "IMAP account management"),
+        (name = "email-rules", description = "Rules selecting which emails become feed items"),
+        (name = "feeds", description = "Feeds, feed items and rendered RSS/Atom documents"),
+        (name = "chat-integrations", description = "Slack, Discord and Matrix channels receiving new feed items"),
+        (name = "deliveries", description = "Queue of outbound webhook and chat requests and its dead letters"),
+        (name = "quotas", description = "Limits on feeds, stored items and processing time per account or quota group"),
+        (name = "senders", description = "Sender alias groups, per-sender statistics and account sender filters"),
+        (name = "imap", description = "Connection tests and on-demand processing"),
+        (name = "setup", description = "First-run setup and guided account setup: connection probe, folder suggestions and creating account, rules and feeds at once"),
+        (name = "import", description = "Rules and feeds from the exports of other mail-to-RSS tools"),
+        (name = "background", description = "Background processing service and processing runs"),
+        (name = "admin", description = "Maintenance tasks, feed reorganization and version"),
+        (name = "jobs", description = "Progress and outcome of backfills, on-demand processing and other long-running jobs"),
+        (name = "analysis", description = "Storage forecasts for retention planning, storage monitoring, rating reports and rule evaluation costs"),
+        (name = "settings", description = "Server-wide settings such as the retention of history tables"),
+        (name = "fever", description = "Fever-compatible sync API for mobile feed readers"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Routes serving the generated spec and Swagger UI
+pub fn routes() -> Router {
+    SwaggerUi::new(SWAGGER_UI_PATH)
+        .url(OPENAPI_JSON_PATH, ApiDoc::openapi())
+        .into()
+}
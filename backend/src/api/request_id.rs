@@ -0,0 +1,51 @@
+//! Request IDs for correlating logs
+//!
+//! Every request gets an ID: the `X-Request-Id` a proxy in front already set,
+//! or a new UUID. Whatever is logged while handling the request, jobs it
+//! starts included, runs in a `request` span carrying the ID, and the
+//! response echoes it back in `X-Request-Id`, so a client's error report can
+//! be matched with the server's logs.
+
+use axum::{
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::{info, info_span, Instrument};
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest incoming request ID taken over; longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// ID of the request being handled, for handlers that need it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// The request's own ID when it is a sensible one to log
+fn incoming_id<B>(request: &Request<B>) -> Option<String> {
+    let id = request.headers().get(&REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let sensible = !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH && id.chars().all(|c| c.is_ascii_graphic());
+    sensible.then(|| id.to_string())
+}
+
+/// Handle the request in a span with its ID, log its outcome and return the ID
+pub async fn trace_requests<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let id = incoming_id(&request).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    request.extensions_mut().insert(RequestId(id.clone()));
+    // The path only: query strings may hold tokens and signatures
+    let span = info_span!("request", request_id = %id, method = %request.method(), path = request.uri().path());
+
+    let started = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.in_scope(|| info!(
+        status = response.status().as_u16(),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "Handled request"
+    ));
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
@@ -0,0 +1,381 @@
+use crate::{
+    api::{
+        maintenance::{MaintenanceWindow, MAINTENANCE_PATH},
+        types::{BackfillMetadataRequest, EnterMaintenanceRequest, FeedReorganizationResponse, MaintenanceStatus, MergeFeedsRequest, MovedFeedItem, OffloadBodiesRequest, SplitFeedRequest, TaskStartedResponse, TaskState, TaskStatus, VersionResponse},
+        version, AppState,
+    },
+    background::maintenance::{BodyOffloadService, MetadataBackfillService, BACKFILL_METADATA_TASK, OFFLOAD_BODIES_TASK},
+    background::quota::{self, QuotaExceeded, QuotaResource},
+    db::{models::Feed, operations_generic::{EmailRuleOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric}},
+    feed::reorganize::{self, AppendOnlyFeed, Reorganization, SplitFilter},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Seconds to wait for runs in progress when entering maintenance mode
+const DEFAULT_DRAIN_TIMEOUT_SECONDS: u64 = 60;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(MAINTENANCE_PATH, get(get_maintenance).post(enter_maintenance).delete(leave_maintenance))
+        .route("/api/admin/maintenance/backfill-metadata", post(backfill_metadata))
+        .route("/api/admin/maintenance/offload-bodies", post(offload_bodies))
+        .route("/api/admin/maintenance/tasks", get(list_tasks))
+        .route("/api/admin/maintenance/tasks/:task_id", get(get_task))
+        .route("/api/admin/version", get(get_version))
+        .route("/api/admin/feeds/merge", post(merge_feeds))
+        .route("/api/admin/feeds/:id/split", post(split_feed))
+}
+
+fn find_feed(state: &AppState, id: &str) -> Result<Feed, (StatusCode, String)> {
+    FeedOpsGeneric::get_by_id(&state.pool, id)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Feed '{}' not found", id)))
+}
+
+/// Append-only feeds conflict; anything else is a database error
+fn reorganization_error(action: &str, error: anyhow::Error) -> (StatusCode, String) {
+    if error.is::<AppendOnlyFeed>() {
+        (StatusCode::CONFLICT, error.to_string())
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to {} feeds: {}", action, error))
+    }
+}
+
+fn reorganization_response(dry_run: bool, source_feed_id: String, target_feed_id: Option<String>, reorganization: Reorganization) -> FeedReorganizationResponse {
+    let items: Vec<MovedFeedItem> = reorganization.items.into_iter().map(|item| MovedFeedItem {
+        id: item.id.unwrap_or_default(),
+        title: item.title,
+        email_from: item.email_from,
+        pub_date: item.pub_date,
+    }).collect();
+    FeedReorganizationResponse { dry_run, source_feed_id, target_feed_id, items_moved: items.len(), items }
+}
+
+/// Move every item of one feed into another and redirect the first one's URLs
+#[utoipa::path(
+    post,
+    path = "/api/admin/feeds/merge",
+    tag = "admin",
+    request_body = MergeFeedsRequest,
+    responses(
+        (status = 200, description = "Items moved, or that would be on a dry run", body = FeedReorganizationResponse),
+        (status = 400, description = "A feed cannot be merged into itself", body = String),
+        (status = 404, description = "Feed not found", body = String),
+        (status = 409, description = "One of the feeds is append-only", body = String),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
+async fn merge_feeds(
+    State(state): State<AppState>,
+    Json(req): Json<MergeFeedsRequest>,
+) -> Result<Json<FeedReorganizationResponse>, (StatusCode, String)> {
+    if req.source_feed_id == req.target_feed_id {
+        return Err((StatusCode::BAD_REQUEST, "A feed cannot be merged into itself".to_string()));
+    }
+    let source = find_feed(&state, &req.source_feed_id)?;
+    let target = find_feed(&state, &req.target_feed_id)?;
+
+    let merged = reorganize::merge(&state.pool, &source, &target, req.dry_run)
+        .map_err(|e| reorganization_error("merge", e))?;
+    if !req.dry_run {
+        info!("Merged feed {} into {}, moving {} items", req.source_feed_id, req.target_feed_id, merged.items.len());
+    }
+    Ok(Json(reorganization_response(req.dry_run, req.source_feed_id, Some(req.target_feed_id), merged)))
+}
+
+/// Move the items of a feed matching a filter into a new feed
+#[utoipa::path(
+    post,
+    path = "/api/admin/feeds/{id}/split",
+    tag = "admin",
+    params(("id" = String, Path, description = "Feed to split")),
+    request_body = SplitFeedRequest,
+    responses(
+        (status = 200, description = "Items moved, or that would be on a dry run", body = FeedReorganizationResponse),
+        (status = 400, description = "No title or no filter condition", body = String),
+        (status = 403, description = "The new feed's account has no feeds left", body = String),
+        (status = 404, description = "Feed or rule not found", body = String),
+        (status = 409, description = "The feed is append-only", body = String),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
+async fn split_feed(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(req): Json<SplitFeedRequest>,
+) -> Result<Json<FeedReorganizationResponse>, (StatusCode, String)> {
+    if req.title.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "The new feed needs a title".to_string()));
+    }
+    let filter = SplitFilter {
+        sender_domain: req.sender_domain.clone(),
+        from_address: req.from_address.clone(),
+        subject_contains: req.subject_contains.clone(),
+    };
+    if filter.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Give sender_domain, from_address or subject_contains".to_string()));
+    }
+    let source = find_feed(&state, &id)?;
+    let email_rule_id = req.email_rule_id.clone().unwrap_or_else(|| source.email_rule_id.clone());
+    let rule = EmailRuleOpsGeneric::get_by_id(&state.pool, &email_rule_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Email rule '{}' not found", email_rule_id)))?;
+    if !req.dry_run {
+        let account = ImapAccountOpsGeneric::get_by_id(&state.pool, &rule.imap_account_id)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load the rule's account: {}", e)))?;
+        if let Err(e) = quota::check(&state.pool, &account, QuotaResource::Feeds) {
+            let status = if e.is::<QuotaExceeded>() { StatusCode::FORBIDDEN } else { StatusCode::INTERNAL_SERVER_ERROR };
+            return Err((status, e.to_string()));
+        }
+    }
+
+    let split = reorganize::split(&state.pool, &source, &filter, req.title.trim(), &email_rule_id, req.dry_run)
+        .map_err(|e| reorganization_error("split", e))?;
+    let target_feed_id = split.created_feed.as_ref().and_then(|feed| feed.id.clone());
+    if let Some(target_feed_id) = &target_feed_id {
+        info!("Split {} items of feed {} into new feed {}", split.items.len(), id, target_feed_id);
+    }
+    Ok(Json(reorganization_response(req.dry_run, id, target_feed_id, split)))
+}
+
+/// Get the running version and what changed since the version that ran before it
+#[utoipa::path(
+    get,
+    path = "/api/admin/version",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Version, schema version and changes since the previous version", body = VersionResponse),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
+async fn get_version(State(state): State<AppState>) -> Result<Json<VersionResponse>, (StatusCode, String)> {
+    version::status(&state.pool)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load version: {}", e)))
+}
+
+/// Start backfilling computed metadata on existing feed items (non-blocking)
+#[utoipa::path(
+    post,
+    path = "/api/admin/maintenance/backfill-metadata",
+    tag = "admin",
+    request_body(content = Option<BackfillMetadataRequest>, description = "Optional backfill settings"),
+    responses(
+        (status = 202, description = "Backfill started", body = TaskStartedResponse),
+        (status = 400, description = "Invalid batch size", body = String),
+        (status = 409, description = "A backfill is already running", body = String),
+    )
+)]
+async fn backfill_metadata(
+    State(state): State<AppState>,
+    request: Option<Json<BackfillMetadataRequest>>,
+) -> Result<(StatusCode, Json<TaskStartedResponse>), (StatusCode, String)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    if matches!(request.batch_size, Some(size) if size <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "batch_size must be greater than 0".to_string()));
+    }
+
+    let already_running = state.tasks.list().await.into_iter()
+        .find(|task| task.kind == BACKFILL_METADATA_TASK && task.state == TaskState::Running);
+    if let Some(task) = already_running {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Metadata backfill {} is already running", task.id),
+        ));
+    }
+
+    let task = state.tasks.start(BACKFILL_METADATA_TASK).await;
+    let task_id = task.id().to_string();
+    info!("API request to backfill feed item metadata, task {}", task_id);
+
+    let mut service = MetadataBackfillService::new(state.pool.clone());
+    if let Some(batch_size) = request.batch_size {
+        service = service.with_batch_size(batch_size);
+    }
+    tokio::spawn(async move {
+        service.run(task).await;
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(TaskStartedResponse {
+            task_id,
+            message: "Metadata backfill started".to_string(),
+        }),
+    ))
+}
+
+/// Start moving the bodies of existing feed items from the database to the
+/// configured blob store (non-blocking)
+#[utoipa::path(
+    post,
+    path = "/api/admin/maintenance/offload-bodies",
+    tag = "admin",
+    request_body(content = Option<OffloadBodiesRequest>, description = "Optional offload settings"),
+    responses(
+        (status = 202, description = "Offload started", body = TaskStartedResponse),
+        (status = 400, description = "Invalid batch size or no blob store configured", body = String),
+        (status = 409, description = "An offload is already running", body = String),
+    )
+)]
+async fn offload_bodies(
+    State(state): State<AppState>,
+    request: Option<Json<OffloadBodiesRequest>>,
+) -> Result<(StatusCode, Json<TaskStartedResponse>), (StatusCode, String)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    if matches!(request.batch_size, Some(size) if size <= 0) {
+        return Err((StatusCode::BAD_REQUEST, "batch_size must be greater than 0".to_string()));
+    }
+    let Some(store) = state.body_store.clone() else {
+        return Err((StatusCode::BAD_REQUEST, "No blob store is configured; set BODY_STORE to filesystem or s3".to_string()));
+    };
+
+    let already_running = state.tasks.list().await.into_iter()
+        .find(|task| task.kind == OFFLOAD_BODIES_TASK && task.state == TaskState::Running);
+    if let Some(task) = already_running {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Body offload {} is already running", task.id),
+        ));
+    }
+
+    let task = state.tasks.start(OFFLOAD_BODIES_TASK).await;
+    let task_id = task.id().to_string();
+    info!("API request to move feed item bodies to {} store, task {}", store.kind(), task_id);
+
+    let mut service = BodyOffloadService::new(state.pool.clone(), store);
+    if let Some(batch_size) = request.batch_size {
+        service = service.with_batch_size(batch_size);
+    }
+    if let Some(min_bytes) = request.min_bytes {
+        service = service.with_min_bytes(min_bytes);
+    }
+    tokio::spawn(async move {
+        service.run(task).await;
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(TaskStartedResponse {
+            task_id,
+            message: "Body offload started".to_string(),
+        }),
+    ))
+}
+
+/// List maintenance tasks started since the server came up
+#[utoipa::path(
+    get,
+    path = "/api/admin/maintenance/tasks",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Maintenance tasks, most recent first", body = [TaskStatus]),
+    )
+)]
+async fn list_tasks(State(state): State<AppState>) -> Json<Vec<TaskStatus>> {
+    Json(state.tasks.list().await)
+}
+
+/// Get the progress of a maintenance task
+#[utoipa::path(
+    get,
+    path = "/api/admin/maintenance/tasks/{task_id}",
+    tag = "admin",
+    params(("task_id" = String, Path, description = "Maintenance task ID")),
+    responses(
+        (status = 200, description = "Task progress", body = TaskStatus),
+        (status = 404, description = "Task not found", body = String),
+    )
+)]
+async fn get_task(
+    Path(task_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<TaskStatus>, (StatusCode, String)> {
+    state.tasks.get(&task_id).await
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Task {} not found", task_id)))
+}
+
+/// Runs the background service still has in progress
+async fn in_flight_runs(state: &AppState) -> usize {
+    match state.background.service.read().await.as_ref() {
+        Some(service) => service.in_flight_runs().await,
+        None => 0,
+    }
+}
+
+/// Get whether the API is in maintenance mode
+#[utoipa::path(
+    get,
+    path = "/api/admin/maintenance",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Maintenance mode status", body = MaintenanceStatus),
+    )
+)]
+async fn get_maintenance(State(state): State<AppState>) -> Json<MaintenanceStatus> {
+    let in_flight_runs = in_flight_runs(&state).await;
+    Json(state.maintenance.status(in_flight_runs).await)
+}
+
+/// Enter maintenance mode for a backup or an upgrade
+///
+/// Changes are refused right away and background processing is paused; the
+/// response comes once the runs in progress have finished, or the drain
+/// timeout passed with `in_flight_runs` still going. Feeds keep being served.
+#[utoipa::path(
+    post,
+    path = "/api/admin/maintenance",
+    tag = "admin",
+    request_body(content = Option<EnterMaintenanceRequest>, description = "Optional maintenance settings"),
+    responses(
+        (status = 200, description = "Maintenance mode is on", body = MaintenanceStatus),
+    )
+)]
+async fn enter_maintenance(
+    State(state): State<AppState>,
+    request: Option<Json<EnterMaintenanceRequest>>,
+) -> Json<MaintenanceStatus> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let window = state.maintenance.enter(MaintenanceWindow::new(request.reason, request.retry_after_seconds)).await;
+    info!("Entering maintenance mode (since {})", window.since.to_rfc3339());
+
+    let drain_timeout = Duration::from_secs(request.drain_timeout_seconds.unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECONDS));
+    let in_flight_runs = match state.background.service.read().await.as_ref() {
+        Some(service) => {
+            service.pause_processing();
+            service.wait_until_idle(drain_timeout).await
+        }
+        None => 0,
+    };
+    if in_flight_runs > 0 {
+        warn!("{} processing runs still in progress after {:?}", in_flight_runs, drain_timeout);
+    }
+
+    Json(state.maintenance.status(in_flight_runs).await)
+}
+
+/// Leave maintenance mode and resume background processing
+#[utoipa::path(
+    delete,
+    path = "/api/admin/maintenance",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Maintenance mode is off", body = MaintenanceStatus),
+    )
+)]
+async fn leave_maintenance(State(state): State<AppState>) -> Json<MaintenanceStatus> {
+    if let Some(window) = state.maintenance.leave().await {
+        info!("Leaving maintenance mode entered at {}", window.since.to_rfc3339());
+    }
+    if let Some(service) = state.background.service.read().await.as_ref() {
+        service.resume_processing();
+    }
+    let in_flight_runs = in_flight_runs(&state).await;
+    Json(state.maintenance.status(in_flight_runs).await)
+}
@@ -0,0 +1,140 @@
+use crate::api::{
+    types::{ErrorResponse, RuleCostQuery, StatsQuery, StatsResponse, StorageForecastQuery},
+    AppState,
+};
+use crate::background::storage::{self, StorageLimits};
+use crate::feed::{forecast, ratings};
+use crate::imap::rule_costs;
+use crate::stats;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/analysis/storage-forecast", get(storage_forecast))
+        .route("/api/analysis/ratings", get(rating_report))
+        .route("/api/analysis/rule-costs", get(rule_cost_report))
+        .route("/api/stats", get(stats))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analysis/storage-forecast",
+    tag = "analysis",
+    params(StorageForecastQuery),
+    responses(
+        (status = 200, description = "Storage growth per feed, projected against the size budget, with retention recommendations", body = StorageForecast),
+        (status = 400, description = "Invalid window or budget", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn storage_forecast(
+    State(state): State<AppState>,
+    Query(query): Query<StorageForecastQuery>,
+) -> Response {
+    let window_days = query.window_days.unwrap_or(forecast::DEFAULT_WINDOW_DAYS);
+    if window_days <= 0 {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "window_days must be a positive number of days".to_string() })).into_response();
+    }
+    let budget_bytes = match query.budget_mb {
+        Some(megabytes) if megabytes <= 0 => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "budget_mb must be a positive number of megabytes".to_string() })).into_response(),
+        Some(megabytes) => Some(megabytes * 1024 * 1024),
+        None => forecast::budget_from_env(),
+    };
+
+    match forecast::forecast(&state.pool, window_days, budget_bytes) {
+        Ok(forecast) => Json(forecast).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to forecast storage: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analysis/ratings",
+    tag = "analysis",
+    responses(
+        (status = 200, description = "Item ratings per sender and rule, with senders suggested for exclusion from rules", body = RatingReport),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn rating_report(State(state): State<AppState>) -> Response {
+    match ratings::report(&state.pool) {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to report ratings: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analysis/rule-costs",
+    tag = "analysis",
+    params(RuleCostQuery),
+    responses(
+        (status = 200, description = "Fetch and matching time per rule and folder over recent runs, most expensive first", body = RuleCostReport),
+        (status = 400, description = "Invalid window", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn rule_cost_report(
+    State(state): State<AppState>,
+    Query(query): Query<RuleCostQuery>,
+) -> Response {
+    let window_days = query.window_days.unwrap_or(rule_costs::DEFAULT_WINDOW_DAYS);
+    if window_days <= 0 {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "window_days must be a positive number of days".to_string() })).into_response();
+    }
+
+    match rule_costs::report(&state.pool, window_days) {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to report rule costs: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/stats",
+    tag = "analysis",
+    params(StatsQuery),
+    responses(
+        (status = 200, description = "Storage against its limits, items per feed and day, top senders, average email size and processing error rates per account", body = StatsResponse),
+        (status = 400, description = "Invalid number of days", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Response {
+    let days = query.days.unwrap_or(stats::DEFAULT_DAYS);
+    if !(1..=stats::MAX_DAYS).contains(&days) {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("days must be between 1 and {}", stats::MAX_DAYS) })).into_response();
+    }
+
+    let now = chrono::Utc::now();
+    let storage = match storage::measure(&state.pool, &StorageLimits::from_env()) {
+        Ok(storage) => storage,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to measure storage: {}", e) })).into_response(),
+    };
+    match stats::collect(&state.pool, days, now) {
+        Ok(stats) => Json(StatsResponse {
+            generated_at: now.to_rfc3339(),
+            storage,
+            stats,
+        }).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to collect statistics: {}", e) })).into_response(),
+    }
+}
@@ -1,45 +1,22 @@
 use crate::{
-    api::AppState,
-    background::{self, service::ServiceStatus},
+    api::{
+        types::{BackgroundConfig, BackgroundProcessResponse, BackgroundStatusResponse, ProcessingRunQuery, RollbackResult, ServiceActionResponse, ServiceStatus, StartServiceRequest},
+        AppState,
+    },
+    background::{self, jobs::PROCESS_ACCOUNT_JOB, rollback::RunRollbackService},
+    db::{models::{ProcessingIntent, ProcessingRun, ProcessingRunFilter, ProcessingRunStatus, RuleCost}, operations_generic::{ProcessingIntentOpsGeneric, ProcessingRunOpsGeneric, RuleCostOpsGeneric}},
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, SecondsFormat, Utc};
 use tracing::{error, info};
 
-#[derive(Serialize)]
-pub struct BackgroundStatusResponse {
-    pub status: ServiceStatus,
-}
-
-#[derive(Deserialize)]
-pub struct StartServiceRequest {
-    #[allow(dead_code)]
-    pub force: Option<bool>,
-}
-
-#[derive(Deserialize)]
-pub struct ProcessAccountRequest {
-    #[allow(dead_code)]
-    pub account_id: String,
-}
-
-#[derive(Serialize)]
-pub struct ProcessAccountResponse {
-    pub account_id: String,
-    pub success: bool,
-    pub message: String,
-}
-
-#[derive(Serialize)]
-pub struct ServiceActionResponse {
-    pub success: bool,
-    pub message: String,
-}
+const DEFAULT_RUN_LIMIT: i64 = 50;
+const MAX_RUN_LIMIT: i64 = 500;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -47,11 +24,25 @@ pub fn routes() -> Router<AppState> {
         .route("/api/background/start", post(start_service))
         .route("/api/background/stop", post(stop_service))
         .route("/api/background/restart", post(restart_service))
+        .route("/api/background/config", put(update_config))
         .route("/api/background/process/:account_id", post(process_account))
         .route("/api/background/process-all", post(process_all_accounts))
+        .route("/api/background/runs", get(list_runs))
+        .route("/api/background/runs/:run_id", get(get_run))
+        .route("/api/background/runs/:run_id/intents", get(get_run_intents))
+        .route("/api/background/runs/:run_id/rule-costs", get(get_run_rule_costs))
+        .route("/api/background/runs/:run_id/rollback", post(rollback_run))
 }
 
 /// Get background service status
+#[utoipa::path(
+    get,
+    path = "/api/background/status",
+    tag = "background",
+    responses(
+        (status = 200, description = "Background service status", body = BackgroundStatusResponse),
+    )
+)]
 async fn get_status(
     State(state): State<AppState>,
 ) -> Result<Json<BackgroundStatusResponse>, (StatusCode, String)> {
@@ -65,6 +56,16 @@ async fn get_status(
 }
 
 /// Start the background service
+#[utoipa::path(
+    post,
+    path = "/api/background/start",
+    tag = "background",
+    request_body = StartServiceRequest,
+    responses(
+        (status = 200, description = "Service started", body = ServiceActionResponse),
+        (status = 500, description = "Service failed to start", body = String),
+    )
+)]
 async fn start_service(
     State(state): State<AppState>,
     Json(_request): Json<StartServiceRequest>,
@@ -87,6 +88,15 @@ async fn start_service(
 }
 
 /// Stop the background service
+#[utoipa::path(
+    post,
+    path = "/api/background/stop",
+    tag = "background",
+    responses(
+        (status = 200, description = "Service stopped", body = ServiceActionResponse),
+        (status = 500, description = "Service failed to stop", body = String),
+    )
+)]
 async fn stop_service(
     State(state): State<AppState>,
 ) -> Result<Json<ServiceActionResponse>, (StatusCode, String)> {
@@ -108,6 +118,15 @@ async fn stop_service(
 }
 
 /// Restart the background service
+#[utoipa::path(
+    post,
+    path = "/api/background/restart",
+    tag = "background",
+    responses(
+        (status = 200, description = "Service restarted", body = ServiceActionResponse),
+        (status = 500, description = "Service failed to restart", body = String),
+    )
+)]
 async fn restart_service(
     State(state): State<AppState>,
 ) -> Result<Json<ServiceActionResponse>, (StatusCode, String)> {
@@ -137,43 +156,86 @@ async fn restart_service(
     }
 }
 
+/// Apply a new background configuration without restarting the service
+///
+/// Intervals, concurrency, retry policy and limits take effect right away;
+/// runs already in progress finish under the old settings.
+#[utoipa::path(
+    put,
+    path = "/api/background/config",
+    tag = "background",
+    request_body = BackgroundConfig,
+    responses(
+        (status = 200, description = "Configuration now in effect", body = BackgroundConfig),
+        (status = 400, description = "Invalid configuration, or a change that needs a restart", body = String),
+        (status = 503, description = "Background service not initialized", body = String),
+    )
+)]
+async fn update_config(
+    State(state): State<AppState>,
+    Json(config): Json<BackgroundConfig>,
+) -> Result<Json<BackgroundConfig>, (StatusCode, String)> {
+    info!("API request to update background configuration");
+
+    match background::update_background_config(&state.background, config).await {
+        Ok(Some(config)) => Ok(Json(config)),
+        Ok(None) => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Background service not initialized".to_string(),
+        )),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid background configuration: {}", e),
+        )),
+    }
+}
+
 /// Process a specific account manually
+#[utoipa::path(
+    post,
+    path = "/api/background/process/{account_id}",
+    tag = "background",
+    params(("account_id" = String, Path, description = "IMAP account ID")),
+    responses(
+        (status = 200, description = "Processing queued; follow it under /api/jobs/{id}", body = BackgroundProcessResponse),
+        (status = 404, description = "Account not found", body = String),
+        (status = 500, description = "The job could not be recorded", body = String),
+    )
+)]
 async fn process_account(
     Path(account_id): Path<String>,
     State(state): State<AppState>,
-) -> Result<Json<ProcessAccountResponse>, (StatusCode, String)> {
+) -> Result<Json<BackgroundProcessResponse>, (StatusCode, String)> {
     info!("API request to process account: {}", account_id);
 
     // Verify the account exists
-    let mut conn = state.pool.get().map_err(|e| {
-        error!("Failed to get database connection: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Database connection failed".to_string(),
-        )
-    })?;
-
-    use crate::db::operations::ImapAccountOps;
-    match ImapAccountOps::get_by_id(&mut conn, &account_id) {
+    use crate::db::operations_generic::ImapAccountOpsGeneric;
+    match ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id) {
         Ok(_account) => {
-            // Use the controller to trigger processing
+            let (job, handle) = state.jobs.create(PROCESS_ACCOUNT_JOB, Some(account_id.clone()))
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record job: {}", e)))?;
+
+            // Use the controller to trigger processing; the scheduler reports through the job
             match state
                 .background
                 .controller
-                .process_account_now(account_id.clone())
+                .process_account_now(account_id.clone(), Some(handle.clone()))
                 .await
             {
-                Ok(()) => Ok(Json(ProcessAccountResponse {
+                Ok(()) => Ok(Json(BackgroundProcessResponse {
                     account_id: account_id.clone(),
                     success: true,
                     message: format!("Triggered processing for account {}", account_id),
+                    job_id: job.id,
                 })),
                 Err(e) => {
                     error!("Failed to trigger account processing {}: {}", account_id, e);
-                    Ok(Json(ProcessAccountResponse {
+                    handle.fail(format!("Failed to trigger processing: {}", e));
+                    Ok(Json(BackgroundProcessResponse {
                         account_id: account_id.clone(),
                         success: false,
                         message: format!("Failed to trigger processing: {}", e),
+                        job_id: job.id,
                     }))
                 }
             }
@@ -186,6 +248,15 @@ async fn process_account(
 }
 
 /// Process all accounts manually (non-blocking)
+#[utoipa::path(
+    post,
+    path = "/api/background/process-all",
+    tag = "background",
+    responses(
+        (status = 200, description = "Processing queued", body = ServiceActionResponse),
+        (status = 500, description = "Processing could not be queued", body = String),
+    )
+)]
 async fn process_all_accounts(
     State(state): State<AppState>,
 ) -> Result<Json<ServiceActionResponse>, (StatusCode, String)> {
@@ -206,3 +277,174 @@ async fn process_all_accounts(
         }
     }
 }
+
+/// Parse a `since`/`until` bound into the form `started_at` is stored in
+fn run_time_bound(name: &str, value: Option<&str>) -> Result<Option<String>, (StatusCode, String)> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|at| at.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::AutoSi, false))
+                .map_err(|_| (StatusCode::BAD_REQUEST, format!("{} must be an RFC 3339 timestamp", name)))
+        })
+        .transpose()
+}
+
+/// List recorded processing runs, newest first
+#[utoipa::path(
+    get,
+    path = "/api/background/runs",
+    tag = "background",
+    params(ProcessingRunQuery),
+    responses(
+        (status = 200, description = "Processing runs, most recently started first", body = [ProcessingRun]),
+        (status = 400, description = "Unknown status, malformed timestamp or limit out of range", body = String),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
+async fn list_runs(
+    State(state): State<AppState>,
+    Query(query): Query<ProcessingRunQuery>,
+) -> Result<Json<Vec<ProcessingRun>>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(DEFAULT_RUN_LIMIT);
+    if !(1..=MAX_RUN_LIMIT).contains(&limit) {
+        return Err((StatusCode::BAD_REQUEST, format!("limit must be between 1 and {}", MAX_RUN_LIMIT)));
+    }
+    let status = match query.status.as_deref() {
+        Some(status) if ProcessingRunStatus::from_str(status).as_str() != status => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown status '{}'; use running, completed, failed, rolled_back or aborted", status),
+            ));
+        }
+        status => status.map(ProcessingRunStatus::from_str),
+    };
+
+    let filter = ProcessingRunFilter {
+        imap_account_id: query.account_id,
+        status,
+        started_after: run_time_bound("since", query.since.as_deref())?,
+        started_before: run_time_bound("until", query.until.as_deref())?,
+        limit,
+    };
+    ProcessingRunOpsGeneric::list(&state.pool, &filter)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load processing runs: {}", e)))
+}
+
+/// Get a recorded processing run
+#[utoipa::path(
+    get,
+    path = "/api/background/runs/{run_id}",
+    tag = "background",
+    params(("run_id" = String, Path, description = "Processing run ID")),
+    responses(
+        (status = 200, description = "The processing run", body = ProcessingRun),
+        (status = 404, description = "Run not found", body = String),
+    )
+)]
+async fn get_run(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ProcessingRun>, (StatusCode, String)> {
+    ProcessingRunOpsGeneric::get_by_id(&state.pool, &run_id)
+        .map(Json)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Processing run {} not found", run_id)))
+}
+
+/// Get the processing intents a run logged, one per email it decided to
+/// turn into a feed item
+#[utoipa::path(
+    get,
+    path = "/api/background/runs/{run_id}/intents",
+    tag = "background",
+    params(("run_id" = String, Path, description = "Processing run ID")),
+    responses(
+        (status = 200, description = "The run's intents, oldest first", body = [ProcessingIntent]),
+        (status = 404, description = "Run not found", body = String),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
+async fn get_run_intents(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ProcessingIntent>>, (StatusCode, String)> {
+    ProcessingRunOpsGeneric::get_by_id(&state.pool, &run_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Processing run {} not found", run_id)))?;
+    ProcessingIntentOpsGeneric::get_by_run_id(&state.pool, &run_id)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load intents: {}", e)))
+}
+
+/// Get what evaluating each of its rules cost a run
+#[utoipa::path(
+    get,
+    path = "/api/background/runs/{run_id}/rule-costs",
+    tag = "background",
+    params(("run_id" = String, Path, description = "Processing run ID")),
+    responses(
+        (status = 200, description = "Fetch and matching time of each rule the run evaluated, in order", body = [RuleCost]),
+        (status = 404, description = "Run not found", body = String),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
+async fn get_run_rule_costs(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RuleCost>>, (StatusCode, String)> {
+    ProcessingRunOpsGeneric::get_by_id(&state.pool, &run_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Processing run {} not found", run_id)))?;
+    RuleCostOpsGeneric::get_by_run_id(&state.pool, &run_id)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load rule costs: {}", e)))
+}
+
+/// Roll back a processing run, removing the feed items it created and
+/// reversing its mailbox changes where possible
+#[utoipa::path(
+    post,
+    path = "/api/background/runs/{run_id}/rollback",
+    tag = "background",
+    params(("run_id" = String, Path, description = "Processing run ID")),
+    responses(
+        (status = 200, description = "Rollback result", body = RollbackResult),
+        (status = 404, description = "Run not found", body = String),
+        (status = 409, description = "Run is still running or already rolled back", body = String),
+        (status = 500, description = "Rollback failed", body = String),
+    )
+)]
+async fn rollback_run(
+    Path(run_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<RollbackResult>, (StatusCode, String)> {
+    info!("API request to roll back processing run: {}", run_id);
+
+    let run = ProcessingRunOpsGeneric::get_by_id(&state.pool, &run_id)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Processing run {} not found", run_id)))?;
+
+    match ProcessingRunStatus::from_str(&run.status) {
+        ProcessingRunStatus::Running => {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Processing run {} is still running", run_id),
+            ));
+        }
+        ProcessingRunStatus::RolledBack => {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Processing run {} has already been rolled back", run_id),
+            ));
+        }
+        _ => {}
+    }
+
+    match RunRollbackService::new(state.pool.clone()).rollback_run(&run).await {
+        Ok(result) => Ok(Json(result)),
+        Err(e) => {
+            error!("Failed to roll back processing run {}: {}", run_id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to roll back run: {}", e),
+            ))
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::api::{
+    types::{ChatIntegrationRequest, ErrorResponse, WebhookTestResponse},
+    AppState,
+};
+use crate::db::{
+    models::NewChatIntegration,
+    operations_generic::{ChatIntegrationOpsGeneric, FeedItemOpsGeneric, FeedOpsGeneric},
+};
+use crate::feed::{chat, webhook};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/feeds/:id/integrations", get(list_integrations).post(create_integration))
+        .route(
+            "/api/chat-integrations/:id",
+            get(get_integration).put(update_integration).delete(delete_integration),
+        )
+        .route("/api/chat-integrations/:id/test", post(test_integration))
+}
+
+/// Build and validate an integration for `feed_id` from a request body
+fn integration_from_request(feed_id: String, req: ChatIntegrationRequest) -> anyhow::Result<NewChatIntegration> {
+    let platform = chat::parse_platform(&req.platform)?;
+    let mut integration = NewChatIntegration::new(feed_id, platform, req.is_active);
+    integration.webhook_url = req.webhook_url;
+    integration.matrix_homeserver = req.matrix_homeserver;
+    integration.matrix_room_id = req.matrix_room_id;
+    integration.matrix_access_token = req.matrix_access_token;
+    integration.message_template = req.message_template;
+    integration.rate_limit_per_minute = req.rate_limit_per_minute;
+
+    chat::validate(&integration)?;
+    Ok(integration)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feeds/{id}/integrations",
+    tag = "chat-integrations",
+    params(("id" = String, Path, description = "Feed ID")),
+    responses(
+        (status = 200, description = "Chat integrations of the feed", body = [ChatIntegration]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_integrations(
+    State(state): State<AppState>,
+    Path(feed_id): Path<String>,
+) -> Response {
+    match ChatIntegrationOpsGeneric::get_by_feed_id(&state.pool, &feed_id) {
+        Ok(integrations) => Json(integrations).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch chat integrations: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/feeds/{id}/integrations",
+    tag = "chat-integrations",
+    params(("id" = String, Path, description = "Feed ID")),
+    request_body = ChatIntegrationRequest,
+    responses(
+        (status = 201, description = "Integration created", body = ChatIntegration),
+        (status = 400, description = "Invalid integration settings", body = ErrorResponse),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn create_integration(
+    State(state): State<AppState>,
+    Path(feed_id): Path<String>,
+    Json(req): Json<ChatIntegrationRequest>,
+) -> Response {
+    if let Err(e) = FeedOpsGeneric::get_by_id(&state.pool, &feed_id) {
+        return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed not found: {}", e) })).into_response();
+    }
+    let new_integration = match integration_from_request(feed_id, req) {
+        Ok(integration) => integration,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match ChatIntegrationOpsGeneric::create(&state.pool, &new_integration) {
+        Ok(integration) => (StatusCode::CREATED, Json(integration)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create chat integration: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/chat-integrations/{id}",
+    tag = "chat-integrations",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The integration", body = ChatIntegration),
+        (status = 404, description = "Integration not found", body = ErrorResponse),
+    )
+)]
+async fn get_integration(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match ChatIntegrationOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(integration) => Json(integration).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Chat integration not found: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/chat-integrations/{id}",
+    tag = "chat-integrations",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = ChatIntegrationRequest,
+    responses(
+        (status = 200, description = "Integration updated", body = ChatIntegration),
+        (status = 400, description = "Invalid integration settings", body = ErrorResponse),
+        (status = 404, description = "Integration not found", body = ErrorResponse),
+    )
+)]
+async fn update_integration(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<ChatIntegrationRequest>,
+) -> Response {
+    let existing = match ChatIntegrationOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(integration) => integration,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Chat integration not found: {}", e) })).into_response(),
+    };
+    let updated = match integration_from_request(existing.feed_id, req) {
+        Ok(integration) => integration,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match ChatIntegrationOpsGeneric::update(&state.pool, &id, &updated) {
+        Ok(integration) => Json(integration).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to update chat integration: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/chat-integrations/{id}",
+    tag = "chat-integrations",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Integration deleted"),
+        (status = 404, description = "Integration not found", body = ErrorResponse),
+    )
+)]
+async fn delete_integration(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match ChatIntegrationOpsGeneric::delete(&state.pool, &id) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to delete chat integration: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/chat-integrations/{id}/test",
+    tag = "chat-integrations",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Newest item of the feed posted, or a sample item when the feed is empty; not rate limited", body = WebhookTestResponse),
+        (status = 404, description = "Integration not found", body = ErrorResponse),
+        (status = 502, description = "Posting failed", body = ErrorResponse),
+    )
+)]
+async fn test_integration(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let not_found = |e: anyhow::Error| (StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: format!("Chat integration not found: {}", e) })).into_response();
+    let integration = match ChatIntegrationOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(integration) => integration,
+        Err(e) => return not_found(e),
+    };
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &integration.feed_id) {
+        Ok(feed) => feed,
+        Err(e) => return not_found(e),
+    };
+
+    let latest = FeedItemOpsGeneric::get_by_feed_id(&state.pool, &integration.feed_id, Some(1))
+        .ok()
+        .and_then(|items| items.into_iter().next());
+    let item_id = latest.as_ref().and_then(|item| item.id.clone());
+    let item = latest.unwrap_or_else(|| webhook::sample_item(&feed));
+
+    match chat::send(&integration, &feed, &item).await {
+        Ok(status) => Json(WebhookTestResponse { status, item_id }).into_response(),
+        Err(e) => (StatusCode::BAD_GATEWAY,
+            Json(ErrorResponse { error: format!("Posting to {} failed: {}", integration.platform, e) })).into_response(),
+    }
+}
@@ -0,0 +1,133 @@
+use crate::api::{types::ErrorResponse, AppState};
+use crate::db::{models::DeliveryStatus, operations_generic::DeliveryOpsGeneric};
+use crate::feed::delivery;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use tracing::warn;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/deliveries/failed", get(list_failed))
+        .route("/api/deliveries/retry", post(retry_failed))
+        .route("/api/deliveries/:id", delete(delete_delivery))
+        .route("/api/deliveries/:id/retry", post(retry_delivery))
+}
+
+/// Run a delivery pass now so retried deliveries do not wait for the scheduler
+async fn send_due(state: &AppState) {
+    if let Err(e) = delivery::process_due(&state.pool).await {
+        warn!("Failed to send queued deliveries: {}", e);
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/deliveries/failed",
+    tag = "deliveries",
+    responses(
+        (status = 200, description = "Deliveries that ran out of attempts, oldest first", body = [Delivery]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_failed(State(state): State<AppState>) -> Response {
+    match DeliveryOpsGeneric::get_by_status(&state.pool, &DeliveryStatus::Failed) {
+        Ok(deliveries) => Json(deliveries).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch deliveries: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/deliveries/retry",
+    tag = "deliveries",
+    responses(
+        (status = 200, description = "All failed deliveries queued again and attempted; their new state", body = [Delivery]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn retry_failed(State(state): State<AppState>) -> Response {
+    let failed = match DeliveryOpsGeneric::get_by_status(&state.pool, &DeliveryStatus::Failed) {
+        Ok(deliveries) => deliveries,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch deliveries: {}", e) })).into_response(),
+    };
+    let mut ids = Vec::new();
+    for failed in failed {
+        let id = failed.id.unwrap_or_default();
+        if let Err(e) = delivery::retry(&state.pool, &id) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to retry delivery {}: {}", id, e) })).into_response();
+        }
+        ids.push(id);
+    }
+
+    send_due(&state).await;
+    let retried: Vec<_> = ids.iter()
+        .filter_map(|id| DeliveryOpsGeneric::get_by_id(&state.pool, id).ok())
+        .collect();
+    Json(retried).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/deliveries/{id}/retry",
+    tag = "deliveries",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Delivery queued again and attempted; its new state", body = Delivery),
+        (status = 404, description = "Delivery not found", body = ErrorResponse),
+        (status = 409, description = "Delivery already succeeded", body = ErrorResponse),
+    )
+)]
+async fn retry_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match DeliveryOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(existing) if existing.status == DeliveryStatus::Delivered.as_str() => {
+            return (StatusCode::CONFLICT,
+                Json(ErrorResponse { error: format!("Delivery {} has already been delivered", id) })).into_response();
+        }
+        Ok(_) => {}
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Delivery not found: {}", e) })).into_response(),
+    }
+    if let Err(e) = delivery::retry(&state.pool, &id) {
+        return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to retry delivery: {}", e) })).into_response();
+    }
+
+    send_due(&state).await;
+    match DeliveryOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(delivery) => Json(delivery).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Delivery not found: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/deliveries/{id}",
+    tag = "deliveries",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Delivery discarded"),
+        (status = 404, description = "Delivery not found", body = ErrorResponse),
+    )
+)]
+async fn delete_delivery(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match DeliveryOpsGeneric::delete(&state.pool, &id) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to delete delivery: {}", e) })).into_response(),
+    }
+}
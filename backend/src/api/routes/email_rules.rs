@@ -1,90 +1,148 @@
-use crate::api::AppState;
+use super::feeds::validate_templates;
+use crate::api::{
+    types::{
+        AttachFeedRequest, BackfillRuleRequest, CreateEmailRuleRequest, CreateRuleWithFeedRequest, ErrorResponse, MatchExpressionValidation, RulePreviewQuery,
+        RuleFeedResponse, RuleStatsResponse, RuleWithFeedResponse, JobStartedResponse, UpdateEmailRuleRequest,
+        ValidateMatchExpressionRequest,
+    },
+    AppState,
+};
+use crate::background::backfill::{RuleBackfillService, BACKFILL_RULE_JOB};
+use crate::background::quota::{self, QuotaExceeded, QuotaResource};
 use crate::db::{
-    models::NewEmailRule,
-    operations_generic::{EmailRuleOpsGeneric, ImapAccountOpsGeneric},
+    connection::DatabasePool,
+    models::{Importance, JobFilter, NewEmailRule, NewFeed, NewRuleFeed, ProcessingOrder},
+    operations_generic::{EmailRuleOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, JobOpsGeneric, RuleFeedOpsGeneric, RuleMatchOpsGeneric},
 };
+use crate::background::deferred::MAX_DELAY_HOURS;
+use crate::feed::chain;
+use crate::feed::routing::{self, RuleTarget};
+use crate::imap::expression::{CompiledExpression, MatchExpression, MatchInput};
+use crate::imap::catch_up::MAX_CATCH_UP_EMAILS;
+use crate::imap::post_process::{self, PostProcessStep};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{delete, get, post},
     Json, Router,
 };
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateEmailRuleRequest {
-    pub name: String,
-    pub imap_account_id: String,
-    pub folder: String,
-    pub to_address: Option<String>,
-    pub from_address: Option<String>,
-    pub subject_contains: Option<String>,
-    pub label: Option<String>,
-    pub is_active: bool,
-    pub post_process_action: Option<String>, // Optional - inherits from account if not provided
-    pub move_to_folder: Option<String>,
-    #[serde(default)]
-    pub inherit_account_defaults: bool, // If true, ignore post_process_action and move_to_folder
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateEmailRuleRequest {
-    pub name: String,
-    pub imap_account_id: String,
-    pub folder: String,
-    pub to_address: Option<String>,
-    pub from_address: Option<String>,
-    pub subject_contains: Option<String>,
-    pub label: Option<String>,
-    pub is_active: bool,
-    pub post_process_action: Option<String>, // Optional - inherits from account if not provided
-    pub move_to_folder: Option<String>,
-    #[serde(default)]
-    pub inherit_account_defaults: bool, // If true, ignore post_process_action and move_to_folder
-}
-
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    error: String,
-}
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/api/email-rules", get(list_rules).post(create_rule))
+        .route("/api/email-rules/with-feed", post(create_rule_with_feed))
+        .route("/api/email-rules/validate-expression", post(validate_match_expression))
         .route(
             "/api/email-rules/:id",
             get(get_rule).put(update_rule).delete(delete_rule),
         )
+        .route("/api/email-rules/:id/stats", get(get_rule_stats))
+        .route("/api/email-rules/:id/preview", get(get_rule_preview))
+        .route("/api/email-rules/:id/backfill", post(backfill_rule))
+        .route("/api/email-rules/:id/feeds", get(list_rule_feeds).post(attach_rule_feed))
+        .route("/api/email-rules/:id/feeds/:feed_id", delete(detach_rule_feed))
 }
 
-async fn list_rules(State(state): State<AppState>) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, 
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
+/// Number of matches returned by the preview when no limit is given
+const DEFAULT_PREVIEW_LIMIT: i64 = 50;
+
+/// The importance a rule matches, normalized; an error message for unknown values
+fn rule_importance(importance: Option<String>) -> Result<Option<String>, String> {
+    match importance.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+        None => Ok(None),
+        Some(value) => Importance::parse(value)
+            .map(|importance| Some(importance.as_str().to_string()))
+            .ok_or_else(|| format!("Unknown importance '{}'; use high, normal or low", value)),
+    }
+}
+
+/// The processing order requested for a rule, newest first when omitted;
+/// an error message for unknown values
+fn rule_processing_order(order: Option<String>) -> Result<String, String> {
+    match order.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+        None => Ok(ProcessingOrder::default().as_str().to_string()),
+        Some(value) => ProcessingOrder::parse(value)
+            .map(|order| order.as_str().to_string())
+            .ok_or_else(|| format!("Unknown processing order '{}'; use newest_first or oldest_first", value)),
+    }
+}
+
+/// The post-process delay requested for a rule; an error message when out of range
+fn rule_post_process_delay(hours: Option<i32>) -> Result<Option<i32>, String> {
+    match hours {
+        Some(hours) if !(0..=MAX_DELAY_HOURS).contains(&hours) => Err(format!(
+            "post_process_delay_hours must be between 0 and {}", MAX_DELAY_HOURS
+        )),
+        hours => Ok(hours.filter(|hours| *hours > 0)),
+    }
+}
+
+/// The fetch limit requested for a rule; an error message when out of range
+fn rule_fetch_limit(limit: Option<i32>) -> Result<Option<i32>, String> {
+    match limit {
+        Some(limit) if !(1..=MAX_CATCH_UP_EMAILS as i32).contains(&limit) => Err(format!(
+            "fetch_limit must be between 1 and {}", MAX_CATCH_UP_EMAILS
+        )),
+        limit => Ok(limit),
+    }
+}
+
+/// A requested match expression and its compiled form; an error message
+/// when it is malformed or a pattern does not compile
+fn compile_match_expression(expression: serde_json::Value) -> Result<(MatchExpression, CompiledExpression), String> {
+    let parsed: MatchExpression = serde_json::from_value(expression)
+        .map_err(|e| format!("Invalid match expression: {}", e))?;
+    let compiled = parsed.compile().map_err(|e| format!("{:#}", e))?;
+    Ok((parsed, compiled))
+}
 
-    match EmailRuleOpsGeneric::get_all(&mut conn) {
+/// The match expression requested for a rule as stored JSON; an error
+/// message when it does not compile
+fn rule_match_expression(expression: Option<serde_json::Value>) -> Result<Option<String>, String> {
+    match expression.filter(|value| !value.is_null()) {
+        None => Ok(None),
+        Some(expression) => {
+            let (parsed, _) = compile_match_expression(expression)?;
+            serde_json::to_string(&parsed).map(Some).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// The post-processing chain requested for a rule as stored JSON; none for
+/// an empty chain, an error message naming the invalid step
+fn rule_post_process_actions(steps: Option<Vec<PostProcessStep>>) -> Result<Option<String>, String> {
+    match steps.filter(|steps| !steps.is_empty()) {
+        None => Ok(None),
+        Some(steps) => {
+            post_process::validate_chain(&steps).map_err(|e| format!("Invalid post_process_actions: {}", e))?;
+            serde_json::to_string(&steps).map(Some).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/email-rules",
+    tag = "email-rules",
+    responses(
+        (status = 200, description = "All email rules", body = [EmailRule]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_rules(State(state): State<AppState>) -> Response {
+    match EmailRuleOpsGeneric::get_all(&state.pool) {
         Ok(rules) => Json(rules).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to fetch rules: {}", e) })).into_response(),
     }
 }
 
-async fn create_rule(
-    State(state): State<AppState>,
-    Json(req): Json<CreateEmailRuleRequest>,
-) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-
-    let new_rule = if req.inherit_account_defaults {
+/// The rule a create request describes; an error message when it is invalid
+fn new_rule(pool: &DatabasePool, req: CreateEmailRuleRequest) -> Result<NewEmailRule, String> {
+    let mut new_rule = if req.inherit_account_defaults {
         // Get the account to inherit defaults
-        match ImapAccountOpsGeneric::get_by_id(&state.pool, &req.imap_account_id) {
+        match ImapAccountOpsGeneric::get_by_id(pool, &req.imap_account_id) {
             Ok(account) => {
                 NewEmailRule::from_account_defaults(
                     req.name,
@@ -97,8 +155,7 @@ async fn create_rule(
                     req.is_active,
                 )
             }
-            Err(e) => return (StatusCode::BAD_REQUEST,
-                Json(ErrorResponse { error: format!("Invalid account ID: {}", e) })).into_response(),
+            Err(e) => return Err(format!("Invalid account ID: {}", e)),
         }
     } else {
         // Use provided values or defaults
@@ -115,24 +172,113 @@ async fn create_rule(
             req.move_to_folder,
         )
     };
+    new_rule.observe_only = req.observe_only;
+    new_rule.importance = rule_importance(req.importance)?;
+    new_rule.processing_order = rule_processing_order(req.processing_order)?;
+    new_rule.post_process_delay_hours = rule_post_process_delay(req.post_process_delay_hours)?;
+    new_rule.match_expression = rule_match_expression(req.match_expression)?;
+    new_rule.post_process_actions = rule_post_process_actions(req.post_process_actions)?;
+    new_rule.fetch_limit = rule_fetch_limit(req.fetch_limit)?;
+    new_rule.include_seen = req.include_seen;
+    new_rule.include_subfolders = req.include_subfolders;
+    new_rule.strip_footers = req.strip_footers;
+    new_rule.strip_quoted_replies = req.strip_quoted_replies;
+    new_rule.strip_tracking_pixels = req.strip_tracking_pixels;
+    new_rule.unwrap_tracking_links = req.unwrap_tracking_links;
+    Ok(new_rule)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/email-rules",
+    tag = "email-rules",
+    request_body = CreateEmailRuleRequest,
+    responses(
+        (status = 201, description = "Rule created", body = EmailRule),
+        (status = 400, description = "Unknown IMAP account, importance or processing order, delay or fetch limit out of range, or invalid match expression or post-processing chain", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn create_rule(
+    State(state): State<AppState>,
+    Json(req): Json<CreateEmailRuleRequest>,
+) -> Response {
+    let new_rule = match new_rule(&state.pool, req) {
+        Ok(new_rule) => new_rule,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
 
     match EmailRuleOpsGeneric::create(&state.pool, &new_rule) {
-        Ok(rule) => (StatusCode::CREATED, Json(rule)).into_response(),
+        Ok(rule) => {
+            state.background.controller.rule_changed(&rule).await;
+            (StatusCode::CREATED, Json(rule)).into_response()
+        }
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to create rule: {}", e) })).into_response(),
     }
 }
 
-async fn get_rule(
+// Create a rule and its feed together, so neither exists without the other
+#[utoipa::path(
+    post,
+    path = "/api/email-rules/with-feed",
+    tag = "email-rules",
+    request_body = CreateRuleWithFeedRequest,
+    responses(
+        (status = 201, description = "Rule and feed created together", body = RuleWithFeedResponse),
+        (status = 400, description = "Invalid rule or feed settings; nothing was created", body = ErrorResponse),
+        (status = 403, description = "The feed exceeds the account's or its quota group's feed quota", body = ErrorResponse),
+        (status = 500, description = "Database error; nothing was created", body = ErrorResponse),
+    )
+)]
+async fn create_rule_with_feed(
     State(state): State<AppState>,
-    Path(id): Path<String>,
+    Json(req): Json<CreateRuleWithFeedRequest>,
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
+    let new_rule = match new_rule(&state.pool, req.rule) {
+        Ok(new_rule) => new_rule,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
     };
+    let title = req.feed.title.filter(|title| !title.trim().is_empty()).unwrap_or_else(|| new_rule.name.clone());
+    if let Some(response) = validate_templates(&title, req.feed.description.as_deref()) {
+        return response;
+    }
+
+    let account = match ImapAccountOpsGeneric::get_by_id(&state.pool, &new_rule.imap_account_id) {
+        Ok(account) => account,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("Invalid account ID: {}", e) })).into_response(),
+    };
+    if let Err(e) = quota::check(&state.pool, &account, QuotaResource::Feeds) {
+        let status = if e.is::<QuotaExceeded>() { StatusCode::FORBIDDEN } else { StatusCode::INTERNAL_SERVER_ERROR };
+        return (status, Json(ErrorResponse { error: e.to_string() })).into_response();
+    }
 
+    let new_feed = NewFeed::new(title, req.feed.description, None, new_rule.id.clone(), req.feed.feed_type, true);
+    match state.pool.transaction(|tx| Ok((tx.create_email_rule(&new_rule)?, tx.create_feed(&new_feed)?))) {
+        Ok((rule, feed)) => {
+            state.background.controller.rule_changed(&rule).await;
+            (StatusCode::CREATED, Json(RuleWithFeedResponse { rule, feed })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create rule and feed: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/email-rules/{id}",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The rule", body = EmailRule),
+        (status = 404, description = "Rule not found", body = ErrorResponse),
+    )
+)]
+async fn get_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
     match EmailRuleOpsGeneric::get_by_id(&state.pool, &id) {
         Ok(rule) => Json(rule).into_response(),
         Err(e) => (StatusCode::NOT_FOUND,
@@ -140,18 +286,24 @@ async fn get_rule(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/email-rules/{id}",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = UpdateEmailRuleRequest,
+    responses(
+        (status = 200, description = "Rule updated", body = EmailRule),
+        (status = 400, description = "Unknown IMAP account, importance or processing order, delay or fetch limit out of range, or invalid match expression or post-processing chain", body = ErrorResponse),
+        (status = 404, description = "Rule not found", body = ErrorResponse),
+    )
+)]
 async fn update_rule(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(req): Json<UpdateEmailRuleRequest>,
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-
-    let updated_rule = if req.inherit_account_defaults {
+    let mut updated_rule = if req.inherit_account_defaults {
         // Get the account to inherit defaults
         match ImapAccountOpsGeneric::get_by_id(&state.pool, &req.imap_account_id) {
             Ok(account) => {
@@ -184,27 +336,313 @@ async fn update_rule(
             req.move_to_folder,
         )
     };
+    updated_rule.observe_only = req.observe_only;
+    updated_rule.importance = match rule_importance(req.importance) {
+        Ok(importance) => importance,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    updated_rule.processing_order = match rule_processing_order(req.processing_order) {
+        Ok(order) => order,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    updated_rule.post_process_delay_hours = match rule_post_process_delay(req.post_process_delay_hours) {
+        Ok(hours) => hours,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    updated_rule.match_expression = match rule_match_expression(req.match_expression) {
+        Ok(expression) => expression,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    updated_rule.post_process_actions = match rule_post_process_actions(req.post_process_actions) {
+        Ok(actions) => actions,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    updated_rule.fetch_limit = match rule_fetch_limit(req.fetch_limit) {
+        Ok(limit) => limit,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    updated_rule.include_seen = req.include_seen;
+    updated_rule.include_subfolders = req.include_subfolders;
+    updated_rule.strip_footers = req.strip_footers;
+    updated_rule.strip_quoted_replies = req.strip_quoted_replies;
+    updated_rule.strip_tracking_pixels = req.strip_tracking_pixels;
+    updated_rule.unwrap_tracking_links = req.unwrap_tracking_links;
 
     match EmailRuleOpsGeneric::update(&state.pool, &id, &updated_rule) {
-        Ok(rule) => Json(rule).into_response(),
+        Ok(rule) => {
+            state.background.controller.rule_changed(&rule).await;
+            Json(rule).into_response()
+        }
         Err(e) => (StatusCode::NOT_FOUND,
             Json(ErrorResponse { error: format!("Failed to update rule: {}", e) })).into_response(),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/email-rules/{id}",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Rule deleted"),
+        (status = 404, description = "Rule not found", body = ErrorResponse),
+        (status = 409, description = "The rule fills an append-only feed", body = ErrorResponse),
+    )
+)]
 async fn delete_rule(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-
+    // Deleting the rule would delete its feeds along with their items
+    let feeds = FeedOpsGeneric::get_by_rule_id(&state.pool, &id).unwrap_or_default();
+    if let Err(e) = chain::ensure_removable(&feeds) {
+        return (StatusCode::CONFLICT, Json(ErrorResponse { error: e.to_string() })).into_response();
+    }
     match EmailRuleOpsGeneric::delete(&state.pool, &id) {
         Ok(_) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => (StatusCode::NOT_FOUND,
             Json(ErrorResponse { error: format!("Failed to delete rule: {}", e) })).into_response(),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/email-rules/{id}/stats",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Matches recorded for the rule", body = RuleStatsResponse),
+        (status = 404, description = "Rule not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_rule_stats(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let rule = match EmailRuleOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(rule) => rule,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Rule not found: {}", e) })).into_response(),
+    };
+
+    match RuleMatchOpsGeneric::stats(&state.pool, &id) {
+        Ok(stats) => Json(RuleStatsResponse {
+            rule_id: id,
+            observe_only: rule.observe_only,
+            match_count: stats.match_count,
+            last_matched_at: stats.last_matched_at,
+        }).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch rule stats: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/email-rules/{id}/preview",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID"), RulePreviewQuery),
+    responses(
+        (status = 200, description = "Emails matched while the rule was observe-only, newest first", body = [RuleMatch]),
+        (status = 404, description = "Rule not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_rule_preview(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<RulePreviewQuery>,
+) -> Response {
+    if let Err(e) = EmailRuleOpsGeneric::get_by_id(&state.pool, &id) {
+        return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Rule not found: {}", e) })).into_response();
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_PREVIEW_LIMIT);
+    match RuleMatchOpsGeneric::get_by_rule_id(&state.pool, &id, Some(limit)) {
+        Ok(matches) => Json(matches).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch rule preview: {}", e) })).into_response(),
+    }
+}
+
+/// Start importing the past emails of a rule's folder into its feed (non-blocking)
+#[utoipa::path(
+    post,
+    path = "/api/email-rules/{id}/backfill",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body(content = Option<BackfillRuleRequest>, description = "Optional backfill settings"),
+    responses(
+        (status = 202, description = "Backfill queued; follow it under /api/jobs/{id}", body = JobStartedResponse),
+        (status = 400, description = "Invalid batch size, or the rule has no feed to fill", body = ErrorResponse),
+        (status = 404, description = "Rule or account not found", body = ErrorResponse),
+        (status = 409, description = "A backfill of the rule is already queued or running", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn backfill_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    request: Option<Json<BackfillRuleRequest>>,
+) -> Response {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    if request.batch_size == Some(0) {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "batch_size must be greater than 0".to_string() })).into_response();
+    }
+    let rule = match EmailRuleOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(rule) => rule,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Rule not found: {}", e) })).into_response(),
+    };
+    if rule.observe_only || routing::active_feeds(&state.pool, &id).map_or(true, |feeds| feeds.is_empty()) {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("Rule '{}' has no feed to fill", rule.name) })).into_response();
+    }
+    let account = match ImapAccountOpsGeneric::get_by_id(&state.pool, &rule.imap_account_id) {
+        Ok(account) => account,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Account not found: {}", e) })).into_response(),
+    };
+
+    let filter = JobFilter { kind: Some(BACKFILL_RULE_JOB.to_string()), target_id: Some(id.clone()), status: None, limit: 1 };
+    let unfinished = JobOpsGeneric::list(&state.pool, &filter).map(|jobs| jobs.into_iter().find(|job| !job.job_status().is_finished()));
+    if let Ok(Some(job)) = unfinished {
+        return (StatusCode::CONFLICT,
+            Json(ErrorResponse { error: format!("Backfill {} of rule '{}' is already {}", job.id.unwrap_or_default(), rule.name, job.status) })).into_response();
+    }
+
+    let message = format!("Backfill of rule '{}' queued", rule.name);
+    let mut service = RuleBackfillService::new(state.pool.clone(), account, rule);
+    if let Some(batch_size) = request.batch_size {
+        service = service.with_batch_size(batch_size);
+    }
+    match state.jobs.spawn(BACKFILL_RULE_JOB, Some(id), move |job| async move { service.run(&job).await }) {
+        Ok(job) => (StatusCode::ACCEPTED, Json(JobStartedResponse { job_id: job.id.unwrap_or_default(), message })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to queue backfill: {}", e) })).into_response(),
+    }
+}
+
+fn rule_feed_response(target: RuleTarget) -> RuleFeedResponse {
+    RuleFeedResponse {
+        feed_id: target.feed.id.unwrap_or_default(),
+        feed_title: target.feed.title,
+        priority: target.priority,
+        attached: target.attached,
+        is_active: target.feed.is_active,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/email-rules/{id}/feeds",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Feeds the rule delivers to, by priority: its own and those attached to it", body = [RuleFeedResponse]),
+        (status = 404, description = "Rule not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_rule_feeds(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = EmailRuleOpsGeneric::get_by_id(&state.pool, &id) {
+        return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Rule not found: {}", e) })).into_response();
+    }
+    match routing::targets(&state.pool, &id) {
+        Ok(targets) => Json(targets.into_iter().map(rule_feed_response).collect::<Vec<_>>()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch feeds of rule: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/email-rules/{id}/feeds",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = AttachFeedRequest,
+    responses(
+        (status = 200, description = "Feed attached, or its priority changed when it already was", body = RuleFeedResponse),
+        (status = 400, description = "The feed is one of the rule's own", body = ErrorResponse),
+        (status = 404, description = "Rule or feed not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn attach_rule_feed(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<AttachFeedRequest>,
+) -> Response {
+    let rule = match EmailRuleOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(rule) => rule,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Rule not found: {}", e) })).into_response(),
+    };
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &req.feed_id) {
+        Ok(feed) => feed,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed not found: {}", e) })).into_response(),
+    };
+    if feed.email_rule_id == id {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("Feed '{}' already belongs to rule '{}'", feed.title, rule.name) })).into_response();
+    }
+
+    match RuleFeedOpsGeneric::attach(&state.pool, &NewRuleFeed::new(id, req.feed_id, req.priority)) {
+        Ok(rule_feed) => Json(rule_feed_response(RuleTarget { feed, priority: rule_feed.priority, attached: true })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to attach feed: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/email-rules/{id}/feeds/{feed_id}",
+    tag = "email-rules",
+    params(("id" = String, Path, description = "Resource ID"), ("feed_id" = String, Path, description = "Feed ID")),
+    responses(
+        (status = 204, description = "Feed detached; it keeps its items"),
+        (status = 404, description = "The feed is not attached to the rule", body = ErrorResponse),
+    )
+)]
+async fn detach_rule_feed(
+    State(state): State<AppState>,
+    Path((id, feed_id)): Path<(String, String)>,
+) -> Response {
+    match RuleFeedOpsGeneric::detach(&state.pool, &id, &feed_id) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to detach feed: {}", e) })).into_response(),
+    }
+}
+
+/// Check that a match expression compiles, optionally trying it on a sample email
+#[utoipa::path(
+    post,
+    path = "/api/email-rules/validate-expression",
+    tag = "email-rules",
+    request_body = ValidateMatchExpressionRequest,
+    responses(
+        (status = 200, description = "The expression compiles", body = MatchExpressionValidation),
+        (status = 400, description = "Malformed expression or invalid regex", body = ErrorResponse),
+    )
+)]
+async fn validate_match_expression(Json(req): Json<ValidateMatchExpressionRequest>) -> Response {
+    let (parsed, compiled) = match compile_match_expression(req.match_expression) {
+        Ok(expression) => expression,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+
+    let matches = req.sample.map(|sample| compiled.matches(&MatchInput::new(&sample.from, &sample.to, &sample.subject, &sample.body)));
+    match serde_json::to_string(&parsed) {
+        Ok(match_expression) => Json(MatchExpressionValidation { match_expression, matches }).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e.to_string() })).into_response(),
+    }
+}
@@ -1,66 +1,35 @@
 use axum::{
-    routing::{get, patch}, 
+    routing::{get, post}, 
     Router, Json, extract::{State, Path, Query},
-    http::StatusCode,
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response}
 };
-use serde::{Deserialize, Serialize};
-use crate::api::AppState;
-use crate::db::{operations_generic::{FeedOpsGeneric, FeedItemOpsGeneric}, models::NewFeed};
-use crate::feed::generator::FeedGenerator;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateFeedRequest {
-    pub title: String,
-    pub description: Option<String>,
-    pub link: Option<String>,
-    pub email_rule_id: String,
-    pub feed_type: String,
-    pub is_active: bool,
-    pub max_items: Option<i32>,
-    pub max_age_days: Option<i32>,
-    pub min_items: Option<i32>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateFeedRequest {
-    pub title: String,
-    pub description: Option<String>,
-    pub link: Option<String>,
-    pub email_rule_id: String,
-    pub feed_type: String,
-    pub is_active: bool,
-    pub max_items: Option<i32>,
-    pub max_age_days: Option<i32>,
-    pub min_items: Option<i32>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct FeedItemsQuery {
-    limit: Option<i64>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FeedItemMetadata {
-    pub id: String,
-    pub title: String,
-    pub pub_date: String,
-    pub author: Option<String>,
-    pub is_read: Option<bool>,
-    pub starred: Option<bool>,
-    pub body_size: Option<i32>,
-    pub created_at: String,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct UpdateFeedItemRequest {
-    pub is_read: Option<bool>,
-    pub starred: Option<bool>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    error: String,
+use crate::api::{
+    types::{BulkFeedItemsRequest, BulkFeedItemsResponse, BulkItemAction, ChainVerification, CleanupQuery, CleanupReport, CreateFeedRequest, ErrorResponse, ExportFormat, ExportQuery, ExportResult, JobStartedResponse, FeedItemMetadata, FeedItemMetadataPage, FeedItemsPage, FeedItemsQuery, ShareFeedItemRequest, SharedItemLink, UnsubscribeResponse, UpdateFeedItemRequest, UpdateFeedRequest, WebhookTestResponse},
+    AppState,
+};
+use crate::background::{cleanup::FeedCleanupService, quota::{self, QuotaExceeded, QuotaResource}, retention};
+use crate::db::{connection::DatabasePool, operations_generic::{AttachmentOpsGeneric, EmailRuleOpsGeneric, FeedOpsGeneric, FeedItemOpsGeneric, FeedRedirectOpsGeneric, ImapAccountOpsGeneric, JobOpsGeneric}, models::{DedupScope, DigestMode, Feed, FeedItem, FeedItemPageFilter, ItemSelection, JobStatus, NewFeed, Rating}};
+use std::collections::HashMap;
+use crate::settings;
+use crate::feed::{attachments, basic_auth, bodies, branding, chain, dedup, export, generator::{FeedGenerator, FeedLinks}, health, item_templates, localization, output, overflow, permalink::{self, LinkError, SignedLinkQuery}, pinning::{self, PinLimitReached}, public_url, template, unsubscribe, webhook};
+
+/// Refuse a feed on `email_rule_id` when its account has no feeds left;
+/// `previous_rule_id` is the feed's rule before an update, whose account
+/// gives the feed up
+fn check_feed_quota(pool: &DatabasePool, email_rule_id: &str, previous_rule_id: Option<&str>) -> Option<Response> {
+    let account_of = |rule_id: &str| EmailRuleOpsGeneric::get_by_id(pool, rule_id).ok().map(|rule| rule.imap_account_id);
+    // A missing rule is reported by the insert itself
+    let account = ImapAccountOpsGeneric::get_by_id(pool, &account_of(email_rule_id)?).ok()?;
+
+    let result = match previous_rule_id.and_then(account_of) {
+        Some(from_account_id) => quota::check_transfer(pool, &account, &from_account_id, QuotaResource::Feeds),
+        None => quota::check(pool, &account, QuotaResource::Feeds),
+    };
+    let error = result.err()?;
+    let status = if error.is::<QuotaExceeded>() { StatusCode::FORBIDDEN } else { StatusCode::INTERNAL_SERVER_ERROR };
+    Some((status, Json(ErrorResponse { error: error.to_string() })).into_response())
 }
 
 pub fn routes() -> Router<AppState> {
@@ -69,36 +38,196 @@ pub fn routes() -> Router<AppState> {
         .route("/api/feeds/:id", get(get_feed).put(update_feed).delete(delete_feed))
         .route("/api/feeds/:id/items", get(get_feed_items))
         .route("/api/feeds/:id/items/metadata", get(get_feed_items_metadata))
-        .route("/api/feed-items/:id", patch(update_feed_item))
+        .route("/api/feeds/:id/items/bulk", post(bulk_update_feed_items))
+        .route("/api/feeds/:id/verify", get(verify_feed_chain))
+        .route("/api/feeds/:id/cleanup", post(cleanup_feed))
+        .route("/api/cleanup", post(cleanup_all_feeds))
+        .route("/api/feeds/:id/export", get(export_feed))
+        .route("/api/exports/:job_id", get(download_export))
+        .route("/api/feeds/:id/webhook/test", post(test_feed_webhook))
+        .route("/api/feed-items/:id", get(get_feed_item).patch(update_feed_item))
+        .route("/api/feed-items/:id/share", post(share_feed_item))
+        .route("/api/feed-items/:id/unsubscribe", post(unsubscribe_feed_item))
         .route("/feeds/:id/rss", get(get_rss_feed))
         .route("/feeds/:id/atom", get(get_atom_feed))
+        .route("/feeds/:feed_id/items/:item_id", get(get_item_page))
+        .route("/feeds/:feed_id/items/:item_id/attachments/:n", get(get_item_attachment))
+        .route("/items/:id/html", get(get_item_html))
+        .merge(Router::new()
+            .route("/feed-items/:id/html", get(get_shared_item_page))
+            .route_layer(middleware::from_fn(require_signature)))
 }
 
-async fn list_feeds(State(state): State<AppState>) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, 
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
+fn validate_summary_length(summary_length: Option<i32>) -> Option<Response> {
+    match summary_length {
+        Some(length) if length <= 0 => Some((StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "summary_length must be a positive number of characters".to_string() })).into_response()),
+        _ => None,
+    }
+}
+
+fn validate_item_limit(item_limit: Option<i32>) -> Option<Response> {
+    match item_limit {
+        Some(limit) if limit <= 0 => Some((StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "item_limit must be a positive number of items".to_string() })).into_response()),
+        _ => None,
+    }
+}
+
+fn validate_max_pinned(max_pinned: Option<i32>) -> Option<Response> {
+    match max_pinned {
+        Some(max) if max < 0 => Some((StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "max_pinned must not be negative".to_string() })).into_response()),
+        _ => None,
+    }
+}
+
+fn validate_digest_mode(digest_mode: Option<&str>) -> Option<Response> {
+    match digest_mode {
+        Some(mode) if DigestMode::parse(mode).is_none() => Some((StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("Unknown digest_mode '{}'; use 'thread' or 'day'", mode) })).into_response()),
+        _ => None,
+    }
+}
+
+fn validate_dedup_scope(dedup_scope: Option<&str>) -> Option<Response> {
+    match dedup_scope {
+        Some(scope) if DedupScope::parse(scope).is_none() => Some((StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: format!("Unknown dedup_scope '{}'; use 'feed', 'account' or 'global'", scope) })).into_response()),
+        _ => None,
+    }
+}
+
+/// Let the background service re-process the folder feeding an active feed
+async fn notify_rule_changed(state: &AppState, feed: &Feed) {
+    if !feed.is_active {
+        return;
+    }
+    if let Ok(rule) = EmailRuleOpsGeneric::get_by_id(&state.pool, &feed.email_rule_id) {
+        state.background.controller.rule_changed(&rule).await;
+    }
+}
+
+fn validate_localization(locale: Option<&str>, timezone: Option<&str>) -> Option<Response> {
+    let error = locale.and_then(|locale| localization::parse_locale(locale).err())
+        .or_else(|| timezone.and_then(|timezone| localization::parse_timezone(timezone).err()))?;
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: error.to_string() })).into_response())
+}
+
+pub(super) fn validate_templates(title: &str, description: Option<&str>) -> Option<Response> {
+    let error = template::validate(title).err()
+        .or_else(|| description.and_then(|description| template::validate(description).err()))?;
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: error.to_string() })).into_response())
+}
+
+fn validate_item_templates(title: &Option<String>, description: &Option<String>) -> Option<Response> {
+    let error = [title, description].into_iter()
+        .flatten()
+        .find_map(|template| item_templates::validate(template).err())?;
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: error.to_string() })).into_response())
+}
+
+/// Credentials to store from the request: the password hashed when one is
+/// given, else the feed's current hash, and none without a username
+fn feed_credentials(username: Option<String>, password: Option<String>, previous: Option<&Feed>) -> Result<(Option<String>, Option<String>), (StatusCode, String)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+    let Some(username) = username else {
+        return match password {
+            Some(_) => Err(bad_request("auth_password needs an auth_username".to_string())),
+            None => Ok((None, None)),
+        };
     };
+    basic_auth::validate(&username, password.as_deref()).map_err(|e| bad_request(e.to_string()))?;
+    let hash = match password {
+        Some(password) => basic_auth::hash_password(&password).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        None => previous.and_then(|feed| feed.auth_password_hash.clone())
+            .ok_or_else(|| bad_request("auth_password is required to protect a feed".to_string()))?,
+    };
+    Ok((Some(username), Some(hash)))
+}
 
-    match FeedOpsGeneric::get_all(&mut conn) {
+fn validate_webhook(url: &Option<String>, method: &Option<String>, body: &Option<String>) -> Option<Response> {
+    let error = webhook::validate(url.as_deref(), method.as_deref(), body.as_deref()).err()?;
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: error.to_string() })).into_response())
+}
+
+fn validate_branding(css: &Option<String>, header: &Option<String>, footer: &Option<String>, logo_url: &Option<String>) -> Option<Response> {
+    let error = branding::validate(css.as_deref(), header.as_deref(), footer.as_deref(), logo_url.as_deref()).err()?;
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error: error.to_string() })).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feeds",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "All feeds", body = [Feed]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_feeds(State(state): State<AppState>) -> Response {
+    match FeedOpsGeneric::get_all(&state.pool) {
         Ok(feeds) => Json(feeds).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to fetch feeds: {}", e) })).into_response(),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/feeds",
+    tag = "feeds",
+    request_body = CreateFeedRequest,
+    responses(
+        (status = 201, description = "Feed created", body = Feed),
+        (status = 400, description = "Invalid feed settings", body = ErrorResponse),
+        (status = 403, description = "The rule's account or its quota group has no feeds left", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
 async fn create_feed(
     State(state): State<AppState>,
     Json(req): Json<CreateFeedRequest>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
+    if let Some(response) = validate_summary_length(req.summary_length) {
+        return response;
+    }
+    if let Some(response) = validate_item_limit(req.item_limit) {
+        return response;
+    }
+    if let Some(response) = validate_max_pinned(req.max_pinned) {
+        return response;
+    }
+    if let Some(response) = validate_localization(req.locale.as_deref(), req.timezone.as_deref()) {
+        return response;
+    }
+    if let Some(response) = validate_templates(&req.title, req.description.as_deref()) {
+        return response;
+    }
+    if let Some(response) = validate_item_templates(&req.title_template, &req.description_template) {
+        return response;
+    }
+    if let Some(response) = validate_webhook(&req.webhook_url, &req.webhook_method, &req.webhook_body) {
+        return response;
+    }
+    if let Some(response) = validate_branding(&req.page_css, &req.page_header_html, &req.page_footer_html, &req.page_logo_url) {
+        return response;
+    }
+    if let Some(response) = validate_digest_mode(req.digest_mode.as_deref()) {
+        return response;
+    }
+    if let Some(response) = validate_dedup_scope(req.dedup_scope.as_deref()) {
+        return response;
+    }
+    if let Some(response) = check_feed_quota(&state.pool, &req.email_rule_id, None) {
+        return response;
+    }
+    let (auth_username, auth_password_hash) = match feed_credentials(req.auth_username, req.auth_password, None) {
+        Ok(credentials) => credentials,
+        Err((status, error)) => return (status, Json(ErrorResponse { error })).into_response(),
     };
 
-    let new_feed = NewFeed::with_retention(
+    let mut new_feed = NewFeed::with_retention(
         req.title,
         req.description,
         req.link,
@@ -109,24 +238,53 @@ async fn create_feed(
         req.max_age_days,
         req.min_items,
     );
+    new_feed.summary_length = req.summary_length;
+    new_feed.locale = req.locale;
+    new_feed.timezone = req.timezone;
+    new_feed.title_template = req.title_template;
+    new_feed.description_template = req.description_template;
+    new_feed.item_limit = req.item_limit;
+    new_feed.full_content = req.full_content;
+    new_feed.auth_username = auth_username;
+    new_feed.auth_password_hash = auth_password_hash;
+    new_feed.webhook_url = req.webhook_url;
+    new_feed.webhook_method = req.webhook_method;
+    new_feed.webhook_body = req.webhook_body;
+    new_feed.public_access = req.public_access;
+    new_feed.max_pinned = req.max_pinned;
+    new_feed.auto_titles = req.auto_titles;
+    new_feed.page_css = req.page_css;
+    new_feed.page_header_html = req.page_header_html;
+    new_feed.page_footer_html = req.page_footer_html;
+    new_feed.page_logo_url = req.page_logo_url;
+    new_feed.append_only = req.append_only.unwrap_or(false);
+    new_feed.digest_mode = req.digest_mode.as_deref().and_then(DigestMode::parse).map(|mode| mode.as_str().to_string());
+    new_feed.dedup_scope = req.dedup_scope.as_deref().and_then(DedupScope::parse).map(|scope| scope.as_str().to_string());
 
     match FeedOpsGeneric::create(&state.pool, &new_feed) {
-        Ok(feed) => (StatusCode::CREATED, Json(feed)).into_response(),
+        Ok(feed) => {
+            notify_rule_changed(&state, &feed).await;
+            (StatusCode::CREATED, Json(feed)).into_response()
+        }
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to create feed: {}", e) })).into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/feeds/{id}",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The feed", body = Feed),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+    )
+)]
 async fn get_feed(
     State(state): State<AppState>,
     Path(id): Path<String>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-
     match FeedOpsGeneric::get_by_id(&state.pool, &id) {
         Ok(feed) => Json(feed).into_response(),
         Err(e) => (StatusCode::NOT_FOUND,
@@ -134,18 +292,72 @@ async fn get_feed(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/feeds/{id}",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = UpdateFeedRequest,
+    responses(
+        (status = 200, description = "Feed updated", body = Feed),
+        (status = 400, description = "Invalid feed settings", body = ErrorResponse),
+        (status = 403, description = "Moving the feed to another account exceeds that account's feed quota", body = ErrorResponse),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 409, description = "The feed is append-only and cannot be turned back", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
 async fn update_feed(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(req): Json<UpdateFeedRequest>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
+    if let Some(response) = validate_summary_length(req.summary_length) {
+        return response;
+    }
+    if let Some(response) = validate_item_limit(req.item_limit) {
+        return response;
+    }
+    if let Some(response) = validate_max_pinned(req.max_pinned) {
+        return response;
+    }
+    if let Some(response) = validate_localization(req.locale.as_deref(), req.timezone.as_deref()) {
+        return response;
+    }
+    if let Some(response) = validate_templates(&req.title, req.description.as_deref()) {
+        return response;
+    }
+    if let Some(response) = validate_item_templates(&req.title_template, &req.description_template) {
+        return response;
+    }
+    if let Some(response) = validate_webhook(&req.webhook_url, &req.webhook_method, &req.webhook_body) {
+        return response;
+    }
+    if let Some(response) = validate_branding(&req.page_css, &req.page_header_html, &req.page_footer_html, &req.page_logo_url) {
+        return response;
+    }
+    if let Some(response) = validate_digest_mode(req.digest_mode.as_deref()) {
+        return response;
+    }
+    if let Some(response) = validate_dedup_scope(req.dedup_scope.as_deref()) {
+        return response;
+    }
+    let previous = FeedOpsGeneric::get_by_id(&state.pool, &id).ok();
+    let was_append_only = previous.as_ref().is_some_and(|feed| feed.append_only);
+    if was_append_only && req.append_only == Some(false) {
+        return (StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "An append-only feed cannot be turned back into a regular one".to_string() })).into_response();
+    }
+    let previous_rule_id = previous.as_ref().map(|feed| feed.email_rule_id.as_str());
+    if let Some(response) = check_feed_quota(&state.pool, &req.email_rule_id, previous_rule_id) {
+        return response;
+    }
+    let (auth_username, auth_password_hash) = match feed_credentials(req.auth_username, req.auth_password, previous.as_ref()) {
+        Ok(credentials) => credentials,
+        Err((status, error)) => return (status, Json(ErrorResponse { error })).into_response(),
     };
 
-    let updated_feed = NewFeed::with_retention(
+    let mut updated_feed = NewFeed::with_retention(
         req.title,
         req.description,
         req.link,
@@ -156,24 +368,72 @@ async fn update_feed(
         req.max_age_days,
         req.min_items,
     );
+    updated_feed.summary_length = req.summary_length;
+    updated_feed.locale = req.locale;
+    updated_feed.timezone = req.timezone;
+    updated_feed.title_template = req.title_template;
+    updated_feed.description_template = req.description_template;
+    updated_feed.item_limit = req.item_limit;
+    updated_feed.full_content = req.full_content;
+    updated_feed.auth_username = auth_username;
+    updated_feed.auth_password_hash = auth_password_hash;
+    updated_feed.webhook_url = req.webhook_url;
+    updated_feed.webhook_method = req.webhook_method;
+    updated_feed.webhook_body = req.webhook_body;
+    updated_feed.public_access = req.public_access;
+    updated_feed.max_pinned = req.max_pinned;
+    updated_feed.auto_titles = req.auto_titles;
+    updated_feed.page_css = req.page_css;
+    updated_feed.page_header_html = req.page_header_html;
+    updated_feed.page_footer_html = req.page_footer_html;
+    updated_feed.page_logo_url = req.page_logo_url;
+    updated_feed.append_only = req.append_only.unwrap_or(was_append_only);
+    updated_feed.digest_mode = req.digest_mode.as_deref().and_then(DigestMode::parse).map(|mode| mode.as_str().to_string());
+    updated_feed.dedup_scope = req.dedup_scope.as_deref().and_then(DedupScope::parse).map(|scope| scope.as_str().to_string());
+    // Chain hashes cover the bodies, so they go back into the database first
+    if updated_feed.append_only && !was_append_only {
+        if let Err(e) = bodies::restore_feed(&state.pool, state.body_store.as_ref(), &id).await {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to chain the feed's items: {}", e) })).into_response();
+        }
+    }
 
     match FeedOpsGeneric::update(&state.pool, &id, &updated_feed) {
-        Ok(feed) => Json(feed).into_response(),
+        Ok(feed) => {
+            if feed.append_only && !was_append_only {
+                if let Err(e) = chain::seal(&state.pool, &feed) {
+                    return (StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ErrorResponse { error: format!("Failed to chain the feed's items: {}", e) })).into_response();
+                }
+            }
+            notify_rule_changed(&state, &feed).await;
+            Json(feed).into_response()
+        }
         Err(e) => (StatusCode::NOT_FOUND,
             Json(ErrorResponse { error: format!("Failed to update feed: {}", e) })).into_response(),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/feeds/{id}",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Feed deleted"),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 409, description = "The feed is append-only", body = ErrorResponse),
+    )
+)]
 async fn delete_feed(
     State(state): State<AppState>,
     Path(id): Path<String>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-
+    if let Ok(feed) = FeedOpsGeneric::get_by_id(&state.pool, &id) {
+        if let Err(e) = chain::ensure_removable([&feed]) {
+            return (StatusCode::CONFLICT, Json(ErrorResponse { error: e.to_string() })).into_response();
+        }
+    }
     match FeedOpsGeneric::delete(&state.pool, &id) {
         Ok(_) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => (StatusCode::NOT_FOUND,
@@ -181,78 +441,453 @@ async fn delete_feed(
     }
 }
 
-async fn get_feed_items(
+#[utoipa::path(
+    post,
+    path = "/api/feeds/{id}/cleanup",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Retention enforced on the feed", body = CleanupResult),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 500, description = "Cleanup failed", body = ErrorResponse),
+    )
+)]
+async fn cleanup_feed(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(feed) => feed,
+        Err(_) => return feed_not_found(&id),
+    };
+    match FeedCleanupService::new(state.pool.clone()).cleanup_feed(&feed).await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to clean up feed: {}", e) })).into_response(),
+    }
+}
+
+/// Job kind of on-demand cleanups run in the background
+const CLEANUP_JOB: &str = "cleanup";
+
+async fn cleanup_report(pool: DatabasePool) -> anyhow::Result<CleanupReport> {
+    let feeds = FeedCleanupService::new(pool.clone()).cleanup_all_feeds().await?;
+    let purged = retention::purge(&pool, chrono::Utc::now())?;
+    Ok(CleanupReport {
+        feeds,
+        rows_purged: purged.into_iter().map(|(target, rows)| (target.as_str().to_string(), rows)).collect(),
+    })
+}
+
+/// Enforce retention now rather than at the daily cleanup: every feed's, and
+/// that of the history tables
+#[utoipa::path(
+    post,
+    path = "/api/cleanup",
+    tag = "feeds",
+    params(CleanupQuery),
+    responses(
+        (status = 200, description = "Retention enforced", body = CleanupReport),
+        (status = 202, description = "Cleanup queued as a job (`background=true`); its result is the report", body = JobStartedResponse),
+        (status = 500, description = "Cleanup failed", body = ErrorResponse),
+    )
+)]
+async fn cleanup_all_feeds(State(state): State<AppState>, Query(query): Query<CleanupQuery>) -> Response {
+    if query.background {
+        let pool = state.pool.clone();
+        return match state.jobs.spawn(CLEANUP_JOB, None, move |_| cleanup_report(pool)) {
+            Ok(job) => (StatusCode::ACCEPTED, Json(JobStartedResponse {
+                job_id: job.id.unwrap_or_default(),
+                message: "Cleanup queued".to_string(),
+            })).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to queue cleanup: {}", e) })).into_response(),
+        };
+    }
+
+    match cleanup_report(state.pool.clone()).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to clean up: {}", e) })).into_response(),
+    }
+}
+
+/// Download the items of a feed as one archive: an EPUB book, an HTML page or
+/// an mbox. Feeds too large to export within the request are exported by a job
+#[utoipa::path(
+    get,
+    path = "/api/feeds/{id}/export",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID"), ExportQuery),
+    responses(
+        (status = 200, description = "The archive, as an attachment", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 202, description = "Export queued as a job; its result is an `ExportResult` with where to download the archive", body = JobStartedResponse),
+        (status = 400, description = "Unknown format", body = ErrorResponse),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 500, description = "Export failed", body = ErrorResponse),
+    )
+)]
+async fn export_feed(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Query(params): Query<FeedItemsQuery>
+    Query(query): Query<ExportQuery>,
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
+    let format = match query.format.as_deref() {
+        None => ExportFormat::Epub,
+        Some(format) => match ExportFormat::parse(format) {
+            Some(format) => format,
+            None => return (StatusCode::BAD_REQUEST,
+                Json(ErrorResponse { error: format!("Unknown format '{}'; use epub, html or mbox", format) })).into_response(),
+        },
+    };
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(feed) => feed,
+        Err(_) => return feed_not_found(&id),
     };
 
-    match FeedItemOpsGeneric::get_by_feed_id(&state.pool, &id, params.limit) {
-        Ok(items) => Json(items).into_response(),
+    let count = FeedItemOpsGeneric::count_by_feed_id(&state.pool, &id).unwrap_or(0);
+    if query.background || count > export::INLINE_ITEM_LIMIT {
+        let (pool, store) = (state.pool.clone(), state.body_store.clone());
+        let message = format!("Export of feed '{}' to {} queued", feed.title, format.as_str());
+        return match state.jobs.spawn(export::EXPORT_JOB, Some(id), move |job| async move { export::run(pool, store, feed, format, &job).await }) {
+            Ok(job) => (StatusCode::ACCEPTED, Json(JobStartedResponse { job_id: job.id.unwrap_or_default(), message })).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to queue export: {}", e) })).into_response(),
+        };
+    }
+
+    let archive: anyhow::Result<Vec<u8>> = async {
+        let items = export::load_items(&state.pool, state.body_store.as_ref(), &id).await?;
+        let feed = feed.clone();
+        tokio::task::spawn_blocking(move || export::render(&feed, &items, format, chrono::Utc::now())).await?
+    }.await;
+    match archive {
+        Ok(archive) => archive_response(format, &export::filename(&feed, format), archive),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Failed to fetch feed items: {}", e) })).into_response(),
+            Json(ErrorResponse { error: format!("Failed to export feed: {}", e) })).into_response(),
     }
 }
 
-// Helper function to get feed data and items
-async fn get_feed_data(state: &AppState, id: &str) -> Result<(crate::db::models::Feed, Vec<crate::db::models::FeedItem>), Response> {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response()),
+/// Download the archive written by an export job
+#[utoipa::path(
+    get,
+    path = "/api/exports/{job_id}",
+    tag = "feeds",
+    params(("job_id" = String, Path, description = "ID of the export job")),
+    responses(
+        (status = 200, description = "The archive, as an attachment", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 404, description = "No such export, or its archive has expired", body = ErrorResponse),
+        (status = 409, description = "The export has not finished or it failed", body = ErrorResponse),
+    )
+)]
+async fn download_export(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Response {
+    let not_found = || (StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: format!("Export {} not found", job_id) })).into_response();
+    let job = match JobOpsGeneric::get_by_id(&state.pool, &job_id) {
+        Ok(job) if job.kind == export::EXPORT_JOB => job,
+        _ => return not_found(),
+    };
+    if job.job_status() != JobStatus::Completed {
+        return (StatusCode::CONFLICT,
+            Json(ErrorResponse { error: format!("Export {} is {}", job_id, job.status) })).into_response();
+    }
+    let Some(result) = job.result.as_deref().and_then(|result| serde_json::from_str::<ExportResult>(result).ok()) else {
+        return not_found();
     };
+    match tokio::fs::read(export::path(&job_id, result.format)).await {
+        Ok(archive) => archive_response(result.format, &result.filename, archive),
+        Err(_) => not_found(),
+    }
+}
 
+fn archive_response(format: ExportFormat, filename: &str, archive: Vec<u8>) -> Response {
+    (StatusCode::OK, [
+        ("content-type", format.content_type().to_string()),
+        ("content-disposition", content_disposition(filename)),
+        ("x-content-type-options", "nosniff".to_string()),
+    ], archive).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feeds/{id}/verify",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Whether the feed's item chain is intact", body = ChainVerification),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn verify_feed_chain(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(feed) => feed,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed not found: {}", e) })).into_response(),
+    };
+    match chain::verify(&state.pool, &feed) {
+        Ok(verification) => Json::<ChainVerification>(verification).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to verify feed: {}", e) })).into_response(),
+    }
+}
+
+/// Cursor pointing after `item`; pages are keyed on the feed's order
+/// `(pinned, pub_date, id)`, so they neither skip nor repeat items when new
+/// ones arrive
+fn item_cursor(item: &FeedItem) -> String {
+    format!("{}|{}|{}", u8::from(item.pinned), item.pub_date, item.id.as_deref().unwrap_or_default())
+}
+
+fn parse_item_cursor(cursor: &str) -> Option<(bool, String, String)> {
+    let (pinned, rest) = cursor.split_once('|')?;
+    let (pub_date, id) = rest.rsplit_once('|')?;
+    let pinned = match pinned {
+        "1" => true,
+        "0" => false,
+        _ => return None,
+    };
+    if pub_date.is_empty() || id.is_empty() {
+        return None;
+    }
+    Some((pinned, pub_date.to_string(), id.to_string()))
+}
+
+/// A page of a feed's items with the feed's total and unread counts and the
+/// cursor of the next page
+struct ItemsPage {
+    items: Vec<FeedItem>,
+    total: i64,
+    unread: i64,
+    next_cursor: Option<String>,
+}
+
+fn items_page(state: &AppState, id: &str, params: &FeedItemsQuery) -> Result<ItemsPage, (StatusCode, String)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+
+    if params.limit.is_some_and(|limit| limit < 1) {
+        return Err(bad_request("limit must be at least 1".to_string()));
+    }
+    let cursor = match params.cursor.as_deref().map(parse_item_cursor) {
+        Some(None) => return Err(bad_request("cursor is not a next_cursor returned for this feed's items".to_string())),
+        Some(cursor) => cursor,
+        None => None,
+    };
+    // One extra item tells whether there is a next page
+    let filter = FeedItemPageFilter {
+        before: timestamp_param("before", params.before.as_deref()).map_err(bad_request)?,
+        after: timestamp_param("after", params.after.as_deref()).map_err(bad_request)?,
+        cursor,
+        limit: params.limit.map(|limit| limit.saturating_add(1)),
+    };
+
+    let counts = FeedItemOpsGeneric::count_by_feed_id(&state.pool, id)
+        .and_then(|total| Ok((total, FeedItemOpsGeneric::count_unread_by_feed_id(&state.pool, id)?)));
+    let (mut items, (total, unread)) = match (FeedItemOpsGeneric::get_page(&state.pool, id, &filter), counts) {
+        (Ok(items), Ok(counts)) => (items, counts),
+        (Err(e), _) | (_, Err(e)) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch feed items: {}", e))),
+    };
+
+    let has_more = params.limit.is_some_and(|limit| items.len() as i64 > limit);
+    if let Some(limit) = params.limit {
+        items.truncate(limit as usize);
+    }
+    let next_cursor = if has_more { items.last().map(item_cursor) } else { None };
+
+    Ok(ItemsPage { items, total, unread, next_cursor })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feeds/{id}/items",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID"), FeedItemsQuery),
+    responses(
+        (status = 200, description = "Items in the feed, pinned first, then newest first, with counts and the cursor of the next page", body = FeedItemsPage),
+        (status = 400, description = "Invalid limit, cursor or timestamp", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_feed_items(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<FeedItemsQuery>
+) -> Response {
+    let ItemsPage { mut items, total, unread, next_cursor } = match items_page(&state, &id, &params) {
+        Ok(page) => page,
+        Err((status, error)) => return (status, Json(ErrorResponse { error })).into_response(),
+    };
+    dedup::resolve_bodies(&state.pool, &mut items);
+    bodies::load(state.body_store.as_ref(), &mut items).await;
+    Json(FeedItemsPage { items, total, unread, next_cursor }).into_response()
+}
+
+// Helper function to get feed data and items
+async fn get_feed_data(state: &AppState, headers: &HeaderMap, id: &str, format: &str) -> Result<(crate::db::models::Feed, Vec<crate::db::models::FeedItem>), Response> {
     // Get the feed metadata
-    let feed = match FeedOpsGeneric::get_by_id(&state.pool, id) {
+    let mut feed = match FeedOpsGeneric::get_by_id(&state.pool, id) {
         Ok(feed) => feed,
         Err(e) => {
             // Check if it's a not found error by checking the error message
             let error_msg = e.to_string();
             if error_msg.contains("not found") || error_msg.contains("NotFound") {
-                return Err((StatusCode::NOT_FOUND,
-                    Json(ErrorResponse { error: format!("Feed with ID '{}' not found", id) })).into_response());
+                return Err(missing_feed(state, id, format));
             } else {
                 return Err((StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ErrorResponse { error: format!("Database error retrieving feed: {}", e) })).into_response());
             }
         }
     };
+    if !is_public(&feed) {
+        return Err(feed_not_found(id));
+    }
+    if !authorized(&feed, headers).await {
+        return Err(authentication_required(&feed));
+    }
+    template::resolve(&state.pool, &mut feed);
 
-    // Get feed items (limit to most recent items, configurable via env var)
-    let item_limit = std::env::var("FEED_ITEM_LIMIT")
-        .unwrap_or_else(|_| "50".to_string())
-        .parse::<i64>()
-        .unwrap_or(50);
-    let items = match FeedItemOpsGeneric::get_by_feed_id(&state.pool, id, Some(item_limit)) {
+    // Get feed items (limit to most recent items, configurable per feed or via settings)
+    let item_limit = output::item_limit(&feed);
+    let mut items = match FeedItemOpsGeneric::get_by_feed_id(&state.pool, id, Some(item_limit)) {
         Ok(items) => items,
         Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to fetch feed items: {}", e) })).into_response()),
     };
+    bodies::load(state.body_store.as_ref(), &mut items).await;
+    output::apply_summary_length(&feed, &mut items);
+    if let Some(max_bytes) = overflow::max_item_bytes() {
+        overflow::cap_items(&mut items, max_bytes, &public_base_url(headers));
+    }
+    if let Some(hours) = health::warning_hours() {
+        match health::status_item(&state.pool, &feed, hours, chrono::Utc::now()) {
+            Ok(Some(status_item)) => items.insert(0, status_item),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to check the health of feed {}: {}", id, e),
+        }
+    }
 
     Ok((feed, items))
 }
 
+/// Whether the anonymous `/feeds/{id}/*` endpoints serve this feed: its own
+/// `public_access` setting, or `FEED_PUBLIC_ENDPOINTS` (on by default)
+fn is_public(feed: &Feed) -> bool {
+    feed.public_access.unwrap_or_else(|| {
+        std::env::var("FEED_PUBLIC_ENDPOINTS")
+            .map(|value| !matches!(value.trim().to_lowercase().as_str(), "0" | "false" | "no"))
+            .unwrap_or(true)
+    })
+}
+
+/// Whether the request carries the credentials of a password-protected feed;
+/// hashing is slow on purpose, so it runs off the async workers
+async fn authorized(feed: &Feed, headers: &HeaderMap) -> bool {
+    if !basic_auth::is_protected(feed) {
+        return true;
+    }
+    let feed = feed.clone();
+    let authorization = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()).map(str::to_string);
+    tokio::task::spawn_blocking(move || basic_auth::authorized(&feed, authorization.as_deref()))
+        .await
+        .unwrap_or(false)
+}
+
+fn authentication_required(feed: &Feed) -> Response {
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, basic_auth::challenge(feed))],
+        Json(ErrorResponse { error: "This feed requires a username and password".to_string() })).into_response()
+}
+
+/// Shared caches may keep public feeds; password-protected ones only the reader's own
+fn cache_control(feed: &Feed) -> String {
+    let visibility = if basic_auth::is_protected(feed) { "private" } else { "public" };
+    format!("{}, max-age={}", visibility, get_cache_duration())
+}
+
+/// Non-public feeds answer like missing ones, so their IDs cannot be probed
+fn feed_not_found(id: &str) -> Response {
+    (StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: format!("Feed with ID '{}' not found", id) })).into_response()
+}
+
+/// A feed that does not exist: a permanent redirect when it was merged into
+/// another, keeping the rest of the path, otherwise 404
+fn missing_feed(state: &AppState, id: &str, rest: &str) -> Response {
+    match FeedRedirectOpsGeneric::get(&state.pool, id) {
+        Ok(Some(redirect)) => moved_to(&format!("/feeds/{}/{}", redirect.feed_id, rest)),
+        Ok(None) => feed_not_found(id),
+        Err(e) => {
+            tracing::warn!("Failed to look up a redirect of feed {}: {}", id, e);
+            feed_not_found(id)
+        }
+    }
+}
+
+fn moved_to(path: &str) -> Response {
+    (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, path.to_string())]).into_response()
+}
+
+/// Base URL for absolute links in feeds: the configured public base URL if
+/// there is one, otherwise derived from the request as a reverse proxy
+/// forwarded it
+fn public_base_url(headers: &HeaderMap) -> String {
+    if let Some(url) = public_url::configured() {
+        return url;
+    }
+    let header_value = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    match header_value("x-forwarded-host").or_else(|| header_value(header::HOST.as_str())) {
+        Some(host) => format!("{}://{}", header_value("x-forwarded-proto").unwrap_or("http"), host),
+        None => String::new(),
+    }
+}
+
+/// Self link of the feed document at `/feeds/{id}/{format}`, item links to
+/// their HTML pages, and item permalinks when the public base URL is configured
+fn feed_links(headers: &HeaderMap, id: &str, format: &str) -> FeedLinks {
+    let base_url = public_base_url(headers);
+    let request_base_url = (!base_url.is_empty()).then_some(base_url);
+    FeedLinks {
+        self_url: request_base_url.as_ref().map(|base_url| format!("{}/feeds/{}/{}", base_url, id, format)),
+        base_url: public_url::configured(),
+        request_base_url,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/feeds/{id}/rss",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "RSS 2.0 document", body = String, content_type = "application/rss+xml"),
+        (status = 301, description = "The feed was merged into the one at `Location`"),
+        (status = 401, description = "The feed is password-protected and the request has no valid Basic auth credentials", body = ErrorResponse),
+        (status = 404, description = "Feed not found or not public", body = ErrorResponse),
+        (status = 500, description = "Feed generation failed", body = ErrorResponse),
+    )
+)]
 async fn get_rss_feed(
     State(state): State<AppState>,
-    Path(id): Path<String>
+    Path(id): Path<String>,
+    headers: HeaderMap
 ) -> Response {
-    let (feed, items) = match get_feed_data(&state, &id).await {
+    let (feed, items) = match get_feed_data(&state, &headers, &id, "rss").await {
         Ok(data) => data,
         Err(error_response) => return error_response,
     };
 
     // Generate RSS feed
-    match FeedGenerator::generate_rss(&feed, &items) {
+    let enclosures = feed_enclosures(&state, &headers, &items);
+    match FeedGenerator::generate_rss_with_enclosures(&feed, &items, &enclosures, &feed_links(&headers, &id, "rss")) {
         Ok(rss_content) => {
-            let cache_duration = get_cache_duration();
             (StatusCode::OK, [
                 ("content-type", "application/rss+xml; charset=utf-8"),
-                ("cache-control", &format!("public, max-age={}", cache_duration)),
+                ("cache-control", &cache_control(&feed)),
             ], rss_content).into_response()
         }
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
@@ -260,28 +895,49 @@ async fn get_rss_feed(
     }
 }
 
-// Helper function to get cache duration from environment
-fn get_cache_duration() -> String {
-    std::env::var("FEED_CACHE_DURATION")
-        .unwrap_or_else(|_| "300".to_string())
+/// Enclosures of the items, or none when they cannot be looked up
+fn feed_enclosures(state: &AppState, headers: &HeaderMap, items: &[FeedItem]) -> HashMap<String, Vec<attachments::Enclosure>> {
+    attachments::enclosures(&state.pool, items, &public_base_url(headers)).unwrap_or_else(|e| {
+        tracing::warn!("Failed to look up attachments for feed items: {}", e);
+        HashMap::new()
+    })
+}
+
+// Helper function to get cache duration from settings
+fn get_cache_duration() -> u64 {
+    settings::feed_cache_duration()
 }
 
+#[utoipa::path(
+    get,
+    path = "/feeds/{id}/atom",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Atom document", body = String, content_type = "application/atom+xml"),
+        (status = 301, description = "The feed was merged into the one at `Location`"),
+        (status = 401, description = "The feed is password-protected and the request has no valid Basic auth credentials", body = ErrorResponse),
+        (status = 404, description = "Feed not found or not public", body = ErrorResponse),
+        (status = 500, description = "Feed generation failed", body = ErrorResponse),
+    )
+)]
 async fn get_atom_feed(
     State(state): State<AppState>,
-    Path(id): Path<String>
+    Path(id): Path<String>,
+    headers: HeaderMap
 ) -> Response {
-    let (feed, items) = match get_feed_data(&state, &id).await {
+    let (feed, items) = match get_feed_data(&state, &headers, &id, "atom").await {
         Ok(data) => data,
         Err(error_response) => return error_response,
     };
 
     // Generate Atom feed
-    match FeedGenerator::generate_atom(&feed, &items) {
+    let enclosures = feed_enclosures(&state, &headers, &items);
+    match FeedGenerator::generate_atom_with_enclosures(&feed, &items, &enclosures, &feed_links(&headers, &id, "atom")) {
         Ok(atom_content) => {
-            let cache_duration = get_cache_duration();
             (StatusCode::OK, [
                 ("content-type", "application/atom+xml; charset=utf-8"),
-                ("cache-control", &format!("public, max-age={}", cache_duration)),
+                ("cache-control", &cache_control(&feed)),
             ], atom_content).into_response()
         }
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
@@ -290,19 +946,24 @@ async fn get_atom_feed(
 }
 
 /// Get feed items metadata for management UI
+#[utoipa::path(
+    get,
+    path = "/api/feeds/{id}/items/metadata",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID"), FeedItemsQuery),
+    responses(
+        (status = 200, description = "Item metadata without bodies, paged like the items", body = FeedItemMetadataPage),
+        (status = 400, description = "Invalid limit, cursor or timestamp", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
 async fn get_feed_items_metadata(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Query(params): Query<FeedItemsQuery>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-    
-    match FeedItemOpsGeneric::get_by_feed_id(&state.pool, &id, params.limit) {
-        Ok(items) => {
+    match items_page(&state, &id, &params) {
+        Ok(ItemsPage { items, total, unread, next_cursor }) => {
             let metadata: Vec<FeedItemMetadata> = items.into_iter().map(|item| {
                 FeedItemMetadata {
                     id: item.id.unwrap_or_else(|| "unknown".to_string()),
@@ -311,29 +972,370 @@ async fn get_feed_items_metadata(
                     author: item.author,
                     is_read: item.is_read,
                     starred: item.starred,
+                    pinned: item.pinned,
+                    rating: item.rating,
                     body_size: item.body_size,
                     created_at: item.created_at,
                 }
             }).collect();
-            Json(metadata).into_response()
+            Json(FeedItemMetadataPage { items: metadata, total, unread, next_cursor }).into_response()
         }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Failed to fetch feed items metadata: {}", e) })).into_response(),
+        Err((status, error)) => (status, Json(ErrorResponse { error })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/feeds/{id}/webhook/test",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Webhook called with the newest item, or a sample item when the feed is empty", body = WebhookTestResponse),
+        (status = 400, description = "Feed has no webhook", body = ErrorResponse),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 502, description = "Webhook call failed", body = ErrorResponse),
+    )
+)]
+async fn test_feed_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(feed) => feed,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed not found: {}", e) })).into_response(),
+    };
+    if feed.webhook_url.is_none() {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "Feed has no webhook configured".to_string() })).into_response();
+    }
+
+    let latest = FeedItemOpsGeneric::get_by_feed_id(&state.pool, &id, Some(1))
+        .ok()
+        .and_then(|items| items.into_iter().next());
+    let item_id = latest.as_ref().and_then(|item| item.id.clone());
+    let item = latest.unwrap_or_else(|| webhook::sample_item(&feed));
+
+    match webhook::deliver(&feed, &item).await {
+        Ok(status) => Json(WebhookTestResponse { status, item_id }).into_response(),
+        Err(e) => (StatusCode::BAD_GATEWAY,
+            Json(ErrorResponse { error: format!("Webhook call failed: {}", e) })).into_response(),
     }
 }
 
-/// Update feed item metadata (read status, starred, etc.)
+#[utoipa::path(
+    get,
+    path = "/feeds/{feed_id}/items/{item_id}",
+    tag = "feeds",
+    params(
+        ("feed_id" = String, Path, description = "Feed ID"),
+        ("item_id" = String, Path, description = "Item ID"),
+    ),
+    responses(
+        (status = 200, description = "HTML page with the item's complete body", body = String, content_type = "text/html"),
+        (status = 301, description = "The item moved to the feed at `Location`"),
+        (status = 404, description = "Item not found or feed not public", body = ErrorResponse),
+    )
+)]
+async fn get_item_page(
+    State(state): State<AppState>,
+    Path((feed_id, item_id)): Path<(String, String)>
+) -> Response {
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &feed_id) {
+        Ok(feed) if is_public(&feed) => feed,
+        Ok(_) => return feed_not_found(&feed_id),
+        Err(_) => return missing_feed(&state, &feed_id, &format!("items/{}", item_id)),
+    };
+    let item = match FeedItemOpsGeneric::get_by_id(&state.pool, &item_id) {
+        Ok(item) if item.feed_id == feed_id => item,
+        // Split off into another feed
+        Ok(item) if item.origin_feed_id.as_deref() == Some(feed_id.as_str()) => {
+            return moved_to(&overflow::item_page_path(&item.feed_id, &item_id));
+        }
+        _ => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Item '{}' not found in feed '{}'", item_id, feed_id) })).into_response(),
+    };
+    item_page(&state, &feed, item, "public").await
+}
+
+#[utoipa::path(
+    get,
+    path = "/items/{id}/html",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Item ID")),
+    responses(
+        (status = 200, description = "HTML page with the item's complete body", body = String, content_type = "text/html"),
+        (status = 404, description = "Item not found or feed not public", body = ErrorResponse),
+    )
+)]
+async fn get_item_html(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    let item_and_feed = FeedItemOpsGeneric::get_by_id(&state.pool, &id)
+        .and_then(|item| Ok((FeedOpsGeneric::get_by_id(&state.pool, &item.feed_id)?, item)));
+    match item_and_feed {
+        Ok((feed, item)) if is_public(&feed) => item_page(&state, &feed, item, "public").await,
+        _ => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed item '{}' not found", id) })).into_response(),
+    }
+}
+
+/// The item's complete body as a standalone page; `cache` is the
+/// Cache-Control scope, `public` or `private`
+async fn item_page(state: &AppState, feed: &Feed, mut item: FeedItem, cache: &str) -> Response {
+    dedup::resolve_bodies(&state.pool, std::slice::from_mut(&mut item));
+    bodies::load(state.body_store.as_ref(), std::slice::from_mut(&mut item)).await;
+
+    (StatusCode::OK, [
+        ("content-type", "text/html; charset=utf-8"),
+        ("cache-control", &format!("{}, max-age={}", cache, get_cache_duration())),
+        // Email HTML is untrusted: no scripts, forms or same-origin access
+        ("content-security-policy", "sandbox"),
+    ], overflow::render_item_page(feed, &item)).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/feeds/{feed_id}/items/{item_id}/attachments/{n}",
+    tag = "feeds",
+    params(
+        ("feed_id" = String, Path, description = "Feed ID"),
+        ("item_id" = String, Path, description = "Item ID"),
+        ("n" = i32, Path, description = "Attachment number, from 1"),
+    ),
+    responses(
+        (status = 200, description = "The attachment's content, with its content type", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 301, description = "The item moved to the feed at `Location`"),
+        (status = 404, description = "Attachment not found or feed not public", body = ErrorResponse),
+    )
+)]
+async fn get_item_attachment(
+    State(state): State<AppState>,
+    Path((feed_id, item_id, n)): Path<(String, String, i32)>
+) -> Response {
+    let not_found = || (StatusCode::NOT_FOUND,
+        Json(ErrorResponse { error: format!("Attachment {} of item '{}' not found", n, item_id) })).into_response();
+    match FeedOpsGeneric::get_by_id(&state.pool, &feed_id) {
+        Ok(feed) if is_public(&feed) => {}
+        Ok(_) => return feed_not_found(&feed_id),
+        Err(_) => return missing_feed(&state, &feed_id, &format!("items/{}/attachments/{}", item_id, n)),
+    }
+    let item = match FeedItemOpsGeneric::get_by_id(&state.pool, &item_id) {
+        Ok(item) if item.feed_id == feed_id => item,
+        Ok(item) if item.origin_feed_id.as_deref() == Some(feed_id.as_str()) => {
+            return moved_to(&format!("{}/attachments/{}", overflow::item_page_path(&item.feed_id, &item_id), n));
+        }
+        _ => return not_found(),
+    };
+    let Some(attachment) = attachments::source_item_id(&item)
+        .and_then(|source_id| AttachmentOpsGeneric::get(&state.pool, source_id, n).ok()) else {
+        return not_found();
+    };
+
+    // Served as a download under its own name, never rendered as a page of this site
+    (StatusCode::OK, [
+        ("content-type", attachment.content_type),
+        ("content-disposition", content_disposition(attachment.filename.as_deref().unwrap_or("attachment"))),
+        ("x-content-type-options", "nosniff".to_string()),
+        ("cache-control", format!("public, max-age={}", get_cache_duration())),
+    ], attachment.content).into_response()
+}
+
+/// `Content-Disposition` of a download, with non-ASCII names encoded as in RFC 6266
+fn content_disposition(filename: &str) -> String {
+    let ascii: String = filename.chars()
+        .map(|c| if (c.is_ascii_graphic() && c != '"' && c != '\\') || c == ' ' { c } else { '_' })
+        .collect();
+    if ascii == filename {
+        format!("attachment; filename=\"{}\"", ascii)
+    } else {
+        format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", ascii, urlencoding::encode(filename))
+    }
+}
+
+/// Let only validly signed, unexpired item links through: 403 for a bad
+/// signature, 410 once expired, 404 while signed links are disabled
+async fn require_signature<B>(
+    Path(item_id): Path<String>,
+    Query(query): Query<SignedLinkQuery>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(key) = permalink::signing_key() else {
+        return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "Signed links are not enabled".to_string() })).into_response();
+    };
+    match permalink::verify(&key, &item_id, &query, chrono::Utc::now().timestamp()) {
+        Ok(()) => next.run(request).await,
+        Err(e) => {
+            let status = match e {
+                LinkError::InvalidSignature => StatusCode::FORBIDDEN,
+                LinkError::Expired(_) => StatusCode::GONE,
+            };
+            (status, Json(ErrorResponse { error: e.to_string() })).into_response()
+        }
+    }
+}
+
+/// Show an item through a signed link, whether or not its feed is public
+#[utoipa::path(
+    get,
+    path = "/feed-items/{id}/html",
+    tag = "feeds",
+    params(
+        ("id" = String, Path, description = "Item ID"),
+        ("exp" = Option<i64>, Query, description = "Unix time the link expires, as signed"),
+        ("sig" = String, Query, description = "Link signature"),
+    ),
+    responses(
+        (status = 200, description = "HTML page with the item's complete body", body = String, content_type = "text/html"),
+        (status = 301, description = "The item moved to the feed at `Location`"),
+        (status = 403, description = "Invalid signature", body = ErrorResponse),
+        (status = 404, description = "Item not found or signed links disabled", body = ErrorResponse),
+        (status = 410, description = "Link expired", body = ErrorResponse),
+    )
+)]
+async fn get_shared_item_page(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    let item_and_feed = FeedItemOpsGeneric::get_by_id(&state.pool, &id)
+        .and_then(|item| Ok((FeedOpsGeneric::get_by_id(&state.pool, &item.feed_id)?, item)));
+    let (feed, item) = match item_and_feed {
+        Ok(found) => found,
+        Err(_) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed item '{}' not found", id) })).into_response(),
+    };
+    // The link may expire, so shared caches must not keep the page
+    item_page(&state, &feed, item, "private").await
+}
+
+/// Create a signed link to an item's page that works without access to its feed
+#[utoipa::path(
+    post,
+    path = "/api/feed-items/{id}/share",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = ShareFeedItemRequest,
+    responses(
+        (status = 200, description = "The signed link", body = SharedItemLink),
+        (status = 400, description = "Invalid expiry or FEED_SIGNING_KEY not set", body = ErrorResponse),
+        (status = 404, description = "Item not found", body = ErrorResponse),
+    )
+)]
+async fn share_feed_item(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<ShareFeedItemRequest>
+) -> Response {
+    let Some(key) = permalink::signing_key() else {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse {
+            error: format!("Signed links are not enabled; set FEED_SIGNING_KEY to at least {} characters", permalink::MIN_KEY_BYTES),
+        })).into_response();
+    };
+    if payload.expires_in_hours.is_some_and(|hours| hours <= 0) {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "expires_in_hours must be a positive number of hours".to_string() })).into_response();
+    }
+    if let Err(e) = FeedItemOpsGeneric::get_by_id(&state.pool, &id) {
+        return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed item not found: {}", e) })).into_response();
+    }
+
+    let expires_at = payload.expires_in_hours.map(|hours| chrono::Utc::now() + chrono::Duration::hours(hours));
+    let path = permalink::item_path(&key, &id, expires_at.map(|at| at.timestamp()));
+    Json(SharedItemLink {
+        url: format!("{}{}", public_base_url(&headers), path),
+        expires_at: expires_at.map(|at| at.to_rfc3339()),
+    }).into_response()
+}
+
+/// Unsubscribe from the list an item's email came from. Sends the RFC 8058
+/// one-click request when the sender offers it; otherwise returns the
+/// unsubscribe page or address to open
+#[utoipa::path(
+    post,
+    path = "/api/feed-items/{id}/unsubscribe",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Unsubscribed, or the link to unsubscribe with", body = UnsubscribeResponse),
+        (status = 404, description = "Item not found or without unsubscribe links", body = ErrorResponse),
+        (status = 502, description = "The one-click request failed", body = ErrorResponse),
+    )
+)]
+async fn unsubscribe_feed_item(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    let item = match FeedItemOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(item) => item,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed item not found: {}", e) })).into_response(),
+    };
+
+    let response = match (item.unsubscribe_url, item.unsubscribe_mailto) {
+        (Some(url), _) if item.unsubscribe_one_click => match unsubscribe::one_click(&url).await {
+            Ok(status) => UnsubscribeResponse { method: "one_click".to_string(), url, unsubscribed: true, status: Some(status) },
+            Err(e) => return (StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse { error: format!("One-click unsubscribe failed: {}", e) })).into_response(),
+        },
+        (Some(url), _) => UnsubscribeResponse { method: "link".to_string(), url, unsubscribed: false, status: None },
+        (None, Some(url)) => UnsubscribeResponse { method: "mailto".to_string(), url, unsubscribed: false, status: None },
+        (None, None) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: "The item's email has no unsubscribe link".to_string() })).into_response(),
+    };
+    Json(response).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/feed-items/{id}",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The item with its complete body", body = FeedItem),
+        (status = 404, description = "Item not found", body = ErrorResponse),
+    )
+)]
+async fn get_feed_item(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Response {
+    match FeedItemOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(mut item) => {
+            dedup::resolve_bodies(&state.pool, std::slice::from_mut(&mut item));
+            bodies::load(state.body_store.as_ref(), std::slice::from_mut(&mut item)).await;
+            Json(item).into_response()
+        }
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed item not found: {}", e) })).into_response(),
+    }
+}
+
+/// Update feed item metadata (read status, starred, pinned, rating); allowed in
+/// append-only feeds too, as it is not part of an item's hash
+#[utoipa::path(
+    patch,
+    path = "/api/feed-items/{id}",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = UpdateFeedItemRequest,
+    responses(
+        (status = 200, description = "Item updated", body = FeedItem),
+        (status = 400, description = "Unknown rating", body = ErrorResponse),
+        (status = 404, description = "Item not found", body = ErrorResponse),
+        (status = 409, description = "The feed already has its most pinned items", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
 async fn update_feed_item(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(payload): Json<UpdateFeedItemRequest>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
-    };
-    
     // Get the existing item
     let mut item = match FeedItemOpsGeneric::get_by_id(&state.pool, &id) {
         Ok(item) => item,
@@ -341,6 +1343,25 @@ async fn update_feed_item(
             Json(ErrorResponse { error: format!("Feed item not found: {}", e) })).into_response(),
     };
     
+    let rating = match payload.rating.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(value) => match Rating::parse(value) {
+            Some(rating) => Some(Some(rating)),
+            None => return (StatusCode::BAD_REQUEST,
+                Json(ErrorResponse { error: format!("Unknown rating '{}'; expected 'up' or 'down'", value) })).into_response(),
+        },
+    };
+
+    // Pin first so a refused pin leaves the item unchanged
+    if let Some(pinned) = payload.pinned {
+        if let Err(e) = pinning::set_pinned(&state.pool, &item, pinned) {
+            let status = if e.is::<PinLimitReached>() { StatusCode::CONFLICT } else { StatusCode::INTERNAL_SERVER_ERROR };
+            return (status, Json(ErrorResponse { error: e.to_string() })).into_response();
+        }
+        item.pinned = pinned;
+    }
+
     // Update the metadata fields
     if let Some(is_read) = payload.is_read {
         item.is_read = Some(is_read);
@@ -348,33 +1369,116 @@ async fn update_feed_item(
     if let Some(starred) = payload.starred {
         item.starred = Some(starred);
     }
+    if let Some(rating) = rating {
+        item.rating = rating.map(|rating| rating.as_str().to_string());
+        if let Err(e) = FeedItemOpsGeneric::set_rating(&state.pool, &id, item.rating.as_deref()) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to rate feed item: {}", e) })).into_response();
+        }
+    }
     
-    // Save the updated item (this requires implementing an update method)
-    match update_feed_item_metadata(&state.pool, &item) {
+    // Save the updated metadata
+    match FeedItemOpsGeneric::update_metadata(&state.pool, &id, item.is_read, item.starred) {
         Ok(_) => (StatusCode::OK, Json(item)).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to update feed item: {}", e) })).into_response(),
     }
 }
 
-/// Helper function to update feed item metadata
-fn update_feed_item_metadata(
-    conn: &mut diesel::SqliteConnection,
-    item: &crate::db::models::FeedItem
-) -> anyhow::Result<()> {
-    use crate::db::schema::feed_items::dsl::*;
-    use diesel::prelude::*;
-    
-    let item_id = item.id.as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Item has no ID"))?;
-    
-    diesel::update(feed_items.filter(id.eq(item_id)))
-        .set((
-            is_read.eq(&item.is_read),
-            starred.eq(&item.starred),
-        ))
-        .execute(conn)
-        .map_err(|e| anyhow::anyhow!("Failed to update feed item: {}", e))?;
-        
-    Ok(())
-}
\ No newline at end of file
+/// The items a bulk request selects, or the message why it selects none
+/// properly: no condition at all, or a malformed timestamp
+fn item_selection(req: &BulkFeedItemsRequest) -> Result<ItemSelection, String> {
+    if req.item_ids.is_none() && req.since.is_none() && req.until.is_none() && !req.unread {
+        return Err("Select items with item_ids, since, until or unread".to_string());
+    }
+    Ok(ItemSelection {
+        item_ids: req.item_ids.clone(),
+        since: timestamp_param("since", req.since.as_deref())?,
+        until: timestamp_param("until", req.until.as_deref())?,
+        unread_only: req.unread,
+    })
+}
+
+/// An RFC 3339 timestamp parameter in the form pub_date is stored in, so the
+/// two compare as text
+fn timestamp_param(name: &str, value: Option<&str>) -> Result<Option<String>, String> {
+    value
+        .map(|value| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(|at| at.with_timezone(&chrono::Utc).to_rfc3339())
+                .map_err(|_| format!("{} must be an RFC 3339 timestamp", name))
+        })
+        .transpose()
+}
+
+/// Mark, star or delete many items of a feed at once
+#[utoipa::path(
+    post,
+    path = "/api/feeds/{id}/items/bulk",
+    tag = "feeds",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = BulkFeedItemsRequest,
+    responses(
+        (status = 200, description = "Items changed", body = BulkFeedItemsResponse),
+        (status = 400, description = "No selection or a malformed timestamp", body = ErrorResponse),
+        (status = 404, description = "Feed not found", body = ErrorResponse),
+        (status = 409, description = "Items of an append-only feed cannot be deleted", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn bulk_update_feed_items(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<BulkFeedItemsRequest>,
+) -> Response {
+    let feed = match FeedOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(feed) => feed,
+        Err(_) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Feed with ID '{}' not found", id) })).into_response(),
+    };
+    let selection = match item_selection(&req) {
+        Ok(selection) => selection,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    if req.action == BulkItemAction::Delete && feed.append_only {
+        return (StatusCode::CONFLICT, Json(ErrorResponse {
+            error: format!("Items of the append-only feed '{}' cannot be deleted", feed.title),
+        })).into_response();
+    }
+    let items = match FeedItemOpsGeneric::select(&state.pool, &id, &selection) {
+        Ok(items) => items,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to select feed items: {}", e) })).into_response(),
+    };
+
+    let (is_read, starred) = match req.action {
+        BulkItemAction::MarkRead => (Some(true), None),
+        BulkItemAction::MarkUnread => (Some(false), None),
+        BulkItemAction::Star => (None, Some(true)),
+        BulkItemAction::Unstar => (None, Some(false)),
+        BulkItemAction::Delete => (None, None),
+    };
+    let (updated, skipped) = if req.action == BulkItemAction::Delete {
+        // Content other feeds link to is handed on, as in retention cleanup
+        let (pinned, removable): (Vec<_>, Vec<_>) = items.iter().partition(|item| item.pinned);
+        for item in &removable {
+            if let Err(e) = dedup::remove_item(&state.pool, item) {
+                return (StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse { error: format!("Failed to delete feed item {:?}: {}", item.id, e) })).into_response();
+            }
+        }
+        if let Err(e) = AttachmentOpsGeneric::delete_orphaned(&state.pool) {
+            tracing::warn!("Failed to remove attachments of deleted items: {}", e);
+        }
+        (removable.len(), pinned.len())
+    } else {
+        let item_ids: Vec<String> = items.iter().filter_map(|item| item.id.clone()).collect();
+        match FeedItemOpsGeneric::set_flags(&state.pool, &item_ids, is_read, starred) {
+            Ok(updated) => (updated, 0),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update feed items: {}", e) })).into_response(),
+        }
+    };
+
+    Json(BulkFeedItemsResponse { action: req.action, matched: items.len(), updated, skipped }).into_response()
+}
@@ -0,0 +1,271 @@
+//! Fever-compatible sync API
+//!
+//! Mobile readers such as Reeder, Unread or ReadKit can sync against
+//! `/fever/` directly instead of subscribing to each feed: they fetch items
+//! and mark them read or saved, which sets the same `is_read` and `starred`
+//! flags as the rest of the API. Accounts are the groups. The API is off
+//! until a Fever user is stored with `PUT /api/settings`.
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Form, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::api::{types::ErrorResponse, AppState};
+use crate::db::{
+    connection::DatabasePool,
+    models::{Feed, FeedItem, SyncSelection},
+    operations_generic::{EmailRuleOpsGeneric, FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric},
+};
+use crate::feed::{bodies, fever::{self, FeverCredentials}, public_url};
+
+/// Most items a single call returns, as in Fever
+const ITEMS_PER_CALL: i64 = 50;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/fever/", post(fever_api))
+}
+
+/// The query string and form body of a call, the body taking precedence
+struct Call(HashMap<String, String>);
+
+impl Call {
+    fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    fn int(&self, name: &str) -> Option<i64> {
+        self.get(name)?.trim().parse().ok()
+    }
+
+    /// A comma-separated list of IDs, skipping what is not a number
+    fn ids(&self, name: &str) -> Vec<i64> {
+        self.get(name).unwrap_or_default().split(',').filter_map(|id| id.trim().parse().ok()).collect()
+    }
+}
+
+/// Feeds by their Fever ID, with the Fever ID of the account each belongs to
+fn feeds_by_group(pool: &DatabasePool) -> anyhow::Result<Vec<(i64, Feed)>> {
+    let accounts: HashMap<String, String> = EmailRuleOpsGeneric::get_all(pool)?
+        .into_iter()
+        .filter_map(|rule| Some((rule.id?, rule.imap_account_id)))
+        .collect();
+    Ok(FeedOpsGeneric::get_all(pool)?
+        .into_iter()
+        .map(|feed| (accounts.get(&feed.email_rule_id).map_or(0, |account_id| fever::numeric_id(account_id)), feed))
+        .collect())
+}
+
+/// IDs of the feeds a `mark=feed` or `mark=group` call names; `None` for
+/// group 0, which holds every feed
+fn marked_feeds(pool: &DatabasePool, call: &Call) -> anyhow::Result<Option<Vec<String>>> {
+    let id = call.int("id").unwrap_or(-1);
+    if call.get("mark") == Some("group") && id == 0 {
+        return Ok(None);
+    }
+    let by_feed = call.get("mark") == Some("feed");
+    Ok(Some(feeds_by_group(pool)?
+        .into_iter()
+        .filter_map(|(group_id, feed)| {
+            let feed_id = feed.id?;
+            (if by_feed { fever::numeric_id(&feed_id) } else { group_id } == id).then_some(feed_id)
+        })
+        .collect()))
+}
+
+/// Apply a `mark` call, returning the name of the ID list it changed
+fn mark(pool: &DatabasePool, call: &Call) -> anyhow::Result<Option<&'static str>> {
+    match (call.get("mark"), call.get("as")) {
+        (Some("item"), Some(state)) => {
+            let (is_read, starred, list) = match state {
+                "read" => (Some(true), None, "unread_item_ids"),
+                "unread" => (Some(false), None, "unread_item_ids"),
+                "saved" => (None, Some(true), "saved_item_ids"),
+                "unsaved" => (None, Some(false), "saved_item_ids"),
+                _ => return Ok(None),
+            };
+            let items = FeedItemOpsGeneric::get_for_sync(pool, &SyncSelection::Ids(call.ids("id")), 1)?;
+            let item_ids: Vec<String> = items.into_iter().filter_map(|item| item.id).collect();
+            FeedItemOpsGeneric::set_flags(pool, &item_ids, is_read, starred)?;
+            Ok(Some(list))
+        }
+        (Some("feed" | "group"), Some("read")) => {
+            // Items that arrived after the client last refreshed stay unread
+            let before = call.int("before")
+                .and_then(|before| DateTime::<Utc>::from_timestamp(before, 0))
+                .unwrap_or_else(Utc::now)
+                .to_rfc3339();
+            let feed_ids = marked_feeds(pool, call)?;
+            FeedItemOpsGeneric::mark_read_before(pool, feed_ids.as_deref(), &before)?;
+            Ok(Some("unread_item_ids"))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn item_json(item: FeedItem) -> Value {
+    json!({
+        "id": item.sync_id.unwrap_or_default(),
+        "feed_id": fever::numeric_id(&item.feed_id),
+        "title": item.title,
+        "author": item.author.unwrap_or_default(),
+        "html": item.email_body_html.or(item.description).unwrap_or_default(),
+        "url": item.link.filter(|link| !link.starts_with("mailto:")).unwrap_or_default(),
+        "is_saved": u8::from(item.starred == Some(true)),
+        "is_read": u8::from(item.is_read == Some(true)),
+        "created_on_time": fever::unix_time(&item.pub_date),
+    })
+}
+
+fn ids_json(ids: Vec<i64>) -> Value {
+    Value::String(ids.iter().map(i64::to_string).collect::<Vec<_>>().join(","))
+}
+
+/// Everything an authenticated call asks for, added to `response`
+async fn answer(state: &AppState, call: &Call, response: &mut Map<String, Value>) -> anyhow::Result<()> {
+    let pool = &state.pool;
+    let changed = if call.has("mark") { mark(pool, call)? } else { None };
+
+    if call.has("groups") || call.has("feeds") {
+        let feeds = feeds_by_group(pool)?;
+        let mut feeds_of_group: Vec<(i64, Vec<i64>)> = Vec::new();
+        for (group_id, feed) in &feeds {
+            let feed_id = fever::numeric_id(feed.id.as_deref().unwrap_or_default());
+            match feeds_of_group.iter_mut().find(|(id, _)| id == group_id) {
+                Some((_, feed_ids)) => feed_ids.push(feed_id),
+                None => feeds_of_group.push((*group_id, vec![feed_id])),
+            }
+        }
+        if call.has("groups") {
+            let group_ids: HashSet<i64> = feeds_of_group.iter().map(|(id, _)| *id).collect();
+            let groups: Vec<Value> = ImapAccountOpsGeneric::get_all(pool)?
+                .into_iter()
+                .filter_map(|account| Some((fever::numeric_id(&account.id?), account.name)))
+                .filter(|(id, _)| group_ids.contains(id))
+                .map(|(id, title)| json!({ "id": id, "title": title }))
+                .collect();
+            response.insert("groups".to_string(), groups.into());
+        }
+        if call.has("feeds") {
+            let newest: HashMap<String, Option<String>> = FeedItemOpsGeneric::newest_by_feed(pool)?.into_iter().collect();
+            let base_url = public_url::configured().unwrap_or_default();
+            let feeds: Vec<Value> = feeds.into_iter()
+                .filter_map(|(_, feed)| {
+                    let id = feed.id?;
+                    let last_updated = newest.get(&id).cloned().flatten().unwrap_or(feed.updated_at);
+                    Some(json!({
+                        "id": fever::numeric_id(&id),
+                        "favicon_id": 0,
+                        "title": feed.title,
+                        "url": format!("{}/feeds/{}/{}", base_url, id, feed.feed_type),
+                        "site_url": feed.link.unwrap_or_default(),
+                        "is_spark": 0,
+                        "last_updated_on_time": fever::unix_time(&last_updated),
+                    }))
+                })
+                .collect();
+            response.insert("feeds".to_string(), feeds.into());
+        }
+        let feeds_groups: Vec<Value> = feeds_of_group.into_iter()
+            .map(|(group_id, feed_ids)| json!({ "group_id": group_id, "feed_ids": ids_json(feed_ids) }))
+            .collect();
+        response.insert("feeds_groups".to_string(), feeds_groups.into());
+    }
+
+    if call.has("favicons") {
+        response.insert("favicons".to_string(), json!([]));
+    }
+    if call.has("links") {
+        response.insert("links".to_string(), json!([]));
+    }
+
+    if call.has("items") {
+        let selection = if call.has("with_ids") {
+            SyncSelection::Ids(call.ids("with_ids").into_iter().take(ITEMS_PER_CALL as usize).collect())
+        } else if let Some(max_id) = call.int("max_id").filter(|id| *id > 0) {
+            SyncSelection::Before(max_id)
+        } else {
+            SyncSelection::After(call.int("since_id").unwrap_or(0))
+        };
+        let mut items = FeedItemOpsGeneric::get_for_sync(pool, &selection, ITEMS_PER_CALL)?;
+        bodies::load(state.body_store.as_ref(), &mut items).await;
+        response.insert("total_items".to_string(), FeedItemOpsGeneric::count_all(pool)?.into());
+        response.insert("items".to_string(), items.into_iter().map(item_json).collect::<Vec<_>>().into());
+    }
+
+    if call.has("unread_item_ids") || changed == Some("unread_item_ids") {
+        response.insert("unread_item_ids".to_string(), ids_json(FeedItemOpsGeneric::get_sync_ids(pool, false)?));
+    }
+    if call.has("saved_item_ids") || changed == Some("saved_item_ids") {
+        response.insert("saved_item_ids".to_string(), ids_json(FeedItemOpsGeneric::get_sync_ids(pool, true)?));
+    }
+    Ok(())
+}
+
+// Fever API: what a call returns depends on its parameters, e.g.
+// `?api&items&since_id=0` with `api_key` in the form body
+#[utoipa::path(
+    post,
+    path = "/fever/",
+    tag = "fever",
+    params(
+        ("api" = Option<String>, Query, description = "Present on every call"),
+        ("groups" = Option<String>, Query, description = "Return the accounts as groups, and `feeds_groups`"),
+        ("feeds" = Option<String>, Query, description = "Return the feeds, and `feeds_groups`"),
+        ("items" = Option<String>, Query, description = "Return up to 50 items, after `since_id`, before `max_id` or those in `with_ids`"),
+        ("since_id" = Option<i64>, Query, description = "Items after this one, oldest first"),
+        ("max_id" = Option<i64>, Query, description = "Items before this one, newest first"),
+        ("with_ids" = Option<String>, Query, description = "Comma-separated item IDs"),
+        ("unread_item_ids" = Option<String>, Query, description = "Return the IDs of unread items"),
+        ("saved_item_ids" = Option<String>, Query, description = "Return the IDs of saved (starred) items"),
+    ),
+    request_body(
+        content = String,
+        content_type = "application/x-www-form-urlencoded",
+        description = "`api_key`: MD5 of `username:password`; to change state also `mark` (`item`, `feed` or `group`), `as` (`read`, `unread`, `saved` or `unsaved`), `id` and, for feeds and groups, `before`",
+    ),
+    responses(
+        (status = 200, description = "`api_version` and `auth`, which is 0 for a wrong API key or while the API is off; once authenticated also what the call asked for"),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn fever_api(
+    State(state): State<AppState>,
+    Query(query): Query<HashMap<String, String>>,
+    form: Option<Form<HashMap<String, String>>>,
+) -> Response {
+    let mut params = query;
+    params.extend(form.map(|Form(form)| form).unwrap_or_default());
+    let call = Call(params);
+
+    let mut response = Map::new();
+    response.insert("api_version".to_string(), json!(3));
+    let credentials = match FeverCredentials::load(&state.pool) {
+        Ok(credentials) => credentials,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to load the Fever user: {}", e) })).into_response(),
+    };
+    let authorized = credentials.zip(call.get("api_key")).is_some_and(|(credentials, api_key)| credentials.accepts(api_key));
+    response.insert("auth".to_string(), json!(u8::from(authorized)));
+    if !authorized {
+        return Json(response).into_response();
+    }
+    response.insert("last_refreshed_on_time".to_string(), json!(Utc::now().timestamp()));
+
+    match answer(&state, &call, &mut response).await {
+        Ok(()) => Json(response).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Fever call failed: {}", e) })).into_response(),
+    }
+}
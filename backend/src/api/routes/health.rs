@@ -1,23 +1,42 @@
-use axum::{routing::get, Router, Json, extract::State};
-use serde::Serialize;
-use chrono::Utc;
-use crate::api::AppState;
-
-#[derive(Serialize)]
-pub struct HealthResponse {
-    status: String,
-    version: String,
-    timestamp: String,
-    database: String,
-}
+//! Health endpoints
+//!
+//! `/health` is the summary kept for existing monitors. Orchestrators get a
+//! split: `/health/live` only says the process serves requests, so a restart
+//! is never triggered by a dependency, while `/health/ready` checks the
+//! database with a real query and the background service, answering 503 when
+//! either is down so traffic can be routed elsewhere.
+
+use axum::{extract::State, http::StatusCode, response::{IntoResponse, Response}, routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use std::time::Instant;
+use crate::api::{
+    types::{AccountFreshness, BackgroundCheck, DependencyCheck, DependencyStatus, HealthResponse, LivenessResponse, ReadinessResponse},
+    AppState,
+};
+use crate::background::{self, service::ServiceState};
+use crate::db::{
+    connection::DatabasePool,
+    operations_generic::{DatabaseOpsGeneric, ImapAccountOpsGeneric, ProcessingRunOpsGeneric},
+};
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/health", get(health_check))
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Service health", body = HealthResponse),
+    )
+)]
 async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
-    let db_status = match state.pool.get() {
-        Ok(_) => "connected",
+    let db_status = match DatabaseOpsGeneric::ping(&state.pool) {
+        Ok(()) => "connected",
         Err(_) => "disconnected",
     };
 
@@ -27,4 +46,84 @@ async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
         timestamp: Utc::now().to_rfc3339(),
         database: db_status.to_string(),
     })
-}
\ No newline at end of file
+}
+
+/// Liveness probe: the process is up, whatever its dependencies
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process serves requests", body = LivenessResponse),
+    )
+)]
+async fn liveness() -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        status: "ok".to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+    })
+}
+
+/// Readiness probe: the database answers and the background service has not
+/// failed
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready", body = ReadinessResponse),
+        (status = 503, description = "A critical dependency is down", body = ReadinessResponse),
+    )
+)]
+async fn readiness(State(state): State<AppState>) -> Response {
+    let database = check_database(&state.pool);
+    let service = background::get_service_status(&state.background).await.unwrap_or_default();
+    let background = BackgroundCheck {
+        state: service.state,
+        is_paused: service.is_paused,
+        accounts_count: service.accounts_count,
+    };
+    let accounts = match database.status {
+        DependencyStatus::Up => account_freshness(&state.pool).unwrap_or_default(),
+        DependencyStatus::Down => Vec::new(),
+    };
+
+    let ready = database.status == DependencyStatus::Up && !matches!(background.state, ServiceState::Error(_));
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(ReadinessResponse {
+        ready,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+        database,
+        background,
+        accounts,
+    })).into_response()
+}
+
+pub(super) fn check_database(pool: &DatabasePool) -> DependencyCheck {
+    let started = Instant::now();
+    let outcome = DatabaseOpsGeneric::ping(pool);
+    let latency_ms = started.elapsed().as_millis().try_into().unwrap_or(u64::MAX);
+    match outcome {
+        Ok(()) => DependencyCheck { status: DependencyStatus::Up, latency_ms, error: None },
+        Err(e) => DependencyCheck { status: DependencyStatus::Down, latency_ms, error: Some(e.to_string()) },
+    }
+}
+
+fn account_freshness(pool: &DatabasePool) -> anyhow::Result<Vec<AccountFreshness>> {
+    let now = Utc::now();
+    let last_completed = ProcessingRunOpsGeneric::last_completed_by_account(pool)?;
+    Ok(ImapAccountOpsGeneric::get_all(pool)?
+        .into_iter()
+        .map(|account| {
+            let account_id = account.id.unwrap_or_default();
+            let last_success = last_completed.iter()
+                .find(|(id, _)| *id == account_id)
+                .and_then(|(_, finished_at)| finished_at.clone());
+            let last_success_age_seconds = last_success.as_deref()
+                .and_then(|finished_at| DateTime::parse_from_rfc3339(finished_at).ok())
+                .map(|finished_at| (now - finished_at.with_timezone(&Utc)).num_seconds().max(0));
+            AccountFreshness { account_id, name: account.name, last_success, last_success_age_seconds }
+        })
+        .collect())
+}
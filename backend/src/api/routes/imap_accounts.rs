@@ -4,43 +4,156 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response}
 };
-use serde::{Deserialize, Serialize};
-use crate::api::AppState;
-use crate::db::{operations_generic::ImapAccountOpsGeneric, models::NewImapAccount};
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateImapAccountRequest {
-    pub name: String,
-    pub host: String,
-    pub port: i32,
-    pub username: String,
-    pub password: String,
-    pub use_tls: bool,
-    #[serde(default = "default_post_process_action")]
-    pub default_post_process_action: String,
-    pub default_move_to_folder: Option<String>,
+use crate::api::{
+    types::{CreateImapAccountRequest, DuplicateAccountResponse, ErrorResponse, UpdateImapAccountRequest},
+    AppState,
+};
+use crate::background::quota;
+use crate::db::{connection::DatabasePool, operations_generic::{EmailRuleOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, QuotaGroupOpsGeneric}, models::{AccountProtocol, ConnectionSecurity, NewImapAccount}};
+use crate::feed::chain;
+use crate::imap::{fingerprint, rate_limit, server_name, tls_pin::TlsPin};
+use tracing::warn;
+
+fn validate_max_bytes_per_second(max_bytes_per_second: Option<i32>) -> Option<Response> {
+    match max_bytes_per_second {
+        Some(limit) if limit <= 0 => Some((StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "max_bytes_per_second must be a positive number of bytes".to_string() })).into_response()),
+        _ => None,
+    }
+}
+
+fn validate_rate_limits(max_connections_per_hour: Option<i32>, min_fetch_delay_seconds: Option<i32>, max_messages_per_fetch: Option<i32>) -> Option<Response> {
+    let error = rate_limit::validate_limits(max_connections_per_hour, min_fetch_delay_seconds, max_messages_per_fetch).err()?;
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateImapAccountRequest {
-    pub name: String,
-    pub host: String,
-    pub port: i32,
-    pub username: String,
-    pub password: String,
-    pub use_tls: bool,
-    #[serde(default = "default_post_process_action")]
-    pub default_post_process_action: String,
-    pub default_move_to_folder: Option<String>,
+/// The protocol asked for, IMAP when none is named
+pub(super) fn requested_protocol(protocol: Option<&str>) -> Result<AccountProtocol, String> {
+    match protocol {
+        Some(protocol) => AccountProtocol::parse(protocol)
+            .ok_or_else(|| format!("Unknown protocol '{}'; use imap, jmap or smtp", protocol)),
+        None => Ok(AccountProtocol::Imap),
+    }
 }
 
-fn default_post_process_action() -> String {
-    "mark_read".to_string()
+/// The security mode asked for, or the one the port suggests when none is named
+pub(super) fn requested_security(security: Option<&str>, port: i32, use_tls: bool) -> Result<ConnectionSecurity, String> {
+    match security {
+        Some(security) => ConnectionSecurity::parse(security)
+            .ok_or_else(|| format!("Unknown security '{}'; use none, starttls or ssl_tls", security)),
+        None => Ok(ConnectionSecurity::suggest(port, use_tls)),
+    }
 }
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    error: String,
+fn validate_tls_pin(tls_pin: Option<&str>, use_tls: bool) -> Option<Response> {
+    let error = match tls_pin {
+        Some(_) if !use_tls => "tls_pin requires use_tls".to_string(),
+        Some(pin) => TlsPin::parse(pin).err()?.to_string(),
+        None => return None,
+    };
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response())
+}
+
+fn validate_tls_server_name(tls_server_name: Option<&str>, use_tls: bool) -> Option<Response> {
+    let error = match tls_server_name {
+        Some(_) if !use_tls => "tls_server_name requires use_tls".to_string(),
+        Some(name) => server_name::validate(name).err()?.to_string(),
+        None => return None,
+    };
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response())
+}
+
+fn validate_quota(
+    pool: &DatabasePool,
+    quota_group_id: Option<&str>,
+    max_feeds: Option<i32>,
+    max_items: Option<i32>,
+    max_processing_minutes_per_day: Option<i32>,
+) -> Option<Response> {
+    let error = match quota::validate_limits(max_feeds, max_items, max_processing_minutes_per_day) {
+        Err(e) => e.to_string(),
+        Ok(()) => {
+            let group_id = quota_group_id?;
+            QuotaGroupOpsGeneric::get_by_id(pool, group_id).err()?;
+            format!("Quota group {} does not exist", group_id)
+        }
+    };
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response())
+}
+
+/// Validate a new account, refusing a second account for the same login
+/// unless `allow_duplicate` is set
+pub(super) fn validate_new_account(pool: &DatabasePool, req: &CreateImapAccountRequest) -> Option<Response> {
+    if let Some(response) = validate_max_bytes_per_second(req.max_bytes_per_second) {
+        return Some(response);
+    }
+    if let Some(response) = validate_rate_limits(req.max_connections_per_hour, req.min_fetch_delay_seconds, req.max_messages_per_fetch) {
+        return Some(response);
+    }
+    let security = match requested_security(req.security.as_deref(), req.port, req.use_tls) {
+        Ok(security) => security,
+        Err(error) => return Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response()),
+    };
+    if let Some(response) = validate_tls_pin(req.tls_pin.as_deref(), security.uses_tls()) {
+        return Some(response);
+    }
+    if let Some(response) = validate_tls_server_name(req.tls_server_name.as_deref(), security.uses_tls()) {
+        return Some(response);
+    }
+    if let Some(response) = validate_quota(pool, req.quota_group_id.as_deref(),
+        req.max_feeds, req.max_items, req.max_processing_minutes_per_day) {
+        return Some(response);
+    }
+    // Accounts receiving over SMTP have no mailbox to duplicate
+    match requested_protocol(req.protocol.as_deref()) {
+        Ok(protocol) if req.allow_duplicate || !protocol.is_polled() => return None,
+        Ok(_) => {}
+        Err(error) => return Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response()),
+    }
+
+    let existing = match ImapAccountOpsGeneric::get_all(pool) {
+        Ok(accounts) => accounts,
+        Err(e) => return Some((StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch accounts: {}", e) })).into_response()),
+    };
+    let duplicate = fingerprint::find_same_login(&existing, &req.host, &req.username)?;
+    Some((StatusCode::CONFLICT, Json(DuplicateAccountResponse {
+        error: format!(
+            "Account '{}' already uses {} on {}; set allow_duplicate to add it anyway",
+            duplicate.name, duplicate.username, duplicate.host
+        ),
+        duplicate_of: duplicate.id.clone().unwrap_or_default(),
+    })).into_response())
+}
+
+pub(super) fn new_account(req: CreateImapAccountRequest) -> NewImapAccount {
+    let mut new_account = NewImapAccount::with_defaults(
+        req.name,
+        req.host,
+        req.port,
+        req.username,
+        req.password,
+        req.use_tls,
+        req.default_post_process_action,
+        req.default_move_to_folder,
+    );
+    if let Ok(security) = requested_security(req.security.as_deref(), req.port, req.use_tls) {
+        new_account.set_security(security);
+    }
+    new_account.max_bytes_per_second = req.max_bytes_per_second;
+    new_account.tls_pin = req.tls_pin;
+    new_account.tls_server_name = req.tls_server_name;
+    new_account.quota_group_id = req.quota_group_id;
+    new_account.max_feeds = req.max_feeds;
+    new_account.max_items = req.max_items;
+    new_account.max_processing_minutes_per_day = req.max_processing_minutes_per_day;
+    new_account.max_connections_per_hour = req.max_connections_per_hour;
+    new_account.min_fetch_delay_seconds = req.min_fetch_delay_seconds;
+    new_account.max_messages_per_fetch = req.max_messages_per_fetch;
+    if let Ok(protocol) = requested_protocol(req.protocol.as_deref()) {
+        new_account.protocol = protocol.as_str().to_string();
+    }
+    new_account
 }
 
 pub fn routes() -> Router<AppState> {
@@ -49,6 +162,15 @@ pub fn routes() -> Router<AppState> {
         .route("/api/imap-accounts/:id", get(get_account).put(update_account).delete(delete_account))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/imap-accounts",
+    tag = "imap-accounts",
+    responses(
+        (status = 200, description = "All IMAP accounts", body = [ImapAccount]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
 async fn list_accounts(State(state): State<AppState>) -> Response {
     match ImapAccountOpsGeneric::get_all(&state.pool) {
         Ok(accounts) => Json(accounts).into_response(),
@@ -57,28 +179,43 @@ async fn list_accounts(State(state): State<AppState>) -> Response {
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/imap-accounts",
+    tag = "imap-accounts",
+    request_body = CreateImapAccountRequest,
+    responses(
+        (status = 201, description = "Account created", body = ImapAccount),
+        (status = 400, description = "Invalid account settings", body = ErrorResponse),
+        (status = 409, description = "An account with the same host and username exists", body = DuplicateAccountResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
 async fn create_account(
     State(state): State<AppState>,
     Json(req): Json<CreateImapAccountRequest>
 ) -> Response {
-    let new_account = NewImapAccount::with_defaults(
-        req.name,
-        req.host,
-        req.port,
-        req.username,
-        req.password,
-        req.use_tls,
-        req.default_post_process_action,
-        req.default_move_to_folder,
-    );
+    if let Some(response) = validate_new_account(&state.pool, &req) {
+        return response;
+    }
 
-    match ImapAccountOpsGeneric::create(&state.pool, &new_account) {
+    match ImapAccountOpsGeneric::create(&state.pool, &new_account(req)) {
         Ok(account) => (StatusCode::CREATED, Json(account)).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: format!("Failed to create account: {}", e) })).into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/imap-accounts/{id}",
+    tag = "imap-accounts",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The account", body = ImapAccount),
+        (status = 404, description = "Account not found", body = ErrorResponse),
+    )
+)]
 async fn get_account(
     State(state): State<AppState>,
     Path(id): Path<String>
@@ -90,18 +227,49 @@ async fn get_account(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/imap-accounts/{id}",
+    tag = "imap-accounts",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = UpdateImapAccountRequest,
+    responses(
+        (status = 200, description = "Account updated", body = ImapAccount),
+        (status = 400, description = "Invalid account settings", body = ErrorResponse),
+        (status = 404, description = "Account not found", body = ErrorResponse),
+    )
+)]
 async fn update_account(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(req): Json<UpdateImapAccountRequest>
 ) -> Response {
-    let mut conn = match state.pool.get() {
-        Ok(conn) => conn,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: format!("Database connection error: {}", e) })).into_response(),
+    if let Some(response) = validate_max_bytes_per_second(req.max_bytes_per_second) {
+        return response;
+    }
+    if let Some(response) = validate_rate_limits(req.max_connections_per_hour, req.min_fetch_delay_seconds, req.max_messages_per_fetch) {
+        return response;
+    }
+    let security = match requested_security(req.security.as_deref(), req.port, req.use_tls) {
+        Ok(security) => security,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+    if let Some(response) = validate_tls_pin(req.tls_pin.as_deref(), security.uses_tls()) {
+        return response;
+    }
+    if let Some(response) = validate_tls_server_name(req.tls_server_name.as_deref(), security.uses_tls()) {
+        return response;
+    }
+    if let Some(response) = validate_quota(&state.pool, req.quota_group_id.as_deref(),
+        req.max_feeds, req.max_items, req.max_processing_minutes_per_day) {
+        return response;
+    }
+    let protocol = match requested_protocol(req.protocol.as_deref()) {
+        Ok(protocol) => protocol,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
     };
 
-    let updated_account = NewImapAccount::with_defaults(
+    let mut updated_account = NewImapAccount::with_defaults(
         req.name,
         req.host,
         req.port,
@@ -111,18 +279,64 @@ async fn update_account(
         req.default_post_process_action,
         req.default_move_to_folder,
     );
+    updated_account.set_security(security);
+    updated_account.max_bytes_per_second = req.max_bytes_per_second;
+    updated_account.tls_pin = req.tls_pin;
+    updated_account.tls_server_name = req.tls_server_name;
+    updated_account.quota_group_id = req.quota_group_id;
+    updated_account.max_feeds = req.max_feeds;
+    updated_account.max_items = req.max_items;
+    updated_account.max_processing_minutes_per_day = req.max_processing_minutes_per_day;
+    updated_account.max_connections_per_hour = req.max_connections_per_hour;
+    updated_account.min_fetch_delay_seconds = req.min_fetch_delay_seconds;
+    updated_account.max_messages_per_fetch = req.max_messages_per_fetch;
+    updated_account.protocol = protocol.as_str().to_string();
 
-    match ImapAccountOps::update(&state.pool, &id, &updated_account) {
-        Ok(account) => Json(account).into_response(),
+    let previous = ImapAccountOpsGeneric::get_by_id(&state.pool, &id).ok();
+
+    match ImapAccountOpsGeneric::update(&state.pool, &id, &updated_account) {
+        Ok(mut account) => {
+            // A different server or login may be a different mailbox
+            let moved = previous.is_some_and(|previous| previous.port != account.port
+                || !fingerprint::same_login(&previous.host, &previous.username, &account.host, &account.username));
+            if moved && account.fingerprint.is_some() {
+                match ImapAccountOpsGeneric::update_fingerprint(&state.pool, &id, None) {
+                    Ok(()) => account.fingerprint = None,
+                    Err(e) => warn!("Failed to reset fingerprint of account {}: {}", id, e),
+                }
+            }
+            Json(account).into_response()
+        }
         Err(e) => (StatusCode::NOT_FOUND,
             Json(ErrorResponse { error: format!("Failed to update account: {}", e) })).into_response(),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/imap-accounts/{id}",
+    tag = "imap-accounts",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Account deleted"),
+        (status = 404, description = "Account not found", body = ErrorResponse),
+        (status = 409, description = "The account fills an append-only feed", body = ErrorResponse),
+    )
+)]
 async fn delete_account(
     State(state): State<AppState>,
     Path(id): Path<String>
 ) -> Response {
+    // Deleting the account would delete its rules' feeds along with their items
+    let feeds: Vec<_> = EmailRuleOpsGeneric::get_by_account_id(&state.pool, &id)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|rule| rule.id)
+        .flat_map(|rule_id| FeedOpsGeneric::get_by_rule_id(&state.pool, &rule_id).unwrap_or_default())
+        .collect();
+    if let Err(e) = chain::ensure_removable(&feeds) {
+        return (StatusCode::CONFLICT, Json(ErrorResponse { error: e.to_string() })).into_response();
+    }
     match ImapAccountOpsGeneric::delete(&state.pool, &id) {
         Ok(_) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => (StatusCode::NOT_FOUND,
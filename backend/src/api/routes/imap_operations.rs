@@ -1,47 +1,54 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
-use serde::{Deserialize, Serialize};
-use tracing::{info, error};
-
-use crate::api::AppState;
-use crate::db::operations_generic::ImapAccountOpsGeneric;
-use crate::imap::{ImapClient, EmailProcessor};
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TestConnectionResponse {
-    pub success: bool,
-    pub message: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub folders: Option<Vec<String>>,
-}
+use tracing::{info, error, warn};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProcessAccountResponse {
-    pub success: bool,
-    pub emails_processed: usize,
-    pub items_created: usize,
-    pub errors: Vec<String>,
-}
+use crate::api::{
+    types::{
+        ConnectionDiagnostics, ErrorResponse, FolderNode, ProcessAccountResponse, SetupConnectionRequest,
+        TestConnectionResponse, TlsFingerprintResponse,
+    },
+    AppState,
+};
+use crate::db::{models::{AccountProtocol, ImapAccount}, operations_generic::ImapAccountOpsGeneric};
+use super::setup::{client_for, validate_connection_request};
+use crate::imap::{client::ImapClientError, fingerprint, folders, tls_pin::TlsPin, ImapClient, EmailProcessor};
+use crate::jmap::JmapClient;
 
 // Test IMAP connection and list folders
+#[utoipa::path(
+    get,
+    path = "/api/imap/{id}/test",
+    tag = "imap",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Connection test result", body = TestConnectionResponse),
+        (status = 404, description = "Account not found", body = String),
+    )
+)]
 pub async fn test_connection(
     Path(account_id): Path<String>,
     State(state): State<AppState>,
 ) -> Result<Json<TestConnectionResponse>, (StatusCode, String)> {
     info!("Testing IMAP connection for account: {}", account_id);
     
-    let mut conn = state.pool
-        .get()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
-    
     // Get the account
     let account = ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id)
         .map_err(|e| (StatusCode::NOT_FOUND, format!("Account not found: {}", e)))?;
+    match account.account_protocol() {
+        AccountProtocol::Imap => {}
+        AccountProtocol::Jmap => return Ok(Json(test_jmap_connection(&account).await)),
+        AccountProtocol::Smtp => return Ok(Json(TestConnectionResponse {
+            success: false,
+            message: format!("Account '{}' receives its mail over SMTP; there is no server to connect to", account.name),
+            folders: None,
+            duplicate_of: Vec::new(),
+        })),
+    }
     
     // Test connection
     let client = ImapClient::new(&account)
@@ -53,10 +60,18 @@ pub async fn test_connection(
             match client.list_folders().await {
                 Ok(folders) => {
                     info!("Successfully connected and retrieved {} folders", folders.len());
+                    let duplicate_of = check_duplicate_mailbox(&state, &client, &account).await;
+                    let message = if duplicate_of.is_empty() {
+                        format!("Successfully connected to {}", account.host)
+                    } else {
+                        format!("Successfully connected to {}, but this mailbox is already configured as account {}",
+                                account.host, duplicate_of.join(", "))
+                    };
                     Ok(Json(TestConnectionResponse {
                         success: true,
-                        message: format!("Successfully connected to {}", account.host),
+                        message,
                         folders: Some(folders),
+                        duplicate_of,
                     }))
                 }
                 Err(e) => {
@@ -65,6 +80,7 @@ pub async fn test_connection(
                         success: true,
                         message: format!("Connected to {} but couldn't list folders: {}", account.host, e),
                         folders: None,
+                        duplicate_of: Vec::new(),
                     }))
                 }
             }
@@ -73,7 +89,9 @@ pub async fn test_connection(
             error!("Connection test failed: {}", e);
             
             // Extract more specific error information
-            let error_message = if e.to_string().contains("Unable to parse status response") {
+            let error_message = if matches!(e.downcast_ref(), Some(ImapClientError::CertificatePinMismatch { .. })) {
+                e.to_string()
+            } else if e.to_string().contains("Unable to parse status response") {
                 format!("Connection failed: The server response was not in expected IMAP format. \
                         This often happens when: \
                         1) The port is wrong (e.g., using HTTP port instead of IMAP port), \
@@ -107,29 +125,198 @@ pub async fn test_connection(
                 success: false,
                 message: error_message,
                 folders: None,
+                duplicate_of: Vec::new(),
             }))
         }
     }
 }
 
+/// Discover the JMAP session of the account and list its mailboxes
+async fn test_jmap_connection(account: &ImapAccount) -> TestConnectionResponse {
+    let folders = match JmapClient::connect(account).await {
+        Ok(client) => client.list_folders().await,
+        Err(e) => Err(e),
+    };
+    match folders {
+        Ok(folders) => {
+            info!("Successfully connected to JMAP server and retrieved {} mailboxes", folders.len());
+            TestConnectionResponse {
+                success: true,
+                message: format!("Successfully connected to {}", account.host),
+                folders: Some(folders),
+                duplicate_of: Vec::new(),
+            }
+        }
+        Err(e) => {
+            error!("JMAP connection test failed: {:#}", e);
+            TestConnectionResponse {
+                success: false,
+                message: format!("Connection failed: {:#}", e),
+                folders: None,
+                duplicate_of: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Fingerprint the account's mailbox and report accounts that reach the same one
+async fn check_duplicate_mailbox(state: &AppState, client: &ImapClient, account: &ImapAccount) -> Vec<String> {
+    match fingerprint::refresh(&state.pool, client, account).await {
+        Ok(aliases) => aliases,
+        Err(e) => {
+            warn!("Could not fingerprint mailbox of account '{}': {}", account.name, e);
+            Vec::new()
+        }
+    }
+}
+
+// Diagnose each step of connecting to a saved account
+#[utoipa::path(
+    post,
+    path = "/api/imap-accounts/{id}/test",
+    tag = "imap",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Outcome of each step of connecting; `failed_step` names the first that failed", body = ConnectionDiagnostics),
+        (status = 404, description = "Account not found", body = String),
+    )
+)]
+pub async fn diagnose_account(
+    Path(account_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ConnectionDiagnostics>, (StatusCode, String)> {
+    let account = ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id)
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("Account not found: {}", e)))?;
+
+    let client = ImapClient::new(&account)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create IMAP client: {}", e)))?;
+    let diagnostics = client.diagnose().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Diagnostics failed: {}", e)))?;
+
+    info!("Diagnosed account '{}': {}", account.name,
+          diagnostics.failed_step.as_deref().map_or("all steps passed".to_string(), |step| format!("{} failed", step)));
+    Ok(Json(diagnostics))
+}
+
+// Diagnose each step of connecting with settings that are not saved yet
+#[utoipa::path(
+    post,
+    path = "/api/imap-accounts/test",
+    tag = "imap",
+    request_body = SetupConnectionRequest,
+    responses(
+        (status = 200, description = "Outcome of each step of connecting; `failed_step` names the first that failed", body = ConnectionDiagnostics),
+        (status = 400, description = "Invalid TLS pin or server name", body = ErrorResponse),
+    )
+)]
+pub async fn diagnose_settings(Json(req): Json<SetupConnectionRequest>) -> Response {
+    if let Some(response) = validate_connection_request(&req) {
+        return response;
+    }
+    let diagnostics = match client_for(&req) {
+        Ok(client) => client.diagnose().await,
+        Err(e) => Err(e.context("Failed to create IMAP client")),
+    };
+    match diagnostics {
+        Ok(diagnostics) => Json(diagnostics).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("{:#}", e) })).into_response(),
+    }
+}
+
+// Show the certificate fingerprints of the account's server
+#[utoipa::path(
+    get,
+    path = "/api/imap/{id}/tls-fingerprint",
+    tag = "imap",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Fingerprints of the presented certificate; it is not validated, compare it out of band before pinning it", body = TlsFingerprintResponse),
+        (status = 400, description = "Account does not use TLS", body = String),
+        (status = 404, description = "Account not found", body = String),
+        (status = 502, description = "TLS handshake failed", body = String),
+    )
+)]
+pub async fn tls_fingerprint(
+    Path(account_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<TlsFingerprintResponse>, (StatusCode, String)> {
+    let account = ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id)
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("Account not found: {}", e)))?;
+    if !account.use_tls {
+        return Err((StatusCode::BAD_REQUEST, "Account does not use TLS".to_string()));
+    }
+
+    let client = ImapClient::new(&account)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create IMAP client: {}", e)))?;
+    let observed = client.tls_fingerprints().await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+
+    let (certificate_pin, public_key_pin) = observed.pins();
+    Ok(Json(TlsFingerprintResponse {
+        certificate_pin: certificate_pin.to_string(),
+        public_key_pin: public_key_pin.to_string(),
+        matches_pin: account.tls_pin.as_deref()
+            .map(|pin| TlsPin::parse(pin).is_ok_and(|pin| pin.matches(&observed))),
+        pin: account.tls_pin,
+    }))
+}
+
+// Browse the account's folders, e.g. to pick one for a rule
+#[utoipa::path(
+    get,
+    path = "/api/imap-accounts/{id}/folders",
+    tag = "imap",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Folder hierarchy with message and unseen counts", body = [FolderNode]),
+        (status = 404, description = "Account not found", body = String),
+        (status = 502, description = "Folders could not be read from the server", body = String),
+    )
+)]
+pub async fn list_folders(
+    Path(account_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<FolderNode>>, (StatusCode, String)> {
+    let account = ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id)
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("Account not found: {}", e)))?;
+
+    let statuses = match account.account_protocol() {
+        AccountProtocol::Jmap => match JmapClient::connect(&account).await {
+            Ok(client) => client.folder_statuses().await,
+            Err(e) => Err(e),
+        },
+        _ => ImapClient::new(&account)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create IMAP client: {}", e)))?
+            .folder_statuses()
+            .await,
+    }
+    .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{:#}", e)))?;
+
+    Ok(Json(folders::tree(&statuses)))
+}
+
 // Process emails for an account
+#[utoipa::path(
+    post,
+    path = "/api/imap/{id}/process",
+    tag = "imap",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Processing result", body = ProcessAccountResponse),
+        (status = 404, description = "Account not found", body = String),
+    )
+)]
 pub async fn process_account(
     Path(account_id): Path<String>,
     State(state): State<AppState>,
 ) -> Result<Json<ProcessAccountResponse>, (StatusCode, String)> {
     info!("Processing IMAP account: {}", account_id);
     
-    let mut conn = state.pool
-        .get()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
-    
     // Get the account
     let account = ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id)
         .map_err(|e| (StatusCode::NOT_FOUND, format!("Account not found: {}", e)))?;
     
-    // Drop the connection before creating processor
-    drop(conn);
-    
     // Create processor with pool
     let processor = EmailProcessor::new(account, state.pool.clone());
     
@@ -144,6 +331,10 @@ pub async fn process_account(
                 emails_processed: result.total_emails_processed,
                 items_created: result.new_feed_items_created,
                 errors: result.errors,
+                post_process_failures: result.post_process_failures,
+                run_id: result.run_id,
+                bytes_received: result.transfer.bytes_received,
+                bytes_sent: result.transfer.bytes_sent,
             }))
         }
         Err(e) => {
@@ -153,27 +344,34 @@ pub async fn process_account(
                 emails_processed: 0,
                 items_created: 0,
                 errors: vec![format!("Processing failed: {}", e)],
+                post_process_failures: vec![],
+                run_id: None,
+                bytes_received: 0,
+                bytes_sent: 0,
             }))
         }
     }
 }
 
 // Process all active accounts
+#[utoipa::path(
+    post,
+    path = "/api/imap/process-all",
+    tag = "imap",
+    responses(
+        (status = 200, description = "Processing result per account", body = [ProcessAccountResponse]),
+        (status = 500, description = "Database error", body = String),
+    )
+)]
 pub async fn process_all_accounts(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<ProcessAccountResponse>>, (StatusCode, String)> {
     info!("Processing all IMAP accounts");
     
-    let mut conn = state.pool
-        .get()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?;
-    
     // Get all accounts
-    let accounts = ImapAccountOpsGeneric::get_all(&mut conn)
+    let accounts = ImapAccountOpsGeneric::get_all(&state.pool)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get accounts: {}", e)))?;
     
-    drop(conn);
-    
     let mut results = Vec::new();
     
     for account in accounts {
@@ -187,6 +385,10 @@ pub async fn process_all_accounts(
                     emails_processed: result.total_emails_processed,
                     items_created: result.new_feed_items_created,
                     errors: result.errors,
+                    post_process_failures: result.post_process_failures,
+                    run_id: result.run_id,
+                    bytes_received: result.transfer.bytes_received,
+                    bytes_sent: result.transfer.bytes_sent,
                 });
             }
             Err(e) => {
@@ -196,6 +398,10 @@ pub async fn process_all_accounts(
                     emails_processed: 0,
                     items_created: 0,
                     errors: vec![format!("Processing failed: {}", e)],
+                    post_process_failures: vec![],
+                    run_id: None,
+                    bytes_received: 0,
+                    bytes_sent: 0,
                 });
             }
         }
@@ -207,6 +413,10 @@ pub async fn process_all_accounts(
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/api/imap/:id/test", get(test_connection))
+        .route("/api/imap/:id/tls-fingerprint", get(tls_fingerprint))
+        .route("/api/imap-accounts/:id/folders", get(list_folders))
+        .route("/api/imap-accounts/:id/test", post(diagnose_account))
+        .route("/api/imap-accounts/test", post(diagnose_settings))
         .route("/api/imap/:id/process", post(process_account))
         .route("/api/imap/process-all", post(process_all_accounts))
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use std::collections::HashSet;
+use tracing::info;
+
+use super::imap_accounts::{new_account, validate_new_account};
+use crate::api::{
+    types::{ErrorResponse, ImportRequest, ImportResponse, SkippedImport},
+    AppState,
+};
+use crate::background::quota::{self, QuotaExceeded, QuotaResource};
+use crate::db::{
+    models::{EmailRule, NewEmailRule, NewFeed},
+    operations_generic::{EmailRuleOpsGeneric, ImapAccountOpsGeneric},
+};
+use crate::feed::template;
+use crate::import::{self, ImportFormat, ImportMapping, MatchOn};
+
+/// Folder of the mappings that name none, unless the request gives one
+const DEFAULT_FOLDER: &str = "INBOX";
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/import", post(import))
+}
+
+/// Folder, `To` and `From` addresses a mapping's rule matches on, for
+/// recognizing mappings an account already has a rule for
+fn rule_key(folder: &str, to_address: Option<&str>, from_address: Option<&str>) -> (String, String, String) {
+    let lower = |value: Option<&str>| value.unwrap_or_default().trim().to_lowercase();
+    (folder.trim().to_string(), lower(to_address), lower(from_address))
+}
+
+fn mapping_key(mapping: &ImportMapping, folder: &str) -> (String, String, String) {
+    match mapping.match_on.unwrap_or(MatchOn::To) {
+        MatchOn::To => rule_key(folder, Some(&mapping.address), None),
+        MatchOn::From => rule_key(folder, None, Some(&mapping.address)),
+    }
+}
+
+// Create rules and feeds from another tool's export
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    tag = "import",
+    request_body = ImportRequest,
+    responses(
+        (status = 201, description = "Rules and feeds created, on the new account when one was given", body = ImportResponse),
+        (status = 200, description = "Every mapping was already covered; nothing was created", body = ImportResponse),
+        (status = 400, description = "Unreadable export or invalid account settings; nothing was created", body = ErrorResponse),
+        (status = 403, description = "The feeds exceed the account's or its quota group's feed quota", body = ErrorResponse),
+        (status = 404, description = "Account not found", body = ErrorResponse),
+        (status = 409, description = "An account with the same host and username exists", body = DuplicateAccountResponse),
+        (status = 500, description = "Database error; nothing was created", body = ErrorResponse),
+    )
+)]
+async fn import(
+    State(state): State<AppState>,
+    Json(req): Json<ImportRequest>,
+) -> Response {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+    let Some(format) = ImportFormat::parse(&req.format) else {
+        return bad_request(format!("Unknown format '{}'; use kill_the_newsletter, csv or json", req.format));
+    };
+    let mappings = match import::parse(format, &req.content) {
+        Ok(mappings) if mappings.is_empty() => return bad_request("The export has no mappings".to_string()),
+        Ok(mappings) => mappings,
+        Err(e) => return bad_request(format!("{:#}", e)),
+    };
+    let default_folder = req.folder.map(|folder| folder.trim().to_string()).filter(|folder| !folder.is_empty())
+        .unwrap_or_else(|| DEFAULT_FOLDER.to_string());
+
+    let (account, new_account, existing_rules) = match (req.imap_account_id, req.account) {
+        (Some(account_id), None) => match ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id) {
+            Ok(account) => match EmailRuleOpsGeneric::get_by_account_id(&state.pool, &account_id) {
+                Ok(rules) => (account, None, rules),
+                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse { error: format!("Failed to load rules: {}", e) })).into_response(),
+            },
+            Err(e) => return (StatusCode::NOT_FOUND,
+                Json(ErrorResponse { error: format!("Account not found: {}", e) })).into_response(),
+        },
+        (None, Some(account)) => {
+            if let Some(response) = validate_new_account(&state.pool, &account) {
+                return response;
+            }
+            let new_account = new_account(account);
+            (new_account.to_account(), Some(new_account), Vec::<EmailRule>::new())
+        }
+        _ => return bad_request("Give either imap_account_id or account".to_string()),
+    };
+
+    let mut seen: HashSet<_> = existing_rules.iter()
+        .map(|rule| rule_key(&rule.folder, rule.to_address.as_deref(), rule.from_address.as_deref()))
+        .collect();
+    let mut new_rules = Vec::new();
+    let mut new_feeds = Vec::new();
+    let mut skipped = Vec::new();
+    for mapping in mappings {
+        let folder = mapping.folder.clone().unwrap_or_else(|| default_folder.clone());
+        if !seen.insert(mapping_key(&mapping, &folder)) {
+            skipped.push(SkippedImport {
+                reason: format!("A rule already matches {} in {}", mapping.address, folder),
+                address: mapping.address,
+                title: mapping.title,
+            });
+            continue;
+        }
+        if let Err(e) = template::validate(&mapping.title) {
+            return bad_request(format!("Mapping '{}': {}", mapping.address, e));
+        }
+
+        let (to_address, from_address) = match mapping.match_on.unwrap_or(MatchOn::To) {
+            MatchOn::To => (Some(mapping.address), None),
+            MatchOn::From => (None, Some(mapping.address)),
+        };
+        let rule = NewEmailRule::from_account_defaults(mapping.title.clone(), &account, folder, to_address, from_address, None, None, true);
+        let feed_type = mapping.feed_type.unwrap_or_else(|| "rss".to_string());
+        new_feeds.push(NewFeed::with_retention(mapping.title, None, None, rule.id.clone(), feed_type, true, None, None, None));
+        new_rules.push(rule);
+    }
+
+    if !new_feeds.is_empty() {
+        if let Err(e) = quota::check_room(&state.pool, &account, QuotaResource::Feeds, new_feeds.len() as i64) {
+            let status = if e.is::<QuotaExceeded>() { StatusCode::FORBIDDEN } else { StatusCode::INTERNAL_SERVER_ERROR };
+            return (status, Json(ErrorResponse { error: e.to_string() })).into_response();
+        }
+    }
+
+    let account_created = new_account.is_some();
+    let created = match new_account {
+        Some(new_account) => ImapAccountOpsGeneric::create_with_feeds(&state.pool, &new_account, &new_rules, &new_feeds)
+            .map(|(account, rules, feeds)| (account.id.unwrap_or_default(), rules, feeds)),
+        None => state.pool.transaction(|tx| {
+            let rules = new_rules.iter().map(|rule| tx.create_email_rule(rule)).collect::<anyhow::Result<Vec<_>>>()?;
+            let feeds = new_feeds.iter().map(|feed| tx.create_feed(feed)).collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((account.id.clone().unwrap_or_default(), rules, feeds))
+        }),
+    };
+    match created {
+        Ok((imap_account_id, rules, feeds)) => {
+            for rule in &rules {
+                state.background.controller.rule_changed(rule).await;
+            }
+            info!("Imported {} feeds from a {} export into account {} ({} skipped)", feeds.len(), format.as_str(), imap_account_id, skipped.len());
+            let status = if feeds.is_empty() && !account_created { StatusCode::OK } else { StatusCode::CREATED };
+            (status, Json(ImportResponse { imap_account_id, account_created, rules, feeds, skipped })).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to import: {}", e) })).into_response(),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::api::{
+    types::{ErrorResponse, JobQuery, JobResponse},
+    AppState,
+};
+use crate::db::{
+    models::{JobFilter, JobStatus},
+    operations_generic::JobOpsGeneric,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+const DEFAULT_JOB_LIMIT: i64 = 50;
+const MAX_JOB_LIMIT: i64 = 500;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/jobs", get(list_jobs))
+        .route("/api/jobs/:id", get(get_job))
+}
+
+fn bad_request(error: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response()
+}
+
+/// List background jobs, newest first
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "jobs",
+    params(JobQuery),
+    responses(
+        (status = 200, description = "Jobs, most recently created first", body = [JobResponse]),
+        (status = 400, description = "Unknown status or limit out of range", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_jobs(
+    State(state): State<AppState>,
+    Query(query): Query<JobQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(DEFAULT_JOB_LIMIT);
+    if !(1..=MAX_JOB_LIMIT).contains(&limit) {
+        return bad_request(format!("limit must be between 1 and {}", MAX_JOB_LIMIT));
+    }
+    let status = match query.status.as_deref().map(|status| (status, JobStatus::parse(status))) {
+        Some((status, None)) => {
+            return bad_request(format!("Unknown status '{}'; use queued, running, completed or failed", status));
+        }
+        Some((_, status)) => status,
+        None => None,
+    };
+
+    let filter = JobFilter {
+        kind: query.kind,
+        target_id: query.target_id,
+        status,
+        limit,
+    };
+    match JobOpsGeneric::list(&state.pool, &filter) {
+        Ok(jobs) => Json(jobs.into_iter().map(JobResponse::from).collect::<Vec<_>>()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to load jobs: {}", e) })).into_response(),
+    }
+}
+
+/// Get a background job with its progress and, once completed, its result
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}",
+    tag = "jobs",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "The job", body = JobResponse),
+        (status = 404, description = "Job not found", body = ErrorResponse),
+    )
+)]
+async fn get_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match JobOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(job) => Json(JobResponse::from(job)).into_response(),
+        Err(_) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Job {} not found", id) })).into_response(),
+    }
+}
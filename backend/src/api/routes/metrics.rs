@@ -0,0 +1,30 @@
+use crate::api::{types::ErrorResponse, AppState};
+use crate::background::metrics;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/metrics", get(get_metrics))
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Seconds since each feed's newest item and each account's last completed run, and rule evaluation costs, in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_metrics(State(state): State<AppState>) -> Response {
+    match metrics::render(&state.pool) {
+        Ok(body) => ([(header::CONTENT_TYPE, metrics::CONTENT_TYPE)], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to collect metrics: {}", e) })).into_response(),
+    }
+}
@@ -1,6 +1,20 @@
+pub mod admin;
+pub mod analysis;
 pub mod background;
+pub mod chat_integrations;
+pub mod deliveries;
 pub mod health;
 pub mod imap_accounts;
 pub mod email_rules;
 pub mod feeds;
-pub mod imap_operations;
\ No newline at end of file
+pub mod fever;
+pub mod imap_operations;
+pub mod import;
+pub mod jobs;
+pub mod metrics;
+pub mod quotas;
+pub mod sender_filters;
+pub mod senders;
+pub mod settings;
+pub mod setup;
+pub mod timeline;
\ No newline at end of file
@@ -0,0 +1,240 @@
+use crate::api::{
+    types::{AccountQuotaResponse, ErrorResponse, QuotaGroupRequest, QuotaReport},
+    AppState,
+};
+use crate::background::quota;
+use crate::db::{
+    connection::DatabasePool,
+    models::{NewQuotaGroup, QuotaGroup},
+    operations_generic::{ImapAccountOpsGeneric, QuotaGroupOpsGeneric},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/quota-groups", get(list_groups).post(create_group))
+        .route("/api/quota-groups/:id", get(get_group).put(update_group).delete(delete_group))
+        .route("/api/quota-groups/:id/usage", get(get_group_usage))
+        .route("/api/imap-accounts/:id/quota", get(get_account_quota))
+}
+
+/// Build and validate a quota group from a request body
+fn group_from_request(req: QuotaGroupRequest) -> anyhow::Result<NewQuotaGroup> {
+    if req.name.trim().is_empty() {
+        anyhow::bail!("name must not be empty");
+    }
+    quota::validate_limits(req.max_feeds, req.max_items, req.max_processing_minutes_per_day)?;
+    Ok(NewQuotaGroup::new(req.name, req.max_feeds, req.max_items, req.max_processing_minutes_per_day))
+}
+
+/// The group's limits next to the usage summed over its accounts
+fn group_report(pool: &DatabasePool, group: &QuotaGroup) -> anyhow::Result<QuotaReport> {
+    let account_ids: Vec<String> = ImapAccountOpsGeneric::get_by_quota_group(pool, group.id.as_deref().unwrap_or_default())?
+        .into_iter()
+        .filter_map(|account| account.id)
+        .collect();
+    Ok(QuotaReport {
+        max_feeds: group.max_feeds,
+        max_items: group.max_items,
+        max_processing_minutes_per_day: group.max_processing_minutes_per_day,
+        usage: quota::usage(pool, &account_ids)?,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/quota-groups",
+    tag = "quotas",
+    responses(
+        (status = 200, description = "All quota groups, by name", body = [QuotaGroup]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_groups(State(state): State<AppState>) -> Response {
+    match QuotaGroupOpsGeneric::get_all(&state.pool) {
+        Ok(groups) => Json(groups).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch quota groups: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/quota-groups",
+    tag = "quotas",
+    request_body = QuotaGroupRequest,
+    responses(
+        (status = 201, description = "Quota group created", body = QuotaGroup),
+        (status = 400, description = "Invalid limits", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn create_group(
+    State(state): State<AppState>,
+    Json(req): Json<QuotaGroupRequest>,
+) -> Response {
+    let new_group = match group_from_request(req) {
+        Ok(group) => group,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match QuotaGroupOpsGeneric::create(&state.pool, &new_group) {
+        Ok(group) => (StatusCode::CREATED, Json(group)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create quota group: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/quota-groups/{id}",
+    tag = "quotas",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The quota group", body = QuotaGroup),
+        (status = 404, description = "Quota group not found", body = ErrorResponse),
+    )
+)]
+async fn get_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match QuotaGroupOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(group) => Json(group).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Quota group not found: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/quota-groups/{id}",
+    tag = "quotas",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = QuotaGroupRequest,
+    responses(
+        (status = 200, description = "Quota group updated; lowered limits apply from the next check", body = QuotaGroup),
+        (status = 400, description = "Invalid limits", body = ErrorResponse),
+        (status = 404, description = "Quota group not found", body = ErrorResponse),
+    )
+)]
+async fn update_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<QuotaGroupRequest>,
+) -> Response {
+    let updated = match group_from_request(req) {
+        Ok(group) => group,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match QuotaGroupOpsGeneric::update(&state.pool, &id, &updated) {
+        Ok(group) => Json(group).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to update quota group: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/quota-groups/{id}",
+    tag = "quotas",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Quota group deleted; its accounts keep only their own limits"),
+        (status = 404, description = "Quota group not found", body = ErrorResponse),
+    )
+)]
+async fn delete_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match QuotaGroupOpsGeneric::delete(&state.pool, &id) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to delete quota group: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/quota-groups/{id}/usage",
+    tag = "quotas",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "Limits of the group and usage summed over its accounts", body = QuotaReport),
+        (status = 404, description = "Quota group not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_group_usage(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let group = match QuotaGroupOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(group) => group,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Quota group not found: {}", e) })).into_response(),
+    };
+
+    match group_report(&state.pool, &group) {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to compute quota usage: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/imap-accounts/{id}/quota",
+    tag = "quotas",
+    params(("id" = String, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Limits and usage of the account and of its quota group", body = AccountQuotaResponse),
+        (status = 404, description = "Account not found", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_account_quota(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    let account = match ImapAccountOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(account) => account,
+        Err(e) => return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Account not found: {}", e) })).into_response(),
+    };
+
+    let report = || -> anyhow::Result<AccountQuotaResponse> {
+        let group = match account.quota_group_id.as_deref() {
+            Some(group_id) => match QuotaGroupOpsGeneric::get_by_id(&state.pool, group_id) {
+                Ok(group) => Some(group_report(&state.pool, &group)?),
+                Err(_) => None,
+            },
+            None => None,
+        };
+        Ok(AccountQuotaResponse {
+            account: QuotaReport {
+                max_feeds: account.max_feeds,
+                max_items: account.max_items,
+                max_processing_minutes_per_day: account.max_processing_minutes_per_day,
+                usage: quota::usage(&state.pool, std::slice::from_ref(&id))?,
+            },
+            group,
+        })
+    };
+
+    match report() {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to compute quota usage: {}", e) })).into_response(),
+    }
+}
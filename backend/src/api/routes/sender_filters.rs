@@ -0,0 +1,185 @@
+use crate::api::{
+    types::{ErrorResponse, SenderFilterRequest},
+    AppState,
+};
+use crate::db::{
+    models::{AccountSenderFilter, NewAccountSenderFilter, SenderFilterKind},
+    operations_generic::{AccountSenderFilterOpsGeneric, ImapAccountOpsGeneric},
+};
+use crate::imap::sender_filters;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/imap-accounts/:id/filters", get(list_filters).post(create_filter))
+        .route(
+            "/api/imap-accounts/:id/filters/:filter_id",
+            get(get_filter).put(update_filter).delete(delete_filter),
+        )
+}
+
+/// Build and validate a filter of `account_id` from a request body
+fn filter_from_request(account_id: String, req: SenderFilterRequest) -> anyhow::Result<NewAccountSenderFilter> {
+    let kind = SenderFilterKind::parse(&req.kind)
+        .ok_or_else(|| anyhow::anyhow!("kind must be 'allow' or 'deny'"))?;
+    let pattern = sender_filters::normalize_pattern(&req.pattern)?;
+    Ok(NewAccountSenderFilter::new(account_id, kind, pattern))
+}
+
+/// The filter `filter_id` when it belongs to the account `account_id`
+fn account_filter(state: &AppState, account_id: &str, filter_id: &str) -> Result<AccountSenderFilter, (StatusCode, String)> {
+    match AccountSenderFilterOpsGeneric::get_by_id(&state.pool, filter_id) {
+        Ok(filter) if filter.imap_account_id == account_id => Ok(filter),
+        Ok(_) => Err((StatusCode::NOT_FOUND, format!("Sender filter {} not found in account {}", filter_id, account_id))),
+        Err(e) => Err((StatusCode::NOT_FOUND, format!("Sender filter not found: {}", e))),
+    }
+}
+
+/// The same pattern on the same list twice is refused by the unique index
+fn save_error(e: anyhow::Error) -> Response {
+    if e.to_string().to_lowercase().contains("unique") {
+        return (StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "The account already has this filter".to_string() })).into_response();
+    }
+    (StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error: format!("Failed to save sender filter: {}", e) })).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/imap-accounts/{id}/filters",
+    tag = "senders",
+    params(("id" = String, Path, description = "Account ID")),
+    responses(
+        (status = 200, description = "Allowed and denied senders of the account", body = [AccountSenderFilter]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_filters(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+) -> Response {
+    match AccountSenderFilterOpsGeneric::get_by_account_id(&state.pool, &account_id) {
+        Ok(filters) => Json(filters).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch sender filters: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/imap-accounts/{id}/filters",
+    tag = "senders",
+    params(("id" = String, Path, description = "Account ID")),
+    request_body = SenderFilterRequest,
+    responses(
+        (status = 201, description = "Filter created", body = AccountSenderFilter),
+        (status = 400, description = "Unknown kind or invalid pattern", body = ErrorResponse),
+        (status = 404, description = "Account not found", body = ErrorResponse),
+        (status = 409, description = "The account already has this filter", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn create_filter(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+    Json(req): Json<SenderFilterRequest>,
+) -> Response {
+    if let Err(e) = ImapAccountOpsGeneric::get_by_id(&state.pool, &account_id) {
+        return (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Account not found: {}", e) })).into_response();
+    }
+    let new_filter = match filter_from_request(account_id, req) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match AccountSenderFilterOpsGeneric::create(&state.pool, &new_filter) {
+        Ok(filter) => (StatusCode::CREATED, Json(filter)).into_response(),
+        Err(e) => save_error(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/imap-accounts/{id}/filters/{filter_id}",
+    tag = "senders",
+    params(("id" = String, Path, description = "Account ID"), ("filter_id" = String, Path, description = "Filter ID")),
+    responses(
+        (status = 200, description = "The filter", body = AccountSenderFilter),
+        (status = 404, description = "Filter not found in the account", body = ErrorResponse),
+    )
+)]
+async fn get_filter(
+    State(state): State<AppState>,
+    Path((account_id, filter_id)): Path<(String, String)>,
+) -> Response {
+    match account_filter(&state, &account_id, &filter_id) {
+        Ok(filter) => Json(filter).into_response(),
+        Err((status, error)) => (status, Json(ErrorResponse { error })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/imap-accounts/{id}/filters/{filter_id}",
+    tag = "senders",
+    params(("id" = String, Path, description = "Account ID"), ("filter_id" = String, Path, description = "Filter ID")),
+    request_body = SenderFilterRequest,
+    responses(
+        (status = 200, description = "Filter updated", body = AccountSenderFilter),
+        (status = 400, description = "Unknown kind or invalid pattern", body = ErrorResponse),
+        (status = 404, description = "Filter not found in the account", body = ErrorResponse),
+        (status = 409, description = "The account already has this filter", body = ErrorResponse),
+    )
+)]
+async fn update_filter(
+    State(state): State<AppState>,
+    Path((account_id, filter_id)): Path<(String, String)>,
+    Json(req): Json<SenderFilterRequest>,
+) -> Response {
+    if let Err((status, error)) = account_filter(&state, &account_id, &filter_id) {
+        return (status, Json(ErrorResponse { error })).into_response();
+    }
+    let updated = match filter_from_request(account_id, req) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match AccountSenderFilterOpsGeneric::update(&state.pool, &filter_id, &updated) {
+        Ok(filter) => Json(filter).into_response(),
+        Err(e) => save_error(e),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/imap-accounts/{id}/filters/{filter_id}",
+    tag = "senders",
+    params(("id" = String, Path, description = "Account ID"), ("filter_id" = String, Path, description = "Filter ID")),
+    responses(
+        (status = 204, description = "Filter deleted"),
+        (status = 404, description = "Filter not found in the account", body = ErrorResponse),
+    )
+)]
+async fn delete_filter(
+    State(state): State<AppState>,
+    Path((account_id, filter_id)): Path<(String, String)>,
+) -> Response {
+    if let Err((status, error)) = account_filter(&state, &account_id, &filter_id) {
+        return (status, Json(ErrorResponse { error })).into_response();
+    }
+    match AccountSenderFilterOpsGeneric::delete(&state.pool, &filter_id) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to delete sender filter: {}", e) })).into_response(),
+    }
+}
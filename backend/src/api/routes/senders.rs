@@ -0,0 +1,203 @@
+use crate::api::{
+    types::{ErrorResponse, SenderAliasRequest, SenderAliasResponse},
+    AppState,
+};
+use crate::db::{
+    connection::DatabasePool,
+    models::NewSenderAlias,
+    operations_generic::SenderAliasOpsGeneric,
+};
+use crate::imap::senders;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/senders/aliases", get(list_aliases).post(create_alias))
+        .route("/api/senders/aliases/:id", get(get_alias).put(update_alias).delete(delete_alias))
+        .route("/api/senders/stats", get(get_sender_stats))
+}
+
+/// Build and validate an alias group from a request body; `alias_id` is the
+/// group being updated, whose own members may be kept
+fn alias_from_request(
+    pool: &DatabasePool,
+    req: SenderAliasRequest,
+    alias_id: Option<&str>,
+) -> anyhow::Result<NewSenderAlias> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        anyhow::bail!("name must not be empty");
+    }
+
+    let mut addresses: Vec<String> = Vec::new();
+    for address in &req.addresses {
+        let member = senders::normalize(address);
+        if member.is_empty() {
+            continue;
+        }
+        if member.contains(char::is_whitespace) || !member.contains('.') {
+            anyhow::bail!("'{}' is not an email address or domain", address.trim());
+        }
+        if !addresses.contains(&member) {
+            addresses.push(member);
+        }
+    }
+    if addresses.is_empty() {
+        anyhow::bail!("addresses must not be empty");
+    }
+
+    for other in SenderAliasOpsGeneric::get_all(pool)? {
+        if other.id.as_deref() == alias_id {
+            continue;
+        }
+        if other.name.eq_ignore_ascii_case(name) {
+            anyhow::bail!("A sender alias named '{}' already exists", other.name);
+        }
+        let other_members: Vec<String> = other.address_list().iter().map(|member| senders::normalize(member)).collect();
+        if let Some(member) = addresses.iter().find(|member| other_members.contains(member)) {
+            anyhow::bail!("'{}' already belongs to sender alias '{}'", member, other.name);
+        }
+    }
+
+    Ok(NewSenderAlias::new(name.to_string(), &addresses))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/senders/aliases",
+    tag = "senders",
+    responses(
+        (status = 200, description = "All sender alias groups, by name", body = [SenderAliasResponse]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn list_aliases(State(state): State<AppState>) -> Response {
+    match SenderAliasOpsGeneric::get_all(&state.pool) {
+        Ok(aliases) => Json(aliases.into_iter().map(SenderAliasResponse::from).collect::<Vec<_>>()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to fetch sender aliases: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/senders/aliases",
+    tag = "senders",
+    request_body = SenderAliasRequest,
+    responses(
+        (status = 201, description = "Sender alias group created", body = SenderAliasResponse),
+        (status = 400, description = "Invalid name or addresses, or an address already in another group", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn create_alias(
+    State(state): State<AppState>,
+    Json(req): Json<SenderAliasRequest>,
+) -> Response {
+    let new_alias = match alias_from_request(&state.pool, req, None) {
+        Ok(alias) => alias,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match SenderAliasOpsGeneric::create(&state.pool, &new_alias) {
+        Ok(alias) => (StatusCode::CREATED, Json(SenderAliasResponse::from(alias))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create sender alias: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/senders/aliases/{id}",
+    tag = "senders",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 200, description = "The sender alias group", body = SenderAliasResponse),
+        (status = 404, description = "Sender alias not found", body = ErrorResponse),
+    )
+)]
+async fn get_alias(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match SenderAliasOpsGeneric::get_by_id(&state.pool, &id) {
+        Ok(alias) => Json(SenderAliasResponse::from(alias)).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Sender alias not found: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/senders/aliases/{id}",
+    tag = "senders",
+    params(("id" = String, Path, description = "Resource ID")),
+    request_body = SenderAliasRequest,
+    responses(
+        (status = 200, description = "Sender alias group updated; rules match the new addresses from the next run", body = SenderAliasResponse),
+        (status = 400, description = "Invalid name or addresses, or an address already in another group", body = ErrorResponse),
+        (status = 404, description = "Sender alias not found", body = ErrorResponse),
+    )
+)]
+async fn update_alias(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<SenderAliasRequest>,
+) -> Response {
+    let updated = match alias_from_request(&state.pool, req, Some(&id)) {
+        Ok(alias) => alias,
+        Err(e) => return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: e.to_string() })).into_response(),
+    };
+
+    match SenderAliasOpsGeneric::update(&state.pool, &id, &updated) {
+        Ok(alias) => Json(SenderAliasResponse::from(alias)).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to update sender alias: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/senders/aliases/{id}",
+    tag = "senders",
+    params(("id" = String, Path, description = "Resource ID")),
+    responses(
+        (status = 204, description = "Sender alias group deleted; its addresses count as separate senders again"),
+        (status = 404, description = "Sender alias not found", body = ErrorResponse),
+    )
+)]
+async fn delete_alias(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    match SenderAliasOpsGeneric::delete(&state.pool, &id) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: format!("Failed to delete sender alias: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/senders/stats",
+    tag = "senders",
+    responses(
+        (status = 200, description = "Items per sender across all feeds, alias groups counted under their name, most items first", body = [SenderStats]),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_sender_stats(State(state): State<AppState>) -> Response {
+    match senders::stats(&state.pool) {
+        Ok(stats) => Json(stats).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to compute sender statistics: {}", e) })).into_response(),
+    }
+}
@@ -0,0 +1,221 @@
+use crate::api::{
+    types::{ErrorResponse, NotificationTestResult, RetentionSettingRequest, RuntimeSetting, SettingsResponse, UpdateSettingsRequest},
+    AppState,
+};
+use crate::background::{self, cleanup::FeedRetention, notifications::{Alert, NotificationSettings}, retention};
+use crate::feed::{fever::{self, FeverCredentials}, public_url};
+use crate::db::{models::RetentionTarget, operations_generic::RetentionPolicyOpsGeneric};
+use crate::settings::{self, SettingKey};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/settings", get(get_settings).put(update_settings))
+        .route("/api/settings/notifications/test", post(test_notifications))
+}
+
+/// The table a retention request names; an error message for unknown
+/// tables or limits below one
+fn retention_target(req: &RetentionSettingRequest) -> Result<RetentionTarget, String> {
+    let target = RetentionTarget::parse(&req.table).ok_or_else(|| {
+        let tables: Vec<&str> = RetentionTarget::ALL.iter().map(RetentionTarget::as_str).collect();
+        format!("Unknown table '{}'; use {}", req.table, tables.join(", "))
+    })?;
+    if req.max_rows.is_some_and(|rows| rows < 1) {
+        return Err(format!("max_rows of {} must be at least 1", target.as_str()));
+    }
+    if req.max_age_days.is_some_and(|days| days < 1) {
+        return Err(format!("max_age_days of {} must be at least 1", target.as_str()));
+    }
+    Ok(target)
+}
+
+/// The settings a request changes with their normalized values, or an error
+/// message for unknown names and values that do not fit
+fn runtime_values(req: &UpdateSettingsRequest) -> Result<Vec<(SettingKey, Option<String>)>, String> {
+    let mut values = Vec::new();
+    for (name, value) in &req.values {
+        let key = SettingKey::parse(name).ok_or_else(|| {
+            let names: Vec<&str> = SettingKey::ALL.iter().map(SettingKey::as_str).collect();
+            format!("Unknown setting '{}'; use {}", name, names.join(", "))
+        })?;
+        values.push((key, value.as_deref().map(|value| key.normalize(value)).transpose()?));
+    }
+    Ok(values)
+}
+
+fn runtime_settings() -> Vec<RuntimeSetting> {
+    SettingKey::ALL.iter().map(|key| {
+        let (value, source) = settings::resolve(*key);
+        RuntimeSetting {
+            key: key.as_str().to_string(),
+            value,
+            source,
+            kind: key.kind(),
+            env_var: key.env_var().to_string(),
+            default: key.default_value(),
+        }
+    }).collect()
+}
+
+/// Hand changed background settings on to the running scheduler
+async fn notify_scheduler(state: &AppState) {
+    let Some(status) = background::get_service_status(&state.background).await else { return };
+    let config = settings::apply_background(status.config);
+    if let Err(e) = background::update_background_config(&state.background, config).await {
+        tracing::error!("Failed to apply the changed settings to the scheduler: {}", e);
+    }
+}
+
+fn settings_response(state: &AppState) -> Response {
+    let loaded = retention::settings(&state.pool)
+        .and_then(|retention| Ok((retention, FeedRetention::load(&state.pool)?, NotificationSettings::load(&state.pool)?)))
+        .and_then(|loaded| Ok((loaded, FeverCredentials::load(&state.pool)?)));
+    match loaded {
+        Ok(((retention, feed_retention, notifications), fever)) => Json(SettingsResponse {
+            retention,
+            public_base_url: public_url::configured(),
+            feed_retention,
+            notifications,
+            fever_username: fever.map(|fever| fever.username),
+            values: runtime_settings(),
+        }).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to load settings: {}", e) })).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/settings",
+    tag = "settings",
+    responses(
+        (status = 200, description = "Current settings", body = SettingsResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_settings(State(state): State<AppState>) -> Response {
+    settings_response(&state)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/settings",
+    tag = "settings",
+    request_body = UpdateSettingsRequest,
+    responses(
+        (status = 200, description = "Settings updated", body = SettingsResponse),
+        (status = 400, description = "Unknown table or setting, limit below one, or invalid public base URL, feed retention, notification settings, Fever user or setting value; nothing was changed", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn update_settings(
+    State(state): State<AppState>,
+    Json(req): Json<UpdateSettingsRequest>,
+) -> Response {
+    let mut updates = Vec::new();
+    for setting in &req.retention {
+        match retention_target(setting) {
+            Ok(target) => updates.push((target, setting.max_rows, setting.max_age_days)),
+            Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+        }
+    }
+    if let Some(url) = req.public_base_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        if let Err(error) = public_url::normalize(url) {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+        }
+    }
+    if let Some(Err(error)) = req.feed_retention.as_ref().map(FeedRetention::validate) {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("Invalid feed retention: {}", error) })).into_response();
+    }
+    if let Some(Err(error)) = req.notifications.as_ref().map(NotificationSettings::validate) {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: format!("Invalid notification settings: {}", error) })).into_response();
+    }
+    if let Some(Err(error)) = req.fever.as_ref().map(|user| fever::validate(&user.username, &user.password)) {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+    }
+    let values = match runtime_values(&req) {
+        Ok(values) => values,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response(),
+    };
+
+    for (target, max_rows, max_age_days) in updates {
+        if let Err(e) = RetentionPolicyOpsGeneric::upsert(&state.pool, target, max_rows, max_age_days) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update retention of {}: {}", target.as_str(), e) })).into_response();
+        }
+    }
+    if let Some(url) = &req.public_base_url {
+        if let Err(e) = public_url::store(&state.pool, url) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update the public base URL: {}", e) })).into_response();
+        }
+    }
+    if let Some(feed_retention) = &req.feed_retention {
+        if let Err(e) = feed_retention.store(&state.pool) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update the feed retention: {}", e) })).into_response();
+        }
+    }
+    if let Some(notifications) = &req.notifications {
+        if let Err(e) = notifications.store(&state.pool) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update the notification settings: {}", e) })).into_response();
+        }
+    }
+    if let Some(user) = &req.fever {
+        if let Err(e) = FeverCredentials::store(&state.pool, &user.username, &user.password) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update the Fever user: {}", e) })).into_response();
+        }
+    }
+    for (key, value) in &values {
+        if let Err(e) = settings::store(&state.pool, *key, value.as_deref()) {
+            return (StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: format!("Failed to update {}: {}", key.as_str(), e) })).into_response();
+        }
+    }
+    if values.iter().any(|(key, _)| key.affects_scheduler()) {
+        notify_scheduler(&state).await;
+    }
+    settings_response(&state)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/settings/notifications/test",
+    tag = "settings",
+    responses(
+        (status = 200, description = "A test alert was sent on every channel; the outcome of each", body = [NotificationTestResult]),
+        (status = 400, description = "No notification channels are configured", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn test_notifications(State(state): State<AppState>) -> Response {
+    let settings = match NotificationSettings::load(&state.pool) {
+        Ok(settings) => settings,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to load the notification settings: {}", e) })).into_response(),
+    };
+    if settings.channels.is_empty() {
+        return (StatusCode::BAD_REQUEST,
+            Json(ErrorResponse { error: "No notification channels are configured".to_string() })).into_response();
+    }
+
+    let outcomes = settings.send(&Alert::test()).await;
+    let results: Vec<NotificationTestResult> = settings.channels.iter().zip(outcomes).enumerate()
+        .map(|(index, (channel, outcome))| NotificationTestResult {
+            channel: index + 1,
+            kind: channel.kind,
+            delivered: outcome.is_ok(),
+            error: outcome.err().map(|e| format!("{:#}", e)),
+        })
+        .collect();
+    Json(results).into_response()
+}
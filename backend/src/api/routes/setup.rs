@@ -0,0 +1,373 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use super::health::check_database;
+use super::imap_accounts::{new_account, requested_security, validate_new_account};
+use crate::api::{
+    types::{
+        DependencyStatus, ErrorResponse, InitialSetupRequest, InitialSetupResponse, SetupConnectionRequest,
+        SetupConnectionResponse, SetupDatabaseStatus, SetupFeedRequest, SetupFinalizeRequest, SetupFinalizeResponse,
+        SetupStatusResponse,
+    },
+    AppState,
+};
+use crate::background::quota::{self, QuotaExceeded, QuotaResource};
+use crate::db::{
+    connection::DatabasePool,
+    models::{AccountProtocol, ImapAccount, NewEmailRule, NewFeed, NewImapAccount},
+    operations_generic::ImapAccountOpsGeneric,
+};
+use crate::feed::template;
+use crate::imap::{fingerprint, server_name, setup, tls_pin::TlsPin, ImapClient};
+use crate::jmap::JmapClient;
+use crate::setup::{self as first_run, AdminUser};
+
+/// Held while an initial setup runs, so two at once cannot both succeed
+static INITIAL_SETUP: Mutex<()> = Mutex::const_new(());
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/setup", get(setup_status).post(initial_setup))
+        .route("/api/setup/validate-connection", post(validate_connection))
+        .route("/api/setup/suggest-folders", post(suggest_folders))
+        .route("/api/setup/finalize", post(finalize))
+}
+
+pub(super) fn validate_connection_request(req: &SetupConnectionRequest) -> Option<Response> {
+    let use_tls = match requested_security(req.security.as_deref(), req.port, req.use_tls) {
+        Ok(security) => security.uses_tls(),
+        Err(error) => return Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response()),
+    };
+    let error = match (&req.tls_pin, &req.tls_server_name) {
+        (Some(_), _) if !use_tls => "tls_pin requires use_tls".to_string(),
+        (_, Some(_)) if !use_tls => "tls_server_name requires use_tls".to_string(),
+        (pin, name) => pin.as_deref().map_or(Ok(()), |pin| TlsPin::parse(pin).map(drop))
+            .and_then(|()| name.as_deref().map_or(Ok(()), server_name::validate))
+            .err()?.to_string(),
+    };
+    Some((StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response())
+}
+
+/// A rule and a feed for each requested folder, or why one is invalid
+fn rules_and_feeds(account: &ImapAccount, feeds: Vec<SetupFeedRequest>) -> Result<(Vec<NewEmailRule>, Vec<NewFeed>), String> {
+    let mut new_rules = Vec::new();
+    let mut new_feeds = Vec::new();
+    for feed in feeds {
+        let folder = feed.folder.trim().to_string();
+        if folder.is_empty() {
+            return Err("folder must not be empty".to_string());
+        }
+        let title = feed.title.filter(|title| !title.trim().is_empty()).unwrap_or_else(|| folder.clone());
+        template::validate(&title).map_err(|e| e.to_string())?;
+
+        let rule = NewEmailRule::from_account_defaults(
+            title.clone(),
+            account,
+            folder,
+            feed.to_address,
+            feed.from_address,
+            feed.subject_contains,
+            None,
+            true,
+        );
+        new_feeds.push(NewFeed::with_retention(title, None, None, rule.id.clone(), feed.feed_type, true, None, None, None));
+        new_rules.push(rule);
+    }
+    Ok((new_rules, new_feeds))
+}
+
+/// Create the account with its rules and feeds, after checking the feed quota
+async fn create_account(state: &AppState, new_account: &NewImapAccount, new_rules: &[NewEmailRule], new_feeds: &[NewFeed]) -> Result<SetupFinalizeResponse, Response> {
+    let account = new_account.to_account();
+    if let Err(e) = quota::check_room(&state.pool, &account, QuotaResource::Feeds, new_feeds.len() as i64) {
+        let status = if e.is::<QuotaExceeded>() { StatusCode::FORBIDDEN } else { StatusCode::INTERNAL_SERVER_ERROR };
+        return Err((status, Json(ErrorResponse { error: e.to_string() })).into_response());
+    }
+
+    match ImapAccountOpsGeneric::create_with_feeds(&state.pool, new_account, new_rules, new_feeds) {
+        Ok((account, rules, feeds)) => {
+            for rule in &rules {
+                state.background.controller.rule_changed(rule).await;
+            }
+            Ok(SetupFinalizeResponse { account, rules, feeds })
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create account: {}", e) })).into_response()),
+    }
+}
+
+/// Client for the settings of an account that is not saved yet
+pub(super) fn client_for(req: &SetupConnectionRequest) -> anyhow::Result<ImapClient> {
+    let mut account = NewImapAccount::new(
+        format!("{}@{}", req.username, req.host),
+        req.host.clone(),
+        req.port,
+        req.username.clone(),
+        req.password.clone(),
+        req.use_tls,
+    );
+    if let Ok(security) = requested_security(req.security.as_deref(), req.port, req.use_tls) {
+        account.set_security(security);
+    }
+    account.tls_pin = req.tls_pin.clone();
+    account.tls_server_name = req.tls_server_name.clone();
+    ImapClient::new(&account.to_account())
+}
+
+// Probe a server before the account is saved
+#[utoipa::path(
+    post,
+    path = "/api/setup/validate-connection",
+    tag = "setup",
+    request_body = SetupConnectionRequest,
+    responses(
+        (status = 200, description = "Probe result; on failure `failed_step` tells which step to fix", body = SetupConnectionResponse),
+        (status = 400, description = "Invalid TLS pin", body = ErrorResponse),
+    )
+)]
+async fn validate_connection(
+    State(state): State<AppState>,
+    Json(req): Json<SetupConnectionRequest>,
+) -> Response {
+    if let Some(response) = validate_connection_request(&req) {
+        return response;
+    }
+    let client = match client_for(&req) {
+        Ok(client) => client,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create IMAP client: {}", e) })).into_response(),
+    };
+
+    let response = match client.probe().await {
+        Ok(probe) => {
+            info!("Setup probe of {}:{} succeeded", req.host, req.port);
+            let duplicate_of = ImapAccountOpsGeneric::get_all(&state.pool)
+                .map_err(|e| warn!("Could not check for duplicate accounts: {}", e))
+                .ok()
+                .and_then(|accounts| fingerprint::find_same_login(&accounts, &req.host, &req.username).and_then(|account| account.id.clone()));
+            SetupConnectionResponse {
+                success: true,
+                failed_step: None,
+                message: format!("Logged in to {} as {}", req.host, req.username),
+                greeting: Some(probe.greeting),
+                capabilities: probe.capabilities,
+                duplicate_of,
+            }
+        }
+        Err(e) => {
+            warn!("Setup probe of {}:{} failed: {}", req.host, req.port, e);
+            SetupConnectionResponse {
+                success: false,
+                failed_step: Some(setup::failed_step(&e).to_string()),
+                message: e.to_string(),
+                greeting: None,
+                capabilities: Vec::new(),
+                duplicate_of: None,
+            }
+        }
+    };
+    Json(response).into_response()
+}
+
+// Suggest folders to turn into feeds
+#[utoipa::path(
+    post,
+    path = "/api/setup/suggest-folders",
+    tag = "setup",
+    request_body = SetupConnectionRequest,
+    responses(
+        (status = 200, description = "Folders with message counts and newsletter scores, most newsletter-like first", body = [FolderSuggestion]),
+        (status = 400, description = "Invalid TLS pin", body = ErrorResponse),
+        (status = 502, description = "Connecting to the server or listing its folders failed", body = ErrorResponse),
+    )
+)]
+async fn suggest_folders(Json(req): Json<SetupConnectionRequest>) -> Response {
+    if let Some(response) = validate_connection_request(&req) {
+        return response;
+    }
+    let client = match client_for(&req) {
+        Ok(client) => client,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to create IMAP client: {}", e) })).into_response(),
+    };
+
+    match client.sample_folders(setup::SAMPLE_SIZE).await {
+        Ok(samples) => Json(setup::suggest(&samples)).into_response(),
+        Err(e) => (StatusCode::BAD_GATEWAY,
+            Json(ErrorResponse { error: format!("Failed to read folders: {}", e) })).into_response(),
+    }
+}
+
+// Create the account with a rule and a feed per folder
+#[utoipa::path(
+    post,
+    path = "/api/setup/finalize",
+    tag = "setup",
+    request_body = SetupFinalizeRequest,
+    responses(
+        (status = 201, description = "Account, rules and feeds created together", body = SetupFinalizeResponse),
+        (status = 400, description = "Invalid account or feed settings; nothing was created", body = ErrorResponse),
+        (status = 403, description = "The feeds exceed the account's or its quota group's feed quota", body = ErrorResponse),
+        (status = 409, description = "An account with the same host and username exists", body = DuplicateAccountResponse),
+        (status = 500, description = "Database error; nothing was created", body = ErrorResponse),
+    )
+)]
+async fn finalize(
+    State(state): State<AppState>,
+    Json(req): Json<SetupFinalizeRequest>,
+) -> Response {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+    if req.feeds.is_empty() {
+        return bad_request("feeds must not be empty".to_string());
+    }
+    if let Some(response) = validate_new_account(&state.pool, &req.account) {
+        return response;
+    }
+    let new_account = new_account(req.account);
+    let (new_rules, new_feeds) = match rules_and_feeds(&new_account.to_account(), req.feeds) {
+        Ok(rules_and_feeds) => rules_and_feeds,
+        Err(error) => return bad_request(error),
+    };
+
+    match create_account(&state, &new_account, &new_rules, &new_feeds).await {
+        Ok(created) => (StatusCode::CREATED, Json(created)).into_response(),
+        Err(response) => response,
+    }
+}
+
+fn database_status(pool: &DatabasePool) -> SetupDatabaseStatus {
+    let backend = match pool {
+        DatabasePool::SQLite(_) => "sqlite",
+        #[cfg(feature = "postgres")]
+        DatabasePool::PostgreSQL(_) => "postgres",
+    };
+    SetupDatabaseStatus { backend: backend.to_string(), check: check_database(pool) }
+}
+
+// Report whether the instance still needs its first-run setup
+#[utoipa::path(
+    get,
+    path = "/api/setup",
+    tag = "setup",
+    responses(
+        (status = 200, description = "Whether the instance is initialized, and the database check", body = SetupStatusResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn setup_status(State(state): State<AppState>) -> Response {
+    let database = database_status(&state.pool);
+    let status = AdminUser::load(&state.pool)
+        .and_then(|admin| Ok((admin, ImapAccountOpsGeneric::get_all(&state.pool)?.len())));
+    match status {
+        Ok((admin, account_count)) => Json(SetupStatusResponse {
+            initialized: admin.is_some() || account_count > 0,
+            has_admin: admin.is_some(),
+            account_count,
+            database,
+        }).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to read the setup state: {}", e) })).into_response(),
+    }
+}
+
+/// Connect to the new account's server as its runs will, or describe why that failed
+async fn validate_live(account: &ImapAccount) -> Result<(), SetupConnectionResponse> {
+    let failed = |failed_step: &str, e: anyhow::Error| SetupConnectionResponse {
+        success: false,
+        failed_step: Some(failed_step.to_string()),
+        message: format!("{:#}", e),
+        greeting: None,
+        capabilities: Vec::new(),
+        duplicate_of: None,
+    };
+    match account.account_protocol() {
+        AccountProtocol::Imap => {
+            let client = ImapClient::new(account).map_err(|e| failed("protocol", e))?;
+            client.probe().await.map(drop).map_err(|e| failed(setup::failed_step(&e), e))
+        }
+        AccountProtocol::Jmap => JmapClient::connect(account).await.map(drop).map_err(|e| failed("connect", e)),
+        // Mail is delivered to the listener; there is no server to reach
+        AccountProtocol::Smtp => Ok(()),
+    }
+}
+
+// Initialize a fresh instance in one step
+#[utoipa::path(
+    post,
+    path = "/api/setup",
+    tag = "setup",
+    request_body = InitialSetupRequest,
+    responses(
+        (status = 201, description = "Admin user, account, rules and feeds created", body = InitialSetupResponse),
+        (status = 400, description = "Invalid admin, account or feed settings; nothing was created", body = ErrorResponse),
+        (status = 409, description = "The instance is already initialized", body = ErrorResponse),
+        (status = 422, description = "The account's server could not be reached or refused the login; nothing was created", body = SetupConnectionResponse),
+        (status = 503, description = "The database check failed", body = SetupDatabaseStatus),
+    )
+)]
+async fn initial_setup(
+    State(state): State<AppState>,
+    Json(req): Json<InitialSetupRequest>,
+) -> Response {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error })).into_response();
+    let _running = INITIAL_SETUP.lock().await;
+
+    let database = database_status(&state.pool);
+    if database.check.status != DependencyStatus::Up {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(database)).into_response();
+    }
+    match first_run::is_initialized(&state.pool) {
+        Ok(false) => {}
+        Ok(true) => return (StatusCode::CONFLICT,
+            Json(ErrorResponse { error: "The instance is already initialized".to_string() })).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to read the setup state: {}", e) })).into_response(),
+    }
+
+    if let Err(error) = first_run::validate_admin(&req.admin.username, &req.admin.password) {
+        return bad_request(error);
+    }
+    if let Some(response) = validate_new_account(&state.pool, &req.account) {
+        return response;
+    }
+    let new_account = new_account(req.account);
+    let account = new_account.to_account();
+    let (new_rules, new_feeds) = match rules_and_feeds(&account, req.feeds) {
+        Ok(rules_and_feeds) => rules_and_feeds,
+        Err(error) => return bad_request(error),
+    };
+    let admin = match AdminUser::new(&req.admin.username, &req.admin.password) {
+        Ok(admin) => admin,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to hash the admin password: {}", e) })).into_response(),
+    };
+
+    if let Err(probe) = validate_live(&account).await {
+        warn!("Initial setup could not reach {}: {}", account.host, probe.message);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(probe)).into_response();
+    }
+
+    let created = match create_account(&state, &new_account, &new_rules, &new_feeds).await {
+        Ok(created) => created,
+        Err(response) => return response,
+    };
+    if let Err(e) = admin.store(&state.pool) {
+        return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Created account '{}' but failed to store the admin user: {}", created.account.name, e) })).into_response();
+    }
+    info!("Initialized the instance with admin '{}' and account '{}'", admin.username, created.account.name);
+
+    (StatusCode::CREATED, Json(InitialSetupResponse {
+        admin_username: admin.username,
+        account: created.account,
+        rules: created.rules,
+        feeds: created.feeds,
+    })).into_response()
+}
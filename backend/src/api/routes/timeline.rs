@@ -0,0 +1,101 @@
+use crate::api::{
+    types::{ErrorResponse, TimelineItem, TimelineQuery, TimelineResponse},
+    AppState,
+};
+use crate::db::{
+    models::{FeedItem, TimelineFilter},
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+/// Items per page when no limit is given
+const DEFAULT_TIMELINE_LIMIT: i64 = 50;
+const MAX_TIMELINE_LIMIT: i64 = 200;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/timeline", get(get_timeline))
+}
+
+/// Cursor pointing after `item`; pages are keyed on the sort order
+/// `(pub_date, id)`, so they neither skip nor repeat items when new ones arrive
+fn cursor_after(item: &FeedItem) -> String {
+    format!("{}|{}", item.pub_date, item.id.as_deref().unwrap_or_default())
+}
+
+fn parse_cursor(cursor: &str) -> Option<(String, String)> {
+    let (pub_date, id) = cursor.rsplit_once('|')?;
+    if pub_date.is_empty() || id.is_empty() {
+        return None;
+    }
+    Some((pub_date.to_string(), id.to_string()))
+}
+
+fn id_list(ids: &str) -> Vec<String> {
+    ids.split(',').map(str::trim).filter(|id| !id.is_empty()).map(String::from).collect()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/timeline",
+    tag = "feeds",
+    params(TimelineQuery),
+    responses(
+        (status = 200, description = "Items across feeds, newest first, with the cursor of the next page", body = TimelineResponse),
+        (status = 400, description = "Invalid limit or cursor", body = ErrorResponse),
+        (status = 500, description = "Database error", body = ErrorResponse),
+    )
+)]
+async fn get_timeline(
+    State(state): State<AppState>,
+    Query(query): Query<TimelineQuery>,
+) -> Response {
+    let bad_request = |error: &str| (StatusCode::BAD_REQUEST,
+        Json(ErrorResponse { error: error.to_string() })).into_response();
+
+    let limit = query.limit.unwrap_or(DEFAULT_TIMELINE_LIMIT);
+    if !(1..=MAX_TIMELINE_LIMIT).contains(&limit) {
+        return bad_request(&format!("limit must be between 1 and {}", MAX_TIMELINE_LIMIT));
+    }
+    let before = match query.cursor.as_deref().map(parse_cursor) {
+        Some(None) => return bad_request("cursor is not a next_cursor returned by the timeline"),
+        Some(cursor) => cursor,
+        None => None,
+    };
+
+    // One extra item tells whether there is a next page
+    let filter = TimelineFilter {
+        feed_ids: query.feeds.as_deref().map(id_list),
+        exclude_feed_ids: query.exclude_feeds.as_deref().map(id_list).unwrap_or_default(),
+        unread_only: query.unread_only,
+        before,
+        limit: limit + 1,
+    };
+    let (mut items, feeds) = match (FeedItemOpsGeneric::get_timeline(&state.pool, &filter), FeedOpsGeneric::get_all(&state.pool)) {
+        (Ok(items), Ok(feeds)) => (items, feeds),
+        (Err(e), _) | (_, Err(e)) => return (StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: format!("Failed to load timeline: {}", e) })).into_response(),
+    };
+
+    let has_more = items.len() as i64 > limit;
+    items.truncate(limit as usize);
+    let next_cursor = if has_more { items.last().map(cursor_after) } else { None };
+
+    let items = items
+        .into_iter()
+        .map(|item| {
+            let feed_title = feeds.iter()
+                .find(|feed| feed.id.as_deref() == Some(item.feed_id.as_str()))
+                .map(|feed| feed.title.clone())
+                .unwrap_or_default();
+            TimelineItem { item, feed_title }
+        })
+        .collect();
+
+    Json(TimelineResponse { items, next_cursor }).into_response()
+}
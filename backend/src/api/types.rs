@@ -0,0 +1,1371 @@
+//! Request and response bodies of the HTTP API
+//!
+//! Shared by the route handlers and the typed client (`client` feature) so
+//! both sides of the API serialize exactly the same shapes. Resources that are
+//! returned as stored (accounts, rules, feeds, items, processing runs) use the
+//! models in `crate::db::models` directly.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::db::models::{EmailRule, Feed, FeedItem, ImapAccount, Job, SenderAlias};
+
+pub use crate::background::cleanup::{CleanupResult, FeedCleanup, FeedRetention};
+pub use crate::background::config::BackgroundConfig;
+pub use crate::background::notifications::{ChannelKind, NotificationChannel, NotificationSettings, SmtpSecurity};
+pub use crate::background::quota::QuotaUsage;
+pub use crate::background::retention::RetentionSetting;
+pub use crate::background::rollback::RollbackResult;
+pub use crate::background::service::{ServiceState, ServiceStatus};
+pub use crate::background::storage::{Safeguard, StorageStatus};
+pub use crate::settings::{SettingKind, SettingSource};
+pub use crate::background::tasks::{TaskState, TaskStatus};
+pub use crate::db::models::JobStatus;
+pub use crate::feed::chain::ChainVerification;
+pub use crate::feed::export::{ExportFormat, ExportResult};
+pub use crate::feed::forecast::{FeedForecast, StorageForecast};
+pub use crate::feed::ratings::{RatingReport, RuleRating, RuleSuggestion, SenderRating};
+pub use crate::imap::diagnostics::{ConnectionDiagnostics, DiagnosticStep, StepStatus};
+pub use crate::imap::folders::FolderNode;
+pub use crate::imap::post_process::PostProcessStep;
+pub use crate::imap::rule_costs::{FolderCostSummary, RuleCostReport, RuleCostSummary};
+pub use crate::imap::senders::SenderStats;
+pub use crate::stats::{AccountStats, DayCount, FeedStats, Stats, TopSender};
+pub use crate::imap::setup::FolderSuggestion;
+pub use crate::import::{ImportFormat, ImportMapping, MatchOn};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+// Health
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: String,
+    pub version: String,
+    pub timestamp: String,
+    pub database: String,
+}
+
+/// The process is up and serving requests
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LivenessResponse {
+    pub status: String,
+    pub timestamp: String,
+}
+
+/// Whether the instance can do its work, with the checks that decided it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessResponse {
+    /// False when a critical dependency is down; the endpoint then answers 503
+    pub ready: bool,
+    pub version: String,
+    pub timestamp: String,
+    pub database: DependencyCheck,
+    pub background: BackgroundCheck,
+    /// Last completed processing run of each account; informational, stale
+    /// accounts do not make the instance unready
+    pub accounts: Vec<AccountFreshness>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+}
+
+/// Outcome of checking a dependency
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DependencyCheck {
+    pub status: DependencyStatus,
+    /// How long the check took
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// State of the background service; only an errored service is critical,
+/// since a stopped or paused one was stopped on purpose
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackgroundCheck {
+    pub state: ServiceState,
+    pub is_paused: bool,
+    pub accounts_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountFreshness {
+    pub account_id: String,
+    pub name: String,
+    /// When the account's last processing run completed
+    pub last_success: Option<String>,
+    pub last_success_age_seconds: Option<i64>,
+}
+
+// IMAP accounts
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateImapAccountRequest {
+    pub name: String,
+    pub host: String,
+    pub port: i32,
+    pub username: String,
+    pub password: String,
+    /// Whether to use TLS; implied by `security` when that is given
+    #[serde(default)]
+    pub use_tls: bool,
+    /// `none`, `starttls` or `ssl_tls` (implicit TLS); when omitted it is
+    /// suggested from `use_tls` and the port, with implicit TLS on 993
+    pub security: Option<String>,
+    #[serde(default = "default_post_process_action")]
+    pub default_post_process_action: String,
+    pub default_move_to_folder: Option<String>,
+    /// Bandwidth limit for this account's IMAP connections; omit for unlimited
+    pub max_bytes_per_second: Option<i32>,
+    /// Pinned server certificate (`cert-sha256:<hex>`) or public key
+    /// (`pubkey-sha256:<hex>`) fingerprint; requires TLS
+    pub tls_pin: Option<String>,
+    /// Name sent as SNI and verified against the certificate instead of
+    /// `host`; requires TLS
+    pub tls_server_name: Option<String>,
+    /// Quota group whose limits the account shares
+    pub quota_group_id: Option<String>,
+    /// Feeds allowed on the account's rules; omit for unlimited
+    pub max_feeds: Option<i32>,
+    /// Items allowed across the account's feeds; omit for unlimited
+    pub max_items: Option<i32>,
+    /// Minutes of processing allowed per UTC day; omit for unlimited
+    pub max_processing_minutes_per_day: Option<i32>,
+    /// Connections allowed to the server in any hour; omit for unlimited
+    pub max_connections_per_hour: Option<i32>,
+    /// Seconds to wait after a fetch before starting the next one
+    pub min_fetch_delay_seconds: Option<i32>,
+    /// Most messages fetched from a folder at once; omit to use the rules' fetch limits
+    pub max_messages_per_fetch: Option<i32>,
+    /// `imap` (default), `jmap` for a JMAP server at the host and port, or
+    /// `smtp` for mail delivered to the SMTP listener instead of fetched; the
+    /// connection settings are unused then
+    pub protocol: Option<String>,
+    /// Create the account even if one with the same host and username exists
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+/// Returned with 409 when an account for the same mailbox already exists
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DuplicateAccountResponse {
+    pub error: String,
+    /// ID of the existing account
+    pub duplicate_of: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateImapAccountRequest {
+    pub name: String,
+    pub host: String,
+    pub port: i32,
+    pub username: String,
+    pub password: String,
+    /// Whether to use TLS; implied by `security` when that is given
+    #[serde(default)]
+    pub use_tls: bool,
+    /// `none`, `starttls` or `ssl_tls` (implicit TLS); when omitted it is
+    /// suggested from `use_tls` and the port, with implicit TLS on 993
+    pub security: Option<String>,
+    #[serde(default = "default_post_process_action")]
+    pub default_post_process_action: String,
+    pub default_move_to_folder: Option<String>,
+    /// Bandwidth limit for this account's IMAP connections; omit for unlimited
+    pub max_bytes_per_second: Option<i32>,
+    /// Pinned server certificate (`cert-sha256:<hex>`) or public key
+    /// (`pubkey-sha256:<hex>`) fingerprint; requires TLS
+    pub tls_pin: Option<String>,
+    /// Name sent as SNI and verified against the certificate instead of
+    /// `host`; requires TLS
+    pub tls_server_name: Option<String>,
+    /// Quota group whose limits the account shares
+    pub quota_group_id: Option<String>,
+    /// Feeds allowed on the account's rules; omit for unlimited
+    pub max_feeds: Option<i32>,
+    /// Items allowed across the account's feeds; omit for unlimited
+    pub max_items: Option<i32>,
+    /// Minutes of processing allowed per UTC day; omit for unlimited
+    pub max_processing_minutes_per_day: Option<i32>,
+    /// Connections allowed to the server in any hour; omit for unlimited
+    pub max_connections_per_hour: Option<i32>,
+    /// Seconds to wait after a fetch before starting the next one
+    pub min_fetch_delay_seconds: Option<i32>,
+    /// Most messages fetched from a folder at once; omit to use the rules' fetch limits
+    pub max_messages_per_fetch: Option<i32>,
+    /// `imap` (default), `jmap` for a JMAP server at the host and port, or
+    /// `smtp` for mail delivered to the SMTP listener instead of fetched; the
+    /// connection settings are unused then
+    pub protocol: Option<String>,
+}
+
+fn default_post_process_action() -> String {
+    "mark_read".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// Email rules
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateEmailRuleRequest {
+    pub name: String,
+    pub imap_account_id: String,
+    pub folder: String,
+    pub to_address: Option<String>,
+    pub from_address: Option<String>,
+    pub subject_contains: Option<String>,
+    pub label: Option<String>,
+    pub is_active: bool,
+    pub post_process_action: Option<String>, // Optional - inherits from account if not provided
+    pub move_to_folder: Option<String>,
+    #[serde(default)]
+    pub inherit_account_defaults: bool, // If true, ignore post_process_action and move_to_folder
+    /// Only record matches for preview; no feed items are created and emails are left untouched
+    #[serde(default)]
+    pub observe_only: bool,
+    /// Only match emails of this importance: `high`, `normal` or `low`
+    #[serde(default)]
+    pub importance: Option<String>,
+    /// `newest_first` (default) or `oldest_first`, e.g. for backfills
+    #[serde(default)]
+    pub processing_order: Option<String>,
+    /// Hours to leave matched mail untouched before applying the post-process action
+    #[serde(default)]
+    pub post_process_delay_hours: Option<i32>,
+    /// Advanced matching: regex patterns on `from`, `to`, `subject` or `body`
+    /// combined with `all`, `any` and `not`, checked along with the other criteria
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub match_expression: Option<serde_json::Value>,
+    /// Emails fetched from the folder per run, at most 1000; omitted for the default of 100
+    #[serde(default)]
+    pub fetch_limit: Option<i32>,
+    /// Fetch emails already marked seen too (default); off fetches only unseen mail
+    #[serde(default = "default_true")]
+    pub include_seen: bool,
+    /// Process the folder's subfolders as well
+    #[serde(default)]
+    pub include_subfolders: bool,
+    /// Remove newsletter footers (unsubscribe and preference blocks, signatures)
+    #[serde(default)]
+    pub strip_footers: bool,
+    /// Remove quoted replies (`>` lines, "On ... wrote:" and forwarded originals)
+    #[serde(default)]
+    pub strip_quoted_replies: bool,
+    /// Remove 1x1 and open-tracking images (default)
+    #[serde(default = "default_true")]
+    pub strip_tracking_pixels: bool,
+    /// Rewrite click-tracking redirect links to their destination, without `utm_*` parameters
+    #[serde(default)]
+    pub unwrap_tracking_links: bool,
+    /// Actions applied in order to the emails turned into items, each after
+    /// its own delay; replaces `post_process_action`, `move_to_folder` and
+    /// `post_process_delay_hours` when given
+    #[serde(default)]
+    pub post_process_actions: Option<Vec<PostProcessStep>>,
+}
+
+/// The feed created along with a rule
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuleFeedRequest {
+    /// Defaults to the rule's name
+    pub title: Option<String>,
+    pub description: Option<String>,
+    #[serde(default = "default_feed_type")]
+    pub feed_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateRuleWithFeedRequest {
+    pub rule: CreateEmailRuleRequest,
+    pub feed: RuleFeedRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuleWithFeedResponse {
+    pub rule: EmailRule,
+    pub feed: Feed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateEmailRuleRequest {
+    pub name: String,
+    pub imap_account_id: String,
+    pub folder: String,
+    pub to_address: Option<String>,
+    pub from_address: Option<String>,
+    pub subject_contains: Option<String>,
+    pub label: Option<String>,
+    pub is_active: bool,
+    pub post_process_action: Option<String>, // Optional - inherits from account if not provided
+    pub move_to_folder: Option<String>,
+    #[serde(default)]
+    pub inherit_account_defaults: bool, // If true, ignore post_process_action and move_to_folder
+    /// Only record matches for preview; no feed items are created and emails are left untouched
+    #[serde(default)]
+    pub observe_only: bool,
+    /// Only match emails of this importance: `high`, `normal` or `low`
+    #[serde(default)]
+    pub importance: Option<String>,
+    /// `newest_first` (default) or `oldest_first`, e.g. for backfills
+    #[serde(default)]
+    pub processing_order: Option<String>,
+    /// Hours to leave matched mail untouched before applying the post-process action
+    #[serde(default)]
+    pub post_process_delay_hours: Option<i32>,
+    /// Advanced matching: regex patterns on `from`, `to`, `subject` or `body`
+    /// combined with `all`, `any` and `not`, checked along with the other criteria
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub match_expression: Option<serde_json::Value>,
+    /// Emails fetched from the folder per run, at most 1000; omitted for the default of 100
+    #[serde(default)]
+    pub fetch_limit: Option<i32>,
+    /// Fetch emails already marked seen too (default); off fetches only unseen mail
+    #[serde(default = "default_true")]
+    pub include_seen: bool,
+    /// Process the folder's subfolders as well
+    #[serde(default)]
+    pub include_subfolders: bool,
+    /// Remove newsletter footers (unsubscribe and preference blocks, signatures)
+    #[serde(default)]
+    pub strip_footers: bool,
+    /// Remove quoted replies (`>` lines, "On ... wrote:" and forwarded originals)
+    #[serde(default)]
+    pub strip_quoted_replies: bool,
+    /// Remove 1x1 and open-tracking images (default)
+    #[serde(default = "default_true")]
+    pub strip_tracking_pixels: bool,
+    /// Rewrite click-tracking redirect links to their destination, without `utm_*` parameters
+    #[serde(default)]
+    pub unwrap_tracking_links: bool,
+    /// Actions applied in order to the emails turned into items, each after
+    /// its own delay; replaces `post_process_action`, `move_to_folder` and
+    /// `post_process_delay_hours` when given
+    #[serde(default)]
+    pub post_process_actions: Option<Vec<PostProcessStep>>,
+}
+
+/// Email to try a match expression on
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SampleEmail {
+    #[serde(default)]
+    pub from: String,
+    #[serde(default)]
+    pub to: String,
+    #[serde(default)]
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ValidateMatchExpressionRequest {
+    #[schema(value_type = Object)]
+    pub match_expression: serde_json::Value,
+    /// Email to check the expression against
+    pub sample: Option<SampleEmail>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MatchExpressionValidation {
+    /// The expression as it would be stored
+    pub match_expression: String,
+    /// Whether the sample matches; null without a sample
+    pub matches: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuleStatsResponse {
+    pub rule_id: String,
+    pub observe_only: bool,
+    /// Distinct emails matched while the rule was observe-only
+    pub match_count: i64,
+    pub last_matched_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RulePreviewQuery {
+    /// Most recent matches to return; defaults to 50
+    pub limit: Option<i64>,
+}
+
+// Feeds and feed items
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateFeedRequest {
+    /// May reference `{{rule.name}}`, `{{rule.folder}}`, `{{rule.label}}`, `{{account.name}}` and `{{account.host}}`
+    pub title: String,
+    /// May use the same variables as `title`
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub email_rule_id: String,
+    pub feed_type: String,
+    pub is_active: bool,
+    pub max_items: Option<i32>,
+    pub max_age_days: Option<i32>,
+    pub min_items: Option<i32>,
+    pub summary_length: Option<i32>,
+    /// Locale for display dates in items, e.g. `de_DE`; omit for POSIX
+    pub locale: Option<String>,
+    /// IANA timezone for display dates in items; omit for UTC
+    pub timezone: Option<String>,
+    /// Item title template such as `{{subject}} — {{from_name}}`; see the README for the placeholders
+    pub title_template: Option<String>,
+    /// Item description template such as `<p>{{folder}}</p>{{summary}}`; omit to use the email content
+    pub description_template: Option<String>,
+    /// Most items in the RSS and Atom documents; omit to use `FEED_ITEM_LIMIT`
+    pub item_limit: Option<i32>,
+    /// Include each item's full HTML body, or only its summary of `summary_length` characters; omit for the full body
+    pub full_content: Option<bool>,
+    /// Username readers must send with HTTP Basic auth to get the RSS and Atom documents; omit to leave them open
+    pub auth_username: Option<String>,
+    /// Password that goes with `auth_username`, stored hashed
+    pub auth_password: Option<String>,
+    /// URL called for each new item, e.g. a Slack or Discord incoming webhook
+    pub webhook_url: Option<String>,
+    /// `POST` (default), `PUT` or `PATCH`
+    pub webhook_method: Option<String>,
+    /// JSON body template using `{{feed.id}}`, `{{feed.title}}`, `{{item.id}}`, `{{item.title}}`, `{{item.author}}`,
+    /// `{{item.date}}`, `{{item.link}}`, `{{item.url}}` and `{{item.summary}}`; omit to send the item as JSON
+    pub webhook_body: Option<String>,
+    /// Serve the anonymous `/feeds/{id}/*` endpoints; omit to follow `FEED_PUBLIC_ENDPOINTS`
+    pub public_access: Option<bool>,
+    /// Most items that may be pinned; omit for 10
+    pub max_pinned: Option<i32>,
+    /// Title items of emails without a subject from their body's first heading or sentence; omit to enable
+    pub auto_titles: Option<bool>,
+    /// CSS added to the feed's hosted item pages (`/feeds/{id}/items/{item_id}`)
+    pub page_css: Option<String>,
+    /// HTML snippet shown above the item on its hosted page
+    pub page_header_html: Option<String>,
+    /// HTML snippet shown below the item on its hosted page
+    pub page_footer_html: Option<String>,
+    /// http(s) URL of a logo shown at the top of the hosted item pages
+    pub page_logo_url: Option<String>,
+    /// Keep every item unaltered, chained by hash; cannot be turned off again. Omit for false
+    pub append_only: Option<bool>,
+    /// Merge emails of the same thread (`thread`) or day (`day`) into one item; omit for one item per email
+    pub digest_mode: Option<String>,
+    /// Skip emails already stored in this feed (`feed`), any feed of the account (`account`) or any feed (`global`); omit for `FEED_DEDUP_SCOPE`
+    pub dedup_scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateFeedRequest {
+    /// May reference `{{rule.name}}`, `{{rule.folder}}`, `{{rule.label}}`, `{{account.name}}` and `{{account.host}}`
+    pub title: String,
+    /// May use the same variables as `title`
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub email_rule_id: String,
+    pub feed_type: String,
+    pub is_active: bool,
+    pub max_items: Option<i32>,
+    pub max_age_days: Option<i32>,
+    pub min_items: Option<i32>,
+    pub summary_length: Option<i32>,
+    /// Locale for display dates in items, e.g. `de_DE`; omit for POSIX
+    pub locale: Option<String>,
+    /// IANA timezone for display dates in items; omit for UTC
+    pub timezone: Option<String>,
+    /// Item title template such as `{{subject}} — {{from_name}}`; see the README for the placeholders
+    pub title_template: Option<String>,
+    /// Item description template such as `<p>{{folder}}</p>{{summary}}`; omit to use the email content
+    pub description_template: Option<String>,
+    /// Most items in the RSS and Atom documents; omit to use `FEED_ITEM_LIMIT`
+    pub item_limit: Option<i32>,
+    /// Include each item's full HTML body, or only its summary of `summary_length` characters; omit for the full body
+    pub full_content: Option<bool>,
+    /// Username readers must send with HTTP Basic auth to get the RSS and Atom documents; omit to leave them open
+    pub auth_username: Option<String>,
+    /// New password that goes with `auth_username`, stored hashed; omit to keep the current one
+    pub auth_password: Option<String>,
+    /// URL called for each new item, e.g. a Slack or Discord incoming webhook
+    pub webhook_url: Option<String>,
+    /// `POST` (default), `PUT` or `PATCH`
+    pub webhook_method: Option<String>,
+    /// JSON body template using `{{feed.id}}`, `{{feed.title}}`, `{{item.id}}`, `{{item.title}}`, `{{item.author}}`,
+    /// `{{item.date}}`, `{{item.link}}`, `{{item.url}}` and `{{item.summary}}`; omit to send the item as JSON
+    pub webhook_body: Option<String>,
+    /// Serve the anonymous `/feeds/{id}/*` endpoints; omit to follow `FEED_PUBLIC_ENDPOINTS`
+    pub public_access: Option<bool>,
+    /// Most items that may be pinned; omit for 10
+    pub max_pinned: Option<i32>,
+    /// Title items of emails without a subject from their body's first heading or sentence; omit to enable
+    pub auto_titles: Option<bool>,
+    /// CSS added to the feed's hosted item pages (`/feeds/{id}/items/{item_id}`)
+    pub page_css: Option<String>,
+    /// HTML snippet shown above the item on its hosted page
+    pub page_header_html: Option<String>,
+    /// HTML snippet shown below the item on its hosted page
+    pub page_footer_html: Option<String>,
+    /// http(s) URL of a logo shown at the top of the hosted item pages
+    pub page_logo_url: Option<String>,
+    /// Keep every item unaltered, chained by hash; cannot be turned off again. Omit to keep the current setting
+    pub append_only: Option<bool>,
+    /// Merge emails of the same thread (`thread`) or day (`day`) into one item; omit for one item per email
+    pub digest_mode: Option<String>,
+    /// Skip emails already stored in this feed (`feed`), any feed of the account (`account`) or any feed (`global`); omit for `FEED_DEDUP_SCOPE`
+    pub dedup_scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookTestResponse {
+    /// HTTP status returned by the webhook or chat service
+    pub status: u16,
+    /// Item that was sent; absent when a sample item was used
+    pub item_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeedItemsQuery {
+    /// Items per page; all items when omitted
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Only items published before this RFC 3339 timestamp
+    pub before: Option<String>,
+    /// Only items published after this RFC 3339 timestamp
+    pub after: Option<String>,
+}
+
+/// A page of a feed's items, pinned first, then newest first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeedItemsPage {
+    pub items: Vec<FeedItem>,
+    /// Items in the feed, whatever the page or date range
+    pub total: i64,
+    /// Items in the feed not marked read
+    pub unread: i64,
+    /// Pass as `cursor` to get the next page; null on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// A page of a feed's item metadata, in the order of [`FeedItemsPage`]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeedItemMetadataPage {
+    pub items: Vec<FeedItemMetadata>,
+    pub total: i64,
+    pub unread: i64,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeedItemMetadata {
+    pub id: String,
+    pub title: String,
+    pub pub_date: String,
+    pub author: Option<String>,
+    pub is_read: Option<bool>,
+    pub starred: Option<bool>,
+    pub pinned: bool,
+    /// 'up' or 'down', if rated
+    pub rating: Option<String>,
+    pub body_size: Option<i32>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateFeedItemRequest {
+    pub is_read: Option<bool>,
+    pub starred: Option<bool>,
+    /// Keep the item first in its feed and out of retention cleanup
+    pub pinned: Option<bool>,
+    /// 'up' or 'down'; an empty string clears the rating
+    pub rating: Option<String>,
+}
+
+/// What a bulk request does to the items it selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemAction {
+    MarkRead,
+    MarkUnread,
+    Star,
+    Unstar,
+    /// Pinned items are kept
+    Delete,
+}
+
+/// Items of a feed to change at once: those listed in `item_ids`, published
+/// between `since` and `until`, and unread when `unread` is set. At least one
+/// condition is required; `{"unread": true}` selects all unread items
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkFeedItemsRequest {
+    pub action: BulkItemAction,
+    pub item_ids: Option<Vec<String>>,
+    /// RFC 3339 timestamp; items published at or after it
+    pub since: Option<String>,
+    /// RFC 3339 timestamp; items published before it
+    pub until: Option<String>,
+    #[serde(default)]
+    pub unread: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkFeedItemsResponse {
+    pub action: BulkItemAction,
+    /// Items the request selected
+    pub matched: usize,
+    /// Items changed or deleted
+    pub updated: usize,
+    /// Pinned items a delete left alone
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ShareFeedItemRequest {
+    /// Hours until the link stops working; omit for a link that does not expire
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SharedItemLink {
+    /// Signed link to the item's page, absolute when the base URL is known
+    pub url: String,
+    pub expires_at: Option<String>,
+}
+
+/// Outcome of unsubscribing from an item's list
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnsubscribeResponse {
+    /// 'one_click' when the one-click request was sent; 'link' or 'mailto'
+    /// when `url` has to be opened to unsubscribe
+    pub method: String,
+    pub url: String,
+    /// Whether the sender accepted the one-click request
+    pub unsubscribed: bool,
+    /// HTTP status of the one-click request
+    pub status: Option<u16>,
+}
+
+// Chat integrations
+
+/// Body of create and update requests for chat integrations
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChatIntegrationRequest {
+    /// `slack`, `discord` or `matrix`
+    pub platform: String,
+    /// Incoming webhook URL; required for Slack and Discord
+    pub webhook_url: Option<String>,
+    /// Required for Matrix, e.g. `https://matrix.org`
+    pub matrix_homeserver: Option<String>,
+    /// Required for Matrix: a room ID such as `!abc123:matrix.org`, not an alias
+    pub matrix_room_id: Option<String>,
+    /// Required for Matrix: access token of the account posting to the room
+    pub matrix_access_token: Option<String>,
+    /// Message text with the feed webhook variables; defaults to `New in {{feed.title}}: {{item.title}} {{item.url}}`
+    pub message_template: Option<String>,
+    /// Messages per minute, further items are dropped; defaults to 10
+    pub rate_limit_per_minute: Option<i32>,
+    pub is_active: bool,
+}
+
+// Quotas
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuotaGroupRequest {
+    pub name: String,
+    /// Feeds allowed across the group's accounts; omit for unlimited
+    pub max_feeds: Option<i32>,
+    /// Items allowed across the group's accounts; omit for unlimited
+    pub max_items: Option<i32>,
+    /// Minutes of processing allowed per UTC day across the group's accounts; omit for unlimited
+    pub max_processing_minutes_per_day: Option<i32>,
+}
+
+/// Limits of an account or quota group next to what it uses
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuotaReport {
+    pub max_feeds: Option<i32>,
+    pub max_items: Option<i32>,
+    pub max_processing_minutes_per_day: Option<i32>,
+    pub usage: QuotaUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountQuotaResponse {
+    /// The account's own limits and usage
+    pub account: QuotaReport,
+    /// Limits and usage of the account's quota group, summed over its accounts
+    pub group: Option<QuotaReport>,
+}
+
+// Senders
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SenderAliasRequest {
+    /// Canonical sender name; rules can use it as `from_address`
+    pub name: String,
+    /// Addresses and domains of the sender; a domain also covers its subdomains
+    pub addresses: Vec<String>,
+}
+
+/// Body of create and update requests for account sender filters
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SenderFilterRequest {
+    /// `allow` or `deny`
+    pub kind: String,
+    /// Sender address such as `news@example.com`, or a domain such as `example.com` that also covers its subdomains
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SenderAliasResponse {
+    pub id: String,
+    pub name: String,
+    pub addresses: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<SenderAlias> for SenderAliasResponse {
+    fn from(alias: SenderAlias) -> Self {
+        Self {
+            addresses: alias.address_list(),
+            id: alias.id.unwrap_or_default(),
+            name: alias.name,
+            created_at: alias.created_at,
+            updated_at: alias.updated_at,
+        }
+    }
+}
+
+// Timeline
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimelineQuery {
+    /// Items per page, at most 200; defaults to 50
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Comma-separated IDs of the feeds to include; all feeds when omitted
+    pub feeds: Option<String>,
+    /// Comma-separated IDs of feeds to leave out
+    pub exclude_feeds: Option<String>,
+    /// Only items not marked read
+    #[serde(default)]
+    pub unread_only: bool,
+}
+
+/// A feed item with the title of its feed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TimelineItem {
+    #[serde(flatten)]
+    pub item: FeedItem,
+    pub feed_title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TimelineResponse {
+    /// Items across feeds, newest `pub_date` first
+    pub items: Vec<TimelineItem>,
+    /// Pass as `cursor` to get the next page; null on the last page
+    pub next_cursor: Option<String>,
+}
+
+// Analysis
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StorageForecastQuery {
+    /// Days of recent items the growth rates are computed from; defaults to 30
+    pub window_days: Option<i64>,
+    /// Size budget in megabytes; defaults to `STORAGE_BUDGET_MB`
+    pub budget_mb: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RuleCostQuery {
+    /// Days of runs to total; defaults to 7
+    pub window_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsQuery {
+    /// Days of items and runs to cover, at most 365; defaults to 30
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatsResponse {
+    pub generated_at: String,
+    /// Database size and free disk space against the storage limits
+    pub storage: StorageStatus,
+    /// Items, senders and processing runs over the last `days`
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+// IMAP operations
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TestConnectionResponse {
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folders: Option<Vec<String>>,
+    /// IDs of other accounts whose mailbox has the same fingerprint
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_of: Vec<String>,
+}
+
+/// Fingerprints of the certificate an account's server presents, for
+/// pinning it on first use
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TlsFingerprintResponse {
+    /// Pin for exactly this certificate
+    pub certificate_pin: String,
+    /// Pin for the certificate's public key; survives renewals that keep the key
+    pub public_key_pin: String,
+    /// Pin currently set on the account
+    pub pin: Option<String>,
+    /// Whether the presented certificate satisfies the current pin; null without one
+    pub matches_pin: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProcessAccountResponse {
+    pub success: bool,
+    pub emails_processed: usize,
+    pub items_created: usize,
+    pub errors: Vec<String>,
+    /// Emails turned into items whose post-processing action failed, one per UID
+    #[serde(default)]
+    pub post_process_failures: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+// Setup
+
+/// Connection settings of an account that is not saved yet
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupConnectionRequest {
+    pub host: String,
+    pub port: i32,
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub use_tls: bool,
+    /// `none`, `starttls` or `ssl_tls`; suggested from `use_tls` and the port when omitted
+    pub security: Option<String>,
+    pub tls_pin: Option<String>,
+    pub tls_server_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupConnectionResponse {
+    pub success: bool,
+    /// Step that failed: `connect`, `tls`, `login` or `protocol`
+    pub failed_step: Option<String>,
+    pub message: String,
+    /// Greeting the server sent on connect
+    pub greeting: Option<String>,
+    /// Capabilities the server announces after login, e.g. `IDLE` or `MOVE`
+    pub capabilities: Vec<String>,
+    /// ID of an existing account with the same host and username
+    pub duplicate_of: Option<String>,
+}
+
+/// A folder to turn into a rule and a feed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupFeedRequest {
+    pub folder: String,
+    /// Title of the feed and name of its rule; defaults to the folder name
+    pub title: Option<String>,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub subject_contains: Option<String>,
+    #[serde(default = "default_feed_type")]
+    pub feed_type: String,
+}
+
+fn default_feed_type() -> String {
+    "rss".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupFinalizeRequest {
+    pub account: CreateImapAccountRequest,
+    /// One rule and one feed are created per entry
+    pub feeds: Vec<SetupFeedRequest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupFinalizeResponse {
+    pub account: ImapAccount,
+    pub rules: Vec<EmailRule>,
+    pub feeds: Vec<Feed>,
+}
+
+/// Database backend and whether it answers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupDatabaseStatus {
+    /// `sqlite` or `postgres`
+    pub backend: String,
+    #[serde(flatten)]
+    pub check: DependencyCheck,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetupStatusResponse {
+    /// False until the first-run setup created an admin user, or accounts exist
+    pub initialized: bool,
+    pub has_admin: bool,
+    pub account_count: usize,
+    pub database: SetupDatabaseStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminUserRequest {
+    pub username: String,
+    /// At least 8 characters; stored as an Argon2 hash
+    pub password: String,
+}
+
+/// Everything a fresh instance needs, created in one step
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InitialSetupRequest {
+    pub admin: AdminUserRequest,
+    /// Checked against its server before anything is created
+    pub account: CreateImapAccountRequest,
+    /// One rule and one feed are created per entry; may be empty
+    #[serde(default)]
+    pub feeds: Vec<SetupFeedRequest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InitialSetupResponse {
+    pub admin_username: String,
+    pub account: ImapAccount,
+    pub rules: Vec<EmailRule>,
+    pub feeds: Vec<Feed>,
+}
+
+// Import
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportRequest {
+    /// `kill_the_newsletter`, `csv` or `json`
+    pub format: String,
+    /// The export, as text
+    pub content: String,
+    /// Account to add the rules to; give this or `account`
+    pub imap_account_id: Option<String>,
+    /// Account to create with the rules; give this or `imap_account_id`
+    pub account: Option<CreateImapAccountRequest>,
+    /// Folder of the mappings that do not name one; defaults to `INBOX`
+    pub folder: Option<String>,
+}
+
+/// A mapping that was not imported
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SkippedImport {
+    pub address: String,
+    pub title: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    /// The account the rules were added to
+    pub imap_account_id: String,
+    /// Whether the import created the account
+    pub account_created: bool,
+    pub rules: Vec<EmailRule>,
+    pub feeds: Vec<Feed>,
+    /// Mappings already covered by a rule of the account, or repeated in the export
+    pub skipped: Vec<SkippedImport>,
+}
+
+// Background service
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackgroundStatusResponse {
+    pub status: ServiceStatus,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct StartServiceRequest {
+    pub force: Option<bool>,
+}
+
+/// Acknowledgement that background processing of an account was queued
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackgroundProcessResponse {
+    pub account_id: String,
+    pub success: bool,
+    pub message: String,
+    /// Job following the processing, under `/api/jobs/{id}`
+    pub job_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProcessingRunQuery {
+    /// Only runs of this account
+    pub account_id: Option<String>,
+    /// Only runs with this status: `running`, `completed`, `failed`,
+    /// `rolled_back` or `aborted`
+    pub status: Option<String>,
+    /// Only runs started at or after this RFC 3339 timestamp
+    pub since: Option<String>,
+    /// Only runs started before this RFC 3339 timestamp
+    pub until: Option<String>,
+    /// Runs to return, at most 500; defaults to 50
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServiceActionResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+// Maintenance
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BackfillMetadataRequest {
+    pub batch_size: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct OffloadBodiesRequest {
+    pub batch_size: Option<i64>,
+    /// Smallest body moved, in bytes; `BODY_STORE_MIN_BYTES` when omitted
+    pub min_bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BackfillRuleRequest {
+    /// UIDs fetched per batch; 100 when omitted
+    pub batch_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttachFeedRequest {
+    /// Feed of another rule that should also receive this rule's emails
+    pub feed_id: String,
+    /// Feeds with a higher priority receive an email first; the rule's own feeds have 0
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A feed a rule delivers its matching emails to
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuleFeedResponse {
+    pub feed_id: String,
+    pub feed_title: String,
+    pub priority: i32,
+    /// Attached to the rule rather than one of its own feeds
+    pub attached: bool,
+    /// Inactive feeds receive nothing
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaskStartedResponse {
+    pub task_id: String,
+    pub message: String,
+}
+
+// Jobs
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobStartedResponse {
+    /// Poll `/api/jobs/{id}` for the job's progress
+    pub job_id: String,
+    pub message: String,
+}
+
+/// A background job with its progress
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobResponse {
+    pub id: String,
+    pub kind: String,
+    /// The account, rule or feed the job works on
+    pub target_id: Option<String>,
+    pub status: JobStatus,
+    /// Percentage done; `null` while a running job's total is unknown
+    pub progress: Option<u8>,
+    pub processed: i32,
+    pub total: Option<i32>,
+    /// What the job is doing at the moment
+    pub message: Option<String>,
+    pub error: Option<String>,
+    /// Summary of a completed job, depending on its kind
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            status: job.job_status(),
+            progress: job.progress(),
+            result: job.result.as_deref().and_then(|result| serde_json::from_str(result).ok()),
+            id: job.id.unwrap_or_default(),
+            kind: job.kind,
+            target_id: job.target_id,
+            processed: job.processed,
+            total: job.total,
+            message: job.message,
+            error: job.error_message,
+            created_at: job.created_at,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JobQuery {
+    /// Only jobs of this kind, e.g. `backfill_rule`
+    pub kind: Option<String>,
+    /// Only jobs working on this account, rule or feed
+    pub target_id: Option<String>,
+    /// Only jobs with this status: `queued`, `running`, `completed` or `failed`
+    pub status: Option<String>,
+    /// Jobs to return, at most 500; defaults to 50
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CleanupQuery {
+    /// Run the cleanup as a job and answer `202` with its ID right away
+    #[serde(default)]
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// `epub` (the default), `html` or `mbox`
+    pub format: Option<String>,
+    /// Export as a job and answer `202` with its ID even for a small feed
+    #[serde(default)]
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct EnterMaintenanceRequest {
+    /// Shown to clients whose changes are refused
+    pub reason: Option<String>,
+    /// Seconds refused clients are told to wait before retrying (default 300)
+    pub retry_after_seconds: Option<u64>,
+    /// Seconds to wait for processing runs in progress to finish (default 60)
+    pub drain_timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MaintenanceStatus {
+    pub active: bool,
+    pub since: Option<String>,
+    pub reason: Option<String>,
+    pub retry_after_seconds: Option<u64>,
+    /// Processing runs still in progress; zero once it is safe to back up
+    pub in_flight_runs: usize,
+}
+
+// Feed reorganization
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MergeFeedsRequest {
+    /// Feed whose items move; it is deleted and its URLs redirect to the target
+    pub source_feed_id: String,
+    pub target_feed_id: String,
+    /// Only report what would move
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Items of the feed matching every condition given move to a new feed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SplitFeedRequest {
+    /// Title of the new feed
+    pub title: String,
+    /// Rule of the new feed; the split feed's rule when omitted
+    pub email_rule_id: Option<String>,
+    /// Sender domain, covering its subdomains
+    pub sender_domain: Option<String>,
+    pub from_address: Option<String>,
+    pub subject_contains: Option<String>,
+    /// Only report what would move
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MovedFeedItem {
+    pub id: String,
+    pub title: String,
+    pub email_from: Option<String>,
+    pub pub_date: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeedReorganizationResponse {
+    pub dry_run: bool,
+    pub source_feed_id: String,
+    /// Feed the items move to; null on a dry run of a split
+    pub target_feed_id: Option<String>,
+    /// Items moved, or that would be on a dry run
+    pub items_moved: usize,
+    pub items: Vec<MovedFeedItem>,
+}
+
+// Version
+
+/// What a change means for existing setups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Existing setups behave differently
+    Behavior,
+    /// Existing setups need changes to keep working
+    Breaking,
+    /// Still works, but is going away
+    Deprecation,
+}
+
+/// A change made by a version
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionChange {
+    pub version: String,
+    pub kind: ChangeKind,
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionResponse {
+    /// Version of the running backend
+    pub version: String,
+    /// Commit the backend was built from; null unless `GIT_HASH` was set at build time
+    pub git_hash: Option<String>,
+    /// Newest migration applied to the database
+    pub schema_version: Option<String>,
+    /// Version that ran against the database before this one; null on a fresh install
+    pub previous_version: Option<String>,
+    /// When this version first started, i.e. when it was upgraded to
+    pub first_run_at: Option<String>,
+    /// Changes since `previous_version`, oldest first
+    pub changes: Vec<VersionChange>,
+}
+
+// Settings
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SettingsResponse {
+    /// Retention of each history table
+    pub retention: Vec<RetentionSetting>,
+    /// Base URL feeds link to: the stored setting, or `PUBLIC_BASE_URL` when
+    /// none is stored; null when links follow the request's host
+    pub public_base_url: Option<String>,
+    /// Retention of feeds that leave a limit unset
+    pub feed_retention: FeedRetention,
+    /// When failing accounts are alerted about, and on which channels
+    pub notifications: NotificationSettings,
+    /// User the Fever sync API at `/fever/` accepts; null while it is off
+    pub fever_username: Option<String>,
+    /// Runtime settings with their effective values
+    pub values: Vec<RuntimeSetting>,
+}
+
+/// A setting that falls back to an environment variable
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuntimeSetting {
+    /// Name of the setting, e.g. `feed_cache_duration`
+    pub key: String,
+    /// Effective value
+    pub value: String,
+    /// Whether `value` is stored, from `env_var`, or the default
+    pub source: SettingSource,
+    pub kind: SettingKind,
+    /// Environment variable used when nothing is stored
+    pub env_var: String,
+    /// Value used when neither is set
+    pub default: String,
+}
+
+/// New limits for one history table; null leaves that limit off
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RetentionSettingRequest {
+    pub table: String,
+    pub max_rows: Option<i32>,
+    pub max_age_days: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSettingsRequest {
+    /// Tables left out keep their retention
+    #[serde(default)]
+    pub retention: Vec<RetentionSettingRequest>,
+    /// New public base URL; an empty string removes the stored one and
+    /// left out keeps it
+    pub public_base_url: Option<String>,
+    /// New default retention of feeds; left out keeps it
+    pub feed_retention: Option<FeedRetention>,
+    /// New alert settings, replacing every channel; left out keeps them
+    pub notifications: Option<NotificationSettings>,
+    /// New user of the Fever sync API; an empty username turns the API off
+    /// and left out keeps it
+    pub fever: Option<FeverUserRequest>,
+    /// New runtime settings by name; null removes the stored value so the
+    /// environment variable applies again, and settings left out keep theirs
+    #[serde(default)]
+    pub values: HashMap<String, Option<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeverUserRequest {
+    pub username: String,
+    /// Clients sign in with the MD5 of `username:password`, so use one
+    /// not used anywhere else
+    #[serde(default)]
+    pub password: String,
+}
+
+/// Outcome of a test alert on one channel
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationTestResult {
+    /// Position of the channel in the settings, from 1
+    pub channel: usize,
+    pub kind: ChannelKind,
+    pub delivered: bool,
+    /// Why the alert did not go out
+    pub error: Option<String>,
+}
+
+/// What an on-demand cleanup removed
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CleanupReport {
+    /// Items removed from the feeds
+    pub feeds: CleanupResult,
+    /// Rows purged from each history table with a retention policy
+    pub rows_purged: HashMap<String, usize>,
+}
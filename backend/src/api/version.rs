@@ -0,0 +1,141 @@
+//! Version and upgrade notes
+//!
+//! Every start records the running version in `app_versions`. After an
+//! upgrade, `GET /api/admin/version` lists the changes in behavior between
+//! the version that ran before and this one, so the frontend can show what
+//! changed once a container is replaced. Add an entry to [`CHANGES`] with any
+//! release that changes what existing setups do.
+
+use anyhow::Result;
+use std::cmp::Ordering;
+use tracing::info;
+
+use crate::api::types::{ChangeKind, VersionChange, VersionResponse};
+use crate::db::{connection::DatabasePool, operations_generic::AppVersionOpsGeneric};
+
+/// Version of the running binary
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the binary was built from, when `GIT_HASH` was set at build time
+pub const GIT_HASH: Option<&str> = option_env!("GIT_HASH");
+
+/// Changes existing setups notice after upgrading, oldest first: the version
+/// that made the change, its kind and a summary
+pub const CHANGES: &[(&str, ChangeKind, &str)] = &[
+    (
+        "0.1.0",
+        ChangeKind::Behavior,
+        "Processing runs, rule matches, deliveries and rule costs can be trimmed by retention policies under /api/settings; tables without a policy keep everything as before",
+    ),
+    (
+        "0.1.0",
+        ChangeKind::Behavior,
+        "With a public base URL configured (PUBLIC_BASE_URL, FEED_PUBLIC_URL or the public_base_url setting), RSS item GUIDs are permalinks to the item pages, so feed readers may show existing items once more",
+    ),
+    (
+        "0.1.0",
+        ChangeKind::Behavior,
+        "Feeds created or updated without max_items, max_age_days or min_items store no limit and follow the feed_retention setting, which keeps the former defaults (100 items, 30 days, at least 10) until it is changed",
+    ),
+    (
+        "0.1.0",
+        ChangeKind::Behavior,
+        "TLS accounts on port 993 now connect with implicit TLS instead of STARTTLS; set security to starttls on an account whose server expects STARTTLS there",
+    ),
+];
+
+/// Record that this version started, logging an upgrade from the version
+/// that ran before it
+pub fn record_run(pool: &DatabasePool) -> Result<()> {
+    let previous = previous_version(pool)?;
+    let recorded = AppVersionOpsGeneric::record_run(pool, VERSION, GIT_HASH)?;
+    if let Some(previous) = previous {
+        if recorded.first_run_at == recorded.last_run_at {
+            info!("Upgraded from {} to {}", previous, VERSION);
+        }
+    }
+    Ok(())
+}
+
+/// The version that ran before this one, if another one ever did
+fn previous_version(pool: &DatabasePool) -> Result<Option<String>> {
+    Ok(AppVersionOpsGeneric::get_all(pool)?
+        .into_iter()
+        .map(|version| version.version)
+        .find(|version| version != VERSION))
+}
+
+/// The running version, the one before it and what changed in between
+pub fn status(pool: &DatabasePool) -> Result<VersionResponse> {
+    let versions = AppVersionOpsGeneric::get_all(pool)?;
+    let previous_version = versions.iter().find(|version| version.version != VERSION).map(|version| version.version.clone());
+    let first_run_at = versions.iter().find(|version| version.version == VERSION).map(|version| version.first_run_at.clone());
+
+    Ok(VersionResponse {
+        version: VERSION.to_string(),
+        git_hash: GIT_HASH.map(str::to_string),
+        schema_version: AppVersionOpsGeneric::schema_version(pool)?,
+        changes: changes_between(previous_version.as_deref(), VERSION),
+        previous_version,
+        first_run_at,
+    })
+}
+
+/// Changes made after `previous` up to and including `current`; none on a
+/// fresh install or a downgrade
+pub fn changes_between(previous: Option<&str>, current: &str) -> Vec<VersionChange> {
+    let Some(previous) = previous else {
+        return Vec::new();
+    };
+    CHANGES
+        .iter()
+        .filter(|(version, _, _)| {
+            compare_versions(version, previous) == Ordering::Greater && compare_versions(version, current) != Ordering::Greater
+        })
+        .map(|(version, kind, summary)| VersionChange {
+            version: version.to_string(),
+            kind: *kind,
+            summary: summary.to_string(),
+        })
+        .collect()
+}
+
+/// Compare dotted versions numerically, ignoring pre-release and build suffixes
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.1.0", "0.1"), Ordering::Equal);
+        assert_eq!(compare_versions("0.10.0", "0.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0.0-rc.1", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.0.9", "0.1.0"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_changes_between_versions() {
+        assert!(changes_between(None, VERSION).is_empty());
+        assert!(changes_between(Some(VERSION), VERSION).is_empty());
+        assert_eq!(changes_between(Some("0.0.1"), "0.1.0").len(), CHANGES.iter().filter(|(version, _, _)| *version == "0.1.0").count());
+        // A downgrade shows nothing
+        assert!(changes_between(Some("9.0.0"), "0.1.0").is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+//! Importing the past emails of a rule
+//!
+//! A new rule only sees what its first runs fetch, so older mail in its
+//! folder never reaches the feed. A backfill walks the whole folder, oldest
+//! first in batches of UIDs, and turns every matching email into an item,
+//! reporting its progress as a job. The feeds' retention limits are applied
+//! once it finishes, so a backfill into a feed keeping 100 items leaves the
+//! newest 100.
+
+use crate::background::{cleanup::FeedCleanupService, jobs::JobHandle};
+use crate::db::{connection::DatabasePool, models::{EmailRule, ImapAccount}, operations_generic::FeedOpsGeneric};
+use crate::imap::processor::{BackfillResult, EmailProcessor};
+use anyhow::Result;
+use tracing::info;
+
+/// Job kind of rule backfills
+pub const BACKFILL_RULE_JOB: &str = "backfill_rule";
+
+/// Number of UIDs fetched per batch
+pub const DEFAULT_BACKFILL_UID_BATCH: u32 = 100;
+
+pub struct RuleBackfillService {
+    pool: DatabasePool,
+    account: ImapAccount,
+    rule: EmailRule,
+    batch_size: u32,
+}
+
+impl RuleBackfillService {
+    pub fn new(pool: DatabasePool, account: ImapAccount, rule: EmailRule) -> Self {
+        Self {
+            pool,
+            account,
+            rule,
+            batch_size: DEFAULT_BACKFILL_UID_BATCH,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: u32) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Run the backfill to completion, reporting its progress on `job`
+    pub async fn run(&self, job: &JobHandle) -> Result<BackfillResult> {
+        let processor = EmailProcessor::new(self.account.clone(), self.pool.clone());
+        let result = processor.backfill_rule(&self.rule, self.batch_size, job).await?;
+
+        let cleanup_service = FeedCleanupService::new(self.pool.clone());
+        for feed_id in &result.feed_ids {
+            let feed = FeedOpsGeneric::get_by_id(&self.pool, feed_id)?;
+            let cleanup = cleanup_service.cleanup_feed(&feed).await?;
+            if cleanup.items_removed > 0 {
+                info!("Retention of feed '{}' removed {} items after the backfill", feed.title, cleanup.items_removed);
+            }
+        }
+        info!("Backfill {} of rule '{}' complete: {} items created", job.id(), self.rule.name, result.items_created);
+        Ok(result)
+    }
+}
@@ -0,0 +1,198 @@
+//! Targeted re-processing after rule and feed edits
+//!
+//! Saving a rule or feed sends a [`RuleChange`] for the account and folder it
+//! reads from. Changes are collected per account by a [`ChangeDebouncer`] and
+//! only turned into a processing pass once the account has been quiet for the
+//! configured period, so a burst of edits triggers a single pass over the
+//! affected folders rather than one per save.
+
+use crate::background::scheduler::EmailScheduler;
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+
+/// A rule or feed edit affecting one folder of an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleChange {
+    pub account_id: String,
+    pub folder: String,
+}
+
+/// A processing pass limited to the folders that changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetedPass {
+    pub account_id: String,
+    pub folders: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PendingChanges {
+    folders: BTreeSet<String>,
+    last_change: Instant,
+}
+
+/// Collects changes per account until the account has been quiet long enough
+#[derive(Debug)]
+pub struct ChangeDebouncer {
+    quiet_period: Duration,
+    pending: HashMap<String, PendingChanges>,
+}
+
+impl ChangeDebouncer {
+    pub fn new(quiet_period: Duration) -> Self {
+        Self {
+            quiet_period,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Record a change, pushing back the account's pass until it goes quiet again
+    pub fn record(&mut self, change: RuleChange, now: Instant) {
+        let pending = self.pending.entry(change.account_id).or_insert_with(|| PendingChanges {
+            folders: BTreeSet::new(),
+            last_change: now,
+        });
+        pending.folders.insert(change.folder);
+        pending.last_change = now;
+    }
+
+    /// When the next pass becomes due, if any changes are pending
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values()
+            .map(|pending| pending.last_change + self.quiet_period)
+            .min()
+    }
+
+    /// Remove and return the passes whose quiet period has elapsed
+    pub fn take_due(&mut self, now: Instant) -> Vec<TargetedPass> {
+        let due: Vec<String> = self.pending.iter()
+            .filter(|(_, pending)| now >= pending.last_change + self.quiet_period)
+            .map(|(account_id, _)| account_id.clone())
+            .collect();
+
+        due.into_iter()
+            .filter_map(|account_id| {
+                let pending = self.pending.remove(&account_id)?;
+                Some(TargetedPass {
+                    account_id,
+                    folders: pending.folders.into_iter().collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Change the quiet period, e.g. after a configuration reload; pending
+    /// changes become due by the new period
+    pub fn set_quiet_period(&mut self, quiet_period: Duration) {
+        self.quiet_period = quiet_period;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Feed changes into a debouncer and run targeted passes as they become due
+///
+/// Passes for accounts that are busy with another pass are re-queued, so the
+/// edit is still applied once the running pass finishes. Runs until
+/// `change_rx` is closed. The quiet period follows the scheduler's
+/// configuration.
+pub async fn run_debouncer(scheduler: EmailScheduler, mut change_rx: mpsc::UnboundedReceiver<RuleChange>) {
+    let mut debouncer = ChangeDebouncer::new(scheduler.config().change_debounce());
+    let (retry_tx, mut retry_rx) = mpsc::unbounded_channel::<RuleChange>();
+
+    loop {
+        debouncer.set_quiet_period(scheduler.config().change_debounce());
+        let deadline = debouncer.next_deadline();
+
+        tokio::select! {
+            change = change_rx.recv() => match change {
+                Some(change) => debouncer.record(change, Instant::now()),
+                None => break,
+            },
+            Some(change) = retry_rx.recv() => debouncer.record(change, Instant::now()),
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into()), if deadline.is_some() => {
+                for pass in debouncer.take_due(Instant::now()) {
+                    let scheduler = scheduler.clone();
+                    let retry_tx = retry_tx.clone();
+                    tokio::spawn(async move {
+                        match scheduler.process_folders_now(&pass.account_id, &pass.folders).await {
+                            Ok(Some(stats)) => info!(
+                                "Re-processed {:?} of account {} after rule changes: {} emails",
+                                pass.folders, pass.account_id, stats.emails_processed
+                            ),
+                            Ok(None) => {
+                                debug!("Account {} is busy, re-queueing targeted pass", pass.account_id);
+                                for folder in pass.folders {
+                                    let _ = retry_tx.send(RuleChange { account_id: pass.account_id.clone(), folder });
+                                }
+                            }
+                            Err(e) => error!("Targeted pass for account {} failed: {}", pass.account_id, e),
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    debug!("Rule change debouncer stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(account_id: &str, folder: &str) -> RuleChange {
+        RuleChange {
+            account_id: account_id.to_string(),
+            folder: folder.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pass_waits_for_quiet_period() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        debouncer.record(change("acct", "INBOX"), start);
+        assert!(debouncer.take_due(start + Duration::from_secs(9)).is_empty());
+
+        let due = debouncer.take_due(start + Duration::from_secs(10));
+        assert_eq!(due, vec![TargetedPass { account_id: "acct".to_string(), folders: vec!["INBOX".to_string()] }]);
+        assert!(debouncer.is_empty());
+    }
+
+    #[test]
+    fn test_burst_of_edits_collapses_into_one_pass() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        for (offset, folder) in [(0, "INBOX"), (4, "Lists"), (8, "INBOX")] {
+            debouncer.record(change("acct", folder), start + Duration::from_secs(offset));
+        }
+
+        // Each edit restarts the quiet period
+        assert!(debouncer.take_due(start + Duration::from_secs(15)).is_empty());
+        assert_eq!(debouncer.next_deadline(), Some(start + Duration::from_secs(18)));
+
+        let due = debouncer.take_due(start + Duration::from_secs(18));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].folders, vec!["INBOX".to_string(), "Lists".to_string()]);
+    }
+
+    #[test]
+    fn test_accounts_are_debounced_independently() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        debouncer.record(change("a", "INBOX"), start);
+        debouncer.record(change("b", "INBOX"), start + Duration::from_secs(5));
+
+        let due = debouncer.take_due(start + Duration::from_secs(10));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].account_id, "a");
+        assert_eq!(debouncer.next_deadline(), Some(start + Duration::from_secs(15)));
+    }
+}
@@ -1,15 +1,84 @@
 use anyhow::Result;
-use crate::db::{connection::DatabasePool, operations_generic::{FeedOpsGeneric, FeedItemOpsGeneric}};
+use crate::background::clock::{Clock, SystemClock};
+use crate::db::{connection::DatabasePool, models::{AppSetting, Feed}, operations_generic::{AppSettingOpsGeneric, AttachmentOpsGeneric, FeedOpsGeneric, FeedItemOpsGeneric}};
+use crate::feed::{bodies, dedup};
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn, debug};
 use chrono::{Utc, Duration};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Share of each feed's items `tighten_all_feeds` removes per pass
+const TIGHTEN_DIVISOR: usize = 4;
+
+/// Retention of feeds that leave a limit unset; null limits are off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FeedRetention {
+    pub max_items: Option<i32>,
+    pub max_age_days: Option<i32>,
+    pub min_items: Option<i32>,
+}
+
+impl Default for FeedRetention {
+    /// Keep the newest 100 items for 30 days, but at least 10
+    fn default() -> Self {
+        Self { max_items: Some(100), max_age_days: Some(30), min_items: Some(10) }
+    }
+}
+
+impl FeedRetention {
+    /// The stored default, or the built-in one when none is stored
+    pub fn load(pool: &DatabasePool) -> Result<Self> {
+        match AppSettingOpsGeneric::get(pool, AppSetting::FEED_RETENTION)? {
+            Some(setting) => Ok(serde_json::from_str(&setting.value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Store the default applying to feeds from now on
+    pub fn store(&self, pool: &DatabasePool) -> Result<()> {
+        AppSettingOpsGeneric::set(pool, AppSetting::FEED_RETENTION, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// An error message unless the limits are at least one (`min_items` zero)
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_items.is_some_and(|items| items < 1) {
+            return Err("max_items must be at least 1".to_string());
+        }
+        if self.max_age_days.is_some_and(|days| days < 1) {
+            return Err("max_age_days must be at least 1".to_string());
+        }
+        if self.min_items.is_some_and(|items| items < 0) {
+            return Err("min_items must not be negative".to_string());
+        }
+        Ok(())
+    }
+
+    /// `feed` with the limits it leaves unset taken from this default
+    pub fn apply(&self, feed: &Feed) -> Feed {
+        Feed {
+            max_items: feed.max_items.or(self.max_items),
+            max_age_days: feed.max_age_days.or(self.max_age_days),
+            min_items: feed.min_items.or(self.min_items),
+            ..feed.clone()
+        }
+    }
+}
 
 pub struct FeedCleanupService {
     pool: DatabasePool,
+    clock: Arc<dyn Clock>,
 }
 
 impl FeedCleanupService {
     pub fn new(pool: DatabasePool) -> Self {
-        Self { pool }
+        Self::with_clock(pool, Arc::new(SystemClock))
+    }
+    
+    /// A cleanup service judging item ages by `clock`
+    pub fn with_clock(pool: DatabasePool, clock: Arc<dyn Clock>) -> Self {
+        Self { pool, clock }
     }
     
     /// Run cleanup for all feeds based on their retention policies
@@ -17,13 +86,15 @@ impl FeedCleanupService {
         info!("Starting feed cleanup process");
         
         let feeds = FeedOpsGeneric::get_all(&self.pool)?;
+        let defaults = FeedRetention::load(&self.pool)?;
         let mut total_result = CleanupResult::default();
         
         for feed in feeds {
-            match self.cleanup_feed(&feed).await {
+            match self.enforce(&defaults.apply(&feed)).await {
                 Ok(result) => {
                     total_result.feeds_processed += 1;
                     total_result.items_removed += result.items_removed;
+                    total_result.feeds.extend(result.feeds);
                     
                     if result.items_removed > 0 {
                         info!("Cleaned up {} items from feed '{}'", result.items_removed, feed.title);
@@ -38,27 +109,48 @@ impl FeedCleanupService {
         
         info!("Cleanup complete: {} feeds processed, {} items removed, {} errors", 
               total_result.feeds_processed, total_result.items_removed, total_result.errors);
+        self.remove_orphaned_attachments();
+        self.remove_unused_bodies().await;
         
         Ok(total_result)
     }
     
-    /// Cleanup a specific feed based on its retention policies
-    pub async fn cleanup_feed(&self, feed: &crate::db::models::Feed) -> Result<CleanupResult> {
+    /// Cleanup a specific feed based on its retention policies, or the
+    /// default retention for the limits it leaves unset
+    pub async fn cleanup_feed(&self, feed: &Feed) -> Result<CleanupResult> {
+        let feed = FeedRetention::load(&self.pool)?.apply(feed);
+        let result = self.enforce(&feed).await?;
+        self.remove_orphaned_attachments();
+        self.remove_unused_bodies().await;
+        Ok(result)
+    }
+    
+    /// Remove the items of `feed` its retention limits no longer keep
+    async fn enforce(&self, feed: &Feed) -> Result<CleanupResult> {
         let feed_id = feed.id.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Feed has no ID"))?;
             
+        if feed.append_only {
+            debug!("Feed '{}' is append-only, keeping all its items", feed.title);
+            return Ok(CleanupResult::of_feed(feed_id, &feed.title, 0));
+        }
+        
         debug!("Cleaning up feed '{}' ({})", feed.title, feed_id);
         
-        // Get all feed items for this feed, ordered by creation date (newest first)
-        let all_items = FeedItemOpsGeneric::get_by_feed_id(&self.pool, feed_id, None)?;
+        // Get all feed items for this feed, newest first by publication date;
+        // pinned items are kept whatever the retention policies say
+        let all_items: Vec<_> = FeedItemOpsGeneric::get_by_feed_id(&self.pool, feed_id, None)?
+            .into_iter()
+            .filter(|item| !item.pinned)
+            .collect();
         
         if all_items.is_empty() {
             debug!("No items to clean up in feed '{}'", feed.title);
-            return Ok(CleanupResult { items_removed: 0, ..Default::default() });
+            return Ok(CleanupResult::of_feed(feed_id, &feed.title, 0));
         }
         
         let mut items_to_remove = Vec::new();
-        let now = Utc::now();
+        let now = self.clock.utc_now();
         
         // Apply retention policies
         
@@ -89,12 +181,10 @@ impl FeedCleanupService {
                 let target_count = max_items.max(min_items);
                 let items_to_remove_by_count = all_items.len().saturating_sub(target_count);
                 
-                // Sort items by creation date (oldest first for removal)
-                let mut sorted_items = all_items.clone();
-                sorted_items.sort_by(|a, b| a.created_at.cmp(&b.created_at));
-                
-                // Take the oldest items for removal (but avoid duplicates with age-based removal)
-                for item in sorted_items.iter().take(items_to_remove_by_count) {
+                // Take the oldest items by publication date for removal (but avoid
+                // duplicates with age-based removal); going by creation date would
+                // evict the newest mail when a backfill adds older mail after it
+                for item in all_items.iter().rev().take(items_to_remove_by_count) {
                     if let Some(item_id) = &item.id {
                         if !items_to_remove.contains(item_id) {
                             items_to_remove.push(item_id.clone());
@@ -117,10 +207,13 @@ impl FeedCleanupService {
             }
         }
         
-        // Actually remove the items
+        // Actually remove the items, keeping content that other feeds still link to
         let mut removed_count = 0;
         for item_id in items_to_remove {
-            match FeedItemOpsGeneric::delete(&self.pool, &item_id) {
+            let Some(item) = all_items.iter().find(|item| item.id.as_ref() == Some(&item_id)) else {
+                continue;
+            };
+            match dedup::remove_item(&self.pool, item) {
                 Ok(_) => {
                     removed_count += 1;
                     debug!("Removed feed item: {}", item_id);
@@ -131,17 +224,94 @@ impl FeedCleanupService {
             }
         }
         
-        Ok(CleanupResult {
-            feeds_processed: 1,
-            items_removed: removed_count,
-            errors: 0,
-        })
+        Ok(CleanupResult::of_feed(feed_id, &feed.title, removed_count))
+    }
+    
+    /// Remove the oldest `1 / TIGHTEN_DIVISOR` of every feed's unpinned items,
+    /// keeping at least its `min_items`, to bring storage back under a limit;
+    /// append-only feeds are left alone
+    pub async fn tighten_all_feeds(&self) -> Result<CleanupResult> {
+        let defaults = FeedRetention::load(&self.pool)?;
+        let mut total_result = CleanupResult::default();
+        
+        for feed in FeedOpsGeneric::get_all(&self.pool)?.into_iter().filter(|feed| !feed.append_only) {
+            let feed = defaults.apply(&feed);
+            let Some(feed_id) = feed.id.as_ref() else { continue };
+            let items: Vec<_> = match FeedItemOpsGeneric::get_by_feed_id(&self.pool, feed_id, None) {
+                Ok(items) => items.into_iter().filter(|item| !item.pinned).collect(),
+                Err(e) => {
+                    warn!("Failed to load items of feed '{}' to tighten retention: {}", feed.title, e);
+                    total_result.errors += 1;
+                    continue;
+                }
+            };
+            
+            let min_items = feed.min_items.unwrap_or(0).max(0) as usize;
+            let removable = items.len().saturating_sub(min_items);
+            let to_remove = items.len().div_ceil(TIGHTEN_DIVISOR).min(removable);
+            
+            // Items come newest first, so the oldest are at the end
+            let mut removed_count = 0;
+            for item in items.iter().rev().take(to_remove) {
+                match dedup::remove_item(&self.pool, item) {
+                    Ok(_) => removed_count += 1,
+                    Err(e) => warn!("Failed to remove feed item {:?}: {}", item.id, e),
+                }
+            }
+            if removed_count > 0 {
+                info!("Tightened retention of feed '{}': removed its {} oldest items", feed.title, removed_count);
+            }
+            total_result.feeds_processed += 1;
+            total_result.items_removed += removed_count;
+            total_result.feeds.push(FeedCleanup { feed_id: feed_id.clone(), title: feed.title.clone(), items_removed: removed_count });
+        }
+        self.remove_orphaned_attachments();
+        self.remove_unused_bodies().await;
+        
+        Ok(total_result)
+    }
+    
+    /// Delete the attachments of items removed by any means
+    fn remove_orphaned_attachments(&self) {
+        match AttachmentOpsGeneric::delete_orphaned(&self.pool) {
+            Ok(0) => {}
+            Ok(count) => info!("Removed {} attachments of deleted items", count),
+            Err(e) => warn!("Failed to remove attachments of deleted items: {}", e),
+        }
+    }
+    
+    /// Delete the body blobs of items removed by any means, when bodies are kept in a blob store
+    async fn remove_unused_bodies(&self) {
+        let Some(store) = bodies::store_from_env() else { return };
+        if let Err(e) = bodies::prune(&self.pool, &store).await {
+            warn!("Failed to remove bodies of deleted items: {}", e);
+        }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct CleanupResult {
     pub feeds_processed: usize,
     pub items_removed: usize,
     pub errors: usize,
+    /// Items removed from each feed processed
+    pub feeds: Vec<FeedCleanup>,
+}
+
+impl CleanupResult {
+    fn of_feed(feed_id: &str, title: &str, items_removed: usize) -> Self {
+        Self {
+            feeds_processed: 1,
+            items_removed,
+            errors: 0,
+            feeds: vec![FeedCleanup { feed_id: feed_id.to_string(), title: title.to_string(), items_removed }],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeedCleanup {
+    pub feed_id: String,
+    pub title: String,
+    pub items_removed: usize,
 }
\ No newline at end of file
@@ -0,0 +1,70 @@
+//! Time source for the background services
+//!
+//! The scheduler and the feed cleanup read the time through a [`Clock`] so
+//! tests can drive them with a [`ManualClock`] instead of waiting for real
+//! intervals to pass.
+
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Source of the current time, both monotonic and wall-clock
+pub trait Clock: Send + Sync {
+    /// Monotonic time, used for run intervals and retry delays
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, used for item ages and quota resets
+    fn utc_now(&self) -> DateTime<Utc>;
+}
+
+/// The real time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that stands still until advanced
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    start_utc: DateTime<Utc>,
+    elapsed: Mutex<Duration>,
+}
+
+impl ManualClock {
+    /// A clock reading `start_utc`
+    pub fn new(start_utc: DateTime<Utc>) -> Self {
+        Self {
+            start: Instant::now(),
+            start_utc,
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// Move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        self.start_utc + chrono::Duration::from_std(self.elapsed()).expect("manual clock advanced out of range")
+    }
+}
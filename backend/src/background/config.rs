@@ -1,10 +1,11 @@
 //! Configuration for background email processing
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::time::Duration;
 
 /// Configuration for background email processing
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BackgroundConfig {
     /// Global processing interval (how often to check all accounts)
     pub global_interval_minutes: u64,
@@ -12,12 +13,19 @@ pub struct BackgroundConfig {
     /// Per-account processing interval (minimum time between processing same account)
     pub per_account_interval_minutes: u64,
     
+    /// Quiet period after a rule or feed edit before its folder is re-processed
+    pub change_debounce_seconds: u64,
+    
     /// Maximum number of concurrent account processing tasks
     pub max_concurrent_accounts: usize,
     
     /// Whether background processing is enabled
     pub enabled: bool,
     
+    /// Process accounts whose run was interrupted by a crash right after startup
+    /// instead of waiting for their next regular run
+    pub catch_up_interrupted: bool,
+    
     /// Retry configuration
     pub retry: RetryConfig,
     
@@ -26,7 +34,7 @@ pub struct BackgroundConfig {
 }
 
 /// Retry configuration for failed processing attempts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RetryConfig {
     /// Maximum number of retry attempts
     pub max_attempts: u32,
@@ -42,7 +50,7 @@ pub struct RetryConfig {
 }
 
 /// Processing limits to prevent resource exhaustion
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ProcessingLimits {
     /// Maximum number of emails to process per account per run
     pub max_emails_per_run: usize,
@@ -59,8 +67,10 @@ impl Default for BackgroundConfig {
         Self {
             global_interval_minutes: 15,      // Check all accounts every 15 minutes
             per_account_interval_minutes: 30, // Process same account max once per 30 minutes
+            change_debounce_seconds: 10,      // Re-process edited rules after 10 quiet seconds
             max_concurrent_accounts: 3,       // Process up to 3 accounts simultaneously
             enabled: true,
+            catch_up_interrupted: true,
             retry: RetryConfig::default(),
             limits: ProcessingLimits::default(),
         }
@@ -106,6 +116,12 @@ impl BackgroundConfig {
             }
         }
         
+        if let Ok(debounce) = std::env::var("BACKGROUND_CHANGE_DEBOUNCE_SECONDS") {
+            if let Ok(val) = debounce.parse() {
+                config.change_debounce_seconds = val;
+            }
+        }
+        
         if let Ok(concurrent) = std::env::var("BACKGROUND_MAX_CONCURRENT_ACCOUNTS") {
             if let Ok(val) = concurrent.parse() {
                 config.max_concurrent_accounts = val;
@@ -116,6 +132,10 @@ impl BackgroundConfig {
             config.enabled = enabled.to_lowercase() == "true";
         }
         
+        if let Ok(catch_up) = std::env::var("BACKGROUND_CATCH_UP_INTERRUPTED") {
+            config.catch_up_interrupted = catch_up.to_lowercase() == "true";
+        }
+        
         // Retry configuration
         if let Ok(attempts) = std::env::var("BACKGROUND_RETRY_MAX_ATTEMPTS") {
             if let Ok(val) = attempts.parse() {
@@ -161,6 +181,11 @@ impl BackgroundConfig {
         Duration::from_secs(self.per_account_interval_minutes * 60)
     }
     
+    /// Get the quiet period for rule/feed change debouncing as Duration
+    pub fn change_debounce(&self) -> Duration {
+        Duration::from_secs(self.change_debounce_seconds)
+    }
+    
     /// Get initial retry delay as Duration
     #[allow(dead_code)]
     pub fn initial_retry_delay(&self) -> Duration {
@@ -2,24 +2,27 @@
 //! 
 //! Provides message-based communication between the web API and background service
 
+use crate::background::{changes::RuleChange, config::BackgroundConfig, jobs::JobHandle};
+use crate::db::models::EmailRule;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
-use tracing::info;
+use tracing::{debug, info};
 
 /// Control messages that can be sent to the background service
 #[derive(Debug, Clone)]
 pub enum ControlMessage {
     /// Trigger immediate processing of all accounts
     ProcessAllNow,
-    /// Trigger immediate processing of a specific account
-    ProcessAccountNow { account_id: String },
+    /// Trigger immediate processing of a specific account, reported on `job`
+    ProcessAccountNow { account_id: String, job: Option<JobHandle> },
+    /// A rule or feed reading from this account folder was created or edited
+    RuleChanged(RuleChange),
     /// Pause the background service
     Pause,
     /// Resume the background service
     Resume,
-    /// Reload configuration
-    #[allow(dead_code)]
-    ReloadConfig,
+    /// Apply a new configuration to the running scheduler
+    ReloadConfig(Box<BackgroundConfig>),
     /// Get current status (response sent via response channel)
     GetStatus { response_tx: mpsc::UnboundedSender<ServiceStatusResponse> },
     /// Shutdown the service gracefully
@@ -71,10 +74,30 @@ impl ServiceController {
         self.send_command(ControlMessage::ProcessAllNow).await
     }
     
-    /// Trigger immediate processing of a specific account
-    pub async fn process_account_now(&self, account_id: String) -> Result<(), String> {
+    /// Trigger immediate processing of a specific account, reporting its
+    /// progress and outcome on `job` if given
+    pub async fn process_account_now(&self, account_id: String, job: Option<JobHandle>) -> Result<(), String> {
         info!("Triggering immediate processing of account: {}", account_id);
-        self.send_command(ControlMessage::ProcessAccountNow { account_id }).await
+        self.send_command(ControlMessage::ProcessAccountNow { account_id, job }).await
+    }
+    
+    /// Schedule a debounced re-processing of the folder an edited rule reads from
+    ///
+    /// Inactive rules are ignored. Failures are only logged: the change is
+    /// picked up by the next regular cycle anyway.
+    pub async fn rule_changed(&self, rule: &EmailRule) {
+        if !rule.is_active {
+            return;
+        }
+        
+        let change = RuleChange {
+            account_id: rule.imap_account_id.clone(),
+            folder: rule.folder.clone(),
+        };
+        debug!("Rule change in folder '{}' of account {}", change.folder, change.account_id);
+        if let Err(e) = self.send_command(ControlMessage::RuleChanged(change)).await {
+            debug!("Rule change not delivered to background service: {}", e);
+        }
     }
     
     /// Pause the background service
@@ -91,6 +114,14 @@ impl ServiceController {
         self.send_command(ControlMessage::Resume).await
     }
     
+    /// Apply a new configuration to the running scheduler; invalid
+    /// configurations are logged and ignored
+    #[allow(dead_code)]
+    pub async fn reload_config(&self, config: BackgroundConfig) -> Result<(), String> {
+        info!("Reloading background configuration");
+        self.send_command(ControlMessage::ReloadConfig(Box::new(config))).await
+    }
+    
     /// Get service status
     #[allow(dead_code)]
     pub async fn get_status(&self) -> Result<ServiceStatusResponse, String> {
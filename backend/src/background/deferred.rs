@@ -0,0 +1,203 @@
+//! Post-processing held back by a rule's delay
+//!
+//! A rule with `post_process_delay_hours` leaves the emails it turns into
+//! items untouched, e.g. unread in the inbox for a day, and records the action
+//! it would have applied as a deferred action due once the delay passes, as
+//! do the delayed steps of a post-processing chain. A sweep applies the due
+//! actions in batches per folder, like a run does, and records each with the
+//! run that created the item so rolling that run back reverses it. Actions whose account cannot be reached stay pending for the
+//! next sweep; those the server refuses, or whose email left the folder, are
+//! marked failed.
+
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{DeferredAction, EmailAction, NewProcessingRunAction, ProcessingIntentStatus},
+    operations_generic::{DeferredActionOpsGeneric, ImapAccountOpsGeneric, ProcessingIntentOpsGeneric, ProcessingRunActionOpsGeneric},
+};
+use crate::imap::{post_process::{BatchOutcome, PendingEmail, PostProcessBatch}, ImapClient};
+
+/// Longest delay a rule may set, in hours
+pub const MAX_DELAY_HOURS: i32 = 30 * 24;
+
+/// Apply every due deferred action; returns how many were applied
+pub async fn process_due(pool: &DatabasePool) -> Result<usize> {
+    // One pass at a time, so an action is never applied twice concurrently
+    static RUNNING: Mutex<()> = Mutex::const_new(());
+    let _running = RUNNING.lock().await;
+
+    let due = DeferredActionOpsGeneric::get_due(pool, &Utc::now().to_rfc3339())?;
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let mut by_account: HashMap<String, Vec<DeferredAction>> = HashMap::new();
+    for action in due {
+        by_account.entry(action.imap_account_id.clone()).or_default().push(action);
+    }
+
+    let mut applied = 0;
+    for (account_id, actions) in by_account {
+        let client = match ImapAccountOpsGeneric::get_by_id(pool, &account_id)
+            .and_then(|account| ImapClient::new(&account))
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Holding {} deferred actions of account {}: {}", actions.len(), account_id, e);
+                continue;
+            }
+        };
+        for (batch, actions) in batches(actions) {
+            applied += apply_batch(pool, &client, &batch, &actions).await;
+        }
+    }
+    if applied > 0 {
+        info!("Applied {} deferred post-processing actions", applied);
+    }
+    Ok(applied)
+}
+
+/// Group the actions of one account by what they do to which folder, and by
+/// run so each applied action is recorded with its run. Moves and deletions
+/// come last, so steps of a chain falling due together find their email
+fn batches(actions: Vec<DeferredAction>) -> Vec<(PostProcessBatch, Vec<DeferredAction>)> {
+    let mut batches: Vec<(PostProcessBatch, Vec<DeferredAction>)> = Vec::new();
+    for action in actions {
+        let Ok(uid) = u32::try_from(action.uid) else {
+            warn!("Skipping deferred action {:?} with invalid UID {}", action.id, action.uid);
+            continue;
+        };
+        let email = PendingEmail {
+            uid,
+            message_id: action.email_message_id.clone().unwrap_or_default(),
+            item_id: action.feed_item_id.clone(),
+            intent_id: action.processing_intent_id.clone(),
+        };
+        let same = |(batch, actions): &&mut (PostProcessBatch, Vec<DeferredAction>)| {
+            batch.folder == action.folder
+                && batch.action.as_str() == action.action
+                && batch.target_folder == action.target_folder
+                && actions[0].processing_run_id == action.processing_run_id
+        };
+        match batches.iter_mut().find(same) {
+            Some((batch, actions)) => {
+                batch.emails.push(email);
+                actions.push(action);
+            }
+            None => batches.push((
+                PostProcessBatch {
+                    action: EmailAction::from_str(&action.action),
+                    folder: action.folder.clone(),
+                    target_folder: action.target_folder.clone(),
+                    emails: vec![email],
+                },
+                vec![action],
+            )),
+        }
+    }
+    batches.sort_by_key(|(batch, _)| batch.action.removes_email());
+    batches
+}
+
+/// Apply one batch and settle its actions; returns how many were applied
+async fn apply_batch(pool: &DatabasePool, client: &ImapClient, batch: &PostProcessBatch, actions: &[DeferredAction]) -> usize {
+    let outcome = match client.apply_post_process_batch(batch).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            warn!("Holding deferred {} of {} emails in '{}' for the next sweep: {:#}", batch.action.as_str(), actions.len(), batch.folder, e);
+            return 0;
+        }
+    };
+    let BatchOutcome { applied, failed } = outcome;
+    let failures: HashMap<u32, String> = failed.into_iter().collect();
+
+    for (email, action) in batch.emails.iter().zip(actions) {
+        let action_id = action.id.as_deref().unwrap_or_default();
+        let settled = match failures.get(&email.uid) {
+            None => {
+                let run_action = NewProcessingRunAction::new(
+                    action.processing_run_id.clone(),
+                    Some(email.item_id.clone()),
+                    batch.folder.clone(),
+                    email.uid,
+                    action.email_message_id.clone(),
+                    &batch.action,
+                    batch.target_folder.clone(),
+                );
+                if let Err(e) = ProcessingRunActionOpsGeneric::create(pool, &run_action) {
+                    warn!("Failed to record deferred {} of email UID {} in run {}: {}", batch.action.as_str(), email.uid, action.processing_run_id, e);
+                }
+                resolve_intent(pool, &email.intent_id, ProcessingIntentStatus::Applied, &email.item_id);
+                DeferredActionOpsGeneric::delete(pool, action_id)
+            }
+            Some(reason) => {
+                warn!("⚠️ Failed to apply deferred {} to email UID {} in folder '{}': {}", batch.action.as_str(), email.uid, batch.folder, reason);
+                resolve_intent(pool, &email.intent_id, ProcessingIntentStatus::Failed, &email.item_id);
+                DeferredActionOpsGeneric::mark_failed(pool, action_id, reason)
+            }
+        };
+        if let Err(e) = settled {
+            warn!("Failed to settle deferred action {}: {}", action_id, e);
+        }
+    }
+    debug!("Applied deferred {} to {} of {} emails in '{}'", batch.action.as_str(), applied.len(), actions.len(), batch.folder);
+    applied.len()
+}
+
+fn resolve_intent(pool: &DatabasePool, intent_id: &str, status: ProcessingIntentStatus, item_id: &str) {
+    if let Err(e) = ProcessingIntentOpsGeneric::resolve(pool, intent_id, &status, Some(item_id)) {
+        warn!("Failed to mark processing intent {} {}: {}", intent_id, status.as_str(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(id: &str, run_id: &str, folder: &str, uid: i64, action: &str) -> DeferredAction {
+        DeferredAction {
+            id: Some(id.to_string()),
+            email_rule_id: "rule-1".to_string(),
+            imap_account_id: "account-1".to_string(),
+            processing_run_id: run_id.to_string(),
+            processing_intent_id: format!("intent-{}", id),
+            feed_item_id: format!("item-{}", id),
+            folder: folder.to_string(),
+            uid,
+            email_message_id: None,
+            action: action.to_string(),
+            target_folder: None,
+            due_at: String::new(),
+            status: "pending".to_string(),
+            last_error: None,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_batches_group_by_folder_action_and_run() {
+        let grouped = batches(vec![
+            action("a", "run-1", "INBOX", 3, "mark_read"),
+            action("b", "run-1", "INBOX", 4, "mark_read"),
+            action("c", "run-1", "INBOX", 5, "delete"),
+            action("d", "run-2", "INBOX", 6, "mark_read"),
+            action("e", "run-1", "Lists", 7, "mark_read"),
+            action("f", "run-1", "INBOX", -1, "mark_read"),
+        ]);
+        let summary: Vec<(&str, &str, Vec<u32>)> = grouped.iter()
+            .map(|(batch, actions)| (batch.folder.as_str(), actions[0].processing_run_id.as_str(), batch.uids()))
+            .collect();
+        assert_eq!(summary, [
+            ("INBOX", "run-1", vec![3, 4]),
+            ("INBOX", "run-2", vec![6]),
+            ("Lists", "run-1", vec![7]),
+            ("INBOX", "run-1", vec![5]),
+        ]);
+        assert_eq!(grouped[0].0.emails[1].intent_id, "intent-b");
+    }
+}
@@ -0,0 +1,179 @@
+//! Background jobs started from the API
+//!
+//! Operations that take longer than a request, such as a rule backfill,
+//! processing an account on demand or cleaning up feeds, run as jobs. Each
+//! job is recorded in the `jobs` table as soon as it is requested, so the
+//! caller gets its ID back right away and can poll `GET /api/jobs/:id` for
+//! its status, progress and result. The work itself reports through a
+//! [`JobHandle`], whichever part of the backend does it: the [`JobRunner`]
+//! runs most jobs itself, a few at a time, while process-now jobs are handed
+//! to the scheduler of the background service.
+//!
+//! Jobs still queued or running when the service starts were cut off by a
+//! restart and are marked failed, and finished jobs, like the archives
+//! written by export jobs, are kept for [`JOB_RETENTION_DAYS`].
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{Job, JobStatus, NewJob},
+    operations_generic::JobOpsGeneric,
+};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing::{info, info_span, warn, Instrument};
+
+/// Job kind of processing an account on demand
+pub const PROCESS_ACCOUNT_JOB: &str = "process_account";
+
+/// Jobs the runner works on at the same time; later ones wait queued
+pub const MAX_CONCURRENT_JOBS: usize = 2;
+
+/// Days finished jobs are kept before being deleted at startup
+pub const JOB_RETENTION_DAYS: i64 = 30;
+
+/// Error recorded on jobs found unfinished at startup
+pub const INTERRUPTED_MESSAGE: &str = "Interrupted before finishing; the service stopped while the job was in progress";
+
+/// Runs jobs in the background, at most [`MAX_CONCURRENT_JOBS`] at once
+#[derive(Clone)]
+pub struct JobRunner {
+    pool: DatabasePool,
+    slots: Arc<Semaphore>,
+}
+
+impl JobRunner {
+    pub fn new(pool: DatabasePool) -> Self {
+        Self {
+            pool,
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_JOBS)),
+        }
+    }
+
+    /// Record a queued job for work done elsewhere, which reports through the
+    /// returned handle
+    pub fn create(&self, kind: &str, target_id: Option<String>) -> Result<(Job, JobHandle)> {
+        let job = JobOpsGeneric::create(&self.pool, &NewJob::new(kind, target_id))?;
+        let handle = JobHandle {
+            id: job.id.clone().unwrap_or_default(),
+            pool: self.pool.clone(),
+        };
+        Ok((job, handle))
+    }
+
+    /// Record a queued job and run `work` once a slot is free
+    ///
+    /// The job completes with the value `work` returns as its result, or
+    /// fails with its error.
+    pub fn spawn<F, Fut, T>(&self, kind: &str, target_id: Option<String>, work: F) -> Result<Job>
+    where
+        F: FnOnce(JobHandle) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Serialize,
+    {
+        let (job, handle) = self.create(kind, target_id)?;
+        let slots = self.slots.clone();
+        let span = info_span!("job", job_id = handle.id(), kind);
+        tokio::spawn(async move {
+            let Ok(_slot) = slots.acquire_owned().await else {
+                handle.fail("The job runner is shut down".to_string());
+                return;
+            };
+            handle.start();
+            match work(handle.clone()).await {
+                Ok(result) => handle.complete(&result),
+                Err(e) => {
+                    warn!("Job {} failed: {:#}", handle.id(), e);
+                    handle.fail(format!("{:#}", e));
+                }
+            }
+        }.instrument(span));
+        Ok(job)
+    }
+}
+
+/// Reports the progress and outcome of a job
+///
+/// Progress is best effort: a failure to record it is logged and never
+/// interrupts the work.
+#[derive(Clone)]
+pub struct JobHandle {
+    id: String,
+    pool: DatabasePool,
+}
+
+impl fmt::Debug for JobHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobHandle").field("id", &self.id).finish()
+    }
+}
+
+impl JobHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn start(&self) {
+        self.record("start", JobOpsGeneric::start(&self.pool, &self.id));
+    }
+
+    /// Set the units of work in all, against which progress is measured
+    pub fn set_total(&self, total: usize) {
+        let total = i32::try_from(total).unwrap_or(i32::MAX);
+        self.record("total", JobOpsGeneric::set_total(&self.pool, &self.id, total));
+    }
+
+    pub fn advance(&self, processed: usize) {
+        if processed > 0 {
+            let processed = i32::try_from(processed).unwrap_or(i32::MAX);
+            self.record("progress", JobOpsGeneric::advance(&self.pool, &self.id, processed, None));
+        }
+    }
+
+    /// Describe what the job is doing at the moment
+    pub fn note(&self, message: &str) {
+        self.record("message", JobOpsGeneric::advance(&self.pool, &self.id, 0, Some(message)));
+    }
+
+    pub fn complete<T: Serialize>(&self, result: &T) {
+        let result = serde_json::to_string(result).ok();
+        self.record("completion", JobOpsGeneric::finish(&self.pool, &self.id, &JobStatus::Completed, result, None).map(drop));
+    }
+
+    pub fn fail(&self, error: String) {
+        self.record("failure", JobOpsGeneric::finish(&self.pool, &self.id, &JobStatus::Failed, None, Some(error)).map(drop));
+    }
+
+    fn record(&self, what: &str, outcome: Result<()>) {
+        if let Err(e) = outcome {
+            warn!("Failed to record {} of job {}: {}", what, self.id, e);
+        }
+    }
+}
+
+/// Fail the jobs a previous process left unfinished and delete old ones
+///
+/// Must run before any job starts, as every unfinished job is taken to be
+/// interrupted.
+pub fn recover_interrupted_jobs(pool: &DatabasePool) -> Result<usize> {
+    let failed = JobOpsGeneric::fail_unfinished(pool, INTERRUPTED_MESSAGE)?;
+    if failed > 0 {
+        warn!("Marked {} jobs interrupted by the last shutdown as failed", failed);
+    }
+
+    let cutoff = (Utc::now() - Duration::days(JOB_RETENTION_DAYS)).to_rfc3339();
+    let deleted = JobOpsGeneric::delete_finished_before(pool, &cutoff)?;
+    if deleted > 0 {
+        info!("Deleted {} jobs finished more than {} days ago", deleted, JOB_RETENTION_DAYS);
+    }
+    match crate::feed::export::remove_expired() {
+        Ok(0) => {}
+        Ok(removed) => info!("Removed {} export archives older than {} days", removed, JOB_RETENTION_DAYS),
+        Err(e) => warn!("Failed to remove expired export archives: {}", e),
+    }
+    Ok(failed)
+}
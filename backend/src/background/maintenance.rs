@@ -0,0 +1,168 @@
+use anyhow::Result;
+use crate::background::tasks::TaskHandle;
+use crate::db::{connection::DatabasePool, operations_generic::FeedItemOpsGeneric};
+use crate::feed::{blob::BlobStore, bodies, metadata::ComputedMetadata};
+use tracing::{info, warn};
+
+/// Task kind reported for metadata backfills
+pub const BACKFILL_METADATA_TASK: &str = "backfill_metadata";
+
+/// Task kind reported for moving item bodies to the blob store
+pub const OFFLOAD_BODIES_TASK: &str = "offload_bodies";
+
+/// Number of feed items loaded and updated per batch
+pub const DEFAULT_BACKFILL_BATCH_SIZE: i64 = 200;
+
+/// Fills in computed metadata (body size, content hash, language, read and
+/// starred flags) on feed items created before those fields existed
+pub struct MetadataBackfillService {
+    pool: DatabasePool,
+    batch_size: i64,
+}
+
+impl MetadataBackfillService {
+    pub fn new(pool: DatabasePool) -> Self {
+        Self {
+            pool,
+            batch_size: DEFAULT_BACKFILL_BATCH_SIZE,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Run the backfill to completion, recording the outcome on the task
+    pub async fn run(&self, task: TaskHandle) {
+        match self.backfill(&task).await {
+            Ok(updated) => {
+                info!("Metadata backfill {} complete: {} feed items updated", task.id(), updated);
+                task.complete().await;
+            }
+            Err(e) => {
+                warn!("Metadata backfill {} failed: {}", task.id(), e);
+                task.fail(e.to_string()).await;
+            }
+        }
+    }
+
+    async fn backfill(&self, task: &TaskHandle) -> Result<usize> {
+        let total = FeedItemOpsGeneric::count_missing_metadata(&self.pool)?;
+        task.set_total(total as usize).await;
+        info!("Backfilling metadata for {} feed items", total);
+
+        let mut after_id: Option<String> = None;
+        let mut updated = 0;
+
+        loop {
+            let batch = FeedItemOpsGeneric::get_missing_metadata_batch(&self.pool, after_id.as_deref(), self.batch_size)?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            after_id = last.id.clone();
+
+            let mut batch_updated = 0;
+            for item in &batch {
+                let Some(item_id) = &item.id else {
+                    continue;
+                };
+                let metadata = ComputedMetadata::for_item(item);
+                FeedItemOpsGeneric::update_computed_metadata(
+                    &self.pool,
+                    item_id,
+                    metadata.body_size,
+                    item.content_hash.as_deref().unwrap_or(&metadata.content_hash),
+                    item.language.as_deref().unwrap_or(&metadata.language),
+                    item.is_read.unwrap_or(false),
+                    item.starred.unwrap_or(false),
+                )?;
+                batch_updated += 1;
+            }
+
+            updated += batch_updated;
+            task.advance(batch.len(), batch_updated).await;
+
+            // Let other work on the runtime proceed between batches
+            tokio::task::yield_now().await;
+        }
+
+        Ok(updated)
+    }
+}
+
+/// Moves the bodies of items stored before a blob store was configured out
+/// of the database, then removes blobs no item refers to any longer
+pub struct BodyOffloadService {
+    pool: DatabasePool,
+    store: BlobStore,
+    batch_size: i64,
+    min_bytes: usize,
+}
+
+impl BodyOffloadService {
+    pub fn new(pool: DatabasePool, store: BlobStore) -> Self {
+        Self {
+            pool,
+            store,
+            batch_size: DEFAULT_BACKFILL_BATCH_SIZE,
+            min_bytes: bodies::min_bytes(),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Move only bodies of at least `min_bytes`, instead of `BODY_STORE_MIN_BYTES`
+    pub fn with_min_bytes(mut self, min_bytes: usize) -> Self {
+        self.min_bytes = min_bytes;
+        self
+    }
+
+    /// Run the offload to completion, recording the outcome on the task
+    pub async fn run(&self, task: TaskHandle) {
+        match self.offload(&task).await {
+            Ok(moved) => {
+                info!("Body offload {} complete: {} item bodies moved to {} store", task.id(), moved, self.store.kind());
+                task.complete().await;
+            }
+            Err(e) => {
+                warn!("Body offload {} failed: {}", task.id(), e);
+                task.fail(e.to_string()).await;
+            }
+        }
+    }
+
+    async fn offload(&self, task: &TaskHandle) -> Result<usize> {
+        let total = FeedItemOpsGeneric::count_stored_bodies(&self.pool)?;
+        task.set_total(total as usize).await;
+        info!("Moving bodies of up to {} feed items to {} store", total, self.store.kind());
+
+        let mut after_id: Option<String> = None;
+        let mut moved = 0;
+
+        loop {
+            let batch = FeedItemOpsGeneric::get_stored_bodies_batch(&self.pool, after_id.as_deref(), self.batch_size)?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            after_id = last.id.clone();
+
+            let mut batch_moved = 0;
+            for item in &batch {
+                if bodies::offload(&self.pool, &self.store, item, self.min_bytes).await? {
+                    batch_moved += 1;
+                }
+            }
+
+            moved += batch_moved;
+            task.advance(batch.len(), batch_moved).await;
+            tokio::task::yield_now().await;
+        }
+
+        bodies::prune(&self.pool, &self.store).await?;
+        Ok(moved)
+    }
+}
@@ -0,0 +1,166 @@
+//! Freshness metrics in the Prometheus text format
+//!
+//! A feed that stops getting items usually means a broken rule, and an
+//! account that stops completing runs usually means expired credentials or an
+//! unreachable server. Both show up as growing ages here, which an alert such
+//! as `mail2feed_feed_newest_item_age_seconds > 3 * 86400` can watch:
+//!
+//! - `mail2feed_feed_newest_item_age_seconds`: seconds since the feed's newest
+//!   item was stored, or since the feed was created when it has none yet
+//! - `mail2feed_account_last_success_age_seconds`: seconds since the account's
+//!   last completed processing run, or since the account was created when it
+//!   has not completed one yet
+//!
+//! Both carry the ID and name of the feed or account as labels, and feeds
+//! also carry `active` so paused feeds can be left out of alerts.
+//!
+//! Storage is reported alongside, see [`super::storage`]:
+//!
+//! - `mail2feed_database_bytes`: bytes in use by the database
+//! - `mail2feed_disk_free_bytes`: free bytes on the disk holding the data,
+//!   left out when it cannot be read
+//! - `mail2feed_storage_limit_exceeded`: 1 while a storage limit is crossed
+//!
+//! Rule evaluation cost is counted per rule over every recorded run, see
+//! [`crate::imap::rule_costs`]:
+//!
+//! - `mail2feed_rule_fetch_milliseconds_total`: time spent fetching the
+//!   rule's folder
+//! - `mail2feed_rule_match_microseconds_total`: time spent checking fetched
+//!   emails against the rule
+//! - `mail2feed_rule_evaluations_total`: emails checked against the rule
+//!
+//! Rows purged by retention, see [`super::retention`]:
+//!
+//! - `mail2feed_retention_purged_rows_total`: rows purged from the history
+//!   table named by the `table` label
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+use crate::background::{retention, storage::{self, StorageLimits}};
+use crate::imap::rule_costs::{self, RuleCostSummary};
+use crate::db::{
+    connection::DatabasePool,
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, ProcessingRunOpsGeneric},
+};
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Render the freshness of every feed and account
+pub fn render(pool: &DatabasePool) -> Result<String> {
+    let now = Utc::now();
+    let mut out = String::new();
+
+    let newest_items = FeedItemOpsGeneric::newest_by_feed(pool)?;
+    header(&mut out, "mail2feed_feed_newest_item_age_seconds",
+           "Seconds since the feed's newest item was stored, or since the feed was created without items");
+    for feed in FeedOpsGeneric::get_all(pool)? {
+        let feed_id = feed.id.unwrap_or_default();
+        let newest = newest_items.iter()
+            .find(|(id, _)| *id == feed_id)
+            .and_then(|(_, created_at)| created_at.clone());
+        let Some(age) = age_seconds(newest.as_deref().unwrap_or(&feed.created_at), now) else { continue };
+        let active = if feed.is_active { "true" } else { "false" };
+        sample(&mut out, "mail2feed_feed_newest_item_age_seconds",
+               &[("feed_id", &feed_id), ("feed", &feed.title), ("active", active)], age);
+    }
+
+    let last_completed = ProcessingRunOpsGeneric::last_completed_by_account(pool)?;
+    header(&mut out, "mail2feed_account_last_success_age_seconds",
+           "Seconds since the account's last completed processing run, or since the account was created without one");
+    for account in ImapAccountOpsGeneric::get_all(pool)? {
+        let account_id = account.id.unwrap_or_default();
+        let finished = last_completed.iter()
+            .find(|(id, _)| *id == account_id)
+            .and_then(|(_, finished_at)| finished_at.clone());
+        let Some(age) = age_seconds(finished.as_deref().unwrap_or(&account.created_at), now) else { continue };
+        sample(&mut out, "mail2feed_account_last_success_age_seconds",
+               &[("account_id", &account_id), ("account", &account.name)], age);
+    }
+
+    let status = storage::measure(pool, &StorageLimits::from_env())?;
+    header(&mut out, "mail2feed_database_bytes", "Bytes in use by the database");
+    sample(&mut out, "mail2feed_database_bytes", &[], status.database_bytes);
+    if let Some(free) = status.disk_free_bytes {
+        header(&mut out, "mail2feed_disk_free_bytes", "Free bytes on the disk holding the data");
+        sample(&mut out, "mail2feed_disk_free_bytes", &[], free);
+    }
+    header(&mut out, "mail2feed_storage_limit_exceeded", "1 while the database size or free disk space crosses its limit");
+    sample(&mut out, "mail2feed_storage_limit_exceeded", &[], i64::from(!status.warnings.is_empty()));
+
+    let rules = rule_costs::totals(pool)?;
+    rule_counter(&mut out, &rules, "mail2feed_rule_fetch_milliseconds_total",
+                 "Milliseconds spent fetching the rule's folder", |rule| rule.fetch_ms);
+    rule_counter(&mut out, &rules, "mail2feed_rule_match_microseconds_total",
+                 "Microseconds spent checking fetched emails against the rule", |rule| rule.match_us);
+    rule_counter(&mut out, &rules, "mail2feed_rule_evaluations_total",
+                 "Emails checked against the rule", |rule| rule.evaluations);
+
+    header_of(&mut out, "mail2feed_retention_purged_rows_total", "Rows purged from the history table by retention", "counter");
+    for setting in retention::settings(pool)? {
+        sample(&mut out, "mail2feed_retention_purged_rows_total", &[("table", &setting.table)], setting.rows_purged);
+    }
+
+    Ok(out)
+}
+
+/// Whole seconds from an RFC 3339 timestamp to `now`; `None` when it does not parse
+fn age_seconds(timestamp: &str, now: DateTime<Utc>) -> Option<i64> {
+    let at = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    Some((now - at.with_timezone(&Utc)).num_seconds().max(0))
+}
+
+fn header(out: &mut String, name: &str, help: &str) {
+    header_of(out, name, help, "gauge");
+}
+
+fn header_of(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn rule_counter(out: &mut String, rules: &[RuleCostSummary], name: &str, help: &str, value: impl Fn(&RuleCostSummary) -> i64) {
+    header_of(out, name, help, "counter");
+    for rule in rules {
+        sample(out, name, &[("rule_id", &rule.rule_id), ("rule", &rule.rule_name), ("folder", &rule.folder)], value(rule));
+    }
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: i64) {
+    let labels: Vec<String> = labels.iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+        .collect();
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+    }
+}
+
+/// Escape a label value as the text format requires
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_escapes_label_values() {
+        let mut out = String::new();
+        sample(&mut out, "metric", &[("feed", "Say \"hi\"\\\nbye")], 42);
+        assert_eq!(out, "metric{feed=\"Say \\\"hi\\\"\\\\\\nbye\"} 42\n");
+    }
+
+    #[test]
+    fn test_age_seconds() {
+        let now = DateTime::parse_from_rfc3339("2025-08-24T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(age_seconds("2025-08-21T12:00:00+00:00", now), Some(3 * 86_400));
+        assert_eq!(age_seconds("2025-08-25T12:00:00+00:00", now), Some(0));
+        assert_eq!(age_seconds("yesterday", now), None);
+    }
+}
@@ -4,11 +4,25 @@
 //! continuously in the background, monitoring IMAP accounts and generating
 //! RSS/Atom feeds from new emails.
 
+pub mod backfill;
+pub mod changes;
 pub mod cleanup;
+pub mod clock;
 pub mod config;
 pub mod control;
+pub mod deferred;
+pub mod jobs;
+pub mod maintenance;
+pub mod metrics;
+pub mod notifications;
+pub mod quota;
+pub mod recovery;
+pub mod retention;
+pub mod rollback;
 pub mod scheduler;
 pub mod service;
+pub mod storage;
+pub mod tasks;
 
 pub use config::BackgroundConfig;
 pub use control::ServiceController;
@@ -35,7 +49,16 @@ pub async fn initialize_background_service(
     let (control_tx, control_rx) = tokio::sync::mpsc::unbounded_channel();
     let controller = ServiceController::new(control_tx);
     
-    let service = BackgroundService::new(pool, config, control_rx)?;
+    let mut service = BackgroundService::new(pool.clone(), config, control_rx)?;
+    
+    // Runs left running by a previous process never finish on their own
+    match recovery::recover_interrupted_runs(&pool) {
+        Ok(result) => service.set_interrupted_accounts(result.affected_accounts()),
+        Err(e) => error!("Failed to recover interrupted processing runs: {}", e),
+    }
+    if let Err(e) = jobs::recover_interrupted_jobs(&pool) {
+        error!("Failed to recover interrupted jobs: {}", e);
+    }
     let service_handle = Arc::new(RwLock::new(Some(service)));
     
     info!("Background service initialized successfully");
@@ -72,6 +95,19 @@ pub async fn stop_background_service(handle: &BackgroundServiceHandle) -> anyhow
     Ok(())
 }
 
+/// Apply a new configuration to the background service without restarting
+/// it, returning the configuration now in effect; `None` when the service is
+/// not initialized
+pub async fn update_background_config(handle: &BackgroundServiceHandle, config: BackgroundConfig) -> anyhow::Result<Option<BackgroundConfig>> {
+    let service_guard = handle.service.read().await;
+    
+    let Some(service) = service_guard.as_ref() else {
+        return Ok(None);
+    };
+    service.update_config(config)?;
+    Ok(Some(service.get_config()))
+}
+
 /// Get background service status
 pub async fn get_service_status(handle: &BackgroundServiceHandle) -> Option<service::ServiceStatus> {
     let service_guard = handle.service.read().await;
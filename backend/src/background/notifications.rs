@@ -0,0 +1,523 @@
+//! Alerts for accounts that keep failing
+//!
+//! A run fails when it errors out or any of its rules cannot be processed.
+//! Once an account's runs have failed [`NotificationSettings::failure_threshold`]
+//! times in a row, or right away when a login is refused, an alert goes out
+//! on every configured channel: email over SMTP, a JSON webhook, an ntfy
+//! topic or a Gotify server. One alert is sent per streak of failures; the
+//! next clean run ends the streak.
+//!
+//! The settings are stored as JSON in `app_settings` and changed with
+//! `PUT /api/settings`. Alerts are sent directly rather than through the
+//! delivery queue, which belongs to feeds, and a channel that cannot be
+//! reached is only logged.
+
+use anyhow::Result;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::background::scheduler::AccountState;
+use crate::db::{connection::DatabasePool, models::{AppSetting, ImapAccount}, operations_generic::AppSettingOpsGeneric};
+use crate::feed::{delivery::OutboundRequest, webhook};
+use crate::imap::{client::ImapClientError, processor::ProcessingResult};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Priority of Gotify messages unless a channel sets one; high enough to pop up
+const DEFAULT_GOTIFY_PRIORITY: i32 = 8;
+
+/// When alerts are raised and where they go
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Failed runs in a row that raise an alert for an account
+    pub failure_threshold: u32,
+    /// Alert on the first refused login instead of waiting for the threshold
+    pub alert_on_auth_error: bool,
+    /// Channels every alert goes to; alerts are off without any
+    pub channels: Vec<NotificationChannel>,
+}
+
+impl Default for NotificationSettings {
+    /// Alert after three failed runs or a refused login, once channels are added
+    fn default() -> Self {
+        Self { failure_threshold: 3, alert_on_auth_error: true, channels: Vec::new() }
+    }
+}
+
+/// Kind of a notification channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    Email,
+    Webhook,
+    Ntfy,
+    Gotify,
+}
+
+impl ChannelKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelKind::Email => "email",
+            ChannelKind::Webhook => "webhook",
+            ChannelKind::Ntfy => "ntfy",
+            ChannelKind::Gotify => "gotify",
+        }
+    }
+}
+
+/// How the connection to an SMTP server is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection, port 587 by default
+    #[default]
+    Starttls,
+    /// TLS from the start, port 465 by default
+    Tls,
+    /// No encryption, port 25 by default; for relays on the same host only
+    None,
+}
+
+/// Where alerts go; the fields a kind does not use are ignored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct NotificationChannel {
+    pub kind: ChannelKind,
+    /// Webhook URL, ntfy topic URL such as `https://ntfy.sh/mail2feed`, or
+    /// Gotify server URL
+    #[serde(default)]
+    pub url: Option<String>,
+    /// ntfy access token, or the Gotify application token (required)
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Gotify message priority; defaults to 8
+    #[serde(default)]
+    pub priority: Option<i32>,
+    /// Email: SMTP server to send through
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    /// Email: port of the SMTP server; defaults to the one `smtp_security` uses
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub smtp_security: SmtpSecurity,
+    /// Email: login for the SMTP server; omit to send without one
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_password: Option<String>,
+    /// Email: sender address, e.g. `mail2feed <alerts@example.com>`
+    #[serde(default)]
+    pub email_from: Option<String>,
+    /// Email: recipient addresses
+    #[serde(default)]
+    pub email_to: Vec<String>,
+}
+
+/// Why an account is alerted about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertReason {
+    /// Its runs failed this many times in a row
+    ConsecutiveFailures(u32),
+    /// Its server refused the login
+    AuthenticationFailed,
+    /// Sent by `POST /api/settings/notifications/test`
+    Test,
+}
+
+/// An alert about one account
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub account_id: String,
+    pub account_name: String,
+    pub reason: AlertReason,
+    /// Failed runs in a row so far
+    pub consecutive_failures: u32,
+    /// What the last run failed with
+    pub error: String,
+}
+
+impl Alert {
+    /// Alert sent by `POST /api/settings/notifications/test`
+    pub fn test() -> Self {
+        Self {
+            account_id: String::new(),
+            account_name: "Test account".to_string(),
+            reason: AlertReason::Test,
+            consecutive_failures: 0,
+            error: "This is a test alert; nothing is wrong".to_string(),
+        }
+    }
+
+    /// Name of the alert in webhook bodies
+    pub fn event(&self) -> &'static str {
+        match self.reason {
+            AlertReason::ConsecutiveFailures(_) => "account_failing",
+            AlertReason::AuthenticationFailed => "account_login_failed",
+            AlertReason::Test => "test",
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self.reason {
+            AlertReason::ConsecutiveFailures(runs) => format!("mail2feed: account '{}' failed {} runs in a row", self.account_name, runs),
+            AlertReason::AuthenticationFailed => format!("mail2feed: login to account '{}' failed", self.account_name),
+            AlertReason::Test => "mail2feed: test alert".to_string(),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        format!("{}\n\nLast error: {}", self.title(), self.error)
+    }
+}
+
+/// What went wrong in a run: the error it failed with, or the errors of the
+/// rules it could not process; `None` for a clean run
+pub fn run_problem(result: &Result<ProcessingResult>) -> Option<String> {
+    match result {
+        Ok(result) if result.errors.is_empty() => None,
+        Ok(result) => Some(result.errors.join("; ")),
+        Err(e) => Some(format!("{:#}", e)),
+    }
+}
+
+/// Whether a run's problem is a refused login
+///
+/// Rule errors only reach the scheduler as text, so besides the error type
+/// this recognizes the message an [`ImapClientError::AuthenticationFailed`]
+/// is shown with.
+pub fn is_auth_error(result: &Result<ProcessingResult>, problem: &str) -> bool {
+    let typed = result.as_ref().err().is_some_and(|e| e.chain().any(|cause| {
+        matches!(cause.downcast_ref::<ImapClientError>(), Some(ImapClientError::AuthenticationFailed { .. }))
+    }));
+    typed || problem.contains("Authentication failed for user")
+}
+
+impl NotificationSettings {
+    /// The stored settings, or the built-in ones when none are stored
+    pub fn load(pool: &DatabasePool) -> Result<Self> {
+        match AppSettingOpsGeneric::get(pool, AppSetting::NOTIFICATIONS)? {
+            Some(setting) => Ok(serde_json::from_str(&setting.value)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn store(&self, pool: &DatabasePool) -> Result<()> {
+        AppSettingOpsGeneric::set(pool, AppSetting::NOTIFICATIONS, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// An error message for a threshold below one or a channel missing
+    /// what its kind needs
+    pub fn validate(&self) -> Result<(), String> {
+        if self.failure_threshold < 1 {
+            return Err("failure_threshold must be at least 1".to_string());
+        }
+        for (index, channel) in self.channels.iter().enumerate() {
+            channel.validate().map_err(|e| format!("Channel {} ({}): {}", index + 1, channel.kind.as_str(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Why an account whose runs failed `consecutive_failures` times in a
+    /// row should be alerted about, if it should
+    pub fn alert_reason(&self, consecutive_failures: u32, auth_error: bool) -> Option<AlertReason> {
+        if self.channels.is_empty() {
+            None
+        } else if auth_error && self.alert_on_auth_error {
+            Some(AlertReason::AuthenticationFailed)
+        } else if consecutive_failures >= self.failure_threshold {
+            Some(AlertReason::ConsecutiveFailures(consecutive_failures))
+        } else {
+            None
+        }
+    }
+
+    /// Send `alert` on every channel; the outcome of each, in channel order
+    pub async fn send(&self, alert: &Alert) -> Vec<Result<()>> {
+        let mut outcomes = Vec::new();
+        for (index, channel) in self.channels.iter().enumerate() {
+            let outcome = channel.send(alert).await;
+            if let Err(e) = &outcome {
+                warn!("Failed to send alert '{}' on channel {} ({}): {:#}", alert.title(), index + 1, channel.kind.as_str(), e);
+            }
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+}
+
+impl NotificationChannel {
+    fn validate(&self) -> Result<()> {
+        let required = |value: &Option<String>, field: &str| -> Result<String> {
+            value.as_deref().map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("{} is required", field))
+        };
+        match self.kind {
+            ChannelKind::Webhook | ChannelKind::Ntfy => {
+                webhook::check_url(&required(&self.url, "url")?, "notification URL")?;
+            }
+            ChannelKind::Gotify => {
+                webhook::check_url(&required(&self.url, "url")?, "notification URL")?;
+                required(&self.token, "token")?;
+            }
+            ChannelKind::Email => {
+                required(&self.smtp_host, "smtp_host")?;
+                if self.smtp_username.is_some() != self.smtp_password.is_some() {
+                    anyhow::bail!("smtp_username and smtp_password go together");
+                }
+                required(&self.email_from, "email_from")?.parse::<Mailbox>()
+                    .map_err(|e| anyhow::anyhow!("Invalid email_from: {}", e))?;
+                if self.email_to.is_empty() {
+                    anyhow::bail!("email_to needs at least one address");
+                }
+                for to in &self.email_to {
+                    to.parse::<Mailbox>().map_err(|e| anyhow::anyhow!("Invalid email_to address '{}': {}", to, e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The HTTP request that delivers `alert` on a webhook, ntfy or Gotify channel
+    pub fn request(&self, alert: &Alert) -> Result<OutboundRequest> {
+        let url = self.url.as_deref().unwrap_or_default().trim();
+        let request = match self.kind {
+            ChannelKind::Webhook => OutboundRequest::json(Method::POST, url, json!({
+                "event": alert.event(),
+                "account": { "id": alert.account_id, "name": alert.account_name },
+                "consecutive_failures": alert.consecutive_failures,
+                "error": alert.error,
+                "title": alert.title(),
+                "message": alert.message(),
+            }).to_string()),
+            ChannelKind::Ntfy => {
+                let request = OutboundRequest {
+                    method: Method::POST,
+                    url: url.to_string(),
+                    headers: vec![("Content-Type".to_string(), "text/plain; charset=utf-8".to_string())],
+                    body: alert.message(),
+                };
+                // Header values must be ASCII; ntfy decodes RFC 2047 titles
+                let request = request
+                    .header("Title", format!("=?UTF-8?B?{}?=", base64::Engine::encode(&base64::engine::general_purpose::STANDARD, alert.title())))
+                    .header("Tags", "warning".to_string());
+                match self.token.as_deref().filter(|token| !token.is_empty()) {
+                    Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+                    None => request,
+                }
+            }
+            ChannelKind::Gotify => {
+                let mut endpoint = Url::parse(url)?;
+                endpoint.path_segments_mut().map_err(|_| anyhow::anyhow!("Invalid Gotify URL '{}'", url))?
+                    .pop_if_empty().push("message");
+                OutboundRequest::json(Method::POST, endpoint.to_string(), json!({
+                    "title": alert.title(),
+                    "message": alert.message(),
+                    "priority": self.priority.unwrap_or(DEFAULT_GOTIFY_PRIORITY),
+                }).to_string())
+                .header("X-Gotify-Key", self.token.clone().unwrap_or_default())
+            }
+            ChannelKind::Email => anyhow::bail!("Email alerts are not sent over HTTP"),
+        };
+        Ok(request)
+    }
+
+    /// The email that delivers `alert` on an email channel
+    pub fn email(&self, alert: &Alert) -> Result<Message> {
+        let mut message = Message::builder()
+            .from(self.email_from.as_deref().unwrap_or_default().parse()?)
+            .subject(alert.title())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.email_to {
+            message = message.to(to.parse()?);
+        }
+        Ok(message.body(alert.message())?)
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        if self.kind != ChannelKind::Email {
+            self.request(alert)?.send().await?;
+            return Ok(());
+        }
+
+        let host = self.smtp_host.as_deref().unwrap_or_default().trim();
+        let builder = match self.smtp_security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host).port(25),
+        };
+        let mut builder = builder.timeout(Some(SMTP_TIMEOUT));
+        if let Some(port) = self.smtp_port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&self.smtp_username, &self.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        builder.build().send(self.email(alert)?).await?;
+        Ok(())
+    }
+}
+
+/// Count a finished run of `account` towards its failure streak, and send an
+/// alert in the background once the streak calls for one
+///
+/// Called by the scheduler with the account's state locked, after the run
+/// was recorded.
+pub fn after_run(pool: &DatabasePool, account: &ImapAccount, state: &mut AccountState, result: &Result<ProcessingResult>) {
+    let Some(problem) = run_problem(result) else {
+        state.failing_runs = 0;
+        state.failure_alerted = false;
+        return;
+    };
+    state.failing_runs += 1;
+    if state.failure_alerted {
+        return;
+    }
+
+    let settings = match NotificationSettings::load(pool) {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Failed to load notification settings: {}", e);
+            return;
+        }
+    };
+    let Some(reason) = settings.alert_reason(state.failing_runs, is_auth_error(result, &problem)) else {
+        return;
+    };
+    state.failure_alerted = true;
+
+    let alert = Alert {
+        account_id: account.id.clone().unwrap_or_default(),
+        account_name: account.name.clone(),
+        reason,
+        consecutive_failures: state.failing_runs,
+        error: problem,
+    };
+    info!("Alerting on {} channels: {}", settings.channels.len(), alert.title());
+    tokio::spawn(async move {
+        settings.send(&alert).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(kind: ChannelKind) -> NotificationChannel {
+        NotificationChannel {
+            kind,
+            url: None,
+            token: None,
+            priority: None,
+            smtp_host: None,
+            smtp_port: None,
+            smtp_security: SmtpSecurity::default(),
+            smtp_username: None,
+            smtp_password: None,
+            email_from: None,
+            email_to: Vec::new(),
+        }
+    }
+
+    fn alert(reason: AlertReason) -> Alert {
+        Alert {
+            account_id: "account-1".to_string(),
+            account_name: "Work".to_string(),
+            reason,
+            consecutive_failures: 3,
+            error: "Processing timeout".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_alert_reasons() {
+        let mut settings = NotificationSettings::default();
+        assert_eq!(settings.alert_reason(5, true), None, "no channels, no alerts");
+
+        settings.channels.push(NotificationChannel { url: Some("https://ntfy.sh/alerts".to_string()), ..channel(ChannelKind::Ntfy) });
+        assert_eq!(settings.alert_reason(2, false), None);
+        assert_eq!(settings.alert_reason(3, false), Some(AlertReason::ConsecutiveFailures(3)));
+        assert_eq!(settings.alert_reason(1, true), Some(AlertReason::AuthenticationFailed));
+
+        settings.alert_on_auth_error = false;
+        assert_eq!(settings.alert_reason(1, true), None);
+    }
+
+    #[test]
+    fn test_auth_errors_are_recognized() {
+        let refused: Result<ProcessingResult> = Ok(ProcessingResult {
+            errors: vec!["Rule 'News': Authentication failed for user 'me' - NO [AUTHENTICATIONFAILED]".to_string()],
+            ..Default::default()
+        });
+        let problem = run_problem(&refused).unwrap();
+        assert!(is_auth_error(&refused, &problem));
+
+        let typed: Result<ProcessingResult> = Err(anyhow::Error::new(ImapClientError::AuthenticationFailed {
+            username: "me".to_string(),
+            source: "bad password".to_string(),
+        }).context("Connecting"));
+        assert!(is_auth_error(&typed, "Connecting"));
+
+        let timeout: Result<ProcessingResult> = Err(anyhow::anyhow!("Processing timeout"));
+        assert!(!is_auth_error(&timeout, &run_problem(&timeout).unwrap()));
+        assert_eq!(run_problem(&Ok(ProcessingResult::default())), None);
+    }
+
+    #[test]
+    fn test_channels_are_validated() {
+        let mut settings = NotificationSettings { channels: vec![channel(ChannelKind::Gotify)], ..Default::default() };
+        assert!(settings.validate().unwrap_err().contains("url is required"));
+
+        settings.channels[0].url = Some("https://gotify.example.com".to_string());
+        assert!(settings.validate().unwrap_err().contains("token is required"));
+        settings.channels[0].token = Some("app-token".to_string());
+        assert!(settings.validate().is_ok());
+
+        settings.channels.push(NotificationChannel {
+            smtp_host: Some("smtp.example.com".to_string()),
+            email_from: Some("mail2feed <alerts@example.com>".to_string()),
+            email_to: vec!["not an address".to_string()],
+            ..channel(ChannelKind::Email)
+        });
+        assert!(settings.validate().unwrap_err().starts_with("Channel 2 (email)"));
+        settings.channels[1].email_to = vec!["admin@example.com".to_string()];
+        assert!(settings.validate().is_ok());
+
+        settings.failure_threshold = 0;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_channel_requests() {
+        let gotify = NotificationChannel {
+            url: Some("https://gotify.example.com/".to_string()),
+            token: Some("app-token".to_string()),
+            ..channel(ChannelKind::Gotify)
+        };
+        let request = gotify.request(&alert(AlertReason::ConsecutiveFailures(3))).unwrap();
+        assert_eq!(request.url, "https://gotify.example.com/message");
+        assert!(request.headers.contains(&("X-Gotify-Key".to_string(), "app-token".to_string())));
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["title"], "mail2feed: account 'Work' failed 3 runs in a row");
+        assert_eq!(body["priority"], 8);
+
+        let email = NotificationChannel {
+            email_from: Some("alerts@example.com".to_string()),
+            email_to: vec!["admin@example.com".to_string()],
+            ..channel(ChannelKind::Email)
+        };
+        let message = String::from_utf8(email.email(&alert(AlertReason::AuthenticationFailed)).unwrap().formatted()).unwrap();
+        assert!(message.contains("Subject: mail2feed: login to account 'Work' failed"), "{}", message);
+        assert!(message.contains("Last error: Processing timeout"), "{}", message);
+    }
+}
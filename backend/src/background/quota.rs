@@ -0,0 +1,260 @@
+//! Resource quotas for shared deployments
+//!
+//! Limits on feeds, stored items and processing minutes per UTC day can be
+//! set on an IMAP account, on the quota group it belongs to, or both. An
+//! account limit caps that account alone; a group limit caps the total of all
+//! accounts in the group. Unset limits do not apply.
+//!
+//! The API refuses new feeds over a limit, and processing stops creating
+//! items once the item limit is reached and does not start once the day's
+//! processing minutes are used up. Emails left unprocessed stay in the
+//! mailbox and are picked up when the limit is raised or items expire.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{ImapAccount, ProcessingRun},
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, ProcessingRunOpsGeneric, QuotaGroupOpsGeneric},
+};
+
+/// A resource limited by quotas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaResource {
+    Feeds,
+    Items,
+    ProcessingMinutes,
+}
+
+impl QuotaResource {
+    fn describe(&self, count: i64) -> String {
+        match self {
+            QuotaResource::Feeds => format!("{} feeds", count),
+            QuotaResource::Items => format!("{} stored items", count),
+            QuotaResource::ProcessingMinutes => format!("{} processing minutes per day", count),
+        }
+    }
+}
+
+/// Resources used by an account or quota group
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QuotaUsage {
+    pub feeds: i64,
+    pub items: i64,
+    /// Minutes spent processing since midnight UTC
+    pub processing_minutes_today: i64,
+}
+
+/// A limit that has been reached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// `account 'name'` or `quota group 'name'`
+    pub scope: String,
+    pub resource: QuotaResource,
+    pub limit: i64,
+    pub used: i64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Quota exceeded: {} allows {} and has used {}",
+               self.scope, self.resource.describe(self.limit), self.used)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+/// How many more items an account may store before a limit is reached
+#[derive(Debug, Clone)]
+pub struct ItemAllowance {
+    pub remaining: i64,
+    scope: String,
+    limit: i64,
+}
+
+impl ItemAllowance {
+    /// The error reported once the allowance is used up
+    pub fn exceeded(&self) -> QuotaExceeded {
+        QuotaExceeded {
+            scope: self.scope.clone(),
+            resource: QuotaResource::Items,
+            limit: self.limit,
+            used: self.limit - self.remaining.min(0),
+        }
+    }
+}
+
+/// Reject negative limits; zero allows nothing
+pub fn validate_limits(max_feeds: Option<i32>, max_items: Option<i32>, max_processing_minutes_per_day: Option<i32>) -> Result<()> {
+    for (name, limit) in [
+        ("max_feeds", max_feeds),
+        ("max_items", max_items),
+        ("max_processing_minutes_per_day", max_processing_minutes_per_day),
+    ] {
+        if limit.is_some_and(|limit| limit < 0) {
+            anyhow::bail!("{} must not be negative", name);
+        }
+    }
+    Ok(())
+}
+
+/// Limits applying to a set of accounts
+struct Scope {
+    name: String,
+    account_ids: Vec<String>,
+    max_feeds: Option<i32>,
+    max_items: Option<i32>,
+    max_processing_minutes_per_day: Option<i32>,
+}
+
+impl Scope {
+    fn limit(&self, resource: QuotaResource) -> Option<i64> {
+        match resource {
+            QuotaResource::Feeds => self.max_feeds,
+            QuotaResource::Items => self.max_items,
+            QuotaResource::ProcessingMinutes => self.max_processing_minutes_per_day,
+        }
+        .map(i64::from)
+    }
+}
+
+/// The account's own limits and those of its quota group
+fn scopes(pool: &DatabasePool, account: &ImapAccount) -> Result<Vec<Scope>> {
+    let account_id = account.id.clone().unwrap_or_default();
+    let mut scopes = vec![Scope {
+        name: format!("account '{}'", account.name),
+        account_ids: vec![account_id],
+        max_feeds: account.max_feeds,
+        max_items: account.max_items,
+        max_processing_minutes_per_day: account.max_processing_minutes_per_day,
+    }];
+
+    if let Some(group_id) = &account.quota_group_id {
+        // A group deleted in the meantime no longer limits anything
+        if let Ok(group) = QuotaGroupOpsGeneric::get_by_id(pool, group_id) {
+            let account_ids = ImapAccountOpsGeneric::get_by_quota_group(pool, group_id)?
+                .into_iter()
+                .filter_map(|member| member.id)
+                .collect();
+            scopes.push(Scope {
+                name: format!("quota group '{}'", group.name),
+                account_ids,
+                max_feeds: group.max_feeds,
+                max_items: group.max_items,
+                max_processing_minutes_per_day: group.max_processing_minutes_per_day,
+            });
+        }
+    }
+    Ok(scopes)
+}
+
+fn used(pool: &DatabasePool, account_ids: &[String], resource: QuotaResource) -> Result<i64> {
+    match resource {
+        QuotaResource::Feeds => FeedOpsGeneric::count_by_account_ids(pool, account_ids),
+        QuotaResource::Items => FeedItemOpsGeneric::count_by_account_ids(pool, account_ids),
+        QuotaResource::ProcessingMinutes => processing_minutes_today(pool, account_ids),
+    }
+}
+
+/// Resources used by a set of accounts, e.g. the members of a quota group
+pub fn usage(pool: &DatabasePool, account_ids: &[String]) -> Result<QuotaUsage> {
+    Ok(QuotaUsage {
+        feeds: used(pool, account_ids, QuotaResource::Feeds)?,
+        items: used(pool, account_ids, QuotaResource::Items)?,
+        processing_minutes_today: used(pool, account_ids, QuotaResource::ProcessingMinutes)?,
+    })
+}
+
+/// Fail with [`QuotaExceeded`] when the account, or its group, has no room
+/// for one more of `resource`
+pub fn check(pool: &DatabasePool, account: &ImapAccount, resource: QuotaResource) -> Result<()> {
+    check_scopes(pool, account, resource, None, 1)
+}
+
+/// Like [`check`], for `count` more of `resource` at once; the account need
+/// not be stored yet
+pub fn check_room(pool: &DatabasePool, account: &ImapAccount, resource: QuotaResource, count: i64) -> Result<()> {
+    check_scopes(pool, account, resource, None, count)
+}
+
+/// Like [`check`], for moving one of `resource` to the account from the
+/// account `from_account_id`; limits covering both accounts are unaffected
+pub fn check_transfer(pool: &DatabasePool, account: &ImapAccount, from_account_id: &str, resource: QuotaResource) -> Result<()> {
+    check_scopes(pool, account, resource, Some(from_account_id), 1)
+}
+
+fn check_scopes(pool: &DatabasePool, account: &ImapAccount, resource: QuotaResource, from_account_id: Option<&str>, count: i64) -> Result<()> {
+    for scope in scopes(pool, account)? {
+        if from_account_id.is_some_and(|from| scope.account_ids.iter().any(|id| id == from)) {
+            continue;
+        }
+        let Some(limit) = scope.limit(resource) else { continue };
+        let used = used(pool, &scope.account_ids, resource)?;
+        if used + count > limit {
+            return Err(QuotaExceeded { scope: scope.name, resource, limit, used }.into());
+        }
+    }
+    Ok(())
+}
+
+/// The tightest item limit on the account; `None` when items are unlimited
+pub fn item_allowance(pool: &DatabasePool, account: &ImapAccount) -> Result<Option<ItemAllowance>> {
+    let mut allowance: Option<ItemAllowance> = None;
+    for scope in scopes(pool, account)? {
+        let Some(limit) = scope.limit(QuotaResource::Items) else { continue };
+        let remaining = limit - used(pool, &scope.account_ids, QuotaResource::Items)?;
+        if allowance.as_ref().is_none_or(|tightest| remaining < tightest.remaining) {
+            allowance = Some(ItemAllowance { remaining, scope: scope.name, limit });
+        }
+    }
+    Ok(allowance)
+}
+
+/// Start of the next UTC day, when the processing minutes quota resets
+pub fn next_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    start_of_day(now) + Duration::days(1)
+}
+
+fn start_of_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+fn processing_minutes_today(pool: &DatabasePool, account_ids: &[String]) -> Result<i64> {
+    let now = Utc::now();
+    let since = start_of_day(now).to_rfc3339();
+    let runs = ProcessingRunOpsGeneric::get_started_since(pool, account_ids, &since)?;
+    Ok(runs.iter().map(|run| run_seconds(run, now)).sum::<i64>() / 60)
+}
+
+/// Duration of a run; runs still going count up to now
+fn run_seconds(run: &ProcessingRun, now: DateTime<Utc>) -> i64 {
+    let parse = |value: &str| DateTime::parse_from_rfc3339(value).ok().map(|at| at.with_timezone(&Utc));
+    let Some(started) = parse(&run.started_at) else { return 0 };
+    let finished = run.finished_at.as_deref().and_then(parse).unwrap_or(now);
+    (finished - started).num_seconds().max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_exceeded_message() {
+        let exceeded = QuotaExceeded {
+            scope: "quota group 'Kids'".to_string(),
+            resource: QuotaResource::ProcessingMinutes,
+            limit: 30,
+            used: 31,
+        };
+        assert_eq!(exceeded.to_string(), "Quota exceeded: quota group 'Kids' allows 30 processing minutes per day and has used 31");
+    }
+
+    #[test]
+    fn test_next_reset_is_next_utc_midnight() {
+        let now = DateTime::parse_from_rfc3339("2025-08-23T17:45:12Z").unwrap().with_timezone(&Utc);
+        assert_eq!(next_reset(now).to_rfc3339(), "2025-08-24T00:00:00+00:00");
+    }
+}
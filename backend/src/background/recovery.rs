@@ -0,0 +1,100 @@
+//! Startup recovery of interrupted processing runs
+//!
+//! A processing run stays in the `running` state until the processor finishes
+//! it, so a run still running when the service starts was cut off by a crash
+//! or a kill. Such runs are marked `aborted`: the items and mailbox actions
+//! recorded up to that point are kept, and the run can be rolled back like a
+//! finished one. The scheduler then processes the affected accounts again
+//! right away (unless `BACKGROUND_CATCH_UP_INTERRUPTED=false`), with duplicate
+//! detection skipping the emails the aborted run already turned into items.
+//!
+//! Before creating an item and post-processing its email, a run logs a
+//! processing intent holding a snapshot of the email. Intents an interrupted
+//! run left pending are reconciled here: when the feed lacks the item, which
+//! the next run could not recreate if the email was already moved or deleted,
+//! the item is rebuilt from the snapshot.
+
+use anyhow::Result;
+use tracing::{info, warn};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{ProcessingIntentStatus, ProcessingRun},
+    operations_generic::{ImapAccountOpsGeneric, ProcessingIntentOpsGeneric, ProcessingRunOpsGeneric},
+};
+use crate::imap::EmailProcessor;
+
+/// Error message recorded on aborted runs
+pub const ABORTED_MESSAGE: &str = "Interrupted before finishing; the service stopped while the run was in progress";
+
+/// Runs found interrupted at startup
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryResult {
+    /// The runs now marked aborted, oldest first
+    pub aborted_runs: Vec<ProcessingRun>,
+    /// Feed items rebuilt from the snapshots of pending intents
+    pub recovered_items: usize,
+    /// Pending intents whose feed item was already in place
+    pub reconciled_intents: usize,
+}
+
+impl RecoveryResult {
+    /// Accounts with an aborted run, each listed once
+    pub fn affected_accounts(&self) -> Vec<String> {
+        let mut accounts: Vec<String> = Vec::new();
+        for run in &self.aborted_runs {
+            if !accounts.contains(&run.imap_account_id) {
+                accounts.push(run.imap_account_id.clone());
+            }
+        }
+        accounts
+    }
+}
+
+/// Mark the runs left running by a previous process as aborted
+///
+/// Must run before any processing starts, as every run in the running state
+/// is taken to be interrupted.
+pub fn recover_interrupted_runs(pool: &DatabasePool) -> Result<RecoveryResult> {
+    let aborted_runs = ProcessingRunOpsGeneric::abort_running(pool, ABORTED_MESSAGE)?;
+    for run in &aborted_runs {
+        warn!(
+            "Processing run {} of account {} (started {}) was interrupted; marked aborted",
+            run.id.as_deref().unwrap_or_default(),
+            run.imap_account_id,
+            run.started_at
+        );
+    }
+    if !aborted_runs.is_empty() {
+        info!("Recovered {} interrupted processing runs", aborted_runs.len());
+    }
+    
+    let mut result = RecoveryResult { aborted_runs, ..Default::default() };
+    reconcile_pending_intents(pool, &mut result)?;
+    Ok(result)
+}
+
+/// Settle the intents interrupted runs left pending; an intent that cannot
+/// be settled stays pending and is retried on the next start
+fn reconcile_pending_intents(pool: &DatabasePool, result: &mut RecoveryResult) -> Result<()> {
+    for intent in ProcessingIntentOpsGeneric::get_pending(pool)? {
+        let recovered = ProcessingRunOpsGeneric::get_by_id(pool, &intent.processing_run_id)
+            .and_then(|run| ImapAccountOpsGeneric::get_by_id(pool, &run.imap_account_id))
+            .and_then(|account| EmailProcessor::new(account, pool.clone()).recover_intent(&intent));
+        match recovered {
+            Ok(ProcessingIntentStatus::Recovered) => result.recovered_items += 1,
+            Ok(_) => result.reconciled_intents += 1,
+            Err(e) => warn!(
+                "Could not reconcile processing intent {} for email UID {} in '{}': {}",
+                intent.id.as_deref().unwrap_or_default(),
+                intent.uid,
+                intent.folder,
+                e
+            ),
+        }
+    }
+    if result.recovered_items > 0 {
+        info!("Rebuilt {} feed items lost by interrupted runs", result.recovered_items);
+    }
+    Ok(())
+}
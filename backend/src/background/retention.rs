@@ -0,0 +1,144 @@
+//! Retention of history tables
+//!
+//! Every run adds to the processing history, observe-only rules record their
+//! matches, webhooks and chat integrations queue deliveries and each rule's
+//! evaluation cost is kept per run. A retention policy per table keeps its
+//! newest `max_rows` rows, drops rows older than `max_age_days`, or both;
+//! tables without a policy keep everything. The daily cleanup enforces the
+//! policies and adds the rows it purges to each policy's `rows_purged`, which
+//! the metrics report as `mail2feed_retention_purged_rows_total`.
+//!
+//! Rows still in use are never purged: runs that have not finished, that
+//! left intents to recover or whose actions wait on a post-process delay,
+//! and deliveries still queued. A purged run takes its actions, intents and
+//! rule costs with it; its items stay and can no longer be rolled back.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{RetentionCandidate, RetentionTarget},
+    operations_generic::RetentionPolicyOpsGeneric,
+};
+
+/// Retention of one history table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RetentionSetting {
+    /// `processing_runs`, `rule_matches`, `deliveries` or `rule_costs`
+    pub table: String,
+    /// Rows kept, newest first; unlimited when null
+    pub max_rows: Option<i32>,
+    /// Days rows are kept; unlimited when null
+    pub max_age_days: Option<i32>,
+    /// Rows purged from the table so far
+    pub rows_purged: i64,
+    pub last_purged_at: Option<String>,
+}
+
+/// The retention of every history table, unlimited for those without a policy
+pub fn settings(pool: &DatabasePool) -> Result<Vec<RetentionSetting>> {
+    let policies = RetentionPolicyOpsGeneric::get_all(pool)?;
+    Ok(RetentionTarget::ALL
+        .iter()
+        .map(|target| {
+            let policy = policies.iter().find(|policy| policy.target == target.as_str());
+            RetentionSetting {
+                table: target.as_str().to_string(),
+                max_rows: policy.and_then(|policy| policy.max_rows),
+                max_age_days: policy.and_then(|policy| policy.max_age_days),
+                rows_purged: policy.map_or(0, |policy| policy.rows_purged),
+                last_purged_at: policy.and_then(|policy| policy.last_purged_at.clone()),
+            }
+        })
+        .collect())
+}
+
+/// IDs of the candidates, newest first, that fall outside the limits at `now`
+pub fn expired(candidates: &[RetentionCandidate], max_rows: Option<i32>, max_age_days: Option<i32>, now: DateTime<Utc>) -> Vec<String> {
+    let cutoff = max_age_days.map(|days| now - Duration::days(i64::from(days)));
+    candidates
+        .iter()
+        .enumerate()
+        .filter(|(index, (_, timestamp))| {
+            let too_many = max_rows.is_some_and(|max_rows| *index >= max_rows.max(0) as usize);
+            let too_old = cutoff.is_some_and(|cutoff| {
+                DateTime::parse_from_rfc3339(timestamp).is_ok_and(|at| at.with_timezone(&Utc) < cutoff)
+            });
+            too_many || too_old
+        })
+        .map(|(_, (id, _))| id.clone())
+        .collect()
+}
+
+/// Enforce every policy, returning the rows purged per table
+///
+/// A table that fails is logged and skipped so the others are still purged.
+pub fn purge(pool: &DatabasePool, now: DateTime<Utc>) -> Result<Vec<(RetentionTarget, usize)>> {
+    let mut purged = Vec::new();
+    for policy in RetentionPolicyOpsGeneric::get_all(pool)? {
+        let Some(target) = RetentionTarget::parse(&policy.target) else {
+            warn!("Skipping retention policy of unknown table '{}'", policy.target);
+            continue;
+        };
+        if policy.max_rows.is_none() && policy.max_age_days.is_none() {
+            continue;
+        }
+        match purge_target(pool, target, policy.max_rows, policy.max_age_days, now) {
+            Ok(0) => {}
+            Ok(rows) => {
+                info!("Retention purged {} rows from {}", rows, target.as_str());
+                purged.push((target, rows));
+            }
+            Err(e) => warn!("Failed to apply retention to {}: {}", target.as_str(), e),
+        }
+    }
+    Ok(purged)
+}
+
+fn purge_target(pool: &DatabasePool, target: RetentionTarget, max_rows: Option<i32>, max_age_days: Option<i32>, now: DateTime<Utc>) -> Result<usize> {
+    let candidates = RetentionPolicyOpsGeneric::candidates(pool, target)?;
+    let ids = expired(&candidates, max_rows, max_age_days, now);
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let rows = RetentionPolicyOpsGeneric::delete(pool, target, &ids)?;
+    RetentionPolicyOpsGeneric::record_purge(pool, target, rows as i64)?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(now: DateTime<Utc>, ages_in_days: &[i64]) -> Vec<RetentionCandidate> {
+        ages_in_days
+            .iter()
+            .enumerate()
+            .map(|(n, days)| (format!("row-{}", n), (now - Duration::days(*days)).to_rfc3339()))
+            .collect()
+    }
+
+    #[test]
+    fn test_expired_by_rows_and_age() {
+        let now = Utc::now();
+        let rows = candidates(now, &[0, 1, 5, 40, 90]);
+
+        assert!(expired(&rows, None, None, now).is_empty());
+        assert_eq!(expired(&rows, Some(3), None, now), ["row-3", "row-4"]);
+        assert_eq!(expired(&rows, None, Some(30), now), ["row-3", "row-4"]);
+        assert_eq!(expired(&rows, Some(2), Some(60), now), ["row-2", "row-3", "row-4"]);
+        assert_eq!(expired(&rows, Some(0), None, now).len(), 5);
+    }
+
+    #[test]
+    fn test_unparseable_timestamps_only_count_against_rows() {
+        let now = Utc::now();
+        let rows = vec![("new".to_string(), "soon".to_string()), ("old".to_string(), "2000-01-01T00:00:00Z".to_string())];
+        assert_eq!(expired(&rows, None, Some(1), now), ["old"]);
+        assert_eq!(expired(&rows, Some(1), None, now), ["old"]);
+    }
+}
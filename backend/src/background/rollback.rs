@@ -0,0 +1,153 @@
+use anyhow::Result;
+use crate::db::{
+    connection::DatabasePool,
+    models::{EmailAction, ProcessingRun, ProcessingRunAction, ProcessingRunStatus},
+    operations_generic::{DeferredActionOpsGeneric, FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, ProcessingRunActionOpsGeneric, ProcessingRunOpsGeneric},
+};
+use crate::feed::dedup;
+use crate::imap::ImapClient;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+use tracing::{info, warn, debug};
+
+pub struct RunRollbackService {
+    pool: DatabasePool,
+}
+
+impl RunRollbackService {
+    pub fn new(pool: DatabasePool) -> Self {
+        Self { pool }
+    }
+
+    /// Roll back a finished processing run: reverse its post-processing actions
+    /// where the mailbox still allows it, then remove the feed items it created
+    pub async fn rollback_run(&self, run: &ProcessingRun) -> Result<RollbackResult> {
+        let run_id = run.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Processing run has no ID"))?;
+
+        match ProcessingRunStatus::from_str(&run.status) {
+            ProcessingRunStatus::Running => {
+                return Err(anyhow::anyhow!("Processing run {} is still running", run_id));
+            }
+            ProcessingRunStatus::RolledBack => {
+                return Err(anyhow::anyhow!("Processing run {} has already been rolled back", run_id));
+            }
+            _ => {}
+        }
+
+        info!("Rolling back processing run {}", run_id);
+
+        let mut result = RollbackResult {
+            run_id: run_id.clone(),
+            ..Default::default()
+        };
+
+        // Actions still waiting for their rule's delay are simply never applied
+        result.deferred_actions_cancelled = DeferredActionOpsGeneric::delete_pending_by_run_id(&self.pool, run_id)?;
+
+        let actions = ProcessingRunActionOpsGeneric::get_by_run_id(&self.pool, run_id)?;
+        if !actions.is_empty() {
+            self.reverse_actions(run, &actions, &mut result).await;
+        }
+
+        // Items of append-only feeds stay, and are reported as kept
+        let append_only: HashSet<String> = FeedOpsGeneric::get_all(&self.pool)?
+            .into_iter()
+            .filter(|feed| feed.append_only)
+            .filter_map(|feed| feed.id)
+            .collect();
+        for item in FeedItemOpsGeneric::get_by_processing_run_id(&self.pool, run_id)? {
+            if append_only.contains(&item.feed_id) {
+                result.errors.push(format!("Kept item '{}': its feed is append-only", item.title));
+                continue;
+            }
+            match dedup::remove_item(&self.pool, &item) {
+                Ok(()) => result.items_removed += 1,
+                Err(e) => result.errors.push(format!("Failed to remove item '{}': {}", item.title, e)),
+            }
+        }
+        ProcessingRunOpsGeneric::update_status(&self.pool, run_id, &ProcessingRunStatus::RolledBack)?;
+
+        info!("Rollback of run {} complete: {} items removed, {} actions reversed, {} skipped, {} errors",
+              run_id, result.items_removed, result.actions_reversed, result.actions_skipped, result.errors.len());
+
+        Ok(result)
+    }
+
+    /// Best-effort reversal of mailbox changes; failures are reported but do not abort the rollback
+    async fn reverse_actions(&self, run: &ProcessingRun, actions: &[ProcessingRunAction], result: &mut RollbackResult) {
+        let client = match ImapAccountOpsGeneric::get_by_id(&self.pool, &run.imap_account_id)
+            .and_then(|account| ImapClient::new(&account))
+        {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Cannot reverse post-processing for run: {}", e);
+                result.actions_skipped += actions.len();
+                result.errors.push(format!("Account unavailable, mailbox changes not reversed: {}", e));
+                return;
+            }
+        };
+
+        for action in actions {
+            match self.reverse_action(&client, action).await {
+                Ok(true) => result.actions_reversed += 1,
+                Ok(false) => result.actions_skipped += 1,
+                Err(e) => {
+                    warn!("Failed to reverse {} for UID {} in '{}': {}", action.action, action.uid, action.folder, e);
+                    result.errors.push(format!("{} UID {} in '{}': {}", action.action, action.uid, action.folder, e));
+                }
+            }
+        }
+    }
+
+    /// Returns Ok(false) when the action cannot be reversed
+    async fn reverse_action(&self, client: &ImapClient, action: &ProcessingRunAction) -> Result<bool> {
+        let uid = u32::try_from(action.uid)
+            .map_err(|_| anyhow::anyhow!("Invalid UID {}", action.uid))?;
+
+        match EmailAction::from_str(&action.action) {
+            EmailAction::MarkAsRead => {
+                client.mark_as_unread_in_folder(uid, &action.folder).await?;
+                Ok(true)
+            }
+            EmailAction::MoveToFolder => {
+                // The UID changes when a message is moved, so find it again by Message-ID
+                let (Some(target_folder), Some(message_id)) = (&action.target_folder, &action.email_message_id) else {
+                    debug!("Move of UID {} has no target folder or Message-ID recorded, skipping", uid);
+                    return Ok(false);
+                };
+
+                match client.find_uid_by_message_id(target_folder, message_id).await? {
+                    Some(moved_uid) => {
+                        client.move_to_folder_from_folder(moved_uid, target_folder, &action.folder).await?;
+                        Ok(true)
+                    }
+                    None => {
+                        debug!("Message {} no longer in '{}', skipping", message_id, target_folder);
+                        Ok(false)
+                    }
+                }
+            }
+            EmailAction::Delete | EmailAction::DoNothing => {
+                // Expunged messages cannot be restored
+                Ok(false)
+            }
+            EmailAction::AddFlag | EmailAction::CopyToFolder => {
+                // The flag may have been set before, and copies harm nothing
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct RollbackResult {
+    pub run_id: String,
+    pub items_removed: usize,
+    pub actions_reversed: usize,
+    pub actions_skipped: usize,
+    /// Post-processing held back by a rule's delay, dropped before it was applied
+    pub deferred_actions_cancelled: usize,
+    pub errors: Vec<String>,
+}
@@ -2,14 +2,18 @@
 //! 
 //! Manages the scheduling and execution of background email processing tasks
 
-use crate::background::{config::BackgroundConfig, cleanup::FeedCleanupService};
+use crate::background::{config::BackgroundConfig, cleanup::FeedCleanupService, deferred, clock::{Clock, SystemClock}, jobs::JobHandle, notifications, quota::{self, QuotaResource}, retention, storage::{self, StorageLimits}};
 use crate::db::{models::ImapAccount, connection::DatabasePool, operations_generic::ImapAccountOpsGeneric};
-use crate::imap::processor::EmailProcessor;
+use crate::feed::delivery;
+use crate::imap::processor::{EmailProcessor, ProcessingResult};
+use crate::imap::rate_limit::RateLimiter;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{Mutex, RwLock};
-use tokio::time::interval;
+use tokio::sync::{watch, Mutex, RwLock, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, interval_at};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
@@ -33,33 +37,107 @@ pub struct AccountState {
     pub is_processing: bool,
     pub next_allowed_run: Instant,
     pub retry_count: u32,
+    /// Runs in a row that failed or left rules unprocessed, for alerts
+    pub failing_runs: u32,
+    /// Whether the current streak of failing runs was alerted about
+    pub failure_alerted: bool,
+}
+
+impl AccountState {
+    fn new(account_id: String, next_allowed_run: Instant) -> Self {
+        Self {
+            account_id,
+            stats: ProcessingStats::default(),
+            is_processing: false,
+            next_allowed_run,
+            retry_count: 0,
+            failing_runs: 0,
+            failure_alerted: false,
+        }
+    }
+    
+    /// Whether the account may be processed at `now`
+    pub fn is_due(&self, now: Instant) -> bool {
+        !self.is_processing && now >= self.next_allowed_run
+    }
+    
+    /// Record a successful run finished at `now`; the next one waits a full interval
+    pub fn record_success(&mut self, now: Instant, result: &ProcessingResult, config: &BackgroundConfig) {
+        self.stats.last_run = Some(now);
+        self.stats.emails_processed += result.total_emails_processed;
+        self.stats.errors_count += result.errors.len();
+        self.stats.last_success = Some(now);
+        self.stats.last_error = None;
+        self.stats.consecutive_failures = 0;
+        self.retry_count = 0;
+        self.next_allowed_run = now + config.per_account_interval();
+    }
+    
+    /// Record a failed run finished at `now`
+    ///
+    /// The account is retried with exponential backoff; once the retries are
+    /// used up it falls back to its regular interval and the backoff starts over.
+    pub fn record_failure(&mut self, now: Instant, error: &anyhow::Error, config: &BackgroundConfig) {
+        self.stats.last_run = Some(now);
+        self.stats.errors_count += 1;
+        self.stats.last_error = Some(error.to_string());
+        self.stats.consecutive_failures += 1;
+        self.retry_count += 1;
+        
+        let retry_delay = if self.retry_count <= config.retry.max_attempts {
+            config.calculate_retry_delay(self.retry_count - 1)
+        } else {
+            self.retry_count = 0;
+            config.per_account_interval()
+        };
+        
+        self.next_allowed_run = now + retry_delay;
+    }
 }
 
 /// Email processing scheduler
 #[derive(Clone)]
 pub struct EmailScheduler {
     pool: DatabasePool,
-    config: BackgroundConfig,
+    /// Current configuration; replaced by `update_config` while running
+    config: Arc<watch::Sender<BackgroundConfig>>,
     account_states: Arc<RwLock<HashMap<String, AccountState>>>,
     cancellation_token: CancellationToken,
     is_running: Arc<Mutex<bool>>,
-    processing_semaphore: Arc<tokio::sync::Semaphore>,
+    processing_semaphore: Arc<Semaphore>,
+    /// Permits the semaphore holds in total, following `max_concurrent_accounts`
+    processing_capacity: Arc<AtomicUsize>,
+    /// Set while no new runs, cleanups or deliveries may start, e.g. for maintenance
+    paused: Arc<AtomicBool>,
+    /// Accounts whose run was interrupted before this process started
+    interrupted_accounts: Vec<String>,
+    clock: Arc<dyn Clock>,
 }
 
 impl EmailScheduler {
     /// Create a new email scheduler
     pub fn new(pool: DatabasePool, config: BackgroundConfig) -> anyhow::Result<Self> {
+        Self::with_clock(pool, config, Arc::new(SystemClock))
+    }
+    
+    /// Create a scheduler reading the time from `clock`, e.g. a manual clock in tests
+    pub fn with_clock(pool: DatabasePool, config: BackgroundConfig, clock: Arc<dyn Clock>) -> anyhow::Result<Self> {
         config.validate()?;
         
-        let processing_semaphore = Arc::new(tokio::sync::Semaphore::new(config.max_concurrent_accounts));
+        let processing_semaphore = Arc::new(Semaphore::new(config.max_concurrent_accounts));
+        let processing_capacity = Arc::new(AtomicUsize::new(config.max_concurrent_accounts));
         
         Ok(Self {
             pool,
-            config,
+            config: Arc::new(watch::channel(config).0),
             account_states: Arc::new(RwLock::new(HashMap::new())),
             cancellation_token: CancellationToken::new(),
             is_running: Arc::new(Mutex::new(false)),
             processing_semaphore,
+            processing_capacity,
+            paused: Arc::new(AtomicBool::new(false)),
+            interrupted_accounts: Vec::new(),
+            clock,
         })
     }
     
@@ -73,11 +151,12 @@ impl EmailScheduler {
         drop(is_running);
         
         info!("Starting email processing scheduler...");
+        let config = self.config();
         info!(
             "Configuration: global_interval={}min, per_account_interval={}min, max_concurrent={}",
-            self.config.global_interval_minutes,
-            self.config.per_account_interval_minutes,
-            self.config.max_concurrent_accounts
+            config.global_interval_minutes,
+            config.per_account_interval_minutes,
+            config.max_concurrent_accounts
         );
         
         // Initialize account states
@@ -110,12 +189,95 @@ impl EmailScheduler {
         Ok(())
     }
     
+    /// The configuration in effect
+    pub fn config(&self) -> BackgroundConfig {
+        self.config.borrow().clone()
+    }
+    
+    /// Apply a new configuration without restarting
+    ///
+    /// Runs already started finish under the old settings. The processing
+    /// slots are resized right away, though lowering them only takes effect as
+    /// runs in progress release theirs, and the scheduling interval restarts
+    /// from now when it changes.
+    pub fn update_config(&self, new_config: BackgroundConfig) -> anyhow::Result<()> {
+        new_config.validate()?;
+        
+        let old_capacity = self.processing_capacity.swap(new_config.max_concurrent_accounts, Ordering::SeqCst);
+        let new_capacity = new_config.max_concurrent_accounts;
+        if new_capacity > old_capacity {
+            self.processing_semaphore.add_permits(new_capacity - old_capacity);
+        } else if new_capacity < old_capacity {
+            // Take the surplus permits out of circulation once runs hand them back
+            let semaphore = self.processing_semaphore.clone();
+            let surplus = (old_capacity - new_capacity) as u32;
+            tokio::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many_owned(surplus).await {
+                    permits.forget();
+                }
+            });
+        }
+        
+        info!(
+            "Applied background configuration: global_interval={}min, per_account_interval={}min, max_concurrent={}",
+            new_config.global_interval_minutes,
+            new_config.per_account_interval_minutes,
+            new_config.max_concurrent_accounts
+        );
+        self.config.send_replace(new_config);
+        Ok(())
+    }
+    
+    /// Accounts to catch up on, or hold back, when the scheduler starts
+    pub fn set_interrupted_accounts(&mut self, account_ids: Vec<String>) {
+        self.interrupted_accounts = account_ids;
+    }
+    
     /// Check if the scheduler is running
     #[allow(dead_code)]
     pub async fn is_running(&self) -> bool {
         *self.is_running.lock().await
     }
     
+    /// Stop starting runs, cleanups and deliveries; runs already started finish
+    pub fn pause(&self) {
+        info!("Pausing email processing");
+        self.paused.store(true, Ordering::SeqCst);
+    }
+    
+    /// Start runs again after `pause`
+    pub fn resume(&self) {
+        info!("Resuming email processing");
+        self.paused.store(false, Ordering::SeqCst);
+    }
+    
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+    
+    /// Runs in progress, whether scheduled, targeted or started by hand
+    pub async fn in_flight_runs(&self) -> usize {
+        // Surplus permits still waiting to be retired after lowering the
+        // capacity count as available, so this never underflows
+        let holding_permits = self.processing_capacity.load(Ordering::SeqCst).saturating_sub(self.processing_semaphore.available_permits());
+        let marked = self.account_states.read().await.values().filter(|state| state.is_processing).count();
+        holding_permits.max(marked)
+    }
+    
+    /// Wait up to `timeout` for the runs in progress to finish
+    ///
+    /// Returns how many are still running, so zero once the scheduler is idle.
+    pub async fn wait_until_idle(&self, timeout: Duration) -> usize {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let running = self.in_flight_runs().await;
+            if running == 0 || tokio::time::Instant::now() >= deadline {
+                return running;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+    
     /// Get processing statistics for all accounts
     #[allow(dead_code)]
     pub async fn get_stats(&self) -> HashMap<String, ProcessingStats> {
@@ -134,17 +296,45 @@ impl EmailScheduler {
     
     /// Manually trigger processing for a specific account
     pub async fn process_account_now(&self, account_id: &str) -> anyhow::Result<ProcessingStats> {
+        self.process_account_manually(account_id, None).await
+    }
+    
+    /// Process an account on demand as `job`, which reports the rules
+    /// processed and ends with the emails processed
+    pub async fn process_account_job(&self, account_id: &str, job: JobHandle) {
+        job.start();
+        match self.process_account_manually(account_id, Some(job.clone())).await {
+            Ok(stats) => job.complete(&serde_json::json!({
+                "emails_processed": stats.emails_processed,
+                "errors_count": stats.errors_count,
+            })),
+            Err(e) => job.fail(e.to_string()),
+        }
+    }
+    
+    async fn process_account_manually(&self, account_id: &str, job: Option<JobHandle>) -> anyhow::Result<ProcessingStats> {
+        if self.is_paused() {
+            return Err(anyhow::anyhow!("Processing is paused"));
+        }
         let _permit = self.processing_semaphore.acquire().await
             .map_err(|_| anyhow::anyhow!("Failed to acquire processing permit"))?;
         
         let account = self.get_account_by_id(account_id).await?;
-        let processor = EmailProcessor::new(account.clone(), self.pool.clone());
-        let start_time = std::time::Instant::now();
+        if !account.account_protocol().is_polled() {
+            return Err(anyhow::anyhow!("Account '{}' receives its mail over SMTP; there is nothing to fetch", account.name));
+        }
+        let mut processor = EmailProcessor::new(account.clone(), self.pool.clone())
+            .with_cancellation(self.cancellation_token.child_token());
+        if let Some(job) = job {
+            processor = processor.with_job(job);
+        }
+        let start_time = self.clock.now();
+        let config = self.config();
         
         info!("Manually processing account '{}' ({})", account.name, account_id);
         
         let result = tokio::time::timeout(
-            self.config.max_processing_time(),
+            config.max_processing_time(),
             processor.process_account()
         ).await;
         
@@ -170,20 +360,13 @@ impl EmailScheduler {
         
         // Update account state
         let mut states = self.account_states.write().await;
-        let now = std::time::Instant::now();
+        let now = self.clock.now();
         
         if let Some(state) = states.get_mut(account_id) {
-            state.stats.last_run = Some(now);
-            
+            notifications::after_run(&self.pool, &account, state, &processing_result);
             match &processing_result {
                 Ok(result) => {
-                    state.stats.emails_processed += result.total_emails_processed;
-                    state.stats.errors_count += result.errors.len();
-                    state.stats.last_success = Some(now);
-                    state.stats.last_error = None;
-                    state.stats.consecutive_failures = 0;
-                    state.retry_count = 0;
-                    state.next_allowed_run = now + self.config.per_account_interval();
+                    state.record_success(now, result, &config);
                     
                     Ok(ProcessingStats {
                         emails_processed: result.total_emails_processed,
@@ -195,19 +378,7 @@ impl EmailScheduler {
                     })
                 }
                 Err(e) => {
-                    state.stats.errors_count += 1;
-                    state.stats.last_error = Some(e.to_string());
-                    state.stats.consecutive_failures += 1;
-                    state.retry_count += 1;
-                    
-                    let retry_delay = if state.retry_count <= self.config.retry.max_attempts {
-                        self.config.calculate_retry_delay(state.retry_count - 1)
-                    } else {
-                        state.retry_count = 0;
-                        self.config.per_account_interval()
-                    };
-                    
-                    state.next_allowed_run = now + retry_delay;
+                    state.record_failure(now, e, &config);
                     
                     error!("Manual account processing failed for {}: {}", account_id, e);
                     Err(anyhow::anyhow!("Processing failed: {}", e))
@@ -215,9 +386,9 @@ impl EmailScheduler {
             }
         } else {
             match processing_result {
-                Ok(_) => Ok(ProcessingStats {
-                    emails_processed: 0,
-                    errors_count: 0,
+                Ok(result) => Ok(ProcessingStats {
+                    emails_processed: result.total_emails_processed,
+                    errors_count: result.errors.len(),
                     last_run: Some(start_time),
                     last_success: Some(start_time),
                     last_error: None,
@@ -228,23 +399,120 @@ impl EmailScheduler {
         }
     }
     
+    /// Run a targeted pass over `folders` of an account, e.g. after rule edits
+    ///
+    /// Returns `Ok(None)` without processing when the account is already being
+    /// processed or processing is paused, so the caller can retry later. A
+    /// targeted pass does not count as the account's regular run and leaves
+    /// its schedule untouched.
+    pub async fn process_folders_now(&self, account_id: &str, folders: &[String]) -> anyhow::Result<Option<ProcessingStats>> {
+        if self.is_paused() {
+            return Ok(None);
+        }
+        {
+            let mut states = self.account_states.write().await;
+            match states.get_mut(account_id) {
+                Some(state) if state.is_processing => return Ok(None),
+                Some(state) => state.is_processing = true,
+                None => {}
+            }
+        }
+        
+        let result = self.run_targeted_pass(account_id, folders).await;
+        
+        let mut states = self.account_states.write().await;
+        if let Some(state) = states.get_mut(account_id) {
+            state.is_processing = false;
+            if let Ok(stats) = &result {
+                state.stats.emails_processed += stats.emails_processed;
+                state.stats.errors_count += stats.errors_count;
+            }
+        }
+        
+        result.map(Some)
+    }
+    
+    async fn run_targeted_pass(&self, account_id: &str, folders: &[String]) -> anyhow::Result<ProcessingStats> {
+        let _permit = self.processing_semaphore.acquire().await
+            .map_err(|_| anyhow::anyhow!("Failed to acquire processing permit"))?;
+        
+        let account = self.get_account_by_id(account_id).await?;
+        // Mail delivered over SMTP is only matched as it arrives
+        if !account.account_protocol().is_polled() {
+            return Ok(ProcessingStats::default());
+        }
+        let processor = EmailProcessor::new(account.clone(), self.pool.clone())
+            .with_cancellation(self.cancellation_token.child_token());
+        let start_time = self.clock.now();
+        
+        info!("Re-processing folders {:?} of account '{}' after rule changes", folders, account.name);
+        
+        let result = tokio::time::timeout(
+            self.config().max_processing_time(),
+            processor.process_folders(folders)
+        ).await
+            .map_err(|_| anyhow::anyhow!("Processing timeout"))??;
+        
+        info!(
+            "Targeted pass for account '{}' processed {} emails in {:?}",
+            account.name,
+            result.total_emails_processed,
+            start_time.elapsed()
+        );
+        
+        Ok(ProcessingStats {
+            emails_processed: result.total_emails_processed,
+            errors_count: result.errors.len(),
+            last_run: Some(start_time),
+            last_success: Some(start_time),
+            last_error: None,
+            consecutive_failures: 0,
+        })
+    }
+    
     /// Main scheduler loop
     async fn run_scheduler_loop(&self) {
-        let mut ticker = interval(self.config.global_interval());
+        let mut config_rx = self.config.subscribe();
+        let mut ticker = interval(self.config().global_interval());
         let mut cleanup_ticker = interval(std::time::Duration::from_secs(24 * 60 * 60)); // Run cleanup daily
+        let mut delivery_ticker = interval(std::time::Duration::from_secs(30)); // Retry queued webhook and chat deliveries
+        let mut deferred_ticker = interval(std::time::Duration::from_secs(5 * 60)); // Apply post-processing whose rule delay passed
         
         loop {
             tokio::select! {
                 _ = ticker.tick() => {
-                    if let Err(e) = self.process_due_accounts().await {
+                    if let Err(e) = self.start_due_accounts().await {
                         error!("Error during scheduled processing: {}", e);
                     }
                 }
                 _ = cleanup_ticker.tick() => {
-                    if let Err(e) = self.run_cleanup().await {
+                    if self.is_paused() {
+                        debug!("Processing is paused, skipping feed cleanup");
+                    } else if let Err(e) = self.run_cleanup().await {
                         error!("Error during feed cleanup: {}", e);
                     }
                 }
+                _ = delivery_ticker.tick() => {
+                    if self.is_paused() {
+                        debug!("Processing is paused, holding queued deliveries");
+                    } else if let Err(e) = delivery::process_due(&self.pool).await {
+                        error!("Error sending queued deliveries: {}", e);
+                    }
+                }
+                _ = deferred_ticker.tick() => {
+                    if self.is_paused() {
+                        debug!("Processing is paused, holding deferred post-processing");
+                    } else if let Err(e) = deferred::process_due(&self.pool).await {
+                        error!("Error applying deferred post-processing: {}", e);
+                    }
+                }
+                Ok(()) = config_rx.changed() => {
+                    let global_interval = config_rx.borrow_and_update().global_interval();
+                    if global_interval != ticker.period() {
+                        info!("Processing all accounts every {:?} from now on", global_interval);
+                        ticker = interval_at(tokio::time::Instant::now() + global_interval, global_interval);
+                    }
+                }
                 _ = self.cancellation_token.cancelled() => {
                     info!("Scheduler loop cancelled");
                     break;
@@ -253,12 +521,40 @@ impl EmailScheduler {
         }
     }
     
-    /// Process accounts that are due for processing
-    async fn process_due_accounts(&self) -> anyhow::Result<()> {
+    /// Run one scheduling pass and wait for the runs it started
+    ///
+    /// Returns how many accounts were processed. The scheduler loop does the
+    /// same on every tick without waiting, so together with a manual clock
+    /// this lets tests step through the schedule.
+    pub async fn process_due_accounts(&self) -> anyhow::Result<usize> {
+        let tasks = self.start_due_accounts().await?;
+        let started = tasks.len();
+        for task in tasks {
+            if let Err(e) = task.await {
+                error!("Account processing task failed: {}", e);
+            }
+        }
+        Ok(started)
+    }
+    
+    /// Start processing the accounts that are due
+    async fn start_due_accounts(&self) -> anyhow::Result<Vec<JoinHandle<()>>> {
+        if self.is_paused() {
+            debug!("Processing is paused, not starting any runs");
+            return Ok(Vec::new());
+        }
         debug!("Checking for accounts due for processing...");
         
+        // Bring storage back within its limits, or hold off adding to it
+        match storage::enforce(&self.pool, &StorageLimits::from_env()).await {
+            Ok(status) if status.ingestion_paused => return Ok(Vec::new()),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to check storage limits: {}", e),
+        }
+        
         let accounts = self.get_active_accounts().await?;
-        let now = Instant::now();
+        let now = self.clock.now();
+        let config = self.config();
         let mut tasks = Vec::new();
         
         for account in accounts {
@@ -268,17 +564,28 @@ impl EmailScheduler {
                 continue;
             };
             
-            // Check if account is due for processing
+            // Check if account is due for processing; accounts added since
+            // the scheduler started are due right away
             let should_process = {
-                let states = self.account_states.read().await;
-                if let Some(state) = states.get(account_id) {
-                    !state.is_processing && now >= state.next_allowed_run
-                } else {
-                    true // New account, should process
-                }
+                let mut states = self.account_states.write().await;
+                states.entry(account_id.clone())
+                    .or_insert_with(|| AccountState::new(account_id.clone(), now))
+                    .is_due(now)
             };
             
             if should_process {
+                // Leave the server alone until the account's rate limits allow a fetch
+                if let Some(wait) = RateLimiter::global().fetch_wait(&account, Instant::now()) {
+                    self.defer_for_rate_limit(account_id, &account.name, wait).await;
+                    continue;
+                }
+                
+                // Wait for the daily processing quota to reset
+                if let Err(e) = quota::check(&self.pool, &account, QuotaResource::ProcessingMinutes) {
+                    self.defer_until_quota_reset(account_id, &account.name, e).await;
+                    continue;
+                }
+                
                 // Check if we can acquire a processing slot
                 if self.processing_semaphore.available_permits() > 0 {
                     // Mark as processing
@@ -286,18 +593,20 @@ impl EmailScheduler {
                     
                     // Spawn processing task with proper ownership
                     let pool = self.pool.clone();
-                    let config = self.config.clone();
+                    let config = config.clone();
                     let account_states = self.account_states.clone();
                     let semaphore = self.processing_semaphore.clone();
+                    let clock = self.clock.clone();
                     let account_id_clone = account_id.clone();
+                    let cancellation = self.cancellation_token.child_token();
                     
                     let task = tokio::spawn(async move {
                         // Acquire permit inside the task
                         let _permit = semaphore.acquire().await;
                         
                         // Process the account
-                        let processor = EmailProcessor::new(account.clone(), pool);
-                        let start_time = std::time::Instant::now();
+                        let processor = EmailProcessor::new(account.clone(), pool.clone()).with_cancellation(cancellation);
+                        let start_time = clock.now();
                         
                         let result = match tokio::time::timeout(
                             config.max_processing_time(),
@@ -324,37 +633,14 @@ impl EmailScheduler {
                         
                         // Update account state
                         let mut states = account_states.write().await;
-                        let now = std::time::Instant::now();
+                        let now = clock.now();
                         
                         if let Some(state) = states.get_mut(&account_id_clone) {
-                            state.stats.last_run = Some(now);
                             state.is_processing = false;
-                            
-                            match result {
-                                Ok(processing_result) => {
-                                    state.stats.emails_processed += processing_result.total_emails_processed;
-                                    state.stats.errors_count += processing_result.errors.len();
-                                    state.stats.last_success = Some(now);
-                                    state.stats.last_error = None;
-                                    state.stats.consecutive_failures = 0;
-                                    state.retry_count = 0;
-                                    state.next_allowed_run = now + config.per_account_interval();
-                                }
-                                Err(e) => {
-                                    state.stats.errors_count += 1;
-                                    state.stats.last_error = Some(e.to_string());
-                                    state.stats.consecutive_failures += 1;
-                                    state.retry_count += 1;
-                                    
-                                    let retry_delay = if state.retry_count <= config.retry.max_attempts {
-                                        config.calculate_retry_delay(state.retry_count - 1)
-                                    } else {
-                                        state.retry_count = 0;
-                                        config.per_account_interval()
-                                    };
-                                    
-                                    state.next_allowed_run = now + retry_delay;
-                                }
+                            notifications::after_run(&pool, &account, state, &result);
+                            match &result {
+                                Ok(processing_result) => state.record_success(now, processing_result, &config),
+                                Err(e) => state.record_failure(now, e, &config),
                             }
                         }
                     });
@@ -370,7 +656,7 @@ impl EmailScheduler {
             debug!("Started {} background processing tasks", tasks.len());
         }
         
-        Ok(())
+        Ok(tasks)
     }
     
     /// Initialize account states for all active accounts
@@ -378,18 +664,24 @@ impl EmailScheduler {
         let accounts = self.get_active_accounts().await?;
         let mut states = self.account_states.write().await;
         
-        let now = Instant::now();
+        let now = self.clock.now();
+        let config = self.config();
         
         for account in accounts {
             if let Some(account_id) = &account.id {
                 if !states.contains_key(account_id) {
-                    states.insert(account_id.clone(), AccountState {
-                        account_id: account_id.clone(),
-                        stats: ProcessingStats::default(),
-                        is_processing: false,
-                        next_allowed_run: now,
-                        retry_count: 0,
-                    });
+                    // An interrupted run may have crashed the process, so
+                    // without catch-up its account waits for a regular interval
+                    let next_allowed_run = if !self.interrupted_accounts.contains(account_id) {
+                        now
+                    } else if config.catch_up_interrupted {
+                        info!("Catching up on account '{}' after its interrupted run", account.name);
+                        now
+                    } else {
+                        info!("Holding back account '{}' after its interrupted run", account.name);
+                        now + config.per_account_interval()
+                    };
+                    states.insert(account_id.clone(), AccountState::new(account_id.clone(), next_allowed_run));
                 }
             }
         }
@@ -398,6 +690,29 @@ impl EmailScheduler {
         Ok(())
     }
     
+    /// Hold back an account over its processing quota until midnight UTC
+    async fn defer_until_quota_reset(&self, account_id: &str, account_name: &str, error: anyhow::Error) {
+        let now = self.clock.utc_now();
+        let wait = (quota::next_reset(now) - now).to_std().unwrap_or_default();
+        info!("Skipping account '{}' until the quota resets in {:?}: {}", account_name, wait, error);
+        
+        let mut states = self.account_states.write().await;
+        if let Some(state) = states.get_mut(account_id) {
+            state.stats.last_error = Some(error.to_string());
+            state.next_allowed_run = self.clock.now() + wait;
+        }
+    }
+    
+    /// Hold back a rate limited account until its next fetch is allowed
+    async fn defer_for_rate_limit(&self, account_id: &str, account_name: &str, wait: Duration) {
+        debug!("Skipping rate limited account '{}' for {:?}", account_name, wait);
+        
+        let mut states = self.account_states.write().await;
+        if let Some(state) = states.get_mut(account_id) {
+            state.next_allowed_run = self.clock.now() + wait;
+        }
+    }
+    
     /// Mark account as processing or not processing
     async fn mark_account_processing(&self, account_id: &str, processing: bool) {
         let mut states = self.account_states.write().await;
@@ -407,8 +722,10 @@ impl EmailScheduler {
     }
     
     /// Get all active IMAP accounts
+    /// Accounts whose mail is fetched; mail of SMTP accounts is delivered to the listener
     async fn get_active_accounts(&self) -> anyhow::Result<Vec<ImapAccount>> {
         ImapAccountOpsGeneric::get_all(&self.pool)
+            .map(|accounts| accounts.into_iter().filter(|account| account.account_protocol().is_polled()).collect())
             .map_err(|e| anyhow::anyhow!("Failed to fetch accounts: {}", e))
     }
     
@@ -427,6 +744,10 @@ impl EmailScheduler {
             cancellation_token: self.cancellation_token.clone(),
             is_running: self.is_running.clone(),
             processing_semaphore: self.processing_semaphore.clone(),
+            processing_capacity: self.processing_capacity.clone(),
+            paused: self.paused.clone(),
+            interrupted_accounts: self.interrupted_accounts.clone(),
+            clock: self.clock.clone(),
         }
     }
     
@@ -434,7 +755,7 @@ impl EmailScheduler {
     async fn run_cleanup(&self) -> anyhow::Result<()> {
         debug!("Starting scheduled feed cleanup...");
         
-        let cleanup_service = FeedCleanupService::new(self.pool.clone());
+        let cleanup_service = FeedCleanupService::with_clock(self.pool.clone(), self.clock.clone());
         let result = cleanup_service.cleanup_all_feeds().await?;
         
         if result.items_removed > 0 {
@@ -444,6 +765,57 @@ impl EmailScheduler {
             debug!("Feed cleanup completed: no items removed");
         }
         
+        // History tables are trimmed along with the feeds
+        retention::purge(&self.pool, self.clock.utc_now())?;
+        
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::{clock::ManualClock, config::RetryConfig};
+    
+    fn config() -> BackgroundConfig {
+        BackgroundConfig {
+            per_account_interval_minutes: 30,
+            retry: RetryConfig {
+                max_attempts: 3,
+                initial_delay_seconds: 60,
+                max_delay_seconds: 150,
+                backoff_multiplier: 2.0,
+            },
+            ..Default::default()
+        }
+    }
+    
+    #[test]
+    fn test_failed_runs_back_off_then_fall_back_to_interval() {
+        let config = config();
+        let clock = ManualClock::new(chrono::Utc::now());
+        let mut state = AccountState::new("account".to_string(), clock.now());
+        let error = anyhow::anyhow!("Connection refused");
+        
+        // 60s, 120s, then 240s capped at 150s; after that the regular interval
+        for delay in [60, 120, 150, 30 * 60] {
+            assert!(state.is_due(clock.now()));
+            state.record_failure(clock.now(), &error, &config);
+            clock.advance(Duration::from_secs(delay - 1));
+            assert!(!state.is_due(clock.now()), "due before {}s passed", delay);
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(state.retry_count, 0);
+        assert_eq!(state.stats.consecutive_failures, 4);
+        assert_eq!(state.stats.last_error.as_deref(), Some("Connection refused"));
+        
+        // The backoff starts over, and a success clears it
+        state.record_failure(clock.now(), &error, &config);
+        assert_eq!(state.next_allowed_run, clock.now() + Duration::from_secs(60));
+        clock.advance(Duration::from_secs(60));
+        state.record_success(clock.now(), &ProcessingResult::default(), &config);
+        assert_eq!(state.retry_count, 0);
+        assert_eq!(state.stats.consecutive_failures, 0);
+        assert_eq!(state.stats.last_error, None);
+        assert_eq!(state.next_allowed_run, clock.now() + config.per_account_interval());
+    }
+}
@@ -2,16 +2,17 @@
 //! 
 //! Provides the main service interface for managing background email processing
 
-use crate::background::{config::BackgroundConfig, scheduler::EmailScheduler, control::{ControlMessage, ServiceStatusResponse}};
+use crate::background::{changes, config::BackgroundConfig, scheduler::EmailScheduler, control::{ControlMessage, ServiceStatusResponse}};
 use crate::db::connection::DatabasePool;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::{RwLock, mpsc};
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 /// Overall service status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum ServiceState {
     /// Service is stopped
     Stopped,
@@ -26,7 +27,7 @@ pub enum ServiceState {
 }
 
 /// Background service status information
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ServiceStatus {
     /// Current state of the service
     pub state: ServiceState,
@@ -39,6 +40,8 @@ pub struct ServiceStatus {
     pub accounts_count: usize,
     /// Number of accounts currently being processed
     pub active_processing_count: usize,
+    /// Whether processing is paused, e.g. for maintenance
+    pub is_paused: bool,
     /// Total emails processed since start
     pub total_emails_processed: usize,
     /// Total errors since start
@@ -52,9 +55,7 @@ pub struct BackgroundService {
     scheduler: EmailScheduler,
     state: Arc<RwLock<ServiceState>>,
     started_at: Arc<RwLock<Option<Instant>>>,
-    config: BackgroundConfig,
     control_rx: mpsc::UnboundedReceiver<ControlMessage>,
-    is_paused: Arc<RwLock<bool>>,
 }
 
 impl BackgroundService {
@@ -69,21 +70,24 @@ impl BackgroundService {
             warn!("Background processing is disabled in configuration");
         }
         
-        let scheduler = EmailScheduler::new(pool, config.clone())?;
+        let scheduler = EmailScheduler::new(pool, config)?;
         
         Ok(Self {
             scheduler,
             state: Arc::new(RwLock::new(ServiceState::Stopped)),
             started_at: Arc::new(RwLock::new(None)),
-            config,
             control_rx,
-            is_paused: Arc::new(RwLock::new(false)),
         })
     }
     
+    /// Accounts whose last run was interrupted, for the scheduler to catch up on
+    pub fn set_interrupted_accounts(&mut self, account_ids: Vec<String>) {
+        self.scheduler.set_interrupted_accounts(account_ids);
+    }
+    
     /// Start the background service
     pub async fn start(&mut self) -> anyhow::Result<()> {
-        if !self.config.enabled {
+        if !self.scheduler.config().enabled {
             return Err(anyhow::anyhow!("Background processing is disabled"));
         }
         
@@ -226,7 +230,7 @@ impl BackgroundService {
         let scheduler_stats = self.scheduler.get_stats().await;
         
         let accounts_count = scheduler_stats.len();
-        let active_processing_count = 0; // TODO: Get from scheduler
+        let active_processing_count = self.scheduler.in_flight_runs().await;
         
         let (total_emails_processed, total_errors) = scheduler_stats.values().fold(
             (0usize, 0usize), 
@@ -243,9 +247,10 @@ impl BackgroundService {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs()),
-            config: self.config.clone(),
+            config: self.scheduler.config(),
             accounts_count,
             active_processing_count,
+            is_paused: self.scheduler.is_paused(),
             total_emails_processed,
             total_errors,
             uptime_seconds,
@@ -288,28 +293,46 @@ impl BackgroundService {
         }
     }
     
-    /// Update service configuration (requires restart to take effect)
-    #[allow(dead_code)]
-    pub fn update_config(&mut self, new_config: BackgroundConfig) -> anyhow::Result<()> {
-        new_config.validate()?;
-        self.config = new_config;
-        info!("Service configuration updated (restart required for changes to take effect)");
-        Ok(())
+    /// Stop starting runs, cleanups and deliveries until `resume_processing`
+    pub fn pause_processing(&self) {
+        self.scheduler.pause();
+    }
+    
+    /// Start runs again after `pause_processing`
+    pub fn resume_processing(&self) {
+        self.scheduler.resume();
+    }
+    
+    /// Runs in progress, whether scheduled or started by hand
+    pub async fn in_flight_runs(&self) -> usize {
+        self.scheduler.in_flight_runs().await
+    }
+    
+    /// Wait up to `timeout` for runs in progress, returning how many are left
+    pub async fn wait_until_idle(&self, timeout: std::time::Duration) -> usize {
+        self.scheduler.wait_until_idle(timeout).await
+    }
+    
+    /// Apply a new configuration to the running scheduler
+    ///
+    /// Intervals, concurrency, retry policy and limits change in place.
+    /// Background processing cannot be switched on or off this way, since that
+    /// decides whether the service is started at all; use pause and resume.
+    pub fn update_config(&self, new_config: BackgroundConfig) -> anyhow::Result<()> {
+        check_reloadable(&self.scheduler.config(), &new_config)?;
+        self.scheduler.update_config(new_config)
     }
     
     /// Get current configuration
-    #[allow(dead_code)]
-    pub fn get_config(&self) -> &BackgroundConfig {
-        &self.config
+    pub fn get_config(&self) -> BackgroundConfig {
+        self.scheduler.config()
     }
     
     /// Start the control message handler
     async fn start_control_handler(&mut self) {
         let state = self.state.clone();
         let started_at = self.started_at.clone();
-        let is_paused = self.is_paused.clone();
         let scheduler = self.scheduler.clone();
-        let _config = self.config.clone();
         
         // Take ownership of the control receiver
         let mut control_rx = std::mem::replace(&mut self.control_rx, {
@@ -317,6 +340,10 @@ impl BackgroundService {
             rx
         });
         
+        // Rule and feed edits are debounced before their folders are re-processed
+        let (change_tx, change_rx) = mpsc::unbounded_channel();
+        tokio::spawn(changes::run_debouncer(scheduler.clone(), change_rx));
+        
         tokio::spawn(async move {
             info!("Starting background service control message handler");
             
@@ -328,36 +355,49 @@ impl BackgroundService {
                         // This is non-blocking and will be handled by the scheduler
                     }
                     
-                    ControlMessage::ProcessAccountNow { account_id } => {
+                    ControlMessage::ProcessAccountNow { account_id, job } => {
                         info!("Received command: ProcessAccountNow for account {}", account_id);
                         // Trigger processing for specific account
-                        if let Err(e) = scheduler.process_account_now(&account_id).await {
-                            error!("Failed to process account {}: {}", account_id, e);
+                        match job {
+                            Some(job) => scheduler.process_account_job(&account_id, job).await,
+                            None => {
+                                if let Err(e) = scheduler.process_account_now(&account_id).await {
+                                    error!("Failed to process account {}: {}", account_id, e);
+                                }
+                            }
+                        }
+                    }
+                    
+                    ControlMessage::RuleChanged(change) => {
+                        debug!("Received rule change for folder '{}' of account {}", change.folder, change.account_id);
+                        if change_tx.send(change).is_err() {
+                            warn!("Rule change debouncer is not running");
                         }
                     }
                     
                     ControlMessage::Pause => {
                         info!("Received command: Pause");
-                        let mut paused = is_paused.write().await;
-                        *paused = true;
+                        scheduler.pause();
                     }
                     
                     ControlMessage::Resume => {
                         info!("Received command: Resume");
-                        let mut paused = is_paused.write().await;
-                        *paused = false;
+                        scheduler.resume();
                     }
                     
-                    ControlMessage::ReloadConfig => {
+                    ControlMessage::ReloadConfig(config) => {
                         info!("Received command: ReloadConfig");
-                        // Configuration reload will require service restart
-                        warn!("Configuration reload requires service restart - not implemented yet");
+                        if let Err(e) = check_reloadable(&scheduler.config(), &config)
+                            .and_then(|()| scheduler.update_config(*config))
+                        {
+                            error!("Failed to reload background configuration: {}", e);
+                        }
                     }
                     
                     ControlMessage::GetStatus { response_tx } => {
                         let current_state = state.read().await.clone();
                         let start_time = started_at.read().await;
-                        let paused = *is_paused.read().await;
+                        let paused = scheduler.is_paused();
                         
                         let uptime = if let Some(start) = *start_time {
                             start.elapsed().as_secs()
@@ -368,7 +408,7 @@ impl BackgroundService {
                         let response = ServiceStatusResponse::Status {
                             is_running: matches!(current_state, ServiceState::Running),
                             is_paused: paused,
-                            accounts_processing: 0, // TODO: Get from scheduler
+                            accounts_processing: scheduler.in_flight_runs().await,
                             total_processed: 0,     // TODO: Get from scheduler
                             uptime_seconds: uptime,
                         };
@@ -395,6 +435,17 @@ impl BackgroundService {
     }
 }
 
+/// Refuse configuration changes that need a restart
+fn check_reloadable(current: &BackgroundConfig, new_config: &BackgroundConfig) -> anyhow::Result<()> {
+    if new_config.enabled != current.enabled {
+        return Err(anyhow::anyhow!(
+            "enabled cannot be changed at runtime; restart with BACKGROUND_PROCESSING_ENABLED={}, or pause processing instead",
+            new_config.enabled
+        ));
+    }
+    Ok(())
+}
+
 impl Default for ServiceStatus {
     fn default() -> Self {
         Self {
@@ -403,6 +454,7 @@ impl Default for ServiceStatus {
             config: BackgroundConfig::default(),
             accounts_count: 0,
             active_processing_count: 0,
+            is_paused: false,
             total_emails_processed: 0,
             total_errors: 0,
             uptime_seconds: None,
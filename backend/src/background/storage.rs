@@ -0,0 +1,240 @@
+//! Database size and disk space monitoring
+//!
+//! Each scheduling pass measures how many bytes the database holds and how
+//! much space is left on the disk it lives on, and checks them against two
+//! optional limits:
+//!
+//! - `STORAGE_MAX_DATABASE_MB`: once the database holds more, the safeguard in
+//!   `STORAGE_SAFEGUARD` applies. `tighten_retention` (the default) removes
+//!   the oldest quarter of every feed's unpinned items each pass until the
+//!   database is back under the limit (append-only feeds excepted);
+//!   `pause_ingestion` stops processing new mail instead, leaving it in the
+//!   mailbox.
+//! - `STORAGE_MIN_FREE_MB`: once less disk space is free, processing pauses.
+//!   Deleting items frees room inside the database file but does not shrink
+//!   it, so tightening retention would not help here.
+//!
+//! The disk checked is the one holding the SQLite database file, or
+//! `STORAGE_DATA_DIR` when set (e.g. for PostgreSQL). Crossing a limit is
+//! logged as a warning on every pass and shows in `/api/stats` and the
+//! Prometheus metrics.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{error, warn};
+use utoipa::ToSchema;
+
+use crate::background::cleanup::FeedCleanupService;
+use crate::db::{
+    connection::{DatabasePool, DatabaseType},
+    operations_generic::DatabaseOpsGeneric,
+};
+
+const MEGABYTE: i64 = 1024 * 1024;
+
+/// What crossing the database size limit does
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Safeguard {
+    /// Remove the oldest items of every feed until the database is under the limit
+    #[default]
+    TightenRetention,
+    /// Stop processing new mail until the database is under the limit
+    PauseIngestion,
+}
+
+/// Storage limits; unset limits do not apply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StorageLimits {
+    pub max_database_bytes: Option<i64>,
+    pub min_free_bytes: Option<i64>,
+    pub safeguard: Safeguard,
+}
+
+impl StorageLimits {
+    /// Limits from `STORAGE_MAX_DATABASE_MB`, `STORAGE_MIN_FREE_MB` and `STORAGE_SAFEGUARD`
+    pub fn from_env() -> Self {
+        let megabytes = |name: &str| std::env::var(name)
+            .ok()
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .filter(|megabytes| *megabytes > 0)
+            .map(|megabytes| megabytes.saturating_mul(MEGABYTE));
+        let safeguard = match std::env::var("STORAGE_SAFEGUARD").ok().as_deref().map(str::trim) {
+            Some("pause_ingestion") => Safeguard::PauseIngestion,
+            Some("tighten_retention") | None => Safeguard::TightenRetention,
+            Some(other) => {
+                warn!("Unknown STORAGE_SAFEGUARD '{}', tightening retention instead", other);
+                Safeguard::TightenRetention
+            }
+        };
+        Self {
+            max_database_bytes: megabytes("STORAGE_MAX_DATABASE_MB"),
+            min_free_bytes: megabytes("STORAGE_MIN_FREE_MB"),
+            safeguard,
+        }
+    }
+}
+
+/// Database size and free disk space against the storage limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StorageStatus {
+    /// Bytes in use by the database
+    pub database_bytes: i64,
+    /// Directory whose disk is checked
+    pub data_dir: String,
+    /// Free bytes on that disk; null when it could not be read
+    pub disk_free_bytes: Option<i64>,
+    pub disk_total_bytes: Option<i64>,
+    pub max_database_bytes: Option<i64>,
+    pub min_free_bytes: Option<i64>,
+    pub safeguard: Safeguard,
+    /// Whether retention is being tightened to get under the database limit
+    pub retention_tightened: bool,
+    /// Whether processing of new mail is paused
+    pub ingestion_paused: bool,
+    /// The limits crossed; empty while storage is within them
+    pub warnings: Vec<String>,
+}
+
+impl StorageStatus {
+    /// Check the measured sizes against `limits`
+    pub fn evaluate(
+        database_bytes: i64,
+        data_dir: &Path,
+        disk_free_bytes: Option<i64>,
+        disk_total_bytes: Option<i64>,
+        limits: &StorageLimits,
+    ) -> Self {
+        let mut warnings = Vec::new();
+        let mut retention_tightened = false;
+        let mut ingestion_paused = false;
+
+        if let Some(max) = limits.max_database_bytes.filter(|max| database_bytes > *max) {
+            warnings.push(format!(
+                "Database holds {} MB, over the {} MB limit of STORAGE_MAX_DATABASE_MB",
+                database_bytes / MEGABYTE, max / MEGABYTE
+            ));
+            match limits.safeguard {
+                Safeguard::TightenRetention => retention_tightened = true,
+                Safeguard::PauseIngestion => ingestion_paused = true,
+            }
+        }
+        if let (Some(min), Some(free)) = (limits.min_free_bytes, disk_free_bytes) {
+            if free < min {
+                warnings.push(format!(
+                    "Only {} MB free on the disk of {}, under the {} MB of STORAGE_MIN_FREE_MB",
+                    free / MEGABYTE, data_dir.display(), min / MEGABYTE
+                ));
+                ingestion_paused = true;
+            }
+        }
+
+        Self {
+            database_bytes,
+            data_dir: data_dir.display().to_string(),
+            disk_free_bytes,
+            disk_total_bytes,
+            max_database_bytes: limits.max_database_bytes,
+            min_free_bytes: limits.min_free_bytes,
+            safeguard: limits.safeguard,
+            retention_tightened,
+            ingestion_paused,
+            warnings,
+        }
+    }
+}
+
+/// Measure the database and its disk against `limits`
+pub fn measure(pool: &DatabasePool, limits: &StorageLimits) -> Result<StorageStatus> {
+    let database_bytes = DatabaseOpsGeneric::size_bytes(pool)?;
+    let data_dir = data_dir();
+    let to_bytes = |space: std::io::Result<u64>| space.ok().and_then(|bytes| i64::try_from(bytes).ok());
+    let disk_free_bytes = to_bytes(fs2::available_space(&data_dir));
+    if disk_free_bytes.is_none() {
+        warn!("Failed to read free disk space of {}", data_dir.display());
+    }
+    let disk_total_bytes = to_bytes(fs2::total_space(&data_dir));
+
+    Ok(StorageStatus::evaluate(database_bytes, &data_dir, disk_free_bytes, disk_total_bytes, limits))
+}
+
+/// Measure storage and apply the safeguards of any limit crossed
+pub async fn enforce(pool: &DatabasePool, limits: &StorageLimits) -> Result<StorageStatus> {
+    let status = measure(pool, limits)?;
+    for warning in &status.warnings {
+        error!("🚨 Storage limit crossed: {}", warning);
+    }
+
+    if status.retention_tightened {
+        let result = FeedCleanupService::new(pool.clone()).tighten_all_feeds().await?;
+        warn!("🚨 Tightened retention to free storage: removed {} items from {} feeds",
+              result.items_removed, result.feeds_processed);
+    }
+    if status.ingestion_paused {
+        warn!("🚨 Processing of new mail is paused until storage is back within its limits");
+    }
+    Ok(status)
+}
+
+/// Directory whose disk holds the data: `STORAGE_DATA_DIR`, else that of the
+/// SQLite database file, else the working directory
+fn data_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("STORAGE_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|url| matches!(DatabaseType::from_url(url), DatabaseType::SQLite) && !url.contains(":memory:"))
+        .and_then(|url| {
+            let path = PathBuf::from(url.trim_start_matches("sqlite://"));
+            path.parent().map(Path::to_path_buf)
+        })
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(safeguard: Safeguard) -> StorageLimits {
+        StorageLimits {
+            max_database_bytes: Some(100 * MEGABYTE),
+            min_free_bytes: Some(500 * MEGABYTE),
+            safeguard,
+        }
+    }
+
+    #[test]
+    fn test_within_limits() {
+        let status = StorageStatus::evaluate(50 * MEGABYTE, Path::new("/data"), Some(MEGABYTE * 1024), None, &limits(Safeguard::TightenRetention));
+        assert!(status.warnings.is_empty());
+        assert!(!status.retention_tightened);
+        assert!(!status.ingestion_paused);
+
+        // Without limits nothing is ever crossed
+        let status = StorageStatus::evaluate(i64::MAX, Path::new("/data"), Some(0), None, &StorageLimits::default());
+        assert!(status.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_database_limit_applies_safeguard() {
+        let status = StorageStatus::evaluate(150 * MEGABYTE, Path::new("/data"), None, None, &limits(Safeguard::TightenRetention));
+        assert_eq!(status.warnings, ["Database holds 150 MB, over the 100 MB limit of STORAGE_MAX_DATABASE_MB"]);
+        assert!(status.retention_tightened);
+        assert!(!status.ingestion_paused);
+
+        let status = StorageStatus::evaluate(150 * MEGABYTE, Path::new("/data"), None, None, &limits(Safeguard::PauseIngestion));
+        assert!(!status.retention_tightened);
+        assert!(status.ingestion_paused);
+    }
+
+    #[test]
+    fn test_low_disk_space_pauses_ingestion() {
+        let status = StorageStatus::evaluate(50 * MEGABYTE, Path::new("/data"), Some(200 * MEGABYTE), None, &limits(Safeguard::TightenRetention));
+        assert_eq!(status.warnings, ["Only 200 MB free on the disk of /data, under the 500 MB of STORAGE_MIN_FREE_MB"]);
+        assert!(!status.retention_tightened);
+        assert!(status.ingestion_paused);
+    }
+}
@@ -0,0 +1,125 @@
+//! In-memory registry of long-running maintenance tasks
+//!
+//! Tasks report progress through a [`TaskHandle`] so the API can poll them
+//! while they run in the background. Status is not persisted and is lost on
+//! restart.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaskStatus {
+    pub id: String,
+    pub kind: String,
+    pub state: TaskState,
+    /// Rows examined so far
+    pub processed: usize,
+    /// Rows that needed work when the task started
+    pub total: usize,
+    /// Rows actually changed
+    pub updated: usize,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct TaskRegistry {
+    tasks: Arc<RwLock<HashMap<String, TaskStatus>>>,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new running task and return a handle for reporting progress
+    pub async fn start(&self, kind: &str) -> TaskHandle {
+        let id = Uuid::new_v4().to_string();
+        let status = TaskStatus {
+            id: id.clone(),
+            kind: kind.to_string(),
+            state: TaskState::Running,
+            processed: 0,
+            total: 0,
+            updated: 0,
+            started_at: Utc::now().to_rfc3339(),
+            finished_at: None,
+            error: None,
+        };
+        self.tasks.write().await.insert(id.clone(), status);
+
+        TaskHandle {
+            id,
+            registry: self.clone(),
+        }
+    }
+
+    pub async fn get(&self, id: &str) -> Option<TaskStatus> {
+        self.tasks.read().await.get(id).cloned()
+    }
+
+    /// All known tasks, most recently started first
+    pub async fn list(&self) -> Vec<TaskStatus> {
+        let mut tasks: Vec<TaskStatus> = self.tasks.read().await.values().cloned().collect();
+        tasks.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        tasks
+    }
+
+    async fn update(&self, id: &str, f: impl FnOnce(&mut TaskStatus)) {
+        if let Some(status) = self.tasks.write().await.get_mut(id) {
+            f(status);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskHandle {
+    id: String,
+    registry: TaskRegistry,
+}
+
+impl TaskHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub async fn set_total(&self, total: usize) {
+        self.registry.update(&self.id, |status| status.total = total).await;
+    }
+
+    pub async fn advance(&self, processed: usize, updated: usize) {
+        self.registry.update(&self.id, |status| {
+            status.processed += processed;
+            status.updated += updated;
+        }).await;
+    }
+
+    pub async fn complete(&self) {
+        self.registry.update(&self.id, |status| {
+            status.state = TaskState::Completed;
+            status.finished_at = Some(Utc::now().to_rfc3339());
+        }).await;
+    }
+
+    pub async fn fail(&self, error: String) {
+        self.registry.update(&self.id, |status| {
+            status.state = TaskState::Failed;
+            status.finished_at = Some(Utc::now().to_rfc3339());
+            status.error = Some(error);
+        }).await;
+    }
+}
@@ -0,0 +1,39 @@
+use dotenvy::dotenv;
+use mail2feed_backend::background::maintenance::{BodyOffloadService, OFFLOAD_BODIES_TASK};
+use mail2feed_backend::background::tasks::{TaskRegistry, TaskState};
+use mail2feed_backend::db::connection::create_pool;
+use mail2feed_backend::feed::blob::BlobStore;
+use tracing::{error, info};
+
+/// Move the bodies of existing feed items out of the database into the blob
+/// store configured by `BODY_STORE`, e.g. before starting the server with it
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+    mail2feed_backend::logging::init();
+
+    let Some(store) = BlobStore::from_env()? else {
+        eprintln!("No blob store is configured; set BODY_STORE to filesystem or s3");
+        std::process::exit(1);
+    };
+
+    let pool =
+        create_pool().map_err(|e| anyhow::anyhow!("Failed to create database pool: {}", e))?;
+
+    let tasks = TaskRegistry::new();
+    let task = tasks.start(OFFLOAD_BODIES_TASK).await;
+    let task_id = task.id().to_string();
+    BodyOffloadService::new(pool, store).run(task).await;
+
+    match tasks.get(&task_id).await {
+        Some(status) if status.state == TaskState::Completed => {
+            info!("Moved {} of {} item bodies examined", status.updated, status.processed);
+        }
+        status => {
+            error!("Offload failed: {}", status.and_then(|status| status.error).unwrap_or_default());
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
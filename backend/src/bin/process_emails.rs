@@ -1,5 +1,5 @@
 use dotenvy::dotenv;
-use mail2feed_backend::db::{create_pool, establish_connection, operations::ImapAccountOps};
+use mail2feed_backend::db::{connection::create_pool, operations_generic::ImapAccountOpsGeneric};
 use mail2feed_backend::imap::processor::EmailProcessor;
 use std::env;
 use tracing::{error, info};
@@ -7,7 +7,7 @@ use tracing::{error, info};
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
-    tracing_subscriber::fmt::init();
+    mail2feed_backend::logging::init();
 
     let args: Vec<String> = env::args().collect();
 
@@ -26,8 +26,7 @@ async fn main() -> anyhow::Result<()> {
         create_pool().map_err(|e| anyhow::anyhow!("Failed to create database pool: {}", e))?;
 
     // Get the account
-    let mut conn = establish_connection();
-    let account = ImapAccountOps::get_by_id(&mut conn, account_id)
+    let account = ImapAccountOpsGeneric::get_by_id(&pool, account_id)
         .map_err(|e| anyhow::anyhow!("Account not found: {}", e))?;
 
     info!(
@@ -43,6 +42,7 @@ async fn main() -> anyhow::Result<()> {
             info!("Processing completed successfully!");
             info!("Total emails processed: {}", result.total_emails_processed);
             info!("New feed items created: {}", result.new_feed_items_created);
+            info!("Bytes transferred: {} received, {} sent", result.transfer.bytes_received, result.transfer.bytes_sent);
 
             if !result.errors.is_empty() {
                 info!("Errors encountered:");
@@ -1,5 +1,7 @@
 use anyhow::Result;
-use mail2feed_backend::db::{connection, models::*, operations_generic::ImapAccountOpsGeneric};
+use mail2feed_backend::db::connection;
+#[cfg(feature = "postgres")]
+use mail2feed_backend::db::{models::*, operations_generic::ImapAccountOpsGeneric};
 
 fn main() -> Result<()> {
     println!("🧪 Testing PostgreSQL database operations...");
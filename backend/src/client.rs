@@ -0,0 +1,507 @@
+//! Typed client for the mail2feed HTTP API
+//!
+//! Enabled with the `client` feature. Requests and responses use the same
+//! types as the server (`crate::api::types` and `crate::db::models`), so a
+//! client built from the same version always agrees with the server on shape.
+
+use anyhow::Result;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::api::types::*;
+use crate::db::models::{ChatIntegration, Delivery, EmailRule, Feed, FeedItem, ImapAccount, ProcessingIntent, ProcessingRun, QuotaGroup, RuleMatch};
+
+/// Error returned when the server answers with a non-success status
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mail2feed API returned {}: {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[derive(Debug, Clone)]
+pub struct Mail2FeedClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl Mail2FeedClient {
+    /// Create a client for the instance at `base_url`, e.g. `http://localhost:3001`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Create a client using a preconfigured `reqwest::Client` (timeouts, proxies, ...)
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // Health
+
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.send(self.request(Method::GET, "/health")).await
+    }
+
+    pub async fn liveness(&self) -> Result<LivenessResponse> {
+        self.send(self.request(Method::GET, "/health/live")).await
+    }
+
+    /// Readiness report, also when the instance is not ready (503)
+    pub async fn readiness(&self) -> Result<ReadinessResponse> {
+        let response = self.request(Method::GET, "/health/ready").send().await?;
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(response.json().await?);
+        }
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Freshness metrics in the Prometheus text format
+    pub async fn metrics(&self) -> Result<String> {
+        self.send_text(self.request(Method::GET, "/metrics")).await
+    }
+
+    // IMAP accounts
+
+    pub async fn list_accounts(&self) -> Result<Vec<ImapAccount>> {
+        self.send(self.request(Method::GET, "/api/imap-accounts")).await
+    }
+
+    pub async fn get_account(&self, account_id: &str) -> Result<ImapAccount> {
+        self.send(self.request(Method::GET, &format!("/api/imap-accounts/{}", account_id))).await
+    }
+
+    pub async fn create_account(&self, request: &CreateImapAccountRequest) -> Result<ImapAccount> {
+        self.send(self.request(Method::POST, "/api/imap-accounts").json(request)).await
+    }
+
+    pub async fn update_account(&self, account_id: &str, request: &UpdateImapAccountRequest) -> Result<ImapAccount> {
+        self.send(self.request(Method::PUT, &format!("/api/imap-accounts/{}", account_id)).json(request)).await
+    }
+
+    pub async fn delete_account(&self, account_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/imap-accounts/{}", account_id))).await
+    }
+
+    // IMAP operations
+
+    pub async fn test_connection(&self, account_id: &str) -> Result<TestConnectionResponse> {
+        self.send(self.request(Method::GET, &format!("/api/imap/{}/test", account_id))).await
+    }
+
+    pub async fn tls_fingerprint(&self, account_id: &str) -> Result<TlsFingerprintResponse> {
+        self.send(self.request(Method::GET, &format!("/api/imap/{}/tls-fingerprint", account_id))).await
+    }
+
+    /// Outcome of each step of connecting to the account
+    pub async fn diagnose_account(&self, account_id: &str) -> Result<ConnectionDiagnostics> {
+        self.send(self.request(Method::POST, &format!("/api/imap-accounts/{}/test", account_id))).await
+    }
+
+    /// Outcome of each step of connecting with settings that are not saved yet
+    pub async fn diagnose_settings(&self, request: &SetupConnectionRequest) -> Result<ConnectionDiagnostics> {
+        self.send(self.request(Method::POST, "/api/imap-accounts/test").json(request)).await
+    }
+
+    /// The account's folders as a tree, with message and unseen counts
+    pub async fn list_folders(&self, account_id: &str) -> Result<Vec<FolderNode>> {
+        self.send(self.request(Method::GET, &format!("/api/imap-accounts/{}/folders", account_id))).await
+    }
+
+    pub async fn process_account(&self, account_id: &str) -> Result<ProcessAccountResponse> {
+        self.send(self.request(Method::POST, &format!("/api/imap/{}/process", account_id))).await
+    }
+
+    pub async fn process_all_accounts(&self) -> Result<Vec<ProcessAccountResponse>> {
+        self.send(self.request(Method::POST, "/api/imap/process-all")).await
+    }
+
+    // Setup
+
+    /// Log in with settings that are not saved yet and report the server's capabilities
+    pub async fn setup_validate_connection(&self, request: &SetupConnectionRequest) -> Result<SetupConnectionResponse> {
+        self.send(self.request(Method::POST, "/api/setup/validate-connection").json(request)).await
+    }
+
+    /// Folders of the server, most newsletter-like first
+    pub async fn setup_suggest_folders(&self, request: &SetupConnectionRequest) -> Result<Vec<FolderSuggestion>> {
+        self.send(self.request(Method::POST, "/api/setup/suggest-folders").json(request)).await
+    }
+
+    /// Create the account with a rule and a feed per folder, all or nothing
+    pub async fn setup_finalize(&self, request: &SetupFinalizeRequest) -> Result<SetupFinalizeResponse> {
+        self.send(self.request(Method::POST, "/api/setup/finalize").json(request)).await
+    }
+
+    // Email rules
+
+    pub async fn list_rules(&self) -> Result<Vec<EmailRule>> {
+        self.send(self.request(Method::GET, "/api/email-rules")).await
+    }
+
+    pub async fn get_rule(&self, rule_id: &str) -> Result<EmailRule> {
+        self.send(self.request(Method::GET, &format!("/api/email-rules/{}", rule_id))).await
+    }
+
+    pub async fn create_rule(&self, request: &CreateEmailRuleRequest) -> Result<EmailRule> {
+        self.send(self.request(Method::POST, "/api/email-rules").json(request)).await
+    }
+
+    pub async fn update_rule(&self, rule_id: &str, request: &UpdateEmailRuleRequest) -> Result<EmailRule> {
+        self.send(self.request(Method::PUT, &format!("/api/email-rules/{}", rule_id)).json(request)).await
+    }
+
+    pub async fn delete_rule(&self, rule_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/email-rules/{}", rule_id))).await
+    }
+
+    pub async fn get_rule_stats(&self, rule_id: &str) -> Result<RuleStatsResponse> {
+        self.send(self.request(Method::GET, &format!("/api/email-rules/{}/stats", rule_id))).await
+    }
+
+    /// Emails matched while the rule was observe-only, newest first
+    pub async fn get_rule_preview(&self, rule_id: &str, query: &RulePreviewQuery) -> Result<Vec<RuleMatch>> {
+        self.send(self.request(Method::GET, &format!("/api/email-rules/{}/preview", rule_id)).query(query)).await
+    }
+
+    /// Queue importing the past emails of a rule's folder; follow it with `get_job`
+    pub async fn backfill_rule(&self, rule_id: &str, request: &BackfillRuleRequest) -> Result<JobStartedResponse> {
+        self.send(self.request(Method::POST, &format!("/api/email-rules/{}/backfill", rule_id)).json(request)).await
+    }
+
+    // Feeds and feed items
+
+    pub async fn list_feeds(&self) -> Result<Vec<Feed>> {
+        self.send(self.request(Method::GET, "/api/feeds")).await
+    }
+
+    pub async fn get_feed(&self, feed_id: &str) -> Result<Feed> {
+        self.send(self.request(Method::GET, &format!("/api/feeds/{}", feed_id))).await
+    }
+
+    pub async fn create_feed(&self, request: &CreateFeedRequest) -> Result<Feed> {
+        self.send(self.request(Method::POST, "/api/feeds").json(request)).await
+    }
+
+    pub async fn update_feed(&self, feed_id: &str, request: &UpdateFeedRequest) -> Result<Feed> {
+        self.send(self.request(Method::PUT, &format!("/api/feeds/{}", feed_id)).json(request)).await
+    }
+
+    pub async fn delete_feed(&self, feed_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/feeds/{}", feed_id))).await
+    }
+
+    /// A page of a feed's items; pass `next_cursor` back as `cursor` for the next
+    pub async fn get_feed_items(&self, feed_id: &str, query: &FeedItemsQuery) -> Result<FeedItemsPage> {
+        self.send(self.request(Method::GET, &format!("/api/feeds/{}/items", feed_id)).query(query)).await
+    }
+
+    pub async fn get_feed_items_metadata(&self, feed_id: &str, query: &FeedItemsQuery) -> Result<FeedItemMetadataPage> {
+        self.send(self.request(Method::GET, &format!("/api/feeds/{}/items/metadata", feed_id)).query(query)).await
+    }
+
+    /// Enforce the feed's retention now
+    pub async fn cleanup_feed(&self, feed_id: &str) -> Result<CleanupResult> {
+        self.send(self.request(Method::POST, &format!("/api/feeds/{}/cleanup", feed_id))).await
+    }
+
+    /// Enforce the retention of every feed and history table now
+    pub async fn cleanup(&self) -> Result<CleanupReport> {
+        self.send(self.request(Method::POST, "/api/cleanup")).await
+    }
+
+    /// Queue the cleanup of `cleanup` as a job whose result is the report
+    pub async fn queue_cleanup(&self) -> Result<JobStartedResponse> {
+        self.send(self.request(Method::POST, "/api/cleanup").query(&CleanupQuery { background: true })).await
+    }
+
+    /// Call the feed's webhook with its newest item
+    pub async fn test_feed_webhook(&self, feed_id: &str) -> Result<WebhookTestResponse> {
+        self.send(self.request(Method::POST, &format!("/api/feeds/{}/webhook/test", feed_id))).await
+    }
+
+    pub async fn get_feed_item(&self, item_id: &str) -> Result<FeedItem> {
+        self.send(self.request(Method::GET, &format!("/api/feed-items/{}", item_id))).await
+    }
+
+    pub async fn update_feed_item(&self, item_id: &str, request: &UpdateFeedItemRequest) -> Result<FeedItem> {
+        self.send(self.request(Method::PATCH, &format!("/api/feed-items/{}", item_id)).json(request)).await
+    }
+
+    /// Mark, star or delete the items of a feed that `request` selects
+    pub async fn bulk_update_feed_items(&self, feed_id: &str, request: &BulkFeedItemsRequest) -> Result<BulkFeedItemsResponse> {
+        self.send(self.request(Method::POST, &format!("/api/feeds/{}/items/bulk", feed_id)).json(request)).await
+    }
+
+    /// Send the item's one-click unsubscribe request, or get its unsubscribe link
+    pub async fn unsubscribe_feed_item(&self, item_id: &str) -> Result<UnsubscribeResponse> {
+        self.send(self.request(Method::POST, &format!("/api/feed-items/{}/unsubscribe", item_id))).await
+    }
+
+    /// Items across feeds, newest first; pass `next_cursor` back as `cursor` for the next page
+    pub async fn timeline(&self, query: &TimelineQuery) -> Result<TimelineResponse> {
+        self.send(self.request(Method::GET, "/api/timeline").query(query)).await
+    }
+
+    /// Fetch the rendered RSS document for a feed
+    pub async fn get_rss_feed(&self, feed_id: &str) -> Result<String> {
+        self.send_text(self.request(Method::GET, &format!("/feeds/{}/rss", feed_id))).await
+    }
+
+    /// Fetch the rendered Atom document for a feed
+    pub async fn get_atom_feed(&self, feed_id: &str) -> Result<String> {
+        self.send_text(self.request(Method::GET, &format!("/feeds/{}/atom", feed_id))).await
+    }
+
+    // Chat integrations
+
+    pub async fn list_chat_integrations(&self, feed_id: &str) -> Result<Vec<ChatIntegration>> {
+        self.send(self.request(Method::GET, &format!("/api/feeds/{}/integrations", feed_id))).await
+    }
+
+    pub async fn create_chat_integration(&self, feed_id: &str, request: &ChatIntegrationRequest) -> Result<ChatIntegration> {
+        self.send(self.request(Method::POST, &format!("/api/feeds/{}/integrations", feed_id)).json(request)).await
+    }
+
+    pub async fn get_chat_integration(&self, integration_id: &str) -> Result<ChatIntegration> {
+        self.send(self.request(Method::GET, &format!("/api/chat-integrations/{}", integration_id))).await
+    }
+
+    pub async fn update_chat_integration(&self, integration_id: &str, request: &ChatIntegrationRequest) -> Result<ChatIntegration> {
+        self.send(self.request(Method::PUT, &format!("/api/chat-integrations/{}", integration_id)).json(request)).await
+    }
+
+    pub async fn delete_chat_integration(&self, integration_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/chat-integrations/{}", integration_id))).await
+    }
+
+    /// Post the feed's newest item through the integration
+    pub async fn test_chat_integration(&self, integration_id: &str) -> Result<WebhookTestResponse> {
+        self.send(self.request(Method::POST, &format!("/api/chat-integrations/{}/test", integration_id))).await
+    }
+
+    // Outbound deliveries
+
+    /// Webhook and chat deliveries that ran out of attempts
+    pub async fn list_failed_deliveries(&self) -> Result<Vec<Delivery>> {
+        self.send(self.request(Method::GET, "/api/deliveries/failed")).await
+    }
+
+    /// Queue all failed deliveries again and attempt them
+    pub async fn retry_failed_deliveries(&self) -> Result<Vec<Delivery>> {
+        self.send(self.request(Method::POST, "/api/deliveries/retry")).await
+    }
+
+    pub async fn retry_delivery(&self, delivery_id: &str) -> Result<Delivery> {
+        self.send(self.request(Method::POST, &format!("/api/deliveries/{}/retry", delivery_id))).await
+    }
+
+    pub async fn delete_delivery(&self, delivery_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/deliveries/{}", delivery_id))).await
+    }
+
+    // Quotas
+
+    pub async fn list_quota_groups(&self) -> Result<Vec<QuotaGroup>> {
+        self.send(self.request(Method::GET, "/api/quota-groups")).await
+    }
+
+    pub async fn get_quota_group(&self, group_id: &str) -> Result<QuotaGroup> {
+        self.send(self.request(Method::GET, &format!("/api/quota-groups/{}", group_id))).await
+    }
+
+    pub async fn create_quota_group(&self, request: &QuotaGroupRequest) -> Result<QuotaGroup> {
+        self.send(self.request(Method::POST, "/api/quota-groups").json(request)).await
+    }
+
+    pub async fn update_quota_group(&self, group_id: &str, request: &QuotaGroupRequest) -> Result<QuotaGroup> {
+        self.send(self.request(Method::PUT, &format!("/api/quota-groups/{}", group_id)).json(request)).await
+    }
+
+    pub async fn delete_quota_group(&self, group_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/quota-groups/{}", group_id))).await
+    }
+
+    /// Limits of the group and usage summed over its accounts
+    pub async fn get_quota_group_usage(&self, group_id: &str) -> Result<QuotaReport> {
+        self.send(self.request(Method::GET, &format!("/api/quota-groups/{}/usage", group_id))).await
+    }
+
+    /// Limits and usage of the account and of its quota group
+    pub async fn get_account_quota(&self, account_id: &str) -> Result<AccountQuotaResponse> {
+        self.send(self.request(Method::GET, &format!("/api/imap-accounts/{}/quota", account_id))).await
+    }
+
+    // Senders
+
+    pub async fn list_sender_aliases(&self) -> Result<Vec<SenderAliasResponse>> {
+        self.send(self.request(Method::GET, "/api/senders/aliases")).await
+    }
+
+    pub async fn get_sender_alias(&self, alias_id: &str) -> Result<SenderAliasResponse> {
+        self.send(self.request(Method::GET, &format!("/api/senders/aliases/{}", alias_id))).await
+    }
+
+    pub async fn create_sender_alias(&self, request: &SenderAliasRequest) -> Result<SenderAliasResponse> {
+        self.send(self.request(Method::POST, "/api/senders/aliases").json(request)).await
+    }
+
+    pub async fn update_sender_alias(&self, alias_id: &str, request: &SenderAliasRequest) -> Result<SenderAliasResponse> {
+        self.send(self.request(Method::PUT, &format!("/api/senders/aliases/{}", alias_id)).json(request)).await
+    }
+
+    pub async fn delete_sender_alias(&self, alias_id: &str) -> Result<()> {
+        self.send_empty(self.request(Method::DELETE, &format!("/api/senders/aliases/{}", alias_id))).await
+    }
+
+    /// Items per sender across all feeds, alias groups counted under their name
+    pub async fn sender_stats(&self) -> Result<Vec<SenderStats>> {
+        self.send(self.request(Method::GET, "/api/senders/stats")).await
+    }
+
+    // Analysis
+
+    /// Storage growth per feed projected against a size budget
+    pub async fn storage_forecast(&self, query: &StorageForecastQuery) -> Result<StorageForecast> {
+        self.send(self.request(Method::GET, "/api/analysis/storage-forecast").query(query)).await
+    }
+
+    /// Storage, items per feed and day, top senders and run error rates
+    pub async fn stats(&self, query: &StatsQuery) -> Result<StatsResponse> {
+        self.send(self.request(Method::GET, "/api/stats").query(query)).await
+    }
+
+    // Background service and processing runs
+
+    pub async fn background_status(&self) -> Result<BackgroundStatusResponse> {
+        self.send(self.request(Method::GET, "/api/background/status")).await
+    }
+
+    pub async fn start_background(&self, request: &StartServiceRequest) -> Result<ServiceActionResponse> {
+        self.send(self.request(Method::POST, "/api/background/start").json(request)).await
+    }
+
+    pub async fn stop_background(&self) -> Result<ServiceActionResponse> {
+        self.send(self.request(Method::POST, "/api/background/stop")).await
+    }
+
+    pub async fn restart_background(&self) -> Result<ServiceActionResponse> {
+        self.send(self.request(Method::POST, "/api/background/restart")).await
+    }
+
+    pub async fn update_background_config(&self, config: &BackgroundConfig) -> Result<BackgroundConfig> {
+        self.send(self.request(Method::PUT, "/api/background/config").json(config)).await
+    }
+
+    pub async fn queue_account_processing(&self, account_id: &str) -> Result<BackgroundProcessResponse> {
+        self.send(self.request(Method::POST, &format!("/api/background/process/{}", account_id))).await
+    }
+
+    pub async fn queue_all_processing(&self) -> Result<ServiceActionResponse> {
+        self.send(self.request(Method::POST, "/api/background/process-all")).await
+    }
+
+    /// Recorded processing runs, most recently started first
+    pub async fn list_runs(&self, query: &ProcessingRunQuery) -> Result<Vec<ProcessingRun>> {
+        self.send(self.request(Method::GET, "/api/background/runs").query(query)).await
+    }
+
+    pub async fn get_run(&self, run_id: &str) -> Result<ProcessingRun> {
+        self.send(self.request(Method::GET, &format!("/api/background/runs/{}", run_id))).await
+    }
+
+    pub async fn get_run_intents(&self, run_id: &str) -> Result<Vec<ProcessingIntent>> {
+        self.send(self.request(Method::GET, &format!("/api/background/runs/{}/intents", run_id))).await
+    }
+
+    pub async fn rollback_run(&self, run_id: &str) -> Result<RollbackResult> {
+        self.send(self.request(Method::POST, &format!("/api/background/runs/{}/rollback", run_id))).await
+    }
+
+    // Jobs
+
+    /// Jobs, most recently created first
+    pub async fn list_jobs(&self, query: &JobQuery) -> Result<Vec<JobResponse>> {
+        self.send(self.request(Method::GET, "/api/jobs").query(query)).await
+    }
+
+    pub async fn get_job(&self, job_id: &str) -> Result<JobResponse> {
+        self.send(self.request(Method::GET, &format!("/api/jobs/{}", job_id))).await
+    }
+
+    // Maintenance
+
+    pub async fn backfill_metadata(&self, request: &BackfillMetadataRequest) -> Result<TaskStartedResponse> {
+        self.send(self.request(Method::POST, "/api/admin/maintenance/backfill-metadata").json(request)).await
+    }
+
+    pub async fn list_tasks(&self) -> Result<Vec<TaskStatus>> {
+        self.send(self.request(Method::GET, "/api/admin/maintenance/tasks")).await
+    }
+
+    pub async fn get_task(&self, task_id: &str) -> Result<TaskStatus> {
+        self.send(self.request(Method::GET, &format!("/api/admin/maintenance/tasks/{}", task_id))).await
+    }
+
+    /// Running version and the changes since the version that ran before it
+    pub async fn version(&self) -> Result<VersionResponse> {
+        self.send(self.request(Method::GET, "/api/admin/version")).await
+    }
+
+    /// Move every item of one feed into another, which takes over its URLs
+    pub async fn merge_feeds(&self, request: &MergeFeedsRequest) -> Result<FeedReorganizationResponse> {
+        self.send(self.request(Method::POST, "/api/admin/feeds/merge").json(request)).await
+    }
+
+    /// Move the items of a feed matching a filter into a new feed
+    pub async fn split_feed(&self, feed_id: &str, request: &SplitFeedRequest) -> Result<FeedReorganizationResponse> {
+        self.send(self.request(Method::POST, &format!("/api/admin/feeds/{}/split", feed_id)).json(request)).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, format!("{}{}", self.base_url, path))
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = Self::check(request.send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    async fn send_text(&self, request: RequestBuilder) -> Result<String> {
+        let response = Self::check(request.send().await?).await?;
+        Ok(response.text().await?)
+    }
+
+    async fn send_empty(&self, request: RequestBuilder) -> Result<()> {
+        Self::check(request.send().await?).await?;
+        Ok(())
+    }
+
+    /// Turn non-success responses into an [`ApiError`], using the server's
+    /// `{"error": ...}` body when it sent one
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&body)
+            .map(|e| e.error)
+            .unwrap_or(body);
+        Err(ApiError { status, message }.into())
+    }
+}
@@ -0,0 +1,186 @@
+//! Configuration profiles
+//!
+//! Configuration is read from environment variables. With `MAIL2FEED_PROFILE`
+//! set, the named profile of a TOML file (`MAIL2FEED_CONFIG`, by default
+//! `mail2feed.toml`) supplies them instead, so switching between a local
+//! database and a production-like one is a matter of picking a profile:
+//!
+//! ```toml
+//! [dev]
+//! database_url = "sqlite:../data/mail2feed.db"
+//! server_port = 3001
+//!
+//! [prod]
+//! database_url = "postgres://mail2feed@db/mail2feed"
+//! server_host = "0.0.0.0"
+//!
+//! [prod.background]
+//! global_interval_minutes = 15
+//!
+//! [prod.env]
+//! FEED_ITEM_LIMIT = "100"
+//! ```
+//!
+//! Variables set in the process environment override the profile, and the
+//! profile overrides `.env`. Unknown keys are an error, so a typo does not
+//! quietly leave a production setting at its default.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Configuration file read when `MAIL2FEED_CONFIG` is unset
+pub const DEFAULT_CONFIG_FILE: &str = "mail2feed.toml";
+
+/// Settings of one profile; each maps to the environment variable in its comment
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// `DATABASE_URL`
+    pub database_url: Option<String>,
+    /// `SERVER_HOST`
+    pub server_host: Option<String>,
+    /// `SERVER_PORT`
+    pub server_port: Option<u16>,
+    /// `CORS_ALLOWED_ORIGINS`
+    pub cors_allowed_origins: Option<String>,
+    #[serde(default)]
+    pub background: BackgroundProfile,
+    /// Any other variables, by name
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Background processing settings of a profile (`BACKGROUND_*`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackgroundProfile {
+    pub enabled: Option<bool>,
+    pub global_interval_minutes: Option<u64>,
+    pub per_account_interval_minutes: Option<u64>,
+    pub max_concurrent_accounts: Option<usize>,
+    pub change_debounce_seconds: Option<u64>,
+    pub catch_up_interrupted: Option<bool>,
+}
+
+impl Profile {
+    /// The environment variables this profile sets
+    pub fn variables(&self) -> BTreeMap<String, String> {
+        let mut variables = self.env.clone();
+        let mut set = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                variables.insert(name.to_string(), value);
+            }
+        };
+        set("DATABASE_URL", self.database_url.clone());
+        set("SERVER_HOST", self.server_host.clone());
+        set("SERVER_PORT", self.server_port.map(|port| port.to_string()));
+        set("CORS_ALLOWED_ORIGINS", self.cors_allowed_origins.clone());
+        let background = &self.background;
+        set("BACKGROUND_PROCESSING_ENABLED", background.enabled.map(|enabled| enabled.to_string()));
+        set("BACKGROUND_GLOBAL_INTERVAL_MINUTES", background.global_interval_minutes.map(|minutes| minutes.to_string()));
+        set("BACKGROUND_PER_ACCOUNT_INTERVAL_MINUTES", background.per_account_interval_minutes.map(|minutes| minutes.to_string()));
+        set("BACKGROUND_MAX_CONCURRENT_ACCOUNTS", background.max_concurrent_accounts.map(|accounts| accounts.to_string()));
+        set("BACKGROUND_CHANGE_DEBOUNCE_SECONDS", background.change_debounce_seconds.map(|seconds| seconds.to_string()));
+        set("BACKGROUND_CATCH_UP_INTERRUPTED", background.catch_up_interrupted.map(|enabled| enabled.to_string()));
+        variables
+    }
+}
+
+/// Parse the profiles of a configuration file, by name
+pub fn parse(content: &str) -> Result<HashMap<String, Profile>> {
+    Ok(toml::from_str(content)?)
+}
+
+/// The profile named `name` in the file at `path`
+pub fn load_profile(path: &Path, name: &str) -> Result<Profile> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read configuration file {}", path.display()))?;
+    let mut profiles = parse(&content)
+        .with_context(|| format!("Invalid configuration file {}", path.display()))?;
+    profiles.remove(name).ok_or_else(|| {
+        let mut names: Vec<_> = profiles.keys().cloned().collect();
+        names.sort();
+        anyhow::anyhow!("No profile '{}' in {}; it has {}", name, path.display(), names.join(", "))
+    })
+}
+
+/// Variables present before `.env` was loaded, which a profile must not override
+pub fn process_variables() -> HashSet<String> {
+    std::env::vars_os().filter_map(|(name, _)| name.into_string().ok()).collect()
+}
+
+/// Apply the profile named by `MAIL2FEED_PROFILE`, if any, leaving the
+/// variables in `process_variables` alone; returns the profile's name and file
+pub fn apply_profile(process_variables: &HashSet<String>) -> Result<Option<(String, PathBuf)>> {
+    let Some(name) = std::env::var("MAIL2FEED_PROFILE").ok().filter(|name| !name.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let path = PathBuf::from(std::env::var("MAIL2FEED_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string()));
+    let profile = load_profile(&path, name.trim())?;
+    for (variable, value) in profile.variables() {
+        if !process_variables.contains(&variable) {
+            std::env::set_var(variable, value);
+        }
+    }
+    Ok(Some((name.trim().to_string(), path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[dev]
+database_url = "sqlite:../data/mail2feed.db"
+server_port = 3001
+
+[prod]
+database_url = "postgres://mail2feed@db/mail2feed"
+server_host = "0.0.0.0"
+
+[prod.background]
+enabled = true
+global_interval_minutes = 15
+
+[prod.env]
+FEED_ITEM_LIMIT = "100"
+"#;
+
+    #[test]
+    fn test_profile_variables() {
+        let profiles = parse(CONFIG).unwrap();
+        let dev = profiles["dev"].variables();
+        assert_eq!(dev.len(), 2);
+        assert_eq!(dev["SERVER_PORT"], "3001");
+
+        let prod = profiles["prod"].variables();
+        assert_eq!(prod["DATABASE_URL"], "postgres://mail2feed@db/mail2feed");
+        assert_eq!(prod["BACKGROUND_PROCESSING_ENABLED"], "true");
+        assert_eq!(prod["BACKGROUND_GLOBAL_INTERVAL_MINUTES"], "15");
+        assert_eq!(prod["FEED_ITEM_LIMIT"], "100");
+        assert!(!prod.contains_key("SERVER_PORT"));
+    }
+
+    #[test]
+    fn test_example_file_parses() {
+        let profiles = parse(include_str!("../mail2feed.example.toml")).unwrap();
+        assert!(["dev", "staging", "prod"].iter().all(|name| profiles.contains_key(*name)));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(parse("[prod]\ndatabase_ulr = \"sqlite:x.db\"\n").is_err());
+        assert!(parse("[prod.background]\ninterval = 5\n").is_err());
+    }
+
+    #[test]
+    fn test_missing_profile_names_the_others() {
+        let path = std::env::temp_dir().join(format!("mail2feed-config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, CONFIG).unwrap();
+        let error = load_profile(&path, "staging").unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("No profile 'staging'") && error.contains("dev, prod"), "{}", error);
+    }
+}
@@ -1,4 +1,3 @@
-use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool};
 use diesel::Connection;
 use dotenvy::dotenv;
@@ -110,8 +109,35 @@ pub enum DatabaseConnection {
     PostgreSQL(PostgresConnection),
 }
 
+/// Connection a transaction is open on, see `DatabasePool::transaction`
+pub enum Transaction<'a> {
+    SQLite(&'a mut diesel::sqlite::SqliteConnection),
+    #[cfg(feature = "postgres")]
+    PostgreSQL(&'a mut diesel::pg::PgConnection),
+}
+
 // Helper methods for DatabasePool compatibility
 impl DatabasePool {
+    /// Run `f` in a single transaction: what it writes is committed when it
+    /// returns `Ok` and rolled back when it returns an error. The operations
+    /// available inside are the methods of `Transaction` in `operations_generic`
+    pub fn transaction<T>(&self, f: impl FnOnce(&mut Transaction) -> Result<T>) -> Result<T> {
+        match self {
+            DatabasePool::SQLite(pool) => {
+                let mut pooled = pool.get()?;
+                let conn: &mut diesel::sqlite::SqliteConnection = &mut pooled;
+                conn.transaction(|conn| f(&mut Transaction::SQLite(conn)))
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pool) => {
+                let mut pooled = pool.get()?;
+                let conn: &mut diesel::pg::PgConnection = &mut pooled;
+                conn.transaction(|conn| f(&mut Transaction::PostgreSQL(conn)))
+            }
+        }
+    }
+
+
     /// Get a connection from the pool - returns appropriate connection type
     /// This provides compatibility with existing `.get()` calls
     pub fn get(&self) -> Result<DatabaseConnection> {
@@ -139,6 +165,7 @@ impl DatabasePool {
     pub fn as_sqlite_pool(&self) -> Result<&Pool<ConnectionManager<diesel::sqlite::SqliteConnection>>> {
         match self {
             DatabasePool::SQLite(pool) => Ok(pool),
+            #[allow(unreachable_patterns)]
             _ => Err(anyhow::anyhow!("Expected SQLite database but found PostgreSQL"))
         }
     }
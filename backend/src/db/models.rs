@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::db::schema::*;
@@ -15,6 +16,12 @@ pub enum EmailAction {
     MoveToFolder,
     #[serde(rename = "do_nothing")]
     DoNothing,
+    /// Set a flag or keyword, e.g. `\Flagged`; only in post-processing chains
+    #[serde(rename = "add_flag")]
+    AddFlag,
+    /// Copy to a folder, leaving the email where it is; only in post-processing chains
+    #[serde(rename = "copy_to_folder")]
+    CopyToFolder,
 }
 
 impl EmailAction {
@@ -24,20 +31,266 @@ impl EmailAction {
             EmailAction::Delete => "delete", 
             EmailAction::MoveToFolder => "move_to_folder",
             EmailAction::DoNothing => "do_nothing",
+            EmailAction::AddFlag => "add_flag",
+            EmailAction::CopyToFolder => "copy_to_folder",
         }
     }
     
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s {
             "delete" => EmailAction::Delete,
             "move_to_folder" => EmailAction::MoveToFolder,
             "do_nothing" => EmailAction::DoNothing,
+            "add_flag" => EmailAction::AddFlag,
+            "copy_to_folder" => EmailAction::CopyToFolder,
             _ => EmailAction::MarkAsRead, // Default
         }
     }
+    
+    /// Whether the email is gone from its folder afterwards
+    pub fn removes_email(&self) -> bool {
+        matches!(self, EmailAction::Delete | EmailAction::MoveToFolder)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+/// Importance an email declares in its X-Priority, Importance or Priority header
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Importance {
+    #[serde(rename = "high")]
+    High,
+    #[serde(rename = "normal")]
+    Normal,
+    #[serde(rename = "low")]
+    Low,
+}
+
+impl Importance {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Importance::High => "high",
+            Importance::Normal => "normal",
+            Importance::Low => "low",
+        }
+    }
+    
+    /// Parse a stored or requested importance; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "high" => Some(Importance::High),
+            "normal" => Some(Importance::Normal),
+            "low" => Some(Importance::Low),
+            _ => None,
+        }
+    }
+}
+
+/// How an account's mail arrives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountProtocol {
+    /// Fetched from the account's IMAP server by the scheduler
+    Imap,
+    /// Delivered to the built-in SMTP or LMTP listener; nothing is polled
+    Smtp,
+    /// Fetched from the account's JMAP server by the scheduler
+    Jmap,
+}
+
+impl AccountProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountProtocol::Imap => "imap",
+            AccountProtocol::Smtp => "smtp",
+            AccountProtocol::Jmap => "jmap",
+        }
+    }
+
+    /// Parse a stored or requested protocol; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "imap" => Some(AccountProtocol::Imap),
+            "smtp" => Some(AccountProtocol::Smtp),
+            "jmap" => Some(AccountProtocol::Jmap),
+            _ => None,
+        }
+    }
+
+    /// Whether the scheduler fetches the account's mail
+    pub fn is_polled(&self) -> bool {
+        matches!(self, AccountProtocol::Imap | AccountProtocol::Jmap)
+    }
+}
+
+/// How an account's connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionSecurity {
+    /// Plain text throughout
+    #[serde(rename = "none")]
+    None,
+    /// A plain connection upgraded with STARTTLS, usually on port 143
+    #[serde(rename = "starttls")]
+    StartTls,
+    /// TLS from the first byte (implicit TLS), usually on port 993
+    #[serde(rename = "ssl_tls")]
+    SslTls,
+}
+
+impl ConnectionSecurity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionSecurity::None => "none",
+            ConnectionSecurity::StartTls => "starttls",
+            ConnectionSecurity::SslTls => "ssl_tls",
+        }
+    }
+
+    /// Parse a stored or requested mode; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Some(ConnectionSecurity::None),
+            "starttls" => Some(ConnectionSecurity::StartTls),
+            "ssl_tls" => Some(ConnectionSecurity::SslTls),
+            _ => None,
+        }
+    }
+
+    /// The mode servers usually expect on `port`: implicit TLS on 993, plain
+    /// text on 143 unless TLS is wanted, and otherwise STARTTLS when TLS is
+    /// wanted
+    pub fn suggest(port: i32, use_tls: bool) -> Self {
+        match (port, use_tls) {
+            (_, false) => ConnectionSecurity::None,
+            (993, true) => ConnectionSecurity::SslTls,
+            (_, true) => ConnectionSecurity::StartTls,
+        }
+    }
+
+    pub fn uses_tls(&self) -> bool {
+        *self != ConnectionSecurity::None
+    }
+}
+
+/// Reader's rating of a feed item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rating {
+    #[serde(rename = "up")]
+    Up,
+    #[serde(rename = "down")]
+    Down,
+}
+
+impl Rating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rating::Up => "up",
+            Rating::Down => "down",
+        }
+    }
+
+    /// Parse a stored or requested rating; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "up" => Some(Rating::Up),
+            "down" => Some(Rating::Down),
+            _ => None,
+        }
+    }
+}
+
+/// How a feed merges emails into combined items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DigestMode {
+    /// One item per thread, following `References` and `In-Reply-To`
+    #[serde(rename = "thread")]
+    Thread,
+    /// One item per day
+    #[serde(rename = "day")]
+    Day,
+}
+
+impl DigestMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestMode::Thread => "thread",
+            DigestMode::Day => "day",
+        }
+    }
+
+    /// Parse a stored or requested mode; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "thread" => Some(DigestMode::Thread),
+            "day" => Some(DigestMode::Day),
+            _ => None,
+        }
+    }
+}
+
+/// Feeds searched for an email's Message-ID before storing it in a feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DedupScope {
+    /// Only the feed itself
+    #[default]
+    #[serde(rename = "feed")]
+    Feed,
+    /// Every feed on a rule of the account the email came from
+    #[serde(rename = "account")]
+    Account,
+    /// Every feed
+    #[serde(rename = "global")]
+    Global,
+}
+
+impl DedupScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DedupScope::Feed => "feed",
+            DedupScope::Account => "account",
+            DedupScope::Global => "global",
+        }
+    }
+
+    /// Parse a stored or requested scope; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "feed" => Some(DedupScope::Feed),
+            "account" => Some(DedupScope::Account),
+            "global" => Some(DedupScope::Global),
+            _ => None,
+        }
+    }
+}
+
+/// Order in which a rule works through the emails fetched in a run
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ProcessingOrder {
+    #[default]
+    #[serde(rename = "newest_first")]
+    NewestFirst,
+    #[serde(rename = "oldest_first")]
+    OldestFirst,
+}
+
+impl ProcessingOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingOrder::NewestFirst => "newest_first",
+            ProcessingOrder::OldestFirst => "oldest_first",
+        }
+    }
+    
+    /// Parse a stored or requested order; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "newest_first" => Some(ProcessingOrder::NewestFirst),
+            "oldest_first" => Some(ProcessingOrder::OldestFirst),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
 #[diesel(table_name = imap_accounts)]
 pub struct ImapAccount {
     pub id: Option<String>,    // Changed to match Nullable<Text> in schema
@@ -51,6 +304,48 @@ pub struct ImapAccount {
     pub updated_at: String,
     pub default_post_process_action: String,
     pub default_move_to_folder: Option<String>,
+    pub max_bytes_per_second: Option<i32>,
+    /// Identifies the mailbox behind the account; set once a connection has been made
+    pub fingerprint: Option<String>,
+    /// Pinned TLS fingerprint, `cert-sha256:<hex>` or `pubkey-sha256:<hex>`
+    pub tls_pin: Option<String>,
+    /// Quota group whose limits the account shares with the group's other accounts
+    pub quota_group_id: Option<String>,
+    /// Feeds allowed on the account's rules
+    pub max_feeds: Option<i32>,
+    /// Items allowed across the account's feeds
+    pub max_items: Option<i32>,
+    /// Minutes of processing allowed per UTC day
+    pub max_processing_minutes_per_day: Option<i32>,
+    /// Name sent as SNI and checked against the server's certificate, when
+    /// it is not `host`
+    pub tls_server_name: Option<String>,
+    /// `none`, `starttls` or `ssl_tls`; `use_tls` is set for the last two
+    pub security: String,
+    /// Connections allowed to the server in any hour
+    pub max_connections_per_hour: Option<i32>,
+    /// Seconds to wait after a fetch before the next one starts
+    pub min_fetch_delay_seconds: Option<i32>,
+    /// Most messages fetched from a folder at once
+    pub max_messages_per_fetch: Option<i32>,
+    /// Capabilities the server announced, space separated; detected once and
+    /// again whenever the fetch strategy has to be probed
+    pub server_capabilities: Option<String>,
+    /// Fetch strategy that last worked, tried first on the next fetch
+    pub fetch_strategy: Option<String>,
+    /// `imap`, or `smtp` for accounts whose mail is delivered to the SMTP listener
+    pub protocol: String,
+}
+
+impl ImapAccount {
+    pub fn connection_security(&self) -> ConnectionSecurity {
+        ConnectionSecurity::parse(&self.security)
+            .unwrap_or_else(|| ConnectionSecurity::suggest(self.port, self.use_tls))
+    }
+
+    pub fn account_protocol(&self) -> AccountProtocol {
+        AccountProtocol::parse(&self.protocol).unwrap_or(AccountProtocol::Imap)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
@@ -67,6 +362,18 @@ pub struct NewImapAccount {
     pub updated_at: String,
     pub default_post_process_action: String,
     pub default_move_to_folder: Option<String>,
+    pub max_bytes_per_second: Option<i32>,
+    pub tls_pin: Option<String>,
+    pub quota_group_id: Option<String>,
+    pub max_feeds: Option<i32>,
+    pub max_items: Option<i32>,
+    pub max_processing_minutes_per_day: Option<i32>,
+    pub tls_server_name: Option<String>,
+    pub security: String,
+    pub max_connections_per_hour: Option<i32>,
+    pub min_fetch_delay_seconds: Option<i32>,
+    pub max_messages_per_fetch: Option<i32>,
+    pub protocol: String,
 }
 
 impl NewImapAccount {
@@ -84,6 +391,18 @@ impl NewImapAccount {
             updated_at: now.to_rfc3339(),
             default_post_process_action: "mark_read".to_string(),
             default_move_to_folder: None,
+            max_bytes_per_second: None,
+            tls_pin: None,
+            quota_group_id: None,
+            max_feeds: None,
+            max_items: None,
+            max_processing_minutes_per_day: None,
+            tls_server_name: None,
+            security: ConnectionSecurity::suggest(port, use_tls).as_str().to_string(),
+            max_connections_per_hour: None,
+            min_fetch_delay_seconds: None,
+            max_messages_per_fetch: None,
+            protocol: AccountProtocol::Imap.as_str().to_string(),
         }
     }
     
@@ -101,11 +420,61 @@ impl NewImapAccount {
             updated_at: now.to_rfc3339(),
             default_post_process_action,
             default_move_to_folder,
+            max_bytes_per_second: None,
+            tls_pin: None,
+            quota_group_id: None,
+            max_feeds: None,
+            max_items: None,
+            max_processing_minutes_per_day: None,
+            tls_server_name: None,
+            security: ConnectionSecurity::suggest(port, use_tls).as_str().to_string(),
+            max_connections_per_hour: None,
+            min_fetch_delay_seconds: None,
+            max_messages_per_fetch: None,
+            protocol: AccountProtocol::Imap.as_str().to_string(),
+        }
+    }
+
+    /// The account as it will be stored, to connect or check quotas before saving it
+    pub fn to_account(&self) -> ImapAccount {
+        ImapAccount {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            host: self.host.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            password: self.password.clone(),
+            use_tls: self.use_tls,
+            created_at: self.created_at.clone(),
+            updated_at: self.updated_at.clone(),
+            default_post_process_action: self.default_post_process_action.clone(),
+            default_move_to_folder: self.default_move_to_folder.clone(),
+            max_bytes_per_second: self.max_bytes_per_second,
+            fingerprint: None,
+            tls_pin: self.tls_pin.clone(),
+            quota_group_id: self.quota_group_id.clone(),
+            max_feeds: self.max_feeds,
+            max_items: self.max_items,
+            max_processing_minutes_per_day: self.max_processing_minutes_per_day,
+            tls_server_name: self.tls_server_name.clone(),
+            security: self.security.clone(),
+            max_connections_per_hour: self.max_connections_per_hour,
+            min_fetch_delay_seconds: self.min_fetch_delay_seconds,
+            max_messages_per_fetch: self.max_messages_per_fetch,
+            server_capabilities: None,
+            fetch_strategy: None,
+            protocol: self.protocol.clone(),
         }
     }
+
+    /// Secure the connection with `security`, keeping `use_tls` in step
+    pub fn set_security(&mut self, security: ConnectionSecurity) {
+        self.use_tls = security.uses_tls();
+        self.security = security.as_str().to_string();
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
 #[diesel(table_name = email_rules)]
 pub struct EmailRule {
     pub id: Option<String>,
@@ -121,6 +490,54 @@ pub struct EmailRule {
     pub updated_at: String,
     pub post_process_action: String,
     pub move_to_folder: Option<String>,
+    /// Record matches for preview without creating feed items or touching the mailbox
+    pub observe_only: bool,
+    /// Only match emails of this importance ('high', 'normal' or 'low'); emails
+    /// without an importance header count as normal
+    pub importance: Option<String>,
+    /// 'newest_first' or 'oldest_first'; oldest first suits backfills, where
+    /// a run's quota or a failure should leave the newest mail for later
+    pub processing_order: String,
+    /// Highest UID of the folder already handled; runs fetch only mail above it
+    pub last_seen_uid: Option<i64>,
+    /// UIDVALIDITY of the folder when `last_seen_uid` was recorded; the mark
+    /// is ignored once the server reports another
+    pub uid_validity: Option<i64>,
+    /// Hours matched mail stays untouched before the post-process action is
+    /// applied; none or 0 applies it right away
+    pub post_process_delay_hours: Option<i32>,
+    /// Advanced matching: JSON expression of regex patterns combined with
+    /// `all`, `any` and `not`, checked along with the other criteria
+    pub match_expression: Option<String>,
+    /// Emails fetched from the folder per run; none for the default
+    pub fetch_limit: Option<i32>,
+    /// Whether emails already marked seen are fetched too
+    pub include_seen: bool,
+    /// Whether the folder's subfolders are processed as well
+    pub include_subfolders: bool,
+    /// Content filters applied to the emails before their items are created
+    pub strip_footers: bool,
+    pub strip_quoted_replies: bool,
+    pub strip_tracking_pixels: bool,
+    pub unwrap_tracking_links: bool,
+    /// Post-processing chain: JSON array of actions applied in order, each
+    /// after its own delay; when set it replaces `post_process_action`,
+    /// `move_to_folder` and `post_process_delay_hours`
+    pub post_process_actions: Option<String>,
+}
+
+impl EmailRule {
+    /// Grace period before the rule's action is applied, if it has one
+    pub fn post_process_delay(&self) -> Option<chrono::Duration> {
+        self.post_process_delay_hours
+            .filter(|hours| *hours > 0)
+            .map(|hours| chrono::Duration::hours(hours.into()))
+    }
+
+    /// The rule's processing order; newest first for unknown stored values
+    pub fn processing_order(&self) -> ProcessingOrder {
+        ProcessingOrder::parse(&self.processing_order).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
@@ -139,6 +556,19 @@ pub struct NewEmailRule {
     pub updated_at: String,
     pub post_process_action: String,
     pub move_to_folder: Option<String>,
+    pub observe_only: bool,
+    pub importance: Option<String>,
+    pub processing_order: String,
+    pub post_process_delay_hours: Option<i32>,
+    pub match_expression: Option<String>,
+    pub fetch_limit: Option<i32>,
+    pub include_seen: bool,
+    pub include_subfolders: bool,
+    pub strip_footers: bool,
+    pub strip_quoted_replies: bool,
+    pub strip_tracking_pixels: bool,
+    pub unwrap_tracking_links: bool,
+    pub post_process_actions: Option<String>,
 }
 
 impl NewEmailRule {
@@ -167,6 +597,19 @@ impl NewEmailRule {
             updated_at: now.to_rfc3339(),
             post_process_action: "mark_read".to_string(),
             move_to_folder: None,
+            observe_only: false,
+            importance: None,
+            processing_order: ProcessingOrder::default().as_str().to_string(),
+            post_process_delay_hours: None,
+            match_expression: None,
+            fetch_limit: None,
+            include_seen: true,
+            include_subfolders: false,
+            strip_footers: false,
+            strip_quoted_replies: false,
+            strip_tracking_pixels: true,
+            unwrap_tracking_links: false,
+            post_process_actions: None,
         }
     }
     
@@ -197,6 +640,19 @@ impl NewEmailRule {
             updated_at: now.to_rfc3339(),
             post_process_action,
             move_to_folder,
+            observe_only: false,
+            importance: None,
+            processing_order: ProcessingOrder::default().as_str().to_string(),
+            post_process_delay_hours: None,
+            match_expression: None,
+            fetch_limit: None,
+            include_seen: true,
+            include_subfolders: false,
+            strip_footers: false,
+            strip_quoted_replies: false,
+            strip_tracking_pixels: true,
+            unwrap_tracking_links: false,
+            post_process_actions: None,
         }
     }
     
@@ -225,7 +681,7 @@ impl NewEmailRule {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
 #[diesel(table_name = feeds)]
 pub struct Feed {
     pub id: Option<String>,
@@ -240,6 +696,67 @@ pub struct Feed {
     pub max_items: Option<i32>,
     pub max_age_days: Option<i32>,
     pub min_items: Option<i32>,
+    pub summary_length: Option<i32>,
+    /// Locale for display dates in items, e.g. `de_DE`
+    pub locale: Option<String>,
+    /// IANA timezone for display dates in items, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+    /// Item title template, e.g. `[{date}] {subject}`
+    pub title_template: Option<String>,
+    /// URL called for each new item, e.g. a Slack or Discord incoming webhook
+    pub webhook_url: Option<String>,
+    /// HTTP method of the webhook call; POST when unset
+    pub webhook_method: Option<String>,
+    /// JSON body template of the webhook call with `{{item.*}}` and `{{feed.*}}` variables
+    pub webhook_body: Option<String>,
+    /// Whether `/feeds/{id}/*` is served; unset follows `FEED_PUBLIC_ENDPOINTS`
+    pub public_access: Option<bool>,
+    /// Most items that may be pinned; unset uses `DEFAULT_MAX_PINNED`
+    pub max_pinned: Option<i32>,
+    /// Title items of emails without a subject from their body; unset enables it
+    pub auto_titles: Option<bool>,
+    /// CSS added to the feed's hosted item pages
+    pub page_css: Option<String>,
+    /// HTML shown above the item on its hosted page
+    pub page_header_html: Option<String>,
+    /// HTML shown below the item on its hosted page
+    pub page_footer_html: Option<String>,
+    /// Logo shown at the top of the feed's hosted item pages
+    pub page_logo_url: Option<String>,
+    /// Items cannot be edited or deleted and are chained by hash; cannot be turned off
+    pub append_only: bool,
+    /// Hash of the latest chained item of an append-only feed
+    pub chain_head: Option<String>,
+    /// Merge emails of the same thread ('thread') or day ('day') into one
+    /// item; unset keeps one item per email
+    pub digest_mode: Option<String>,
+    /// Item description template, e.g. `<p>{{from_name}}</p>{{summary}}`
+    pub description_template: Option<String>,
+    /// Most items in the rendered feed; unset uses `FEED_ITEM_LIMIT`
+    pub item_limit: Option<i32>,
+    /// Whether rendered items carry the full HTML body or only the summary; unset includes it
+    pub full_content: Option<bool>,
+    /// Username readers must send to get the RSS and Atom documents; unset leaves them open
+    pub auth_username: Option<String>,
+    /// Argon2 hash of the feed's password; not exposed
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub auth_password_hash: Option<String>,
+    /// Where the feed looks for emails it already has ('feed', 'account' or
+    /// 'global'); unset uses `FEED_DEDUP_SCOPE`
+    pub dedup_scope: Option<String>,
+}
+
+impl Feed {
+    /// How the feed merges emails into combined items, if it does
+    pub fn digest_mode(&self) -> Option<DigestMode> {
+        self.digest_mode.as_deref().and_then(DigestMode::parse)
+    }
+
+    /// The feed's own duplicate detection scope, if it overrides the default
+    pub fn dedup_scope(&self) -> Option<DedupScope> {
+        self.dedup_scope.as_deref().and_then(DedupScope::parse)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
@@ -254,9 +771,60 @@ pub struct NewFeed {
     pub is_active: bool,
     pub created_at: String,
     pub updated_at: String,
+    // Unset limits are stored as NULL, not the column defaults, so the
+    // default feed retention applies to them
+    #[diesel(treat_none_as_default_value = false)]
     pub max_items: Option<i32>,
+    #[diesel(treat_none_as_default_value = false)]
     pub max_age_days: Option<i32>,
+    #[diesel(treat_none_as_default_value = false)]
     pub min_items: Option<i32>,
+    pub summary_length: Option<i32>,
+    /// Locale for display dates in items, e.g. `de_DE`
+    pub locale: Option<String>,
+    /// IANA timezone for display dates in items, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+    /// Item title template, e.g. `[{date}] {subject}`
+    pub title_template: Option<String>,
+    /// URL called for each new item, e.g. a Slack or Discord incoming webhook
+    pub webhook_url: Option<String>,
+    /// HTTP method of the webhook call; POST when unset
+    pub webhook_method: Option<String>,
+    /// JSON body template of the webhook call with `{{item.*}}` and `{{feed.*}}` variables
+    pub webhook_body: Option<String>,
+    /// Whether `/feeds/{id}/*` is served; unset follows `FEED_PUBLIC_ENDPOINTS`
+    pub public_access: Option<bool>,
+    /// Most items that may be pinned; unset uses `DEFAULT_MAX_PINNED`
+    pub max_pinned: Option<i32>,
+    /// Title items of emails without a subject from their body; unset enables it
+    pub auto_titles: Option<bool>,
+    /// CSS added to the feed's hosted item pages
+    pub page_css: Option<String>,
+    /// HTML shown above the item on its hosted page
+    pub page_header_html: Option<String>,
+    /// HTML shown below the item on its hosted page
+    pub page_footer_html: Option<String>,
+    /// Logo shown at the top of the feed's hosted item pages
+    pub page_logo_url: Option<String>,
+    /// Items cannot be edited or deleted and are chained by hash; cannot be turned off
+    pub append_only: bool,
+    /// Hash of the latest chained item of an append-only feed
+    pub chain_head: Option<String>,
+    /// Merge emails of the same thread ('thread') or day ('day') into one
+    /// item; unset keeps one item per email
+    pub digest_mode: Option<String>,
+    /// Item description template, e.g. `<p>{{from_name}}</p>{{summary}}`
+    pub description_template: Option<String>,
+    /// Most items in the rendered feed; unset uses `FEED_ITEM_LIMIT`
+    pub item_limit: Option<i32>,
+    /// Whether rendered items carry the full HTML body or only the summary; unset includes it
+    pub full_content: Option<bool>,
+    /// Username readers must send to get the RSS and Atom documents; unset leaves them open
+    pub auth_username: Option<String>,
+    /// Argon2 hash of the feed's password
+    pub auth_password_hash: Option<String>,
+    /// Where the feed looks for emails it already has; unset uses `FEED_DEDUP_SCOPE`
+    pub dedup_scope: Option<String>,
 }
 
 impl NewFeed {
@@ -282,6 +850,29 @@ impl NewFeed {
             max_items: Some(100),       // Default: keep last 100 items
             max_age_days: Some(30),     // Default: keep items for 30 days
             min_items: Some(10),        // Default: always keep at least 10 items
+            summary_length: None,       // Default: use DEFAULT_SUMMARY_LENGTH
+            locale: None,
+            timezone: None,
+            title_template: None,
+            webhook_url: None,
+            webhook_method: None,
+            webhook_body: None,
+            public_access: None,
+            max_pinned: None,
+            auto_titles: None,
+            page_css: None,
+            page_header_html: None,
+            page_footer_html: None,
+            page_logo_url: None,
+            append_only: false,
+            chain_head: None,
+            digest_mode: None,
+            description_template: None,
+            item_limit: None,
+            full_content: None,
+            auth_username: None,
+            auth_password_hash: None,
+            dedup_scope: None,
         }
     }
 
@@ -307,14 +898,38 @@ impl NewFeed {
             is_active,
             created_at: now.to_rfc3339(),
             updated_at: now.to_rfc3339(),
-            max_items: max_items.or(Some(100)),       // Default: keep last 100 items
-            max_age_days: max_age_days.or(Some(30)),  // Default: keep items for 30 days
-            min_items: min_items.or(Some(10)),        // Default: always keep at least 10 items
+            // Limits left unset follow the default feed retention
+            max_items,
+            max_age_days,
+            min_items,
+            summary_length: None,
+            locale: None,
+            timezone: None,
+            title_template: None,
+            webhook_url: None,
+            webhook_method: None,
+            webhook_body: None,
+            public_access: None,
+            max_pinned: None,
+            auto_titles: None,
+            page_css: None,
+            page_header_html: None,
+            page_footer_html: None,
+            page_logo_url: None,
+            append_only: false,
+            chain_head: None,
+            digest_mode: None,
+            description_template: None,
+            item_limit: None,
+            full_content: None,
+            auth_username: None,
+            auth_password_hash: None,
+            dedup_scope: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
 #[diesel(table_name = feed_items)]
 pub struct FeedItem {
     pub id: Option<String>,
@@ -332,6 +947,53 @@ pub struct FeedItem {
     pub is_read: Option<bool>,
     pub starred: Option<bool>,
     pub body_size: Option<i32>,
+    pub processing_run_id: Option<String>,
+    pub content_hash: Option<String>,
+    pub canonical_item_id: Option<String>,
+    pub language: Option<String>,
+    /// Kept first in generated feeds and never removed by retention cleanup
+    pub pinned: bool,
+    /// Importance declared by the email ('high', 'normal' or 'low'), if any
+    pub importance: Option<String>,
+    /// Gmail category tab of the email ('primary', 'social', 'promotions',
+    /// 'updates' or 'forums'), if known
+    pub category: Option<String>,
+    /// Hash of the previous item of an append-only feed's chain; null for the first
+    pub chain_previous: Option<String>,
+    /// Hash of this item's content and `chain_previous`; null outside append-only feeds
+    pub chain_hash: Option<String>,
+    /// Sanitized HTML part of the email, when it had one; `email_body` holds its plain text
+    pub email_body_html: Option<String>,
+    /// Key of the blob holding `email_body` and `email_body_html` once they
+    /// were moved out of the database; both are null then
+    pub body_ref: Option<String>,
+    /// Reader's thumbs up or down ('up' or 'down'), if rated
+    pub rating: Option<String>,
+    /// Feed the item was first published in, once it was moved to another
+    /// by a merge or split; its GUID keeps using that feed
+    pub origin_feed_id: Option<String>,
+    /// Web page, or RFC 8058 one-click endpoint, unsubscribing from the
+    /// email's list; from its `List-Unsubscribe` header or footer
+    pub unsubscribe_url: Option<String>,
+    /// `mailto:` address from the email's `List-Unsubscribe` header
+    pub unsubscribe_mailto: Option<String>,
+    /// Whether `unsubscribe_url` takes a one-click unsubscribe POST
+    pub unsubscribe_one_click: bool,
+    /// `In-Reply-To` header of the email
+    pub in_reply_to: Option<String>,
+    /// `References` header of the email
+    pub email_references: Option<String>,
+    /// Message-ID of the first email of the item's thread
+    pub thread_id: Option<String>,
+    /// Message-IDs of the further emails merged into this item by its feed's
+    /// digest mode, one per line
+    pub digest_message_ids: Option<String>,
+    /// Content hash the item's RSS GUID is built from, so an email stored
+    /// again gets the same GUID; null for items that keep `{feed}_{item}`
+    pub guid: Option<String>,
+    /// Number given in the order items arrive and never reused, by which
+    /// sync clients such as those of the Fever API page through items
+    pub sync_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
@@ -352,6 +1014,26 @@ pub struct NewFeedItem {
     pub is_read: Option<bool>,
     pub starred: Option<bool>,
     pub body_size: Option<i32>,
+    pub processing_run_id: Option<String>,
+    pub content_hash: Option<String>,
+    pub canonical_item_id: Option<String>,
+    pub language: Option<String>,
+    /// Kept first in generated feeds and never removed by retention cleanup
+    pub pinned: bool,
+    pub importance: Option<String>,
+    pub category: Option<String>,
+    pub chain_previous: Option<String>,
+    pub chain_hash: Option<String>,
+    pub email_body_html: Option<String>,
+    pub body_ref: Option<String>,
+    pub unsubscribe_url: Option<String>,
+    pub unsubscribe_mailto: Option<String>,
+    pub unsubscribe_one_click: bool,
+    pub in_reply_to: Option<String>,
+    pub email_references: Option<String>,
+    pub thread_id: Option<String>,
+    pub digest_message_ids: Option<String>,
+    pub guid: Option<String>,
 }
 
 impl NewFeedItem {
@@ -384,6 +1066,1337 @@ impl NewFeedItem {
             is_read: Some(false),           // New items start unread
             starred: Some(false),           // New items start unstarred
             body_size: Some(body_size),     // Calculate body size
+            processing_run_id: None,
+            content_hash: None,
+            canonical_item_id: None,
+            language: None,
+            pinned: false,
+            importance: None,
+            category: None,
+            chain_previous: None,
+            chain_hash: None,
+            email_body_html: None,
+            body_ref: None,
+            unsubscribe_url: None,
+            unsubscribe_mailto: None,
+            unsubscribe_one_click: false,
+            in_reply_to: None,
+            email_references: None,
+            thread_id: None,
+            digest_message_ids: None,
+            guid: None,
+        }
+    }
+}
+
+/// Changes to an item another email is merged into by its feed's digest mode
+#[derive(Debug, Clone)]
+pub struct DigestMerge {
+    pub title: String,
+    pub pub_date: String,
+    pub email_message_id: Option<String>,
+    pub email_subject: Option<String>,
+    pub email_from: Option<String>,
+    pub description: Option<String>,
+    pub email_body: Option<String>,
+    pub email_body_html: Option<String>,
+    pub body_size: i32,
+    pub digest_message_ids: String,
+}
+
+/// URL of a feed merged into another, kept so readers subscribed to it follow
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = feed_redirects)]
+pub struct FeedRedirect {
+    pub old_feed_id: String,
+    pub feed_id: String,
+    pub created_at: String,
+}
+
+impl FeedRedirect {
+    pub fn new(old_feed_id: String, feed_id: String) -> Self {
+        Self {
+            old_feed_id,
+            feed_id,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// A file attached to the email an item was made from
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = attachments)]
+pub struct Attachment {
+    pub id: Option<String>,
+    pub feed_item_id: String,
+    /// Numbers an item's attachments from 1, in the order they appear in the email
+    pub position: i32,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub content: Vec<u8>,
+    pub created_at: String,
+}
+
+/// An attachment without its content, for listing enclosures
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = attachments)]
+pub struct AttachmentInfo {
+    pub feed_item_id: String,
+    pub position: i32,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = attachments)]
+pub struct NewAttachment {
+    pub id: String,
+    pub feed_item_id: String,
+    pub position: i32,
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub content: Vec<u8>,
+    pub created_at: String,
+}
+
+impl NewAttachment {
+    pub fn new(feed_item_id: String, position: i32, filename: Option<String>, content_type: String, content: Vec<u8>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            feed_item_id,
+            position,
+            filename,
+            content_type,
+            size_bytes: content.len() as i64,
+            content,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessingRunStatus {
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "completed")]
+    Completed,
+    #[serde(rename = "failed")]
+    Failed,
+    #[serde(rename = "rolled_back")]
+    RolledBack,
+    /// Interrupted by a crash or restart; found still running at startup
+    #[serde(rename = "aborted")]
+    Aborted,
+}
+
+impl ProcessingRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingRunStatus::Running => "running",
+            ProcessingRunStatus::Completed => "completed",
+            ProcessingRunStatus::Failed => "failed",
+            ProcessingRunStatus::RolledBack => "rolled_back",
+            ProcessingRunStatus::Aborted => "aborted",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "completed" => ProcessingRunStatus::Completed,
+            "failed" => ProcessingRunStatus::Failed,
+            "rolled_back" => ProcessingRunStatus::RolledBack,
+            "aborted" => ProcessingRunStatus::Aborted,
+            _ => ProcessingRunStatus::Running,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = processing_runs)]
+pub struct ProcessingRun {
+    pub id: Option<String>,
+    pub imap_account_id: String,
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub emails_processed: i32,
+    pub items_created: i32,
+    pub error_message: Option<String>,
+    pub bytes_received: i64,
+    pub bytes_sent: i64,
+    /// Start of the gap since the account's last completed run that the run
+    /// caught up on
+    pub catch_up_from: Option<String>,
+    pub catch_up_to: Option<String>,
+    /// Whether the catch-up hit its email limit before reaching `catch_up_from`
+    pub catch_up_truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = processing_runs)]
+pub struct NewProcessingRun {
+    pub id: String,
+    pub imap_account_id: String,
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub emails_processed: i32,
+    pub items_created: i32,
+    pub error_message: Option<String>,
+    pub bytes_received: i64,
+    pub bytes_sent: i64,
+    pub catch_up_from: Option<String>,
+    pub catch_up_to: Option<String>,
+    pub catch_up_truncated: bool,
+}
+
+impl NewProcessingRun {
+    pub fn new(imap_account_id: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            imap_account_id,
+            status: ProcessingRunStatus::Running.as_str().to_string(),
+            started_at: Utc::now().to_rfc3339(),
+            finished_at: None,
+            emails_processed: 0,
+            items_created: 0,
+            error_message: None,
+            bytes_received: 0,
+            bytes_sent: 0,
+            catch_up_from: None,
+            catch_up_to: None,
+            catch_up_truncated: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = processing_run_actions)]
+pub struct ProcessingRunAction {
+    pub id: Option<String>,
+    pub processing_run_id: String,
+    pub feed_item_id: Option<String>,
+    pub folder: String,
+    pub uid: i64,
+    pub email_message_id: Option<String>,
+    pub action: String,
+    pub target_folder: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = processing_run_actions)]
+pub struct NewProcessingRunAction {
+    pub id: String,
+    pub processing_run_id: String,
+    pub feed_item_id: Option<String>,
+    pub folder: String,
+    pub uid: i64,
+    pub email_message_id: Option<String>,
+    pub action: String,
+    pub target_folder: Option<String>,
+    pub created_at: String,
+}
+
+impl NewProcessingRunAction {
+    pub fn new(
+        processing_run_id: String,
+        feed_item_id: Option<String>,
+        folder: String,
+        uid: u32,
+        email_message_id: Option<String>,
+        action: &EmailAction,
+        target_folder: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            processing_run_id,
+            feed_item_id,
+            folder,
+            uid: uid as i64,
+            email_message_id,
+            action: action.as_str().to_string(),
+            target_folder,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProcessingIntentStatus {
+    /// Logged; the feed item and mailbox action may or may not have happened
+    #[serde(rename = "pending")]
+    Pending,
+    /// Feed item created and mailbox action applied
+    #[serde(rename = "applied")]
+    Applied,
+    /// Feed item or mailbox action failed; the email was left for the next run
+    #[serde(rename = "failed")]
+    Failed,
+    /// Feed item created; the mailbox action waits for the rule's delay
+    #[serde(rename = "deferred")]
+    Deferred,
+    /// Found pending at startup with its feed item in place
+    #[serde(rename = "reconciled")]
+    Reconciled,
+    /// Found pending at startup without its feed item, which was rebuilt from the snapshot
+    #[serde(rename = "recovered")]
+    Recovered,
+}
+
+impl ProcessingIntentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingIntentStatus::Pending => "pending",
+            ProcessingIntentStatus::Applied => "applied",
+            ProcessingIntentStatus::Failed => "failed",
+            ProcessingIntentStatus::Deferred => "deferred",
+            ProcessingIntentStatus::Reconciled => "reconciled",
+            ProcessingIntentStatus::Recovered => "recovered",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "applied" => ProcessingIntentStatus::Applied,
+            "failed" => ProcessingIntentStatus::Failed,
+            "deferred" => ProcessingIntentStatus::Deferred,
+            "reconciled" => ProcessingIntentStatus::Reconciled,
+            "recovered" => ProcessingIntentStatus::Recovered,
+            _ => ProcessingIntentStatus::Pending,
+        }
+    }
+}
+
+/// What a run decided to do with an email, logged before doing it
+///
+/// The snapshot holds the email as JSON so a feed item lost to a crash can
+/// be rebuilt after the email was moved or deleted; it is dropped once the
+/// intent is resolved.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = processing_intents)]
+pub struct ProcessingIntent {
+    pub id: Option<String>,
+    pub processing_run_id: String,
+    pub feed_id: String,
+    pub folder: String,
+    pub uid: i64,
+    pub email_message_id: Option<String>,
+    /// Post-processing action decided on: `mark_read`, `delete`, `move_to_folder` or `do_nothing`
+    pub action: String,
+    pub target_folder: Option<String>,
+    pub item_title: String,
+    #[serde(skip)]
+    pub email_snapshot: Option<String>,
+    pub feed_item_id: Option<String>,
+    /// `pending`, `applied`, `failed`, `deferred`, `reconciled` or `recovered`
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = processing_intents)]
+pub struct NewProcessingIntent {
+    pub id: String,
+    pub processing_run_id: String,
+    pub feed_id: String,
+    pub folder: String,
+    pub uid: i64,
+    pub email_message_id: Option<String>,
+    pub action: String,
+    pub target_folder: Option<String>,
+    pub item_title: String,
+    pub email_snapshot: Option<String>,
+    pub status: String,
+    pub created_at: String,
+}
+
+impl NewProcessingIntent {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        processing_run_id: String,
+        feed_id: String,
+        folder: String,
+        uid: u32,
+        email_message_id: Option<String>,
+        action: &EmailAction,
+        target_folder: Option<String>,
+        item_title: String,
+        email_snapshot: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            processing_run_id,
+            feed_id,
+            folder,
+            uid: uid as i64,
+            email_message_id,
+            action: action.as_str().to_string(),
+            target_folder,
+            item_title,
+            email_snapshot: Some(email_snapshot),
+            status: ProcessingIntentStatus::Pending.as_str().to_string(),
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DeferredActionStatus {
+    /// Waiting for its due time, or for the account to be reachable
+    #[serde(rename = "pending")]
+    Pending,
+    /// The email could not be acted on, e.g. as it left the folder
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl DeferredActionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeferredActionStatus::Pending => "pending",
+            DeferredActionStatus::Failed => "failed",
         }
     }
-}
\ No newline at end of file
+}
+
+/// Mailbox action held back by its rule's post-process delay
+///
+/// The background sweep applies it once `due_at` passes and then removes the
+/// row; the action is recorded with the run that created the item, so a
+/// rollback of that run reverses it.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = deferred_actions)]
+pub struct DeferredAction {
+    pub id: Option<String>,
+    pub email_rule_id: String,
+    pub imap_account_id: String,
+    pub processing_run_id: String,
+    pub processing_intent_id: String,
+    pub feed_item_id: String,
+    pub folder: String,
+    pub uid: i64,
+    pub email_message_id: Option<String>,
+    /// `mark_read`, `delete` or `move_to_folder`
+    pub action: String,
+    pub target_folder: Option<String>,
+    pub due_at: String,
+    /// `pending` or `failed`
+    pub status: String,
+    pub last_error: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = deferred_actions)]
+pub struct NewDeferredAction {
+    pub id: String,
+    pub email_rule_id: String,
+    pub imap_account_id: String,
+    pub processing_run_id: String,
+    pub processing_intent_id: String,
+    pub feed_item_id: String,
+    pub folder: String,
+    pub uid: i64,
+    pub email_message_id: Option<String>,
+    pub action: String,
+    pub target_folder: Option<String>,
+    pub due_at: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+impl NewDeferredAction {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        email_rule_id: String,
+        imap_account_id: String,
+        processing_run_id: String,
+        processing_intent_id: String,
+        feed_item_id: String,
+        folder: String,
+        uid: u32,
+        email_message_id: Option<String>,
+        action: &EmailAction,
+        target_folder: Option<String>,
+        due_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            email_rule_id,
+            imap_account_id,
+            processing_run_id,
+            processing_intent_id,
+            feed_item_id,
+            folder,
+            uid: uid as i64,
+            email_message_id,
+            action: action.as_str().to_string(),
+            target_folder,
+            due_at: due_at.to_rfc3339(),
+            status: DeferredActionStatus::Pending.as_str().to_string(),
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Cost of evaluating a rule in a processing run
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = rule_costs)]
+pub struct RuleCost {
+    pub id: Option<String>,
+    pub processing_run_id: String,
+    pub email_rule_id: String,
+    pub folder: String,
+    /// Emails fetched from the rule's folder
+    pub emails_fetched: i32,
+    /// Emails the rule's criteria were checked against
+    pub evaluations: i32,
+    pub matches: i32,
+    /// Milliseconds spent fetching the folder
+    pub fetch_ms: i64,
+    /// Microseconds spent checking the criteria
+    pub match_us: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = rule_costs)]
+pub struct NewRuleCost {
+    pub id: String,
+    pub processing_run_id: String,
+    pub email_rule_id: String,
+    pub folder: String,
+    pub emails_fetched: i32,
+    pub evaluations: i32,
+    pub matches: i32,
+    pub fetch_ms: i64,
+    pub match_us: i64,
+    pub created_at: String,
+}
+
+impl NewRuleCost {
+    pub fn new(processing_run_id: String, email_rule_id: String, folder: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            processing_run_id,
+            email_rule_id,
+            folder,
+            emails_fetched: 0,
+            evaluations: 0,
+            matches: 0,
+            fetch_ms: 0,
+            match_us: 0,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Email matched by an observe-only rule
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = rule_matches)]
+pub struct RuleMatch {
+    pub id: Option<String>,
+    pub email_rule_id: String,
+    pub email_message_id: String,
+    pub email_subject: String,
+    pub email_from: String,
+    pub email_date: String,
+    pub matched_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = rule_matches)]
+pub struct NewRuleMatch {
+    pub id: String,
+    pub email_rule_id: String,
+    pub email_message_id: String,
+    pub email_subject: String,
+    pub email_from: String,
+    pub email_date: String,
+    pub matched_at: String,
+}
+
+impl NewRuleMatch {
+    pub fn new(
+        email_rule_id: String,
+        email_message_id: String,
+        email_subject: String,
+        email_from: String,
+        email_date: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            email_rule_id,
+            email_message_id,
+            email_subject,
+            email_from,
+            email_date: email_date.to_rfc3339(),
+            matched_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Totals over the matches recorded for a rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RuleMatchStats {
+    pub match_count: i64,
+    pub last_matched_at: Option<String>,
+}
+
+/// History table a retention policy applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionTarget {
+    /// Finished processing runs, with their actions, intents and rule costs
+    ProcessingRuns,
+    /// Emails matched by observe-only rules
+    RuleMatches,
+    /// Webhook and chat deliveries that were delivered or failed for good
+    Deliveries,
+    /// Rule evaluation costs
+    RuleCosts,
+}
+
+impl RetentionTarget {
+    pub const ALL: [RetentionTarget; 4] = [
+        RetentionTarget::ProcessingRuns,
+        RetentionTarget::RuleMatches,
+        RetentionTarget::Deliveries,
+        RetentionTarget::RuleCosts,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetentionTarget::ProcessingRuns => "processing_runs",
+            RetentionTarget::RuleMatches => "rule_matches",
+            RetentionTarget::Deliveries => "deliveries",
+            RetentionTarget::RuleCosts => "rule_costs",
+        }
+    }
+
+    /// Parse a stored or requested target; `None` for unknown values
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|target| target.as_str() == s.trim().to_ascii_lowercase())
+    }
+}
+
+/// How long a history table keeps its rows; rows beyond `max_rows` (newest
+/// kept) or older than `max_age_days` are purged by the daily cleanup
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = retention_policies)]
+pub struct RetentionPolicy {
+    pub id: Option<String>,
+    /// `processing_runs`, `rule_matches`, `deliveries` or `rule_costs`
+    pub target: String,
+    pub max_rows: Option<i32>,
+    pub max_age_days: Option<i32>,
+    /// Rows purged from the table so far
+    pub rows_purged: i64,
+    pub last_purged_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = retention_policies)]
+pub struct NewRetentionPolicy {
+    pub id: String,
+    pub target: String,
+    pub max_rows: Option<i32>,
+    pub max_age_days: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl NewRetentionPolicy {
+    pub fn new(target: RetentionTarget, max_rows: Option<i32>, max_age_days: Option<i32>) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            target: target.as_str().to_string(),
+            max_rows,
+            max_age_days,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// A version of the backend that has run against the database
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = app_versions)]
+pub struct AppVersion {
+    pub version: String,
+    pub git_hash: Option<String>,
+    /// When this version first started, i.e. when it was upgraded to
+    pub first_run_at: String,
+    pub last_run_at: String,
+}
+
+impl AppVersion {
+    pub fn new(version: String, git_hash: Option<String>) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            version,
+            git_hash,
+            first_run_at: now.clone(),
+            last_run_at: now,
+        }
+    }
+}
+
+/// A setting changed at runtime, under one of the keys below
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = app_settings)]
+pub struct AppSetting {
+    pub key: String,
+    pub value: String,
+    pub updated_at: String,
+}
+
+impl AppSetting {
+    /// Base URL the instance is reached at from outside
+    pub const PUBLIC_BASE_URL: &'static str = "public_base_url";
+    /// Retention of feeds that leave a limit unset, as JSON
+    pub const FEED_RETENTION: &'static str = "feed_retention";
+    /// Failure alert thresholds and channels, as JSON
+    pub const NOTIFICATIONS: &'static str = "notifications";
+    /// Admin user created by the setup wizard, as JSON
+    pub const ADMIN_USER: &'static str = "admin_user";
+    /// User of the Fever sync API and its API key, as JSON
+    pub const FEVER: &'static str = "fever";
+
+    pub fn new(key: String, value: String) -> Self {
+        Self {
+            key,
+            value,
+            updated_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Items for sync clients, by their `sync_id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncSelection {
+    /// The items after this one, oldest first
+    After(i64),
+    /// The items before this one, newest first
+    Before(i64),
+    /// Exactly these items, oldest first
+    Ids(Vec<i64>),
+}
+
+/// Selection of items across feeds for the timeline, newest first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimelineFilter {
+    /// Only items of these feeds; all feeds when unset
+    pub feed_ids: Option<Vec<String>>,
+    pub exclude_feed_ids: Vec<String>,
+    pub unread_only: bool,
+    /// `(pub_date, id)` of the last item of the previous page
+    pub before: Option<(String, String)>,
+    pub limit: i64,
+}
+
+/// One page of a feed's items, in the order of the feed: pinned first, then
+/// newest first by `(pub_date, id)`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedItemPageFilter {
+    /// Only items published before this RFC 3339 timestamp
+    pub before: Option<String>,
+    /// Only items published after this RFC 3339 timestamp
+    pub after: Option<String>,
+    /// `(pinned, pub_date, id)` of the last item of the previous page
+    pub cursor: Option<(bool, String, String)>,
+    pub limit: Option<i64>,
+}
+
+/// Selection of a feed's items for a bulk change; the conditions set must
+/// all hold
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemSelection {
+    /// Only these items
+    pub item_ids: Option<Vec<String>>,
+    /// Only items published at or after this RFC 3339 timestamp
+    pub since: Option<String>,
+    /// Only items published before this RFC 3339 timestamp
+    pub until: Option<String>,
+    pub unread_only: bool,
+}
+
+/// Selection of processing runs for the run history, newest first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessingRunFilter {
+    pub imap_account_id: Option<String>,
+    pub status: Option<ProcessingRunStatus>,
+    /// Only runs started at or after this RFC 3339 timestamp
+    pub started_after: Option<String>,
+    /// Only runs started before this RFC 3339 timestamp
+    pub started_before: Option<String>,
+    pub limit: i64,
+}
+
+/// An account created by the setup wizard with its rules and their feeds
+pub type AccountSetup = (ImapAccount, Vec<EmailRule>, Vec<Feed>);
+
+/// Sender (`email_from`), item count and newest publication date
+pub type SenderItemCount = (Option<String>, i64, Option<String>);
+
+/// Sender (`email_from`) and item count
+pub type SenderCount = (Option<String>, i64);
+
+/// Account ID, run status and run count
+pub type RunStatusCount = (String, String, i64);
+
+/// Rated items of a feed from one sender with one rating: feed id, `From`
+/// header, rating and item count
+pub type RatingCount = (String, Option<String>, Option<String>, i64);
+
+/// A row retention may purge: its ID and the timestamp its age is judged by
+pub type RetentionCandidate = (String, String);
+
+/// Per-feed item count, sized item count, body size sum and oldest publication date
+pub type FeedStorageTotals = (String, i64, i64, Option<i64>, Option<String>);
+
+/// Totals over the items stored in a feed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedStorageStats {
+    pub feed_id: String,
+    pub item_count: i64,
+    /// Items with a recorded `body_size`; older items get one from the metadata backfill
+    pub sized_items: i64,
+    pub total_body_size: i64,
+    /// Items published since the start of the sampling window
+    pub recent_items: i64,
+    pub oldest_pub_date: Option<String>,
+}
+
+impl FeedStorageStats {
+    /// Combine per-feed totals with per-feed counts of recent items
+    pub fn from_rows(
+        totals: Vec<FeedStorageTotals>,
+        recent: Vec<(String, i64)>,
+    ) -> Vec<Self> {
+        totals
+            .into_iter()
+            .map(|(feed_id, item_count, sized_items, total_body_size, oldest_pub_date)| {
+                let recent_items = recent.iter()
+                    .find(|(recent_feed_id, _)| *recent_feed_id == feed_id)
+                    .map_or(0, |(_, count)| *count);
+                Self {
+                    feed_id,
+                    item_count,
+                    sized_items,
+                    total_body_size: total_body_size.unwrap_or(0),
+                    recent_items,
+                    oldest_pub_date,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Bytes a database holds, as the database reports them
+#[derive(Debug, Clone, Copy, QueryableByName)]
+pub struct DatabaseSize {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub bytes: i64,
+}
+
+/// Chat service a [`ChatIntegration`] posts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatPlatform {
+    #[serde(rename = "slack")]
+    Slack,
+    #[serde(rename = "discord")]
+    Discord,
+    #[serde(rename = "matrix")]
+    Matrix,
+}
+
+impl ChatPlatform {
+    pub const ALL: [ChatPlatform; 3] = [ChatPlatform::Slack, ChatPlatform::Discord, ChatPlatform::Matrix];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatPlatform::Slack => "slack",
+            ChatPlatform::Discord => "discord",
+            ChatPlatform::Matrix => "matrix",
+        }
+    }
+
+    /// The platform named `s`; `None` for unknown platforms
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|platform| platform.as_str() == s)
+    }
+}
+
+/// Slack, Discord or Matrix destination for a feed's new items
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = chat_integrations)]
+pub struct ChatIntegration {
+    pub id: Option<String>,
+    pub feed_id: String,
+    /// `slack`, `discord` or `matrix`
+    pub platform: String,
+    /// Incoming webhook URL for Slack and Discord
+    pub webhook_url: Option<String>,
+    /// Homeserver base URL for Matrix, e.g. `https://matrix.org`
+    pub matrix_homeserver: Option<String>,
+    /// Room ID for Matrix, e.g. `!abc123:matrix.org`
+    pub matrix_room_id: Option<String>,
+    /// Access token of the Matrix account posting to the room
+    pub matrix_access_token: Option<String>,
+    /// Message text with the feed webhook's `{{feed.*}}` and `{{item.*}}` variables
+    pub message_template: Option<String>,
+    /// Messages allowed per minute; more are dropped
+    pub rate_limit_per_minute: Option<i32>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = chat_integrations)]
+pub struct NewChatIntegration {
+    pub id: String,
+    pub feed_id: String,
+    pub platform: String,
+    pub webhook_url: Option<String>,
+    pub matrix_homeserver: Option<String>,
+    pub matrix_room_id: Option<String>,
+    pub matrix_access_token: Option<String>,
+    pub message_template: Option<String>,
+    pub rate_limit_per_minute: Option<i32>,
+    pub is_active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl NewChatIntegration {
+    pub fn new(feed_id: String, platform: ChatPlatform, is_active: bool) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            feed_id,
+            platform: platform.as_str().to_string(),
+            webhook_url: None,
+            matrix_homeserver: None,
+            matrix_room_id: None,
+            matrix_access_token: None,
+            message_template: None,
+            rate_limit_per_minute: None,
+            is_active,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// Whether an [`AccountSenderFilter`] lets senders through or keeps them out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SenderFilterKind {
+    #[serde(rename = "allow")]
+    Allow,
+    #[serde(rename = "deny")]
+    Deny,
+}
+
+impl SenderFilterKind {
+    pub const ALL: [SenderFilterKind; 2] = [SenderFilterKind::Allow, SenderFilterKind::Deny];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SenderFilterKind::Allow => "allow",
+            SenderFilterKind::Deny => "deny",
+        }
+    }
+
+    /// The kind named `s`; `None` for unknown kinds
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+}
+
+/// Sender address or domain an account lets through to its rules or keeps out
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = account_sender_filters)]
+pub struct AccountSenderFilter {
+    pub id: Option<String>,
+    pub imap_account_id: String,
+    /// `allow` or `deny`
+    pub kind: String,
+    /// Lowercase address such as `news@example.com`, or a domain such as
+    /// `example.com` that covers its subdomains
+    pub pattern: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = account_sender_filters)]
+pub struct NewAccountSenderFilter {
+    pub id: String,
+    pub imap_account_id: String,
+    pub kind: String,
+    pub pattern: String,
+    pub created_at: String,
+}
+
+impl NewAccountSenderFilter {
+    pub fn new(imap_account_id: String, kind: SenderFilterKind, pattern: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            imap_account_id,
+            kind: kind.as_str().to_string(),
+            pattern,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Feed of another rule that a rule also delivers its matching emails to
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = rule_feeds)]
+pub struct RuleFeed {
+    pub id: Option<String>,
+    pub email_rule_id: String,
+    pub feed_id: String,
+    /// Feeds with a higher priority receive an email first; the rule's own
+    /// feeds have priority 0
+    pub priority: i32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = rule_feeds)]
+pub struct NewRuleFeed {
+    pub id: String,
+    pub email_rule_id: String,
+    pub feed_id: String,
+    pub priority: i32,
+    pub created_at: String,
+}
+
+impl NewRuleFeed {
+    pub fn new(email_rule_id: String, feed_id: String, priority: i32) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            email_rule_id,
+            feed_id,
+            priority,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "delivered")]
+    Delivered,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Queued outbound HTTP request for a webhook or chat integration
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = deliveries)]
+pub struct Delivery {
+    pub id: Option<String>,
+    pub feed_id: String,
+    pub item_id: Option<String>,
+    /// `webhook` or `chat`
+    pub kind: String,
+    /// Deliveries in the same queue are sent in order, e.g. `chat:<integration id>`
+    pub queue: String,
+    pub method: String,
+    pub url: String,
+    /// JSON object of request headers; not exposed as it may hold credentials
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub headers: String,
+    pub body: String,
+    /// `pending`, `delivered` or `failed` once attempts are exhausted
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = deliveries)]
+pub struct NewDelivery {
+    pub id: String,
+    pub feed_id: String,
+    pub item_id: Option<String>,
+    pub kind: String,
+    pub queue: String,
+    pub method: String,
+    pub url: String,
+    pub headers: String,
+    pub body: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl NewDelivery {
+    pub fn new(feed_id: String, item_id: Option<String>, kind: &str, queue: String, method: String, url: String, headers: String, body: String) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            feed_id,
+            item_id,
+            kind: kind.to_string(),
+            queue,
+            method,
+            url,
+            headers,
+            body,
+            status: DeliveryStatus::Pending.as_str().to_string(),
+            attempts: 0,
+            next_attempt_at: now.clone(),
+            last_error: None,
+            delivered_at: None,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// Accounts sharing one set of resource limits, e.g. those of one household member
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = quota_groups)]
+pub struct QuotaGroup {
+    pub id: Option<String>,
+    pub name: String,
+    /// Feeds allowed across the group's accounts
+    pub max_feeds: Option<i32>,
+    /// Items allowed across the group's accounts
+    pub max_items: Option<i32>,
+    /// Minutes of processing allowed per UTC day across the group's accounts
+    pub max_processing_minutes_per_day: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = quota_groups)]
+pub struct NewQuotaGroup {
+    pub id: String,
+    pub name: String,
+    pub max_feeds: Option<i32>,
+    pub max_items: Option<i32>,
+    pub max_processing_minutes_per_day: Option<i32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl NewQuotaGroup {
+    pub fn new(name: String, max_feeds: Option<i32>, max_items: Option<i32>, max_processing_minutes_per_day: Option<i32>) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            max_feeds,
+            max_items,
+            max_processing_minutes_per_day,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// Sending addresses and domains treated as one logical sender, e.g. the
+/// rotating `mail1.substack.com`, `mail2.substack.com` of one newsletter
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = sender_aliases)]
+pub struct SenderAlias {
+    pub id: Option<String>,
+    /// Canonical name the members are reported and matched under
+    pub name: String,
+    /// Member addresses (`news@example.com`) and domains (`example.com`), one per line
+    pub addresses: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl SenderAlias {
+    pub fn address_list(&self) -> Vec<String> {
+        self.addresses.lines().map(str::to_string).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = sender_aliases)]
+pub struct NewSenderAlias {
+    pub id: String,
+    pub name: String,
+    pub addresses: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl NewSenderAlias {
+    pub fn new(name: String, addresses: &[String]) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            addresses: addresses.join("\n"),
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+/// State of a background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free slot of the job runner
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(JobStatus::Queued),
+            "running" => Some(JobStatus::Running),
+            "completed" => Some(JobStatus::Completed),
+            "failed" => Some(JobStatus::Failed),
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed)
+    }
+}
+
+/// A long-running operation started from the API, such as a backfill
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = jobs)]
+pub struct Job {
+    pub id: Option<String>,
+    pub kind: String,
+    /// The account, rule or feed the job works on
+    pub target_id: Option<String>,
+    pub status: String,
+    /// Units of work done so far, e.g. emails examined or rules processed
+    pub processed: i32,
+    /// Units of work in all, once known
+    pub total: Option<i32>,
+    /// What the job is doing at the moment
+    pub message: Option<String>,
+    pub error_message: Option<String>,
+    /// JSON summary of a completed job
+    pub result: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+impl Job {
+    pub fn job_status(&self) -> JobStatus {
+        JobStatus::parse(&self.status).unwrap_or(JobStatus::Failed)
+    }
+
+    /// Percentage done: 100 once finished, otherwise derived from `processed`
+    /// out of `total` and capped at 99, or `None` while the total is unknown
+    pub fn progress(&self) -> Option<u8> {
+        let status = self.job_status();
+        if status.is_finished() {
+            return Some(100);
+        }
+        if status == JobStatus::Queued {
+            return Some(0);
+        }
+        match self.total {
+            Some(total) if total > 0 => Some((i64::from(self.processed.max(0)) * 100 / i64::from(total)).min(99) as u8),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[diesel(table_name = jobs)]
+pub struct NewJob {
+    pub id: String,
+    pub kind: String,
+    pub target_id: Option<String>,
+    pub status: String,
+    pub processed: i32,
+    pub total: Option<i32>,
+    pub message: Option<String>,
+    pub error_message: Option<String>,
+    pub result: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+impl NewJob {
+    pub fn new(kind: &str, target_id: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            target_id,
+            status: JobStatus::Queued.as_str().to_string(),
+            processed: 0,
+            total: None,
+            message: None,
+            error_message: None,
+            result: None,
+            created_at: Utc::now().to_rfc3339(),
+            started_at: None,
+            finished_at: None,
+        }
+    }
+}
+
+/// Selection of jobs, newest first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobFilter {
+    pub kind: Option<String>,
+    pub target_id: Option<String>,
+    pub status: Option<JobStatus>,
+    pub limit: i64,
+}
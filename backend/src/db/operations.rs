@@ -37,6 +37,23 @@ impl ImapAccountOps {
                 imap_accounts::username.eq(&updated_account.username),
                 imap_accounts::password.eq(&updated_account.password),
                 imap_accounts::use_tls.eq(updated_account.use_tls),
+                imap_accounts::default_post_process_action.eq(&updated_account.default_post_process_action),
+                imap_accounts::default_move_to_folder.eq(&updated_account.default_move_to_folder),
+                imap_accounts::max_bytes_per_second.eq(updated_account.max_bytes_per_second),
+                imap_accounts::tls_pin.eq(&updated_account.tls_pin),
+                imap_accounts::tls_server_name.eq(&updated_account.tls_server_name),
+                imap_accounts::security.eq(&updated_account.security),
+                imap_accounts::quota_group_id.eq(&updated_account.quota_group_id),
+                imap_accounts::max_feeds.eq(updated_account.max_feeds),
+                imap_accounts::max_items.eq(updated_account.max_items),
+                imap_accounts::max_processing_minutes_per_day.eq(updated_account.max_processing_minutes_per_day),
+                imap_accounts::max_connections_per_hour.eq(updated_account.max_connections_per_hour),
+                imap_accounts::min_fetch_delay_seconds.eq(updated_account.min_fetch_delay_seconds),
+                imap_accounts::max_messages_per_fetch.eq(updated_account.max_messages_per_fetch),
+                imap_accounts::protocol.eq(&updated_account.protocol),
+                // Probe the server again with the edited settings
+                imap_accounts::server_capabilities.eq(None::<String>),
+                imap_accounts::fetch_strategy.eq(None::<String>),
                 imap_accounts::updated_at.eq(&updated_account.updated_at),
             ))
             .execute(conn)
@@ -45,6 +62,32 @@ impl ImapAccountOps {
         Self::get_by_id(conn, account_id)
     }
 
+    pub fn get_by_quota_group(conn: &mut SqliteConnection, group_id: &str) -> Result<Vec<ImapAccount>> {
+        imap_accounts::table
+            .filter(imap_accounts::quota_group_id.eq(group_id))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load accounts of quota group {}: {}", group_id, e))
+    }
+
+    pub fn update_fingerprint(conn: &mut SqliteConnection, account_id: &str, fingerprint: Option<&str>) -> Result<()> {
+        diesel::update(imap_accounts::table.filter(imap_accounts::id.eq(account_id)))
+            .set(imap_accounts::fingerprint.eq(fingerprint))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update fingerprint of IMAP account {}: {}", account_id, e))?;
+        Ok(())
+    }
+
+    pub fn update_server_profile(conn: &mut SqliteConnection, account_id: &str, capabilities: Option<&str>, strategy: Option<&str>) -> Result<()> {
+        diesel::update(imap_accounts::table.filter(imap_accounts::id.eq(account_id)))
+            .set((
+                imap_accounts::server_capabilities.eq(capabilities),
+                imap_accounts::fetch_strategy.eq(strategy),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update server profile of IMAP account {}: {}", account_id, e))?;
+        Ok(())
+    }
+
     pub fn delete(conn: &mut SqliteConnection, account_id: &str) -> Result<()> {
         diesel::delete(imap_accounts::table.filter(imap_accounts::id.eq(account_id)))
             .execute(conn)
@@ -104,6 +147,22 @@ impl EmailRuleOps {
                 email_rules::subject_contains.eq(&updated_rule.subject_contains),
                 email_rules::label.eq(&updated_rule.label),
                 email_rules::is_active.eq(updated_rule.is_active),
+                email_rules::observe_only.eq(updated_rule.observe_only),
+                email_rules::importance.eq(&updated_rule.importance),
+                email_rules::processing_order.eq(&updated_rule.processing_order),
+                email_rules::post_process_delay_hours.eq(updated_rule.post_process_delay_hours),
+                email_rules::match_expression.eq(&updated_rule.match_expression),
+                email_rules::fetch_limit.eq(updated_rule.fetch_limit),
+                email_rules::include_seen.eq(updated_rule.include_seen),
+                email_rules::include_subfolders.eq(updated_rule.include_subfolders),
+                email_rules::strip_footers.eq(updated_rule.strip_footers),
+                email_rules::strip_quoted_replies.eq(updated_rule.strip_quoted_replies),
+                email_rules::strip_tracking_pixels.eq(updated_rule.strip_tracking_pixels),
+                email_rules::unwrap_tracking_links.eq(updated_rule.unwrap_tracking_links),
+                email_rules::post_process_actions.eq(&updated_rule.post_process_actions),
+                // Check mail already seen against the edited rule
+                email_rules::last_seen_uid.eq(None::<i64>),
+                email_rules::uid_validity.eq(None::<i64>),
                 email_rules::updated_at.eq(&updated_rule.updated_at),
             ))
             .execute(conn)
@@ -112,6 +171,17 @@ impl EmailRuleOps {
         Self::get_by_id(conn, rule_id)
     }
 
+    pub fn set_high_water_mark(conn: &mut SqliteConnection, rule_id: &str, uid_validity: i64, last_seen_uid: i64) -> Result<()> {
+        diesel::update(email_rules::table.filter(email_rules::id.eq(rule_id)))
+            .set((
+                email_rules::uid_validity.eq(uid_validity),
+                email_rules::last_seen_uid.eq(last_seen_uid),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update last seen UID of email rule {}: {}", rule_id, e))?;
+        Ok(())
+    }
+
     pub fn delete(conn: &mut SqliteConnection, rule_id: &str) -> Result<()> {
         diesel::delete(email_rules::table.filter(email_rules::id.eq(rule_id)))
             .execute(conn)
@@ -169,6 +239,31 @@ impl FeedOps {
                 feeds::email_rule_id.eq(&updated_feed.email_rule_id),
                 feeds::feed_type.eq(&updated_feed.feed_type),
                 feeds::is_active.eq(updated_feed.is_active),
+                feeds::max_items.eq(updated_feed.max_items),
+                feeds::max_age_days.eq(updated_feed.max_age_days),
+                feeds::min_items.eq(updated_feed.min_items),
+                feeds::summary_length.eq(updated_feed.summary_length),
+                feeds::locale.eq(&updated_feed.locale),
+                feeds::timezone.eq(&updated_feed.timezone),
+                feeds::title_template.eq(&updated_feed.title_template),
+                feeds::webhook_url.eq(&updated_feed.webhook_url),
+                feeds::webhook_method.eq(&updated_feed.webhook_method),
+                feeds::webhook_body.eq(&updated_feed.webhook_body),
+                feeds::public_access.eq(updated_feed.public_access),
+                feeds::max_pinned.eq(updated_feed.max_pinned),
+                feeds::auto_titles.eq(updated_feed.auto_titles),
+                feeds::page_css.eq(&updated_feed.page_css),
+                feeds::page_header_html.eq(&updated_feed.page_header_html),
+                feeds::page_footer_html.eq(&updated_feed.page_footer_html),
+                feeds::page_logo_url.eq(&updated_feed.page_logo_url),
+                feeds::append_only.eq(updated_feed.append_only),
+                feeds::digest_mode.eq(&updated_feed.digest_mode),
+                feeds::description_template.eq(&updated_feed.description_template),
+                feeds::item_limit.eq(updated_feed.item_limit),
+                feeds::full_content.eq(updated_feed.full_content),
+                feeds::auth_username.eq(&updated_feed.auth_username),
+                feeds::auth_password_hash.eq(&updated_feed.auth_password_hash),
+                feeds::dedup_scope.eq(&updated_feed.dedup_scope),
                 feeds::updated_at.eq(&updated_feed.updated_at),
             ))
             .execute(conn)
@@ -177,6 +272,24 @@ impl FeedOps {
         Self::get_by_id(conn, feed_id)
     }
 
+    pub fn set_chain_head(conn: &mut SqliteConnection, feed_id: &str, head: &str) -> Result<()> {
+        diesel::update(feeds::table.filter(feeds::id.eq(feed_id)))
+            .set(feeds::chain_head.eq(head))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update chain head of feed {}: {}", feed_id, e))?;
+        Ok(())
+    }
+
+    /// Number of feeds on the rules of the given accounts
+    pub fn count_by_account_ids(conn: &mut SqliteConnection, account_ids: &[String]) -> Result<i64> {
+        feeds::table
+            .inner_join(email_rules::table)
+            .filter(email_rules::imap_account_id.eq_any(account_ids))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feeds: {}", e))
+    }
+
     pub fn delete(conn: &mut SqliteConnection, feed_id: &str) -> Result<()> {
         diesel::delete(feeds::table.filter(feeds::id.eq(feed_id)))
             .execute(conn)
@@ -188,6 +301,110 @@ impl FeedOps {
 pub struct FeedItemOps;
 
 impl FeedItemOps {
+    /// Number of items stored in the feeds of the given accounts
+    pub fn count_by_account_ids(conn: &mut SqliteConnection, account_ids: &[String]) -> Result<i64> {
+        feed_items::table
+            .inner_join(feeds::table.inner_join(email_rules::table))
+            .filter(email_rules::imap_account_id.eq_any(account_ids))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feed items: {}", e))
+    }
+
+    /// Number of items stored in a feed
+    pub fn count_by_feed_id(conn: &mut SqliteConnection, feed_id: &str) -> Result<i64> {
+        feed_items::table
+            .filter(feed_items::feed_id.eq(feed_id))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count items of feed {}: {}", feed_id, e))
+    }
+
+    /// Number of items in a feed not marked read
+    pub fn count_unread_by_feed_id(conn: &mut SqliteConnection, feed_id: &str) -> Result<i64> {
+        feed_items::table
+            .filter(feed_items::feed_id.eq(feed_id))
+            .filter(feed_items::is_read.is_null().or(feed_items::is_read.eq(false)))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count unread items of feed {}: {}", feed_id, e))
+    }
+
+    /// When each feed last got an item, by feed ID
+    pub fn newest_by_feed(conn: &mut SqliteConnection) -> Result<Vec<(String, Option<String>)>> {
+        feed_items::table
+            .group_by(feed_items::feed_id)
+            .select((feed_items::feed_id, diesel::dsl::max(feed_items::created_at)))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load newest feed items: {}", e))
+    }
+
+    /// Item count and newest publication date per `email_from`
+    pub fn counts_by_sender(conn: &mut SqliteConnection) -> Result<Vec<SenderItemCount>> {
+        feed_items::table
+            .group_by(feed_items::email_from)
+            .select((feed_items::email_from, diesel::dsl::count_star(), diesel::dsl::max(feed_items::pub_date)))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feed items by sender: {}", e))
+    }
+
+    /// Feed ID and publication date of every item published at or after `since`
+    pub fn pub_dates_since(conn: &mut SqliteConnection, since: &str) -> Result<Vec<(String, String)>> {
+        feed_items::table
+            .filter(feed_items::pub_date.ge(since))
+            .select((feed_items::feed_id, feed_items::pub_date))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load feed item dates: {}", e))
+    }
+
+    /// The `limit` senders with the most items published at or after `since`,
+    /// most first
+    pub fn top_senders(conn: &mut SqliteConnection, since: &str, limit: i64) -> Result<Vec<SenderCount>> {
+        feed_items::table
+            .filter(feed_items::pub_date.ge(since))
+            .filter(feed_items::email_from.is_not_null())
+            .group_by(feed_items::email_from)
+            .select((feed_items::email_from, diesel::dsl::count_star()))
+            .order((diesel::dsl::count_star().desc(), feed_items::email_from))
+            .limit(limit)
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count top senders: {}", e))
+    }
+
+    /// Rated item count per feed, `email_from` and rating
+    pub fn rating_counts(conn: &mut SqliteConnection) -> Result<Vec<RatingCount>> {
+        feed_items::table
+            .filter(feed_items::rating.is_not_null())
+            .group_by((feed_items::feed_id, feed_items::email_from, feed_items::rating))
+            .select((feed_items::feed_id, feed_items::email_from, feed_items::rating, diesel::dsl::count_star()))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feed item ratings: {}", e))
+    }
+
+    /// Item counts and body sizes of every feed with items, counting items
+    /// published at or after `since` as recent
+    pub fn storage_stats(conn: &mut SqliteConnection, since: &str) -> Result<Vec<FeedStorageStats>> {
+        let totals = feed_items::table
+            .group_by(feed_items::feed_id)
+            .select((
+                feed_items::feed_id,
+                diesel::dsl::count_star(),
+                diesel::dsl::count(feed_items::body_size),
+                diesel::dsl::sum(feed_items::body_size),
+                diesel::dsl::min(feed_items::pub_date),
+            ))
+            .load::<FeedStorageTotals>(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to compute feed item sizes: {}", e))?;
+        let recent = feed_items::table
+            .filter(feed_items::pub_date.ge(since))
+            .group_by(feed_items::feed_id)
+            .select((feed_items::feed_id, diesel::dsl::count_star()))
+            .load::<(String, i64)>(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count recent feed items: {}", e))?;
+
+        Ok(FeedStorageStats::from_rows(totals, recent))
+    }
+
     pub fn create(conn: &mut SqliteConnection, new_item: &NewFeedItem) -> Result<FeedItem> {
         tracing::info!("📝 Creating feed item: id={}, title={}, feed_id={}", new_item.id, new_item.title, new_item.feed_id);
         tracing::debug!("Feed item details: pub_date={}, author={:?}, body_size={:?}", 
@@ -218,10 +435,12 @@ impl FeedItemOps {
             .map_err(|e| anyhow::anyhow!("Failed to find feed item {}: {}", item_id, e))
     }
 
+    /// Items of a feed, pinned first, then newest first by `(pub_date, id)`
+    /// whatever order they were inserted in
     pub fn get_by_feed_id(conn: &mut SqliteConnection, feed_id: &str, limit: Option<i64>) -> Result<Vec<FeedItem>> {
         let mut query = feed_items::table
             .filter(feed_items::feed_id.eq(feed_id))
-            .order(feed_items::pub_date.desc())
+            .order((feed_items::pinned.desc(), feed_items::pub_date.desc(), feed_items::id.desc()))
             .into_boxed();
 
         if let Some(limit_val) = limit {
@@ -233,6 +452,209 @@ impl FeedItemOps {
             .map_err(|e| anyhow::anyhow!("Failed to load feed items for feed {}: {}", feed_id, e))
     }
 
+    /// A page of a feed's items in the order of [`Self::get_by_feed_id`],
+    /// starting after the page's cursor
+    pub fn get_page(conn: &mut SqliteConnection, feed_id: &str, filter: &FeedItemPageFilter) -> Result<Vec<FeedItem>> {
+        let mut query = feed_items::table
+            .filter(feed_items::feed_id.eq(feed_id))
+            .order((feed_items::pinned.desc(), feed_items::pub_date.desc(), feed_items::id.desc()))
+            .into_boxed();
+
+        if let Some(before) = &filter.before {
+            query = query.filter(feed_items::pub_date.lt(before));
+        }
+        if let Some(after) = &filter.after {
+            query = query.filter(feed_items::pub_date.gt(after));
+        }
+        if let Some((cursor_pinned, cursor_date, cursor_id)) = &filter.cursor {
+            let older = feed_items::pub_date.lt(cursor_date)
+                .or(feed_items::pub_date.eq(cursor_date).and(feed_items::id.lt(cursor_id)));
+            // Unpinned items follow all pinned ones
+            query = if *cursor_pinned {
+                query.filter(feed_items::pinned.eq(false).or(older))
+            } else {
+                query.filter(feed_items::pinned.eq(false).and(older))
+            };
+        }
+        if let Some(limit) = filter.limit {
+            query = query.limit(limit);
+        }
+
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load a page of feed {}: {}", feed_id, e))
+    }
+
+    /// Items across feeds ordered by `(pub_date, id)`, newest first
+    pub fn get_timeline(conn: &mut SqliteConnection, filter: &TimelineFilter) -> Result<Vec<FeedItem>> {
+        let mut query = feed_items::table
+            .order((feed_items::pub_date.desc(), feed_items::id.desc()))
+            .limit(filter.limit)
+            .into_boxed();
+
+        if let Some(feed_ids) = &filter.feed_ids {
+            query = query.filter(feed_items::feed_id.eq_any(feed_ids));
+        }
+        if !filter.exclude_feed_ids.is_empty() {
+            query = query.filter(feed_items::feed_id.ne_all(&filter.exclude_feed_ids));
+        }
+        if filter.unread_only {
+            query = query.filter(feed_items::is_read.is_null().or(feed_items::is_read.eq(false)));
+        }
+        if let Some((before_date, before_id)) = &filter.before {
+            query = query.filter(
+                feed_items::pub_date.lt(before_date)
+                    .or(feed_items::pub_date.eq(before_date).and(feed_items::id.lt(before_id))),
+            );
+        }
+
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load timeline: {}", e))
+    }
+
+    /// A feed's items matching `selection`, newest first
+    pub fn select(conn: &mut SqliteConnection, feed_id: &str, selection: &ItemSelection) -> Result<Vec<FeedItem>> {
+        let query = || {
+            let mut query = feed_items::table
+                .filter(feed_items::feed_id.eq(feed_id))
+                .into_boxed();
+            if selection.unread_only {
+                query = query.filter(feed_items::is_read.is_null().or(feed_items::is_read.eq(false)));
+            }
+            if let Some(since) = &selection.since {
+                query = query.filter(feed_items::pub_date.ge(since));
+            }
+            if let Some(until) = &selection.until {
+                query = query.filter(feed_items::pub_date.lt(until));
+            }
+            query
+        };
+        let mut items = match &selection.item_ids {
+            None => query().load::<FeedItem>(conn),
+            // In chunks, well below SQLite's limit on bound parameters
+            Some(item_ids) => item_ids.chunks(500).try_fold(Vec::new(), |mut items, chunk| {
+                items.extend(query().filter(feed_items::id.eq_any(chunk)).load::<FeedItem>(conn)?);
+                Ok(items)
+            }),
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to select items of feed {}: {}", feed_id, e))?;
+        items.sort_by(|a, b| (&b.pub_date, &b.id).cmp(&(&a.pub_date, &a.id)));
+        Ok(items)
+    }
+
+    /// Set the read or starred state of many items; a state left `None` is
+    /// kept. Returns the number of items changed
+    pub fn set_flags(conn: &mut SqliteConnection, item_ids: &[String], is_read: Option<bool>, starred: Option<bool>) -> Result<usize> {
+        let mut updated = 0;
+        for chunk in item_ids.chunks(500) {
+            let items = feed_items::table.filter(feed_items::id.eq_any(chunk));
+            let count = match (is_read, starred) {
+                (Some(is_read), Some(starred)) => diesel::update(items)
+                    .set((feed_items::is_read.eq(Some(is_read)), feed_items::starred.eq(Some(starred))))
+                    .execute(conn),
+                (Some(is_read), None) => diesel::update(items).set(feed_items::is_read.eq(Some(is_read))).execute(conn),
+                (None, Some(starred)) => diesel::update(items).set(feed_items::starred.eq(Some(starred))).execute(conn),
+                (None, None) => Ok(0),
+            };
+            updated += count.map_err(|e| anyhow::anyhow!("Failed to update feed items: {}", e))?;
+        }
+        Ok(updated)
+    }
+
+    /// Up to `limit` items for sync clients, by `sync_id`
+    pub fn get_for_sync(conn: &mut SqliteConnection, selection: &SyncSelection, limit: i64) -> Result<Vec<FeedItem>> {
+        let query = feed_items::table.limit(limit).into_boxed();
+        let query = match selection {
+            SyncSelection::After(sync_id) => query.filter(feed_items::sync_id.gt(sync_id)).order(feed_items::sync_id.asc()),
+            SyncSelection::Before(sync_id) => query.filter(feed_items::sync_id.lt(sync_id)).order(feed_items::sync_id.desc()),
+            SyncSelection::Ids(sync_ids) => query.filter(feed_items::sync_id.eq_any(sync_ids)).order(feed_items::sync_id.asc()),
+        };
+        query.load(conn).map_err(|e| anyhow::anyhow!("Failed to load feed items to sync: {}", e))
+    }
+
+    /// `sync_id`s of the items not marked read, or of the starred ones
+    pub fn get_sync_ids(conn: &mut SqliteConnection, starred: bool) -> Result<Vec<i64>> {
+        let query = feed_items::table.select(feed_items::sync_id).order(feed_items::sync_id.asc()).into_boxed();
+        let query = if starred {
+            query.filter(feed_items::starred.eq(true))
+        } else {
+            query.filter(feed_items::is_read.is_null().or(feed_items::is_read.eq(false)))
+        };
+        let sync_ids = query.load::<Option<i64>>(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load feed item sync IDs: {}", e))?;
+        Ok(sync_ids.into_iter().flatten().collect())
+    }
+
+    /// Mark read the items created before `before` in `feed_ids`, or in every
+    /// feed when `None`
+    pub fn mark_read_before(conn: &mut SqliteConnection, feed_ids: Option<&[String]>, before: &str) -> Result<usize> {
+        let mut items = feed_items::table
+            .filter(feed_items::created_at.lt(before))
+            .filter(feed_items::is_read.is_null().or(feed_items::is_read.eq(false)))
+            .into_boxed();
+        if let Some(feed_ids) = feed_ids {
+            items = items.filter(feed_items::feed_id.eq_any(feed_ids));
+        }
+        let item_ids: Vec<Option<String>> = items.select(feed_items::id).load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load unread feed items: {}", e))?;
+        Self::set_flags(conn, &item_ids.into_iter().flatten().collect::<Vec<_>>(), Some(true), None)
+    }
+
+    pub fn count_all(conn: &mut SqliteConnection) -> Result<i64> {
+        feed_items::table
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feed items: {}", e))
+    }
+
+    pub fn update_metadata(conn: &mut SqliteConnection, item_id: &str, is_read: Option<bool>, starred: Option<bool>) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::is_read.eq(is_read),
+                feed_items::starred.eq(starred),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    pub fn set_pinned(conn: &mut SqliteConnection, item_id: &str, pinned: bool) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set(feed_items::pinned.eq(pinned))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update pin of feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    pub fn set_rating(conn: &mut SqliteConnection, item_id: &str, rating: Option<&str>) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set(feed_items::rating.eq(rating))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update rating of feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    pub fn set_chain(conn: &mut SqliteConnection, item_id: &str, previous: Option<&str>, hash: &str) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::chain_previous.eq(previous),
+                feed_items::chain_hash.eq(hash),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to chain feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    pub fn count_pinned(conn: &mut SqliteConnection, feed_id: &str) -> Result<i64> {
+        feed_items::table
+            .filter(feed_items::feed_id.eq(feed_id))
+            .filter(feed_items::pinned.eq(true))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count pinned items of feed {}: {}", feed_id, e))
+    }
+
     #[allow(dead_code)]
     pub fn get_by_email_message_id(conn: &mut SqliteConnection, message_id: &str) -> Result<Option<FeedItem>> {
         feed_items::table
@@ -242,6 +664,24 @@ impl FeedItemOps {
             .map_err(|e| anyhow::anyhow!("Failed to find feed item by message ID {}: {}", message_id, e))
     }
 
+    /// Move items to another feed, recording the feed each came from unless
+    /// it had already moved before
+    pub fn move_to_feed(conn: &mut SqliteConnection, item_ids: &[String], feed_id: &str) -> Result<usize> {
+        let mut moved = 0;
+        // In chunks, well below SQLite's limit on bound parameters
+        for chunk in item_ids.chunks(500) {
+            diesel::update(feed_items::table.filter(feed_items::id.eq_any(chunk)).filter(feed_items::origin_feed_id.is_null()))
+                .set(feed_items::origin_feed_id.eq(feed_items::feed_id.nullable()))
+                .execute(conn)
+                .map_err(|e| anyhow::anyhow!("Failed to record the origin of moved items: {}", e))?;
+            moved += diesel::update(feed_items::table.filter(feed_items::id.eq_any(chunk)))
+                .set(feed_items::feed_id.eq(feed_id))
+                .execute(conn)
+                .map_err(|e| anyhow::anyhow!("Failed to move items to feed {}: {}", feed_id, e))?;
+        }
+        Ok(moved)
+    }
+
     #[allow(dead_code)]
     pub fn delete_by_feed_id(conn: &mut SqliteConnection, feed_id: &str) -> Result<()> {
         diesel::delete(feed_items::table.filter(feed_items::feed_id.eq(feed_id)))
@@ -257,67 +697,1444 @@ impl FeedItemOps {
             .map_err(|e| anyhow::anyhow!("Failed to delete feed item {}: {}", item_id, e))?;
         Ok(())
     }
-}
 
-// Convenience functions for the pool-based operations
+    /// Items missing computed metadata. Rows predating the body_size column
+    /// were given its default of 0, so a zero size with a stored body counts too
+    pub fn count_missing_metadata(conn: &mut SqliteConnection) -> Result<i64> {
+        feed_items::table
+            .filter(
+                feed_items::body_size.is_null()
+                    .or(feed_items::body_size.eq(0).and(feed_items::email_body.ne("")))
+                    .or(feed_items::content_hash.is_null())
+                    .or(feed_items::language.is_null())
+                    .or(feed_items::is_read.is_null())
+                    .or(feed_items::starred.is_null()),
+            )
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feed items missing metadata: {}", e))
+    }
 
-use diesel::r2d2::{ConnectionManager, Pool};
+    /// Next batch of items missing computed metadata, ordered by ID after `after_id`
+    pub fn get_missing_metadata_batch(conn: &mut SqliteConnection, after_id: Option<&str>, limit: i64) -> Result<Vec<FeedItem>> {
+        let mut query = feed_items::table
+            .filter(
+                feed_items::body_size.is_null()
+                    .or(feed_items::body_size.eq(0).and(feed_items::email_body.ne("")))
+                    .or(feed_items::content_hash.is_null())
+                    .or(feed_items::language.is_null())
+                    .or(feed_items::is_read.is_null())
+                    .or(feed_items::starred.is_null()),
+            )
+            .order(feed_items::id.asc())
+            .limit(limit)
+            .into_boxed();
 
-pub fn get_email_rules_by_account(
-    pool: &Pool<ConnectionManager<SqliteConnection>>,
-    account_id: &str,
-) -> Result<Vec<EmailRule>> {
-    let mut conn = pool.get()
-        .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-    EmailRuleOps::get_by_account_id(&mut conn, account_id)
-}
+        if let Some(after_id) = after_id {
+            query = query.filter(feed_items::id.gt(after_id));
+        }
 
-pub fn get_feeds_by_rule(
-    pool: &Pool<ConnectionManager<SqliteConnection>>,
-    rule_id: &str,
-) -> Result<Vec<Feed>> {
-    let mut conn = pool.get()
-        .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-    FeedOps::get_by_rule_id(&mut conn, rule_id)
-}
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load feed items missing metadata: {}", e))
+    }
 
-pub fn create_feed_item(
-    pool: &Pool<ConnectionManager<SqliteConnection>>,
-    new_item: NewFeedItem,
-) -> Result<String> {
-    tracing::debug!("📡 Getting database connection from pool for item: '{}'", new_item.title);
-    let mut conn = pool.get()
-        .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-    tracing::debug!("✅ Got database connection from pool");
-    
-    let item = FeedItemOps::create(&mut conn, &new_item)?;
-    let item_id = item.id.ok_or_else(|| anyhow::anyhow!("Created item has no ID"))?;
-    tracing::info!("🎉 Pool-based feed item creation complete: ID={}", item_id);
-    Ok(item_id)
-}
+    pub fn update_computed_metadata(
+        conn: &mut SqliteConnection,
+        item_id: &str,
+        body_size: i32,
+        content_hash: &str,
+        language: &str,
+        is_read: bool,
+        starred: bool,
+    ) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::body_size.eq(Some(body_size)),
+                feed_items::content_hash.eq(Some(content_hash)),
+                feed_items::language.eq(Some(language)),
+                feed_items::is_read.eq(Some(is_read)),
+                feed_items::starred.eq(Some(starred)),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update metadata for feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
 
-pub fn get_all_feeds(
-    pool: &Pool<ConnectionManager<SqliteConnection>>,
-) -> Result<Vec<Feed>> {
-    let mut conn = pool.get()
-        .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-    FeedOps::get_all(&mut conn)
-}
+    pub fn get_by_processing_run_id(conn: &mut SqliteConnection, run_id: &str) -> Result<Vec<FeedItem>> {
+        feed_items::table
+            .filter(feed_items::processing_run_id.eq(run_id))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load feed items for run {}: {}", run_id, e))
+    }
+
+    /// Find an item in another feed that holds the content for the same email
+    pub fn find_canonical(
+        conn: &mut SqliteConnection,
+        exclude_feed_id: &str,
+        message_id: Option<&str>,
+        content_hash: &str,
+    ) -> Result<Option<FeedItem>> {
+        let mut query = feed_items::table
+            .filter(feed_items::canonical_item_id.is_null())
+            .filter(feed_items::feed_id.ne(exclude_feed_id))
+            .into_boxed();
+
+        query = match message_id {
+            Some(message_id) => query.filter(
+                feed_items::email_message_id.eq(message_id)
+                    .or(feed_items::content_hash.eq(content_hash)),
+            ),
+            None => query.filter(feed_items::content_hash.eq(content_hash)),
+        };
+
+        query
+            .order(feed_items::created_at.asc())
+            .first(conn)
+            .optional()
+            .map_err(|e| anyhow::anyhow!("Failed to look up canonical item: {}", e))
+    }
+
+    pub fn get_linked(conn: &mut SqliteConnection, canonical_id: &str) -> Result<Vec<FeedItem>> {
+        feed_items::table
+            .filter(feed_items::canonical_item_id.eq(canonical_id))
+            .order(feed_items::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load items linked to {}: {}", canonical_id, e))
+    }
+
+    pub fn count_references(conn: &mut SqliteConnection, canonical_id: &str) -> Result<i64> {
+        feed_items::table
+            .filter(feed_items::canonical_item_id.eq(canonical_id))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count references to {}: {}", canonical_id, e))
+    }
+
+    /// Make a linked item hold the content itself
+    pub fn promote_to_canonical(conn: &mut SqliteConnection, item_id: &str, email_body: Option<String>, email_body_html: Option<String>) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::canonical_item_id.eq(None::<String>),
+                feed_items::email_body.eq(email_body),
+                feed_items::email_body_html.eq(email_body_html),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to promote feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    /// Point every item linked to `from_id` at `to_id` instead
+    pub fn relink(conn: &mut SqliteConnection, from_id: &str, to_id: &str) -> Result<usize> {
+        diesel::update(feed_items::table.filter(feed_items::canonical_item_id.eq(from_id)))
+            .set(feed_items::canonical_item_id.eq(to_id))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to relink items from {} to {}: {}", from_id, to_id, e))
+    }
+
+    /// Point an item at the blob now holding its body and drop the body from the row
+    pub fn set_body_ref(conn: &mut SqliteConnection, item_id: &str, body_ref: &str) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::body_ref.eq(body_ref),
+                feed_items::email_body.eq(None::<String>),
+                feed_items::email_body_html.eq(None::<String>),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to set body reference of feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    /// Put an item's body back into its row, dropping its blob reference
+    pub fn restore_body(conn: &mut SqliteConnection, item_id: &str, email_body: Option<String>, email_body_html: Option<String>) -> Result<()> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::body_ref.eq(None::<String>),
+                feed_items::email_body.eq(email_body),
+                feed_items::email_body_html.eq(email_body_html),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to restore body of feed item {}: {}", item_id, e))?;
+        Ok(())
+    }
+
+    /// Whether a feed already has the email: an item, or an email merged
+    /// into a digest item, with its Message-ID, else an item with the same
+    /// title, sender and date, else one with the same content hash
+    pub fn is_duplicate(conn: &mut SqliteConnection, feed_id: &str, message_id: &str, title: &str, from: &str, pub_date: &str, content_hash: &str) -> Result<bool> {
+        let check = |conn: &mut SqliteConnection| -> diesel::QueryResult<bool> {
+            if !message_id.is_empty() {
+                let count: i64 = feed_items::table
+                    .filter(feed_items::feed_id.eq(feed_id))
+                    .filter(feed_items::email_message_id.eq(message_id)
+                        .or(feed_items::digest_message_ids.like(format!("%{}%", message_id))))
+                    .count()
+                    .get_result(conn)?;
+                if count > 0 {
+                    return Ok(true);
+                }
+            }
+            let count: i64 = feed_items::table
+                .filter(feed_items::feed_id.eq(feed_id))
+                .filter(feed_items::title.eq(title))
+                .filter(feed_items::email_from.eq(from))
+                .filter(feed_items::pub_date.eq(pub_date))
+                .count()
+                .get_result(conn)?;
+            if count > 0 || content_hash.is_empty() {
+                return Ok(count > 0);
+            }
+            let count: i64 = feed_items::table
+                .filter(feed_items::feed_id.eq(feed_id))
+                .filter(feed_items::content_hash.eq(content_hash))
+                .count()
+                .get_result(conn)?;
+            Ok(count > 0)
+        };
+        check(conn).map_err(|e| anyhow::anyhow!("Failed to check feed {} for duplicates: {}", feed_id, e))
+    }
+
+    /// Whether any feed, or any feed on a rule of `account_id`, has an item
+    /// of the email with this Message-ID
+    pub fn message_id_exists(conn: &mut SqliteConnection, message_id: &str, account_id: Option<&str>) -> Result<bool> {
+        let mut query = feed_items::table
+            .filter(feed_items::email_message_id.eq(message_id))
+            .into_boxed();
+        if let Some(account_id) = account_id {
+            let account_feeds = feeds::table
+                .inner_join(email_rules::table)
+                .filter(email_rules::imap_account_id.eq(account_id))
+                .select(feeds::id.assume_not_null());
+            query = query.filter(feed_items::feed_id.eq_any(account_feeds));
+        }
+        query
+            .select(feed_items::feed_id)
+            .first::<String>(conn)
+            .optional()
+            .map(|item| item.is_some())
+            .map_err(|e| anyhow::anyhow!("Failed to look up message {}: {}", message_id, e))
+    }
+
+    /// Newest item of a feed's thread that further emails can be merged into
+    pub fn get_digest_of_thread(conn: &mut SqliteConnection, feed_id: &str, thread_id: &str) -> Result<Option<FeedItem>> {
+        feed_items::table
+            .filter(feed_items::feed_id.eq(feed_id))
+            .filter(feed_items::thread_id.eq(thread_id))
+            .filter(feed_items::body_ref.is_null())
+            .filter(feed_items::canonical_item_id.is_null())
+            .order((feed_items::pub_date.desc(), feed_items::id.desc()))
+            .first(conn)
+            .optional()
+            .map_err(|e| anyhow::anyhow!("Failed to find thread {} in feed {}: {}", thread_id, feed_id, e))
+    }
+
+    /// Newest item of a feed published on `day` (`YYYY-MM-DD`, UTC) that
+    /// further emails can be merged into
+    pub fn get_digest_of_day(conn: &mut SqliteConnection, feed_id: &str, day: &str) -> Result<Option<FeedItem>> {
+        feed_items::table
+            .filter(feed_items::feed_id.eq(feed_id))
+            .filter(feed_items::pub_date.like(format!("{}%", day)))
+            .filter(feed_items::body_ref.is_null())
+            .filter(feed_items::canonical_item_id.is_null())
+            .order((feed_items::pub_date.desc(), feed_items::id.desc()))
+            .first(conn)
+            .optional()
+            .map_err(|e| anyhow::anyhow!("Failed to find items of {} in feed {}: {}", day, feed_id, e))
+    }
+
+    pub fn merge_digest(conn: &mut SqliteConnection, item_id: &str, merge: &DigestMerge) -> Result<FeedItem> {
+        diesel::update(feed_items::table.filter(feed_items::id.eq(item_id)))
+            .set((
+                feed_items::title.eq(&merge.title),
+                feed_items::pub_date.eq(&merge.pub_date),
+                feed_items::email_message_id.eq(&merge.email_message_id),
+                feed_items::email_subject.eq(&merge.email_subject),
+                feed_items::email_from.eq(&merge.email_from),
+                feed_items::author.eq(&merge.email_from),
+                feed_items::description.eq(&merge.description),
+                feed_items::email_body.eq(&merge.email_body),
+                feed_items::email_body_html.eq(&merge.email_body_html),
+                feed_items::body_size.eq(merge.body_size),
+                feed_items::digest_message_ids.eq(&merge.digest_message_ids),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to merge into feed item {}: {}", item_id, e))?;
+        Self::get_by_id(conn, item_id)
+    }
+
+    /// Items holding their own body in the database, outside append-only feeds
+    pub fn count_stored_bodies(conn: &mut SqliteConnection) -> Result<i64> {
+        feed_items::table
+            .filter(feed_items::body_ref.is_null())
+            .filter(feed_items::canonical_item_id.is_null())
+            .filter(feed_items::email_body.is_not_null().or(feed_items::email_body_html.is_not_null()))
+            .filter(feed_items::feed_id.ne_all(feeds::table.filter(feeds::append_only.eq(true)).select(feeds::id.assume_not_null())))
+            .count()
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count feed items with stored bodies: {}", e))
+    }
+
+    /// Next batch of items holding their own body in the database, outside
+    /// append-only feeds, ordered by ID after `after_id`
+    pub fn get_stored_bodies_batch(conn: &mut SqliteConnection, after_id: Option<&str>, limit: i64) -> Result<Vec<FeedItem>> {
+        let mut query = feed_items::table
+            .filter(feed_items::body_ref.is_null())
+            .filter(feed_items::canonical_item_id.is_null())
+            .filter(feed_items::email_body.is_not_null().or(feed_items::email_body_html.is_not_null()))
+            .filter(feed_items::feed_id.ne_all(feeds::table.filter(feeds::append_only.eq(true)).select(feeds::id.assume_not_null())))
+            .order(feed_items::id.asc())
+            .limit(limit)
+            .into_boxed();
+
+        if let Some(after_id) = after_id {
+            query = query.filter(feed_items::id.gt(after_id));
+        }
+
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load feed items with stored bodies: {}", e))
+    }
+
+    /// Every blob key items refer to
+    pub fn get_body_refs(conn: &mut SqliteConnection) -> Result<Vec<String>> {
+        feed_items::table
+            .filter(feed_items::body_ref.is_not_null())
+            .select(feed_items::body_ref.assume_not_null())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load body references: {}", e))
+    }
 
-pub fn get_feed_items_by_feed(
-    pool: &Pool<ConnectionManager<SqliteConnection>>,
-    feed_id: &str,
-) -> Result<Vec<FeedItem>> {
-    let mut conn = pool.get()
-        .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-    FeedItemOps::get_by_feed_id(&mut conn, feed_id, None)
+    pub fn delete_by_processing_run_id(conn: &mut SqliteConnection, run_id: &str) -> Result<usize> {
+        diesel::delete(feed_items::table.filter(feed_items::processing_run_id.eq(run_id)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete feed items for run {}: {}", run_id, e))
+    }
 }
 
-pub fn delete_feed_item(
-    pool: &Pool<ConnectionManager<SqliteConnection>>,
-    item_id: &str,
-) -> Result<()> {
-    let mut conn = pool.get()
-        .map_err(|e| anyhow::anyhow!("Failed to get database connection: {}", e))?;
-    FeedItemOps::delete(&mut conn, item_id)
-}
\ No newline at end of file
+pub struct ProcessingRunOps;
+
+impl ProcessingRunOps {
+    pub fn create(conn: &mut SqliteConnection, new_run: &NewProcessingRun) -> Result<ProcessingRun> {
+        diesel::insert_into(processing_runs::table)
+            .values(new_run)
+            .execute(conn)?;
+
+        Self::get_by_id(conn, &new_run.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, run_id: &str) -> Result<ProcessingRun> {
+        processing_runs::table
+            .filter(processing_runs::id.eq(run_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find processing run {}: {}", run_id, e))
+    }
+
+    pub fn finish(
+        conn: &mut SqliteConnection,
+        run_id: &str,
+        run_status: &ProcessingRunStatus,
+        emails_processed: i32,
+        items_created: i32,
+        error_message: Option<String>,
+    ) -> Result<ProcessingRun> {
+        diesel::update(processing_runs::table.filter(processing_runs::id.eq(run_id)))
+            .set((
+                processing_runs::status.eq(run_status.as_str()),
+                processing_runs::finished_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+                processing_runs::emails_processed.eq(emails_processed),
+                processing_runs::items_created.eq(items_created),
+                processing_runs::error_message.eq(error_message),
+            ))
+            .execute(conn)?;
+
+        Self::get_by_id(conn, run_id)
+    }
+
+    /// Count an item the run created, so an interrupted run's count stays right
+    pub fn count_item_created(conn: &mut SqliteConnection, run_id: &str) -> Result<()> {
+        diesel::update(processing_runs::table.filter(processing_runs::id.eq(run_id)))
+            .set(processing_runs::items_created.eq(processing_runs::items_created + 1))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count item of processing run {}: {}", run_id, e))?;
+        Ok(())
+    }
+
+    pub fn update_status(conn: &mut SqliteConnection, run_id: &str, run_status: &ProcessingRunStatus) -> Result<ProcessingRun> {
+        diesel::update(processing_runs::table.filter(processing_runs::id.eq(run_id)))
+            .set(processing_runs::status.eq(run_status.as_str()))
+            .execute(conn)?;
+
+        Self::get_by_id(conn, run_id)
+    }
+
+    /// Runs of the given accounts started at or after `since` (RFC 3339)
+    pub fn get_started_since(conn: &mut SqliteConnection, account_ids: &[String], since: &str) -> Result<Vec<ProcessingRun>> {
+        processing_runs::table
+            .filter(processing_runs::imap_account_id.eq_any(account_ids))
+            .filter(processing_runs::started_at.ge(since))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load processing runs: {}", e))
+    }
+
+    /// Run count per account and status of the runs started at or after `since`
+    pub fn status_counts_since(conn: &mut SqliteConnection, since: &str) -> Result<Vec<RunStatusCount>> {
+        processing_runs::table
+            .filter(processing_runs::started_at.ge(since))
+            .group_by((processing_runs::imap_account_id, processing_runs::status))
+            .select((processing_runs::imap_account_id, processing_runs::status, diesel::dsl::count_star()))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count processing runs: {}", e))
+    }
+
+    /// Runs ordered by `started_at`, newest first
+    pub fn list(conn: &mut SqliteConnection, filter: &ProcessingRunFilter) -> Result<Vec<ProcessingRun>> {
+        let mut query = processing_runs::table
+            .order((processing_runs::started_at.desc(), processing_runs::id.desc()))
+            .limit(filter.limit)
+            .into_boxed();
+
+        if let Some(account_id) = &filter.imap_account_id {
+            query = query.filter(processing_runs::imap_account_id.eq(account_id));
+        }
+        if let Some(run_status) = &filter.status {
+            query = query.filter(processing_runs::status.eq(run_status.as_str()));
+        }
+        if let Some(after) = &filter.started_after {
+            query = query.filter(processing_runs::started_at.ge(after));
+        }
+        if let Some(before) = &filter.started_before {
+            query = query.filter(processing_runs::started_at.lt(before));
+        }
+
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load processing runs: {}", e))
+    }
+
+    /// When each account last finished a completed run, by account ID
+    pub fn last_completed_by_account(conn: &mut SqliteConnection) -> Result<Vec<(String, Option<String>)>> {
+        processing_runs::table
+            .filter(processing_runs::status.eq(ProcessingRunStatus::Completed.as_str()))
+            .group_by(processing_runs::imap_account_id)
+            .select((processing_runs::imap_account_id, diesel::dsl::max(processing_runs::finished_at)))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load last completed runs: {}", e))
+    }
+
+    /// When the account's most recent completed run started (RFC 3339)
+    pub fn last_completed_start(conn: &mut SqliteConnection, account_id: &str) -> Result<Option<String>> {
+        processing_runs::table
+            .filter(processing_runs::imap_account_id.eq(account_id))
+            .filter(processing_runs::status.eq(ProcessingRunStatus::Completed.as_str()))
+            .select(diesel::dsl::max(processing_runs::started_at))
+            .get_result(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find last completed run of account {}: {}", account_id, e))
+    }
+
+    /// Mark every run still in the running state as aborted; returns the
+    /// aborted runs, oldest first
+    pub fn abort_running(conn: &mut SqliteConnection, error_message: &str) -> Result<Vec<ProcessingRun>> {
+        conn.transaction(|conn| {
+            let run_ids: Vec<Option<String>> = processing_runs::table
+                .filter(processing_runs::status.eq(ProcessingRunStatus::Running.as_str()))
+                .select(processing_runs::id)
+                .load(conn)?;
+
+            diesel::update(processing_runs::table.filter(processing_runs::id.eq_any(&run_ids)))
+                .set((
+                    processing_runs::status.eq(ProcessingRunStatus::Aborted.as_str()),
+                    processing_runs::finished_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+                    processing_runs::error_message.eq(Some(error_message)),
+                ))
+                .execute(conn)?;
+
+            processing_runs::table
+                .filter(processing_runs::id.eq_any(&run_ids))
+                .order(processing_runs::started_at.asc())
+                .load(conn)
+        })
+        .map_err(|e: diesel::result::Error| anyhow::anyhow!("Failed to abort interrupted processing runs: {}", e))
+    }
+
+    pub fn record_transfer(conn: &mut SqliteConnection, run_id: &str, bytes_received: i64, bytes_sent: i64) -> Result<ProcessingRun> {
+        diesel::update(processing_runs::table.filter(processing_runs::id.eq(run_id)))
+            .set((
+                processing_runs::bytes_received.eq(bytes_received),
+                processing_runs::bytes_sent.eq(bytes_sent),
+            ))
+            .execute(conn)?;
+
+        Self::get_by_id(conn, run_id)
+    }
+
+    pub fn record_catch_up(conn: &mut SqliteConnection, run_id: &str, from: &str, to: &str, truncated: bool) -> Result<ProcessingRun> {
+        diesel::update(processing_runs::table.filter(processing_runs::id.eq(run_id)))
+            .set((
+                processing_runs::catch_up_from.eq(Some(from)),
+                processing_runs::catch_up_to.eq(Some(to)),
+                processing_runs::catch_up_truncated.eq(truncated),
+            ))
+            .execute(conn)?;
+
+        Self::get_by_id(conn, run_id)
+    }
+}
+
+pub struct ProcessingRunActionOps;
+
+impl ProcessingRunActionOps {
+    pub fn create(conn: &mut SqliteConnection, new_action: &NewProcessingRunAction) -> Result<ProcessingRunAction> {
+        diesel::insert_into(processing_run_actions::table)
+            .values(new_action)
+            .execute(conn)?;
+
+        processing_run_actions::table
+            .filter(processing_run_actions::id.eq(&new_action.id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find processing run action {}: {}", new_action.id, e))
+    }
+
+    pub fn get_by_run_id(conn: &mut SqliteConnection, run_id: &str) -> Result<Vec<ProcessingRunAction>> {
+        processing_run_actions::table
+            .filter(processing_run_actions::processing_run_id.eq(run_id))
+            .order(processing_run_actions::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load actions for run {}: {}", run_id, e))
+    }
+}
+
+pub struct ProcessingIntentOps;
+
+impl ProcessingIntentOps {
+    pub fn create(conn: &mut SqliteConnection, new_intent: &NewProcessingIntent) -> Result<ProcessingIntent> {
+        diesel::insert_into(processing_intents::table)
+            .values(new_intent)
+            .execute(conn)?;
+
+        processing_intents::table
+            .filter(processing_intents::id.eq(&new_intent.id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find processing intent {}: {}", new_intent.id, e))
+    }
+
+    /// Record the outcome of an intent and drop its email snapshot
+    pub fn resolve(
+        conn: &mut SqliteConnection,
+        intent_id: &str,
+        intent_status: &ProcessingIntentStatus,
+        item_id: Option<&str>,
+    ) -> Result<ProcessingIntent> {
+        diesel::update(processing_intents::table.filter(processing_intents::id.eq(intent_id)))
+            .set((
+                processing_intents::status.eq(intent_status.as_str()),
+                processing_intents::feed_item_id.eq(item_id),
+                processing_intents::email_snapshot.eq(None::<String>),
+                processing_intents::resolved_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+            ))
+            .execute(conn)?;
+
+        processing_intents::table
+            .filter(processing_intents::id.eq(intent_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find processing intent {}: {}", intent_id, e))
+    }
+
+    /// Intents not resolved yet, oldest first
+    pub fn get_pending(conn: &mut SqliteConnection) -> Result<Vec<ProcessingIntent>> {
+        processing_intents::table
+            .filter(processing_intents::status.eq(ProcessingIntentStatus::Pending.as_str()))
+            .order(processing_intents::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load pending processing intents: {}", e))
+    }
+
+    pub fn get_by_run_id(conn: &mut SqliteConnection, run_id: &str) -> Result<Vec<ProcessingIntent>> {
+        processing_intents::table
+            .filter(processing_intents::processing_run_id.eq(run_id))
+            .order(processing_intents::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load intents for run {}: {}", run_id, e))
+    }
+}
+
+pub struct DeferredActionOps;
+
+impl DeferredActionOps {
+    pub fn create(conn: &mut SqliteConnection, new_action: &NewDeferredAction) -> Result<DeferredAction> {
+        diesel::insert_into(deferred_actions::table)
+            .values(new_action)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to defer {} of email UID {}: {}", new_action.action, new_action.uid, e))?;
+
+        deferred_actions::table
+            .filter(deferred_actions::id.eq(&new_action.id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find deferred action {}: {}", new_action.id, e))
+    }
+
+    /// Pending actions due at or before `now`, earliest first
+    pub fn get_due(conn: &mut SqliteConnection, now: &str) -> Result<Vec<DeferredAction>> {
+        deferred_actions::table
+            .filter(deferred_actions::status.eq(DeferredActionStatus::Pending.as_str()))
+            .filter(deferred_actions::due_at.le(now))
+            .order(deferred_actions::due_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load due deferred actions: {}", e))
+    }
+
+    pub fn mark_failed(conn: &mut SqliteConnection, action_id: &str, error: &str) -> Result<()> {
+        diesel::update(deferred_actions::table.filter(deferred_actions::id.eq(action_id)))
+            .set((
+                deferred_actions::status.eq(DeferredActionStatus::Failed.as_str()),
+                deferred_actions::last_error.eq(Some(error)),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update deferred action {}: {}", action_id, e))?;
+        Ok(())
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, action_id: &str) -> Result<()> {
+        diesel::delete(deferred_actions::table.filter(deferred_actions::id.eq(action_id)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete deferred action {}: {}", action_id, e))?;
+        Ok(())
+    }
+
+    /// Drop the actions of a run not applied yet; returns how many
+    pub fn delete_pending_by_run_id(conn: &mut SqliteConnection, run_id: &str) -> Result<usize> {
+        diesel::delete(
+            deferred_actions::table
+                .filter(deferred_actions::processing_run_id.eq(run_id))
+                .filter(deferred_actions::status.eq(DeferredActionStatus::Pending.as_str())),
+        )
+        .execute(conn)
+        .map_err(|e| anyhow::anyhow!("Failed to cancel deferred actions of run {}: {}", run_id, e))
+    }
+}
+
+pub struct AttachmentOps;
+
+impl AttachmentOps {
+    pub fn create_all(conn: &mut SqliteConnection, new_attachments: &[NewAttachment]) -> Result<usize> {
+        diesel::insert_into(attachments::table)
+            .values(new_attachments)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to store attachments: {}", e))
+    }
+
+    pub fn get(conn: &mut SqliteConnection, item_id: &str, position: i32) -> Result<Attachment> {
+        attachments::table
+            .filter(attachments::feed_item_id.eq(item_id))
+            .filter(attachments::position.eq(position))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find attachment {} of item {}: {}", position, item_id, e))
+    }
+
+    /// The attachments of the given items, without their content
+    pub fn list_for_items(conn: &mut SqliteConnection, item_ids: &[String]) -> Result<Vec<AttachmentInfo>> {
+        attachments::table
+            .filter(attachments::feed_item_id.eq_any(item_ids))
+            .order((attachments::feed_item_id.asc(), attachments::position.asc()))
+            .select(AttachmentInfo::as_select())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load attachments: {}", e))
+    }
+
+    /// Hand the attachments of one item over to another, e.g. a promoted copy
+    pub fn reassign(conn: &mut SqliteConnection, from_item_id: &str, to_item_id: &str) -> Result<usize> {
+        diesel::update(attachments::table.filter(attachments::feed_item_id.eq(from_item_id)))
+            .set(attachments::feed_item_id.eq(to_item_id))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to move attachments of item {}: {}", from_item_id, e))
+    }
+
+    /// Delete attachments whose item is gone, returning how many
+    pub fn delete_orphaned(conn: &mut SqliteConnection) -> Result<usize> {
+        let item_ids = feed_items::table.select(feed_items::id.assume_not_null());
+        diesel::delete(attachments::table.filter(attachments::feed_item_id.ne_all(item_ids)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete orphaned attachments: {}", e))
+    }
+}
+
+pub struct RuleMatchOps;
+
+impl RuleMatchOps {
+    /// Record a match unless the rule already matched this message; returns
+    /// whether it was new
+    pub fn create_if_new(conn: &mut SqliteConnection, new_match: &NewRuleMatch) -> Result<bool> {
+        let inserted = diesel::insert_or_ignore_into(rule_matches::table)
+            .values(new_match)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to record match for rule {}: {}", new_match.email_rule_id, e))?;
+        Ok(inserted > 0)
+    }
+
+    pub fn get_by_rule_id(conn: &mut SqliteConnection, rule_id: &str, limit: Option<i64>) -> Result<Vec<RuleMatch>> {
+        let mut query = rule_matches::table
+            .filter(rule_matches::email_rule_id.eq(rule_id))
+            .order(rule_matches::matched_at.desc())
+            .into_boxed();
+        if let Some(limit) = limit {
+            query = query.limit(limit);
+        }
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load matches for rule {}: {}", rule_id, e))
+    }
+
+    pub fn stats(conn: &mut SqliteConnection, rule_id: &str) -> Result<RuleMatchStats> {
+        let (match_count, last_matched_at) = rule_matches::table
+            .filter(rule_matches::email_rule_id.eq(rule_id))
+            .select((diesel::dsl::count_star(), diesel::dsl::max(rule_matches::matched_at)))
+            .first::<(i64, Option<String>)>(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to count matches for rule {}: {}", rule_id, e))?;
+        Ok(RuleMatchStats { match_count, last_matched_at })
+    }
+}
+
+pub struct RuleCostOps;
+
+impl RuleCostOps {
+    pub fn create(conn: &mut SqliteConnection, new_cost: &NewRuleCost) -> Result<()> {
+        diesel::insert_into(rule_costs::table)
+            .values(new_cost)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to record cost of rule {}: {}", new_cost.email_rule_id, e))?;
+        Ok(())
+    }
+
+    pub fn get_by_run_id(conn: &mut SqliteConnection, run_id: &str) -> Result<Vec<RuleCost>> {
+        rule_costs::table
+            .filter(rule_costs::processing_run_id.eq(run_id))
+            .order(rule_costs::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load rule costs of run {}: {}", run_id, e))
+    }
+
+    /// Costs recorded at or after `since`
+    pub fn get_since(conn: &mut SqliteConnection, since: &str) -> Result<Vec<RuleCost>> {
+        rule_costs::table
+            .filter(rule_costs::created_at.ge(since))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load rule costs: {}", e))
+    }
+}
+
+pub struct AppVersionOps;
+
+impl AppVersionOps {
+    /// Record that a version started, keeping when it first did
+    pub fn record_run(conn: &mut SqliteConnection, version: &str, git_hash: Option<&str>) -> Result<AppVersion> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let updated = diesel::update(app_versions::table.filter(app_versions::version.eq(version)))
+            .set((
+                app_versions::git_hash.eq(git_hash),
+                app_versions::last_run_at.eq(&now),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update version {}: {}", version, e))?;
+        if updated == 0 {
+            diesel::insert_into(app_versions::table)
+                .values(&AppVersion::new(version.to_string(), git_hash.map(str::to_string)))
+                .execute(conn)
+                .map_err(|e| anyhow::anyhow!("Failed to record version {}: {}", version, e))?;
+        }
+
+        app_versions::table
+            .find(version)
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find version {}: {}", version, e))
+    }
+
+    /// Versions that have run, most recently run first
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<AppVersion>> {
+        app_versions::table
+            .order(app_versions::last_run_at.desc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load versions: {}", e))
+    }
+
+    /// Version of the newest migration applied to the database
+    pub fn schema_version(conn: &mut SqliteConnection) -> Result<Option<String>> {
+        use diesel_migrations::MigrationHarness;
+
+        let applied = conn
+            .applied_migrations()
+            .map_err(|e| anyhow::anyhow!("Failed to load applied migrations: {}", e))?;
+        Ok(applied.iter().max().map(ToString::to_string))
+    }
+}
+
+pub struct FeedRedirectOps;
+
+impl FeedRedirectOps {
+    pub fn get(conn: &mut SqliteConnection, old_feed_id: &str) -> Result<Option<FeedRedirect>> {
+        feed_redirects::table
+            .find(old_feed_id)
+            .first(conn)
+            .optional()
+            .map_err(|e| anyhow::anyhow!("Failed to look up redirect of feed {}: {}", old_feed_id, e))
+    }
+
+    /// Send `old_feed_id` to `feed_id`, along with the feeds redirected to it
+    pub fn create(conn: &mut SqliteConnection, old_feed_id: &str, feed_id: &str) -> Result<FeedRedirect> {
+        diesel::update(feed_redirects::table.filter(feed_redirects::feed_id.eq(old_feed_id)))
+            .set(feed_redirects::feed_id.eq(feed_id))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to move redirects of feed {}: {}", old_feed_id, e))?;
+        let redirect = FeedRedirect::new(old_feed_id.to_string(), feed_id.to_string());
+        diesel::replace_into(feed_redirects::table)
+            .values(&redirect)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to redirect feed {}: {}", old_feed_id, e))?;
+        Ok(redirect)
+    }
+}
+
+pub struct AppSettingOps;
+
+impl AppSettingOps {
+    pub fn get(conn: &mut SqliteConnection, key: &str) -> Result<Option<AppSetting>> {
+        app_settings::table
+            .find(key)
+            .first(conn)
+            .optional()
+            .map_err(|e| anyhow::anyhow!("Failed to load setting {}: {}", key, e))
+    }
+
+    /// Store a setting, replacing its previous value
+    pub fn set(conn: &mut SqliteConnection, key: &str, value: &str) -> Result<AppSetting> {
+        let setting = AppSetting::new(key.to_string(), value.to_string());
+        diesel::replace_into(app_settings::table)
+            .values(&setting)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to store setting {}: {}", key, e))?;
+        Ok(setting)
+    }
+
+    /// Remove a setting, so its environment default applies again
+    pub fn delete(conn: &mut SqliteConnection, key: &str) -> Result<()> {
+        diesel::delete(app_settings::table.find(key))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to remove setting {}: {}", key, e))?;
+        Ok(())
+    }
+}
+
+pub struct RetentionPolicyOps;
+
+/// IDs deleted per statement, well below SQLite's limit on bound parameters
+const RETENTION_DELETE_CHUNK: usize = 500;
+
+impl RetentionPolicyOps {
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<RetentionPolicy>> {
+        retention_policies::table
+            .order(retention_policies::target.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load retention policies: {}", e))
+    }
+
+    /// Set a table's limits, creating its policy on first use
+    pub fn upsert(conn: &mut SqliteConnection, target: RetentionTarget, max_rows: Option<i32>, max_age_days: Option<i32>) -> Result<RetentionPolicy> {
+        let updated = diesel::update(retention_policies::table.filter(retention_policies::target.eq(target.as_str())))
+            .set((
+                retention_policies::max_rows.eq(max_rows),
+                retention_policies::max_age_days.eq(max_age_days),
+                retention_policies::updated_at.eq(chrono::Utc::now().to_rfc3339()),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update retention of {}: {}", target.as_str(), e))?;
+        if updated == 0 {
+            diesel::insert_into(retention_policies::table)
+                .values(&NewRetentionPolicy::new(target, max_rows, max_age_days))
+                .execute(conn)
+                .map_err(|e| anyhow::anyhow!("Failed to create retention of {}: {}", target.as_str(), e))?;
+        }
+
+        retention_policies::table
+            .filter(retention_policies::target.eq(target.as_str()))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find retention of {}: {}", target.as_str(), e))
+    }
+
+    /// Add rows purged from a table to its policy's total
+    pub fn record_purge(conn: &mut SqliteConnection, target: RetentionTarget, rows: i64) -> Result<()> {
+        diesel::update(retention_policies::table.filter(retention_policies::target.eq(target.as_str())))
+            .set((
+                retention_policies::rows_purged.eq(retention_policies::rows_purged + rows),
+                retention_policies::last_purged_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to record purge of {}: {}", target.as_str(), e))?;
+        Ok(())
+    }
+
+    /// Rows of a table retention may purge, newest first. Runs still
+    /// running, with intents to recover or with actions waiting on their
+    /// delay are left out, as are deliveries still queued
+    pub fn candidates(conn: &mut SqliteConnection, target: RetentionTarget) -> Result<Vec<RetentionCandidate>> {
+        let load_error = |e: diesel::result::Error| anyhow::anyhow!("Failed to load {} for retention: {}", target.as_str(), e);
+        match target {
+            RetentionTarget::ProcessingRuns => {
+                let mut held: Vec<String> = processing_intents::table
+                    .filter(processing_intents::status.eq(ProcessingIntentStatus::Pending.as_str()))
+                    .select(processing_intents::processing_run_id)
+                    .load(conn)
+                    .map_err(load_error)?;
+                held.extend(deferred_actions::table
+                    .filter(deferred_actions::status.eq(DeferredActionStatus::Pending.as_str()))
+                    .select(deferred_actions::processing_run_id)
+                    .load::<String>(conn)
+                    .map_err(load_error)?);
+                let runs: Vec<RetentionCandidate> = processing_runs::table
+                    .filter(processing_runs::finished_at.is_not_null())
+                    .select((processing_runs::id.assume_not_null(), processing_runs::started_at))
+                    .order(processing_runs::started_at.desc())
+                    .load(conn)
+                    .map_err(load_error)?;
+                Ok(runs.into_iter().filter(|(run_id, _)| !held.contains(run_id)).collect())
+            }
+            RetentionTarget::RuleMatches => rule_matches::table
+                .select((rule_matches::id.assume_not_null(), rule_matches::matched_at))
+                .order(rule_matches::matched_at.desc())
+                .load(conn)
+                .map_err(load_error),
+            RetentionTarget::Deliveries => deliveries::table
+                .filter(deliveries::status.ne(DeliveryStatus::Pending.as_str()))
+                .select((deliveries::id.assume_not_null(), deliveries::created_at))
+                .order(deliveries::created_at.desc())
+                .load(conn)
+                .map_err(load_error),
+            RetentionTarget::RuleCosts => rule_costs::table
+                .select((rule_costs::id.assume_not_null(), rule_costs::created_at))
+                .order(rule_costs::created_at.desc())
+                .load(conn)
+                .map_err(load_error),
+        }
+    }
+
+    /// Delete rows of a table by ID; runs take their actions, intents, rule
+    /// costs and deferred actions with them, and their items forget them
+    pub fn delete(conn: &mut SqliteConnection, target: RetentionTarget, ids: &[String]) -> Result<usize> {
+        conn.transaction(|conn| {
+            let mut deleted = 0;
+            for chunk in ids.chunks(RETENTION_DELETE_CHUNK) {
+                deleted += match target {
+                    RetentionTarget::ProcessingRuns => {
+                        diesel::delete(processing_run_actions::table.filter(processing_run_actions::processing_run_id.eq_any(chunk)))
+                            .execute(conn)?;
+                        diesel::delete(processing_intents::table.filter(processing_intents::processing_run_id.eq_any(chunk)))
+                            .execute(conn)?;
+                        diesel::delete(rule_costs::table.filter(rule_costs::processing_run_id.eq_any(chunk)))
+                            .execute(conn)?;
+                        diesel::delete(deferred_actions::table.filter(deferred_actions::processing_run_id.eq_any(chunk)))
+                            .execute(conn)?;
+                        diesel::update(feed_items::table.filter(feed_items::processing_run_id.eq_any(chunk)))
+                            .set(feed_items::processing_run_id.eq(None::<String>))
+                            .execute(conn)?;
+                        diesel::delete(processing_runs::table.filter(processing_runs::id.eq_any(chunk)))
+                            .execute(conn)?
+                    }
+                    RetentionTarget::RuleMatches => diesel::delete(rule_matches::table.filter(rule_matches::id.eq_any(chunk)))
+                        .execute(conn)?,
+                    RetentionTarget::Deliveries => diesel::delete(deliveries::table.filter(deliveries::id.eq_any(chunk)))
+                        .execute(conn)?,
+                    RetentionTarget::RuleCosts => diesel::delete(rule_costs::table.filter(rule_costs::id.eq_any(chunk)))
+                        .execute(conn)?,
+                };
+            }
+            Ok(deleted)
+        })
+        .map_err(|e: diesel::result::Error| anyhow::anyhow!("Failed to purge {}: {}", target.as_str(), e))
+    }
+}
+
+pub struct ChatIntegrationOps;
+
+impl ChatIntegrationOps {
+    pub fn create(conn: &mut SqliteConnection, new_integration: &NewChatIntegration) -> Result<ChatIntegration> {
+        diesel::insert_into(chat_integrations::table)
+            .values(new_integration)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to create chat integration: {}", e))?;
+
+        Self::get_by_id(conn, &new_integration.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, integration_id: &str) -> Result<ChatIntegration> {
+        chat_integrations::table
+            .filter(chat_integrations::id.eq(integration_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find chat integration {}: {}", integration_id, e))
+    }
+
+    pub fn get_by_feed_id(conn: &mut SqliteConnection, feed_id: &str) -> Result<Vec<ChatIntegration>> {
+        chat_integrations::table
+            .filter(chat_integrations::feed_id.eq(feed_id))
+            .order(chat_integrations::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load chat integrations for feed {}: {}", feed_id, e))
+    }
+
+    pub fn update(conn: &mut SqliteConnection, integration_id: &str, updated: &NewChatIntegration) -> Result<ChatIntegration> {
+        let rows = diesel::update(chat_integrations::table.filter(chat_integrations::id.eq(integration_id)))
+            .set((
+                chat_integrations::platform.eq(&updated.platform),
+                chat_integrations::webhook_url.eq(&updated.webhook_url),
+                chat_integrations::matrix_homeserver.eq(&updated.matrix_homeserver),
+                chat_integrations::matrix_room_id.eq(&updated.matrix_room_id),
+                chat_integrations::matrix_access_token.eq(&updated.matrix_access_token),
+                chat_integrations::message_template.eq(&updated.message_template),
+                chat_integrations::rate_limit_per_minute.eq(updated.rate_limit_per_minute),
+                chat_integrations::is_active.eq(updated.is_active),
+                chat_integrations::updated_at.eq(&updated.updated_at),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update chat integration {}: {}", integration_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Chat integration {} not found", integration_id);
+        }
+
+        Self::get_by_id(conn, integration_id)
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, integration_id: &str) -> Result<()> {
+        let rows = diesel::delete(chat_integrations::table.filter(chat_integrations::id.eq(integration_id)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete chat integration {}: {}", integration_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Chat integration {} not found", integration_id);
+        }
+        Ok(())
+    }
+}
+
+pub struct AccountSenderFilterOps;
+
+impl AccountSenderFilterOps {
+    pub fn create(conn: &mut SqliteConnection, new_filter: &NewAccountSenderFilter) -> Result<AccountSenderFilter> {
+        diesel::insert_into(account_sender_filters::table)
+            .values(new_filter)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to create sender filter: {}", e))?;
+
+        Self::get_by_id(conn, &new_filter.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, filter_id: &str) -> Result<AccountSenderFilter> {
+        account_sender_filters::table
+            .filter(account_sender_filters::id.eq(filter_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find sender filter {}: {}", filter_id, e))
+    }
+
+    pub fn get_by_account_id(conn: &mut SqliteConnection, account_id: &str) -> Result<Vec<AccountSenderFilter>> {
+        account_sender_filters::table
+            .filter(account_sender_filters::imap_account_id.eq(account_id))
+            .order((account_sender_filters::kind.asc(), account_sender_filters::pattern.asc()))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load sender filters for account {}: {}", account_id, e))
+    }
+
+    pub fn update(conn: &mut SqliteConnection, filter_id: &str, updated: &NewAccountSenderFilter) -> Result<AccountSenderFilter> {
+        let rows = diesel::update(account_sender_filters::table.filter(account_sender_filters::id.eq(filter_id)))
+            .set((
+                account_sender_filters::kind.eq(&updated.kind),
+                account_sender_filters::pattern.eq(&updated.pattern),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update sender filter {}: {}", filter_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Sender filter {} not found", filter_id);
+        }
+
+        Self::get_by_id(conn, filter_id)
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, filter_id: &str) -> Result<()> {
+        let rows = diesel::delete(account_sender_filters::table.filter(account_sender_filters::id.eq(filter_id)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete sender filter {}: {}", filter_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Sender filter {} not found", filter_id);
+        }
+        Ok(())
+    }
+}
+
+pub struct RuleFeedOps;
+
+impl RuleFeedOps {
+    /// Attach a feed to a rule, or change its priority when already attached
+    pub fn attach(conn: &mut SqliteConnection, new_rule_feed: &NewRuleFeed) -> Result<RuleFeed> {
+        let existing = rule_feeds::table
+            .filter(rule_feeds::email_rule_id.eq(&new_rule_feed.email_rule_id))
+            .filter(rule_feeds::feed_id.eq(&new_rule_feed.feed_id));
+        let rows = diesel::update(existing)
+            .set(rule_feeds::priority.eq(new_rule_feed.priority))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update attached feed {}: {}", new_rule_feed.feed_id, e))?;
+        if rows == 0 {
+            diesel::insert_into(rule_feeds::table)
+                .values(new_rule_feed)
+                .execute(conn)
+                .map_err(|e| anyhow::anyhow!("Failed to attach feed {}: {}", new_rule_feed.feed_id, e))?;
+        }
+
+        rule_feeds::table
+            .filter(rule_feeds::email_rule_id.eq(&new_rule_feed.email_rule_id))
+            .filter(rule_feeds::feed_id.eq(&new_rule_feed.feed_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find attached feed {}: {}", new_rule_feed.feed_id, e))
+    }
+
+    /// Feeds attached to a rule, with their attachment; attachments of
+    /// deleted feeds are left out
+    pub fn get_feeds_by_rule_id(conn: &mut SqliteConnection, rule_id: &str) -> Result<Vec<(RuleFeed, Feed)>> {
+        rule_feeds::table
+            .inner_join(feeds::table)
+            .filter(rule_feeds::email_rule_id.eq(rule_id))
+            .order((rule_feeds::priority.desc(), rule_feeds::created_at.asc()))
+            .select((RuleFeed::as_select(), feeds::all_columns))
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load feeds attached to rule {}: {}", rule_id, e))
+    }
+
+    pub fn detach(conn: &mut SqliteConnection, rule_id: &str, feed_id: &str) -> Result<()> {
+        let rows = diesel::delete(
+            rule_feeds::table
+                .filter(rule_feeds::email_rule_id.eq(rule_id))
+                .filter(rule_feeds::feed_id.eq(feed_id)),
+        )
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to detach feed {}: {}", feed_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Feed {} is not attached to rule {}", feed_id, rule_id);
+        }
+        Ok(())
+    }
+}
+
+pub struct DeliveryOps;
+
+impl DeliveryOps {
+    pub fn create(conn: &mut SqliteConnection, new_delivery: &NewDelivery) -> Result<Delivery> {
+        diesel::insert_into(deliveries::table)
+            .values(new_delivery)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to queue delivery: {}", e))?;
+
+        Self::get_by_id(conn, &new_delivery.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, delivery_id: &str) -> Result<Delivery> {
+        deliveries::table
+            .filter(deliveries::id.eq(delivery_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find delivery {}: {}", delivery_id, e))
+    }
+
+    /// Deliveries with `status`, oldest first
+    pub fn get_by_status(conn: &mut SqliteConnection, status: &DeliveryStatus) -> Result<Vec<Delivery>> {
+        deliveries::table
+            .filter(deliveries::status.eq(status.as_str()))
+            .order(deliveries::created_at.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load {} deliveries: {}", status.as_str(), e))
+    }
+
+    pub fn update_state(
+        conn: &mut SqliteConnection,
+        delivery_id: &str,
+        status: &DeliveryStatus,
+        attempts: i32,
+        next_attempt_at: &str,
+        last_error: Option<&str>,
+    ) -> Result<Delivery> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let delivered_at = (*status == DeliveryStatus::Delivered).then(|| now.clone());
+        let rows = diesel::update(deliveries::table.filter(deliveries::id.eq(delivery_id)))
+            .set((
+                deliveries::status.eq(status.as_str()),
+                deliveries::attempts.eq(attempts),
+                deliveries::next_attempt_at.eq(next_attempt_at),
+                deliveries::last_error.eq(last_error),
+                deliveries::delivered_at.eq(delivered_at),
+                deliveries::updated_at.eq(&now),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update delivery {}: {}", delivery_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Delivery {} not found", delivery_id);
+        }
+
+        Self::get_by_id(conn, delivery_id)
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, delivery_id: &str) -> Result<()> {
+        let rows = diesel::delete(deliveries::table.filter(deliveries::id.eq(delivery_id)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete delivery {}: {}", delivery_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Delivery {} not found", delivery_id);
+        }
+        Ok(())
+    }
+
+    /// Remove deliveries that succeeded before `cutoff`
+    pub fn delete_delivered_before(conn: &mut SqliteConnection, cutoff: &str) -> Result<usize> {
+        diesel::delete(
+            deliveries::table
+                .filter(deliveries::status.eq(DeliveryStatus::Delivered.as_str()))
+                .filter(deliveries::delivered_at.lt(cutoff)),
+        )
+        .execute(conn)
+        .map_err(|e| anyhow::anyhow!("Failed to prune deliveries: {}", e))
+    }
+}
+
+pub struct QuotaGroupOps;
+
+impl QuotaGroupOps {
+    pub fn create(conn: &mut SqliteConnection, new_group: &NewQuotaGroup) -> Result<QuotaGroup> {
+        diesel::insert_into(quota_groups::table)
+            .values(new_group)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to create quota group: {}", e))?;
+
+        Self::get_by_id(conn, &new_group.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, group_id: &str) -> Result<QuotaGroup> {
+        quota_groups::table
+            .filter(quota_groups::id.eq(group_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find quota group {}: {}", group_id, e))
+    }
+
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<QuotaGroup>> {
+        quota_groups::table
+            .order(quota_groups::name.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load quota groups: {}", e))
+    }
+
+    pub fn update(conn: &mut SqliteConnection, group_id: &str, updated: &NewQuotaGroup) -> Result<QuotaGroup> {
+        let rows = diesel::update(quota_groups::table.filter(quota_groups::id.eq(group_id)))
+            .set((
+                quota_groups::name.eq(&updated.name),
+                quota_groups::max_feeds.eq(updated.max_feeds),
+                quota_groups::max_items.eq(updated.max_items),
+                quota_groups::max_processing_minutes_per_day.eq(updated.max_processing_minutes_per_day),
+                quota_groups::updated_at.eq(&updated.updated_at),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update quota group {}: {}", group_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Quota group {} not found", group_id);
+        }
+
+        Self::get_by_id(conn, group_id)
+    }
+
+    /// Delete a group; its accounts keep only their own limits
+    pub fn delete(conn: &mut SqliteConnection, group_id: &str) -> Result<()> {
+        let rows = conn.transaction(|conn| {
+            diesel::update(imap_accounts::table.filter(imap_accounts::quota_group_id.eq(group_id)))
+                .set(imap_accounts::quota_group_id.eq(None::<String>))
+                .execute(conn)?;
+            diesel::delete(quota_groups::table.filter(quota_groups::id.eq(group_id))).execute(conn)
+        })
+        .map_err(|e: diesel::result::Error| anyhow::anyhow!("Failed to delete quota group {}: {}", group_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Quota group {} not found", group_id);
+        }
+        Ok(())
+    }
+}
+
+pub struct SenderAliasOps;
+
+impl SenderAliasOps {
+    pub fn create(conn: &mut SqliteConnection, new_alias: &NewSenderAlias) -> Result<SenderAlias> {
+        diesel::insert_into(sender_aliases::table)
+            .values(new_alias)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to create sender alias: {}", e))?;
+
+        Self::get_by_id(conn, &new_alias.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, alias_id: &str) -> Result<SenderAlias> {
+        sender_aliases::table
+            .filter(sender_aliases::id.eq(alias_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find sender alias {}: {}", alias_id, e))
+    }
+
+    pub fn get_all(conn: &mut SqliteConnection) -> Result<Vec<SenderAlias>> {
+        sender_aliases::table
+            .order(sender_aliases::name.asc())
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to load sender aliases: {}", e))
+    }
+
+    pub fn update(conn: &mut SqliteConnection, alias_id: &str, updated: &NewSenderAlias) -> Result<SenderAlias> {
+        let rows = diesel::update(sender_aliases::table.filter(sender_aliases::id.eq(alias_id)))
+            .set((
+                sender_aliases::name.eq(&updated.name),
+                sender_aliases::addresses.eq(&updated.addresses),
+                sender_aliases::updated_at.eq(&updated.updated_at),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to update sender alias {}: {}", alias_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Sender alias {} not found", alias_id);
+        }
+
+        Self::get_by_id(conn, alias_id)
+    }
+
+    pub fn delete(conn: &mut SqliteConnection, alias_id: &str) -> Result<()> {
+        let rows = diesel::delete(sender_aliases::table.filter(sender_aliases::id.eq(alias_id)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete sender alias {}: {}", alias_id, e))?;
+        if rows == 0 {
+            anyhow::bail!("Sender alias {} not found", alias_id);
+        }
+        Ok(())
+    }
+}
+
+pub struct JobOps;
+
+impl JobOps {
+    pub fn create(conn: &mut SqliteConnection, new_job: &NewJob) -> Result<Job> {
+        diesel::insert_into(jobs::table)
+            .values(new_job)
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to create job: {}", e))?;
+
+        Self::get_by_id(conn, &new_job.id)
+    }
+
+    pub fn get_by_id(conn: &mut SqliteConnection, job_id: &str) -> Result<Job> {
+        jobs::table
+            .filter(jobs::id.eq(job_id))
+            .first(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to find job {}: {}", job_id, e))
+    }
+
+    pub fn list(conn: &mut SqliteConnection, filter: &JobFilter) -> Result<Vec<Job>> {
+        let mut query = jobs::table
+            .order((jobs::created_at.desc(), jobs::id.desc()))
+            .limit(filter.limit)
+            .into_boxed();
+
+        if let Some(kind) = &filter.kind {
+            query = query.filter(jobs::kind.eq(kind));
+        }
+        if let Some(target_id) = &filter.target_id {
+            query = query.filter(jobs::target_id.eq(target_id));
+        }
+        if let Some(status) = &filter.status {
+            query = query.filter(jobs::status.eq(status.as_str()));
+        }
+
+        query
+            .load(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to list jobs: {}", e))
+    }
+
+    pub fn start(conn: &mut SqliteConnection, job_id: &str) -> Result<()> {
+        diesel::update(jobs::table.filter(jobs::id.eq(job_id)))
+            .set((
+                jobs::status.eq(JobStatus::Running.as_str()),
+                jobs::started_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to start job {}: {}", job_id, e))?;
+        Ok(())
+    }
+
+    pub fn set_total(conn: &mut SqliteConnection, job_id: &str, total: i32) -> Result<()> {
+        diesel::update(jobs::table.filter(jobs::id.eq(job_id)))
+            .set(jobs::total.eq(Some(total)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to set total of job {}: {}", job_id, e))?;
+        Ok(())
+    }
+
+    /// Add `processed` units of work to the job's progress
+    pub fn advance(conn: &mut SqliteConnection, job_id: &str, processed: i32, message: Option<&str>) -> Result<()> {
+        let target = jobs::table.filter(jobs::id.eq(job_id));
+        let result = match message {
+            Some(message) => diesel::update(target)
+                .set((jobs::processed.eq(jobs::processed + processed), jobs::message.eq(Some(message))))
+                .execute(conn),
+            None => diesel::update(target)
+                .set(jobs::processed.eq(jobs::processed + processed))
+                .execute(conn),
+        };
+        result.map_err(|e| anyhow::anyhow!("Failed to record progress of job {}: {}", job_id, e))?;
+        Ok(())
+    }
+
+    pub fn finish(conn: &mut SqliteConnection, job_id: &str, status: &JobStatus, result: Option<String>, error_message: Option<String>) -> Result<Job> {
+        diesel::update(jobs::table.filter(jobs::id.eq(job_id)))
+            .set((
+                jobs::status.eq(status.as_str()),
+                jobs::finished_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+                jobs::result.eq(result),
+                jobs::error_message.eq(error_message),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to finish job {}: {}", job_id, e))?;
+
+        Self::get_by_id(conn, job_id)
+    }
+
+    /// Fail the jobs left queued or running by a previous process
+    pub fn fail_unfinished(conn: &mut SqliteConnection, error_message: &str) -> Result<usize> {
+        diesel::update(jobs::table.filter(jobs::status.eq_any([JobStatus::Queued.as_str(), JobStatus::Running.as_str()])))
+            .set((
+                jobs::status.eq(JobStatus::Failed.as_str()),
+                jobs::finished_at.eq(Some(chrono::Utc::now().to_rfc3339())),
+                jobs::error_message.eq(Some(error_message)),
+            ))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to fail interrupted jobs: {}", e))
+    }
+
+    /// Delete the jobs that finished before `cutoff` (RFC 3339)
+    pub fn delete_finished_before(conn: &mut SqliteConnection, cutoff: &str) -> Result<usize> {
+        diesel::delete(jobs::table.filter(jobs::finished_at.lt(cutoff)))
+            .execute(conn)
+            .map_err(|e| anyhow::anyhow!("Failed to delete old jobs: {}", e))
+    }
+}
+
+pub struct DatabaseOps;
+
+impl DatabaseOps {
+    /// Bytes in use by the database, leaving out pages freed by deletes that
+    /// SQLite keeps for reuse
+    pub fn size_bytes(conn: &mut SqliteConnection) -> Result<i64> {
+        diesel::sql_query(
+            "SELECT (page_count - freelist_count) * page_size AS bytes \
+             FROM pragma_page_count(), pragma_freelist_count(), pragma_page_size()",
+        )
+        .get_result::<DatabaseSize>(conn)
+        .map(|size| size.bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to measure database size: {}", e))
+    }
+
+    /// Run a trivial query, checking that the database answers
+    pub fn ping(conn: &mut SqliteConnection) -> Result<()> {
+        diesel::sql_query("SELECT 1")
+            .execute(conn)
+            .map(drop)
+            .map_err(|e| anyhow::anyhow!("Database did not answer: {}", e))
+    }
+}
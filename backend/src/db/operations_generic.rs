@@ -1,7 +1,98 @@
 use crate::db::models::*;
-use crate::db::connection::DatabasePool;
+use crate::db::connection::{DatabasePool, Transaction};
 use anyhow::Result;
 
+/// Operations that can take part in a transaction opened with
+/// `DatabasePool::transaction`
+impl Transaction<'_> {
+    pub fn create_imap_account(&mut self, new_account: &NewImapAccount) -> Result<ImapAccount> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::ImapAccountOps::create(conn, new_account),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::create_imap_account(conn, new_account),
+        }
+    }
+
+    pub fn create_email_rule(&mut self, new_rule: &NewEmailRule) -> Result<EmailRule> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::EmailRuleOps::create(conn, new_rule),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::create_email_rule(conn, new_rule),
+        }
+    }
+
+    pub fn create_feed(&mut self, new_feed: &NewFeed) -> Result<Feed> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedOps::create(conn, new_feed),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::create_feed(conn, new_feed),
+        }
+    }
+
+    pub fn get_feed(&mut self, feed_id: &str) -> Result<Feed> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedOps::get_by_id(conn, feed_id),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::get_feed(conn, feed_id)
+                .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Feed not found"))),
+        }
+    }
+
+    pub fn set_chain_head(&mut self, feed_id: &str, head: &str) -> Result<()> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedOps::set_chain_head(conn, feed_id, head),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => {
+                crate::db::operations_pg::set_feed_chain_head(conn, feed_id, head)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Move items to another feed, recording the feed each came from
+    pub fn move_feed_items(&mut self, item_ids: &[String], feed_id: &str) -> Result<usize> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedItemOps::move_to_feed(conn, item_ids, feed_id),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::move_feed_items(conn, item_ids, feed_id),
+        }
+    }
+
+    /// Send `old_feed_id` to `feed_id`, along with the feeds redirected to it
+    pub fn redirect_feed(&mut self, old_feed_id: &str, feed_id: &str) -> Result<FeedRedirect> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedRedirectOps::create(conn, old_feed_id, feed_id),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::create_feed_redirect(conn, old_feed_id, feed_id),
+        }
+    }
+
+    pub fn delete_feed(&mut self, feed_id: &str) -> Result<()> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedOps::delete(conn, feed_id),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::delete_feed(conn, feed_id).map(|_| ()),
+        }
+    }
+
+    pub fn create_feed_item(&mut self, new_item: &NewFeedItem) -> Result<FeedItem> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::FeedItemOps::create(conn, new_item),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::create_feed_item(conn, new_item),
+        }
+    }
+
+    /// Add an item to a processing run's count
+    pub fn count_run_item(&mut self, run_id: &str) -> Result<()> {
+        match self {
+            Transaction::SQLite(conn) => crate::db::operations::ProcessingRunOps::count_item_created(conn, run_id),
+            #[cfg(feature = "postgres")]
+            Transaction::PostgreSQL(conn) => crate::db::operations_pg::count_processing_run_item(conn, run_id),
+        }
+    }
+}
+
 pub struct ImapAccountOpsGeneric;
 
 impl ImapAccountOpsGeneric {
@@ -22,6 +113,21 @@ impl ImapAccountOpsGeneric {
         }
     }
 
+    /// Create an account, its rules and their feeds in one transaction
+    pub fn create_with_feeds(
+        pool: &DatabasePool,
+        new_account: &NewImapAccount,
+        new_rules: &[NewEmailRule],
+        new_feeds: &[NewFeed],
+    ) -> Result<AccountSetup> {
+        pool.transaction(|tx| {
+            let account = tx.create_imap_account(new_account)?;
+            let rules = new_rules.iter().map(|rule| tx.create_email_rule(rule)).collect::<Result<Vec<_>>>()?;
+            let feeds = new_feeds.iter().map(|feed| tx.create_feed(feed)).collect::<Result<Vec<_>>>()?;
+            Ok((account, rules, feeds))
+        })
+    }
+
     pub fn get_by_id(
         pool: &DatabasePool,
         account_id: &str,
@@ -69,9 +175,45 @@ impl ImapAccountOpsGeneric {
             #[cfg(feature = "postgres")]
             DatabasePool::PostgreSQL(pg_pool) => {
                 let mut conn = pg_pool.get()?;
-                // Need to create UpdateImapAccount from NewImapAccount - this is a type mismatch we'll address
-                // For now, return an error indicating PostgreSQL update is not yet implemented
-                Err(anyhow::anyhow!("PostgreSQL IMAP account update not yet implemented"))
+                crate::db::operations_pg::update_imap_account(&mut conn, account_id, updated_account)
+            }
+        }
+    }
+
+    pub fn update_fingerprint(
+        pool: &DatabasePool,
+        account_id: &str,
+        fingerprint: Option<&str>,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ImapAccountOps::update_fingerprint(&mut conn, account_id, fingerprint)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_imap_account_fingerprint(&mut conn, account_id, fingerprint)
+            }
+        }
+    }
+
+    /// Record the server's capabilities and the fetch strategy that worked
+    pub fn update_server_profile(
+        pool: &DatabasePool,
+        account_id: &str,
+        capabilities: Option<&str>,
+        strategy: Option<&str>,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ImapAccountOps::update_server_profile(&mut conn, account_id, capabilities, strategy)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_imap_account_server_profile(&mut conn, account_id, capabilities, strategy)
             }
         }
     }
@@ -93,6 +235,23 @@ impl ImapAccountOpsGeneric {
             }
         }
     }
+
+    pub fn get_by_quota_group(
+        pool: &DatabasePool,
+        group_id: &str,
+    ) -> Result<Vec<ImapAccount>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ImapAccountOps::get_by_quota_group(&mut conn, group_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_imap_accounts_by_quota_group(&mut conn, group_id)
+            }
+        }
+    }
 }
 
 pub struct EmailRuleOpsGeneric;
@@ -200,6 +359,21 @@ impl EmailRuleOpsGeneric {
         }
     }
 
+    pub fn set_high_water_mark(pool: &DatabasePool, rule_id: &str, uid_validity: i64, last_seen_uid: i64) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::EmailRuleOps::set_high_water_mark(&mut conn, rule_id, uid_validity, last_seen_uid)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_email_rule_high_water_mark(&mut conn, rule_id, uid_validity, last_seen_uid)?;
+                Ok(())
+            }
+        }
+    }
+
     pub fn delete(
         pool: &DatabasePool,
         rule_id: &str,
@@ -324,6 +498,21 @@ impl FeedOpsGeneric {
         }
     }
 
+    pub fn set_chain_head(pool: &DatabasePool, feed_id: &str, head: &str) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedOps::set_chain_head(&mut conn, feed_id, head)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_feed_chain_head(&mut conn, feed_id, head)?;
+                Ok(())
+            }
+        }
+    }
+
     pub fn delete(
         pool: &DatabasePool,
         feed_id: &str,
@@ -341,6 +530,23 @@ impl FeedOpsGeneric {
             }
         }
     }
+
+    pub fn count_by_account_ids(
+        pool: &DatabasePool,
+        account_ids: &[String],
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedOps::count_by_account_ids(&mut conn, account_ids)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_feeds_by_accounts(&mut conn, account_ids)
+            }
+        }
+    }
 }
 
 pub struct FeedItemOpsGeneric;
@@ -399,55 +605,2244 @@ impl FeedItemOpsGeneric {
         }
     }
 
-    pub fn get_by_email_message_id(
-        pool: &DatabasePool,
-        message_id: &str,
-    ) -> Result<Option<FeedItem>> {
+    /// A page of a feed's items, pinned first, then newest first
+    pub fn get_page(pool: &DatabasePool, feed_id: &str, filter: &FeedItemPageFilter) -> Result<Vec<FeedItem>> {
         match pool {
             DatabasePool::SQLite(sqlite_pool) => {
                 let mut conn = sqlite_pool.get()?;
-                crate::db::operations::FeedItemOps::get_by_email_message_id(&mut conn, message_id)
+                crate::db::operations::FeedItemOps::get_page(&mut conn, feed_id, filter)
             }
             #[cfg(feature = "postgres")]
-            DatabasePool::PostgreSQL(_pg_pool) => {
-                // Not implemented in PostgreSQL version yet
-                Err(anyhow::anyhow!("PostgreSQL get_by_email_message_id not yet implemented"))
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_page(&mut conn, feed_id, filter)
             }
         }
     }
 
-    pub fn delete_by_feed_id(
+    pub fn get_timeline(
         pool: &DatabasePool,
-        feed_id: &str,
-    ) -> Result<()> {
+        filter: &TimelineFilter,
+    ) -> Result<Vec<FeedItem>> {
         match pool {
             DatabasePool::SQLite(sqlite_pool) => {
                 let mut conn = sqlite_pool.get()?;
-                crate::db::operations::FeedItemOps::delete_by_feed_id(&mut conn, feed_id)
+                crate::db::operations::FeedItemOps::get_timeline(&mut conn, filter)
             }
             #[cfg(feature = "postgres")]
-            DatabasePool::PostgreSQL(_pg_pool) => {
-                // Not implemented in PostgreSQL version yet
-                Err(anyhow::anyhow!("PostgreSQL delete_by_feed_id not yet implemented"))
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_timeline_items(&mut conn, filter)
             }
         }
     }
 
-    pub fn delete(
+    /// A feed's items matching `selection`, newest first
+    pub fn select(pool: &DatabasePool, feed_id: &str, selection: &ItemSelection) -> Result<Vec<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::select(&mut conn, feed_id, selection)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::select_feed_items(&mut conn, feed_id, selection)
+            }
+        }
+    }
+
+    /// Set the read or starred state of many items, keeping the one left `None`
+    pub fn set_flags(pool: &DatabasePool, item_ids: &[String], is_read: Option<bool>, starred: Option<bool>) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::set_flags(&mut conn, item_ids, is_read, starred)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_feed_item_flags(&mut conn, item_ids, is_read, starred)
+            }
+        }
+    }
+
+    /// Up to `limit` items for sync clients, by `sync_id`
+    pub fn get_for_sync(pool: &DatabasePool, selection: &SyncSelection, limit: i64) -> Result<Vec<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_for_sync(&mut conn, selection, limit)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_items_for_sync(&mut conn, selection, limit)
+            }
+        }
+    }
+
+    /// `sync_id`s of the items not marked read, or of the starred ones
+    pub fn get_sync_ids(pool: &DatabasePool, starred: bool) -> Result<Vec<i64>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_sync_ids(&mut conn, starred)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_sync_ids(&mut conn, starred)
+            }
+        }
+    }
+
+    /// Mark read the items created before `before` (RFC 3339) in `feed_ids`,
+    /// or in every feed when `None`
+    pub fn mark_read_before(pool: &DatabasePool, feed_ids: Option<&[String]>, before: &str) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::mark_read_before(&mut conn, feed_ids, before)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::mark_feed_items_read_before(&mut conn, feed_ids, before)
+            }
+        }
+    }
+
+    pub fn count_all(pool: &DatabasePool) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_all(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_all_feed_items(&mut conn)
+            }
+        }
+    }
+
+    pub fn update_metadata(
         pool: &DatabasePool,
         item_id: &str,
+        is_read: Option<bool>,
+        starred: Option<bool>,
     ) -> Result<()> {
         match pool {
             DatabasePool::SQLite(sqlite_pool) => {
                 let mut conn = sqlite_pool.get()?;
-                crate::db::operations::FeedItemOps::delete(&mut conn, item_id)
+                crate::db::operations::FeedItemOps::update_metadata(&mut conn, item_id, is_read, starred)
             }
             #[cfg(feature = "postgres")]
             DatabasePool::PostgreSQL(pg_pool) => {
                 let mut conn = pg_pool.get()?;
-                crate::db::operations_pg::delete_feed_item(&mut conn, item_id)?;
+                crate::db::operations_pg::update_feed_item_metadata(&mut conn, item_id, is_read, starred)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_pinned(pool: &DatabasePool, item_id: &str, pinned: bool) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::set_pinned(&mut conn, item_id, pinned)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_feed_item_pinned(&mut conn, item_id, pinned)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_rating(pool: &DatabasePool, item_id: &str, rating: Option<&str>) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::set_rating(&mut conn, item_id, rating)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_feed_item_rating(&mut conn, item_id, rating)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_chain(pool: &DatabasePool, item_id: &str, previous: Option<&str>, hash: &str) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::set_chain(&mut conn, item_id, previous, hash)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_feed_item_chain(&mut conn, item_id, previous, hash)?;
                 Ok(())
             }
         }
     }
-}
\ No newline at end of file
+
+    pub fn count_pinned(pool: &DatabasePool, feed_id: &str) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_pinned(&mut conn, feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_pinned_feed_items(&mut conn, feed_id)
+            }
+        }
+    }
+
+    pub fn get_by_email_message_id(
+        pool: &DatabasePool,
+        message_id: &str,
+    ) -> Result<Option<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_by_email_message_id(&mut conn, message_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(_pg_pool) => {
+                // Not implemented in PostgreSQL version yet
+                Err(anyhow::anyhow!("PostgreSQL get_by_email_message_id not yet implemented"))
+            }
+        }
+    }
+
+    pub fn delete_by_feed_id(
+        pool: &DatabasePool,
+        feed_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::delete_by_feed_id(&mut conn, feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(_pg_pool) => {
+                // Not implemented in PostgreSQL version yet
+                Err(anyhow::anyhow!("PostgreSQL delete_by_feed_id not yet implemented"))
+            }
+        }
+    }
+
+    pub fn delete(
+        pool: &DatabasePool,
+        item_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::delete(&mut conn, item_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_feed_item(&mut conn, item_id)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_body_ref(
+        pool: &DatabasePool,
+        item_id: &str,
+        body_ref: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::set_body_ref(&mut conn, item_id, body_ref)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_feed_item_body_ref(&mut conn, item_id, body_ref)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn restore_body(
+        pool: &DatabasePool,
+        item_id: &str,
+        email_body: Option<String>,
+        email_body_html: Option<String>,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::restore_body(&mut conn, item_id, email_body, email_body_html)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::restore_feed_item_body(&mut conn, item_id, email_body, email_body_html)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn is_duplicate(pool: &DatabasePool, feed_id: &str, message_id: &str, title: &str, from: &str, pub_date: &str, content_hash: &str) -> Result<bool> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::is_duplicate(&mut conn, feed_id, message_id, title, from, pub_date, content_hash)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::is_feed_item_duplicate(&mut conn, feed_id, message_id, title, from, pub_date, content_hash)
+            }
+        }
+    }
+
+    pub fn message_id_exists(pool: &DatabasePool, message_id: &str, account_id: Option<&str>) -> Result<bool> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::message_id_exists(&mut conn, message_id, account_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::feed_item_message_id_exists(&mut conn, message_id, account_id)
+            }
+        }
+    }
+
+    pub fn get_digest_of_thread(pool: &DatabasePool, feed_id: &str, thread_id: &str) -> Result<Option<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_digest_of_thread(&mut conn, feed_id, thread_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_digest_of_thread(&mut conn, feed_id, thread_id)
+            }
+        }
+    }
+
+    pub fn get_digest_of_day(pool: &DatabasePool, feed_id: &str, day: &str) -> Result<Option<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_digest_of_day(&mut conn, feed_id, day)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_digest_of_day(&mut conn, feed_id, day)
+            }
+        }
+    }
+
+    pub fn merge_digest(pool: &DatabasePool, item_id: &str, merge: &DigestMerge) -> Result<FeedItem> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::merge_digest(&mut conn, item_id, merge)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::merge_feed_item_digest(&mut conn, item_id, merge)
+            }
+        }
+    }
+
+    pub fn count_stored_bodies(
+        pool: &DatabasePool,
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_stored_bodies(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_feed_items_with_stored_bodies(&mut conn)
+            }
+        }
+    }
+
+    pub fn get_stored_bodies_batch(
+        pool: &DatabasePool,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_stored_bodies_batch(&mut conn, after_id, limit)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_items_with_stored_bodies(&mut conn, after_id, limit)
+            }
+        }
+    }
+
+    pub fn get_body_refs(
+        pool: &DatabasePool,
+    ) -> Result<Vec<String>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_body_refs(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_body_refs(&mut conn)
+            }
+        }
+    }
+
+    pub fn count_missing_metadata(
+        pool: &DatabasePool,
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_missing_metadata(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_feed_items_missing_metadata(&mut conn)
+            }
+        }
+    }
+
+    pub fn get_missing_metadata_batch(
+        pool: &DatabasePool,
+        after_id: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_missing_metadata_batch(&mut conn, after_id, limit)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_items_missing_metadata(&mut conn, after_id, limit)
+            }
+        }
+    }
+
+    pub fn update_computed_metadata(
+        pool: &DatabasePool,
+        item_id: &str,
+        body_size: i32,
+        content_hash: &str,
+        language: &str,
+        is_read: bool,
+        starred: bool,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::update_computed_metadata(&mut conn, item_id, body_size, content_hash, language, is_read, starred)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_feed_item_computed_metadata(&mut conn, item_id, body_size, content_hash, language, is_read, starred)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get_by_processing_run_id(
+        pool: &DatabasePool,
+        run_id: &str,
+    ) -> Result<Vec<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_by_processing_run_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_items_by_processing_run(&mut conn, run_id)
+            }
+        }
+    }
+
+    pub fn find_canonical(
+        pool: &DatabasePool,
+        exclude_feed_id: &str,
+        message_id: Option<&str>,
+        content_hash: &str,
+    ) -> Result<Option<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::find_canonical(&mut conn, exclude_feed_id, message_id, content_hash)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::find_canonical_feed_item(&mut conn, exclude_feed_id, message_id, content_hash)
+            }
+        }
+    }
+
+    pub fn get_linked(
+        pool: &DatabasePool,
+        canonical_id: &str,
+    ) -> Result<Vec<FeedItem>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::get_linked(&mut conn, canonical_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_linked_feed_items(&mut conn, canonical_id)
+            }
+        }
+    }
+
+    pub fn count_references(
+        pool: &DatabasePool,
+        canonical_id: &str,
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_references(&mut conn, canonical_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_feed_item_references(&mut conn, canonical_id)
+            }
+        }
+    }
+
+    pub fn promote_to_canonical(
+        pool: &DatabasePool,
+        item_id: &str,
+        email_body: Option<String>,
+        email_body_html: Option<String>,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::promote_to_canonical(&mut conn, item_id, email_body, email_body_html)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::promote_feed_item_to_canonical(&mut conn, item_id, email_body, email_body_html)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn relink(
+        pool: &DatabasePool,
+        from_id: &str,
+        to_id: &str,
+    ) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::relink(&mut conn, from_id, to_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::relink_feed_items(&mut conn, from_id, to_id)
+            }
+        }
+    }
+
+    pub fn delete_by_processing_run_id(
+        pool: &DatabasePool,
+        run_id: &str,
+    ) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::delete_by_processing_run_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_feed_items_by_processing_run(&mut conn, run_id)
+            }
+        }
+    }
+
+    pub fn count_by_account_ids(
+        pool: &DatabasePool,
+        account_ids: &[String],
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_by_account_ids(&mut conn, account_ids)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_feed_items_by_accounts(&mut conn, account_ids)
+            }
+        }
+    }
+
+    pub fn count_by_feed_id(pool: &DatabasePool, feed_id: &str) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_by_feed_id(&mut conn, feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_feed_items_by_feed(&mut conn, feed_id)
+            }
+        }
+    }
+
+    pub fn count_unread_by_feed_id(pool: &DatabasePool, feed_id: &str) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::count_unread_by_feed_id(&mut conn, feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::count_unread_feed_items_by_feed(&mut conn, feed_id)
+            }
+        }
+    }
+
+    pub fn newest_by_feed(pool: &DatabasePool) -> Result<Vec<(String, Option<String>)>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::newest_by_feed(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_newest_items_by_feed(&mut conn)
+            }
+        }
+    }
+
+    pub fn counts_by_sender(pool: &DatabasePool) -> Result<Vec<SenderItemCount>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::counts_by_sender(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_counts_by_sender(&mut conn)
+            }
+        }
+    }
+
+    pub fn pub_dates_since(pool: &DatabasePool, since: &str) -> Result<Vec<(String, String)>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::pub_dates_since(&mut conn, since)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_pub_dates_since(&mut conn, since)
+            }
+        }
+    }
+
+    pub fn top_senders(pool: &DatabasePool, since: &str, limit: i64) -> Result<Vec<SenderCount>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::top_senders(&mut conn, since, limit)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_top_feed_item_senders(&mut conn, since, limit)
+            }
+        }
+    }
+
+    pub fn rating_counts(pool: &DatabasePool) -> Result<Vec<RatingCount>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::rating_counts(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_item_rating_counts(&mut conn)
+            }
+        }
+    }
+
+    pub fn storage_stats(
+        pool: &DatabasePool,
+        since: &str,
+    ) -> Result<Vec<FeedStorageStats>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedItemOps::storage_stats(&mut conn, since)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_storage_stats(&mut conn, since)
+            }
+        }
+    }
+}
+
+pub struct ProcessingRunOpsGeneric;
+
+impl ProcessingRunOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_run: &NewProcessingRun,
+    ) -> Result<ProcessingRun> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::create(&mut conn, new_run)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_processing_run(&mut conn, new_run)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        run_id: &str,
+    ) -> Result<ProcessingRun> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::get_by_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_processing_run(&mut conn, run_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Processing run not found")))
+            }
+        }
+    }
+
+    pub fn list(
+        pool: &DatabasePool,
+        filter: &ProcessingRunFilter,
+    ) -> Result<Vec<ProcessingRun>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::list(&mut conn, filter)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::list_processing_runs(&mut conn, filter)
+            }
+        }
+    }
+
+    pub fn finish(
+        pool: &DatabasePool,
+        run_id: &str,
+        status: &ProcessingRunStatus,
+        emails_processed: i32,
+        items_created: i32,
+        error_message: Option<String>,
+    ) -> Result<ProcessingRun> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::finish(&mut conn, run_id, status, emails_processed, items_created, error_message)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::finish_processing_run(&mut conn, run_id, status, emails_processed, items_created, error_message)
+            }
+        }
+    }
+
+    pub fn update_status(
+        pool: &DatabasePool,
+        run_id: &str,
+        status: &ProcessingRunStatus,
+    ) -> Result<ProcessingRun> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::update_status(&mut conn, run_id, status)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_processing_run_status(&mut conn, run_id, status)
+            }
+        }
+    }
+
+    pub fn abort_running(
+        pool: &DatabasePool,
+        error_message: &str,
+    ) -> Result<Vec<ProcessingRun>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::abort_running(&mut conn, error_message)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::abort_running_processing_runs(&mut conn, error_message)
+            }
+        }
+    }
+
+    pub fn record_transfer(
+        pool: &DatabasePool,
+        run_id: &str,
+        bytes_received: i64,
+        bytes_sent: i64,
+    ) -> Result<ProcessingRun> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::record_transfer(&mut conn, run_id, bytes_received, bytes_sent)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::record_processing_run_transfer(&mut conn, run_id, bytes_received, bytes_sent)
+            }
+        }
+    }
+
+    pub fn record_catch_up(
+        pool: &DatabasePool,
+        run_id: &str,
+        from: &str,
+        to: &str,
+        truncated: bool,
+    ) -> Result<ProcessingRun> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::record_catch_up(&mut conn, run_id, from, to, truncated)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::record_processing_run_catch_up(&mut conn, run_id, from, to, truncated)
+            }
+        }
+    }
+
+    pub fn last_completed_start(pool: &DatabasePool, account_id: &str) -> Result<Option<String>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::last_completed_start(&mut conn, account_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_last_completed_run_start(&mut conn, account_id)
+            }
+        }
+    }
+
+    pub fn get_started_since(
+        pool: &DatabasePool,
+        account_ids: &[String],
+        since: &str,
+    ) -> Result<Vec<ProcessingRun>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::get_started_since(&mut conn, account_ids, since)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_processing_runs_started_since(&mut conn, account_ids, since)
+            }
+        }
+    }
+
+    pub fn status_counts_since(pool: &DatabasePool, since: &str) -> Result<Vec<RunStatusCount>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::status_counts_since(&mut conn, since)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_processing_run_status_counts_since(&mut conn, since)
+            }
+        }
+    }
+
+    pub fn last_completed_by_account(pool: &DatabasePool) -> Result<Vec<(String, Option<String>)>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunOps::last_completed_by_account(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_last_completed_runs_by_account(&mut conn)
+            }
+        }
+    }
+}
+
+pub struct ProcessingRunActionOpsGeneric;
+
+impl ProcessingRunActionOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_action: &NewProcessingRunAction,
+    ) -> Result<ProcessingRunAction> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunActionOps::create(&mut conn, new_action)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_processing_run_action(&mut conn, new_action)
+            }
+        }
+    }
+
+    pub fn get_by_run_id(
+        pool: &DatabasePool,
+        run_id: &str,
+    ) -> Result<Vec<ProcessingRunAction>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingRunActionOps::get_by_run_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_processing_run_actions(&mut conn, run_id)
+            }
+        }
+    }
+}
+
+pub struct ProcessingIntentOpsGeneric;
+
+impl ProcessingIntentOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_intent: &NewProcessingIntent,
+    ) -> Result<ProcessingIntent> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingIntentOps::create(&mut conn, new_intent)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_processing_intent(&mut conn, new_intent)
+            }
+        }
+    }
+
+    pub fn resolve(
+        pool: &DatabasePool,
+        intent_id: &str,
+        status: &ProcessingIntentStatus,
+        feed_item_id: Option<&str>,
+    ) -> Result<ProcessingIntent> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingIntentOps::resolve(&mut conn, intent_id, status, feed_item_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::resolve_processing_intent(&mut conn, intent_id, status, feed_item_id)
+            }
+        }
+    }
+
+    pub fn get_pending(pool: &DatabasePool) -> Result<Vec<ProcessingIntent>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingIntentOps::get_pending(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_pending_processing_intents(&mut conn)
+            }
+        }
+    }
+
+    pub fn get_by_run_id(
+        pool: &DatabasePool,
+        run_id: &str,
+    ) -> Result<Vec<ProcessingIntent>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ProcessingIntentOps::get_by_run_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_processing_intents(&mut conn, run_id)
+            }
+        }
+    }
+}
+
+pub struct DeferredActionOpsGeneric;
+
+impl DeferredActionOpsGeneric {
+    pub fn create(pool: &DatabasePool, new_action: &NewDeferredAction) -> Result<DeferredAction> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeferredActionOps::create(&mut conn, new_action)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_deferred_action(&mut conn, new_action)
+            }
+        }
+    }
+
+    /// Pending actions due at or before `now`, earliest first
+    pub fn get_due(pool: &DatabasePool, now: &str) -> Result<Vec<DeferredAction>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeferredActionOps::get_due(&mut conn, now)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_due_deferred_actions(&mut conn, now)
+            }
+        }
+    }
+
+    pub fn mark_failed(pool: &DatabasePool, action_id: &str, error: &str) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeferredActionOps::mark_failed(&mut conn, action_id, error)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::mark_deferred_action_failed(&mut conn, action_id, error)
+            }
+        }
+    }
+
+    pub fn delete(pool: &DatabasePool, action_id: &str) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeferredActionOps::delete(&mut conn, action_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_deferred_action(&mut conn, action_id)
+            }
+        }
+    }
+
+    /// Drop the actions of a run not applied yet; returns how many
+    pub fn delete_pending_by_run_id(pool: &DatabasePool, run_id: &str) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeferredActionOps::delete_pending_by_run_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_pending_deferred_actions(&mut conn, run_id)
+            }
+        }
+    }
+}
+
+pub struct AttachmentOpsGeneric;
+
+impl AttachmentOpsGeneric {
+    pub fn create_all(pool: &DatabasePool, new_attachments: &[NewAttachment]) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AttachmentOps::create_all(&mut conn, new_attachments)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_attachments(&mut conn, new_attachments)
+            }
+        }
+    }
+
+    pub fn get(pool: &DatabasePool, item_id: &str, position: i32) -> Result<Attachment> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AttachmentOps::get(&mut conn, item_id, position)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_attachment(&mut conn, item_id, position)
+            }
+        }
+    }
+
+    pub fn list_for_items(pool: &DatabasePool, item_ids: &[String]) -> Result<Vec<AttachmentInfo>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AttachmentOps::list_for_items(&mut conn, item_ids)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::list_attachments_for_items(&mut conn, item_ids)
+            }
+        }
+    }
+
+    pub fn reassign(pool: &DatabasePool, from_item_id: &str, to_item_id: &str) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AttachmentOps::reassign(&mut conn, from_item_id, to_item_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::reassign_attachments(&mut conn, from_item_id, to_item_id)
+            }
+        }
+    }
+
+    pub fn delete_orphaned(pool: &DatabasePool) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AttachmentOps::delete_orphaned(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_orphaned_attachments(&mut conn)
+            }
+        }
+    }
+}
+
+pub struct RuleMatchOpsGeneric;
+
+impl RuleMatchOpsGeneric {
+    pub fn create_if_new(
+        pool: &DatabasePool,
+        new_match: &NewRuleMatch,
+    ) -> Result<bool> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleMatchOps::create_if_new(&mut conn, new_match)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_rule_match_if_new(&mut conn, new_match)
+            }
+        }
+    }
+
+    pub fn get_by_rule_id(
+        pool: &DatabasePool,
+        rule_id: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<RuleMatch>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleMatchOps::get_by_rule_id(&mut conn, rule_id, limit)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_rule_matches(&mut conn, rule_id, limit)
+            }
+        }
+    }
+
+    pub fn stats(
+        pool: &DatabasePool,
+        rule_id: &str,
+    ) -> Result<RuleMatchStats> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleMatchOps::stats(&mut conn, rule_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_rule_match_stats(&mut conn, rule_id)
+            }
+        }
+    }
+}
+
+pub struct RuleCostOpsGeneric;
+
+impl RuleCostOpsGeneric {
+    pub fn create(pool: &DatabasePool, new_cost: &NewRuleCost) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleCostOps::create(&mut conn, new_cost)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_rule_cost(&mut conn, new_cost)
+            }
+        }
+    }
+
+    pub fn get_by_run_id(pool: &DatabasePool, run_id: &str) -> Result<Vec<RuleCost>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleCostOps::get_by_run_id(&mut conn, run_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_rule_costs_by_run(&mut conn, run_id)
+            }
+        }
+    }
+
+    /// Costs recorded at or after `since`
+    pub fn get_since(pool: &DatabasePool, since: &str) -> Result<Vec<RuleCost>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleCostOps::get_since(&mut conn, since)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_rule_costs_since(&mut conn, since)
+            }
+        }
+    }
+}
+
+pub struct AppVersionOpsGeneric;
+
+impl AppVersionOpsGeneric {
+    /// Record that a version started, keeping when it first did
+    pub fn record_run(pool: &DatabasePool, version: &str, git_hash: Option<&str>) -> Result<AppVersion> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AppVersionOps::record_run(&mut conn, version, git_hash)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::record_app_version_run(&mut conn, version, git_hash)
+            }
+        }
+    }
+
+    /// Versions that have run, most recently run first
+    pub fn get_all(pool: &DatabasePool) -> Result<Vec<AppVersion>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AppVersionOps::get_all(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_app_versions(&mut conn)
+            }
+        }
+    }
+
+    /// Version of the newest migration applied to the database
+    pub fn schema_version(pool: &DatabasePool) -> Result<Option<String>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AppVersionOps::schema_version(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_schema_version(&mut conn)
+            }
+        }
+    }
+}
+
+pub struct FeedRedirectOpsGeneric;
+
+impl FeedRedirectOpsGeneric {
+    pub fn get(pool: &DatabasePool, old_feed_id: &str) -> Result<Option<FeedRedirect>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::FeedRedirectOps::get(&mut conn, old_feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_feed_redirect(&mut conn, old_feed_id)
+            }
+        }
+    }
+}
+
+pub struct AppSettingOpsGeneric;
+
+impl AppSettingOpsGeneric {
+    pub fn get(pool: &DatabasePool, key: &str) -> Result<Option<AppSetting>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AppSettingOps::get(&mut conn, key)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_app_setting(&mut conn, key)
+            }
+        }
+    }
+
+    /// Store a setting, replacing its previous value
+    pub fn set(pool: &DatabasePool, key: &str, value: &str) -> Result<AppSetting> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AppSettingOps::set(&mut conn, key, value)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_app_setting(&mut conn, key, value)
+            }
+        }
+    }
+
+    /// Remove a setting, so its environment default applies again
+    pub fn delete(pool: &DatabasePool, key: &str) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AppSettingOps::delete(&mut conn, key)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_app_setting(&mut conn, key)
+            }
+        }
+    }
+}
+
+pub struct RetentionPolicyOpsGeneric;
+
+impl RetentionPolicyOpsGeneric {
+    pub fn get_all(pool: &DatabasePool) -> Result<Vec<RetentionPolicy>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RetentionPolicyOps::get_all(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_retention_policies(&mut conn)
+            }
+        }
+    }
+
+    /// Set a table's limits, creating its policy on first use
+    pub fn upsert(pool: &DatabasePool, target: RetentionTarget, max_rows: Option<i32>, max_age_days: Option<i32>) -> Result<RetentionPolicy> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RetentionPolicyOps::upsert(&mut conn, target, max_rows, max_age_days)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::upsert_retention_policy(&mut conn, target, max_rows, max_age_days)
+            }
+        }
+    }
+
+    /// Add rows purged from a table to its policy's total
+    pub fn record_purge(pool: &DatabasePool, target: RetentionTarget, rows: i64) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RetentionPolicyOps::record_purge(&mut conn, target, rows)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::record_retention_purge(&mut conn, target, rows)
+            }
+        }
+    }
+
+    /// Rows of a table retention may purge, newest first
+    pub fn candidates(pool: &DatabasePool, target: RetentionTarget) -> Result<Vec<RetentionCandidate>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RetentionPolicyOps::candidates(&mut conn, target)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_retention_candidates(&mut conn, target)
+            }
+        }
+    }
+
+    /// Delete rows of a table by ID, with what depends on them
+    pub fn delete(pool: &DatabasePool, target: RetentionTarget, ids: &[String]) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RetentionPolicyOps::delete(&mut conn, target, ids)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_retention_rows(&mut conn, target, ids)
+            }
+        }
+    }
+}
+
+pub struct ChatIntegrationOpsGeneric;
+
+impl ChatIntegrationOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_integration: &NewChatIntegration,
+    ) -> Result<ChatIntegration> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ChatIntegrationOps::create(&mut conn, new_integration)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_chat_integration(&mut conn, new_integration)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        integration_id: &str,
+    ) -> Result<ChatIntegration> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ChatIntegrationOps::get_by_id(&mut conn, integration_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_chat_integration(&mut conn, integration_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Chat integration not found")))
+            }
+        }
+    }
+
+    pub fn get_by_feed_id(
+        pool: &DatabasePool,
+        feed_id: &str,
+    ) -> Result<Vec<ChatIntegration>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ChatIntegrationOps::get_by_feed_id(&mut conn, feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_chat_integrations_by_feed(&mut conn, feed_id)
+            }
+        }
+    }
+
+    pub fn update(
+        pool: &DatabasePool,
+        integration_id: &str,
+        updated: &NewChatIntegration,
+    ) -> Result<ChatIntegration> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ChatIntegrationOps::update(&mut conn, integration_id, updated)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_chat_integration(&mut conn, integration_id, updated)
+            }
+        }
+    }
+
+    pub fn delete(
+        pool: &DatabasePool,
+        integration_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::ChatIntegrationOps::delete(&mut conn, integration_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                match crate::db::operations_pg::delete_chat_integration(&mut conn, integration_id)? {
+                    0 => Err(anyhow::anyhow!("Chat integration not found")),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+pub struct AccountSenderFilterOpsGeneric;
+
+impl AccountSenderFilterOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_filter: &NewAccountSenderFilter,
+    ) -> Result<AccountSenderFilter> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AccountSenderFilterOps::create(&mut conn, new_filter)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_account_sender_filter(&mut conn, new_filter)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        filter_id: &str,
+    ) -> Result<AccountSenderFilter> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AccountSenderFilterOps::get_by_id(&mut conn, filter_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_account_sender_filter(&mut conn, filter_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Sender filter not found")))
+            }
+        }
+    }
+
+    pub fn get_by_account_id(
+        pool: &DatabasePool,
+        account_id: &str,
+    ) -> Result<Vec<AccountSenderFilter>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AccountSenderFilterOps::get_by_account_id(&mut conn, account_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_account_sender_filters_by_account(&mut conn, account_id)
+            }
+        }
+    }
+
+    pub fn update(
+        pool: &DatabasePool,
+        filter_id: &str,
+        updated: &NewAccountSenderFilter,
+    ) -> Result<AccountSenderFilter> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AccountSenderFilterOps::update(&mut conn, filter_id, updated)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_account_sender_filter(&mut conn, filter_id, updated)
+            }
+        }
+    }
+
+    pub fn delete(
+        pool: &DatabasePool,
+        filter_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::AccountSenderFilterOps::delete(&mut conn, filter_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                match crate::db::operations_pg::delete_account_sender_filter(&mut conn, filter_id)? {
+                    0 => Err(anyhow::anyhow!("Sender filter not found")),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+pub struct RuleFeedOpsGeneric;
+
+impl RuleFeedOpsGeneric {
+    /// Attach a feed to a rule, or change its priority when already attached
+    pub fn attach(
+        pool: &DatabasePool,
+        new_rule_feed: &NewRuleFeed,
+    ) -> Result<RuleFeed> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleFeedOps::attach(&mut conn, new_rule_feed)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::attach_rule_feed(&mut conn, new_rule_feed)
+            }
+        }
+    }
+
+    /// Feeds attached to a rule, with their attachment, by priority
+    pub fn get_feeds_by_rule_id(
+        pool: &DatabasePool,
+        rule_id: &str,
+    ) -> Result<Vec<(RuleFeed, Feed)>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleFeedOps::get_feeds_by_rule_id(&mut conn, rule_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_rule_feeds_with_feeds(&mut conn, rule_id)
+            }
+        }
+    }
+
+    pub fn detach(
+        pool: &DatabasePool,
+        rule_id: &str,
+        feed_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::RuleFeedOps::detach(&mut conn, rule_id, feed_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                match crate::db::operations_pg::detach_rule_feed(&mut conn, rule_id, feed_id)? {
+                    0 => Err(anyhow::anyhow!("Feed {} is not attached to rule {}", feed_id, rule_id)),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+pub struct DeliveryOpsGeneric;
+
+impl DeliveryOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_delivery: &NewDelivery,
+    ) -> Result<Delivery> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeliveryOps::create(&mut conn, new_delivery)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_delivery(&mut conn, new_delivery)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        delivery_id: &str,
+    ) -> Result<Delivery> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeliveryOps::get_by_id(&mut conn, delivery_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_delivery(&mut conn, delivery_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Delivery not found")))
+            }
+        }
+    }
+
+    pub fn get_by_status(
+        pool: &DatabasePool,
+        status: &DeliveryStatus,
+    ) -> Result<Vec<Delivery>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeliveryOps::get_by_status(&mut conn, status)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_deliveries_by_status(&mut conn, status)
+            }
+        }
+    }
+
+    pub fn update_state(
+        pool: &DatabasePool,
+        delivery_id: &str,
+        status: &DeliveryStatus,
+        attempts: i32,
+        next_attempt_at: &str,
+        last_error: Option<&str>,
+    ) -> Result<Delivery> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeliveryOps::update_state(&mut conn, delivery_id, status, attempts, next_attempt_at, last_error)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_delivery_state(&mut conn, delivery_id, status, attempts, next_attempt_at, last_error)
+            }
+        }
+    }
+
+    pub fn delete(
+        pool: &DatabasePool,
+        delivery_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeliveryOps::delete(&mut conn, delivery_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                match crate::db::operations_pg::delete_delivery(&mut conn, delivery_id)? {
+                    0 => Err(anyhow::anyhow!("Delivery not found")),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    pub fn delete_delivered_before(
+        pool: &DatabasePool,
+        cutoff: &str,
+    ) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DeliveryOps::delete_delivered_before(&mut conn, cutoff)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_delivered_before(&mut conn, cutoff)
+            }
+        }
+    }
+}
+
+pub struct QuotaGroupOpsGeneric;
+
+impl QuotaGroupOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_group: &NewQuotaGroup,
+    ) -> Result<QuotaGroup> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::QuotaGroupOps::create(&mut conn, new_group)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_quota_group(&mut conn, new_group)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        group_id: &str,
+    ) -> Result<QuotaGroup> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::QuotaGroupOps::get_by_id(&mut conn, group_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_quota_group(&mut conn, group_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Quota group not found")))
+            }
+        }
+    }
+
+    pub fn get_all(
+        pool: &DatabasePool,
+    ) -> Result<Vec<QuotaGroup>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::QuotaGroupOps::get_all(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_all_quota_groups(&mut conn)
+            }
+        }
+    }
+
+    pub fn update(
+        pool: &DatabasePool,
+        group_id: &str,
+        updated: &NewQuotaGroup,
+    ) -> Result<QuotaGroup> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::QuotaGroupOps::update(&mut conn, group_id, updated)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_quota_group(&mut conn, group_id, updated)
+            }
+        }
+    }
+
+    pub fn delete(
+        pool: &DatabasePool,
+        group_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::QuotaGroupOps::delete(&mut conn, group_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                match crate::db::operations_pg::delete_quota_group(&mut conn, group_id)? {
+                    0 => Err(anyhow::anyhow!("Quota group not found")),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+pub struct SenderAliasOpsGeneric;
+
+impl SenderAliasOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_alias: &NewSenderAlias,
+    ) -> Result<SenderAlias> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::SenderAliasOps::create(&mut conn, new_alias)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_sender_alias(&mut conn, new_alias)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        alias_id: &str,
+    ) -> Result<SenderAlias> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::SenderAliasOps::get_by_id(&mut conn, alias_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_sender_alias(&mut conn, alias_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Sender alias not found")))
+            }
+        }
+    }
+
+    pub fn get_all(
+        pool: &DatabasePool,
+    ) -> Result<Vec<SenderAlias>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::SenderAliasOps::get_all(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_all_sender_aliases(&mut conn)
+            }
+        }
+    }
+
+    pub fn update(
+        pool: &DatabasePool,
+        alias_id: &str,
+        updated: &NewSenderAlias,
+    ) -> Result<SenderAlias> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::SenderAliasOps::update(&mut conn, alias_id, updated)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::update_sender_alias(&mut conn, alias_id, updated)
+            }
+        }
+    }
+
+    pub fn delete(
+        pool: &DatabasePool,
+        alias_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::SenderAliasOps::delete(&mut conn, alias_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                match crate::db::operations_pg::delete_sender_alias(&mut conn, alias_id)? {
+                    0 => Err(anyhow::anyhow!("Sender alias not found")),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+pub struct JobOpsGeneric;
+
+impl JobOpsGeneric {
+    pub fn create(
+        pool: &DatabasePool,
+        new_job: &NewJob,
+    ) -> Result<Job> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::create(&mut conn, new_job)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::create_job(&mut conn, new_job)
+            }
+        }
+    }
+
+    pub fn get_by_id(
+        pool: &DatabasePool,
+        job_id: &str,
+    ) -> Result<Job> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::get_by_id(&mut conn, job_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_job(&mut conn, job_id)
+                    .and_then(|opt| opt.ok_or_else(|| anyhow::anyhow!("Job not found")))
+            }
+        }
+    }
+
+    pub fn list(
+        pool: &DatabasePool,
+        filter: &JobFilter,
+    ) -> Result<Vec<Job>> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::list(&mut conn, filter)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::list_jobs(&mut conn, filter)
+            }
+        }
+    }
+
+    pub fn start(
+        pool: &DatabasePool,
+        job_id: &str,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::start(&mut conn, job_id)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::start_job(&mut conn, job_id)
+            }
+        }
+    }
+
+    pub fn set_total(
+        pool: &DatabasePool,
+        job_id: &str,
+        total: i32,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::set_total(&mut conn, job_id, total)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::set_job_total(&mut conn, job_id, total)
+            }
+        }
+    }
+
+    pub fn advance(
+        pool: &DatabasePool,
+        job_id: &str,
+        processed: i32,
+        message: Option<&str>,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::advance(&mut conn, job_id, processed, message)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::advance_job(&mut conn, job_id, processed, message)
+            }
+        }
+    }
+
+    pub fn finish(
+        pool: &DatabasePool,
+        job_id: &str,
+        status: &JobStatus,
+        result: Option<String>,
+        error_message: Option<String>,
+    ) -> Result<Job> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::finish(&mut conn, job_id, status, result, error_message)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::finish_job(&mut conn, job_id, status, result, error_message)
+            }
+        }
+    }
+
+    pub fn fail_unfinished(
+        pool: &DatabasePool,
+        error_message: &str,
+    ) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::fail_unfinished(&mut conn, error_message)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::fail_unfinished_jobs(&mut conn, error_message)
+            }
+        }
+    }
+
+    pub fn delete_finished_before(
+        pool: &DatabasePool,
+        cutoff: &str,
+    ) -> Result<usize> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::JobOps::delete_finished_before(&mut conn, cutoff)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::delete_jobs_finished_before(&mut conn, cutoff)
+            }
+        }
+    }
+}
+
+pub struct DatabaseOpsGeneric;
+
+impl DatabaseOpsGeneric {
+    pub fn size_bytes(
+        pool: &DatabasePool,
+    ) -> Result<i64> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DatabaseOps::size_bytes(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::get_database_size(&mut conn)
+            }
+        }
+    }
+
+    pub fn ping(
+        pool: &DatabasePool,
+    ) -> Result<()> {
+        match pool {
+            DatabasePool::SQLite(sqlite_pool) => {
+                let mut conn = sqlite_pool.get()?;
+                crate::db::operations::DatabaseOps::ping(&mut conn)
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::PostgreSQL(pg_pool) => {
+                let mut conn = pg_pool.get()?;
+                crate::db::operations_pg::ping_database(&mut conn)
+            }
+        }
+    }
+}
@@ -7,8 +7,6 @@ use diesel::r2d2::{ConnectionManager, Pool};
 #[cfg(feature = "postgres")]
 use crate::db::models::*;
 #[cfg(feature = "postgres")]
-use crate::db::schema::*;
-#[cfg(feature = "postgres")]
 use anyhow::Result;
 #[cfg(feature = "postgres")]
 use chrono::{DateTime, Utc};
@@ -76,6 +74,20 @@ pub fn update_imap_account(
             use_tls.eq(updated_account.use_tls),
             default_post_process_action.eq(&updated_account.default_post_process_action),
             default_move_to_folder.eq(&updated_account.default_move_to_folder),
+            max_bytes_per_second.eq(updated_account.max_bytes_per_second),
+            tls_pin.eq(&updated_account.tls_pin),
+            tls_server_name.eq(&updated_account.tls_server_name),
+            security.eq(&updated_account.security),
+            quota_group_id.eq(&updated_account.quota_group_id),
+            max_feeds.eq(updated_account.max_feeds),
+            max_items.eq(updated_account.max_items),
+            max_processing_minutes_per_day.eq(updated_account.max_processing_minutes_per_day),
+            max_connections_per_hour.eq(updated_account.max_connections_per_hour),
+            min_fetch_delay_seconds.eq(updated_account.min_fetch_delay_seconds),
+            max_messages_per_fetch.eq(updated_account.max_messages_per_fetch),
+            protocol.eq(&updated_account.protocol),
+            server_capabilities.eq(None::<String>),
+            fetch_strategy.eq(None::<String>),
             updated_at.eq(&updated_account.updated_at),
         ))
         .get_result::<ImapAccount>(conn)?;
@@ -83,6 +95,49 @@ pub fn update_imap_account(
     Ok(updated)
 }
 
+#[cfg(feature = "postgres")]
+pub fn get_imap_accounts_by_quota_group(
+    conn: &mut PgConnection,
+    group_id: &str,
+) -> Result<Vec<ImapAccount>> {
+    use crate::db::schema::imap_accounts::dsl::*;
+
+    let accounts = imap_accounts
+        .filter(quota_group_id.eq(group_id))
+        .load::<ImapAccount>(conn)?;
+    
+    Ok(accounts)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_imap_account_fingerprint(
+    conn: &mut PgConnection,
+    account_id: &str,
+    fingerprint_param: Option<&str>,
+) -> Result<()> {
+    use crate::db::schema::imap_accounts::dsl::*;
+
+    diesel::update(imap_accounts.filter(id.eq(account_id)))
+        .set(fingerprint.eq(fingerprint_param))
+        .execute(conn)?;
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_imap_account_server_profile(
+    conn: &mut PgConnection,
+    account_id: &str,
+    capabilities: Option<&str>,
+    strategy: Option<&str>,
+) -> Result<()> {
+    use crate::db::schema::imap_accounts::dsl::*;
+
+    diesel::update(imap_accounts.filter(id.eq(account_id)))
+        .set((server_capabilities.eq(capabilities), fetch_strategy.eq(strategy)))
+        .execute(conn)?;
+    Ok(())
+}
+
 #[cfg(feature = "postgres")]
 pub fn delete_imap_account(
     conn: &mut PgConnection,
@@ -172,6 +227,21 @@ pub fn update_email_rule(
             is_active.eq(updated_rule.is_active),
             post_process_action.eq(&updated_rule.post_process_action),
             move_to_folder.eq(&updated_rule.move_to_folder),
+            observe_only.eq(updated_rule.observe_only),
+            importance.eq(&updated_rule.importance),
+            processing_order.eq(&updated_rule.processing_order),
+            post_process_delay_hours.eq(updated_rule.post_process_delay_hours),
+            match_expression.eq(&updated_rule.match_expression),
+            fetch_limit.eq(updated_rule.fetch_limit),
+            include_seen.eq(updated_rule.include_seen),
+            include_subfolders.eq(updated_rule.include_subfolders),
+            strip_footers.eq(updated_rule.strip_footers),
+            strip_quoted_replies.eq(updated_rule.strip_quoted_replies),
+            strip_tracking_pixels.eq(updated_rule.strip_tracking_pixels),
+            unwrap_tracking_links.eq(updated_rule.unwrap_tracking_links),
+            post_process_actions.eq(&updated_rule.post_process_actions),
+            last_seen_uid.eq(None::<i64>),
+            uid_validity.eq(None::<i64>),
             updated_at.eq(&updated_rule.updated_at),
         ))
         .get_result::<EmailRule>(conn)?;
@@ -179,6 +249,25 @@ pub fn update_email_rule(
     Ok(updated)
 }
 
+#[cfg(feature = "postgres")]
+pub fn set_email_rule_high_water_mark(
+    conn: &mut PgConnection,
+    rule_id: &str,
+    validity: i64,
+    last_uid: i64,
+) -> Result<usize> {
+    use crate::db::schema::email_rules::dsl::*;
+
+    let updated = diesel::update(email_rules.filter(id.eq(rule_id)))
+        .set((
+            uid_validity.eq(validity),
+            last_seen_uid.eq(last_uid),
+        ))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
 #[cfg(feature = "postgres")]
 pub fn delete_email_rule(
     conn: &mut PgConnection,
@@ -267,6 +356,28 @@ pub fn update_feed(
             max_items.eq(updated_feed.max_items),
             max_age_days.eq(updated_feed.max_age_days),
             min_items.eq(updated_feed.min_items),
+            summary_length.eq(updated_feed.summary_length),
+            locale.eq(&updated_feed.locale),
+            timezone.eq(&updated_feed.timezone),
+            title_template.eq(&updated_feed.title_template),
+            webhook_url.eq(&updated_feed.webhook_url),
+            webhook_method.eq(&updated_feed.webhook_method),
+            webhook_body.eq(&updated_feed.webhook_body),
+            public_access.eq(updated_feed.public_access),
+            max_pinned.eq(updated_feed.max_pinned),
+            auto_titles.eq(updated_feed.auto_titles),
+            page_css.eq(&updated_feed.page_css),
+            page_header_html.eq(&updated_feed.page_header_html),
+            page_footer_html.eq(&updated_feed.page_footer_html),
+            page_logo_url.eq(&updated_feed.page_logo_url),
+            append_only.eq(updated_feed.append_only),
+            digest_mode.eq(&updated_feed.digest_mode),
+            description_template.eq(&updated_feed.description_template),
+            item_limit.eq(updated_feed.item_limit),
+            full_content.eq(updated_feed.full_content),
+            auth_username.eq(&updated_feed.auth_username),
+            auth_password_hash.eq(&updated_feed.auth_password_hash),
+            dedup_scope.eq(&updated_feed.dedup_scope),
             updated_at.eq(&updated_feed.updated_at),
         ))
         .get_result::<Feed>(conn)?;
@@ -274,6 +385,37 @@ pub fn update_feed(
     Ok(updated)
 }
 
+#[cfg(feature = "postgres")]
+pub fn set_feed_chain_head(
+    conn: &mut PgConnection,
+    feed_id: &str,
+    head: &str,
+) -> Result<usize> {
+    use crate::db::schema::feeds::dsl::*;
+
+    let updated = diesel::update(feeds.filter(id.eq(feed_id)))
+        .set(chain_head.eq(head))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_feeds_by_accounts(
+    conn: &mut PgConnection,
+    account_ids: &[String],
+) -> Result<i64> {
+    use crate::db::schema::{email_rules, feeds};
+
+    let count = feeds::table
+        .inner_join(email_rules::table)
+        .filter(email_rules::imap_account_id.eq_any(account_ids))
+        .count()
+        .get_result::<i64>(conn)?;
+    
+    Ok(count)
+}
+
 #[cfg(feature = "postgres")]
 pub fn delete_feed(
     conn: &mut PgConnection,
@@ -288,6 +430,151 @@ pub fn delete_feed(
 }
 
 // Feed Item operations
+#[cfg(feature = "postgres")]
+pub fn count_feed_items_by_accounts(
+    conn: &mut PgConnection,
+    account_ids: &[String],
+) -> Result<i64> {
+    use crate::db::schema::{email_rules, feed_items, feeds};
+
+    let count = feed_items::table
+        .inner_join(feeds::table.inner_join(email_rules::table))
+        .filter(email_rules::imap_account_id.eq_any(account_ids))
+        .count()
+        .get_result::<i64>(conn)?;
+    
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_feed_items_by_feed(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let count = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_unread_feed_items_by_feed(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let count = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(is_read.is_null().or(is_read.eq(false)))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_newest_items_by_feed(
+    conn: &mut PgConnection,
+) -> Result<Vec<(String, Option<String>)>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let newest = feed_items
+        .group_by(feed_id)
+        .select((feed_id, diesel::dsl::max(created_at)))
+        .load::<(String, Option<String>)>(conn)?;
+
+    Ok(newest)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_counts_by_sender(
+    conn: &mut PgConnection,
+) -> Result<Vec<SenderItemCount>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let counts = feed_items
+        .group_by(email_from)
+        .select((email_from, diesel::dsl::count_star(), diesel::dsl::max(pub_date)))
+        .load::<SenderItemCount>(conn)?;
+
+    Ok(counts)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_pub_dates_since(
+    conn: &mut PgConnection,
+    since: &str,
+) -> Result<Vec<(String, String)>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let dates = feed_items
+        .filter(pub_date.ge(since))
+        .select((feed_id, pub_date))
+        .load::<(String, String)>(conn)?;
+
+    Ok(dates)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_top_feed_item_senders(
+    conn: &mut PgConnection,
+    since: &str,
+    limit: i64,
+) -> Result<Vec<SenderCount>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let counts = feed_items
+        .filter(pub_date.ge(since))
+        .filter(email_from.is_not_null())
+        .group_by(email_from)
+        .select((email_from, diesel::dsl::count_star()))
+        .order((diesel::dsl::count_star().desc(), email_from))
+        .limit(limit)
+        .load::<SenderCount>(conn)?;
+
+    Ok(counts)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_rating_counts(
+    conn: &mut PgConnection,
+) -> Result<Vec<RatingCount>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let counts = feed_items
+        .filter(rating.is_not_null())
+        .group_by((feed_id, email_from, rating))
+        .select((feed_id, email_from, rating, diesel::dsl::count_star()))
+        .load::<RatingCount>(conn)?;
+
+    Ok(counts)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_storage_stats(
+    conn: &mut PgConnection,
+    since: &str,
+) -> Result<Vec<FeedStorageStats>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let totals = feed_items
+        .group_by(feed_id)
+        .select((feed_id, diesel::dsl::count_star(), diesel::dsl::count(body_size), diesel::dsl::sum(body_size), diesel::dsl::min(pub_date)))
+        .load::<FeedStorageTotals>(conn)?;
+    let recent = feed_items
+        .filter(pub_date.ge(since))
+        .group_by(feed_id)
+        .select((feed_id, diesel::dsl::count_star()))
+        .load::<(String, i64)>(conn)?;
+
+    Ok(FeedStorageStats::from_rows(totals, recent))
+}
+
 #[cfg(feature = "postgres")]
 pub fn create_feed_item(
     conn: &mut PgConnection,
@@ -327,7 +614,7 @@ pub fn get_items_by_feed_id(
 
     let mut query = feed_items
         .filter(feed_id.eq(feed_id_param))
-        .order(pub_date.desc())
+        .order((pinned.desc(), pub_date.desc(), id.desc()))
         .into_boxed();
 
     if let Some(limit_val) = limit {
@@ -346,6 +633,182 @@ pub fn get_feed_items_by_feed(
     get_items_by_feed_id(conn, feed_id_param, None)
 }
 
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_page(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    filter: &FeedItemPageFilter,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let mut query = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .order((pinned.desc(), pub_date.desc(), id.desc()))
+        .into_boxed();
+
+    if let Some(before) = &filter.before {
+        query = query.filter(pub_date.lt(before));
+    }
+    if let Some(after) = &filter.after {
+        query = query.filter(pub_date.gt(after));
+    }
+    if let Some((cursor_pinned, cursor_date, cursor_id)) = &filter.cursor {
+        let older = pub_date.lt(cursor_date).or(pub_date.eq(cursor_date).and(id.lt(cursor_id)));
+        query = if *cursor_pinned {
+            query.filter(pinned.eq(false).or(older))
+        } else {
+            query.filter(pinned.eq(false).and(older))
+        };
+    }
+    if let Some(limit_val) = filter.limit {
+        query = query.limit(limit_val);
+    }
+
+    let items = query.load::<FeedItem>(conn)?;
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_timeline_items(
+    conn: &mut PgConnection,
+    filter: &TimelineFilter,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let mut query = feed_items
+        .order((pub_date.desc(), id.desc()))
+        .limit(filter.limit)
+        .into_boxed();
+
+    if let Some(feed_ids) = &filter.feed_ids {
+        query = query.filter(feed_id.eq_any(feed_ids));
+    }
+    if !filter.exclude_feed_ids.is_empty() {
+        query = query.filter(feed_id.ne_all(&filter.exclude_feed_ids));
+    }
+    if filter.unread_only {
+        query = query.filter(is_read.is_null().or(is_read.eq(false)));
+    }
+    if let Some((before_date, before_id)) = &filter.before {
+        query = query.filter(pub_date.lt(before_date).or(pub_date.eq(before_date).and(id.lt(before_id))));
+    }
+
+    let items = query.load::<FeedItem>(conn)?;
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn select_feed_items(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    selection: &ItemSelection,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let mut query = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .order((pub_date.desc(), id.desc()))
+        .into_boxed();
+
+    if let Some(item_ids) = &selection.item_ids {
+        query = query.filter(id.eq_any(item_ids));
+    }
+    if selection.unread_only {
+        query = query.filter(is_read.is_null().or(is_read.eq(false)));
+    }
+    if let Some(since) = &selection.since {
+        query = query.filter(pub_date.ge(since));
+    }
+    if let Some(until) = &selection.until {
+        query = query.filter(pub_date.lt(until));
+    }
+
+    let items = query.load::<FeedItem>(conn)?;
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn set_feed_item_flags(
+    conn: &mut PgConnection,
+    item_ids: &[String],
+    is_read_param: Option<bool>,
+    starred_param: Option<bool>,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let items = feed_items.filter(id.eq_any(item_ids));
+    let updated = match (is_read_param, starred_param) {
+        (Some(read), Some(star)) => diesel::update(items).set((is_read.eq(Some(read)), starred.eq(Some(star)))).execute(conn)?,
+        (Some(read), None) => diesel::update(items).set(is_read.eq(Some(read))).execute(conn)?,
+        (None, Some(star)) => diesel::update(items).set(starred.eq(Some(star))).execute(conn)?,
+        (None, None) => 0,
+    };
+
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_items_for_sync(
+    conn: &mut PgConnection,
+    selection: &SyncSelection,
+    limit: i64,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let query = feed_items.limit(limit).into_boxed();
+    let query = match selection {
+        SyncSelection::After(after) => query.filter(sync_id.gt(after)).order(sync_id.asc()),
+        SyncSelection::Before(before) => query.filter(sync_id.lt(before)).order(sync_id.desc()),
+        SyncSelection::Ids(ids) => query.filter(sync_id.eq_any(ids)).order(sync_id.asc()),
+    };
+
+    Ok(query.load::<FeedItem>(conn)?)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_sync_ids(
+    conn: &mut PgConnection,
+    starred_only: bool,
+) -> Result<Vec<i64>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let query = feed_items.select(sync_id).order(sync_id.asc()).into_boxed();
+    let query = if starred_only {
+        query.filter(starred.eq(true))
+    } else {
+        query.filter(is_read.is_null().or(is_read.eq(false)))
+    };
+    let ids = query.load::<Option<i64>>(conn)?;
+
+    Ok(ids.into_iter().flatten().collect())
+}
+
+#[cfg(feature = "postgres")]
+pub fn mark_feed_items_read_before(
+    conn: &mut PgConnection,
+    feed_ids: Option<&[String]>,
+    before: &str,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let mut items = diesel::update(feed_items)
+        .filter(created_at.lt(before))
+        .filter(is_read.is_null().or(is_read.eq(false)))
+        .into_boxed();
+    if let Some(ids) = feed_ids {
+        items = items.filter(feed_id.eq_any(ids));
+    }
+
+    Ok(items.set(is_read.eq(Some(true))).execute(conn)?)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_all_feed_items(conn: &mut PgConnection) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    Ok(feed_items.count().get_result::<i64>(conn)?)
+}
+
 #[cfg(feature = "postgres")]
 pub fn update_feed_item(
     conn: &mut PgConnection,
@@ -366,6 +829,8 @@ pub fn update_feed_item(
             email_subject.eq(&updated_item.email_subject),
             email_from.eq(&updated_item.email_from),
             email_body.eq(&updated_item.email_body),
+            email_body_html.eq(&updated_item.email_body_html),
+            body_ref.eq(&updated_item.body_ref),
             is_read.eq(updated_item.is_read),
             starred.eq(updated_item.starred),
             body_size.eq(updated_item.body_size),
@@ -376,81 +841,2046 @@ pub fn update_feed_item(
 }
 
 #[cfg(feature = "postgres")]
-pub fn delete_feed_item(
+pub fn update_feed_item_metadata(
     conn: &mut PgConnection,
     item_id: &str,
+    is_read_param: Option<bool>,
+    starred_param: Option<bool>,
 ) -> Result<usize> {
     use crate::db::schema::feed_items::dsl::*;
 
-    let deleted = diesel::delete(feed_items.filter(id.eq(item_id)))
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            is_read.eq(is_read_param),
+            starred.eq(starred_param),
+        ))
         .execute(conn)?;
     
-    Ok(deleted)
+    Ok(updated)
 }
 
 #[cfg(feature = "postgres")]
-pub fn cleanup_old_feed_items(
+pub fn set_feed_item_pinned(
     conn: &mut PgConnection,
-    feed_id_param: &str,
-    max_items: i32,
+    item_id: &str,
+    pinned_param: bool,
 ) -> Result<usize> {
     use crate::db::schema::feed_items::dsl::*;
 
-    // Get items sorted by pub_date descending, then delete all except the first max_items
-    let items_to_keep: Vec<Option<String>> = feed_items
-        .filter(feed_id.eq(feed_id_param))
-        .order(pub_date.desc())
-        .limit(max_items as i64)
-        .select(id)
-        .load::<Option<String>>(conn)?;
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set(pinned.eq(pinned_param))
+        .execute(conn)?;
 
-    if items_to_keep.is_empty() {
-        return Ok(0);
-    }
+    Ok(updated)
+}
 
-    let deleted = diesel::delete(feed_items
-        .filter(feed_id.eq(feed_id_param))
-        .filter(id.ne_all(items_to_keep)))
-    .execute(conn)?;
+#[cfg(feature = "postgres")]
+pub fn move_feed_items(conn: &mut PgConnection, item_ids: &[String], feed_id_param: &str) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
 
-    Ok(deleted)
+    diesel::update(feed_items.filter(id.eq_any(item_ids)).filter(origin_feed_id.is_null()))
+        .set(origin_feed_id.eq(feed_id.nullable()))
+        .execute(conn)?;
+    let moved = diesel::update(feed_items.filter(id.eq_any(item_ids)))
+        .set(feed_id.eq(feed_id_param))
+        .execute(conn)?;
+
+    Ok(moved)
 }
 
 #[cfg(feature = "postgres")]
-pub fn cleanup_old_items_by_date(
+pub fn set_feed_item_rating(
     conn: &mut PgConnection,
-    feed_id_param: &str,
-    cutoff_date: DateTime<Utc>,
+    item_id: &str,
+    rating_param: Option<&str>,
 ) -> Result<usize> {
     use crate::db::schema::feed_items::dsl::*;
 
-    let cutoff_date_str = cutoff_date.to_rfc3339();
-    let deleted = diesel::delete(feed_items
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set(rating.eq(rating_param))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn set_feed_item_chain(
+    conn: &mut PgConnection,
+    item_id: &str,
+    previous: Option<&str>,
+    hash: &str,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            chain_previous.eq(previous),
+            chain_hash.eq(hash),
+        ))
+        .execute(conn)?;
+
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_pinned_feed_items(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let count = feed_items
         .filter(feed_id.eq(feed_id_param))
-        .filter(pub_date.lt(cutoff_date_str)))
-    .execute(conn)?;
+        .filter(pinned.eq(true))
+        .count()
+        .get_result(conn)?;
 
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_feed_item(
+    conn: &mut PgConnection,
+    item_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let deleted = diesel::delete(feed_items.filter(id.eq(item_id)))
+        .execute(conn)?;
+    
     Ok(deleted)
 }
 
 #[cfg(feature = "postgres")]
-pub fn find_duplicate_items(
+pub fn count_feed_items_missing_metadata(
     conn: &mut PgConnection,
-    feed_id_param: &str,
-    title_param: &str,
-    link_param: Option<&str>,
+) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let count = feed_items
+        .filter(
+            body_size.is_null()
+                .or(body_size.eq(0).and(email_body.ne("")))
+                .or(content_hash.is_null())
+                .or(language.is_null())
+                .or(is_read.is_null())
+                .or(starred.is_null()),
+        )
+        .count()
+        .get_result::<i64>(conn)?;
+    
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_items_missing_metadata(
+    conn: &mut PgConnection,
+    after_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let mut query = feed_items
+        .filter(
+            body_size.is_null()
+                .or(body_size.eq(0).and(email_body.ne("")))
+                .or(content_hash.is_null())
+                .or(language.is_null())
+                .or(is_read.is_null())
+                .or(starred.is_null()),
+        )
+        .order(id.asc())
+        .limit(limit)
+        .into_boxed();
+
+    if let Some(after_id) = after_id {
+        query = query.filter(id.gt(after_id));
+    }
+
+    let items = query.load::<FeedItem>(conn)?;
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_feed_item_computed_metadata(
+    conn: &mut PgConnection,
+    item_id: &str,
+    body_size_param: i32,
+    content_hash_param: &str,
+    language_param: &str,
+    is_read_param: bool,
+    starred_param: bool,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            body_size.eq(Some(body_size_param)),
+            content_hash.eq(Some(content_hash_param)),
+            language.eq(Some(language_param)),
+            is_read.eq(Some(is_read_param)),
+            starred.eq(Some(starred_param)),
+        ))
+        .execute(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_items_by_processing_run(
+    conn: &mut PgConnection,
+    run_id: &str,
 ) -> Result<Vec<FeedItem>> {
     use crate::db::schema::feed_items::dsl::*;
 
+    let items = feed_items
+        .filter(processing_run_id.eq(run_id))
+        .load::<FeedItem>(conn)?;
+    
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn find_canonical_feed_item(
+    conn: &mut PgConnection,
+    exclude_feed_id: &str,
+    message_id: Option<&str>,
+    hash: &str,
+) -> Result<Option<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
     let mut query = feed_items
+        .filter(canonical_item_id.is_null())
+        .filter(feed_id.ne(exclude_feed_id))
+        .into_boxed();
+
+    query = match message_id {
+        Some(message_id) => query.filter(email_message_id.eq(message_id).or(content_hash.eq(hash))),
+        None => query.filter(content_hash.eq(hash)),
+    };
+
+    let item = query
+        .order(created_at.asc())
+        .first::<FeedItem>(conn)
+        .optional()?;
+    
+    Ok(item)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_linked_feed_items(
+    conn: &mut PgConnection,
+    canonical_id: &str,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let items = feed_items
+        .filter(canonical_item_id.eq(canonical_id))
+        .order(created_at.asc())
+        .load::<FeedItem>(conn)?;
+    
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_feed_item_references(
+    conn: &mut PgConnection,
+    canonical_id: &str,
+) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let count = feed_items
+        .filter(canonical_item_id.eq(canonical_id))
+        .count()
+        .get_result::<i64>(conn)?;
+    
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn promote_feed_item_to_canonical(
+    conn: &mut PgConnection,
+    item_id: &str,
+    body: Option<String>,
+    body_html: Option<String>,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            canonical_item_id.eq(None::<String>),
+            email_body.eq(body),
+            email_body_html.eq(body_html),
+        ))
+        .execute(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn set_feed_item_body_ref(
+    conn: &mut PgConnection,
+    item_id: &str,
+    reference: &str,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            body_ref.eq(reference),
+            email_body.eq(None::<String>),
+            email_body_html.eq(None::<String>),
+        ))
+        .execute(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn restore_feed_item_body(
+    conn: &mut PgConnection,
+    item_id: &str,
+    body: Option<String>,
+    body_html: Option<String>,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let updated = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            body_ref.eq(None::<String>),
+            email_body.eq(body),
+            email_body_html.eq(body_html),
+        ))
+        .execute(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn feed_item_message_id_exists(
+    conn: &mut PgConnection,
+    message_id: &str,
+    account_id: Option<&str>,
+) -> Result<bool> {
+    use crate::db::schema::{email_rules, feed_items, feeds};
+
+    let mut query = feed_items::table
+        .filter(feed_items::email_message_id.eq(message_id))
+        .into_boxed();
+    if let Some(account_id) = account_id {
+        let account_feeds = feeds::table
+            .inner_join(email_rules::table)
+            .filter(email_rules::imap_account_id.eq(account_id))
+            .select(feeds::id.assume_not_null());
+        query = query.filter(feed_items::feed_id.eq_any(account_feeds));
+    }
+    let item = query
+        .select(feed_items::feed_id)
+        .first::<String>(conn)
+        .optional()?;
+
+    Ok(item.is_some())
+}
+
+#[cfg(feature = "postgres")]
+pub fn is_feed_item_duplicate(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    message_id_param: &str,
+    title_param: &str,
+    from_param: &str,
+    pub_date_param: &str,
+    content_hash_param: &str,
+) -> Result<bool> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    if !message_id_param.is_empty() {
+        let count: i64 = feed_items
+            .filter(feed_id.eq(feed_id_param))
+            .filter(email_message_id.eq(message_id_param)
+                .or(digest_message_ids.like(format!("%{}%", message_id_param))))
+            .count()
+            .get_result(conn)?;
+        if count > 0 {
+            return Ok(true);
+        }
+    }
+    let count: i64 = feed_items
         .filter(feed_id.eq(feed_id_param))
         .filter(title.eq(title_param))
+        .filter(email_from.eq(from_param))
+        .filter(pub_date.eq(pub_date_param))
+        .count()
+        .get_result(conn)?;
+    if count > 0 || content_hash_param.is_empty() {
+        return Ok(count > 0);
+    }
+    let count: i64 = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(content_hash.eq(content_hash_param))
+        .count()
+        .get_result(conn)?;
+
+    Ok(count > 0)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_digest_of_thread(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    thread_id_param: &str,
+) -> Result<Option<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let item = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(thread_id.eq(thread_id_param))
+        .filter(body_ref.is_null())
+        .filter(canonical_item_id.is_null())
+        .order((pub_date.desc(), id.desc()))
+        .first::<FeedItem>(conn)
+        .optional()?;
+
+    Ok(item)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_digest_of_day(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    day: &str,
+) -> Result<Option<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let item = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(pub_date.like(format!("{}%", day)))
+        .filter(body_ref.is_null())
+        .filter(canonical_item_id.is_null())
+        .order((pub_date.desc(), id.desc()))
+        .first::<FeedItem>(conn)
+        .optional()?;
+
+    Ok(item)
+}
+
+#[cfg(feature = "postgres")]
+pub fn merge_feed_item_digest(
+    conn: &mut PgConnection,
+    item_id: &str,
+    merge: &DigestMerge,
+) -> Result<FeedItem> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let item = diesel::update(feed_items.filter(id.eq(item_id)))
+        .set((
+            title.eq(&merge.title),
+            pub_date.eq(&merge.pub_date),
+            email_message_id.eq(&merge.email_message_id),
+            email_subject.eq(&merge.email_subject),
+            email_from.eq(&merge.email_from),
+            author.eq(&merge.email_from),
+            description.eq(&merge.description),
+            email_body.eq(&merge.email_body),
+            email_body_html.eq(&merge.email_body_html),
+            body_size.eq(merge.body_size),
+            digest_message_ids.eq(&merge.digest_message_ids),
+        ))
+        .get_result::<FeedItem>(conn)?;
+
+    Ok(item)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_feed_items_with_stored_bodies(
+    conn: &mut PgConnection,
+) -> Result<i64> {
+    use crate::db::schema::feed_items::dsl::*;
+    use crate::db::schema::feeds;
+
+    let count = feed_items
+        .filter(body_ref.is_null())
+        .filter(canonical_item_id.is_null())
+        .filter(email_body.is_not_null().or(email_body_html.is_not_null()))
+        .filter(feed_id.ne_all(feeds::table.filter(feeds::append_only.eq(true)).select(feeds::id.assume_not_null())))
+        .count()
+        .get_result::<i64>(conn)?;
+    
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_items_with_stored_bodies(
+    conn: &mut PgConnection,
+    after_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+    use crate::db::schema::feeds;
+
+    let mut query = feed_items
+        .filter(body_ref.is_null())
+        .filter(canonical_item_id.is_null())
+        .filter(email_body.is_not_null().or(email_body_html.is_not_null()))
+        .filter(feed_id.ne_all(feeds::table.filter(feeds::append_only.eq(true)).select(feeds::id.assume_not_null())))
+        .order(id.asc())
+        .limit(limit)
         .into_boxed();
 
-    if let Some(link_val) = link_param {
-        query = query.filter(link.eq(link_val));
+    if let Some(after_id) = after_id {
+        query = query.filter(id.gt(after_id));
     }
 
-    let duplicates = query.load::<FeedItem>(conn)?;
-    Ok(duplicates)
-}
\ No newline at end of file
+    let items = query.load::<FeedItem>(conn)?;
+    Ok(items)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_item_body_refs(
+    conn: &mut PgConnection,
+) -> Result<Vec<String>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let refs = feed_items
+        .filter(body_ref.is_not_null())
+        .select(body_ref.assume_not_null())
+        .load::<String>(conn)?;
+    
+    Ok(refs)
+}
+
+#[cfg(feature = "postgres")]
+pub fn relink_feed_items(
+    conn: &mut PgConnection,
+    from_id: &str,
+    to_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let updated = diesel::update(feed_items.filter(canonical_item_id.eq(from_id)))
+        .set(canonical_item_id.eq(to_id))
+        .execute(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_feed_items_by_processing_run(
+    conn: &mut PgConnection,
+    run_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let deleted = diesel::delete(feed_items.filter(processing_run_id.eq(run_id)))
+        .execute(conn)?;
+    
+    Ok(deleted)
+}
+
+#[cfg(feature = "postgres")]
+pub fn cleanup_old_feed_items(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    max_items: i32,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    // Get items sorted by pub_date descending, then delete all except the first max_items
+    let items_to_keep: Vec<Option<String>> = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .order((pub_date.desc(), id.desc()))
+        .limit(max_items as i64)
+        .select(id)
+        .load::<Option<String>>(conn)?;
+
+    if items_to_keep.is_empty() {
+        return Ok(0);
+    }
+
+    let deleted = diesel::delete(feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(id.ne_all(items_to_keep)))
+    .execute(conn)?;
+
+    Ok(deleted)
+}
+
+#[cfg(feature = "postgres")]
+pub fn cleanup_old_items_by_date(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    cutoff_date: DateTime<Utc>,
+) -> Result<usize> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let cutoff_date_str = cutoff_date.to_rfc3339();
+    let deleted = diesel::delete(feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(pub_date.lt(cutoff_date_str)))
+    .execute(conn)?;
+
+    Ok(deleted)
+}
+
+#[cfg(feature = "postgres")]
+pub fn find_duplicate_items(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+    title_param: &str,
+    link_param: Option<&str>,
+) -> Result<Vec<FeedItem>> {
+    use crate::db::schema::feed_items::dsl::*;
+
+    let mut query = feed_items
+        .filter(feed_id.eq(feed_id_param))
+        .filter(title.eq(title_param))
+        .into_boxed();
+
+    if let Some(link_val) = link_param {
+        query = query.filter(link.eq(link_val));
+    }
+
+    let duplicates = query.load::<FeedItem>(conn)?;
+    Ok(duplicates)
+}
+// Processing run operations
+#[cfg(feature = "postgres")]
+pub fn create_processing_run(
+    conn: &mut PgConnection,
+    new_run: &NewProcessingRun,
+) -> Result<ProcessingRun> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let result = diesel::insert_into(processing_runs)
+        .values(new_run)
+        .get_result::<ProcessingRun>(conn)?;
+    
+    Ok(result)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_processing_run(
+    conn: &mut PgConnection,
+    run_id: &str,
+) -> Result<Option<ProcessingRun>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let run = processing_runs
+        .filter(id.eq(run_id))
+        .first::<ProcessingRun>(conn)
+        .optional()?;
+    
+    Ok(run)
+}
+
+#[cfg(feature = "postgres")]
+pub fn finish_processing_run(
+    conn: &mut PgConnection,
+    run_id: &str,
+    run_status: &ProcessingRunStatus,
+    emails_processed_param: i32,
+    items_created_param: i32,
+    error_message_param: Option<String>,
+) -> Result<ProcessingRun> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let updated = diesel::update(processing_runs.filter(id.eq(run_id)))
+        .set((
+            status.eq(run_status.as_str()),
+            finished_at.eq(Some(Utc::now().to_rfc3339())),
+            emails_processed.eq(emails_processed_param),
+            items_created.eq(items_created_param),
+            error_message.eq(error_message_param),
+        ))
+        .get_result::<ProcessingRun>(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn count_processing_run_item(
+    conn: &mut PgConnection,
+    run_id: &str,
+) -> Result<()> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    diesel::update(processing_runs.filter(id.eq(run_id)))
+        .set(items_created.eq(items_created + 1))
+        .execute(conn)?;
+    
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_processing_run_status(
+    conn: &mut PgConnection,
+    run_id: &str,
+    run_status: &ProcessingRunStatus,
+) -> Result<ProcessingRun> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let updated = diesel::update(processing_runs.filter(id.eq(run_id)))
+        .set(status.eq(run_status.as_str()))
+        .get_result::<ProcessingRun>(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn list_processing_runs(
+    conn: &mut PgConnection,
+    filter: &ProcessingRunFilter,
+) -> Result<Vec<ProcessingRun>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let mut query = processing_runs
+        .order((started_at.desc(), id.desc()))
+        .limit(filter.limit)
+        .into_boxed();
+
+    if let Some(account_id) = &filter.imap_account_id {
+        query = query.filter(imap_account_id.eq(account_id));
+    }
+    if let Some(run_status) = &filter.status {
+        query = query.filter(status.eq(run_status.as_str()));
+    }
+    if let Some(after) = &filter.started_after {
+        query = query.filter(started_at.ge(after));
+    }
+    if let Some(before) = &filter.started_before {
+        query = query.filter(started_at.lt(before));
+    }
+
+    let runs = query.load::<ProcessingRun>(conn)?;
+    Ok(runs)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_processing_runs_started_since(
+    conn: &mut PgConnection,
+    account_ids: &[String],
+    since: &str,
+) -> Result<Vec<ProcessingRun>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let runs = processing_runs
+        .filter(imap_account_id.eq_any(account_ids))
+        .filter(started_at.ge(since))
+        .load::<ProcessingRun>(conn)?;
+    
+    Ok(runs)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_processing_run_status_counts_since(
+    conn: &mut PgConnection,
+    since: &str,
+) -> Result<Vec<RunStatusCount>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let counts = processing_runs
+        .filter(started_at.ge(since))
+        .group_by((imap_account_id, status))
+        .select((imap_account_id, status, diesel::dsl::count_star()))
+        .load::<RunStatusCount>(conn)?;
+
+    Ok(counts)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_last_completed_runs_by_account(
+    conn: &mut PgConnection,
+) -> Result<Vec<(String, Option<String>)>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let runs = processing_runs
+        .filter(status.eq(ProcessingRunStatus::Completed.as_str()))
+        .group_by(imap_account_id)
+        .select((imap_account_id, diesel::dsl::max(finished_at)))
+        .load::<(String, Option<String>)>(conn)?;
+
+    Ok(runs)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_last_completed_run_start(
+    conn: &mut PgConnection,
+    account_id: &str,
+) -> Result<Option<String>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let started = processing_runs
+        .filter(imap_account_id.eq(account_id))
+        .filter(status.eq(ProcessingRunStatus::Completed.as_str()))
+        .select(diesel::dsl::max(started_at))
+        .get_result::<Option<String>>(conn)?;
+
+    Ok(started)
+}
+
+#[cfg(feature = "postgres")]
+pub fn abort_running_processing_runs(
+    conn: &mut PgConnection,
+    error_message_param: &str,
+) -> Result<Vec<ProcessingRun>> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let mut aborted = diesel::update(processing_runs.filter(status.eq(ProcessingRunStatus::Running.as_str())))
+        .set((
+            status.eq(ProcessingRunStatus::Aborted.as_str()),
+            finished_at.eq(Some(Utc::now().to_rfc3339())),
+            error_message.eq(Some(error_message_param)),
+        ))
+        .get_results::<ProcessingRun>(conn)?;
+
+    aborted.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(aborted)
+}
+
+#[cfg(feature = "postgres")]
+pub fn record_processing_run_transfer(
+    conn: &mut PgConnection,
+    run_id: &str,
+    bytes_received_param: i64,
+    bytes_sent_param: i64,
+) -> Result<ProcessingRun> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let updated = diesel::update(processing_runs.filter(id.eq(run_id)))
+        .set((
+            bytes_received.eq(bytes_received_param),
+            bytes_sent.eq(bytes_sent_param),
+        ))
+        .get_result::<ProcessingRun>(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn record_processing_run_catch_up(
+    conn: &mut PgConnection,
+    run_id: &str,
+    from: &str,
+    to: &str,
+    truncated: bool,
+) -> Result<ProcessingRun> {
+    use crate::db::schema::processing_runs::dsl::*;
+
+    let updated = diesel::update(processing_runs.filter(id.eq(run_id)))
+        .set((
+            catch_up_from.eq(Some(from)),
+            catch_up_to.eq(Some(to)),
+            catch_up_truncated.eq(truncated),
+        ))
+        .get_result::<ProcessingRun>(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn create_processing_run_action(
+    conn: &mut PgConnection,
+    new_action: &NewProcessingRunAction,
+) -> Result<ProcessingRunAction> {
+    use crate::db::schema::processing_run_actions::dsl::*;
+
+    let result = diesel::insert_into(processing_run_actions)
+        .values(new_action)
+        .get_result::<ProcessingRunAction>(conn)?;
+    
+    Ok(result)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_processing_run_actions(
+    conn: &mut PgConnection,
+    run_id: &str,
+) -> Result<Vec<ProcessingRunAction>> {
+    use crate::db::schema::processing_run_actions::dsl::*;
+
+    let actions = processing_run_actions
+        .filter(processing_run_id.eq(run_id))
+        .order(created_at.asc())
+        .load::<ProcessingRunAction>(conn)?;
+    
+    Ok(actions)
+}
+
+// Processing intent operations
+#[cfg(feature = "postgres")]
+pub fn create_processing_intent(
+    conn: &mut PgConnection,
+    new_intent: &NewProcessingIntent,
+) -> Result<ProcessingIntent> {
+    use crate::db::schema::processing_intents::dsl::*;
+
+    let result = diesel::insert_into(processing_intents)
+        .values(new_intent)
+        .get_result::<ProcessingIntent>(conn)?;
+    
+    Ok(result)
+}
+
+#[cfg(feature = "postgres")]
+pub fn resolve_processing_intent(
+    conn: &mut PgConnection,
+    intent_id: &str,
+    intent_status: &ProcessingIntentStatus,
+    item_id: Option<&str>,
+) -> Result<ProcessingIntent> {
+    use crate::db::schema::processing_intents::dsl::*;
+
+    let updated = diesel::update(processing_intents.filter(id.eq(intent_id)))
+        .set((
+            status.eq(intent_status.as_str()),
+            feed_item_id.eq(item_id),
+            email_snapshot.eq(None::<String>),
+            resolved_at.eq(Some(Utc::now().to_rfc3339())),
+        ))
+        .get_result::<ProcessingIntent>(conn)?;
+    
+    Ok(updated)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_pending_processing_intents(conn: &mut PgConnection) -> Result<Vec<ProcessingIntent>> {
+    use crate::db::schema::processing_intents::dsl::*;
+
+    let intents = processing_intents
+        .filter(status.eq(ProcessingIntentStatus::Pending.as_str()))
+        .order(created_at.asc())
+        .load::<ProcessingIntent>(conn)?;
+    
+    Ok(intents)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_processing_intents(
+    conn: &mut PgConnection,
+    run_id: &str,
+) -> Result<Vec<ProcessingIntent>> {
+    use crate::db::schema::processing_intents::dsl::*;
+
+    let intents = processing_intents
+        .filter(processing_run_id.eq(run_id))
+        .order(created_at.asc())
+        .load::<ProcessingIntent>(conn)?;
+    
+    Ok(intents)
+}
+
+// Deferred action operations
+#[cfg(feature = "postgres")]
+pub fn create_deferred_action(
+    conn: &mut PgConnection,
+    new_action: &NewDeferredAction,
+) -> Result<DeferredAction> {
+    use crate::db::schema::deferred_actions::dsl::*;
+
+    let result = diesel::insert_into(deferred_actions)
+        .values(new_action)
+        .get_result::<DeferredAction>(conn)?;
+    
+    Ok(result)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_due_deferred_actions(conn: &mut PgConnection, now: &str) -> Result<Vec<DeferredAction>> {
+    use crate::db::schema::deferred_actions::dsl::*;
+
+    let actions = deferred_actions
+        .filter(status.eq(DeferredActionStatus::Pending.as_str()))
+        .filter(due_at.le(now))
+        .order(due_at.asc())
+        .load::<DeferredAction>(conn)?;
+    
+    Ok(actions)
+}
+
+#[cfg(feature = "postgres")]
+pub fn mark_deferred_action_failed(
+    conn: &mut PgConnection,
+    action_id: &str,
+    error: &str,
+) -> Result<()> {
+    use crate::db::schema::deferred_actions::dsl::*;
+
+    diesel::update(deferred_actions.filter(id.eq(action_id)))
+        .set((
+            status.eq(DeferredActionStatus::Failed.as_str()),
+            last_error.eq(Some(error)),
+        ))
+        .execute(conn)?;
+    
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_deferred_action(conn: &mut PgConnection, action_id: &str) -> Result<()> {
+    use crate::db::schema::deferred_actions::dsl::*;
+
+    diesel::delete(deferred_actions.filter(id.eq(action_id))).execute(conn)?;
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_pending_deferred_actions(conn: &mut PgConnection, run_id: &str) -> Result<usize> {
+    use crate::db::schema::deferred_actions::dsl::*;
+
+    let deleted = diesel::delete(
+        deferred_actions
+            .filter(processing_run_id.eq(run_id))
+            .filter(status.eq(DeferredActionStatus::Pending.as_str())),
+    )
+    .execute(conn)?;
+    
+    Ok(deleted)
+}
+
+// Attachment operations
+#[cfg(feature = "postgres")]
+pub fn create_attachments(conn: &mut PgConnection, new_attachments: &[NewAttachment]) -> Result<usize> {
+    use crate::db::schema::attachments::dsl::*;
+
+    let count = diesel::insert_into(attachments)
+        .values(new_attachments)
+        .execute(conn)?;
+    
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_attachment(conn: &mut PgConnection, item_id: &str, attachment_position: i32) -> Result<Attachment> {
+    use crate::db::schema::attachments::dsl::*;
+
+    let attachment = attachments
+        .filter(feed_item_id.eq(item_id))
+        .filter(position.eq(attachment_position))
+        .first::<Attachment>(conn)?;
+    
+    Ok(attachment)
+}
+
+#[cfg(feature = "postgres")]
+pub fn list_attachments_for_items(conn: &mut PgConnection, item_ids: &[String]) -> Result<Vec<AttachmentInfo>> {
+    use crate::db::schema::attachments::dsl::*;
+
+    let infos = attachments
+        .filter(feed_item_id.eq_any(item_ids))
+        .order((feed_item_id.asc(), position.asc()))
+        .select(AttachmentInfo::as_select())
+        .load(conn)?;
+    
+    Ok(infos)
+}
+
+#[cfg(feature = "postgres")]
+pub fn reassign_attachments(conn: &mut PgConnection, from_item_id: &str, to_item_id: &str) -> Result<usize> {
+    use crate::db::schema::attachments::dsl::*;
+
+    let count = diesel::update(attachments.filter(feed_item_id.eq(from_item_id)))
+        .set(feed_item_id.eq(to_item_id))
+        .execute(conn)?;
+    
+    Ok(count)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_orphaned_attachments(conn: &mut PgConnection) -> Result<usize> {
+    use crate::db::schema::{attachments, feed_items};
+
+    let item_ids = feed_items::table.select(feed_items::id.assume_not_null());
+    let count = diesel::delete(attachments::table.filter(attachments::feed_item_id.ne_all(item_ids)))
+        .execute(conn)?;
+    
+    Ok(count)
+}
+
+// Rule match operations
+#[cfg(feature = "postgres")]
+pub fn create_rule_match_if_new(
+    conn: &mut PgConnection,
+    new_match: &NewRuleMatch,
+) -> Result<bool> {
+    use crate::db::schema::rule_matches::dsl::*;
+
+    let inserted = diesel::insert_into(rule_matches)
+        .values(new_match)
+        .on_conflict((email_rule_id, email_message_id))
+        .do_nothing()
+        .execute(conn)?;
+    
+    Ok(inserted > 0)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_rule_matches(
+    conn: &mut PgConnection,
+    rule_id: &str,
+    limit: Option<i64>,
+) -> Result<Vec<RuleMatch>> {
+    use crate::db::schema::rule_matches::dsl::*;
+
+    let mut query = rule_matches
+        .filter(email_rule_id.eq(rule_id))
+        .order(matched_at.desc())
+        .into_boxed();
+    if let Some(limit_param) = limit {
+        query = query.limit(limit_param);
+    }
+    let matches = query.load::<RuleMatch>(conn)?;
+    
+    Ok(matches)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_rule_match_stats(
+    conn: &mut PgConnection,
+    rule_id: &str,
+) -> Result<RuleMatchStats> {
+    use crate::db::schema::rule_matches::dsl::*;
+
+    let (match_count, last_matched_at) = rule_matches
+        .filter(email_rule_id.eq(rule_id))
+        .select((diesel::dsl::count_star(), diesel::dsl::max(matched_at)))
+        .first::<(i64, Option<String>)>(conn)?;
+    
+    Ok(RuleMatchStats { match_count, last_matched_at })
+}
+
+// Rule cost operations
+#[cfg(feature = "postgres")]
+pub fn create_rule_cost(
+    conn: &mut PgConnection,
+    new_cost: &NewRuleCost,
+) -> Result<()> {
+    use crate::db::schema::rule_costs::dsl::*;
+
+    diesel::insert_into(rule_costs)
+        .values(new_cost)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_rule_costs_by_run(
+    conn: &mut PgConnection,
+    run_id: &str,
+) -> Result<Vec<RuleCost>> {
+    use crate::db::schema::rule_costs::dsl::*;
+
+    let costs = rule_costs
+        .filter(processing_run_id.eq(run_id))
+        .order(created_at.asc())
+        .load::<RuleCost>(conn)?;
+
+    Ok(costs)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_rule_costs_since(
+    conn: &mut PgConnection,
+    since: &str,
+) -> Result<Vec<RuleCost>> {
+    use crate::db::schema::rule_costs::dsl::*;
+
+    let costs = rule_costs
+        .filter(created_at.ge(since))
+        .load::<RuleCost>(conn)?;
+
+    Ok(costs)
+}
+
+// Retention policy operations
+#[cfg(feature = "postgres")]
+pub fn get_retention_policies(
+    conn: &mut PgConnection,
+) -> Result<Vec<RetentionPolicy>> {
+    use crate::db::schema::retention_policies::dsl::*;
+
+    let policies = retention_policies
+        .order(target.asc())
+        .load::<RetentionPolicy>(conn)?;
+
+    Ok(policies)
+}
+
+#[cfg(feature = "postgres")]
+pub fn record_app_version_run(
+    conn: &mut PgConnection,
+    version_param: &str,
+    git_hash_param: Option<&str>,
+) -> Result<AppVersion> {
+    use crate::db::schema::app_versions::dsl::*;
+
+    let new_version = AppVersion::new(version_param.to_string(), git_hash_param.map(str::to_string));
+    let recorded = diesel::insert_into(app_versions)
+        .values(&new_version)
+        .on_conflict(version)
+        .do_update()
+        .set((
+            git_hash.eq(git_hash_param),
+            last_run_at.eq(&new_version.last_run_at),
+        ))
+        .get_result::<AppVersion>(conn)?;
+
+    Ok(recorded)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_app_versions(conn: &mut PgConnection) -> Result<Vec<AppVersion>> {
+    use crate::db::schema::app_versions::dsl::*;
+
+    let versions = app_versions
+        .order(last_run_at.desc())
+        .load::<AppVersion>(conn)?;
+
+    Ok(versions)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_schema_version(conn: &mut PgConnection) -> Result<Option<String>> {
+    use diesel_migrations::MigrationHarness;
+
+    let applied = conn
+        .applied_migrations()
+        .map_err(|e| anyhow::anyhow!("Failed to load applied migrations: {}", e))?;
+    Ok(applied.iter().max().map(ToString::to_string))
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_feed_redirect(conn: &mut PgConnection, old_feed_id_param: &str) -> Result<Option<FeedRedirect>> {
+    use crate::db::schema::feed_redirects::dsl::*;
+
+    let redirect = feed_redirects
+        .find(old_feed_id_param)
+        .first::<FeedRedirect>(conn)
+        .optional()?;
+
+    Ok(redirect)
+}
+
+#[cfg(feature = "postgres")]
+pub fn create_feed_redirect(conn: &mut PgConnection, old_feed_id_param: &str, feed_id_param: &str) -> Result<FeedRedirect> {
+    use crate::db::schema::feed_redirects::dsl::*;
+
+    diesel::update(feed_redirects.filter(feed_id.eq(old_feed_id_param)))
+        .set(feed_id.eq(feed_id_param))
+        .execute(conn)?;
+    let new_redirect = FeedRedirect::new(old_feed_id_param.to_string(), feed_id_param.to_string());
+    let redirect = diesel::insert_into(feed_redirects)
+        .values(&new_redirect)
+        .on_conflict(old_feed_id)
+        .do_update()
+        .set(feed_id.eq(feed_id_param))
+        .get_result::<FeedRedirect>(conn)?;
+
+    Ok(redirect)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_app_setting(conn: &mut PgConnection, key_param: &str) -> Result<Option<AppSetting>> {
+    use crate::db::schema::app_settings::dsl::*;
+
+    let setting = app_settings
+        .find(key_param)
+        .first::<AppSetting>(conn)
+        .optional()?;
+
+    Ok(setting)
+}
+
+#[cfg(feature = "postgres")]
+pub fn set_app_setting(conn: &mut PgConnection, key_param: &str, value_param: &str) -> Result<AppSetting> {
+    use crate::db::schema::app_settings::dsl::*;
+
+    let new_setting = AppSetting::new(key_param.to_string(), value_param.to_string());
+    let stored = diesel::insert_into(app_settings)
+        .values(&new_setting)
+        .on_conflict(key)
+        .do_update()
+        .set((
+            value.eq(value_param),
+            updated_at.eq(&new_setting.updated_at),
+        ))
+        .get_result::<AppSetting>(conn)?;
+
+    Ok(stored)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_app_setting(conn: &mut PgConnection, key_param: &str) -> Result<()> {
+    use crate::db::schema::app_settings::dsl::*;
+
+    diesel::delete(app_settings.find(key_param)).execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn upsert_retention_policy(
+    conn: &mut PgConnection,
+    policy_target: RetentionTarget,
+    max_rows_param: Option<i32>,
+    max_age_days_param: Option<i32>,
+) -> Result<RetentionPolicy> {
+    use crate::db::schema::retention_policies::dsl::*;
+
+    let new_policy = NewRetentionPolicy::new(policy_target, max_rows_param, max_age_days_param);
+    let policy = diesel::insert_into(retention_policies)
+        .values(&new_policy)
+        .on_conflict(target)
+        .do_update()
+        .set((
+            max_rows.eq(max_rows_param),
+            max_age_days.eq(max_age_days_param),
+            updated_at.eq(&new_policy.updated_at),
+        ))
+        .get_result::<RetentionPolicy>(conn)?;
+
+    Ok(policy)
+}
+
+#[cfg(feature = "postgres")]
+pub fn record_retention_purge(
+    conn: &mut PgConnection,
+    policy_target: RetentionTarget,
+    rows: i64,
+) -> Result<()> {
+    use crate::db::schema::retention_policies::dsl::*;
+
+    diesel::update(retention_policies.filter(target.eq(policy_target.as_str())))
+        .set((
+            rows_purged.eq(rows_purged + rows),
+            last_purged_at.eq(Some(Utc::now().to_rfc3339())),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_retention_candidates(
+    conn: &mut PgConnection,
+    policy_target: RetentionTarget,
+) -> Result<Vec<RetentionCandidate>> {
+    use crate::db::schema::{deferred_actions, deliveries, processing_intents, processing_runs, rule_costs, rule_matches};
+
+    let candidates = match policy_target {
+        RetentionTarget::ProcessingRuns => {
+            let mut held: Vec<String> = processing_intents::table
+                .filter(processing_intents::status.eq(ProcessingIntentStatus::Pending.as_str()))
+                .select(processing_intents::processing_run_id)
+                .load(conn)?;
+            held.extend(deferred_actions::table
+                .filter(deferred_actions::status.eq(DeferredActionStatus::Pending.as_str()))
+                .select(deferred_actions::processing_run_id)
+                .load::<String>(conn)?);
+            processing_runs::table
+                .filter(processing_runs::finished_at.is_not_null())
+                .select((processing_runs::id.assume_not_null(), processing_runs::started_at))
+                .order(processing_runs::started_at.desc())
+                .load::<RetentionCandidate>(conn)?
+                .into_iter()
+                .filter(|(run_id, _)| !held.contains(run_id))
+                .collect()
+        }
+        RetentionTarget::RuleMatches => rule_matches::table
+            .select((rule_matches::id.assume_not_null(), rule_matches::matched_at))
+            .order(rule_matches::matched_at.desc())
+            .load(conn)?,
+        RetentionTarget::Deliveries => deliveries::table
+            .filter(deliveries::status.ne(DeliveryStatus::Pending.as_str()))
+            .select((deliveries::id.assume_not_null(), deliveries::created_at))
+            .order(deliveries::created_at.desc())
+            .load(conn)?,
+        RetentionTarget::RuleCosts => rule_costs::table
+            .select((rule_costs::id.assume_not_null(), rule_costs::created_at))
+            .order(rule_costs::created_at.desc())
+            .load(conn)?,
+    };
+
+    Ok(candidates)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_retention_rows(
+    conn: &mut PgConnection,
+    policy_target: RetentionTarget,
+    ids: &[String],
+) -> Result<usize> {
+    use crate::db::schema::{deferred_actions, deliveries, feed_items, processing_intents, processing_run_actions, processing_runs, rule_costs, rule_matches};
+
+    let deleted = conn.transaction(|conn| -> diesel::QueryResult<usize> {
+        Ok(match policy_target {
+            RetentionTarget::ProcessingRuns => {
+                diesel::delete(processing_run_actions::table.filter(processing_run_actions::processing_run_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::delete(processing_intents::table.filter(processing_intents::processing_run_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::delete(rule_costs::table.filter(rule_costs::processing_run_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::delete(deferred_actions::table.filter(deferred_actions::processing_run_id.eq_any(ids)))
+                    .execute(conn)?;
+                diesel::update(feed_items::table.filter(feed_items::processing_run_id.eq_any(ids)))
+                    .set(feed_items::processing_run_id.eq(None::<String>))
+                    .execute(conn)?;
+                diesel::delete(processing_runs::table.filter(processing_runs::id.eq_any(ids)))
+                    .execute(conn)?
+            }
+            RetentionTarget::RuleMatches => diesel::delete(rule_matches::table.filter(rule_matches::id.eq_any(ids)))
+                .execute(conn)?,
+            RetentionTarget::Deliveries => diesel::delete(deliveries::table.filter(deliveries::id.eq_any(ids)))
+                .execute(conn)?,
+            RetentionTarget::RuleCosts => diesel::delete(rule_costs::table.filter(rule_costs::id.eq_any(ids)))
+                .execute(conn)?,
+        })
+    })?;
+
+    Ok(deleted)
+}
+
+// Chat integration operations
+#[cfg(feature = "postgres")]
+pub fn create_chat_integration(
+    conn: &mut PgConnection,
+    new_integration: &NewChatIntegration,
+) -> Result<ChatIntegration> {
+    use crate::db::schema::chat_integrations::dsl::*;
+
+    let integration = diesel::insert_into(chat_integrations)
+        .values(new_integration)
+        .get_result::<ChatIntegration>(conn)?;
+    
+    Ok(integration)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_chat_integration(
+    conn: &mut PgConnection,
+    integration_id: &str,
+) -> Result<Option<ChatIntegration>> {
+    use crate::db::schema::chat_integrations::dsl::*;
+
+    let integration = chat_integrations
+        .filter(id.eq(integration_id))
+        .first::<ChatIntegration>(conn)
+        .optional()?;
+    
+    Ok(integration)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_chat_integrations_by_feed(
+    conn: &mut PgConnection,
+    feed_id_param: &str,
+) -> Result<Vec<ChatIntegration>> {
+    use crate::db::schema::chat_integrations::dsl::*;
+
+    let integrations = chat_integrations
+        .filter(feed_id.eq(feed_id_param))
+        .order(created_at.asc())
+        .load::<ChatIntegration>(conn)?;
+    
+    Ok(integrations)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_chat_integration(
+    conn: &mut PgConnection,
+    integration_id: &str,
+    updated: &NewChatIntegration,
+) -> Result<ChatIntegration> {
+    use crate::db::schema::chat_integrations::dsl::*;
+
+    let integration = diesel::update(chat_integrations.filter(id.eq(integration_id)))
+        .set((
+            platform.eq(&updated.platform),
+            webhook_url.eq(&updated.webhook_url),
+            matrix_homeserver.eq(&updated.matrix_homeserver),
+            matrix_room_id.eq(&updated.matrix_room_id),
+            matrix_access_token.eq(&updated.matrix_access_token),
+            message_template.eq(&updated.message_template),
+            rate_limit_per_minute.eq(updated.rate_limit_per_minute),
+            is_active.eq(updated.is_active),
+            updated_at.eq(&updated.updated_at),
+        ))
+        .get_result::<ChatIntegration>(conn)?;
+    
+    Ok(integration)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_chat_integration(
+    conn: &mut PgConnection,
+    integration_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::chat_integrations::dsl::*;
+
+    let deleted = diesel::delete(chat_integrations.filter(id.eq(integration_id)))
+        .execute(conn)?;
+    
+    Ok(deleted)
+}
+
+// Account sender filter operations
+#[cfg(feature = "postgres")]
+pub fn create_account_sender_filter(
+    conn: &mut PgConnection,
+    new_filter: &NewAccountSenderFilter,
+) -> Result<AccountSenderFilter> {
+    use crate::db::schema::account_sender_filters::dsl::*;
+
+    let filter = diesel::insert_into(account_sender_filters)
+        .values(new_filter)
+        .get_result::<AccountSenderFilter>(conn)?;
+    
+    Ok(filter)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_account_sender_filter(
+    conn: &mut PgConnection,
+    filter_id: &str,
+) -> Result<Option<AccountSenderFilter>> {
+    use crate::db::schema::account_sender_filters::dsl::*;
+
+    let filter = account_sender_filters
+        .filter(id.eq(filter_id))
+        .first::<AccountSenderFilter>(conn)
+        .optional()?;
+    
+    Ok(filter)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_account_sender_filters_by_account(
+    conn: &mut PgConnection,
+    account_id: &str,
+) -> Result<Vec<AccountSenderFilter>> {
+    use crate::db::schema::account_sender_filters::dsl::*;
+
+    let filters = account_sender_filters
+        .filter(imap_account_id.eq(account_id))
+        .order((kind.asc(), pattern.asc()))
+        .load::<AccountSenderFilter>(conn)?;
+    
+    Ok(filters)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_account_sender_filter(
+    conn: &mut PgConnection,
+    filter_id: &str,
+    updated: &NewAccountSenderFilter,
+) -> Result<AccountSenderFilter> {
+    use crate::db::schema::account_sender_filters::dsl::*;
+
+    let filter = diesel::update(account_sender_filters.filter(id.eq(filter_id)))
+        .set((
+            kind.eq(&updated.kind),
+            pattern.eq(&updated.pattern),
+        ))
+        .get_result::<AccountSenderFilter>(conn)?;
+    
+    Ok(filter)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_account_sender_filter(
+    conn: &mut PgConnection,
+    filter_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::account_sender_filters::dsl::*;
+
+    let deleted = diesel::delete(account_sender_filters.filter(id.eq(filter_id)))
+        .execute(conn)?;
+    
+    Ok(deleted)
+}
+
+// Rule feed operations
+#[cfg(feature = "postgres")]
+pub fn attach_rule_feed(
+    conn: &mut PgConnection,
+    new_rule_feed: &NewRuleFeed,
+) -> Result<RuleFeed> {
+    use crate::db::schema::rule_feeds::dsl::*;
+
+    let rule_feed = diesel::insert_into(rule_feeds)
+        .values(new_rule_feed)
+        .on_conflict((email_rule_id, feed_id))
+        .do_update()
+        .set(priority.eq(new_rule_feed.priority))
+        .get_result::<RuleFeed>(conn)?;
+
+    Ok(rule_feed)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_rule_feeds_with_feeds(
+    conn: &mut PgConnection,
+    rule_id: &str,
+) -> Result<Vec<(RuleFeed, Feed)>> {
+    use crate::db::schema::{feeds, rule_feeds};
+
+    let attached = rule_feeds::table
+        .inner_join(feeds::table)
+        .filter(rule_feeds::email_rule_id.eq(rule_id))
+        .order((rule_feeds::priority.desc(), rule_feeds::created_at.asc()))
+        .select((RuleFeed::as_select(), feeds::all_columns))
+        .load::<(RuleFeed, Feed)>(conn)?;
+
+    Ok(attached)
+}
+
+#[cfg(feature = "postgres")]
+pub fn detach_rule_feed(
+    conn: &mut PgConnection,
+    rule_id: &str,
+    detached_feed_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::rule_feeds::dsl::*;
+
+    let deleted = diesel::delete(rule_feeds.filter(email_rule_id.eq(rule_id)).filter(feed_id.eq(detached_feed_id)))
+        .execute(conn)?;
+
+    Ok(deleted)
+}
+
+// Delivery queue operations
+#[cfg(feature = "postgres")]
+pub fn create_delivery(
+    conn: &mut PgConnection,
+    new_delivery: &NewDelivery,
+) -> Result<Delivery> {
+    use crate::db::schema::deliveries::dsl::*;
+
+    let delivery = diesel::insert_into(deliveries)
+        .values(new_delivery)
+        .get_result::<Delivery>(conn)?;
+    
+    Ok(delivery)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_delivery(
+    conn: &mut PgConnection,
+    delivery_id: &str,
+) -> Result<Option<Delivery>> {
+    use crate::db::schema::deliveries::dsl::*;
+
+    let delivery = deliveries
+        .filter(id.eq(delivery_id))
+        .first::<Delivery>(conn)
+        .optional()?;
+    
+    Ok(delivery)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_deliveries_by_status(
+    conn: &mut PgConnection,
+    status_param: &DeliveryStatus,
+) -> Result<Vec<Delivery>> {
+    use crate::db::schema::deliveries::dsl::*;
+
+    let found = deliveries
+        .filter(status.eq(status_param.as_str()))
+        .order(created_at.asc())
+        .load::<Delivery>(conn)?;
+    
+    Ok(found)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_delivery_state(
+    conn: &mut PgConnection,
+    delivery_id: &str,
+    status_param: &DeliveryStatus,
+    attempts_param: i32,
+    next_attempt_at_param: &str,
+    last_error_param: Option<&str>,
+) -> Result<Delivery> {
+    use crate::db::schema::deliveries::dsl::*;
+
+    let now = Utc::now().to_rfc3339();
+    let delivered_at_param = (*status_param == DeliveryStatus::Delivered).then(|| now.clone());
+    let delivery = diesel::update(deliveries.filter(id.eq(delivery_id)))
+        .set((
+            status.eq(status_param.as_str()),
+            attempts.eq(attempts_param),
+            next_attempt_at.eq(next_attempt_at_param),
+            last_error.eq(last_error_param),
+            delivered_at.eq(delivered_at_param),
+            updated_at.eq(&now),
+        ))
+        .get_result::<Delivery>(conn)?;
+    
+    Ok(delivery)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_delivery(
+    conn: &mut PgConnection,
+    delivery_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::deliveries::dsl::*;
+
+    let deleted = diesel::delete(deliveries.filter(id.eq(delivery_id)))
+        .execute(conn)?;
+    
+    Ok(deleted)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_delivered_before(
+    conn: &mut PgConnection,
+    cutoff: &str,
+) -> Result<usize> {
+    use crate::db::schema::deliveries::dsl::*;
+
+    let deleted = diesel::delete(
+        deliveries
+            .filter(status.eq(DeliveryStatus::Delivered.as_str()))
+            .filter(delivered_at.lt(cutoff)),
+    )
+    .execute(conn)?;
+    
+    Ok(deleted)
+}
+
+// Quota group operations
+#[cfg(feature = "postgres")]
+pub fn create_quota_group(
+    conn: &mut PgConnection,
+    new_group: &NewQuotaGroup,
+) -> Result<QuotaGroup> {
+    use crate::db::schema::quota_groups::dsl::*;
+
+    let group = diesel::insert_into(quota_groups)
+        .values(new_group)
+        .get_result::<QuotaGroup>(conn)?;
+    
+    Ok(group)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_quota_group(
+    conn: &mut PgConnection,
+    group_id: &str,
+) -> Result<Option<QuotaGroup>> {
+    use crate::db::schema::quota_groups::dsl::*;
+
+    let group = quota_groups
+        .filter(id.eq(group_id))
+        .first::<QuotaGroup>(conn)
+        .optional()?;
+    
+    Ok(group)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_all_quota_groups(
+    conn: &mut PgConnection,
+) -> Result<Vec<QuotaGroup>> {
+    use crate::db::schema::quota_groups::dsl::*;
+
+    let groups = quota_groups
+        .order(name.asc())
+        .load::<QuotaGroup>(conn)?;
+    
+    Ok(groups)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_quota_group(
+    conn: &mut PgConnection,
+    group_id: &str,
+    updated: &NewQuotaGroup,
+) -> Result<QuotaGroup> {
+    use crate::db::schema::quota_groups::dsl::*;
+
+    let group = diesel::update(quota_groups.filter(id.eq(group_id)))
+        .set((
+            name.eq(&updated.name),
+            max_feeds.eq(updated.max_feeds),
+            max_items.eq(updated.max_items),
+            max_processing_minutes_per_day.eq(updated.max_processing_minutes_per_day),
+            updated_at.eq(&updated.updated_at),
+        ))
+        .get_result::<QuotaGroup>(conn)?;
+    
+    Ok(group)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_quota_group(
+    conn: &mut PgConnection,
+    group_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::{imap_accounts, quota_groups};
+
+    let deleted = conn.transaction(|conn| {
+        diesel::update(imap_accounts::table.filter(imap_accounts::quota_group_id.eq(group_id)))
+            .set(imap_accounts::quota_group_id.eq(None::<String>))
+            .execute(conn)?;
+        diesel::delete(quota_groups::table.filter(quota_groups::id.eq(group_id))).execute(conn)
+    })?;
+    
+    Ok(deleted)
+}
+
+// Sender alias operations
+#[cfg(feature = "postgres")]
+pub fn create_sender_alias(
+    conn: &mut PgConnection,
+    new_alias: &NewSenderAlias,
+) -> Result<SenderAlias> {
+    use crate::db::schema::sender_aliases::dsl::*;
+
+    let alias = diesel::insert_into(sender_aliases)
+        .values(new_alias)
+        .get_result::<SenderAlias>(conn)?;
+
+    Ok(alias)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_sender_alias(
+    conn: &mut PgConnection,
+    alias_id: &str,
+) -> Result<Option<SenderAlias>> {
+    use crate::db::schema::sender_aliases::dsl::*;
+
+    let alias = sender_aliases
+        .filter(id.eq(alias_id))
+        .first::<SenderAlias>(conn)
+        .optional()?;
+
+    Ok(alias)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_all_sender_aliases(
+    conn: &mut PgConnection,
+) -> Result<Vec<SenderAlias>> {
+    use crate::db::schema::sender_aliases::dsl::*;
+
+    let aliases = sender_aliases
+        .order(name.asc())
+        .load::<SenderAlias>(conn)?;
+
+    Ok(aliases)
+}
+
+#[cfg(feature = "postgres")]
+pub fn update_sender_alias(
+    conn: &mut PgConnection,
+    alias_id: &str,
+    updated: &NewSenderAlias,
+) -> Result<SenderAlias> {
+    use crate::db::schema::sender_aliases::dsl::*;
+
+    let alias = diesel::update(sender_aliases.filter(id.eq(alias_id)))
+        .set((
+            name.eq(&updated.name),
+            addresses.eq(&updated.addresses),
+            updated_at.eq(&updated.updated_at),
+        ))
+        .get_result::<SenderAlias>(conn)?;
+
+    Ok(alias)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_sender_alias(
+    conn: &mut PgConnection,
+    alias_id: &str,
+) -> Result<usize> {
+    use crate::db::schema::sender_aliases::dsl::*;
+
+    let deleted = diesel::delete(sender_aliases.filter(id.eq(alias_id)))
+        .execute(conn)?;
+
+    Ok(deleted)
+}
+
+// Job operations
+#[cfg(feature = "postgres")]
+pub fn create_job(
+    conn: &mut PgConnection,
+    new_job: &NewJob,
+) -> Result<Job> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let job = diesel::insert_into(jobs)
+        .values(new_job)
+        .get_result::<Job>(conn)?;
+
+    Ok(job)
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_job(
+    conn: &mut PgConnection,
+    job_id: &str,
+) -> Result<Option<Job>> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let job = jobs
+        .filter(id.eq(job_id))
+        .first::<Job>(conn)
+        .optional()?;
+
+    Ok(job)
+}
+
+#[cfg(feature = "postgres")]
+pub fn list_jobs(
+    conn: &mut PgConnection,
+    filter: &JobFilter,
+) -> Result<Vec<Job>> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let mut query = jobs
+        .order((created_at.desc(), id.desc()))
+        .limit(filter.limit)
+        .into_boxed();
+
+    if let Some(job_kind) = &filter.kind {
+        query = query.filter(kind.eq(job_kind));
+    }
+    if let Some(job_target) = &filter.target_id {
+        query = query.filter(target_id.eq(job_target));
+    }
+    if let Some(job_status) = &filter.status {
+        query = query.filter(status.eq(job_status.as_str()));
+    }
+
+    let found = query.load::<Job>(conn)?;
+    Ok(found)
+}
+
+#[cfg(feature = "postgres")]
+pub fn start_job(
+    conn: &mut PgConnection,
+    job_id: &str,
+) -> Result<()> {
+    use crate::db::schema::jobs::dsl::*;
+
+    diesel::update(jobs.filter(id.eq(job_id)))
+        .set((
+            status.eq(JobStatus::Running.as_str()),
+            started_at.eq(Some(Utc::now().to_rfc3339())),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn set_job_total(
+    conn: &mut PgConnection,
+    job_id: &str,
+    total_param: i32,
+) -> Result<()> {
+    use crate::db::schema::jobs::dsl::*;
+
+    diesel::update(jobs.filter(id.eq(job_id)))
+        .set(total.eq(Some(total_param)))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn advance_job(
+    conn: &mut PgConnection,
+    job_id: &str,
+    processed_param: i32,
+    message_param: Option<&str>,
+) -> Result<()> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let target = jobs.filter(id.eq(job_id));
+    match message_param {
+        Some(text) => diesel::update(target)
+            .set((processed.eq(processed + processed_param), message.eq(Some(text))))
+            .execute(conn)?,
+        None => diesel::update(target)
+            .set(processed.eq(processed + processed_param))
+            .execute(conn)?,
+    };
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn finish_job(
+    conn: &mut PgConnection,
+    job_id: &str,
+    job_status: &JobStatus,
+    result_param: Option<String>,
+    error_message_param: Option<String>,
+) -> Result<Job> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let job = diesel::update(jobs.filter(id.eq(job_id)))
+        .set((
+            status.eq(job_status.as_str()),
+            finished_at.eq(Some(Utc::now().to_rfc3339())),
+            result.eq(result_param),
+            error_message.eq(error_message_param),
+        ))
+        .get_result::<Job>(conn)?;
+
+    Ok(job)
+}
+
+#[cfg(feature = "postgres")]
+pub fn fail_unfinished_jobs(
+    conn: &mut PgConnection,
+    error_message_param: &str,
+) -> Result<usize> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let failed = diesel::update(jobs.filter(status.eq_any([JobStatus::Queued.as_str(), JobStatus::Running.as_str()])))
+        .set((
+            status.eq(JobStatus::Failed.as_str()),
+            finished_at.eq(Some(Utc::now().to_rfc3339())),
+            error_message.eq(Some(error_message_param)),
+        ))
+        .execute(conn)?;
+
+    Ok(failed)
+}
+
+#[cfg(feature = "postgres")]
+pub fn delete_jobs_finished_before(
+    conn: &mut PgConnection,
+    cutoff: &str,
+) -> Result<usize> {
+    use crate::db::schema::jobs::dsl::*;
+
+    let deleted = diesel::delete(jobs.filter(finished_at.lt(cutoff)))
+        .execute(conn)?;
+
+    Ok(deleted)
+}
+
+#[cfg(feature = "postgres")]
+pub fn ping_database(conn: &mut PgConnection) -> Result<()> {
+    diesel::sql_query("SELECT 1").execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub fn get_database_size(conn: &mut PgConnection) -> Result<i64> {
+    let size = diesel::sql_query("SELECT pg_database_size(current_database()) AS bytes")
+        .get_result::<DatabaseSize>(conn)?;
+
+    Ok(size.bytes)
+}
@@ -1,5 +1,103 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    account_sender_filters (id) {
+        id -> Nullable<Text>,
+        imap_account_id -> Text,
+        kind -> Text,
+        pattern -> Text,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    app_settings (key) {
+        key -> Text,
+        value -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    app_versions (version) {
+        version -> Text,
+        git_hash -> Nullable<Text>,
+        first_run_at -> Text,
+        last_run_at -> Text,
+    }
+}
+
+diesel::table! {
+    attachments (id) {
+        id -> Nullable<Text>,
+        feed_item_id -> Text,
+        position -> Integer,
+        filename -> Nullable<Text>,
+        content_type -> Text,
+        size_bytes -> BigInt,
+        content -> Binary,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    chat_integrations (id) {
+        id -> Nullable<Text>,
+        feed_id -> Text,
+        platform -> Text,
+        webhook_url -> Nullable<Text>,
+        matrix_homeserver -> Nullable<Text>,
+        matrix_room_id -> Nullable<Text>,
+        matrix_access_token -> Nullable<Text>,
+        message_template -> Nullable<Text>,
+        rate_limit_per_minute -> Nullable<Integer>,
+        is_active -> Bool,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    deliveries (id) {
+        id -> Nullable<Text>,
+        feed_id -> Text,
+        item_id -> Nullable<Text>,
+        kind -> Text,
+        queue -> Text,
+        method -> Text,
+        url -> Text,
+        headers -> Text,
+        body -> Text,
+        status -> Text,
+        attempts -> Integer,
+        next_attempt_at -> Text,
+        last_error -> Nullable<Text>,
+        delivered_at -> Nullable<Text>,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    deferred_actions (id) {
+        id -> Nullable<Text>,
+        email_rule_id -> Text,
+        imap_account_id -> Text,
+        processing_run_id -> Text,
+        processing_intent_id -> Text,
+        feed_item_id -> Text,
+        folder -> Text,
+        uid -> BigInt,
+        email_message_id -> Nullable<Text>,
+        action -> Text,
+        target_folder -> Nullable<Text>,
+        due_at -> Text,
+        status -> Text,
+        last_error -> Nullable<Text>,
+        created_at -> Text,
+    }
+}
+
 diesel::table! {
     email_rules (id) {
         id -> Nullable<Text>,
@@ -15,6 +113,21 @@ diesel::table! {
         updated_at -> Text,
         post_process_action -> Text,
         move_to_folder -> Nullable<Text>,
+        observe_only -> Bool,
+        importance -> Nullable<Text>,
+        processing_order -> Text,
+        last_seen_uid -> Nullable<BigInt>,
+        uid_validity -> Nullable<BigInt>,
+        post_process_delay_hours -> Nullable<Integer>,
+        match_expression -> Nullable<Text>,
+        fetch_limit -> Nullable<Integer>,
+        include_seen -> Bool,
+        include_subfolders -> Bool,
+        strip_footers -> Bool,
+        strip_quoted_replies -> Bool,
+        strip_tracking_pixels -> Bool,
+        unwrap_tracking_links -> Bool,
+        post_process_actions -> Nullable<Text>,
     }
 }
 
@@ -35,6 +148,36 @@ diesel::table! {
         is_read -> Nullable<Bool>,
         starred -> Nullable<Bool>,
         body_size -> Nullable<Integer>,
+        processing_run_id -> Nullable<Text>,
+        content_hash -> Nullable<Text>,
+        canonical_item_id -> Nullable<Text>,
+        language -> Nullable<Text>,
+        pinned -> Bool,
+        importance -> Nullable<Text>,
+        category -> Nullable<Text>,
+        chain_previous -> Nullable<Text>,
+        chain_hash -> Nullable<Text>,
+        email_body_html -> Nullable<Text>,
+        body_ref -> Nullable<Text>,
+        rating -> Nullable<Text>,
+        origin_feed_id -> Nullable<Text>,
+        unsubscribe_url -> Nullable<Text>,
+        unsubscribe_mailto -> Nullable<Text>,
+        unsubscribe_one_click -> Bool,
+        in_reply_to -> Nullable<Text>,
+        email_references -> Nullable<Text>,
+        thread_id -> Nullable<Text>,
+        digest_message_ids -> Nullable<Text>,
+        guid -> Nullable<Text>,
+        sync_id -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    feed_redirects (old_feed_id) {
+        old_feed_id -> Text,
+        feed_id -> Text,
+        created_at -> Text,
     }
 }
 
@@ -52,6 +195,29 @@ diesel::table! {
         max_items -> Nullable<Integer>,
         max_age_days -> Nullable<Integer>,
         min_items -> Nullable<Integer>,
+        summary_length -> Nullable<Integer>,
+        locale -> Nullable<Text>,
+        timezone -> Nullable<Text>,
+        title_template -> Nullable<Text>,
+        webhook_url -> Nullable<Text>,
+        webhook_method -> Nullable<Text>,
+        webhook_body -> Nullable<Text>,
+        public_access -> Nullable<Bool>,
+        max_pinned -> Nullable<Integer>,
+        auto_titles -> Nullable<Bool>,
+        page_css -> Nullable<Text>,
+        page_header_html -> Nullable<Text>,
+        page_footer_html -> Nullable<Text>,
+        page_logo_url -> Nullable<Text>,
+        append_only -> Bool,
+        chain_head -> Nullable<Text>,
+        digest_mode -> Nullable<Text>,
+        description_template -> Nullable<Text>,
+        item_limit -> Nullable<Integer>,
+        full_content -> Nullable<Bool>,
+        auth_username -> Nullable<Text>,
+        auth_password_hash -> Nullable<Text>,
+        dedup_scope -> Nullable<Text>,
     }
 }
 
@@ -68,16 +234,203 @@ diesel::table! {
         updated_at -> Text,
         default_post_process_action -> Text,
         default_move_to_folder -> Nullable<Text>,
+        max_bytes_per_second -> Nullable<Integer>,
+        fingerprint -> Nullable<Text>,
+        tls_pin -> Nullable<Text>,
+        quota_group_id -> Nullable<Text>,
+        max_feeds -> Nullable<Integer>,
+        max_items -> Nullable<Integer>,
+        max_processing_minutes_per_day -> Nullable<Integer>,
+        tls_server_name -> Nullable<Text>,
+        security -> Text,
+        max_connections_per_hour -> Nullable<Integer>,
+        min_fetch_delay_seconds -> Nullable<Integer>,
+        max_messages_per_fetch -> Nullable<Integer>,
+        server_capabilities -> Nullable<Text>,
+        fetch_strategy -> Nullable<Text>,
+        protocol -> Text,
+    }
+}
+
+diesel::table! {
+    jobs (id) {
+        id -> Nullable<Text>,
+        kind -> Text,
+        target_id -> Nullable<Text>,
+        status -> Text,
+        processed -> Integer,
+        total -> Nullable<Integer>,
+        message -> Nullable<Text>,
+        error_message -> Nullable<Text>,
+        result -> Nullable<Text>,
+        created_at -> Text,
+        started_at -> Nullable<Text>,
+        finished_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    processing_intents (id) {
+        id -> Nullable<Text>,
+        processing_run_id -> Text,
+        feed_id -> Text,
+        folder -> Text,
+        uid -> BigInt,
+        email_message_id -> Nullable<Text>,
+        action -> Text,
+        target_folder -> Nullable<Text>,
+        item_title -> Text,
+        email_snapshot -> Nullable<Text>,
+        feed_item_id -> Nullable<Text>,
+        status -> Text,
+        created_at -> Text,
+        resolved_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    processing_run_actions (id) {
+        id -> Nullable<Text>,
+        processing_run_id -> Text,
+        feed_item_id -> Nullable<Text>,
+        folder -> Text,
+        uid -> BigInt,
+        email_message_id -> Nullable<Text>,
+        action -> Text,
+        target_folder -> Nullable<Text>,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    processing_runs (id) {
+        id -> Nullable<Text>,
+        imap_account_id -> Text,
+        status -> Text,
+        started_at -> Text,
+        finished_at -> Nullable<Text>,
+        emails_processed -> Integer,
+        items_created -> Integer,
+        error_message -> Nullable<Text>,
+        bytes_received -> BigInt,
+        bytes_sent -> BigInt,
+        catch_up_from -> Nullable<Text>,
+        catch_up_to -> Nullable<Text>,
+        catch_up_truncated -> Bool,
+    }
+}
+
+diesel::table! {
+    quota_groups (id) {
+        id -> Nullable<Text>,
+        name -> Text,
+        max_feeds -> Nullable<Integer>,
+        max_items -> Nullable<Integer>,
+        max_processing_minutes_per_day -> Nullable<Integer>,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    retention_policies (id) {
+        id -> Nullable<Text>,
+        target -> Text,
+        max_rows -> Nullable<Integer>,
+        max_age_days -> Nullable<Integer>,
+        rows_purged -> BigInt,
+        last_purged_at -> Nullable<Text>,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    rule_costs (id) {
+        id -> Nullable<Text>,
+        processing_run_id -> Text,
+        email_rule_id -> Text,
+        folder -> Text,
+        emails_fetched -> Integer,
+        evaluations -> Integer,
+        matches -> Integer,
+        fetch_ms -> BigInt,
+        match_us -> BigInt,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    rule_feeds (id) {
+        id -> Nullable<Text>,
+        email_rule_id -> Text,
+        feed_id -> Text,
+        priority -> Integer,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    rule_matches (id) {
+        id -> Nullable<Text>,
+        email_rule_id -> Text,
+        email_message_id -> Text,
+        email_subject -> Text,
+        email_from -> Text,
+        email_date -> Text,
+        matched_at -> Text,
+    }
+}
+
+diesel::table! {
+    sender_aliases (id) {
+        id -> Nullable<Text>,
+        name -> Text,
+        addresses -> Text,
+        created_at -> Text,
+        updated_at -> Text,
     }
 }
 
+diesel::joinable!(account_sender_filters -> imap_accounts (imap_account_id));
+diesel::joinable!(attachments -> feed_items (feed_item_id));
+diesel::joinable!(chat_integrations -> feeds (feed_id));
+diesel::joinable!(deferred_actions -> email_rules (email_rule_id));
+diesel::joinable!(deferred_actions -> processing_runs (processing_run_id));
+diesel::joinable!(deliveries -> feeds (feed_id));
 diesel::joinable!(email_rules -> imap_accounts (imap_account_id));
 diesel::joinable!(feed_items -> feeds (feed_id));
+diesel::joinable!(feed_redirects -> feeds (feed_id));
 diesel::joinable!(feeds -> email_rules (email_rule_id));
+diesel::joinable!(processing_intents -> processing_runs (processing_run_id));
+diesel::joinable!(processing_run_actions -> processing_runs (processing_run_id));
+diesel::joinable!(processing_runs -> imap_accounts (imap_account_id));
+diesel::joinable!(rule_costs -> email_rules (email_rule_id));
+diesel::joinable!(rule_costs -> processing_runs (processing_run_id));
+diesel::joinable!(rule_feeds -> feeds (feed_id));
+diesel::joinable!(rule_matches -> email_rules (email_rule_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    account_sender_filters,
+    app_settings,
+    app_versions,
+    attachments,
+    chat_integrations,
+    deferred_actions,
+    deliveries,
     email_rules,
     feed_items,
+    feed_redirects,
     feeds,
     imap_accounts,
+    jobs,
+    processing_intents,
+    processing_run_actions,
+    processing_runs,
+    quota_groups,
+    retention_policies,
+    rule_costs,
+    rule_feeds,
+    rule_matches,
+    sender_aliases,
 );
@@ -0,0 +1,99 @@
+//! Email attachments served as enclosures
+//!
+//! Files attached to an email, such as the PDF or images of a newsletter,
+//! are stored in the database with the item made from it, numbered from 1 in
+//! the order they appear. Each is kept up to `FEED_ATTACHMENT_MAX_BYTES`
+//! (10 MiB by default); larger ones are left out, and `0` stores none. They
+//! are served at `/feeds/{feed_id}/items/{item_id}/attachments/{n}` and
+//! listed as enclosures: RSS allows one per item, so RSS items carry the
+//! first, while Atom entries link to every one. Items linked to a copy in
+//! another feed share that copy's attachments.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use tracing::{debug, info};
+
+use crate::db::{connection::DatabasePool, models::{FeedItem, NewAttachment}, operations_generic::AttachmentOpsGeneric};
+use crate::imap::mime::MailAttachment;
+
+/// Largest attachment stored when `FEED_ATTACHMENT_MAX_BYTES` is not set
+pub const DEFAULT_MAX_BYTES: usize = 10 * 1024 * 1024;
+
+/// A link to an item's attachment, for a feed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enclosure {
+    pub url: String,
+    pub content_type: String,
+    pub length: i64,
+}
+
+/// Largest attachment stored, in bytes; zero when attachments are not stored
+pub fn max_bytes() -> usize {
+    std::env::var("FEED_ATTACHMENT_MAX_BYTES")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+/// Path attachment `position` of an item is served at
+pub fn path(feed_id: &str, item_id: &str, position: i32) -> String {
+    format!("/feeds/{}/items/{}/attachments/{}", feed_id, item_id, position)
+}
+
+/// Store the attachments of the email `item_id` was made from, leaving out
+/// those over `max_bytes`; returns how many were stored
+pub fn store(pool: &DatabasePool, item_id: &str, attachments: &[MailAttachment], max_bytes: usize) -> Result<usize> {
+    let kept = attachments.iter().filter(|attachment| {
+        let fits = !attachment.content.is_empty() && attachment.content.len() <= max_bytes;
+        if !fits {
+            info!("Not storing attachment {:?} of {} bytes for item {}",
+                  attachment.filename, attachment.content.len(), item_id);
+        }
+        fits
+    });
+    let new_attachments: Vec<NewAttachment> = kept.zip(1..)
+        .map(|(attachment, position)| NewAttachment::new(
+            item_id.to_string(),
+            position,
+            attachment.filename.clone(),
+            attachment.content_type.clone(),
+            attachment.content.clone(),
+        ))
+        .collect();
+    if new_attachments.is_empty() {
+        return Ok(0);
+    }
+    debug!("Storing {} attachments for item {}", new_attachments.len(), item_id);
+    AttachmentOpsGeneric::create_all(pool, &new_attachments)
+}
+
+/// The item whose attachments `item` shows: its canonical copy when linked to one
+pub fn source_item_id(item: &FeedItem) -> Option<&str> {
+    item.canonical_item_id.as_deref().or(item.id.as_deref())
+}
+
+/// Enclosures of each item, by item ID, with URLs under `base_url`
+pub fn enclosures(pool: &DatabasePool, items: &[FeedItem], base_url: &str) -> Result<HashMap<String, Vec<Enclosure>>> {
+    let source_ids: Vec<String> = items.iter().filter_map(source_item_id).map(str::to_string).collect();
+    if source_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut by_source: HashMap<String, Vec<_>> = HashMap::new();
+    for info in AttachmentOpsGeneric::list_for_items(pool, &source_ids)? {
+        by_source.entry(info.feed_item_id.clone()).or_default().push(info);
+    }
+
+    let mut enclosures = HashMap::new();
+    for item in items {
+        let (Some(item_id), Some(infos)) = (&item.id, source_item_id(item).and_then(|id| by_source.get(id))) else {
+            continue;
+        };
+        enclosures.insert(item_id.clone(), infos.iter().map(|info| Enclosure {
+            url: format!("{}{}", base_url, path(&item.feed_id, item_id, info.position)),
+            content_type: info.content_type.clone(),
+            length: info.size_bytes,
+        }).collect());
+    }
+    Ok(enclosures)
+}
@@ -0,0 +1,103 @@
+//! Password-protected feeds via HTTP Basic auth
+//!
+//! Many feed readers can send a username and password with a feed request
+//! but nothing more elaborate. A feed with `auth_username` set only serves
+//! `/feeds/{id}/rss` and `/feeds/{id}/atom` to requests carrying those
+//! credentials, and answers others with a `WWW-Authenticate` challenge. The
+//! password is stored as an Argon2 hash; the API never returns it.
+
+use anyhow::{anyhow, Result};
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::Engine;
+
+use crate::db::models::Feed;
+
+/// Longest password accepted, so hashing stays cheap
+pub const MAX_PASSWORD_LENGTH: usize = 256;
+
+/// Whether the feed asks for credentials
+pub fn is_protected(feed: &Feed) -> bool {
+    feed.auth_username.is_some()
+}
+
+/// Check a username and password before they are stored
+pub fn validate(username: &str, password: Option<&str>) -> Result<()> {
+    if username.trim().is_empty() || username.contains(':') || username.chars().any(char::is_control) {
+        return Err(anyhow!("auth_username must not be empty or contain ':' or control characters"));
+    }
+    match password {
+        Some(password) if password.is_empty() || password.len() > MAX_PASSWORD_LENGTH => {
+            Err(anyhow!("auth_password must be 1 to {} bytes long", MAX_PASSWORD_LENGTH))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Argon2 hash of `password` in PHC string format
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow!("Failed to hash the feed password: {}", e))
+}
+
+/// Whether an `Authorization` header value carries the feed's credentials;
+/// unprotected feeds accept any request
+pub fn authorized(feed: &Feed, authorization: Option<&str>) -> bool {
+    let (Some(username), Some(hash)) = (&feed.auth_username, &feed.auth_password_hash) else {
+        return !is_protected(feed);
+    };
+    let Some((given_username, given_password)) = authorization.and_then(credentials) else {
+        return false;
+    };
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    // Verify the password even for a wrong username, so both take as long
+    let password_matches = Argon2::default().verify_password(given_password.as_bytes(), &hash).is_ok();
+    password_matches && given_username == *username
+}
+
+/// `WWW-Authenticate` value challenging a reader for the feed's credentials
+pub fn challenge(feed: &Feed) -> String {
+    let realm: String = feed.title.chars().filter(|c| *c != '"' && *c != '\\' && !c.is_control()).collect();
+    format!("Basic realm=\"{}\", charset=\"UTF-8\"", realm)
+}
+
+/// Username and password of a `Basic` authorization header value
+fn credentials(authorization: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = authorization.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let (username, password) = String::from_utf8(decoded).ok()?.split_once(':').map(|(u, p)| (u.to_string(), p.to_string()))?;
+    Some((username, password))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_from_header() {
+        // "reader:s3cret:with colon"
+        let header = "Basic cmVhZGVyOnMzY3JldDp3aXRoIGNvbG9u";
+        assert_eq!(credentials(header), Some(("reader".to_string(), "s3cret:with colon".to_string())));
+        assert_eq!(credentials("basic cmVhZGVyOnMzY3JldDp3aXRoIGNvbG9u").map(|(u, _)| u), Some("reader".to_string()));
+        assert_eq!(credentials("Bearer cmVhZGVyOnMzY3JldA=="), None);
+        assert_eq!(credentials("Basic not-base64!"), None);
+    }
+
+    #[test]
+    fn test_validate_credentials() {
+        assert!(validate("reader", Some("s3cret")).is_ok());
+        assert!(validate("reader", None).is_ok());
+        assert!(validate("", Some("s3cret")).is_err());
+        assert!(validate("read:er", Some("s3cret")).is_err());
+        assert!(validate("reader", Some("")).is_err());
+        assert!(validate("reader", Some(&"x".repeat(MAX_PASSWORD_LENGTH + 1))).is_err());
+    }
+}
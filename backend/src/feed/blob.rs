@@ -0,0 +1,186 @@
+//! Blob storage for item bodies
+//!
+//! `BODY_STORE` picks where item bodies moved out of the database are kept:
+//! `database` (the default) leaves them in `feed_items`, `filesystem` writes
+//! them as files under `BODY_STORE_PATH`, and `s3` puts them in a bucket of
+//! Amazon S3 or a compatible service such as MinIO (see [`S3Store`]). Blobs
+//! are addressed by relative keys like `bodies/<item id>.json`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+pub use super::s3::S3Store;
+
+/// Where blobs are kept
+#[derive(Debug, Clone)]
+pub enum BlobStore {
+    Filesystem(FilesystemStore),
+    S3(S3Store),
+}
+
+impl BlobStore {
+    /// The store configured by `BODY_STORE`; none when bodies stay in the database
+    pub fn from_env() -> Result<Option<Self>> {
+        let kind = std::env::var("BODY_STORE").unwrap_or_default().trim().to_lowercase();
+        match kind.as_str() {
+            "" | "database" => Ok(None),
+            "filesystem" => {
+                let root = std::env::var("BODY_STORE_PATH")
+                    .ok()
+                    .filter(|path| !path.trim().is_empty())
+                    .context("BODY_STORE=filesystem requires BODY_STORE_PATH")?;
+                Ok(Some(Self::Filesystem(FilesystemStore::new(root))))
+            }
+            "s3" => Ok(Some(Self::S3(S3Store::from_env()?))),
+            other => bail!("Unknown BODY_STORE '{}', expected database, filesystem or s3", other),
+        }
+    }
+
+    /// Short name of the backend, for logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Filesystem(_) => "filesystem",
+            Self::S3(_) => "s3",
+        }
+    }
+
+    /// Store `content` under `key`, replacing what was there
+    pub async fn put(&self, key: &str, content: Vec<u8>) -> Result<()> {
+        check_key(key)?;
+        match self {
+            Self::Filesystem(store) => store.put(key, content).await,
+            Self::S3(store) => store.put(key, content).await,
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        check_key(key)?;
+        match self {
+            Self::Filesystem(store) => store.get(key).await,
+            Self::S3(store) => store.get(key).await,
+        }
+    }
+
+    /// Remove the blob under `key`; removing a missing blob is not an error
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        check_key(key)?;
+        match self {
+            Self::Filesystem(store) => store.delete(key).await,
+            Self::S3(store) => store.delete(key).await,
+        }
+    }
+
+    /// Keys of every blob under `prefix`, which ends with a `/`
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        check_key(prefix)?;
+        match self {
+            Self::Filesystem(store) => store.list(prefix).await,
+            Self::S3(store) => store.list(prefix).await,
+        }
+    }
+}
+
+/// Keys are relative paths that cannot leave the store's root
+fn check_key(key: &str) -> Result<()> {
+    let escapes = key.split('/').any(|segment| segment == ".." || segment == ".");
+    if key.is_empty() || key.starts_with('/') || key.contains('\\') || escapes {
+        bail!("Invalid blob key '{}'", key);
+    }
+    Ok(())
+}
+
+/// Blobs kept as files under a directory
+#[derive(Debug, Clone)]
+pub struct FilesystemStore {
+    root: PathBuf,
+}
+
+impl FilesystemStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    async fn put(&self, key: &str, content: Vec<u8>) -> Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        // Written aside and renamed, so a crash never leaves half a blob
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, content).await
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        tokio::fs::rename(&partial, &path).await
+            .with_context(|| format!("Failed to move blob into {}", path.display()))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let path = self.root.join(key);
+        tokio::fs::read(&path).await.with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.root.join(key);
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let dir = self.root.join(prefix);
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to list {}", dir.display())),
+        };
+        let mut keys = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type().await?.is_file() && !name.ends_with(".partial") {
+                keys.push(format!("{}{}", prefix, name));
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_stay_inside_the_store() {
+        assert!(check_key("bodies/abc.json").is_ok());
+        assert!(check_key("bodies/").is_ok());
+        for key in ["", "/etc/passwd", "bodies/../secret", "./bodies", "bodies\\x"] {
+            assert!(check_key(key).is_err(), "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filesystem_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::Filesystem(FilesystemStore::new(dir.path()));
+
+        assert!(store.list("bodies/").await.unwrap().is_empty());
+        store.put("bodies/b.json", b"second".to_vec()).await.unwrap();
+        store.put("bodies/a.json", b"first".to_vec()).await.unwrap();
+        store.put("bodies/a.json", b"replaced".to_vec()).await.unwrap();
+        assert_eq!(store.get("bodies/a.json").await.unwrap(), b"replaced");
+        assert_eq!(store.list("bodies/").await.unwrap(), ["bodies/a.json", "bodies/b.json"]);
+
+        store.delete("bodies/a.json").await.unwrap();
+        store.delete("bodies/a.json").await.unwrap();
+        assert!(store.get("bodies/a.json").await.is_err());
+        assert_eq!(store.list("bodies/").await.unwrap(), ["bodies/b.json"]);
+    }
+}
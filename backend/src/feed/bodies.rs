@@ -0,0 +1,157 @@
+//! Item bodies kept outside the database
+//!
+//! HTML newsletters make up most of the database, and so of its backups.
+//! With a blob store configured (see [`blob`](super::blob)), each new item
+//! whose body is at least `BODY_STORE_MIN_BYTES` long (4 KiB by default, text
+//! and HTML together) has it written to the store as one JSON blob; its row
+//! keeps the summary, size and hashes, and the blob's key in `body_ref`.
+//! Feeds, item pages and the API read bodies back from the store only when
+//! they show them.
+//!
+//! Items of append-only feeds keep their bodies in the database, since their
+//! chain hashes cover them; a feed made append-only brings its items' bodies
+//! back first. Bodies stored before a store was configured are moved by the
+//! offload maintenance task, which, like the daily cleanup, also removes the
+//! blobs of items that are gone.
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+
+use super::blob::BlobStore;
+use super::dedup;
+use crate::db::{connection::DatabasePool, models::FeedItem, operations_generic::FeedItemOpsGeneric};
+
+/// Directory of the store holding item bodies
+pub const KEY_PREFIX: &str = "bodies/";
+
+/// Smallest body moved to the store when `BODY_STORE_MIN_BYTES` is not set
+pub const DEFAULT_MIN_BYTES: usize = 4096;
+
+/// What a body blob holds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredBody {
+    text: Option<String>,
+    html: Option<String>,
+}
+
+/// Smallest body, in bytes, moved out of the database
+pub fn min_bytes() -> usize {
+    std::env::var("BODY_STORE_MIN_BYTES")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_MIN_BYTES)
+}
+
+/// The store configured by `BODY_STORE`; bodies stay in the database when
+/// none is, or when its settings are invalid
+pub fn store_from_env() -> Option<BlobStore> {
+    BlobStore::from_env().unwrap_or_else(|e| {
+        error!("Keeping item bodies in the database: {}", e);
+        None
+    })
+}
+
+/// Key of the blob holding an item's body
+pub fn key(item_id: &str) -> String {
+    format!("{}{}.json", KEY_PREFIX, item_id)
+}
+
+fn body_len(item: &FeedItem) -> usize {
+    item.email_body.as_deref().map_or(0, str::len) + item.email_body_html.as_deref().map_or(0, str::len)
+}
+
+/// Move an item's body to the store if it holds its own body of at least
+/// `min_bytes`; returns whether it was moved
+pub async fn offload(pool: &DatabasePool, store: &BlobStore, item: &FeedItem, min_bytes: usize) -> Result<bool> {
+    let item_id = item.id.as_deref().ok_or_else(|| anyhow::anyhow!("Feed item has no ID"))?;
+    let size = body_len(item);
+    if item.body_ref.is_some() || item.canonical_item_id.is_some() || size == 0 || size < min_bytes {
+        return Ok(false);
+    }
+
+    let key = key(item_id);
+    let blob = serde_json::to_vec(&StoredBody { text: item.email_body.clone(), html: item.email_body_html.clone() })?;
+    store.put(&key, blob).await?;
+    if let Err(e) = FeedItemOpsGeneric::set_body_ref(pool, item_id, &key) {
+        if let Err(e) = store.delete(&key).await {
+            debug!("Could not remove unused blob {}: {}", key, e);
+        }
+        return Err(e);
+    }
+    debug!("Moved {} byte body of feed item {} to {} store", size, item_id, store.kind());
+    Ok(true)
+}
+
+async fn fetch(store: Option<&BlobStore>, body_ref: &str) -> Result<StoredBody> {
+    let store = store.context("No blob store is configured")?;
+    let blob = store.get(body_ref).await?;
+    serde_json::from_slice(&blob).with_context(|| format!("Invalid body blob {}", body_ref))
+}
+
+/// Fill in the bodies of items kept in the store. Items whose blob cannot
+/// be read are left without a body
+pub async fn load(store: Option<&BlobStore>, items: &mut [FeedItem]) {
+    let stored = items.iter_mut()
+        .filter(|item| item.email_body.is_none() && item.email_body_html.is_none());
+    for item in stored {
+        let Some(body_ref) = item.body_ref.as_deref() else {
+            continue;
+        };
+        match fetch(store, body_ref).await {
+            Ok(body) => {
+                item.email_body = body.text;
+                item.email_body_html = body.html;
+            }
+            Err(e) => warn!("Body of feed item {:?} unavailable: {}", item.id, e),
+        }
+    }
+}
+
+/// Bring the bodies of a feed's items back into the database, before the
+/// feed becomes append-only; linked items get their own copy. Returns the
+/// items restored
+pub async fn restore_feed(pool: &DatabasePool, store: Option<&BlobStore>, feed_id: &str) -> Result<usize> {
+    let mut items = FeedItemOpsGeneric::get_by_feed_id(pool, feed_id, None)?;
+    dedup::resolve_bodies(pool, &mut items);
+
+    let mut restored = 0;
+    for item in items.iter().filter(|item| item.email_body.is_none() && item.email_body_html.is_none()) {
+        let (Some(item_id), Some(body_ref)) = (item.id.as_deref(), item.body_ref.as_deref()) else {
+            continue;
+        };
+        let body = fetch(store, body_ref).await
+            .with_context(|| format!("Failed to restore body of feed item {}", item_id))?;
+        if item.canonical_item_id.is_some() {
+            FeedItemOpsGeneric::promote_to_canonical(pool, item_id, body.text, body.html)?;
+        } else {
+            FeedItemOpsGeneric::restore_body(pool, item_id, body.text, body.html)?;
+        }
+        restored += 1;
+    }
+    if restored > 0 {
+        info!("Restored {} item bodies of feed {} into the database", restored, feed_id);
+    }
+    Ok(restored)
+}
+
+/// Remove body blobs no item refers to any longer; returns how many were removed
+pub async fn prune(pool: &DatabasePool, store: &BlobStore) -> Result<usize> {
+    // Listed before the references are read, so a blob stored meanwhile is kept
+    let keys = store.list(KEY_PREFIX).await?;
+    let referenced: HashSet<String> = FeedItemOpsGeneric::get_body_refs(pool)?.into_iter().collect();
+
+    let mut removed = 0;
+    for key in keys.iter().filter(|key| !referenced.contains(*key)) {
+        match store.delete(key).await {
+            Ok(()) => removed += 1,
+            Err(e) => warn!("Failed to remove unused body blob {}: {}", key, e),
+        }
+    }
+    if removed > 0 {
+        info!("Removed {} unused body blobs", removed);
+    }
+    Ok(removed)
+}
@@ -0,0 +1,71 @@
+//! Per-feed presentation of hosted item pages
+//!
+//! A feed may brand the pages at `/feeds/{id}/items/{item_id}` with custom
+//! CSS, a logo and HTML snippets shown above and below the item. The pages
+//! are served with `Content-Security-Policy: sandbox`, so scripts in the
+//! snippets do not run, just like scripts in the email bodies.
+
+use anyhow::Result;
+
+use crate::db::models::Feed;
+use crate::feed::{overflow, webhook};
+
+/// Largest custom CSS accepted, in bytes
+pub const MAX_CSS_BYTES: usize = 64 * 1024;
+
+/// Largest header or footer snippet accepted, in bytes
+pub const MAX_SNIPPET_BYTES: usize = 16 * 1024;
+
+/// Check the branding settings of a feed
+pub fn validate(css: Option<&str>, header_html: Option<&str>, footer_html: Option<&str>, logo_url: Option<&str>) -> Result<()> {
+    if let Some(css) = css {
+        if css.len() > MAX_CSS_BYTES {
+            anyhow::bail!("page_css must be at most {} KB", MAX_CSS_BYTES / 1024);
+        }
+        // Keeps the CSS from closing its <style> element
+        if css.contains('<') {
+            anyhow::bail!("page_css must not contain '<'");
+        }
+    }
+    for (field, snippet) in [("page_header_html", header_html), ("page_footer_html", footer_html)] {
+        if snippet.is_some_and(|snippet| snippet.len() > MAX_SNIPPET_BYTES) {
+            anyhow::bail!("{} must be at most {} KB", field, MAX_SNIPPET_BYTES / 1024);
+        }
+    }
+    if let Some(url) = logo_url {
+        webhook::check_url(url, "page logo URL")?;
+    }
+    Ok(())
+}
+
+/// `<style>` element with the feed's CSS, for the page head
+pub fn style(feed: &Feed) -> String {
+    match non_empty(&feed.page_css) {
+        Some(css) => format!("<style>{}</style>", css),
+        None => String::new(),
+    }
+}
+
+/// Logo and header snippet, for the top of the page body
+pub fn header(feed: &Feed) -> String {
+    let logo = non_empty(&feed.page_logo_url).map(|url| {
+        format!("<img class=\"feed-logo\" src=\"{}\" alt=\"{}\">", overflow::escape_html(url), overflow::escape_html(&feed.title))
+    });
+    let snippet = non_empty(&feed.page_header_html);
+    if logo.is_none() && snippet.is_none() {
+        return String::new();
+    }
+    format!("<header>{}{}</header>", logo.unwrap_or_default(), snippet.unwrap_or_default())
+}
+
+/// Footer snippet, for the bottom of the page body
+pub fn footer(feed: &Feed) -> String {
+    match non_empty(&feed.page_footer_html) {
+        Some(snippet) => format!("<footer>{}</footer>", snippet),
+        None => String::new(),
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|value| !value.trim().is_empty())
+}
@@ -0,0 +1,288 @@
+//! Append-only feeds
+//!
+//! Items of an append-only feed cannot be edited or deleted: retention
+//! cleanup, storage safeguards and run rollbacks leave them alone, and the API
+//! refuses to delete the feed, its rule or its account. Each item stores the
+//! hash of the item before it (`chain_previous`) and a SHA-256 hash of its own
+//! content together with that (`chain_hash`); the feed keeps the hash of its
+//! latest item (`chain_head`). Changing, removing or reordering any item breaks
+//! the chain, which `verify` detects by recomputing every hash and walking back
+//! from the head.
+//!
+//! Reader state (read, starred, pinned) is not part of the hash and can still
+//! be changed. Once on, append-only cannot be turned off; items the feed held
+//! before are chained in the order they were created.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::{DatabasePool, Transaction},
+    models::{Feed, FeedItem, NewFeedItem},
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric},
+};
+use crate::feed::dedup;
+
+/// Keeps two processing runs from appending to a chain at once, which would fork it
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// Editing or deleting refused because the feed is append-only
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppendOnly {
+    pub feed_title: String,
+}
+
+impl std::fmt::Display for AppendOnly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Feed '{}' is append-only; its items cannot be edited or deleted", self.feed_title)
+    }
+}
+
+impl std::error::Error for AppendOnly {}
+
+/// Refuse with `AppendOnly` when any of `feeds` is append-only
+pub fn ensure_removable<'a>(feeds: impl IntoIterator<Item = &'a Feed>) -> Result<(), AppendOnly> {
+    match feeds.into_iter().find(|feed| feed.append_only) {
+        Some(feed) => Err(AppendOnly { feed_title: feed.title.clone() }),
+        None => Ok(()),
+    }
+}
+
+/// The fields of an item its hash covers
+#[derive(Serialize)]
+struct ChainedContent<'a> {
+    id: &'a str,
+    feed_id: &'a str,
+    title: &'a str,
+    description: Option<&'a str>,
+    link: Option<&'a str>,
+    author: Option<&'a str>,
+    pub_date: &'a str,
+    email_message_id: Option<&'a str>,
+    email_subject: Option<&'a str>,
+    email_from: Option<&'a str>,
+    email_body: Option<&'a str>,
+    created_at: &'a str,
+    /// Left out when absent, so items stored before HTML bodies keep their hash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email_body_html: Option<&'a str>,
+}
+
+impl<'a> From<&'a NewFeedItem> for ChainedContent<'a> {
+    fn from(item: &'a NewFeedItem) -> Self {
+        Self {
+            id: &item.id,
+            feed_id: &item.feed_id,
+            title: &item.title,
+            description: item.description.as_deref(),
+            link: item.link.as_deref(),
+            author: item.author.as_deref(),
+            pub_date: &item.pub_date,
+            email_message_id: item.email_message_id.as_deref(),
+            email_subject: item.email_subject.as_deref(),
+            email_from: item.email_from.as_deref(),
+            email_body: item.email_body.as_deref(),
+            created_at: &item.created_at,
+            email_body_html: item.email_body_html.as_deref(),
+        }
+    }
+}
+
+impl<'a> From<&'a FeedItem> for ChainedContent<'a> {
+    fn from(item: &'a FeedItem) -> Self {
+        Self {
+            id: item.id.as_deref().unwrap_or_default(),
+            feed_id: &item.feed_id,
+            title: &item.title,
+            description: item.description.as_deref(),
+            link: item.link.as_deref(),
+            author: item.author.as_deref(),
+            pub_date: &item.pub_date,
+            email_message_id: item.email_message_id.as_deref(),
+            email_subject: item.email_subject.as_deref(),
+            email_from: item.email_from.as_deref(),
+            email_body: item.email_body.as_deref(),
+            created_at: &item.created_at,
+            email_body_html: item.email_body_html.as_deref(),
+        }
+    }
+}
+
+/// Hex SHA-256 of the previous item's hash and an item's content
+fn item_hash(previous: Option<&str>, content: &ChainedContent) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous.unwrap_or_default().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(serde_json::to_vec(content).expect("item content serializes to JSON"));
+    format!("{:x}", hasher.finalize())
+}
+
+/// Store a new item at the end of its append-only feed's chain, within `tx`
+/// so the item and the feed's new head are written together
+pub fn append(tx: &mut Transaction, new_item: &mut NewFeedItem) -> Result<FeedItem> {
+    let _guard = APPEND_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let feed = tx.get_feed(&new_item.feed_id)?;
+
+    let hash = item_hash(feed.chain_head.as_deref(), &ChainedContent::from(&*new_item));
+    new_item.chain_previous = feed.chain_head;
+    new_item.chain_hash = Some(hash.clone());
+    let item = tx.create_feed_item(new_item)?;
+    tx.set_chain_head(&new_item.feed_id, &hash)?;
+    Ok(item)
+}
+
+/// Chain the items a feed held before it became append-only, oldest first;
+/// items linked to another feed's copy get their own copy of the body first,
+/// so later changes to that feed cannot alter them. Returns the items chained
+pub fn seal(pool: &DatabasePool, feed: &Feed) -> Result<usize> {
+    let feed_id = feed.id.as_deref().ok_or_else(|| anyhow::anyhow!("Feed has no ID"))?;
+    let _guard = APPEND_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut items: Vec<_> = FeedItemOpsGeneric::get_by_feed_id(pool, feed_id, None)?
+        .into_iter()
+        .filter(|item| item.chain_hash.is_none())
+        .collect();
+    items.sort_by(|a, b| (&a.created_at, &a.id).cmp(&(&b.created_at, &b.id)));
+    dedup::resolve_bodies(pool, &mut items);
+
+    let mut head = FeedOpsGeneric::get_by_id(pool, feed_id)?.chain_head;
+    for item in &items {
+        let item_id = item.id.as_deref().ok_or_else(|| anyhow::anyhow!("Feed item has no ID"))?;
+        if item.canonical_item_id.is_some() {
+            FeedItemOpsGeneric::promote_to_canonical(pool, item_id, item.email_body.clone(), item.email_body_html.clone())?;
+        }
+        let hash = item_hash(head.as_deref(), &ChainedContent::from(item));
+        FeedItemOpsGeneric::set_chain(pool, item_id, head.as_deref(), &hash)?;
+        FeedOpsGeneric::set_chain_head(pool, feed_id, &hash)?;
+        head = Some(hash);
+    }
+
+    if !items.is_empty() {
+        info!("Chained {} existing items of append-only feed '{}'", items.len(), feed.title);
+    }
+    Ok(items.len())
+}
+
+/// Result of checking a feed's chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChainVerification {
+    pub feed_id: String,
+    pub append_only: bool,
+    /// Items whose hash was recomputed
+    pub items_checked: usize,
+    /// Items without a hash; any in an append-only feed are a problem
+    pub unchained_items: usize,
+    /// Hash of the feed's latest item
+    pub head: Option<String>,
+    /// Whether every item matches its hash and the chain from the head reaches them all
+    pub valid: bool,
+    /// What is wrong with the chain; empty when it is intact
+    pub problems: Vec<String>,
+}
+
+/// Check that every item of a feed matches its hash and that the chain from
+/// the feed's head runs through all of them
+pub fn verify(pool: &DatabasePool, feed: &Feed) -> Result<ChainVerification> {
+    let feed_id = feed.id.as_deref().ok_or_else(|| anyhow::anyhow!("Feed has no ID"))?;
+    let items = FeedItemOpsGeneric::get_by_feed_id(pool, feed_id, None)?;
+    let mut problems = Vec::new();
+
+    let mut by_hash = HashMap::new();
+    let mut unchained_items = 0;
+    for item in &items {
+        let Some(hash) = item.chain_hash.as_deref() else {
+            unchained_items += 1;
+            continue;
+        };
+        if item_hash(item.chain_previous.as_deref(), &ChainedContent::from(item)) != hash {
+            problems.push(format!("Item '{}' ({}) does not match its hash", item.title, item.id.as_deref().unwrap_or_default()));
+        }
+        by_hash.insert(hash, item);
+    }
+    let items_checked = by_hash.len();
+    if feed.append_only && unchained_items > 0 {
+        problems.push(format!("{} items are not chained", unchained_items));
+    }
+
+    // Walk back from the head; every chained item should be passed exactly once
+    let mut reached = 0;
+    let mut next = feed.chain_head.as_deref();
+    while let Some(hash) = next {
+        let Some(item) = by_hash.get(hash) else {
+            problems.push(format!("Item with hash {} is missing from the chain", hash));
+            break;
+        };
+        reached += 1;
+        if reached > items_checked {
+            problems.push("The chain loops back on itself".to_string());
+            break;
+        }
+        next = item.chain_previous.as_deref();
+    }
+    if reached < items_checked {
+        problems.push(format!("{} chained items cannot be reached from the head", items_checked - reached));
+    }
+
+    if !problems.is_empty() {
+        warn!("Chain of feed '{}' failed verification: {}", feed.title, problems.join("; "));
+    }
+    Ok(ChainVerification {
+        feed_id: feed_id.to_string(),
+        append_only: feed.append_only,
+        items_checked,
+        unchained_items,
+        head: feed.chain_head.clone(),
+        valid: problems.is_empty(),
+        problems,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn new_item(title: &str) -> NewFeedItem {
+        NewFeedItem::new(
+            "feed-1".to_string(),
+            title.to_string(),
+            None,
+            None,
+            None,
+            Utc::now(),
+            Some("<notice@example.com>".to_string()),
+            Some(title.to_string()),
+            Some("office@example.com".to_string()),
+            Some("Official notice".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_hash_covers_content_and_previous_item() {
+        let item = new_item("Notice 1");
+        let hash = item_hash(None, &ChainedContent::from(&item));
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, item_hash(None, &ChainedContent::from(&item)));
+        assert_ne!(hash, item_hash(Some("0"), &ChainedContent::from(&item)));
+
+        let mut edited = item.clone();
+        edited.email_body = Some("Amended notice".to_string());
+        assert_ne!(hash, item_hash(None, &ChainedContent::from(&edited)));
+    }
+
+    #[test]
+    fn test_hash_ignores_reader_state() {
+        let item = new_item("Notice 1");
+        let mut read = item.clone();
+        read.is_read = Some(true);
+        read.starred = Some(true);
+        read.pinned = true;
+        assert_eq!(item_hash(None, &ChainedContent::from(&item)), item_hash(None, &ChainedContent::from(&read)));
+    }
+}
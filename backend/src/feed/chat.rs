@@ -0,0 +1,209 @@
+//! Chat integrations posting new feed items to Slack, Discord and Matrix
+//!
+//! A feed can have any number of integrations. Slack and Discord are reached
+//! through their incoming webhooks; Matrix messages are sent to a room through
+//! the client-server API with the posting account's access token. Messages are
+//! plain text rendered from a template over the feed webhook variables
+//! ([`webhook::VARIABLES`]).
+//!
+//! Every integration is rate limited: at most `rate_limit_per_minute` messages
+//! go out in any minute and further items are dropped, so importing a backlog
+//! does not flood the channel. Messages within the limit go through the
+//! [`delivery`] queue, which retries them in order.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use reqwest::Method;
+use serde_json::json;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{ChatIntegration, ChatPlatform, Feed, FeedItem, NewChatIntegration},
+    operations_generic::ChatIntegrationOpsGeneric,
+};
+use crate::feed::{delivery::{self, OutboundRequest}, template, webhook};
+
+/// Message used when an integration has no template
+pub const DEFAULT_TEMPLATE: &str = "New in {{feed.title}}: {{item.title}} {{item.url}}";
+
+/// Messages per minute when an integration sets no limit
+pub const DEFAULT_RATE_LIMIT: i32 = 10;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Check an integration's settings before saving them
+pub fn validate(integration: &NewChatIntegration) -> Result<()> {
+    let platform = parse_platform(&integration.platform)?;
+    match platform {
+        ChatPlatform::Slack | ChatPlatform::Discord => {
+            let url = integration.webhook_url.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("A {} integration needs a webhook_url", platform.as_str()))?;
+            webhook::check_url(url, "webhook URL")?;
+        }
+        ChatPlatform::Matrix => {
+            let homeserver = integration.matrix_homeserver.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("A matrix integration needs a matrix_homeserver"))?;
+            webhook::check_url(homeserver, "Matrix homeserver URL")?;
+            let room_id = integration.matrix_room_id.as_deref()
+                .ok_or_else(|| anyhow::anyhow!("A matrix integration needs a matrix_room_id"))?;
+            if !room_id.starts_with('!') || !room_id.contains(':') {
+                anyhow::bail!("matrix_room_id must be a room ID such as '!abc123:matrix.org', not an alias");
+            }
+            if integration.matrix_access_token.as_deref().unwrap_or_default().is_empty() {
+                anyhow::bail!("A matrix integration needs a matrix_access_token");
+            }
+        }
+    }
+    if let Some(message_template) = &integration.message_template {
+        template::validate_variables(message_template, webhook::VARIABLES)?;
+    }
+    if integration.rate_limit_per_minute.is_some_and(|limit| limit <= 0) {
+        anyhow::bail!("rate_limit_per_minute must be a positive number of messages");
+    }
+    Ok(())
+}
+
+/// Message text for an item, cut to the platform's length limit
+pub fn render_message(integration: &ChatIntegration, feed: &Feed, item: &FeedItem) -> Result<String> {
+    let message_template = integration.message_template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+    let message = webhook::render_text(message_template, feed, item)?;
+    let max_chars = max_message_chars(parse_platform(&integration.platform)?);
+    Ok(truncate(message.trim(), max_chars))
+}
+
+/// The request posting an item to the integration's channel. Matrix
+/// messages get a fresh transaction ID, which retries of the request reuse.
+pub fn request(integration: &ChatIntegration, feed: &Feed, item: &FeedItem) -> Result<OutboundRequest> {
+    let message = render_message(integration, feed, item)?;
+    let webhook_url = integration.webhook_url.as_deref().unwrap_or_default();
+
+    Ok(match parse_platform(&integration.platform)? {
+        ChatPlatform::Slack => OutboundRequest::json(Method::POST, webhook_url, json!({ "text": message }).to_string()),
+        ChatPlatform::Discord => OutboundRequest::json(Method::POST, webhook_url, json!({ "content": message }).to_string()),
+        ChatPlatform::Matrix => {
+            let url = format!(
+                "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+                integration.matrix_homeserver.as_deref().unwrap_or_default().trim_end_matches('/'),
+                urlencoding::encode(integration.matrix_room_id.as_deref().unwrap_or_default()),
+                Uuid::new_v4()
+            );
+            OutboundRequest::json(Method::PUT, url, json!({ "msgtype": "m.text", "body": message }).to_string())
+                .header("Authorization", format!("Bearer {}", integration.matrix_access_token.as_deref().unwrap_or_default()))
+        }
+    })
+}
+
+/// Post an item to the integration's channel right away, bypassing the queue;
+/// returns the response status
+pub async fn send(integration: &ChatIntegration, feed: &Feed, item: &FeedItem) -> Result<u16> {
+    request(integration, feed, item)?.send().await
+}
+
+/// Queue a new item for the feed's active integrations within their rate
+/// limits and send what is due; failures are logged
+pub async fn notify(pool: &DatabasePool, feed: &Feed, item: &FeedItem) {
+    let Some(feed_id) = feed.id.as_deref() else {
+        return;
+    };
+    let integrations = match ChatIntegrationOpsGeneric::get_by_feed_id(pool, feed_id) {
+        Ok(integrations) => integrations,
+        Err(e) => {
+            warn!("Failed to load chat integrations of feed '{}': {}", feed.title, e);
+            return;
+        }
+    };
+
+    for integration in integrations.iter().filter(|integration| integration.is_active) {
+        let integration_id = integration.id.as_deref().unwrap_or_default();
+        let limit = integration.rate_limit_per_minute.unwrap_or(DEFAULT_RATE_LIMIT);
+        if !take_slot(integration_id, limit) {
+            info!("Rate limit of {} messages per minute reached for {} integration {}, skipping item '{}'",
+                  limit, integration.platform, integration_id, item.title);
+            continue;
+        }
+        let queued = request(integration, feed, item).and_then(|request| delivery::enqueue(pool, delivery::Origin {
+            kind: "chat",
+            queue: format!("chat:{}", integration_id),
+            feed_id,
+            item_id: item.id.as_deref(),
+        }, &request));
+        if let Err(e) = queued {
+            warn!("Failed to queue item '{}' for {} integration {}: {}", item.title, integration.platform, integration_id, e);
+        }
+    }
+
+    if let Err(e) = delivery::process_due(pool).await {
+        warn!("Failed to send queued deliveries: {}", e);
+    }
+}
+
+/// The named platform, or an error listing the supported ones
+pub fn parse_platform(platform: &str) -> Result<ChatPlatform> {
+    ChatPlatform::parse(platform).ok_or_else(|| {
+        let known: Vec<_> = ChatPlatform::ALL.iter().map(ChatPlatform::as_str).collect();
+        anyhow::anyhow!("Unknown chat platform '{}'; use one of {}", platform, known.join(", "))
+    })
+}
+
+fn max_message_chars(platform: ChatPlatform) -> usize {
+    match platform {
+        ChatPlatform::Slack => 4000,
+        ChatPlatform::Discord => 2000,
+        ChatPlatform::Matrix => 16000,
+    }
+}
+
+fn truncate(message: &str, max_chars: usize) -> String {
+    if message.chars().count() <= max_chars {
+        return message.to_string();
+    }
+    let mut truncated: String = message.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Claim one of the integration's messages for the current minute; false when
+/// the limit is used up
+fn take_slot(integration_id: &str, per_minute: i32) -> bool {
+    static SENT: OnceLock<Mutex<HashMap<String, VecDeque<Instant>>>> = OnceLock::new();
+    let mut sent = SENT.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    let times = sent.entry(integration_id.to_string()).or_default();
+
+    let now = Instant::now();
+    while times.front().is_some_and(|sent_at| now.duration_since(*sent_at) >= RATE_WINDOW) {
+        times.pop_front();
+    }
+    if times.len() >= per_minute.max(1) as usize {
+        return false;
+    }
+    times.push_back(now);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_window() {
+        assert!(take_slot("rate-test", 2));
+        assert!(take_slot("rate-test", 2));
+        assert!(!take_slot("rate-test", 2));
+        // Limits are per integration
+        assert!(take_slot("rate-test-other", 2));
+    }
+
+    #[test]
+    fn test_truncate_to_platform_limit() {
+        assert_eq!(truncate("short", 10), "short");
+        let long = "é".repeat(2500);
+        let message = truncate(&long, max_message_chars(ChatPlatform::Discord));
+        assert_eq!(message.chars().count(), 2000);
+        assert!(message.ends_with('…'));
+    }
+}
@@ -0,0 +1,320 @@
+//! Content filters of rules
+//!
+//! Before an email becomes an item, the filters its rule turned on clean its
+//! plain text and HTML:
+//!
+//! - footers: unsubscribe and preference blocks, elements marked as footers
+//!   and everything after a `-- ` signature delimiter
+//! - quoted replies: `>` lines and everything after an `On ... wrote:` or
+//!   `-----Original Message-----` line, and quote blocks in HTML
+//! - tracking pixels: 1×1 and open-tracking images, removed when the HTML is
+//!   sanitized (see [`sanitize`](crate::feed::sanitize))
+//! - tracking links: click-tracking redirects such as
+//!   `https://t.example.com/click?url=https%3A%2F%2Fexample.com%2Fpost` are
+//!   rewritten to their destination, without `utm_*` parameters
+//!
+//! The filters are heuristics for common newsletters; unrecognized content is
+//! left alone.
+
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+use crate::db::models::EmailRule;
+use crate::imap::mime::EmailContent;
+
+/// Phrases marking a line or paragraph as newsletter footer boilerplate
+const FOOTER_PHRASES: &[&str] = &[
+    "unsubscribe",
+    "you are receiving this",
+    "you received this email",
+    "update your preferences",
+    "manage your preferences",
+    "manage your subscription",
+    "no longer wish to receive",
+];
+
+/// Query parameters click-tracking redirects carry their destination in
+const DESTINATION_PARAMETERS: &[&str] = &["url", "u", "q", "target", "redirect", "redirect_url", "dest", "destination", "link"];
+
+/// Redirects followed when unwrapping a link, for wrappers around wrappers
+const MAX_UNWRAPS: usize = 3;
+
+/// The filters a rule applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentFilters {
+    pub footers: bool,
+    pub quoted_replies: bool,
+    pub tracking_pixels: bool,
+    pub tracking_links: bool,
+}
+
+impl Default for ContentFilters {
+    /// The filters of a new rule: tracking pixels only
+    fn default() -> Self {
+        Self {
+            footers: false,
+            quoted_replies: false,
+            tracking_pixels: true,
+            tracking_links: false,
+        }
+    }
+}
+
+impl ContentFilters {
+    pub fn of(rule: &EmailRule) -> Self {
+        Self {
+            footers: rule.strip_footers,
+            quoted_replies: rule.strip_quoted_replies,
+            tracking_pixels: rule.strip_tracking_pixels,
+            tracking_links: rule.unwrap_tracking_links,
+        }
+    }
+
+    /// Clean an email's text and HTML; the HTML still needs sanitizing
+    pub fn apply(&self, content: &EmailContent) -> EmailContent {
+        let mut text = content.text.clone();
+        let mut html = content.html.clone();
+        if self.quoted_replies {
+            text = strip_quoted_text(&text);
+            html = html.map(|html| strip_quoted_html(&html));
+        }
+        if self.footers {
+            text = strip_footer_text(&text);
+            html = html.map(|html| strip_footer_html(&html));
+        }
+        if self.tracking_links {
+            text = unwrap_links_in_text(&text);
+            html = html.map(|html| unwrap_links_in_html(&html));
+        }
+        EmailContent {
+            text,
+            html,
+            attachments: content.attachments.clone(),
+        }
+    }
+}
+
+/// Destination of a click-tracking link, without `utm_*` parameters; other
+/// links only lose those parameters
+pub fn unwrap_link(url: &str) -> String {
+    let mut url = url.to_string();
+    for _ in 0..MAX_UNWRAPS {
+        match destination(&url) {
+            Some(next) => url = next,
+            None => break,
+        }
+    }
+    strip_utm_parameters(&url)
+}
+
+fn strip_quoted_text(text: &str) -> String {
+    static SEPARATOR: OnceLock<Regex> = OnceLock::new();
+    let separator = SEPARATOR.get_or_init(|| {
+        Regex::new(r"(?i)^(on\s.+\swrote:|-{2,}\s*original message\s*-{2,})$").expect("valid reply separator pattern")
+    });
+    let kept: Vec<&str> = text.lines()
+        .take_while(|line| !separator.is_match(line.trim()))
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .collect();
+    kept.join("\n").trim_end().to_string()
+}
+
+fn strip_quoted_html(html: &str) -> String {
+    static QUOTE: OnceLock<Regex> = OnceLock::new();
+    let quote = QUOTE.get_or_init(|| {
+        Regex::new(r#"(?i)<blockquote\b[^>]*\btype\s*=\s*["']?cite[^>]*>|<div\b[^>]*\bclass\s*=\s*["'][^"']*\b(gmail_quote|yahoo_quoted|moz-cite-prefix)\b[^>]*>"#)
+            .expect("valid quote pattern")
+    });
+    remove_elements(html, quote)
+}
+
+fn mentions_footer(text: &str) -> bool {
+    let text = text.to_lowercase();
+    FOOTER_PHRASES.iter().any(|phrase| text.contains(phrase))
+}
+
+/// Cut the text at its signature delimiter, or at the first footer line in
+/// its second half
+fn strip_footer_text(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let half = lines.len() / 2;
+    let end = lines.iter()
+        .enumerate()
+        .position(|(index, line)| line.trim_end() == "--" || (index >= half && mentions_footer(line)))
+        .unwrap_or(lines.len());
+    lines[..end].join("\n").trim_end().to_string()
+}
+
+fn strip_footer_html(html: &str) -> String {
+    static FOOTER: OnceLock<Regex> = OnceLock::new();
+    static PARAGRAPH: OnceLock<Regex> = OnceLock::new();
+    let footer = FOOTER.get_or_init(|| {
+        Regex::new(r#"(?i)<footer\b[^>]*>|<[a-z][a-z0-9]*\b[^>]*\b(class|id)\s*=\s*["'][^"']*footer[^"']*["'][^>]*>"#)
+            .expect("valid footer pattern")
+    });
+    let paragraph = PARAGRAPH.get_or_init(|| Regex::new(r"(?is)<p\b[^>]*>.*?</p>").expect("valid paragraph pattern"));
+
+    let html = remove_elements(html, footer);
+    paragraph.replace_all(&html, |captures: &Captures| {
+        if mentions_footer(&captures[0]) { String::new() } else { captures[0].to_string() }
+    })
+    .into_owned()
+}
+
+/// Remove each element whose opening tag matches `opening`, with its
+/// content; an element that is never closed runs to the end
+fn remove_elements(html: &str, opening: &Regex) -> String {
+    let mut kept = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(found) = opening.find(rest) {
+        kept.push_str(&rest[..found.start()]);
+        let name: String = found.as_str()[1..].chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        let after = &rest[found.end()..];
+        rest = element_end(after, &name).map_or("", |end| &after[end..]);
+    }
+    kept.push_str(rest);
+    kept
+}
+
+/// Index just past the closing tag of an element named `name` whose opening
+/// tag ends right before `html`, counting nested elements of the same name
+fn element_end(html: &str, name: &str) -> Option<usize> {
+    // ASCII lowercasing keeps byte offsets, so they index `html` as well
+    let lower = html.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    let (open, close) = (format!("<{}", name), format!("</{}", name));
+    let mut depth = 1;
+    let mut index = 0;
+    while let Some(start) = lower[index..].find('<').map(|offset| index + offset) {
+        let tag_end = lower[start..].find('>').map_or(lower.len(), |offset| start + offset + 1);
+        let tag = &lower[start..tag_end];
+        let named = |prefix: &str| {
+            tag.starts_with(prefix) && !tag[prefix.len()..].starts_with(|c: char| c.is_ascii_alphanumeric())
+        };
+        if named(&close) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(tag_end);
+            }
+        } else if named(&open) && !tag.ends_with("/>") {
+            depth += 1;
+        }
+        index = tag_end;
+    }
+    None
+}
+
+fn is_web_link(link: &str) -> bool {
+    let link = link.to_ascii_lowercase();
+    link.starts_with("https://") || link.starts_with("http://")
+}
+
+/// The link a click-tracking redirect leads to
+fn destination(url: &str) -> Option<String> {
+    // URL Defense: https://urldefense.com/v3/__https://example.com/post__;!!token
+    if let Some((_, wrapped)) = url.split_once("/v3/__") {
+        return wrapped.split("__;").next().filter(|link| is_web_link(link)).map(str::to_string);
+    }
+    let (_, query) = url.split_once('?')?;
+    let query = query.split('#').next().unwrap_or_default();
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(name, _)| DESTINATION_PARAMETERS.contains(&name.to_ascii_lowercase().as_str()))
+        .find_map(|(_, value)| {
+            let value = urlencoding::decode(value).ok()?.into_owned();
+            is_web_link(&value).then_some(value)
+        })
+}
+
+fn strip_utm_parameters(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let (query, fragment) = match query.split_once('#') {
+        Some((query, fragment)) => (query, Some(fragment)),
+        None => (query, None),
+    };
+    let kept: Vec<&str> = query.split('&')
+        .filter(|pair| !pair.is_empty() && !pair.to_ascii_lowercase().starts_with("utm_"))
+        .collect();
+
+    let mut stripped = base.to_string();
+    if !kept.is_empty() {
+        stripped.push('?');
+        stripped.push_str(&kept.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        stripped.push('#');
+        stripped.push_str(fragment);
+    }
+    stripped
+}
+
+fn unwrap_links_in_text(text: &str) -> String {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let url = URL.get_or_init(|| Regex::new(r#"(?i)https?://[^\s<>"'()\[\]]+"#).expect("valid URL pattern"));
+    url.replace_all(text, |captures: &Captures| unwrap_link(&captures[0])).into_owned()
+}
+
+fn unwrap_links_in_html(html: &str) -> String {
+    static HREF: OnceLock<Regex> = OnceLock::new();
+    let href = HREF.get_or_init(|| Regex::new(r#"(?i)(\bhref\s*=\s*)(["'])(https?://[^"']*)["']"#).expect("valid href pattern"));
+    href.replace_all(html, |captures: &Captures| {
+        let link = unwrap_link(&captures[3].replace("&amp;", "&")).replace('&', "&amp;");
+        format!("{}{}{}{}", &captures[1], &captures[2], link, &captures[2])
+    })
+    .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> ContentFilters {
+        ContentFilters { footers: true, quoted_replies: true, tracking_pixels: true, tracking_links: true }
+    }
+
+    fn text(text: &str) -> String {
+        all().apply(&EmailContent { text: text.to_string(), ..Default::default() }).text
+    }
+
+    fn html(html: &str) -> String {
+        all().apply(&EmailContent { html: Some(html.to_string()), ..Default::default() }).html.unwrap()
+    }
+
+    #[test]
+    fn test_quoted_replies_are_removed() {
+        assert_eq!(text("Sounds good.\n> Shall we meet?\n\nThanks"), "Sounds good.\n\nThanks");
+        assert_eq!(text("Yes!\n\nOn Mon, 1 Sep 2025 at 10:00, Ann <ann@example.com> wrote:\n> Coming?"), "Yes!");
+        assert_eq!(
+            html(r#"<p>Yes!</p><div class="gmail_quote"><div>On Monday Ann wrote:</div><blockquote type="cite"><div>Coming?</div></blockquote></div><p>Bye</p>"#),
+            "<p>Yes!</p><p>Bye</p>"
+        );
+    }
+
+    #[test]
+    fn test_footers_are_removed() {
+        let body = "News of the week.\nMore news.\nEven more.\nYou are receiving this because you subscribed.\nUnsubscribe: https://example.com/u";
+        assert_eq!(text(body), "News of the week.\nMore news.\nEven more.");
+        assert_eq!(text("Unsubscribe tips inside\nBody\n-- \nAnn"), "Unsubscribe tips inside\nBody");
+        assert_eq!(
+            html(r#"<p>News</p><table id="templateFooter"><tr><td><table><tr><td>Acme Inc.</td></tr></table></td></tr></table><p>Click <a href="https://example.com/u">here</a> to unsubscribe.</p>"#),
+            "<p>News</p>"
+        );
+    }
+
+    #[test]
+    fn test_tracking_links_are_unwrapped() {
+        assert_eq!(
+            unwrap_link("https://t.example.com/click?id=7&url=https%3A%2F%2Fexample.com%2Fpost%3Futm_source%3Dnews%26page%3D2"),
+            "https://example.com/post?page=2"
+        );
+        assert_eq!(unwrap_link("https://urldefense.com/v3/__https://example.com/post__;!!abc"), "https://example.com/post");
+        assert_eq!(unwrap_link("https://example.com/post?utm_medium=email#top"), "https://example.com/post#top");
+        assert_eq!(unwrap_link("https://list-manage.com/track/click?u=abc&id=42"), "https://list-manage.com/track/click?u=abc&id=42");
+        assert_eq!(
+            html(r#"<a href="https://www.google.com/url?q=https://example.com/a%3Fx%3D1%26y%3D2&amp;sa=D">A</a>"#),
+            r#"<a href="https://example.com/a?x=1&amp;y=2">A</a>"#
+        );
+    }
+}
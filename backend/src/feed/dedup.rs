@@ -0,0 +1,104 @@
+//! Cross-feed deduplication
+//!
+//! A feed skips emails it already has. Its dedup scope, `FEED_DEDUP_SCOPE`
+//! unless the feed sets its own, widens that by Message-ID to every feed of
+//! the account the email came from (`account`) or to every feed (`global`),
+//! so a newsletter reaching two addresses is stored once.
+//!
+//! With `FEED_GLOBAL_DEDUP` enabled, an email that already has an item in
+//! another feed gets a linked item pointing at that canonical copy instead of
+//! a second copy of its body. Links act as reference counts on the canonical
+//! item: removing it hands its content to one of the remaining links.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use crate::db::{connection::DatabasePool, models::{DedupScope, Feed, FeedItem}, operations_generic::{AttachmentOpsGeneric, FeedItemOpsGeneric}};
+
+/// Whether duplicate emails across feeds should be linked rather than copied
+pub fn is_enabled() -> bool {
+    std::env::var("FEED_GLOBAL_DEDUP")
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Where a feed looks for emails it already has: its own scope, else
+/// `FEED_DEDUP_SCOPE`, else only the feed itself
+pub fn scope(feed: &Feed) -> DedupScope {
+    feed.dedup_scope().unwrap_or_else(|| {
+        std::env::var("FEED_DEDUP_SCOPE").ok()
+            .and_then(|value| DedupScope::parse(&value))
+            .unwrap_or_default()
+    })
+}
+
+/// Whether a feed of `scope` beyond the feed itself already has an item of
+/// the email; emails without a Message-ID are never matched this way
+pub fn seen_in_scope(pool: &DatabasePool, scope: DedupScope, message_id: &str, account_id: &str) -> Result<bool> {
+    if message_id.is_empty() {
+        return Ok(false);
+    }
+    match scope {
+        DedupScope::Feed => Ok(false),
+        DedupScope::Account => FeedItemOpsGeneric::message_id_exists(pool, message_id, Some(account_id)),
+        DedupScope::Global => FeedItemOpsGeneric::message_id_exists(pool, message_id, None),
+    }
+}
+
+/// Hash of the normalized email content, stable across re-sent copies
+pub fn content_hash(subject: &str, from: &str, body: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(subject.trim().to_lowercase().as_bytes());
+    hasher.update([0]);
+    hasher.update(from.trim().to_lowercase().as_bytes());
+    hasher.update([0]);
+    for word in body.split_whitespace() {
+        hasher.update(word.as_bytes());
+        hasher.update(b" ");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Find the item in another feed that already holds this email's content
+pub fn find_canonical(pool: &DatabasePool, feed_id: &str, message_id: &str, hash: &str) -> Result<Option<FeedItem>> {
+    let message_id = (!message_id.is_empty()).then_some(message_id);
+    FeedItemOpsGeneric::find_canonical(pool, feed_id, message_id, hash)
+}
+
+/// Remove an item, handing its content to a linked item if others still reference it
+pub fn remove_item(pool: &DatabasePool, item: &FeedItem) -> Result<()> {
+    let item_id = item.id.as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Feed item has no ID"))?;
+
+    if item.canonical_item_id.is_none() && FeedItemOpsGeneric::count_references(pool, item_id)? > 0 {
+        let linked = FeedItemOpsGeneric::get_linked(pool, item_id)?;
+        if let Some(heir_id) = linked.first().and_then(|heir| heir.id.as_ref()) {
+            debug!("Promoting feed item {} to canonical copy in place of {}", heir_id, item_id);
+            FeedItemOpsGeneric::promote_to_canonical(pool, heir_id, item.email_body.clone(), item.email_body_html.clone())?;
+            if let Some(body_ref) = &item.body_ref {
+                FeedItemOpsGeneric::set_body_ref(pool, heir_id, body_ref)?;
+            }
+            FeedItemOpsGeneric::relink(pool, item_id, heir_id)?;
+            AttachmentOpsGeneric::reassign(pool, item_id, heir_id)?;
+        }
+    }
+
+    FeedItemOpsGeneric::delete(pool, item_id)
+}
+
+/// Fill in the body of linked items from their canonical copies, or the
+/// reference to it when the copy's body is kept in a blob store
+pub fn resolve_bodies(pool: &DatabasePool, items: &mut [FeedItem]) {
+    for item in items.iter_mut().filter(|item| item.email_body.is_none()) {
+        if let Some(canonical_id) = &item.canonical_item_id {
+            match FeedItemOpsGeneric::get_by_id(pool, canonical_id) {
+                Ok(canonical) => {
+                    item.email_body = canonical.email_body;
+                    item.email_body_html = canonical.email_body_html;
+                    item.body_ref = canonical.body_ref;
+                }
+                Err(e) => debug!("Canonical item {} unavailable: {}", canonical_id, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! At-least-once outbound delivery queue
+//!
+//! Webhooks and chat integrations do not call out directly. They render their
+//! request and queue it as a [`Delivery`]; the queue sends it, and retries
+//! failures with exponential backoff until [`MAX_ATTEMPTS`] is reached. Then
+//! the delivery is marked failed and stays in the dead-letter list for a
+//! manual retry.
+//!
+//! Each delivery belongs to a queue (one per webhook or integration).
+//! Deliveries in a queue go out in the order they were queued: a retrying
+//! delivery holds back the ones behind it, so a receiver never sees a newer
+//! item before an older one. Requests are stored fully rendered, so a retry
+//! repeats exactly the same request, e.g. with the same Matrix transaction ID.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{Delivery, DeliveryStatus, NewDelivery},
+    operations_generic::DeliveryOpsGeneric,
+};
+
+/// Attempts before a delivery is given up and marked failed
+pub const MAX_ATTEMPTS: i32 = 8;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// How long delivered requests are kept
+const DELIVERED_RETENTION_DAYS: i64 = 7;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A rendered HTTP request
+#[derive(Debug, Clone)]
+pub struct OutboundRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl OutboundRequest {
+    /// A request with a JSON body
+    pub fn json(method: Method, url: impl Into<String>, body: String) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: String) -> Self {
+        self.headers.push((name.to_string(), value));
+        self
+    }
+
+    /// Send the request; returns the response status, or an error for
+    /// transport failures and non-success statuses
+    pub async fn send(&self) -> Result<u16> {
+        let mut request = http_client()
+            .request(self.method.clone(), &self.url)
+            .body(self.body.clone());
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("{} returned {}", host(&self.url), status);
+        }
+        Ok(status.as_u16())
+    }
+}
+
+/// Where a queued request comes from
+#[derive(Debug, Clone)]
+pub struct Origin<'a> {
+    /// `webhook` or `chat`
+    pub kind: &'static str,
+    /// Queue the request is ordered in
+    pub queue: String,
+    pub feed_id: &'a str,
+    pub item_id: Option<&'a str>,
+}
+
+/// Add a request to its queue
+pub fn enqueue(pool: &DatabasePool, origin: Origin<'_>, request: &OutboundRequest) -> Result<Delivery> {
+    let headers: HashMap<_, _> = request.headers.iter().cloned().collect();
+    let new_delivery = NewDelivery::new(
+        origin.feed_id.to_string(),
+        origin.item_id.map(str::to_string),
+        origin.kind,
+        origin.queue,
+        request.method.to_string(),
+        request.url.clone(),
+        serde_json::to_string(&headers)?,
+        request.body.clone(),
+    );
+    DeliveryOpsGeneric::create(pool, &new_delivery)
+}
+
+/// Send every due delivery, in queue order; returns how many succeeded
+pub async fn process_due(pool: &DatabasePool) -> Result<usize> {
+    // One pass at a time, so a delivery is never sent twice concurrently
+    static RUNNING: Mutex<()> = Mutex::const_new(());
+    let _running = RUNNING.lock().await;
+
+    let mut queues: Vec<(String, Vec<Delivery>)> = Vec::new();
+    for delivery in DeliveryOpsGeneric::get_by_status(pool, &DeliveryStatus::Pending)? {
+        match queues.iter_mut().find(|(queue, _)| *queue == delivery.queue) {
+            Some((_, deliveries)) => deliveries.push(delivery),
+            None => queues.push((delivery.queue.clone(), vec![delivery])),
+        }
+    }
+
+    let now = Utc::now();
+    let mut delivered = 0;
+    for (queue, deliveries) in queues {
+        for delivery in deliveries {
+            if !is_due(&delivery, now) {
+                break;
+            }
+            match attempt(pool, &delivery).await? {
+                DeliveryStatus::Delivered => delivered += 1,
+                DeliveryStatus::Pending => {
+                    debug!("Holding back the rest of delivery queue {} until {} is retried", queue, delivery.id.as_deref().unwrap_or_default());
+                    break;
+                }
+                DeliveryStatus::Failed => {}
+            }
+        }
+    }
+
+    let cutoff = (now - chrono::Duration::days(DELIVERED_RETENTION_DAYS)).to_rfc3339();
+    let pruned = DeliveryOpsGeneric::delete_delivered_before(pool, &cutoff)?;
+    if pruned > 0 {
+        debug!("Pruned {} delivered requests", pruned);
+    }
+    Ok(delivered)
+}
+
+/// Queue a failed or pending delivery to be sent on the next pass
+pub fn retry(pool: &DatabasePool, delivery_id: &str) -> Result<Delivery> {
+    let delivery = DeliveryOpsGeneric::get_by_id(pool, delivery_id)?;
+    if delivery.status == DeliveryStatus::Delivered.as_str() {
+        anyhow::bail!("Delivery {} has already been delivered", delivery_id);
+    }
+    DeliveryOpsGeneric::update_state(
+        pool,
+        delivery_id,
+        &DeliveryStatus::Pending,
+        0,
+        &Utc::now().to_rfc3339(),
+        delivery.last_error.as_deref(),
+    )
+}
+
+/// Delay before the attempt following `attempts` failed ones
+pub fn backoff(attempts: i32) -> Duration {
+    let doublings = attempts.clamp(1, 16) as u32 - 1;
+    INITIAL_BACKOFF.saturating_mul(2u32.pow(doublings)).min(MAX_BACKOFF)
+}
+
+/// Send a delivery and record the outcome; returns its new status
+async fn attempt(pool: &DatabasePool, delivery: &Delivery) -> Result<DeliveryStatus> {
+    let delivery_id = delivery.id.as_deref().unwrap_or_default();
+    let attempts = delivery.attempts + 1;
+
+    let result = match stored_request(delivery) {
+        Ok(request) => request.send().await,
+        Err(e) => Err(e),
+    };
+    let now = Utc::now();
+    let (status, next_attempt_at, error) = match result {
+        Ok(code) => {
+            debug!("Delivery {} to {} answered {}", delivery_id, host(&delivery.url), code);
+            (DeliveryStatus::Delivered, delivery.next_attempt_at.clone(), None)
+        }
+        Err(e) if attempts >= MAX_ATTEMPTS => {
+            warn!("Delivery {} to {} failed for good after {} attempts: {}", delivery_id, host(&delivery.url), attempts, e);
+            (DeliveryStatus::Failed, delivery.next_attempt_at.clone(), Some(e.to_string()))
+        }
+        Err(e) => {
+            let delay = backoff(attempts);
+            info!("Delivery {} to {} failed (attempt {} of {}), retrying in {:?}: {}",
+                  delivery_id, host(&delivery.url), attempts, MAX_ATTEMPTS, delay, e);
+            let next = now + chrono::Duration::from_std(delay)?;
+            (DeliveryStatus::Pending, next.to_rfc3339(), Some(e.to_string()))
+        }
+    };
+
+    DeliveryOpsGeneric::update_state(pool, delivery_id, &status, attempts, &next_attempt_at, error.as_deref())?;
+    Ok(status)
+}
+
+fn stored_request(delivery: &Delivery) -> Result<OutboundRequest> {
+    let headers: HashMap<String, String> = serde_json::from_str(&delivery.headers)?;
+    Ok(OutboundRequest {
+        method: Method::from_bytes(delivery.method.as_bytes())?,
+        url: delivery.url.clone(),
+        headers: headers.into_iter().collect(),
+        body: delivery.body.clone(),
+    })
+}
+
+fn is_due(delivery: &Delivery, now: DateTime<Utc>) -> bool {
+    DateTime::parse_from_rfc3339(&delivery.next_attempt_at)
+        .map(|at| at <= now)
+        .unwrap_or(true)
+}
+
+/// HTTP client shared by all outbound requests
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .expect("Failed to build outgoing HTTP client")
+    })
+}
+
+/// Host of a URL for log and error messages, which should not carry tokens
+/// embedded in webhook paths
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "receiver".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        assert_eq!(backoff(1), Duration::from_secs(30));
+        assert_eq!(backoff(2), Duration::from_secs(60));
+        assert_eq!(backoff(4), Duration::from_secs(240));
+        assert_eq!(backoff(MAX_ATTEMPTS + 20), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_error_messages_name_only_the_host() {
+        assert_eq!(host("https://hooks.slack.com/services/T/B/secret"), "hooks.slack.com");
+        assert_eq!(host("not a url"), "receiver");
+    }
+}
@@ -0,0 +1,281 @@
+//! Digest mode: several emails of a feed in one item
+//!
+//! Some senders split one update across many emails. A feed with a digest
+//! mode merges them: in `thread` mode an email joins the item of its thread,
+//! whose root is the oldest message its `References` header names (or the
+//! one `In-Reply-To` names); in `day` mode it joins the item of the same UTC
+//! day. Bodies are kept in date order, each headed by its email's subject,
+//! sender and date, and the item's summary is made anew. Every email but the
+//! item's first (its lead) has its Message-ID kept in `digest_message_ids`,
+//! so it counts as processed.
+//!
+//! Only items whose body is still in the database are merged into; once a
+//! body was moved to the blob store the next email starts a new item.
+
+use anyhow::Result;
+use chrono::DateTime;
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::{overflow::escape_html, summarizer};
+use crate::db::{
+    connection::DatabasePool,
+    models::{DigestMerge, DigestMode, FeedItem, NewFeedItem},
+    operations_generic::FeedItemOpsGeneric,
+};
+
+/// Message-ID of the first email of a thread: the oldest one an email
+/// references, else the one it replies to, else its own
+pub fn thread_id(message_id: &str, in_reply_to: Option<&str>, references: Option<&str>) -> Option<String> {
+    references.and_then(first_message_id)
+        .or_else(|| in_reply_to.and_then(first_message_id))
+        .or_else(|| Some(message_id.trim()).filter(|id| !id.is_empty()))
+        .map(str::to_string)
+}
+
+fn first_message_id(header: &str) -> Option<&str> {
+    static MESSAGE_ID: OnceLock<Regex> = OnceLock::new();
+    let message_id = MESSAGE_ID.get_or_init(|| Regex::new(r"<[^<>\s]+>").expect("valid Message-ID pattern"));
+    message_id.find(header).map(|found| found.as_str())
+}
+
+/// The item of `feed_id` the email of `item` is merged into, if there is one
+pub fn find_target(pool: &DatabasePool, feed_id: &str, mode: DigestMode, item: &NewFeedItem) -> Result<Option<FeedItem>> {
+    match mode {
+        DigestMode::Thread => match item.thread_id.as_deref() {
+            Some(thread_id) => FeedItemOpsGeneric::get_digest_of_thread(pool, feed_id, thread_id),
+            None => Ok(None),
+        },
+        DigestMode::Day => match item.pub_date.get(..10) {
+            Some(day) => FeedItemOpsGeneric::get_digest_of_day(pool, feed_id, day),
+            None => Ok(None),
+        },
+    }
+}
+
+/// Merge the email of `item` into `target`; returns the updated item
+pub fn merge(pool: &DatabasePool, mode: DigestMode, target: &FeedItem, item: &NewFeedItem, summary_length: usize) -> Result<FeedItem> {
+    let target_id = target.id.as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Digest item has no ID"))?;
+    FeedItemOpsGeneric::merge_digest(pool, target_id, &merged(mode, target, item, summary_length))
+}
+
+/// One side of a merge: the item's lead email, with the bodies merged into
+/// it so far, or the email being merged
+struct Part<'a> {
+    id: &'a str,
+    title: &'a str,
+    pub_date: &'a str,
+    message_id: Option<&'a str>,
+    subject: Option<&'a str>,
+    from: Option<&'a str>,
+    text: &'a str,
+    html: Option<&'a str>,
+}
+
+impl<'a> Part<'a> {
+    fn of_item(item: &'a FeedItem) -> Self {
+        Self {
+            id: item.id.as_deref().unwrap_or_default(),
+            title: &item.title,
+            pub_date: &item.pub_date,
+            message_id: item.email_message_id.as_deref(),
+            subject: item.email_subject.as_deref(),
+            from: item.email_from.as_deref(),
+            text: item.email_body.as_deref().unwrap_or_default(),
+            html: item.email_body_html.as_deref(),
+        }
+    }
+
+    fn of_new_item(item: &'a NewFeedItem) -> Self {
+        Self {
+            id: &item.id,
+            title: &item.title,
+            pub_date: &item.pub_date,
+            message_id: item.email_message_id.as_deref(),
+            subject: item.email_subject.as_deref(),
+            from: item.email_from.as_deref(),
+            text: item.email_body.as_deref().unwrap_or_default(),
+            html: item.email_body_html.as_deref(),
+        }
+    }
+
+    /// Subject, sender and date introducing the part
+    fn heading(&self) -> String {
+        let subject = self.subject.unwrap_or(self.title);
+        let date = DateTime::parse_from_rfc3339(self.pub_date)
+            .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|_| self.pub_date.to_string());
+        match self.from {
+            Some(from) => format!("{} ({}, {})", subject, from, date),
+            None => format!("{} ({})", subject, date),
+        }
+    }
+
+    /// HTML of the part; plain text is shown preformatted next to HTML parts
+    fn html(&self) -> String {
+        self.html.map_or_else(|| format!("<pre>{}</pre>", escape_html(self.text)), str::to_string)
+    }
+}
+
+/// Emails are fetched newest first, so an email older than the item's lead
+/// becomes its new lead and the item's bodies follow it
+fn merged(mode: DigestMode, target: &FeedItem, item: &NewFeedItem, summary_length: usize) -> DigestMerge {
+    let (existing, merging) = (Part::of_item(target), Part::of_new_item(item));
+    // Stored dates are all RFC 3339 in UTC, so they order as strings
+    let (lead, follower) = if merging.pub_date < existing.pub_date { (merging, existing) } else { (existing, merging) };
+
+    let heading = follower.heading();
+    let email_body = format!("{}\n\n{}\n{}\n\n{}", lead.text, heading, "-".repeat(heading.chars().count()), follower.text);
+    let email_body_html = (lead.html.is_some() || follower.html.is_some())
+        .then(|| format!("{}\n<hr>\n<h3>{}</h3>\n{}", lead.html(), escape_html(&heading), follower.html()));
+
+    let follower_id = follower.message_id.unwrap_or(follower.id);
+    let digest_message_ids = match target.digest_message_ids.as_deref() {
+        Some(ids) if !ids.is_empty() => format!("{}\n{}", ids, follower_id),
+        _ => follower_id.to_string(),
+    };
+    let title = match mode {
+        DigestMode::Thread => lead.title.to_string(),
+        DigestMode::Day => format!("{} (+{} more)", base_title(lead.title), digest_message_ids.lines().count()),
+    };
+
+    DigestMerge {
+        title,
+        pub_date: lead.pub_date.to_string(),
+        email_message_id: lead.message_id.map(str::to_string),
+        email_subject: lead.subject.map(str::to_string),
+        email_from: lead.from.map(str::to_string),
+        description: Some(summarizer::summarize(&email_body, summary_length)),
+        body_size: email_body.len() as i32,
+        email_body: Some(email_body),
+        email_body_html,
+        digest_message_ids,
+    }
+}
+
+/// A day digest's title without its "(+N more)" count
+fn base_title(title: &str) -> &str {
+    static COUNT: OnceLock<Regex> = OnceLock::new();
+    let count = COUNT.get_or_init(|| Regex::new(r" \(\+\d+ more\)$").expect("valid count pattern"));
+    count.find(title).map_or(title, |found| &title[..found.start()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn new_item(subject: &str, body: &str, html: Option<&str>) -> NewFeedItem {
+        let mut item = NewFeedItem::new(
+            "feed".to_string(),
+            subject.to_string(),
+            None,
+            None,
+            None,
+            Utc.with_ymd_and_hms(2025, 9, 1, 9, 30, 0).unwrap(),
+            Some(format!("<{}@example.com>", subject.replace(' ', "-"))),
+            Some(subject.to_string()),
+            Some("news@example.com".to_string()),
+            Some(body.to_string()),
+        );
+        item.email_body_html = html.map(str::to_string);
+        item
+    }
+
+    fn stored(item: NewFeedItem) -> FeedItem {
+        FeedItem {
+            id: Some(item.id),
+            feed_id: item.feed_id,
+            title: item.title,
+            description: item.description,
+            link: None,
+            author: None,
+            pub_date: item.pub_date,
+            email_message_id: item.email_message_id,
+            email_subject: item.email_subject,
+            email_from: item.email_from,
+            email_body: item.email_body,
+            created_at: item.created_at,
+            is_read: Some(false),
+            starred: Some(false),
+            body_size: item.body_size,
+            processing_run_id: None,
+            content_hash: None,
+            canonical_item_id: None,
+            language: None,
+            pinned: false,
+            importance: None,
+            category: None,
+            chain_previous: None,
+            chain_hash: None,
+            email_body_html: item.email_body_html,
+            body_ref: None,
+            rating: None,
+            origin_feed_id: None,
+            unsubscribe_url: None,
+            unsubscribe_mailto: None,
+            unsubscribe_one_click: false,
+            in_reply_to: None,
+            email_references: None,
+            thread_id: item.thread_id,
+            digest_message_ids: None,
+            guid: None,
+            sync_id: None,
+        }
+    }
+
+    #[test]
+    fn test_thread_id_is_the_oldest_reference() {
+        assert_eq!(thread_id("<c@x>", Some("<b@x>"), Some("<a@x>\r\n <b@x>")).as_deref(), Some("<a@x>"));
+        assert_eq!(thread_id("<c@x>", Some("<b@x> (Re: Issue 1)"), None).as_deref(), Some("<b@x>"));
+        assert_eq!(thread_id("<c@x>", None, Some("")).as_deref(), Some("<c@x>"));
+        assert_eq!(thread_id("", None, None), None);
+    }
+
+    #[test]
+    fn test_day_digest_appends_bodies_and_counts_emails() {
+        let first = stored(new_item("Morning", "First update", None));
+        let merged = merged(DigestMode::Day, &first, &new_item("Evening", "Second update", None), 200);
+
+        assert_eq!(merged.title, "Morning (+1 more)");
+        let body = merged.email_body.unwrap();
+        assert!(body.starts_with("First update\n\nEvening (news@example.com, 2025-09-01 09:30 UTC)\n---"));
+        assert!(body.ends_with("\n\nSecond update"));
+        assert_eq!(merged.body_size, body.len() as i32);
+        assert_eq!(merged.email_body_html, None);
+        assert_eq!(merged.digest_message_ids, "<Evening@example.com>");
+
+        let mut digest = stored(new_item("Morning", &body, None));
+        digest.title = merged.title;
+        digest.digest_message_ids = Some(merged.digest_message_ids);
+        let merged = super::merged(DigestMode::Day, &digest, &new_item("Night", "Third update", None), 200);
+        assert_eq!(merged.title, "Morning (+2 more)");
+        assert_eq!(merged.digest_message_ids, "<Evening@example.com>\n<Night@example.com>");
+    }
+
+    #[test]
+    fn test_older_email_becomes_the_lead() {
+        let newer = stored(new_item("Re: Issue 1", "Reply", None));
+        let mut older = new_item("Issue 1", "Question", None);
+        older.pub_date = "2025-09-01T08:00:00+00:00".to_string();
+        let merged = merged(DigestMode::Thread, &newer, &older, 200);
+
+        assert_eq!(merged.title, "Issue 1");
+        assert_eq!(merged.pub_date, "2025-09-01T08:00:00+00:00");
+        assert_eq!(merged.email_message_id.as_deref(), Some("<Issue-1@example.com>"));
+        assert!(merged.email_body.unwrap().starts_with("Question\n\nRe: Issue 1 (news@example.com, 2025-09-01 09:30 UTC)"));
+        assert_eq!(merged.digest_message_ids, "<Re:-Issue-1@example.com>");
+    }
+
+    #[test]
+    fn test_thread_digest_keeps_title_and_combines_html() {
+        let first = stored(new_item("Issue 1", "Plain <b>text</b>", None));
+        let merged = merged(DigestMode::Thread, &first, &new_item("Re: Issue 1", "Reply", Some("<p>Reply</p>")), 200);
+
+        assert_eq!(merged.title, "Issue 1");
+        let html = merged.email_body_html.unwrap();
+        assert!(html.starts_with("<pre>Plain &lt;b&gt;text&lt;/b&gt;</pre>\n<hr>\n<h3>Re: Issue 1 (news@example.com"));
+        assert!(html.ends_with("<p>Reply</p>"));
+    }
+}
@@ -0,0 +1,503 @@
+//! Offline archives of a feed's items
+//!
+//! `GET /api/feeds/:id/export` bundles the items stored in a feed, oldest
+//! first, into one download for reading or keeping offline:
+//!
+//! - `epub`: an EPUB 3 book with a chapter per item
+//! - `html`: a single page with a table of contents
+//! - `mbox`: the emails in mboxrd format, for importing into a mail client
+//!
+//! Only the parsed parts of an email are stored, so the messages of an mbox
+//! are rebuilt from each item's headers and its text and HTML bodies rather
+//! than being the original bytes.
+//!
+//! Feeds with more than [`INLINE_ITEM_LIMIT`] items are exported by a job
+//! instead of within the request. The job writes the archive under
+//! `EXPORT_PATH` (a `mail2feed-exports` directory in the system's temporary
+//! directory by default), to be fetched from `GET /api/exports/:job_id`;
+//! archives are removed after [`JOB_RETENTION_DAYS`], like the jobs.
+
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use utoipa::ToSchema;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use super::{blob::BlobStore, bodies, dedup, overflow::escape_html, sanitize};
+use crate::background::jobs::{JobHandle, JOB_RETENTION_DAYS};
+use crate::db::{connection::DatabasePool, models::{Feed, FeedItem}, operations_generic::FeedItemOpsGeneric};
+use crate::imap::senders;
+
+/// Job kind of exports run in the background
+pub const EXPORT_JOB: &str = "export_feed";
+
+/// Most items exported within the request; larger feeds are exported by a job
+pub const INLINE_ITEM_LIMIT: i64 = 200;
+
+/// Archive formats a feed can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Epub,
+    Html,
+    Mbox,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Epub, ExportFormat::Html, ExportFormat::Mbox];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Epub => "epub",
+            ExportFormat::Html => "html",
+            ExportFormat::Mbox => "mbox",
+        }
+    }
+
+    pub fn parse(format: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|candidate| candidate.as_str() == format.trim().to_lowercase())
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Epub => "application/epub+zip",
+            ExportFormat::Html => "text/html; charset=utf-8",
+            ExportFormat::Mbox => "application/mbox",
+        }
+    }
+}
+
+/// Result of an export job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ExportResult {
+    pub format: ExportFormat,
+    /// Name the archive is downloaded as
+    pub filename: String,
+    pub items: usize,
+    pub bytes: usize,
+    /// Where to download the archive
+    pub download_url: String,
+}
+
+/// Every item of a feed, oldest first, with its body
+pub async fn load_items(pool: &DatabasePool, store: Option<&BlobStore>, feed_id: &str) -> Result<Vec<FeedItem>> {
+    let mut items = FeedItemOpsGeneric::get_by_feed_id(pool, feed_id, None)?;
+    items.sort_by(|a, b| (&a.pub_date, &a.id).cmp(&(&b.pub_date, &b.id)));
+    dedup::resolve_bodies(pool, &mut items);
+    bodies::load(store, &mut items).await;
+    Ok(items)
+}
+
+/// Name of the archive of `feed`, from its title
+pub fn filename(feed: &Feed, format: ExportFormat) -> String {
+    format!("{}.{}", slug(&feed.title), format.as_str())
+}
+
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() { "feed".to_string() } else { slug.to_string() }
+}
+
+/// What the archive says about the feed it came from
+struct Book<'a> {
+    id: &'a str,
+    title: &'a str,
+    description: Option<&'a str>,
+    /// Language tag: the feed's locale, else English
+    lang: String,
+}
+
+impl<'a> Book<'a> {
+    fn new(feed: &'a Feed) -> Self {
+        Self {
+            id: feed.id.as_deref().unwrap_or_default(),
+            title: &feed.title,
+            description: feed.description.as_deref(),
+            lang: feed.locale.as_deref()
+                .map(|locale| locale.replace('_', "-"))
+                .filter(|locale| !locale.trim().is_empty())
+                .unwrap_or_else(|| "en".to_string()),
+        }
+    }
+}
+
+/// The archive of `items` in `format`
+pub fn render(feed: &Feed, items: &[FeedItem], format: ExportFormat, exported_at: DateTime<Utc>) -> Result<Vec<u8>> {
+    let book = Book::new(feed);
+    match format {
+        ExportFormat::Epub => epub(&book, items, exported_at),
+        ExportFormat::Html => Ok(html_page(&book, items, exported_at).into_bytes()),
+        ExportFormat::Mbox => Ok(mbox(items).into_bytes()),
+    }
+}
+
+/// Directory holding the archives of export jobs
+pub fn dir() -> PathBuf {
+    std::env::var("EXPORT_PATH")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("mail2feed-exports"))
+}
+
+/// File of the archive written by the export job `job_id`
+pub fn path(job_id: &str, format: ExportFormat) -> PathBuf {
+    dir().join(format!("{}.{}", job_id, format.as_str()))
+}
+
+/// Export `feed` as the job `job`, writing the archive for download
+pub async fn run(pool: DatabasePool, store: Option<BlobStore>, feed: Feed, format: ExportFormat, job: &JobHandle) -> Result<ExportResult> {
+    let feed_id = feed.id.clone().ok_or_else(|| anyhow!("Feed has no ID"))?;
+    job.note("Loading items");
+    let items = load_items(&pool, store.as_ref(), &feed_id).await?;
+    job.set_total(items.len());
+
+    job.note(&format!("Writing {} archive", format.as_str()));
+    let filename = filename(&feed, format);
+    let count = items.len();
+    let archive = tokio::task::spawn_blocking(move || render(&feed, &items, format, Utc::now())).await??;
+    job.advance(count);
+
+    let path = path(job.id(), format);
+    tokio::fs::create_dir_all(dir()).await.context("Failed to create the export directory")?;
+    tokio::fs::write(&path, &archive).await.with_context(|| format!("Failed to write {}", path.display()))?;
+    info!("Exported {} items of feed {} to {}", count, feed_id, path.display());
+
+    Ok(ExportResult {
+        format,
+        filename,
+        items: count,
+        bytes: archive.len(),
+        download_url: format!("/api/exports/{}", job.id()),
+    })
+}
+
+/// Remove archives older than [`JOB_RETENTION_DAYS`]; returns how many were removed
+pub fn remove_expired() -> Result<usize> {
+    let entries = match std::fs::read_dir(dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let max_age = Duration::days(JOB_RETENTION_DAYS).to_std()?;
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let expired = entry.metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > max_age));
+        if expired && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn item_date(item: &FeedItem) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&item.pub_date).ok().map(|date| date.with_timezone(&Utc))
+}
+
+/// Sender and date line under an item's title
+fn byline(item: &FeedItem) -> String {
+    let sender = item.email_from.as_deref().or(item.author.as_deref());
+    let date = item_date(item).map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string());
+    [sender.map(str::to_string), date].into_iter().flatten().collect::<Vec<_>>().join(" · ")
+}
+
+/// The item's body as HTML: its sanitized HTML body, else its text body in
+/// paragraphs, else its description
+fn body_html(item: &FeedItem) -> String {
+    if let Some(html) = item.email_body_html.as_deref().filter(|html| !html.trim().is_empty()) {
+        return sanitize::sanitize_html(html, sanitize::remote_images_blocked(), true);
+    }
+    let text = item.email_body.as_deref().or(item.description.as_deref()).unwrap_or_default();
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph.trim()).replace('\n', "<br/>")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Title, byline and body of an item
+fn item_section(item: &FeedItem, heading: &str) -> String {
+    format!(
+        "<{heading}>{}</{heading}>\n<p class=\"byline\">{}</p>\n{}",
+        escape_html(&item.title),
+        escape_html(&byline(item)),
+        body_html(item),
+        heading = heading
+    )
+}
+
+const STYLE: &str = "body { font-family: serif; line-height: 1.5; margin: 0 auto; max-width: 45em; padding: 0 1em; }\n\
+    .byline { color: #666; font-size: 0.9em; }\n\
+    img { max-width: 100%; height: auto; }\n\
+    article { border-top: 1px solid #ccc; margin-top: 2em; }\n";
+
+fn html_page(book: &Book, items: &[FeedItem], exported_at: DateTime<Utc>) -> String {
+    let contents: Vec<String> = items.iter().enumerate()
+        .map(|(n, item)| format!("<li><a href=\"#item-{}\">{}</a></li>", n + 1, escape_html(&item.title)))
+        .collect();
+    let articles: Vec<String> = items.iter().enumerate()
+        .map(|(n, item)| format!("<article id=\"item-{}\">\n{}\n</article>", n + 1, item_section(item, "h2")))
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{style}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n{description}<p class=\"byline\">{count} items, exported {exported}</p>\n\
+         <nav>\n<ol>\n{contents}\n</ol>\n</nav>\n{articles}\n</body>\n</html>\n",
+        lang = escape_html(&book.lang),
+        title = escape_html(book.title),
+        style = STYLE,
+        description = book.description.map(|d| format!("<p>{}</p>\n", escape_html(d))).unwrap_or_default(),
+        count = items.len(),
+        exported = exported_at.format("%Y-%m-%d %H:%M UTC"),
+        contents = contents.join("\n"),
+        articles = articles.join("\n"),
+    )
+}
+
+/// HTML serialized as XHTML: void elements closed and `&nbsp;`, which XML
+/// does not define, as a character reference. Expects the well-formed
+/// markup `sanitize_html` writes
+fn xhtml(html: &str) -> String {
+    static VOID: OnceLock<Regex> = OnceLock::new();
+    let void = VOID.get_or_init(|| {
+        Regex::new(r#"<(area|base|br|col|embed|hr|img|input|link|meta|source|track|wbr)((?:\s+[^\s"'>/=]+(?:="[^"]*")?)*)\s*/?>"#)
+            .expect("valid void element pattern")
+    });
+    void.replace_all(html, "<$1$2/>").replace("&nbsp;", "&#160;")
+}
+
+fn xhtml_document(title: &str, lang: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+         <head>\n<meta charset=\"utf-8\"/>\n<title>{title}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+         <body>\n{body}\n</body>\n</html>\n",
+        lang = escape_html(lang),
+        title = escape_html(title),
+        body = body
+    )
+}
+
+const CONTAINER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+    <rootfiles>\n<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n</rootfiles>\n\
+    </container>\n";
+
+fn epub(book: &Book, items: &[FeedItem], exported_at: DateTime<Utc>) -> Result<Vec<u8>> {
+    let lang = &book.lang;
+    let chapters: Vec<(String, String)> = items.iter().enumerate()
+        .map(|(n, item)| {
+            let lang = item.language.as_deref().unwrap_or(lang);
+            let content = xhtml_document(&item.title, lang, &xhtml(&item_section(item, "h1")));
+            (format!("item-{:04}.xhtml", n + 1), content)
+        })
+        .collect();
+
+    let contents: Vec<String> = chapters.iter().zip(items)
+        .map(|((href, _), item)| format!("<li><a href=\"{}\">{}</a></li>", href, escape_html(&item.title)))
+        .collect();
+    let nav_body = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n<ol>\n{}\n</ol>\n</nav>",
+        escape_html(book.title),
+        if contents.is_empty() { "<li><span>No items</span></li>".to_string() } else { contents.join("\n") }
+    );
+    let nav = xhtml_document(book.title, lang, &nav_body);
+
+    // Chapters with remote images must say so for EPUB readers
+    let manifest: Vec<String> = chapters.iter().enumerate()
+        .map(|(n, (href, content))| {
+            let remote = content.contains("src=\"http://") || content.contains("src=\"https://");
+            format!(
+                "<item id=\"item-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"{}/>",
+                n + 1,
+                href,
+                if remote { " properties=\"remote-resources\"" } else { "" }
+            )
+        })
+        .collect();
+    let spine: Vec<String> = (1..=chapters.len()).map(|n| format!("<itemref idref=\"item-{}\"/>", n)).collect();
+    let package = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:identifier id=\"book-id\">urn:mail2feed:feed:{id}</dc:identifier>\n\
+         <dc:title>{title}</dc:title>\n<dc:language>{lang}</dc:language>\n{description}\
+         <meta property=\"dcterms:modified\">{modified}</meta>\n</metadata>\n\
+         <manifest>\n<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n{manifest}\n</manifest>\n\
+         <spine>\n<itemref idref=\"nav\"/>\n{spine}\n</spine>\n</package>\n",
+        id = escape_html(book.id),
+        title = escape_html(book.title),
+        lang = escape_html(lang),
+        description = book.description.map(|d| format!("<dc:description>{}</dc:description>\n", escape_html(d))).unwrap_or_default(),
+        modified = exported_at.format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest = manifest.join("\n"),
+        spine = spine.join("\n"),
+    );
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // The mimetype comes first and uncompressed, so readers can sniff it
+    zip.start_file("mimetype", FileOptions::default().compression_method(CompressionMethod::Stored))?;
+    zip.write_all(b"application/epub+zip")?;
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let files = [
+        ("META-INF/container.xml".to_string(), CONTAINER.to_string()),
+        ("OEBPS/content.opf".to_string(), package),
+        ("OEBPS/nav.xhtml".to_string(), nav),
+        ("OEBPS/style.css".to_string(), STYLE.to_string()),
+    ];
+    for (name, content) in files.into_iter().chain(chapters.into_iter().map(|(href, content)| (format!("OEBPS/{}", href), content))) {
+        zip.start_file(name, deflated)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Header value without line breaks, RFC 2047 encoded when not ASCII
+fn encode_header(value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    if value.is_ascii() {
+        value
+    } else {
+        format!("=?UTF-8?B?{}?=", base64::engine::general_purpose::STANDARD.encode(value))
+    }
+}
+
+/// Address header value with only its display name encoded
+fn encode_address(value: &str) -> String {
+    match value.rfind('<') {
+        Some(start) if start > 0 => {
+            let name = value[..start].trim().trim_matches('"');
+            format!("{} {}", encode_header(name), encode_header(value[start..].trim()))
+        }
+        _ => encode_header(value.trim()),
+    }
+}
+
+/// Body lines with those that would read as a message separator quoted, as in mboxrd
+fn mbox_body(body: &str) -> String {
+    let mut escaped = String::new();
+    for line in body.replace("\r\n", "\n").lines() {
+        if line.trim_start_matches('>').starts_with("From ") {
+            escaped.push('>');
+        }
+        escaped.push_str(line);
+        escaped.push('\n');
+    }
+    escaped
+}
+
+fn mbox_message(item: &FeedItem) -> String {
+    let from = item.email_from.as_deref().or(item.author.as_deref()).unwrap_or("unknown@localhost");
+    let envelope = Some(senders::address(from)).filter(|address| address.contains('@') && !address.contains(' '));
+    let date = item_date(item).unwrap_or_else(Utc::now);
+
+    let mut message = format!(
+        "From {} {}\n",
+        envelope.as_deref().unwrap_or("MAILER-DAEMON"),
+        date.format("%a %b %e %H:%M:%S %Y")
+    );
+    message.push_str(&format!("From: {}\n", encode_address(from)));
+    message.push_str(&format!("Subject: {}\n", encode_header(item.email_subject.as_deref().unwrap_or(&item.title))));
+    message.push_str(&format!("Date: {}\n", date.to_rfc2822()));
+    if let Some(message_id) = item.email_message_id.as_deref().filter(|id| !id.trim().is_empty()) {
+        let message_id = message_id.trim().trim_start_matches('<').trim_end_matches('>');
+        message.push_str(&format!("Message-ID: <{}>\n", encode_header(message_id)));
+    }
+    if let Some(in_reply_to) = item.in_reply_to.as_deref() {
+        message.push_str(&format!("In-Reply-To: {}\n", encode_header(in_reply_to)));
+    }
+    if let Some(references) = item.email_references.as_deref() {
+        message.push_str(&format!("References: {}\n", encode_header(references)));
+    }
+    message.push_str("MIME-Version: 1.0\n");
+
+    let text = item.email_body.as_deref().or(item.description.as_deref()).unwrap_or_default();
+    let part = |content_type: &str, body: &str| {
+        format!("Content-Type: {}; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n{}", content_type, mbox_body(body))
+    };
+    match item.email_body_html.as_deref().filter(|html| !html.trim().is_empty()) {
+        Some(html) => {
+            let boundary = format!("mail2feed-{}", item.id.as_deref().unwrap_or("item"));
+            message.push_str(&format!("Content-Type: multipart/alternative; boundary=\"{}\"\n\n", boundary));
+            message.push_str(&format!("--{}\n{}\n", boundary, part("text/plain", text)));
+            message.push_str(&format!("--{}\n{}\n", boundary, part("text/html", html)));
+            message.push_str(&format!("--{}--\n", boundary));
+        }
+        None => message.push_str(&part("text/plain", text)),
+    }
+    message.push('\n');
+    message
+}
+
+fn mbox(items: &[FeedItem]) -> String {
+    items.iter().map(mbox_message).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::generator::FeedGenerator;
+    use std::io::Read;
+
+    fn item(title: &str) -> FeedItem {
+        let date = DateTime::parse_from_rfc3339("2025-03-04T05:06:07+00:00").unwrap().with_timezone(&Utc);
+        FeedGenerator::email_to_feed_item(
+            "feed-1".to_string(),
+            title,
+            "Zoë <zoe@example.com>",
+            "Hello\nFrom the team\n\n>From before",
+            Some("<abc@example.com>".to_string()),
+            date,
+        )
+    }
+
+    #[test]
+    fn test_xhtml_closes_void_elements() {
+        let html = sanitize::sanitize_html("<p>a<br>b&nbsp;<img src=\"x.png\" alt=\"1 > 0\"></p><hr>", false, false);
+        assert_eq!(xhtml(&html), "<p>a<br/>b&#160;<img src=\"x.png\" alt=\"1 &gt; 0\"/></p><hr/>");
+    }
+
+    #[test]
+    fn test_mbox_message() {
+        let message = mbox_message(&item("Weekly"));
+        assert!(message.starts_with("From zoe@example.com Tue Mar  4 05:06:07 2025\n"), "{}", message);
+        assert!(message.contains("\nFrom: =?UTF-8?B?Wm/Dqw==?= <zoe@example.com>\n"), "{}", message);
+        assert!(message.contains("\nMessage-ID: <abc@example.com>\n"), "{}", message);
+        assert!(message.contains("\n\nHello\n>From the team\n\n>>From before\n"), "{}", message);
+    }
+
+    #[test]
+    fn test_epub_starts_with_stored_mimetype() {
+        let book = Book { id: "feed-1", title: "Weekly", description: None, lang: "en".to_string() };
+        let archive = epub(&book, &[item("First <issue>")], Utc::now()).unwrap();
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mimetype = zip.by_index(0).unwrap();
+        assert_eq!((mimetype.name(), mimetype.compression()), ("mimetype", CompressionMethod::Stored));
+        drop(mimetype);
+
+        let mut chapter = String::new();
+        zip.by_name("OEBPS/item-0001.xhtml").unwrap().read_to_string(&mut chapter).unwrap();
+        assert!(chapter.contains("<h1>First &lt;issue&gt;</h1>"), "{}", chapter);
+        assert!(chapter.contains("<p>Hello<br/>From the team</p>"), "{}", chapter);
+        assert_eq!(slug("Weekly News: Été 2025!"), "weekly-news-été-2025");
+        assert_eq!(slug("!!!"), "feed");
+    }
+}
@@ -0,0 +1,108 @@
+//! Credentials and numbering of the Fever sync API
+//!
+//! Fever clients such as Reeder sign in with an `api_key`, the MD5 hex
+//! digest of `username:password`, and refer to feeds and items by integer.
+//! Only that digest is stored, since it is all a client ever sends. Items are
+//! numbered by their `sync_id`, which only grows, so clients can page with
+//! `since_id` and `max_id`; feeds, and the accounts they are grouped by, are
+//! numbered by a hash of their ID, which stays the same as long as they exist.
+
+use anyhow::Result;
+use chrono::DateTime;
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::db::{connection::DatabasePool, models::AppSetting, operations_generic::AppSettingOpsGeneric};
+
+/// The one user the Fever API accepts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeverCredentials {
+    pub username: String,
+    /// Lowercase MD5 hex digest of `username:password`
+    pub api_key: String,
+}
+
+impl FeverCredentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        let username = username.trim().to_string();
+        let api_key = hex(&Md5::digest(format!("{}:{}", username, password)));
+        Self { username, api_key }
+    }
+
+    pub fn load(pool: &DatabasePool) -> Result<Option<Self>> {
+        match AppSettingOpsGeneric::get(pool, AppSetting::FEVER)? {
+            Some(setting) => Ok(Some(serde_json::from_str(&setting.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store these credentials, or turn the API off when `username` is empty
+    pub fn store(pool: &DatabasePool, username: &str, password: &str) -> Result<()> {
+        if username.trim().is_empty() {
+            AppSettingOpsGeneric::delete(pool, AppSetting::FEVER)?;
+        } else {
+            AppSettingOpsGeneric::set(pool, AppSetting::FEVER, &serde_json::to_string(&Self::new(username, password))?)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a client's `api_key` is this user's, in either case
+    pub fn accepts(&self, api_key: &str) -> bool {
+        let api_key = api_key.trim().to_ascii_lowercase();
+        api_key.len() == self.api_key.len()
+            && api_key.bytes().zip(self.api_key.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+/// Check credentials before they are stored; an empty username turns the API off
+pub fn validate(username: &str, password: &str) -> Result<(), String> {
+    if !username.trim().is_empty() && password.is_empty() {
+        return Err("Fever password must not be empty".to_string());
+    }
+    if username.contains(':') || username.chars().any(char::is_control) {
+        return Err("Fever username must not contain ':' or control characters".to_string());
+    }
+    Ok(())
+}
+
+/// Integer standing in for a feed or account ID, below 2^48 so clients
+/// that keep numbers as doubles see it unchanged
+pub fn numeric_id(id: &str) -> i64 {
+    let digest = Sha256::digest(id.as_bytes());
+    digest[..6].iter().fold(0, |n, byte| (n << 8) | i64::from(*byte))
+}
+
+/// Seconds since the epoch of an RFC 3339 timestamp, 0 when it does not parse
+pub fn unix_time(timestamp: &str) -> i64 {
+    DateTime::parse_from_rfc3339(timestamp).map(|time| time.timestamp()).unwrap_or(0)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_key_is_md5_of_username_and_password() {
+        let credentials = FeverCredentials::new(" reader ", "secret");
+        assert_eq!(credentials.username, "reader");
+        // md5("reader:secret")
+        assert_eq!(credentials.api_key, "d86e2552797d076a6178f7b038ad6b69");
+        assert!(credentials.accepts("D86E2552797D076A6178F7B038AD6B69"));
+        assert!(!credentials.accepts("d86e2552797d076a6178f7b038ad6b6"));
+    }
+
+    #[test]
+    fn test_numeric_ids_are_stable_and_small() {
+        let id = numeric_id("0b6f3c3e-8f7a-4e43-9d8c-3f1f6a2b9e10");
+        assert_eq!(id, numeric_id("0b6f3c3e-8f7a-4e43-9d8c-3f1f6a2b9e10"));
+        assert_ne!(id, numeric_id("a-different-feed"));
+        assert!((0..1 << 48).contains(&id));
+        assert_eq!(unix_time("2025-09-30T09:00:00+00:00"), 1759222800);
+        assert_eq!(unix_time("yesterday"), 0);
+    }
+}
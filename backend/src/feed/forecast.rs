@@ -0,0 +1,294 @@
+//! Storage growth forecast for retention planning
+//!
+//! Each feed's growth is estimated from its stored items: the items published
+//! over a recent window give a rate per day, and the recorded body sizes give
+//! an average size per item. Retention settings cap how much a feed can hold,
+//! so a feed with `max_items` or `max_age_days` levels off while one without
+//! keeps growing. Projecting every feed forward shows when the items would
+//! outgrow a size budget (`STORAGE_BUDGET_MB`), and when they would, retention
+//! settings that keep them within it are recommended.
+//!
+//! Sizes are those of the stored email bodies, which make up most of the
+//! database; items without a recorded size, from before sizes were kept, are
+//! assumed to be of average size (run the metadata backfill to record them).
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::background::cleanup::FeedRetention;
+use crate::db::{
+    connection::DatabasePool,
+    models::{Feed, FeedStorageStats},
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric},
+};
+
+/// Days of items the rates are computed from when none is given
+pub const DEFAULT_WINDOW_DAYS: i64 = 30;
+
+/// How far ahead the budget is looked for
+const HORIZON_DAYS: i64 = 3650;
+
+/// Projected storage of a feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FeedForecast {
+    pub feed_id: String,
+    pub title: String,
+    pub item_count: i64,
+    /// Items without a recorded body size, counted at the average size
+    pub unsized_items: i64,
+    pub average_item_bytes: i64,
+    pub stored_bytes: i64,
+    pub items_per_day: f64,
+    pub bytes_per_day: f64,
+    pub max_items: Option<i32>,
+    pub max_age_days: Option<i32>,
+    /// Size the feed levels off at under its retention settings; null when it keeps growing
+    pub steady_state_bytes: Option<i64>,
+    /// Suggested `max_items` to stay within the budget
+    pub recommended_max_items: Option<i32>,
+    /// Suggested `max_age_days` to stay within the budget
+    pub recommended_max_age_days: Option<i32>,
+}
+
+/// Projected storage of all feeds against a size budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StorageForecast {
+    pub generated_at: String,
+    pub window_days: i64,
+    pub budget_bytes: Option<i64>,
+    pub stored_bytes: i64,
+    pub bytes_per_day: f64,
+    /// Size all feeds level off at; null when some feed keeps growing
+    pub steady_state_bytes: Option<i64>,
+    /// Date the budget is projected to be reached; null without a budget or
+    /// when it is not reached within ten years
+    pub budget_reached_at: Option<String>,
+    pub days_until_budget: Option<i64>,
+    /// Feeds, growing fastest first
+    pub feeds: Vec<FeedForecast>,
+}
+
+/// Size budget from `STORAGE_BUDGET_MB`, in bytes
+pub fn budget_from_env() -> Option<i64> {
+    std::env::var("STORAGE_BUDGET_MB")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|megabytes| *megabytes > 0)
+        .map(|megabytes| megabytes * 1024 * 1024)
+}
+
+/// Forecast storage from the items stored now
+pub fn forecast(pool: &DatabasePool, window_days: i64, budget_bytes: Option<i64>) -> Result<StorageForecast> {
+    let now = Utc::now();
+    let since = (now - Duration::days(window_days)).to_rfc3339();
+    let stats = FeedItemOpsGeneric::storage_stats(pool, &since)?;
+    // Feeds are projected with the limits cleanup enforces on them
+    let defaults = FeedRetention::load(pool)?;
+    let feeds: Vec<Feed> = FeedOpsGeneric::get_all(pool)?.iter().map(|feed| defaults.apply(feed)).collect();
+    Ok(project(&feeds, &stats, window_days, budget_bytes, now))
+}
+
+/// Forecast storage from per-feed item statistics
+pub fn project(
+    feeds: &[Feed],
+    stats: &[FeedStorageStats],
+    window_days: i64,
+    budget_bytes: Option<i64>,
+    now: DateTime<Utc>,
+) -> StorageForecast {
+    let mut forecasts: Vec<FeedForecast> = feeds
+        .iter()
+        .map(|feed| {
+            let feed_id = feed.id.clone().unwrap_or_default();
+            let stats = stats.iter().find(|stats| stats.feed_id == feed_id).cloned().unwrap_or_default();
+            feed_forecast(feed, feed_id, &stats, window_days, now)
+        })
+        .collect();
+    forecasts.sort_by(|a, b| b.bytes_per_day.total_cmp(&a.bytes_per_day));
+
+    let stored_bytes = forecasts.iter().map(|feed| feed.stored_bytes).sum();
+    let bytes_per_day = forecasts.iter().map(|feed| feed.bytes_per_day).sum();
+    let steady_state_bytes: Option<i64> = forecasts.iter().map(|feed| feed.steady_state_bytes).sum();
+
+    let days_until_budget = budget_bytes.and_then(|budget| days_until(&forecasts, budget));
+    if let Some(budget) = budget_bytes {
+        if steady_state_bytes.is_none_or(|bytes| bytes > budget) {
+            recommend(&mut forecasts, budget);
+        }
+    }
+
+    StorageForecast {
+        generated_at: now.to_rfc3339(),
+        window_days,
+        budget_bytes,
+        stored_bytes,
+        bytes_per_day,
+        steady_state_bytes,
+        budget_reached_at: days_until_budget.map(|days| (now + Duration::days(days)).date_naive().to_string()),
+        days_until_budget,
+        feeds: forecasts,
+    }
+}
+
+fn feed_forecast(feed: &Feed, feed_id: String, stats: &FeedStorageStats, window_days: i64, now: DateTime<Utc>) -> FeedForecast {
+    let average_item_bytes = if stats.sized_items > 0 { stats.total_body_size / stats.sized_items } else { 0 };
+
+    // A feed younger than the window is measured over its own age
+    let age_days = stats.oldest_pub_date.as_deref()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map_or(0.0, |oldest| (now - oldest.with_timezone(&Utc)).num_seconds() as f64 / 86_400.0);
+    let sample_days = age_days.clamp(1.0, window_days.max(1) as f64);
+    let items_per_day = stats.recent_items as f64 / sample_days;
+
+    // Retention keeps at least min_items, whichever limit removes items
+    let min_items = feed.min_items.unwrap_or(0) as f64;
+    let by_count = feed.max_items.map(|max_items| (max_items as f64).max(min_items));
+    let by_age = feed.max_age_days.map(|max_age_days| (items_per_day * max_age_days as f64).max(min_items));
+    let steady_items = match (by_count, by_age) {
+        (Some(count), Some(age)) => Some(count.min(age)),
+        (count, age) => count.or(age),
+    };
+
+    FeedForecast {
+        feed_id,
+        title: feed.title.clone(),
+        item_count: stats.item_count,
+        unsized_items: stats.item_count - stats.sized_items,
+        average_item_bytes,
+        stored_bytes: stats.item_count * average_item_bytes,
+        items_per_day,
+        bytes_per_day: items_per_day * average_item_bytes as f64,
+        max_items: feed.max_items,
+        max_age_days: feed.max_age_days,
+        steady_state_bytes: steady_items.map(|items| (items * average_item_bytes as f64).round() as i64),
+        recommended_max_items: None,
+        recommended_max_age_days: None,
+    }
+}
+
+/// Size of the feed after `days` more days
+fn bytes_after(feed: &FeedForecast, days: i64) -> f64 {
+    let grown = feed.stored_bytes as f64 + feed.bytes_per_day * days as f64;
+    feed.steady_state_bytes.map_or(grown, |cap| grown.min(cap as f64))
+}
+
+fn days_until(feeds: &[FeedForecast], budget: i64) -> Option<i64> {
+    (0..=HORIZON_DAYS).find(|days| feeds.iter().map(|feed| bytes_after(feed, *days)).sum::<f64>() >= budget as f64)
+}
+
+/// Split the budget over the growing feeds by growth rate and suggest
+/// retention keeping each within its share; feeds that stay within their
+/// share already get no suggestion
+fn recommend(feeds: &mut [FeedForecast], budget: i64) {
+    let static_bytes: i64 = feeds.iter().filter(|feed| feed.bytes_per_day <= 0.0).map(|feed| feed.stored_bytes).sum();
+    let growth: f64 = feeds.iter().map(|feed| feed.bytes_per_day).sum();
+    let available = (budget - static_bytes) as f64;
+    if growth <= 0.0 || available <= 0.0 {
+        return;
+    }
+
+    for feed in feeds.iter_mut().filter(|feed| feed.bytes_per_day > 0.0) {
+        let share = available * feed.bytes_per_day / growth;
+        if feed.steady_state_bytes.is_some_and(|bytes| bytes as f64 <= share) {
+            continue;
+        }
+        let items = (share / feed.average_item_bytes as f64).floor();
+        let days = (share / feed.bytes_per_day).floor();
+        feed.recommended_max_items = Some(items.clamp(1.0, i32::MAX as f64) as i32);
+        feed.recommended_max_age_days = Some(days.clamp(1.0, i32::MAX as f64) as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(id: &str, max_items: Option<i32>, max_age_days: Option<i32>) -> Feed {
+        let now = Utc::now().to_rfc3339();
+        Feed {
+            id: Some(id.to_string()),
+            title: id.to_string(),
+            description: None,
+            link: None,
+            email_rule_id: "rule".to_string(),
+            feed_type: "rss".to_string(),
+            is_active: true,
+            created_at: now.clone(),
+            updated_at: now,
+            max_items,
+            max_age_days,
+            min_items: None,
+            summary_length: None,
+            locale: None,
+            timezone: None,
+            title_template: None,
+            webhook_url: None,
+            webhook_method: None,
+            webhook_body: None,
+            public_access: None,
+            max_pinned: None,
+            auto_titles: None,
+            page_css: None,
+            page_header_html: None,
+            page_footer_html: None,
+            page_logo_url: None,
+            append_only: false,
+            chain_head: None,
+            digest_mode: None,
+            description_template: None,
+            item_limit: None,
+            full_content: None,
+            auth_username: None,
+            auth_password_hash: None,
+            dedup_scope: None,
+        }
+    }
+
+    fn stats(id: &str, item_count: i64, recent_items: i64, now: DateTime<Utc>) -> FeedStorageStats {
+        FeedStorageStats {
+            feed_id: id.to_string(),
+            item_count,
+            sized_items: item_count,
+            total_body_size: item_count * 1000,
+            recent_items,
+            oldest_pub_date: Some((now - Duration::days(90)).to_rfc3339()),
+        }
+    }
+
+    #[test]
+    fn test_retention_levels_growth_off() {
+        let now = Utc::now();
+        let feeds = [feed("capped", Some(100), None), feed("aged", None, Some(7)), feed("open", None, None)];
+        let stats = [stats("capped", 50, 300, now), stats("aged", 50, 300, now), stats("open", 50, 300, now)];
+        let forecast = project(&feeds, &stats, 30, None, now);
+
+        for feed in &forecast.feeds {
+            assert_eq!(feed.items_per_day, 10.0);
+            assert_eq!(feed.bytes_per_day, 10_000.0);
+        }
+        let steady: Vec<_> = forecast.feeds.iter().map(|feed| (feed.feed_id.as_str(), feed.steady_state_bytes)).collect();
+        assert!(steady.contains(&("capped", Some(100_000))));
+        assert!(steady.contains(&("aged", Some(70_000))));
+        assert!(steady.contains(&("open", None)));
+        assert_eq!(forecast.steady_state_bytes, None);
+        assert_eq!(forecast.stored_bytes, 150_000);
+    }
+
+    #[test]
+    fn test_budget_date_and_recommendations() {
+        let now = Utc::now();
+        let feeds = [feed("open", None, None)];
+        let forecast = project(&feeds, &[stats("open", 50, 300, now)], 30, Some(250_000), now);
+
+        // 50 kB stored, growing 10 kB a day
+        assert_eq!(forecast.days_until_budget, Some(20));
+        assert_eq!(forecast.feeds[0].recommended_max_items, Some(250));
+        assert_eq!(forecast.feeds[0].recommended_max_age_days, Some(25));
+
+        let within = project(&[feed("open", Some(100), None)], &[stats("open", 50, 300, now)], 30, Some(250_000), now);
+        assert_eq!(within.days_until_budget, None);
+        assert_eq!(within.feeds[0].recommended_max_items, None);
+    }
+}
@@ -1,48 +1,137 @@
 use anyhow::Result;
-use atom_syndication::{Feed as AtomFeed, Entry, Person, Content, Text};
+use atom_syndication::{Feed as AtomFeed, Entry, Link, Person, Content, Text};
 use chrono::{DateTime, Utc};
 use rss::{Channel, Item, Guid};
+use std::collections::HashMap;
 use crate::db::models::{Feed, FeedItem};
+use crate::feed::attachments::Enclosure;
+use crate::feed::item_templates;
+use crate::feed::localization::{self, FeedLocalization};
+use crate::feed::output;
+use crate::feed::overflow::{item_html_path, item_page_path};
+
+/// Namespace of the RSS `content:encoded` element
+const CONTENT_NAMESPACE: &str = "http://purl.org/rss/1.0/modules/content/";
+
+/// Where a rendered feed is served, for its self link and item permalinks
+#[derive(Debug, Clone, Default)]
+pub struct FeedLinks {
+    /// Absolute URL of the feed document itself
+    pub self_url: Option<String>,
+    /// Configured public base URL; items link to their pages under it
+    pub base_url: Option<String>,
+    /// Base URL the feed was requested under; items link to their HTML pages under it
+    pub request_base_url: Option<String>,
+}
+
+impl FeedLinks {
+    /// Absolute URL of an item's page in `feed_id`, when the base URL is configured
+    fn item_url(&self, feed_id: &str, item: &FeedItem) -> Option<String> {
+        let base_url = self.base_url.as_ref()?;
+        Some(format!("{}{}", base_url, item_page_path(feed_id, item.id.as_ref()?)))
+    }
+
+    /// Where readers open an item: its own web link, else its HTML page when
+    /// the feed's URL is known; stored `mailto:` links only as a last resort
+    fn item_link(&self, item: &FeedItem) -> Option<String> {
+        match &item.link {
+            Some(link) if !link.starts_with("mailto:") => Some(link.clone()),
+            link => self.request_base_url.as_ref()
+                .zip(item.id.as_ref())
+                .map(|(base_url, id)| format!("{}{}", base_url, item_html_path(id)))
+                .or_else(|| link.clone()),
+        }
+    }
+}
 
 pub struct FeedGenerator;
 
 impl FeedGenerator {
     pub fn generate_rss(feed: &Feed, items: &[FeedItem]) -> Result<String> {
+        Self::generate_rss_with_enclosures(feed, items, &HashMap::new(), &FeedLinks::default())
+    }
+    
+    /// RSS with the first of each item's `enclosures`, keyed by item ID
+    pub fn generate_rss_with_enclosures(feed: &Feed, items: &[FeedItem], enclosures: &HashMap<String, Vec<Enclosure>>, links: &FeedLinks) -> Result<String> {
         let mut channel = Channel::default();
         
         channel.set_title(&feed.title);
         channel.set_description(feed.description.as_deref().unwrap_or("Mail2Feed RSS"));
-        channel.set_link(feed.link.as_deref().unwrap_or("#"));
+        channel.set_link(feed.link.as_deref().or(links.self_url.as_deref()).unwrap_or("#"));
+        if let Some(self_url) = &links.self_url {
+            channel.set_atom_ext(Some(rss::extension::atom::AtomExtension {
+                links: vec![Self::self_link(self_url, "application/rss+xml")],
+            }));
+        }
         
+        let localization = FeedLocalization::for_feed(feed);
+        let full_content = output::full_content(feed);
         let mut rss_items = Vec::new();
         
         for item in items {
             let mut rss_item = Item::default();
             
-            rss_item.set_title(Some(item.title.clone()));
-            rss_item.set_description(item.description.clone());
-            rss_item.set_link(item.link.clone());
+            rss_item.set_title(Some(item_templates::render_title(feed, item, &localization)));
+            rss_item.set_description(Self::description(feed, item, &localization));
+            rss_item.set_content(item.email_body_html.clone().filter(|_| full_content));
+            rss_item.set_link(links.item_link(item));
             rss_item.set_author(item.author.clone());
-            rss_item.set_pub_date(Some(item.pub_date.clone()));
+            // RSS 2.0 requires RFC 822 dates; stored dates are RFC 3339
+            let pub_date = DateTime::parse_from_rfc3339(&item.pub_date)
+                .map(|date| date.to_rfc2822())
+                .unwrap_or_else(|_| item.pub_date.clone());
+            rss_item.set_pub_date(Some(pub_date));
             
-            // Create a unique GUID for the item
-            let feed_id = feed.id.as_ref().map_or("unknown", |v| v);
-            let item_id = item.id.as_ref().map_or("unknown", |v| v);
-            let guid = Guid {
-                value: format!("{}_{}", feed_id, item_id),
-                permalink: false,
+            // The item's page when the public base URL is configured, otherwise a unique
+            // GUID; both stay with the feed an item was first published in when it moves.
+            // GUIDs do not follow the item link, so readers don't show old items again.
+            // The unique GUID comes from the email's content hash, so an email stored
+            // again, e.g. after a rollback, is not shown as a new item
+            let feed_id = item.origin_feed_id.as_deref().or(feed.id.as_deref()).unwrap_or("unknown");
+            let guid = match links.item_url(feed_id, item) {
+                Some(url) => Guid { value: url, permalink: true },
+                None => {
+                    let item_id = item.guid.as_deref().or(item.id.as_deref()).unwrap_or("unknown");
+                    Guid { value: format!("{}_{}", feed_id, item_id), permalink: false }
+                }
             };
             rss_item.set_guid(Some(guid));
             
+            // RSS 2.0 allows a single enclosure per item
+            if let Some(enclosure) = item.id.as_ref().and_then(|id| enclosures.get(id)).and_then(|list| list.first()) {
+                rss_item.set_enclosure(Some(rss::Enclosure {
+                    url: enclosure.url.clone(),
+                    length: enclosure.length.to_string(),
+                    mime_type: enclosure.content_type.clone(),
+                }));
+            }
+            
+            if let Some(link) = Self::unsubscribe_link(item) {
+                rss_item.set_atom_ext(Some(rss::extension::atom::AtomExtension { links: vec![link] }));
+            }
+            
             rss_items.push(rss_item);
         }
         
+        // Sanitized HTML bodies go into content:encoded
+        if rss_items.iter().any(|item| item.content.is_some()) {
+            channel.namespaces.insert("content".to_string(), CONTENT_NAMESPACE.to_string());
+        }
+        // Unsubscribe links are atom:link elements
+        if rss_items.iter().any(|item| item.atom_ext.is_some()) {
+            channel.namespaces.insert("atom".to_string(), rss::extension::atom::NAMESPACE.to_string());
+        }
         channel.set_items(rss_items);
         
         Ok(channel.to_string())
     }
     
     pub fn generate_atom(feed: &Feed, items: &[FeedItem]) -> Result<String> {
+        Self::generate_atom_with_enclosures(feed, items, &HashMap::new(), &FeedLinks::default())
+    }
+    
+    /// Atom with an enclosure link for each of an item's `enclosures`, keyed by item ID
+    pub fn generate_atom_with_enclosures(feed: &Feed, items: &[FeedItem], enclosures: &HashMap<String, Vec<Enclosure>>, links: &FeedLinks) -> Result<String> {
         let mut atom_feed = AtomFeed::default();
         
         atom_feed.set_title(feed.title.clone());
@@ -53,7 +142,12 @@ impl FeedGenerator {
         if let Some(description) = &feed.description {
             atom_feed.set_subtitle(Text::plain(description.clone()));
         }
+        if let Some(self_url) = &links.self_url {
+            atom_feed.set_links(vec![Self::self_link(self_url, "application/atom+xml")]);
+        }
         
+        let localization = FeedLocalization::for_feed(feed);
+        let full_content = output::full_content(feed);
         let mut entries = Vec::new();
         
         for item in items {
@@ -61,7 +155,7 @@ impl FeedGenerator {
             
             let item_id = item.id.as_ref().map_or("unknown", |v| v);
             entry.set_id(format!("urn:uuid:{}", item_id));
-            entry.set_title(item.title.clone());
+            entry.set_title(item_templates::render_title(feed, item, &localization));
             
             // Parse the pub_date string to DateTime<Utc>
             if let Ok(pub_date) = DateTime::parse_from_rfc3339(&item.pub_date) {
@@ -76,11 +170,21 @@ impl FeedGenerator {
                 entry.set_updated(now);
             }
             
-            if let Some(description) = &item.description {
+            // The sanitized HTML body when there is one and the feed includes
+            // full content, with the description as summary
+            let description = Self::description(feed, item, &localization);
+            let content = match item.email_body_html.as_ref().filter(|_| full_content) {
+                Some(html) => {
+                    entry.set_summary(description.map(Text::html));
+                    Some(html.clone())
+                }
+                None => description,
+            };
+            if let Some(content) = content {
                 let content = Content {
                     content_type: Some("html".to_string()),
                     src: None,
-                    value: Some(description.clone()),
+                    value: Some(content),
                     base: None,
                     lang: None,
                 };
@@ -96,6 +200,22 @@ impl FeedGenerator {
                 entry.set_authors(vec![author]);
             }
             
+            let mut entry_links = Vec::new();
+            if let Some(href) = links.item_link(item).or_else(|| links.item_url(&item.feed_id, item)) {
+                entry_links.push(Link { href, rel: "alternate".to_string(), ..Default::default() });
+            }
+            if let Some(list) = item.id.as_ref().and_then(|id| enclosures.get(id)) {
+                entry_links.extend(list.iter().map(|enclosure| Link {
+                    href: enclosure.url.clone(),
+                    rel: "enclosure".to_string(),
+                    mime_type: Some(enclosure.content_type.clone()),
+                    length: Some(enclosure.length.to_string()),
+                    ..Default::default()
+                }));
+            }
+            entry_links.extend(Self::unsubscribe_link(item));
+            entry.set_links(entry_links);
+            
             entries.push(entry);
         }
         
@@ -104,6 +224,33 @@ impl FeedGenerator {
         Ok(atom_feed.to_string())
     }
     
+    fn self_link(href: &str, mime_type: &str) -> Link {
+        Link {
+            href: href.to_string(),
+            rel: "self".to_string(),
+            mime_type: Some(mime_type.to_string()),
+            ..Default::default()
+        }
+    }
+    
+    /// `rel="unsubscribe"` link to the item's unsubscribe page, or its
+    /// `mailto:` address when it has no page
+    fn unsubscribe_link(item: &FeedItem) -> Option<Link> {
+        let href = item.unsubscribe_url.clone().or_else(|| item.unsubscribe_mailto.clone())?;
+        Some(Link { href, rel: "unsubscribe".to_string(), ..Default::default() })
+    }
+    
+    /// Item description from the feed's template, headed by the localized date
+    /// when the feed has localization settings
+    fn description(feed: &Feed, item: &FeedItem, localization: &FeedLocalization) -> Option<String> {
+        let description = item_templates::render_description(feed, item, localization);
+        if localization::is_configured(feed) {
+            localization::render_description(item, description, localization)
+        } else {
+            description
+        }
+    }
+    
     #[allow(dead_code)]
     pub fn email_to_feed_item(
         feed_id: String,
@@ -130,6 +277,28 @@ impl FeedGenerator {
             is_read: Some(false),
             starred: Some(false),
             body_size: Some(body_size),
+            processing_run_id: None,
+            content_hash: None,
+            canonical_item_id: None,
+            language: None,
+            pinned: false,
+            importance: None,
+            category: None,
+            chain_previous: None,
+            chain_hash: None,
+            email_body_html: None,
+            body_ref: None,
+            rating: None,
+            origin_feed_id: None,
+            unsubscribe_url: None,
+            unsubscribe_mailto: None,
+            unsubscribe_one_click: false,
+            in_reply_to: None,
+            email_references: None,
+            thread_id: None,
+            digest_message_ids: None,
+            guid: None,
+            sync_id: None,
         }
     }
     
@@ -0,0 +1,87 @@
+//! Feed health items
+//!
+//! A feed whose account keeps failing simply stops getting items, which
+//! readers cannot tell apart from a quiet newsletter. With
+//! `FEED_HEALTH_WARNING_HOURS` set, a feed whose account has failed to
+//! complete a run for longer than that gets a single "mail2feed status" item
+//! at the top of its RSS and Atom output. The item is not stored; its ID and
+//! date stay the same for as long as the outage lasts, so readers show it
+//! once, and it disappears with the next completed run.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{Feed, FeedItem, ProcessingRunFilter, ProcessingRunStatus},
+    operations_generic::{EmailRuleOpsGeneric, ImapAccountOpsGeneric, ProcessingRunOpsGeneric},
+};
+use crate::feed::{generator::FeedGenerator, overflow::escape_html};
+
+/// Hours an account may go without a completed run before its feeds carry a
+/// status item; off when `FEED_HEALTH_WARNING_HOURS` is unset or 0
+pub fn warning_hours() -> Option<i64> {
+    std::env::var("FEED_HEALTH_WARNING_HOURS")
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .filter(|hours| *hours > 0)
+}
+
+/// The status item for a feed whose account has been failing for more than
+/// `hours` at `now`, if it has
+///
+/// An account is failing when a run failed after its last completed run; it
+/// has been failing since that completed run, or since it was created when
+/// none ever completed. Feeds of inactive rules get no status item.
+pub fn status_item(pool: &DatabasePool, feed: &Feed, hours: i64, now: DateTime<Utc>) -> Result<Option<FeedItem>> {
+    let rule = EmailRuleOpsGeneric::get_by_id(pool, &feed.email_rule_id)?;
+    if !rule.is_active {
+        return Ok(None);
+    }
+    let account = ImapAccountOpsGeneric::get_by_id(pool, &rule.imap_account_id)?;
+    let failing_since = match ProcessingRunOpsGeneric::last_completed_start(pool, &rule.imap_account_id)? {
+        Some(started_at) => started_at,
+        None => account.created_at.clone(),
+    };
+    let Ok(failing_since_at) = DateTime::parse_from_rfc3339(&failing_since) else {
+        return Ok(None);
+    };
+    let stale_at = failing_since_at.with_timezone(&Utc) + Duration::hours(hours);
+    if now < stale_at {
+        return Ok(None);
+    }
+
+    let latest_failure = ProcessingRunOpsGeneric::list(pool, &ProcessingRunFilter {
+        imap_account_id: Some(rule.imap_account_id.clone()),
+        status: Some(ProcessingRunStatus::Failed),
+        started_after: Some(failing_since.clone()),
+        limit: 1,
+        ..Default::default()
+    })?;
+    let Some(failure) = latest_failure.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let mut item = FeedGenerator::email_to_feed_item(
+        feed.id.clone().unwrap_or_default(),
+        &format!("mail2feed status: {} has not been checked for over {} hours", account.name, hours),
+        "mail2feed",
+        "",
+        None,
+        stale_at,
+    );
+    let error = failure.error_message.as_deref().unwrap_or("unknown error");
+    item.id = Some(format!("mail2feed-status-{}-{}", rule.imap_account_id, failing_since_at.timestamp()));
+    item.description = Some(format!(
+        "<p>mail2feed has not fetched mail for this feed from {} since {}. The last attempt failed: {}</p>\
+         <p>New items will appear once the account works again.</p>",
+        escape_html(&account.name),
+        failing_since_at.to_rfc2822(),
+        escape_html(error),
+    ));
+    item.email_subject = None;
+    item.email_from = None;
+    item.email_body = None;
+    item.body_size = None;
+    Ok(Some(item))
+}
@@ -0,0 +1,152 @@
+//! Per-feed templates for item titles and descriptions
+//!
+//! A feed may set `title_template` (e.g. `{{subject}} — {{from_name}}`) and
+//! `description_template` (e.g. `<p>From {{from}}</p>{{summary}}`), rendered
+//! for each item when the feed is generated. Only the placeholders in
+//! [`VARIABLES`] exist, and templates using others are rejected when the feed
+//! is saved. Title templates written before placeholders took double braces
+//! (`[{date}] {subject}`) keep working.
+//!
+//! `{{folder}}` is the folder of the feed's rule; it is filled in by
+//! [`template::resolve`](crate::feed::template::resolve) and renders empty
+//! when the feed was not resolved.
+
+use std::borrow::Cow;
+
+use anyhow::Result;
+
+use crate::db::models::{Feed, FeedItem};
+use crate::feed::localization::FeedLocalization;
+use crate::feed::overflow::escape_html;
+use crate::feed::{template, titles};
+use crate::imap::senders;
+
+/// Placeholders available in item title and description templates
+pub const VARIABLES: &[&str] = &[
+    "subject",
+    "from",
+    "from_name",
+    "from_address",
+    "date",
+    "folder",
+    "feed",
+    "title",
+    "summary",
+];
+
+/// Single-brace placeholders of the original title templates
+const LEGACY_VARIABLES: &[&str] = &["subject", "from", "date", "feed"];
+
+/// Check that an item template only uses known placeholders and is well-formed
+pub fn validate(template: &str) -> Result<()> {
+    template::validate_variables(&normalize(template), VARIABLES)
+}
+
+/// Item title rendered from the feed's title template, or the stored title
+pub fn render_title(feed: &Feed, item: &FeedItem, localization: &FeedLocalization) -> String {
+    match configured(&feed.title_template) {
+        Some(template) => render(template, feed, item, localization, false),
+        None => item.title.clone(),
+    }
+}
+
+/// Item description rendered from the feed's description template, or the
+/// stored description
+///
+/// Email values are HTML-escaped; `{{summary}}` is the stored description,
+/// which already is HTML.
+pub fn render_description(feed: &Feed, item: &FeedItem, localization: &FeedLocalization) -> Option<String> {
+    match configured(&feed.description_template) {
+        Some(template) => Some(render(template, feed, item, localization, true)),
+        None => item.description.clone(),
+    }
+}
+
+/// Fill in `{{folder}}`, leaving the item placeholders for generation
+///
+/// Templates that do not parse are returned as is.
+pub fn resolve_folder(template: &str, folder: &str) -> String {
+    let normalized = normalize(template);
+    template::render_variables(&normalized, VARIABLES, |variable| match variable {
+        "folder" => folder.to_string(),
+        _ => format!("{{{{{}}}}}", variable),
+    })
+    .unwrap_or_else(|_| template.to_string())
+}
+
+fn configured(template: &Option<String>) -> Option<&str> {
+    template.as_deref().filter(|template| !template.trim().is_empty())
+}
+
+/// Convert `{subject}`-style placeholders when the template has no `{{`
+fn normalize(template: &str) -> Cow<'_, str> {
+    if template::has_variables(template) {
+        return Cow::Borrowed(template);
+    }
+    let mut normalized = template.to_string();
+    for variable in LEGACY_VARIABLES {
+        normalized = normalized.replace(&format!("{{{}}}", variable), &format!("{{{{{}}}}}", variable));
+    }
+    Cow::Owned(normalized)
+}
+
+fn render(template: &str, feed: &Feed, item: &FeedItem, localization: &FeedLocalization, html: bool) -> String {
+    // Emails without a subject use the title derived from their body
+    let subject = item.email_subject.as_deref().filter(|subject| !titles::is_missing(subject)).unwrap_or(&item.title);
+    let from = item.email_from.as_deref().or(item.author.as_deref()).unwrap_or_default();
+
+    let value = |variable: &str| {
+        let text = match variable {
+            "subject" => subject.to_string(),
+            "from" => from.to_string(),
+            "from_name" => display_name(from),
+            "from_address" => senders::address(from),
+            "date" => localization.format_stored_date(&item.pub_date).unwrap_or_default(),
+            "feed" => feed.title.clone(),
+            "title" => item.title.clone(),
+            "summary" => return item.description.clone().unwrap_or_default(),
+            _ => String::new(),
+        };
+        if html { escape_html(&text) } else { text }
+    };
+    template::render_variables(&normalize(template), VARIABLES, value)
+        .unwrap_or_else(|_| template.to_string())
+}
+
+/// Display name of a `From` header such as `"Rust Weekly" <news@example.com>`,
+/// or its address when it has none
+fn display_name(from: &str) -> String {
+    let name = from.rfind('<').map(|start| from[..start].trim().trim_matches('"').trim()).unwrap_or_default();
+    if name.is_empty() {
+        senders::address(from)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_placeholders_are_converted() {
+        assert_eq!(normalize("[{date}] {subject}"), "[{{date}}] {{subject}}");
+        assert_eq!(normalize("{{subject}} {date}"), "{{subject}} {date}");
+        assert!(validate("[{feed}] {unknown}").is_ok());
+        assert!(validate("{{subject}} — {{sender}}").is_err());
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(display_name("\"Rust Weekly\" <News@Example.com>"), "Rust Weekly");
+        assert_eq!(display_name("<news@example.com>"), "news@example.com");
+        assert_eq!(display_name("news@example.com"), "news@example.com");
+    }
+
+    #[test]
+    fn test_resolve_folder_keeps_item_placeholders() {
+        assert_eq!(resolve_folder("{{ subject }} ({{folder}})", "Lists/Rust"), "{{subject}} (Lists/Rust)");
+        assert_eq!(resolve_folder("[{date}] {subject}", "INBOX"), "[{{date}}] {{subject}}");
+        assert_eq!(resolve_folder("{{bogus}}", "INBOX"), "{{bogus}}");
+    }
+}
@@ -0,0 +1,98 @@
+//! Localized display dates
+//!
+//! Feeds may set a locale and a timezone for the human-readable dates shown
+//! inside item descriptions and, through item templates, titles. Machine-readable dates (RSS
+//! `pubDate`, Atom `published`/`updated`) are never localized so readers can
+//! always parse them.
+
+use anyhow::Result;
+use chrono::{DateTime, Locale, Utc};
+use chrono_tz::Tz;
+
+use crate::db::models::{Feed, FeedItem};
+
+/// Locale-dependent date and time representation (`D_T_FMT`)
+const DISPLAY_FORMAT: &str = "%c";
+
+/// Locale and timezone used to render a feed's display dates
+#[derive(Debug, Clone, Copy)]
+pub struct FeedLocalization {
+    locale: Locale,
+    timezone: Tz,
+}
+
+impl Default for FeedLocalization {
+    fn default() -> Self {
+        Self {
+            locale: Locale::POSIX,
+            timezone: Tz::UTC,
+        }
+    }
+}
+
+impl FeedLocalization {
+    /// Settings of a feed; unset or invalid values fall back to POSIX and UTC
+    pub fn for_feed(feed: &Feed) -> Self {
+        let default = Self::default();
+        Self {
+            locale: feed.locale.as_deref()
+                .and_then(|locale| parse_locale(locale).ok())
+                .unwrap_or(default.locale),
+            timezone: feed.timezone.as_deref()
+                .and_then(|timezone| parse_timezone(timezone).ok())
+                .unwrap_or(default.timezone),
+        }
+    }
+
+    /// Human-readable date in the feed's locale and timezone
+    pub fn format_date(&self, date: DateTime<Utc>) -> String {
+        date.with_timezone(&self.timezone)
+            .format_localized(DISPLAY_FORMAT, self.locale)
+            .to_string()
+    }
+
+    /// Display string for a stored RFC 3339 date, if it parses
+    pub fn format_stored_date(&self, date: &str) -> Option<String> {
+        parse_stored_date(date).map(|date| self.format_date(date))
+    }
+}
+
+/// Whether the feed renders display dates into its items
+pub fn is_configured(feed: &Feed) -> bool {
+    feed.locale.is_some() || feed.timezone.is_some()
+}
+
+/// Parse a locale such as `de_DE` or `pt-BR`
+pub fn parse_locale(locale: &str) -> Result<Locale> {
+    locale.trim().replace('-', "_").parse::<Locale>()
+        .map_err(|_| anyhow::anyhow!("Unknown locale '{}'", locale))
+}
+
+/// Parse an IANA timezone such as `Europe/Berlin`
+pub fn parse_timezone(timezone: &str) -> Result<Tz> {
+    timezone.trim().parse::<Tz>()
+        .map_err(|_| anyhow::anyhow!("Unknown timezone '{}'", timezone))
+}
+
+/// Item description with the localized date shown above the content
+pub fn render_description(item: &FeedItem, description: Option<String>, localization: &FeedLocalization) -> Option<String> {
+    let Some(date) = parse_stored_date(&item.pub_date) else {
+        return description;
+    };
+
+    let header = format!(
+        "<p><time datetime=\"{}\">{}</time></p>",
+        date.to_rfc3339(),
+        localization.format_date(date)
+    );
+    Some(match description {
+        Some(description) => format!("{}{}", header, description),
+        None => header,
+    })
+}
+
+fn parse_stored_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
@@ -0,0 +1,58 @@
+//! Metadata computed from an email's content
+//!
+//! Shared by the processor when items are created and by the maintenance
+//! backfill that fills these fields in for older rows.
+
+use crate::db::models::FeedItem;
+use crate::feed::{dedup, summarizer};
+
+/// ISO 639-3 code stored when the language cannot be determined
+pub const UNDETERMINED_LANGUAGE: &str = "und";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedMetadata {
+    pub body_size: i32,
+    pub content_hash: String,
+    pub language: String,
+}
+
+impl ComputedMetadata {
+    pub fn compute(subject: &str, from: &str, body: Option<&str>) -> Self {
+        let body = body.unwrap_or_default();
+        Self {
+            body_size: body.len() as i32,
+            content_hash: dedup::content_hash(subject, from, body),
+            language: detect_language(body),
+        }
+    }
+
+    /// Compute metadata for an existing item, falling back to its description
+    /// for language detection when the body is not stored locally
+    pub fn for_item(item: &FeedItem) -> Self {
+        let subject = item.email_subject.as_deref().unwrap_or(&item.title);
+        let from = item.email_from.as_deref().unwrap_or_default();
+        let mut metadata = Self::compute(subject, from, item.email_body.as_deref());
+
+        if item.email_body.is_none() {
+            // Linked items keep the size and hash recorded when they were created
+            metadata.body_size = item.body_size.unwrap_or(0);
+            if let Some(hash) = &item.content_hash {
+                metadata.content_hash = hash.clone();
+            }
+            if let Some(description) = &item.description {
+                metadata.language = detect_language(description);
+            }
+        }
+
+        metadata
+    }
+}
+
+/// Detect the language of an email body (plain text or HTML)
+pub fn detect_language(body: &str) -> String {
+    let text = summarizer::html_to_text(body);
+    whatlang::detect(&text)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code().to_string())
+        .unwrap_or_else(|| UNDETERMINED_LANGUAGE.to_string())
+}
@@ -1,4 +1,37 @@
+pub mod attachments;
+pub mod basic_auth;
+pub mod blob;
+pub mod bodies;
+pub mod branding;
+pub mod chain;
+pub mod chat;
+pub mod content_filters;
+pub mod dedup;
+pub mod export;
+pub mod fever;
+pub mod digest;
+pub mod forecast;
+pub mod delivery;
 pub mod generator;
+pub mod health;
+pub mod item_templates;
+pub mod localization;
+pub mod metadata;
+pub mod output;
+pub mod overflow;
+pub mod permalink;
+pub mod pinning;
+pub mod public_url;
+pub mod ratings;
+pub mod reorganize;
+pub mod routing;
+pub mod s3;
+pub mod sanitize;
+pub mod summarizer;
+pub mod template;
+pub mod titles;
+pub mod unsubscribe;
+pub mod webhook;
 
 // Phase 3: Feed generation will be implemented
-// pub use generator::FeedGenerator;
\ No newline at end of file
+// pub use generator::FeedGenerator;
@@ -0,0 +1,45 @@
+//! Per-feed output settings of the rendered RSS and Atom documents
+//!
+//! A feed can serve fewer or more items than `FEED_ITEM_LIMIT`, and can
+//! leave the full HTML bodies out so readers only get the summaries. The
+//! summary length applies when items are stored, and again when the feed is
+//! rendered, so changing it also shortens or lengthens the items already in
+//! the feed. Stored items are never changed.
+
+use crate::db::models::{Feed, FeedItem};
+use crate::feed::summarizer::{self, DEFAULT_SUMMARY_LENGTH};
+use crate::settings;
+
+/// Most items rendered in the feed: its own limit, else `FEED_ITEM_LIMIT`
+pub fn item_limit(feed: &Feed) -> i64 {
+    feed.item_limit
+        .filter(|limit| *limit > 0)
+        .map_or_else(settings::feed_item_limit, i64::from)
+}
+
+/// Characters in the feed's item summaries
+pub fn summary_length(feed: &Feed) -> usize {
+    feed.summary_length
+        .filter(|length| *length > 0)
+        .map_or(DEFAULT_SUMMARY_LENGTH, |length| length as usize)
+}
+
+/// Whether rendered items carry their full HTML body; unset includes it
+pub fn full_content(feed: &Feed) -> bool {
+    feed.full_content.unwrap_or(true)
+}
+
+/// Summarize the descriptions of `items` from their bodies to the feed's
+/// summary length when it has one; items without a loaded body keep theirs
+pub fn apply_summary_length(feed: &Feed, items: &mut [FeedItem]) {
+    if feed.summary_length.is_none() {
+        return;
+    }
+    let length = summary_length(feed);
+    for item in items.iter_mut() {
+        if let Some(body) = item.email_body.as_deref().filter(|body| !body.trim().is_empty()) {
+            item.description = Some(summarizer::summarize(body, length));
+        }
+    }
+}
+
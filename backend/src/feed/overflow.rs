@@ -0,0 +1,100 @@
+//! Hard size cap on item content in generated feeds
+//!
+//! Some promotional emails carry megabytes of HTML, which breaks feed
+//! readers. Items whose content exceeds `FEED_ITEM_MAX_BYTES` are replaced in
+//! the feed by a plain-text preview and a link to the hosted item page. The
+//! stored item is never changed, so the API and the item page still return
+//! the complete body.
+
+use crate::db::models::{Feed, FeedItem};
+use crate::feed::{branding, summarizer};
+
+/// Cap used when `FEED_ITEM_MAX_BYTES` is not set
+pub const DEFAULT_MAX_ITEM_BYTES: usize = 256 * 1024;
+
+/// Length of the preview shown in place of an oversized item
+const PREVIEW_CHARS: usize = summarizer::DEFAULT_SUMMARY_LENGTH;
+
+/// Largest item content, in bytes, emitted into a feed; `None` when the cap is
+/// disabled with `FEED_ITEM_MAX_BYTES=0`
+pub fn max_item_bytes() -> Option<usize> {
+    let max_bytes = std::env::var("FEED_ITEM_MAX_BYTES")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_ITEM_BYTES);
+    (max_bytes > 0).then_some(max_bytes)
+}
+
+/// Path of the hosted page showing an item in full
+pub fn item_page_path(feed_id: &str, item_id: &str) -> String {
+    format!("/feeds/{}/items/{}", feed_id, item_id)
+}
+
+/// Path of the standalone HTML page of an item, wherever its feed is
+pub fn item_html_path(item_id: &str) -> String {
+    format!("/items/{}/html", item_id)
+}
+
+/// Replace the content of items over `max_bytes` with a preview linking to
+/// their item page under `base_url`; HTML bodies over it are left out, so the
+/// preview stands in for them
+pub fn cap_items(items: &mut [FeedItem], max_bytes: usize, base_url: &str) {
+    for item in items.iter_mut() {
+        if item.email_body_html.as_ref().is_some_and(|html| html.len() > max_bytes) {
+            item.email_body_html = None;
+        }
+        let (Some(item_id), Some(description)) = (&item.id, &item.description) else {
+            continue;
+        };
+        if description.len() <= max_bytes {
+            continue;
+        }
+
+        let url = format!("{}{}", base_url, item_page_path(&item.feed_id, item_id));
+        let preview = summarizer::summarize(description, PREVIEW_CHARS.min(max_bytes));
+        item.description = Some(format!(
+            "<p>{}</p><p><a href=\"{}\">Read the full message ({} KB)</a></p>",
+            escape_html(&preview),
+            escape_html(&url),
+            description.len() / 1024
+        ));
+    }
+}
+
+/// Standalone HTML page with the item's complete body, branded as set on its feed
+///
+/// Shows the sanitized HTML body when there is one, and falls back to the
+/// description for items stored without a body. Plain-text bodies are shown
+/// preformatted.
+pub fn render_item_page(feed: &Feed, item: &FeedItem) -> String {
+    let body = item.email_body_html.as_deref()
+        .or(item.email_body.as_deref())
+        .or(item.description.as_deref())
+        .unwrap_or_default();
+    let content = if looks_like_html(body) {
+        body.to_string()
+    } else {
+        format!("<pre style=\"white-space: pre-wrap\">{}</pre>", escape_html(body))
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>{}</head><body>{}{}{}</body></html>\n",
+        escape_html(&item.title),
+        branding::style(feed),
+        branding::header(feed),
+        content,
+        branding::footer(feed)
+    )
+}
+
+pub(crate) fn looks_like_html(body: &str) -> bool {
+    let lower = body.to_ascii_lowercase();
+    ["<html", "<body", "<div", "<p>", "<table", "<br"].iter().any(|tag| lower.contains(tag))
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
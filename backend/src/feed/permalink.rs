@@ -0,0 +1,131 @@
+//! Signed item permalinks
+//!
+//! A signed link (`/feed-items/{id}/html?exp=...&sig=...`) shows one item's
+//! hosted page to whoever holds it, whether or not the item's feed is public,
+//! so a single newsletter issue can be shared without sharing the feed. The
+//! signature is an HMAC-SHA256 under `FEED_SIGNING_KEY` of the item ID and the
+//! optional expiry; without the key no links can be made or followed.
+
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+/// Shortest signing key accepted, in bytes
+pub const MIN_KEY_BYTES: usize = 32;
+
+/// Why a signed link was refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    /// The link has no signature or one that does not match
+    InvalidSignature,
+    /// The link was valid until the given Unix time
+    Expired(i64),
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::InvalidSignature => write!(f, "Invalid link signature"),
+            LinkError::Expired(expires) => write!(f, "Link expired at {}", expires),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+/// Query of a signed link
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SignedLinkQuery {
+    /// Unix time after which the link stops working; none for links that do not expire
+    pub exp: Option<i64>,
+    pub sig: Option<String>,
+}
+
+/// Key for signing links from `FEED_SIGNING_KEY`; `None` when unset or
+/// shorter than `MIN_KEY_BYTES`
+pub fn signing_key() -> Option<Vec<u8>> {
+    std::env::var("FEED_SIGNING_KEY")
+        .ok()
+        .map(|key| key.trim().as_bytes().to_vec())
+        .filter(|key| key.len() >= MIN_KEY_BYTES)
+}
+
+/// Hex HMAC-SHA256 of the item ID and expiry
+pub fn sign(key: &[u8], item_id: &str, expires: Option<i64>) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(item_id.as_bytes());
+    mac.update(b"\n");
+    if let Some(expires) = expires {
+        mac.update(expires.to_string().as_bytes());
+    }
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Check a link's signature and expiry at Unix time `now`
+pub fn verify(key: &[u8], item_id: &str, query: &SignedLinkQuery, now: i64) -> Result<(), LinkError> {
+    let signature = query.sig.as_deref().ok_or(LinkError::InvalidSignature)?;
+    let expected = sign(key, item_id, query.exp);
+    // Compare every byte so the time taken does not reveal the matching prefix
+    let matches = signature.len() == expected.len()
+        && signature.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+    if !matches {
+        return Err(LinkError::InvalidSignature);
+    }
+    match query.exp {
+        Some(expires) if expires < now => Err(LinkError::Expired(expires)),
+        _ => Ok(()),
+    }
+}
+
+/// Path of the signed link to an item
+pub fn item_path(key: &[u8], item_id: &str, expires: Option<i64>) -> String {
+    let signature = sign(key, item_id, expires);
+    match expires {
+        Some(expires) => format!("/feed-items/{}/html?exp={}&sig={}", item_id, expires, signature),
+        None => format!("/feed-items/{}/html?sig={}", item_id, signature),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn query(exp: Option<i64>, sig: &str) -> SignedLinkQuery {
+        SignedLinkQuery { exp, sig: Some(sig.to_string()) }
+    }
+
+    #[test]
+    fn test_signed_links_verify_until_expiry() {
+        let signature = sign(KEY, "item-1", Some(1_000));
+        assert_eq!(verify(KEY, "item-1", &query(Some(1_000), &signature), 999), Ok(()));
+        assert_eq!(verify(KEY, "item-1", &query(Some(1_000), &signature), 1_001), Err(LinkError::Expired(1_000)));
+
+        let forever = sign(KEY, "item-1", None);
+        assert_eq!(verify(KEY, "item-1", &query(None, &forever), i64::MAX), Ok(()));
+    }
+
+    #[test]
+    fn test_tampered_links_are_rejected() {
+        let signature = sign(KEY, "item-1", Some(1_000));
+        // Another item, a later expiry, a dropped expiry or another key
+        assert_eq!(verify(KEY, "item-2", &query(Some(1_000), &signature), 0), Err(LinkError::InvalidSignature));
+        assert_eq!(verify(KEY, "item-1", &query(Some(9_000), &signature), 0), Err(LinkError::InvalidSignature));
+        assert_eq!(verify(KEY, "item-1", &query(None, &signature), 0), Err(LinkError::InvalidSignature));
+        let other = sign(b"another key of at least 32 bytes!", "item-1", Some(1_000));
+        assert_eq!(verify(KEY, "item-1", &query(Some(1_000), &other), 0), Err(LinkError::InvalidSignature));
+        assert_eq!(verify(KEY, "item-1", &SignedLinkQuery::default(), 0), Err(LinkError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_item_path() {
+        let path = item_path(KEY, "item-1", Some(1_000));
+        assert_eq!(path, format!("/feed-items/item-1/html?exp=1000&sig={}", sign(KEY, "item-1", Some(1_000))));
+        assert!(item_path(KEY, "item-1", None).starts_with("/feed-items/item-1/html?sig="));
+    }
+}
@@ -0,0 +1,50 @@
+//! Item pinning
+//!
+//! Pinned items stay at the top of a feed: they come before all other items
+//! in the generated RSS and Atom documents and in the items API, and retention
+//! cleanup never removes them. Each feed may pin at most `max_pinned` items
+//! (`DEFAULT_MAX_PINNED` when unset), so pins cannot crowd out new mail.
+
+use anyhow::Result;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{Feed, FeedItem},
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric},
+};
+
+/// Pinned items allowed in a feed without `max_pinned`
+pub const DEFAULT_MAX_PINNED: i32 = 10;
+
+/// Most items the feed may pin
+pub fn max_pinned(feed: &Feed) -> i64 {
+    feed.max_pinned.unwrap_or(DEFAULT_MAX_PINNED) as i64
+}
+
+/// Pinning refused because the feed already has its most pinned items
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinLimitReached {
+    pub feed_title: String,
+    pub limit: i64,
+}
+
+impl std::fmt::Display for PinLimitReached {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Feed '{}' allows at most {} pinned items; unpin one first", self.feed_title, self.limit)
+    }
+}
+
+impl std::error::Error for PinLimitReached {}
+
+/// Pin or unpin an item, refusing with `PinLimitReached` when its feed is full
+pub fn set_pinned(pool: &DatabasePool, item: &FeedItem, pinned: bool) -> Result<()> {
+    let item_id = item.id.as_deref().ok_or_else(|| anyhow::anyhow!("Feed item has no ID"))?;
+    if pinned && !item.pinned {
+        let feed = FeedOpsGeneric::get_by_id(pool, &item.feed_id)?;
+        let limit = max_pinned(&feed);
+        if FeedItemOpsGeneric::count_pinned(pool, &item.feed_id)? >= limit {
+            return Err(PinLimitReached { feed_title: feed.title, limit }.into());
+        }
+    }
+    FeedItemOpsGeneric::set_pinned(pool, item_id, pinned)
+}
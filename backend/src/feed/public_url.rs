@@ -0,0 +1,82 @@
+//! Public base URL of the instance
+//!
+//! Self links, item permalinks and enclosure URLs must point where readers
+//! reach the instance, which behind a reverse proxy is not where the backend
+//! listens. The base URL is, in order: the `public_base_url` setting changed
+//! with `PUT /api/settings`, `PUBLIC_BASE_URL` (or its older name
+//! `FEED_PUBLIC_URL`), and otherwise whatever the request's forwarding or
+//! `Host` headers say. Only a configured base URL turns item GUIDs into
+//! permalinks, since one derived from requests can differ between readers.
+
+use anyhow::Result;
+use reqwest::Url;
+use std::sync::RwLock;
+
+use crate::db::{connection::DatabasePool, models::AppSetting, operations_generic::AppSettingOpsGeneric};
+
+/// The stored setting, cached so links can be built without a database round trip
+static STORED: RwLock<Option<String>> = RwLock::new(None);
+
+/// Base URL from `PUBLIC_BASE_URL`, falling back to `FEED_PUBLIC_URL`
+pub fn from_env() -> Option<String> {
+    ["PUBLIC_BASE_URL", "FEED_PUBLIC_URL"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .find(|url| !url.is_empty())
+}
+
+/// The configured base URL without a trailing slash: the stored setting, or
+/// the environment's
+pub fn configured() -> Option<String> {
+    STORED.read().unwrap_or_else(|e| e.into_inner()).clone().or_else(from_env)
+}
+
+/// Cache the stored setting; called once the database is available
+pub fn load(pool: &DatabasePool) -> Result<()> {
+    let stored = AppSettingOpsGeneric::get(pool, AppSetting::PUBLIC_BASE_URL)?.map(|setting| setting.value);
+    *STORED.write().unwrap_or_else(|e| e.into_inner()) = stored;
+    Ok(())
+}
+
+/// Store `url` as the base URL, or remove the setting when it is empty so the
+/// environment applies again
+pub fn store(pool: &DatabasePool, url: &str) -> Result<()> {
+    let stored = if url.trim().is_empty() {
+        AppSettingOpsGeneric::delete(pool, AppSetting::PUBLIC_BASE_URL)?;
+        None
+    } else {
+        let url = normalize(url).map_err(anyhow::Error::msg)?;
+        Some(AppSettingOpsGeneric::set(pool, AppSetting::PUBLIC_BASE_URL, &url)?.value)
+    };
+    *STORED.write().unwrap_or_else(|e| e.into_inner()) = stored;
+    Ok(())
+}
+
+/// `url` without a trailing slash; an error message unless it is an absolute
+/// http(s) URL without a query or fragment
+pub fn normalize(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid public base URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("The public base URL '{}' must be an http or https URL with a host", url));
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(format!("The public base URL '{}' must not have a query or fragment", url));
+    }
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_public_base_url() {
+        assert_eq!(normalize(" https://mail2feed.example.com/ ").unwrap(), "https://mail2feed.example.com");
+        assert_eq!(normalize("http://proxy.local:8080/mail2feed").unwrap(), "http://proxy.local:8080/mail2feed");
+        assert!(normalize("mail2feed.example.com").is_err());
+        assert!(normalize("ftp://mail2feed.example.com").is_err());
+        assert!(normalize("https://mail2feed.example.com/?x=1").is_err());
+    }
+}
@@ -0,0 +1,190 @@
+//! Item ratings as relevance feedback
+//!
+//! Readers rate items thumbs up or down. The ratings are totalled per
+//! logical sender (alias groups count as one, as in the sender statistics)
+//! and per rule, and a sender a rule keeps bringing in that is rated down
+//! consistently is suggested for exclusion from that rule. A suggestion
+//! needs `MIN_RATINGS` ratings of the sender under the rule, at least
+//! `DOWN_SHARE` of them down, so a single bad issue does not count.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{EmailRule, Feed, Rating, RatingCount},
+    operations_generic::{EmailRuleOpsGeneric, FeedItemOpsGeneric, FeedOpsGeneric},
+};
+use crate::imap::senders::{self, SenderAliases};
+
+/// Ratings of a sender under a rule before it can be suggested for exclusion
+pub const MIN_RATINGS: i64 = 3;
+
+/// Share of down ratings from which a sender is suggested for exclusion
+pub const DOWN_SHARE: f64 = 0.8;
+
+/// Ratings of the items from one logical sender
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SenderRating {
+    /// Alias group name, or the address for senders without one
+    pub sender: String,
+    pub up: i64,
+    pub down: i64,
+    /// Share of up ratings, from 0 to 1
+    pub score: f64,
+}
+
+/// Ratings of the items a rule brought in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RuleRating {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub up: i64,
+    pub down: i64,
+    /// Share of up ratings, from 0 to 1
+    pub score: f64,
+}
+
+/// A suggested change to a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RuleSuggestion {
+    pub rule_id: String,
+    pub rule_name: String,
+    /// Sender to leave out of the rule
+    pub exclude_sender: String,
+    pub up: i64,
+    pub down: i64,
+    pub reason: String,
+}
+
+/// Ratings per sender and rule, with suggested rule refinements
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RatingReport {
+    pub generated_at: String,
+    /// Senders, lowest score first
+    pub senders: Vec<SenderRating>,
+    /// Rules, lowest score first
+    pub rules: Vec<RuleRating>,
+    pub suggestions: Vec<RuleSuggestion>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Tally {
+    up: i64,
+    down: i64,
+}
+
+impl Tally {
+    fn add(&mut self, rating: Rating, count: i64) {
+        match rating {
+            Rating::Up => self.up += count,
+            Rating::Down => self.down += count,
+        }
+    }
+
+    fn total(&self) -> i64 {
+        self.up + self.down
+    }
+
+    fn score(&self) -> f64 {
+        if self.total() == 0 { 0.0 } else { self.up as f64 / self.total() as f64 }
+    }
+}
+
+/// Report on the ratings stored now
+pub fn report(pool: &DatabasePool) -> Result<RatingReport> {
+    let counts = FeedItemOpsGeneric::rating_counts(pool)?;
+    let feeds = FeedOpsGeneric::get_all(pool)?;
+    let rules = EmailRuleOpsGeneric::get_all(pool)?;
+    let aliases = SenderAliases::load(pool)?;
+    Ok(build(&counts, &feeds, &rules, &aliases))
+}
+
+/// Build the report from rated item counts
+pub fn build(counts: &[RatingCount], feeds: &[Feed], rules: &[EmailRule], aliases: &SenderAliases) -> RatingReport {
+    let mut by_sender: Vec<(String, Tally)> = Vec::new();
+    let mut by_rule: Vec<(String, Tally)> = Vec::new();
+    let mut by_rule_sender: Vec<((String, String), Tally)> = Vec::new();
+
+    for (feed_id, from, rating, count) in counts {
+        let Some(rating) = rating.as_deref().and_then(Rating::parse) else {
+            continue;
+        };
+        let address = senders::address(from.as_deref().unwrap_or_default());
+        let sender = (!address.is_empty())
+            .then(|| aliases.canonical(&address).map_or_else(|| address.clone(), str::to_string));
+        let rule_id = feeds
+            .iter()
+            .find(|feed| feed.id.as_deref() == Some(feed_id.as_str()))
+            .map(|feed| feed.email_rule_id.clone());
+
+        if let Some(sender) = &sender {
+            tally(&mut by_sender, sender.clone()).add(rating, *count);
+        }
+        if let Some(rule_id) = &rule_id {
+            tally(&mut by_rule, rule_id.clone()).add(rating, *count);
+        }
+        if let (Some(sender), Some(rule_id)) = (sender, rule_id) {
+            tally(&mut by_rule_sender, (rule_id, sender)).add(rating, *count);
+        }
+    }
+
+    let rule_name = |rule_id: &str| {
+        rules
+            .iter()
+            .find(|rule| rule.id.as_deref() == Some(rule_id))
+            .map_or_else(|| rule_id.to_string(), |rule| rule.name.clone())
+    };
+
+    let mut sender_ratings: Vec<SenderRating> = by_sender
+        .into_iter()
+        .map(|(sender, tally)| SenderRating { sender, up: tally.up, down: tally.down, score: tally.score() })
+        .collect();
+    sender_ratings.sort_by(|a, b| a.score.total_cmp(&b.score).then_with(|| a.sender.cmp(&b.sender)));
+
+    let mut rule_ratings: Vec<RuleRating> = by_rule
+        .into_iter()
+        .map(|(rule_id, tally)| RuleRating {
+            rule_name: rule_name(&rule_id),
+            rule_id,
+            up: tally.up,
+            down: tally.down,
+            score: tally.score(),
+        })
+        .collect();
+    rule_ratings.sort_by(|a, b| a.score.total_cmp(&b.score).then_with(|| a.rule_name.cmp(&b.rule_name)));
+
+    let mut suggestions: Vec<RuleSuggestion> = by_rule_sender
+        .into_iter()
+        .filter(|(_, tally)| tally.total() >= MIN_RATINGS && tally.down as f64 >= DOWN_SHARE * tally.total() as f64)
+        .map(|((rule_id, sender), tally)| RuleSuggestion {
+            rule_name: rule_name(&rule_id),
+            reason: format!("{} of {} rated items from {} were rated down", tally.down, tally.total(), sender),
+            rule_id,
+            exclude_sender: sender,
+            up: tally.up,
+            down: tally.down,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.down.cmp(&a.down).then_with(|| a.exclude_sender.cmp(&b.exclude_sender)));
+
+    RatingReport {
+        generated_at: Utc::now().to_rfc3339(),
+        senders: sender_ratings,
+        rules: rule_ratings,
+        suggestions,
+    }
+}
+
+fn tally<K: PartialEq>(tallies: &mut Vec<(K, Tally)>, key: K) -> &mut Tally {
+    let index = match tallies.iter().position(|(existing, _)| *existing == key) {
+        Some(index) => index,
+        None => {
+            tallies.push((key, Tally::default()));
+            tallies.len() - 1
+        }
+    };
+    &mut tallies[index].1
+}
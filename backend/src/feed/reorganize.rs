@@ -0,0 +1,175 @@
+//! Merging and splitting feeds
+//!
+//! Merging moves every item of one feed into another and deletes the first,
+//! leaving a redirect so `/feeds/{old-id}/*` keeps working for subscribed
+//! readers. Splitting moves the items of a feed that match a filter into a
+//! new feed next to it. Moved items remember the feed they were first
+//! published in, and their GUIDs keep using it, so readers do not show them
+//! again. Both can be previewed without changing anything.
+//!
+//! Append-only feeds are chained by hash and can be neither merged nor split.
+
+use anyhow::Result;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{Feed, FeedItem, NewFeed},
+    operations_generic::FeedItemOpsGeneric,
+};
+use crate::imap::senders;
+
+/// Which items of a feed a split moves; every condition set must match
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitFilter {
+    /// Sender domain, covering its subdomains
+    pub sender_domain: Option<String>,
+    pub from_address: Option<String>,
+    /// Case-insensitive part of the subject
+    pub subject_contains: Option<String>,
+}
+
+impl SplitFilter {
+    pub fn is_empty(&self) -> bool {
+        self.sender_domain.is_none() && self.from_address.is_none() && self.subject_contains.is_none()
+    }
+
+    pub fn matches(&self, item: &FeedItem) -> bool {
+        let address = item.email_from.as_deref().map(senders::address).unwrap_or_default();
+        let domain = address.rsplit_once('@').map(|(_, domain)| domain).unwrap_or_default();
+        let subject = item.email_subject.as_deref().unwrap_or(&item.title).to_lowercase();
+
+        self.sender_domain.as_deref().map(senders::normalize).is_none_or(|wanted| {
+            domain == wanted || domain.ends_with(&format!(".{}", wanted))
+        }) && self.from_address.as_deref().is_none_or(|wanted| address == senders::address(wanted))
+            && self.subject_contains.as_deref().is_none_or(|wanted| subject.contains(&wanted.to_lowercase()))
+    }
+}
+
+/// What a merge or split moves
+#[derive(Debug, Clone)]
+pub struct Reorganization {
+    /// Items moved, or that would be on a dry run
+    pub items: Vec<FeedItem>,
+    /// Feed created by a split; none on a dry run and for merges
+    pub created_feed: Option<Feed>,
+}
+
+/// Why a feed cannot be merged or split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppendOnlyFeed(pub String);
+
+impl std::fmt::Display for AppendOnlyFeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Feed '{}' is append-only; its items cannot be moved", self.0)
+    }
+}
+
+impl std::error::Error for AppendOnlyFeed {}
+
+fn check_movable(feed: &Feed) -> Result<()> {
+    if feed.append_only {
+        return Err(AppendOnlyFeed(feed.id.clone().unwrap_or_default()).into());
+    }
+    Ok(())
+}
+
+fn item_ids(items: &[FeedItem]) -> Vec<String> {
+    items.iter().filter_map(|item| item.id.clone()).collect()
+}
+
+/// Move all items of `source` into `target`, then delete `source` and
+/// redirect its URLs to `target`
+pub fn merge(pool: &DatabasePool, source: &Feed, target: &Feed, dry_run: bool) -> Result<Reorganization> {
+    check_movable(source)?;
+    check_movable(target)?;
+    let source_id = source.id.clone().unwrap_or_default();
+    let target_id = target.id.clone().unwrap_or_default();
+    let items = FeedItemOpsGeneric::get_by_feed_id(pool, &source_id, None)?;
+
+    if !dry_run {
+        let ids = item_ids(&items);
+        pool.transaction(|tx| {
+            tx.move_feed_items(&ids, &target_id)?;
+            tx.redirect_feed(&source_id, &target_id)?;
+            tx.delete_feed(&source_id)
+        })?;
+    }
+    Ok(Reorganization { items, created_feed: None })
+}
+
+/// Move the items of `source` matching `filter` into a new feed titled
+/// `title` on `email_rule_id`, which takes the source's type, visibility and
+/// presentation settings
+pub fn split(
+    pool: &DatabasePool,
+    source: &Feed,
+    filter: &SplitFilter,
+    title: &str,
+    email_rule_id: &str,
+    dry_run: bool,
+) -> Result<Reorganization> {
+    check_movable(source)?;
+    let source_id = source.id.clone().unwrap_or_default();
+    let items: Vec<FeedItem> = FeedItemOpsGeneric::get_by_feed_id(pool, &source_id, None)?
+        .into_iter()
+        .filter(|item| filter.matches(item))
+        .collect();
+    if dry_run {
+        return Ok(Reorganization { items, created_feed: None });
+    }
+
+    let mut new_feed = NewFeed::new(title.to_string(), None, None, email_rule_id.to_string(), source.feed_type.clone(), true);
+    new_feed.public_access = source.public_access;
+    new_feed.summary_length = source.summary_length;
+    new_feed.locale = source.locale.clone();
+    new_feed.timezone = source.timezone.clone();
+    new_feed.title_template = source.title_template.clone();
+    new_feed.auto_titles = source.auto_titles;
+    new_feed.page_css = source.page_css.clone();
+    new_feed.page_header_html = source.page_header_html.clone();
+    new_feed.page_footer_html = source.page_footer_html.clone();
+    new_feed.page_logo_url = source.page_logo_url.clone();
+    new_feed.digest_mode = source.digest_mode.clone();
+    new_feed.description_template = source.description_template.clone();
+    new_feed.item_limit = source.item_limit;
+    new_feed.full_content = source.full_content;
+    new_feed.auth_username = source.auth_username.clone();
+    new_feed.auth_password_hash = source.auth_password_hash.clone();
+    new_feed.dedup_scope = source.dedup_scope.clone();
+
+    let ids = item_ids(&items);
+    let created_feed = pool.transaction(|tx| {
+        let feed = tx.create_feed(&new_feed)?;
+        tx.move_feed_items(&ids, feed.id.as_deref().unwrap_or_default())?;
+        Ok(feed)
+    })?;
+    Ok(Reorganization { items, created_feed: Some(created_feed) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::generator::FeedGenerator;
+    use chrono::Utc;
+
+    fn item(from: &str, subject: &str) -> FeedItem {
+        FeedGenerator::email_to_feed_item("feed".to_string(), subject, from, "", None, Utc::now())
+    }
+
+    #[test]
+    fn test_split_filter_matches() {
+        let domain = SplitFilter { sender_domain: Some("@Example.com".to_string()), ..Default::default() };
+        assert!(domain.matches(&item("News <news@example.com>", "Weekly")));
+        assert!(domain.matches(&item("alerts@mail.example.com", "Alert")));
+        assert!(!domain.matches(&item("news@notexample.com", "Weekly")));
+
+        let both = SplitFilter {
+            from_address: Some("News@Example.com".to_string()),
+            subject_contains: Some("weekly".to_string()),
+            ..Default::default()
+        };
+        assert!(both.matches(&item("News <news@example.com>", "The Weekly Digest")));
+        assert!(!both.matches(&item("News <news@example.com>", "Daily")));
+        assert!(SplitFilter::default().is_empty());
+    }
+}
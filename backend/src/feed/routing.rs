@@ -0,0 +1,46 @@
+//! Feeds a rule's matching emails go to
+//!
+//! Every rule delivers to the feeds it owns, and to feeds of other rules
+//! attached to it with `POST /api/email-rules/{id}/feeds`, so one newsletter
+//! can land in a topic feed and in an aggregate feed at once. Each active
+//! target gets its own item. Higher priorities are served first, which
+//! decides who still gets the email when the item quota runs out halfway;
+//! a rule's own feeds have priority 0.
+
+use anyhow::Result;
+
+use crate::db::{connection::DatabasePool, models::Feed, operations_generic::{FeedOpsGeneric, RuleFeedOpsGeneric}};
+
+/// A feed a rule delivers to
+#[derive(Debug, Clone)]
+pub struct RuleTarget {
+    pub feed: Feed,
+    pub priority: i32,
+    /// Attached to the rule rather than owned by it
+    pub attached: bool,
+}
+
+/// The feeds of rule `rule_id` by priority, its own first among equals
+pub fn targets(pool: &DatabasePool, rule_id: &str) -> Result<Vec<RuleTarget>> {
+    let own = FeedOpsGeneric::get_by_rule_id(pool, rule_id)?
+        .into_iter()
+        .map(|feed| (feed, 0, false));
+    let attached = RuleFeedOpsGeneric::get_feeds_by_rule_id(pool, rule_id)?
+        .into_iter()
+        .map(|(rule_feed, feed)| (feed, rule_feed.priority, true));
+
+    let mut targets: Vec<RuleTarget> = own.chain(attached)
+        .map(|(feed, priority, attached)| RuleTarget { feed, priority, attached })
+        .collect();
+    targets.sort_by_key(|target| std::cmp::Reverse(target.priority));
+    Ok(targets)
+}
+
+/// The active feeds rule `rule_id` delivers to, by priority
+pub fn active_feeds(pool: &DatabasePool, rule_id: &str) -> Result<Vec<Feed>> {
+    Ok(targets(pool, rule_id)?
+        .into_iter()
+        .filter(|target| target.feed.is_active)
+        .map(|target| target.feed)
+        .collect())
+}
@@ -0,0 +1,126 @@
+//! Sanitizing HTML email bodies for feeds
+//!
+//! The HTML part of an email is cleaned before it is stored, so feed readers
+//! and the item page can render it as is. Scripts, styles, forms, event
+//! handlers and `javascript:` links are removed, links open without a
+//! referrer, and tracking pixels (images of at most 1×1 pixels or with an
+//! open-tracking URL) lose their source so opening the item does not report
+//! back, unless the rule turned its tracking pixel filter off. With
+//! `FEED_BLOCK_REMOTE_IMAGES` enabled every remote image is dropped this way;
+//! only their alt text remains.
+
+use std::collections::HashSet;
+
+/// URL fragments of common open-tracking endpoints
+const TRACKER_PATTERNS: &[&str] = &["/track/open", "/wf/open", "/open.aspx", "/open.php", "/pixel", "beacon", "/o.gif"];
+
+/// Whether images served from other hosts should be stripped from stored HTML
+pub fn remote_images_blocked() -> bool {
+    std::env::var("FEED_BLOCK_REMOTE_IMAGES")
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Clean an HTML email body for display in feeds
+pub fn sanitize_html(html: &str, block_remote_images: bool, strip_tracking_pixels: bool) -> String {
+    let pixels = if strip_tracking_pixels { tiny_image_sources(html) } else { HashSet::new() };
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .attribute_filter(move |element, attribute, value| {
+            if (element, attribute) != ("img", "src") {
+                return Some(value.into());
+            }
+            let remote = value.starts_with("http://") || value.starts_with("https://") || value.starts_with("//");
+            let blocked = pixels.contains(value) || (strip_tracking_pixels && is_tracker(value)) || (block_remote_images && remote);
+            (!blocked).then(|| value.into())
+        })
+        .clean(html)
+        .to_string()
+}
+
+fn is_tracker(src: &str) -> bool {
+    let src = src.to_ascii_lowercase();
+    TRACKER_PATTERNS.iter().any(|pattern| src.contains(pattern))
+}
+
+/// Sources of `<img>` tags sized at most 1×1 pixels
+fn tiny_image_sources(html: &str) -> HashSet<String> {
+    // ASCII lowercasing keeps byte offsets, so they index `html` as well
+    let lower = html.to_ascii_lowercase();
+    let mut sources = HashSet::new();
+    let mut rest = 0;
+    while let Some(start) = lower[rest..].find("<img").map(|index| rest + index) {
+        let end = lower[start..].find('>').map_or(lower.len(), |index| start + index);
+        let tag = &html[start..end];
+        let tiny = |name| attribute(tag, name).and_then(|value| value.trim_end_matches("px").parse::<u32>().ok()).is_some_and(|size| size <= 1);
+        if tiny("width") && tiny("height") {
+            if let Some(src) = attribute(tag, "src") {
+                sources.insert(src.to_string());
+            }
+        }
+        rest = end;
+    }
+    sources
+}
+
+/// The value of attribute `name` in the text of a tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(index) = lower[from..].find(name).map(|index| from + index) {
+        from = index + name.len();
+        let preceded_by_space = lower[..index].ends_with(|c: char| c.is_ascii_whitespace());
+        let after = lower[from..].trim_start();
+        if !preceded_by_space || !after.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - after.len() + 1;
+        let value = tag[value_start..].trim_start();
+        return Some(match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(|c: char| c.is_ascii_whitespace() || c == '/').next().unwrap_or_default(),
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_and_handlers_are_removed() {
+        let html = r#"<p onclick="steal()">Hello<script>alert(1)</script></p><a href="javascript:go()">x</a><style>p{}</style>"#;
+        let clean = sanitize_html(html, false, true);
+        assert!(!clean.contains("script") && !clean.contains("onclick") && !clean.contains("javascript"), "{}", clean);
+        assert!(!clean.contains("p{}"), "{}", clean);
+        assert!(clean.contains("<p>Hello</p>"), "{}", clean);
+    }
+
+    #[test]
+    fn test_links_open_without_referrer() {
+        let clean = sanitize_html(r#"<a href="https://example.com/post">Read</a>"#, false, true);
+        assert_eq!(clean, r#"<a href="https://example.com/post" rel="noopener noreferrer nofollow">Read</a>"#);
+    }
+
+    #[test]
+    fn test_tracking_pixels_lose_their_source() {
+        let html = concat!(
+            r#"<img src="https://cdn.example.com/logo.png" alt="Logo" width="120">"#,
+            r#"<img width="1" height="1" src="https://mail.example.com/x?id=42">"#,
+            r#"<img src='https://links.example.com/wf/open?upn=abc' alt="">"#,
+        );
+        let clean = sanitize_html(html, false, true);
+        assert!(clean.contains("logo.png"), "{}", clean);
+        assert!(!clean.contains("x?id=42") && !clean.contains("wf/open"), "{}", clean);
+
+        let kept = sanitize_html(html, false, false);
+        assert!(kept.contains("x?id=42") && kept.contains("wf/open"), "{}", kept);
+    }
+
+    #[test]
+    fn test_remote_images_can_be_blocked() {
+        let clean = sanitize_html(r#"<img src="https://cdn.example.com/logo.png" alt="Logo">"#, true, true);
+        assert_eq!(clean, r#"<img alt="Logo">"#);
+    }
+}
@@ -0,0 +1,208 @@
+//! HTML-aware summarization of email bodies
+//!
+//! Produces the plain-text descriptions shown in feed readers. Markup is
+//! stripped, entities are decoded and the text is cut on a sentence or word
+//! boundary so summaries never end in the middle of a tag or entity.
+
+/// Summary length used when a feed does not configure its own
+pub const DEFAULT_SUMMARY_LENGTH: usize = 500;
+
+/// Summarize an email body (plain text or HTML) to at most `max_chars` characters
+pub fn summarize(body: &str, max_chars: usize) -> String {
+    let text = html_to_text(body);
+
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    let prefix: String = text.chars().take(max_chars).collect();
+
+    // Prefer ending on a full sentence if one finishes in the back half of the summary
+    let sentence_end = prefix
+        .rmatch_indices(['.', '!', '?'])
+        .map(|(idx, _)| idx + 1)
+        .find(|&end| prefix[end..].starts_with(' '));
+    if let Some(end) = sentence_end {
+        if prefix[..end].chars().count() >= max_chars / 2 {
+            return prefix[..end].to_string();
+        }
+    }
+
+    // Otherwise cut at the last word boundary
+    let cut = prefix.rfind(char::is_whitespace).unwrap_or(prefix.len());
+    format!("{}...", prefix[..cut].trim_end())
+}
+
+const INLINE_TAGS: &[&str] = &["a", "abbr", "b", "code", "em", "font", "i", "small", "span", "strong", "sub", "sup", "u"];
+
+/// Strip markup and decode entities, collapsing whitespace
+pub fn html_to_text(input: &str) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+    let mut skip_until: Option<&'static str> = None;
+
+    while let Some((idx, c)) = chars.next() {
+        let rest = &input[idx..];
+
+        if let Some(closing) = skip_until {
+            if starts_with_ignore_case(rest, closing) {
+                skip_until = None;
+                skip_tag(&mut chars);
+            }
+            continue;
+        }
+
+        if c == '<' && looks_like_tag(rest) {
+            let tag = tag_name(rest);
+            if tag == "script" || tag == "style" || tag == "head" {
+                skip_until = Some(match tag.as_str() {
+                    "script" => "</script",
+                    "style" => "</style",
+                    _ => "</head",
+                });
+            }
+            skip_tag(&mut chars);
+            // Block-level elements and line breaks separate words
+            if !INLINE_TAGS.contains(&tag.as_str()) {
+                text.push(' ');
+            }
+            continue;
+        }
+
+        if c == '&' {
+            if let Some((decoded, len)) = decode_entity(rest) {
+                text.push(decoded);
+                for _ in 1..len {
+                    chars.next();
+                }
+                continue;
+            }
+        }
+
+        text.push(c);
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn starts_with_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.len() >= needle.len()
+        && haystack.is_char_boundary(needle.len())
+        && haystack[..needle.len()].eq_ignore_ascii_case(needle)
+}
+
+fn looks_like_tag(rest: &str) -> bool {
+    matches!(rest[1..].chars().next(), Some(c) if c.is_ascii_alphabetic() || c == '/' || c == '!')
+}
+
+fn tag_name(rest: &str) -> String {
+    rest[1..]
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn skip_tag(chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>) {
+    let mut quote: Option<char> = None;
+    for (_, c) in chars.by_ref() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => break,
+            _ => {}
+        }
+    }
+}
+
+/// Decode an entity at the start of `rest`, returning the character and the
+/// number of chars consumed
+fn decode_entity(rest: &str) -> Option<(char, usize)> {
+    let end = rest.char_indices().take(12).find(|&(_, c)| c == ';')?.0;
+    let name = &rest[1..end];
+
+    let decoded = if let Some(num) = name.strip_prefix('#') {
+        let code = if let Some(hex) = num.strip_prefix('x').or_else(|| num.strip_prefix('X')) {
+            u32::from_str_radix(hex, 16).ok()?
+        } else {
+            num.parse::<u32>().ok()?
+        };
+        char::from_u32(code)?
+    } else {
+        match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => ' ',
+            "ndash" => '–',
+            "mdash" => '—',
+            "hellip" => '…',
+            "lsquo" => '‘',
+            "rsquo" => '’',
+            "ldquo" => '“',
+            "rdquo" => '”',
+            "copy" => '©',
+            "reg" => '®',
+            "trade" => '™',
+            _ => return None,
+        }
+    };
+
+    Some((decoded, rest[..=end].chars().count()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_tags_and_decodes_entities() {
+        let html = "<html><head><title>x</title></head><body><p>Fish &amp; chips</p><p>&lt;tasty&gt;&nbsp;&#33;</p></body></html>";
+        assert_eq!(html_to_text(html), "Fish & chips <tasty> !");
+    }
+
+    #[test]
+    fn test_drops_script_and_style_content() {
+        let html = "<style>p { color: red; }</style><p>Hello</p><script>alert('<b>');</script> world";
+        assert_eq!(html_to_text(html), "Hello world");
+    }
+
+    #[test]
+    fn test_plain_text_with_angle_brackets_is_kept() {
+        assert_eq!(html_to_text("a < b and c > d"), "a < b and c > d");
+        assert_eq!(html_to_text("AT&T rocks"), "AT&T rocks");
+    }
+
+    #[test]
+    fn test_inline_tags_do_not_split_words() {
+        assert_eq!(html_to_text("<p>un<b>break</b>able</p><p>next</p>"), "unbreakable next");
+    }
+
+    #[test]
+    fn test_short_text_is_unchanged() {
+        assert_eq!(summarize("Short message.", 500), "Short message.");
+    }
+
+    #[test]
+    fn test_cuts_on_sentence_boundary() {
+        let body = "First sentence here. Second sentence is a bit longer. Third one never fits.";
+        assert_eq!(summarize(body, 60), "First sentence here. Second sentence is a bit longer.");
+    }
+
+    #[test]
+    fn test_cuts_on_word_boundary() {
+        let body = "<p>Lorem ipsum dolor sit amet consectetur adipiscing</p>";
+        assert_eq!(summarize(body, 20), "Lorem ipsum dolor...");
+    }
+
+    #[test]
+    fn test_never_splits_multibyte_characters() {
+        let body = "Grüße aus Köln und überall sonst";
+        let summary = summarize(body, 12);
+        assert!(summary.starts_with("Grüße aus"));
+    }
+}
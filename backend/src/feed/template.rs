@@ -0,0 +1,206 @@
+//! Rule and account variables in feed titles and descriptions
+//!
+//! A feed title such as `Newsletters — {{rule.name}} ({{account.name}})` is
+//! resolved whenever the feed is generated, so renaming the rule or account
+//! shows up in readers without editing the feed. Only the variables in
+//! [`VARIABLES`] exist; anything else is rejected when the feed is saved.
+//! Values are substituted as plain text, there are no expressions. Other
+//! templates (webhook bodies) bring their own variables through
+//! [`render_variables`].
+
+use anyhow::Result;
+use tracing::debug;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{EmailRule, Feed, ImapAccount},
+    operations_generic::{EmailRuleOpsGeneric, ImapAccountOpsGeneric},
+};
+use crate::feed::item_templates;
+
+/// Variables available in feed titles and descriptions
+pub const VARIABLES: &[&str] = &[
+    "rule.name",
+    "rule.folder",
+    "rule.label",
+    "account.name",
+    "account.host",
+];
+
+/// Values of the template variables for one feed
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    rule: Option<EmailRule>,
+    account: Option<ImapAccount>,
+}
+
+impl TemplateContext {
+    pub fn new(rule: Option<EmailRule>, account: Option<ImapAccount>) -> Self {
+        Self { rule, account }
+    }
+
+    /// Value of a whitelisted variable; empty when the rule or account is gone
+    fn value(&self, variable: &str) -> &str {
+        let rule = self.rule.as_ref();
+        let account = self.account.as_ref();
+        match variable {
+            "rule.name" => rule.map(|rule| rule.name.as_str()),
+            "rule.folder" => rule.map(|rule| rule.folder.as_str()),
+            "rule.label" => rule.and_then(|rule| rule.label.as_deref()),
+            "account.name" => account.map(|account| account.name.as_str()),
+            "account.host" => account.map(|account| account.host.as_str()),
+            _ => None,
+        }
+        .unwrap_or_default()
+    }
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// Whether the text contains template variables
+pub fn has_variables(text: &str) -> bool {
+    text.contains("{{")
+}
+
+/// Check that a template only uses known variables and is well-formed
+pub fn validate(template: &str) -> Result<()> {
+    validate_variables(template, VARIABLES)
+}
+
+/// Substitute the context's values into a template
+///
+/// Templates that do not parse are returned as is.
+pub fn render(template: &str, context: &TemplateContext) -> String {
+    render_variables(template, VARIABLES, |variable| context.value(variable).to_string())
+        .unwrap_or_else(|_| template.to_string())
+}
+
+/// Check that a template only uses the given variables and is well-formed
+pub fn validate_variables(template: &str, variables: &[&str]) -> Result<()> {
+    parse(template, variables).map(|_| ())
+}
+
+/// Substitute `value(variable)` for each of the given variables in a template
+pub fn render_variables(template: &str, variables: &[&str], value: impl Fn(&str) -> String) -> Result<String> {
+    Ok(parse(template, variables)?
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.to_string(),
+            Segment::Variable(variable) => value(variable),
+        })
+        .collect())
+}
+
+/// Resolve the variables in a feed's title and description from its rule and
+/// account
+///
+/// Also fills in the rule folder of the item templates.
+pub fn resolve(pool: &DatabasePool, feed: &mut Feed) {
+    let item_templates = [&feed.title_template, &feed.description_template];
+    if !has_variables(&feed.title)
+        && !feed.description.as_deref().is_some_and(has_variables)
+        && !item_templates.iter().any(|template| template.as_deref().is_some_and(has_variables))
+    {
+        return;
+    }
+
+    let rule = EmailRuleOpsGeneric::get_by_id(pool, &feed.email_rule_id)
+        .map_err(|e| debug!("Rule {} unavailable for feed templates: {}", feed.email_rule_id, e))
+        .ok();
+    let account = rule.as_ref().and_then(|rule| {
+        ImapAccountOpsGeneric::get_by_id(pool, &rule.imap_account_id)
+            .map_err(|e| debug!("Account {} unavailable for feed templates: {}", rule.imap_account_id, e))
+            .ok()
+    });
+
+    let folder = rule.as_ref().map(|rule| rule.folder.clone()).unwrap_or_default();
+    for template in [&mut feed.title_template, &mut feed.description_template] {
+        *template = template.as_deref().map(|template| item_templates::resolve_folder(template, &folder));
+    }
+
+    let context = TemplateContext::new(rule, account);
+    feed.title = render(&feed.title, &context);
+    feed.description = feed.description.as_deref().map(|description| render(description, &context));
+}
+
+fn parse<'a>(template: &'a str, variables: &[&str]) -> Result<Vec<Segment<'a>>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        segments.push(Segment::Text(&rest[..start]));
+        let after = &rest[start + 2..];
+        let end = after.find("}}")
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '{{{{' in template '{}'", template))?;
+
+        let variable = after[..end].trim();
+        if !variables.contains(&variable) {
+            anyhow::bail!("Unknown template variable '{}'; available: {}", variable, variables.join(", "));
+        }
+        segments.push(Segment::Variable(variable));
+        rest = &after[end + 2..];
+    }
+    segments.push(Segment::Text(rest));
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TemplateContext {
+        let rule = EmailRule {
+            id: Some("rule-1".to_string()),
+            name: "Rust Weekly".to_string(),
+            imap_account_id: "account-1".to_string(),
+            folder: "Lists/Rust".to_string(),
+            to_address: None,
+            from_address: None,
+            subject_contains: None,
+            label: None,
+            is_active: true,
+            created_at: String::new(),
+            updated_at: String::new(),
+            post_process_action: "do_nothing".to_string(),
+            move_to_folder: None,
+            observe_only: false,
+            importance: None,
+            processing_order: "newest_first".to_string(),
+            last_seen_uid: None,
+            uid_validity: None,
+            post_process_delay_hours: None,
+            match_expression: None,
+            fetch_limit: None,
+            include_seen: true,
+            include_subfolders: false,
+            strip_footers: false,
+            strip_quoted_replies: false,
+            strip_tracking_pixels: true,
+            unwrap_tracking_links: false,
+            post_process_actions: None,
+        };
+        TemplateContext::new(Some(rule), None)
+    }
+
+    #[test]
+    fn test_renders_whitelisted_variables() {
+        assert_eq!(
+            render("Newsletters — {{rule.name}} ({{ rule.folder }})", &context()),
+            "Newsletters — Rust Weekly (Lists/Rust)"
+        );
+        // Missing values render empty
+        assert_eq!(render("{{rule.label}}{{account.name}}!", &context()), "!");
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_malformed_variables() {
+        assert!(validate("{{account.password}}").is_err());
+        assert!(validate("{{rule.name").is_err());
+        assert!(validate("Plain } title {").is_ok());
+        assert_eq!(render("{{account.password}}", &context()), "{{account.password}}");
+    }
+}
@@ -0,0 +1,147 @@
+//! Titles for emails without a subject
+//!
+//! Automated notification mails often arrive without a subject and would
+//! otherwise be listed with an empty title or as `[Email UID: N]`. Their
+//! bodies usually open with what they are about, so such items are titled
+//! with the body's first heading or, without one, its first sentence after
+//! any greeting such as `Hi,`, cut to `MAX_TITLE_CHARS` on a word boundary.
+//! Feeds keep the subject as is with `auto_titles: false`.
+
+use crate::db::models::Feed;
+use crate::feed::summarizer::html_to_text;
+use crate::imap::client::BODY_UNAVAILABLE;
+
+/// Longest title derived from a body, in characters
+pub const MAX_TITLE_CHARS: usize = 80;
+
+/// Whether a subject is missing: empty, or the placeholder given to emails
+/// whose headers could not be read
+pub fn is_missing(subject: &str) -> bool {
+    let subject = subject.trim();
+    subject.is_empty() || (subject.starts_with("[Email UID: ") && subject.ends_with(']'))
+}
+
+/// Title of the item for an email in `feed`: its subject, or a title derived
+/// from the body when the subject is missing and the feed allows it
+pub fn item_title(feed: &Feed, subject: &str, body: &str) -> String {
+    if !is_missing(subject) || feed.auto_titles == Some(false) {
+        return subject.to_string();
+    }
+    from_body(body).unwrap_or_else(|| subject.to_string())
+}
+
+/// The first heading of a body (plain text, Markdown or HTML), or its first sentence
+pub fn from_body(body: &str) -> Option<String> {
+    if body.trim() == BODY_UNAVAILABLE {
+        return None;
+    }
+    let text = if looks_like_html(body) {
+        html_heading(body).or_else(|| first_sentence(&html_to_text(body)))
+    } else {
+        text_heading(body).or_else(|| {
+            body.lines().find(|line| has_words(line) && !is_greeting(line)).and_then(first_sentence)
+        })
+    }?;
+    let title = shorten(&text);
+    (!title.is_empty()).then_some(title)
+}
+
+fn looks_like_html(body: &str) -> bool {
+    let lower = body.to_ascii_lowercase();
+    ["<html", "<body", "<div", "<p>", "<table", "<br", "<h1", "<h2", "<h3"].iter().any(|tag| lower.contains(tag))
+}
+
+fn has_words(line: &str) -> bool {
+    line.chars().any(char::is_alphanumeric)
+}
+
+/// A salutation line such as `Hi,` or `Dear team:`
+fn is_greeting(line: &str) -> bool {
+    let line = line.trim();
+    (line.ends_with(',') || line.ends_with(':')) && line.split_whitespace().count() <= 3
+}
+
+/// Text of the first `<h1>` to `<h6>` element
+fn html_heading(body: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so they index `body` as well
+    let lower = body.to_ascii_lowercase();
+    let start = (1..=6).filter_map(|level| lower.find(&format!("<h{}", level))).min()?;
+    let content_start = start + lower[start..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</h")?;
+    let text = html_to_text(&body[content_start..content_end]);
+    has_words(&text).then(|| text.trim().to_string())
+}
+
+/// First Markdown heading: `# Heading`, or a line underlined with `===`
+fn text_heading(body: &str) -> Option<String> {
+    let lines: Vec<&str> = body.lines().map(str::trim).collect();
+    lines.iter().enumerate().find_map(|(index, line)| {
+        if let Some(heading) = line.strip_prefix('#') {
+            let heading = heading.trim_start_matches('#');
+            if heading.starts_with(' ') && has_words(heading) {
+                return Some(heading.trim().trim_end_matches('#').trim_end().to_string());
+            }
+        }
+        let underline = lines.get(index + 1)?;
+        (has_words(line) && underline.len() >= 3 && underline.chars().all(|c| c == '='))
+            .then(|| line.to_string())
+    })
+}
+
+/// Text up to the end of its first sentence, without the final full stop
+fn first_sentence(text: &str) -> Option<String> {
+    let text = text.trim();
+    let end = text
+        .match_indices(['.', '!', '?'])
+        .map(|(index, mark)| index + mark.len())
+        .find(|&end| text[end..].starts_with(char::is_whitespace))
+        .unwrap_or(text.len());
+    let sentence = text[..end].trim_end_matches('.').trim();
+    has_words(sentence).then(|| sentence.to_string())
+}
+
+/// Collapse whitespace and cut to `MAX_TITLE_CHARS` on a word boundary
+fn shorten(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_TITLE_CHARS {
+        return text;
+    }
+    let prefix: String = text.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = prefix.rfind(' ').unwrap_or(prefix.len());
+    format!("{}...", prefix[..cut].trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headings_come_first() {
+        assert_eq!(from_body("Hello there.\n\n## Build #42 failed ##\n\nDetails").as_deref(), Some("Build #42 failed"));
+        assert_eq!(from_body("Weekly report\n=============\nAll good.").as_deref(), Some("Weekly report"));
+        assert_eq!(
+            from_body("<html><body><p>Intro</p><H2 class=\"x\">Disk &amp; memory <b>alert</b></H2></body></html>").as_deref(),
+            Some("Disk & memory alert"),
+        );
+    }
+
+    #[test]
+    fn test_first_sentence_without_heading() {
+        assert_eq!(from_body("\n----\nYour backup finished at 03:00. 12 files were copied.").as_deref(), Some("Your backup finished at 03:00"));
+        assert_eq!(from_body("<div>Payment received! Thanks.</div>").as_deref(), Some("Payment received!"));
+        assert_eq!(from_body("Version 1.2.3 is out").as_deref(), Some("Version 1.2.3 is out"));
+        assert_eq!(from_body("Dear team:\n\nThe deploy is done.").as_deref(), Some("The deploy is done"));
+        assert_eq!(from_body(&"word ".repeat(40)).unwrap(), format!("{}...", "word ".repeat(16).trim_end()));
+        assert_eq!(from_body(BODY_UNAVAILABLE), None);
+        assert_eq!(from_body("  \n---\n"), None);
+    }
+
+    #[test]
+    fn test_missing_subjects() {
+        assert!(is_missing(""));
+        assert!(is_missing("  "));
+        assert!(is_missing("[Email UID: 42]"));
+        assert!(!is_missing("Re: [Email UID: 42] thread"));
+        assert!(!is_missing("Hello"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Unsubscribe links of newsletter items
+//!
+//! Mailing lists name their unsubscribe addresses in a `List-Unsubscribe`
+//! header (RFC 2369), e.g. `<mailto:leave@example.com>, <https://example.com/u/42>`,
+//! and senders supporting one-click unsubscribe (RFC 8058) add
+//! `List-Unsubscribe-Post: List-Unsubscribe=One-Click`. Emails without the
+//! header often still link to an unsubscribe page in their footer, which is
+//! used for the web link instead.
+//!
+//! One-click unsubscribe is a POST of `List-Unsubscribe=One-Click` to the
+//! header's HTTPS URL; it is only offered when the header came with the
+//! `List-Unsubscribe-Post` opt-in, since a plain GET or POST to other links
+//! may need a browser.
+
+use anyhow::Result;
+use regex::Regex;
+use reqwest::Method;
+use std::sync::OnceLock;
+
+use super::delivery::OutboundRequest;
+use crate::imap::{client::Email, mime::EmailContent};
+
+/// Body of an RFC 8058 one-click unsubscribe request
+pub const ONE_CLICK_BODY: &str = "List-Unsubscribe=One-Click";
+
+/// Words marking a footer link as an unsubscribe link
+const KEYWORDS: &[&str] = &["unsubscribe", "opt out", "opt-out", "optout"];
+
+/// Where an email can be unsubscribed from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Unsubscribe {
+    /// Web page or one-click endpoint
+    pub url: Option<String>,
+    /// `mailto:` address from the header
+    pub mailto: Option<String>,
+    /// Whether `url` accepts an RFC 8058 one-click POST
+    pub one_click: bool,
+}
+
+impl Unsubscribe {
+    /// The unsubscribe links of an email: its headers, else its footer
+    pub fn of(email: &Email, content: &EmailContent) -> Self {
+        let mut unsubscribe = Self::from_headers(email.list_unsubscribe.as_deref(), email.list_unsubscribe_post.as_deref());
+        if unsubscribe.url.is_none() {
+            unsubscribe.url = content.html.as_deref().and_then(footer_link_in_html)
+                .or_else(|| footer_link_in_text(&content.text));
+        }
+        unsubscribe
+    }
+
+    /// Links of `List-Unsubscribe` and `List-Unsubscribe-Post` header values
+    pub fn from_headers(list_unsubscribe: Option<&str>, list_unsubscribe_post: Option<&str>) -> Self {
+        let links: Vec<&str> = list_unsubscribe.unwrap_or_default()
+            .split(',')
+            .filter_map(|link| link.trim().strip_prefix('<')?.strip_suffix('>'))
+            .map(str::trim)
+            .collect();
+        let url = links.iter().find(|link| is_web_link(link)).map(|link| link.to_string());
+        let mailto = links.iter().find(|link| link.to_ascii_lowercase().starts_with("mailto:")).map(|link| link.to_string());
+        let one_click = list_unsubscribe_post.is_some_and(|value| value.trim().eq_ignore_ascii_case(ONE_CLICK_BODY))
+            && url.as_deref().is_some_and(|url| url.to_ascii_lowercase().starts_with("https://"));
+        Self { url, mailto, one_click }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.url.is_none() && self.mailto.is_none()
+    }
+}
+
+/// Send the one-click unsubscribe request to `url`; returns the response status
+pub async fn one_click(url: &str) -> Result<u16> {
+    if !url.to_ascii_lowercase().starts_with("https://") {
+        anyhow::bail!("One-click unsubscribe requires an https URL");
+    }
+    let request = OutboundRequest {
+        method: Method::POST,
+        url: url.to_string(),
+        headers: vec![("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string())],
+        body: ONE_CLICK_BODY.to_string(),
+    };
+    request.send().await
+}
+
+fn is_web_link(link: &str) -> bool {
+    let link = link.to_ascii_lowercase();
+    link.starts_with("https://") || link.starts_with("http://")
+}
+
+fn mentions_unsubscribe(text: &str) -> bool {
+    let text = text.to_lowercase();
+    KEYWORDS.iter().any(|keyword| text.contains(keyword))
+}
+
+/// The last link of an HTML body whose text or address mentions unsubscribing
+fn footer_link_in_html(html: &str) -> Option<String> {
+    static ANCHOR: OnceLock<Regex> = OnceLock::new();
+    let anchor = ANCHOR.get_or_init(|| {
+        Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']+)["'][^>]*>(.*?)</a>"#).expect("valid anchor pattern")
+    });
+    anchor.captures_iter(html)
+        .filter(|captures| is_web_link(&captures[1]) && (mentions_unsubscribe(&captures[1]) || mentions_unsubscribe(&captures[2])))
+        .last()
+        .map(|captures| captures[1].replace("&amp;", "&"))
+}
+
+/// The last web link of a plain text body that mentions unsubscribing, or
+/// sits on a line that does
+fn footer_link_in_text(text: &str) -> Option<String> {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let url = URL.get_or_init(|| Regex::new(r#"(?i)https?://[^\s<>"'()\[\]]+"#).expect("valid URL pattern"));
+    text.lines()
+        .flat_map(|line| url.find_iter(line).map(move |found| (line, found.as_str())))
+        .filter(|(line, link)| mentions_unsubscribe(line) || mentions_unsubscribe(link))
+        .last()
+        .map(|(_, link)| link.trim_end_matches(['.', ',', ';', '>']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_links() {
+        let unsubscribe = Unsubscribe::from_headers(
+            Some("<mailto:leave@lists.example.com?subject=unsubscribe>,\r\n <https://lists.example.com/u/42>"),
+            Some("List-Unsubscribe=One-Click"),
+        );
+        assert_eq!(unsubscribe.url.as_deref(), Some("https://lists.example.com/u/42"));
+        assert_eq!(unsubscribe.mailto.as_deref(), Some("mailto:leave@lists.example.com?subject=unsubscribe"));
+        assert!(unsubscribe.one_click);
+    }
+
+    #[test]
+    fn test_one_click_needs_the_post_header_and_https() {
+        assert!(!Unsubscribe::from_headers(Some("<https://lists.example.com/u/42>"), None).one_click);
+        assert!(!Unsubscribe::from_headers(Some("<http://lists.example.com/u/42>"), Some("List-Unsubscribe=One-Click")).one_click);
+        let mailto_only = Unsubscribe::from_headers(Some("<mailto:leave@lists.example.com>"), Some("List-Unsubscribe=One-Click"));
+        assert!(!mailto_only.one_click && mailto_only.url.is_none());
+        assert!(Unsubscribe::from_headers(Some("not a link"), None).is_empty());
+    }
+
+    #[test]
+    fn test_footer_link_in_html() {
+        let html = r#"<p><a href="https://example.com/post">Read more</a></p>
+            <p>Too much mail? <a class="footer" href="https://example.com/prefs?u=1&amp;list=2">Unsubscribe</a></p>"#;
+        assert_eq!(footer_link_in_html(html).as_deref(), Some("https://example.com/prefs?u=1&list=2"));
+        assert_eq!(footer_link_in_html(r#"<a href="https://example.com/unsubscribe/9">here</a>"#).as_deref(), Some("https://example.com/unsubscribe/9"));
+        assert_eq!(footer_link_in_html(r#"<a href="https://example.com/post">Read more</a>"#), None);
+    }
+
+    #[test]
+    fn test_footer_link_in_text() {
+        let text = "Read it at https://example.com/post\n\nTo unsubscribe visit https://example.com/u/7.\n";
+        assert_eq!(footer_link_in_text(text).as_deref(), Some("https://example.com/u/7"));
+        assert_eq!(footer_link_in_text("Read it at https://example.com/post"), None);
+    }
+}
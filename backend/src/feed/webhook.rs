@@ -0,0 +1,181 @@
+//! Per-feed webhooks fired for each new item
+//!
+//! A feed may name a URL that is called whenever processing adds an item to
+//! it, e.g. a Slack, Discord or Matrix incoming webhook. The JSON body is a
+//! template over the variables in [`VARIABLES`]; values are JSON-escaped so
+//! they go inside string literals:
+//!
+//! ```text
+//! {"text": "New in {{feed.title}}: {{item.title}} {{item.url}}"}
+//! ```
+//!
+//! Without a body template the feed and item are sent as a JSON object.
+//! Calls go through the [`delivery`] queue, which retries failures; they never
+//! fail processing.
+
+use anyhow::Result;
+use chrono::Utc;
+use reqwest::{Method, Url};
+use serde_json::json;
+use tracing::warn;
+
+use crate::db::{connection::DatabasePool, models::{Feed, FeedItem}};
+use crate::feed::{delivery::{self, OutboundRequest}, generator::FeedGenerator, overflow, public_url, summarizer, template};
+
+/// Variables available in webhook body templates
+pub const VARIABLES: &[&str] = &[
+    "feed.id",
+    "feed.title",
+    "item.id",
+    "item.title",
+    "item.author",
+    "item.date",
+    "item.link",
+    "item.url",
+    "item.summary",
+];
+
+const DEFAULT_METHOD: &str = "POST";
+const METHODS: &[&str] = &["POST", "PUT", "PATCH"];
+
+/// Check a feed's webhook settings before saving them
+pub fn validate(url: Option<&str>, method: Option<&str>, body: Option<&str>) -> Result<()> {
+    if let Some(url) = url {
+        check_url(url, "webhook URL")?;
+    }
+    if let Some(method) = method {
+        parse_method(method)?;
+    }
+    if let Some(body) = body {
+        template::validate_variables(body, VARIABLES)?;
+        let rendered = template::render_variables(body, VARIABLES, |_| "value".to_string())?;
+        serde_json::from_str::<serde_json::Value>(&rendered)
+            .map_err(|e| anyhow::anyhow!("Webhook body is not valid JSON ({}); variables are strings, place them inside quotes", e))?;
+    }
+    Ok(())
+}
+
+/// Webhook body for a new item
+pub fn render_body(feed: &Feed, item: &FeedItem) -> Result<String> {
+    match feed.webhook_body.as_deref() {
+        Some(body) => template::render_variables(body, VARIABLES, |variable| {
+            json_escape(&value(feed, item, variable))
+        }),
+        None => {
+            let field = |variable: &str| json!(value(feed, item, variable));
+            Ok(json!({
+                "feed": {
+                    "id": field("feed.id"),
+                    "title": field("feed.title"),
+                },
+                "item": {
+                    "id": field("item.id"),
+                    "title": field("item.title"),
+                    "author": field("item.author"),
+                    "date": field("item.date"),
+                    "link": field("item.link"),
+                    "url": field("item.url"),
+                    "summary": field("item.summary"),
+                },
+            }).to_string())
+        }
+    }
+}
+
+/// Substitute an item's values into a plain-text template, e.g. a chat message
+pub fn render_text(template: &str, feed: &Feed, item: &FeedItem) -> Result<String> {
+    template::render_variables(template, VARIABLES, |variable| value(feed, item, variable))
+}
+
+/// The webhook call for an item
+pub fn request(feed: &Feed, item: &FeedItem) -> Result<OutboundRequest> {
+    let url = feed.webhook_url.as_deref()
+        .ok_or_else(|| anyhow::anyhow!("Feed has no webhook configured"))?;
+    let method = parse_method(feed.webhook_method.as_deref().unwrap_or(DEFAULT_METHOD))?;
+    Ok(OutboundRequest::json(method, url, render_body(feed, item)?))
+}
+
+/// Call the feed's webhook for an item right away, bypassing the queue;
+/// returns the response status
+pub async fn deliver(feed: &Feed, item: &FeedItem) -> Result<u16> {
+    request(feed, item)?.send().await
+}
+
+/// Queue the feed's webhook call for a new item, if it has a webhook, and
+/// send what is due; failures are logged
+pub async fn notify(pool: &DatabasePool, feed: &Feed, item: &FeedItem) {
+    if feed.webhook_url.is_none() {
+        return;
+    }
+    let feed_id = feed.id.as_deref().unwrap_or_default();
+    let queued = request(feed, item).and_then(|request| delivery::enqueue(pool, delivery::Origin {
+        kind: "webhook",
+        queue: format!("webhook:{}", feed_id),
+        feed_id,
+        item_id: item.id.as_deref(),
+    }, &request));
+    if let Err(e) = queued {
+        warn!("Failed to queue webhook of feed '{}' for item '{}': {}", feed.title, item.title, e);
+        return;
+    }
+    if let Err(e) = delivery::process_due(pool).await {
+        warn!("Failed to send queued deliveries: {}", e);
+    }
+}
+
+/// Stand-in item for trying a webhook or chat integration on a feed without
+/// items
+pub fn sample_item(feed: &Feed) -> FeedItem {
+    FeedGenerator::email_to_feed_item(
+        feed.id.clone().unwrap_or_default(),
+        "Test item from mail2feed",
+        "mail2feed@localhost",
+        "This is a test message from mail2feed.",
+        None,
+        Utc::now(),
+    )
+}
+
+fn value(feed: &Feed, item: &FeedItem, variable: &str) -> String {
+    let item_id = item.id.as_deref().unwrap_or_default();
+    match variable {
+        "feed.id" => feed.id.clone().unwrap_or_default(),
+        "feed.title" => feed.title.clone(),
+        "item.id" => item_id.to_string(),
+        "item.title" => item.title.clone(),
+        "item.author" => item.author.clone().unwrap_or_default(),
+        "item.date" => item.pub_date.clone(),
+        "item.link" => item.link.clone().unwrap_or_default(),
+        "item.url" => public_url::configured()
+            .map(|base| format!("{}{}", base, overflow::item_page_path(&item.feed_id, item_id)))
+            .unwrap_or_default(),
+        "item.summary" => item.description.as_deref()
+            .map(|description| summarizer::summarize(description, summarizer::DEFAULT_SUMMARY_LENGTH))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Check that `url` is an absolute http(s) URL
+pub(crate) fn check_url(url: &str, what: &str) -> Result<()> {
+    let parsed = Url::parse(url)
+        .map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", what, url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        anyhow::bail!("The {} must use http or https", what);
+    }
+    Ok(())
+}
+
+fn parse_method(method: &str) -> Result<Method> {
+    let method = method.trim().to_uppercase();
+    if !METHODS.contains(&method.as_str()) {
+        anyhow::bail!("Unsupported webhook method '{}'; use one of {}", method, METHODS.join(", "));
+    }
+    Method::from_bytes(method.as_bytes()).map_err(Into::into)
+}
+
+/// Contents of a JSON string literal holding `text`
+fn json_escape(text: &str) -> String {
+    let quoted = serde_json::Value::String(text.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
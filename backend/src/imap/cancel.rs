@@ -0,0 +1,194 @@
+//! Timeouts and cancellation for IMAP commands
+//!
+//! Two things keep an `ImapClient` operation from hanging on a slow or
+//! vanished server:
+//!
+//! - Connecting is bounded by `IMAP_COMMAND_TIMEOUT_SECONDS` (default 60),
+//!   and every connection is a [`TimedStream`] whose reads and writes fail
+//!   once they have waited as long, so a single command that gets no answer
+//!   fails instead of waiting forever.
+//! - [`run`] drops the operation as soon as the client's `CancellationToken`
+//!   fires (the scheduler cancels it on shutdown). Dropping it closes the
+//!   connections it opened, and the operation returns [`Cancelled`].
+
+use anyhow::Result;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::time::Sleep;
+use tokio_util::sync::CancellationToken;
+
+/// Seconds an IMAP command may go without an answer by default
+pub const DEFAULT_COMMAND_TIMEOUT_SECONDS: u64 = 60;
+
+/// An IMAP operation stopped because its client was cancelled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancelled {
+    pub operation: String,
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IMAP {} was cancelled", self.operation)
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// How long a command may wait for the server, from `IMAP_COMMAND_TIMEOUT_SECONDS`
+pub fn command_timeout() -> Duration {
+    let seconds = std::env::var("IMAP_COMMAND_TIMEOUT_SECONDS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECONDS);
+    Duration::from_secs(seconds)
+}
+
+/// Connect to `host:port` within the command timeout, with the timeout
+/// applied to the connection's reads and writes
+pub async fn connect(host: &str, port: u16) -> io::Result<TimedStream<TcpStream>> {
+    let timeout = command_timeout();
+    let mut last_error = None;
+    for address in tokio::net::lookup_host((host, port)).await? {
+        match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => return Ok(TimedStream::new(stream, timeout)),
+            Ok(Err(e)) => last_error = Some(e),
+            Err(_) => last_error = Some(io::Error::new(io::ErrorKind::TimedOut, format!("Connecting to {} timed out", address))),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("{} did not resolve to an address", host))
+    }))
+}
+
+/// A stream whose reads and writes fail with `TimedOut` once they have been
+/// waiting for `timeout`
+#[derive(Debug)]
+pub struct TimedStream<S> {
+    inner: S,
+    timeout: Duration,
+    /// When the read or write now waiting gives up
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> TimedStream<S> {
+    pub fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout, deadline: None }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Pass a finished read or write on, or fail one that has waited too long
+    fn check<T>(&mut self, cx: &mut Context<'_>, poll: Poll<io::Result<T>>) -> Poll<io::Result<T>> {
+        if poll.is_ready() {
+            self.deadline = None;
+            return poll;
+        }
+        let timeout = self.timeout;
+        let deadline = self.deadline.get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        match deadline.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                self.deadline = None;
+                Poll::Ready(Err(io::Error::new(io::ErrorKind::TimedOut, format!("No answer from the server in {:?}", timeout))))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TimedStream<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        this.check(cx, poll)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TimedStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        this.check(cx, poll)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_flush(cx);
+        this.check(cx, poll)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Run an IMAP operation, giving up with [`Cancelled`] as soon as `token`
+/// is cancelled
+pub async fn run<T>(token: &CancellationToken, operation: &str, future: impl Future<Output = Result<T>>) -> Result<T> {
+    if token.is_cancelled() {
+        return Err(Cancelled { operation: operation.to_string() }.into());
+    }
+    tokio::select! {
+        result = future => result,
+        _ = token.cancelled() => Err(Cancelled { operation: operation.to_string() }.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_cancellation_interrupts_blocked_read() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let token = CancellationToken::new();
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+        // The server accepts but never answers, so the read waits
+        let error = run(&token, "read", async {
+            let mut stream = connect("127.0.0.1", port).await?;
+            let _server_side = listener.accept().await?;
+            let mut buffer = [0u8; 1];
+            stream.read_exact(&mut buffer).await?;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(error.downcast_ref::<Cancelled>(), Some(&Cancelled { operation: "read".to_string() }));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_client_starts_nothing() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let result: Result<()> = run(&token, "noop", async { panic!("should not run") }).await;
+        assert!(result.unwrap_err().is::<Cancelled>());
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_read_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let _server_side = listener.accept().await.unwrap();
+
+        let mut stream = TimedStream::new(stream, Duration::from_millis(50));
+        let mut buffer = [0u8; 1];
+        let error = stream.read_exact(&mut buffer).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
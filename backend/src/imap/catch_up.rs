@@ -0,0 +1,148 @@
+//! Catching up on mail after downtime
+//!
+//! A regular run fetches only the newest `DEFAULT_FETCH_LIMIT` emails of each
+//! folder, or a rule's own `fetch_limit`, so after the service was down for a while older mail that arrived
+//! in the meantime would be missed. When the account's last completed run
+//! started more than `CATCH_UP_AFTER_HOURS` ago, the run fetches up to
+//! `MAX_CATCH_UP_EMAILS` per folder instead and processes those dated within
+//! the gap. The gap is recorded on the run, along with whether the limit
+//! stopped the catch-up short of its start.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::client::Email;
+use crate::db::models::EmailRule;
+
+/// Emails fetched per folder on a regular run
+pub const DEFAULT_FETCH_LIMIT: u32 = 100;
+
+/// Emails fetched from the folder of `rule` on a regular run
+pub fn fetch_limit(rule: &EmailRule) -> u32 {
+    rule.fetch_limit
+        .and_then(|limit| u32::try_from(limit).ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_FETCH_LIMIT)
+}
+
+/// Most emails fetched per folder when catching up
+pub const MAX_CATCH_UP_EMAILS: u32 = 1000;
+
+/// Time since the last completed run after which a run catches up
+pub const CATCH_UP_AFTER_HOURS: i64 = 6;
+
+/// Allowance for Date headers that lag behind delivery, in hours
+const DATE_SLACK_HOURS: i64 = 24;
+
+/// The gap a run catches up on
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatchUp {
+    /// When the account's last completed run started
+    pub from: DateTime<Utc>,
+    /// When this run started
+    pub to: DateTime<Utc>,
+    /// Whether a folder had more mail in the gap than could be fetched
+    pub truncated: bool,
+}
+
+impl CatchUp {
+    /// The catch-up a run starting at `now` needs, given when the account's
+    /// last completed run started; none for accounts never processed before
+    pub fn detect(last_completed: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<Self> {
+        let from = last_completed?;
+        (now - from > Duration::hours(CATCH_UP_AFTER_HOURS)).then_some(Self {
+            from,
+            to: now,
+            truncated: false,
+        })
+    }
+
+    /// Narrow the emails fetched from a folder with `MAX_CATCH_UP_EMAILS` to
+    /// those a regular run would see plus those dated within the gap
+    pub fn select(&mut self, mut emails: Vec<Email>) -> Vec<Email> {
+        let oldest = emails.iter().map(|email| email.date).min();
+        if emails.len() >= MAX_CATCH_UP_EMAILS as usize && oldest.is_some_and(|oldest| oldest > self.from) {
+            self.truncated = true;
+        }
+
+        let mut newest_uids: Vec<u32> = emails.iter().map(|email| email.uid).collect();
+        newest_uids.sort_unstable_by(|a, b| b.cmp(a));
+        newest_uids.truncate(DEFAULT_FETCH_LIMIT as usize);
+
+        let cutoff = self.from - Duration::hours(DATE_SLACK_HOURS);
+        emails.retain(|email| email.date >= cutoff || newest_uids.contains(&email.uid));
+        emails
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(uid: u32, date: DateTime<Utc>) -> Email {
+        Email {
+            uid,
+            message_id: format!("<{}@example.com>", uid),
+            subject: format!("Issue {}", uid),
+            from: "news@example.com".to_string(),
+            to: "reader@example.com".to_string(),
+            date,
+            body: String::new(),
+            is_seen: false,
+            importance: None,
+            category: None,
+            labels: None,
+            content_type: None,
+            transfer_encoding: None,
+            list_unsubscribe: None,
+            list_unsubscribe_post: None,
+            in_reply_to: None,
+            references: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_only_after_a_long_gap() {
+        let now = Utc::now();
+        assert_eq!(CatchUp::detect(None, now), None);
+        assert_eq!(CatchUp::detect(Some(now - Duration::minutes(30)), now), None);
+
+        let catch_up = CatchUp::detect(Some(now - Duration::days(7)), now).unwrap();
+        assert_eq!(catch_up.from, now - Duration::days(7));
+        assert_eq!(catch_up.to, now);
+        assert!(!catch_up.truncated);
+    }
+
+    #[test]
+    fn test_select_keeps_gap_and_newest_emails() {
+        let now = Utc::now();
+        let mut catch_up = CatchUp::detect(Some(now - Duration::days(7)), now).unwrap();
+
+        // 150 emails from the last week, then 50 much older ones
+        let mut emails: Vec<Email> = (0..200)
+            .map(|uid| {
+                let age = if uid < 50 { Duration::days(90) } else { Duration::hours(200 - uid as i64) };
+                email(uid, now - age)
+            })
+            .collect();
+        // An old email among the newest by UID, e.g. one moved into the folder
+        emails.push(email(500, now - Duration::days(400)));
+
+        let selected = catch_up.select(emails);
+        assert_eq!(selected.len(), 151);
+        assert!(selected.iter().any(|email| email.uid == 500));
+        assert!(selected.iter().all(|email| email.uid >= 50));
+        assert!(!catch_up.truncated);
+    }
+
+    #[test]
+    fn test_select_flags_gap_beyond_the_limit() {
+        let now = Utc::now();
+        let mut catch_up = CatchUp::detect(Some(now - Duration::days(7)), now).unwrap();
+
+        let emails = (0..MAX_CATCH_UP_EMAILS)
+            .map(|uid| email(uid, now - Duration::minutes(uid as i64)))
+            .collect();
+        assert_eq!(catch_up.select(emails).len(), MAX_CATCH_UP_EMAILS as usize);
+        assert!(catch_up.truncated);
+    }
+}
@@ -1,9 +1,31 @@
 use anyhow::{Result, Context};
-use crate::db::models::ImapAccount;
+use crate::db::models::{ConnectionSecurity, EmailAction, ImapAccount, Importance};
+use async_imap::types::{Fetch, Flag, NameAttribute};
 use chrono::{DateTime, Utc};
 use tracing::{debug, info, warn, error};
-use native_tls::TlsConnector;
-use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_native_tls::TlsStream;
+use tokio_util::sync::CancellationToken;
+use super::cancel;
+use super::diagnostics::ConnectionDiagnostics;
+use super::fingerprint;
+use super::fetch_strategy::{FetchStrategy, ServerProfile};
+use super::folders::FolderStatus;
+use super::high_water::{self, FolderFetch, HighWaterMark};
+use super::{importance, labels};
+use super::mime;
+use super::post_process::{self, BatchOutcome, PostProcessBatch};
+use super::rate_limit::RateLimiter;
+use super::setup::{self, FolderSample, ServerProbe};
+use super::server_name;
+use super::session::{self, fetch, list, uid_fetch, uid_store, ImapStream, Session, Transport};
+use super::tls_pin::{PeerFingerprints, TlsPin};
+use super::throttle::{ThrottledStream, TransferMeter, TransferStats};
+
+/// Body given to emails fetched without one
+pub const BODY_UNAVAILABLE: &str = "[Body not available - fetched headers only]";
+
 
 // Enhanced error handling for IMAP specific errors
 #[derive(Debug)]
@@ -21,6 +43,7 @@ pub enum ImapClientError {
     ProtocolError { operation: String, details: String },
     #[allow(dead_code)]
     ServerCompatibilityIssue { server: String, operation: String },
+    CertificatePinMismatch { host: String, expected: String, observed: String },
 }
 
 impl std::fmt::Display for ImapClientError {
@@ -50,6 +73,9 @@ impl std::fmt::Display for ImapClientError {
             ImapClientError::ServerCompatibilityIssue { server, operation } => {
                 write!(f, "Server compatibility issue with '{}' during '{}' - server may not fully support IMAP protocol", server, operation)
             }
+            ImapClientError::CertificatePinMismatch { host, expected, observed } => {
+                write!(f, "TLS certificate of {} does not match the pinned {} (server presented {}) - refusing to connect; if the certificate was legitimately replaced, update the account's TLS pin", host, expected, observed)
+            }
         }
     }
 }
@@ -66,65 +92,340 @@ impl std::error::Error for ImapClientError {
 
 pub struct ImapClient {
     account: ImapAccount,
+    meter: TransferMeter,
+    /// Stops the client's operations, interrupting commands in flight
+    cancellation: CancellationToken,
+    /// Gmail labels rules ask for, looked up on fetched emails
+    labels: Vec<String>,
+    /// What fetches have learned about the server
+    server: Arc<Mutex<ServerProfile>>,
 }
 
 impl ImapClient {
     pub fn new(account: &ImapAccount) -> Result<Self> {
         Ok(Self {
             account: account.clone(),
+            meter: TransferMeter::new(account.max_bytes_per_second),
+            cancellation: CancellationToken::new(),
+            labels: Vec::new(),
+            server: Arc::new(Mutex::new(ServerProfile::of(account))),
         })
     }
-    
+
+    /// Give up on operations once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Look up which of `labels` fetched emails carry, on Gmail servers
+    pub fn with_labels(mut self, labels: impl IntoIterator<Item = String>) -> Self {
+        self.labels = labels.into_iter().filter(|label| !label.trim().is_empty()).collect();
+        self
+    }
+
+    /// The server's capabilities and the fetch strategy that works for it, as
+    /// last detected
+    pub fn server_profile(&self) -> ServerProfile {
+        self.server.lock().map(|server| server.clone()).unwrap_or_default()
+    }
+
+    /// Bytes transferred by all connections this client has made so far
+    pub fn transfer_stats(&self) -> TransferStats {
+        self.meter.stats()
+    }
+
     pub async fn test_connection(&self) -> Result<()> {
-        let account = self.account.clone();
-        
-        tokio::task::spawn_blocking(move || {
-            debug!("Testing connection to {}:{} (TLS: {})", 
-                account.host, account.port, account.use_tls);
-                
-            if account.use_tls {
-                let mut session = Self::connect_tls_sync(&account)?;
-                
-                // Try a NOOP command first to test basic connectivity
-                session.noop()
-                    .context("Failed to execute NOOP command - server may not support IMAP properly")?;
-                    
-                // Then try listing folders
-                let _ = session.list(Some(""), Some("*"))
-                    .context("Failed to list folders - check if the server requires specific folder prefixes")?;
-                    
-                // Logout (but don't fail the test if logout has issues)
-                if let Err(e) = session.logout() {
-                    warn!("Logout failed (this is usually not critical): {}", e);
-                }
-            } else {
-                let mut session = Self::connect_plain_sync(&account)?;
-                    
-                // Try a NOOP command first to test basic connectivity
-                session.noop()
-                    .context("Failed to execute NOOP command - server may not support IMAP properly")?;
-                    
-                // Then try listing folders
-                let _ = session.list(Some(""), Some("*"))
-                    .context("Failed to list folders - check if the server requires specific folder prefixes")?;
-                    
-                // Logout (but don't fail the test if logout has issues)
-                if let Err(e) = session.logout() {
-                    warn!("Logout failed (this is usually not critical): {}", e);
-                }
+        cancel::run(&self.cancellation, "connection test", async {
+            debug!("Testing connection to {}:{} (security: {})",
+                self.account.host, self.account.port, self.account.connection_security().as_str());
+
+            let mut session = self.connect().await?;
+
+            // Try a NOOP command first to test basic connectivity
+            session.noop().await
+                .context("Failed to execute NOOP command - server may not support IMAP properly")?;
+
+            // Then try listing folders
+            list(&mut session, Some(""), Some("*")).await
+                .context("Failed to list folders - check if the server requires specific folder prefixes")?;
+
+            // Logout (but don't fail the test if logout has issues)
+            if let Err(e) = session.logout().await {
+                warn!("Logout failed (this is usually not critical): {}", e);
             }
-            
+
             debug!("Connection test successful");
             Ok(())
         })
         .await
-        .unwrap()
     }
 
-    fn connect_tls_sync(account: &ImapAccount) -> Result<imap::Session<native_tls::TlsStream<TcpStream>>> {
-        debug!("Creating TLS connection to {}:{}", account.host, account.port);
-        
-        let tls = TlsConnector::builder().build()
+    /// Run the steps of connecting one at a time and report how each went
+    pub async fn diagnose(&self) -> Result<ConnectionDiagnostics> {
+        cancel::run(&self.cancellation, "connection diagnostics", async {
+            Ok(self.run_diagnostics().await)
+        })
+        .await
+    }
+
+    async fn run_diagnostics(&self) -> ConnectionDiagnostics {
+        let account = &self.account;
+        let mut diagnostics = ConnectionDiagnostics::default();
+        let port = account.port as u16;
+
+        let resolved = diagnostics.run("dns", async {
+            let addresses: Vec<std::net::SocketAddr> = tokio::net::lookup_host((account.host.as_str(), port)).await
+                .with_context(|| format!("Could not resolve {}", account.host))?
+                .collect();
+            if addresses.is_empty() {
+                return Err(anyhow::anyhow!("{} did not resolve to an address", account.host));
+            }
+            Ok(addresses)
+        }, |addresses| {
+            let addresses: Vec<String> = addresses.iter().map(|address| address.ip().to_string()).collect();
+            format!("{} resolved to {}", account.host, addresses.join(", "))
+        }).await;
+        if resolved.is_none() {
+            return diagnostics.finish();
+        }
+
+        let Some(stream) = diagnostics.run("tcp", self.open_tcp(), |stream| {
+            match stream.get_ref().get_ref().peer_addr() {
+                Ok(address) => format!("Connected to {}", address),
+                Err(_) => format!("Connected to {}:{}", account.host, port),
+            }
+        }).await else {
+            return diagnostics.finish();
+        };
+
+        if account.use_tls {
+            let tls_stream = diagnostics.run("tls", async {
+                let pin = account.tls_pin.as_deref().map(TlsPin::parse).transpose()?;
+                let (tls_stream, _) = Self::handshake_on(account, stream, pin.is_some()).await?;
+                if let Some(pin) = &pin {
+                    Self::verify_pin(account, &tls_stream, pin)?;
+                }
+                Ok(tls_stream)
+            }, |_| {
+                let handshake = match account.connection_security() {
+                    ConnectionSecurity::SslTls => "Implicit TLS",
+                    _ => "STARTTLS",
+                };
+                match &account.tls_pin {
+                    Some(pin) => format!("{} handshake completed; certificate matches pin {}", handshake, pin),
+                    None => format!("{} handshake completed; certificate verified for {}", handshake, server_name::for_account(account)),
+                }
+            }).await;
+            match tls_stream {
+                Some(tls_stream) => Self::diagnose_session(diagnostics, ImapStream::Tls(Box::new(tls_stream)), account, false).await,
+                None => diagnostics.finish(),
+            }
+        } else {
+            diagnostics.skip("tls", "Account does not use TLS");
+            Self::diagnose_session(diagnostics, ImapStream::Plain(stream), account, true).await
+        }
+    }
+
+    /// The login, capabilities and folder listing steps of [`Self::run_diagnostics`]
+    async fn diagnose_session(mut diagnostics: ConnectionDiagnostics, mut stream: ImapStream, account: &ImapAccount, read_greeting: bool) -> ConnectionDiagnostics {
+        let session = diagnostics.run("auth", async move {
+            if read_greeting {
+                Self::read_greeting(&mut stream).await.context("Failed to read server greeting")?;
+            }
+            async_imap::Client::new(stream).login(&account.username, &account.password).await
+                .map_err(|(e, _)| ImapClientError::AuthenticationFailed {
+                    username: account.username.clone(),
+                    source: e.to_string(),
+                }.into())
+        }, |_| format!("Logged in as {}", account.username)).await;
+        let Some(mut session) = session else {
+            return diagnostics.finish();
+        };
+
+        let capabilities = diagnostics.run("capabilities", async {
+            let capabilities = session.capabilities().await
+                .context("Failed to read server capabilities")?;
+            Ok(setup::capability_names(capabilities.iter()))
+        }, |capabilities| capabilities.join(" ")).await;
+        if capabilities.is_some() {
+            diagnostics.run("folders", async {
+                let names = list(&mut session, Some(""), Some("*")).await.context("Failed to list folders")?;
+                Ok(names.len())
+            }, |count| format!("Listed {} folders", count)).await;
+        }
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after diagnostics: {}", e);
+        }
+        diagnostics.finish()
+    }
+
+    /// Fingerprint of the mailbox behind this account, from the server
+    /// greeting and the UIDVALIDITY of INBOX
+    pub async fn fingerprint(&self) -> Result<String> {
+        cancel::run(&self.cancellation, "fingerprint", async {
+            let (mut session, greeting) = self.connect_with_greeting().await?;
+
+            // EXAMINE is read-only, so fingerprinting never changes flags
+            let inbox = session.examine("INBOX").await
+                .context("Failed to examine INBOX")?;
+
+            if let Err(e) = session.logout().await {
+                warn!("Logout failed after fingerprinting: {}", e);
+            }
+
+            Ok(fingerprint::compute(&greeting, inbox.uid_validity))
+        })
+        .await
+    }
+
+    /// Fingerprints of the certificate the server presents, without logging
+    /// in. Any certificate is accepted so a pin can be set on first use.
+    pub async fn tls_fingerprints(&self) -> Result<PeerFingerprints> {
+        cancel::run(&self.cancellation, "TLS fingerprint lookup", async {
+            if !self.account.use_tls {
+                return Err(anyhow::anyhow!("Account does not use TLS"));
+            }
+            let (mut tls_stream, _) = self.handshake(true).await?;
+            let fingerprints = Self::peer_fingerprints(&self.account, &tls_stream)?;
+            if let Err(e) = tls_stream.shutdown().await {
+                debug!("TLS shutdown after reading fingerprints failed: {}", e);
+            }
+            Ok(fingerprints)
+        })
+        .await
+    }
+
+    /// Log in and read the server's greeting and capabilities
+    pub async fn probe(&self) -> Result<ServerProbe> {
+        cancel::run(&self.cancellation, "probe", async {
+            let (mut session, greeting) = self.connect_with_greeting().await?;
+            let capabilities = session.capabilities().await
+                .context("Failed to read server capabilities")?;
+            let capabilities = setup::capability_names(capabilities.iter());
+
+            if let Err(e) = session.logout().await {
+                warn!("Logout failed after probing: {}", e);
+            }
+
+            Ok(ServerProbe { greeting, capabilities })
+        })
+        .await
+    }
+
+    /// Message count and the headers of up to `sample_size` of the newest
+    /// messages of every folder, read-only
+    pub async fn sample_folders(&self, sample_size: u32) -> Result<Vec<FolderSample>> {
+        cancel::run(&self.cancellation, "folder sampling", async {
+            let mut session = self.connect().await?;
+            Self::sample_folders_with_session(&mut session, sample_size).await
+        })
+        .await
+    }
+
+    async fn sample_folders_with_session(session: &mut Session, sample_size: u32) -> Result<Vec<FolderSample>> {
+        let folders = match Self::try_list_folders_empty(session).await {
+            Ok(folders) if !folders.is_empty() => folders,
+            _ => Self::try_list_folders_none(session).await?,
+        };
+
+        let mut samples = Vec::new();
+        for folder in folders {
+            // EXAMINE is read-only, so sampling never changes flags
+            let mailbox = match session.examine(&folder).await {
+                Ok(mailbox) => mailbox,
+                Err(e) => {
+                    debug!("Skipping folder '{}' that cannot be examined: {}", folder, e);
+                    continue;
+                }
+            };
+
+            let mut headers = Vec::new();
+            if mailbox.exists > 0 && sample_size > 0 {
+                let first = mailbox.exists.saturating_sub(sample_size - 1).max(1);
+                match fetch(session, &format!("{}:{}", first, mailbox.exists), "BODY.PEEK[HEADER]").await {
+                    Ok(messages) => headers.extend(messages.iter()
+                        .filter_map(|message| message.header().or_else(|| message.body()))
+                        .map(|header| String::from_utf8_lossy(header).into_owned())),
+                    Err(e) => warn!("Failed to sample headers of folder '{}': {}", folder, e),
+                }
+            }
+            samples.push(FolderSample { folder, messages: mailbox.exists, headers });
+        }
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after sampling folders: {}", e);
+        }
+        Ok(samples)
+    }
+
+    /// Connect and log in
+    async fn connect(&self) -> Result<Session> {
+        self.connect_with_greeting().await.map(|(session, _)| session)
+    }
+
+    /// Connect and log in, returning the server's greeting along with the session
+    async fn connect_with_greeting(&self) -> Result<(Session, String)> {
+        let account = &self.account;
+        let (stream, greeting) = if account.use_tls {
+            debug!("Creating TLS connection to {}:{}", account.host, account.port);
+
+            let pin = account.tls_pin.as_deref().map(TlsPin::parse).transpose()?;
+            let (tls_stream, greeting) = self.handshake(pin.is_some()).await?;
+            if let Some(pin) = pin {
+                Self::verify_pin(account, &tls_stream, &pin)?;
+            }
+            (ImapStream::Tls(Box::new(tls_stream)), greeting)
+        } else {
+            debug!("Creating plain connection to {}:{}", account.host, account.port);
+
+            let mut stream = self.open_tcp().await?;
+            let greeting = Self::read_greeting(&mut stream).await
+                .context("Failed to read server greeting")?;
+            (ImapStream::Plain(stream), greeting)
+        };
+
+        debug!("Connection established, attempting login");
+
+        let session = async_imap::Client::new(stream)
+            .login(&account.username, &account.password)
+            .await
+            .map_err(|(e, _)| {
+                error!("Login failed: {:?}", e);
+                let hint = if account.use_tls {
+                    "Check username and password. If using Gmail, ensure you're using an app-specific password."
+                } else {
+                    "Check username and password."
+                };
+                ImapClientError::AuthenticationFailed {
+                    username: account.username.clone(),
+                    source: format!("{:?}. {}", e, hint),
+                }
+            })?;
+
+        debug!("Login successful");
+        Ok((session, greeting))
+    }
+
+    /// Open a connection and complete the TLS handshake, after STARTTLS unless
+    /// the account uses implicit TLS, sending and verifying the account's TLS
+    /// server name. With `pinned` the
+    /// certificate is not checked against the system CAs or the hostname; the
+    /// caller verifies it against the pin instead.
+    async fn handshake(&self, pinned: bool) -> Result<(TlsStream<Transport>, String)> {
+        let stream = self.open_tcp().await?;
+        Self::handshake_on(&self.account, stream, pinned).await
+    }
+
+    /// The STARTTLS and TLS handshake part of [`Self::handshake`], on an
+    /// open connection. With implicit TLS the greeting is read once the
+    /// handshake is done.
+    async fn handshake_on(account: &ImapAccount, mut stream: Transport, pinned: bool) -> Result<(TlsStream<Transport>, String)> {
+        let tls = native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(pinned)
+            .danger_accept_invalid_hostnames(pinned)
+            .build()
             .map_err(|e| {
                 error!("TLS connector creation failed: {}", e);
                 ImapClientError::TlsHandshakeFailed {
@@ -132,38 +433,122 @@ impl ImapClient {
                     source: Box::new(e),
                 }
             })?;
-            
-        let client = imap::connect_starttls((account.host.as_str(), account.port as u16), &account.host, &tls)
+
+        let connection_failed = |e: anyhow::Error| {
+            error!("TLS connection failed: {}", e);
+            ImapClientError::ConnectionFailed {
+                host: account.host.clone(),
+                port: account.port as u16,
+                source: e.into(),
+            }
+        };
+        let implicit = account.connection_security() == ConnectionSecurity::SslTls;
+        let greeting = if implicit {
+            None
+        } else {
+            Some(Self::starttls(&mut stream).await.map_err(connection_failed)?)
+        };
+
+        let server_name = server_name::for_account(account);
+        if server_name != account.host {
+            debug!("Using TLS server name {} for {}", server_name, account.host);
+        }
+        let mut tls_stream = tokio_native_tls::TlsConnector::from(tls)
+            .connect(server_name, stream)
+            .await
             .map_err(|e| {
-                error!("TLS connection failed: {}", e);
-                ImapClientError::ConnectionFailed {
+                error!("TLS handshake failed: {}", e);
+                ImapClientError::TlsHandshakeFailed {
                     host: account.host.clone(),
-                    port: account.port as u16,
-                    source: Box::new(e),
+                    source: e.to_string().into(),
                 }
             })?;
-            
-        debug!("TLS connection established, attempting login");
-        
-        let session = client
-            .login(&account.username, &account.password)
-            .map_err(|e| {
-                error!("Login failed: {:?}", e.0);
-                ImapClientError::AuthenticationFailed {
-                    username: account.username.clone(),
-                    source: format!("{:?}. Check username and password. If using Gmail, ensure you're using an app-specific password.", e.0),
-                }
+        let greeting = match greeting {
+            Some(greeting) => greeting,
+            None => Self::read_greeting(&mut tls_stream).await.map_err(connection_failed)?,
+        };
+        Ok((tls_stream, greeting))
+    }
+
+    fn peer_fingerprints(account: &ImapAccount, tls_stream: &TlsStream<Transport>) -> Result<PeerFingerprints> {
+        let certificate = tls_stream.get_ref().peer_certificate()?
+            .ok_or_else(|| ImapClientError::TlsHandshakeFailed {
+                host: account.host.clone(),
+                source: "server presented no certificate".into(),
             })?;
-            
-        debug!("Login successful");
-        Ok(session)
+        PeerFingerprints::from_der(&certificate.to_der()?)
     }
 
-    fn connect_plain_sync(account: &ImapAccount) -> Result<imap::Session<TcpStream>> {
-        debug!("Creating plain connection to {}:{}", account.host, account.port);
-        
-        // For plain IMAP connections, we need to construct the client manually
-        let tcp_stream = TcpStream::connect((account.host.as_str(), account.port as u16))
+    /// Fail unless the presented certificate satisfies the account's pin
+    fn verify_pin(account: &ImapAccount, tls_stream: &TlsStream<Transport>, pin: &TlsPin) -> Result<()> {
+        let observed = Self::peer_fingerprints(account, tls_stream)?;
+        if !pin.matches(&observed) {
+            let (certificate_pin, public_key_pin) = observed.pins();
+            let observed = match pin {
+                TlsPin::Certificate(_) => certificate_pin,
+                TlsPin::PublicKey(_) => public_key_pin,
+            };
+            error!("TLS pin mismatch for {}: expected {}, got {}", account.host, pin, observed);
+            return Err(ImapClientError::CertificatePinMismatch {
+                host: account.host.clone(),
+                expected: pin.to_string(),
+                observed: observed.to_string(),
+            }.into());
+        }
+        debug!("Server certificate matches pin {}", pin);
+        Ok(())
+    }
+
+    /// Read one line, a byte at a time so nothing after it is consumed
+    /// before the TLS handshake or the IMAP client takes over the stream
+    async fn read_line<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String> {
+        let mut line = Vec::new();
+        while line.last() != Some(&b'\n') {
+            let mut byte = [0u8; 1];
+            if stream.read(&mut byte).await? == 0 {
+                break;
+            }
+            line.push(byte[0]);
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    /// Read the server's greeting line
+    async fn read_greeting<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String> {
+        let line = Self::read_line(stream).await?;
+        if !line.starts_with("* OK") && !line.starts_with("* PREAUTH") {
+            return Err(anyhow::anyhow!("Unexpected server greeting: {}", line.trim_end()));
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    /// Read the greeting and issue STARTTLS on a plain stream, leaving it
+    /// ready for the TLS handshake. Returns the greeting.
+    async fn starttls<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<String> {
+        let greeting = Self::read_greeting(stream).await?;
+
+        stream.write_all(b"a0 STARTTLS\r\n").await?;
+        stream.flush().await?;
+
+        loop {
+            let line = Self::read_line(stream).await?;
+            if line.is_empty() {
+                return Err(anyhow::anyhow!("Connection closed during STARTTLS"));
+            }
+            if let Some(status) = line.strip_prefix("a0 ") {
+                return if status.starts_with("OK") {
+                    Ok(greeting)
+                } else {
+                    Err(anyhow::anyhow!("Server refused STARTTLS: {}", status.trim_end()))
+                };
+            }
+        }
+    }
+
+    async fn open_tcp(&self) -> Result<Transport> {
+        let account = &self.account;
+        RateLimiter::global().acquire_connection(account, std::time::Instant::now())?;
+        let stream = cancel::connect(&account.host, account.port as u16).await
             .map_err(|e| {
                 error!("TCP connection failed: {}", e);
                 ImapClientError::ConnectionFailed {
@@ -172,168 +557,226 @@ impl ImapClient {
                     source: Box::new(e),
                 }
             })?;
-            
-        let client = imap::Client::new(tcp_stream);
-            
-        debug!("Plain connection established, attempting login");
-        
-        let session = client
-            .login(&account.username, &account.password)
-            .map_err(|e| {
-                error!("Login failed: {:?}", e.0);
-                ImapClientError::AuthenticationFailed {
-                    username: account.username.clone(),
-                    source: format!("{:?}. Check username and password.", e.0),
-                }
-            })?;
-            
-        debug!("Login successful");
-        Ok(session)
+        Ok(ThrottledStream::new(stream, self.meter.clone()))
     }
-    
+
     pub async fn list_folders(&self) -> Result<Vec<String>> {
         debug!("Listing folders for account: {}", self.account.name);
-        
-        let account = self.account.clone();
-        
-        tokio::task::spawn_blocking(move || {
-            let folders = if account.use_tls {
-                let mut session = Self::connect_tls_sync(&account)?;
-                Self::list_folders_with_session(&mut session)?
-            } else {
-                let mut session = Self::connect_plain_sync(&account)?;
-                Self::list_folders_with_session(&mut session)?
-            };
-            
+
+        cancel::run(&self.cancellation, "folder listing", async {
+            let mut session = self.connect().await?;
+            let folders = Self::list_folders_with_session(&mut session).await?;
+
             if folders.is_empty() {
                 warn!("No folders found - this might indicate a configuration issue");
             }
-            
+
             Ok(folders)
         })
         .await
-        .unwrap()
     }
-    
-    fn list_folders_with_session<T>(session: &mut imap::Session<T>) -> Result<Vec<String>>
-    where 
-        T: std::io::Read + std::io::Write
-    {
+
+    async fn list_folders_with_session(session: &mut Session) -> Result<Vec<String>> {
         // Try first approach
-        if let Ok(folders) = Self::try_list_folders_empty(session) {
+        if let Ok(folders) = Self::try_list_folders_empty(session).await {
             if !folders.is_empty() {
-                if let Err(e) = session.logout() {
+                if let Err(e) = session.logout().await {
                     warn!("Logout failed after listing folders: {}", e);
                 }
                 return Ok(folders);
             }
         }
-        
+
         // Try second approach
-        if let Ok(folders) = Self::try_list_folders_inbox(session) {
+        if let Ok(folders) = Self::try_list_folders_inbox(session).await {
             if !folders.is_empty() {
-                if let Err(e) = session.logout() {
+                if let Err(e) = session.logout().await {
                     warn!("Logout failed after listing folders: {}", e);
                 }
                 return Ok(folders);
             }
         }
-        
+
         // Try third approach
-        if let Ok(folders) = Self::try_list_folders_none(session) {
-            if let Err(e) = session.logout() {
+        if let Ok(folders) = Self::try_list_folders_none(session).await {
+            if let Err(e) = session.logout().await {
                 warn!("Logout failed after listing folders: {}", e);
             }
             return Ok(folders);
         }
-        
-        if let Err(e) = session.logout() {
+
+        if let Err(e) = session.logout().await {
             warn!("Logout failed after listing folders: {}", e);
         }
-        
+
         Err(anyhow::anyhow!("Failed to list folders with any prefix combination"))
     }
-    
-    fn try_list_folders_empty<T>(session: &mut imap::Session<T>) -> Result<Vec<String>>
-    where 
-        T: std::io::Read + std::io::Write
-    {
-        let names = session.list(Some(""), Some("*"))?;
+
+    async fn try_list_folders_empty(session: &mut Session) -> Result<Vec<String>> {
+        let names = list(session, Some(""), Some("*")).await?;
         let folders: Vec<String> = names
-            .into_iter()
+            .iter()
             .map(|name| name.name().to_string())
             .collect();
         debug!("Found {} folders with empty prefix", folders.len());
         Ok(folders)
     }
-    
-    fn try_list_folders_inbox<T>(session: &mut imap::Session<T>) -> Result<Vec<String>>
-    where 
-        T: std::io::Read + std::io::Write
-    {
+
+    async fn try_list_folders_inbox(session: &mut Session) -> Result<Vec<String>> {
         warn!("Failed to list with empty prefix, trying INBOX prefix");
-        let names = session.list(Some("INBOX"), Some("*"))?;
+        let names = list(session, Some("INBOX"), Some("*")).await?;
         let folders: Vec<String> = names
-            .into_iter()
+            .iter()
             .map(|name| name.name().to_string())
             .collect();
         debug!("Found {} folders with INBOX prefix", folders.len());
         Ok(folders)
     }
-    
-    fn try_list_folders_none<T>(session: &mut imap::Session<T>) -> Result<Vec<String>>
-    where 
-        T: std::io::Read + std::io::Write
-    {
+
+    async fn try_list_folders_none(session: &mut Session) -> Result<Vec<String>> {
         warn!("Failed to list with INBOX prefix, trying without prefix");
-        let names = session.list(None, Some("*"))?;
+        let names = list(session, None, Some("*")).await?;
         let folders: Vec<String> = names
-            .into_iter()
+            .iter()
             .map(|name| name.name().to_string())
             .collect();
         debug!("Found {} folders with no prefix", folders.len());
         Ok(folders)
     }
-    
-    pub async fn fetch_emails_from_folder(&self, folder: &str, limit: Option<u32>) -> Result<Vec<Email>> {
-        debug!("Fetching emails from folder '{}' with limit {:?} (TLS: {})", folder, limit, self.account.use_tls);
-        
-        let account = self.account.clone();
-        let folder = folder.to_string();
-        
-        tokio::task::spawn_blocking(move || {
-            let result = if account.use_tls {
-                Self::fetch_emails_tls_sync(&account, &folder, limit)
+
+    /// Every folder with its message and unseen counts, read with `STATUS`
+    /// so no folder is selected. Counts are left out for folders that cannot
+    /// be selected or whose status the server refuses.
+    pub async fn folder_statuses(&self) -> Result<Vec<FolderStatus>> {
+        debug!("Reading folder statuses for account: {}", self.account.name);
+
+        cancel::run(&self.cancellation, "folder status", async {
+            let mut session = self.connect().await?;
+            Self::folder_statuses_with_session(&mut session).await
+        })
+        .await
+    }
+
+    async fn folder_statuses_with_session(session: &mut Session) -> Result<Vec<FolderStatus>> {
+        let listed = match list(session, Some(""), Some("*")).await {
+            Ok(names) if !names.is_empty() => names,
+            _ => list(session, None, Some("*")).await.context("Failed to list folders")?,
+        };
+        let mut statuses: Vec<FolderStatus> = listed.iter()
+            .map(|name| FolderStatus {
+                name: name.name().to_string(),
+                delimiter: name.delimiter().map(str::to_string),
+                selectable: !name.attributes().contains(&NameAttribute::NoSelect),
+                messages: None,
+                unseen: None,
+            })
+            .collect();
+
+        for status in statuses.iter_mut().filter(|status| status.selectable) {
+            match session.status(&status.name, "(MESSAGES UNSEEN)").await {
+                Ok(mailbox) => (status.messages, status.unseen) = (Some(mailbox.exists), mailbox.unseen),
+                Err(e) => debug!("Could not read status of folder '{}': {}", status.name, e),
+            }
+        }
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after reading folder statuses: {}", e);
+        }
+        Ok(statuses)
+    }
+
+    /// The UIDVALIDITY and message count of `folder`, read with `STATUS`
+    pub async fn folder_uid_validity(&self, folder: &str) -> Result<(u32, Option<u32>)> {
+        cancel::run(&self.cancellation, "folder status", async {
+            let mut session = self.connect().await?;
+            let status = session.status(folder, "(UIDVALIDITY MESSAGES)").await;
+            let _ = session.logout().await;
+            let status = status.with_context(|| format!("Failed to read status of folder '{}'", folder))?;
+
+            let uid_validity = status.uid_validity
+                .ok_or_else(|| anyhow::anyhow!("Server reported no UIDVALIDITY for folder '{}'", folder))?;
+            Ok((uid_validity, Some(status.exists)))
+        })
+        .await
+    }
+
+    /// The folders below `folder` at any depth, parents first; none when the
+    /// server has a flat namespace. Folders that cannot be selected are left out.
+    pub async fn list_subfolders(&self, folder: &str) -> Result<Vec<String>> {
+        debug!("Listing subfolders of '{}' for account: {}", folder, self.account.name);
+
+        cancel::run(&self.cancellation, "subfolder listing", async {
+            let mut session = self.connect().await?;
+            Self::list_subfolders_with_session(&mut session, folder).await
+        })
+        .await
+    }
+
+    async fn list_subfolders_with_session(session: &mut Session, folder: &str) -> Result<Vec<String>> {
+        // The hierarchy delimiter comes with the folder itself
+        let delimiter = list(session, Some(""), Some(folder)).await?
+            .iter()
+            .find_map(|name| name.delimiter().map(str::to_string));
+        let subfolders = match delimiter {
+            Some(delimiter) => {
+                let pattern = format!("{}{}*", folder, delimiter);
+                let mut subfolders: Vec<String> = list(session, Some(""), Some(&pattern)).await?
+                    .iter()
+                    .filter(|name| !name.attributes().contains(&NameAttribute::NoSelect))
+                    .map(|name| name.name().to_string())
+                    .collect();
+                subfolders.sort();
+                subfolders
+            }
+            None => {
+                debug!("Folder '{}' has no hierarchy delimiter, so no subfolders", folder);
+                Vec::new()
+            }
+        };
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after listing subfolders: {}", e);
+        }
+        Ok(subfolders)
+    }
+
+    /// Fetch up to `limit` emails from a folder: those above `mark` when it
+    /// still applies, else the newest; only unseen ones with `unseen_only`
+    pub async fn fetch_emails_from_folder(&self, folder: &str, limit: Option<u32>, mark: Option<HighWaterMark>, unseen_only: bool) -> Result<FolderFetch> {
+        debug!("Fetching emails from folder '{}' with limit {:?} after {:?}, unseen only: {} (TLS: {})", folder, limit, mark, unseen_only, self.account.use_tls);
+
+        cancel::run(&self.cancellation, "fetch", async {
+            let result = if self.account.use_tls {
+                self.fetch_emails_tls(folder, limit, mark, unseen_only).await
             } else {
-                Self::fetch_emails_plain_sync(&account, &folder, limit)
+                self.fetch_emails_plain(folder, limit, mark, unseen_only).await
             };
-            
+
             match &result {
-                Ok(emails) => info!("fetch_emails_from_folder returned {} emails", emails.len()),
+                Ok(fetch) => info!("fetch_emails_from_folder returned {} emails", fetch.emails.len()),
                 Err(e) => error!("fetch_emails_from_folder failed: {}", e),
             }
-            
+
             result
         })
         .await
-        .unwrap()
     }
-    
-    fn fetch_emails_tls_sync(account: &ImapAccount, folder: &str, limit: Option<u32>) -> Result<Vec<Email>> {
-        let mut session = Self::connect_tls_sync(account)?;
-        
+
+    async fn fetch_emails_tls(&self, folder: &str, limit: Option<u32>, mark: Option<HighWaterMark>, unseen_only: bool) -> Result<FolderFetch> {
+        let mut session = self.connect().await?;
+
         // First, list available folders for debugging
         info!("Listing available folders for verification");
-        match session.list(Some(""), Some("*")) {
+        match list(&mut session, Some(""), Some("*")).await {
             Ok(folders) => {
                 let folder_names: Vec<String> = folders.iter().map(|f| f.name().to_string()).collect();
                 info!("Available folders: {:?}", folder_names);
-                
+
                 // Check if our target folder exists (case-insensitive)
                 let folder_exists = folder_names.iter().any(|f| f.eq_ignore_ascii_case(folder));
                 if !folder_exists {
                     warn!("Target folder '{}' not found in available folders. Available: {:?}", folder, folder_names);
-                    
+
                     // Try to find similar folders
                     let similar: Vec<String> = folder_names.iter()
                         .filter(|f| f.to_lowercase().contains(&folder.to_lowercase().replace("folders/", "")))
@@ -347,17 +790,17 @@ impl ImapClient {
                 warn!("Could not list folders: {}", e);
             }
         }
-        
+
         // Select the folder
         info!("Attempting to select folder: '{}'", folder);
-        let _mailbox = match session.select(folder) {
+        let _mailbox = match session.select(folder).await {
             Ok(mailbox) => {
                 info!("Successfully selected folder '{}', {} messages found", folder, mailbox.exists);
                 mailbox
             },
             Err(e) => {
                 error!("Failed to select folder '{}': {}", folder, e);
-                
+
                 // Try alternative folder names for ProtonMail Bridge
                 let alternatives = vec![
                     folder.replace("Folders/", ""),  // Remove "Folders/" prefix
@@ -365,184 +808,116 @@ impl ImapClient {
                     format!("INBOX.{}", folder.replace("Folders/", "").replace("/", ".")), // INBOX prefix
                     "INBOX".to_string(), // Fall back to INBOX
                 ];
-                
+
                 for alt_folder in alternatives {
                     info!("Trying alternative folder name: '{}'", alt_folder);
-                    match session.select(&alt_folder) {
+                    match session.select(&alt_folder).await {
                         Ok(mailbox) => {
                             warn!("Successfully selected alternative folder '{}' instead of '{}', {} messages found", alt_folder, folder, mailbox.exists);
-                            return Self::fetch_from_selected_folder(session, &alt_folder, limit);
+                            return Self::fetch_from_selected_folder(session, &alt_folder, limit, mark, unseen_only, &self.labels, &self.server).await;
                         },
                         Err(e2) => {
                             debug!("Alternative folder '{}' also failed: {}", alt_folder, e2);
                         }
                     }
                 }
-                
+
                 return Err(anyhow::anyhow!("Failed to select folder '{}' and all alternatives: {}", folder, e));
             }
         };
-        
-        Self::fetch_from_selected_folder(session, folder, limit)
+
+        Self::fetch_from_selected_folder(session, folder, limit, mark, unseen_only, &self.labels, &self.server).await
     }
-    
-    fn fetch_emails_plain_sync(account: &ImapAccount, folder: &str, limit: Option<u32>) -> Result<Vec<Email>> {
-        let session = Self::connect_plain_sync(account)?;
-        
-        Self::fetch_from_selected_folder(session, folder, limit)
+
+    async fn fetch_emails_plain(&self, folder: &str, limit: Option<u32>, mark: Option<HighWaterMark>, unseen_only: bool) -> Result<FolderFetch> {
+        let session = self.connect().await?;
+
+        Self::fetch_from_selected_folder(session, folder, limit, mark, unseen_only, &self.labels, &self.server).await
     }
-    
-    
-    fn fetch_from_selected_folder<T>(mut session: imap::Session<T>, folder: &str, limit: Option<u32>) -> Result<Vec<Email>>
-    where
-        T: std::io::Read + std::io::Write
-    {
-        let mailbox = session.examine(folder)?; // Use EXAMINE instead of SELECT for read-only access
+
+    async fn fetch_from_selected_folder(mut session: Session, folder: &str, limit: Option<u32>, mark: Option<HighWaterMark>, unseen_only: bool, wanted_labels: &[String], shared_server: &Mutex<ServerProfile>) -> Result<FolderFetch> {
+        let mut server = shared_server.lock().map(|server| server.clone()).unwrap_or_default();
+        if server.capabilities.is_none() {
+            server.detect_capabilities(&mut session).await;
+        }
+
+        let mailbox = session.examine(folder).await?; // Use EXAMINE instead of SELECT for read-only access
         let total_messages = mailbox.exists;
-        
+        let uid_validity = mailbox.uid_validity;
+        let after = HighWaterMark::resume_after(mark, uid_validity);
+        let mut resumed = after.is_some();
+
         if total_messages == 0 {
-            if let Err(e) = session.logout() {
+            if let Err(e) = session.logout().await {
                 warn!("Logout failed (this is usually not critical): {}", e);
             }
-            return Ok(vec![]);
+            return Ok(FolderFetch { emails: vec![], uid_validity, resumed });
         }
-        
+
         // Use all messages or respect the provided limit
         let fetch_count = limit.unwrap_or(total_messages).min(total_messages);
-        
+
         info!("Fetching messages: limit={:?}, total_messages={}", limit, total_messages);
-        
+
         // Use ProtonMail Bridge compatible approach: get UIDs first, then fetch headers
-        info!("Step 1: Getting UIDs using UID SEARCH ALL");
+        let query = match (after, unseen_only) {
+            (Some(after), false) => format!("UID {}:*", after.saturating_add(1)),
+            (Some(after), true) => format!("UID {}:* UNSEEN", after.saturating_add(1)),
+            (None, false) => "ALL".to_string(),
+            (None, true) => "UNSEEN".to_string(),
+        };
+        info!("Step 1: Getting UIDs using UID SEARCH {}", query);
         let mut emails = Vec::new();
-        
-        // Get all UIDs first
-        match session.uid_search("ALL") {
+
+        // Get the UIDs first
+        match session::uid_search(&mut session, &query).await {
             Ok(uids) => {
                 info!("Found {} UIDs total", uids.len());
-                
-                // Sort UIDs and take the last N (most recent messages)
-                let mut sorted_uids: Vec<u32> = uids.into_iter().collect();
-                sorted_uids.sort_unstable();
-                
-                let uids_to_fetch: Vec<u32> = sorted_uids
-                    .into_iter()
-                    .rev() // Get newest first (highest UIDs)
-                    .take(fetch_count as usize)
-                    .collect();
-                
+
+                // The oldest N above the mark, or else the newest N
+                let uids_to_fetch = high_water::select_uids(uids.into_iter().collect(), after, fetch_count as usize);
+
                 if uids_to_fetch.is_empty() {
                     info!("No UIDs to fetch");
                 } else {
                     info!("Fetching headers for {} UIDs: {:?}", uids_to_fetch.len(), uids_to_fetch);
-                    
+
                     // Convert UIDs to comma-separated string
                     let uid_list = uids_to_fetch.iter().map(|u| u.to_string()).collect::<Vec<_>>().join(",");
-                    
-                    // Try UID FETCH with headers first
-                    match session.uid_fetch(&uid_list, "BODY.PEEK[HEADER]") {
-                        Ok(messages) => {
-                            info!("BODY.PEEK[HEADER] fetch succeeded, processing {} messages", messages.len());
-                            
-                            // Store partial emails from headers
-                            let mut partial_emails = Vec::new();
-                            for message in messages.iter() {
-                                match parse_email(message) {
-                                    Ok(email) => {
-                                        info!("Successfully parsed email headers: UID={}, from='{}', to='{}', subject='{}'", 
-                                              email.uid, email.from, email.to, email.subject);
-                                        partial_emails.push(email);
-                                    }
-                                    Err(e) => {
-                                        warn!("Failed to parse email from BODY.PEEK[HEADER]: {}", e);
-                                    }
-                                }
-                            }
-                            
-                            // Now try to fetch bodies separately
-                            match session.uid_fetch(&uid_list, "BODY.PEEK[TEXT]") {
-                                Ok(body_messages) => {
-                                    info!("BODY.PEEK[TEXT] fetch succeeded, processing {} body messages", body_messages.len());
-                                    
-                                    // Match bodies to headers by UID
-                                    for body_message in body_messages.iter() {
-                                        if let Some(body_uid) = body_message.uid {
-                                            if let Some(partial_email) = partial_emails.iter_mut().find(|e| e.uid == body_uid) {
-                                                // Try body() first (for BODY.PEEK[TEXT]), then text() as fallback
-                                                let body_data = body_message.body()
-                                                    .or_else(|| body_message.text());
-                                                    
-                                                if let Some(data) = body_data {
-                                                    partial_email.body = String::from_utf8_lossy(data).to_string();
-                                                    info!("Added body content to email UID {}: {} chars", body_uid, partial_email.body.len());
-                                                } else {
-                                                    warn!("No body data found for UID {} despite successful fetch", body_uid);
-                                                }
-                                            }
-                                        }
-                                    }
-                                },
-                                Err(e) => {
-                                    warn!("BODY.PEEK[TEXT] fetch failed: {}, using headers-only emails", e);
+
+                    // Start with the strategy that worked last time, and
+                    // probe the rest only when it fails
+                    let known = server.strategy;
+                    server.strategy = None;
+                    for strategy in FetchStrategy::order(known) {
+                        match Self::fetch_with_strategy(&mut session, &uid_list, strategy).await {
+                            Ok(fetched) => {
+                                if known != Some(strategy) {
+                                    info!("Fetch strategy '{}' works for this server", strategy.as_str());
                                 }
+                                server.strategy = Some(strategy);
+                                emails = fetched;
+                                break;
                             }
-                            
-                            emails.extend(partial_emails);
-                        },
-                        Err(e) => {
-                            error!("BODY.PEEK[HEADER] fetch failed with specific error: {:?}", e);
-                            warn!("BODY.PEEK[HEADER] fetch failed: {}, trying ENVELOPE", e);
-                            // Try UID FETCH with ENVELOPE as fallback
-                            match session.uid_fetch(&uid_list, "ENVELOPE") {
-                                Ok(messages) => {
-                                    info!("ENVELOPE fetch succeeded, processing {} messages", messages.len());
-                                    for message in messages.iter() {
-                                        match parse_email(message) {
-                                            Ok(email) => {
-                                                info!("Successfully parsed email from ENVELOPE: UID={}, from='{}', to='{}', subject='{}'", 
-                                                      email.uid, email.from, email.to, email.subject);
-                                                emails.push(email);
-                                            }
-                                            Err(e) => {
-                                                warn!("Failed to parse email from ENVELOPE: {}", e);
-                                            }
-                                        }
-                                    }
-                                },
-                                Err(e2) => {
-                                    error!("ENVELOPE fetch failed with specific error: {:?}", e2);
-                                    warn!("ENVELOPE fetch also failed: {}, falling back to basic UID fetch", e2);
-                                    // Final fallback to basic UID fetch (just UIDs)
-                                    match session.uid_fetch(&uid_list, "UID") {
-                                        Ok(messages) => {
-                                            warn!("Using UID-only fetch - emails will have minimal data");
-                                            for message in messages.iter() {
-                                                match parse_email(message) {
-                                                    Ok(email) => {
-                                                        info!("UID-only parsed email: UID={}, from='{}', to='{}', subject='{}'", 
-                                                              email.uid, email.from, email.to, email.subject);
-                                                        emails.push(email);
-                                                    }
-                                                    Err(e) => {
-                                                        warn!("Failed to parse email from UID-only: {}", e);
-                                                    }
-                                                }
-                                            }
-                                        },
-                                        Err(e3) => {
-                                            error!("Even basic UID fetch failed: {:?}", e3);
-                                        }
-                                    }
+                            Err(e) => {
+                                warn!("Fetch strategy '{}' failed: {}", strategy.as_str(), e);
+                                if known == Some(strategy) {
+                                    // Whatever the server was, it has changed
+                                    server.detect_capabilities(&mut session).await;
                                 }
                             }
                         }
                     }
+                    if server.strategy.is_none() {
+                        error!("Every fetch strategy failed for UIDs {}", uid_list);
+                    }
                 }
             },
             Err(search_err) => {
-                error!("UID SEARCH ALL failed: {:?}", search_err);
+                error!("UID SEARCH {} failed: {:?}", query, search_err);
                 warn!("Could not get UIDs from server, falling back to sequence-based fetch");
-                
+                resumed = false;
+
                 // Fallback to sequence-based fetch if UID SEARCH fails
                 let start = if total_messages > fetch_count {
                     total_messages - fetch_count + 1
@@ -550,14 +925,14 @@ impl ImapClient {
                     1
                 };
                 let sequence_set = format!("{}:{}", start, total_messages);
-                
-                match session.fetch(&sequence_set, "UID") {
+
+                match fetch(&mut session, &sequence_set, "UID").await {
                     Ok(messages) => {
                         warn!("Using sequence-based UID-only fetch - emails will have minimal data");
                         for message in messages.iter() {
                             match parse_email(message) {
                                 Ok(email) => {
-                                    info!("Sequence-based UID-only email: UID={}, from='{}', to='{}', subject='{}'", 
+                                    info!("Sequence-based UID-only email: UID={}, from='{}', to='{}', subject='{}'",
                                           email.uid, email.from, email.to, email.subject);
                                     emails.push(email);
                                 }
@@ -573,210 +948,382 @@ impl ImapClient {
                 }
             }
         }
-        
+
         info!("Parsed {} emails from IMAP messages", emails.len());
-        
+
+        let uids: Vec<u32> = emails.iter().map(|email| email.uid).collect();
+        let mut categories = importance::gmail_categories(&mut session, &server, &uids).await;
+        let mut labels = labels::gmail_labels(&mut session, &server, &uids, wanted_labels).await;
+        for email in &mut emails {
+            email.category = categories.remove(&email.uid);
+            email.labels = labels.as_mut().map(|labels| labels.remove(&email.uid).unwrap_or_default());
+        }
+
         // Sort by date, newest first
-        emails.sort_by(|a, b| b.date.cmp(&a.date));
-        
-        if let Err(e) = session.logout() {
+        emails.sort_by_key(|e| std::cmp::Reverse(e.date));
+
+        if let Ok(mut shared) = shared_server.lock() {
+            *shared = server;
+        }
+
+        if let Err(e) = session.logout().await {
             warn!("Logout failed (this is usually not critical): {}", e);
         }
+        Ok(FolderFetch { emails, uid_validity, resumed })
+    }
+
+    /// Fetch the emails of `uid_list` the way `strategy` does; fails when the
+    /// server refuses its fetch, not when single emails fail to parse
+    async fn fetch_with_strategy(session: &mut Session, uid_list: &str, strategy: FetchStrategy) -> Result<Vec<Email>> {
+        let items = match strategy {
+            FetchStrategy::Headers => "BODY.PEEK[HEADER]",
+            FetchStrategy::Envelope => "ENVELOPE",
+            FetchStrategy::UidOnly => "UID",
+        };
+        let messages = uid_fetch(session, uid_list, items).await?;
+        if messages.is_empty() {
+            // A refused fetch comes back empty rather than failing, and the
+            // UIDs were just found, so nothing back means refused
+            return Err(anyhow::anyhow!("Server returned no messages for {}", items));
+        }
+        info!("{} fetch succeeded, processing {} messages", items, messages.len());
+        if strategy == FetchStrategy::UidOnly {
+            warn!("Using UID-only fetch - emails will have minimal data");
+        }
+
+        let mut emails = Vec::new();
+        for message in messages.iter() {
+            match parse_email(message) {
+                Ok(email) => {
+                    info!("Successfully parsed email from {}: UID={}, from='{}', to='{}', subject='{}'",
+                          items, email.uid, email.from, email.to, email.subject);
+                    emails.push(email);
+                }
+                Err(e) => {
+                    warn!("Failed to parse email from {}: {}", items, e);
+                }
+            }
+        }
+
+        if strategy == FetchStrategy::Headers {
+            // Now try to fetch bodies separately
+            match uid_fetch(session, uid_list, "BODY.PEEK[TEXT]").await {
+                Ok(body_messages) => {
+                    info!("BODY.PEEK[TEXT] fetch succeeded, processing {} body messages", body_messages.len());
+
+                    // Match bodies to headers by UID
+                    for body_message in body_messages.iter() {
+                        if let Some(body_uid) = body_message.uid {
+                            if let Some(partial_email) = emails.iter_mut().find(|e| e.uid == body_uid) {
+                                // Try body() first (for BODY.PEEK[TEXT]), then text() as fallback
+                                let body_data = body_message.body()
+                                    .or_else(|| body_message.text());
+
+                                if let Some(data) = body_data {
+                                    partial_email.body = String::from_utf8_lossy(data).to_string();
+                                    info!("Added body content to email UID {}: {} chars", body_uid, partial_email.body.len());
+                                } else {
+                                    warn!("No body data found for UID {} despite successful fetch", body_uid);
+                                }
+                            }
+                        }
+                    }
+                },
+                Err(e) => {
+                    warn!("BODY.PEEK[TEXT] fetch failed: {}, using headers-only emails", e);
+                }
+            }
+        }
         Ok(emails)
     }
-    
+
     #[allow(dead_code)]
     pub async fn search_emails(&self, _folder: &str, _query: &str) -> Result<Vec<Email>> {
-        // Search methods disabled for now - not currently used 
+        // Search methods disabled for now - not currently used
         warn!("Search functionality not yet implemented - returning empty results");
         Ok(vec![])
     }
-    
+
     /// Mark an email as read by UID in a specific folder
     pub async fn mark_as_read(&self, uid: u32) -> Result<()> {
         self.mark_as_read_in_folder(uid, "INBOX").await
     }
-    
+
     /// Mark an email as read by UID in a specific folder
     pub async fn mark_as_read_in_folder(&self, uid: u32, folder: &str) -> Result<()> {
         info!("Marking email UID {} as read in folder '{}'", uid, folder);
-        
-        let account = self.account.clone();
-        let folder = folder.to_string();
-        
-        tokio::task::spawn_blocking(move || {
-            if account.use_tls {
-                Self::mark_as_read_tls_sync(&account, uid, &folder)
-            } else {
-                Self::mark_as_read_plain_sync(&account, uid, &folder)
-            }
+
+        cancel::run(&self.cancellation, "mark as read", async {
+            let mut session = self.connect().await?;
+            Self::mark_as_read_with_session(&mut session, uid, folder).await
         })
         .await
-        .unwrap()
-    }
-    
-    fn mark_as_read_tls_sync(account: &ImapAccount, uid: u32, folder: &str) -> Result<()> {
-        let mut session = Self::connect_tls_sync(account)?;
-        Self::mark_as_read_with_session(&mut session, uid, folder)
-    }
-    
-    fn mark_as_read_plain_sync(account: &ImapAccount, uid: u32, folder: &str) -> Result<()> {
-        let mut session = Self::connect_plain_sync(account)?;
-        Self::mark_as_read_with_session(&mut session, uid, folder)
     }
-    
-    fn mark_as_read_with_session<T>(session: &mut imap::Session<T>, uid: u32, folder: &str) -> Result<()>
-    where
-        T: std::io::Read + std::io::Write
-    {
+
+    async fn mark_as_read_with_session(session: &mut Session, uid: u32, folder: &str) -> Result<()> {
         // Select the folder first
-        session.select(folder)
+        session.select(folder).await
             .with_context(|| format!("Failed to select folder '{}' to mark email as read", folder))?;
-        
+
         // Use UID STORE command to add the \Seen flag
-        session.uid_store(format!("{}", uid), "+FLAGS.SILENT (\\Seen)")
+        uid_store(session, &uid.to_string(), "+FLAGS.SILENT (\\Seen)").await
             .with_context(|| format!("Failed to mark email UID {} as read", uid))?;
-            
+
         info!("Successfully marked email UID {} as read in folder '{}'", uid, folder);
-        
-        if let Err(e) = session.logout() {
+
+        if let Err(e) = session.logout().await {
             warn!("Logout failed after marking email as read: {}", e);
         }
-        
+
         Ok(())
     }
-    
+
+    /// Clear the read flag on an email by UID in a specific folder
+    pub async fn mark_as_unread_in_folder(&self, uid: u32, folder: &str) -> Result<()> {
+        info!("Marking email UID {} as unread in folder '{}'", uid, folder);
+
+        cancel::run(&self.cancellation, "mark as unread", async {
+            let mut session = self.connect().await?;
+            Self::mark_as_unread_with_session(&mut session, uid, folder).await
+        })
+        .await
+    }
+
+    async fn mark_as_unread_with_session(session: &mut Session, uid: u32, folder: &str) -> Result<()> {
+        session.select(folder).await
+            .with_context(|| format!("Failed to select folder '{}' to mark email as unread", folder))?;
+
+        // Use UID STORE command to remove the \Seen flag
+        uid_store(session, &uid.to_string(), "-FLAGS.SILENT (\\Seen)").await
+            .with_context(|| format!("Failed to mark email UID {} as unread", uid))?;
+
+        info!("Successfully marked email UID {} as unread in folder '{}'", uid, folder);
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after marking email as unread: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Look up the UID of a message in a folder by its Message-ID header
+    pub async fn find_uid_by_message_id(&self, folder: &str, message_id: &str) -> Result<Option<u32>> {
+        debug!("Searching folder '{}' for Message-ID {}", folder, message_id);
+
+        cancel::run(&self.cancellation, "Message-ID search", async {
+            let mut session = self.connect().await?;
+            Self::find_uid_by_message_id_with_session(&mut session, folder, message_id).await
+        })
+        .await
+    }
+
+    async fn find_uid_by_message_id_with_session(session: &mut Session, folder: &str, message_id: &str) -> Result<Option<u32>> {
+        session.select(folder).await
+            .with_context(|| format!("Failed to select folder '{}' to search for message", folder))?;
+
+        let query = format!("HEADER Message-ID \"{}\"", message_id.replace('"', ""));
+        let uids = session::uid_search(session, &query).await
+            .with_context(|| format!("Failed to search folder '{}' for Message-ID {}", folder, message_id))?;
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after searching for message: {}", e);
+        }
+
+        Ok(uids.into_iter().max())
+    }
+
     /// Delete an email by UID
     pub async fn delete_email(&self, uid: u32) -> Result<()> {
         self.delete_email_in_folder(uid, "INBOX").await
     }
-    
+
     /// Delete an email by UID in a specific folder
     pub async fn delete_email_in_folder(&self, uid: u32, folder: &str) -> Result<()> {
         info!("Deleting email UID {} in folder '{}'", uid, folder);
-        
-        let account = self.account.clone();
-        let folder = folder.to_string();
-        
-        tokio::task::spawn_blocking(move || {
-            if account.use_tls {
-                Self::delete_email_tls_sync(&account, uid, &folder)
-            } else {
-                Self::delete_email_plain_sync(&account, uid, &folder)
-            }
+
+        cancel::run(&self.cancellation, "delete", async {
+            let mut session = self.connect().await?;
+            Self::delete_email_with_session(&mut session, uid, folder).await
         })
         .await
-        .unwrap()
     }
-    
-    fn delete_email_tls_sync(account: &ImapAccount, uid: u32, folder: &str) -> Result<()> {
-        let mut session = Self::connect_tls_sync(account)?;
-        Self::delete_email_with_session(&mut session, uid, folder)
-    }
-    
-    fn delete_email_plain_sync(account: &ImapAccount, uid: u32, folder: &str) -> Result<()> {
-        let mut session = Self::connect_plain_sync(account)?;
-        Self::delete_email_with_session(&mut session, uid, folder)
-    }
-    
-    fn delete_email_with_session<T>(session: &mut imap::Session<T>, uid: u32, folder: &str) -> Result<()>
-    where
-        T: std::io::Read + std::io::Write
-    {
+
+    async fn delete_email_with_session(session: &mut Session, uid: u32, folder: &str) -> Result<()> {
         // Select the folder first
-        session.select(folder)
+        session.select(folder).await
             .with_context(|| format!("Failed to select folder '{}' to delete email", folder))?;
-        
-        // Mark email as deleted using UID STORE
-        session.uid_store(format!("{}", uid), "+FLAGS.SILENT (\\Deleted)")
-            .with_context(|| format!("Failed to mark email UID {} as deleted", uid))?;
-        
-        // Expunge to actually remove the email
-        session.expunge()
-            .with_context(|| format!("Failed to expunge email UID {} after marking as deleted", uid))?;
-            
+
+        Self::remove_with_session(session, &uid.to_string()).await?;
+
         info!("Successfully deleted email UID {} from folder '{}'", uid, folder);
-        
-        if let Err(e) = session.logout() {
+
+        if let Err(e) = session.logout().await {
             warn!("Logout failed after deleting email: {}", e);
         }
-        
+
         Ok(())
     }
-    
+
     /// Move an email to another folder by UID
     pub async fn move_to_folder(&self, uid: u32, target_folder: &str) -> Result<()> {
         self.move_to_folder_from_folder(uid, "INBOX", target_folder).await
     }
-    
+
     /// Move an email to another folder by UID from a specific source folder
     pub async fn move_to_folder_from_folder(&self, uid: u32, source_folder: &str, target_folder: &str) -> Result<()> {
         info!("Moving email UID {} from folder '{}' to folder '{}'", uid, source_folder, target_folder);
-        
-        let account = self.account.clone();
-        let source_folder = source_folder.to_string();
-        let target_folder = target_folder.to_string();
-        
-        tokio::task::spawn_blocking(move || {
-            if account.use_tls {
-                Self::move_to_folder_tls_sync(&account, uid, &source_folder, &target_folder)
-            } else {
-                Self::move_to_folder_plain_sync(&account, uid, &source_folder, &target_folder)
-            }
+
+        cancel::run(&self.cancellation, "move", async {
+            let mut session = self.connect().await?;
+            Self::move_to_folder_with_session(&mut session, uid, source_folder, target_folder).await
         })
         .await
-        .unwrap()
-    }
-    
-    fn move_to_folder_tls_sync(account: &ImapAccount, uid: u32, source_folder: &str, target_folder: &str) -> Result<()> {
-        let mut session = Self::connect_tls_sync(account)?;
-        Self::move_to_folder_with_session(&mut session, uid, source_folder, target_folder)
     }
-    
-    fn move_to_folder_plain_sync(account: &ImapAccount, uid: u32, source_folder: &str, target_folder: &str) -> Result<()> {
-        let mut session = Self::connect_plain_sync(account)?;
-        Self::move_to_folder_with_session(&mut session, uid, source_folder, target_folder)
-    }
-    
-    fn move_to_folder_with_session<T>(session: &mut imap::Session<T>, uid: u32, source_folder: &str, target_folder: &str) -> Result<()>
-    where
-        T: std::io::Read + std::io::Write
-    {
+
+    async fn move_to_folder_with_session(session: &mut Session, uid: u32, source_folder: &str, target_folder: &str) -> Result<()> {
         // Select the source folder first
-        session.select(source_folder)
+        session.select(source_folder).await
             .with_context(|| format!("Failed to select source folder '{}' to move email", source_folder))?;
-        
-        // Try UID MOVE command first (modern IMAP extension)
-        match session.uid_mv(format!("{}", uid), target_folder) {
-            Ok(_) => {
-                info!("Successfully moved email UID {} from '{}' to folder '{}' using UID MOVE", uid, source_folder, target_folder);
-            }
-            Err(_) => {
-                // Fallback to copy + delete for servers that don't support MOVE
-                warn!("UID MOVE not supported, using COPY + DELETE fallback");
-                
-                // Copy the email to the target folder
-                session.uid_copy(format!("{}", uid), target_folder)
-                    .with_context(|| format!("Failed to copy email UID {} to folder '{}'", uid, target_folder))?;
-                
-                // Mark original email as deleted
-                session.uid_store(format!("{}", uid), "+FLAGS.SILENT (\\Deleted)")
-                    .with_context(|| format!("Failed to mark email UID {} as deleted after copy", uid))?;
-                
-                // Expunge to remove the original
-                session.expunge()
-                    .with_context(|| format!("Failed to expunge email UID {} after copy", uid))?;
-                
-                info!("Successfully moved email UID {} from '{}' to folder '{}' using COPY + DELETE", uid, source_folder, target_folder);
+
+        Self::move_with_session(session, &uid.to_string(), target_folder).await?;
+        info!("Successfully moved email UID {} from '{}' to folder '{}'", uid, source_folder, target_folder);
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after moving email: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Apply a rule's post-processing action to the emails of a batch over a
+    /// single session, reporting the outcome per UID
+    pub async fn apply_post_process_batch(&self, batch: &PostProcessBatch) -> Result<BatchOutcome> {
+        let uids = batch.uids();
+        info!("Applying {} to {} emails in folder '{}'", batch.action.as_str(), uids.len(), batch.folder);
+        if uids.is_empty() || matches!(batch.action, EmailAction::DoNothing) {
+            return Ok(BatchOutcome { applied: uids, failed: Vec::new() });
+        }
+
+        cancel::run(&self.cancellation, "post-processing batch", async {
+            let mut session = self.connect().await?;
+            Ok(Self::apply_post_process_batch_with_session(&mut session, batch, &uids).await)
+        })
+        .await
+    }
+
+    async fn apply_post_process_batch_with_session(session: &mut Session, batch: &PostProcessBatch, uids: &[u32]) -> BatchOutcome {
+        let outcome = Self::apply_to_folder_with_session(session, batch, uids).await;
+        info!("Applied {} to {} of {} emails in folder '{}'",
+              batch.action.as_str(), outcome.applied.len(), uids.len(), batch.folder);
+
+        if let Err(e) = session.logout().await {
+            warn!("Logout failed after post-processing emails: {}", e);
+        }
+
+        outcome
+    }
+
+    async fn apply_to_folder_with_session(session: &mut Session, batch: &PostProcessBatch, uids: &[u32]) -> BatchOutcome {
+        if let Err(e) = session.select(&batch.folder).await {
+            return BatchOutcome::all_failed(uids, &format!("Failed to select folder '{}': {}", batch.folder, e));
+        }
+
+        // Emails moved or deleted since they were fetched cannot be acted on
+        let mut outcome = BatchOutcome::default();
+        let present: Vec<u32> = match session::uid_search(session, &format!("UID {}", post_process::uid_set(uids))).await {
+            Ok(found) => {
+                let (present, missing): (Vec<u32>, Vec<u32>) = uids.iter().partition(|uid| found.contains(uid));
+                outcome.failed.extend(missing.into_iter().map(|uid| (uid, format!("No longer in folder '{}'", batch.folder))));
+                present
+            }
+            Err(e) => {
+                warn!("Failed to look up emails in folder '{}', acting on all of them: {}", batch.folder, e);
+                uids.to_vec()
             }
+        };
+        if present.is_empty() {
+            return outcome;
         }
-        
-        if let Err(e) = session.logout() {
-            warn!("Logout failed after moving email: {}", e);
+
+        match Self::apply_action_with_session(session, batch, &post_process::uid_set(&present)).await {
+            Ok(()) => outcome.applied.extend(present),
+            Err(e) if present.len() > 1 => {
+                // One message the server refuses should not hold back the rest
+                warn!("Batched {} of {} emails failed, retrying one at a time: {}", batch.action.as_str(), present.len(), e);
+                for uid in present {
+                    match Self::apply_action_with_session(session, batch, &uid.to_string()).await {
+                        Ok(()) => outcome.applied.push(uid),
+                        Err(e) => outcome.failed.push((uid, format!("{:#}", e))),
+                    }
+                }
+            }
+            Err(e) => outcome.failed.extend(present.into_iter().map(|uid| (uid, format!("{:#}", e)))),
+        }
+        outcome
+    }
+
+    /// Apply the batch's action to the UIDs of `uid_set` in the selected folder
+    async fn apply_action_with_session(session: &mut Session, batch: &PostProcessBatch, uid_set: &str) -> Result<()> {
+        match batch.action {
+            EmailAction::DoNothing => {}
+            EmailAction::MarkAsRead => {
+                uid_store(session, uid_set, "+FLAGS.SILENT (\\Seen)").await
+                    .with_context(|| format!("Failed to mark emails {} as read", uid_set))?;
+            }
+            EmailAction::Delete => Self::remove_with_session(session, uid_set).await?,
+            EmailAction::MoveToFolder => {
+                let target_folder = batch.target_folder.as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("No target folder to move emails to"))?;
+                Self::move_with_session(session, uid_set, target_folder).await?;
+            }
+            EmailAction::AddFlag => {
+                let flag = batch.target_folder.as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("No flag to add to emails"))?;
+                uid_store(session, uid_set, &format!("+FLAGS.SILENT ({})", flag)).await
+                    .with_context(|| format!("Failed to flag emails {} {}", uid_set, flag))?;
+            }
+            EmailAction::CopyToFolder => {
+                let target_folder = batch.target_folder.as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("No target folder to copy emails to"))?;
+                session.uid_copy(uid_set, target_folder).await
+                    .with_context(|| format!("Failed to copy emails {} to folder '{}'", uid_set, target_folder))?;
+            }
         }
-        
         Ok(())
     }
+
+    /// Flag the UIDs of `uid_set` in the selected folder as deleted and expunge
+    /// them. With UIDPLUS only those UIDs are expunged; plain EXPUNGE also
+    /// removes whatever other clients flagged as deleted in the folder.
+    async fn remove_with_session(session: &mut Session, uid_set: &str) -> Result<()> {
+        uid_store(session, uid_set, "+FLAGS.SILENT (\\Deleted)").await
+            .with_context(|| format!("Failed to mark emails {} as deleted", uid_set))?;
+        let uidplus = Self::has_capability(session, "UIDPLUS").await;
+        session::expunge(session, uidplus.then_some(uid_set)).await
+            .with_context(|| format!("Failed to expunge emails {} after marking as deleted", uid_set))
+    }
+
+    /// Move the UIDs of `uid_set` out of the selected folder with UID MOVE,
+    /// or by copying and removing them where the server lacks MOVE
+    async fn move_with_session(session: &mut Session, uid_set: &str, target_folder: &str) -> Result<()> {
+        if Self::has_capability(session, "MOVE").await {
+            match session.uid_mv(uid_set, target_folder).await {
+                Ok(()) => return Ok(()),
+                Err(e) => warn!("UID MOVE of emails {} failed, using COPY + DELETE fallback: {}", uid_set, e),
+            }
+        } else {
+            debug!("Server lacks MOVE, copying emails {} to '{}' and deleting them", uid_set, target_folder);
+        }
+        session.uid_copy(uid_set, target_folder).await
+            .with_context(|| format!("Failed to copy emails {} to folder '{}'", uid_set, target_folder))?;
+        Self::remove_with_session(session, uid_set).await
+    }
+
+    async fn has_capability(session: &mut Session, capability: &str) -> bool {
+        session.capabilities().await
+            .map(|capabilities| capabilities.has_str(capability))
+            .unwrap_or(false)
+    }
 }
 
 /// Decode MIME-encoded headers (like =?utf-8?q?..?=)
@@ -791,54 +1338,25 @@ fn decode_mime_header(encoded: &str) -> String {
     }
 }
 
-fn parse_email(fetch: &imap::types::Fetch) -> Result<Email> {
+fn parse_email(fetch: &Fetch) -> Result<Email> {
     let uid = fetch.uid.ok_or_else(|| anyhow::anyhow!("Message has no UID"))?;
     
-    let mut subject = String::new();
-    let mut from = String::new();
-    let mut to = String::new();
-    let mut date = Utc::now();
-    let mut message_id = String::new();
+    let mut headers = mime::Headers::default();
     let body;
     
     // Try parsing BODY[HEADER.FIELDS] first
-    if let Some(body_data) = fetch.body() {
-        let body_str = String::from_utf8_lossy(body_data);
-        info!("Raw BODY data: {}", body_str.chars().take(200).collect::<String>());
-        
-        // Parse header fields from BODY response
-        for line in body_str.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-            
-            if line.starts_with("Subject: ") {
-                let raw_subject = line.strip_prefix("Subject: ").unwrap_or("");
-                subject = decode_mime_header(raw_subject);
-            } else if line.starts_with("From: ") {
-                let raw_from = line.strip_prefix("From: ").unwrap_or("");
-                from = decode_mime_header(raw_from);
-            } else if line.starts_with("To: ") {
-                let raw_to = line.strip_prefix("To: ").unwrap_or("");
-                to = decode_mime_header(raw_to);
-            } else if line.starts_with("Date: ") {
-                let date_str = line.strip_prefix("Date: ").unwrap_or("");
-                if let Ok(parsed_date) = DateTime::parse_from_rfc2822(date_str) {
-                    date = parsed_date.with_timezone(&Utc);
-                }
-            } else if line.starts_with("Message-ID: ") || line.starts_with("Message-Id: ") {
-                message_id = line.strip_prefix("Message-ID: ")
-                    .or_else(|| line.strip_prefix("Message-Id: "))
-                    .unwrap_or("").to_string();
-                debug!("Found Message-ID: {}", message_id);
-            }
-        }
+    let parsed = fetch.body().and_then(|body_data| {
+        info!("Raw BODY data: {}", String::from_utf8_lossy(body_data).chars().take(200).collect::<String>());
+        mime::Headers::parse(body_data)
+    });
+    if let Some(parsed) = parsed {
+        headers = parsed;
+        debug!("Found Message-ID: {}", headers.message_id);
     } else if let Some(envelope) = fetch.envelope() {
         // Fall back to ENVELOPE (structured data)
         if let Some(subj) = &envelope.subject {
             let raw_subject = String::from_utf8_lossy(subj);
-            subject = decode_mime_header(&raw_subject);
+            headers.subject = decode_mime_header(&raw_subject);
         }
         
         // Parse from addresses
@@ -846,13 +1364,13 @@ fn parse_email(fetch: &imap::types::Fetch) -> Result<Email> {
             if !from_addrs.is_empty() {
                 let addr = &from_addrs[0];
                 let name = addr.name.as_ref()
-                    .map(|n| String::from_utf8_lossy(n).to_string())
+                    .map(|n| decode_mime_header(&String::from_utf8_lossy(n)))
                     .unwrap_or_default();
                 let email = format!("{}@{}", 
                     addr.mailbox.as_ref().map(|m| String::from_utf8_lossy(m)).unwrap_or_default(),
                     addr.host.as_ref().map(|h| String::from_utf8_lossy(h)).unwrap_or_default()
                 );
-                from = if !name.is_empty() {
+                headers.from = if !name.is_empty() {
                     format!("{} <{}>", name, email)
                 } else {
                     email
@@ -868,7 +1386,7 @@ fn parse_email(fetch: &imap::types::Fetch) -> Result<Email> {
                     addr.mailbox.as_ref().map(|m| String::from_utf8_lossy(m)).unwrap_or_default(),
                     addr.host.as_ref().map(|h| String::from_utf8_lossy(h)).unwrap_or_default()
                 );
-                to = email;
+                headers.to = email;
             }
         }
         
@@ -876,51 +1394,31 @@ fn parse_email(fetch: &imap::types::Fetch) -> Result<Email> {
         if let Some(date_str) = &envelope.date {
             let date_string = String::from_utf8_lossy(date_str);
             if let Ok(parsed_date) = DateTime::parse_from_rfc2822(&date_string) {
-                date = parsed_date.with_timezone(&Utc);
+                headers.date = Some(parsed_date.with_timezone(&Utc));
             }
         }
         
         // Parse message ID
         if let Some(msg_id) = &envelope.message_id {
-            message_id = String::from_utf8_lossy(msg_id).to_string();
+            headers.message_id = String::from_utf8_lossy(msg_id).to_string();
         }
-    } else if let Some(header_data) = fetch.header() {
+    } else if let Some(parsed) = fetch.header().and_then(mime::Headers::parse) {
         // Fall back to parsing raw headers if neither BODY nor ENVELOPE is available
-        let header_str = String::from_utf8_lossy(header_data);
-        
-        // Simple header parsing
-        for line in header_str.lines() {
-            if line.starts_with("Subject: ") {
-                let raw_subject = line.strip_prefix("Subject: ").unwrap_or("");
-                subject = decode_mime_header(raw_subject);
-            } else if line.starts_with("From: ") {
-                let raw_from = line.strip_prefix("From: ").unwrap_or("");
-                from = decode_mime_header(raw_from);
-            } else if line.starts_with("To: ") {
-                let raw_to = line.strip_prefix("To: ").unwrap_or("");
-                to = decode_mime_header(raw_to);
-            } else if line.starts_with("Date: ") {
-                let date_str = line.strip_prefix("Date: ").unwrap_or("");
-                // Try to parse the date
-                if let Ok(parsed_date) = DateTime::parse_from_rfc2822(date_str) {
-                    date = parsed_date.with_timezone(&Utc);
-                }
-            } else if line.starts_with("Message-ID: ") {
-                message_id = line.strip_prefix("Message-ID: ").unwrap_or("").to_string();
-            }
-        }
+        headers = parsed;
     }
+    let mime::Headers { mut subject, mut from, to, date, mut message_id, importance: declared_importance, content_type, transfer_encoding, list_unsubscribe, list_unsubscribe_post, in_reply_to, references } = headers;
+    let date = date.unwrap_or_else(Utc::now);
     
     // Parse body if available
     if let Some(body_data) = fetch.text() {
         body = String::from_utf8_lossy(body_data).to_string();
     } else {
         // If no body is available, use a placeholder
-        body = "[Body not available - fetched headers only]".to_string();
+        body = BODY_UNAVAILABLE.to_string();
     }
     
     // Check if email is seen
-    let is_seen = fetch.flags().iter().any(|flag| matches!(flag, imap::types::Flag::Seen));
+    let is_seen = fetch.flags().any(|flag| matches!(flag, Flag::Seen));
     
     // If we don't have basic email info, generate defaults
     if subject.is_empty() && from.is_empty() && message_id.is_empty() {
@@ -943,10 +1441,19 @@ fn parse_email(fetch: &imap::types::Fetch) -> Result<Email> {
         date,
         body,
         is_seen,
+        importance: declared_importance,
+        category: None,
+        labels: None,
+        content_type,
+        transfer_encoding,
+        list_unsubscribe,
+        list_unsubscribe_post,
+        in_reply_to,
+        references,
     })
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[allow(dead_code)]
 pub struct Email {
     pub uid: u32,
@@ -957,4 +1464,66 @@ pub struct Email {
     pub date: DateTime<Utc>,
     pub body: String,
     pub is_seen: bool,
+    /// Importance declared in the headers, if any
+    #[serde(default)]
+    pub importance: Option<Importance>,
+    /// Gmail category tab, on Gmail servers
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Gmail labels, on Gmail servers; `None` where the server has none
+    #[serde(default)]
+    pub labels: Option<Vec<String>>,
+    /// `Content-Type` header, telling how to read a multipart body
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// `Content-Transfer-Encoding` header of a single-part body
+    #[serde(default)]
+    pub transfer_encoding: Option<String>,
+    /// `List-Unsubscribe` header of mailing list posts
+    #[serde(default)]
+    pub list_unsubscribe: Option<String>,
+    /// `List-Unsubscribe-Post` header, offering one-click unsubscribe
+    #[serde(default)]
+    pub list_unsubscribe_post: Option<String>,
+    /// `In-Reply-To` header: the message this one answers
+    #[serde(default)]
+    pub in_reply_to: Option<String>,
+    /// `References` header: the thread's earlier messages, oldest first
+    #[serde(default)]
+    pub references: Option<String>,
+}
+
+impl Email {
+    /// An email from a complete raw message, e.g. one delivered over SMTP;
+    /// it has no UID and counts as unseen
+    pub fn from_message(raw: &[u8]) -> Option<Self> {
+        let header_end = raw.windows(4).position(|window| window == b"\r\n\r\n").map(|at| at + 4)
+            .or_else(|| raw.windows(2).position(|window| window == b"\n\n").map(|at| at + 2))
+            .unwrap_or(raw.len());
+        let headers = mime::Headers::parse(&raw[..header_end])?;
+        let mime::Headers { mut subject, mut from, to, date, message_id, importance, content_type, transfer_encoding, list_unsubscribe, list_unsubscribe_post, in_reply_to, references } = headers;
+        if subject.is_empty() && from.is_empty() {
+            subject = "[Delivered email]".to_string();
+            from = "[Unknown sender]".to_string();
+        }
+        Some(Email {
+            uid: 0,
+            message_id,
+            subject,
+            from,
+            to,
+            date: date.unwrap_or_else(Utc::now),
+            body: String::from_utf8_lossy(&raw[header_end..]).to_string(),
+            is_seen: false,
+            importance,
+            category: None,
+            labels: None,
+            content_type,
+            transfer_encoding,
+            list_unsubscribe,
+            list_unsubscribe_post,
+            in_reply_to,
+            references,
+        })
+    }
 }
\ No newline at end of file
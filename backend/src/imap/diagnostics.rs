@@ -0,0 +1,124 @@
+//! Step-by-step connection diagnostics
+//!
+//! A connection test that only says "failed" leaves users guessing whether
+//! the host name, a firewall, the certificate or the password is wrong. The
+//! diagnostics run the steps of connecting one at a time (resolving the host,
+//! opening the TCP connection, the TLS handshake, logging in, reading
+//! capabilities and listing folders) and report each with its outcome, how
+//! long it took, and what it found or why it failed. The first failing step
+//! ends the run and the steps after it are reported as skipped.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+/// Steps of connecting, in the order they run
+pub const STEPS: &[&str] = &["dns", "tcp", "tls", "auth", "capabilities", "folders"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DiagnosticStep {
+    /// One of `dns`, `tcp`, `tls`, `auth`, `capabilities` and `folders`
+    pub step: String,
+    pub status: StepStatus,
+    /// What the step found, or why it was skipped
+    pub detail: Option<String>,
+    /// Why the step failed
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionDiagnostics {
+    /// Whether every step that ran passed
+    pub success: bool,
+    pub failed_step: Option<String>,
+    /// Every step, in order
+    pub steps: Vec<DiagnosticStep>,
+}
+
+impl Default for ConnectionDiagnostics {
+    fn default() -> Self {
+        Self { success: true, failed_step: None, steps: Vec::new() }
+    }
+}
+
+impl ConnectionDiagnostics {
+    /// Run `step` and record its outcome, described by `detail` when it
+    /// passes; None when it failed
+    pub async fn run<T>(&mut self, step: &str, f: impl Future<Output = Result<T>>, detail: impl FnOnce(&T) -> String) -> Option<T> {
+        let started = Instant::now();
+        let result = f.await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (status, detail, error) = match &result {
+            Ok(value) => (StepStatus::Passed, Some(detail(value)), None),
+            Err(e) => (StepStatus::Failed, None, Some(format!("{:#}", e))),
+        };
+        if status == StepStatus::Failed && self.success {
+            self.success = false;
+            self.failed_step = Some(step.to_string());
+        }
+        self.steps.push(DiagnosticStep { step: step.to_string(), status, detail, error, duration_ms });
+        result.ok()
+    }
+
+    /// Record `step` as not applicable
+    pub fn skip(&mut self, step: &str, reason: &str) {
+        self.steps.push(DiagnosticStep {
+            step: step.to_string(),
+            status: StepStatus::Skipped,
+            detail: Some(reason.to_string()),
+            error: None,
+            duration_ms: 0,
+        });
+    }
+
+    /// Report the steps that did not get to run as skipped
+    pub fn finish(mut self) -> Self {
+        for step in STEPS {
+            if !self.steps.iter().any(|recorded| recorded.step == *step) {
+                let reason = match &self.failed_step {
+                    Some(failed) => format!("Not run because the {} step failed", failed),
+                    None => "Not run".to_string(),
+                };
+                self.skip(step, &reason);
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_steps_after_a_failure_are_skipped() {
+        let mut diagnostics = ConnectionDiagnostics::default();
+        diagnostics.run("dns", async { Ok(1) }, |_| "resolved".to_string()).await;
+        diagnostics.run("tcp", async { Err::<(), _>(anyhow::anyhow!("refused")) }, |_| String::new()).await;
+        let diagnostics = diagnostics.finish();
+
+        assert!(!diagnostics.success);
+        assert_eq!(diagnostics.failed_step.as_deref(), Some("tcp"));
+        let statuses: Vec<_> = diagnostics.steps.iter().map(|step| (step.step.as_str(), step.status)).collect();
+        assert_eq!(statuses, [
+            ("dns", StepStatus::Passed),
+            ("tcp", StepStatus::Failed),
+            ("tls", StepStatus::Skipped),
+            ("auth", StepStatus::Skipped),
+            ("capabilities", StepStatus::Skipped),
+            ("folders", StepStatus::Skipped),
+        ]);
+        assert_eq!(diagnostics.steps[1].error.as_deref(), Some("refused"));
+    }
+}
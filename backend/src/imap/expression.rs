@@ -0,0 +1,193 @@
+//! Advanced rule matching
+//!
+//! A rule's `match_expression` is JSON combining regex patterns on the
+//! email's fields with `all`, `any` and `not`:
+//!
+//! ```json
+//! {"all": [
+//!     {"regex": {"field": "from", "pattern": "@(news|digest)\\.example\\.com$"}},
+//!     {"not": {"regex": {"field": "subject", "pattern": "^re:"}}}
+//! ]}
+//! ```
+//!
+//! Fields are `from`, `to`, `subject` and `body` (the decoded text body).
+//! Patterns are case-insensitive unless `case_sensitive` is set. The
+//! expression is checked along with the rule's substring criteria, all of
+//! which have to hold. Expressions are compiled and validated when a rule is
+//! saved, so a rule with a pattern that does not compile is refused.
+
+use anyhow::{bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+
+use super::client::Email;
+use super::mime::EmailContent;
+
+/// Deepest nesting of `all`, `any` and `not` accepted
+pub const MAX_DEPTH: usize = 16;
+
+/// Largest compiled size of a pattern, in bytes
+const REGEX_SIZE_LIMIT: usize = 256 * 1024;
+
+/// Part of an email a pattern is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchField {
+    From,
+    To,
+    Subject,
+    Body,
+}
+
+/// A match expression as stored on a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum MatchExpression {
+    /// Every sub-expression matches; an empty list always matches
+    All(Vec<MatchExpression>),
+    /// At least one sub-expression matches; an empty list never matches
+    Any(Vec<MatchExpression>),
+    Not(Box<MatchExpression>),
+    Regex {
+        field: MatchField,
+        pattern: String,
+        #[serde(default)]
+        case_sensitive: bool,
+    },
+}
+
+/// A match expression with its patterns compiled
+#[derive(Debug, Clone)]
+pub enum CompiledExpression {
+    All(Vec<CompiledExpression>),
+    Any(Vec<CompiledExpression>),
+    Not(Box<CompiledExpression>),
+    Regex { field: MatchField, regex: Regex },
+}
+
+/// Fields of an email for matching; the body is decoded on first use
+pub struct MatchInput<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub subject: &'a str,
+    body: OnceCell<String>,
+    email: Option<&'a Email>,
+}
+
+impl<'a> MatchInput<'a> {
+    pub fn of(email: &'a Email) -> Self {
+        Self { from: &email.from, to: &email.to, subject: &email.subject, body: OnceCell::new(), email: Some(email) }
+    }
+
+    /// Input from plain field values, e.g. a sample email to test a rule on
+    pub fn new(from: &'a str, to: &'a str, subject: &'a str, body: &str) -> Self {
+        Self { from, to, subject, body: OnceCell::from(body.to_string()), email: None }
+    }
+
+    fn field(&self, field: MatchField) -> &str {
+        match field {
+            MatchField::From => self.from,
+            MatchField::To => self.to,
+            MatchField::Subject => self.subject,
+            MatchField::Body => self.body.get_or_init(|| self.email.map(|email| EmailContent::of(email).text).unwrap_or_default()),
+        }
+    }
+}
+
+impl MatchExpression {
+    /// Parse an expression from its stored JSON
+    pub fn parse(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Invalid match expression")
+    }
+
+    /// Compile every pattern, failing on the first that does not compile or
+    /// on nesting deeper than `MAX_DEPTH`
+    pub fn compile(&self) -> Result<CompiledExpression> {
+        self.compile_at(1)
+    }
+
+    fn compile_at(&self, depth: usize) -> Result<CompiledExpression> {
+        if depth > MAX_DEPTH {
+            bail!("Match expression is nested deeper than {} levels", MAX_DEPTH);
+        }
+        let compile_all = |expressions: &[MatchExpression]| {
+            expressions.iter().map(|expression| expression.compile_at(depth + 1)).collect::<Result<Vec<_>>>()
+        };
+        Ok(match self {
+            MatchExpression::All(expressions) => CompiledExpression::All(compile_all(expressions)?),
+            MatchExpression::Any(expressions) => CompiledExpression::Any(compile_all(expressions)?),
+            MatchExpression::Not(expression) => CompiledExpression::Not(Box::new(expression.compile_at(depth + 1)?)),
+            MatchExpression::Regex { field, pattern, case_sensitive } => {
+                let regex = RegexBuilder::new(pattern)
+                    .case_insensitive(!case_sensitive)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .build()
+                    .with_context(|| format!("Invalid regex '{}'", pattern))?;
+                CompiledExpression::Regex { field: *field, regex }
+            }
+        })
+    }
+}
+
+impl CompiledExpression {
+    /// Compile a rule's stored expression; `None` when it has none
+    pub fn of_rule(match_expression: Option<&str>) -> Result<Option<Self>> {
+        match match_expression.map(str::trim).filter(|json| !json.is_empty()) {
+            Some(json) => Ok(Some(MatchExpression::parse(json)?.compile()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn matches(&self, input: &MatchInput) -> bool {
+        match self {
+            CompiledExpression::All(expressions) => expressions.iter().all(|expression| expression.matches(input)),
+            CompiledExpression::Any(expressions) => expressions.iter().any(|expression| expression.matches(input)),
+            CompiledExpression::Not(expression) => !expression.matches(input),
+            CompiledExpression::Regex { field, regex } => regex.is_match(input.field(*field)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(json: &str) -> Result<CompiledExpression> {
+        MatchExpression::parse(json)?.compile()
+    }
+
+    #[test]
+    fn test_combinators() {
+        let expression = compile(r#"{"all": [
+            {"any": [
+                {"regex": {"field": "from", "pattern": "@news\\.example\\.com$"}},
+                {"regex": {"field": "from", "pattern": "^digest@"}}
+            ]},
+            {"not": {"regex": {"field": "subject", "pattern": "^re:"}}}
+        ]}"#).unwrap();
+
+        assert!(expression.matches(&MatchInput::new("weekly@news.example.com", "", "Issue 12", "")));
+        assert!(expression.matches(&MatchInput::new("Digest@other.org", "", "Today", "")));
+        assert!(!expression.matches(&MatchInput::new("weekly@news.example.com", "", "RE: Issue 12", "")));
+        assert!(!expression.matches(&MatchInput::new("someone@example.com", "", "Issue 12", "")));
+    }
+
+    #[test]
+    fn test_case_sensitivity_and_body() {
+        let expression = compile(r#"{"regex": {"field": "body", "pattern": "Unsubscribe", "case_sensitive": true}}"#).unwrap();
+        assert!(expression.matches(&MatchInput::new("", "", "", "Click to Unsubscribe")));
+        assert!(!expression.matches(&MatchInput::new("", "", "", "click to unsubscribe")));
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected() {
+        let error = compile(r#"{"regex": {"field": "subject", "pattern": "(unclosed"}}"#).unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Invalid regex '(unclosed'"), "{:#}", error);
+        assert!(compile(r#"{"regex": {"field": "cc", "pattern": "x"}}"#).is_err());
+        assert!(compile(r#"{"and": []}"#).is_err());
+
+        let nested = (0..MAX_DEPTH).fold(r#"{"all": []}"#.to_string(), |inner, _| format!(r#"{{"not": {}}}"#, inner));
+        assert!(compile(&nested).unwrap_err().to_string().contains("nested deeper"));
+    }
+}
@@ -0,0 +1,110 @@
+//! How emails are fetched from an account's server
+//!
+//! Servers differ in what `UID FETCH` items they handle: some choke on
+//! `BODY.PEEK[HEADER]`, a few even on `ENVELOPE`. Fetching falls back from
+//! headers and bodies to envelopes to bare UIDs, and the strategy that
+//! worked is stored on the account along with the capabilities the server
+//! announced. Later fetches start with that strategy and go through the
+//! whole cascade again, capabilities included, only when it fails.
+
+use tracing::{debug, warn};
+
+use crate::db::models::ImapAccount;
+use crate::imap::setup;
+use crate::imap::session::Session;
+
+/// Data fetched for each email, from the most to the least complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchStrategy {
+    /// `BODY.PEEK[HEADER]`, then `BODY.PEEK[TEXT]` for the bodies
+    Headers,
+    /// `ENVELOPE`: sender, recipients, subject and date without a body
+    Envelope,
+    /// `UID` alone
+    UidOnly,
+}
+
+impl FetchStrategy {
+    /// Every strategy in the order they are probed
+    pub const ALL: [FetchStrategy; 3] = [FetchStrategy::Headers, FetchStrategy::Envelope, FetchStrategy::UidOnly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FetchStrategy::Headers => "headers",
+            FetchStrategy::Envelope => "envelope",
+            FetchStrategy::UidOnly => "uid",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|strategy| strategy.as_str() == value)
+    }
+
+    /// Strategies to try: `known` first, then the full cascade
+    pub fn order(known: Option<FetchStrategy>) -> Vec<FetchStrategy> {
+        known.into_iter()
+            .chain(Self::ALL.into_iter().filter(|strategy| Some(*strategy) != known))
+            .collect()
+    }
+}
+
+/// What is known about an account's server
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerProfile {
+    /// Announced capabilities; `None` until detected
+    pub capabilities: Option<Vec<String>>,
+    /// Strategy that last worked
+    pub strategy: Option<FetchStrategy>,
+}
+
+impl ServerProfile {
+    /// The profile stored on the account
+    pub fn of(account: &ImapAccount) -> Self {
+        Self {
+            capabilities: account.server_capabilities.as_deref()
+                .map(|capabilities| capabilities.split_whitespace().map(str::to_string).collect()),
+            strategy: account.fetch_strategy.as_deref().and_then(FetchStrategy::parse),
+        }
+    }
+
+    /// Capabilities as stored, space separated
+    pub fn capabilities_str(&self) -> Option<String> {
+        self.capabilities.as_ref().map(|capabilities| capabilities.join(" "))
+    }
+
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().flatten().any(|known| known.eq_ignore_ascii_case(capability))
+    }
+
+    /// Ask the server for its capabilities; they stay unknown if it will not say
+    pub async fn detect_capabilities(&mut self, session: &mut Session) {
+        match session.capabilities().await {
+            Ok(capabilities) => {
+                let names = setup::capability_names(capabilities.iter());
+                debug!("Server capabilities: {}", names.join(" "));
+                self.capabilities = Some(names);
+            }
+            Err(e) => {
+                warn!("Could not detect server capabilities: {}", e);
+                self.capabilities = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_strategy_is_tried_first() {
+        use FetchStrategy::*;
+        assert_eq!(FetchStrategy::order(None), [Headers, Envelope, UidOnly]);
+        assert_eq!(FetchStrategy::order(Some(Envelope)), [Envelope, Headers, UidOnly]);
+        assert_eq!(FetchStrategy::order(Some(UidOnly)), [UidOnly, Headers, Envelope]);
+        for strategy in FetchStrategy::ALL {
+            assert_eq!(FetchStrategy::parse(strategy.as_str()), Some(strategy));
+        }
+        assert_eq!(FetchStrategy::parse("body"), None);
+    }
+}
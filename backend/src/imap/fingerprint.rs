@@ -0,0 +1,113 @@
+//! Duplicate mailbox detection
+//!
+//! Two accounts with the same host and username are the same mailbox. Aliases
+//! (another hostname for the same server, an address alias as username) are
+//! caught by a fingerprint of the server greeting and the UIDVALIDITY of
+//! INBOX, which together identify a mailbox independent of how it is reached.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::db::{connection::DatabasePool, models::ImapAccount, operations_generic::ImapAccountOpsGeneric};
+use super::ImapClient;
+
+/// Whether two logins point at the same mailbox by name
+pub fn same_login(host_a: &str, username_a: &str, host_b: &str, username_b: &str) -> bool {
+    normalize_host(host_a) == normalize_host(host_b)
+        && username_a.trim().eq_ignore_ascii_case(username_b.trim())
+}
+
+/// Existing account with the same host and username, if any
+pub fn find_same_login<'a>(accounts: &'a [ImapAccount], host: &str, username: &str) -> Option<&'a ImapAccount> {
+    accounts.iter()
+        .find(|account| same_login(&account.host, &account.username, host, username))
+}
+
+/// Other accounts whose mailbox has the same fingerprint as `account`
+pub fn find_aliases<'a>(accounts: &'a [ImapAccount], account: &ImapAccount) -> Vec<&'a ImapAccount> {
+    let Some(fingerprint) = &account.fingerprint else {
+        return Vec::new();
+    };
+    accounts.iter()
+        .filter(|other| other.id != account.id && other.fingerprint.as_ref() == Some(fingerprint))
+        .collect()
+}
+
+/// Fingerprint the account's mailbox, store it and return the IDs of other
+/// accounts that reach the same mailbox
+pub async fn refresh(pool: &DatabasePool, client: &ImapClient, account: &ImapAccount) -> Result<Vec<String>> {
+    let account_id = account.id.as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Account has no ID"))?;
+
+    let fingerprint = client.fingerprint().await?;
+    ImapAccountOpsGeneric::update_fingerprint(pool, account_id, Some(&fingerprint))?;
+
+    let account = ImapAccount {
+        fingerprint: Some(fingerprint),
+        ..account.clone()
+    };
+    let accounts = ImapAccountOpsGeneric::get_all(pool)?;
+    let aliases: Vec<String> = find_aliases(&accounts, &account).into_iter()
+        .filter_map(|alias| alias.id.clone())
+        .collect();
+
+    if !aliases.is_empty() {
+        warn!("Account '{}' reaches the same mailbox as account(s) {}; emails will be fetched twice",
+              account.name, aliases.join(", "));
+    }
+    Ok(aliases)
+}
+
+/// Fingerprint of a mailbox from the server greeting and INBOX UIDVALIDITY
+pub fn compute(greeting: &str, inbox_uid_validity: Option<u32>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(normalize_greeting(greeting).as_bytes());
+    hasher.update([0]);
+    if let Some(uid_validity) = inbox_uid_validity {
+        hasher.update(uid_validity.to_be_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// Drop the parts of a greeting that vary per connection
+///
+/// Servers often include the client address, a session ID or a timestamp
+/// ("* OK Gimap ready for requests from 192.0.2.1 x12mb"), so only words
+/// without digits are kept.
+fn normalize_greeting(greeting: &str) -> String {
+    greeting.split_whitespace()
+        .filter(|word| !word.chars().any(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_login_ignores_case_and_whitespace() {
+        assert!(same_login("IMAP.Example.com.", " Alice@Example.com", "imap.example.com", "alice@example.com"));
+        assert!(!same_login("imap.example.com", "alice", "imap.example.com", "bob"));
+        assert!(!same_login("imap.example.com", "alice", "mail.example.com", "alice"));
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_per_connection_details() {
+        let first = compute("* OK Gimap ready for requests from 192.0.2.1 x12mb34", Some(1));
+        let second = compute("* OK Gimap ready for requests from 198.51.100.7 q98ab76", Some(1));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_fingerprint_distinguishes_mailboxes() {
+        let greeting = "* OK [CAPABILITY IMAP4rev1 IDLE] Dovecot ready.";
+        assert_ne!(compute(greeting, Some(1700000000)), compute(greeting, Some(1700000001)));
+        assert_ne!(compute(greeting, Some(1)), compute("* OK Courier-IMAP ready.", Some(1)));
+    }
+}
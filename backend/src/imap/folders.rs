@@ -0,0 +1,110 @@
+//! Folder browsing
+//!
+//! The folders of an account are listed once and each selectable one is
+//! asked for its message and unseen counts with `STATUS`, which reads them
+//! without selecting the folder. The flat list is then nested by the server's
+//! hierarchy delimiter, so `Folders/News/Tech` on a ProtonMail Bridge shows up
+//! under `Folders/News`. A folder whose parent is not listed is placed at the
+//! top. Names stay the full paths rules are written with; the label is the
+//! last segment only.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A folder as listed by the server, with its counts
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderStatus {
+    pub name: String,
+    pub delimiter: Option<String>,
+    pub selectable: bool,
+    pub messages: Option<u32>,
+    pub unseen: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FolderNode {
+    /// Full folder path, as used in rules
+    pub name: String,
+    /// Last segment of the path
+    pub label: String,
+    /// False for folders that only hold other folders
+    pub selectable: bool,
+    /// Null when the folder cannot be selected or its status could not be read
+    pub messages: Option<u32>,
+    pub unseen: Option<u32>,
+    pub children: Vec<FolderNode>,
+}
+
+/// `name` as an IMAP quoted string
+pub fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Nest the listed folders under their nearest listed ancestor, sorted by name
+pub fn tree(folders: &[FolderStatus]) -> Vec<FolderNode> {
+    let mut sorted: Vec<&FolderStatus> = folders.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    sorted.dedup_by(|a, b| a.name == b.name);
+
+    let mut roots = Vec::new();
+    for folder in sorted {
+        let segments: Vec<&str> = match folder.delimiter.as_deref() {
+            Some(delimiter) if !delimiter.is_empty() => folder.name.split(delimiter).collect(),
+            _ => vec![folder.name.as_str()],
+        };
+        let node = FolderNode {
+            name: folder.name.clone(),
+            label: segments.last().unwrap_or(&"").to_string(),
+            selectable: folder.selectable,
+            messages: folder.messages,
+            unseen: folder.unseen,
+            children: Vec::new(),
+        };
+        insert(&mut roots, node, folder.delimiter.as_deref().unwrap_or(""));
+    }
+    roots
+}
+
+/// Add `node` below the deepest node of `nodes` whose path is a prefix of it
+fn insert(nodes: &mut Vec<FolderNode>, node: FolderNode, delimiter: &str) {
+    let parent = (!delimiter.is_empty())
+        .then(|| nodes.iter_mut().find(|parent| node.name.starts_with(&format!("{}{}", parent.name, delimiter))))
+        .flatten();
+    match parent {
+        Some(parent) => insert(&mut parent.children, node, delimiter),
+        None => nodes.push(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(name: &str, selectable: bool) -> FolderStatus {
+        FolderStatus { name: name.to_string(), delimiter: Some("/".to_string()), selectable, messages: None, unseen: None }
+    }
+
+    #[test]
+    fn test_tree_nests_by_delimiter() {
+        let folders = [
+            folder("INBOX", true),
+            folder("Folders/News/Tech", true),
+            folder("Folders", false),
+            folder("Folders/News", true),
+            folder("Labels/Work", true),
+        ];
+        let tree = tree(&folders);
+
+        let names: Vec<&str> = tree.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, ["Folders", "INBOX", "Labels/Work"]);
+        let news = &tree[0].children[0];
+        assert_eq!((news.name.as_str(), news.label.as_str()), ("Folders/News", "News"));
+        assert_eq!(news.children[0].label, "Tech");
+        assert_eq!(tree[2].label, "Work");
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("Folders/A \"B\""), "\"Folders/A \\\"B\\\"\"");
+    }
+}
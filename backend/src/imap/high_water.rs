@@ -0,0 +1,157 @@
+//! Fetching only mail a rule has not seen
+//!
+//! Each rule remembers the highest UID of its folder it has handled, along
+//! with the folder's UIDVALIDITY. While the server reports the same
+//! UIDVALIDITY, a run fetches only the messages above that UID (`UID SEARCH
+//! UID n:*`), oldest first and up to the run's fetch limit, so mail arriving
+//! faster than the limit is worked through over several runs instead of
+//! skipped. Without a mark, or once UIDVALIDITY changes, the folder's newest
+//! messages are fetched as before.
+//!
+//! The mark only moves past emails that were settled: an email left in the
+//! mailbox for the next run, e.g. by an exhausted item quota, holds it back.
+//! Editing a rule clears its mark, so the folder is checked against the edited
+//! rule again.
+
+use tracing::{debug, warn};
+
+use super::client::Email;
+use crate::db::models::EmailRule;
+
+/// The highest UID of a folder a rule has handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighWaterMark {
+    pub uid_validity: u32,
+    pub last_uid: u32,
+}
+
+impl HighWaterMark {
+    /// The rule's mark, if it has a valid one
+    pub fn of(rule: &EmailRule) -> Option<Self> {
+        Some(Self {
+            uid_validity: u32::try_from(rule.uid_validity?).ok()?,
+            last_uid: u32::try_from(rule.last_seen_uid?).ok()?,
+        })
+    }
+
+    /// The UID after which to fetch from a folder whose UIDVALIDITY is
+    /// `uid_validity`; none when the mark belongs to an earlier incarnation
+    pub fn resume_after(mark: Option<Self>, uid_validity: Option<u32>) -> Option<u32> {
+        let mark = mark?;
+        if uid_validity == Some(mark.uid_validity) {
+            Some(mark.last_uid)
+        } else {
+            warn!("UIDVALIDITY changed from {} to {:?}, fetching the newest messages instead",
+                  mark.uid_validity, uid_validity);
+            None
+        }
+    }
+}
+
+/// Emails fetched from a folder
+#[derive(Debug, Clone, Default)]
+pub struct FolderFetch {
+    pub emails: Vec<Email>,
+    /// The folder's UIDVALIDITY, if the server reported one
+    pub uid_validity: Option<u32>,
+    /// Whether only messages above the rule's mark were fetched
+    pub resumed: bool,
+}
+
+/// UIDs to fetch out of those found in a folder: the lowest `limit` above
+/// `after`, or without it the highest `limit`
+pub fn select_uids(mut uids: Vec<u32>, after: Option<u32>, limit: usize) -> Vec<u32> {
+    uids.sort_unstable();
+    match after {
+        // `n:*` includes the highest UID even when it is below n
+        Some(after) => uids.into_iter().filter(|uid| *uid > after).take(limit).collect(),
+        None => uids.into_iter().rev().take(limit).collect(),
+    }
+}
+
+/// The mark after a run fetched `fetch` under `previous`, where `unsettled`
+/// is the lowest UID left for a later run; none when it does not move
+pub fn advance(previous: Option<HighWaterMark>, fetch: &FolderFetch, unsettled: Option<u32>) -> Option<HighWaterMark> {
+    let uid_validity = fetch.uid_validity?;
+    let mut last_uid = fetch.emails.iter().map(|email| email.uid).max()?;
+    if let Some(unsettled) = unsettled {
+        last_uid = last_uid.min(unsettled.saturating_sub(1));
+    }
+
+    let current = previous.filter(|mark| mark.uid_validity == uid_validity);
+    if current.is_some_and(|mark| mark.last_uid >= last_uid) {
+        return None;
+    }
+    debug!("Advancing last seen UID to {} (UIDVALIDITY {})", last_uid, uid_validity);
+    Some(HighWaterMark { uid_validity, last_uid })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn fetch(uids: &[u32]) -> FolderFetch {
+        FolderFetch {
+            emails: uids.iter().map(|uid| Email {
+                uid: *uid,
+                message_id: format!("<{}@example.com>", uid),
+                subject: format!("Issue {}", uid),
+                from: "news@example.com".to_string(),
+                to: "reader@example.com".to_string(),
+                date: Utc::now(),
+                body: String::new(),
+                is_seen: false,
+                importance: None,
+                category: None,
+                labels: None,
+                content_type: None,
+                transfer_encoding: None,
+                list_unsubscribe: None,
+                list_unsubscribe_post: None,
+                in_reply_to: None,
+                references: None,
+            }).collect(),
+            uid_validity: Some(7),
+            resumed: true,
+        }
+    }
+
+    #[test]
+    fn test_select_uids() {
+        let uids = vec![5, 1, 9, 3, 7];
+        assert_eq!(select_uids(uids.clone(), None, 2), [9, 7]);
+        assert_eq!(select_uids(uids.clone(), Some(3), 2), [5, 7]);
+        // `9:*` on a folder whose highest UID is 9 returns 9 again
+        assert!(select_uids(uids, Some(9), 10).is_empty());
+    }
+
+    #[test]
+    fn test_resume_only_under_the_same_uid_validity() {
+        let mark = HighWaterMark { uid_validity: 7, last_uid: 40 };
+        assert_eq!(HighWaterMark::resume_after(Some(mark), Some(7)), Some(40));
+        assert_eq!(HighWaterMark::resume_after(Some(mark), Some(8)), None);
+        assert_eq!(HighWaterMark::resume_after(Some(mark), None), None);
+        assert_eq!(HighWaterMark::resume_after(None, Some(7)), None);
+    }
+
+    #[test]
+    fn test_advance_stops_before_unsettled_emails() {
+        let mark = HighWaterMark { uid_validity: 7, last_uid: 40 };
+        assert_eq!(advance(Some(mark), &fetch(&[41, 42, 43]), None), Some(HighWaterMark { uid_validity: 7, last_uid: 43 }));
+        assert_eq!(advance(Some(mark), &fetch(&[41, 42, 43]), Some(42)), Some(HighWaterMark { uid_validity: 7, last_uid: 41 }));
+        // Nothing new, or nothing settled
+        assert_eq!(advance(Some(mark), &fetch(&[]), None), None);
+        assert_eq!(advance(Some(mark), &fetch(&[41, 42]), Some(41)), None);
+    }
+
+    #[test]
+    fn test_advance_replaces_mark_of_another_uid_validity() {
+        let stale = HighWaterMark { uid_validity: 3, last_uid: 900 };
+        assert_eq!(advance(Some(stale), &fetch(&[12]), None), Some(HighWaterMark { uid_validity: 7, last_uid: 12 }));
+
+        let mut unknown = fetch(&[12]);
+        unknown.uid_validity = None;
+        assert_eq!(advance(None, &unknown, None), None);
+    }
+}
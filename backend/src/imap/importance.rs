@@ -0,0 +1,98 @@
+//! Importance and category of an email
+//!
+//! Importance comes from the headers mail clients set for it: `X-Priority`
+//! (1-5), `Importance`, `Priority` and `X-MSMail-Priority`. Gmail does not put
+//! its category tabs in headers, so on servers with the Gmail extensions the
+//! category of each fetched email is looked up with one `X-GM-RAW` search per
+//! tab.
+
+use std::collections::HashMap;
+
+use tracing::{debug, warn};
+
+use crate::db::models::Importance;
+use crate::imap::fetch_strategy::ServerProfile;
+use crate::imap::session::{self, Session};
+
+/// Gmail category tabs, as used in `category:` searches
+pub const GMAIL_CATEGORIES: [&str; 5] = ["primary", "social", "promotions", "updates", "forums"];
+
+/// Capability advertised by servers that support Gmail's IMAP extensions
+const GMAIL_CAPABILITY: &str = "X-GM-EXT-1";
+
+/// The importance a header line declares, if it is an importance header
+pub fn from_header(line: &str) -> Option<Importance> {
+    let (name, value) = line.split_once(':')?;
+    let value = value.trim().to_ascii_lowercase();
+    match name.trim().to_ascii_lowercase().as_str() {
+        // "1 (Highest)" through "5 (Lowest)"
+        "x-priority" => match value.chars().next()? {
+            '1' | '2' => Some(Importance::High),
+            '3' => Some(Importance::Normal),
+            '4' | '5' => Some(Importance::Low),
+            _ => None,
+        },
+        "importance" | "x-msmail-priority" => Importance::parse(&value),
+        "priority" => match value.as_str() {
+            "urgent" => Some(Importance::High),
+            "normal" => Some(Importance::Normal),
+            "non-urgent" => Some(Importance::Low),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the server supports Gmail's IMAP extensions
+pub fn is_gmail(server: &ServerProfile) -> bool {
+    server.has_capability(GMAIL_CAPABILITY)
+}
+
+/// Gmail category of each of `uids` in the selected folder; empty on servers
+/// without the Gmail extensions
+pub async fn gmail_categories(session: &mut Session, server: &ServerProfile, uids: &[u32]) -> HashMap<u32, String> {
+    let mut categories = HashMap::new();
+    if uids.is_empty() || !is_gmail(server) {
+        return categories;
+    }
+
+    for category in GMAIL_CATEGORIES {
+        match session::uid_search(session, &format!("X-GM-RAW \"category:{}\"", category)).await {
+            Ok(matched) => {
+                for uid in uids.iter().filter(|uid| matched.contains(uid)) {
+                    categories.insert(*uid, category.to_string());
+                }
+            }
+            Err(e) => {
+                warn!("Gmail category search for '{}' failed: {}", category, e);
+                break;
+            }
+        }
+    }
+    debug!("Found Gmail categories for {} of {} emails", categories.len(), uids.len());
+    categories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_importance_headers() {
+        assert_eq!(from_header("X-Priority: 1 (Highest)"), Some(Importance::High));
+        assert_eq!(from_header("X-Priority: 3"), Some(Importance::Normal));
+        assert_eq!(from_header("x-priority: 5 (Lowest)"), Some(Importance::Low));
+        assert_eq!(from_header("Importance: High"), Some(Importance::High));
+        assert_eq!(from_header("X-MSMail-Priority: Low"), Some(Importance::Low));
+        assert_eq!(from_header("Priority: urgent"), Some(Importance::High));
+        assert_eq!(from_header("Priority: non-urgent"), Some(Importance::Low));
+    }
+
+    #[test]
+    fn test_other_headers_have_no_importance() {
+        assert_eq!(from_header("Subject: Importance: high"), None);
+        assert_eq!(from_header("X-Priority: urgent"), None);
+        assert_eq!(from_header("Importance: extreme"), None);
+        assert_eq!(from_header("not a header"), None);
+    }
+}
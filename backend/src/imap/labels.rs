@@ -0,0 +1,91 @@
+//! Gmail labels of an email
+//!
+//! Gmail files mail under labels rather than folders; its IMAP folders are
+//! views of them, so one email can show up in several. On servers with the
+//! Gmail extensions (`X-GM-EXT-1`) the emails of a fetch carrying the labels
+//! the rules ask for are found with `UID SEARCH X-GM-RAW "label:..."`, the
+//! way their categories are, and rules can then require one of them. Other
+//! servers have no labels: their emails carry `None`, and the label
+//! criterion of a rule is skipped for them, as it was before labels were
+//! read at all.
+
+use std::collections::HashMap;
+
+use tracing::{debug, warn};
+
+use crate::imap::fetch_strategy::ServerProfile;
+use crate::imap::importance;
+use crate::imap::session::{self, Session};
+
+/// Which of `wanted` each of `uids` in the selected folder carries, every
+/// UID included; `None` on servers without the Gmail extensions, without
+/// labels wanted, or when the labels could not be searched
+pub async fn gmail_labels(session: &mut Session, server: &ServerProfile, uids: &[u32], wanted: &[String]) -> Option<HashMap<u32, Vec<String>>> {
+    let mut terms: Vec<(String, &String)> = wanted.iter().map(|label| (search_term(label), label)).collect();
+    terms.retain(|(term, _)| !term.is_empty());
+    terms.sort();
+    terms.dedup_by(|a, b| a.0 == b.0);
+    if uids.is_empty() || terms.is_empty() || !importance::is_gmail(server) {
+        return None;
+    }
+
+    let mut labels: HashMap<u32, Vec<String>> = uids.iter().map(|uid| (*uid, Vec::new())).collect();
+    for (term, label) in &terms {
+        match session::uid_search(session, &format!("X-GM-RAW \"label:{}\"", term)).await {
+            Ok(matched) => {
+                for uid in matched {
+                    if let Some(found) = labels.get_mut(&uid) {
+                        found.push(label.to_string());
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Gmail label search for '{}' failed, matching rules without labels: {}", label, e);
+                return None;
+            }
+        }
+    }
+    debug!("Searched {} Gmail labels of {} emails", terms.len(), uids.len());
+    Some(labels)
+}
+
+/// Whether `labels` include `wanted`, ignoring case and the backslash of
+/// system labels, so `Important` matches `\Important`
+pub fn has_label(labels: &[String], wanted: &str) -> bool {
+    let wanted = search_term(wanted);
+    !wanted.is_empty() && labels.iter().any(|label| search_term(label) == wanted)
+}
+
+/// A label as Gmail search writes it: lowercase, without the backslash of
+/// system labels, with spaces and the `/` of nested labels as hyphens
+pub fn search_term(label: &str) -> String {
+    label.trim()
+        .trim_start_matches('\\')
+        .to_lowercase()
+        .chars()
+        .filter(|c| !matches!(c, '"' | '\\'))
+        .map(|c| if c.is_whitespace() || c == '/' { '-' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_terms_of_labels() {
+        assert_eq!(search_term("Newsletters"), "newsletters");
+        assert_eq!(search_term("\\Important"), "important");
+        assert_eq!(search_term(" Work stuff/2025 "), "work-stuff-2025");
+        assert_eq!(search_term("Say \"hi\""), "say-hi");
+    }
+
+    #[test]
+    fn test_label_matching_ignores_case_and_system_backslash() {
+        let labels = vec!["\\Important".to_string(), "Work/Projects".to_string()];
+        assert!(has_label(&labels, "important"));
+        assert!(has_label(&labels, "work/projects"));
+        assert!(!has_label(&labels, "Work"));
+        assert!(!has_label(&labels, " "));
+    }
+}
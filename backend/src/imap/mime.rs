@@ -0,0 +1,280 @@
+//! MIME parsing of fetched emails
+//!
+//! Headers and bodies are read with `mail-parser`, which unfolds continued
+//! header lines, decodes RFC 2047 encoded words, base64 and quoted-printable
+//! payloads and charsets other than UTF-8, and walks nested multiparts.
+//!
+//! The body fetched with `BODY.PEEK[TEXT]` comes without the message's
+//! headers, so `EmailContent::parse` puts its `Content-Type` and
+//! `Content-Transfer-Encoding` back in front of it, or, when they were not
+//! fetched, the multipart boundary the body opens with. It takes the first
+//! `text/plain` and `text/html` parts that are not attachments; a message
+//! with only an HTML part gets its plain text from the HTML. Every other
+//! part, such as a PDF or an image, is kept as an attachment.
+
+use chrono::{DateTime, Utc};
+use mail_parser::{Addr, Address, MessageParser, MimeHeaders, PartType};
+
+use super::client::Email;
+use super::importance;
+use crate::db::models::Importance;
+use crate::feed::{overflow, summarizer};
+
+/// The readable content of an email
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmailContent {
+    pub text: String,
+    /// The HTML part, decoded but not sanitized
+    pub html: Option<String>,
+    pub attachments: Vec<MailAttachment>,
+}
+
+/// A decoded attachment of an email
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MailAttachment {
+    pub filename: Option<String>,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+impl EmailContent {
+    /// The content of a fetched email
+    pub fn of(email: &Email) -> Self {
+        Self::parse(&email.body, email.content_type.as_deref(), email.transfer_encoding.as_deref())
+    }
+
+    /// The content of a raw body sent with the given `Content-Type` and
+    /// `Content-Transfer-Encoding`
+    pub fn parse(body: &str, content_type: Option<&str>, transfer_encoding: Option<&str>) -> Self {
+        let content_type = content_type.map(str::to_string).or_else(|| {
+            sniff_boundary(body).map(|boundary| format!("multipart/mixed; boundary=\"{}\"", boundary))
+        });
+        let Some(content_type) = content_type else {
+            // Servers without the headers fetched: plain text, or HTML if it looks like it
+            return if overflow::looks_like_html(body) {
+                Self { text: summarizer::html_to_text(body), html: Some(body.to_string()), attachments: Vec::new() }
+            } else {
+                Self { text: body.to_string(), html: None, attachments: Vec::new() }
+            };
+        };
+
+        let mut raw = format!("Content-Type: {}\r\n", content_type);
+        if let Some(transfer_encoding) = transfer_encoding {
+            raw.push_str(&format!("Content-Transfer-Encoding: {}\r\n", transfer_encoding));
+        }
+        raw.push_str("\r\n");
+        raw.push_str(body);
+        let Some(message) = MessageParser::default().parse(raw.as_bytes()) else {
+            return Self { text: body.to_string(), html: None, attachments: Vec::new() };
+        };
+
+        let html = message.html_bodies().find_map(|part| match &part.body {
+            PartType::Html(html) => Some(html.trim().to_string()),
+            _ => None,
+        });
+        let text = message.text_bodies().find_map(|part| match &part.body {
+            PartType::Text(text) => Some(text.trim().to_string()),
+            _ => None,
+        });
+        let text = match (text, &html) {
+            (Some(text), _) => text,
+            (None, Some(html)) => summarizer::html_to_text(html),
+            (None, None) => body.to_string(),
+        };
+        let attachments = message.attachments().map(|part| MailAttachment {
+            filename: part.attachment_name().map(str::to_string),
+            content_type: part.content_type()
+                .map(|content_type| match content_type.subtype() {
+                    Some(subtype) => format!("{}/{}", content_type.ctype(), subtype),
+                    None => content_type.ctype().to_string(),
+                })
+                .unwrap_or_else(|| "application/octet-stream".to_string())
+                .to_lowercase(),
+            content: part.contents().to_vec(),
+        }).collect();
+        Self { text, html, attachments }
+    }
+}
+
+/// The fields of a message's headers an `Email` is built from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Headers {
+    pub subject: String,
+    pub from: String,
+    pub to: String,
+    pub date: Option<DateTime<Utc>>,
+    pub message_id: String,
+    pub importance: Option<Importance>,
+    pub content_type: Option<String>,
+    pub transfer_encoding: Option<String>,
+    pub list_unsubscribe: Option<String>,
+    pub list_unsubscribe_post: Option<String>,
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+}
+
+impl Headers {
+    /// Parse a block of raw headers, e.g. from `BODY.PEEK[HEADER]`
+    pub fn parse(raw: &[u8]) -> Option<Self> {
+        let message = MessageParser::default().parse_headers(raw)?;
+        let raw_header = |name: &str| message.header_raw(name).map(|value| value.trim().to_string());
+        let importance = message.headers_raw()
+            .find_map(|(name, value)| importance::from_header(&format!("{}: {}", name, value.trim())));
+        Some(Self {
+            subject: message.subject().unwrap_or_default().to_string(),
+            from: message.from().map(format_addresses).unwrap_or_default(),
+            to: message.to().map(format_addresses).unwrap_or_default(),
+            date: message.date().and_then(|date| DateTime::from_timestamp(date.to_timestamp(), 0)),
+            // Stored with angle brackets, as sent
+            message_id: message.message_id().map(|id| format!("<{}>", id)).unwrap_or_default(),
+            importance,
+            content_type: raw_header("Content-Type"),
+            transfer_encoding: raw_header("Content-Transfer-Encoding"),
+            list_unsubscribe: raw_header("List-Unsubscribe"),
+            list_unsubscribe_post: raw_header("List-Unsubscribe-Post"),
+            in_reply_to: raw_header("In-Reply-To"),
+            references: raw_header("References"),
+        })
+    }
+}
+
+/// Addresses as `Name <address>`, or the bare address without a name
+fn format_addresses(address: &Address) -> String {
+    address.iter().map(format_address).collect::<Vec<_>>().join(", ")
+}
+
+fn format_address(addr: &Addr) -> String {
+    let address = addr.address.as_deref().unwrap_or_default();
+    match addr.name.as_deref().filter(|name| !name.is_empty()) {
+        Some(name) if !address.is_empty() => format!("{} <{}>", name, address),
+        Some(name) => name.to_string(),
+        None => address.to_string(),
+    }
+}
+
+/// The boundary of a body that opens with one and closes with it
+fn sniff_boundary(body: &str) -> Option<&str> {
+    let first_line = body.trim_start().lines().next()?.trim_end();
+    let boundary = first_line.strip_prefix("--").filter(|boundary| !boundary.is_empty() && !boundary.contains(' '))?;
+    body.contains(&format!("--{}--", boundary)).then_some(boundary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALTERNATIVE: &str = "--b1\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Caf=C3=A9 opens at nine.\r\n\
+--b1\r\n\
+Content-Type: text/html; charset=\"utf-8\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+PHA+Q2Fmw6kgb3BlbnMgYXQgPGI+bmluZTwvYj4uPC9wPg==\r\n\
+--b1--\r\n";
+
+    #[test]
+    fn test_multipart_alternative_yields_both_parts() {
+        let content = EmailContent::parse(ALTERNATIVE, Some("multipart/alternative;\r\n boundary=\"b1\""), None);
+        assert_eq!(content.text, "Café opens at nine.");
+        assert_eq!(content.html.as_deref(), Some("<p>Café opens at <b>nine</b>.</p>"));
+    }
+
+    #[test]
+    fn test_boundary_is_sniffed_without_headers() {
+        let content = EmailContent::parse(ALTERNATIVE, None, None);
+        assert_eq!(content.text, "Café opens at nine.");
+        assert!(content.html.is_some());
+    }
+
+    #[test]
+    fn test_nested_parts_and_attachments() {
+        let body = "--outer\n\
+Content-Type: multipart/alternative; boundary=inner\n\
+\n\
+--inner\n\
+Content-Type: text/html; charset=iso-8859-1\n\
+Content-Transfer-Encoding: quoted-printable\n\
+\n\
+<p>Gr=FC=DFe</p>\n\
+--inner--\n\
+--outer\n\
+Content-Type: text/plain\n\
+Content-Disposition: attachment; filename=notes.txt\n\
+\n\
+Not the body\n\
+--outer--\n";
+        let content = EmailContent::parse(body, Some("multipart/mixed; boundary=outer"), None);
+        assert_eq!(content.html.as_deref(), Some("<p>Grüße</p>"));
+        // Without a plain part, the text comes from the HTML
+        assert_eq!(content.text, "Grüße");
+        assert_eq!(content.attachments, [MailAttachment {
+            filename: Some("notes.txt".to_string()),
+            content_type: "text/plain".to_string(),
+            content: b"Not the body".to_vec(),
+        }]);
+    }
+
+    #[test]
+    fn test_single_part_bodies() {
+        let content = EmailContent::parse("SGVsbG8=", Some("text/plain"), Some("base64"));
+        assert_eq!(content, EmailContent { text: "Hello".to_string(), ..Default::default() });
+
+        let content = EmailContent::parse("<div>Hello</div>", None, None);
+        assert_eq!(content.text, "Hello");
+        assert_eq!(content.html.as_deref(), Some("<div>Hello</div>"));
+
+        let content = EmailContent::parse("Just text", None, None);
+        assert_eq!(content, EmailContent { text: "Just text".to_string(), ..Default::default() });
+    }
+
+    #[test]
+    fn test_headers_are_unfolded_and_decoded() {
+        let raw = b"Subject: =?utf-8?q?Caf=C3=A9_news?=\r\n\
+From: \"Caf\xc3\xa9 Team\" <team@example.com>\r\n\
+To: reader@example.com, other@example.com\r\n\
+Date: Sun, 3 Aug 2025 15:02:03 +0200\r\n\
+Message-ID: <news-1@example.com>\r\n\
+X-Priority: 1 (Highest)\r\n\
+Content-Type: multipart/alternative;\r\n\
+\tboundary=\"b1\"\r\n\
+\r\n";
+        let headers = Headers::parse(raw).unwrap();
+        assert_eq!(headers.subject, "Café news");
+        assert_eq!(headers.from, "Café Team <team@example.com>");
+        assert_eq!(headers.to, "reader@example.com, other@example.com");
+        assert_eq!(headers.date.unwrap().to_rfc3339(), "2025-08-03T13:02:03+00:00");
+        assert_eq!(headers.message_id, "<news-1@example.com>");
+        assert_eq!(headers.importance, Some(Importance::High));
+        assert!(headers.content_type.unwrap().contains("boundary=\"b1\""));
+        assert_eq!(headers.transfer_encoding, None);
+    }
+
+    #[test]
+    fn test_list_unsubscribe_headers() {
+        let raw = b"Subject: Weekly\r\n\
+List-Unsubscribe: <mailto:leave@lists.example.com>,\r\n\
+\t<https://lists.example.com/u/42>\r\n\
+List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n\
+\r\n";
+        let headers = Headers::parse(raw).unwrap();
+        let links = headers.list_unsubscribe.unwrap();
+        assert!(links.starts_with("<mailto:leave@lists.example.com>,"));
+        assert!(links.ends_with("<https://lists.example.com/u/42>"));
+        assert_eq!(headers.list_unsubscribe_post.as_deref(), Some("List-Unsubscribe=One-Click"));
+    }
+
+    #[test]
+    fn test_thread_headers() {
+        let raw = b"Subject: Re: Weekly\r\n\
+In-Reply-To: <b@example.com>\r\n\
+References: <a@example.com>\r\n\
+\t<b@example.com>\r\n\
+\r\n";
+        let headers = Headers::parse(raw).unwrap();
+        assert_eq!(headers.in_reply_to.as_deref(), Some("<b@example.com>"));
+        assert!(headers.references.unwrap().starts_with("<a@example.com>"));
+    }
+}
@@ -1,7 +1,28 @@
+pub mod cancel;
+pub mod catch_up;
 pub mod client;
 pub mod crlf_wrapper;
+pub mod diagnostics;
+pub mod expression;
+pub mod fetch_strategy;
+pub mod fingerprint;
+pub mod folders;
+pub mod high_water;
+pub mod importance;
+pub mod labels;
+pub mod mime;
+pub mod post_process;
 pub mod processor;
 pub mod protocol_compat;
+pub mod rate_limit;
+pub mod rule_costs;
+pub mod sender_filters;
+pub mod senders;
+pub mod server_name;
+pub mod session;
+pub mod setup;
+pub mod throttle;
+pub mod tls_pin;
 
 use anyhow::Result;
 use crate::db::models::ImapAccount;
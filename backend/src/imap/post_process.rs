@@ -0,0 +1,261 @@
+//! Batched post-processing
+//!
+//! A rule run no longer marks, deletes or moves each email as soon as its
+//! item is created, which cost a connection and a round-trip per email.
+//! Instead the run collects the UIDs its action applies to and, once every
+//! email is turned into an item, applies the action to all of them with a
+//! single `UID STORE` or `UID MOVE` over one session. When the server rejects
+//! the batched command each UID is retried on its own, so one bad message
+//! cannot hold back the rest, and the outcome is reported per UID: emails no
+//! longer in the folder and those the server refused are listed with why.
+//!
+//! A rule may instead have a chain of actions, e.g. mark read, flag, copy to
+//! Archive, then delete after a week. The steps are applied in order, each
+//! as a batch of its own; a step with a delay is deferred like the delayed
+//! single action, and an email a step fails on is left out of the steps
+//! after it. Moving or deleting takes the email out of the folder, so only
+//! the last step may do either.
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use utoipa::ToSchema;
+
+use crate::background::deferred::MAX_DELAY_HOURS;
+use crate::db::models::{EmailAction, EmailRule};
+
+/// An email whose item was created and which awaits its rule's action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEmail {
+    pub uid: u32,
+    pub message_id: String,
+    pub item_id: String,
+    pub intent_id: String,
+}
+
+/// The emails of one folder awaiting the same action
+#[derive(Debug, Clone)]
+pub struct PostProcessBatch {
+    pub action: EmailAction,
+    pub folder: String,
+    /// Folder the emails are moved or copied to; the flag `add_flag` sets
+    pub target_folder: Option<String>,
+    pub emails: Vec<PendingEmail>,
+}
+
+impl PostProcessBatch {
+    /// An empty batch for the action configured on `rule`; a move without a
+    /// target folder does nothing
+    pub fn for_rule(rule: &EmailRule) -> Self {
+        let action = match EmailAction::from_str(&rule.post_process_action) {
+            EmailAction::MoveToFolder if rule.move_to_folder.is_none() => EmailAction::DoNothing,
+            action => action,
+        };
+        Self {
+            action,
+            folder: rule.folder.clone(),
+            target_folder: rule.move_to_folder.clone(),
+            emails: Vec::new(),
+        }
+    }
+
+    pub fn uids(&self) -> Vec<u32> {
+        self.emails.iter().map(|email| email.uid).collect()
+    }
+}
+
+/// One action of a rule's post-processing chain, as stored in
+/// `post_process_actions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PostProcessStep {
+    /// `mark_read`, `add_flag`, `copy_to_folder`, `move_to_folder` or `delete`
+    pub action: String,
+    /// Target of `copy_to_folder` and `move_to_folder`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Flag or keyword `add_flag` sets, e.g. `\Flagged` or `$Archived`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag: Option<String>,
+    /// Hours after the item is created before the action is applied; none
+    /// or 0 applies it right away
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_hours: Option<i32>,
+}
+
+/// A step of a chain as it is applied: an empty batch of its action and how
+/// long the action waits
+#[derive(Debug, Clone)]
+pub struct ChainStep {
+    pub batch: PostProcessBatch,
+    pub delay: Option<Duration>,
+}
+
+/// Check a chain: known actions with their folder or flag, delays in range
+/// and never shorter than an earlier step's, and nothing after a step that
+/// moves or deletes the email; an error message naming the step otherwise
+pub fn validate_chain(steps: &[PostProcessStep]) -> Result<(), String> {
+    let mut previous_delay = 0;
+    for (index, step) in steps.iter().enumerate() {
+        let position = index + 1;
+        let action = match step.action.as_str() {
+            "mark_read" | "add_flag" | "copy_to_folder" | "move_to_folder" | "delete" => EmailAction::from_str(&step.action),
+            other => return Err(format!(
+                "Action {}: unknown action '{}'; use mark_read, add_flag, copy_to_folder, move_to_folder or delete", position, other
+            )),
+        };
+        let has_folder = step.folder.as_deref().is_some_and(|folder| !folder.trim().is_empty());
+        if matches!(action, EmailAction::CopyToFolder | EmailAction::MoveToFolder) && !has_folder {
+            return Err(format!("Action {}: {} needs a folder", position, action.as_str()));
+        }
+        if matches!(action, EmailAction::AddFlag) && !step.flag.as_deref().is_some_and(is_flag) {
+            return Err(format!("Action {}: add_flag needs a flag without spaces or brackets, e.g. \\Flagged", position));
+        }
+
+        let delay = step.delay_hours.unwrap_or(0);
+        if !(0..=MAX_DELAY_HOURS).contains(&delay) {
+            return Err(format!("Action {}: delay_hours must be between 0 and {}", position, MAX_DELAY_HOURS));
+        }
+        if delay < previous_delay {
+            return Err(format!("Action {}: delay_hours may not be shorter than an earlier action's", position));
+        }
+        previous_delay = delay;
+
+        if action.removes_email() && position < steps.len() {
+            return Err(format!("Action {}: nothing can follow {}, it takes the email out of the folder", position, action.as_str()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `flag` can be sent as a flag or keyword of `UID STORE`
+fn is_flag(flag: &str) -> bool {
+    let name = flag.strip_prefix('\\').unwrap_or(flag);
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic() && !"(){%*\"\\]".contains(c))
+}
+
+/// The actions `rule` applies to the emails it turns into items, in order:
+/// its chain when it has one, else its single action; empty when there is
+/// nothing to do
+pub fn chain_for_rule(rule: &EmailRule) -> Vec<ChainStep> {
+    let Some(chain) = rule.post_process_actions.as_deref() else {
+        let batch = PostProcessBatch::for_rule(rule);
+        if matches!(batch.action, EmailAction::DoNothing) {
+            return Vec::new();
+        }
+        return vec![ChainStep { batch, delay: rule.post_process_delay() }];
+    };
+
+    // Chains are checked when saved, so a broken one leaves the mailbox alone
+    let steps = match serde_json::from_str::<Vec<PostProcessStep>>(chain).map_err(|e| e.to_string()).and_then(|steps| {
+        validate_chain(&steps)?;
+        Ok(steps)
+    }) {
+        Ok(steps) => steps,
+        Err(e) => {
+            warn!("Ignoring invalid post-processing chain of rule '{}': {}", rule.name, e);
+            return Vec::new();
+        }
+    };
+    steps.into_iter()
+        .map(|step| {
+            let action = EmailAction::from_str(&step.action);
+            let target_folder = if matches!(action, EmailAction::AddFlag) { step.flag } else { step.folder };
+            ChainStep {
+                batch: PostProcessBatch { action, folder: rule.folder.clone(), target_folder, emails: Vec::new() },
+                delay: step.delay_hours.filter(|hours| *hours > 0).map(|hours| Duration::hours(hours.into())),
+            }
+        })
+        .collect()
+}
+
+/// What became of each UID of a batch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchOutcome {
+    pub applied: Vec<u32>,
+    /// UIDs the action could not be applied to, with the reason
+    pub failed: Vec<(u32, String)>,
+}
+
+impl BatchOutcome {
+    /// Every UID failing for the same reason, e.g. when the folder cannot be selected
+    pub fn all_failed(uids: &[u32], reason: &str) -> Self {
+        Self {
+            applied: Vec::new(),
+            failed: uids.iter().map(|uid| (*uid, reason.to_string())).collect(),
+        }
+    }
+}
+
+/// An IMAP sequence set of `uids`, with consecutive UIDs collapsed into ranges
+pub fn uid_set(uids: &[u32]) -> String {
+    let mut uids = uids.to_vec();
+    uids.sort_unstable();
+    uids.dedup();
+
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for uid in uids {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == uid => *end = uid,
+            _ => ranges.push((uid, uid)),
+        }
+    }
+    ranges.iter()
+        .map(|(start, end)| if start == end { start.to_string() } else { format!("{}:{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uid_set_collapses_ranges() {
+        assert_eq!(uid_set(&[]), "");
+        assert_eq!(uid_set(&[4]), "4");
+        assert_eq!(uid_set(&[7, 3, 4, 5, 9, 10, 4]), "3:5,7,9:10");
+    }
+
+    #[test]
+    fn test_all_failed() {
+        let outcome = BatchOutcome::all_failed(&[3, 8], "Folder is gone");
+        assert!(outcome.applied.is_empty());
+        assert_eq!(outcome.failed, [(3, "Folder is gone".to_string()), (8, "Folder is gone".to_string())]);
+    }
+
+    fn step(action: &str, folder: Option<&str>, flag: Option<&str>, delay_hours: Option<i32>) -> PostProcessStep {
+        PostProcessStep {
+            action: action.to_string(),
+            folder: folder.map(str::to_string),
+            flag: flag.map(str::to_string),
+            delay_hours,
+        }
+    }
+
+    #[test]
+    fn test_validate_chain() {
+        let chain = [
+            step("mark_read", None, None, None),
+            step("add_flag", None, Some("\\Flagged"), None),
+            step("copy_to_folder", Some("Archive"), None, Some(0)),
+            step("delete", None, None, Some(24 * 7)),
+        ];
+        assert_eq!(validate_chain(&chain), Ok(()));
+        assert_eq!(validate_chain(&[]), Ok(()));
+
+        let invalid = [
+            (vec![step("archive", None, None, None)], "Action 1: unknown action 'archive'"),
+            (vec![step("do_nothing", None, None, None)], "Action 1: unknown action"),
+            (vec![step("mark_read", None, None, None), step("copy_to_folder", Some(" "), None, None)], "Action 2: copy_to_folder needs a folder"),
+            (vec![step("add_flag", None, Some("Two words"), None)], "Action 1: add_flag needs a flag"),
+            (vec![step("add_flag", None, None, None)], "Action 1: add_flag needs a flag"),
+            (vec![step("mark_read", None, None, Some(-1))], "Action 1: delay_hours must be between"),
+            (vec![step("mark_read", None, None, Some(48)), step("delete", None, None, Some(24))], "Action 2: delay_hours may not be shorter"),
+            (vec![step("move_to_folder", Some("Archive"), None, None), step("mark_read", None, None, None)], "Action 1: nothing can follow move_to_folder"),
+        ];
+        for (chain, error) in invalid {
+            let result = validate_chain(&chain);
+            assert!(result.as_ref().is_err_and(|message| message.starts_with(error)), "{:?}: {:?}", chain, result);
+        }
+    }
+}
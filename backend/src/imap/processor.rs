@@ -1,19 +1,59 @@
 use anyhow::{Result, Context};
-use crate::db::models::{EmailRule, ImapAccount, NewFeedItem, EmailAction};
-use crate::db::{connection::DatabasePool, operations_generic::{EmailRuleOpsGeneric, FeedOpsGeneric, FeedItemOpsGeneric}};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::db::models::{AccountProtocol, EmailRule, Feed, FeedItem, ImapAccount, Importance, NewFeedItem, EmailAction, NewDeferredAction, NewProcessingIntent, NewProcessingRun, NewProcessingRunAction, NewRuleCost, NewRuleMatch, ProcessingIntent, ProcessingIntentStatus, ProcessingOrder, ProcessingRunStatus};
+use crate::db::{connection::DatabasePool, operations_generic::{DeferredActionOpsGeneric, EmailRuleOpsGeneric, FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, ProcessingIntentOpsGeneric, ProcessingRunOpsGeneric, ProcessingRunActionOpsGeneric, RuleCostOpsGeneric, RuleMatchOpsGeneric}};
+use crate::background::quota::{self, ItemAllowance, QuotaExceeded, QuotaResource};
+use crate::background::jobs::JobHandle;
+use crate::jmap::JmapClient;
+use crate::feed::{attachments, blob::BlobStore, bodies, chain, chat, content_filters::ContentFilters, dedup, digest, metadata::ComputedMetadata, output, routing, sanitize, summarizer, titles, unsubscribe::Unsubscribe, webhook};
+use super::expression::{CompiledExpression, MatchInput};
+use super::catch_up::{fetch_limit, CatchUp, MAX_CATCH_UP_EMAILS};
 use super::client::{ImapClient, Email};
-use tracing::{info, warn, error, debug};
+use super::fingerprint;
+use super::fetch_strategy::ServerProfile;
+use super::high_water::{self, FolderFetch, HighWaterMark};
+use super::labels;
+use super::mime::EmailContent;
+use super::post_process::{self, BatchOutcome, ChainStep, PendingEmail, PostProcessBatch};
+use super::rate_limit::{RateLimiter, RateLimits};
+use super::sender_filters::SenderFilters;
+use super::senders::SenderAliases;
+use super::throttle::TransferStats;
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{field, info, instrument, warn, error, debug, Span};
 
 pub struct EmailProcessor {
     account: ImapAccount,
     pool: DatabasePool,
+    /// Where large item bodies are moved, if anywhere
+    body_store: Option<BlobStore>,
+    /// Cancels the IMAP commands of this processor, e.g. on scheduler shutdown
+    cancellation: CancellationToken,
+    /// Job the processing reports its progress to, if run as one
+    job: Option<JobHandle>,
 }
 
 impl EmailProcessor {
     pub fn new(account: ImapAccount, pool: DatabasePool) -> Self {
-        Self { account, pool }
+        Self { account, pool, body_store: bodies::store_from_env(), cancellation: CancellationToken::new(), job: None }
+    }
+
+    /// Stop IMAP commands in flight as soon as `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Report the rules processed so far on `job`
+    pub fn with_job(mut self, job: JobHandle) -> Self {
+        self.job = Some(job);
+        self
     }
     
+    #[instrument(name = "account", skip_all, fields(account_id = self.account.id.as_deref().unwrap_or_default(), run_id = field::Empty))]
     pub async fn process_account(&self) -> Result<ProcessingResult> {
         info!("Processing IMAP account: {}", self.account.name);
         
@@ -23,68 +63,567 @@ impl EmailProcessor {
         
         // Get email rules for this account
         let rules = EmailRuleOpsGeneric::get_by_account_id(&self.pool, account_id)?;
+        self.process_rules(account_id, rules).await
+    }
+    
+    /// Process only the rules reading from `folders`, e.g. after those rules were edited
+    #[instrument(name = "account", skip_all, fields(account_id = self.account.id.as_deref().unwrap_or_default(), run_id = field::Empty))]
+    pub async fn process_folders(&self, folders: &[String]) -> Result<ProcessingResult> {
+        info!("Processing folders {:?} of IMAP account: {}", folders, self.account.name);
+        
+        let account_id = self.account.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Account has no ID"))?;
+        
+        let rules = EmailRuleOpsGeneric::get_by_account_id(&self.pool, account_id)?
+            .into_iter()
+            .filter(|rule| folders.contains(&rule.folder))
+            .collect();
+        self.process_rules(account_id, rules).await
+    }
+    
+    /// Match an email delivered over SMTP against the account's rules and
+    /// turn it into an item of each feed of the matching rules
+    ///
+    /// There is no mailbox, so nothing is post-processed and no high-water
+    /// mark is kept; emails already in a feed are skipped as usual, so a
+    /// redelivered email is harmless.
+    #[instrument(name = "ingest", skip_all, fields(account_id = self.account.id.as_deref().unwrap_or_default(), run_id = field::Empty))]
+    pub async fn ingest(&self, email: &Email) -> Result<ProcessingResult> {
+        let account_id = self.account.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Account has no ID"))?;
+        let rules: Vec<EmailRule> = EmailRuleOpsGeneric::get_by_account_id(&self.pool, account_id)?
+            .into_iter()
+            .filter(|rule| rule.is_active)
+            .collect();
+        let mut item_allowance = quota::item_allowance(&self.pool, &self.account)?;
+        
+        let run = ProcessingRunOpsGeneric::create(&self.pool, &NewProcessingRun::new(account_id.to_string()))?;
+        let run_id = run.id.ok_or_else(|| anyhow::anyhow!("Processing run has no ID"))?;
+        Span::current().record("run_id", run_id.as_str());
+        let mut result = ProcessingResult {
+            run_id: Some(run_id.clone()),
+            ..Default::default()
+        };
+        
+        let aliases = self.sender_aliases();
+        let sender_filters = self.sender_filters();
+        for rule in &rules {
+            let mut cost = NewRuleCost::new(run_id.clone(), rule.id.clone().unwrap_or_default(), rule.folder.clone());
+            let outcome = self.ingest_rule(email, rule, &run_id, &aliases, &sender_filters, &mut item_allowance, &mut cost).await;
+            self.record_rule_cost(rule, &cost);
+            match outcome {
+                Ok(rule_result) => {
+                    result.total_emails_processed += rule_result.emails_processed;
+                    result.new_feed_items_created += rule_result.items_created;
+                    if let Some(exceeded) = rule_result.quota_exceeded {
+                        warn!("Stopped ingesting email '{}' for account '{}': {}", email.subject, self.account.name, exceeded);
+                        result.errors.push(format!("Rule '{}': {}", rule.name, exceeded));
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!("Error ingesting email '{}' with rule '{}': {}", email.subject, rule.name, e);
+                    result.errors.push(format!("Rule '{}': {:#}", rule.name, e));
+                }
+            }
+        }
+        
+        self.finish_run(&run_id, &result);
+        Ok(result)
+    }
+    
+    /// Fetch the newest emails of each rule's folder from the account's JMAP
+    /// server and store the matching ones
+    ///
+    /// JMAP runs keep no high-water mark: each run looks at up to the rule's
+    /// fetch limit of the folder's newest emails, and duplicate detection
+    /// skips those already in the feed. Rule actions are not applied.
+    async fn process_jmap(&self, account_id: &str, rules: Vec<EmailRule>) -> Result<ProcessingResult> {
+        quota::check(&self.pool, &self.account, QuotaResource::ProcessingMinutes)?;
+        let mut item_allowance = quota::item_allowance(&self.pool, &self.account)?;
+        RateLimiter::global().begin_fetch(&self.account, Instant::now())?;
+        let client = JmapClient::connect(&self.account).await?;
+        
+        let run = ProcessingRunOpsGeneric::create(&self.pool, &NewProcessingRun::new(account_id.to_string()))?;
+        let run_id = run.id.ok_or_else(|| anyhow::anyhow!("Processing run has no ID"))?;
+        Span::current().record("run_id", run_id.as_str());
+        let mut result = ProcessingResult {
+            run_id: Some(run_id.clone()),
+            ..Default::default()
+        };
+        if let Some(job) = &self.job {
+            job.set_total(rules.iter().filter(|rule| rule.is_active).count());
+        }
+        
+        let aliases = self.sender_aliases();
+        let sender_filters = self.sender_filters();
+        'rules: for rule in rules.iter().filter(|rule| rule.is_active) {
+            info!("Processing rule: {} for JMAP mailbox: {}", rule.name, rule.folder);
+            let mut cost = NewRuleCost::new(run_id.clone(), rule.id.clone().unwrap_or_default(), rule.folder.clone());
+            let limit = RateLimits::of(&self.account).cap_batch(fetch_limit(rule));
+            let started = Instant::now();
+            let emails = match client.fetch_emails(&rule.folder, limit, !rule.include_seen).await {
+                Ok(emails) => emails,
+                Err(e) => {
+                    error!("Error fetching JMAP mailbox '{}' for rule '{}': {}", rule.folder, rule.name, e);
+                    result.errors.push(format!("Rule '{}': {:#}", rule.name, e));
+                    continue;
+                }
+            };
+            cost.fetch_ms = started.elapsed().as_millis() as i64;
+            cost.emails_fetched = emails.len() as i32;
+            
+            for email in &emails {
+                match self.ingest_rule(email, rule, &run_id, &aliases, &sender_filters, &mut item_allowance, &mut cost).await {
+                    Ok(rule_result) => {
+                        result.total_emails_processed += rule_result.emails_processed;
+                        result.new_feed_items_created += rule_result.items_created;
+                        if let Some(exceeded) = rule_result.quota_exceeded {
+                            warn!("Stopped processing account '{}': {}", self.account.name, exceeded);
+                            result.errors.push(format!("Rule '{}': {}", rule.name, exceeded));
+                            self.record_rule_cost(rule, &cost);
+                            break 'rules;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error processing rule '{}' in JMAP mailbox '{}': {}", rule.name, rule.folder, e);
+                        result.errors.push(format!("Rule '{}': {:#}", rule.name, e));
+                        break;
+                    }
+                }
+            }
+            self.record_rule_cost(rule, &cost);
+            if let Some(job) = &self.job {
+                job.advance(1);
+            }
+        }
+        
+        self.finish_run(&run_id, &result);
+        Ok(result)
+    }
+    
+    /// Record how a run ended, failed when any of its rules failed
+    fn finish_run(&self, run_id: &str, result: &ProcessingResult) {
+        let status = if result.errors.is_empty() {
+            ProcessingRunStatus::Completed
+        } else {
+            ProcessingRunStatus::Failed
+        };
+        let error_message = (!result.errors.is_empty()).then(|| result.errors.join("; "));
+        if let Err(e) = ProcessingRunOpsGeneric::finish(
+            &self.pool,
+            run_id,
+            &status,
+            result.total_emails_processed as i32,
+            result.new_feed_items_created as i32,
+            error_message,
+        ) {
+            warn!("Failed to record completion of processing run {}: {}", run_id, e);
+        }
+    }
+    
+    /// Store a delivered or JMAP email in the rule's feeds if it matches the
+    /// rule; observe-only rules just record the match
+    async fn ingest_rule(&self, email: &Email, rule: &EmailRule, run_id: &str, aliases: &SenderAliases, sender_filters: &SenderFilters, item_allowance: &mut Option<ItemAllowance>, cost: &mut NewRuleCost) -> Result<RuleProcessingResult> {
+        let rule_id = rule.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Rule has no ID"))?;
+        let expression = self.compile_expression(rule)?;
+        let mut result = RuleProcessingResult {
+            emails_processed: 0,
+            items_created: 0,
+            items_merged: 0,
+            quota_exceeded: None,
+            post_process_failures: Vec::new(),
+        };
+        if !self.evaluate(email, rule, aliases, sender_filters, expression.as_ref(), cost) {
+            return Ok(result);
+        }
+        
+        if rule.observe_only {
+            let new_match = NewRuleMatch::new(
+                rule_id.to_string(),
+                email.message_id.clone(),
+                email.subject.clone(),
+                email.from.clone(),
+                email.date,
+            );
+            result.emails_processed = usize::from(RuleMatchOpsGeneric::create_if_new(&self.pool, &new_match)?);
+            return Ok(result);
+        }
+        
+        let feeds = routing::active_feeds(&self.pool, rule_id)?;
+        if feeds.is_empty() {
+            warn!("No feed configured for rule: {}", rule.name);
+            return Ok(result);
+        }
+        result.emails_processed = 1;
+        let content = EmailContent::of(email);
+        let filters = ContentFilters::of(rule);
+        for feed in &feeds {
+            let item_title = titles::item_title(feed, &email.subject, content.html.as_deref().unwrap_or(&content.text));
+            if self.email_exists_in_feed(email, &content, &item_title, feed)? {
+                info!("⏭️ Email already exists in feed '{}': {}", feed.title, email.subject);
+                continue;
+            }
+            if let Some(allowance) = item_allowance.as_mut() {
+                if allowance.remaining <= 0 {
+                    if result.items_created + result.items_merged == 0 {
+                        result.emails_processed = 0;
+                    }
+                    result.quota_exceeded = Some(allowance.exceeded());
+                    return Ok(result);
+                }
+            }
+            
+            match self.create_feed_item(email, &content, &filters, &item_title, feed, run_id)? {
+                StoredItem::Created(item) => {
+                    result.items_created += 1;
+                    if let Some(allowance) = item_allowance.as_mut() {
+                        allowance.remaining -= 1;
+                    }
+                    info!("✅ Created feed item {} from delivered email: '{}'", item.id.as_deref().unwrap_or_default(), email.subject);
+                    self.publish(feed, &item, email).await;
+                }
+                StoredItem::Merged(_) => result.items_merged += 1,
+            }
+        }
+        Ok(result)
+    }
+    
+    /// Announce a newly created item and move its body out of the database
+    async fn publish(&self, feed: &Feed, item: &FeedItem, email: &Email) {
+        webhook::notify(&self.pool, feed, item).await;
+        chat::notify(&self.pool, feed, item).await;
         
+        // Append-only feeds keep bodies in the database, their chain hashes cover them;
+        // digests keep theirs while further emails may be merged in
+        if let Some(store) = self.body_store.as_ref().filter(|_| !feed.append_only && feed.digest_mode().is_none()) {
+            if let Err(e) = bodies::offload(&self.pool, store, item, bodies::min_bytes()).await {
+                warn!("Keeping body of email '{}' in the database: {}", email.subject, e);
+            }
+        }
+    }
+    
+    async fn process_rules(&self, account_id: &str, rules: Vec<EmailRule>) -> Result<ProcessingResult> {
         if rules.is_empty() {
             info!("No active rules for account: {}", self.account.name);
             return Ok(ProcessingResult {
                 total_emails_processed: 0,
                 new_feed_items_created: 0,
                 errors: vec![],
+                run_id: None,
+                transfer: TransferStats::default(),
+                post_process_failures: vec![],
             });
         }
         
-        let client = ImapClient::new(&self.account)?;
-        let mut result = ProcessingResult::default();
+        if self.account.account_protocol() == AccountProtocol::Jmap {
+            return self.process_jmap(account_id, rules).await;
+        }
+        
+        quota::check(&self.pool, &self.account, QuotaResource::ProcessingMinutes)?;
+        let mut item_allowance = quota::item_allowance(&self.pool, &self.account)?;
+        RateLimiter::global().begin_fetch(&self.account, Instant::now())?;
+        
+        let client = ImapClient::new(&self.account)?
+            .with_cancellation(self.cancellation.clone())
+            .with_labels(rules.iter().filter(|rule| rule.is_active).filter_map(|rule| rule.label.clone()));
+        
+        // Fingerprint new accounts once so aliases of an existing mailbox get flagged
+        if self.account.fingerprint.is_none() {
+            if let Err(e) = fingerprint::refresh(&self.pool, &client, &self.account).await {
+                debug!("Could not fingerprint mailbox of account '{}': {}", self.account.name, e);
+            }
+        }
+        
+        // Fetch further back when the account has not completed a run for a while
+        let mut catch_up = self.detect_catch_up(account_id);
+        
+        // Record the run so the items it creates can be traced and rolled back
+        let run = ProcessingRunOpsGeneric::create(&self.pool, &NewProcessingRun::new(account_id.to_string()))?;
+        let run_id = run.id.ok_or_else(|| anyhow::anyhow!("Processing run has no ID"))?;
+        Span::current().record("run_id", run_id.as_str());
+        
+        let mut result = ProcessingResult {
+            run_id: Some(run_id.clone()),
+            ..Default::default()
+        };
+        
+        if let Some(job) = &self.job {
+            job.set_total(rules.iter().filter(|rule| rule.is_active).count());
+        }
         
-        // Process each rule
-        for rule in rules {
+        // Process each rule, once per folder it reads from
+        'rules: for rule in rules {
             if !rule.is_active {
                 continue;
             }
+            if let Some(job) = &self.job {
+                job.note(&format!("Processing rule '{}'", rule.name));
+            }
             
-            match self.process_rule(&client, &rule).await {
-                Ok(rule_result) => {
-                    result.total_emails_processed += rule_result.emails_processed;
-                    result.new_feed_items_created += rule_result.items_created;
-                }
-                Err(e) => {
-                    error!("Error processing rule '{}': {}", rule.name, e);
-                    result.errors.push(format!("Rule '{}': {}", rule.name, e));
+            for (index, rule) in self.rule_folders(&client, rule).await.iter().enumerate() {
+                let own_folder = index == 0;
+                match self.process_rule(&client, rule, &run_id, &mut item_allowance, catch_up.as_mut(), own_folder).await {
+                    Ok(rule_result) => {
+                        result.total_emails_processed += rule_result.emails_processed;
+                        result.new_feed_items_created += rule_result.items_created;
+                        result.post_process_failures.extend(rule_result.post_process_failures);
+                        if let Some(exceeded) = rule_result.quota_exceeded {
+                            warn!("Stopped processing account '{}': {}", self.account.name, exceeded);
+                            result.errors.push(format!("Rule '{}': {}", rule.name, exceeded));
+                            break 'rules;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error processing rule '{}' in folder '{}': {}", rule.name, rule.folder, e);
+                        result.errors.push(format!("Rule '{}': {:#}", rule.name, e));
+                    }
                 }
             }
+            if let Some(job) = &self.job {
+                job.advance(1);
+            }
+        }
+        
+        self.finish_run(&run_id, &result);
+        
+        if let Some(catch_up) = &catch_up {
+            if catch_up.truncated {
+                warn!("Run {} caught up on account '{}' only partway: more than {} emails arrived in a folder since {}",
+                      run_id, self.account.name, MAX_CATCH_UP_EMAILS, catch_up.from.to_rfc3339());
+            }
+            if let Err(e) = ProcessingRunOpsGeneric::record_catch_up(
+                &self.pool,
+                &run_id,
+                &catch_up.from.to_rfc3339(),
+                &catch_up.to.to_rfc3339(),
+                catch_up.truncated,
+            ) {
+                warn!("Failed to record catch-up range of processing run {}: {}", run_id, e);
+            }
+        }
+        
+        self.record_server_profile(&client);
+        result.transfer = client.transfer_stats();
+        info!("Run {} transferred {} bytes in, {} bytes out", run_id, result.transfer.bytes_received, result.transfer.bytes_sent);
+        if let Err(e) = ProcessingRunOpsGeneric::record_transfer(
+            &self.pool,
+            &run_id,
+            result.transfer.bytes_received as i64,
+            result.transfer.bytes_sent as i64,
+        ) {
+            warn!("Failed to record bytes transferred by processing run {}: {}", run_id, e);
+        }
+        
+        Ok(result)
+    }
+    
+    /// Import every email of the rule's folder that matches it into its feed,
+    /// walking the folder from the oldest UID up in batches of `batch_size`
+    /// and reporting the emails examined on `job`
+    ///
+    /// Unlike a regular run this leaves the emails, the rule's high-water
+    /// mark and the feed's notifications alone, and seen emails are imported
+    /// whatever the rule's `include_seen`. Emails already in the feed are
+    /// skipped, so a backfill can be repeated. The items belong to a
+    /// processing run of their own, which can be rolled back.
+    #[instrument(name = "backfill", skip_all, fields(
+        account_id = self.account.id.as_deref().unwrap_or_default(),
+        rule_id = rule.id.as_deref().unwrap_or_default(),
+        feed_id = field::Empty,
+        run_id = field::Empty,
+    ))]
+    pub async fn backfill_rule(&self, rule: &EmailRule, batch_size: u32, job: &JobHandle) -> Result<BackfillResult> {
+        if self.account.account_protocol() != AccountProtocol::Imap {
+            anyhow::bail!("Backfills read IMAP folders; account '{}' uses {}", self.account.name, self.account.account_protocol().as_str());
+        }
+        let account_id = self.account.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Account has no ID"))?;
+        let rule_id = rule.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Rule has no ID"))?;
+        let feeds = routing::active_feeds(&self.pool, rule_id)?;
+        if feeds.is_empty() {
+            anyhow::bail!("No feed configured for rule '{}'", rule.name);
         }
+        let feed_ids: Vec<String> = feeds.iter().filter_map(|feed| feed.id.clone()).collect();
+        Span::current().record("feed_id", feed_ids.join(",").as_str());
+        let expression = self.compile_expression(rule)?;
+        let mut item_allowance = quota::item_allowance(&self.pool, &self.account)?;
+        let batch_size = RateLimits::of(&self.account).cap_batch(batch_size.max(1));
+        
+        let client = ImapClient::new(&self.account)?
+            .with_cancellation(self.cancellation.clone())
+            .with_labels(rule.label.clone());
+        let (uid_validity, messages) = client.folder_uid_validity(&rule.folder).await?;
+        job.set_total(messages.unwrap_or(0) as usize);
+        info!("⏮️ Backfilling rule '{}' from {} messages of folder '{}'", rule.name, messages.unwrap_or(0), rule.folder);
         
+        let run = ProcessingRunOpsGeneric::create(&self.pool, &NewProcessingRun::new(account_id.to_string()))?;
+        let run_id = run.id.ok_or_else(|| anyhow::anyhow!("Processing run has no ID"))?;
+        Span::current().record("run_id", run_id.as_str());
+        let mut result = BackfillResult { run_id: run_id.clone(), feed_ids, ..Default::default() };
+        
+        let outcome = self.backfill_batches(&client, rule, &feeds, &run_id, uid_validity, batch_size, &mut item_allowance, expression.as_ref(), &mut result, job).await;
+        let (status, error_message) = match &outcome {
+            Ok(()) => (ProcessingRunStatus::Completed, None),
+            Err(e) => (ProcessingRunStatus::Failed, Some(format!("Backfill of rule '{}': {:#}", rule.name, e))),
+        };
+        if let Err(e) = ProcessingRunOpsGeneric::finish(&self.pool, &run_id, &status, result.emails_matched as i32, result.items_created as i32, error_message) {
+            warn!("Failed to record completion of backfill run {}: {}", run_id, e);
+        }
+        self.record_server_profile(&client);
+        let transfer = client.transfer_stats();
+        if let Err(e) = ProcessingRunOpsGeneric::record_transfer(&self.pool, &run_id, transfer.bytes_received as i64, transfer.bytes_sent as i64) {
+            warn!("Failed to record bytes transferred by backfill run {}: {}", run_id, e);
+        }
+        
+        outcome?;
+        info!("⏮️ Backfill of rule '{}' examined {} emails, {} matched, {} items created",
+              rule.name, result.emails_examined, result.emails_matched, result.items_created);
         Ok(result)
     }
     
-    async fn process_rule(&self, client: &ImapClient, rule: &EmailRule) -> Result<RuleProcessingResult> {
+    async fn backfill_batches(&self, client: &ImapClient, rule: &EmailRule, feeds: &[Feed], run_id: &str, uid_validity: u32, batch_size: u32, item_allowance: &mut Option<ItemAllowance>, expression: Option<&CompiledExpression>, result: &mut BackfillResult, job: &JobHandle) -> Result<()> {
+        let aliases = self.sender_aliases();
+        let sender_filters = self.sender_filters();
+        let filters = ContentFilters::of(rule);
+        let mut cost = NewRuleCost::new(run_id.to_string(), rule.id.clone().unwrap_or_default(), rule.folder.clone());
+        let mut mark = HighWaterMark { uid_validity, last_uid: 0 };
+        
+        loop {
+            let fetch = client.fetch_emails_from_folder(&rule.folder, Some(batch_size), Some(mark), false)
+                .await
+                .with_context(|| format!("Failed to fetch emails from folder: {}", rule.folder))?;
+            if !fetch.resumed {
+                anyhow::bail!("UIDVALIDITY of folder '{}' changed during the backfill; start it again", rule.folder);
+            }
+            let Some(last_uid) = fetch.emails.iter().map(|email| email.uid).max() else {
+                return Ok(());
+            };
+            
+            let mut created = 0;
+            for email in &fetch.emails {
+                if !self.evaluate(email, rule, &aliases, &sender_filters, expression, &mut cost) {
+                    continue;
+                }
+                result.emails_matched += 1;
+                let content = EmailContent::of(email);
+                for feed in feeds {
+                    let item_title = titles::item_title(feed, &email.subject, content.html.as_deref().unwrap_or(&content.text));
+                    if self.email_exists_in_feed(email, &content, &item_title, feed)? {
+                        continue;
+                    }
+                    if let Some(allowance) = item_allowance.as_mut() {
+                        if allowance.remaining <= 0 {
+                            result.items_created += created;
+                            return Err(allowance.exceeded().into());
+                        }
+                    }
+                    match self.create_feed_item(email, &content, &filters, &item_title, feed, run_id)? {
+                        StoredItem::Created(item) => {
+                            created += 1;
+                            if let Some(allowance) = item_allowance.as_mut() {
+                                allowance.remaining -= 1;
+                            }
+                            if let Some(store) = self.body_store.as_ref().filter(|_| !feed.append_only && feed.digest_mode().is_none()) {
+                                if let Err(e) = bodies::offload(&self.pool, store, &item, bodies::min_bytes()).await {
+                                    warn!("Keeping body of email '{}' in the database: {}", email.subject, e);
+                                }
+                            }
+                        }
+                        StoredItem::Merged(_) => result.items_merged += 1,
+                    }
+                }
+            }
+            
+            result.emails_examined += fetch.emails.len();
+            result.items_created += created;
+            job.advance(fetch.emails.len());
+            debug!("Backfilled rule '{}' up to UID {}", rule.name, last_uid);
+            mark.last_uid = last_uid;
+        }
+    }
+    
+    /// The rule, followed by a copy reading from each of its folder's
+    /// subfolders when it includes them
+    ///
+    /// UIDs are per folder, so the copies start without a high-water mark and
+    /// fetch the newest mail of their folder every run; items already in the
+    /// feed are skipped as usual. When the subfolders cannot be listed only
+    /// the rule's own folder is processed.
+    async fn rule_folders(&self, client: &ImapClient, rule: EmailRule) -> Vec<EmailRule> {
+        if !rule.include_subfolders {
+            return vec![rule];
+        }
+        let subfolders = client.list_subfolders(&rule.folder).await.unwrap_or_else(|e| {
+            warn!("Could not list subfolders of '{}' for rule '{}', processing the folder alone: {}", rule.folder, rule.name, e);
+            Vec::new()
+        });
+        debug!("Rule '{}' reads from {} subfolders of '{}'", rule.name, subfolders.len(), rule.folder);
+        
+        let copies: Vec<EmailRule> = subfolders.into_iter()
+            .map(|folder| EmailRule { folder, last_seen_uid: None, uid_validity: None, ..rule.clone() })
+            .collect();
+        std::iter::once(rule).chain(copies).collect()
+    }
+    
+    /// Process a rule against one folder; `own_folder` is false for the
+    /// subfolders of a rule including them, which keep no high-water mark
+    #[instrument(name = "rule", skip_all, fields(rule_id = rule.id.as_deref().unwrap_or_default(), folder = %rule.folder, feed_id = field::Empty))]
+    async fn process_rule(&self, client: &ImapClient, rule: &EmailRule, run_id: &str, item_allowance: &mut Option<ItemAllowance>, catch_up: Option<&mut CatchUp>, own_folder: bool) -> Result<RuleProcessingResult> {
         info!("Processing rule: {} for folder: {}", rule.name, rule.folder);
         
-        // Get the feed associated with this rule
+        if rule.observe_only {
+            return self.observe_rule(client, rule, run_id, catch_up, own_folder).await;
+        }
+        
+        // Get the feeds this rule delivers to
         let rule_id = rule.id.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Rule has no ID"))?;
-        let feeds = FeedOpsGeneric::get_by_rule_id(&self.pool, rule_id)?;
+        let feeds = routing::active_feeds(&self.pool, rule_id)?;
         if feeds.is_empty() {
             warn!("No feed configured for rule: {}", rule.name);
             return Ok(RuleProcessingResult {
                 emails_processed: 0,
                 items_created: 0,
+                items_merged: 0,
+                quota_exceeded: None,
+                post_process_failures: Vec::new(),
             });
         }
         
-        let feed = &feeds[0]; // Use the first feed
-        let feed_id = feed.id.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Feed has no ID"))?;
+        let feed_ids: Vec<&str> = feeds.iter().filter_map(|feed| feed.id.as_deref()).collect();
+        Span::current().record("feed_id", feed_ids.join(",").as_str());
+        
+        let expression = self.compile_expression(rule)?;
         
         // Fetch emails from the specified folder
-        let emails = client.fetch_emails_from_folder(&rule.folder, Some(100))
-            .await
-            .with_context(|| format!("Failed to fetch emails from folder: {}", rule.folder))?;
+        let mut cost = NewRuleCost::new(run_id.to_string(), rule_id.clone(), rule.folder.clone());
+        let fetch = self.fetch_rule_emails(client, rule, catch_up, &mut cost).await?;
+        let emails = &fetch.emails;
         
         let mut result = RuleProcessingResult {
             emails_processed: 0,
             items_created: 0,
+            items_merged: 0,
+            quota_exceeded: None,
+            post_process_failures: Vec::new(),
         };
+        let aliases = self.sender_aliases();
+        let sender_filters = self.sender_filters();
+        let filters = ContentFilters::of(rule);
+        // Lowest UID left in the mailbox for the next run, which the high-water mark must not pass
+        let mut unsettled: Option<u32> = None;
+        let mut hold_back = |uid: u32| unsettled = Some(unsettled.map_or(uid, |lowest| lowest.min(uid)));
+        let chain = post_process::chain_for_rule(rule);
+        let mut pending: Vec<PendingEmail> = Vec::new();
+        if rule.post_process_actions.is_none()
+            && matches!(EmailAction::from_str(&rule.post_process_action), EmailAction::MoveToFolder)
+            && rule.move_to_folder.is_none()
+        {
+            warn!("Move action configured but no target folder specified for rule '{}'", rule.name);
+        }
         
         info!("Processing {} emails against rule criteria", emails.len());
         
@@ -94,64 +633,289 @@ impl EmailProcessor {
             debug!("Checking email - UID: {}, Subject: '{}', From: '{}' against rule: {}", 
                    email.uid, email.subject, email.from, rule.name);
                    
-            if self.matches_rule(&email, rule) {
-                result.emails_processed += 1;
-                info!("✅ Email {} matches rule '{}': {}", email_number, rule.name, email.subject);
-                info!("Email details: from='{}', date='{}'", email.from, email.date.format("%Y-%m-%d %H:%M:%S"));
-                
+            if !self.evaluate(email, rule, &aliases, &sender_filters, expression.as_ref(), &mut cost) {
+                debug!("❌ Email {} does not match rule criteria: '{}'", email_number, email.subject);
+                continue;
+            }
+            result.emails_processed += 1;
+            info!("✅ Email {} matches rule '{}': {}", email_number, rule.name, email.subject);
+            info!("Email details: from='{}', date='{}'", email.from, email.date.format("%Y-%m-%d %H:%M:%S"));
+            
+            let content = EmailContent::of(email);
+            // Item and intent of the email in each feed that did not have it yet
+            let mut stored: Vec<(String, String)> = Vec::new();
+            let mut settled = true;
+            for feed in &feeds {
+                let feed_id = feed.id.as_deref().unwrap_or_default();
                 // Check if we already have this email in the feed
-                debug!("Checking duplicate for email {}: '{}'", email_number, email.subject);
-                if !self.email_exists_in_feed(&email, feed_id)? {
-                    // Create a new feed item
-                    info!("📝 Attempting to create feed item for email {}: '{}'", email_number, email.subject);
-                    match self.create_feed_item(&email, feed_id) {
-                        Ok(item_id) => {
-                            result.items_created += 1;
-                            info!("✅ Successfully created feed item {} with ID {}: '{}'", email_number, item_id, email.subject);
-                            
-                            // Post-process the email according to the rule
-                            if let Err(e) = self.post_process_email(client, &email, rule).await {
-                                warn!("⚠️ Failed to post-process email {}: '{}' - {}", email_number, email.subject, e);
-                            } else {
-                                info!("✅ Post-processed email {} successfully", email_number);
-                            }
-                        }
-                        Err(e) => {
-                            error!("❌ Failed to create feed item for email {}: '{}' - Error: {}", email_number, email.subject, e);
+                debug!("Checking duplicate for email {} in feed '{}': '{}'", email_number, feed.title, email.subject);
+                let item_title = titles::item_title(feed, &email.subject, content.html.as_deref().unwrap_or(&content.text));
+                if self.email_exists_in_feed(email, &content, &item_title, feed)? {
+                    info!("⏭️ Email {} already exists in feed '{}': {}", email_number, feed.title, email.subject);
+                    continue;
+                }
+                if let Some(allowance) = item_allowance.as_mut() {
+                    if allowance.remaining <= 0 {
+                        result.quota_exceeded = Some(allowance.exceeded());
+                        break;
+                    }
+                }
+                
+                // Log the decision first so a crash cannot lose the item of a moved or deleted email
+                let intent_id = match self.log_intent(run_id, email, &item_title, feed_id, rule, chain.last()) {
+                    Ok(intent_id) => intent_id,
+                    Err(e) => {
+                        error!("❌ Failed to log processing of email {}: '{}', leaving it for the next run - Error: {}", email_number, email.subject, e);
+                        settled = false;
+                        continue;
+                    }
+                };
+                
+                // Create a new feed item
+                info!("📝 Attempting to create feed item for email {} in feed '{}': '{}'", email_number, feed.title, email.subject);
+                match self.create_feed_item(email, &content, &filters, &item_title, feed, run_id) {
+                    Ok(StoredItem::Merged(item)) => {
+                        let item_id = item.id.clone().unwrap_or_default();
+                        result.items_merged += 1;
+                        info!("✅ Merged email {} into digest item {}: '{}'", email_number, item_id, email.subject);
+                        stored.push((item_id, intent_id));
+                    }
+                    Ok(StoredItem::Created(item)) => {
+                        let item_id = item.id.clone().unwrap_or_default();
+                        result.items_created += 1;
+                        if let Some(allowance) = item_allowance.as_mut() {
+                            allowance.remaining -= 1;
                         }
+                        info!("✅ Successfully created feed item {} with ID {}: '{}'", email_number, item_id, email.subject);
+                        self.publish(feed, &item, email).await;
+                        stored.push((item_id, intent_id));
                     }
+                    Err(e) => {
+                        error!("❌ Failed to create feed item for email {}: '{}' - Error: {}", email_number, email.subject, e);
+                        self.resolve_intent(&intent_id, ProcessingIntentStatus::Failed, None);
+                        settled = false;
+                    }
+                }
+            }
+            
+            // Leave the email in the mailbox when a feed still misses it, for
+            // the next run or for when there is room again
+            let quota_exceeded = result.quota_exceeded.is_some();
+            if quota_exceeded {
+                if stored.is_empty() {
+                    result.emails_processed -= 1;
+                }
+                emails[index..].iter().for_each(|email| hold_back(email.uid));
+            } else if !settled {
+                hold_back(email.uid);
+            }
+            
+            // The email is post-processed once, together with the rest of the
+            // rule's emails below, under the intent of its first item
+            let mut stored = stored.into_iter();
+            if let Some((item_id, intent_id)) = stored.next() {
+                if chain.is_empty() || quota_exceeded || !settled {
+                    self.resolve_intent(&intent_id, ProcessingIntentStatus::Applied, Some(&item_id));
                 } else {
-                    info!("⏭️ Email {} already exists in feed: {}", email_number, email.subject);
+                    pending.push(PendingEmail {
+                        uid: email.uid,
+                        message_id: email.message_id.clone(),
+                        item_id,
+                        intent_id,
+                    });
                 }
-            } else {
-                debug!("❌ Email {} does not match rule criteria: '{}'", email_number, email.subject);
+            }
+            for (item_id, intent_id) in stored {
+                self.resolve_intent(&intent_id, ProcessingIntentStatus::Applied, Some(&item_id));
+            }
+            if quota_exceeded {
+                break;
             }
         }
         
-        info!("📊 Rule processing complete: processed {} emails, created {} feed items", 
-              result.emails_processed, result.items_created);
+        result.post_process_failures = self.post_process_chain(client, chain, pending, run_id, rule).await;
+        
+        info!("📊 Rule processing complete: processed {} emails, created {} feed items, merged {} into digests", 
+              result.emails_processed, result.items_created, result.items_merged);
+        if own_folder {
+            self.record_high_water_mark(rule, &fetch, unsettled);
+        }
+        self.record_rule_cost(rule, &cost);
         
-        if result.emails_processed > 0 && result.items_created == 0 {
+        let items_stored = result.items_created + result.items_merged;
+        if result.emails_processed > 0 && items_stored == 0 {
             error!("🚨 CRITICAL: {} emails were processed but NO feed items were created!", result.emails_processed);
-        } else if result.items_created < result.emails_processed {
+        } else if items_stored < result.emails_processed {
             warn!("⚠️ Mismatch: {} emails processed but only {} feed items created", 
-                  result.emails_processed, result.items_created);
-        } else if result.items_created == result.emails_processed {
+                  result.emails_processed, items_stored);
+        } else if items_stored == result.emails_processed {
             info!("✅ All processed emails successfully converted to feed items");
         }
         
         Ok(result)
     }
     
-    fn matches_rule(&self, email: &Email, rule: &EmailRule) -> bool {
+    /// Record the emails an observe-only rule matches without creating feed
+    /// items or post-processing them
+    async fn observe_rule(&self, client: &ImapClient, rule: &EmailRule, run_id: &str, catch_up: Option<&mut CatchUp>, own_folder: bool) -> Result<RuleProcessingResult> {
+        let rule_id = rule.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Rule has no ID"))?;
+        
+        let expression = self.compile_expression(rule)?;
+        let mut cost = NewRuleCost::new(run_id.to_string(), rule_id.clone(), rule.folder.clone());
+        let fetch = self.fetch_rule_emails(client, rule, catch_up, &mut cost).await?;
+        
+        let aliases = self.sender_aliases();
+        let sender_filters = self.sender_filters();
+        let mut new_matches = 0;
+        for email in fetch.emails.iter().filter(|email| self.evaluate(email, rule, &aliases, &sender_filters, expression.as_ref(), &mut cost)) {
+            let new_match = NewRuleMatch::new(
+                rule_id.to_string(),
+                email.message_id.clone(),
+                email.subject.clone(),
+                email.from.clone(),
+                email.date,
+            );
+            if RuleMatchOpsGeneric::create_if_new(&self.pool, &new_match)? {
+                new_matches += 1;
+            }
+        }
+        
+        info!("👀 Observe-only rule '{}' matched {} new emails", rule.name, new_matches);
+        if own_folder {
+            self.record_high_water_mark(rule, &fetch, None);
+        }
+        self.record_rule_cost(rule, &cost);
+        Ok(RuleProcessingResult {
+            emails_processed: new_matches,
+            items_created: 0,
+            items_merged: 0,
+            quota_exceeded: None,
+            post_process_failures: Vec::new(),
+        })
+    }
+    
+    /// The gap since the account's last completed run, when long enough that
+    /// the newest emails alone may not cover it
+    fn detect_catch_up(&self, account_id: &str) -> Option<CatchUp> {
+        let last_completed = match ProcessingRunOpsGeneric::last_completed_start(&self.pool, account_id) {
+            Ok(started) => started.and_then(|started| DateTime::parse_from_rfc3339(&started).ok()),
+            Err(e) => {
+                warn!("Failed to look up last completed run of account '{}', not catching up: {}", self.account.name, e);
+                None
+            }
+        };
+        let catch_up = CatchUp::detect(last_completed.map(|started| started.with_timezone(&Utc)), Utc::now())?;
+        info!("⏪ Account '{}' last completed a run at {}, catching up on up to {} emails per folder",
+              self.account.name, catch_up.from.to_rfc3339(), MAX_CATCH_UP_EMAILS);
+        Some(catch_up)
+    }
+    
+    /// Fetch the emails of a rule's folder above its high-water mark, or else
+    /// the newest reaching back into the catch-up gap when there is one, in
+    /// the rule's processing order and no more than the account's batch size;
+    /// the time taken goes into `cost`
+    async fn fetch_rule_emails(&self, client: &ImapClient, rule: &EmailRule, catch_up: Option<&mut CatchUp>, cost: &mut NewRuleCost) -> Result<FolderFetch> {
+        let limit = if catch_up.is_some() { MAX_CATCH_UP_EMAILS } else { fetch_limit(rule) };
+        let limit = RateLimits::of(&self.account).cap_batch(limit);
+        let started = Instant::now();
+        let mut fetch = client.fetch_emails_from_folder(&rule.folder, Some(limit), HighWaterMark::of(rule), !rule.include_seen)
+            .await
+            .with_context(|| format!("Failed to fetch emails from folder: {}", rule.folder))?;
+        cost.fetch_ms = started.elapsed().as_millis() as i64;
+        cost.emails_fetched = fetch.emails.len() as i32;
+        
+        // Everything above the mark is new, so only a fetch of the newest mail reaches back
+        if let Some(catch_up) = catch_up.filter(|_| !fetch.resumed) {
+            fetch.emails = catch_up.select(std::mem::take(&mut fetch.emails));
+        }
+        // By date, then UID, so emails sharing a Date header keep a stable order
+        match rule.processing_order() {
+            ProcessingOrder::NewestFirst => fetch.emails.sort_by_key(|email| std::cmp::Reverse((email.date, email.uid))),
+            ProcessingOrder::OldestFirst => fetch.emails.sort_by_key(|email| (email.date, email.uid)),
+        }
+        Ok(fetch)
+    }
+    
+    /// Move the rule's high-water mark past the emails of `fetch` settled this run
+    fn record_high_water_mark(&self, rule: &EmailRule, fetch: &FolderFetch, unsettled: Option<u32>) {
+        let Some(rule_id) = rule.id.as_deref() else { return };
+        let Some(mark) = high_water::advance(HighWaterMark::of(rule), fetch, unsettled) else { return };
+        if let Err(e) = EmailRuleOpsGeneric::set_high_water_mark(&self.pool, rule_id, mark.uid_validity as i64, mark.last_uid as i64) {
+            warn!("Failed to record last seen UID of rule '{}': {}", rule.name, e);
+        }
+    }
+    
+    /// Store what the client's fetches learned about the server, so the next
+    /// run starts with the fetch strategy that worked
+    fn record_server_profile(&self, client: &ImapClient) {
+        let Some(account_id) = self.account.id.as_deref() else { return };
+        let server = client.server_profile();
+        if server == ServerProfile::of(&self.account) {
+            return;
+        }
+        let strategy = server.strategy.map(|strategy| strategy.as_str());
+        if let Err(e) = ImapAccountOpsGeneric::update_server_profile(&self.pool, account_id, server.capabilities_str().as_deref(), strategy) {
+            warn!("Failed to record fetch strategy of account '{}': {}", self.account.name, e);
+        }
+    }
+    
+    /// Record what evaluating the rule cost this run
+    fn record_rule_cost(&self, rule: &EmailRule, cost: &NewRuleCost) {
+        debug!("Rule '{}' fetched {} emails in {} ms and checked {} in {} µs",
+               rule.name, cost.emails_fetched, cost.fetch_ms, cost.evaluations, cost.match_us);
+        if let Err(e) = RuleCostOpsGeneric::create(&self.pool, cost) {
+            warn!("Failed to record evaluation cost of rule '{}': {}", rule.name, e);
+        }
+    }
+    
+    /// Check an email against the account's sender filters and then the
+    /// rule, counting the check and its time in `cost`
+    fn evaluate(&self, email: &Email, rule: &EmailRule, aliases: &SenderAliases, sender_filters: &SenderFilters, expression: Option<&CompiledExpression>, cost: &mut NewRuleCost) -> bool {
+        let started = Instant::now();
+        let admitted = sender_filters.admits(&email.from);
+        if !admitted {
+            debug!("Sender '{}' is filtered out by account '{}'", email.from, self.account.name);
+        }
+        let matched = admitted && self.matches_rule(email, rule, aliases, expression);
+        cost.match_us += started.elapsed().as_micros() as i64;
+        cost.evaluations += 1;
+        cost.matches += i32::from(matched);
+        matched
+    }
+    
+    /// The rule's compiled match expression, if it has one
+    fn compile_expression(&self, rule: &EmailRule) -> Result<Option<CompiledExpression>> {
+        CompiledExpression::of_rule(rule.match_expression.as_deref())
+            .with_context(|| format!("Rule '{}' has an invalid match expression", rule.name))
+    }
+    
+    /// Sender alias groups for rule matching; none when they cannot be loaded
+    fn sender_aliases(&self) -> SenderAliases {
+        SenderAliases::load(&self.pool).unwrap_or_else(|e| {
+            warn!("Failed to load sender aliases, matching senders literally: {}", e);
+            SenderAliases::default()
+        })
+    }
+    
+    /// The account's sender allowlist and denylist; none when they cannot be loaded
+    fn sender_filters(&self) -> SenderFilters {
+        let account_id = self.account.id.as_deref().unwrap_or_default();
+        SenderFilters::load(&self.pool, account_id).unwrap_or_else(|e| {
+            warn!("Failed to load sender filters of account '{}', admitting all senders: {}", self.account.name, e);
+            SenderFilters::default()
+        })
+    }
+    
+    fn matches_rule(&self, email: &Email, rule: &EmailRule, aliases: &SenderAliases, expression: Option<&CompiledExpression>) -> bool {
         info!("Matching email against rule '{}': from_pattern={:?}, to_pattern={:?}, subject_pattern={:?}", 
                rule.name, rule.from_address, rule.to_address, rule.subject_contains);
         info!("Email details: UID={}, from='{}', to='{}', subject='{}'", 
                email.uid, email.from, email.to, email.subject);
         
-        // Check from address
+        // Check from address, counting every address of the sender's alias group
         if let Some(from_pattern) = &rule.from_address {
-            if !email.from.to_lowercase().contains(&from_pattern.to_lowercase()) {
+            if !aliases.matches_from(&email.from, from_pattern) {
                 info!("Email from '{}' does not contain pattern '{}'", email.from, from_pattern);
                 return false;
             } else {
@@ -179,152 +943,310 @@ impl EmailProcessor {
             }
         }
         
-        // TODO: Check labels/tags when IMAP server supports them
+        // Check importance; emails without an importance header count as normal
+        if let Some(wanted) = rule.importance.as_deref().and_then(Importance::parse) {
+            let importance = email.importance.unwrap_or(Importance::Normal);
+            if importance != wanted {
+                info!("Email importance '{}' is not '{}'", importance.as_str(), wanted.as_str());
+                return false;
+            }
+        }
+        
+        // Check the advanced match expression
+        if let Some(expression) = expression {
+            if !expression.matches(&MatchInput::of(email)) {
+                info!("Email does not match the rule's match expression");
+                return false;
+            }
+        }
+        
+        // Check the Gmail label; servers without labels cannot tell, so the
+        // criterion only applies where labels were read
+        if let Some(wanted) = rule.label.as_deref().filter(|label| !label.trim().is_empty()) {
+            match &email.labels {
+                Some(email_labels) if !labels::has_label(email_labels, wanted) => {
+                    info!("Email labels {:?} do not include '{}'", email_labels, wanted);
+                    return false;
+                }
+                Some(_) => {}
+                None => debug!("Server has no labels, not checking label '{}'", wanted),
+            }
+        }
         
         info!("Email matches all rule criteria");
         true
     }
     
-    fn email_exists_in_feed(&self, email: &Email, feed_id_val: &str) -> Result<bool> {
-        use crate::db::schema::feed_items::dsl::*;
-        use diesel::prelude::*;
+    /// Whether the feed already has the email, by Message-ID when it has one,
+    /// else by title, sender and date, else by content hash so resent copies
+    /// with another Message-ID or date are caught
+    fn email_exists_in_feed(&self, email: &Email, content: &EmailContent, item_title: &str, feed: &Feed) -> Result<bool> {
+        let feed_id = feed.id.as_deref().unwrap_or_default();
+        let exists = FeedItemOpsGeneric::is_duplicate(
+            &self.pool,
+            feed_id,
+            &email.message_id,
+            item_title,
+            &email.from,
+            &email.date.to_rfc3339(),
+            &dedup::content_hash(&email.subject, &email.from, &content.text),
+        )?;
+        // Wider scopes also skip emails other feeds already have
+        let scope = dedup::scope(feed);
+        let exists = exists || dedup::seen_in_scope(&self.pool, scope, &email.message_id, self.account.id.as_deref().unwrap_or_default())?;
+        debug!("Duplicate check for '{}' from '{}' (message ID '{}', scope {}): {}",
+               email.subject, email.from, email.message_id, scope.as_str(), if exists { "already stored" } else { "new" });
+        Ok(exists)
+    }
+    
+    /// Store an email's item, with its plain text as the body and its HTML
+    /// part, sanitized, next to it, both cleaned by the rule's content
+    /// filters; in a feed with a digest mode the email may be merged into an
+    /// existing item instead
+    fn create_feed_item(&self, email: &Email, content: &EmailContent, filters: &ContentFilters, item_title: &str, feed: &Feed, run_id: &str) -> Result<StoredItem> {
+        let feed_id_val = feed.id.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Feed has no ID"))?;
+        let summary_length = output::summary_length(feed);
+        // Footer links and the email's fingerprint come from the unfiltered content
+        let unsubscribe = Unsubscribe::of(email, content);
+        let metadata = ComputedMetadata::compute(&email.subject, &email.from, Some(&content.text));
+        let content = &filters.apply(content);
         
-        match &self.pool {
-            crate::db::connection::DatabasePool::SQLite(sqlite_pool) => {
-                let mut conn = sqlite_pool.get()?;
-                
-                // Priority 1: Use message ID if available and not empty
-                if !email.message_id.is_empty() {
-                    debug!("Checking duplicate by message ID: '{}'", email.message_id);
-                    let count = feed_items
-                        .filter(feed_id.eq(feed_id_val))
-                        .filter(email_message_id.eq(&email.message_id))
-                        .count()
-                        .get_result::<i64>(&mut conn)?;
-                    
-                    if count > 0 {
-                        debug!("Found duplicate by message ID '{}': {} existing items", email.message_id, count);
-                        return Ok(true);
-                    }
-                }
-                
-                // Priority 2: Fall back to subject + from + date combination for more robust duplicate detection
-                debug!("Checking duplicate by subject+from combination: '{}' from '{}'", email.subject, email.from);
-                let email_date_str = email.date.to_rfc3339();
-                debug!("Comparing with email date: {}", email_date_str);
-                let count = feed_items
-                    .filter(feed_id.eq(feed_id_val))
-                    .filter(title.eq(&email.subject))
-                    .filter(email_from.eq(&email.from))
-                    .filter(pub_date.eq(email_date_str))
-                    .count()
-                    .get_result::<i64>(&mut conn)?;
-                    
-                debug!("Duplicate check for '{}' from '{}': found {} existing items", 
-                       email.subject, email.from, count);
-                Ok(count > 0)
-            }
-            #[cfg(feature = "postgres")]
-            crate::db::connection::DatabasePool::PostgreSQL(pg_pool) => {
-                let mut conn = pg_pool.get()?;
-                
-                // Priority 1: Use message ID if available and not empty
-                if !email.message_id.is_empty() {
-                    debug!("Checking duplicate by message ID: '{}'", email.message_id);
-                    let count = feed_items
-                        .filter(feed_id.eq(feed_id_val))
-                        .filter(email_message_id.eq(&email.message_id))
-                        .count()
-                        .get_result::<i64>(&mut conn)?;
-                    
-                    if count > 0 {
-                        debug!("Found duplicate by message ID '{}': {} existing items", email.message_id, count);
-                        return Ok(true);
-                    }
-                }
-                
-                // Priority 2: Fall back to subject + from + date combination for more robust duplicate detection
-                debug!("Checking duplicate by subject+from combination: '{}' from '{}'", email.subject, email.from);
-                let email_date_str = email.date.to_rfc3339();
-                debug!("Comparing with email date: {}", email_date_str);
-                let count = feed_items
-                    .filter(feed_id.eq(feed_id_val))
-                    .filter(title.eq(&email.subject))
-                    .filter(email_from.eq(&email.from))
-                    .filter(pub_date.eq(email_date_str))
-                    .count()
-                    .get_result::<i64>(&mut conn)?;
-                    
-                debug!("Duplicate check for '{}' from '{}': found {} existing items", 
-                       email.subject, email.from, count);
-                Ok(count > 0)
-            }
-        }
-    }
-    
-    fn create_feed_item(&self, email: &Email, feed_id_val: &str) -> Result<String> {
-        let new_item = NewFeedItem::new(
+        let mut new_item = NewFeedItem::new(
             feed_id_val.to_string(),
-            email.subject.clone(),
-            Some(self.truncate_body(&email.body, 500)),
+            item_title.to_string(),
+            Some(summarizer::summarize(&content.text, summary_length)),
             Some(format!("mailto:{}?subject={}", email.from, urlencoding::encode(&email.subject))),
             Some(email.from.clone()),
             email.date,
             Some(email.message_id.clone()),
             Some(email.subject.clone()),
             Some(email.from.clone()),
-            Some(email.body.clone()),
+            Some(content.text.clone()),
         );
+        new_item.email_body_html = content.html.as_deref()
+            .map(|html| sanitize::sanitize_html(html, sanitize::remote_images_blocked(), filters.tracking_pixels));
+        new_item.processing_run_id = Some(run_id.to_string());
+        new_item.guid = Some(metadata.content_hash.clone());
+        new_item.content_hash = Some(metadata.content_hash);
+        new_item.language = Some(metadata.language);
+        new_item.importance = email.importance.map(|importance| importance.as_str().to_string());
+        new_item.category = email.category.clone();
+        new_item.unsubscribe_url = unsubscribe.url;
+        new_item.unsubscribe_mailto = unsubscribe.mailto;
+        new_item.unsubscribe_one_click = unsubscribe.one_click;
+        new_item.in_reply_to = email.in_reply_to.clone();
+        new_item.email_references = email.references.clone();
+        new_item.thread_id = digest::thread_id(&email.message_id, email.in_reply_to.as_deref(), email.references.as_deref());
         
-        FeedItemOpsGeneric::create(&self.pool, &new_item).map(|item| item.id)
+        // Items of append-only feeds cannot change, so they keep one per email
+        if let Some(mode) = feed.digest_mode().filter(|_| !feed.append_only) {
+            if let Some(target) = digest::find_target(&self.pool, feed_id_val, mode, &new_item)? {
+                debug!("Merging email '{}' into digest item {:?}", email.subject, target.id);
+                return digest::merge(&self.pool, mode, &target, &new_item, summary_length).map(StoredItem::Merged);
+            }
+        }
+        
+        // Link to an existing copy in another feed instead of storing the body
+        // again; append-only feeds keep their own copy
+        if dedup::is_enabled() && !feed.append_only {
+            let hash = new_item.content_hash.as_deref().unwrap_or_default();
+            if let Some(canonical) = dedup::find_canonical(&self.pool, feed_id_val, &email.message_id, hash)? {
+                debug!("Linking email '{}' to existing item {:?}", email.subject, canonical.id);
+                new_item.canonical_item_id = canonical.id;
+                new_item.email_body = None;
+                new_item.email_body_html = None;
+            }
+        }
+        
+        // The item and the run's count of it are written together
+        let item = self.pool.transaction(|tx| {
+            let item = if feed.append_only {
+                chain::append(tx, &mut new_item)?
+            } else {
+                tx.create_feed_item(&new_item)?
+            };
+            tx.count_run_item(run_id)?;
+            Ok(item)
+        })?;
+        let Some(item_id) = item.id.as_deref() else {
+            anyhow::bail!("Created item has no ID");
+        };
+        
+        // A linked item shows the attachments of its canonical copy
+        if item.canonical_item_id.is_none() && !content.attachments.is_empty() {
+            if let Err(e) = attachments::store(&self.pool, item_id, &content.attachments, attachments::max_bytes()) {
+                warn!("Failed to store attachments of email '{}': {}", email.subject, e);
+            }
+        }
+        Ok(StoredItem::Created(item))
+    }
+    
+    /// Log what is about to happen to an email, with a snapshot of it and
+    /// the last step of the rule's chain; returns the intent ID
+    fn log_intent(&self, run_id: &str, email: &Email, item_title: &str, feed_id: &str, rule: &EmailRule, last_step: Option<&ChainStep>) -> Result<String> {
+        let (action, target_folder) = match last_step {
+            Some(step) => (step.batch.action.clone(), step.batch.target_folder.clone()),
+            None => (EmailAction::DoNothing, None),
+        };
+        let new_intent = NewProcessingIntent::new(
+            run_id.to_string(),
+            feed_id.to_string(),
+            rule.folder.clone(),
+            email.uid,
+            (!email.message_id.is_empty()).then(|| email.message_id.clone()),
+            &action,
+            target_folder,
+            item_title.to_string(),
+            serde_json::to_string(email)?,
+        );
+        ProcessingIntentOpsGeneric::create(&self.pool, &new_intent)?;
+        Ok(new_intent.id)
     }
     
-    fn truncate_body(&self, body: &str, max_length: usize) -> String {
-        if body.len() <= max_length {
-            body.to_string()
+    fn resolve_intent(&self, intent_id: &str, status: ProcessingIntentStatus, item_id: Option<&str>) {
+        if let Err(e) = ProcessingIntentOpsGeneric::resolve(&self.pool, intent_id, &status, item_id) {
+            warn!("Failed to mark processing intent {} {}: {}", intent_id, status.as_str(), e);
+        }
+    }
+    
+    /// Settle an intent left pending by an interrupted run
+    ///
+    /// The email may already have been moved or deleted, so when the feed has
+    /// no item for it the item is rebuilt from the intent's snapshot.
+    pub fn recover_intent(&self, intent: &ProcessingIntent) -> Result<ProcessingIntentStatus> {
+        let intent_id = intent.id.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Processing intent has no ID"))?;
+        let snapshot = intent.email_snapshot.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Processing intent {} has no email snapshot", intent_id))?;
+        let email: Email = serde_json::from_str(snapshot)?;
+        let feed = FeedOpsGeneric::get_by_id(&self.pool, &intent.feed_id)?;
+        
+        let content = EmailContent::of(&email);
+        let (status, item_id) = if self.email_exists_in_feed(&email, &content, &intent.item_title, &feed)? {
+            (ProcessingIntentStatus::Reconciled, None)
         } else {
-            format!("{}...", &body[..max_length])
-        }
-    }
-    
-    /// Post-process an email according to the rule's action configuration
-    async fn post_process_email(&self, client: &ImapClient, email: &Email, rule: &EmailRule) -> Result<()> {
-        let action = EmailAction::from_str(&rule.post_process_action);
-        
-        info!("Post-processing email '{}' with action: {:?}", email.subject, action);
-        
-        match action {
-            EmailAction::DoNothing => {
-                debug!("No post-processing action for email: {}", email.subject);
-                Ok(())
-            }
-            EmailAction::MarkAsRead => {
-                client.mark_as_read_in_folder(email.uid, &rule.folder)
-                    .await
-                    .with_context(|| format!("Failed to mark email {} as read in folder '{}'", email.uid, rule.folder))?;
-                info!("Marked email '{}' as read in folder '{}'", email.subject, rule.folder);
-                Ok(())
-            }
-            EmailAction::Delete => {
-                client.delete_email_in_folder(email.uid, &rule.folder)
-                    .await
-                    .with_context(|| format!("Failed to delete email {} from folder '{}'", email.uid, rule.folder))?;
-                info!("Deleted email '{}' from folder '{}'", email.subject, rule.folder);
-                Ok(())
-            }
-            EmailAction::MoveToFolder => {
-                if let Some(target_folder) = &rule.move_to_folder {
-                    client.move_to_folder_from_folder(email.uid, &rule.folder, target_folder)
-                        .await
-                        .with_context(|| format!("Failed to move email {} from '{}' to folder '{}'", email.uid, rule.folder, target_folder))?;
-                    info!("Moved email '{}' from '{}' to folder '{}'", email.subject, rule.folder, target_folder);
-                    Ok(())
-                } else {
-                    warn!("Move action configured but no target folder specified for rule '{}'", rule.name);
-                    Ok(())
+            // Items of rules deleted since are stored with the default filters
+            let filters = EmailRuleOpsGeneric::get_by_id(&self.pool, &feed.email_rule_id)
+                .map(|rule| ContentFilters::of(&rule))
+                .unwrap_or_default();
+            let stored = self.create_feed_item(&email, &content, &filters, &intent.item_title, &feed, &intent.processing_run_id)?;
+            let item = stored.item();
+            info!("Recovered feed item {:?} for email '{}' from an interrupted run", item.id, email.subject);
+            (ProcessingIntentStatus::Recovered, item.id.clone())
+        };
+        ProcessingIntentOpsGeneric::resolve(&self.pool, intent_id, &status, item_id.as_deref())?;
+        Ok(status)
+    }
+    
+    /// Remember a post-processing action so a rollback of the run can reverse it
+    fn record_run_action(&self, run_id: &str, email: &PendingEmail, batch: &PostProcessBatch) {
+        if matches!(batch.action, EmailAction::DoNothing) {
+            return;
+        }
+        
+        let new_action = NewProcessingRunAction::new(
+            run_id.to_string(),
+            Some(email.item_id.clone()),
+            batch.folder.clone(),
+            email.uid,
+            (!email.message_id.is_empty()).then(|| email.message_id.clone()),
+            &batch.action,
+            batch.target_folder.clone(),
+        );
+        
+        if let Err(e) = ProcessingRunActionOpsGeneric::create(&self.pool, &new_action) {
+            warn!("Failed to record {} action for email UID {} in run {}: {}", batch.action.as_str(), email.uid, run_id, e);
+        }
+    }
+    
+    /// Apply the rule's chain to the emails turned into items this run, one
+    /// step after the other, returning a message for each email a step failed
+    /// on; such an email is left out of the steps after it
+    async fn post_process_chain(&self, client: &ImapClient, chain: Vec<ChainStep>, mut emails: Vec<PendingEmail>, run_id: &str, rule: &EmailRule) -> Vec<String> {
+        let mut messages = Vec::new();
+        for step in chain {
+            if emails.is_empty() {
+                break;
+            }
+            let batch = PostProcessBatch { emails, ..step.batch };
+            let failures = match step.delay {
+                Some(delay) => self.defer_post_process(&batch, run_id, rule, Utc::now() + delay),
+                None => self.post_process_emails(client, &batch, run_id, rule).await,
+            };
+            emails = batch.emails.into_iter().filter(|email| !failures.iter().any(|(uid, _)| *uid == email.uid)).collect();
+            messages.extend(failures.into_iter().map(|(_, message)| message));
+        }
+        messages
+    }
+    
+    /// Schedule a step on the emails turned into items this run for `due_at`,
+    /// returning the emails it could not be scheduled for with a message
+    fn defer_post_process(&self, batch: &PostProcessBatch, run_id: &str, rule: &EmailRule, due_at: DateTime<Utc>) -> Vec<(u32, String)> {
+        let (Some(rule_id), Some(account_id)) = (rule.id.as_ref(), self.account.id.as_ref()) else {
+            return Vec::new();
+        };
+        
+        let mut messages = Vec::new();
+        for email in &batch.emails {
+            let new_action = NewDeferredAction::new(
+                rule_id.clone(),
+                account_id.clone(),
+                run_id.to_string(),
+                email.intent_id.clone(),
+                email.item_id.clone(),
+                batch.folder.clone(),
+                email.uid,
+                (!email.message_id.is_empty()).then(|| email.message_id.clone()),
+                &batch.action,
+                batch.target_folder.clone(),
+                due_at,
+            );
+            match DeferredActionOpsGeneric::create(&self.pool, &new_action) {
+                Ok(_) => self.resolve_intent(&email.intent_id, ProcessingIntentStatus::Deferred, Some(&email.item_id)),
+                Err(e) => {
+                    warn!("⚠️ Failed to schedule {} of email UID {} in folder '{}': {}", batch.action.as_str(), email.uid, batch.folder, e);
+                    self.resolve_intent(&email.intent_id, ProcessingIntentStatus::Failed, Some(&email.item_id));
+                    messages.push((email.uid, format!("Rule '{}': could not schedule {} of email UID {} in '{}': {}",
+                                                      rule.name, batch.action.as_str(), email.uid, batch.folder, e)));
+                }
+            }
+        }
+        if !batch.emails.is_empty() {
+            info!("⏳ Deferred {} of {} emails until {}", batch.action.as_str(), batch.emails.len() - messages.len(), due_at.to_rfc3339());
+        }
+        messages
+    }
+    
+    /// Apply a step to the emails turned into items this run with one batched
+    /// command, returning the emails it failed on with a message
+    async fn post_process_emails(&self, client: &ImapClient, batch: &PostProcessBatch, run_id: &str, rule: &EmailRule) -> Vec<(u32, String)> {
+        if batch.emails.is_empty() {
+            return Vec::new();
+        }
+        
+        let outcome = client.apply_post_process_batch(batch).await.unwrap_or_else(|e| {
+            BatchOutcome::all_failed(&batch.uids(), &format!("{:#}", e))
+        });
+        let failures: HashMap<u32, String> = outcome.failed.into_iter().collect();
+        
+        let mut messages = Vec::new();
+        for email in &batch.emails {
+            match failures.get(&email.uid) {
+                None => {
+                    self.record_run_action(run_id, email, batch);
+                    self.resolve_intent(&email.intent_id, ProcessingIntentStatus::Applied, Some(&email.item_id));
+                }
+                Some(reason) => {
+                    warn!("⚠️ Failed to {} email UID {} in folder '{}': {}", batch.action.as_str(), email.uid, batch.folder, reason);
+                    self.resolve_intent(&email.intent_id, ProcessingIntentStatus::Failed, Some(&email.item_id));
+                    messages.push((email.uid, format!("Rule '{}': could not {} email UID {} in '{}': {}",
+                                                      rule.name, batch.action.as_str(), email.uid, batch.folder, reason)));
                 }
             }
         }
+        info!("✅ Post-processed {} of {} emails with action: {}", batch.emails.len() - messages.len(), batch.emails.len(), batch.action.as_str());
+        messages
     }
 }
 
@@ -333,12 +1255,53 @@ pub struct ProcessingResult {
     pub total_emails_processed: usize,
     pub new_feed_items_created: usize,
     pub errors: Vec<String>,
+    /// Processing run recorded for this pass, if any work was attempted
+    pub run_id: Option<String>,
+    /// Bytes transferred over IMAP during this pass
+    pub transfer: TransferStats,
+    /// Emails whose item was created but whose rule action failed, one per
+    /// UID; they stay in the mailbox and do not fail the run
+    pub post_process_failures: Vec<String>,
+}
+
+/// Outcome of importing a rule's past emails
+#[derive(Debug, Default, Serialize)]
+pub struct BackfillResult {
+    pub run_id: String,
+    /// Feeds the rule delivers to, by priority
+    pub feed_ids: Vec<String>,
+    pub emails_examined: usize,
+    pub emails_matched: usize,
+    pub items_created: usize,
+    /// Emails merged into existing digest items
+    pub items_merged: usize,
+}
+
+/// The item an email went into
+#[derive(Debug)]
+enum StoredItem {
+    Created(FeedItem),
+    /// An existing item of a feed with a digest mode
+    Merged(FeedItem),
+}
+
+impl StoredItem {
+    fn item(&self) -> &FeedItem {
+        match self {
+            StoredItem::Created(item) | StoredItem::Merged(item) => item,
+        }
+    }
 }
 
 #[derive(Debug)]
 struct RuleProcessingResult {
     pub emails_processed: usize,
     pub items_created: usize,
+    /// Emails merged into existing items by their feed's digest mode
+    pub items_merged: usize,
+    /// Item limit reached before all matching emails were turned into items
+    pub quota_exceeded: Option<QuotaExceeded>,
+    pub post_process_failures: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -1,4 +1,4 @@
-// Protocol compatibility module - currently unused as we've moved to synchronous imap
+// Protocol compatibility module - currently unused; ImapClient talks to ProtonMail Bridge directly
 // Keeping this file for potential future use or reference
 
 #[allow(dead_code)]
@@ -0,0 +1,219 @@
+//! Rate limiting and politeness towards IMAP servers
+//!
+//! Providers such as Gmail lock accounts that connect or fetch too often. An
+//! account can cap the connections made to its server in any hour, require a
+//! minimum delay between fetches, and cap the messages fetched from a folder
+//! at once. The limits are tracked here for the whole process, so they hold
+//! whichever part of the backend talks to the server: every connection an
+//! `ImapClient` opens is counted and refused once the hourly cap is reached,
+//! the processor starts a fetch only when both limits allow it and caps its
+//! batches, and the scheduler holds accounts back until their next fetch is
+//! allowed rather than starting a run bound to fail.
+
+use crate::db::models::ImapAccount;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Window the connection cap applies to
+const WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// The rate limits of an account; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub max_connections_per_hour: Option<u32>,
+    pub min_fetch_delay: Option<Duration>,
+    pub max_messages_per_fetch: Option<u32>,
+}
+
+impl RateLimits {
+    pub fn of(account: &ImapAccount) -> Self {
+        let positive = |limit: Option<i32>| limit.and_then(|limit| u32::try_from(limit).ok()).filter(|limit| *limit > 0);
+        Self {
+            max_connections_per_hour: positive(account.max_connections_per_hour),
+            min_fetch_delay: positive(account.min_fetch_delay_seconds).map(|seconds| Duration::from_secs(seconds.into())),
+            max_messages_per_fetch: positive(account.max_messages_per_fetch),
+        }
+    }
+
+    /// `limit` lowered to the account's batch size
+    pub fn cap_batch(&self, limit: u32) -> u32 {
+        self.max_messages_per_fetch.map_or(limit, |max| limit.min(max))
+    }
+}
+
+/// Check requested limits, which must be positive when set
+pub fn validate_limits(max_connections_per_hour: Option<i32>, min_fetch_delay_seconds: Option<i32>, max_messages_per_fetch: Option<i32>) -> Result<(), String> {
+    for (name, limit) in [
+        ("max_connections_per_hour", max_connections_per_hour),
+        ("min_fetch_delay_seconds", min_fetch_delay_seconds),
+        ("max_messages_per_fetch", max_messages_per_fetch),
+    ] {
+        if limit.is_some_and(|limit| limit <= 0) {
+            return Err(format!("{} must be a positive number", name));
+        }
+    }
+    Ok(())
+}
+
+/// Why an account has to wait before talking to its server again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub account: String,
+    pub reason: String,
+    /// Time until the limit allows it
+    pub wait: Duration,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Account '{}' is rate limited ({}); next allowed in {}s", self.account, self.reason, self.wait.as_secs().max(1))
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Recent activity of one account
+#[derive(Debug, Default)]
+struct History {
+    /// Connections opened within the last hour, oldest first
+    connections: VecDeque<Instant>,
+    last_fetch: Option<Instant>,
+}
+
+impl History {
+    fn forget_before(&mut self, now: Instant) {
+        while self.connections.front().is_some_and(|opened| now.saturating_duration_since(*opened) >= WINDOW) {
+            self.connections.pop_front();
+        }
+    }
+
+    /// Time until another connection is allowed, and why
+    fn connection_wait(&self, limits: &RateLimits, now: Instant) -> Option<(Duration, String)> {
+        let max = limits.max_connections_per_hour?;
+        if self.connections.len() < max as usize {
+            return None;
+        }
+        let oldest = self.connections[self.connections.len() - max as usize];
+        let wait = (oldest + WINDOW).saturating_duration_since(now);
+        Some((wait, format!("{} connections in the last hour", self.connections.len())))
+    }
+
+    /// Time until another fetch is allowed, and why
+    fn fetch_wait(&self, limits: &RateLimits, now: Instant) -> Option<(Duration, String)> {
+        let delay_wait = limits.min_fetch_delay.zip(self.last_fetch)
+            .map(|(delay, last)| (last + delay).saturating_duration_since(now))
+            .filter(|wait| !wait.is_zero())
+            .map(|wait| (wait, format!("{}s between fetches", limits.min_fetch_delay.unwrap_or_default().as_secs())));
+        match (delay_wait, self.connection_wait(limits, now)) {
+            (Some(delay), Some(connections)) => Some(if delay.0 >= connections.0 { delay } else { connections }),
+            (delay, connections) => delay.or(connections),
+        }
+    }
+}
+
+/// Connection and fetch history of every account, shared by the process
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    accounts: Mutex<HashMap<String, History>>,
+}
+
+impl RateLimiter {
+    /// The limiter consulted by clients, the processor and the scheduler
+    pub fn global() -> &'static RateLimiter {
+        static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+        LIMITER.get_or_init(RateLimiter::default)
+    }
+
+    /// Count a connection to the account's server, unless the hourly cap is
+    /// reached; accounts not saved yet are not limited
+    pub fn acquire_connection(&self, account: &ImapAccount, now: Instant) -> Result<(), RateLimited> {
+        let Some(id) = &account.id else { return Ok(()) };
+        let limits = RateLimits::of(account);
+        let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        let history = accounts.entry(id.clone()).or_default();
+        history.forget_before(now);
+        if let Some((wait, reason)) = history.connection_wait(&limits, now) {
+            return Err(RateLimited { account: account.name.clone(), reason, wait });
+        }
+        history.connections.push_back(now);
+        Ok(())
+    }
+
+    /// Time the account has to wait before its next fetch, if any
+    pub fn fetch_wait(&self, account: &ImapAccount, now: Instant) -> Option<Duration> {
+        let id = account.id.as_ref()?;
+        let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        let history = accounts.get_mut(id)?;
+        history.forget_before(now);
+        history.fetch_wait(&RateLimits::of(account), now).map(|(wait, _)| wait)
+    }
+
+    /// Start a fetch for the account, unless its limits call for waiting
+    pub fn begin_fetch(&self, account: &ImapAccount, now: Instant) -> Result<(), RateLimited> {
+        let Some(id) = &account.id else { return Ok(()) };
+        let limits = RateLimits::of(account);
+        let mut accounts = self.accounts.lock().unwrap_or_else(|e| e.into_inner());
+        let history = accounts.entry(id.clone()).or_default();
+        history.forget_before(now);
+        if let Some((wait, reason)) = history.fetch_wait(&limits, now) {
+            return Err(RateLimited { account: account.name.clone(), reason, wait });
+        }
+        history.last_fetch = Some(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::NewImapAccount;
+
+    fn account(max_connections_per_hour: Option<i32>, min_fetch_delay_seconds: Option<i32>) -> ImapAccount {
+        let mut account = NewImapAccount::new("Gmail".into(), "imap.gmail.com".into(), 993, "user".into(), "secret".into(), true);
+        account.max_connections_per_hour = max_connections_per_hour;
+        account.min_fetch_delay_seconds = min_fetch_delay_seconds;
+        account.max_messages_per_fetch = Some(25);
+        account.to_account()
+    }
+
+    #[test]
+    fn test_connections_are_capped_per_hour() {
+        let limiter = RateLimiter::default();
+        let account = account(Some(2), None);
+        let start = Instant::now();
+
+        limiter.acquire_connection(&account, start).unwrap();
+        limiter.acquire_connection(&account, start + Duration::from_secs(600)).unwrap();
+        let limited = limiter.acquire_connection(&account, start + Duration::from_secs(1200)).unwrap_err();
+        assert_eq!(limited.wait, Duration::from_secs(2400));
+        assert_eq!(limiter.fetch_wait(&account, start + Duration::from_secs(1200)), Some(Duration::from_secs(2400)));
+
+        // The first connection leaves the window after an hour
+        limiter.acquire_connection(&account, start + WINDOW).unwrap();
+    }
+
+    #[test]
+    fn test_fetches_wait_for_the_minimum_delay() {
+        let limiter = RateLimiter::default();
+        let account = account(None, Some(300));
+        let start = Instant::now();
+
+        assert_eq!(limiter.fetch_wait(&account, start), None);
+        limiter.begin_fetch(&account, start).unwrap();
+        let limited = limiter.begin_fetch(&account, start + Duration::from_secs(100)).unwrap_err();
+        assert_eq!(limited.wait, Duration::from_secs(200));
+        assert!(limited.to_string().contains("300s between fetches"));
+        limiter.begin_fetch(&account, start + Duration::from_secs(300)).unwrap();
+    }
+
+    #[test]
+    fn test_limits_of_an_account() {
+        let limits = RateLimits::of(&account(Some(0), None));
+        assert_eq!(limits.max_connections_per_hour, None);
+        assert_eq!(limits.cap_batch(100), 25);
+        assert_eq!(limits.cap_batch(10), 10);
+        assert!(validate_limits(Some(10), None, Some(0)).unwrap_err().contains("max_messages_per_fetch"));
+    }
+}
@@ -0,0 +1,196 @@
+//! Rule evaluation cost
+//!
+//! Every run records, per rule, how long fetching the rule's folder took,
+//! how many emails came back and how long checking them against the rule's
+//! criteria took. Totalled over a window, the rules that cost the most show
+//! where lower fetch limits or narrower criteria pay off, and the folders
+//! fetched by several rules show where merging rules or moving mail into
+//! separate folders would save fetches.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{EmailRule, ImapAccount, RuleCost},
+    operations_generic::{EmailRuleOpsGeneric, ImapAccountOpsGeneric, RuleCostOpsGeneric},
+};
+
+/// Days of runs the report covers when none is given
+pub const DEFAULT_WINDOW_DAYS: i64 = 7;
+
+/// Evaluation cost of a rule totalled over the runs in the window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RuleCostSummary {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub account_id: Option<String>,
+    pub folder: String,
+    /// Runs that evaluated the rule
+    pub runs: i64,
+    pub emails_fetched: i64,
+    pub evaluations: i64,
+    pub matches: i64,
+    pub fetch_ms: i64,
+    pub match_us: i64,
+    pub avg_fetch_ms: f64,
+    /// Microseconds per email checked
+    pub avg_match_us: f64,
+}
+
+impl RuleCostSummary {
+    /// Fetching and matching time in microseconds, what rules are ranked by
+    pub fn total_us(&self) -> i64 {
+        self.fetch_ms * 1000 + self.match_us
+    }
+}
+
+/// Fetches of a folder totalled over the runs in the window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FolderCostSummary {
+    pub account_id: Option<String>,
+    pub account_name: Option<String>,
+    pub folder: String,
+    /// Rules reading from the folder
+    pub rules: i64,
+    pub fetches: i64,
+    pub emails_fetched: i64,
+    pub fetch_ms: i64,
+    pub avg_fetch_ms: f64,
+}
+
+/// Most expensive rules and folders over a window of runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RuleCostReport {
+    pub generated_at: String,
+    pub window_days: i64,
+    /// Rules, most expensive first
+    pub rules: Vec<RuleCostSummary>,
+    /// Folders, slowest to fetch first
+    pub folders: Vec<FolderCostSummary>,
+}
+
+/// Report on the costs recorded over the last `window_days`
+pub fn report(pool: &DatabasePool, window_days: i64) -> Result<RuleCostReport> {
+    let since = (Utc::now() - Duration::days(window_days)).to_rfc3339();
+    let costs = RuleCostOpsGeneric::get_since(pool, &since)?;
+    let rules = EmailRuleOpsGeneric::get_all(pool)?;
+    let accounts = ImapAccountOpsGeneric::get_all(pool)?;
+    Ok(build(&costs, &rules, &accounts, window_days))
+}
+
+/// Per-rule totals of every recorded cost, for the metrics
+pub fn totals(pool: &DatabasePool) -> Result<Vec<RuleCostSummary>> {
+    let costs = RuleCostOpsGeneric::get_since(pool, "")?;
+    let rules = EmailRuleOpsGeneric::get_all(pool)?;
+    Ok(summarize_rules(&costs, &rules))
+}
+
+/// Build the report from recorded costs
+pub fn build(costs: &[RuleCost], rules: &[EmailRule], accounts: &[ImapAccount], window_days: i64) -> RuleCostReport {
+    RuleCostReport {
+        generated_at: Utc::now().to_rfc3339(),
+        window_days,
+        rules: summarize_rules(costs, rules),
+        folders: summarize_folders(costs, rules, accounts),
+    }
+}
+
+fn summarize_rules(costs: &[RuleCost], rules: &[EmailRule]) -> Vec<RuleCostSummary> {
+    let mut summaries: Vec<RuleCostSummary> = Vec::new();
+    for cost in costs {
+        let summary = match summaries.iter().position(|summary| summary.rule_id == cost.email_rule_id) {
+            Some(index) => &mut summaries[index],
+            None => {
+                let rule = rules.iter().find(|rule| rule.id.as_deref() == Some(cost.email_rule_id.as_str()));
+                summaries.push(RuleCostSummary {
+                    rule_id: cost.email_rule_id.clone(),
+                    rule_name: rule.map_or_else(|| cost.email_rule_id.clone(), |rule| rule.name.clone()),
+                    account_id: rule.map(|rule| rule.imap_account_id.clone()),
+                    folder: cost.folder.clone(),
+                    runs: 0,
+                    emails_fetched: 0,
+                    evaluations: 0,
+                    matches: 0,
+                    fetch_ms: 0,
+                    match_us: 0,
+                    avg_fetch_ms: 0.0,
+                    avg_match_us: 0.0,
+                });
+                summaries.last_mut().unwrap()
+            }
+        };
+        summary.runs += 1;
+        summary.emails_fetched += i64::from(cost.emails_fetched);
+        summary.evaluations += i64::from(cost.evaluations);
+        summary.matches += i64::from(cost.matches);
+        summary.fetch_ms += cost.fetch_ms;
+        summary.match_us += cost.match_us;
+    }
+
+    for summary in &mut summaries {
+        summary.avg_fetch_ms = average(summary.fetch_ms, summary.runs);
+        summary.avg_match_us = average(summary.match_us, summary.evaluations);
+    }
+    summaries.sort_by(|a, b| b.total_us().cmp(&a.total_us()).then_with(|| a.rule_name.cmp(&b.rule_name)));
+    summaries
+}
+
+fn summarize_folders(costs: &[RuleCost], rules: &[EmailRule], accounts: &[ImapAccount]) -> Vec<FolderCostSummary> {
+    let mut summaries: Vec<(FolderCostSummary, Vec<&str>)> = Vec::new();
+    for cost in costs {
+        let account_id = rules
+            .iter()
+            .find(|rule| rule.id.as_deref() == Some(cost.email_rule_id.as_str()))
+            .map(|rule| rule.imap_account_id.clone());
+        let (summary, rule_ids) = match summaries
+            .iter()
+            .position(|(summary, _)| summary.account_id == account_id && summary.folder == cost.folder)
+        {
+            Some(index) => &mut summaries[index],
+            None => {
+                let account_name = accounts
+                    .iter()
+                    .find(|account| account.id.is_some() && account.id == account_id)
+                    .map(|account| account.name.clone());
+                summaries.push((
+                    FolderCostSummary {
+                        account_id,
+                        account_name,
+                        folder: cost.folder.clone(),
+                        rules: 0,
+                        fetches: 0,
+                        emails_fetched: 0,
+                        fetch_ms: 0,
+                        avg_fetch_ms: 0.0,
+                    },
+                    Vec::new(),
+                ));
+                summaries.last_mut().unwrap()
+            }
+        };
+        if !rule_ids.contains(&cost.email_rule_id.as_str()) {
+            rule_ids.push(&cost.email_rule_id);
+        }
+        summary.fetches += 1;
+        summary.emails_fetched += i64::from(cost.emails_fetched);
+        summary.fetch_ms += cost.fetch_ms;
+    }
+
+    let mut folders: Vec<FolderCostSummary> = summaries
+        .into_iter()
+        .map(|(mut summary, rule_ids)| {
+            summary.rules = rule_ids.len() as i64;
+            summary.avg_fetch_ms = average(summary.fetch_ms, summary.fetches);
+            summary
+        })
+        .collect();
+    folders.sort_by(|a, b| b.fetch_ms.cmp(&a.fetch_ms).then_with(|| a.folder.cmp(&b.folder)));
+    folders
+}
+
+fn average(total: i64, count: i64) -> f64 {
+    if count == 0 { 0.0 } else { total as f64 / count as f64 }
+}
@@ -0,0 +1,119 @@
+//! Account-level sender allowlist and denylist
+//!
+//! Spam and unrelated mail should not reach any feed, whatever the rules of
+//! an account say. Each account can list sender addresses and domains to
+//! allow or deny; a domain covers its subdomains, as in sender aliases. The
+//! lists are checked before an email is matched against a rule:
+//!
+//! - A denied sender never matches, even when it is also allowed.
+//! - When the account allows any senders, only those match.
+//! - Without filters every sender goes on to the rules.
+//!
+//! Emails that are filtered out stay in the mailbox untouched, like emails
+//! no rule matches.
+
+use anyhow::{anyhow, Result};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::{AccountSenderFilter, SenderFilterKind},
+    operations_generic::AccountSenderFilterOpsGeneric,
+};
+use crate::imap::senders;
+
+/// The allowed and denied senders of an account
+#[derive(Debug, Clone, Default)]
+pub struct SenderFilters {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl SenderFilters {
+    pub fn new(filters: &[AccountSenderFilter]) -> Self {
+        let mut sender_filters = Self::default();
+        for filter in filters {
+            match SenderFilterKind::parse(&filter.kind) {
+                Some(SenderFilterKind::Allow) => sender_filters.allow.push(filter.pattern.clone()),
+                Some(SenderFilterKind::Deny) => sender_filters.deny.push(filter.pattern.clone()),
+                None => {}
+            }
+        }
+        sender_filters
+    }
+
+    /// The filters of the account `account_id`
+    pub fn load(pool: &DatabasePool, account_id: &str) -> Result<Self> {
+        Ok(Self::new(&AccountSenderFilterOpsGeneric::get_by_account_id(pool, account_id)?))
+    }
+
+    /// Whether an email from the `From` header `from` may go on to the rules
+    pub fn admits(&self, from: &str) -> bool {
+        let address = senders::address(from);
+        if self.deny.iter().any(|pattern| senders::covers(pattern, &address)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|pattern| senders::covers(pattern, &address))
+    }
+}
+
+/// The pattern of a filter as stored: a lowercase address, or a domain
+/// without a leading `@`
+pub fn normalize_pattern(pattern: &str) -> Result<String> {
+    let pattern = senders::normalize(pattern);
+    let valid = match pattern.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && is_domain(domain),
+        None => is_domain(&pattern),
+    };
+    if !valid {
+        return Err(anyhow!("pattern must be a sender address such as news@example.com or a domain such as example.com"));
+    }
+    Ok(pattern)
+}
+
+fn is_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filters(allow: &[&str], deny: &[&str]) -> SenderFilters {
+        SenderFilters {
+            allow: allow.iter().map(|pattern| pattern.to_string()).collect(),
+            deny: deny.iter().map(|pattern| pattern.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_denied_senders_are_kept_out() {
+        let filters = filters(&[], &["spam.example", "promo@shop.example"]);
+        assert!(!filters.admits("Deals <deals@mail.spam.example>"));
+        assert!(!filters.admits("PROMO@shop.example"));
+        assert!(filters.admits("orders@shop.example"));
+        assert!(SenderFilters::default().admits("anyone@example.com"));
+    }
+
+    #[test]
+    fn test_allowlist_admits_only_listed_senders() {
+        let filters = filters(&["substack.com", "news@example.com"], &["bad.substack.com"]);
+        assert!(filters.admits("Weekly <weekly@mail.substack.com>"));
+        assert!(filters.admits("news@example.com"));
+        assert!(!filters.admits("other@example.com"));
+        // Denying wins over allowing
+        assert!(!filters.admits("spam@bad.substack.com"));
+    }
+
+    #[test]
+    fn test_normalize_pattern() {
+        assert_eq!(normalize_pattern(" @Example.COM ").unwrap(), "example.com");
+        assert_eq!(normalize_pattern("News@Example.com").unwrap(), "news@example.com");
+        assert!(normalize_pattern("").is_err());
+        assert!(normalize_pattern("news@").is_err());
+        assert!(normalize_pattern("not a domain").is_err());
+        assert!(normalize_pattern("a@b@c.com").is_err());
+    }
+}
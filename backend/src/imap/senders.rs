@@ -0,0 +1,203 @@
+//! Sender aliases
+//!
+//! Newsletter platforms rotate their sending addresses (`mail1.substack.com`,
+//! `mail2.substack.com`, ...), which splits one sender across rules and
+//! statistics. An alias group names one logical sender and lists the
+//! addresses and domains it sends from; a domain also covers its subdomains.
+//!
+//! A rule's `from_address` matches an email when the sender contains it, as
+//! without aliases, or when the pattern stands for the sender's group: it is
+//! the group's name, or an address or domain the group covers. Per-sender
+//! statistics count all of a group's addresses under its name.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::SenderAlias,
+    operations_generic::{FeedItemOpsGeneric, SenderAliasOpsGeneric},
+};
+
+/// Items received from one logical sender
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SenderStats {
+    /// Alias group name, or the address for senders without one
+    pub sender: String,
+    /// Alias group the sender belongs to
+    pub alias_id: Option<String>,
+    /// Addresses items were received from, sorted
+    pub addresses: Vec<String>,
+    pub item_count: i64,
+    /// Publication date of the newest item
+    pub latest_pub_date: Option<String>,
+}
+
+struct AliasGroup {
+    id: Option<String>,
+    name: String,
+    /// Lowercase addresses and domains
+    members: Vec<String>,
+}
+
+impl AliasGroup {
+    /// Length of the longest member covering `address`, the most specific match
+    fn coverage(&self, address: &str) -> Option<usize> {
+        self.members.iter().filter(|member| covers(member, address)).map(String::len).max()
+    }
+}
+
+/// The configured alias groups
+#[derive(Default)]
+pub struct SenderAliases {
+    groups: Vec<AliasGroup>,
+}
+
+impl SenderAliases {
+    pub fn new(aliases: &[SenderAlias]) -> Self {
+        let groups = aliases
+            .iter()
+            .map(|alias| AliasGroup {
+                id: alias.id.clone(),
+                name: alias.name.clone(),
+                members: alias.address_list().iter().map(|member| normalize(member)).collect(),
+            })
+            .collect();
+        Self { groups }
+    }
+
+    pub fn load(pool: &DatabasePool) -> Result<Self> {
+        Ok(Self::new(&SenderAliasOpsGeneric::get_all(pool)?))
+    }
+
+    /// Name of the group the sender of `from` belongs to
+    pub fn canonical(&self, from: &str) -> Option<&str> {
+        self.group_of(&address(from)).map(|group| group.name.as_str())
+    }
+
+    /// Whether a rule's `from_address` pattern matches an email from `from`
+    pub fn matches_from(&self, from: &str, pattern: &str) -> bool {
+        let pattern = pattern.trim().to_lowercase();
+        if from.to_lowercase().contains(&pattern) {
+            return true;
+        }
+        let Some(group) = self.group_of(&address(from)) else {
+            return false;
+        };
+        if group.name.to_lowercase() == pattern {
+            return true;
+        }
+        // The pattern has to stand for the same group, not just be covered by it
+        let pattern_address = if pattern.contains('@') { pattern.clone() } else { format!("@{}", normalize(&pattern)) };
+        self.group_of(&pattern_address).is_some_and(|pattern_group| std::ptr::eq(pattern_group, group))
+    }
+
+    fn group_of(&self, address: &str) -> Option<&AliasGroup> {
+        self.groups
+            .iter()
+            .filter_map(|group| group.coverage(address).map(|length| (group, length)))
+            .max_by_key(|(_, length)| *length)
+            .map(|(group, _)| group)
+    }
+}
+
+/// Items per logical sender across all feeds, most items first
+pub fn stats(pool: &DatabasePool) -> Result<Vec<SenderStats>> {
+    let aliases = SenderAliases::load(pool)?;
+    let mut stats: Vec<SenderStats> = Vec::new();
+    for (from, item_count, latest_pub_date) in FeedItemOpsGeneric::counts_by_sender(pool)? {
+        let address = address(from.as_deref().unwrap_or_default());
+        if address.is_empty() {
+            continue;
+        }
+        let group = aliases.group_of(&address);
+        let sender = group.map_or_else(|| address.clone(), |group| group.name.clone());
+        let entry = match stats.iter_mut().position(|entry| entry.sender == sender) {
+            Some(index) => &mut stats[index],
+            None => {
+                stats.push(SenderStats {
+                    sender,
+                    alias_id: group.and_then(|group| group.id.clone()),
+                    addresses: Vec::new(),
+                    item_count: 0,
+                    latest_pub_date: None,
+                });
+                stats.last_mut().unwrap()
+            }
+        };
+        if !entry.addresses.contains(&address) {
+            entry.addresses.push(address);
+        }
+        entry.item_count += item_count;
+        entry.latest_pub_date = entry.latest_pub_date.take().max(latest_pub_date);
+    }
+
+    for entry in &mut stats {
+        entry.addresses.sort();
+    }
+    stats.sort_by(|a, b| b.item_count.cmp(&a.item_count).then_with(|| a.sender.cmp(&b.sender)));
+    Ok(stats)
+}
+
+/// Lowercase address of a `From` header such as `News <news@example.com>`
+pub fn address(from: &str) -> String {
+    let from = from.trim();
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    address.trim().to_lowercase()
+}
+
+/// Lowercase member without the `@` that may lead a domain
+pub fn normalize(member: &str) -> String {
+    member.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// Whether a member address or domain covers `address`; a domain covers its subdomains
+pub(crate) fn covers(member: &str, address: &str) -> bool {
+    if member.contains('@') {
+        return member == address;
+    }
+    let domain = address.rsplit('@').next().unwrap_or_default();
+    domain == member || domain.ends_with(&format!(".{}", member))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases() -> SenderAliases {
+        SenderAliases {
+            groups: vec![
+                AliasGroup { id: None, name: "Substack Weekly".to_string(), members: vec!["substack.com".to_string(), "hello@substack-mail.com".to_string()] },
+                AliasGroup { id: None, name: "Platformer".to_string(), members: vec!["platformer.substack.com".to_string()] },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_canonical_prefers_the_most_specific_member() {
+        let aliases = aliases();
+        assert_eq!(aliases.canonical("News <news@mail1.substack.com>"), Some("Substack Weekly"));
+        assert_eq!(aliases.canonical("HELLO@substack-mail.com"), Some("Substack Weekly"));
+        assert_eq!(aliases.canonical("casey@platformer.substack.com"), Some("Platformer"));
+        assert_eq!(aliases.canonical("other@substack-mail.com"), None);
+        assert_eq!(aliases.canonical("someone@notsubstack.com"), None);
+    }
+
+    #[test]
+    fn test_rule_patterns_match_every_member() {
+        let aliases = aliases();
+        assert!(aliases.matches_from("news@mail2.substack.com", "mail1.substack.com"));
+        assert!(aliases.matches_from("hello@substack-mail.com", "substack weekly"));
+        assert!(aliases.matches_from("news@mail2.substack.com", "hello@substack-mail.com"));
+        assert!(!aliases.matches_from("news@example.com", "mail1.substack.com"));
+        assert!(!aliases.matches_from("news@mail2.substack.com", "example.com"));
+        assert!(!aliases.matches_from("news@mail2.substack.com", "platformer.substack.com"));
+        assert!(!aliases.matches_from("news@mail2.substack.com", "Platformer"));
+        // Substring patterns keep working without aliases
+        assert!(SenderAliases::default().matches_from("News <news@example.com>", "EXAMPLE"));
+    }
+}
@@ -0,0 +1,65 @@
+//! TLS server name of IMAP accounts
+//!
+//! The TLS handshake sends the account's host as the server name (SNI) and
+//! checks the certificate against it. Behind a proxy such as HAProxy fronting
+//! Dovecot, or with split-horizon DNS, the host connected to may not be the
+//! name on the certificate; an account's `tls_server_name` is then used for
+//! SNI and verification instead, while connections still go to the host.
+//! With a pinned certificate the name is sent but not verified.
+
+use anyhow::{bail, Result};
+
+use crate::db::models::ImapAccount;
+
+/// Longest DNS name, in bytes
+const MAX_NAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+/// Name the handshake with an account's server sends and verifies
+pub fn for_account(account: &ImapAccount) -> &str {
+    account.tls_server_name.as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&account.host)
+}
+
+/// Check that `name` is a DNS name that can be sent as SNI
+pub fn validate(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        bail!("tls_server_name must be a host name of 1 to {} characters", MAX_NAME_LEN);
+    }
+    for label in name.split('.') {
+        let valid = !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-');
+        if !valid {
+            bail!("tls_server_name '{}' is not a valid host name", name);
+        }
+    }
+    if name.parse::<std::net::IpAddr>().is_ok() {
+        bail!("tls_server_name must be a host name, not an IP address");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_names_are_accepted() {
+        for name in ["imap.example.com", "dovecot-1.internal", "localhost", "xn--bcher-kva.example"] {
+            assert!(validate(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_other_names_are_rejected() {
+        let long = format!("{}.example.com", "a".repeat(64));
+        for name in ["", "imap.example.com:993", "https://imap.example.com", "-imap.example.com", "imap..example.com", "10.0.0.5", "mail server", &long] {
+            assert!(validate(name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! IMAP sessions and the connections they run over
+//!
+//! Every connection is a TCP stream with the command timeout applied to it
+//! (see [`cancel`](super::cancel)), metered and throttled by the account's
+//! [`TransferMeter`](super::throttle::TransferMeter), and wrapped in TLS for
+//! accounts that use it, whether by STARTTLS or implicitly.
+//!
+//! async-imap hands the responses of LIST and FETCH over as streams, which
+//! must be read to the end before the next command, and it does not report
+//! a NO or BAD answer to SEARCH, STORE or EXPUNGE. The helpers here read
+//! those commands to the end, failing the ones the server refuses.
+
+use std::collections::HashSet;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use anyhow::Result;
+use async_imap::imap_proto::{MailboxDatum, Response, Status};
+use async_imap::types::{Fetch, Name};
+use futures::TryStreamExt;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_native_tls::TlsStream;
+
+use super::cancel::TimedStream;
+use super::throttle::ThrottledStream;
+
+/// An open TCP connection, before any TLS
+pub type Transport = ThrottledStream<TimedStream<TcpStream>>;
+
+/// A logged in IMAP session
+pub type Session = async_imap::Session<ImapStream>;
+
+#[derive(Debug)]
+pub enum ImapStream {
+    Plain(Transport),
+    Tls(Box<TlsStream<Transport>>),
+}
+
+impl AsyncRead for ImapStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ImapStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            ImapStream::Tls(stream) => Pin::new(stream.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ImapStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ImapStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            ImapStream::Tls(stream) => Pin::new(stream.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ImapStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            ImapStream::Tls(stream) => Pin::new(stream.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ImapStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            ImapStream::Tls(stream) => Pin::new(stream.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+/// The folders `LIST` returns
+pub async fn list(session: &mut Session, reference: Option<&str>, pattern: Option<&str>) -> Result<Vec<Name>> {
+    Ok(session.list(reference, pattern).await?.try_collect().await?)
+}
+
+/// `FETCH` of messages by sequence number
+pub async fn fetch(session: &mut Session, sequence_set: &str, items: &str) -> Result<Vec<Fetch>> {
+    Ok(session.fetch(sequence_set, items).await?.try_collect().await?)
+}
+
+/// `UID FETCH` of messages. A refused fetch returns no messages rather
+/// than failing.
+pub async fn uid_fetch(session: &mut Session, uid_set: &str, items: &str) -> Result<Vec<Fetch>> {
+    Ok(session.uid_fetch(uid_set, items).await?.try_collect().await?)
+}
+
+/// UIDs `UID SEARCH` finds for `query`
+pub async fn uid_search(session: &mut Session, query: &str) -> Result<HashSet<u32>> {
+    let mut uids = HashSet::new();
+    run_checked(session, &format!("UID SEARCH {}", query), |response| {
+        if let Response::MailboxData(MailboxDatum::Search(found)) = response {
+            uids.extend(found.iter().copied());
+        }
+    }).await?;
+    Ok(uids)
+}
+
+/// `UID STORE` of flags
+pub async fn uid_store(session: &mut Session, uid_set: &str, query: &str) -> Result<()> {
+    run_checked(session, &format!("UID STORE {} {}", uid_set, query), |_| {}).await
+}
+
+/// Expunge the messages flagged as deleted in the selected folder: those of
+/// `uid_set` with `UID EXPUNGE`, or all of them
+pub async fn expunge(session: &mut Session, uid_set: Option<&str>) -> Result<()> {
+    let command = match uid_set {
+        Some(uid_set) => format!("UID EXPUNGE {}", uid_set),
+        None => "EXPUNGE".to_string(),
+    };
+    run_checked(session, &command, |_| {}).await
+}
+
+/// Run `command` and pass its responses to `on_response` up to the tagged
+/// one, failing when that is not OK
+async fn run_checked(session: &mut Session, command: &str, mut on_response: impl FnMut(&Response<'_>)) -> Result<()> {
+    let tag = session.run_command(command).await?;
+    while let Some(response) = session.read_response().await? {
+        match response.parsed() {
+            Response::Done { tag: done, status, outcome } if *done == tag => {
+                return match status {
+                    Status::Ok => Ok(()),
+                    _ => Err(anyhow::anyhow!("{:?} {}", status, outcome.information.as_deref().unwrap_or_default())),
+                };
+            }
+            other => on_response(other),
+        }
+    }
+    Err(anyhow::anyhow!("Connection closed during {}", command.split(' ').take(2).collect::<Vec<_>>().join(" ")))
+}
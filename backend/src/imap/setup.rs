@@ -0,0 +1,207 @@
+//! Guided account setup
+//!
+//! Before an account is saved, the setup wizard probes the server with the
+//! entered settings and samples its folders to suggest which ones hold
+//! newsletters. A folder's newsletter score, from 0 to 1, comes mostly from
+//! the share of its newest messages carrying mailing-list headers
+//! (`List-Id`, `List-Unsubscribe` or `Precedence: bulk`) and partly from a
+//! newsletter-like folder name. Sent, draft, trash and spam folders score 0.
+
+use async_imap::types::Capability;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::client::ImapClientError;
+use super::senders;
+
+/// Newest messages of each folder whose headers are sampled
+pub const SAMPLE_SIZE: u32 = 20;
+
+/// Senders reported per folder
+const TOP_SENDERS: usize = 3;
+
+/// Share of the score given by mailing-list headers; the rest comes from the folder name
+const HEADER_WEIGHT: f64 = 0.7;
+
+/// Folder name words hinting at newsletters, matched as word prefixes
+const NAME_HINTS: &[&str] = &[
+    "newsletter", "news", "digest", "list", "mailing", "update", "promotion", "subscription", "substack", "bulk",
+];
+
+/// Folder name words of folders that never hold newsletters worth a feed
+const SKIPPED_NAMES: &[&str] = &["sent", "draft", "drafts", "trash", "deleted", "junk", "spam", "outbox"];
+
+/// What logging in told about the server
+#[derive(Debug, Clone)]
+pub struct ServerProbe {
+    pub greeting: String,
+    pub capabilities: Vec<String>,
+}
+
+/// Message count and headers of the newest messages of a folder
+#[derive(Debug, Clone)]
+pub struct FolderSample {
+    pub folder: String,
+    pub messages: u32,
+    /// Raw header block of each sampled message
+    pub headers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FolderSuggestion {
+    pub folder: String,
+    pub messages: u32,
+    /// Likelihood from 0 to 1 that the folder holds newsletters
+    pub newsletter_score: f64,
+    /// Most frequent sender addresses among the sampled messages, most frequent first
+    pub top_senders: Vec<String>,
+}
+
+/// Score the sampled folders, most newsletter-like first
+pub fn suggest(samples: &[FolderSample]) -> Vec<FolderSuggestion> {
+    let mut suggestions: Vec<FolderSuggestion> = samples
+        .iter()
+        .map(|sample| FolderSuggestion {
+            folder: sample.folder.clone(),
+            messages: sample.messages,
+            newsletter_score: newsletter_score(&sample.folder, &sample.headers),
+            top_senders: top_senders(&sample.headers),
+        })
+        .collect();
+    suggestions.sort_by(|a, b| {
+        b.newsletter_score.total_cmp(&a.newsletter_score).then_with(|| a.folder.cmp(&b.folder))
+    });
+    suggestions
+}
+
+/// Names of the capabilities a server announced, `IMAP4rev1` first and the
+/// rest sorted
+pub fn capability_names<'a>(capabilities: impl IntoIterator<Item = &'a Capability>) -> Vec<String> {
+    let mut names: Vec<String> = capabilities.into_iter()
+        .map(|capability| match capability {
+            Capability::Imap4rev1 => "IMAP4rev1".to_string(),
+            Capability::Auth(mechanism) => format!("AUTH={}", mechanism),
+            Capability::Atom(name) => name.clone(),
+        })
+        .collect();
+    names.sort_by_key(|name| (name != "IMAP4rev1", name.clone()));
+    names
+}
+
+/// Step a connection attempt failed at: `connect`, `tls`, `login` or `protocol`
+pub fn failed_step(error: &anyhow::Error) -> &'static str {
+    match error.downcast_ref::<ImapClientError>() {
+        Some(ImapClientError::ConnectionFailed { .. }) => "connect",
+        Some(ImapClientError::TlsHandshakeFailed { .. } | ImapClientError::CertificatePinMismatch { .. }) => "tls",
+        Some(ImapClientError::AuthenticationFailed { .. }) => "login",
+        _ => "protocol",
+    }
+}
+
+fn newsletter_score(folder: &str, headers: &[String]) -> f64 {
+    let words: Vec<String> = folder
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.iter().any(|word| SKIPPED_NAMES.contains(&word.as_str())) {
+        return 0.0;
+    }
+
+    let name_hint = words.iter().any(|word| NAME_HINTS.iter().any(|hint| word.starts_with(hint)));
+    let list_share = if headers.is_empty() {
+        0.0
+    } else {
+        headers.iter().filter(|block| is_list_message(block)).count() as f64 / headers.len() as f64
+    };
+    let score = HEADER_WEIGHT * list_share + if name_hint { 1.0 - HEADER_WEIGHT } else { 0.0 };
+    (score * 100.0).round() / 100.0
+}
+
+/// Whether a header block belongs to mailing-list or bulk mail
+fn is_list_message(block: &str) -> bool {
+    header_values(block, "list-id").next().is_some()
+        || header_values(block, "list-unsubscribe").next().is_some()
+        || header_values(block, "precedence").any(|value| {
+            let value = value.to_ascii_lowercase();
+            value == "bulk" || value == "list"
+        })
+}
+
+fn top_senders(headers: &[String]) -> Vec<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for from in headers.iter().filter_map(|block| header_values(block, "from").next()) {
+        let address = senders::address(&from);
+        if address.is_empty() {
+            continue;
+        }
+        match counts.iter_mut().find(|(seen, _)| *seen == address) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((address, 1)),
+        }
+    }
+    // Stable, so equally frequent senders keep the order they were first seen in
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts.into_iter().take(TOP_SENDERS).map(|(address, _)| address).collect()
+}
+
+/// Unfolded values of the header `name` (lowercase) in a header block
+fn header_values<'a>(block: &'a str, name: &'a str) -> impl Iterator<Item = String> + 'a {
+    let mut fields: Vec<String> = Vec::new();
+    for line in block.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some(field) = fields.last_mut() {
+                field.push(' ');
+                field.push_str(line.trim());
+            }
+        } else if !line.trim().is_empty() {
+            fields.push(line.trim_end().to_string());
+        }
+    }
+    fields.into_iter().filter_map(move |field| {
+        let (field_name, value) = field.split_once(':')?;
+        field_name.trim().eq_ignore_ascii_case(name).then(|| value.trim().to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(folder: &str, headers: &[&str]) -> FolderSample {
+        FolderSample {
+            folder: folder.to_string(),
+            messages: headers.len() as u32,
+            headers: headers.iter().map(|block| block.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_folders_with_list_mail_rank_first() {
+        let newsletter = "From: Weekly <news@mail1.substack.com>\r\nList-Unsubscribe:\r\n <https://example.com/unsub>\r\n";
+        let bulk = "From: shop@example.com\r\nPrecedence: BULK\r\n";
+        let personal = "From: Alice <alice@example.com>\r\nSubject: Lunch?\r\n";
+        let suggestions = suggest(&[
+            sample("INBOX", &[personal, newsletter, personal, personal, personal]),
+            sample("Newsletters", &[newsletter, newsletter, bulk, personal, personal]),
+            sample("Archive/Lists", &[]),
+            sample("[Gmail]/Spam", &[bulk, bulk]),
+        ]);
+
+        let scores: Vec<(&str, f64)> = suggestions.iter().map(|s| (s.folder.as_str(), s.newsletter_score)).collect();
+        assert_eq!(scores, vec![("Newsletters", 0.72), ("Archive/Lists", 0.3), ("INBOX", 0.14), ("[Gmail]/Spam", 0.0)]);
+        assert_eq!(suggestions[0].top_senders, vec!["news@mail1.substack.com", "alice@example.com", "shop@example.com"]);
+    }
+
+    #[test]
+    fn test_capability_names() {
+        let capabilities = [
+            Capability::Atom("MOVE".to_string()),
+            Capability::Auth("PLAIN".to_string()),
+            Capability::Imap4rev1,
+            Capability::Atom("IDLE".to_string()),
+        ];
+        assert_eq!(capability_names(&capabilities), vec!["IMAP4rev1", "AUTH=PLAIN", "IDLE", "MOVE"]);
+        assert!(capability_names(&[]).is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+//! Bandwidth throttling for IMAP connections
+//!
+//! Every connection an `ImapClient` opens is wrapped in a [`ThrottledStream`]
+//! sharing the client's [`TransferMeter`]. The meter counts bytes in both
+//! directions and, when the account has a bandwidth limit, enforces it with a
+//! token bucket so the limit holds across the several connections made during
+//! a single processing run.
+
+use serde::Serialize;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::Sleep;
+
+/// Bytes transferred over IMAP, as reported on processing runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TransferStats {
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+/// Shared byte counters and optional rate limit for one account's connections
+#[derive(Debug, Clone, Default)]
+pub struct TransferMeter {
+    inner: Arc<MeterState>,
+}
+
+#[derive(Debug, Default)]
+struct MeterState {
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    limiter: Option<Mutex<TokenBucket>>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    bytes_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TransferMeter {
+    /// A meter limited to `max_bytes_per_second`; zero or negative means unlimited
+    pub fn new(max_bytes_per_second: Option<i32>) -> Self {
+        let limiter = max_bytes_per_second
+            .filter(|&limit| limit > 0)
+            .map(|limit| Mutex::new(TokenBucket {
+                bytes_per_second: limit as f64,
+                tokens: limit as f64,
+                last_refill: Instant::now(),
+            }));
+
+        Self {
+            inner: Arc::new(MeterState {
+                limiter,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn stats(&self) -> TransferStats {
+        TransferStats {
+            bytes_received: self.inner.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.inner.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Largest single read or write allowed, so one call never exceeds a second's budget
+    fn max_chunk(&self, requested: usize) -> usize {
+        match &self.inner.limiter {
+            Some(limiter) => {
+                let bucket = limiter.lock().unwrap_or_else(|e| e.into_inner());
+                requested.min(bucket.bytes_per_second as usize).max(1)
+            }
+            None => requested,
+        }
+    }
+
+    fn record_received(&self, bytes: usize) -> Option<Duration> {
+        self.inner.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.throttle(bytes)
+    }
+
+    fn record_sent(&self, bytes: usize) -> Option<Duration> {
+        self.inner.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.throttle(bytes)
+    }
+
+    /// Consume tokens for `bytes`, returning how long to wait until the
+    /// bucket is back in credit
+    fn throttle(&self, bytes: usize) -> Option<Duration> {
+        let limiter = self.inner.limiter.as_ref()?;
+        let mut bucket = limiter.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.last_refill).as_secs_f64() * bucket.bytes_per_second;
+        bucket.tokens = (bucket.tokens + refill).min(bucket.bytes_per_second) - bytes as f64;
+        bucket.last_refill = now;
+
+        (bucket.tokens < 0.0).then(|| Duration::from_secs_f64(-bucket.tokens / bucket.bytes_per_second))
+    }
+}
+
+/// A stream that reports its traffic to a [`TransferMeter`] and is slowed
+/// down to the meter's rate limit
+#[derive(Debug)]
+pub struct ThrottledStream<S> {
+    inner: S,
+    meter: TransferMeter,
+    /// Pause owed to the rate limit, served before the next read or write
+    pause: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> ThrottledStream<S> {
+    pub fn new(inner: S, meter: TransferMeter) -> Self {
+        Self { inner, meter, pause: None }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Wait out the pause owed, if any
+    fn poll_pause(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(pause) = &mut self.pause {
+            if pause.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            self.pause = None;
+        }
+        Poll::Ready(())
+    }
+
+    fn owe(&mut self, wait: Option<Duration>) {
+        self.pause = wait.map(|wait| Box::pin(tokio::time::sleep(wait)));
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ThrottledStream<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.poll_pause(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let unfilled = buf.initialize_unfilled();
+        let len = this.meter.max_chunk(unfilled.len()).min(unfilled.len());
+        let mut limited = ReadBuf::new(&mut unfilled[..len]);
+        if Pin::new(&mut this.inner).poll_read(cx, &mut limited)?.is_pending() {
+            return Poll::Pending;
+        }
+        let read = limited.filled().len();
+        buf.advance(read);
+        let wait = this.meter.record_received(read);
+        this.owe(wait);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ThrottledStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.poll_pause(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let len = this.meter.max_chunk(buf.len()).min(buf.len());
+        let written = match Pin::new(&mut this.inner).poll_write(cx, &buf[..len])? {
+            Poll::Ready(written) => written,
+            Poll::Pending => return Poll::Pending,
+        };
+        let wait = this.meter.record_sent(written);
+        this.owe(wait);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_counts_bytes_in_both_directions() {
+        let meter = TransferMeter::new(None);
+        let mut stream = ThrottledStream::new(Cursor::new(b"* OK ready\r\n".to_vec()), meter.clone());
+
+        let mut buf = [0u8; 64];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 12);
+        stream.write_all(b"a1 NOOP\r\n").await.unwrap();
+
+        assert_eq!(meter.stats(), TransferStats { bytes_received: 12, bytes_sent: 9 });
+    }
+
+    #[tokio::test]
+    async fn test_meters_share_counts_across_connections() {
+        let meter = TransferMeter::new(None);
+        for _ in 0..3 {
+            let mut stream = ThrottledStream::new(Cursor::new(vec![0u8; 100]), meter.clone());
+            tokio::io::copy(&mut stream, &mut tokio::io::sink()).await.unwrap();
+        }
+        assert_eq!(meter.stats().bytes_received, 300);
+    }
+
+    #[tokio::test]
+    async fn test_limits_read_rate() {
+        let meter = TransferMeter::new(Some(1000));
+        let mut stream = ThrottledStream::new(Cursor::new(vec![0u8; 2500]), meter.clone());
+
+        let started = Instant::now();
+        tokio::io::copy(&mut stream, &mut tokio::io::sink()).await.unwrap();
+
+        // The first second's worth is available immediately, the rest is paced
+        assert!(started.elapsed() >= Duration::from_millis(1400));
+        assert_eq!(meter.stats().bytes_received, 2500);
+    }
+
+    #[test]
+    fn test_non_positive_limit_is_unlimited() {
+        let meter = TransferMeter::new(Some(0));
+        assert_eq!(meter.max_chunk(8192), 8192);
+    }
+}
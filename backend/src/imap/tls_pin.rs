@@ -0,0 +1,204 @@
+//! TLS certificate pinning for IMAP accounts
+//!
+//! An account may pin the SHA-256 fingerprint of the server's certificate
+//! (`cert-sha256:<hex>`) or of its public key (`pubkey-sha256:<hex>`, which
+//! survives renewals that keep the key). A pinned connection only proceeds
+//! when the presented certificate matches; the pin then replaces CA and
+//! hostname checks, so self-signed servers can be trusted on first use.
+//!
+//! Hex digits may be upper or lower case and separated by colons, as most
+//! tools print them. A bare fingerprint is taken as a certificate pin.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+const CERT_PREFIX: &str = "cert-sha256:";
+const PUBKEY_PREFIX: &str = "pubkey-sha256:";
+
+/// A parsed pin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsPin {
+    Certificate([u8; 32]),
+    PublicKey([u8; 32]),
+}
+
+impl TlsPin {
+    /// Parse a pin as stored on an account
+    pub fn parse(pin: &str) -> Result<Self> {
+        let pin = pin.trim();
+        let lower = pin.to_ascii_lowercase();
+        let (certificate, hex) = if let Some(hex) = lower.strip_prefix(CERT_PREFIX) {
+            (true, hex)
+        } else if let Some(hex) = lower.strip_prefix(PUBKEY_PREFIX) {
+            (false, hex)
+        } else {
+            (true, lower.as_str())
+        };
+
+        let digest = parse_hex(hex).ok_or_else(|| anyhow::anyhow!(
+            "Invalid TLS pin '{}'; use {}<sha256 hex> or {}<sha256 hex>", pin, CERT_PREFIX, PUBKEY_PREFIX
+        ))?;
+        Ok(if certificate { TlsPin::Certificate(digest) } else { TlsPin::PublicKey(digest) })
+    }
+
+    /// Whether the fingerprints of a presented certificate satisfy the pin
+    pub fn matches(&self, observed: &PeerFingerprints) -> bool {
+        match self {
+            TlsPin::Certificate(digest) => observed.certificate == *digest,
+            TlsPin::PublicKey(digest) => observed.public_key == *digest,
+        }
+    }
+}
+
+impl std::fmt::Display for TlsPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TlsPin::Certificate(digest) => write!(f, "{}{}", CERT_PREFIX, format_hex(digest)),
+            TlsPin::PublicKey(digest) => write!(f, "{}{}", PUBKEY_PREFIX, format_hex(digest)),
+        }
+    }
+}
+
+/// SHA-256 fingerprints of a server certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerFingerprints {
+    pub certificate: [u8; 32],
+    pub public_key: [u8; 32],
+}
+
+impl PeerFingerprints {
+    /// Fingerprints of a DER encoded X.509 certificate
+    pub fn from_der(certificate: &[u8]) -> Result<Self> {
+        let public_key = subject_public_key_info(certificate)
+            .ok_or_else(|| anyhow::anyhow!("Server certificate is not a valid X.509 certificate"))?;
+        Ok(Self {
+            certificate: Sha256::digest(certificate).into(),
+            public_key: Sha256::digest(public_key).into(),
+        })
+    }
+
+    /// The fingerprints in their pin form, certificate first
+    pub fn pins(&self) -> (TlsPin, TlsPin) {
+        (TlsPin::Certificate(self.certificate), TlsPin::PublicKey(self.public_key))
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<[u8; 32]> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| *b != b':').collect();
+    if digits.len() != 64 {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(digits.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
+
+fn format_hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+/// The DER encoded subjectPublicKeyInfo of a certificate
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let (_, certificate, _) = read_element(certificate, 0x30)?;
+    let (_, mut tbs, _) = read_element(certificate, 0x30)?;
+
+    // Optional explicit [0] version
+    if tbs.first() == Some(&0xa0) {
+        tbs = read_element(tbs, 0xa0)?.2;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for tag in [0x02, 0x30, 0x30, 0x30, 0x30] {
+        tbs = read_element(tbs, tag)?.2;
+    }
+    let (element, _, _) = read_element(tbs, 0x30)?;
+    Some(element)
+}
+
+/// Split the DER element with `tag` off the front of `input`, returning the
+/// whole element, its contents and the rest of the input
+fn read_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8], &[u8])> {
+    if *input.first()? != tag {
+        return None;
+    }
+    let first = *input.get(1)?;
+    let (length, header) = if first & 0x80 == 0 {
+        (first as usize, 2)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let length = input.get(2..2 + count)?
+            .iter()
+            .fold(0usize, |length, b| (length << 8) | *b as usize);
+        (length, 2 + count)
+    };
+    let end = header.checked_add(length)?;
+    let element = input.get(..end)?;
+    Some((element, &element[header..], &input[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DER element with a short or long form length
+    fn element(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut der = vec![tag];
+        if contents.len() < 0x80 {
+            der.push(contents.len() as u8);
+        } else {
+            der.extend([0x82, (contents.len() >> 8) as u8, contents.len() as u8]);
+        }
+        der.extend_from_slice(contents);
+        der
+    }
+
+    fn certificate(spki: &[u8]) -> Vec<u8> {
+        let name = element(0x30, &element(0x31, b"name"));
+        let tbs = [
+            element(0xa0, &element(0x02, &[2])),
+            element(0x02, &[0x01, 0x23]),
+            element(0x30, &element(0x06, &[0x2a])),
+            name.clone(),
+            element(0x30, &[0u8; 200]),
+            name,
+            spki.to_vec(),
+            element(0xa3, &[0u8; 10]),
+        ].concat();
+        element(0x30, &[element(0x30, &tbs), element(0x30, &element(0x06, &[0x2a])), element(0x03, &[0, 1, 2])].concat())
+    }
+
+    #[test]
+    fn test_extracts_public_key_info() {
+        let spki = element(0x30, &[element(0x30, &element(0x06, &[0x2a])), element(0x03, &[0u8; 300])].concat());
+        let der = certificate(&spki);
+        assert_eq!(subject_public_key_info(&der), Some(spki.as_slice()));
+
+        let fingerprints = PeerFingerprints::from_der(&der).unwrap();
+        assert_eq!(fingerprints.public_key, <[u8; 32]>::from(Sha256::digest(&spki)));
+        assert_eq!(fingerprints.certificate, <[u8; 32]>::from(Sha256::digest(&der)));
+
+        assert!(PeerFingerprints::from_der(&der[..der.len() - 5]).is_err());
+        assert!(PeerFingerprints::from_der(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_parse_and_match_pins() {
+        let observed = PeerFingerprints { certificate: [0xab; 32], public_key: [0x01; 32] };
+        let (certificate_pin, public_key_pin) = observed.pins();
+        assert!(certificate_pin.to_string().starts_with("cert-sha256:AB:AB:"));
+
+        for pin in [certificate_pin.to_string(), public_key_pin.to_string(), "ab".repeat(32), format!("PUBKEY-SHA256:{}", "01".repeat(32))] {
+            assert!(TlsPin::parse(&pin).unwrap().matches(&observed), "{}", pin);
+        }
+        assert!(!TlsPin::parse(&format!("pubkey-sha256:{}", "ab".repeat(32))).unwrap().matches(&observed));
+
+        for invalid in ["", "sha1:abcd", &"ab".repeat(31), &"zz".repeat(32)] {
+            assert!(TlsPin::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
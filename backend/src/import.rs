@@ -0,0 +1,188 @@
+//! Migrating from other mail-to-RSS tools
+//!
+//! `POST /api/import` reads the newsletters another tool turns into feeds and
+//! creates a rule and a feed for each on one IMAP account. The exports it
+//! reads come in three formats:
+//!
+//! - `kill_the_newsletter`: the feeds of a Kill the Newsletter! instance, as
+//!   a JSON array (or `{"feeds": [...]}`) of objects with a `title` and the
+//!   `reference` their address and feed URL are made of, or the full `email`
+//! - `csv`: a header row naming the columns, then an `address` and a `title`
+//!   per row, optionally with `match_on`, `folder` and `feed_type`
+//! - `json`: an array of objects with the same fields as the CSV columns
+//!
+//! The CSV and JSON formats describe any tool that gives each newsletter its
+//! own address, feedmail included. Every mapping becomes a rule matching the
+//! address in the `To` header (or the `From` header with `match_on: from`),
+//! so mail keeps reaching the right feed once those addresses deliver to the
+//! account's mailbox.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Domain of the addresses of the public Kill the Newsletter! instance
+pub const KILL_THE_NEWSLETTER_DOMAIN: &str = "kill-the-newsletter.com";
+
+/// Exports `POST /api/import` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    KillTheNewsletter,
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 3] = [ImportFormat::KillTheNewsletter, ImportFormat::Csv, ImportFormat::Json];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::KillTheNewsletter => "kill_the_newsletter",
+            ImportFormat::Csv => "csv",
+            ImportFormat::Json => "json",
+        }
+    }
+
+    pub fn parse(format: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|candidate| candidate.as_str() == format.trim().to_lowercase())
+    }
+}
+
+/// Header a mapping's address is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchOn {
+    To,
+    From,
+}
+
+/// One newsletter to turn into a rule and a feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ImportMapping {
+    pub address: String,
+    /// Title of the feed and name of its rule; the address when omitted
+    #[serde(default)]
+    pub title: String,
+    /// `to` when omitted
+    pub match_on: Option<MatchOn>,
+    /// Folder the rule watches; the import's folder when omitted
+    pub folder: Option<String>,
+    /// `rss` when omitted
+    pub feed_type: Option<String>,
+}
+
+impl ImportMapping {
+    /// The mapping trimmed, or why it cannot be imported
+    fn normalized(self) -> Result<Self> {
+        let address = self.address.trim().to_string();
+        if address.is_empty() {
+            bail!("address must not be empty");
+        }
+        let title = Some(self.title.trim()).filter(|title| !title.is_empty()).unwrap_or(&address).to_string();
+        let blank_to_none = |value: Option<String>| value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        Ok(Self {
+            address,
+            title,
+            match_on: Some(self.match_on.unwrap_or(MatchOn::To)),
+            folder: blank_to_none(self.folder),
+            feed_type: blank_to_none(self.feed_type),
+        })
+    }
+}
+
+/// The mappings described by `content`, an export in `format`
+///
+/// Fails on the first mapping that cannot be read, naming it, so nothing is
+/// imported from a file that was only partly understood.
+pub fn parse(format: ImportFormat, content: &str) -> Result<Vec<ImportMapping>> {
+    let mappings = match format {
+        ImportFormat::KillTheNewsletter => kill_the_newsletter(content)?,
+        ImportFormat::Csv => csv_mappings(content)?,
+        ImportFormat::Json => serde_json::from_str::<Vec<ImportMapping>>(content).context("Invalid JSON mappings")?,
+    };
+    mappings.into_iter()
+        .enumerate()
+        .map(|(n, mapping)| mapping.normalized().with_context(|| format!("Mapping {}", n + 1)))
+        .collect()
+}
+
+/// A feed of a Kill the Newsletter! instance
+#[derive(Debug, Deserialize)]
+struct KillTheNewsletterFeed {
+    title: String,
+    reference: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum KillTheNewsletterExport {
+    Feeds(Vec<KillTheNewsletterFeed>),
+    Wrapped { feeds: Vec<KillTheNewsletterFeed> },
+}
+
+fn kill_the_newsletter(content: &str) -> Result<Vec<ImportMapping>> {
+    let feeds = match serde_json::from_str(content).context("Invalid Kill the Newsletter! export")? {
+        KillTheNewsletterExport::Feeds(feeds) | KillTheNewsletterExport::Wrapped { feeds } => feeds,
+    };
+    feeds.into_iter()
+        .enumerate()
+        .map(|(n, feed)| {
+            let address = match (feed.email, feed.reference) {
+                (Some(email), _) => email,
+                (None, Some(reference)) => format!("{}@{}", reference.trim(), KILL_THE_NEWSLETTER_DOMAIN),
+                (None, None) => return Err(anyhow!("Feed {} has neither a reference nor an email", n + 1)),
+            };
+            Ok(ImportMapping { address, title: feed.title, match_on: None, folder: None, feed_type: None })
+        })
+        .collect()
+}
+
+fn csv_mappings(content: &str) -> Result<Vec<ImportMapping>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).flexible(true).from_reader(content.as_bytes());
+    let headers = reader.headers().context("Invalid CSV header")?.clone();
+    if !headers.iter().any(|header| header == "address") {
+        bail!("The CSV header must name an 'address' column");
+    }
+    reader.deserialize::<ImportMapping>()
+        .enumerate()
+        // Line 1 is the header
+        .map(|(n, row)| row.with_context(|| format!("Invalid CSV on line {}", n + 2)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kill_the_newsletter_references_become_addresses() {
+        let export = r#"{"feeds": [
+            {"title": "Money Stuff", "reference": "a1b2c3"},
+            {"title": "Own domain", "email": "news@ktn.example.org"}
+        ]}"#;
+        let mappings = parse(ImportFormat::KillTheNewsletter, export).unwrap();
+        assert_eq!(mappings.iter().map(|m| (m.address.as_str(), m.title.as_str())).collect::<Vec<_>>(), [
+            ("a1b2c3@kill-the-newsletter.com", "Money Stuff"),
+            ("news@ktn.example.org", "Own domain"),
+        ]);
+        assert!(parse(ImportFormat::KillTheNewsletter, r#"[{"title": "Nothing"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_csv_columns_in_any_order() {
+        let csv = "title,address,match_on,folder\n\
+                   Weekly, weekly@example.com ,,\n\
+                   ,alerts@example.com,from,Alerts\n";
+        let mappings = parse(ImportFormat::Csv, csv).unwrap();
+        assert_eq!(mappings, [
+            ImportMapping { address: "weekly@example.com".to_string(), title: "Weekly".to_string(), match_on: Some(MatchOn::To), folder: None, feed_type: None },
+            ImportMapping { address: "alerts@example.com".to_string(), title: "alerts@example.com".to_string(), match_on: Some(MatchOn::From), folder: Some("Alerts".to_string()), feed_type: None },
+        ]);
+
+        let error = parse(ImportFormat::Csv, "address,title,match_on\na@example.com,A,cc\n").unwrap_err();
+        assert!(format!("{:#}", error).contains("line 2"), "{:#}", error);
+        assert!(parse(ImportFormat::Csv, "email,title\na@example.com,A\n").is_err());
+    }
+}
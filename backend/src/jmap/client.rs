@@ -0,0 +1,280 @@
+//! HTTP/JSON client for a JMAP mail server (RFC 8620 and RFC 8621)
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+use crate::db::models::ImapAccount;
+use crate::imap::client::Email;
+use crate::imap::folders::FolderStatus;
+
+const CORE_CAPABILITY: &str = "urn:ietf:params:jmap:core";
+const MAIL_CAPABILITY: &str = "urn:ietf:params:jmap:mail";
+
+/// How long one request to the server may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The parts of the JMAP session resource the client uses
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub api_url: String,
+    /// URL template with `{accountId}`, `{blobId}`, `{type}` and `{name}`
+    pub download_url: String,
+    /// ID of the account to use for each capability
+    pub primary_accounts: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mailbox {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// e.g. `inbox`, `archive` or `junk`
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub total_emails: Option<u32>,
+    #[serde(default)]
+    pub unread_emails: Option<u32>,
+}
+
+/// An email as listed by `Email/get`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EmailRef {
+    id: String,
+    blob_id: String,
+    #[serde(default)]
+    keywords: HashMap<String, bool>,
+}
+
+pub struct JmapClient {
+    http: reqwest::Client,
+    username: String,
+    password: String,
+    session: Session,
+    /// The mail account of the session
+    account_id: String,
+}
+
+impl JmapClient {
+    /// Discover the JMAP session of the account's server and the mail
+    /// account to use
+    pub async fn connect(account: &ImapAccount) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create the HTTP client")?;
+        let url = session_url(account);
+        debug!("Discovering JMAP session at {}", url);
+        let response = authorize(http.get(&url), &account.username, &account.password)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", url))?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            anyhow::bail!("Authentication failed: {} refused the credentials", account.host);
+        }
+        let session: Session = response.error_for_status()
+            .with_context(|| format!("JMAP session discovery at {} failed", url))?
+            .json()
+            .await
+            .with_context(|| format!("{} did not return a JMAP session", url))?;
+        let account_id = session.primary_accounts.get(MAIL_CAPABILITY).cloned()
+            .ok_or_else(|| anyhow::anyhow!("{} has no JMAP mail account", account.host))?;
+        info!("Connected to JMAP server {} (account {})", account.host, account_id);
+        Ok(Self { http, username: account.username.clone(), password: account.password.clone(), session, account_id })
+    }
+
+    /// Send method calls and return the responses, in order, failing on the
+    /// first method error
+    async fn call(&self, method_calls: Value) -> Result<Vec<Value>> {
+        let request = json!({ "using": [CORE_CAPABILITY, MAIL_CAPABILITY], "methodCalls": method_calls });
+        let response: Value = authorize(self.http.post(&self.session.api_url), &self.username, &self.password)
+            .json(&request)
+            .send()
+            .await
+            .context("JMAP request failed")?
+            .error_for_status()
+            .context("JMAP request failed")?
+            .json()
+            .await
+            .context("JMAP server returned an invalid response")?;
+        let responses = response["methodResponses"].as_array()
+            .ok_or_else(|| anyhow::anyhow!("JMAP response has no methodResponses"))?;
+        responses.iter()
+            .map(|invocation| match invocation[0].as_str() {
+                Some("error") => Err(anyhow::anyhow!(
+                    "JMAP method failed: {}",
+                    invocation[1]["description"].as_str().or(invocation[1]["type"].as_str()).unwrap_or("unknown error")
+                )),
+                _ => Ok(invocation[1].clone()),
+            })
+            .collect()
+    }
+
+    pub async fn mailboxes(&self) -> Result<Vec<Mailbox>> {
+        let responses = self.call(json!([
+            ["Mailbox/get", { "accountId": self.account_id, "ids": null, "properties": ["id", "name", "parentId", "role", "totalEmails", "unreadEmails"] }, "m"],
+        ])).await?;
+        serde_json::from_value(responses[0]["list"].clone()).context("JMAP server returned invalid mailboxes")
+    }
+
+    /// Paths of all mailboxes, parents and children joined with `/`, as
+    /// rules name their folder
+    pub async fn list_folders(&self) -> Result<Vec<String>> {
+        let mailboxes = self.mailboxes().await?;
+        let mut paths: Vec<String> = mailboxes.iter().map(|mailbox| mailbox_path(&mailboxes, mailbox)).collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// All mailboxes with their message and unread counts
+    pub async fn folder_statuses(&self) -> Result<Vec<FolderStatus>> {
+        let mailboxes = self.mailboxes().await?;
+        Ok(mailboxes.iter()
+            .map(|mailbox| FolderStatus {
+                name: mailbox_path(&mailboxes, mailbox),
+                delimiter: Some("/".to_string()),
+                selectable: true,
+                messages: mailbox.total_emails,
+                unseen: mailbox.unread_emails,
+            })
+            .collect())
+    }
+
+    /// The newest emails of a folder, up to `limit`, as complete messages
+    pub async fn fetch_emails(&self, folder: &str, limit: u32, unseen_only: bool) -> Result<Vec<Email>> {
+        let mailboxes = self.mailboxes().await?;
+        let mailbox = find_mailbox(&mailboxes, folder)
+            .ok_or_else(|| anyhow::anyhow!("Folder '{}' does not exist on the JMAP server", folder))?;
+        let mut filter = json!({ "inMailbox": mailbox.id });
+        if unseen_only {
+            filter["notKeyword"] = json!("$seen");
+        }
+        let responses = self.call(json!([
+            ["Email/query", {
+                "accountId": self.account_id,
+                "filter": filter,
+                "sort": [{ "property": "receivedAt", "isAscending": false }],
+                "limit": limit,
+            }, "q"],
+            ["Email/get", {
+                "accountId": self.account_id,
+                "#ids": { "resultOf": "q", "name": "Email/query", "path": "/ids" },
+                "properties": ["id", "blobId", "keywords"],
+            }, "g"],
+        ])).await?;
+        let refs: Vec<EmailRef> = serde_json::from_value(responses[1]["list"].clone())
+            .context("JMAP server returned invalid emails")?;
+        debug!("Found {} emails in JMAP mailbox '{}'", refs.len(), folder);
+
+        let mut emails = Vec::with_capacity(refs.len());
+        for email_ref in refs {
+            let raw = self.download(&email_ref.blob_id).await
+                .with_context(|| format!("Failed to download email {}", email_ref.id))?;
+            let Some(mut email) = Email::from_message(&raw) else {
+                debug!("Skipping JMAP email {}: the message could not be parsed", email_ref.id);
+                continue;
+            };
+            email.is_seen = email_ref.keywords.get("$seen").copied().unwrap_or(false);
+            emails.push(email);
+        }
+        Ok(emails)
+    }
+
+    /// The raw RFC 5322 message of a blob
+    async fn download(&self, blob_id: &str) -> Result<Vec<u8>> {
+        let url = self.session.download_url
+            .replace("{accountId}", &urlencoding::encode(&self.account_id))
+            .replace("{blobId}", &urlencoding::encode(blob_id))
+            .replace("{type}", &urlencoding::encode("message/rfc822"))
+            .replace("{name}", "email.eml");
+        let bytes = authorize(self.http.get(&url), &self.username, &self.password)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// `/.well-known/jmap` on the account's host, over HTTPS unless the account
+/// turns TLS off
+pub fn session_url(account: &ImapAccount) -> String {
+    let scheme = if account.use_tls { "https" } else { "http" };
+    format!("{}://{}:{}/.well-known/jmap", scheme, account.host, account.port)
+}
+
+/// API tokens, such as Fastmail's, are sent as bearer tokens when the
+/// account has no username; otherwise username and password go as Basic auth
+fn authorize(request: reqwest::RequestBuilder, username: &str, password: &str) -> reqwest::RequestBuilder {
+    if username.trim().is_empty() {
+        request.bearer_auth(password)
+    } else {
+        request.basic_auth(username, Some(password))
+    }
+}
+
+/// The mailbox a rule's folder names, by its path; `INBOX` ignores case as in IMAP
+fn find_mailbox<'a>(mailboxes: &'a [Mailbox], folder: &str) -> Option<&'a Mailbox> {
+    mailboxes.iter().find(|mailbox| {
+        let path = mailbox_path(mailboxes, mailbox);
+        path == folder || (path == "INBOX" && folder.eq_ignore_ascii_case("INBOX"))
+    })
+}
+
+/// Names of the mailbox and its parents joined with `/`; the inbox is
+/// called `INBOX` whatever its name, as rules default to that folder
+fn mailbox_path(mailboxes: &[Mailbox], mailbox: &Mailbox) -> String {
+    let display_name = |mailbox: &'_ Mailbox| match mailbox.role.as_deref() {
+        Some("inbox") if mailbox.parent_id.is_none() => "INBOX".to_string(),
+        _ => mailbox.name.clone(),
+    };
+    let mut names = vec![display_name(mailbox)];
+    let mut parent_id = mailbox.parent_id.as_deref();
+    // Bounded, in case a server reports a cycle
+    while let Some(parent) = parent_id.and_then(|id| mailboxes.iter().find(|mailbox| mailbox.id == id)).filter(|_| names.len() < 32) {
+        names.push(display_name(parent));
+        parent_id = parent.parent_id.as_deref();
+    }
+    names.reverse();
+    names.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mailbox(id: &str, name: &str, parent_id: Option<&str>, role: Option<&str>) -> Mailbox {
+        Mailbox {
+            id: id.to_string(),
+            name: name.to_string(),
+            parent_id: parent_id.map(str::to_string),
+            role: role.map(str::to_string),
+            total_emails: None,
+            unread_emails: None,
+        }
+    }
+
+    #[test]
+    fn test_folders_are_found_by_path_and_inbox_by_role() {
+        let mailboxes = [
+            mailbox("m1", "Inbox", None, Some("inbox")),
+            mailbox("m2", "Newsletters", None, None),
+            mailbox("m3", "Tech", Some("m2"), None),
+        ];
+        assert_eq!(find_mailbox(&mailboxes, "INBOX").unwrap().id, "m1");
+        assert_eq!(find_mailbox(&mailboxes, "inbox").unwrap().id, "m1");
+        assert!(find_mailbox(&mailboxes, "Inbox").is_some());
+        assert_eq!(find_mailbox(&mailboxes, "Newsletters/Tech").unwrap().id, "m3");
+        assert!(find_mailbox(&mailboxes, "Tech").is_none());
+    }
+}
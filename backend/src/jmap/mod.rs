@@ -0,0 +1,10 @@
+//! JMAP support for accounts with the `jmap` protocol, e.g. at Fastmail
+//!
+//! The account's host and port locate the session resource at
+//! `/.well-known/jmap`; TLS is used unless the account turns it off. Emails
+//! are downloaded as complete messages and parsed like those fetched over
+//! IMAP, so rules, feeds and duplicate detection treat them the same.
+
+pub mod client;
+
+pub use client::JmapClient;
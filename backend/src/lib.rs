@@ -1,7 +1,20 @@
 #![allow(dead_code)] // Allow unused code for future phases
+#![allow(clippy::too_many_arguments)] // Model constructors mirror table columns
 
 pub mod api;
 pub mod background;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod config;
 pub mod db;
 pub mod feed;
-pub mod imap;
\ No newline at end of file
+pub mod imap;
+pub mod import;
+pub mod jmap;
+pub mod logging;
+pub mod settings;
+pub mod setup;
+pub mod smtp;
+pub mod stats;
+#[cfg(feature = "test-support")]
+pub mod testing;
\ No newline at end of file
@@ -0,0 +1,48 @@
+//! Log output
+//!
+//! Logs are human-readable text unless `LOG_FORMAT=json`, which writes one
+//! JSON object per line for log aggregators. Each JSON line carries the
+//! fields of the spans it was logged in: the `request_id` of an API request,
+//! the `job_id` of a background job, and the `account_id`, `run_id`,
+//! `rule_id` and `feed_id` of processing, so the lines of one request or run
+//! can be found together. `RUST_LOG` filters either format, defaulting to
+//! `info`.
+
+use tracing::warn;
+use tracing_subscriber::EnvFilter;
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.trim().to_lowercase().as_str() {
+            "text" | "" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Install the global logger in the format `LOG_FORMAT` names
+///
+/// Call once, after the configuration profile is applied. An unknown format
+/// falls back to text, with a warning.
+pub fn init() {
+    let requested = std::env::var("LOG_FORMAT").unwrap_or_default();
+    let format = LogFormat::parse(&requested);
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format.unwrap_or_default() {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(false).with_span_list(true).init(),
+    }
+    if format.is_none() {
+        warn!("Unknown LOG_FORMAT '{}'; use text or json. Logging as text", requested);
+    }
+}
@@ -1,28 +1,28 @@
-mod api;
-mod background;
-mod db;
-mod feed;
-mod imap;
-
 use diesel::prelude::*;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenvy::dotenv;
 use std::env;
 use std::net::SocketAddr;
-use tower_http::cors::{CorsLayer, Any};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tracing::{info, error};
-use tracing_subscriber;
-use crate::db::connection::{DatabasePool, create_pool as create_generic_pool};
+use mail2feed_backend::{api, background, config, db, logging, settings, smtp};
+use mail2feed_backend::db::connection::create_pool as create_generic_pool;
 
 pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 pub const POSTGRES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_postgres");
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let process_variables = config::process_variables();
     dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let profile = config::apply_profile(&process_variables);
+    logging::init();
     
     info!("Mail2Feed Backend Starting...");
+    match profile? {
+        Some((name, path)) => info!("Using configuration profile '{}' from {}", name, path.display()),
+        None => info!("No configuration profile selected, using the environment"),
+    }
     
     // Run database migrations based on database type
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
@@ -46,9 +46,19 @@ async fn main() -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to create database pool: {}", e))?;
     
     info!("Database pool created successfully!");
+
+    if let Err(e) = api::version::record_run(&pool) {
+        error!("Failed to record the running version: {}", e);
+    }
+    if let Err(e) = mail2feed_backend::feed::public_url::load(&pool) {
+        error!("Failed to load the public base URL setting: {}", e);
+    }
+    if let Err(e) = settings::load(&pool) {
+        error!("Failed to load the stored settings: {}", e);
+    }
     
     // Initialize and start background service
-    let background_config = background::BackgroundConfig::from_env();
+    let background_config = settings::apply_background(background::BackgroundConfig::from_env());
     let background_handle = background::initialize_background_service(pool.clone(), background_config).await?;
     
     // Start background service automatically if enabled
@@ -59,6 +69,17 @@ async fn main() -> anyhow::Result<()> {
         info!("Background service started successfully");
     }
     
+    // Receive mail for SMTP accounts when a listen address is configured
+    match smtp::SmtpConfig::from_env() {
+        Ok(Some(smtp_config)) => {
+            if let Err(e) = smtp::start(pool.clone(), smtp_config).await {
+                error!("Failed to start the SMTP listener: {:#}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("SMTP listener not started: {:#}", e),
+    }
+    
     // Get server configuration from environment
     let host = env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port = env::var("SERVER_PORT")
@@ -66,19 +87,11 @@ async fn main() -> anyhow::Result<()> {
         .parse::<u16>()
         .unwrap_or(3000);
     
-    // Configure CORS
-    let cors_origins = env::var("CORS_ALLOWED_ORIGINS")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
-    
-    let cors = if cors_origins == "*" {
-        CorsLayer::new().allow_origin(Any)
-    } else {
-        let origins: Vec<_> = cors_origins
-            .split(',')
-            .map(|s| s.trim().parse().unwrap())
-            .collect();
-        CorsLayer::new().allow_origin(origins)
-    };
+    // Configure CORS; the allowed origins are looked up per request so a
+    // changed setting applies without a restart
+    let cors = CorsLayer::new().allow_origin(AllowOrigin::predicate(|origin, _| {
+        origin.to_str().is_ok_and(settings::cors_allows)
+    }));
     
     // Build the application routes
     let app = api::create_routes(pool, background_handle.clone())
@@ -0,0 +1,288 @@
+//! Runtime settings layered over the environment
+//!
+//! The knobs in [`SettingKey`] used to be read from environment variables
+//! only, so changing one meant a restart. Each can now be stored in the
+//! `app_settings` table with `PUT /api/settings`; a stored value wins over
+//! the variable, which wins over the built-in default. Stored values are
+//! cached here so requests never wait on the database for them.
+//!
+//! Feed responses and the CORS layer read the cache on every request and
+//! pick up a change immediately. The scheduler keeps its own copy of the
+//! intervals, so [`apply_background`] folds the effective values into a
+//! [`BackgroundConfig`], both at startup and when the API hands a change on
+//! to the running service.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use utoipa::ToSchema;
+
+use crate::background::config::BackgroundConfig;
+use crate::db::{connection::DatabasePool, operations_generic::AppSettingOpsGeneric};
+
+/// A setting that can be changed at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingKey {
+    /// Seconds feed readers and proxies may cache feeds and item pages
+    FeedCacheDuration,
+    /// Most recent items served per feed
+    FeedItemLimit,
+    /// Comma-separated origins the API answers cross-origin requests from, or `*`
+    CorsAllowedOrigins,
+    /// Minutes between checks of all accounts
+    BackgroundGlobalIntervalMinutes,
+    /// Minimum minutes between two runs of the same account
+    BackgroundPerAccountIntervalMinutes,
+    /// Quiet seconds after a rule or feed edit before its folder is re-processed
+    BackgroundChangeDebounceSeconds,
+    /// Accounts processed at the same time
+    BackgroundMaxConcurrentAccounts,
+}
+
+/// Where the effective value of a setting comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingSource {
+    /// Stored with `PUT /api/settings`
+    Database,
+    /// The setting's environment variable
+    Environment,
+    /// Neither is set
+    Default,
+}
+
+/// Type of a setting's value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingKind {
+    /// A whole number; the ones naming an interval or count are at least 1
+    Integer,
+    /// Comma-separated http(s) origins, or `*`
+    OriginList,
+}
+
+impl SettingKey {
+    pub const ALL: [SettingKey; 7] = [
+        SettingKey::FeedCacheDuration,
+        SettingKey::FeedItemLimit,
+        SettingKey::CorsAllowedOrigins,
+        SettingKey::BackgroundGlobalIntervalMinutes,
+        SettingKey::BackgroundPerAccountIntervalMinutes,
+        SettingKey::BackgroundChangeDebounceSeconds,
+        SettingKey::BackgroundMaxConcurrentAccounts,
+    ];
+
+    /// Name of the setting in the API and the `app_settings` table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingKey::FeedCacheDuration => "feed_cache_duration",
+            SettingKey::FeedItemLimit => "feed_item_limit",
+            SettingKey::CorsAllowedOrigins => "cors_allowed_origins",
+            SettingKey::BackgroundGlobalIntervalMinutes => "background_global_interval_minutes",
+            SettingKey::BackgroundPerAccountIntervalMinutes => "background_per_account_interval_minutes",
+            SettingKey::BackgroundChangeDebounceSeconds => "background_change_debounce_seconds",
+            SettingKey::BackgroundMaxConcurrentAccounts => "background_max_concurrent_accounts",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|key| key.as_str() == name.trim())
+    }
+
+    /// Environment variable the setting falls back to
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            SettingKey::FeedCacheDuration => "FEED_CACHE_DURATION",
+            SettingKey::FeedItemLimit => "FEED_ITEM_LIMIT",
+            SettingKey::CorsAllowedOrigins => "CORS_ALLOWED_ORIGINS",
+            SettingKey::BackgroundGlobalIntervalMinutes => "BACKGROUND_GLOBAL_INTERVAL_MINUTES",
+            SettingKey::BackgroundPerAccountIntervalMinutes => "BACKGROUND_PER_ACCOUNT_INTERVAL_MINUTES",
+            SettingKey::BackgroundChangeDebounceSeconds => "BACKGROUND_CHANGE_DEBOUNCE_SECONDS",
+            SettingKey::BackgroundMaxConcurrentAccounts => "BACKGROUND_MAX_CONCURRENT_ACCOUNTS",
+        }
+    }
+
+    /// Value used when neither the database nor the environment sets one
+    pub fn default_value(&self) -> String {
+        let background = BackgroundConfig::default();
+        match self {
+            SettingKey::FeedCacheDuration => "300".to_string(),
+            SettingKey::FeedItemLimit => "50".to_string(),
+            SettingKey::CorsAllowedOrigins => "http://localhost:3000".to_string(),
+            SettingKey::BackgroundGlobalIntervalMinutes => background.global_interval_minutes.to_string(),
+            SettingKey::BackgroundPerAccountIntervalMinutes => background.per_account_interval_minutes.to_string(),
+            SettingKey::BackgroundChangeDebounceSeconds => background.change_debounce_seconds.to_string(),
+            SettingKey::BackgroundMaxConcurrentAccounts => background.max_concurrent_accounts.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> SettingKind {
+        match self {
+            SettingKey::CorsAllowedOrigins => SettingKind::OriginList,
+            _ => SettingKind::Integer,
+        }
+    }
+
+    /// Whether the running scheduler must be told when the setting changes
+    pub fn affects_scheduler(&self) -> bool {
+        self.as_str().starts_with("background_")
+    }
+
+    /// Smallest value an integer setting takes
+    fn minimum(&self) -> u64 {
+        match self {
+            SettingKey::FeedCacheDuration | SettingKey::BackgroundChangeDebounceSeconds => 0,
+            _ => 1,
+        }
+    }
+
+    /// `value` in the form it is stored in; an error message when it does not
+    /// fit the setting's type
+    pub fn normalize(&self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match self.kind() {
+            SettingKind::Integer => {
+                let number: u64 = value.parse()
+                    .map_err(|_| format!("{} must be a whole number, not '{}'", self.as_str(), value))?;
+                if number < self.minimum() {
+                    return Err(format!("{} must be at least {}", self.as_str(), self.minimum()));
+                }
+                Ok(number.to_string())
+            }
+            SettingKind::OriginList => {
+                if value == "*" {
+                    return Ok(value.to_string());
+                }
+                let origins: Vec<&str> = value.split(',').map(str::trim).filter(|origin| !origin.is_empty()).collect();
+                if origins.is_empty() {
+                    return Err(format!("{} needs at least one origin, or *", self.as_str()));
+                }
+                for origin in &origins {
+                    let url = reqwest::Url::parse(origin)
+                        .map_err(|e| format!("Invalid origin '{}' in {}: {}", origin, self.as_str(), e))?;
+                    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() || url.path() != "/" || origin.ends_with('/') {
+                        return Err(format!("Invalid origin '{}' in {}; use scheme://host[:port]", origin, self.as_str()));
+                    }
+                }
+                Ok(origins.join(","))
+            }
+        }
+    }
+}
+
+/// Stored values by key, cached so requests do not hit the database
+static STORED: RwLock<Option<HashMap<SettingKey, String>>> = RwLock::new(None);
+
+fn stored(key: SettingKey) -> Option<String> {
+    STORED.read().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|stored| stored.get(&key).cloned())
+}
+
+/// The effective value of `key` and where it comes from
+///
+/// An environment value that does not fit the setting is ignored, as it was
+/// before settings could be stored.
+pub fn resolve(key: SettingKey) -> (String, SettingSource) {
+    if let Some(value) = stored(key) {
+        return (value, SettingSource::Database);
+    }
+    match std::env::var(key.env_var()).ok().and_then(|value| key.normalize(&value).ok()) {
+        Some(value) => (value, SettingSource::Environment),
+        None => (key.default_value(), SettingSource::Default),
+    }
+}
+
+/// The effective value of `key`
+pub fn value(key: SettingKey) -> String {
+    resolve(key).0
+}
+
+fn integer(key: SettingKey) -> u64 {
+    value(key).parse().unwrap_or_else(|_| key.default_value().parse().unwrap_or_default())
+}
+
+/// Seconds feeds may be cached (`FEED_CACHE_DURATION`)
+pub fn feed_cache_duration() -> u64 {
+    integer(SettingKey::FeedCacheDuration)
+}
+
+/// Items served per feed (`FEED_ITEM_LIMIT`)
+pub fn feed_item_limit() -> i64 {
+    integer(SettingKey::FeedItemLimit).try_into().unwrap_or(i64::MAX)
+}
+
+/// Whether the API answers cross-origin requests from `origin`
+/// (`CORS_ALLOWED_ORIGINS`)
+pub fn cors_allows(origin: &str) -> bool {
+    let allowed = value(SettingKey::CorsAllowedOrigins);
+    allowed == "*" || allowed.split(',').any(|allowed| allowed.trim() == origin)
+}
+
+/// `config` with the effective background settings folded in
+pub fn apply_background(mut config: BackgroundConfig) -> BackgroundConfig {
+    config.global_interval_minutes = integer(SettingKey::BackgroundGlobalIntervalMinutes);
+    config.per_account_interval_minutes = integer(SettingKey::BackgroundPerAccountIntervalMinutes);
+    config.change_debounce_seconds = integer(SettingKey::BackgroundChangeDebounceSeconds);
+    config.max_concurrent_accounts = integer(SettingKey::BackgroundMaxConcurrentAccounts).try_into().unwrap_or(usize::MAX);
+    config
+}
+
+/// Cache the stored settings; called once the database is available
+pub fn load(pool: &DatabasePool) -> Result<()> {
+    let mut values = HashMap::new();
+    for key in SettingKey::ALL {
+        if let Some(setting) = AppSettingOpsGeneric::get(pool, key.as_str())? {
+            values.insert(key, setting.value);
+        }
+    }
+    *STORED.write().unwrap_or_else(|e| e.into_inner()) = Some(values);
+    Ok(())
+}
+
+/// Store `value` for `key`, already normalized, or remove the stored value
+/// when it is `None` so the environment applies again
+pub fn store(pool: &DatabasePool, key: SettingKey, value: Option<&str>) -> Result<()> {
+    let value = match value {
+        Some(value) => Some(AppSettingOpsGeneric::set(pool, key.as_str(), value)?.value),
+        None => {
+            AppSettingOpsGeneric::delete(pool, key.as_str())?;
+            None
+        }
+    };
+    let mut guard = STORED.write().unwrap_or_else(|e| e.into_inner());
+    let stored = guard.get_or_insert_with(HashMap::new);
+    match value {
+        Some(value) => stored.insert(key, value),
+        None => stored.remove(&key),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_setting_values() {
+        assert_eq!(SettingKey::FeedItemLimit.normalize(" 100 ").unwrap(), "100");
+        assert!(SettingKey::FeedItemLimit.normalize("0").is_err());
+        assert!(SettingKey::FeedItemLimit.normalize("-5").is_err());
+        assert_eq!(SettingKey::FeedCacheDuration.normalize("0").unwrap(), "0");
+        assert_eq!(
+            SettingKey::CorsAllowedOrigins.normalize("https://a.example.com, http://localhost:5173").unwrap(),
+            "https://a.example.com,http://localhost:5173"
+        );
+        assert_eq!(SettingKey::CorsAllowedOrigins.normalize(" * ").unwrap(), "*");
+        assert!(SettingKey::CorsAllowedOrigins.normalize("https://a.example.com/app").is_err());
+        assert!(SettingKey::CorsAllowedOrigins.normalize(" , ").is_err());
+    }
+
+    #[test]
+    fn test_setting_names_round_trip() {
+        for key in SettingKey::ALL {
+            assert_eq!(SettingKey::parse(key.as_str()), Some(key));
+        }
+        assert!(SettingKey::BackgroundMaxConcurrentAccounts.affects_scheduler());
+        assert!(!SettingKey::FeedItemLimit.affects_scheduler());
+    }
+}
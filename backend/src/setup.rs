@@ -0,0 +1,65 @@
+//! First-run state of an instance
+//!
+//! A fresh instance has no admin user and no accounts. The setup wizard
+//! (`GET` and `POST /api/setup`) asks for both once; after that the instance
+//! counts as initialized and the one-shot setup is refused. Instances that
+//! already had accounts before the wizard existed count as initialized too.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::AppSetting,
+    operations_generic::{AppSettingOpsGeneric, ImapAccountOpsGeneric},
+};
+use crate::feed::basic_auth;
+
+/// The administrator created by the setup wizard; the password is kept as
+/// an Argon2 hash only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub username: String,
+    pub password_hash: String,
+    pub created_at: String,
+}
+
+impl AdminUser {
+    pub fn new(username: &str, password: &str) -> Result<Self> {
+        Ok(Self {
+            username: username.trim().to_string(),
+            password_hash: basic_auth::hash_password(password)?,
+            created_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    pub fn load(pool: &DatabasePool) -> Result<Option<Self>> {
+        match AppSettingOpsGeneric::get(pool, AppSetting::ADMIN_USER)? {
+            Some(setting) => Ok(Some(serde_json::from_str(&setting.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn store(&self, pool: &DatabasePool) -> Result<()> {
+        AppSettingOpsGeneric::set(pool, AppSetting::ADMIN_USER, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Check the admin credentials asked for before they are stored
+pub fn validate_admin(username: &str, password: &str) -> Result<(), String> {
+    basic_auth::validate(username, None).map_err(|_| "admin username must not be empty or contain ':' or control characters".to_string())?;
+    if password.chars().count() < MIN_PASSWORD_LENGTH || password.len() > basic_auth::MAX_PASSWORD_LENGTH {
+        return Err(format!("admin password must be {} to {} characters long", MIN_PASSWORD_LENGTH, basic_auth::MAX_PASSWORD_LENGTH));
+    }
+    Ok(())
+}
+
+/// Shortest admin password accepted
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Whether the instance has an admin user or any account
+pub fn is_initialized(pool: &DatabasePool) -> Result<bool> {
+    Ok(AdminUser::load(pool)?.is_some() || !ImapAccountOpsGeneric::get_all(pool)?.is_empty())
+}
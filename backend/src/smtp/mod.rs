@@ -0,0 +1,134 @@
+//! SMTP and LMTP listener for newsletters delivered straight to mail2feed
+//!
+//! Accounts with the `smtp` protocol have no mailbox to poll. Mail for them
+//! is delivered to this listener instead, e.g. by an MX record pointing at
+//! the instance or by a local MTA handing mail over with LMTP. A recipient is
+//! accepted when it is the `to_address` of an active rule of such an account;
+//! the message is then matched against that account's rules like fetched
+//! mail, and stored before the delivery is acknowledged.
+//!
+//! The listener is off unless `SMTP_LISTEN_ADDR` is set. It speaks plain
+//! text only: put it behind an MTA or a TLS proxy when it faces the internet.
+
+mod session;
+
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use tokio::net::TcpListener;
+use tracing::{debug, info, warn};
+
+use crate::db::{
+    connection::DatabasePool,
+    models::ImapAccount,
+    operations_generic::{EmailRuleOpsGeneric, ImapAccountOpsGeneric},
+};
+
+/// Largest message accepted when `SMTP_MAX_MESSAGE_BYTES` is not set
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 25 * 1024 * 1024;
+
+/// Most recipients accepted for one message
+pub const MAX_RECIPIENTS: usize = 100;
+
+/// The protocol spoken by the listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Smtp,
+    /// RFC 2033: `LHLO` instead of `EHLO`, and one reply per recipient after the data
+    Lmtp,
+}
+
+impl Dialect {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "smtp" => Some(Self::Smtp),
+            "lmtp" => Some(Self::Lmtp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub listen_addr: SocketAddr,
+    pub dialect: Dialect,
+    /// Name the listener greets with
+    pub hostname: String,
+    pub max_message_bytes: usize,
+}
+
+impl SmtpConfig {
+    /// The listener configured by `SMTP_LISTEN_ADDR`, `SMTP_PROTOCOL`,
+    /// `SMTP_HOSTNAME` and `SMTP_MAX_MESSAGE_BYTES`; `None` when it is off
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(listen_addr) = std::env::var("SMTP_LISTEN_ADDR").ok().filter(|addr| !addr.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let listen_addr = listen_addr.trim().parse()
+            .with_context(|| format!("SMTP_LISTEN_ADDR '{}' is not an address and port", listen_addr))?;
+        let dialect = match std::env::var("SMTP_PROTOCOL") {
+            Ok(value) => Dialect::parse(&value)
+                .ok_or_else(|| anyhow::anyhow!("SMTP_PROTOCOL '{}' is not smtp or lmtp", value))?,
+            Err(_) => Dialect::Smtp,
+        };
+        let hostname = std::env::var("SMTP_HOSTNAME").ok()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| "mail2feed".to_string());
+        let max_message_bytes = std::env::var("SMTP_MAX_MESSAGE_BYTES").ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES);
+        Ok(Some(Self { listen_addr, dialect, hostname: hostname.trim().to_string(), max_message_bytes }))
+    }
+}
+
+/// Bind the configured address and serve deliveries in the background
+pub async fn start(pool: DatabasePool, config: SmtpConfig) -> Result<()> {
+    let listener = TcpListener::bind(config.listen_addr).await
+        .with_context(|| format!("Failed to listen for mail on {}", config.listen_addr))?;
+    info!("Receiving mail over {:?} on {}", config.dialect, config.listen_addr);
+    tokio::spawn(serve(listener, pool, config));
+    Ok(())
+}
+
+/// Accept connections on `listener`, one session each, until it fails
+pub async fn serve(listener: TcpListener, pool: DatabasePool, config: SmtpConfig) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("Mail connection from {}", peer);
+                let pool = pool.clone();
+                let config = config.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = session::run(stream, &pool, &config).await {
+                        debug!("Mail session with {} ended: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) => {
+                warn!("Failed to accept mail connection: {}", e);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+/// The SMTP accounts with an active rule for `address`
+pub fn accounts_for(pool: &DatabasePool, address: &str) -> Result<Vec<ImapAccount>> {
+    let address = address.trim();
+    let mut accounts = Vec::new();
+    for account in ImapAccountOpsGeneric::get_all(pool)? {
+        if account.account_protocol().is_polled() {
+            continue;
+        }
+        let account_id = account.id.as_deref().unwrap_or_default();
+        let receives = EmailRuleOpsGeneric::get_by_account_id(pool, account_id)?
+            .iter()
+            .filter(|rule| rule.is_active)
+            .filter_map(|rule| rule.to_address.as_deref())
+            .any(|to_address| to_address.trim().eq_ignore_ascii_case(address));
+        if receives {
+            accounts.push(account);
+        }
+    }
+    Ok(accounts)
+}
@@ -0,0 +1,298 @@
+//! One SMTP or LMTP conversation, from the greeting to `QUIT`
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+
+use super::{accounts_for, Dialect, SmtpConfig, MAX_RECIPIENTS};
+use crate::db::{connection::DatabasePool, models::ImapAccount};
+use crate::imap::client::Email;
+use crate::imap::processor::EmailProcessor;
+
+/// How long a client may stay silent before the connection is closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Longest command line accepted; RFC 5321 asks for at least 512 bytes
+const MAX_COMMAND_BYTES: usize = 4096;
+
+/// A line read from the client
+enum Line {
+    Complete(Vec<u8>),
+    /// Longer than allowed; the rest of it was skipped
+    TooLong,
+    Closed,
+}
+
+/// An accepted recipient and the accounts receiving its mail
+struct Recipient {
+    address: String,
+    accounts: Vec<ImapAccount>,
+}
+
+/// State of the mail transaction under way
+#[derive(Default)]
+struct Transaction {
+    sender: Option<String>,
+    recipients: Vec<Recipient>,
+}
+
+pub(super) async fn run(stream: TcpStream, pool: &DatabasePool, config: &SmtpConfig) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let hello_verb = match config.dialect {
+        Dialect::Smtp => "EHLO",
+        Dialect::Lmtp => "LHLO",
+    };
+    let service = match config.dialect {
+        Dialect::Smtp => "ESMTP",
+        Dialect::Lmtp => "LMTP",
+    };
+    send(&mut write, &format!("220 {} {} mail2feed ready", config.hostname, service)).await?;
+
+    let mut greeted = false;
+    let mut transaction = Transaction::default();
+    loop {
+        let line = match tokio::time::timeout(IDLE_TIMEOUT, read_line(&mut reader, MAX_COMMAND_BYTES)).await {
+            Ok(line) => line?,
+            Err(_) => {
+                send(&mut write, "421 4.4.2 Idle for too long, closing the connection").await?;
+                return Ok(());
+            }
+        };
+        let line = match line {
+            Line::Complete(line) => String::from_utf8_lossy(&line).trim_end().to_string(),
+            Line::TooLong => {
+                send(&mut write, "500 5.5.2 Line too long").await?;
+                continue;
+            }
+            Line::Closed => return Ok(()),
+        };
+        let (verb, args) = line.split_once(' ').unwrap_or((&line, ""));
+        let verb = verb.to_ascii_uppercase();
+
+        let reply = match verb.as_str() {
+            "HELO" if config.dialect == Dialect::Smtp => {
+                greeted = true;
+                transaction = Transaction::default();
+                format!("250 {}", config.hostname)
+            }
+            "EHLO" | "LHLO" if verb == hello_verb => {
+                greeted = true;
+                transaction = Transaction::default();
+                format!(
+                    "250-{}\r\n250-PIPELINING\r\n250-8BITMIME\r\n250-ENHANCEDSTATUSCODES\r\n250 SIZE {}",
+                    config.hostname, config.max_message_bytes
+                )
+            }
+            "HELO" | "EHLO" | "LHLO" => format!("500 5.5.1 Greet with {}", hello_verb),
+            "MAIL" if !greeted => format!("503 5.5.1 Send {} first", hello_verb),
+            "MAIL" if transaction.sender.is_some() => "503 5.5.1 Sender already given".to_string(),
+            "MAIL" => match path(args, "FROM:") {
+                Some((_, params)) if declared_size(params).is_some_and(|size| size > config.max_message_bytes) => {
+                    format!("552 5.3.4 Messages may be at most {} bytes", config.max_message_bytes)
+                }
+                Some((sender, _)) => {
+                    transaction.sender = Some(sender);
+                    "250 2.1.0 OK".to_string()
+                }
+                None => "501 5.5.4 Syntax: MAIL FROM:<address>".to_string(),
+            },
+            "RCPT" if transaction.sender.is_none() => "503 5.5.1 Send MAIL first".to_string(),
+            "RCPT" if transaction.recipients.len() >= MAX_RECIPIENTS => "452 4.5.3 Too many recipients".to_string(),
+            "RCPT" => match path(args, "TO:").filter(|(address, _)| !address.is_empty()) {
+                Some((address, _)) => match accounts_for(pool, &address) {
+                    Ok(accounts) if accounts.is_empty() => format!("550 5.1.1 No feed receives mail for <{}>", address),
+                    Ok(accounts) => {
+                        transaction.recipients.push(Recipient { address, accounts });
+                        "250 2.1.5 OK".to_string()
+                    }
+                    Err(e) => {
+                        warn!("Failed to look up the rules for <{}>: {}", address, e);
+                        "451 4.3.0 Could not look up the recipient, try again later".to_string()
+                    }
+                },
+                None => "501 5.5.4 Syntax: RCPT TO:<address>".to_string(),
+            },
+            "DATA" if transaction.sender.is_none() => "503 5.5.1 Send MAIL first".to_string(),
+            "DATA" if transaction.recipients.is_empty() => "554 5.5.1 No valid recipients".to_string(),
+            "DATA" => {
+                send(&mut write, "354 End data with <CR><LF>.<CR><LF>").await?;
+                let Some(message) = read_message(&mut reader, config.max_message_bytes).await? else {
+                    return Ok(());
+                };
+                let replies = match message {
+                    Some(raw) => deliver(pool, &transaction.recipients, &raw).await,
+                    None => {
+                        let reply = format!("552 5.3.4 Messages may be at most {} bytes", config.max_message_bytes);
+                        vec![reply; transaction.recipients.len()]
+                    }
+                };
+                transaction = Transaction::default();
+                match config.dialect {
+                    // One reply per recipient, in the order they were accepted
+                    Dialect::Lmtp => replies.join("\r\n"),
+                    // Failed recipients are retried with the rest; stored items are not duplicated
+                    Dialect::Smtp => replies.iter()
+                        .find(|reply| !reply.starts_with('2'))
+                        .cloned()
+                        .unwrap_or_else(|| "250 2.0.0 Message accepted".to_string()),
+                }
+            }
+            "RSET" => {
+                transaction = Transaction::default();
+                "250 2.0.0 OK".to_string()
+            }
+            "NOOP" => "250 2.0.0 OK".to_string(),
+            "VRFY" => "252 2.5.0 Cannot verify, send the message".to_string(),
+            "QUIT" => {
+                send(&mut write, "221 2.0.0 Bye").await?;
+                return Ok(());
+            }
+            _ => "502 5.5.2 Command not implemented".to_string(),
+        };
+        send(&mut write, &reply).await?;
+    }
+}
+
+/// Store the message for each account its recipients belong to, once per
+/// account, and give the reply for each recipient
+async fn deliver(pool: &DatabasePool, recipients: &[Recipient], raw: &[u8]) -> Vec<String> {
+    let Some(email) = Email::from_message(raw) else {
+        return vec!["554 5.6.0 The message could not be parsed".to_string(); recipients.len()];
+    };
+
+    let mut stored: HashMap<String, bool> = HashMap::new();
+    for account in recipients.iter().flat_map(|recipient| &recipient.accounts) {
+        let account_id = account.id.clone().unwrap_or_default();
+        if stored.contains_key(&account_id) {
+            continue;
+        }
+        // Rules match on the recipients, which a list's To header may not name
+        let mut email = email.clone();
+        let addresses = recipients.iter()
+            .filter(|recipient| recipient.accounts.iter().any(|other| other.id == account.id))
+            .map(|recipient| recipient.address.as_str());
+        for address in addresses {
+            if !email.to.to_lowercase().contains(&address.to_lowercase()) {
+                email.to = if email.to.is_empty() { address.to_string() } else { format!("{}, {}", email.to, address) };
+            }
+        }
+
+        let ok = match EmailProcessor::new(account.clone(), pool.clone()).ingest(&email).await {
+            Ok(result) if result.errors.is_empty() => {
+                info!("Delivered email '{}' to account '{}': {} new items", email.subject, account.name, result.new_feed_items_created);
+                true
+            }
+            Ok(result) => {
+                warn!("Could not deliver email '{}' to account '{}': {}", email.subject, account.name, result.errors.join("; "));
+                false
+            }
+            Err(e) => {
+                warn!("Could not deliver email '{}' to account '{}': {:#}", email.subject, account.name, e);
+                false
+            }
+        };
+        stored.insert(account_id, ok);
+    }
+
+    recipients.iter()
+        .map(|recipient| {
+            let ok = recipient.accounts.iter().all(|account| stored.get(account.id.as_deref().unwrap_or_default()) == Some(&true));
+            if ok {
+                format!("250 2.0.0 <{}> OK", recipient.address)
+            } else {
+                format!("451 4.3.0 Could not store the message for <{}>, try again later", recipient.address)
+            }
+        })
+        .collect()
+}
+
+/// The message after `DATA`, without its dot-stuffing; `Some(None)` when it
+/// is too large and `None` when the client went away
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R, max_bytes: usize) -> Result<Option<Option<Vec<u8>>>> {
+    let mut message = Vec::new();
+    let mut oversized = false;
+    loop {
+        // Room for the rest of the message and the closing ".\r\n"
+        let limit = max_bytes.saturating_sub(message.len()) + 3;
+        let line = match tokio::time::timeout(IDLE_TIMEOUT, read_line(reader, limit)).await {
+            Ok(line) => line?,
+            Err(_) => return Ok(None),
+        };
+        match line {
+            Line::Complete(line) if line == b".\r\n" || line == b".\n" => break,
+            Line::Complete(line) => {
+                let line = line.strip_prefix(b".").unwrap_or(&line);
+                if message.len() + line.len() > max_bytes {
+                    oversized = true;
+                }
+                if !oversized {
+                    message.extend_from_slice(line);
+                }
+            }
+            Line::TooLong => oversized = true,
+            Line::Closed => return Ok(None),
+        }
+        if oversized {
+            message.clear();
+        }
+    }
+    Ok(Some((!oversized).then_some(message)))
+}
+
+/// The next line including its line break, skipping past lines longer than `limit`
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, limit: usize) -> Result<Line> {
+    let mut line = Vec::new();
+    let mut too_long = false;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(Line::Closed);
+        }
+        let (used, complete) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (available.len(), false),
+        };
+        if !too_long && line.len() + used > limit {
+            too_long = true;
+            line = Vec::new();
+        }
+        if !too_long {
+            line.extend_from_slice(&available[..used]);
+        }
+        reader.consume(used);
+        if complete {
+            return Ok(if too_long { Line::TooLong } else { Line::Complete(line) });
+        }
+    }
+}
+
+/// The address of `FROM:<address>` or `TO:<address>` and the parameters after it
+fn path<'a>(args: &'a str, keyword: &str) -> Option<(String, &'a str)> {
+    let rest = args.get(..keyword.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(keyword))
+        .map(|_| args[keyword.len()..].trim_start())?;
+    let rest = rest.strip_prefix('<')?;
+    let (address, params) = rest.split_once('>')?;
+    // Source routes (`@relay:user@host`) are ignored, as RFC 5321 allows
+    let address = address.rsplit_once(':').map_or(address, |(_, address)| address);
+    Some((address.trim().to_string(), params.trim()))
+}
+
+/// The `SIZE=` parameter of `MAIL FROM`
+fn declared_size(params: &str) -> Option<usize> {
+    params.split_whitespace()
+        .find_map(|param| param.get(..5).filter(|key| key.eq_ignore_ascii_case("SIZE=")).map(|_| &param[5..]))
+        .and_then(|size| size.parse().ok())
+}
+
+async fn send<W: AsyncWrite + Unpin>(write: &mut W, reply: &str) -> Result<()> {
+    write.write_all(reply.as_bytes()).await?;
+    write.write_all(b"\r\n").await?;
+    write.flush().await?;
+    Ok(())
+}
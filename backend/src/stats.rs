@@ -0,0 +1,148 @@
+//! Usage statistics
+//!
+//! Aggregates for `GET /api/stats` over a window of recent days: items each
+//! feed got per day, the senders with the most items, the average stored
+//! email size, and how often each account's processing runs failed. Days
+//! are UTC calendar days, matching the UTC `pub_date` items are stored with.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::db::{
+    connection::DatabasePool,
+    models::ProcessingRunStatus,
+    operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric, ImapAccountOpsGeneric, ProcessingRunOpsGeneric},
+};
+
+/// Days the statistics cover when none are given
+pub const DEFAULT_DAYS: i64 = 30;
+pub const MAX_DAYS: i64 = 365;
+
+/// Senders listed in `top_senders`
+const TOP_SENDERS: i64 = 10;
+
+/// Items a feed got on one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DayCount {
+    /// UTC day, `YYYY-MM-DD`
+    pub date: String,
+    pub items: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FeedStats {
+    pub feed_id: String,
+    pub title: String,
+    /// Items stored in the feed, whenever published
+    pub stored_items: i64,
+    /// Bytes of the stored items' bodies
+    pub stored_bytes: i64,
+    /// Items published on each day of the window, oldest day first, days
+    /// without items included
+    pub items_per_day: Vec<DayCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TopSender {
+    pub sender: String,
+    pub items: i64,
+}
+
+/// Processing runs of an account started in the window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AccountStats {
+    pub account_id: String,
+    pub name: String,
+    /// Finished runs; runs still going are left out
+    pub runs: i64,
+    /// Runs that failed or were aborted by a restart
+    pub failed_runs: i64,
+    /// `failed_runs / runs`; null without runs
+    pub error_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Stats {
+    pub days: i64,
+    pub feeds: Vec<FeedStats>,
+    /// Senders with the most items published in the window, most first
+    pub top_senders: Vec<TopSender>,
+    /// Average body size of the stored items with a recorded size
+    pub average_item_bytes: Option<i64>,
+    pub accounts: Vec<AccountStats>,
+}
+
+/// Statistics over the `days` UTC days up to and including `now`'s
+pub fn collect(pool: &DatabasePool, days: i64, now: DateTime<Utc>) -> Result<Stats> {
+    let first_day = now.date_naive() - Duration::days(days - 1);
+    let since = first_day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().to_rfc3339();
+    let dates: Vec<NaiveDate> = first_day.iter_days().take(days as usize).collect();
+
+    let mut per_day: HashMap<(String, NaiveDate), i64> = HashMap::new();
+    for (feed_id, pub_date) in FeedItemOpsGeneric::pub_dates_since(pool, &since)? {
+        if let Ok(published) = DateTime::parse_from_rfc3339(&pub_date) {
+            *per_day.entry((feed_id, published.with_timezone(&Utc).date_naive())).or_default() += 1;
+        }
+    }
+    let storage = FeedItemOpsGeneric::storage_stats(pool, &since)?;
+    let feeds = FeedOpsGeneric::get_all(pool)?
+        .into_iter()
+        .filter_map(|feed| {
+            let feed_id = feed.id?;
+            let stored = storage.iter().find(|stats| stats.feed_id == feed_id);
+            let items_per_day = dates.iter()
+                .map(|date| DayCount {
+                    date: date.to_string(),
+                    items: per_day.get(&(feed_id.clone(), *date)).copied().unwrap_or(0),
+                })
+                .collect();
+            Some(FeedStats {
+                title: feed.title,
+                stored_items: stored.map_or(0, |stats| stats.item_count),
+                stored_bytes: stored.map_or(0, |stats| stats.total_body_size),
+                items_per_day,
+                feed_id,
+            })
+        })
+        .collect();
+
+    let sized_items: i64 = storage.iter().map(|stats| stats.sized_items).sum();
+    let sized_bytes: i64 = storage.iter().map(|stats| stats.total_body_size).sum();
+    let average_item_bytes = (sized_items > 0).then(|| sized_bytes / sized_items);
+
+    let top_senders = FeedItemOpsGeneric::top_senders(pool, &since, TOP_SENDERS)?
+        .into_iter()
+        .filter_map(|(sender, items)| Some(TopSender { sender: sender?, items }))
+        .collect();
+
+    let run_counts = ProcessingRunOpsGeneric::status_counts_since(pool, &since)?;
+    let accounts = ImapAccountOpsGeneric::get_all(pool)?
+        .into_iter()
+        .filter_map(|account| {
+            let account_id = account.id?;
+            let (mut runs, mut failed_runs) = (0, 0);
+            for (_, status, count) in run_counts.iter().filter(|(id, _, _)| *id == account_id) {
+                match ProcessingRunStatus::from_str(status) {
+                    ProcessingRunStatus::Running => {}
+                    ProcessingRunStatus::Failed | ProcessingRunStatus::Aborted => {
+                        runs += count;
+                        failed_runs += count;
+                    }
+                    _ => runs += count,
+                }
+            }
+            Some(AccountStats {
+                account_id,
+                name: account.name,
+                runs,
+                failed_runs,
+                error_rate: (runs > 0).then(|| failed_runs as f64 / runs as f64),
+            })
+        })
+        .collect();
+
+    Ok(Stats { days, feeds, top_senders, average_item_bytes, accounts })
+}
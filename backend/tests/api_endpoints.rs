@@ -6,6 +6,7 @@ use axum::http::{Request, Method};
 use tower::ServiceExt;
 use serde_json::{json, Value};
 use mail2feed_backend::api;
+use mail2feed_backend::db::connection::DatabasePool;
 use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
 use common::setup_test_db;
 use std::sync::Arc;
@@ -20,7 +21,7 @@ async fn app() -> axum::Router {
         service: Arc::new(RwLock::new(None)),
         controller,
     };
-    api::create_routes(pool, background_handle)
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
 }
 
 #[tokio::test]
@@ -97,6 +98,7 @@ async fn test_imap_accounts_crud() {
     let created: Value = serde_json::from_slice(&body).unwrap();
     assert_eq!(created["name"], "Test IMAP");
     assert_eq!(created["host"], "imap.test.com");
+    assert!(created["max_bytes_per_second"].is_null());
     
     let account_id = created["id"].as_str().unwrap();
     
@@ -124,7 +126,8 @@ async fn test_imap_accounts_crud() {
         "port": 143,
         "username": "updated@test.com",
         "password": "newpass",
-        "use_tls": false
+        "use_tls": false,
+        "max_bytes_per_second": 65536
     });
     
     let response = app.clone()
@@ -145,6 +148,24 @@ async fn test_imap_accounts_crud() {
     assert_eq!(updated["name"], "Updated IMAP");
     assert_eq!(updated["host"], "imap.updated.com");
     assert_eq!(updated["port"], 143);
+    assert_eq!(updated["max_bytes_per_second"], 65536);
+    
+    // Bandwidth limits must be positive
+    let mut invalid_update = update_data.clone();
+    invalid_update["max_bytes_per_second"] = json!(0);
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(&format!("/api/imap-accounts/{}", account_id))
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&invalid_update).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     
     // Delete account
     let response = app.clone()
@@ -368,8 +389,9 @@ async fn test_feeds_crud() {
     
     assert_eq!(response.status(), StatusCode::OK);
     let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-    let items: Vec<Value> = serde_json::from_slice(&body).unwrap();
-    assert_eq!(items.len(), 0);
+    let page: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 0);
+    assert_eq!((&page["total"], &page["next_cursor"]), (&serde_json::json!(0), &Value::Null));
     
     // Test RSS feed endpoint (should return empty RSS feed)
     let response = app.clone()
@@ -748,4 +770,395 @@ async fn test_configurable_cache_duration() {
     
     // Clean up environment variable
     std::env::remove_var("FEED_CACHE_DURATION");
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_duplicate_account_detection() {
+    let app = app().await;
+    
+    let create = |account: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(Method::POST)
+                        .uri("/api/imap-accounts")
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(serde_json::to_string(&account).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+    
+    let (status, original) = create(json!({
+        "name": "Work",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "me@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    
+    // Same mailbox written differently is rejected
+    let duplicate = json!({
+        "name": "Work again",
+        "host": "IMAP.test.com",
+        "port": 143,
+        "username": "Me@Test.com",
+        "password": "testpass",
+        "use_tls": false
+    });
+    let (status, conflict) = create(duplicate.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(conflict["duplicate_of"], original["id"]);
+    assert!(conflict["error"].as_str().unwrap().contains("allow_duplicate"));
+    
+    // ...unless explicitly allowed
+    let mut allowed = duplicate;
+    allowed["allow_duplicate"] = json!(true);
+    let (status, _) = create(allowed).await;
+    assert_eq!(status, StatusCode::CREATED);
+    
+    // A different user on the same server is not a duplicate
+    let (status, _) = create(json!({
+        "name": "Other",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "someone@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_feed_title_templates() {
+    let app = app().await;
+    
+    let send = |method: Method, uri: String, body: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(serde_json::to_string(&body).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+    };
+    let json_body = |body: &str| serde_json::from_str::<Value>(body).unwrap();
+    
+    let account_body = |name: &str| json!({
+        "name": name,
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    });
+    let (_, account) = send(Method::POST, "/api/imap-accounts".to_string(), account_body("Work")).await;
+    let account_id = json_body(&account)["id"].as_str().unwrap().to_string();
+    
+    let rule_body = |name: &str| json!({
+        "name": name,
+        "imap_account_id": account_id,
+        "folder": "INBOX",
+        "is_active": true
+    });
+    let (_, rule) = send(Method::POST, "/api/email-rules".to_string(), rule_body("Rust Weekly")).await;
+    let rule_id = json_body(&rule)["id"].as_str().unwrap().to_string();
+    
+    // Unknown variables are rejected
+    let feed_body = |title: &str| json!({
+        "title": title,
+        "description": "From {{account.name}}",
+        "email_rule_id": rule_id,
+        "feed_type": "rss",
+        "is_active": true
+    });
+    let (status, error) = send(Method::POST, "/api/feeds".to_string(), feed_body("{{account.password}}")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json_body(&error)["error"].as_str().unwrap().contains("account.password"));
+    
+    let (status, feed) = send(Method::POST, "/api/feeds".to_string(),
+        feed_body("Newsletters — {{rule.name}} ({{account.name}})")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let feed = json_body(&feed);
+    assert_eq!(feed["title"], "Newsletters — {{rule.name}} ({{account.name}})");
+    let feed_id = feed["id"].as_str().unwrap().to_string();
+    
+    let (_, rss) = send(Method::GET, format!("/feeds/{}/rss", feed_id), json!(null)).await;
+    assert!(rss.contains("<title>Newsletters — Rust Weekly (Work)</title>"));
+    assert!(rss.contains("<description>From Work</description>"));
+    
+    // Renames show up without editing the feed
+    send(Method::PUT, format!("/api/email-rules/{}", rule_id), rule_body("This Week in Rust")).await;
+    send(Method::PUT, format!("/api/imap-accounts/{}", account_id), account_body("Personal")).await;
+    
+    let (_, atom) = send(Method::GET, format!("/feeds/{}/atom", feed_id), json!(null)).await;
+    assert!(atom.contains("Newsletters — This Week in Rust (Personal)"));
+    assert!(atom.contains("From Personal"));
+}
+
+#[tokio::test]
+async fn test_observe_only_rules() {
+    let app = app().await;
+    
+    let send = |method: Method, uri: String, body: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(serde_json::to_string(&body).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+    
+    let (_, account) = send(Method::POST, "/api/imap-accounts".to_string(), json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    
+    let rule_body = |observe_only: bool| json!({
+        "name": "Trial",
+        "imap_account_id": account["id"],
+        "folder": "INBOX",
+        "from_address": "promo@example.com",
+        "is_active": true,
+        "observe_only": observe_only
+    });
+    let (status, rule) = send(Method::POST, "/api/email-rules".to_string(), rule_body(true)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(rule["observe_only"], true);
+    let rule_id = rule["id"].as_str().unwrap().to_string();
+    
+    let (status, stats) = send(Method::GET, format!("/api/email-rules/{}/stats", rule_id), json!(null)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["observe_only"], true);
+    assert_eq!(stats["match_count"], 0);
+    assert!(stats["last_matched_at"].is_null());
+    
+    let (status, preview) = send(Method::GET, format!("/api/email-rules/{}/preview?limit=10", rule_id), json!(null)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(preview, json!([]));
+    
+    // Enabling the rule for real
+    let (_, rule) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), rule_body(false)).await;
+    assert_eq!(rule["observe_only"], false);
+    
+    // Rules created without the flag are live
+    let mut live = rule_body(false);
+    live.as_object_mut().unwrap().remove("observe_only");
+    let (_, rule) = send(Method::POST, "/api/email-rules".to_string(), live).await;
+    assert_eq!(rule["observe_only"], false);
+    
+    let (status, _) = send(Method::GET, "/api/email-rules/missing/stats".to_string(), json!(null)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_rule_importance() {
+    let app = app().await;
+    
+    let send = |method: Method, uri: String, body: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(serde_json::to_string(&body).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+    
+    let (_, account) = send(Method::POST, "/api/imap-accounts".to_string(), json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    
+    let rule_body = |importance: Value| json!({
+        "name": "Alerts",
+        "imap_account_id": account["id"],
+        "folder": "INBOX",
+        "is_active": true,
+        "importance": importance
+    });
+    let (status, rule) = send(Method::POST, "/api/email-rules".to_string(), rule_body(json!(" High "))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(rule["importance"], "high");
+    let rule_id = rule["id"].as_str().unwrap().to_string();
+    
+    let (status, error) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), rule_body(json!("urgent"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("urgent"));
+    
+    // Clearing the importance matches emails of any importance again
+    let (_, rule) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), rule_body(json!(""))).await;
+    assert!(rule["importance"].is_null());
+}
+
+#[tokio::test]
+async fn test_rule_processing_order() {
+    let app = app().await;
+    
+    let send = |method: Method, uri: String, body: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(serde_json::to_string(&body).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+    
+    let (_, account) = send(Method::POST, "/api/imap-accounts".to_string(), json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    
+    let rule_body = |order: Value| json!({
+        "name": "Archive",
+        "imap_account_id": account["id"],
+        "folder": "INBOX",
+        "is_active": true,
+        "processing_order": order
+    });
+    let (status, rule) = send(Method::POST, "/api/email-rules".to_string(), rule_body(Value::Null)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(rule["processing_order"], "newest_first");
+    let rule_id = rule["id"].as_str().unwrap().to_string();
+    
+    let (status, rule) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), rule_body(json!("Oldest_First"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rule["processing_order"], "oldest_first");
+    
+    let (status, error) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), rule_body(json!("random"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("random"));
+}
+
+#[tokio::test]
+async fn test_rule_fetch_options() {
+    let app = app().await;
+    
+    let send = |method: Method, uri: String, body: Value| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .header("Content-Type", "application/json")
+                        .body(Body::from(serde_json::to_string(&body).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+    
+    let (_, account) = send(Method::POST, "/api/imap-accounts".to_string(), json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    
+    // Defaults: the standard limit, seen mail included, no subfolders, only
+    // tracking pixels filtered
+    let mut body = json!({
+        "name": "Lists",
+        "imap_account_id": account["id"],
+        "folder": "Lists",
+        "is_active": true
+    });
+    let (status, rule) = send(Method::POST, "/api/email-rules".to_string(), body.clone()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(rule["fetch_limit"].is_null());
+    assert_eq!(rule["include_seen"], true);
+    assert_eq!(rule["include_subfolders"], false);
+    assert_eq!(rule["strip_tracking_pixels"], true);
+    assert_eq!((rule["strip_footers"].clone(), rule["strip_quoted_replies"].clone(), rule["unwrap_tracking_links"].clone()), (json!(false), json!(false), json!(false)));
+    let rule_id = rule["id"].as_str().unwrap().to_string();
+    
+    body["fetch_limit"] = json!(250);
+    body["include_seen"] = json!(false);
+    body["include_subfolders"] = json!(true);
+    body["strip_footers"] = json!(true);
+    body["strip_tracking_pixels"] = json!(false);
+    let (status, rule) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), body.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rule["fetch_limit"], 250);
+    assert_eq!(rule["include_seen"], false);
+    assert_eq!(rule["include_subfolders"], true);
+    assert_eq!(rule["strip_footers"], true);
+    assert_eq!(rule["strip_tracking_pixels"], false);
+    
+    for limit in [0, 1001] {
+        body["fetch_limit"] = json!(limit);
+        let (status, error) = send(Method::PUT, format!("/api/email-rules/{}", rule_id), body.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"].as_str().unwrap().contains("fetch_limit"));
+    }
+}
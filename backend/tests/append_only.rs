@@ -0,0 +1,198 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::cleanup::FeedCleanupService;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::chain;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn send(pool: &DbPool, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app(pool.clone()).oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn new_item(feed_id: &str, title: &str, days_ago: i64) -> NewFeedItem {
+    NewFeedItem::new(
+        feed_id.to_string(),
+        title.to_string(),
+        None,
+        None,
+        Some("office@example.gov".to_string()),
+        Utc::now() - Duration::days(days_ago),
+        Some(format!("<{}@example.gov>", title)),
+        Some(title.to_string()),
+        Some("office@example.gov".to_string()),
+        Some(format!("Text of {}", title)),
+    )
+}
+
+/// A feed keeping one item, holding two notices; returns the feed and its rule and account IDs
+fn create_feed(pool: &DbPool) -> (Feed, String, String) {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Notices".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Official notices".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let mut new_feed = NewFeed::with_retention(
+        "Notices".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+        Some(1),
+        None,
+        Some(0),
+    );
+    new_feed.max_age_days = None;
+    let feed = FeedOps::create(&mut conn, &new_feed).unwrap();
+    let feed = FeedOps::update(&mut conn, feed.id.as_ref().unwrap(), &new_feed).unwrap();
+    for (title, days_ago) in [("Notice 1", 3), ("Notice 2", 2)] {
+        FeedItemOps::create(&mut conn, &new_item(feed.id.as_ref().unwrap(), title, days_ago)).unwrap();
+    }
+    (feed, rule.id.unwrap(), account.id.unwrap())
+}
+
+fn update_request(feed: &Feed, append_only: Option<bool>) -> Value {
+    let mut body = json!({
+        "title": feed.title,
+        "email_rule_id": feed.email_rule_id,
+        "feed_type": "rss",
+        "is_active": true,
+        "max_items": 1,
+        "min_items": 0,
+    });
+    if let Some(append_only) = append_only {
+        body["append_only"] = json!(append_only);
+    }
+    body
+}
+
+#[tokio::test]
+async fn test_append_only_feed_keeps_a_verifiable_chain() {
+    let pool = setup_test_db();
+    let (feed, rule_id, account_id) = create_feed(&pool);
+    let feed_id = feed.id.clone().unwrap();
+    let verify_uri = format!("/api/feeds/{}/verify", feed_id);
+
+    // A regular feed has no chain to check
+    let (status, verification) = send(&pool, Method::GET, &verify_uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verification["append_only"], false);
+    assert_eq!(verification["unchained_items"], 2);
+    assert_eq!(verification["valid"], true);
+
+    // Turning append-only on chains the items the feed already holds
+    let (status, updated) = send(&pool, Method::PUT, &format!("/api/feeds/{}", feed_id), Some(update_request(&feed, Some(true)))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["append_only"], true);
+    let (_, verification) = send(&pool, Method::GET, &verify_uri, None).await;
+    assert_eq!(verification["items_checked"], 2);
+    assert_eq!(verification["unchained_items"], 0);
+    assert_eq!(verification["valid"], true, "{}", verification);
+
+    // New items continue the chain
+    let database = DatabasePool::SQLite(pool.clone());
+    let head = verification["head"].as_str().unwrap().to_string();
+    let appended = database.transaction(|tx| chain::append(tx, &mut new_item(&feed_id, "Notice 3", 1))).unwrap();
+    assert_eq!(appended.chain_previous.as_deref(), Some(head.as_str()));
+    let (_, verification) = send(&pool, Method::GET, &verify_uri, None).await;
+    assert_eq!(verification["items_checked"], 3);
+    assert_eq!(verification["head"], appended.chain_hash.clone().unwrap());
+    assert_eq!(verification["valid"], true, "{}", verification);
+
+    // It cannot be turned off, and leaving it out keeps it on
+    let (status, _) = send(&pool, Method::PUT, &format!("/api/feeds/{}", feed_id), Some(update_request(&feed, Some(false)))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, updated) = send(&pool, Method::PUT, &format!("/api/feeds/{}", feed_id), Some(update_request(&feed, None))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["append_only"], true);
+
+    // Neither the feed nor what fills it can be deleted
+    for uri in [
+        format!("/api/feeds/{}", feed_id),
+        format!("/api/email-rules/{}", rule_id),
+        format!("/api/imap-accounts/{}", account_id),
+    ] {
+        let (status, body) = send(&pool, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", uri);
+        assert!(body["error"].as_str().unwrap().contains("append-only"), "{}", body);
+    }
+
+    // Retention would keep one item, but removes none
+    let result = FeedCleanupService::new(database.clone()).cleanup_all_feeds().await.unwrap();
+    assert_eq!(result.items_removed, 0);
+    let result = FeedCleanupService::new(database.clone()).tighten_all_feeds().await.unwrap();
+    assert_eq!(result.items_removed, 0);
+
+    // Reader state is not hashed
+    let (status, _) = send(&pool, Method::PATCH, &format!("/api/feed-items/{}", appended.id.clone().unwrap()),
+        Some(json!({ "is_read": true, "starred": true, "pinned": true }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, verification) = send(&pool, Method::GET, &verify_uri, None).await;
+    assert_eq!(verification["valid"], true, "{}", verification);
+
+    // Altering an item's content behind the API's back breaks the chain
+    let items = FeedItemOps::get_by_feed_id(&mut pool.get().unwrap(), &feed_id, None).unwrap();
+    let notice_2 = items.iter().find(|item| item.title == "Notice 2").unwrap();
+    FeedItemOps::promote_to_canonical(&mut pool.get().unwrap(), notice_2.id.as_ref().unwrap(), Some("Amended text".to_string()), None).unwrap();
+    let (_, verification) = send(&pool, Method::GET, &verify_uri, None).await;
+    assert_eq!(verification["valid"], false);
+    assert_eq!(verification["problems"], json!([format!("Item 'Notice 2' ({}) does not match its hash", notice_2.id.as_ref().unwrap())]));
+
+    // So does removing one
+    FeedItemOps::delete(&mut pool.get().unwrap(), notice_2.id.as_ref().unwrap()).unwrap();
+    let (_, verification) = send(&pool, Method::GET, &verify_uri, None).await;
+    assert_eq!(verification["valid"], false);
+    let problems = verification["problems"].to_string();
+    assert!(problems.contains("is missing from the chain"), "{}", problems);
+    assert!(problems.contains("1 chained items cannot be reached from the head"), "{}", problems);
+}
+
+#[tokio::test]
+async fn test_verify_unknown_feed() {
+    let pool = setup_test_db();
+    let (status, _) = send(&pool, Method::GET, "/api/feeds/missing/verify", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::{RunQueryDsl, SqliteConnection};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::{attachments, dedup};
+use mail2feed_backend::imap::mime::MailAttachment;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    FeedOps::create(conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap()
+}
+
+fn create_item(conn: &mut SqliteConnection, feed: &Feed, message_id: &str) -> FeedItem {
+    FeedItemOps::create(conn, &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Monthly report".to_string(),
+        Some("The report is attached.".to_string()),
+        None,
+        Some("reports@example.com".to_string()),
+        Utc::now(),
+        Some(message_id.to_string()),
+        Some("Monthly report".to_string()),
+        Some("reports@example.com".to_string()),
+        Some("The report is attached.".to_string()),
+    )).unwrap()
+}
+
+fn mail_attachments() -> Vec<MailAttachment> {
+    vec![
+        MailAttachment {
+            filename: Some("report.pdf".to_string()),
+            content_type: "application/pdf".to_string(),
+            content: b"%PDF-1.4 report".to_vec(),
+        },
+        MailAttachment {
+            filename: Some("raw-data.csv".to_string()),
+            content_type: "text/csv".to_string(),
+            content: vec![b'x'; 4096],
+        },
+        MailAttachment {
+            filename: Some("Übersicht.png".to_string()),
+            content_type: "image/png".to_string(),
+            content: b"\x89PNG chart".to_vec(),
+        },
+    ]
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, axum::http::HeaderMap, Vec<u8>) {
+    let response = app.clone()
+        .oneshot(Request::builder().method(Method::GET).uri(uri).header("host", "feeds.example.com").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, headers, body.to_vec())
+}
+
+#[tokio::test]
+async fn test_attachments_are_served_and_listed_as_enclosures() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let item = create_item(&mut conn, &feed, "<report@example.com>");
+    drop(conn);
+    let feed_id = feed.id.clone().unwrap();
+    let item_id = item.id.clone().unwrap();
+
+    // The CSV is over the limit and left out; the rest are numbered in order
+    let stored = attachments::store(&DatabasePool::SQLite(pool.clone()), &item_id, &mail_attachments(), 1024).unwrap();
+    assert_eq!(stored, 2);
+    let app = app(pool);
+
+    let (status, headers, body) = get(&app, &format!("/feeds/{}/items/{}/attachments/1", feed_id, item_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "application/pdf");
+    assert_eq!(headers["content-disposition"], "attachment; filename=\"report.pdf\"");
+    assert_eq!(body, b"%PDF-1.4 report");
+
+    let (status, headers, _) = get(&app, &format!("/feeds/{}/items/{}/attachments/2", feed_id, item_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "image/png");
+    assert_eq!(headers["content-disposition"], "attachment; filename=\"_bersicht.png\"; filename*=UTF-8''%C3%9Cbersicht.png");
+
+    let (status, _, _) = get(&app, &format!("/feeds/{}/items/{}/attachments/3", feed_id, item_id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) = get(&app, &format!("/feeds/other-feed/items/{}/attachments/1", item_id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let attachment_url = format!("http://feeds.example.com/feeds/{}/items/{}/attachments", feed_id, item_id);
+    let (_, _, rss) = get(&app, &format!("/feeds/{}/rss", feed_id)).await;
+    let rss = String::from_utf8(rss).unwrap();
+    assert!(rss.contains(&format!("<enclosure url=\"{}/1\" length=\"15\" type=\"application/pdf\"/>", attachment_url)), "{}", rss);
+    assert!(!rss.contains(&format!("{}/2", attachment_url)), "{}", rss);
+
+    let (_, _, atom) = get(&app, &format!("/feeds/{}/atom", feed_id)).await;
+    let atom = String::from_utf8(atom).unwrap();
+    assert!(atom.contains(&format!("href=\"{}/1\"", attachment_url)), "{}", atom);
+    assert!(atom.contains(&format!("href=\"{}/2\"", attachment_url)), "{}", atom);
+    assert_eq!(atom.matches("rel=\"enclosure\"").count(), 2, "{}", atom);
+}
+
+#[test]
+fn test_attachments_follow_promoted_copies_and_are_removed_with_items() {
+    let pool = setup_test_db();
+    let db = DatabasePool::SQLite(pool.clone());
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let canonical = create_item(&mut conn, &feed, "<report@example.com>");
+    let canonical_id = canonical.id.clone().unwrap();
+    let mut linked = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Monthly report".to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        Some("<report@example.com>".to_string()),
+        None,
+        None,
+        None,
+    );
+    linked.canonical_item_id = Some(canonical_id.clone());
+    let linked = FeedItemOps::create(&mut conn, &linked).unwrap();
+    let linked_id = linked.id.clone().unwrap();
+    drop(conn);
+
+    assert_eq!(attachments::store(&db, &canonical_id, &mail_attachments(), attachments::DEFAULT_MAX_BYTES).unwrap(), 3);
+    // The linked item shows its canonical copy's attachments
+    let enclosures = attachments::enclosures(&db, std::slice::from_ref(&linked), "").unwrap();
+    assert_eq!(enclosures[&linked_id].len(), 3);
+    assert_eq!(enclosures[&linked_id][0].url, attachments::path(&linked.feed_id, &linked_id, 1));
+
+    // Removing the canonical copy hands its attachments to the promoted one
+    dedup::remove_item(&db, &canonical).unwrap();
+    let mut conn = pool.get().unwrap();
+    assert_eq!(AttachmentOps::list_for_items(&mut conn, std::slice::from_ref(&linked_id)).unwrap().len(), 3);
+
+    // Without foreign keys enforced, as in the server, cleanup removes them
+    diesel::sql_query("PRAGMA foreign_keys = OFF").execute(&mut conn).unwrap();
+    FeedItemOps::delete(&mut conn, &linked_id).unwrap();
+    assert_eq!(AttachmentOps::delete_orphaned(&mut conn).unwrap(), 3);
+    assert_eq!(AttachmentOps::delete_orphaned(&mut conn).unwrap(), 0);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{self, BackgroundConfig, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned())))
+}
+
+#[tokio::test]
+async fn test_update_background_config() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let handle = background::initialize_background_service(pool.clone(), BackgroundConfig::default()).await.unwrap();
+    let app = api::create_routes(pool, handle);
+
+    let mut config = serde_json::to_value(BackgroundConfig::default()).unwrap();
+    config["global_interval_minutes"] = json!(5);
+    config["max_concurrent_accounts"] = json!(6);
+    config["retry"]["max_attempts"] = json!(5);
+    let (status, body) = send(&app, Method::PUT, "/api/background/config", Some(config.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["max_concurrent_accounts"], 6);
+
+    let (_, body) = send(&app, Method::GET, "/api/background/status", None).await;
+    assert_eq!(body["status"]["config"]["global_interval_minutes"], 5);
+    assert_eq!(body["status"]["config"]["retry"]["max_attempts"], 5);
+
+    // Invalid values and switching processing off are refused, keeping the
+    // configuration in effect
+    let mut invalid = config.clone();
+    invalid["max_concurrent_accounts"] = json!(0);
+    let (status, body) = send(&app, Method::PUT, "/api/background/config", Some(invalid)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.as_str().unwrap().contains("max_concurrent_accounts"), "{}", body);
+    let mut disabled = config;
+    disabled["enabled"] = json!(false);
+    let (status, body) = send(&app, Method::PUT, "/api/background/config", Some(disabled)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.as_str().unwrap().contains("enabled"), "{}", body);
+
+    let (_, body) = send(&app, Method::GET, "/api/background/status", None).await;
+    assert_eq!(body["status"]["config"]["max_concurrent_accounts"], 6);
+    assert_eq!(body["status"]["config"]["enabled"], true);
+}
+
+#[tokio::test]
+async fn test_update_config_without_service() {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    let app = api::create_routes(DatabasePool::SQLite(setup_test_db()), handle);
+
+    let config = serde_json::to_value(BackgroundConfig::default()).unwrap();
+    let (status, _) = send(&app, Method::PUT, "/api/background/config", Some(config)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+}
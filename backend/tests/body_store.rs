@@ -0,0 +1,190 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::maintenance::{BodyOffloadService, OFFLOAD_BODIES_TASK};
+use mail2feed_backend::background::tasks::{TaskRegistry, TaskState};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::blob::{BlobStore, FilesystemStore};
+use mail2feed_backend::feed::bodies;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection, title: &str, append_only: bool) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        format!("{} Account", title),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        format!("{} Rule", title),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    let mut new_feed = NewFeed::new(
+        title.to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    );
+    new_feed.append_only = append_only;
+    FeedOps::create(conn, &new_feed).unwrap()
+}
+
+fn create_item(conn: &mut SqliteConnection, feed: &Feed, title: &str) -> FeedItem {
+    let mut new_item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        Some("Summary".to_string()),
+        None,
+        Some("news@example.com".to_string()),
+        Utc::now(),
+        Some(format!("<{}@example.com>", title.replace(' ', "-"))),
+        Some(title.to_string()),
+        Some("news@example.com".to_string()),
+        Some(format!("{} in plain text", title)),
+    );
+    new_item.email_body_html = Some(format!("<p>{} in HTML</p>", title));
+    FeedItemOps::create(conn, &new_item).unwrap()
+}
+
+#[tokio::test]
+async fn test_offload_task_moves_bodies_and_removes_unused_blobs() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn, "News", false);
+    let notices = create_test_feed(&mut conn, "Notices", true);
+    let item = create_item(&mut conn, &feed, "Weekly issue");
+    let notice = create_item(&mut conn, &notices, "Official notice");
+    drop(conn);
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = BlobStore::Filesystem(FilesystemStore::new(dir.path()));
+    store.put("bodies/gone.json", b"{}".to_vec()).await.unwrap();
+    let database = DatabasePool::SQLite(pool.clone());
+
+    let tasks = TaskRegistry::new();
+    let task = tasks.start(OFFLOAD_BODIES_TASK).await;
+    let task_id = task.id().to_string();
+    BodyOffloadService::new(database.clone(), store.clone()).with_min_bytes(0).run(task).await;
+    let status = tasks.get(&task_id).await.unwrap();
+    assert_eq!(status.state, TaskState::Completed, "{:?}", status.error);
+    assert_eq!((status.total, status.updated), (1, 1));
+
+    // The body left the row, and the blob of a deleted item is gone
+    let item_id = item.id.clone().unwrap();
+    let mut stored = FeedItemOps::get_by_id(&mut pool.get().unwrap(), &item_id).unwrap();
+    assert_eq!(stored.body_ref, Some(bodies::key(&item_id)));
+    assert_eq!((stored.email_body.as_deref(), stored.email_body_html.as_deref()), (None, None));
+    assert_eq!(store.list(bodies::KEY_PREFIX).await.unwrap(), [bodies::key(&item_id)]);
+
+    // Append-only feeds keep their bodies
+    let notice = FeedItemOps::get_by_id(&mut pool.get().unwrap(), notice.id.as_deref().unwrap()).unwrap();
+    assert_eq!(notice.body_ref, None);
+    assert!(notice.email_body.is_some());
+
+    bodies::load(Some(&store), std::slice::from_mut(&mut stored)).await;
+    assert_eq!(stored.email_body.as_deref(), Some("Weekly issue in plain text"));
+    assert_eq!(stored.email_body_html.as_deref(), Some("<p>Weekly issue in HTML</p>"));
+
+    // Bringing them back empties the reference
+    assert_eq!(bodies::restore_feed(&database, Some(&store), feed.id.as_deref().unwrap()).await.unwrap(), 1);
+    let restored = FeedItemOps::get_by_id(&mut pool.get().unwrap(), &item_id).unwrap();
+    assert_eq!(restored.body_ref, None);
+    assert_eq!(restored.email_body.as_deref(), Some("Weekly issue in plain text"));
+    assert_eq!(bodies::prune(&database, &store).await.unwrap(), 1);
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_feeds_read_bodies_from_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("BODY_STORE", "filesystem");
+    std::env::set_var("BODY_STORE_PATH", dir.path());
+
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn, "News", false);
+    let item = create_item(&mut conn, &feed, "Weekly issue");
+    drop(conn);
+    let database = DatabasePool::SQLite(pool.clone());
+    let store = bodies::store_from_env().unwrap();
+    assert!(bodies::offload(&database, &store, &item, 0).await.unwrap());
+
+    let app = app(pool.clone());
+    let feed_id = feed.id.clone().unwrap();
+    let item_id = item.id.clone().unwrap();
+
+    let (status, rss) = send(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(rss.contains("<content:encoded><![CDATA[<p>Weekly issue in HTML</p>"), "{}", rss);
+
+    let (status, body) = send(&app, Method::GET, &format!("/api/feed-items/{}", item_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let served: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(served["email_body"], "Weekly issue in plain text");
+
+    // Making the feed append-only brings the bodies back before chaining them
+    let update = json!({
+        "title": feed.title,
+        "email_rule_id": feed.email_rule_id,
+        "feed_type": "rss",
+        "is_active": true,
+        "append_only": true,
+    });
+    let (status, body) = send(&app, Method::PUT, &format!("/api/feeds/{}", feed_id), Some(update)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let sealed = FeedItemOps::get_by_id(&mut pool.get().unwrap(), &item_id).unwrap();
+    assert_eq!(sealed.body_ref, None);
+    assert_eq!(sealed.email_body.as_deref(), Some("Weekly issue in plain text"));
+    let (_, verification) = send(&app, Method::GET, &format!("/api/feeds/{}/verify", feed_id), None).await;
+    assert!(verification.contains("\"valid\":true"), "{}", verification);
+
+    // With a store configured, the offload task can be started over the API
+    let (status, _) = send(&app, Method::POST, "/api/admin/maintenance/offload-bodies", Some(json!({ "batch_size": 0 }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, Method::POST, "/api/admin/maintenance/offload-bodies", None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{TimeZone, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{FeedItem, NewFeed, NewFeedItem};
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn bulk(app: &axum::Router, feed_id: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/api/feeds/{}/items/bulk", feed_id))
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+/// A feed with one item per September day given; returns the feed's ID and its items
+fn feed_with_items(pool: &DatabasePool, days: &[u32], configure: impl FnOnce(&mut NewFeed)) -> (String, Vec<FeedItem>) {
+    let fixture = TestAccount::new("Work")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News").configure(configure)))
+        .insert(pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let items = days.iter().map(|day| {
+        let date = Utc.with_ymd_and_hms(2025, 9, *day, 8, 0, 0).unwrap();
+        let item = NewFeedItem::new(feed_id.clone(), format!("Issue {}", day), None, None, None, date, None, None, None, None);
+        FeedItemOpsGeneric::create(pool, &item).unwrap()
+    }).collect();
+    (feed_id, items)
+}
+
+fn item(pool: &DatabasePool, item: &FeedItem) -> FeedItem {
+    FeedItemOpsGeneric::get_by_id(pool, item.id.as_deref().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_bulk_actions_by_ids_dates_and_unread() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let (feed_id, items) = feed_with_items(&pool, &[1, 2, 3, 4], |_| {});
+    let app = app(pool.clone());
+
+    // Only the feed's own items are touched
+    let (status, body) = bulk(&app, &feed_id, json!({
+        "action": "star",
+        "item_ids": [items[0].id, items[1].id, "not-an-item"],
+    })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((body["matched"].as_u64(), body["updated"].as_u64()), (Some(2), Some(2)));
+    assert_eq!(item(&pool, &items[0]).starred, Some(true));
+    assert_ne!(item(&pool, &items[2]).starred, Some(true));
+
+    // Items published from the 2nd up to the 4th
+    let (_, body) = bulk(&app, &feed_id, json!({
+        "action": "mark_read",
+        "since": "2025-09-02T00:00:00Z",
+        "until": "2025-09-04T00:00:00+00:00",
+    })).await;
+    assert_eq!(body["updated"], 2);
+    let read: Vec<bool> = items.iter().map(|i| item(&pool, i).is_read == Some(true)).collect();
+    assert_eq!(read, [false, true, true, false]);
+
+    // All unread items
+    let (_, body) = bulk(&app, &feed_id, json!({"action": "mark_read", "unread": true})).await;
+    assert_eq!(body["matched"], 2);
+    assert!(items.iter().all(|i| item(&pool, i).is_read == Some(true)));
+
+    // Deleting keeps pinned items
+    FeedItemOpsGeneric::set_pinned(&pool, items[3].id.as_deref().unwrap(), true).unwrap();
+    let (_, body) = bulk(&app, &feed_id, json!({"action": "delete", "since": "2025-09-03T00:00:00Z"})).await;
+    assert_eq!((body["updated"].as_u64(), body["skipped"].as_u64()), (Some(1), Some(1)));
+    let left = FeedItemOpsGeneric::get_by_feed_id(&pool, &feed_id, None).unwrap();
+    assert_eq!(left.len(), 3);
+    assert!(left.iter().all(|i| i.title != "Issue 3"));
+}
+
+#[tokio::test]
+async fn test_bulk_requests_are_validated() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let (feed_id, _) = feed_with_items(&pool, &[1], |_| {});
+    let (ledger_id, _) = feed_with_items(&pool, &[1], |feed| feed.append_only = true);
+    let app = app(pool.clone());
+
+    let (status, _) = bulk(&app, &feed_id, json!({"action": "delete"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = bulk(&app, &feed_id, json!({"action": "mark_read", "since": "yesterday"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("since"), "{}", body);
+    let (status, _) = bulk(&app, "missing", json!({"action": "mark_read", "unread": true})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = bulk(&app, &ledger_id, json!({"action": "delete", "unread": true})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = bulk(&app, &ledger_id, json!({"action": "mark_read", "unread": true})).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode, Uri};
+use axum::routing::{post, put};
+use axum::Json;
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::chat;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+/// Requests seen by the receiver: decoded path, Authorization header and JSON body
+type Received = Arc<Mutex<Vec<(String, Option<String>, Value)>>>;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// Local stand-in for the Slack and Discord webhooks and a Matrix homeserver
+async fn spawn_receiver() -> (String, Received) {
+    let received: Received = Arc::default();
+    let record = {
+        let received = received.clone();
+        move |uri: Uri, headers: HeaderMap, body: String| async move {
+            let path = urlencoding::decode(uri.path()).unwrap().into_owned();
+            let authorization = headers.get("authorization").map(|value| value.to_str().unwrap().to_string());
+            received.lock().unwrap().push((path, authorization, serde_json::from_str(&body).unwrap()));
+            Json(json!({}))
+        }
+    };
+    let receiver = axum::Router::new()
+        .route("/slack", post(record.clone()))
+        .route("/discord", post(record.clone()))
+        .route("/_matrix/client/v3/rooms/:room/send/m.room.message/:txn", put(record));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(receiver.into_make_service()));
+
+    (format!("http://{}", addr), received)
+}
+
+async fn request(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn create_feed_with_item(pool: &DbPool) -> (Feed, FeedItem) {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let feed = FeedOps::create(&mut conn, &NewFeed::new(
+        "Newsletters".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+    let item = FeedItemOps::create(&mut conn, &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Weekly update".to_string(),
+        Some("<p>Hello</p>".to_string()),
+        None,
+        Some("news@example.com".to_string()),
+        Utc::now(),
+        None,
+        None,
+        None,
+        None,
+    )).unwrap();
+    (feed, item)
+}
+
+fn slack(url: &str) -> Value {
+    json!({ "platform": "slack", "webhook_url": url, "is_active": true })
+}
+
+#[tokio::test]
+async fn test_posts_to_each_platform() {
+    let (receiver_url, received) = spawn_receiver().await;
+    let pool = setup_test_db();
+    let (feed, _) = create_feed_with_item(&pool);
+    let feed_id = feed.id.unwrap();
+    let app = app(pool);
+
+    let integrations = [
+        slack(&format!("{}/slack", receiver_url)),
+        json!({
+            "platform": "discord",
+            "webhook_url": format!("{}/discord", receiver_url),
+            "message_template": "**{{item.title}}** from {{item.author}}",
+            "is_active": true
+        }),
+        json!({
+            "platform": "matrix",
+            "matrix_homeserver": format!("{}/", receiver_url),
+            "matrix_room_id": "!room:example.org",
+            "matrix_access_token": "secret-token",
+            "is_active": true
+        }),
+    ];
+    for integration in integrations {
+        let (status, created) = request(&app, Method::POST, &format!("/api/feeds/{}/integrations", feed_id), Some(integration)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, result) = request(&app, Method::POST, &format!("/api/chat-integrations/{}/test", created["id"].as_str().unwrap()), None).await;
+        assert_eq!(status, StatusCode::OK, "{}", result);
+    }
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[0].0, "/slack");
+    assert_eq!(received[0].2, json!({ "text": "New in Newsletters: Weekly update" }));
+    assert_eq!(received[1].0, "/discord");
+    assert_eq!(received[1].2, json!({ "content": "**Weekly update** from news@example.com" }));
+    assert!(received[2].0.starts_with("/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/"));
+    assert_eq!(received[2].1.as_deref(), Some("Bearer secret-token"));
+    assert_eq!(received[2].2, json!({ "msgtype": "m.text", "body": "New in Newsletters: Weekly update" }));
+}
+
+#[tokio::test]
+async fn test_notify_respects_rate_limit_and_active_flag() {
+    let (receiver_url, received) = spawn_receiver().await;
+    let pool = setup_test_db();
+    let (feed, item) = create_feed_with_item(&pool);
+    let feed_id = feed.id.clone().unwrap();
+    let app = app(pool.clone());
+
+    let mut limited = slack(&format!("{}/slack", receiver_url));
+    limited["rate_limit_per_minute"] = json!(2);
+    let mut inactive = slack(&format!("{}/discord", receiver_url));
+    inactive["platform"] = json!("discord");
+    inactive["is_active"] = json!(false);
+    for integration in [limited, inactive] {
+        let (status, _) = request(&app, Method::POST, &format!("/api/feeds/{}/integrations", feed_id), Some(integration)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let pool = DatabasePool::SQLite(pool);
+    for _ in 0..3 {
+        chat::notify(&pool, &feed, &item).await;
+    }
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    assert!(received.iter().all(|(path, _, _)| path == "/slack"));
+}
+
+#[tokio::test]
+async fn test_integration_api_validates_and_updates() {
+    let pool = setup_test_db();
+    let (feed, _) = create_feed_with_item(&pool);
+    let feed_id = feed.id.unwrap();
+    let app = app(pool);
+    let uri = format!("/api/feeds/{}/integrations", feed_id);
+
+    let invalid = [
+        json!({ "platform": "irc", "webhook_url": "https://example.com", "is_active": true }),
+        json!({ "platform": "slack", "is_active": true }),
+        json!({ "platform": "discord", "webhook_url": "ftp://example.com", "is_active": true }),
+        json!({ "platform": "matrix", "matrix_homeserver": "https://matrix.org", "matrix_room_id": "#news:matrix.org", "matrix_access_token": "t", "is_active": true }),
+        json!({ "platform": "matrix", "matrix_homeserver": "https://matrix.org", "matrix_room_id": "!abc:matrix.org", "is_active": true }),
+        json!({ "platform": "slack", "webhook_url": "https://example.com", "message_template": "{{item.body}}", "is_active": true }),
+        json!({ "platform": "slack", "webhook_url": "https://example.com", "rate_limit_per_minute": 0, "is_active": true }),
+    ];
+    for integration in invalid {
+        let (status, body) = request(&app, Method::POST, &uri, Some(integration.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "accepted {}", integration);
+        assert!(body["error"].is_string());
+    }
+
+    let (status, _) = request(&app, Method::POST, "/api/feeds/missing/integrations", Some(slack("https://example.com"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, created) = request(&app, Method::POST, &uri, Some(slack("https://hooks.slack.com/services/T/B/X"))).await;
+    let integration_uri = format!("/api/chat-integrations/{}", created["id"].as_str().unwrap());
+    let mut update = slack("https://hooks.slack.com/services/T/B/Y");
+    update["message_template"] = json!("{{item.title}}");
+    let (status, updated) = request(&app, Method::PUT, &integration_uri, Some(update)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["feed_id"], feed_id);
+    assert_eq!(updated["webhook_url"], "https://hooks.slack.com/services/T/B/Y");
+    assert_eq!(updated["message_template"], "{{item.title}}");
+
+    // Integrations go away with their feed
+    let (status, _) = request(&app, Method::DELETE, &format!("/api/feeds/{}", feed_id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = request(&app, Method::GET, &integration_uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+use mail2feed_backend::api::{self, types::*};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::client::{ApiError, Mail2FeedClient};
+use mail2feed_backend::db::connection::DatabasePool;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+
+use common::setup_test_db;
+
+/// Serve the API on an ephemeral port and return a client pointed at it
+async fn spawn_server() -> Mail2FeedClient {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    let app = api::create_routes(DatabasePool::SQLite(setup_test_db()), background_handle);
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    let server = axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service());
+    tokio::spawn(server);
+
+    Mail2FeedClient::new(format!("http://{}/", addr))
+}
+
+#[tokio::test]
+async fn test_client_round_trips_typed_requests() {
+    let client = spawn_server().await;
+
+    let health = client.health().await.unwrap();
+    assert_eq!(health.status, "ok");
+
+    let account = client.create_account(&CreateImapAccountRequest {
+        name: "Remote".to_string(),
+        host: "imap.example.com".to_string(),
+        port: 993,
+        username: "user@example.com".to_string(),
+        password: "secret".to_string(),
+        use_tls: true,
+        security: None,
+        default_post_process_action: "do_nothing".to_string(),
+        default_move_to_folder: None,
+        max_bytes_per_second: Some(4096),
+        tls_pin: None,
+        tls_server_name: None,
+        quota_group_id: None,
+        max_feeds: None,
+        max_items: None,
+        max_processing_minutes_per_day: None,
+        max_connections_per_hour: None,
+        min_fetch_delay_seconds: None,
+        max_messages_per_fetch: None,
+        protocol: None,
+        allow_duplicate: false,
+    }).await.unwrap();
+    let account_id = account.id.clone().unwrap();
+    assert_eq!(account.max_bytes_per_second, Some(4096));
+
+    let rule = client.create_rule(&CreateEmailRuleRequest {
+        name: "Newsletters".to_string(),
+        imap_account_id: account_id.clone(),
+        folder: "INBOX".to_string(),
+        to_address: None,
+        from_address: Some("news@example.com".to_string()),
+        subject_contains: None,
+        label: None,
+        is_active: true,
+        post_process_action: None,
+        move_to_folder: None,
+        inherit_account_defaults: true,
+        observe_only: false,
+        importance: None,
+        processing_order: None,
+        post_process_delay_hours: None,
+        match_expression: None,
+        fetch_limit: None,
+        include_seen: true,
+        include_subfolders: false,
+        strip_footers: false,
+        strip_quoted_replies: false,
+        strip_tracking_pixels: true,
+        unwrap_tracking_links: false,
+        post_process_actions: None,
+    }).await.unwrap();
+
+    let feed = client.create_feed(&CreateFeedRequest {
+        title: "Remote Feed".to_string(),
+        description: None,
+        link: None,
+        email_rule_id: rule.id.clone().unwrap(),
+        feed_type: "rss".to_string(),
+        is_active: true,
+        max_items: None,
+        max_age_days: None,
+        min_items: None,
+        summary_length: Some(200),
+        locale: None,
+        timezone: None,
+        title_template: None,
+        webhook_url: None,
+        webhook_method: None,
+        webhook_body: None,
+        public_access: None,
+        max_pinned: None,
+        auto_titles: None,
+        page_css: None,
+        page_header_html: None,
+        page_footer_html: None,
+        page_logo_url: None,
+        append_only: None,
+        digest_mode: None,
+        description_template: None,
+        item_limit: None,
+        full_content: None,
+        auth_username: None,
+        auth_password: None,
+        dedup_scope: None,
+    }).await.unwrap();
+    let feed_id = feed.id.clone().unwrap();
+
+    assert_eq!(client.list_feeds().await.unwrap().len(), 1);
+    assert_eq!(client.get_rule_stats(rule.id.as_deref().unwrap()).await.unwrap().match_count, 0);
+    assert!(client.get_rule_preview(rule.id.as_deref().unwrap(), &RulePreviewQuery::default()).await.unwrap().is_empty());
+    let page = client.get_feed_items(&feed_id, &FeedItemsQuery { limit: Some(10), ..Default::default() }).await.unwrap();
+    assert!(page.items.is_empty());
+    assert_eq!((page.total, page.unread, page.next_cursor), (0, 0, None));
+    assert!(client.get_rss_feed(&feed_id).await.unwrap().contains("<rss"));
+
+    let integration = client.create_chat_integration(&feed_id, &ChatIntegrationRequest {
+        platform: "discord".to_string(),
+        webhook_url: Some("https://discord.com/api/webhooks/1/token".to_string()),
+        matrix_homeserver: None,
+        matrix_room_id: None,
+        matrix_access_token: None,
+        message_template: None,
+        rate_limit_per_minute: Some(5),
+        is_active: true,
+    }).await.unwrap();
+    assert_eq!(client.list_chat_integrations(&feed_id).await.unwrap().len(), 1);
+    client.delete_chat_integration(integration.id.as_deref().unwrap()).await.unwrap();
+    assert!(client.list_chat_integrations(&feed_id).await.unwrap().is_empty());
+
+    assert!(client.list_failed_deliveries().await.unwrap().is_empty());
+    assert!(client.retry_failed_deliveries().await.unwrap().is_empty());
+
+    client.delete_feed(&feed_id).await.unwrap();
+    assert!(client.list_feeds().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_client_surfaces_api_errors() {
+    let client = spawn_server().await;
+
+    let err = client.get_account("does-not-exist").await.unwrap_err();
+    let api_error = err.downcast_ref::<ApiError>().unwrap();
+    assert_eq!(api_error.status, reqwest::StatusCode::NOT_FOUND);
+    assert!(api_error.message.starts_with("Account not found"));
+
+    let err = client.get_task("does-not-exist").await.unwrap_err();
+    assert_eq!(err.downcast_ref::<ApiError>().unwrap().message, "Task does-not-exist not found");
+}
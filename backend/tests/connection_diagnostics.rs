@@ -0,0 +1,129 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::api::types::{ConnectionDiagnostics, StepStatus};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mock_imap::MockImap;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn post(app: axum::Router, uri: &str, body: Option<Value>) -> (StatusCode, Vec<u8>) {
+    let request = Request::builder().method(Method::POST).uri(uri).header("Content-Type", "application/json");
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    (status, hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec())
+}
+
+async fn diagnose(server: &MockImap) -> ConnectionDiagnostics {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let account = server.account(&pool);
+    let (status, body) = post(app(pool), &format!("/api/imap-accounts/{}/test", account.id.unwrap()), None).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn statuses(diagnostics: &ConnectionDiagnostics) -> Vec<(&str, StepStatus)> {
+    diagnostics.steps.iter().map(|step| (step.step.as_str(), step.status)).collect()
+}
+
+#[tokio::test]
+async fn test_every_step_passes() {
+    let server = MockImap::start("IDLE");
+    server.add_folder("Lists");
+    let diagnostics = diagnose(&server).await;
+
+    assert!(diagnostics.success);
+    assert_eq!(diagnostics.failed_step, None);
+    assert_eq!(statuses(&diagnostics), [
+        ("dns", StepStatus::Passed),
+        ("tcp", StepStatus::Passed),
+        ("tls", StepStatus::Skipped),
+        ("auth", StepStatus::Passed),
+        ("capabilities", StepStatus::Passed),
+        ("folders", StepStatus::Passed),
+    ]);
+    assert_eq!(diagnostics.steps[0].detail.as_deref(), Some("127.0.0.1 resolved to 127.0.0.1"));
+    assert_eq!(diagnostics.steps[4].detail.as_deref(), Some("IMAP4rev1 IDLE"));
+    assert_eq!(diagnostics.steps[5].detail.as_deref(), Some("Listed 2 folders"));
+}
+
+#[tokio::test]
+async fn test_tls_step_checks_the_pin() {
+    let server = MockImap::start_tls("");
+    let diagnostics = diagnose(&server).await;
+
+    assert!(diagnostics.success);
+    assert_eq!(diagnostics.steps[2].status, StepStatus::Passed);
+    assert!(diagnostics.steps[2].detail.as_deref().unwrap().contains("matches pin"));
+}
+
+#[tokio::test]
+async fn test_failed_login_skips_later_steps() {
+    let server = MockImap::start("");
+    server.refuse("LOGIN");
+    let diagnostics = diagnose(&server).await;
+
+    assert!(!diagnostics.success);
+    assert_eq!(diagnostics.failed_step.as_deref(), Some("auth"));
+    let auth = &diagnostics.steps[3];
+    assert_eq!(auth.status, StepStatus::Failed);
+    assert!(auth.error.as_deref().unwrap().contains("Authentication failed"));
+    assert_eq!(statuses(&diagnostics)[4..], [("capabilities", StepStatus::Skipped), ("folders", StepStatus::Skipped)]);
+}
+
+#[tokio::test]
+async fn test_unsaved_settings_report_a_closed_port() {
+    // A port nothing listens on any more
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let settings = json!({
+        "host": "127.0.0.1",
+        "port": port,
+        "username": "user",
+        "password": "secret",
+        "use_tls": false,
+    });
+
+    let (status, body) = post(app(DatabasePool::SQLite(setup_test_db())), "/api/imap-accounts/test", Some(settings)).await;
+    assert_eq!(status, StatusCode::OK);
+    let diagnostics: ConnectionDiagnostics = serde_json::from_slice(&body).unwrap();
+    assert_eq!(diagnostics.failed_step.as_deref(), Some("tcp"));
+    assert_eq!(diagnostics.steps[0].status, StepStatus::Passed);
+    assert!(diagnostics.steps[1].error.is_some());
+    assert!(diagnostics.steps[2..].iter().all(|step| step.status == StepStatus::Skipped));
+}
+
+#[tokio::test]
+async fn test_invalid_requests() {
+    let app = app(DatabasePool::SQLite(setup_test_db()));
+    let settings = json!({
+        "host": "127.0.0.1",
+        "port": 143,
+        "username": "user",
+        "password": "secret",
+        "use_tls": false,
+        "tls_pin": "cert-sha256:00",
+    });
+    let (status, _) = post(app.clone(), "/api/imap-accounts/test", Some(settings)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = post(app, "/api/imap-accounts/missing/test", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,96 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn create_account(app: &axum::Router, account: Value) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(Request::builder()
+        .method(Method::POST)
+        .uri("/api/imap-accounts")
+        .header("Content-Type", "application/json")
+        .body(Body::from(account.to_string()))
+        .unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn account(username: &str, port: i32, use_tls: bool, security: Option<&str>) -> Value {
+    json!({
+        "name": username,
+        "host": "imap.example.com",
+        "port": port,
+        "username": username,
+        "password": "secret",
+        "use_tls": use_tls,
+        "security": security,
+    })
+}
+
+#[tokio::test]
+async fn test_implicit_tls_connections() {
+    let server = MockImap::start_implicit_tls("UIDPLUS");
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Issue 1"));
+    let client = server.client();
+
+    client.test_connection().await.unwrap();
+    let fetch = client.fetch_emails_from_folder("INBOX", None, None, false).await.unwrap();
+    assert_eq!(fetch.emails.len(), 1);
+    assert!(!server.mailboxes().sent("STARTTLS"));
+
+    let diagnostics = client.diagnose().await.unwrap();
+    assert!(diagnostics.success);
+    assert!(diagnostics.steps[2].detail.as_deref().unwrap().starts_with("Implicit TLS handshake completed"));
+}
+
+#[tokio::test]
+async fn test_security_is_suggested_from_the_port() {
+    let app = app(DatabasePool::SQLite(setup_test_db()));
+
+    for (username, port, use_tls, security, expected) in [
+        ("implicit", 993, true, None, "ssl_tls"),
+        ("upgraded", 143, true, None, "starttls"),
+        ("plain", 1143, false, None, "none"),
+        ("chosen", 993, false, Some("starttls"), "starttls"),
+        ("downgraded", 993, true, Some("none"), "none"),
+    ] {
+        let (status, created) = create_account(&app, account(username, port, use_tls, security)).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", username);
+        assert_eq!(created["security"], expected, "{}", username);
+        assert_eq!(created["use_tls"], expected != "none", "{}", username);
+    }
+}
+
+#[tokio::test]
+async fn test_security_is_validated() {
+    let app = app(DatabasePool::SQLite(setup_test_db()));
+
+    let (status, body) = create_account(&app, account("unknown", 993, true, Some("tls"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("ssl_tls"));
+
+    let mut pinned = account("pinned", 993, true, Some("none"));
+    pinned["tls_pin"] = json!(format!("cert-sha256:{}", "00".repeat(32)));
+    let (status, _) = create_account(&app, pinned).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,64 @@
+mod common;
+mod mock_imap;
+
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{FeedItem, NewEmailRule};
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+
+use common::setup_test_db;
+
+const REPLY: &str = "Count me in for Friday.\r\n\
+\r\n\
+Details: https://t.example.com/click?id=9&url=https%3A%2F%2Fexample.com%2Fevent%3Futm_source%3Dmail\r\n\
+\r\n\
+On Mon, 1 Sep 2025 at 08:00, Ann <ann@example.com> wrote:\r\n\
+> Who is coming on Friday?\r\n\
+\r\n\
+You are receiving this because you joined the list.\r\n\
+Unsubscribe: https://example.com/u/7\r\n";
+
+/// Process one reply through a rule with the given filters; returns its item
+async fn process(configure: impl FnOnce(&mut NewEmailRule)) -> FeedItem {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", MockMessage::new("list@example.com", "Re: Friday").body(REPLY));
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("List").configure(configure).with_feed(TestFeed::new("List")))
+        .insert(&pool)
+        .unwrap();
+
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let feed_id = fixture.feed("List").id.clone().unwrap();
+    FeedItemOpsGeneric::get_by_feed_id(&pool, &feed_id, None).unwrap().remove(0)
+}
+
+#[tokio::test]
+async fn test_rules_clean_content_with_their_filters() {
+    let item = process(|rule| {
+        rule.strip_footers = true;
+        rule.strip_quoted_replies = true;
+        rule.unwrap_tracking_links = true;
+    })
+    .await;
+
+    assert_eq!(
+        item.email_body.as_deref(),
+        Some("Count me in for Friday.\n\nDetails: https://example.com/event")
+    );
+    // The footer's unsubscribe link is still picked up
+    assert_eq!(item.unsubscribe_url.as_deref(), Some("https://example.com/u/7"));
+}
+
+#[tokio::test]
+async fn test_content_is_kept_without_filters() {
+    let item = process(|_| {}).await;
+
+    let body = item.email_body.unwrap();
+    assert!(body.contains("> Who is coming on Friday?"), "{}", body);
+    assert!(body.contains("Unsubscribe: https://example.com/u/7"), "{}", body);
+    assert!(body.contains("https://t.example.com/click?id=9"), "{}", body);
+}
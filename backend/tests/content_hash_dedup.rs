@@ -0,0 +1,56 @@
+mod common;
+mod mock_imap;
+
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric};
+use mail2feed_backend::feed::{dedup, generator::FeedGenerator};
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+
+use common::setup_test_db;
+
+fn issue(message_id: &str, date: &str) -> MockMessage {
+    MockMessage::new("news@example.com", "Weekly digest")
+        .message_id(message_id)
+        .date(date)
+        .body("This week:\r\n  three   new releases.\r\n")
+}
+
+#[tokio::test]
+async fn test_resent_emails_are_caught_by_content_and_keep_their_guid() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", issue("<issue-1@example.com>", "Mon, 1 Sep 2025 09:00:00 +0000"));
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let process = || async {
+        let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        FeedItemOpsGeneric::get_by_feed_id(&pool, &feed_id, None).unwrap()
+    };
+
+    let items = process().await;
+    assert_eq!(items.len(), 1);
+    let hash = dedup::content_hash("Weekly digest", "news@example.com", "This week:\n three new releases.");
+    assert_eq!(items[0].guid.as_deref(), Some(hash.as_str()));
+    let feed = FeedOpsGeneric::get_by_id(&pool, &feed_id).unwrap();
+    let rss = FeedGenerator::generate_rss(&feed, &items).unwrap();
+    let guid = format!("<guid isPermaLink=\"false\">{}_{}</guid>", feed_id, hash);
+    assert!(rss.contains(&guid), "{}", rss);
+
+    // A resend with a new Message-ID and date is already in the feed
+    server.add_message("INBOX", issue("<issue-1-resent@example.com>", "Mon, 1 Sep 2025 09:04:00 +0000"));
+    assert_eq!(process().await.len(), 1);
+
+    // Stored again after its item was removed, the email keeps its GUID
+    FeedItemOpsGeneric::delete(&pool, items[0].id.as_deref().unwrap()).unwrap();
+    server.add_message("INBOX", issue("<issue-1-again@example.com>", "Tue, 2 Sep 2025 09:00:00 +0000"));
+    let items = process().await;
+    assert_eq!(items.len(), 1);
+    let rss = FeedGenerator::generate_rss(&feed, &items).unwrap();
+    assert!(rss.contains(&guid), "{}", rss);
+}
@@ -51,6 +51,122 @@ fn test_imap_account_crud() {
     assert_eq!(all_after_delete.len(), 0);
 }
 
+#[test]
+fn test_imap_account_fingerprint() {
+    use mail2feed_backend::imap::fingerprint;
+
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+
+    let mut ids = Vec::new();
+    for host in ["imap.gmail.com", "imap.googlemail.com", "imap.example.com"] {
+        let account = NewImapAccount::new(
+            host.to_string(),
+            host.to_string(),
+            993,
+            "user@example.com".to_string(),
+            "password123".to_string(),
+            true,
+        );
+        ids.push(ImapAccountOps::create(&mut conn, &account).unwrap().id.unwrap());
+    }
+
+    let created = ImapAccountOps::get_by_id(&mut conn, &ids[0]).unwrap();
+    assert!(created.fingerprint.is_none());
+
+    // Two hostnames for the same mailbox share a fingerprint
+    let shared = fingerprint::compute("* OK Gimap ready for requests from 192.0.2.1", Some(1));
+    ImapAccountOps::update_fingerprint(&mut conn, &ids[0], Some(&shared)).unwrap();
+    ImapAccountOps::update_fingerprint(&mut conn, &ids[1], Some(&shared)).unwrap();
+    ImapAccountOps::update_fingerprint(&mut conn, &ids[2], Some(&fingerprint::compute("* OK Dovecot ready.", Some(1)))).unwrap();
+
+    let accounts = ImapAccountOps::get_all(&mut conn).unwrap();
+    let first = accounts.iter().find(|account| account.id.as_ref() == Some(&ids[0])).unwrap();
+    let aliases: Vec<_> = fingerprint::find_aliases(&accounts, first).into_iter()
+        .map(|alias| alias.id.clone().unwrap())
+        .collect();
+    assert_eq!(aliases, vec![ids[1].clone()]);
+
+    ImapAccountOps::update_fingerprint(&mut conn, &ids[1], None).unwrap();
+    let accounts = ImapAccountOps::get_all(&mut conn).unwrap();
+    assert!(fingerprint::find_aliases(&accounts, first).is_empty());
+}
+
+#[test]
+fn test_observe_only_rule_matches() {
+    use chrono::{Duration, Utc};
+
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "imap.example.com".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password123".to_string(),
+        true,
+    )).unwrap();
+    let mut new_rule = NewEmailRule::new(
+        "Trial".to_string(),
+        account.id.unwrap(),
+        "INBOX".to_string(),
+        None,
+        Some("promo@example.com".to_string()),
+        None,
+        None,
+        true,
+    );
+    new_rule.observe_only = true;
+    let rule = EmailRuleOps::create(&mut conn, &new_rule).unwrap();
+    let rule_id = rule.id.unwrap();
+    assert!(rule.observe_only);
+
+    let empty = RuleMatchOps::stats(&mut conn, &rule_id).unwrap();
+    assert_eq!(empty, RuleMatchStats { match_count: 0, last_matched_at: None });
+
+    let mut first = NewRuleMatch::new(
+        rule_id.clone(),
+        "<1@example.com>".to_string(),
+        "Spring sale".to_string(),
+        "promo@example.com".to_string(),
+        Utc::now(),
+    );
+    first.matched_at = (Utc::now() - Duration::hours(1)).to_rfc3339();
+    let second = NewRuleMatch::new(
+        rule_id.clone(),
+        "<2@example.com>".to_string(),
+        "Summer sale".to_string(),
+        "promo@example.com".to_string(),
+        Utc::now(),
+    );
+    assert!(RuleMatchOps::create_if_new(&mut conn, &first).unwrap());
+    assert!(RuleMatchOps::create_if_new(&mut conn, &second).unwrap());
+
+    // Seeing the same message again on a later run is not a new match
+    let again = NewRuleMatch::new(
+        rule_id.clone(),
+        "<1@example.com>".to_string(),
+        "Spring sale".to_string(),
+        "promo@example.com".to_string(),
+        Utc::now(),
+    );
+    assert!(!RuleMatchOps::create_if_new(&mut conn, &again).unwrap());
+
+    let stats = RuleMatchOps::stats(&mut conn, &rule_id).unwrap();
+    assert_eq!(stats.match_count, 2);
+    assert_eq!(stats.last_matched_at, Some(second.matched_at.clone()));
+
+    let preview = RuleMatchOps::get_by_rule_id(&mut conn, &rule_id, None).unwrap();
+    let subjects: Vec<_> = preview.iter().map(|m| m.email_subject.as_str()).collect();
+    assert_eq!(subjects, vec!["Summer sale", "Spring sale"]);
+    assert_eq!(RuleMatchOps::get_by_rule_id(&mut conn, &rule_id, Some(1)).unwrap().len(), 1);
+
+    // Matches go away with the rule
+    EmailRuleOps::delete(&mut conn, &rule_id).unwrap();
+    assert_eq!(RuleMatchOps::stats(&mut conn, &rule_id).unwrap().match_count, 0);
+}
+
 #[test]
 fn test_email_rule_crud() {
     let pool = setup_test_db();
@@ -100,7 +216,13 @@ fn test_email_rule_crud() {
     let active_rules = EmailRuleOps::get_active(&mut conn).unwrap();
     assert_eq!(active_rules.len(), 1);
     
-    // Update
+    // High-water mark
+    assert_eq!(created.last_seen_uid, None);
+    EmailRuleOps::set_high_water_mark(&mut conn, created.id.as_ref().unwrap(), 3_000_000_000, 42).unwrap();
+    let marked = EmailRuleOps::get_by_id(&mut conn, created.id.as_ref().unwrap()).unwrap();
+    assert_eq!((marked.uid_validity, marked.last_seen_uid), (Some(3_000_000_000), Some(42)));
+    
+    // Update, which clears the mark so the folder is checked against the edited rule
     let mut updated_rule = new_rule.clone();
     updated_rule.is_active = false;
     updated_rule.subject_contains = Some("Updated".to_string());
@@ -108,6 +230,7 @@ fn test_email_rule_crud() {
     let updated = EmailRuleOps::update(&mut conn, created.id.as_ref().unwrap(), &updated_rule).unwrap();
     assert!(!updated.is_active);
     assert_eq!(updated.subject_contains, Some("Updated".to_string()));
+    assert_eq!((updated.uid_validity, updated.last_seen_uid), (None, None));
     
     // Verify active rules after update
     let active_after_update = EmailRuleOps::get_active(&mut conn).unwrap();
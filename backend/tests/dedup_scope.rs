@@ -0,0 +1,112 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::DedupScope;
+use mail2feed_backend::db::operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric};
+use mail2feed_backend::feed::dedup;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn item_count(pool: &DatabasePool, feed_id: &Option<String>) -> usize {
+    FeedItemOpsGeneric::get_by_feed_id(pool, feed_id.as_deref().unwrap(), None).unwrap().len()
+}
+
+#[tokio::test]
+async fn test_wider_scopes_skip_emails_other_feeds_have() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("UIDPLUS MOVE");
+    // The same newsletter, reaching two addresses
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue").message_id("<weekly@example.com>"));
+    let work = server.test_account("Work")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("Work newsletters")))
+        .insert(&pool)
+        .unwrap();
+    let home = server.test_account("Home")
+        .with_rule(TestRule::new("Newsletters")
+            .with_feed(TestFeed::new("Home newsletters").configure(|feed| feed.dedup_scope = Some("account".to_string()))))
+        .with_rule(TestRule::new("Everything")
+            .with_feed(TestFeed::new("Everything").configure(|feed| feed.dedup_scope = Some("global".to_string()))))
+        .insert(&pool)
+        .unwrap();
+
+    let result = EmailProcessor::new(work.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert_eq!(result.new_feed_items_created, 1, "{:?}", result);
+    let result = EmailProcessor::new(home.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result);
+
+    assert_eq!(item_count(&pool, &work.feed("Work newsletters").id), 1);
+    // The other account's copy does not count for an account-wide scope
+    assert_eq!(item_count(&pool, &home.feed("Home newsletters").id), 1);
+    assert_eq!(item_count(&pool, &home.feed("Everything").id), 0);
+}
+
+#[test]
+fn test_feeds_without_a_scope_follow_the_environment() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work")
+        .with_rule(TestRule::new("Newsletters")
+            .with_feed(TestFeed::new("Default"))
+            .with_feed(TestFeed::new("Own").configure(|feed| feed.dedup_scope = Some("feed".to_string()))))
+        .insert(&pool)
+        .unwrap();
+
+    std::env::set_var("FEED_DEDUP_SCOPE", "Account");
+    assert_eq!(dedup::scope(fixture.feed("Default")), DedupScope::Account);
+    assert_eq!(dedup::scope(fixture.feed("Own")), DedupScope::Feed);
+    std::env::set_var("FEED_DEDUP_SCOPE", "everywhere");
+    assert_eq!(dedup::scope(fixture.feed("Default")), DedupScope::Feed);
+    std::env::remove_var("FEED_DEDUP_SCOPE");
+    assert_eq!(dedup::scope(fixture.feed("Default")), DedupScope::Feed);
+}
+
+#[tokio::test]
+async fn test_dedup_scope_is_validated_and_stored() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work")
+        .with_rule(TestRule::new("Newsletters"))
+        .insert(&pool)
+        .unwrap();
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let app = api::create_routes(pool.clone(), BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    });
+    let create = |dedup_scope: &str| {
+        let body = json!({
+            "title": "Newsletters",
+            "email_rule_id": fixture.rule("Newsletters").id,
+            "feed_type": "rss",
+            "is_active": true,
+            "dedup_scope": dedup_scope,
+        });
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/feeds")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(create("everywhere")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app.clone().oneshot(create("Global")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let feed: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(feed["dedup_scope"], "global");
+    let stored = FeedOpsGeneric::get_by_id(&pool, feed["id"].as_str().unwrap()).unwrap();
+    assert_eq!(stored.dedup_scope(), Some(DedupScope::Global));
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::routing::post;
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::{delivery, webhook};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+/// Receiver that answers 500 while `failing` is set and records the bodies it accepts
+struct Receiver {
+    url: String,
+    failing: Arc<AtomicBool>,
+    received: Arc<Mutex<Vec<Value>>>,
+}
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn spawn_receiver(failing: bool) -> Receiver {
+    let failing = Arc::new(AtomicBool::new(failing));
+    let received: Arc<Mutex<Vec<Value>>> = Arc::default();
+    let handler = {
+        let (failing, received) = (failing.clone(), received.clone());
+        move |body: String| async move {
+            if failing.load(Ordering::SeqCst) {
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            received.lock().unwrap().push(serde_json::from_str(&body).unwrap());
+            StatusCode::OK
+        }
+    };
+    let receiver = axum::Router::new().route("/hook", post(handler));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(receiver.into_make_service()));
+
+    Receiver { url: format!("http://{}/hook", addr), failing, received }
+}
+
+async fn request(app: &axum::Router, method: Method, uri: &str) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(Request::builder().method(method).uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// A feed whose webhook posts `{"title": ...}` to `url`
+fn create_feed(pool: &DbPool, url: &str) -> Feed {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let mut new_feed = NewFeed::new(
+        "Newsletters".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    );
+    new_feed.webhook_url = Some(url.to_string());
+    new_feed.webhook_body = Some(r#"{"title": "{{item.title}}"}"#.to_string());
+    FeedOps::create(&mut conn, &new_feed).unwrap()
+}
+
+fn create_item(pool: &DbPool, feed: &Feed, title: &str) -> FeedItem {
+    FeedItemOps::create(&mut pool.get().unwrap(), &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        None,
+        None,
+        None,
+        None,
+    )).unwrap()
+}
+
+fn deliveries(pool: &DbPool, status: &DeliveryStatus) -> Vec<Delivery> {
+    DeliveryOps::get_by_status(&mut pool.get().unwrap(), status).unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_delivery_backs_off_and_holds_queue_in_order() {
+    let receiver = spawn_receiver(true).await;
+    let pool = setup_test_db();
+    let feed = create_feed(&pool, &receiver.url);
+    let app = app(pool.clone());
+    let db = DatabasePool::SQLite(pool.clone());
+
+    webhook::notify(&db, &feed, &create_item(&pool, &feed, "First")).await;
+    receiver.failing.store(false, Ordering::SeqCst);
+    webhook::notify(&db, &feed, &create_item(&pool, &feed, "Second")).await;
+
+    // The first call failed and waits for its retry; the second waits behind it
+    assert!(receiver.received.lock().unwrap().is_empty());
+    let pending = deliveries(&pool, &DeliveryStatus::Pending);
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending[0].attempts, 1);
+    assert!(pending[0].last_error.as_deref().unwrap().contains("500"));
+    assert!(pending[0].next_attempt_at > Utc::now().to_rfc3339());
+    assert_eq!(pending[1].attempts, 0);
+
+    let (status, retried) = request(&app, Method::POST, &format!("/api/deliveries/{}/retry", pending[0].id.as_ref().unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(retried["status"], "delivered");
+    assert_eq!(retried["attempts"], 1);
+
+    let received = receiver.received.lock().unwrap();
+    assert_eq!(*received, vec![json!({ "title": "First" }), json!({ "title": "Second" })]);
+    assert!(deliveries(&pool, &DeliveryStatus::Pending).is_empty());
+}
+
+#[tokio::test]
+async fn test_exhausted_deliveries_are_dead_lettered_and_retried() {
+    let receiver = spawn_receiver(true).await;
+    let pool = setup_test_db();
+    let feed = create_feed(&pool, &receiver.url);
+    let app = app(pool.clone());
+
+    let mut new_delivery = NewDelivery::new(
+        feed.id.clone().unwrap(),
+        None,
+        "chat",
+        "chat:test".to_string(),
+        "POST".to_string(),
+        receiver.url.clone(),
+        json!({ "Content-Type": "application/json", "Authorization": "Bearer secret" }).to_string(),
+        json!({ "text": "hello" }).to_string(),
+    );
+    new_delivery.attempts = delivery::MAX_ATTEMPTS - 1;
+    DeliveryOps::create(&mut pool.get().unwrap(), &new_delivery).unwrap();
+    delivery::process_due(&DatabasePool::SQLite(pool.clone())).await.unwrap();
+
+    let (status, failed) = request(&app, Method::GET, "/api/deliveries/failed").await;
+    assert_eq!(status, StatusCode::OK);
+    let failed = failed.as_array().unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["attempts"], delivery::MAX_ATTEMPTS);
+    assert_eq!(failed[0]["kind"], "chat");
+    // Headers can carry tokens and are not exposed
+    assert!(failed[0].get("headers").is_none());
+
+    receiver.failing.store(false, Ordering::SeqCst);
+    let (status, retried) = request(&app, Method::POST, "/api/deliveries/retry").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(retried[0]["status"], "delivered");
+    assert_eq!(*receiver.received.lock().unwrap(), vec![json!({ "text": "hello" })]);
+
+    let delivery_uri = format!("/api/deliveries/{}", new_delivery.id);
+    let (status, _) = request(&app, Method::POST, &format!("{}/retry", delivery_uri)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = request(&app, Method::DELETE, &delivery_uri).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = request(&app, Method::POST, &format!("{}/retry", delivery_uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,159 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{Feed, FeedItem};
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{Fixture, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+fn message(subject: &str, message_id: &str, date: &str) -> MockMessage {
+    MockMessage::new("news@example.com", subject)
+        .message_id(message_id)
+        .date(date)
+}
+
+/// A reply to `parent`, whose thread started with `root`
+fn reply(subject: &str, message_id: &str, date: &str, root: &str, parent: &str) -> MockMessage {
+    message(subject, message_id, date)
+        .header_line("In-Reply-To", parent)
+        .header_line("References", &format!("{} {}", root, parent))
+}
+
+fn fixture(server: &MockImap, pool: &DatabasePool, digest_mode: Option<&str>) -> Fixture {
+    server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")
+            .configure(|feed| feed.digest_mode = digest_mode.map(str::to_string))))
+        .insert(pool)
+        .unwrap()
+}
+
+/// Process the server's INBOX; returns the feed's items, oldest first
+async fn process(fixture: &Fixture, pool: &DatabasePool) -> Vec<FeedItem> {
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let mut items = FeedItemOpsGeneric::get_by_feed_id(pool, fixture.feed("News").id.as_ref().unwrap(), None).unwrap();
+    items.reverse();
+    items
+}
+
+#[tokio::test]
+async fn test_items_record_their_thread() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", message("Issue 1", "<a@example.com>", "Mon, 1 Sep 2025 09:00:00 +0000"));
+    server.add_message("INBOX", reply("Re: Issue 1", "<b@example.com>", "Mon, 1 Sep 2025 10:00:00 +0000", "<a@example.com>", "<a@example.com>"));
+    let fixture = fixture(&server, &pool, None);
+
+    let items = process(&fixture, &pool).await;
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].thread_id.as_deref(), Some("<a@example.com>"));
+    assert_eq!(items[1].thread_id.as_deref(), Some("<a@example.com>"));
+    assert_eq!(items[1].in_reply_to.as_deref(), Some("<a@example.com>"));
+    assert_eq!(items[1].email_references.as_deref(), Some("<a@example.com> <a@example.com>"));
+}
+
+#[tokio::test]
+async fn test_thread_digest_merges_replies() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", message("Outage", "<a@example.com>", "Mon, 1 Sep 2025 09:00:00 +0000").body("Servers are down."));
+    server.add_message("INBOX", reply("Re: Outage", "<b@example.com>", "Mon, 1 Sep 2025 10:00:00 +0000", "<a@example.com>", "<a@example.com>").body("Still down."));
+    server.add_message("INBOX", reply("Re: Re: Outage", "<c@example.com>", "Tue, 2 Sep 2025 08:00:00 +0000", "<a@example.com>", "<b@example.com>").body("Fixed."));
+    server.add_message("INBOX", message("Other news", "<d@example.com>", "Mon, 1 Sep 2025 11:00:00 +0000"));
+    let fixture = fixture(&server, &pool, Some("thread"));
+
+    let items = process(&fixture, &pool).await;
+
+    assert_eq!(items.iter().map(|item| item.title.as_str()).collect::<Vec<_>>(), vec!["Outage", "Other news"]);
+    let body = items[0].email_body.as_deref().unwrap();
+    let order: Vec<usize> = ["Servers are down.", "Re: Outage (news@example.com, 2025-09-01 10:00 UTC)", "Still down.", "Fixed."]
+        .iter()
+        .map(|part| body.find(part).unwrap_or_else(|| panic!("{} missing from {}", part, body)))
+        .collect();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]), "{}", body);
+    assert_eq!(items[0].digest_message_ids.as_deref(), Some("<c@example.com>\n<b@example.com>"));
+    assert!(items[0].description.as_deref().unwrap().contains("Servers are down."));
+
+    // Fetched again after a UIDVALIDITY change, merged emails count as processed
+    server.set_uid_validity("INBOX", 7);
+    assert_eq!(process(&fixture, &pool).await.len(), 2);
+    let again = FeedItemOpsGeneric::get_by_id(&pool, items[0].id.as_ref().unwrap()).unwrap();
+    assert_eq!(again.email_body, items[0].email_body);
+}
+
+#[tokio::test]
+async fn test_day_digest_merges_emails_of_a_day() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", message("Morning", "<a@example.com>", "Mon, 1 Sep 2025 08:00:00 +0000"));
+    server.add_message("INBOX", message("Noon", "<b@example.com>", "Mon, 1 Sep 2025 12:00:00 +0000"));
+    server.add_message("INBOX", message("Evening", "<c@example.com>", "Mon, 1 Sep 2025 20:00:00 +0000"));
+    server.add_message("INBOX", message("Next day", "<d@example.com>", "Tue, 2 Sep 2025 08:00:00 +0000"));
+    let fixture = fixture(&server, &pool, Some("day"));
+
+    let items = process(&fixture, &pool).await;
+
+    let titles: Vec<&str> = items.iter().map(|item| item.title.as_str()).collect();
+    assert_eq!(titles.len(), 2, "{:?}", titles);
+    assert!(titles.contains(&"Next day"));
+    let digest = items.iter().find(|item| item.title.ends_with("(+2 more)")).unwrap();
+    assert_eq!(digest.digest_message_ids.as_deref().unwrap().lines().count(), 2);
+    for subject in ["Morning", "Noon", "Evening"] {
+        assert!(digest.email_body.as_deref().unwrap().contains(&format!("Body of {}", subject)));
+    }
+}
+
+#[tokio::test]
+async fn test_digest_mode_is_validated() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    let fixture = fixture(&server, &pool, None);
+    let feed: &Feed = fixture.feed("News");
+    let app = app(pool);
+
+    let put = |digest_mode: &str| {
+        let body = json!({
+            "title": feed.title,
+            "email_rule_id": feed.email_rule_id,
+            "feed_type": feed.feed_type,
+            "is_active": feed.is_active,
+            "digest_mode": digest_mode,
+        });
+        app.clone().oneshot(Request::builder()
+            .method(Method::PUT)
+            .uri(format!("/api/feeds/{}", feed.id.as_deref().unwrap()))
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap())
+    };
+
+    let response = put("Thread").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+    assert_eq!(body["digest_mode"], "thread");
+
+    let response = put("weekly").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
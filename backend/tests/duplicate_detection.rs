@@ -1,12 +1,15 @@
-use mail2feed_backend::db::{models::*, operations::*};
+use mail2feed_backend::db::{connection::DatabasePool, models::*, operations::*, operations_generic::FeedItemOpsGeneric};
+use mail2feed_backend::feed::dedup;
 use mail2feed_backend::db::schema::feed_items;
 use mail2feed_backend::imap::client::Email;
 use diesel::prelude::*;
-use diesel::connection::SimpleConnection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use diesel::r2d2::{ConnectionManager, Pool};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
 /// Helper to create a test database
 fn create_test_database() -> Pool<ConnectionManager<SqliteConnection>> {
     let db_name = format!("test_duplicate_detection_{}.db", Uuid::new_v4());
@@ -15,72 +18,8 @@ fn create_test_database() -> Pool<ConnectionManager<SqliteConnection>> {
     // Create database and run migrations  
     let mut connection = SqliteConnection::establish(&database_url).unwrap();
     
-    // Create tables with direct SQL (simplified approach for testing)
-    connection.batch_execute(r#"
-        CREATE TABLE imap_accounts (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            host TEXT NOT NULL,
-            port INTEGER NOT NULL,
-            username TEXT NOT NULL,
-            password TEXT NOT NULL,
-            use_tls BOOLEAN NOT NULL DEFAULT TRUE,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
-        );
-        
-        CREATE TABLE email_rules (
-            id TEXT PRIMARY KEY,
-            name TEXT NOT NULL,
-            imap_account_id TEXT NOT NULL,
-            folder TEXT NOT NULL DEFAULT 'INBOX',
-            to_address TEXT,
-            from_address TEXT,
-            subject_contains TEXT,
-            label TEXT,
-            is_active BOOLEAN NOT NULL DEFAULT TRUE,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            post_process_action TEXT NOT NULL DEFAULT 'mark_read',
-            move_to_folder TEXT,
-            FOREIGN KEY (imap_account_id) REFERENCES imap_accounts(id) ON DELETE CASCADE
-        );
-        
-        CREATE TABLE feeds (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            description TEXT,
-            link TEXT,
-            email_rule_id TEXT NOT NULL,
-            feed_type TEXT NOT NULL DEFAULT 'rss',
-            is_active BOOLEAN NOT NULL DEFAULT TRUE,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            max_items INTEGER DEFAULT 100,
-            max_age_days INTEGER DEFAULT 30,
-            min_items INTEGER DEFAULT 10,
-            FOREIGN KEY (email_rule_id) REFERENCES email_rules(id) ON DELETE CASCADE
-        );
-        
-        CREATE TABLE feed_items (
-            id TEXT PRIMARY KEY,
-            feed_id TEXT NOT NULL,
-            title TEXT NOT NULL,
-            description TEXT,
-            link TEXT,
-            author TEXT,
-            pub_date TEXT NOT NULL,
-            email_message_id TEXT,
-            email_subject TEXT,
-            email_from TEXT,
-            email_body TEXT,
-            created_at TEXT NOT NULL,
-            is_read BOOLEAN DEFAULT FALSE,
-            starred BOOLEAN DEFAULT FALSE,
-            body_size INTEGER DEFAULT 0,
-            FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE
-        );
-    "#).unwrap();
+    // Create tables by running the real migrations so the schema stays in sync
+    connection.run_pending_migrations(MIGRATIONS).unwrap();
     
     // Create connection pool
     let manager = ConnectionManager::<SqliteConnection>::new(&database_url);
@@ -104,6 +43,18 @@ fn setup_test_data(pool: &Pool<ConnectionManager<SqliteConnection>>) -> (String,
         updated_at: Utc::now().to_rfc3339(),
         default_post_process_action: "do_nothing".to_string(),
         default_move_to_folder: None,
+        max_bytes_per_second: None,
+        tls_pin: None,
+        tls_server_name: None,
+        security: "ssl_tls".to_string(),
+        quota_group_id: None,
+        max_feeds: None,
+        max_items: None,
+        max_processing_minutes_per_day: None,
+        max_connections_per_hour: None,
+        min_fetch_delay_seconds: None,
+        max_messages_per_fetch: None,
+        protocol: "imap".to_string(),
     };
     
     let created_account = ImapAccountOps::create(&mut conn, &account).unwrap();
@@ -124,6 +75,19 @@ fn setup_test_data(pool: &Pool<ConnectionManager<SqliteConnection>>) -> (String,
         updated_at: Utc::now().to_rfc3339(),
         post_process_action: "mark_read".to_string(),
         move_to_folder: None,
+        observe_only: false,
+        importance: None,
+        processing_order: "newest_first".to_string(),
+        post_process_delay_hours: None,
+        match_expression: None,
+        fetch_limit: None,
+        include_seen: true,
+        include_subfolders: false,
+        strip_footers: false,
+        strip_quoted_replies: false,
+        strip_tracking_pixels: true,
+        unwrap_tracking_links: false,
+        post_process_actions: None,
     };
     
     let created_rule = EmailRuleOps::create(&mut conn, &rule).unwrap();
@@ -143,6 +107,29 @@ fn setup_test_data(pool: &Pool<ConnectionManager<SqliteConnection>>) -> (String,
         max_items: Some(100),
         max_age_days: Some(30),
         min_items: Some(10),
+        summary_length: None,
+        locale: None,
+        timezone: None,
+        title_template: None,
+        webhook_url: None,
+        webhook_method: None,
+        webhook_body: None,
+        public_access: None,
+        max_pinned: None,
+        auto_titles: None,
+        page_css: None,
+        page_header_html: None,
+        page_footer_html: None,
+        page_logo_url: None,
+        append_only: false,
+        chain_head: None,
+        digest_mode: None,
+        description_template: None,
+        item_limit: None,
+        full_content: None,
+        auth_username: None,
+        auth_password_hash: None,
+        dedup_scope: None,
     };
     
     let created_feed = FeedOps::create(&mut conn, &feed).unwrap();
@@ -162,9 +149,24 @@ fn create_test_email(uid: u32, subject: &str, from: &str, message_id: &str, date
         date,
         body: "Test email body".to_string(),
         is_seen: false,
+        importance: None,
+        category: None,
+        labels: None,
+        content_type: None,
+        transfer_encoding: None,
+        list_unsubscribe: None,
+        list_unsubscribe_post: None,
+        in_reply_to: None,
+        references: None,
     }
 }
 
+/// Create an item through the database abstraction, returning its ID
+fn create_feed_item(pool: &Pool<ConnectionManager<SqliteConnection>>, new_item: NewFeedItem) -> anyhow::Result<String> {
+    let item = FeedItemOpsGeneric::create(&DatabasePool::SQLite(pool.clone()), &new_item)?;
+    item.id.ok_or_else(|| anyhow::anyhow!("Created item has no ID"))
+}
+
 #[tokio::test]
 async fn test_duplicate_detection_with_message_id() {
     let pool = create_test_database();
@@ -364,6 +366,29 @@ async fn test_duplicate_detection_across_different_feeds() {
         max_items: Some(100),
         max_age_days: Some(30),
         min_items: Some(10),
+        summary_length: None,
+        locale: None,
+        timezone: None,
+        title_template: None,
+        webhook_url: None,
+        webhook_method: None,
+        webhook_body: None,
+        public_access: None,
+        max_pinned: None,
+        auto_titles: None,
+        page_css: None,
+        page_header_html: None,
+        page_footer_html: None,
+        page_logo_url: None,
+        append_only: false,
+        chain_head: None,
+        digest_mode: None,
+        description_template: None,
+        item_limit: None,
+        full_content: None,
+        auth_username: None,
+        auth_password_hash: None,
+        dedup_scope: None,
     };
     
     let created_feed2 = FeedOps::create(&mut conn, &feed2).unwrap();
@@ -489,6 +514,122 @@ async fn test_database_operations_direct() {
     assert_ne!(item_id, item_id2, "Items should have different IDs");
 }
 
+#[tokio::test]
+async fn test_cross_feed_links_survive_canonical_removal() {
+    let pool = create_test_database();
+    let (_account_id, rule_id, feed_id1) = setup_test_data(&pool);
+    let db = DatabasePool::SQLite(pool.clone());
+    let mut conn = pool.get().unwrap();
+    
+    let feed2 = FeedOps::create(&mut conn, &NewFeed::new(
+        "Second Feed".to_string(),
+        None,
+        None,
+        rule_id,
+        "rss".to_string(),
+        true,
+    )).unwrap();
+    let feed_id2 = feed2.id.unwrap();
+    
+    let hash = dedup::content_hash("Announcement", "news@example.com", "Big   news\ntoday");
+    assert_eq!(hash, dedup::content_hash(" announcement ", "NEWS@example.com", "Big news today"));
+    
+    // Canonical copy in the first feed
+    let mut canonical = NewFeedItem::new(
+        feed_id1.clone(),
+        "Announcement".to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        Some("<announce@example.com>".to_string()),
+        None,
+        Some("news@example.com".to_string()),
+        Some("Big news today".to_string()),
+    );
+    canonical.content_hash = Some(hash.clone());
+    let canonical = FeedItemOps::create(&mut conn, &canonical).unwrap();
+    let canonical_id = canonical.id.clone().unwrap();
+    
+    // The same email arriving for the second feed links to it
+    let found = dedup::find_canonical(&db, &feed_id2, "<announce@example.com>", &hash).unwrap();
+    assert_eq!(found.and_then(|item| item.id), Some(canonical_id.clone()));
+    assert!(dedup::find_canonical(&db, &feed_id1, "<announce@example.com>", &hash).unwrap().is_none(),
+            "Items in the same feed are not cross-feed duplicates");
+    
+    let mut linked = NewFeedItem::new(
+        feed_id2.clone(),
+        "Announcement".to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        Some("<announce@example.com>".to_string()),
+        None,
+        Some("news@example.com".to_string()),
+        None,
+    );
+    linked.content_hash = Some(hash);
+    linked.canonical_item_id = Some(canonical_id.clone());
+    let linked = FeedItemOps::create(&mut conn, &linked).unwrap();
+    let linked_id = linked.id.clone().unwrap();
+    assert_eq!(FeedItemOps::count_references(&mut conn, &canonical_id).unwrap(), 1);
+    
+    let mut items = vec![linked];
+    dedup::resolve_bodies(&db, &mut items);
+    assert_eq!(items[0].email_body.as_deref(), Some("Big news today"));
+    
+    // Removing the canonical copy hands its content to the linked item
+    drop(conn);
+    dedup::remove_item(&db, &canonical).unwrap();
+    let mut conn = pool.get().unwrap();
+    assert!(FeedItemOps::get_by_id(&mut conn, &canonical_id).is_err());
+    let promoted = FeedItemOps::get_by_id(&mut conn, &linked_id).unwrap();
+    assert!(promoted.canonical_item_id.is_none());
+    assert_eq!(promoted.email_body.as_deref(), Some("Big news today"));
+}
+
+#[test]
+fn test_is_duplicate_by_message_id_digest_fields_or_content() {
+    let pool = create_test_database();
+    let (_account_id, _rule_id, feed_id) = setup_test_data(&pool);
+    let database = DatabasePool::SQLite(pool.clone());
+    let email_date = Utc::now();
+    let date = email_date.to_rfc3339();
+
+    let mut new_item = NewFeedItem::new(
+        feed_id.clone(),
+        "Weekly".to_string(),
+        None,
+        None,
+        None,
+        email_date,
+        Some("<weekly@example.com>".to_string()),
+        Some("Weekly".to_string()),
+        Some("sender@example.com".to_string()),
+        None,
+    );
+    new_item.digest_message_ids = Some("<reply@example.com>".to_string());
+    let hash = dedup::content_hash("Weekly", "sender@example.com", "This week's news");
+    new_item.content_hash = Some(hash.clone());
+    create_feed_item(&pool, new_item).unwrap();
+
+    let is_duplicate = |message_id: &str, title: &str| {
+        FeedItemOpsGeneric::is_duplicate(&database, &feed_id, message_id, title, "sender@example.com", &date, "").unwrap()
+    };
+    assert!(is_duplicate("<weekly@example.com>", "Other title"));
+    assert!(is_duplicate("<reply@example.com>", "Re: Weekly"));
+    // Without a match by Message-ID, title, sender and date decide
+    assert!(is_duplicate("<other@example.com>", "Weekly"));
+    assert!(is_duplicate("", "Weekly"));
+    assert!(!is_duplicate("<other@example.com>", "Other title"));
+    assert!(!FeedItemOpsGeneric::is_duplicate(&database, "other-feed", "<weekly@example.com>", "Weekly", "sender@example.com", &date, "").unwrap());
+    // Else a resent copy with another Message-ID and date matches by content
+    let later = (email_date + chrono::Duration::minutes(3)).to_rfc3339();
+    assert!(FeedItemOpsGeneric::is_duplicate(&database, &feed_id, "<resent@example.com>", "Weekly", "sender@example.com", &later, &hash).unwrap());
+    assert!(!FeedItemOpsGeneric::is_duplicate(&database, &feed_id, "<resent@example.com>", "Weekly", "sender@example.com", &later, "other").unwrap());
+}
+
 #[test]
 fn test_email_struct_creation() {
     let email_date = Utc::now();
@@ -0,0 +1,127 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::FeedOpsGeneric;
+use mail2feed_backend::testing::{Fixture, TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn put(app: &axum::Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(Request::builder().method(Method::PUT).uri(uri).header("Content-Type", "application/json").body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+/// Status, `WWW-Authenticate` and `Cache-Control` of a feed request sending `authorization`
+async fn get_feed(app: &axum::Router, uri: &str, authorization: Option<&str>) -> (StatusCode, Option<String>, Option<String>) {
+    let mut request = Request::builder().method(Method::GET).uri(uri);
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let header_value = |name| response.headers().get(name).map(|value| value.to_str().unwrap().to_string());
+    (response.status(), header_value(header::WWW_AUTHENTICATE), header_value(header::CACHE_CONTROL))
+}
+
+fn settings(fixture: &Fixture, credentials: Value) -> Value {
+    let mut settings = json!({
+        "title": "Private News",
+        "email_rule_id": fixture.rule("News").id,
+        "feed_type": "rss",
+        "is_active": true,
+    });
+    settings.as_object_mut().unwrap().extend(credentials.as_object().unwrap().clone());
+    settings
+}
+
+#[tokio::test]
+async fn test_protected_feed_requires_credentials() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Mail")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News").with_item("First issue")))
+        .insert(&pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let app = app(pool.clone());
+    let rss = format!("/feeds/{}/rss", feed_id);
+
+    let (status, body) = put(&app, &format!("/api/feeds/{}", feed_id), settings(&fixture, json!({"auth_username": "reader", "auth_password": "s3cret"}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["auth_username"], "reader");
+    assert!(body.get("auth_password_hash").is_none() && body.get("auth_password").is_none(), "{}", body);
+    let stored = FeedOpsGeneric::get_by_id(&pool, &feed_id).unwrap().auth_password_hash.unwrap();
+    assert!(stored.starts_with("$argon2") && !stored.contains("s3cret"));
+
+    let (status, challenge, _) = get_feed(&app, &rss, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(challenge.as_deref(), Some("Basic realm=\"Private News\", charset=\"UTF-8\""));
+
+    // "reader:wrong" and "someone:s3cret"
+    for wrong in ["Basic cmVhZGVyOndyb25n", "Basic c29tZW9uZTpzM2NyZXQ=", "Bearer s3cret"] {
+        let (status, challenge, _) = get_feed(&app, &format!("/feeds/{}/atom", feed_id), Some(wrong)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", wrong);
+        assert!(challenge.is_some());
+    }
+
+    // "reader:s3cret"
+    let (status, _, cache_control) = get_feed(&app, &rss, Some("Basic cmVhZGVyOnMzY3JldA==")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cache_control.unwrap().starts_with("private"));
+
+    // Renaming the user keeps the password; removing the username opens the feed again
+    let (status, _) = put(&app, &format!("/api/feeds/{}", feed_id), settings(&fixture, json!({"auth_username": "friend"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    // "friend:s3cret"
+    assert_eq!(get_feed(&app, &rss, Some("Basic ZnJpZW5kOnMzY3JldA==")).await.0, StatusCode::OK);
+
+    let (status, body) = put(&app, &format!("/api/feeds/{}", feed_id), settings(&fixture, json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["auth_username"].is_null());
+    let (status, _, cache_control) = get_feed(&app, &rss, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cache_control.unwrap().starts_with("public"));
+    assert!(FeedOpsGeneric::get_by_id(&pool, &feed_id).unwrap().auth_password_hash.is_none());
+}
+
+#[tokio::test]
+async fn test_invalid_credentials_are_refused() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Mail")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let app = app(pool);
+
+    for (credentials, error) in [
+        (json!({"auth_username": "reader"}), "auth_password is required"),
+        (json!({"auth_password": "s3cret"}), "needs an auth_username"),
+        (json!({"auth_username": "read:er", "auth_password": "s3cret"}), "auth_username"),
+        (json!({"auth_username": "reader", "auth_password": ""}), "auth_password"),
+    ] {
+        let (status, body) = put(&app, &format!("/api/feeds/{}", feed_id), settings(&fixture, credentials)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains(error), "{}", body);
+    }
+}
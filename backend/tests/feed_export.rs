@@ -0,0 +1,117 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::api::types::{ExportResult, JobResponse, JobStartedResponse, JobStatus};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::testing::{Fixture, TestAccount, TestFeed, TestRule};
+use std::io::{Cursor, Read};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+/// Status, `Content-Type`, `Content-Disposition` and body of a GET
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Option<String>, Option<String>, Vec<u8>) {
+    let request = Request::builder().method(Method::GET).uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let header_value = |name| response.headers().get(name).map(|value| value.to_str().unwrap().to_string());
+    let (status, content_type, disposition) = (response.status(), header_value(header::CONTENT_TYPE), header_value(header::CONTENT_DISPOSITION));
+    (status, content_type, disposition, hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec())
+}
+
+fn weekly(pool: &DatabasePool) -> Fixture {
+    TestAccount::new("Mail")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("Weekly News").with_item("First issue").with_item("Second issue")))
+        .insert(pool)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_small_feed_is_exported_within_the_request() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = weekly(&pool);
+    let feed_id = fixture.feed("Weekly News").id.clone().unwrap();
+    let app = app(pool);
+
+    let (status, content_type, disposition, body) = get(&app, &format!("/api/feeds/{}/export", feed_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("application/epub+zip"));
+    assert_eq!(disposition.as_deref(), Some("attachment; filename=\"weekly-news.epub\""));
+    let mut epub = zip::ZipArchive::new(Cursor::new(body)).unwrap();
+    let mut package = String::new();
+    epub.by_name("OEBPS/content.opf").unwrap().read_to_string(&mut package).unwrap();
+    assert!(package.contains("<dc:title>Weekly News</dc:title>"), "{}", package);
+    assert!(package.contains("<itemref idref=\"item-2\"/>"), "{}", package);
+
+    let (status, content_type, _, body) = get(&app, &format!("/api/feeds/{}/export?format=html", feed_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("text/html; charset=utf-8"));
+    let page = String::from_utf8(body).unwrap();
+    assert!(page.contains("<a href=\"#item-1\">First issue</a>") && page.contains("<a href=\"#item-2\">Second issue</a>"), "{}", page);
+
+    let (status, _, _, body) = get(&app, &format!("/api/feeds/{}/export?format=mbox", feed_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let mbox = String::from_utf8(body).unwrap();
+    assert_eq!(mbox.lines().filter(|line| line.starts_with("From ")).count(), 2, "{}", mbox);
+    assert!(mbox.contains("\nSubject: First issue\n"), "{}", mbox);
+
+    let (status, ..) = get(&app, &format!("/api/feeds/{}/export?format=pdf", feed_id)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, ..) = get(&app, "/api/feeds/missing/export").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_background_export_is_downloaded_once_finished() {
+    let exports = tempfile::tempdir().unwrap();
+    std::env::set_var("EXPORT_PATH", exports.path());
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = weekly(&pool);
+    let feed_id = fixture.feed("Weekly News").id.clone().unwrap();
+    let app = app(pool);
+
+    let (status, _, _, body) = get(&app, &format!("/api/feeds/{}/export?format=mbox&background=true", feed_id)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let started: JobStartedResponse = serde_json::from_slice(&body).unwrap();
+
+    let mut job = None;
+    for _ in 0..100 {
+        let (_, _, _, body) = get(&app, &format!("/api/jobs/{}", started.job_id)).await;
+        let response: JobResponse = serde_json::from_slice(&body).unwrap();
+        if response.finished_at.is_some() {
+            job = Some(response);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let job = job.expect("export job did not finish");
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.error);
+    assert_eq!(job.target_id.as_deref(), Some(feed_id.as_str()));
+    let result: ExportResult = serde_json::from_value(job.result.unwrap()).unwrap();
+    assert_eq!((result.items, result.filename.as_str()), (2, "weekly-news.mbox"));
+    assert_eq!(result.download_url, format!("/api/exports/{}", started.job_id));
+
+    let (status, content_type, disposition, body) = get(&app, &result.download_url).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("application/mbox"));
+    assert_eq!(disposition.as_deref(), Some("attachment; filename=\"weekly-news.mbox\""));
+    assert_eq!(body.len(), result.bytes);
+    assert!(exports.path().join(format!("{}.mbox", started.job_id)).exists());
+
+    let (status, ..) = get(&app, "/api/exports/missing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
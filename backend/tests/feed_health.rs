@@ -0,0 +1,106 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::health;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_feed(pool: &DbPool) -> (String, Feed) {
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("Feed").with_item("Weekly issue")))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    (fixture.account_id(), fixture.feed("Feed").clone())
+}
+
+fn record_run(conn: &mut SqliteConnection, account_id: &str, hours_ago: i64, status: ProcessingRunStatus) {
+    let mut new_run = NewProcessingRun::new(account_id.to_string());
+    new_run.started_at = (Utc::now() - Duration::hours(hours_ago)).to_rfc3339();
+    let run = ProcessingRunOps::create(conn, &new_run).unwrap();
+    let error = (status == ProcessingRunStatus::Failed).then(|| "Authentication failed".to_string());
+    ProcessingRunOps::finish(conn, run.id.as_ref().unwrap(), &status, 0, 0, error).unwrap();
+}
+
+#[test]
+fn test_status_item_while_account_fails() {
+    let pool = setup_test_db();
+    let database = DatabasePool::SQLite(pool.clone());
+    let (account_id, feed) = create_feed(&pool);
+    {
+        let mut conn = pool.get().unwrap();
+        record_run(&mut conn, &account_id, 50, ProcessingRunStatus::Completed);
+        record_run(&mut conn, &account_id, 2, ProcessingRunStatus::Failed);
+    }
+
+    let item = health::status_item(&database, &feed, 24, Utc::now()).unwrap().unwrap();
+    assert!(item.title.starts_with("mail2feed status: Work Mail"), "{}", item.title);
+    assert!(item.description.as_deref().unwrap().contains("Authentication failed"));
+    // The same item for as long as the outage lasts
+    let later = health::status_item(&database, &feed, 24, Utc::now() + Duration::hours(5)).unwrap().unwrap();
+    assert_eq!(later.id, item.id);
+    assert_eq!(later.pub_date, item.pub_date);
+
+    // Not failing for long enough yet
+    assert!(health::status_item(&database, &feed, 72, Utc::now()).unwrap().is_none());
+
+    // A completed run ends the outage
+    {
+        let mut conn = pool.get().unwrap();
+        record_run(&mut conn, &account_id, 0, ProcessingRunStatus::Completed);
+    }
+    assert!(health::status_item(&database, &feed, 24, Utc::now() + Duration::hours(30)).unwrap().is_none());
+}
+
+#[test]
+fn test_no_status_item_without_failures() {
+    let pool = setup_test_db();
+    let database = DatabasePool::SQLite(pool.clone());
+    let (account_id, feed) = create_feed(&pool);
+    {
+        let mut conn = pool.get().unwrap();
+        record_run(&mut conn, &account_id, 50, ProcessingRunStatus::Completed);
+    }
+    // A quiet account is not a failing one
+    assert!(health::status_item(&database, &feed, 24, Utc::now()).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_status_item_heads_generated_feed() {
+    std::env::set_var("FEED_HEALTH_WARNING_HOURS", "24");
+    let pool = setup_test_db();
+    let (account_id, feed) = create_feed(&pool);
+    {
+        let mut conn = pool.get().unwrap();
+        record_run(&mut conn, &account_id, 40, ProcessingRunStatus::Completed);
+        record_run(&mut conn, &account_id, 10, ProcessingRunStatus::Failed);
+    }
+    let feed_id = feed.id.unwrap();
+
+    let request = Request::builder().uri(format!("/feeds/{}/rss", feed_id)).body(Body::empty()).unwrap();
+    let response = app(pool).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let rss = String::from_utf8(body.to_vec()).unwrap();
+    let status_at = rss.find("mail2feed status: Work Mail has not been checked for over 24 hours").expect(&rss);
+    assert!(status_at < rss.find("Weekly issue").unwrap(), "{}", rss);
+}
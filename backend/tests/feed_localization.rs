@@ -0,0 +1,288 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::DateTime;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{Feed, FeedItem};
+use mail2feed_backend::feed::generator::FeedGenerator;
+use mail2feed_backend::feed::item_templates;
+use mail2feed_backend::feed::localization::{self, FeedLocalization};
+use mail2feed_backend::feed::titles;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app() -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(setup_test_db()), background_handle)
+}
+
+async fn post(app: &axum::Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn feed(locale: Option<&str>, timezone: Option<&str>, title_template: Option<&str>) -> Feed {
+    Feed {
+        id: Some("feed-1".to_string()),
+        title: "Newsletters".to_string(),
+        description: None,
+        link: None,
+        email_rule_id: "rule-1".to_string(),
+        feed_type: "rss".to_string(),
+        is_active: true,
+        created_at: "2025-08-01T00:00:00+00:00".to_string(),
+        updated_at: "2025-08-01T00:00:00+00:00".to_string(),
+        max_items: None,
+        max_age_days: None,
+        min_items: None,
+        summary_length: None,
+        locale: locale.map(str::to_string),
+        timezone: timezone.map(str::to_string),
+        title_template: title_template.map(str::to_string),
+        webhook_url: None,
+        webhook_method: None,
+        webhook_body: None,
+        public_access: None,
+        max_pinned: None,
+        auto_titles: None,
+        page_css: None,
+        page_header_html: None,
+        page_footer_html: None,
+        page_logo_url: None,
+        append_only: false,
+        chain_head: None,
+        digest_mode: None,
+        description_template: None,
+        item_limit: None,
+        full_content: None,
+        auth_username: None,
+        auth_password_hash: None,
+        dedup_scope: None,
+    }
+}
+
+fn item() -> FeedItem {
+    FeedItem {
+        id: Some("item-1".to_string()),
+        feed_id: "feed-1".to_string(),
+        title: "Weekly update".to_string(),
+        description: Some("<p>Hello</p>".to_string()),
+        link: None,
+        author: Some("news@example.com".to_string()),
+        pub_date: "2025-08-03T13:02:03+00:00".to_string(),
+        email_message_id: None,
+        email_subject: Some("Weekly update".to_string()),
+        email_from: Some("news@example.com".to_string()),
+        email_body: None,
+        created_at: "2025-08-03T13:05:00+00:00".to_string(),
+        is_read: Some(false),
+        starred: Some(false),
+        body_size: None,
+        processing_run_id: None,
+        content_hash: None,
+        canonical_item_id: None,
+        language: None,
+        pinned: false,
+        importance: None,
+        category: None,
+        chain_previous: None,
+        chain_hash: None,
+        email_body_html: None,
+        body_ref: None,
+        rating: None,
+        origin_feed_id: None,
+        unsubscribe_url: None,
+        unsubscribe_mailto: None,
+        unsubscribe_one_click: false,
+        in_reply_to: None,
+        email_references: None,
+        thread_id: None,
+        digest_message_ids: None,
+        guid: None,
+        sync_id: None,
+    }
+}
+
+/// Text between the first `<tag>` and `</tag>`
+fn element<'a>(xml: &'a str, tag: &str) -> &'a str {
+    let start = xml.find(&format!("<{}>", tag)).unwrap() + tag.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", tag)).unwrap() + start;
+    &xml[start..end]
+}
+
+#[test]
+fn test_rss_pub_date_is_rfc_822() {
+    let rss = FeedGenerator::generate_rss(&feed(Some("de_DE"), Some("Europe/Berlin"), None), &[item()]).unwrap();
+
+    let pub_date = element(&rss, "pubDate");
+    assert_eq!(pub_date, "Sun, 3 Aug 2025 13:02:03 +0000");
+    assert!(DateTime::parse_from_rfc2822(pub_date).is_ok());
+}
+
+#[test]
+fn test_display_dates_use_feed_locale_and_timezone() {
+    let localization = FeedLocalization::for_feed(&feed(Some("de-DE"), Some("Europe/Berlin"), None));
+    let date = DateTime::parse_from_rfc3339("2025-08-03T13:02:03Z").unwrap().into();
+
+    let display = localization.format_date(date);
+    assert!(display.contains("15:02:03"), "expected Berlin time in '{}'", display);
+    assert!(display.contains("So"), "expected German weekday in '{}'", display);
+
+    let rss = FeedGenerator::generate_rss(&feed(Some("de_DE"), Some("Europe/Berlin"), None), &[item()]).unwrap();
+    assert!(rss.contains(&format!("<time datetime=\"2025-08-03T13:02:03+00:00\">{}</time>", display)));
+
+    // Atom timestamps stay machine-readable
+    let atom = FeedGenerator::generate_atom(&feed(Some("de_DE"), Some("Europe/Berlin"), None), &[item()]).unwrap();
+    assert!(atom.contains("<published>2025-08-03T13:02:03+00:00</published>"));
+}
+
+#[test]
+fn test_items_are_unchanged_without_localization() {
+    let rss = FeedGenerator::generate_rss(&feed(None, None, None), &[item()]).unwrap();
+
+    assert_eq!(element(&rss, "title"), "Newsletters");
+    assert!(rss.contains("<title>Weekly update</title>"));
+    assert!(!rss.contains("<time"));
+}
+
+#[test]
+fn test_title_template() {
+    let feed = feed(Some("fr_FR"), Some("America/New_York"), Some("[{feed}] {subject} — {from} ({date})"));
+    let localization = FeedLocalization::for_feed(&feed);
+
+    let title = item_templates::render_title(&feed, &item(), &localization);
+    let date = localization.format_stored_date("2025-08-03T13:02:03+00:00").unwrap();
+    assert_eq!(title, format!("[Newsletters] Weekly update — news@example.com ({})", date));
+    assert!(date.contains("09:02:03"), "expected New York time in '{}'", date);
+
+    let atom = FeedGenerator::generate_atom(&feed, &[item()]).unwrap();
+    assert!(atom.contains("[Newsletters] Weekly update"));
+}
+
+#[test]
+fn test_item_templates() {
+    let mut feed = feed(None, Some("Europe/Berlin"), Some("{{subject}} — {{from_name}}"));
+    feed.description_template = Some("<p>{{from_name}} &lt;{{from_address}}&gt; in {{folder}}</p>{{summary}}".to_string());
+    let mut item = item();
+    item.email_from = Some("\"Rust & Friends\" <News@Example.com>".to_string());
+    let localization = FeedLocalization::for_feed(&feed);
+
+    assert_eq!(item_templates::render_title(&feed, &item, &localization), "Weekly update — Rust & Friends");
+    assert_eq!(
+        item_templates::render_description(&feed, &item, &localization).unwrap(),
+        "<p>Rust &amp; Friends &lt;news@example.com&gt; in </p><p>Hello</p>"
+    );
+
+    // The localized date still heads the templated description
+    let rss = FeedGenerator::generate_rss(&feed, &[item]).unwrap();
+    assert!(rss.contains("Weekly update — Rust &amp; Friends"));
+    assert!(rss.contains("15:02:03 2025</time></p><p>Rust &amp; Friends &lt;news@example.com&gt; in </p>"), "{}", rss);
+}
+
+#[test]
+fn test_subjectless_items_are_titled_from_body() {
+    let body = "Hi,\n\nYour nightly backup completed without errors. 3 GB were copied.";
+    let mut feed = feed(None, None, Some("{subject} ({from})"));
+    assert_eq!(titles::item_title(&feed, "[Email UID: 7]", body), "Your nightly backup completed without errors");
+    assert_eq!(titles::item_title(&feed, "Backup report", body), "Backup report");
+
+    // The template's {subject} falls back to the derived title
+    let mut subjectless = item();
+    subjectless.title = titles::item_title(&feed, "", body);
+    subjectless.email_subject = Some(String::new());
+    let localization = FeedLocalization::for_feed(&feed);
+    assert_eq!(item_templates::render_title(&feed, &subjectless, &localization), "Your nightly backup completed without errors (news@example.com)");
+
+    feed.auto_titles = Some(false);
+    assert_eq!(titles::item_title(&feed, "[Email UID: 7]", body), "[Email UID: 7]");
+}
+
+#[test]
+fn test_locale_and_timezone_validation() {
+    assert!(localization::parse_locale("pt_BR").is_ok());
+    assert!(localization::parse_locale("xx_YY").is_err());
+    assert!(localization::parse_timezone("Asia/Tokyo").is_ok());
+    assert!(localization::parse_timezone("Mars/Olympus").is_err());
+}
+
+#[tokio::test]
+async fn test_feed_api_validates_localization() {
+    let app = app();
+
+    let (_, account) = post(&app, "/api/imap-accounts", json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    let (_, rule) = post(&app, "/api/email-rules", json!({
+        "name": "Newsletters",
+        "imap_account_id": account["id"],
+        "folder": "INBOX",
+        "is_active": true
+    })).await;
+
+    let feed = |locale: &str, timezone: &str| json!({
+        "title": "Newsletters",
+        "email_rule_id": rule["id"],
+        "feed_type": "rss",
+        "is_active": true,
+        "locale": locale,
+        "timezone": timezone,
+        "title_template": "{subject} ({date})"
+    });
+
+    let (status, body) = post(&app, "/api/feeds", feed("de_DE", "Mars/Olympus")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("Mars/Olympus"));
+
+    let (status, _) = post(&app, "/api/feeds", feed("xx_YY", "Europe/Berlin")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, created) = post(&app, "/api/feeds", feed("de_DE", "Europe/Berlin")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["locale"], "de_DE");
+    assert_eq!(created["timezone"], "Europe/Berlin");
+    assert_eq!(created["title_template"], "{subject} ({date})");
+
+    let mut templated = feed("de_DE", "Europe/Berlin");
+    templated["title_template"] = json!("{{subject}} — {{sender}}");
+    let (status, body) = post(&app, "/api/feeds", templated.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("'sender'"));
+
+    templated["title_template"] = json!("{{subject}} — {{from_name}}");
+    templated["description_template"] = json!("<p>{{folder}}</p>{{summary");
+    let (status, _) = post(&app, "/api/feeds", templated.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    templated["description_template"] = json!("<p>{{folder}}</p>{{summary}}");
+    let (status, created) = post(&app, "/api/feeds", templated).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["description_template"], "<p>{{folder}}</p>{{summary}}");
+}
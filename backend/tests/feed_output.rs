@@ -0,0 +1,130 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::NewFeedItem;
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::testing::{Fixture, TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn request(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, String) {
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app.clone()
+        .oneshot(Request::builder().method(method).uri(uri).header("Content-Type", "application/json").body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+/// A feed with three newsletters, each with a plain-text and an HTML body
+fn newsletters(pool: &DatabasePool) -> Fixture {
+    let fixture = TestAccount::new("Mail")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    for (age, issue) in ["First", "Second", "Third"].iter().rev().enumerate() {
+        let body = format!("{} issue: the long story of this week in open source.", issue);
+        let mut item = NewFeedItem::new(
+            feed_id.clone(),
+            format!("{} issue", issue),
+            Some(body.clone()),
+            None,
+            Some("news@example.com".to_string()),
+            Utc::now() - Duration::hours(age as i64),
+            None,
+            None,
+            None,
+            Some(body.clone()),
+        );
+        item.email_body_html = Some(format!("<h1>{} issue</h1>", issue));
+        FeedItemOpsGeneric::create(pool, &item).unwrap();
+    }
+    fixture
+}
+
+fn settings(fixture: &Fixture, output: Value) -> Value {
+    let mut settings = json!({
+        "title": "News",
+        "email_rule_id": fixture.rule("News").id,
+        "feed_type": "rss",
+        "is_active": true,
+    });
+    settings.as_object_mut().unwrap().extend(output.as_object().unwrap().clone());
+    settings
+}
+
+#[tokio::test]
+async fn test_feeds_carry_full_content_by_default() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = newsletters(&pool);
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let app = app(pool);
+
+    let (status, rss) = request(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rss.matches("<item>").count(), 3);
+    assert!(rss.contains("<content:encoded><![CDATA[<h1>Third issue</h1>"), "{}", rss);
+    assert!(rss.contains("Third issue: the long story of this week in open source."), "{}", rss);
+}
+
+#[tokio::test]
+async fn test_per_feed_item_limit_and_summaries() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = newsletters(&pool);
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let app = app(pool);
+
+    let output = json!({"item_limit": 2, "full_content": false, "summary_length": 20});
+    let (status, body) = request(&app, Method::PUT, &format!("/api/feeds/{}", feed_id), Some(settings(&fixture, output))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let feed: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!((feed["item_limit"].clone(), feed["full_content"].clone()), (json!(2), json!(false)));
+
+    // The two newest items, summarized to the feed's length, without HTML bodies
+    let (_, rss) = request(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), None).await;
+    assert_eq!(rss.matches("<item>").count(), 2);
+    assert!(rss.contains("Third issue") && rss.contains("Second issue") && !rss.contains("First issue"), "{}", rss);
+    assert!(!rss.contains("content:encoded"), "{}", rss);
+    assert!(rss.contains("<description><![CDATA[Third issue: the...]]></description>"), "{}", rss);
+
+    let (_, atom) = request(&app, Method::GET, &format!("/feeds/{}/atom", feed_id), None).await;
+    assert_eq!(atom.matches("<entry>").count(), 2);
+    assert!(atom.contains("<content type=\"html\">Third issue: the...</content>"), "{}", atom);
+    assert!(!atom.contains("&lt;h1&gt;") && !atom.contains("<summary"), "{}", atom);
+
+    // The API still returns the stored items in full
+    let (_, page) = request(&app, Method::GET, &format!("/api/feeds/{}/items", feed_id), None).await;
+    assert!(page.contains("<h1>First issue</h1>"), "{}", page);
+}
+
+#[tokio::test]
+async fn test_item_limit_must_be_positive() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = newsletters(&pool);
+    let app = app(pool);
+
+    let (status, body) = request(&app, Method::POST, "/api/feeds", Some(settings(&fixture, json!({"item_limit": 0})))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("item_limit"), "{}", body);
+}
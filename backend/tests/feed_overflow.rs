@@ -0,0 +1,270 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::overflow;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    FeedOps::create(conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap()
+}
+
+fn create_item(conn: &mut SqliteConnection, feed: &Feed, title: &str, body: &str) -> FeedItem {
+    FeedItemOps::create(conn, &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        Some(body.to_string()),
+        None,
+        Some("sender@example.com".to_string()),
+        Utc::now(),
+        Some(format!("<{}@example.com>", title)),
+        Some(title.to_string()),
+        Some("sender@example.com".to_string()),
+        Some(body.to_string()),
+    )).unwrap()
+}
+
+/// Promotional email well over the default cap
+fn huge_body() -> String {
+    let mut body = String::from("<html><body><p>Our biggest sale of the year starts today.</p>");
+    while body.len() <= overflow::DEFAULT_MAX_ITEM_BYTES {
+        body.push_str("<table><tr><td style=\"padding: 0\"><img src=\"https://example.com/spacer.gif\"></td></tr></table>");
+    }
+    body.push_str("<p>Unsubscribe</p></body></html>");
+    body
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, axum::http::HeaderMap, String) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .header("Host", "feeds.example.com")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, headers, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_oversized_items_link_to_item_page() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let feed_id = feed.id.clone().unwrap();
+    let small = create_item(&mut conn, &feed, "Weekly", "<p>Short and sweet.</p>");
+    let huge = create_item(&mut conn, &feed, "Sale", &huge_body());
+    let huge_id = huge.id.clone().unwrap();
+    drop(conn);
+    let app = app(pool);
+
+    let (status, _, rss) = get(&app, &format!("/feeds/{}/rss", feed_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(rss.len() < overflow::DEFAULT_MAX_ITEM_BYTES);
+    assert!(rss.contains(&format!("<![CDATA[{}]]>", small.description.unwrap())));
+    assert!(rss.contains("Our biggest sale of the year starts today."));
+    assert!(rss.contains(&format!("http://feeds.example.com/feeds/{}/items/{}", feed_id, huge_id)));
+
+    let (_, _, atom) = get(&app, &format!("/feeds/{}/atom", feed_id)).await;
+    assert!(atom.len() < overflow::DEFAULT_MAX_ITEM_BYTES);
+
+    // The item page and the API still have the complete body
+    let (status, headers, page) = get(&app, &format!("/feeds/{}/items/{}", feed_id, huge_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "text/html; charset=utf-8");
+    assert_eq!(headers["content-security-policy"], "sandbox");
+    assert!(page.contains(&huge_body()));
+
+    let (status, _, item) = get(&app, &format!("/api/feed-items/{}", huge_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let item: Value = serde_json::from_str(&item).unwrap();
+    assert_eq!(item["description"], huge_body());
+    assert_eq!(item["email_body"], huge_body());
+
+    // Items are only served under their own feed
+    let (status, _, _) = get(&app, &format!("/feeds/other-feed/items/{}", huge_id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_cap_items_keeps_small_items() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let small = create_item(&mut conn, &feed, "Weekly", "<p>Short and sweet.</p>");
+    let plain = create_item(&mut conn, &feed, "Notes", &"Fish & chips, 5 > 3. ".repeat(10));
+
+    let mut items = vec![small.clone(), plain.clone()];
+    overflow::cap_items(&mut items, 100, "https://mail2feed.example.com");
+
+    assert_eq!(items[0].description, small.description);
+    let capped = items[1].description.as_deref().unwrap();
+    assert!(capped.starts_with("<p>Fish &amp; chips, 5 &gt; 3."));
+    assert!(capped.contains(&format!(
+        "<a href=\"https://mail2feed.example.com/feeds/{}/items/{}\">",
+        feed.id.as_deref().unwrap(),
+        plain.id.as_deref().unwrap()
+    )));
+
+    // Plain-text bodies are shown preformatted on the item page
+    let page = overflow::render_item_page(&feed, &plain);
+    assert!(page.contains("<pre style=\"white-space: pre-wrap\">Fish &amp; chips, 5 &gt; 3."));
+}
+
+async fn put_feed(app: &axum::Router, feed: &Feed, branding: Value) -> (StatusCode, Value) {
+    let mut body = json!({
+        "title": feed.title,
+        "email_rule_id": feed.email_rule_id,
+        "feed_type": feed.feed_type,
+        "is_active": feed.is_active,
+    });
+    body.as_object_mut().unwrap().extend(branding.as_object().unwrap().clone());
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/feeds/{}", feed.id.as_deref().unwrap()))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_item_page_applies_feed_branding() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let item = create_item(&mut conn, &feed, "Weekly", "<p>Short and sweet.</p>");
+    drop(conn);
+    let app = app(pool);
+    let page_uri = format!("/feeds/{}/items/{}", feed.id.as_deref().unwrap(), item.id.as_deref().unwrap());
+
+    // Unbranded pages have no extra markup
+    let (_, _, page) = get(&app, &page_uri).await;
+    assert!(!page.contains("<style>") && !page.contains("<header>") && !page.contains("<footer>"));
+
+    let (status, updated) = put_feed(&app, &feed, json!({
+        "page_css": "body { font-family: Georgia, serif; max-width: 40em; }",
+        "page_header_html": "<p class=\"tagline\">Shared by the Reading Club</p>",
+        "page_footer_html": "<a href=\"https://club.example.com\">club.example.com</a>",
+        "page_logo_url": "https://club.example.com/logo.png",
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["page_logo_url"], "https://club.example.com/logo.png");
+
+    let (status, _, page) = get(&app, &page_uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("<style>body { font-family: Georgia, serif; max-width: 40em; }</style></head>"));
+    assert!(page.contains(
+        "<body><header><img class=\"feed-logo\" src=\"https://club.example.com/logo.png\" alt=\"Test Feed\"><p class=\"tagline\">Shared by the Reading Club</p></header><p>Short and sweet.</p>"
+    ));
+    assert!(page.contains("<footer><a href=\"https://club.example.com\">club.example.com</a></footer></body>"));
+
+    for (branding, error) in [
+        (json!({ "page_css": "</style><script>alert(1)</script>" }), "page_css must not contain '<'"),
+        (json!({ "page_logo_url": "javascript:alert(1)" }), "The page logo URL must use http or https"),
+        (json!({ "page_footer_html": "x".repeat(16 * 1024 + 1) }), "page_footer_html must be at most 16 KB"),
+    ] {
+        let (status, response) = put_feed(&app, &feed, branding).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["error"], error);
+    }
+}
+
+#[tokio::test]
+async fn test_items_link_to_their_html_page() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let mut new_item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Weekly".to_string(),
+        Some("Short and sweet.".to_string()),
+        Some("mailto:sender@example.com?subject=Weekly".to_string()),
+        Some("sender@example.com".to_string()),
+        Utc::now(),
+        Some("<weekly@example.com>".to_string()),
+        Some("Weekly".to_string()),
+        Some("sender@example.com".to_string()),
+        Some("Short and sweet.".to_string()),
+    );
+    new_item.email_body_html = Some("<p>Short and <b>sweet</b>.</p>".to_string());
+    let item = FeedItemOps::create(&mut conn, &new_item).unwrap();
+    let item_id = item.id.clone().unwrap();
+    drop(conn);
+    let app = app(pool);
+
+    let page_url = format!("http://feeds.example.com/items/{}/html", item_id);
+    let (_, _, rss) = get(&app, &format!("/feeds/{}/rss", feed.id.as_deref().unwrap())).await;
+    assert!(rss.contains(&format!("<link>{}</link>", page_url)), "{}", rss);
+    assert!(!rss.contains("<link>mailto:"));
+    let (_, _, atom) = get(&app, &format!("/feeds/{}/atom", feed.id.as_deref().unwrap())).await;
+    assert!(atom.contains(&format!(r#"<link href="{}" rel="alternate"/>"#, page_url)), "{}", atom);
+
+    let (status, headers, page) = get(&app, &format!("/items/{}/html", item_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-security-policy"], "sandbox");
+    assert!(page.contains("<p>Short and <b>sweet</b>.</p>"));
+
+    let (status, _, _) = get(&app, "/items/missing/html").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
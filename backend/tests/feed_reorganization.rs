@@ -0,0 +1,185 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> axum::response::Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+async fn post_json(app: &axum::Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let response = send(app, Method::POST, uri, Some(body)).await;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned())))
+}
+
+async fn get_text(app: &axum::Router, uri: &str) -> (StatusCode, Option<String>, String) {
+    let response = send(app, Method::GET, uri, None).await;
+    let status = response.status();
+    let location = response.headers().get(header::LOCATION).map(|value| value.to_str().unwrap().to_string());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, location, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn create_item(pool: &DbPool, feed: &Feed, title: &str, from: &str) -> FeedItem {
+    let mut conn = pool.get().unwrap();
+    let item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        None,
+        Some(title.to_string()),
+        Some(from.to_string()),
+        None,
+    );
+    FeedItemOps::create(&mut conn, &item).unwrap()
+}
+
+fn ids(body: &Value) -> Vec<&str> {
+    body["items"].as_array().unwrap().iter().map(|item| item["id"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_merge_moves_items_and_redirects() {
+    let pool = setup_test_db();
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters")
+            .with_feed(TestFeed::new("Old").with_item("Old issue"))
+            .with_feed(TestFeed::new("New").with_item("New issue")))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let old_id = fixture.feed("Old").id.clone().unwrap();
+    let new_id = fixture.feed("New").id.clone().unwrap();
+    let old_item_id = fixture.items[0].id.clone().unwrap();
+    let app = app(pool.clone());
+
+    let (status, _) = post_json(&app, "/api/admin/feeds/merge", json!({"source_feed_id": old_id, "target_feed_id": old_id})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A dry run lists the items and changes nothing
+    let merge = json!({"source_feed_id": old_id, "target_feed_id": new_id, "dry_run": true});
+    let (status, body) = post_json(&app, "/api/admin/feeds/merge", merge).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["items_moved"], 1);
+    assert_eq!(ids(&body), [old_item_id.as_str()]);
+    let (status, _, _) = get_text(&app, &format!("/feeds/{}/rss", old_id)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = post_json(&app, "/api/admin/feeds/merge", json!({"source_feed_id": old_id, "target_feed_id": new_id})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dry_run"], false);
+    assert_eq!(body["target_feed_id"], new_id.as_str());
+
+    // The old feed's URLs lead to the new one
+    let (status, location, _) = get_text(&app, &format!("/feeds/{}/atom", old_id)).await;
+    assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(location.unwrap(), format!("/feeds/{}/atom", new_id));
+    let (status, location, _) = get_text(&app, &format!("/feeds/{}/items/{}", old_id, old_item_id)).await;
+    assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(location.unwrap(), format!("/feeds/{}/items/{}", new_id, old_item_id));
+
+    // The moved item keeps its GUID
+    let (_, _, rss) = get_text(&app, &format!("/feeds/{}/rss", new_id)).await;
+    assert!(rss.contains("Old issue") && rss.contains("New issue"), "{}", rss);
+    assert!(rss.contains(&format!("{}_{}</guid>", old_id, old_item_id)), "{}", rss);
+}
+
+#[tokio::test]
+async fn test_split_moves_matching_items_into_new_feed() {
+    let pool = setup_test_db();
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("Everything").configure(|feed| feed.public_access = Some(true))))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let feed = fixture.feed("Everything").clone();
+    let feed_id = feed.id.clone().unwrap();
+    let example = create_item(&pool, &feed, "Example weekly", "News <news@lists.example.com>");
+    create_item(&pool, &feed, "Other weekly", "other@example.org");
+    let app = app(pool.clone());
+
+    let (status, _) = post_json(&app, &format!("/api/admin/feeds/{}/split", feed_id), json!({"title": "Example"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let split = json!({"title": "Example", "sender_domain": "example.com", "dry_run": true});
+    let (status, body) = post_json(&app, &format!("/api/admin/feeds/{}/split", feed_id), split).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids(&body), [example.id.as_deref().unwrap()]);
+    assert_eq!(body["target_feed_id"], Value::Null);
+
+    let split = json!({"title": "Example", "sender_domain": "example.com"});
+    let (status, body) = post_json(&app, &format!("/api/admin/feeds/{}/split", feed_id), split).await;
+    assert_eq!(status, StatusCode::OK);
+    let new_id = body["target_feed_id"].as_str().unwrap().to_string();
+
+    let new_feed = {
+        let mut conn = pool.get().unwrap();
+        FeedOps::get_by_id(&mut conn, &new_id).unwrap()
+    };
+    assert_eq!(new_feed.title, "Example");
+    assert_eq!(new_feed.email_rule_id, feed.email_rule_id);
+    assert_eq!(new_feed.public_access, Some(true));
+
+    let (_, _, rss) = get_text(&app, &format!("/feeds/{}/rss", feed_id)).await;
+    assert!(!rss.contains("Example weekly") && rss.contains("Other weekly"), "{}", rss);
+    let (_, _, rss) = get_text(&app, &format!("/feeds/{}/rss", new_id)).await;
+    assert!(rss.contains(&format!("{}_{}</guid>", feed_id, example.id.as_deref().unwrap())), "{}", rss);
+
+    // The item's old page follows it
+    let (status, location, _) = get_text(&app, &format!("/feeds/{}/items/{}", feed_id, example.id.as_deref().unwrap())).await;
+    assert_eq!(status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(location.unwrap(), format!("/feeds/{}/items/{}", new_id, example.id.as_deref().unwrap()));
+}
+
+#[tokio::test]
+async fn test_append_only_feeds_cannot_be_reorganized() {
+    let pool = setup_test_db();
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters")
+            .with_feed(TestFeed::new("Ledger").configure(|feed| feed.append_only = true))
+            .with_feed(TestFeed::new("Other")))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let ledger_id = fixture.feed("Ledger").id.clone().unwrap();
+    let other_id = fixture.feed("Other").id.clone().unwrap();
+    let app = app(pool);
+
+    let (status, body) = post_json(&app, "/api/admin/feeds/merge", json!({"source_feed_id": other_id, "target_feed_id": ledger_id})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body.as_str().unwrap().contains("append-only"), "{}", body);
+    let split = json!({"title": "Split", "subject_contains": "issue"});
+    let (status, _) = post_json(&app, &format!("/api/admin/feeds/{}/split", ledger_id), split).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = post_json(&app, "/api/admin/feeds/merge", json!({"source_feed_id": other_id, "target_feed_id": "missing"})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::routing::any;
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+type Received = Arc<Mutex<Vec<(Method, Value)>>>;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// Local endpoint recording webhook calls on `/hook` and failing on `/fail`
+async fn spawn_receiver() -> (String, Received) {
+    let received: Received = Arc::default();
+    let recorder = received.clone();
+    let receiver = axum::Router::new()
+        .route("/hook", any(move |method: Method, body: String| async move {
+            recorder.lock().unwrap().push((method, serde_json::from_str(&body).unwrap()));
+            StatusCode::NO_CONTENT
+        }))
+        .route("/fail", any(|| async { StatusCode::INTERNAL_SERVER_ERROR }));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(receiver.into_make_service()));
+
+    (format!("http://{}", addr), received)
+}
+
+async fn post(app: &axum::Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+/// Create an account and rule, returning a feed body for that rule
+async fn feed_request(app: &axum::Router) -> Value {
+    let (_, account) = post(app, "/api/imap-accounts", json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    let (_, rule) = post(app, "/api/email-rules", json!({
+        "name": "Newsletters",
+        "imap_account_id": account["id"],
+        "folder": "INBOX",
+        "is_active": true
+    })).await;
+
+    json!({
+        "title": "Newsletters",
+        "email_rule_id": rule["id"],
+        "feed_type": "rss",
+        "is_active": true
+    })
+}
+
+fn with_webhook(mut feed: Value, url: &str, method: Option<&str>, body: Option<&str>) -> Value {
+    feed["webhook_url"] = json!(url);
+    feed["webhook_method"] = json!(method);
+    feed["webhook_body"] = json!(body);
+    feed
+}
+
+#[tokio::test]
+async fn test_webhook_sends_templated_body_for_newest_item() {
+    let (receiver_url, received) = spawn_receiver().await;
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+
+    let feed = with_webhook(
+        feed_request(&app).await,
+        &format!("{}/hook", receiver_url),
+        Some("put"),
+        Some(r#"{"text": "New in {{feed.title}}: {{item.title}}", "id": "{{ item.id }}"}"#),
+    );
+    let (status, feed) = post(&app, "/api/feeds", feed).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(feed["webhook_method"], "put");
+    let feed_id = feed["id"].as_str().unwrap();
+
+    let item = FeedItemOps::create(&mut pool.get().unwrap(), &NewFeedItem::new(
+        feed_id.to_string(),
+        "Say \"hi\"\nto the team".to_string(),
+        Some("<p>Hello</p>".to_string()),
+        None,
+        Some("news@example.com".to_string()),
+        Utc::now(),
+        None,
+        None,
+        None,
+        None,
+    )).unwrap();
+
+    let (status, body) = post(&app, &format!("/api/feeds/{}/webhook/test", feed_id), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], 204);
+    assert_eq!(body["item_id"], item.id.clone().unwrap());
+
+    let received = received.lock().unwrap();
+    let (method, payload) = &received[0];
+    assert_eq!(method, Method::PUT);
+    assert_eq!(payload["text"], "New in Newsletters: Say \"hi\"\nto the team");
+    assert_eq!(payload["id"], item.id.unwrap());
+}
+
+#[tokio::test]
+async fn test_webhook_default_body_and_failures() {
+    let (receiver_url, received) = spawn_receiver().await;
+    let app = app(setup_test_db());
+    let request = feed_request(&app).await;
+
+    // Without a template the item is posted as JSON; an empty feed sends a sample item
+    let feed = with_webhook(request.clone(), &format!("{}/hook", receiver_url), None, None);
+    let (_, feed) = post(&app, "/api/feeds", feed).await;
+    let (status, body) = post(&app, &format!("/api/feeds/{}/webhook/test", feed["id"].as_str().unwrap()), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["item_id"].is_null());
+    {
+        let received = received.lock().unwrap();
+        let (method, payload) = &received[0];
+        assert_eq!(method, Method::POST);
+        assert_eq!(payload["feed"]["title"], "Newsletters");
+        assert_eq!(payload["item"]["title"], "Test item from mail2feed");
+    }
+
+    let feed = with_webhook(request.clone(), &format!("{}/fail", receiver_url), None, None);
+    let (_, feed) = post(&app, "/api/feeds", feed).await;
+    let (status, body) = post(&app, &format!("/api/feeds/{}/webhook/test", feed["id"].as_str().unwrap()), json!({})).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(body["error"].as_str().unwrap().contains("500"));
+
+    let (_, feed) = post(&app, "/api/feeds", request).await;
+    let (status, _) = post(&app, &format!("/api/feeds/{}/webhook/test", feed["id"].as_str().unwrap()), json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_feed_api_validates_webhooks() {
+    let app = app(setup_test_db());
+    let request = feed_request(&app).await;
+
+    let invalid = [
+        with_webhook(request.clone(), "not a url", None, None),
+        with_webhook(request.clone(), "ftp://example.com/hook", None, None),
+        with_webhook(request.clone(), "https://example.com/hook", Some("DELETE"), None),
+        with_webhook(request.clone(), "https://example.com/hook", None, Some(r#"{"text": "{{item.body}}"}"#)),
+        // Values are strings and must be quoted
+        with_webhook(request.clone(), "https://example.com/hook", None, Some(r#"{"text": {{item.title}}}"#)),
+    ];
+    for feed in invalid {
+        let (status, body) = post(&app, "/api/feeds", feed.clone()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "accepted {}", feed);
+        assert!(body["error"].is_string());
+    }
+
+    let (status, _) = post(&app, "/api/feeds", with_webhook(
+        request,
+        "https://hooks.slack.com/services/T000/B000/XXXX",
+        Some("POST"),
+        Some(r#"{"text": "<{{item.url}}|{{item.title}}> — {{item.summary}}"}"#),
+    )).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
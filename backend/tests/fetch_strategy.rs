@@ -0,0 +1,70 @@
+mod common;
+mod mock_imap;
+
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{ImapAccount, NewImapAccount};
+use mail2feed_backend::db::operations_generic::ImapAccountOpsGeneric;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{Fixture, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+
+use common::setup_test_db;
+
+async fn process(pool: &DatabasePool, fixture: &Fixture) -> ImapAccount {
+    let account = ImapAccountOpsGeneric::get_by_id(pool, &fixture.account_id()).unwrap();
+    let result = EmailProcessor::new(account, pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.new_feed_items_created, 1);
+    ImapAccountOpsGeneric::get_by_id(pool, &fixture.account_id()).unwrap()
+}
+
+fn fetched_with(server: &MockImap, items: &str) -> bool {
+    server.mailboxes().commands.iter().any(|command| command.starts_with("UID FETCH") && command.ends_with(items))
+}
+
+#[tokio::test]
+async fn test_working_strategy_is_remembered() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("UIDPLUS");
+    server.refuse("BODY.PEEK[HEADER]");
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue"));
+    let fixture = server.test_account("Bridge")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+
+    let account = process(&pool, &fixture).await;
+    assert_eq!(account.fetch_strategy.as_deref(), Some("envelope"));
+    assert!(account.server_capabilities.unwrap().split(' ').any(|capability| capability == "UIDPLUS"));
+
+    // The next run goes straight to envelopes, without asking for capabilities
+    server.mailboxes().commands.clear();
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Next issue"));
+    process(&pool, &fixture).await;
+    assert!(fetched_with(&server, "ENVELOPE"));
+    assert!(!fetched_with(&server, "BODY.PEEK[HEADER]"));
+    assert!(!server.mailboxes().sent("CAPABILITY"));
+}
+
+#[tokio::test]
+async fn test_failing_strategy_is_probed_again() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.refuse("ENVELOPE");
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue"));
+    let fixture = server.test_account("Upgraded")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+    ImapAccountOpsGeneric::update_server_profile(&pool, &fixture.account_id(), Some("IMAP4rev1"), Some("envelope")).unwrap();
+
+    let account = process(&pool, &fixture).await;
+    assert_eq!(account.fetch_strategy.as_deref(), Some("headers"));
+    assert!(server.mailboxes().sent("CAPABILITY"));
+
+    // Editing the account forgets what was learned about its server
+    let edited = NewImapAccount::new(account.name, account.host, account.port, account.username, account.password, account.use_tls);
+    ImapAccountOpsGeneric::update(&pool, &fixture.account_id(), &edited).unwrap();
+    let account = ImapAccountOpsGeneric::get_by_id(&pool, &fixture.account_id()).unwrap();
+    assert!(account.fetch_strategy.is_none() && account.server_capabilities.is_none());
+}
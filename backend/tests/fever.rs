@@ -0,0 +1,140 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::NewFeedItem;
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::feed::fever;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+/// md5("reader:secret")
+const API_KEY: &str = "d86e2552797d076a6178f7b038ad6b69";
+
+fn app(pool: &DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    api::create_routes(pool.clone(), BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    })
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, content_type: &str, body: String) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", content_type)
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// A Fever call as clients make it: flags in the query, the key in the form body
+async fn fever_call(app: &axum::Router, query: &str, form: &str) -> Value {
+    let form = format!("api_key={}{}", API_KEY, form);
+    let (status, body) = send(app, Method::POST, &format!("/fever/?api&{}", query), "application/x-www-form-urlencoded", form).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["auth"], 1, "{}", body);
+    body
+}
+
+fn item_ids(items: &Value) -> Vec<i64> {
+    items.as_array().unwrap().iter().map(|item| item["id"].as_i64().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_fever_is_off_until_a_user_is_stored() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(&pool);
+    let form = "application/x-www-form-urlencoded";
+
+    let (status, body) = send(&app, Method::POST, "/fever/?api", form, format!("api_key={}", API_KEY)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "api_version": 3, "auth": 0 }));
+
+    let (status, settings) = send(&app, Method::PUT, "/api/settings", "application/json",
+        json!({ "fever": { "username": "reader", "password": "secret" } }).to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(settings["fever_username"], "reader");
+
+    let (_, body) = send(&app, Method::POST, "/fever/?api", form, "api_key=0123456789abcdef0123456789abcdef".to_string()).await;
+    assert_eq!(body["auth"], 0);
+    let (_, body) = send(&app, Method::POST, "/fever/?api", form, format!("api_key={}", API_KEY)).await;
+    assert_eq!(body["auth"], 1);
+    assert!(body["last_refreshed_on_time"].as_i64().unwrap() > 0);
+
+    let (_, settings) = send(&app, Method::PUT, "/api/settings", "application/json",
+        json!({ "fever": { "username": "", "password": "" } }).to_string()).await;
+    assert_eq!(settings["fever_username"], Value::Null);
+    let (_, body) = send(&app, Method::POST, "/fever/?api", form, format!("api_key={}", API_KEY)).await;
+    assert_eq!(body["auth"], 0);
+}
+
+#[tokio::test]
+async fn test_fever_syncs_items_and_read_state() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(&pool);
+    fever::FeverCredentials::store(&pool, "reader", "secret").unwrap();
+    let fixture = TestAccount::new("Newsletters")
+        .with_rule(TestRule::new("Weekly").with_feed(TestFeed::new("Weekly").with_item("Issue 1").with_item("Issue 2")))
+        .with_rule(TestRule::new("Deals").with_feed(TestFeed::new("Deals").with_item("Sale")))
+        .insert(&pool)
+        .unwrap();
+    let weekly = fever::numeric_id(fixture.feed("Weekly").id.as_deref().unwrap());
+
+    let body = fever_call(&app, "groups&feeds", "").await;
+    assert_eq!(body["groups"], json!([{ "id": fever::numeric_id(&fixture.account_id()), "title": "Newsletters" }]));
+    let titles: Vec<&str> = body["feeds"].as_array().unwrap().iter().map(|feed| feed["title"].as_str().unwrap()).collect();
+    assert_eq!(titles.len(), 2);
+    assert!(titles.contains(&"Weekly") && titles.contains(&"Deals"));
+    let feed_ids = body["feeds_groups"][0]["feed_ids"].as_str().unwrap();
+    assert!(feed_ids.split(',').any(|id| id == weekly.to_string()), "{}", feed_ids);
+
+    // Items are numbered in the order they arrived
+    let body = fever_call(&app, "items&since_id=0", "").await;
+    assert_eq!(body["total_items"], 3);
+    let ids = item_ids(&body["items"]);
+    assert_eq!(ids.len(), 3);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", ids);
+    assert_eq!(body["items"][0]["title"], "Issue 1");
+    assert_eq!(body["items"][0]["feed_id"], weekly);
+    assert_eq!(body["items"][0]["is_read"], 0);
+    assert_eq!(item_ids(&fever_call(&app, &format!("items&since_id={}", ids[0]), "").await["items"]), ids[1..]);
+    assert_eq!(item_ids(&fever_call(&app, &format!("items&max_id={}", ids[2]), "").await["items"]), [ids[1], ids[0]]);
+    assert_eq!(item_ids(&fever_call(&app, &format!("items&with_ids={},{}", ids[2], ids[0]), "").await["items"]), [ids[0], ids[2]]);
+
+    // Marking sets the flags the rest of the API uses
+    let body = fever_call(&app, "", &format!("&mark=item&as=read&id={}", ids[0])).await;
+    assert_eq!(body["unread_item_ids"], format!("{},{}", ids[1], ids[2]));
+    let body = fever_call(&app, "", &format!("&mark=item&as=saved&id={}", ids[2])).await;
+    assert_eq!(body["saved_item_ids"], ids[2].to_string());
+    let item = FeedItemOpsGeneric::get_by_id(&pool, fixture.items[2].id.as_deref().unwrap()).unwrap();
+    assert_eq!((item.is_read, item.starred), (Some(false), Some(true)));
+
+    // Items that arrive after the client refreshed stay unread
+    let body = fever_call(&app, "", &format!("&mark=feed&as=read&id={}&before=0", weekly)).await;
+    assert_eq!(body["unread_item_ids"], format!("{},{}", ids[1], ids[2]));
+    let body = fever_call(&app, "", &format!("&mark=feed&as=read&id={}&before={}", weekly, Utc::now().timestamp() + 60)).await;
+    assert_eq!(body["unread_item_ids"], ids[2].to_string());
+    let body = fever_call(&app, "", &format!("&mark=group&as=read&id=0&before={}", Utc::now().timestamp() + 60)).await;
+    assert_eq!(body["unread_item_ids"], "");
+    assert_eq!(fever_call(&app, "saved_item_ids", "").await["saved_item_ids"], ids[2].to_string());
+
+    // Numbers of deleted items are not given out again
+    FeedItemOpsGeneric::delete(&pool, fixture.items[2].id.as_deref().unwrap()).unwrap();
+    let feed_id = fixture.feed("Deals").id.clone().unwrap();
+    let item = FeedItemOpsGeneric::create(&pool, &NewFeedItem::new(feed_id, "Flash sale".to_string(), None, None, None, Utc::now(), None, None, None, None)).unwrap();
+    assert!(item.sync_id.unwrap() > ids[2]);
+}
@@ -0,0 +1,71 @@
+mod common;
+mod mock_imap;
+
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{Fixture, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+
+use common::setup_test_db;
+
+fn add_messages(server: &MockImap) {
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue").label("\\Inbox").label("Newsletters"));
+    server.add_message("INBOX", MockMessage::new("boss@example.com", "Quarterly plan").label("\\Important").label("Work stuff"));
+}
+
+fn labeled_rule(name: &str, label: &str) -> TestRule {
+    TestRule::new(name)
+        .configure(|rule| rule.label = Some(label.to_string()))
+        .with_feed(TestFeed::new(name))
+}
+
+async fn process(pool: &DatabasePool, fixture: &Fixture) {
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+}
+
+fn titles(pool: &DatabasePool, fixture: &Fixture, feed: &str) -> Vec<String> {
+    let feed_id = fixture.feed(feed).id.clone().unwrap();
+    FeedItemOpsGeneric::get_by_feed_id(pool, &feed_id, None).unwrap().into_iter().map(|item| item.title).collect()
+}
+
+#[tokio::test]
+async fn test_rules_match_gmail_labels() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("X-GM-EXT-1");
+    add_messages(&server);
+    let fixture = server.test_account("Gmail")
+        .with_rule(labeled_rule("Newsletters", "newsletters"))
+        .with_rule(labeled_rule("Important", "Important"))
+        .with_rule(labeled_rule("Projects", "Work"))
+        .insert(&pool)
+        .unwrap();
+
+    process(&pool, &fixture).await;
+
+    // One search per label, Gmail-style
+    assert!(server.mailboxes().sent("UID SEARCH X-GM-RAW \"label:newsletters\""));
+    assert!(server.mailboxes().sent("UID SEARCH X-GM-RAW \"label:important\""));
+    assert_eq!(titles(&pool, &fixture, "Newsletters"), ["Weekly issue"]);
+    // System labels match without their backslash
+    assert_eq!(titles(&pool, &fixture, "Important"), ["Quarterly plan"]);
+    // Labels match whole, not by prefix
+    assert!(titles(&pool, &fixture, "Projects").is_empty());
+}
+
+#[tokio::test]
+async fn test_labels_are_not_checked_on_servers_without_them() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    add_messages(&server);
+    let fixture = server.test_account("Plain IMAP")
+        .with_rule(labeled_rule("Newsletters", "Newsletters"))
+        .insert(&pool)
+        .unwrap();
+
+    process(&pool, &fixture).await;
+
+    assert!(!server.mailboxes().sent("UID SEARCH X-GM-RAW"));
+    assert_eq!(titles(&pool, &fixture, "Newsletters").len(), 2);
+}
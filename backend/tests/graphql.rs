@@ -0,0 +1,144 @@
+#![cfg(feature = "graphql")]
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn query(app: &axum::Router, query: &str) -> Value {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/graphql")
+        .header("Content-Type", "application/json")
+        .body(Body::from(json!({ "query": query }).to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// An account with one rule feeding a feed with `items` items and an empty feed
+fn create_dashboard(pool: &DbPool, items: usize) -> ImapAccount {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Newsletters".to_string(),
+        "imap.example.com".to_string(),
+        993,
+        "reader@example.com".to_string(),
+        "secret".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Substack".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        Some("substack.com".to_string()),
+        None,
+        None,
+        true,
+    )).unwrap();
+    for title in ["Full Feed", "Empty Feed"] {
+        FeedOps::create(&mut conn, &NewFeed::new(
+            title.to_string(),
+            None,
+            None,
+            rule.id.clone().unwrap(),
+            "rss".to_string(),
+            true,
+        )).unwrap();
+    }
+    let feed = FeedOps::get_by_rule_id(&mut conn, rule.id.as_ref().unwrap()).unwrap()
+        .into_iter()
+        .find(|feed| feed.title == "Full Feed")
+        .unwrap();
+    for i in 0..items {
+        FeedItemOps::create(&mut conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Issue {}", i),
+            None,
+            None,
+            None,
+            Utc::now(),
+            None,
+            None,
+            None,
+            None,
+        )).unwrap();
+    }
+    account
+}
+
+#[tokio::test]
+async fn test_dashboard_query_returns_nested_counts() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+    let account = create_dashboard(&pool, 3);
+
+    let response = query(&app, r#"{
+        accounts {
+            id
+            name
+            item_count
+            rules {
+                name
+                feeds { title item_count items(limit: 2) { title } }
+            }
+        }
+    }"#).await;
+    assert!(response.get("errors").is_none(), "{}", response);
+
+    let accounts = response["data"]["accounts"].as_array().unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0]["id"], json!(account.id));
+    assert_eq!(accounts[0]["item_count"], 3);
+    assert_eq!(accounts[0].as_object().unwrap().len(), 4, "only selected fields are returned");
+
+    let rules = accounts[0]["rules"].as_array().unwrap();
+    assert_eq!(rules[0]["name"], "Substack");
+    let mut feeds = rules[0]["feeds"].as_array().unwrap().clone();
+    feeds.sort_by_key(|feed| feed["title"].as_str().unwrap().to_string());
+    assert_eq!(feeds[0]["title"], "Empty Feed");
+    assert_eq!(feeds[0]["item_count"], 0);
+    assert_eq!(feeds[1]["item_count"], 3);
+    assert_eq!(feeds[1]["items"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_query_errors_are_reported() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+    let account = create_dashboard(&pool, 0);
+
+    let response = query(&app, r#"{ account(id: "missing") { name } }"#).await;
+    assert!(response["data"].is_null());
+    assert!(response["errors"][0]["message"].as_str().unwrap().contains("missing"));
+
+    // The password stays out of the graph
+    let response = query(&app, &format!(r#"{{ account(id: "{}") {{ password }} }}"#, account.id.unwrap())).await;
+    assert!(response["errors"][0]["message"].as_str().unwrap().contains("password"));
+
+    let response = query(&app, "{ feeds { rule { account { rules { feeds { rule { account { rules { name } } } } } } } } }").await;
+    assert!(response["errors"][0]["message"].as_str().unwrap().contains("nested too deep"));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::api::types::{DependencyStatus, LivenessResponse, ReadinessResponse, ServiceState};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{NewProcessingRun, ProcessingRunStatus};
+use mail2feed_backend::db::operations_generic::ProcessingRunOpsGeneric;
+use mail2feed_backend::testing::TestAccount;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn get<T: DeserializeOwned>(app: axum::Router, uri: &str) -> (StatusCode, T) {
+    let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+/// A pool whose database cannot be opened
+fn unreachable_pool() -> DatabasePool {
+    let manager = ConnectionManager::<SqliteConnection>::new("/nonexistent/mail2feed/db.sqlite");
+    let pool = Pool::builder()
+        .max_size(1)
+        .connection_timeout(Duration::from_millis(200))
+        .build_unchecked(manager);
+    DatabasePool::SQLite(pool)
+}
+
+#[tokio::test]
+async fn test_ready_reports_dependencies_and_account_freshness() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work").insert(&pool).unwrap();
+    let account_id = fixture.account_id();
+    TestAccount::new("Never run").insert(&pool).unwrap();
+
+    let run = ProcessingRunOpsGeneric::create(&pool, &NewProcessingRun::new(account_id.clone())).unwrap();
+    ProcessingRunOpsGeneric::finish(&pool, run.id.as_deref().unwrap(), &ProcessingRunStatus::Completed, 3, 2, None).unwrap();
+
+    let (status, ready) = get::<ReadinessResponse>(app(pool), "/health/ready").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(ready.ready);
+    assert_eq!(ready.database.status, DependencyStatus::Up);
+    // Without a running service processing is simply stopped, which is not critical
+    assert!(matches!(ready.background.state, ServiceState::Stopped));
+
+    assert_eq!(ready.accounts.len(), 2);
+    let work = ready.accounts.iter().find(|account| account.account_id == account_id).unwrap();
+    assert!(work.last_success.is_some());
+    assert!(work.last_success_age_seconds.unwrap() < 60);
+    let never = ready.accounts.iter().find(|account| account.name == "Never run").unwrap();
+    assert_eq!((never.last_success.as_deref(), never.last_success_age_seconds), (None, None));
+}
+
+#[tokio::test]
+async fn test_ready_fails_while_the_database_is_down_but_live_does_not() {
+    let pool = unreachable_pool();
+
+    let (status, ready) = get::<ReadinessResponse>(app(pool.clone()), "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(!ready.ready);
+    assert_eq!(ready.database.status, DependencyStatus::Down);
+    assert!(ready.database.error.is_some());
+    assert!(ready.accounts.is_empty());
+
+    let (status, live) = get::<LivenessResponse>(app(pool.clone()), "/health/live").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(live.status, "ok");
+
+    let (status, health) = get::<serde_json::Value>(app(pool), "/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["database"], "disconnected");
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::{overflow, sanitize};
+use mail2feed_backend::imap::mime::EmailContent;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    FeedOps::create(conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap()
+}
+
+/// A newsletter sent as multipart/alternative, with a script and a tracking pixel in its HTML
+const NEWSLETTER: &str = "--=_part\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+This week: new releases.\r\n\
+--=_part\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+<h1>This week</h1><p>New <a href=3D\"https://example.com/releases\">releases</a>.</p>\r\n\
+<script>track()</script><img src=3D\"https://t.example.com/open?u=3D1\" width=3D\"1\" height=3D\"1\">\r\n\
+--=_part--\r\n";
+
+fn create_item(conn: &mut SqliteConnection, feed: &Feed) -> FeedItem {
+    let content = EmailContent::parse(NEWSLETTER, Some("multipart/alternative; boundary=\"=_part\""), None);
+    let mut new_item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "This week".to_string(),
+        Some(content.text.clone()),
+        None,
+        Some("news@example.com".to_string()),
+        Utc::now(),
+        Some("<weekly@example.com>".to_string()),
+        Some("This week".to_string()),
+        Some("news@example.com".to_string()),
+        Some(content.text.clone()),
+    );
+    new_item.email_body_html = content.html.as_deref().map(|html| sanitize::sanitize_html(html, false, true));
+    FeedItemOps::create(conn, &new_item).unwrap()
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, String) {
+    let response = app.clone()
+        .oneshot(Request::builder().method(Method::GET).uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[test]
+fn test_items_store_plain_text_and_sanitized_html() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let item = create_item(&mut conn, &feed);
+
+    let stored = FeedItemOps::get_by_id(&mut conn, item.id.as_deref().unwrap()).unwrap();
+    assert_eq!(stored.email_body.as_deref(), Some("This week: new releases."));
+    let html = stored.email_body_html.unwrap();
+    assert!(html.contains("<h1>This week</h1>"), "{}", html);
+    assert!(html.contains(r#"<a href="https://example.com/releases" rel="noopener noreferrer nofollow">"#), "{}", html);
+    assert!(!html.contains("script") && !html.contains("t.example.com"), "{}", html);
+}
+
+#[tokio::test]
+async fn test_feeds_carry_html_bodies() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let feed = create_test_feed(&mut conn);
+    let item = create_item(&mut conn, &feed);
+    drop(conn);
+    let app = app(pool);
+    let feed_id = feed.id.as_deref().unwrap();
+
+    let (status, rss) = get(&app, &format!("/feeds/{}/rss", feed_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(rss.contains("xmlns:content=\"http://purl.org/rss/1.0/modules/content/\""), "{}", rss);
+    assert!(rss.contains("<content:encoded><![CDATA[<h1>This week</h1>"), "{}", rss);
+    assert!(rss.contains("<description><![CDATA[This week: new releases.]]></description>"), "{}", rss);
+
+    let (status, atom) = get(&app, &format!("/feeds/{}/atom", feed_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(atom.contains("<summary type=\"html\">This week: new releases.</summary>"), "{}", atom);
+    assert!(atom.contains("<content type=\"html\">&lt;h1&gt;This week&lt;/h1&gt;"), "{}", atom);
+
+    // The item page shows the HTML rather than the plain text
+    let page = overflow::render_item_page(&feed, &item);
+    assert!(page.contains("<h1>This week</h1>"), "{}", page);
+}
@@ -0,0 +1,226 @@
+mod common;
+mod mock_imap;
+
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::{EmailRuleOpsGeneric, FeedItemOpsGeneric};
+use mail2feed_backend::imap::client::BODY_UNAVAILABLE;
+use mail2feed_backend::imap::high_water::HighWaterMark;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+
+use common::setup_test_db;
+
+/// A server whose INBOX holds `count` messages, a day apart and oldest first
+fn server_with_messages(count: usize) -> (MockImap, Vec<u32>) {
+    let server = MockImap::start("UIDPLUS MOVE");
+    let uids = (0..count)
+        .map(|index| {
+            let message = MockMessage::new("News <news@example.com>", &format!("Issue {}", index))
+                .date(&format!("Mon, {} Sep 2025 09:00:00 +0000", index + 1));
+            server.add_message("INBOX", message)
+        })
+        .collect();
+    (server, uids)
+}
+
+fn uids_of(fetch: &mail2feed_backend::imap::high_water::FolderFetch) -> Vec<u32> {
+    let mut uids: Vec<u32> = fetch.emails.iter().map(|email| email.uid).collect();
+    uids.sort();
+    uids
+}
+
+#[tokio::test]
+async fn test_fetches_headers_and_bodies() {
+    let (server, uids) = server_with_messages(3);
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", Some(10), None, false).await.unwrap();
+
+    assert_eq!(fetch.uid_validity, Some(1));
+    assert!(!fetch.resumed);
+    // Newest first
+    assert_eq!(fetch.emails.iter().map(|email| email.uid).collect::<Vec<_>>(), uids.iter().rev().copied().collect::<Vec<_>>());
+    let newest = &fetch.emails[0];
+    assert_eq!(newest.subject, "Issue 2");
+    assert_eq!(newest.from, "News <news@example.com>");
+    assert_eq!(newest.to, "reader@example.com");
+    assert_eq!(newest.body, "Body of Issue 2");
+    assert!(server.mailboxes().sent("EXAMINE"), "fetching must not select the folder read-write");
+}
+
+#[tokio::test]
+async fn test_fetches_newest_up_to_the_limit() {
+    let (server, uids) = server_with_messages(5);
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", Some(2), None, false).await.unwrap();
+
+    assert_eq!(uids_of(&fetch), uids[3..]);
+}
+
+#[tokio::test]
+async fn test_resumes_above_the_high_water_mark() {
+    let (server, uids) = server_with_messages(5);
+    let mark = HighWaterMark { uid_validity: 1, last_uid: uids[1] };
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", Some(2), Some(mark), false).await.unwrap();
+
+    assert!(fetch.resumed);
+    // The oldest ones above the mark, so the rest follow in the next run
+    assert_eq!(uids_of(&fetch), uids[2..4]);
+}
+
+#[tokio::test]
+async fn test_new_uid_validity_fetches_newest_again() {
+    let (server, uids) = server_with_messages(3);
+    server.set_uid_validity("INBOX", 7);
+    let mark = HighWaterMark { uid_validity: 1, last_uid: uids[2] };
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", Some(10), Some(mark), false).await.unwrap();
+
+    assert!(!fetch.resumed);
+    assert_eq!(fetch.uid_validity, Some(7));
+    assert_eq!(uids_of(&fetch), uids);
+}
+
+#[tokio::test]
+async fn test_fetches_only_unseen() {
+    let server = MockImap::start("");
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Read").seen());
+    let unread = server.add_message("INBOX", MockMessage::new("news@example.com", "Unread"));
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", None, None, true).await.unwrap();
+
+    assert_eq!(uids_of(&fetch), vec![unread]);
+    assert!(server.mailboxes().sent("UID SEARCH UNSEEN"));
+}
+
+#[tokio::test]
+async fn test_falls_back_to_envelope() {
+    let (server, uids) = server_with_messages(2);
+    server.refuse("BODY.PEEK[HEADER]");
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", None, None, false).await.unwrap();
+
+    assert_eq!(uids_of(&fetch), uids);
+    let newest = &fetch.emails[0];
+    assert_eq!(newest.subject, "Issue 1");
+    assert_eq!(newest.from, "News <news@example.com>");
+    assert_eq!(newest.body, BODY_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_falls_back_to_uids_only() {
+    let (server, uids) = server_with_messages(2);
+    server.refuse("BODY.PEEK[HEADER]");
+    server.refuse("ENVELOPE");
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", None, None, false).await.unwrap();
+
+    assert_eq!(uids_of(&fetch), uids);
+    assert!(fetch.emails.iter().all(|email| email.subject == format!("[Email UID: {}]", email.uid)));
+}
+
+#[tokio::test]
+async fn test_falls_back_to_sequence_numbers_without_uid_search() {
+    let (server, uids) = server_with_messages(4);
+    server.refuse("UID SEARCH");
+    let mark = HighWaterMark { uid_validity: 1, last_uid: uids[0] };
+
+    let fetch = server.client().fetch_emails_from_folder("INBOX", Some(2), Some(mark), false).await.unwrap();
+
+    assert!(!fetch.resumed);
+    assert_eq!(uids_of(&fetch), uids[2..]);
+    assert!(server.mailboxes().sent("FETCH 3:4 UID"));
+}
+
+#[tokio::test]
+async fn test_lists_folders() {
+    let server = MockImap::start("");
+    server.add_folder("Lists");
+    server.add_folder("Lists/Rust");
+
+    let folders = server.client().list_folders().await.unwrap();
+
+    assert_eq!(folders, vec!["INBOX", "Lists", "Lists/Rust"]);
+}
+
+#[tokio::test]
+async fn test_folder_listing_falls_back_to_inbox_prefix() {
+    let server = MockImap::start("");
+    server.add_folder("INBOX/Lists");
+    server.add_folder("Archive");
+    server.refuse("LIST \"\" *");
+
+    let folders = server.client().list_folders().await.unwrap();
+
+    assert_eq!(folders, vec!["INBOX", "INBOX/Lists"]);
+    assert!(server.mailboxes().sent("LIST \"INBOX\" *"));
+}
+
+#[tokio::test]
+async fn test_lists_selectable_subfolders() {
+    let server = MockImap::start("");
+    server.add_folder("Lists");
+    server.add_unselectable_folder("Lists/Archive");
+    server.add_folder("Lists/Archive/2024");
+    server.add_folder("Lists/Rust");
+    server.add_folder("Listserv");
+
+    let subfolders = server.client().list_subfolders("Lists").await.unwrap();
+
+    assert_eq!(subfolders, vec!["Lists/Archive/2024", "Lists/Rust"]);
+}
+
+#[tokio::test]
+async fn test_tls_fetch_falls_back_to_alternative_folder_names() {
+    let server = MockImap::start_tls("");
+    server.add_folder("Lists");
+    let uid = server.add_message("Lists", MockMessage::new("news@example.com", "Issue 1"));
+
+    // ProtonMail Bridge style name of a folder the server knows as "Lists"
+    let fetch = server.client().fetch_emails_from_folder("Folders/Lists", None, None, false).await.unwrap();
+
+    assert_eq!(uids_of(&fetch), vec![uid]);
+    let mailboxes = server.mailboxes();
+    assert!(mailboxes.sent("SELECT \"Folders/Lists\""));
+    assert!(mailboxes.sent("SELECT \"Lists\""));
+}
+
+#[tokio::test]
+async fn test_processor_turns_emails_into_items_and_marks_them_read() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("UIDPLUS MOVE");
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Issue 1"));
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Issue 2"));
+    let unmatched = server.add_message("INBOX", MockMessage::new("someone@example.com", "Lunch?"));
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News").from_address("news@example.com")
+            .configure(|rule| rule.post_process_action = "mark_as_read".to_string())
+            .with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+
+    assert_eq!(result.new_feed_items_created, 2, "{:?}", result.errors);
+    assert!(result.post_process_failures.is_empty());
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let mut titles: Vec<String> = FeedItemOpsGeneric::get_by_feed_id(&pool, &feed_id, None).unwrap()
+        .into_iter()
+        .map(|item| item.title)
+        .collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Issue 1", "Issue 2"]);
+
+    let mailboxes = server.mailboxes();
+    let seen: Vec<&str> = mailboxes.messages("INBOX").iter()
+        .filter(|message| message.is_seen())
+        .map(|message| message.subject.as_str())
+        .collect();
+    assert_eq!(seen, vec!["Issue 1", "Issue 2"]);
+    drop(mailboxes);
+
+    // Emails the rule does not match count as handled too
+    let rule = EmailRuleOpsGeneric::get_by_id(&pool, fixture.rule("News").id.as_ref().unwrap()).unwrap();
+    assert_eq!(rule.last_seen_uid, Some(unmatched as i64));
+}
@@ -0,0 +1,90 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::api::types::FolderNode;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mock_imap::{MockImap, MockMessage};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn get(app: axum::Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    (status, hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec())
+}
+
+#[tokio::test]
+async fn test_folders_are_nested_with_counts() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_unselectable_folder("Folders");
+    server.add_folder("Folders/News");
+    server.add_folder("Folders/News/Tech");
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Issue 1"));
+    for subject in ["Issue 2", "Issue 3"] {
+        server.add_message("Folders/News", MockMessage::new("news@example.com", subject));
+    }
+    let read = server.add_message("Folders/News", MockMessage::new("news@example.com", "Issue 4"));
+    server.mailboxes().folders.get_mut("Folders/News").unwrap().messages.iter_mut()
+        .find(|message| message.uid == read).unwrap()
+        .flags.push("\\Seen".to_string());
+    let account = server.account(&pool);
+
+    let (status, body) = get(app(pool), &format!("/api/imap-accounts/{}/folders", account.id.unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+    let folders: Vec<FolderNode> = serde_json::from_slice(&body).unwrap();
+
+    let names: Vec<&str> = folders.iter().map(|folder| folder.name.as_str()).collect();
+    assert_eq!(names, ["Folders", "INBOX"]);
+    let parent = &folders[0];
+    assert!(!parent.selectable);
+    assert_eq!((parent.messages, parent.unseen), (None, None));
+    let news = &parent.children[0];
+    assert_eq!((news.label.as_str(), news.messages, news.unseen), ("News", Some(3), Some(2)));
+    assert_eq!(news.children[0].name, "Folders/News/Tech");
+    assert_eq!((news.children[0].messages, news.children[0].unseen), (Some(0), Some(0)));
+    assert_eq!((folders[1].messages, folders[1].unseen), (Some(1), Some(1)));
+
+    // Counts come from STATUS, so no folder was selected
+    let mailboxes = server.mailboxes();
+    assert!(mailboxes.sent("STATUS \"Folders/News\""));
+    assert!(!mailboxes.sent("SELECT") && !mailboxes.sent("EXAMINE"));
+}
+
+#[tokio::test]
+async fn test_refused_status_leaves_counts_out() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_folder("Archive");
+    server.refuse("STATUS \"Archive\"");
+    let account = server.account(&pool);
+
+    let (status, body) = get(app(pool), &format!("/api/imap-accounts/{}/folders", account.id.unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+    let folders: Vec<FolderNode> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(folders[0].name, "Archive");
+    assert_eq!(folders[0].messages, None);
+    assert_eq!(folders[1].messages, Some(0));
+}
+
+#[tokio::test]
+async fn test_folders_of_missing_account() {
+    let (status, _) = get(app(DatabasePool::SQLite(setup_test_db())), "/api/imap-accounts/missing/folders").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
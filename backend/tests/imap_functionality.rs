@@ -16,6 +16,15 @@ fn test_email_parsing_with_headers_and_body() {
         date: Utc::now(),
         body: "This is a test email body with content.".to_string(),
         is_seen: false,
+        importance: None,
+        category: None,
+        labels: None,
+        content_type: None,
+        transfer_encoding: None,
+        list_unsubscribe: None,
+        list_unsubscribe_post: None,
+        in_reply_to: None,
+        references: None,
     };
     
     // Verify all fields are populated correctly
@@ -38,6 +47,15 @@ fn test_email_parsing_with_empty_body() {
         date: Utc::now(),
         body: "[Body not available - fetched headers only]".to_string(),
         is_seen: true,
+        importance: None,
+        category: None,
+        labels: None,
+        content_type: None,
+        transfer_encoding: None,
+        list_unsubscribe: None,
+        list_unsubscribe_post: None,
+        in_reply_to: None,
+        references: None,
     };
     
     assert_eq!(test_email.uid, 456);
@@ -56,6 +74,15 @@ fn test_email_parsing_with_unicode_content() {
         date: Utc::now(),
         body: "Unicode content with emojis 🚀 and special chars àáâãäå".to_string(),
         is_seen: false,
+        importance: None,
+        category: None,
+        labels: None,
+        content_type: None,
+        transfer_encoding: None,
+        list_unsubscribe: None,
+        list_unsubscribe_post: None,
+        in_reply_to: None,
+        references: None,
     };
     
     assert!(test_email.subject.contains("=?utf-8?q?"));
@@ -80,6 +107,21 @@ fn test_protonmail_bridge_account_config() {
         updated_at: Utc::now().to_rfc3339(),
         default_post_process_action: "do_nothing".to_string(),
         default_move_to_folder: None,
+        max_bytes_per_second: None,
+        fingerprint: None,
+        tls_pin: None,
+        tls_server_name: None,
+        security: "none".to_string(),
+        quota_group_id: None,
+        max_feeds: None,
+        max_items: None,
+        max_processing_minutes_per_day: None,
+        max_connections_per_hour: None,
+        min_fetch_delay_seconds: None,
+        max_messages_per_fetch: None,
+        server_capabilities: None,
+        fetch_strategy: None,
+        protocol: "imap".to_string(),
     };
     
     // Verify ProtonMail Bridge characteristics
@@ -103,6 +145,21 @@ fn test_gmail_bridge_account_config() {
         updated_at: Utc::now().to_rfc3339(),
         default_post_process_action: "do_nothing".to_string(),
         default_move_to_folder: None,
+        max_bytes_per_second: None,
+        fingerprint: None,
+        tls_pin: None,
+        tls_server_name: None,
+        security: "ssl_tls".to_string(),
+        quota_group_id: None,
+        max_feeds: None,
+        max_items: None,
+        max_processing_minutes_per_day: None,
+        max_connections_per_hour: None,
+        min_fetch_delay_seconds: None,
+        max_messages_per_fetch: None,
+        server_capabilities: None,
+        fetch_strategy: None,
+        protocol: "imap".to_string(),
     };
     
     // Verify Gmail characteristics
@@ -127,6 +184,21 @@ fn test_imap_client_creation() {
         updated_at: Utc::now().to_rfc3339(),
         default_post_process_action: "do_nothing".to_string(),
         default_move_to_folder: None,
+        max_bytes_per_second: None,
+        fingerprint: None,
+        tls_pin: None,
+        tls_server_name: None,
+        security: "none".to_string(),
+        quota_group_id: None,
+        max_feeds: None,
+        max_items: None,
+        max_processing_minutes_per_day: None,
+        max_connections_per_hour: None,
+        min_fetch_delay_seconds: None,
+        max_messages_per_fetch: None,
+        server_capabilities: None,
+        fetch_strategy: None,
+        protocol: "imap".to_string(),
     };
     
     let client_result = ImapClient::new(&account);
@@ -167,7 +239,7 @@ fn test_fetch_strategy_fallbacks() {
     // 2. ENVELOPE
     // 3. UID only
     
-    let strategies = vec![
+    let strategies = [
         "BODY.PEEK[HEADER]",
         "BODY.PEEK[TEXT]", 
         "ENVELOPE",
@@ -192,7 +264,7 @@ fn test_error_scenarios() {
     assert_eq!(uid_list, "");
     
     // Single UID
-    let single_uid = vec![42u32];
+    let single_uid = [42u32];
     let single_uid_list = single_uid.iter().map(|u| u.to_string()).collect::<Vec<_>>().join(",");
     assert_eq!(single_uid_list, "42");
     
@@ -208,7 +280,7 @@ fn test_error_scenarios() {
 #[test]
 fn test_email_body_uid_matching() {
     // Simulate the body matching process
-    let mut partial_emails = vec![
+    let mut partial_emails = [
         Email {
             uid: 100,
             message_id: "<test1@example.com>".to_string(),
@@ -218,6 +290,15 @@ fn test_email_body_uid_matching() {
             date: Utc::now(),
             body: "[Body not available - fetched headers only]".to_string(),
             is_seen: false,
+            importance: None,
+            category: None,
+            labels: None,
+            content_type: None,
+            transfer_encoding: None,
+            list_unsubscribe: None,
+            list_unsubscribe_post: None,
+            in_reply_to: None,
+            references: None,
         },
         Email {
             uid: 101,
@@ -228,6 +309,15 @@ fn test_email_body_uid_matching() {
             date: Utc::now(),
             body: "[Body not available - fetched headers only]".to_string(),
             is_seen: false,
+            importance: None,
+            category: None,
+            labels: None,
+            content_type: None,
+            transfer_encoding: None,
+            list_unsubscribe: None,
+            list_unsubscribe_post: None,
+            in_reply_to: None,
+            references: None,
         }
     ];
     
@@ -255,7 +345,7 @@ fn test_protonmail_folder_alternatives() {
     let original_folder = "Folders/newsletters/tldrtech";
     
     // Test the alternative folder names we try
-    let alternatives = vec![
+    let alternatives = [
         original_folder.replace("Folders/", ""),  // Remove "Folders/" prefix
         original_folder.replace("Folders/", "").replace("/", "."), // Use dots instead of slashes
         format!("INBOX.{}", original_folder.replace("Folders/", "").replace("/", ".")), // INBOX prefix
@@ -271,7 +361,7 @@ fn test_protonmail_folder_alternatives() {
     assert!(alternatives.len() >= 4);
     
     // Test case-insensitive matching simulation
-    let folder_names = vec!["INBOX", "newsletters.tldrtech", "Sent", "Drafts"];
+    let folder_names = ["INBOX", "newsletters.tldrtech", "Sent", "Drafts"];
     let target_folder = "newsletters.tldrtech";
     
     let folder_exists = folder_names.iter().any(|f| f.eq_ignore_ascii_case(target_folder));
@@ -320,6 +410,15 @@ fn test_character_encoding() {
             date: Utc::now(),
             body: "Test body".to_string(),
             is_seen: false,
+            importance: None,
+            category: None,
+            labels: None,
+            content_type: None,
+            transfer_encoding: None,
+            list_unsubscribe: None,
+            list_unsubscribe_post: None,
+            in_reply_to: None,
+            references: None,
         };
         
         assert_eq!(email.subject, subject);
@@ -340,6 +439,15 @@ fn test_email_rule_matching() {
         date: Utc::now(),
         body: "Newsletter content here".to_string(),
         is_seen: false,
+        importance: None,
+        category: None,
+        labels: None,
+        content_type: None,
+        transfer_encoding: None,
+        list_unsubscribe: None,
+        list_unsubscribe_post: None,
+        in_reply_to: None,
+        references: None,
     };
     
     // Test emails that should not match
@@ -352,6 +460,15 @@ fn test_email_rule_matching() {
         date: Utc::now(),
         body: "Spam content".to_string(),
         is_seen: false,
+        importance: None,
+        category: None,
+        labels: None,
+        content_type: None,
+        transfer_encoding: None,
+        list_unsubscribe: None,
+        list_unsubscribe_post: None,
+        in_reply_to: None,
+        references: None,
     };
     
     // Test the pattern matching logic that EmailProcessor would use
@@ -389,6 +506,15 @@ fn test_mime_header_decoding() {
             date: Utc::now(),
             body: "Test body".to_string(),
             is_seen: false,
+            importance: None,
+            category: None,
+            labels: None,
+            content_type: None,
+            transfer_encoding: None,
+            list_unsubscribe: None,
+            list_unsubscribe_post: None,
+            in_reply_to: None,
+            references: None,
         };
         
         // In a real scenario, the MIME decoding would happen during parsing
@@ -423,6 +549,21 @@ mod protonmail_bridge_tests {
             updated_at: Utc::now().to_rfc3339(),
             default_post_process_action: "do_nothing".to_string(),
             default_move_to_folder: None,
+            max_bytes_per_second: None,
+            fingerprint: None,
+            tls_pin: None,
+            tls_server_name: None,
+            security: "none".to_string(),
+            quota_group_id: None,
+            max_feeds: None,
+            max_items: None,
+            max_processing_minutes_per_day: None,
+            max_connections_per_hour: None,
+            min_fetch_delay_seconds: None,
+            max_messages_per_fetch: None,
+            server_capabilities: None,
+            fetch_strategy: None,
+            protocol: "imap".to_string(),
         };
         
         // Verify characteristics that make ProtonMail Bridge work
@@ -463,7 +604,7 @@ mod protonmail_bridge_tests {
     #[test]
     fn test_body_text_matching_by_uid() {
         // Test the body matching logic that was implemented
-        let mut partial_emails = vec![
+        let mut partial_emails = [
             Email {
                 uid: 87,
                 message_id: "<test87@example.com>".to_string(),
@@ -473,6 +614,15 @@ mod protonmail_bridge_tests {
                 date: Utc::now(),
                 body: "[Body not available - fetched headers only]".to_string(),
                 is_seen: false,
+                importance: None,
+                category: None,
+                labels: None,
+                content_type: None,
+                transfer_encoding: None,
+                list_unsubscribe: None,
+                list_unsubscribe_post: None,
+                in_reply_to: None,
+                references: None,
             },
             Email {
                 uid: 86,
@@ -483,6 +633,15 @@ mod protonmail_bridge_tests {
                 date: Utc::now(),
                 body: "[Body not available - fetched headers only]".to_string(),
                 is_seen: false,
+                importance: None,
+                category: None,
+                labels: None,
+                content_type: None,
+                transfer_encoding: None,
+                list_unsubscribe: None,
+                list_unsubscribe_post: None,
+                in_reply_to: None,
+                references: None,
             }
         ];
         
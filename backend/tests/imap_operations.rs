@@ -0,0 +1,140 @@
+mod common;
+mod mock_imap;
+
+use mail2feed_backend::db::models::EmailAction;
+use mail2feed_backend::imap::post_process::{PendingEmail, PostProcessBatch};
+use mock_imap::{MockImap, MockMessage};
+
+/// Add `count` messages to `folder`, returning their UIDs
+fn add_messages(server: &MockImap, folder: &str, count: usize) -> Vec<u32> {
+    (0..count)
+        .map(|index| server.add_message(folder, MockMessage::new("news@example.com", &format!("Issue {}", index))))
+        .collect()
+}
+
+fn batch(action: EmailAction, folder: &str, uids: &[u32], target_folder: Option<&str>) -> PostProcessBatch {
+    PostProcessBatch {
+        action,
+        folder: folder.to_string(),
+        target_folder: target_folder.map(str::to_string),
+        emails: uids.iter()
+            .map(|uid| PendingEmail {
+                uid: *uid,
+                message_id: format!("<{}@example.com>", uid),
+                item_id: String::new(),
+                intent_id: String::new(),
+            })
+            .collect(),
+    }
+}
+
+#[tokio::test]
+async fn test_mark_as_read_and_unread() {
+    let server = MockImap::start("UIDPLUS MOVE");
+    server.add_folder("Lists");
+    let inbox = add_messages(&server, "INBOX", 2);
+    let listed = add_messages(&server, "Lists", 1)[0];
+    let client = server.client();
+
+    client.mark_as_read_in_folder(listed, "Lists").await.unwrap();
+    assert!(server.mailboxes().message("Lists", listed).is_seen());
+
+    client.mark_as_unread_in_folder(listed, "Lists").await.unwrap();
+    assert!(!server.mailboxes().message("Lists", listed).is_seen());
+
+    client.mark_as_read(inbox[1]).await.unwrap();
+    let mailboxes = server.mailboxes();
+    assert!(mailboxes.message("INBOX", inbox[1]).is_seen());
+    assert!(!mailboxes.message("INBOX", inbox[0]).is_seen());
+}
+
+#[tokio::test]
+async fn test_delete_expunges_only_the_email_with_uidplus() {
+    let server = MockImap::start("UIDPLUS");
+    let uids = add_messages(&server, "INBOX", 3);
+    // Another client flagged the first email without expunging it
+    server.mailboxes().folders.get_mut("INBOX").unwrap().messages[0].flags.push("\\Deleted".to_string());
+
+    server.client().delete_email_in_folder(uids[1], "INBOX").await.unwrap();
+
+    let mailboxes = server.mailboxes();
+    assert_eq!(mailboxes.uids("INBOX"), vec![uids[0], uids[2]]);
+    assert!(mailboxes.sent(&format!("UID EXPUNGE {}", uids[1])));
+    assert!(!mailboxes.commands.iter().any(|command| command == "EXPUNGE"));
+}
+
+#[tokio::test]
+async fn test_delete_falls_back_to_expunge() {
+    let server = MockImap::start("");
+    let uids = add_messages(&server, "INBOX", 3);
+
+    server.client().delete_email(uids[2]).await.unwrap();
+
+    let mailboxes = server.mailboxes();
+    assert_eq!(mailboxes.uids("INBOX"), uids[..2]);
+    assert!(mailboxes.commands.iter().any(|command| command == "EXPUNGE"));
+}
+
+#[tokio::test]
+async fn test_move_uses_uid_move() {
+    let server = MockImap::start("UIDPLUS MOVE");
+    server.add_folder("Archive");
+    let uids = add_messages(&server, "INBOX", 2);
+
+    server.client().move_to_folder(uids[0], "Archive").await.unwrap();
+
+    let mailboxes = server.mailboxes();
+    assert_eq!(mailboxes.uids("INBOX"), vec![uids[1]]);
+    assert_eq!(mailboxes.messages("Archive").len(), 1);
+    assert!(mailboxes.sent(&format!("UID MOVE {}", uids[0])));
+    assert!(!mailboxes.sent("UID COPY"));
+}
+
+#[tokio::test]
+async fn test_move_falls_back_to_copy_and_delete() {
+    let server = MockImap::start("UIDPLUS");
+    server.add_folder("Lists");
+    server.add_folder("Archive");
+    let uids = add_messages(&server, "Lists", 2);
+
+    server.client().move_to_folder_from_folder(uids[1], "Lists", "Archive").await.unwrap();
+
+    let mailboxes = server.mailboxes();
+    assert_eq!(mailboxes.uids("Lists"), vec![uids[0]]);
+    assert_eq!(mailboxes.messages("Archive").len(), 1);
+    assert!(mailboxes.sent(&format!("UID COPY {}", uids[1])));
+    assert!(!mailboxes.sent("UID MOVE"));
+}
+
+#[tokio::test]
+async fn test_move_to_missing_folder_fails() {
+    let server = MockImap::start("MOVE");
+    let uids = add_messages(&server, "INBOX", 1);
+
+    assert!(server.client().move_to_folder(uids[0], "Nowhere").await.is_err());
+    assert_eq!(server.mailboxes().uids("INBOX"), uids);
+}
+
+#[tokio::test]
+async fn test_batches_apply_actions() {
+    let server = MockImap::start("UIDPLUS MOVE");
+    server.add_folder("Archive");
+    let uids = add_messages(&server, "INBOX", 4);
+    let client = server.client();
+
+    let outcome = client.apply_post_process_batch(&batch(EmailAction::MarkAsRead, "INBOX", &uids[..2], None)).await.unwrap();
+    assert_eq!(outcome.applied, uids[..2]);
+
+    let gone = uids[3] + 100;
+    let outcome = client.apply_post_process_batch(&batch(EmailAction::MoveToFolder, "INBOX", &[uids[1], uids[2], gone], Some("Archive"))).await.unwrap();
+    assert_eq!(outcome.applied, uids[1..3]);
+    assert_eq!(outcome.failed.iter().map(|(uid, _)| *uid).collect::<Vec<_>>(), vec![gone]);
+
+    let outcome = client.apply_post_process_batch(&batch(EmailAction::Delete, "INBOX", &uids[3..], None)).await.unwrap();
+    assert_eq!(outcome.applied, uids[3..]);
+
+    let mailboxes = server.mailboxes();
+    assert_eq!(mailboxes.uids("INBOX"), vec![uids[0]]);
+    assert!(mailboxes.message("INBOX", uids[0]).is_seen());
+    assert_eq!(mailboxes.messages("Archive").len(), 2);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::{EmailRuleOpsGeneric, FeedOpsGeneric};
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn import(app: &axum::Router, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/import")
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_kill_the_newsletter_export_creates_account_rules_and_feeds() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(pool.clone());
+    let export = json!([
+        { "title": "Money Stuff", "reference": "a1b2c3" },
+        { "title": "Platformer", "reference": "d4e5f6" },
+    ]);
+
+    let (status, body) = import(&app, json!({
+        "format": "kill_the_newsletter",
+        "content": export.to_string(),
+        "account": {
+            "name": "Newsletters",
+            "host": "imap.example.com",
+            "port": 993,
+            "username": "reader@example.com",
+            "password": "secret",
+            "use_tls": true,
+        },
+        "folder": "Newsletters",
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["account_created"], true);
+
+    let account_id = body["imap_account_id"].as_str().unwrap();
+    let rules = EmailRuleOpsGeneric::get_by_account_id(&pool, account_id).unwrap();
+    let mut matched: Vec<_> = rules.iter().map(|rule| (rule.name.as_str(), rule.folder.as_str(), rule.to_address.as_deref())).collect();
+    matched.sort();
+    assert_eq!(matched, [
+        ("Money Stuff", "Newsletters", Some("a1b2c3@kill-the-newsletter.com")),
+        ("Platformer", "Newsletters", Some("d4e5f6@kill-the-newsletter.com")),
+    ]);
+    for rule in &rules {
+        let feeds = FeedOpsGeneric::get_by_rule_id(&pool, rule.id.as_deref().unwrap()).unwrap();
+        assert_eq!(feeds.iter().map(|feed| feed.title.as_str()).collect::<Vec<_>>(), [rule.name.as_str()]);
+    }
+}
+
+#[tokio::test]
+async fn test_csv_import_skips_addresses_the_account_already_has() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Mail")
+        .with_rule(TestRule::new("Weekly").configure(|rule| rule.to_address = Some("weekly@example.com".to_string())).with_feed(TestFeed::new("Weekly")))
+        .insert(&pool)
+        .unwrap();
+    let account_id = fixture.account.id.clone().unwrap();
+    let app = app(pool.clone());
+
+    let csv = "address,title,match_on,feed_type\n\
+               weekly@example.com,Weekly again,,\n\
+               alerts@example.com,Alerts,from,atom\n\
+               ALERTS@example.com,Alerts twice,from,\n";
+    let (status, body) = import(&app, json!({ "format": "csv", "content": csv, "imap_account_id": account_id })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(body["account_created"], false);
+    assert_eq!(body["rules"].as_array().unwrap().len(), 1);
+    assert_eq!(body["rules"][0]["from_address"], "alerts@example.com");
+    assert_eq!(body["rules"][0]["folder"], "INBOX");
+    assert_eq!(body["feeds"][0]["feed_type"], "atom");
+    let skipped: Vec<_> = body["skipped"].as_array().unwrap().iter().map(|skip| skip["title"].as_str().unwrap()).collect();
+    assert_eq!(skipped, ["Weekly again", "Alerts twice"]);
+
+    // Importing the same file again creates nothing
+    let (status, body) = import(&app, json!({ "format": "csv", "content": csv, "imap_account_id": account_id })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["skipped"].as_array().unwrap().len(), 3);
+    assert_eq!(EmailRuleOpsGeneric::get_by_account_id(&pool, &account_id).unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_unreadable_exports_create_nothing() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Mail").insert(&pool).unwrap();
+    let account_id = fixture.account.id.clone().unwrap();
+    let app = app(pool.clone());
+
+    for (format, content) in [
+        ("csv", "address,title\nfirst@example.com,First\n,Missing address\n"),
+        ("json", "{\"address\": \"not-a-list@example.com\"}"),
+        ("opml", "<opml/>"),
+        ("json", "[]"),
+    ] {
+        let (status, body) = import(&app, json!({ "format": format, "content": content, "imap_account_id": account_id })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", format, body);
+    }
+    let (status, _) = import(&app, json!({ "format": "json", "content": "[]" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = import(&app, json!({ "format": "json", "content": "[{\"address\": \"a@example.com\"}]", "imap_account_id": "missing" })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(EmailRuleOpsGeneric::get_by_account_id(&pool, &account_id).unwrap().is_empty());
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{TimeZone, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{FeedItem, NewFeedItem};
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn add_item(pool: &DatabasePool, feed_id: &str, day: u32) -> FeedItem {
+    let date = Utc.with_ymd_and_hms(2025, 9, day, 8, 0, 0).unwrap();
+    let item = NewFeedItem::new(feed_id.to_string(), format!("Issue {}", day), None, None, None, date, None, None, None, None);
+    FeedItemOpsGeneric::create(pool, &item).unwrap()
+}
+
+fn titles(page: &Value) -> Vec<&str> {
+    page["items"].as_array().unwrap().iter().map(|item| item["title"].as_str().unwrap()).collect()
+}
+
+fn next_page(uri: &str, page: &Value) -> String {
+    format!("{}&cursor={}", uri, urlencoding::encode(page["next_cursor"].as_str().unwrap()))
+}
+
+#[tokio::test]
+async fn test_items_are_paged_with_cursors_and_counts() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let items: Vec<FeedItem> = (1..=5).map(|day| add_item(&pool, &feed_id, day)).collect();
+    FeedItemOpsGeneric::set_pinned(&pool, items[1].id.as_deref().unwrap(), true).unwrap();
+    let read: Vec<String> = [&items[0], &items[2]].iter().map(|item| item.id.clone().unwrap()).collect();
+    FeedItemOpsGeneric::set_flags(&pool, &read, Some(true), None).unwrap();
+    let app = app(pool.clone());
+
+    let uri = format!("/api/feeds/{}/items?limit=2", feed_id);
+    let (status, page) = get(&app, &uri).await;
+    assert_eq!(status, StatusCode::OK, "{}", page);
+    assert_eq!(titles(&page), ["Issue 2", "Issue 5"]);
+    assert_eq!((&page["total"], &page["unread"]), (&Value::from(5), &Value::from(3)));
+
+    // An item arriving meanwhile neither shifts nor repeats the next pages
+    add_item(&pool, &feed_id, 6);
+    let (_, page) = get(&app, &next_page(&uri, &page)).await;
+    assert_eq!(titles(&page), ["Issue 4", "Issue 3"]);
+    assert_eq!(page["total"], 6);
+    let (_, page) = get(&app, &next_page(&uri, &page)).await;
+    assert_eq!(titles(&page), ["Issue 1"]);
+    assert!(page["next_cursor"].is_null());
+
+    // The metadata pages the same way
+    let uri = format!("/api/feeds/{}/items/metadata?limit=3", feed_id);
+    let (_, page) = get(&app, &uri).await;
+    assert_eq!(titles(&page), ["Issue 2", "Issue 6", "Issue 5"]);
+    let (_, page) = get(&app, &next_page(&uri, &page)).await;
+    assert_eq!(titles(&page), ["Issue 4", "Issue 3", "Issue 1"]);
+    assert!(page["next_cursor"].is_null());
+
+    // Date bounds apply to pinned items too
+    let (_, page) = get(&app, &format!("/api/feeds/{}/items?after=2025-09-03T08:00:00Z&before=2025-09-06T00:00:00%2B00:00", feed_id)).await;
+    assert_eq!(titles(&page), ["Issue 5", "Issue 4"]);
+    assert!(page["next_cursor"].is_null());
+
+    // Without a limit every item comes at once, as before
+    let (_, page) = get(&app, &format!("/api/feeds/{}/items", feed_id)).await;
+    assert_eq!(titles(&page).len(), 6);
+}
+
+#[tokio::test]
+async fn test_invalid_page_parameters_are_refused() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(pool);
+
+    for query in ["limit=0", "cursor=garbage", "cursor=2|2025-09-01T08:00:00+00:00|id", "before=yesterday"] {
+        let (status, body) = get(&app, &format!("/api/feeds/any/items?{}", query)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", query, body);
+    }
+}
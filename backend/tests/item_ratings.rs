@@ -0,0 +1,152 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// A feed of rule `Newsletters` with one item per sender in `senders`;
+/// returns the rule and the item IDs
+fn create_feed(pool: &DbPool, senders: &[&str]) -> (EmailRule, Vec<String>) {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Newsletters".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let feed = FeedOps::create(&mut conn, &NewFeed::new(
+        "Rated Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+
+    let item_ids = senders.iter().enumerate().map(|(n, sender)| {
+        FeedItemOps::create(&mut conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Item {}", n),
+            None,
+            None,
+            None,
+            Utc::now(),
+            None,
+            None,
+            Some(sender.to_string()),
+            None,
+        )).unwrap().id.unwrap()
+    }).collect();
+    (rule, item_ids)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn rate(app: &axum::Router, item_id: &str, rating: &str) -> (StatusCode, Value) {
+    send(app, Method::PATCH, &format!("/api/feed-items/{}", item_id), Some(json!({ "rating": rating }))).await
+}
+
+#[tokio::test]
+async fn test_rating_is_set_and_cleared() {
+    let pool = setup_test_db();
+    let (_, items) = create_feed(&pool, &["news@example.com"]);
+    let app = app(pool);
+
+    let (status, item) = rate(&app, &items[0], "UP").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(item["rating"], "up");
+    let (_, item) = send(&app, Method::GET, &format!("/api/feed-items/{}", items[0]), None).await;
+    assert_eq!(item["rating"], "up");
+
+    let (status, item) = rate(&app, &items[0], "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(item["rating"], Value::Null);
+
+    let (status, body) = rate(&app, &items[0], "meh").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "Unknown rating 'meh'; expected 'up' or 'down'");
+}
+
+#[tokio::test]
+async fn test_report_suggests_excluding_consistently_disliked_senders() {
+    let pool = setup_test_db();
+    let (rule, items) = create_feed(&pool, &[
+        "Spam <promo@shop.example>",
+        "promo@shop.example",
+        "promo@shop.example",
+        "promo@shop.example",
+        "Weekly <news@example.com>",
+        "news@example.com",
+        "news@example.com",
+    ]);
+    let app = app(pool);
+
+    for (item, rating) in items.iter().zip(["down", "down", "down", "up", "up", "up", "down"]) {
+        assert_eq!(rate(&app, item, rating).await.0, StatusCode::OK);
+    }
+
+    let (status, report) = send(&app, Method::GET, "/api/analysis/ratings", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let senders: Vec<_> = report["senders"].as_array().unwrap().iter()
+        .map(|sender| (sender["sender"].as_str().unwrap(), sender["up"].as_i64().unwrap(), sender["down"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(senders, [("promo@shop.example", 1, 3), ("news@example.com", 2, 1)]);
+
+    assert_eq!(report["rules"][0]["rule_name"], "Newsletters");
+    assert_eq!(report["rules"][0]["up"], 3);
+    assert_eq!(report["rules"][0]["down"], 4);
+
+    // 3 of 4 down is below the threshold
+    assert_eq!(report["suggestions"], json!([]));
+    assert_eq!(rate(&app, &items[3], "down").await.0, StatusCode::OK);
+
+    let (_, report) = send(&app, Method::GET, "/api/analysis/ratings", None).await;
+    let suggestions = report["suggestions"].as_array().unwrap();
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0]["rule_id"], rule.id.unwrap());
+    assert_eq!(suggestions[0]["exclude_sender"], "promo@shop.example");
+    assert_eq!(suggestions[0]["reason"], "4 of 4 rated items from promo@shop.example were rated down");
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use axum::body::Body;
+use axum::extract::Path;
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::routing::{get, post};
+use axum::Json;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::imap::EmailProcessor;
+use mail2feed_backend::testing::{Fixture, TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+const TOKEN: &str = "fmu1-api-token";
+
+/// Mailbox, keywords and raw message of each email on the mock server
+const EMAILS: [(&str, &str, &str, &str); 3] = [
+    ("e1", "inbox", "$seen", "From: Weekly <weekly@news.example.com>\r\nSubject: Issue 42\r\nMessage-ID: <issue-42@news.example.com>\r\n\r\nThis week in brief.\r\n"),
+    ("e2", "inbox", "", "From: Boss <boss@work.example.com>\r\nSubject: Meeting\r\nMessage-ID: <meeting@work.example.com>\r\n\r\nAt ten.\r\n"),
+    ("e3", "tech", "", "From: Rust Weekly <digest@rust.example.com>\r\nSubject: This Week in Rust\r\nMessage-ID: <twir@rust.example.com>\r\n\r\nNews from the Rust community.\r\n"),
+];
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers.get("authorization").and_then(|value| value.to_str().ok()) == Some(&format!("Bearer {}", TOKEN))
+}
+
+/// Answer `Mailbox/get`, `Email/query` and `Email/get`, resolving `#ids`
+/// from the query before it
+fn invoke(name: &str, args: &Value, responses: &[Value]) -> Value {
+    match name {
+        "Mailbox/get" => json!(["Mailbox/get", { "accountId": "a1", "state": "1", "notFound": [], "list": [
+            { "id": "inbox", "name": "Inbox", "parentId": null, "role": "inbox", "totalEmails": 2, "unreadEmails": 1 },
+            { "id": "news", "name": "Newsletters", "parentId": null, "role": null, "totalEmails": 0, "unreadEmails": 0 },
+            { "id": "tech", "name": "Tech", "parentId": "news", "role": null, "totalEmails": 1, "unreadEmails": 1 },
+        ] }]),
+        "Email/query" => {
+            let ids: Vec<&str> = EMAILS.iter()
+                .filter(|(_, mailbox, _, _)| args["filter"]["inMailbox"] == *mailbox)
+                .filter(|(_, _, keywords, _)| args["filter"]["notKeyword"].as_str().is_none_or(|keyword| !keywords.contains(keyword)))
+                .map(|(id, _, _, _)| *id)
+                .collect();
+            json!(["Email/query", { "accountId": "a1", "ids": ids, "position": 0 }])
+        }
+        "Email/get" => {
+            let ids = match &args["#ids"] {
+                Value::Null => args["ids"].clone(),
+                _ => responses.iter().find(|response| response[0] == "Email/query").unwrap()[1]["ids"].clone(),
+            };
+            let list: Vec<Value> = EMAILS.iter()
+                .filter(|(id, _, _, _)| ids.as_array().unwrap().contains(&json!(id)))
+                .map(|(id, _, keywords, _)| {
+                    let keywords: Value = keywords.split_whitespace().map(|keyword| (keyword.to_string(), json!(true))).collect::<serde_json::Map<_, _>>().into();
+                    json!({ "id": id, "blobId": format!("blob-{}", id), "keywords": keywords })
+                })
+                .collect();
+            json!(["Email/get", { "accountId": "a1", "state": "1", "notFound": [], "list": list }])
+        }
+        _ => json!(["error", { "type": "unknownMethod" }]),
+    }
+}
+
+/// A JMAP server accepting the bearer token `TOKEN`
+async fn spawn_jmap_server() -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let base = format!("http://{}", addr);
+    let session = json!({
+        "capabilities": { "urn:ietf:params:jmap:core": {}, "urn:ietf:params:jmap:mail": {} },
+        "accounts": { "a1": { "name": "reader@example.com", "isPersonal": true } },
+        "primaryAccounts": { "urn:ietf:params:jmap:mail": "a1" },
+        "username": "reader@example.com",
+        "apiUrl": format!("{}/jmap/api/", base),
+        "downloadUrl": format!("{}/jmap/download/{{accountId}}/{{blobId}}/{{name}}?type={{type}}", base),
+        "state": "1",
+    });
+    let server = axum::Router::new()
+        .route("/.well-known/jmap", get(move |headers: HeaderMap| async move {
+            if !authorized(&headers) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            Ok(Json(session))
+        }))
+        .route("/jmap/api/", post(|headers: HeaderMap, Json(request): Json<Value>| async move {
+            if !authorized(&headers) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            let mut responses: Vec<Value> = Vec::new();
+            for call in request["methodCalls"].as_array().unwrap() {
+                let mut response = invoke(call[0].as_str().unwrap(), &call[1], &responses);
+                response.as_array_mut().unwrap().push(call[2].clone());
+                responses.push(response);
+            }
+            Ok(Json(json!({ "methodResponses": responses, "sessionState": "1" })))
+        }))
+        .route("/jmap/download/:account/:blob/:name", get(|headers: HeaderMap, Path((_, blob, _)): Path<(String, String, String)>| async move {
+            if !authorized(&headers) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            EMAILS.iter()
+                .find(|(id, _, _, _)| blob == format!("blob-{}", id))
+                .map(|(_, _, _, raw)| raw.to_string())
+                .ok_or(StatusCode::NOT_FOUND)
+        }));
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(server.into_make_service()));
+    addr
+}
+
+fn jmap_account(addr: SocketAddr, token: &str) -> TestAccount {
+    let token = token.to_string();
+    TestAccount::new("Fastmail")
+        .configure(|account| {
+            account.protocol = "jmap".to_string();
+            account.host = addr.ip().to_string();
+            account.port = addr.port() as i32;
+            account.use_tls = false;
+            account.username = String::new();
+            account.password = token;
+        })
+        .with_rule(TestRule::new("Weekly").from_address("weekly@news.example.com").with_feed(TestFeed::new("Weekly")))
+        .with_rule(TestRule::new("Rust").folder("Newsletters/Tech").with_feed(TestFeed::new("Rust")))
+}
+
+fn items(pool: &DatabasePool, fixture: &Fixture, feed: &str) -> Vec<String> {
+    FeedItemOpsGeneric::get_by_feed_id(pool, fixture.feed(feed).id.as_deref().unwrap(), None)
+        .unwrap()
+        .into_iter()
+        .map(|item| item.title)
+        .collect()
+}
+
+async fn get_json(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_jmap_account_turns_mailboxes_into_feeds() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let addr = spawn_jmap_server().await;
+    let fixture = jmap_account(addr, TOKEN).insert(&pool).unwrap();
+
+    let processor = EmailProcessor::new(fixture.account.clone(), pool.clone());
+    let result = processor.process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.new_feed_items_created, 2);
+    assert_eq!(items(&pool, &fixture, "Weekly"), ["Issue 42"]);
+    assert_eq!(items(&pool, &fixture, "Rust"), ["This Week in Rust"]);
+
+    // Emails already in the feeds are skipped on the next run
+    let result = processor.process_account().await.unwrap();
+    assert_eq!((result.total_emails_processed, result.new_feed_items_created), (2, 0));
+}
+
+#[tokio::test]
+async fn test_jmap_connection_test_and_folders() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let addr = spawn_jmap_server().await;
+    let fixture = jmap_account(addr, TOKEN).insert(&pool).unwrap();
+    let refused = jmap_account(addr, "wrong").insert(&pool).unwrap();
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let app = api::create_routes(pool.clone(), BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    });
+
+    let (status, body) = get_json(&app, &format!("/api/imap/{}/test", fixture.account_id())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["success"], true, "{}", body);
+    assert_eq!(body["folders"], json!(["INBOX", "Newsletters", "Newsletters/Tech"]));
+
+    let (status, body) = get_json(&app, &format!("/api/imap-accounts/{}/folders", fixture.account_id())).await;
+    assert_eq!(status, StatusCode::OK);
+    let names: Vec<&str> = body.as_array().unwrap().iter().map(|node| node["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["INBOX", "Newsletters"]);
+    assert_eq!(body[1]["children"][0]["name"], "Newsletters/Tech");
+
+    let (status, body) = get_json(&app, &format!("/api/imap/{}/test", refused.account_id())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["success"], false);
+    assert!(body["message"].as_str().unwrap().contains("Authentication failed"), "{}", body);
+}
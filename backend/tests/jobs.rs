@@ -0,0 +1,143 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::api::types::{BackgroundProcessResponse, JobResponse, JobStartedResponse, JobStatus};
+use mail2feed_backend::background::jobs::{self, JobRunner, PROCESS_ACCOUNT_JOB};
+use mail2feed_backend::background::scheduler::EmailScheduler;
+use mail2feed_backend::background::{BackgroundConfig, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::JobOpsGeneric;
+use mail2feed_backend::testing::{TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str) -> (StatusCode, Vec<u8>) {
+    let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    (status, hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec())
+}
+
+async fn wait_for(app: &axum::Router, job_id: &str) -> JobResponse {
+    for _ in 0..100 {
+        let (status, body) = send(app, Method::GET, &format!("/api/jobs/{}", job_id)).await;
+        assert_eq!(status, StatusCode::OK);
+        let job: JobResponse = serde_json::from_slice(&body).unwrap();
+        if job.finished_at.is_some() {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("Job {} did not finish", job_id);
+}
+
+#[tokio::test]
+async fn test_processing_an_account_reports_through_its_job() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    for subject in ["Issue 1", "Issue 2"] {
+        server.add_message("INBOX", MockMessage::new("news@example.com", subject));
+    }
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News").from_address("news@example.com").with_feed(TestFeed::new("News")))
+        .with_rule(TestRule::new("Others").from_address("friend@example.com").with_feed(TestFeed::new("Others")))
+        .insert(&pool)
+        .unwrap();
+    let account_id = fixture.account.id.clone().unwrap();
+
+    let (job, handle) = JobRunner::new(pool.clone()).create(PROCESS_ACCOUNT_JOB, Some(account_id.clone())).unwrap();
+    assert_eq!(job.job_status(), JobStatus::Queued);
+    assert_eq!(job.progress(), Some(0));
+
+    let scheduler = EmailScheduler::new(pool.clone(), BackgroundConfig::default()).unwrap();
+    scheduler.process_account_job(&account_id, handle).await;
+
+    let job = JobOpsGeneric::get_by_id(&pool, job.id.as_deref().unwrap()).unwrap();
+    assert_eq!(job.job_status(), JobStatus::Completed, "{:?}", job.error_message);
+    assert_eq!((job.processed, job.total, job.progress()), (2, Some(2), Some(100)));
+    assert!(job.started_at.is_some());
+    let result: Value = serde_json::from_str(job.result.as_deref().unwrap()).unwrap();
+    assert_eq!(result["emails_processed"], 2);
+}
+
+#[tokio::test]
+async fn test_process_now_without_a_running_service_fails_its_job() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = MockImap::start("").test_account("Mock IMAP").insert(&pool).unwrap();
+    let app = app(pool);
+
+    let account_id = fixture.account.id.clone().unwrap();
+    let (status, body) = send(&app, Method::POST, &format!("/api/background/process/{}", account_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let response: BackgroundProcessResponse = serde_json::from_slice(&body).unwrap();
+    assert!(!response.success);
+
+    let job = wait_for(&app, &response.job_id.unwrap()).await;
+    assert_eq!((job.kind.as_str(), job.status), (PROCESS_ACCOUNT_JOB, JobStatus::Failed));
+    assert_eq!(job.target_id.as_deref(), Some(account_id.as_str()));
+    assert!(job.error.unwrap().contains("Failed to trigger processing"));
+
+    let (status, body) = send(&app, Method::GET, &format!("/api/jobs?kind={}&status=failed", PROCESS_ACCOUNT_JOB)).await;
+    assert_eq!(status, StatusCode::OK);
+    let listed: Vec<JobResponse> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(listed.len(), 1);
+
+    let (status, _) = send(&app, Method::GET, "/api/jobs?status=stuck").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, Method::GET, "/api/jobs/missing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_cleanup_can_run_as_a_job() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::POST, "/api/cleanup?background=true").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let started: JobStartedResponse = serde_json::from_slice(&body).unwrap();
+
+    let job = wait_for(&app, &started.job_id).await;
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.error);
+    let report = job.result.unwrap();
+    assert_eq!(report["feeds"]["items_removed"], 0);
+    assert!(report["rows_purged"].is_object());
+}
+
+#[tokio::test]
+async fn test_unfinished_jobs_fail_at_startup() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let runner = JobRunner::new(pool.clone());
+    let (queued, _) = runner.create("cleanup", None).unwrap();
+    let (running, handle) = runner.create("cleanup", None).unwrap();
+    handle.start();
+    let (done, handle) = runner.create("cleanup", None).unwrap();
+    handle.complete(&());
+
+    assert_eq!(jobs::recover_interrupted_jobs(&pool).unwrap(), 2);
+    for job in [queued, running] {
+        let job = JobOpsGeneric::get_by_id(&pool, job.id.as_deref().unwrap()).unwrap();
+        assert_eq!(job.job_status(), JobStatus::Failed);
+        assert_eq!(job.error_message.as_deref(), Some(jobs::INTERRUPTED_MESSAGE));
+    }
+    let done = JobOpsGeneric::get_by_id(&pool, done.id.as_deref().unwrap()).unwrap();
+    assert_eq!(done.job_status(), JobStatus::Completed);
+}
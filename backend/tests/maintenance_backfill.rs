@@ -0,0 +1,230 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::maintenance::MetadataBackfillService;
+use mail2feed_backend::background::tasks::{TaskRegistry, TaskState};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::dedup;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+const ENGLISH_BODY: &str = "<p>The quarterly report shows that our customers are happier than ever before, \
+    and the team would like to thank everyone who helped make this possible over the last few months.</p>";
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    FeedOps::create(conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap()
+}
+
+/// An item as stored before the metadata columns were populated
+fn create_legacy_item(conn: &mut SqliteConnection, feed: &Feed, title: &str, body: &str) -> FeedItem {
+    let new_item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        Some(format!("<{}@example.com>", title)),
+        Some(title.to_string()),
+        Some("sender@example.com".to_string()),
+        Some(body.to_string()),
+    );
+    let item = FeedItemOps::create(conn, &new_item).unwrap();
+
+    diesel::sql_query("UPDATE feed_items SET body_size = NULL, is_read = NULL, starred = NULL WHERE id = ?")
+        .bind::<Text, _>(item.id.clone().unwrap())
+        .execute(conn)
+        .unwrap();
+    FeedItemOps::get_by_id(conn, item.id.as_ref().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_backfill_fills_missing_metadata_in_batches() {
+    let pool = setup_test_db();
+    let (feed_id, current_id) = {
+        let mut conn = pool.get().unwrap();
+        let feed = create_test_feed(&mut conn);
+        for i in 0..4 {
+            create_legacy_item(&mut conn, &feed, &format!("legacy-{}", i), ENGLISH_BODY);
+        }
+
+        // Rows that existed when body_size was added got its default of 0
+        let mut zero_size = NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            "legacy-4".to_string(),
+            None,
+            None,
+            None,
+            Utc::now(),
+            None,
+            Some("legacy-4".to_string()),
+            Some("sender@example.com".to_string()),
+            Some(ENGLISH_BODY.to_string()),
+        );
+        zero_size.body_size = Some(0);
+        FeedItemOps::create(&mut conn, &zero_size).unwrap();
+
+        // Items that already have their metadata are left untouched
+        let mut current = NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            "current".to_string(),
+            None,
+            None,
+            None,
+            Utc::now(),
+            None,
+            None,
+            None,
+            Some("body".to_string()),
+        );
+        current.is_read = Some(true);
+        current.content_hash = Some("existing-hash".to_string());
+        current.language = Some("und".to_string());
+        let current = FeedItemOps::create(&mut conn, &current).unwrap();
+
+        assert_eq!(FeedItemOps::count_missing_metadata(&mut conn).unwrap(), 5);
+        (feed.id.clone().unwrap(), current.id.unwrap())
+    };
+
+    let tasks = TaskRegistry::new();
+    let task = tasks.start("backfill_metadata").await;
+    let task_id = task.id().to_string();
+    MetadataBackfillService::new(DatabasePool::SQLite(pool.clone()))
+        .with_batch_size(2)
+        .run(task)
+        .await;
+
+    let status = tasks.get(&task_id).await.unwrap();
+    assert_eq!(status.state, TaskState::Completed);
+    assert_eq!(status.total, 5);
+    assert_eq!(status.processed, 5);
+    assert_eq!(status.updated, 5);
+    assert!(status.finished_at.is_some());
+
+    let mut conn = pool.get().unwrap();
+    assert_eq!(FeedItemOps::count_missing_metadata(&mut conn).unwrap(), 0);
+    for item in FeedItemOps::get_by_feed_id(&mut conn, &feed_id, None).unwrap() {
+        if item.id.as_deref() == Some(current_id.as_str()) {
+            assert_eq!(item.is_read, Some(true));
+            assert_eq!(item.content_hash.as_deref(), Some("existing-hash"));
+            continue;
+        }
+        assert_eq!(item.body_size, Some(ENGLISH_BODY.len() as i32));
+        assert_eq!(item.is_read, Some(false));
+        assert_eq!(item.starred, Some(false));
+        assert_eq!(item.language.as_deref(), Some("eng"));
+        assert_eq!(
+            item.content_hash.as_deref(),
+            Some(dedup::content_hash(&item.title, "sender@example.com", ENGLISH_BODY).as_str())
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_backfill_endpoint_reports_progress() {
+    let pool = setup_test_db();
+    {
+        let mut conn = pool.get().unwrap();
+        let feed = create_test_feed(&mut conn);
+        create_legacy_item(&mut conn, &feed, "legacy", ENGLISH_BODY);
+    }
+    let app = app(pool.clone());
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/admin/maintenance/backfill-metadata")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let task_id = json["task_id"].as_str().unwrap().to_string();
+
+    let mut state = String::new();
+    for _ in 0..50 {
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/admin/maintenance/tasks/{}", task_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        state = json["state"].as_str().unwrap().to_string();
+        if state != "running" {
+            assert_eq!(json["kind"], "backfill_metadata");
+            assert_eq!(json["updated"], 1);
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(state, "completed");
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/admin/maintenance/tasks/does-not-exist")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Option<String>, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request.header("content-type", "application/json").body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let retry_after = response.headers().get(header::RETRY_AFTER).map(|value| value.to_str().unwrap().to_string());
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, retry_after, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn create_public_feed(pool: &DbPool) -> Feed {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    FeedOps::create(&mut conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap()
+}
+
+#[tokio::test]
+async fn test_maintenance_refuses_changes_but_serves_feeds() {
+    let pool = setup_test_db();
+    let feed = create_public_feed(&pool);
+    let app = app(pool);
+    let feed_id = feed.id.unwrap();
+
+    let (status, _, body) = send(&app, Method::GET, "/api/admin/maintenance", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["active"], false);
+
+    let (status, _, body) = send(&app, Method::POST, "/api/admin/maintenance",
+        Some(json!({"reason": "Nightly backup", "retry_after_seconds": 120}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["active"], true);
+    assert_eq!(body["reason"], "Nightly backup");
+    assert_eq!(body["in_flight_runs"], 0);
+
+    // Changes are refused with a hint when to come back
+    let (status, retry_after, body) = send(&app, Method::DELETE, &format!("/api/feeds/{}", feed_id), None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(retry_after.as_deref(), Some("120"));
+    assert_eq!(body["error"], "Down for maintenance: Nightly backup");
+    let (status, _, _) = send(&app, Method::POST, "/api/admin/maintenance/backfill-metadata", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    // Reads, and the public feeds above all, are still served
+    let (status, _, body) = send(&app, Method::GET, &format!("/api/feeds/{}", feed_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["title"], "Test Feed");
+    let response = app.clone()
+        .oneshot(Request::builder().uri(format!("/feeds/{}/rss", feed_id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Entering again keeps the window already open
+    let (_, _, again) = send(&app, Method::POST, "/api/admin/maintenance", Some(json!({"reason": "Upgrade"}))).await;
+    assert_eq!(again["reason"], "Nightly backup");
+
+    let (status, _, body) = send(&app, Method::DELETE, "/api/admin/maintenance", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["active"], false);
+    let (status, _, _) = send(&app, Method::DELETE, &format!("/api/feeds/{}", feed_id), None).await;
+    assert!(status.is_success(), "{}", status);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_account(pool: &DbPool) -> String {
+    let mut conn = pool.get().unwrap();
+    ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap().id.unwrap()
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn newsletter_expression() -> Value {
+    json!({"all": [
+        {"regex": {"field": "from", "pattern": "@(news|digest)\\.example\\.com$"}},
+        {"not": {"regex": {"field": "subject", "pattern": "^re:"}}}
+    ]})
+}
+
+#[tokio::test]
+async fn test_rule_stores_normalized_expression() {
+    let pool = setup_test_db();
+    let account_id = create_account(&pool);
+    let app = app(pool);
+
+    let (status, rule) = send(&app, Method::POST, "/api/email-rules", json!({
+        "name": "Newsletters",
+        "imap_account_id": account_id,
+        "folder": "INBOX",
+        "is_active": true,
+        "match_expression": newsletter_expression(),
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let stored: Value = serde_json::from_str(rule["match_expression"].as_str().unwrap()).unwrap();
+    assert_eq!(stored["all"][0]["regex"]["case_sensitive"], false);
+    assert_eq!(stored["all"][1]["not"]["regex"]["pattern"], "^re:");
+}
+
+#[tokio::test]
+async fn test_rule_with_invalid_regex_is_refused() {
+    let pool = setup_test_db();
+    let account_id = create_account(&pool);
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::POST, "/api/email-rules", json!({
+        "name": "Broken",
+        "imap_account_id": account_id,
+        "folder": "INBOX",
+        "is_active": true,
+        "match_expression": {"regex": {"field": "subject", "pattern": "(unclosed"}},
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().starts_with("Invalid regex '(unclosed'"), "{}", body);
+
+    let (status, rules) = send(&app, Method::GET, "/api/email-rules", Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rules, json!([]));
+}
+
+#[tokio::test]
+async fn test_validate_expression_against_sample() {
+    let app = app(setup_test_db());
+
+    let (status, body) = send(&app, Method::POST, "/api/email-rules/validate-expression", json!({
+        "match_expression": newsletter_expression(),
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["matches"], Value::Null);
+
+    let (status, body) = send(&app, Method::POST, "/api/email-rules/validate-expression", json!({
+        "match_expression": newsletter_expression(),
+        "sample": {"from": "weekly@NEWS.example.com", "subject": "Issue 12"},
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["matches"], true);
+
+    let (_, body) = send(&app, Method::POST, "/api/email-rules/validate-expression", json!({
+        "match_expression": newsletter_expression(),
+        "sample": {"from": "weekly@news.example.com", "subject": "Re: Issue 12"},
+    })).await;
+    assert_eq!(body["matches"], false);
+
+    let (status, body) = send(&app, Method::POST, "/api/email-rules/validate-expression", json!({
+        "match_expression": {"regex": {"field": "cc", "pattern": "x"}},
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().starts_with("Invalid match expression"), "{}", body);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// Value of the sample of `metric` whose labels contain `label`
+fn sample(body: &str, metric: &str, label: &str) -> i64 {
+    body.lines()
+        .find(|line| line.starts_with(metric) && line.contains(label))
+        .and_then(|line| line.rsplit(' ').next())
+        .unwrap_or_else(|| panic!("no {} sample with {} in:\n{}", metric, label, body))
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_report_feed_and_account_freshness() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Newsletters".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let account_id = account.id.clone().unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "TLDR".to_string(),
+        account_id.clone(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let feed = FeedOps::create(&mut conn, &NewFeed::new(
+        "TLDR \"daily\"".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+    let empty_feed = FeedOps::create(&mut conn, &NewFeed::new(
+        "Empty".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        false,
+    )).unwrap();
+
+    // Newest item stored three days ago
+    for days in [3, 5] {
+        let mut item = NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Issue from {} days ago", days),
+            None,
+            None,
+            None,
+            Utc::now() - Duration::days(days),
+            None,
+            None,
+            None,
+            None,
+        );
+        item.created_at = (Utc::now() - Duration::days(days)).to_rfc3339();
+        FeedItemOps::create(&mut conn, &item).unwrap();
+    }
+
+    // Only completed runs count as successes
+    for (hours, status) in [(2, ProcessingRunStatus::Failed), (26, ProcessingRunStatus::Completed)] {
+        let mut run = NewProcessingRun::new(account_id.clone());
+        run.status = status.as_str().to_string();
+        run.started_at = (Utc::now() - Duration::hours(hours)).to_rfc3339();
+        run.finished_at = Some((Utc::now() - Duration::hours(hours)).to_rfc3339());
+        ProcessingRunOps::create(&mut conn, &run).unwrap();
+    }
+    drop(conn);
+
+    let response = app(pool)
+        .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+    let body = String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap();
+
+    assert!(body.contains("# TYPE mail2feed_feed_newest_item_age_seconds gauge"));
+    assert!(body.contains(r#"feed="TLDR \"daily\"",active="true"}"#));
+    let feed_age = sample(&body, "mail2feed_feed_newest_item_age_seconds", feed.id.as_ref().unwrap());
+    assert!((3 * 86_400..3 * 86_400 + 60).contains(&feed_age), "{}", feed_age);
+    // A feed without items ages from its creation
+    let empty_age = sample(&body, "mail2feed_feed_newest_item_age_seconds", empty_feed.id.as_ref().unwrap());
+    assert!(empty_age < 60, "{}", empty_age);
+
+    let account_age = sample(&body, "mail2feed_account_last_success_age_seconds", &account_id);
+    assert!((26 * 3600..26 * 3600 + 60).contains(&account_age), "{}", account_age);
+}
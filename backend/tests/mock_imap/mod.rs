@@ -0,0 +1,641 @@
+//! In-process IMAP server for tests
+//!
+//! Speaks enough IMAP4rev1 for `ImapClient`: CAPABILITY, LOGIN, LIST, STATUS,
+//! SELECT and EXAMINE, SEARCH and FETCH by UID or sequence number, UID STORE,
+//! COPY, MOVE and EXPUNGE, Gmail's X-GM-RAW label searches when advertising
+//! X-GM-EXT-1, and optionally STARTTLS or implicit TLS with the
+//! certificate in `tests/fixtures`. Every command is recorded, and commands containing a
+//! refused fragment are answered with NO, which is how tests drive the
+//! client's fallbacks:
+//!
+//! ```ignore
+//! let server = MockImap::start("UIDPLUS MOVE");
+//! let uid = server.add_message("INBOX", MockMessage::new("news@example.com", "Issue 1"));
+//! server.refuse("BODY.PEEK[HEADER]");
+//! let fetch = server.client().fetch_emails_from_folder("INBOX", None, None, false).await?;
+//! ```
+
+// Each test crate uses part of the server
+#![allow(dead_code)]
+
+use mail2feed_backend::db::{connection::DatabasePool, models::{ConnectionSecurity, ImapAccount}};
+use mail2feed_backend::imap::client::ImapClient;
+use mail2feed_backend::testing::TestAccount;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::common::setup_test_db;
+
+/// Pin of `tests/fixtures/imap-server.crt`, which the server presents after STARTTLS
+pub const CERTIFICATE_PIN: &str = "cert-sha256:A5:A2:A5:E0:01:87:DE:BD:3E:15:9C:5E:A0:DE:2D:B7:79:A5:E9:68:C3:D9:42:13:46:E1:35:EF:B2:8F:E0:F1";
+
+/// Hierarchy delimiter of the server's folders
+pub const DELIMITER: char = '/';
+
+#[derive(Debug, Clone)]
+pub struct MockMessage {
+    pub uid: u32,
+    pub flags: Vec<String>,
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub message_id: String,
+    /// RFC 2822 date of the Date header
+    pub date: String,
+    /// Further header lines, e.g. `List-Unsubscribe: <...>`
+    pub extra_headers: Vec<String>,
+    pub body: String,
+    /// Gmail labels, e.g. `\Important` or `Newsletters`
+    pub labels: Vec<String>,
+}
+
+impl MockMessage {
+    pub fn new(from: &str, subject: &str) -> Self {
+        Self {
+            uid: 0,
+            flags: Vec::new(),
+            from: from.to_string(),
+            to: "reader@example.com".to_string(),
+            subject: subject.to_string(),
+            message_id: format!("<{}@example.com>", uuid::Uuid::new_v4()),
+            date: "Mon, 1 Sep 2025 09:00:00 +0000".to_string(),
+            extra_headers: Vec::new(),
+            body: format!("Body of {}", subject),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_string());
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = body.to_string();
+        self
+    }
+
+    pub fn date(mut self, date: &str) -> Self {
+        self.date = date.to_string();
+        self
+    }
+
+    pub fn message_id(mut self, message_id: &str) -> Self {
+        self.message_id = message_id.to_string();
+        self
+    }
+
+    pub fn header_line(mut self, name: &str, value: &str) -> Self {
+        self.extra_headers.push(format!("{}: {}", name, value));
+        self
+    }
+
+    pub fn seen(mut self) -> Self {
+        self.flags.push("\\Seen".to_string());
+        self
+    }
+
+    pub fn is_seen(&self) -> bool {
+        self.has_flag("\\Seen")
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|existing| existing.eq_ignore_ascii_case(flag))
+    }
+
+    fn header(&self) -> String {
+        let extra: String = self.extra_headers.iter().map(|line| format!("{}\r\n", line)).collect();
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: {}\r\n{}\r\n",
+            self.from, self.to, self.subject, self.date, self.message_id, extra
+        )
+    }
+
+    fn envelope(&self) -> String {
+        let from = address(&self.from);
+        format!(
+            "({} {} {} {} {} {} NIL NIL NIL {})",
+            quote(&self.date), quote(&self.subject), from, from, from, address(&self.to), quote(&self.message_id)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockFolder {
+    pub messages: Vec<MockMessage>,
+    pub uid_validity: u32,
+    pub selectable: bool,
+}
+
+/// State of the server, shared by its connections
+#[derive(Default)]
+pub struct Mailboxes {
+    pub folders: BTreeMap<String, MockFolder>,
+    /// Commands received, without their tags, in order
+    pub commands: Vec<String>,
+    next_uid: u32,
+    refused: Vec<String>,
+}
+
+impl Mailboxes {
+    pub fn messages(&self, folder: &str) -> &[MockMessage] {
+        &self.folders[folder].messages
+    }
+
+    pub fn uids(&self, folder: &str) -> Vec<u32> {
+        self.messages(folder).iter().map(|message| message.uid).collect()
+    }
+
+    pub fn message(&self, folder: &str, uid: u32) -> &MockMessage {
+        self.messages(folder).iter().find(|message| message.uid == uid)
+            .unwrap_or_else(|| panic!("No UID {} in '{}'", uid, folder))
+    }
+
+    /// Whether a command starting with `command` was received
+    pub fn sent(&self, command: &str) -> bool {
+        self.commands.iter().any(|sent| sent.starts_with(command))
+    }
+
+    fn add(&mut self, folder: &str, mut message: MockMessage) -> u32 {
+        self.next_uid += 1;
+        message.uid = self.next_uid;
+        self.folders.get_mut(folder).unwrap_or_else(|| panic!("No folder '{}'", folder)).messages.push(message);
+        self.next_uid
+    }
+
+    fn folder(&mut self, folder: &str) -> &mut MockFolder {
+        self.folders.get_mut(folder).unwrap()
+    }
+}
+
+/// How connections to the server are secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Security {
+    None,
+    StartTls,
+    Implicit,
+}
+
+pub struct MockImap {
+    pub port: u16,
+    security: Security,
+    mailboxes: Arc<Mutex<Mailboxes>>,
+}
+
+impl MockImap {
+    /// Plain-text server with an empty INBOX, advertising `capabilities`
+    pub fn start(capabilities: &'static str) -> Self {
+        Self::spawn(capabilities, Security::None)
+    }
+
+    /// Server that requires STARTTLS, as accounts with `starttls` security expect
+    pub fn start_tls(capabilities: &'static str) -> Self {
+        Self::spawn(capabilities, Security::StartTls)
+    }
+
+    /// Server that speaks TLS from the first byte, as on port 993
+    pub fn start_implicit_tls(capabilities: &'static str) -> Self {
+        Self::spawn(capabilities, Security::Implicit)
+    }
+
+    fn spawn(capabilities: &'static str, security: Security) -> Self {
+        let acceptor = (security != Security::None).then(|| {
+            let identity = native_tls::Identity::from_pkcs8(
+                include_bytes!("../fixtures/imap-server.crt"),
+                include_bytes!("../fixtures/imap-server.key"),
+            ).unwrap();
+            native_tls::TlsAcceptor::new(identity).unwrap()
+        });
+        let mailboxes = Arc::new(Mutex::new(Mailboxes::default()));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let shared = mailboxes.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let connection = Connection { capabilities, mailboxes: shared.clone(), selected: None };
+                let acceptor = acceptor.clone();
+                std::thread::spawn(move || connection.accept(stream, acceptor, security));
+            }
+        });
+
+        let server = Self { port, security, mailboxes };
+        server.add_folder("INBOX");
+        server
+    }
+
+    pub fn add_folder(&self, name: &str) {
+        self.mailboxes().folders.insert(name.to_string(), MockFolder { messages: Vec::new(), uid_validity: 1, selectable: true });
+    }
+
+    /// A folder listed with `\Noselect`, e.g. a parent that only holds other folders
+    pub fn add_unselectable_folder(&self, name: &str) {
+        self.add_folder(name);
+        self.mailboxes().folder(name).selectable = false;
+    }
+
+    /// Append `message` to `folder`, returning its UID
+    pub fn add_message(&self, folder: &str, message: MockMessage) -> u32 {
+        self.mailboxes().add(folder, message)
+    }
+
+    pub fn set_uid_validity(&self, folder: &str, uid_validity: u32) {
+        self.mailboxes().folder(folder).uid_validity = uid_validity;
+    }
+
+    /// Answer commands containing `fragment` with NO
+    pub fn refuse(&self, fragment: &str) {
+        self.mailboxes().refused.push(fragment.to_string());
+    }
+
+    /// Stop refusing the commands `refuse` named
+    pub fn accept_all(&self) {
+        self.mailboxes().refused.clear();
+    }
+
+    pub fn mailboxes(&self) -> MutexGuard<'_, Mailboxes> {
+        self.mailboxes.lock().unwrap()
+    }
+
+    /// Fixture of an account connecting to this server, to add rules to
+    pub fn test_account(&self, name: &str) -> TestAccount {
+        let (port, security) = (self.port as i32, self.security);
+        TestAccount::new(name).configure(|account| {
+            account.host = "127.0.0.1".to_string();
+            account.port = port;
+            account.set_security(match security {
+                Security::None => ConnectionSecurity::None,
+                Security::StartTls => ConnectionSecurity::StartTls,
+                Security::Implicit => ConnectionSecurity::SslTls,
+            });
+            account.tls_pin = account.use_tls.then(|| CERTIFICATE_PIN.to_string());
+        })
+    }
+
+    /// An account connecting to this server, inserted into `pool`
+    pub fn account(&self, pool: &DatabasePool) -> ImapAccount {
+        self.test_account("Mock IMAP").insert(pool).unwrap().account
+    }
+
+    /// A client of this server, for tests that need no database
+    pub fn client(&self) -> ImapClient {
+        ImapClient::new(&self.account(&DatabasePool::SQLite(setup_test_db()))).unwrap()
+    }
+}
+
+const GREETING: &[u8] = b"* OK IMAP4rev1 mock server ready\r\n";
+
+struct Connection {
+    capabilities: &'static str,
+    mailboxes: Arc<Mutex<Mailboxes>>,
+    selected: Option<String>,
+}
+
+impl Connection {
+    fn accept(self, mut stream: TcpStream, acceptor: Option<native_tls::TlsAcceptor>, security: Security) {
+        if let (Security::Implicit, Some(acceptor)) = (security, &acceptor) {
+            if let Ok(mut tls_stream) = acceptor.accept(stream) {
+                if tls_stream.write_all(GREETING).is_ok() {
+                    self.serve(tls_stream);
+                }
+            }
+            return;
+        }
+        if stream.write_all(GREETING).is_err() {
+            return;
+        }
+        let Some(acceptor) = acceptor else {
+            return self.serve(stream);
+        };
+
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            return;
+        }
+        let tag = line.split(' ').next().unwrap_or("*").to_string();
+        if !line.trim_end().eq_ignore_ascii_case(&format!("{} STARTTLS", tag)) {
+            let _ = stream.write_all(format!("{} BAD STARTTLS required\r\n", tag).as_bytes());
+            return;
+        }
+        let _ = stream.write_all(format!("{} OK Begin TLS negotiation now\r\n", tag).as_bytes());
+        if let Ok(tls_stream) = acceptor.accept(stream) {
+            self.serve(tls_stream);
+        }
+    }
+
+    fn serve<S: Read + Write>(mut self, stream: S) {
+        let mut stream = BufReader::new(stream);
+        let mut line = String::new();
+        while stream.read_line(&mut line).unwrap_or(0) > 0 {
+            let request = line.trim_end().to_string();
+            line.clear();
+            let (tag, command) = request.split_once(' ').unwrap_or((&request, ""));
+            let (response, done) = self.respond(tag, command);
+            if stream.get_mut().write_all(response.as_bytes()).is_err() || done {
+                return;
+            }
+        }
+    }
+
+    /// The response to one command, and whether the connection ends with it
+    fn respond(&mut self, tag: &str, command: &str) -> (String, bool) {
+        let mut mailboxes = self.mailboxes.lock().unwrap();
+        mailboxes.commands.push(command.to_string());
+        if mailboxes.refused.iter().any(|fragment| command.contains(fragment.as_str())) {
+            return (format!("{} NO Command refused\r\n", tag), false);
+        }
+
+        let (name, arguments) = command.split_once(' ').unwrap_or((command, ""));
+        let name = name.to_uppercase();
+        let (name, arguments, by_uid) = match name.as_str() {
+            "UID" => {
+                let (name, arguments) = arguments.split_once(' ').unwrap_or((arguments, ""));
+                (name.to_uppercase(), arguments, true)
+            }
+            _ => (name, arguments, false),
+        };
+
+        let untagged = match (name.as_str(), &self.selected) {
+            ("CAPABILITY", _) => Ok(format!("* CAPABILITY {}\r\n", format!("IMAP4rev1 {}", self.capabilities).trim_end())),
+            ("LOGIN", _) | ("NOOP", _) => Ok(String::new()),
+            ("LOGOUT", _) => return (format!("* BYE Logging out\r\n{} OK LOGOUT completed\r\n", tag), true),
+            ("LIST", _) => Ok(list(&mailboxes, &words(arguments))),
+            ("SELECT", _) | ("EXAMINE", _) => {
+                let folder = words(arguments).into_iter().next().unwrap_or_default();
+                match mailboxes.folders.get(&folder).filter(|found| found.selectable) {
+                    Some(found) => {
+                        let access = if name == "SELECT" { "READ-WRITE" } else { "READ-ONLY" };
+                        let response = format!(
+                            "* FLAGS (\\Seen \\Deleted)\r\n* {} EXISTS\r\n* 0 RECENT\r\n* OK [UIDVALIDITY {}] UIDs valid\r\n* OK [UIDNEXT {}] Predicted next UID\r\n{} OK [{}] {} completed\r\n",
+                            found.messages.len(), found.uid_validity, mailboxes.next_uid + 1, tag, access, name
+                        );
+                        self.selected = Some(folder);
+                        return (response, false);
+                    }
+                    None => Err(format!("NO [NONEXISTENT] No folder '{}'", folder)),
+                }
+            }
+            ("STATUS", _) => {
+                let folder = words(arguments).into_iter().next().unwrap_or_default();
+                match mailboxes.folders.get(&folder).filter(|found| found.selectable) {
+                    Some(found) => {
+                        let unseen = found.messages.iter().filter(|message| !message.is_seen()).count();
+                        Ok(format!("* STATUS {} (MESSAGES {} UNSEEN {} UIDVALIDITY {})\r\n", quote(&folder), found.messages.len(), unseen, found.uid_validity))
+                    }
+                    None => Err(format!("NO [NONEXISTENT] No folder '{}'", folder)),
+                }
+            }
+            (_, None) => Err("BAD No folder selected".to_string()),
+            ("SEARCH", Some(folder)) => {
+                let folder = folder.clone();
+                search(&mailboxes, &folder, &words(arguments), by_uid, self.capabilities)
+            }
+            ("FETCH", Some(folder)) => {
+                let (set, items) = arguments.split_once(' ').unwrap_or((arguments, ""));
+                Ok(fetch(&mailboxes, folder, set, &items.to_uppercase(), by_uid))
+            }
+            ("STORE", Some(folder)) if by_uid => {
+                let folder = folder.clone();
+                let arguments = words(arguments);
+                let (set, operation) = (arguments.first().cloned().unwrap_or_default(), arguments.get(1).cloned().unwrap_or_default());
+                for message in &mut mailboxes.folder(&folder).messages {
+                    if in_set(&set, message.uid, u32::MAX) {
+                        for flag in &arguments[2..] {
+                            message.flags.retain(|existing| !existing.eq_ignore_ascii_case(flag));
+                            if operation.starts_with('+') {
+                                message.flags.push(flag.clone());
+                            }
+                        }
+                    }
+                }
+                Ok(String::new())
+            }
+            ("EXPUNGE", Some(_)) if by_uid && !self.capabilities.contains("UIDPLUS") => Err("BAD UID EXPUNGE requires UIDPLUS".to_string()),
+            ("EXPUNGE", Some(folder)) => {
+                let folder = folder.clone();
+                let set = if by_uid { arguments.to_string() } else { "1:*".to_string() };
+                Ok(expunge(&mut mailboxes, &folder, |message| in_set(&set, message.uid, u32::MAX) && message.has_flag("\\Deleted")))
+            }
+            ("MOVE", Some(_)) if !self.capabilities.contains("MOVE") => Err("BAD MOVE is not supported".to_string()),
+            ("COPY", Some(folder)) | ("MOVE", Some(folder)) if by_uid => {
+                let folder = folder.clone();
+                let arguments = words(arguments);
+                let (set, target) = (arguments.first().cloned().unwrap_or_default(), arguments.get(1).cloned().unwrap_or_default());
+                if mailboxes.folders.get(&target).is_some_and(|found| found.selectable) {
+                    let copied: Vec<MockMessage> = mailboxes.messages(&folder).iter()
+                        .filter(|message| in_set(&set, message.uid, u32::MAX))
+                        .cloned()
+                        .collect();
+                    let uids: Vec<u32> = copied.iter().map(|message| message.uid).collect();
+                    for message in copied {
+                        mailboxes.add(&target, message);
+                    }
+                    Ok(if name == "MOVE" {
+                        expunge(&mut mailboxes, &folder, |message| uids.contains(&message.uid))
+                    } else {
+                        String::new()
+                    })
+                } else {
+                    Err(format!("NO [TRYCREATE] No folder '{}'", target))
+                }
+            }
+            _ => Err(format!("BAD Unsupported command {}", name)),
+        };
+
+        let response = match untagged {
+            Ok(untagged) => format!("{}{} OK {} completed\r\n", untagged, tag, name),
+            Err(status) => format!("{} {}\r\n", tag, status),
+        };
+        (response, false)
+    }
+}
+
+fn list(mailboxes: &Mailboxes, arguments: &[String]) -> String {
+    let reference = arguments.first().cloned().unwrap_or_default();
+    let pattern = arguments.get(1).cloned().unwrap_or_default();
+    if pattern.is_empty() {
+        // Asks for the hierarchy delimiter
+        return format!("* LIST (\\Noselect) \"{}\" \"\"\r\n", DELIMITER);
+    }
+    let pattern = format!("{}{}", reference, pattern);
+    mailboxes.folders.iter()
+        .filter(|(name, _)| matches_pattern(&pattern, name))
+        .map(|(name, folder)| {
+            let children = mailboxes.folders.keys().any(|other| other.starts_with(&format!("{}{}", name, DELIMITER)));
+            let mut attributes = vec![if children { "\\HasChildren" } else { "\\HasNoChildren" }];
+            if !folder.selectable {
+                attributes.push("\\Noselect");
+            }
+            format!("* LIST ({}) \"{}\" {}\r\n", attributes.join(" "), DELIMITER, quote(name))
+        })
+        .collect()
+}
+
+/// Whether `name` matches a LIST pattern, where `*` matches anything and
+/// `%` anything but the delimiter
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.chars().next() {
+        None => name.is_empty(),
+        Some(wildcard @ ('*' | '%')) => {
+            let rest = &pattern[1..];
+            name.char_indices().map(|(index, _)| index).chain([name.len()])
+                .take_while(|index| wildcard == '*' || !name[..*index].contains(DELIMITER))
+                .any(|index| matches_pattern(rest, &name[index..]))
+        }
+        Some(literal) => name.starts_with(literal)
+            && matches_pattern(&pattern[literal.len_utf8()..], &name[literal.len_utf8()..]),
+    }
+}
+
+fn search(mailboxes: &Mailboxes, folder: &str, criteria: &[String], by_uid: bool, capabilities: &str) -> Result<String, String> {
+    let messages = mailboxes.messages(folder);
+    let mut matching: Vec<(usize, &MockMessage)> = messages.iter().enumerate().map(|(index, message)| (index + 1, message)).collect();
+    let mut criteria = criteria.iter();
+    while let Some(criterion) = criteria.next() {
+        match criterion.to_uppercase().as_str() {
+            "ALL" => {}
+            "SEEN" => matching.retain(|(_, message)| message.is_seen()),
+            "UNSEEN" => matching.retain(|(_, message)| !message.is_seen()),
+            "UID" => {
+                let set = criteria.next().cloned().unwrap_or_default();
+                let highest = messages.iter().map(|message| message.uid).max().unwrap_or(0);
+                matching.retain(|(_, message)| in_set(&set, message.uid, highest));
+            }
+            "HEADER" => {
+                let field = criteria.next().cloned().unwrap_or_default();
+                let value = criteria.next().cloned().unwrap_or_default();
+                if !field.eq_ignore_ascii_case("Message-ID") {
+                    return Err(format!("BAD Cannot search header {}", field));
+                }
+                matching.retain(|(_, message)| message.message_id.contains(&value));
+            }
+            "X-GM-RAW" if capabilities.contains("X-GM-EXT-1") => {
+                let query = criteria.next().cloned().unwrap_or_default();
+                match query.strip_prefix("label:") {
+                    Some(wanted) => matching.retain(|(_, message)| message.labels.iter().any(|label| search_term(label) == wanted)),
+                    None => matching.clear(),
+                }
+            }
+            set if set.starts_with(|c: char| c.is_ascii_digit() || c == '*') => {
+                matching.retain(|(sequence, _)| in_set(set, *sequence as u32, messages.len() as u32));
+            }
+            other => return Err(format!("BAD Unsupported search criterion {}", other)),
+        }
+    }
+    let found: Vec<String> = matching.iter()
+        .map(|(sequence, message)| if by_uid { message.uid } else { *sequence as u32 }.to_string())
+        .collect();
+    Ok(format!("* SEARCH {}\r\n", found.join(" ")).replace("SEARCH \r\n", "SEARCH\r\n"))
+}
+
+/// A label as Gmail search writes it, e.g. `work-stuff` for `Work stuff`
+fn search_term(label: &str) -> String {
+    label.trim_start_matches('\\').to_lowercase().replace([' ', '/'], "-")
+}
+
+fn fetch(mailboxes: &Mailboxes, folder: &str, set: &str, items: &str, by_uid: bool) -> String {
+    let messages = mailboxes.messages(folder);
+    let highest = if by_uid { messages.iter().map(|message| message.uid).max().unwrap_or(0) } else { messages.len() as u32 };
+    let mut response = String::new();
+    for (index, message) in messages.iter().enumerate() {
+        let sequence = index as u32 + 1;
+        if !in_set(set, if by_uid { message.uid } else { sequence }, highest) {
+            continue;
+        }
+        let mut parts = vec![format!("UID {}", message.uid)];
+        if items.contains("FLAGS") {
+            parts.push(format!("FLAGS ({})", message.flags.join(" ")));
+        }
+        if items.contains("ENVELOPE") {
+            parts.push(format!("ENVELOPE {}", message.envelope()));
+        }
+        if items.contains("[HEADER]") {
+            let header = message.header();
+            parts.push(format!("BODY[HEADER] {{{}}}\r\n{}", header.len(), header));
+        }
+        if items.contains("[TEXT]") {
+            parts.push(format!("BODY[TEXT] {{{}}}\r\n{}", message.body.len(), message.body));
+        }
+        response.push_str(&format!("* {} FETCH ({})\r\n", sequence, parts.join(" ")));
+    }
+    response
+}
+
+/// Remove the messages of `folder` that `remove` picks, returning the
+/// untagged EXPUNGE responses
+fn expunge(mailboxes: &mut Mailboxes, folder: &str, remove: impl Fn(&MockMessage) -> bool) -> String {
+    let mut untagged = String::new();
+    let mut sequence = 1;
+    mailboxes.folder(folder).messages.retain(|message| {
+        let removed = remove(message);
+        if removed {
+            untagged.push_str(&format!("* {} EXPUNGE\r\n", sequence));
+        } else {
+            sequence += 1;
+        }
+        !removed
+    });
+    untagged
+}
+
+/// Whether `number` is in a sequence set like `1,3:5,7:*`, where `*` is `highest`
+fn in_set(set: &str, number: u32, highest: u32) -> bool {
+    let parse = |bound: &str| if bound == "*" { Some(highest) } else { bound.parse().ok() };
+    set.split(',').any(|range| match range.split_once(':') {
+        Some((start, end)) => match (parse(start), parse(end)) {
+            (Some(start), Some(end)) => (start.min(end)..=start.max(end)).contains(&number),
+            _ => false,
+        },
+        None => parse(range) == Some(number),
+    })
+}
+
+/// Split command arguments on spaces outside quotes and parentheses,
+/// unquoting strings and opening parenthesized lists into their elements
+fn words(arguments: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let (mut quoted, mut escaped, mut started) = (false, false, false);
+    for c in arguments.chars() {
+        match c {
+            _ if escaped => {
+                word.push(c);
+                escaped = false;
+            }
+            '\\' if quoted => escaped = true,
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            ' ' | '(' | ')' if !quoted => {
+                if started || !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                started = false;
+            }
+            _ => word.push(c),
+        }
+    }
+    if started || !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// An ENVELOPE address list of one address, like `news@example.com` or
+/// `News <news@example.com>`
+fn address(value: &str) -> String {
+    let (name, email) = match value.rsplit_once('<') {
+        Some((name, email)) => (Some(name.trim()), email.trim_end_matches('>')),
+        None => (None, value),
+    };
+    let (mailbox, host) = email.split_once('@').unwrap_or((email, ""));
+    format!("(({} NIL {} {}))", name.map_or("NIL".to_string(), quote), quote(mailbox), quote(host))
+}
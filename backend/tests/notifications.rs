@@ -0,0 +1,215 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::routing::post;
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::clock::ManualClock;
+use mail2feed_backend::background::notifications::NotificationSettings;
+use mail2feed_backend::background::scheduler::EmailScheduler;
+use mail2feed_backend::background::{BackgroundConfig, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::testing::{TestFeed, TestRule};
+use mock_imap::MockImap;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+/// Path, selected headers and body of each call a receiver got
+type Received = Arc<Mutex<Vec<(String, HeaderMap, String)>>>;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+/// Local endpoint recording calls on `/hook`, `/ntfy/alerts` and `/gotify/message`
+async fn spawn_receiver() -> (String, Received) {
+    let received: Received = Arc::default();
+    let receiver = ["/hook", "/ntfy/alerts", "/gotify/message"].into_iter().fold(axum::Router::new(), |router, path| {
+        let recorder = received.clone();
+        router.route(path, post(move |headers: HeaderMap, body: String| async move {
+            recorder.lock().unwrap().push((path.to_string(), headers, body));
+            StatusCode::OK
+        }))
+    });
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(receiver.into_make_service()));
+
+    (format!("http://{}", addr), received)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Wait for alerts sent in the background to arrive
+async fn wait_for(received: &Received, count: usize) {
+    for _ in 0..100 {
+        if received.lock().unwrap().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected {} calls, got {}", count, received.lock().unwrap().len());
+}
+
+#[tokio::test]
+async fn test_notification_settings_are_validated_and_stored() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::GET, "/api/settings", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["notifications"], json!({ "failure_threshold": 3, "alert_on_auth_error": true, "channels": [] }));
+    let (status, _) = send(&app, Method::POST, "/api/settings/notifications/test", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for invalid in [
+        json!({ "failure_threshold": 0, "channels": [] }),
+        json!({ "channels": [{ "kind": "gotify", "url": "https://gotify.example.com" }] }),
+        json!({ "channels": [{ "kind": "webhook", "url": "ftp://example.com/hook" }] }),
+        json!({ "channels": [{ "kind": "email", "smtp_host": "smtp.example.com", "email_from": "alerts@example.com" }] }),
+    ] {
+        let (status, body) = send(&app, Method::PUT, "/api/settings", Some(json!({ "notifications": invalid }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+
+    let (status, body) = send(&app, Method::PUT, "/api/settings", Some(json!({ "notifications": {
+        "failure_threshold": 5,
+        "channels": [{
+            "kind": "email",
+            "smtp_host": "smtp.example.com",
+            "smtp_security": "tls",
+            "smtp_username": "alerts",
+            "smtp_password": "secret",
+            "email_from": "mail2feed <alerts@example.com>",
+            "email_to": ["admin@example.com"],
+        }],
+    } }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["notifications"]["failure_threshold"], 5);
+    assert_eq!(body["notifications"]["alert_on_auth_error"], true);
+    assert_eq!(body["notifications"]["channels"][0]["smtp_security"], "tls");
+
+    // Other changes leave the notification settings alone
+    let (status, body) = send(&app, Method::PUT, "/api/settings", Some(json!({ "values": {} }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["notifications"]["channels"][0]["email_to"], json!(["admin@example.com"]));
+}
+
+#[tokio::test]
+async fn test_test_alert_goes_out_on_every_channel() {
+    let (receiver_url, received) = spawn_receiver().await;
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::PUT, "/api/settings", Some(json!({ "notifications": { "channels": [
+        { "kind": "webhook", "url": format!("{}/hook", receiver_url) },
+        { "kind": "ntfy", "url": format!("{}/ntfy/alerts", receiver_url), "token": "tk_secret" },
+        { "kind": "gotify", "url": format!("{}/gotify", receiver_url), "token": "app-token", "priority": 5 },
+        { "kind": "webhook", "url": format!("{}/missing", receiver_url) },
+    ] } }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = send(&app, Method::POST, "/api/settings/notifications/test", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let delivered: Vec<_> = body.as_array().unwrap().iter().map(|result| (result["kind"].as_str().unwrap(), result["delivered"].as_bool().unwrap())).collect();
+    assert_eq!(delivered, [("webhook", true), ("ntfy", true), ("gotify", true), ("webhook", false)]);
+    assert!(body[3]["error"].as_str().unwrap().contains("404"), "{}", body);
+
+    let received = received.lock().unwrap();
+    let (path, _, webhook) = &received[0];
+    assert_eq!(path, "/hook");
+    assert_eq!(serde_json::from_str::<Value>(webhook).unwrap()["event"], "test");
+    let (path, headers, ntfy) = &received[1];
+    assert_eq!(path, "/ntfy/alerts");
+    assert_eq!(headers["authorization"], "Bearer tk_secret");
+    assert!(ntfy.contains("This is a test alert"), "{}", ntfy);
+    let (path, headers, gotify) = &received[2];
+    assert_eq!(path, "/gotify/message");
+    assert_eq!(headers["x-gotify-key"], "app-token");
+    assert_eq!(serde_json::from_str::<Value>(gotify).unwrap()["priority"], 5);
+}
+
+#[tokio::test]
+async fn test_failing_account_is_alerted_about_once_per_streak() {
+    let (receiver_url, received) = spawn_receiver().await;
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.refuse("LOGIN");
+    let fixture = server.test_account("Work")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+    let account_id = fixture.account.id.clone().unwrap();
+
+    let mut settings = NotificationSettings {
+        failure_threshold: 2,
+        alert_on_auth_error: false,
+        ..Default::default()
+    };
+    settings.channels = serde_json::from_value(json!([{ "kind": "webhook", "url": format!("{}/hook", receiver_url) }])).unwrap();
+    settings.store(&pool).unwrap();
+
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let config = BackgroundConfig { per_account_interval_minutes: 1, ..Default::default() };
+    let interval = config.per_account_interval();
+    let scheduler = EmailScheduler::with_clock(pool.clone(), config, clock.clone()).unwrap();
+    let run = || async {
+        assert_eq!(scheduler.process_due_accounts().await.unwrap(), 1);
+        clock.advance(interval);
+    };
+
+    // The refused logins fail the runs; the second reaches the threshold
+    run().await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(received.lock().unwrap().is_empty());
+    run().await;
+    wait_for(&received, 1).await;
+    let alert: Value = serde_json::from_str(&received.lock().unwrap()[0].2).unwrap();
+    assert_eq!(alert["event"], "account_failing");
+    assert_eq!(alert["account"], json!({ "id": account_id, "name": "Work" }));
+    assert_eq!(alert["consecutive_failures"], 2);
+    assert!(alert["error"].as_str().unwrap().contains("Authentication failed"), "{}", alert);
+
+    // Further failures belong to the same streak
+    run().await;
+    let state = scheduler.get_account_state(&account_id).await.unwrap();
+    assert_eq!((state.failing_runs, state.failure_alerted), (3, true));
+
+    // A clean run ends it; a refused login is now alerted about right away
+    server.accept_all();
+    run().await;
+    let state = scheduler.get_account_state(&account_id).await.unwrap();
+    assert_eq!((state.failing_runs, state.failure_alerted), (0, false));
+    settings.alert_on_auth_error = true;
+    settings.store(&pool).unwrap();
+    server.refuse("LOGIN");
+    run().await;
+    wait_for(&received, 2).await;
+    let alert: Value = serde_json::from_str(&received.lock().unwrap()[1].2).unwrap();
+    assert_eq!((alert["event"].as_str(), alert["consecutive_failures"].as_u64()), (Some("account_login_failed"), Some(1)));
+}
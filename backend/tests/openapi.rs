@@ -0,0 +1,260 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api::{self, openapi::ApiDoc};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+use utoipa::OpenApi;
+
+use common::setup_test_db;
+
+fn app() -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(setup_test_db()), background_handle)
+}
+
+fn spec() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).unwrap()
+}
+
+/// Collect every `$ref` in the document
+fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => refs.push(reference.clone()),
+                    _ => collect_refs(value, refs),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_spec_documents_every_route() {
+    let spec = spec();
+    let paths = spec["paths"].as_object().unwrap();
+
+    let expected = [
+        ("/health", "get"),
+        ("/health/live", "get"),
+        ("/health/ready", "get"),
+        ("/metrics", "get"),
+        ("/api/imap-accounts", "get"),
+        ("/api/imap-accounts", "post"),
+        ("/api/imap-accounts/{id}", "get"),
+        ("/api/imap-accounts/{id}", "put"),
+        ("/api/imap-accounts/{id}", "delete"),
+        ("/api/email-rules", "get"),
+        ("/api/email-rules", "post"),
+        ("/api/email-rules/{id}", "get"),
+        ("/api/email-rules/{id}", "put"),
+        ("/api/email-rules/{id}", "delete"),
+        ("/api/email-rules/{id}/stats", "get"),
+        ("/api/email-rules/{id}/preview", "get"),
+        ("/api/email-rules/{id}/backfill", "post"),
+        ("/api/email-rules/{id}/feeds", "get"),
+        ("/api/email-rules/{id}/feeds", "post"),
+        ("/api/email-rules/{id}/feeds/{feed_id}", "delete"),
+        ("/api/email-rules/with-feed", "post"),
+        ("/api/email-rules/validate-expression", "post"),
+        ("/api/feeds", "get"),
+        ("/api/feeds", "post"),
+        ("/api/feeds/{id}", "get"),
+        ("/api/feeds/{id}", "put"),
+        ("/api/feeds/{id}", "delete"),
+        ("/api/feeds/{id}/items", "get"),
+        ("/api/feeds/{id}/items/metadata", "get"),
+        ("/api/feeds/{id}/verify", "get"),
+        ("/api/feeds/{id}/cleanup", "post"),
+        ("/api/cleanup", "post"),
+        ("/api/feeds/{id}/export", "get"),
+        ("/api/exports/{job_id}", "get"),
+        ("/api/feeds/{id}/webhook/test", "post"),
+        ("/api/timeline", "get"),
+        ("/api/feeds/{id}/integrations", "get"),
+        ("/api/feeds/{id}/integrations", "post"),
+        ("/api/chat-integrations/{id}", "get"),
+        ("/api/chat-integrations/{id}", "put"),
+        ("/api/chat-integrations/{id}", "delete"),
+        ("/api/chat-integrations/{id}/test", "post"),
+        ("/api/deliveries/failed", "get"),
+        ("/api/deliveries/retry", "post"),
+        ("/api/deliveries/{id}", "delete"),
+        ("/api/deliveries/{id}/retry", "post"),
+        ("/api/quota-groups", "get"),
+        ("/api/quota-groups", "post"),
+        ("/api/quota-groups/{id}", "get"),
+        ("/api/quota-groups/{id}", "put"),
+        ("/api/quota-groups/{id}", "delete"),
+        ("/api/quota-groups/{id}/usage", "get"),
+        ("/api/imap-accounts/{id}/quota", "get"),
+        ("/api/senders/aliases", "get"),
+        ("/api/senders/aliases", "post"),
+        ("/api/senders/aliases/{id}", "get"),
+        ("/api/senders/aliases/{id}", "put"),
+        ("/api/senders/aliases/{id}", "delete"),
+        ("/api/senders/stats", "get"),
+        ("/api/imap-accounts/{id}/filters", "get"),
+        ("/api/imap-accounts/{id}/filters", "post"),
+        ("/api/imap-accounts/{id}/filters/{filter_id}", "get"),
+        ("/api/imap-accounts/{id}/filters/{filter_id}", "put"),
+        ("/api/imap-accounts/{id}/filters/{filter_id}", "delete"),
+        ("/api/feed-items/{id}", "get"),
+        ("/api/feed-items/{id}", "patch"),
+        ("/api/feeds/{id}/items/bulk", "post"),
+        ("/api/feed-items/{id}/share", "post"),
+        ("/api/feed-items/{id}/unsubscribe", "post"),
+        ("/feeds/{id}/rss", "get"),
+        ("/feeds/{id}/atom", "get"),
+        ("/feeds/{feed_id}/items/{item_id}", "get"),
+        ("/items/{id}/html", "get"),
+        ("/feeds/{feed_id}/items/{item_id}/attachments/{n}", "get"),
+        ("/feed-items/{id}/html", "get"),
+        ("/api/imap/{id}/test", "get"),
+        ("/api/imap/{id}/tls-fingerprint", "get"),
+        ("/api/imap-accounts/{id}/folders", "get"),
+        ("/api/imap-accounts/{id}/test", "post"),
+        ("/api/imap-accounts/test", "post"),
+        ("/api/imap/{id}/process", "post"),
+        ("/api/imap/process-all", "post"),
+        ("/api/setup", "get"),
+        ("/api/setup", "post"),
+        ("/api/setup/validate-connection", "post"),
+        ("/api/setup/suggest-folders", "post"),
+        ("/api/setup/finalize", "post"),
+        ("/api/import", "post"),
+        ("/api/background/status", "get"),
+        ("/api/background/start", "post"),
+        ("/api/background/stop", "post"),
+        ("/api/background/restart", "post"),
+        ("/api/background/config", "put"),
+        ("/api/background/process/{account_id}", "post"),
+        ("/api/background/process-all", "post"),
+        ("/api/background/runs", "get"),
+        ("/api/background/runs/{run_id}", "get"),
+        ("/api/background/runs/{run_id}/intents", "get"),
+        ("/api/background/runs/{run_id}/rule-costs", "get"),
+        ("/api/background/runs/{run_id}/rollback", "post"),
+        ("/api/admin/maintenance", "get"),
+        ("/api/admin/maintenance", "post"),
+        ("/api/admin/maintenance", "delete"),
+        ("/api/admin/maintenance/backfill-metadata", "post"),
+        ("/api/admin/maintenance/offload-bodies", "post"),
+        ("/api/admin/maintenance/tasks", "get"),
+        ("/api/admin/maintenance/tasks/{task_id}", "get"),
+        ("/api/admin/version", "get"),
+        ("/api/admin/feeds/merge", "post"),
+        ("/api/admin/feeds/{id}/split", "post"),
+        ("/api/jobs", "get"),
+        ("/api/jobs/{id}", "get"),
+        ("/api/analysis/storage-forecast", "get"),
+        ("/api/analysis/ratings", "get"),
+        ("/api/analysis/rule-costs", "get"),
+        ("/fever/", "post"),
+        ("/api/settings", "get"),
+        ("/api/settings", "put"),
+        ("/api/settings/notifications/test", "post"),
+        ("/api/stats", "get"),
+    ];
+
+    for (path, method) in expected {
+        assert!(
+            paths.get(path).and_then(|item| item.get(method)).is_some(),
+            "{} {} missing from the OpenAPI spec",
+            method.to_uppercase(),
+            path
+        );
+    }
+
+    let documented: usize = paths.values()
+        .map(|item| item.as_object().unwrap().len())
+        .sum();
+    assert_eq!(documented, expected.len(), "spec documents routes not listed in this test");
+}
+
+#[test]
+fn test_spec_references_resolve() {
+    let spec = spec();
+    let schemas = spec["components"]["schemas"].as_object().unwrap();
+
+    let mut refs = Vec::new();
+    collect_refs(&spec, &mut refs);
+    assert!(!refs.is_empty());
+
+    for reference in refs {
+        let name = reference.strip_prefix("#/components/schemas/")
+            .unwrap_or_else(|| panic!("unexpected reference {}", reference));
+        assert!(schemas.contains_key(name), "schema {} is referenced but not registered", name);
+    }
+}
+
+#[test]
+fn test_spec_path_parameters_are_declared() {
+    let spec = spec();
+
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        let placeholders: Vec<&str> = path.split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .collect();
+
+        for (method, operation) in item.as_object().unwrap() {
+            let declared: Vec<&str> = operation["parameters"].as_array()
+                .map(|params| params.iter()
+                    .filter(|param| param["in"] == "path")
+                    .filter_map(|param| param["name"].as_str())
+                    .collect())
+                .unwrap_or_default();
+            assert_eq!(declared, placeholders, "path parameters of {} {}", method, path);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_openapi_json_endpoint() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/api/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let served: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(served, spec());
+    assert_eq!(served["info"]["title"], "mail2feed API");
+}
+
+#[tokio::test]
+async fn test_swagger_ui_endpoint() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/api/docs/")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::cleanup::FeedCleanupService;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// A feed keeping one item and allowing two pins, with items published
+/// `days_ago`, titled `Item <days>`; returns the feed and its item IDs
+fn create_feed(pool: &DbPool, days_ago: &[i64]) -> (Feed, Vec<String>) {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let mut new_feed = NewFeed::with_retention(
+        "Pinned Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+        Some(1),
+        None,
+        Some(0),
+    );
+    new_feed.max_age_days = None;
+    new_feed.max_pinned = Some(2);
+    let feed = FeedOps::create(&mut conn, &new_feed).unwrap();
+    let feed = FeedOps::update(&mut conn, feed.id.as_ref().unwrap(), &new_feed).unwrap();
+
+    let item_ids = days_ago.iter().map(|days| {
+        FeedItemOps::create(&mut conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Item {}", days),
+            None,
+            None,
+            None,
+            Utc::now() - Duration::days(*days),
+            None,
+            None,
+            None,
+            None,
+        )).unwrap().id.unwrap()
+    }).collect();
+    (feed, item_ids)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+async fn pin(app: &axum::Router, item_id: &str, pinned: bool) -> (StatusCode, Value) {
+    let (status, body) = send(app, Method::PATCH, &format!("/api/feed-items/{}", item_id), Some(json!({ "pinned": pinned }))).await;
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_pinned_items_come_first_up_to_the_cap() {
+    let pool = setup_test_db();
+    let (feed, items) = create_feed(&pool, &[1, 2, 3]);
+    let feed_id = feed.id.unwrap();
+    let app = app(pool);
+
+    let (status, item) = pin(&app, &items[2], true).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(item["pinned"], true);
+    assert_eq!(pin(&app, &items[1], true).await.0, StatusCode::OK);
+
+    let (status, body) = pin(&app, &items[0], true).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"], "Feed 'Pinned Feed' allows at most 2 pinned items; unpin one first");
+
+    // Pinned items lead, newest first, then the rest
+    let (_, body) = send(&app, Method::GET, &format!("/api/feeds/{}/items", feed_id), None).await;
+    let listed: Value = serde_json::from_str(&body).unwrap();
+    let titles: Vec<_> = listed["items"].as_array().unwrap().iter().map(|item| (item["title"].as_str().unwrap(), item["pinned"].as_bool().unwrap())).collect();
+    assert_eq!(titles, [("Item 2", true), ("Item 3", true), ("Item 1", false)]);
+
+    let (_, rss) = send(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), None).await;
+    let positions: Vec<_> = ["Item 2", "Item 3", "Item 1"].iter().map(|title| rss.find(title).unwrap()).collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{}", rss);
+
+    // Unpinning makes room
+    assert_eq!(pin(&app, &items[1], false).await.0, StatusCode::OK);
+    assert_eq!(pin(&app, &items[0], true).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_cleanup_keeps_pinned_items() {
+    let pool = setup_test_db();
+    // Created oldest first, so cleanup would remove Item 3 and Item 2
+    let (feed, items) = create_feed(&pool, &[3, 2, 1]);
+    FeedItemOps::set_pinned(&mut pool.get().unwrap(), &items[0], true).unwrap();
+
+    let cleanup = FeedCleanupService::new(DatabasePool::SQLite(pool.clone()));
+    let result = cleanup.cleanup_feed(&feed).await.unwrap();
+    assert_eq!(result.items_removed, 1);
+
+    let remaining = FeedItemOps::get_by_feed_id(&mut pool.get().unwrap(), feed.id.as_ref().unwrap(), None).unwrap();
+    let titles: Vec<_> = remaining.iter().map(|item| item.title.as_str()).collect();
+    assert_eq!(titles, ["Item 3", "Item 1"]);
+}
+
+#[tokio::test]
+async fn test_negative_pin_cap_is_rejected() {
+    let pool = setup_test_db();
+    let (feed, _) = create_feed(&pool, &[]);
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::PUT, &format!("/api/feeds/{}", feed.id.unwrap()), Some(json!({
+        "title": "Pinned Feed",
+        "email_rule_id": feed.email_rule_id,
+        "feed_type": "rss",
+        "is_active": true,
+        "max_pinned": -1,
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("max_pinned must not be negative"), "{}", body);
+}
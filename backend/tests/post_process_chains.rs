@@ -0,0 +1,135 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::DeferredActionOpsGeneric;
+use mail2feed_backend::imap::processor::{EmailProcessor, ProcessingResult};
+use mail2feed_backend::testing::{Fixture, TestAccount, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn chained_rule(chain: Value) -> TestRule {
+    TestRule::new("Newsletters")
+        .configure(|rule| rule.post_process_actions = Some(chain.to_string()))
+        .with_feed(TestFeed::new("Newsletters"))
+}
+
+async fn process(pool: &DatabasePool, fixture: &Fixture) -> ProcessingResult {
+    EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_chain_applies_actions_in_order_and_defers_delayed_ones() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("UIDPLUS MOVE");
+    server.add_folder("Archive");
+    let uid = server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue"));
+    let fixture = server.test_account("Work")
+        .with_rule(chained_rule(json!([
+            {"action": "mark_read"},
+            {"action": "add_flag", "flag": "\\Flagged"},
+            {"action": "copy_to_folder", "folder": "Archive"},
+            {"action": "delete", "delay_hours": 168}
+        ])))
+        .insert(&pool)
+        .unwrap();
+
+    let result = process(&pool, &fixture).await;
+    assert!(result.errors.is_empty() && result.post_process_failures.is_empty(), "{:?}", result);
+
+    let mailboxes = server.mailboxes();
+    let message = mailboxes.message("INBOX", uid);
+    assert!(message.is_seen() && message.has_flag("\\Flagged"));
+    assert_eq!(mailboxes.messages("Archive").len(), 1);
+    drop(mailboxes);
+
+    // The deletion waits a week, after the other steps
+    let in_a_week = (Utc::now() + Duration::hours(169)).to_rfc3339();
+    assert!(DeferredActionOpsGeneric::get_due(&pool, &Utc::now().to_rfc3339()).unwrap().is_empty());
+    let deferred = DeferredActionOpsGeneric::get_due(&pool, &in_a_week).unwrap();
+    let actions: Vec<(&str, i64)> = deferred.iter().map(|action| (action.action.as_str(), action.uid)).collect();
+    assert_eq!(actions, [("delete", uid as i64)]);
+}
+
+#[tokio::test]
+async fn test_failed_step_is_reported_and_skips_the_rest_of_the_chain() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("UIDPLUS MOVE");
+    let uid = server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue"));
+    server.refuse("UID COPY");
+    let fixture = server.test_account("Work")
+        .with_rule(chained_rule(json!([
+            {"action": "copy_to_folder", "folder": "Archive"},
+            {"action": "mark_read"}
+        ])))
+        .insert(&pool)
+        .unwrap();
+
+    let result = process(&pool, &fixture).await;
+
+    // The item is kept and the run succeeds; only the failing step is reported
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.new_feed_items_created, 1);
+    assert_eq!(result.post_process_failures.len(), 1, "{:?}", result.post_process_failures);
+    assert!(result.post_process_failures[0].contains(&format!("could not copy_to_folder email UID {}", uid)));
+    assert!(!server.mailboxes().message("INBOX", uid).is_seen());
+}
+
+#[tokio::test]
+async fn test_rule_api_validates_and_stores_chains() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work").insert(&pool).unwrap();
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let app = api::create_routes(pool, BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    });
+    let create = |chain: Value| {
+        let app = app.clone();
+        let body = json!({
+            "name": "Chained",
+            "imap_account_id": fixture.account_id(),
+            "folder": "INBOX",
+            "is_active": true,
+            "post_process_actions": chain
+        });
+        async move {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri("/api/email-rules")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+
+    let (status, body) = create(json!([{"action": "delete"}, {"action": "mark_read"}])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("Action 1: nothing can follow delete"), "{}", body);
+    let (status, _) = create(json!([{"action": "copy_to_folder"}])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let chain = json!([{"action": "mark_read"}, {"action": "move_to_folder", "folder": "Archive", "delay_hours": 24}]);
+    let (status, rule) = create(chain.clone()).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", rule);
+    let stored: Value = serde_json::from_str(rule["post_process_actions"].as_str().unwrap()).unwrap();
+    assert_eq!(stored, chain);
+
+    // An empty chain is no chain
+    let (_, rule) = create(json!([])).await;
+    assert!(rule["post_process_actions"].is_null());
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{deferred, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, operations_generic::DeferredActionOpsGeneric, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn request(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// An account nothing listens for, a rule with a day's delay and a finished
+/// run that created one item
+fn setup(pool: &DbPool) -> (EmailRule, ProcessingRun, FeedItem) {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Unreachable".to_string(),
+        "127.0.0.1".to_string(),
+        1,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        false,
+    )).unwrap();
+    let mut new_rule = NewEmailRule::new(
+        "Delayed".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    );
+    new_rule.post_process_delay_hours = Some(24);
+    let rule = EmailRuleOps::create(&mut conn, &new_rule).unwrap();
+    let feed = FeedOps::create(&mut conn, &NewFeed::new(
+        "Delayed Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+
+    let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account.id.clone().unwrap())).unwrap();
+    let mut new_item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Newsletter".to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        Some("<newsletter@example.com>".to_string()),
+        None,
+        None,
+        None,
+    );
+    new_item.processing_run_id = run.id.clone();
+    let item = FeedItemOps::create(&mut conn, &new_item).unwrap();
+    ProcessingRunOps::finish(&mut conn, run.id.as_ref().unwrap(), &ProcessingRunStatus::Completed, 1, 1, None).unwrap();
+    (rule, run, item)
+}
+
+fn defer(pool: &DbPool, rule: &EmailRule, run: &ProcessingRun, item: &FeedItem, uid: u32, due_at: chrono::DateTime<Utc>) -> DeferredAction {
+    let mut conn = pool.get().unwrap();
+    let intent = NewProcessingIntent::new(
+        run.id.clone().unwrap(),
+        item.feed_id.clone(),
+        rule.folder.clone(),
+        uid,
+        item.email_message_id.clone(),
+        &EmailAction::MarkAsRead,
+        None,
+        item.title.clone(),
+        "{}".to_string(),
+    );
+    ProcessingIntentOps::create(&mut conn, &intent).unwrap();
+    ProcessingIntentOps::resolve(&mut conn, &intent.id, &ProcessingIntentStatus::Deferred, item.id.as_deref()).unwrap();
+    DeferredActionOps::create(&mut conn, &NewDeferredAction::new(
+        rule.id.clone().unwrap(),
+        rule.imap_account_id.clone(),
+        run.id.clone().unwrap(),
+        intent.id,
+        item.id.clone().unwrap(),
+        rule.folder.clone(),
+        uid,
+        item.email_message_id.clone(),
+        &EmailAction::MarkAsRead,
+        None,
+        due_at,
+    )).unwrap()
+}
+
+#[tokio::test]
+async fn test_rule_api_validates_delay() {
+    let pool = setup_test_db();
+    let (rule, _, _) = setup(&pool);
+    let app = app(pool);
+
+    let body = |hours: i64| json!({
+        "name": "Delayed",
+        "imap_account_id": rule.imap_account_id,
+        "folder": "INBOX",
+        "is_active": true,
+        "post_process_delay_hours": hours
+    });
+    for hours in [-1, 24 * 365] {
+        let (status, response) = request(&app, Method::POST, "/api/email-rules", Some(body(hours))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "accepted {}", hours);
+        assert!(response["error"].as_str().unwrap().contains("post_process_delay_hours"));
+    }
+
+    let (status, created) = request(&app, Method::POST, "/api/email-rules", Some(body(24))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["post_process_delay_hours"], 24);
+
+    // A delay of 0 applies the action right away, like no delay
+    let uri = format!("/api/email-rules/{}", created["id"].as_str().unwrap());
+    let (status, updated) = request(&app, Method::PUT, &uri, Some(body(0))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(updated["post_process_delay_hours"].is_null());
+}
+
+#[tokio::test]
+async fn test_due_actions_wait_for_the_account() {
+    let pool = setup_test_db();
+    let (rule, run, item) = setup(&pool);
+    assert_eq!(rule.post_process_delay(), Some(Duration::hours(24)));
+
+    let later = defer(&pool, &rule, &run, &item, 7, Utc::now() + Duration::hours(23));
+    let due = defer(&pool, &rule, &run, &item, 8, Utc::now() - Duration::minutes(1));
+    let db = DatabasePool::SQLite(pool.clone());
+    let now = Utc::now().to_rfc3339();
+    let ids: Vec<_> = DeferredActionOpsGeneric::get_due(&db, &now).unwrap().into_iter().map(|action| action.id).collect();
+    assert_eq!(ids, [due.id]);
+
+    // Nothing listens on the account's port, so the due action stays pending
+    assert_eq!(deferred::process_due(&db).await.unwrap(), 0);
+    let pending = DeferredActionOpsGeneric::get_due(&db, &now).unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].status, "pending");
+    assert!(pending[0].last_error.is_none());
+    assert!(later.due_at > now);
+}
+
+#[tokio::test]
+async fn test_rollback_cancels_pending_actions() {
+    let pool = setup_test_db();
+    let (rule, run, item) = setup(&pool);
+    defer(&pool, &rule, &run, &item, 7, Utc::now() + Duration::hours(24));
+
+    let uri = format!("/api/background/runs/{}/rollback", run.id.as_ref().unwrap());
+    let (status, result) = request(&app(pool.clone()), Method::POST, &uri, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["deferred_actions_cancelled"], 1);
+    assert_eq!(result["actions_reversed"], 0);
+
+    let far_future = (Utc::now() + Duration::days(365)).to_rfc3339();
+    assert!(DeferredActionOpsGeneric::get_due(&DatabasePool::SQLite(pool), &far_future).unwrap().is_empty());
+}
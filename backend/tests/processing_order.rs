@@ -0,0 +1,112 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::background::cleanup::FeedCleanupService;
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*};
+
+use common::setup_test_db;
+
+fn create_feed(conn: &mut SqliteConnection, max_items: Option<i32>) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let mut new_feed = NewFeed::with_retention(
+        "Backfilled Feed".to_string(),
+        None,
+        None,
+        rule.id.unwrap(),
+        "rss".to_string(),
+        true,
+        max_items,
+        None,
+        Some(0),
+    );
+    new_feed.max_age_days = None;
+    let feed = FeedOps::create(conn, &new_feed).unwrap();
+    FeedOps::update(conn, feed.id.as_ref().unwrap(), &new_feed).unwrap()
+}
+
+fn create_item(conn: &mut SqliteConnection, feed: &Feed, title: &str, pub_date: DateTime<Utc>) {
+    FeedItemOps::create(conn, &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        None,
+        None,
+        None,
+        pub_date,
+        None,
+        None,
+        None,
+        None,
+    )).unwrap();
+}
+
+fn titles(conn: &mut SqliteConnection, feed: &Feed) -> Vec<String> {
+    FeedItemOps::get_by_feed_id(conn, feed.id.as_ref().unwrap(), None)
+        .unwrap()
+        .into_iter()
+        .map(|item| item.title)
+        .collect()
+}
+
+#[test]
+fn test_items_list_by_pub_date_whatever_the_insertion_order() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let now = Utc::now();
+    let emails = [("Monday", now - Duration::days(3)), ("Tuesday", now - Duration::days(2)), ("Wednesday", now - Duration::days(1))];
+
+    let newest_first = create_feed(&mut conn, None);
+    for (title, pub_date) in emails.iter().rev() {
+        create_item(&mut conn, &newest_first, title, *pub_date);
+    }
+    let oldest_first = create_feed(&mut conn, None);
+    for (title, pub_date) in &emails {
+        create_item(&mut conn, &oldest_first, title, *pub_date);
+    }
+
+    assert_eq!(titles(&mut conn, &newest_first), ["Wednesday", "Tuesday", "Monday"]);
+    assert_eq!(titles(&mut conn, &oldest_first), ["Wednesday", "Tuesday", "Monday"]);
+
+    // Items sharing a date keep the same order from one listing to the next
+    create_item(&mut conn, &oldest_first, "Also Wednesday", emails[2].1);
+    let listed = titles(&mut conn, &oldest_first);
+    assert_eq!(listed, titles(&mut conn, &oldest_first));
+    assert_eq!(&listed[2..], ["Tuesday", "Monday"]);
+}
+
+#[tokio::test]
+async fn test_cleanup_evicts_oldest_published_items() {
+    let pool = setup_test_db();
+    let feed = {
+        let mut conn = pool.get().unwrap();
+        let feed = create_feed(&mut conn, Some(2));
+        // A newest-first backfill adds the oldest email last
+        let now = Utc::now();
+        create_item(&mut conn, &feed, "Wednesday", now - Duration::days(1));
+        create_item(&mut conn, &feed, "Tuesday", now - Duration::days(2));
+        create_item(&mut conn, &feed, "Monday", now - Duration::days(3));
+        feed
+    };
+
+    let cleanup = FeedCleanupService::new(DatabasePool::SQLite(pool.clone()));
+    assert_eq!(cleanup.cleanup_feed(&feed).await.unwrap().items_removed, 1);
+    assert_eq!(titles(&mut pool.get().unwrap(), &feed), ["Wednesday", "Tuesday"]);
+}
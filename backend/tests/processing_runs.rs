@@ -0,0 +1,407 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{recovery, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection) -> (ImapAccount, Feed) {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    let feed = FeedOps::create(conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+
+    (account, feed)
+}
+
+fn create_run_item(conn: &mut SqliteConnection, feed: &Feed, title: &str, run_id: Option<&str>) -> FeedItem {
+    let mut new_item = NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        title.to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        Some(format!("<{}@example.com>", title)),
+        None,
+        None,
+        None,
+    );
+    new_item.processing_run_id = run_id.map(str::to_string);
+    FeedItemOps::create(conn, &new_item).unwrap()
+}
+
+async fn post_rollback(app: axum::Router, run_id: &str) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/background/runs/{}/rollback", run_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, body.to_vec())
+}
+
+#[test]
+fn test_processing_run_lifecycle() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let (account, feed) = create_test_feed(&mut conn);
+
+    let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account.id.clone().unwrap())).unwrap();
+    let run_id = run.id.clone().unwrap();
+    assert_eq!(run.status, "running");
+    assert!(run.finished_at.is_none());
+
+    let item = create_run_item(&mut conn, &feed, "first", Some(&run_id));
+    assert_eq!(item.processing_run_id.as_deref(), Some(run_id.as_str()));
+
+    ProcessingRunActionOps::create(&mut conn, &NewProcessingRunAction::new(
+        run_id.clone(),
+        item.id.clone(),
+        "INBOX".to_string(),
+        42,
+        item.email_message_id.clone(),
+        &EmailAction::MoveToFolder,
+        Some("Archive".to_string()),
+    )).unwrap();
+
+    let finished = ProcessingRunOps::finish(&mut conn, &run_id, &ProcessingRunStatus::Completed, 3, 1, None).unwrap();
+    assert_eq!(finished.status, "completed");
+    assert_eq!(finished.emails_processed, 3);
+    assert_eq!(finished.items_created, 1);
+    assert!(finished.finished_at.is_some());
+    assert_eq!(finished.bytes_received, 0);
+
+    let metered = ProcessingRunOps::record_transfer(&mut conn, &run_id, 48_213, 1_024).unwrap();
+    assert_eq!(metered.bytes_received, 48_213);
+    assert_eq!(metered.bytes_sent, 1_024);
+
+    let actions = ProcessingRunActionOps::get_by_run_id(&mut conn, &run_id).unwrap();
+    assert_eq!(actions.len(), 1);
+    assert_eq!(actions[0].uid, 42);
+    assert_eq!(actions[0].action, "move_to_folder");
+    assert_eq!(actions[0].target_folder.as_deref(), Some("Archive"));
+
+    assert_eq!(FeedItemOps::delete_by_processing_run_id(&mut conn, &run_id).unwrap(), 1);
+    assert!(FeedItemOps::get_by_feed_id(&mut conn, feed.id.as_ref().unwrap(), None).unwrap().is_empty());
+}
+
+#[test]
+fn test_catch_up_range_is_recorded_on_run() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let (account, _feed) = create_test_feed(&mut conn);
+    let account_id = account.id.clone().unwrap();
+
+    assert_eq!(ProcessingRunOps::last_completed_start(&mut conn, &account_id).unwrap(), None);
+
+    // Only completed runs count as a point the account was caught up to
+    let mut week_ago = NewProcessingRun::new(account_id.clone());
+    week_ago.started_at = (Utc::now() - Duration::days(7)).to_rfc3339();
+    let completed = ProcessingRunOps::create(&mut conn, &week_ago).unwrap();
+    ProcessingRunOps::finish(&mut conn, completed.id.as_ref().unwrap(), &ProcessingRunStatus::Completed, 0, 0, None).unwrap();
+    let failed = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap();
+    ProcessingRunOps::finish(&mut conn, failed.id.as_ref().unwrap(), &ProcessingRunStatus::Failed, 0, 0, None).unwrap();
+    assert_eq!(
+        ProcessingRunOps::last_completed_start(&mut conn, &account_id).unwrap(),
+        Some(week_ago.started_at.clone())
+    );
+
+    let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id)).unwrap();
+    assert!(run.catch_up_from.is_none());
+    assert!(!run.catch_up_truncated);
+    let run = ProcessingRunOps::record_catch_up(&mut conn, run.id.as_ref().unwrap(), &week_ago.started_at, &run.started_at, true).unwrap();
+    assert_eq!(run.catch_up_from, Some(week_ago.started_at));
+    assert_eq!(run.catch_up_to.as_ref(), Some(&run.started_at));
+    assert!(run.catch_up_truncated);
+}
+
+#[tokio::test]
+async fn test_rollback_run_removes_only_its_items() {
+    let pool = setup_test_db();
+    let (run_id, feed_id) = {
+        let mut conn = pool.get().unwrap();
+        let (account, feed) = create_test_feed(&mut conn);
+        let account_id = account.id.clone().unwrap();
+
+        let earlier = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap();
+        ProcessingRunOps::finish(&mut conn, earlier.id.as_ref().unwrap(), &ProcessingRunStatus::Completed, 1, 1, None).unwrap();
+        create_run_item(&mut conn, &feed, "keep", earlier.id.as_deref());
+
+        let flood = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id)).unwrap();
+        let flood_id = flood.id.clone().unwrap();
+        create_run_item(&mut conn, &feed, "flood-1", Some(&flood_id));
+        create_run_item(&mut conn, &feed, "flood-2", Some(&flood_id));
+        ProcessingRunOps::finish(&mut conn, &flood_id, &ProcessingRunStatus::Completed, 2, 2, None).unwrap();
+
+        (flood_id, feed.id.clone().unwrap())
+    };
+
+    let (status, body) = post_rollback(app(pool.clone()), &run_id).await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["run_id"], run_id.as_str());
+    assert_eq!(json["items_removed"], 2);
+    assert_eq!(json["actions_reversed"], 0);
+
+    {
+        let mut conn = pool.get().unwrap();
+        let remaining = FeedItemOps::get_by_feed_id(&mut conn, &feed_id, None).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].title, "keep");
+        assert_eq!(ProcessingRunOps::get_by_id(&mut conn, &run_id).unwrap().status, "rolled_back");
+    }
+
+    // A run can only be rolled back once
+    let (status, _) = post_rollback(app(pool.clone()), &run_id).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_rollback_rejects_unknown_and_running_runs() {
+    let pool = setup_test_db();
+    let running_id = {
+        let mut conn = pool.get().unwrap();
+        let (account, _feed) = create_test_feed(&mut conn);
+        let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account.id.clone().unwrap())).unwrap();
+        run.id.unwrap()
+    };
+
+    let (status, _) = post_rollback(app(pool.clone()), "does-not-exist").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = post_rollback(app(pool.clone()), &running_id).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_startup_recovery_aborts_interrupted_runs() {
+    let pool = setup_test_db();
+    let (account_id, interrupted_id, completed_id, feed_id) = {
+        let mut conn = pool.get().unwrap();
+        let (account, feed) = create_test_feed(&mut conn);
+        let account_id = account.id.clone().unwrap();
+
+        let completed = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap();
+        ProcessingRunOps::finish(&mut conn, completed.id.as_ref().unwrap(), &ProcessingRunStatus::Completed, 1, 1, None).unwrap();
+
+        // A run that crashed after creating one item
+        let interrupted = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap();
+        create_run_item(&mut conn, &feed, "partial", interrupted.id.as_deref());
+
+        (account_id, interrupted.id.unwrap(), completed.id.unwrap(), feed.id.unwrap())
+    };
+
+    let result = recovery::recover_interrupted_runs(&DatabasePool::SQLite(pool.clone())).unwrap();
+    assert_eq!(result.aborted_runs.len(), 1);
+    assert_eq!(result.affected_accounts(), vec![account_id]);
+
+    {
+        let mut conn = pool.get().unwrap();
+        let aborted = ProcessingRunOps::get_by_id(&mut conn, &interrupted_id).unwrap();
+        assert_eq!(aborted.status, "aborted");
+        assert!(aborted.finished_at.is_some());
+        assert_eq!(aborted.error_message.as_deref(), Some(recovery::ABORTED_MESSAGE));
+        assert_eq!(ProcessingRunOps::get_by_id(&mut conn, &completed_id).unwrap().status, "completed");
+    }
+
+    // Nothing is left to recover on the next start
+    let again = recovery::recover_interrupted_runs(&DatabasePool::SQLite(pool.clone())).unwrap();
+    assert!(again.aborted_runs.is_empty());
+
+    // The partial run's items can be rolled back
+    let (status, body) = post_rollback(app(pool.clone()), &interrupted_id).await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["items_removed"], 1);
+    let mut conn = pool.get().unwrap();
+    assert!(FeedItemOps::get_by_feed_id(&mut conn, &feed_id, None).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_startup_recovery_rebuilds_items_from_pending_intents() {
+    let pool = setup_test_db();
+    let (run_id, feed_id) = {
+        let mut conn = pool.get().unwrap();
+        let (account, feed) = create_test_feed(&mut conn);
+        let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account.id.clone().unwrap())).unwrap();
+        let run_id = run.id.unwrap();
+
+        // The run logged two emails before crashing; only the first item was saved
+        create_run_item(&mut conn, &feed, "saved", Some(&run_id));
+        for (uid, title) in [(1, "saved"), (2, "lost")] {
+            let snapshot = serde_json::json!({
+                "uid": uid,
+                "message_id": format!("<{}@example.com>", title),
+                "subject": title,
+                "from": "news@example.com",
+                "to": "user@example.com",
+                "date": Utc::now(),
+                "body": format!("The {} newsletter", title),
+                "is_seen": false,
+            });
+            ProcessingIntentOps::create(&mut conn, &NewProcessingIntent::new(
+                run_id.clone(),
+                feed.id.clone().unwrap(),
+                "INBOX".to_string(),
+                uid,
+                Some(format!("<{}@example.com>", title)),
+                &EmailAction::Delete,
+                None,
+                title.to_string(),
+                snapshot.to_string(),
+            )).unwrap();
+        }
+        (run_id, feed.id.unwrap())
+    };
+
+    let result = recovery::recover_interrupted_runs(&DatabasePool::SQLite(pool.clone())).unwrap();
+    assert_eq!(result.aborted_runs.len(), 1);
+    assert_eq!(result.recovered_items, 1);
+    assert_eq!(result.reconciled_intents, 1);
+
+    {
+        let mut conn = pool.get().unwrap();
+        let items = FeedItemOps::get_by_feed_id(&mut conn, &feed_id, None).unwrap();
+        let lost = items.iter().find(|item| item.title == "lost").unwrap();
+        assert_eq!(lost.email_body.as_deref(), Some("The lost newsletter"));
+        assert_eq!(lost.processing_run_id.as_deref(), Some(run_id.as_str()));
+        assert_eq!(items.len(), 2);
+        assert!(ProcessingIntentOps::get_pending(&mut conn).unwrap().is_empty());
+    }
+
+    let response = app(pool.clone())
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/background/runs/{}/intents", run_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let intents: Value = serde_json::from_slice(&body).unwrap();
+    let statuses: Vec<&str> = intents.as_array().unwrap().iter().map(|intent| intent["status"].as_str().unwrap()).collect();
+    assert_eq!(statuses, vec!["reconciled", "recovered"]);
+    assert_eq!(intents[0]["action"], "delete");
+    assert!(intents[0].get("email_snapshot").is_none());
+
+    // The recovered item belongs to the aborted run, so rolling it back removes both
+    let (status, body) = post_rollback(app(pool.clone()), &run_id).await;
+    assert_eq!(status, StatusCode::OK);
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["items_removed"], 2);
+}
+
+async fn get_runs(app: axum::Router, query: &str) -> (StatusCode, Value) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/background/runs{}", query))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_list_runs_filters_history() {
+    let pool = setup_test_db();
+    let (account_id, other_account_id) = {
+        let mut conn = pool.get().unwrap();
+        let (account, _feed) = create_test_feed(&mut conn);
+        let (other_account, _feed) = create_test_feed(&mut conn);
+        let account_id = account.id.unwrap();
+
+        // Three runs of one account started a day apart, the oldest failed
+        for days_ago in [3, 2, 1] {
+            let mut new_run = NewProcessingRun::new(account_id.clone());
+            new_run.started_at = (Utc::now() - Duration::days(days_ago)).to_rfc3339();
+            let run = ProcessingRunOps::create(&mut conn, &new_run).unwrap();
+            let status = if days_ago == 3 { ProcessingRunStatus::Failed } else { ProcessingRunStatus::Completed };
+            ProcessingRunOps::finish(&mut conn, run.id.as_ref().unwrap(), &status, days_ago as i32, 0, None).unwrap();
+        }
+        ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(other_account.id.clone().unwrap())).unwrap();
+        (account_id, other_account.id.unwrap())
+    };
+
+    let (status, runs) = get_runs(app(pool.clone()), "").await;
+    assert_eq!(status, StatusCode::OK);
+    let runs = runs.as_array().unwrap();
+    assert_eq!(runs.len(), 4);
+    assert_eq!(runs[0]["imap_account_id"], other_account_id.as_str());
+    assert_eq!(runs[1]["emails_processed"], 1);
+
+    let (_, runs) = get_runs(app(pool.clone()), &format!("?account_id={}&status=completed", account_id)).await;
+    let processed: Vec<_> = runs.as_array().unwrap().iter().map(|run| run["emails_processed"].as_i64().unwrap()).collect();
+    assert_eq!(processed, [1, 2]);
+
+    let since = (Utc::now() - Duration::hours(60)).to_rfc3339().replace('+', "%2B");
+    let until = (Utc::now() - Duration::hours(36)).to_rfc3339().replace('+', "%2B");
+    let (_, runs) = get_runs(app(pool.clone()), &format!("?since={}&until={}", since, until)).await;
+    let runs = runs.as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0]["emails_processed"], 2);
+
+    let (_, runs) = get_runs(app(pool.clone()), "?limit=2").await;
+    assert_eq!(runs.as_array().unwrap().len(), 2);
+
+    for query in ["?status=done", "?since=yesterday", "?limit=0"] {
+        let (status, _) = get_runs(app(pool.clone()), query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
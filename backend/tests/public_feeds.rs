@@ -0,0 +1,146 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_test_feed(conn: &mut SqliteConnection) -> (Feed, FeedItem) {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    let feed = FeedOps::create(conn, &NewFeed::new(
+        "Test Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+
+    let item = FeedItemOps::create(conn, &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Hello".to_string(),
+        Some("Body".to_string()),
+        None,
+        None,
+        Utc::now(),
+        None,
+        None,
+        None,
+        None,
+    )).unwrap();
+    (feed, item)
+}
+
+async fn status(app: &axum::Router, uri: &str) -> StatusCode {
+    app.clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+/// Status of every anonymous endpoint of the feed
+async fn public_statuses(app: &axum::Router, feed: &Feed, item: &FeedItem) -> [StatusCode; 3] {
+    let feed_id = feed.id.as_ref().unwrap();
+    [
+        status(app, &format!("/feeds/{}/rss", feed_id)).await,
+        status(app, &format!("/feeds/{}/atom", feed_id)).await,
+        status(app, &format!("/feeds/{}/items/{}", feed_id, item.id.as_ref().unwrap())).await,
+    ]
+}
+
+async fn set_public_access(app: &axum::Router, feed: &Feed, public_access: Value) -> Value {
+    let request = json!({
+        "title": feed.title,
+        "email_rule_id": feed.email_rule_id,
+        "feed_type": feed.feed_type,
+        "is_active": feed.is_active,
+        "public_access": public_access,
+    });
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/api/feeds/{}", feed.id.as_ref().unwrap()))
+                .header("Content-Type", "application/json")
+                .body(Body::from(request.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+// A single test, as it changes the process-wide FEED_PUBLIC_ENDPOINTS setting
+#[tokio::test]
+async fn test_public_endpoints_follow_global_setting_and_feed_override() {
+    let pool = setup_test_db();
+    let (feed, item) = create_test_feed(&mut pool.get().unwrap());
+    let app = app(pool);
+    let ok = [StatusCode::OK; 3];
+    let not_found = [StatusCode::NOT_FOUND; 3];
+
+    std::env::remove_var("FEED_PUBLIC_ENDPOINTS");
+    assert_eq!(public_statuses(&app, &feed, &item).await, ok);
+
+    let updated = set_public_access(&app, &feed, json!(false)).await;
+    assert_eq!(updated["public_access"], false);
+    assert_eq!(public_statuses(&app, &feed, &item).await, not_found);
+
+    // The API keeps serving the feed and its items
+    let feed_id = feed.id.as_ref().unwrap();
+    assert_eq!(status(&app, &format!("/api/feeds/{}", feed_id)).await, StatusCode::OK);
+    assert_eq!(status(&app, &format!("/api/feeds/{}/items", feed_id)).await, StatusCode::OK);
+    assert_eq!(status(&app, &format!("/api/feed-items/{}", item.id.as_ref().unwrap())).await, StatusCode::OK);
+
+    std::env::set_var("FEED_PUBLIC_ENDPOINTS", "false");
+    set_public_access(&app, &feed, Value::Null).await;
+    assert_eq!(public_statuses(&app, &feed, &item).await, not_found);
+    assert_eq!(status(&app, &format!("/api/feeds/{}", feed_id)).await, StatusCode::OK);
+
+    // A feed can opt back in while the rest stay private
+    set_public_access(&app, &feed, json!(true)).await;
+    assert_eq!(public_statuses(&app, &feed, &item).await, ok);
+
+    std::env::remove_var("FEED_PUBLIC_ENDPOINTS");
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::DbPool;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, headers: &[(&str, &str)], body: Option<Value>) -> (StatusCode, String) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = request
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+async fn put_public_base_url(app: &axum::Router, url: &str) -> (StatusCode, Value) {
+    let (status, body) = send(app, Method::PUT, "/api/settings", &[], Some(json!({"public_base_url": url}))).await;
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+// One test, since the stored base URL applies to the whole process
+#[tokio::test]
+async fn test_public_base_url_drives_feed_links() {
+    let pool = setup_test_db();
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("News").with_item("Weekly issue")))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let item_id = fixture.items[0].id.clone().unwrap();
+    let app = app(pool);
+
+    // Without a configured base URL the self link follows the proxy's headers,
+    // and GUIDs stay opaque
+    let proxy = [("Host", "127.0.0.1:3001"), ("X-Forwarded-Host", "feeds.example.org"), ("X-Forwarded-Proto", "https")];
+    let (status, rss) = send(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), &proxy, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(rss.contains(&format!("href=\"https://feeds.example.org/feeds/{}/rss\"", feed_id)), "{}", rss);
+    assert!(rss.contains(&format!("<guid isPermaLink=\"false\">{}_{}</guid>", feed_id, item_id)), "{}", rss);
+
+    let (status, body) = put_public_base_url(&app, "example.com/mail2feed").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("Invalid public base URL"), "{}", body);
+
+    let (status, body) = put_public_base_url(&app, "https://mail2feed.example.com/").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["public_base_url"], "https://mail2feed.example.com");
+
+    let item_url = format!("https://mail2feed.example.com/feeds/{}/items/{}", feed_id, item_id);
+    let (_, rss) = send(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), &proxy, None).await;
+    assert!(rss.contains(&format!("<atom:link href=\"https://mail2feed.example.com/feeds/{}/rss\" rel=\"self\"", feed_id)), "{}", rss);
+    assert!(rss.contains(&format!("<guid>{}</guid>", item_url)), "{}", rss);
+
+    let (_, atom) = send(&app, Method::GET, &format!("/feeds/{}/atom", feed_id), &[], None).await;
+    assert!(atom.contains(&format!("<link href=\"https://mail2feed.example.com/feeds/{}/atom\" rel=\"self\"", feed_id)), "{}", atom);
+    let page_url = format!("https://mail2feed.example.com/items/{}/html", item_id);
+    assert!(atom.contains(&format!("<link href=\"{}\" rel=\"alternate\"", page_url)), "{}", atom);
+
+    // Removing the setting falls back to the request again
+    let (status, body) = put_public_base_url(&app, "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["public_base_url"], Value::Null);
+    let (_, rss) = send(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), &proxy, None).await;
+    assert!(!rss.contains("mail2feed.example.com"), "{}", rss);
+}
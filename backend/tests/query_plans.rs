@@ -0,0 +1,72 @@
+mod common;
+
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use common::setup_test_db;
+
+#[derive(QueryableByName)]
+struct PlanStep {
+    #[diesel(sql_type = Text)]
+    detail: String,
+}
+
+/// The steps SQLite plans for `sql`, one line each
+fn query_plan(sql: &str) -> Vec<String> {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    diesel::sql_query(format!("EXPLAIN QUERY PLAN {}", sql))
+        .load::<PlanStep>(&mut *conn)
+        .unwrap()
+        .into_iter()
+        .map(|step| step.detail)
+        .collect()
+}
+
+fn assert_uses_index(sql: &str, index: &str) {
+    let plan = query_plan(sql);
+    assert!(
+        plan.iter().any(|step| step.contains(&format!("USING INDEX {}", index))
+            || step.contains(&format!("USING COVERING INDEX {}", index))),
+        "expected {} for `{}`, got {:?}", index, sql, plan
+    );
+    assert!(!plan.iter().any(|step| step.starts_with("SCAN feed_items")), "full scan for `{}`: {:?}", sql, plan);
+}
+
+#[test]
+fn test_feed_listing_sorts_from_index() {
+    let sql = "SELECT * FROM feed_items WHERE feed_id = 'f' ORDER BY pub_date DESC LIMIT 20";
+    assert_uses_index(sql, "idx_feed_items_feed_pub_date");
+    assert!(
+        !query_plan(sql).iter().any(|step| step.contains("TEMP B-TREE")),
+        "listing a feed by date should not sort in memory"
+    );
+}
+
+#[test]
+fn test_duplicate_checks_use_composite_indexes() {
+    // The Message-ID index of cross-feed lookups answers this one too
+    assert_uses_index(
+        "SELECT COUNT(*) FROM feed_items WHERE feed_id = 'f' AND email_message_id = '<id@example.com>'",
+        "idx_feed_items_message_id_feed",
+    );
+    assert_uses_index(
+        "SELECT COUNT(*) FROM feed_items WHERE feed_id = 'f' AND title = 'Weekly' \
+         AND email_from = 'news@example.com' AND pub_date = '2025-01-01T00:00:00+00:00'",
+        "idx_feed_items_feed_title_from",
+    );
+}
+
+#[test]
+fn test_cross_feed_message_id_lookups_use_covering_index() {
+    assert_uses_index(
+        "SELECT feed_id FROM feed_items WHERE email_message_id = '<id@example.com>' LIMIT 1",
+        "idx_feed_items_message_id_feed",
+    );
+    assert_uses_index(
+        "SELECT feed_id FROM feed_items WHERE email_message_id = '<id@example.com>' AND feed_id IN \
+         (SELECT feeds.id FROM feeds INNER JOIN email_rules ON email_rules.id = feeds.email_rule_id \
+         WHERE email_rules.imap_account_id = 'a') LIMIT 1",
+        "idx_feed_items_message_id_feed",
+    );
+}
@@ -0,0 +1,262 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::quota::{self, QuotaExceeded, QuotaResource};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_account(conn: &mut SqliteConnection, name: &str, quota_group_id: Option<String>, max_feeds: Option<i32>) -> (ImapAccount, EmailRule) {
+    let mut new_account = NewImapAccount::new(
+        name.to_string(),
+        "localhost".to_string(),
+        993,
+        format!("{}@example.com", name),
+        "password".to_string(),
+        true,
+    );
+    new_account.quota_group_id = quota_group_id;
+    new_account.max_feeds = max_feeds;
+    let account = ImapAccountOps::create(conn, &new_account).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        format!("{} rule", name),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    (account, rule)
+}
+
+fn create_feed(conn: &mut SqliteConnection, rule: &EmailRule) -> Feed {
+    FeedOps::create(conn, &NewFeed::new(
+        "Existing Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap()
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn feed_request(rule: &EmailRule) -> Value {
+    json!({
+        "title": "New Feed",
+        "email_rule_id": rule.id,
+        "feed_type": "rss",
+        "is_active": true,
+    })
+}
+
+#[tokio::test]
+async fn test_account_feed_quota_refuses_new_feeds() {
+    let pool = setup_test_db();
+    let (_, rule) = create_account(&mut pool.get().unwrap(), "alice", None, Some(1));
+    let app = app(pool);
+
+    let (status, _) = send(&app, Method::POST, "/api/feeds", Some(feed_request(&rule))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send(&app, Method::POST, "/api/feeds", Some(feed_request(&rule))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "Quota exceeded: account 'alice' allows 1 feeds and has used 1");
+}
+
+#[tokio::test]
+async fn test_group_feed_quota_counts_every_member() {
+    let pool = setup_test_db();
+    let group = QuotaGroupOps::create(&mut pool.get().unwrap(), &NewQuotaGroup::new("Family".to_string(), Some(2), None, None)).unwrap();
+    let (_, alice_rule) = create_account(&mut pool.get().unwrap(), "alice", group.id.clone(), None);
+    let (_, bob_rule) = create_account(&mut pool.get().unwrap(), "bob", group.id.clone(), None);
+    let (_, carol_rule) = create_account(&mut pool.get().unwrap(), "carol", None, None);
+    create_feed(&mut pool.get().unwrap(), &alice_rule);
+    let bob_feed = create_feed(&mut pool.get().unwrap(), &bob_rule);
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::POST, "/api/feeds", Some(feed_request(&alice_rule))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "Quota exceeded: quota group 'Family' allows 2 feeds and has used 2");
+
+    // Accounts outside the group are not limited by it
+    let (status, _) = send(&app, Method::POST, "/api/feeds", Some(feed_request(&carol_rule))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Moving a feed within the group needs no room, moving one in does
+    let mut update = feed_request(&alice_rule);
+    let bob_feed_uri = format!("/api/feeds/{}", bob_feed.id.as_ref().unwrap());
+    let (status, _) = send(&app, Method::PUT, &bob_feed_uri, Some(update.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    update["email_rule_id"] = json!(carol_rule.id);
+    let (status, _) = send(&app, Method::PUT, &bob_feed_uri, Some(update.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    update["email_rule_id"] = json!(bob_rule.id);
+    let (status, _) = send(&app, Method::PUT, &bob_feed_uri, Some(update)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, Method::POST, "/api/feeds", Some(feed_request(&bob_rule))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_quota_group_crud_and_usage() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+
+    let (status, body) = send(&app, Method::POST, "/api/quota-groups", Some(json!({
+        "name": "Team",
+        "max_feeds": -1,
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "max_feeds must not be negative");
+
+    let (status, group) = send(&app, Method::POST, "/api/quota-groups", Some(json!({
+        "name": "Team",
+        "max_feeds": 5,
+        "max_items": 100,
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let group_id = group["id"].as_str().unwrap().to_string();
+    assert_eq!(group["max_processing_minutes_per_day"], Value::Null);
+
+    let (_, rule) = create_account(&mut pool.get().unwrap(), "alice", Some(group_id.clone()), Some(3));
+    let feed = create_feed(&mut pool.get().unwrap(), &rule);
+    FeedItemOps::create(&mut pool.get().unwrap(), &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Hello".to_string(),
+        None,
+        None,
+        None,
+        Utc::now(),
+        None,
+        None,
+        None,
+        None,
+    )).unwrap();
+
+    let (status, usage) = send(&app, Method::GET, &format!("/api/quota-groups/{}/usage", group_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(usage["max_items"], 100);
+    assert_eq!(usage["usage"], json!({ "feeds": 1, "items": 1, "processing_minutes_today": 0 }));
+
+    let (status, quota) = send(&app, Method::GET, &format!("/api/imap-accounts/{}/quota", rule.imap_account_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(quota["account"]["max_feeds"], 3);
+    assert_eq!(quota["account"]["usage"]["feeds"], 1);
+    assert_eq!(quota["group"]["max_feeds"], 5);
+
+    // Deleting the group leaves its accounts with their own limits
+    let (status, _) = send(&app, Method::DELETE, &format!("/api/quota-groups/{}", group_id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let account = ImapAccountOps::get_by_id(&mut pool.get().unwrap(), &rule.imap_account_id).unwrap();
+    assert_eq!(account.quota_group_id, None);
+    assert_eq!(account.max_feeds, Some(3));
+    let (_, quota) = send(&app, Method::GET, &format!("/api/imap-accounts/{}/quota", rule.imap_account_id), None).await;
+    assert_eq!(quota["group"], Value::Null);
+
+    let (status, _) = send(&app, Method::GET, &format!("/api/quota-groups/{}", group_id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_account_rejects_unknown_quota_group() {
+    let pool = setup_test_db();
+    let app = app(pool);
+
+    let (status, body) = send(&app, Method::POST, "/api/imap-accounts", Some(json!({
+        "name": "alice",
+        "host": "localhost",
+        "port": 993,
+        "username": "alice@example.com",
+        "password": "password",
+        "use_tls": true,
+        "quota_group_id": "missing",
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "Quota group missing does not exist");
+}
+
+#[test]
+fn test_processing_minutes_and_item_allowance() {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let (mut account, rule) = create_account(&mut conn, "alice", None, None);
+    let account_id = account.id.clone().unwrap();
+    let feed = create_feed(&mut conn, &rule);
+    for i in 0..3 {
+        FeedItemOps::create(&mut conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Item {}", i),
+            None,
+            None,
+            None,
+            Utc::now(),
+            None,
+            None,
+            None,
+            None,
+        )).unwrap();
+    }
+
+    // A finished 20 minute run at the start of the UTC day
+    let midnight = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let mut run = NewProcessingRun::new(account_id.clone());
+    run.status = ProcessingRunStatus::Completed.as_str().to_string();
+    run.started_at = midnight.to_rfc3339();
+    run.finished_at = Some((midnight + Duration::minutes(20)).to_rfc3339());
+    ProcessingRunOps::create(&mut conn, &run).unwrap();
+    // Runs from yesterday do not count
+    let mut old_run = NewProcessingRun::new(account_id.clone());
+    old_run.started_at = (midnight - Duration::hours(2)).to_rfc3339();
+    old_run.finished_at = Some((midnight - Duration::hours(1)).to_rfc3339());
+    ProcessingRunOps::create(&mut conn, &old_run).unwrap();
+    drop(conn);
+
+    let pool = DatabasePool::SQLite(pool);
+    let usage = quota::usage(&pool, std::slice::from_ref(&account_id)).unwrap();
+    assert_eq!((usage.feeds, usage.items, usage.processing_minutes_today), (1, 3, 20));
+
+    assert!(quota::check(&pool, &account, QuotaResource::ProcessingMinutes).is_ok());
+    assert!(quota::item_allowance(&pool, &account).unwrap().is_none());
+
+    account.max_processing_minutes_per_day = Some(20);
+    account.max_items = Some(5);
+    let error = quota::check(&pool, &account, QuotaResource::ProcessingMinutes).unwrap_err();
+    let exceeded = error.downcast_ref::<QuotaExceeded>().unwrap();
+    assert_eq!((exceeded.limit, exceeded.used), (20, 20));
+    assert_eq!(quota::item_allowance(&pool, &account).unwrap().unwrap().remaining, 2);
+}
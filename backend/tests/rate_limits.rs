@@ -0,0 +1,117 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::imap::ImapClient;
+use mail2feed_backend::testing::{TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn create_account(app: &axum::Router, account: Value) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(Request::builder()
+        .method(Method::POST)
+        .uri("/api/imap-accounts")
+        .header("Content-Type", "application/json")
+        .body(Body::from(account.to_string()))
+        .unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn account(username: &str) -> Value {
+    json!({
+        "name": username,
+        "host": "imap.gmail.com",
+        "port": 993,
+        "username": username,
+        "password": "secret",
+        "use_tls": true,
+    })
+}
+
+#[tokio::test]
+async fn test_limits_are_configured_through_the_api() {
+    let app = app(DatabasePool::SQLite(setup_test_db()));
+
+    let mut limited = account("limited@gmail.com");
+    limited["max_connections_per_hour"] = json!(15);
+    limited["min_fetch_delay_seconds"] = json!(300);
+    limited["max_messages_per_fetch"] = json!(50);
+    let (status, created) = create_account(&app, limited).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["max_connections_per_hour"], 15);
+    assert_eq!(created["min_fetch_delay_seconds"], 300);
+    assert_eq!(created["max_messages_per_fetch"], 50);
+
+    let (_, unlimited) = create_account(&app, account("unlimited@gmail.com")).await;
+    assert!(unlimited["max_connections_per_hour"].is_null());
+
+    let mut invalid = account("invalid@gmail.com");
+    invalid["min_fetch_delay_seconds"] = json!(0);
+    let (status, body) = create_account(&app, invalid).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("min_fetch_delay_seconds"));
+}
+
+#[tokio::test]
+async fn test_connections_are_refused_over_the_hourly_cap() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    let account = server.test_account("Gmail")
+        .configure(|account| account.max_connections_per_hour = Some(1))
+        .insert(&pool)
+        .unwrap()
+        .account;
+    let client = ImapClient::new(&account).unwrap();
+
+    client.list_folders().await.unwrap();
+    let error = client.list_folders().await.unwrap_err();
+    assert!(format!("{:#}", error).contains("rate limited (1 connections in the last hour)"));
+}
+
+#[tokio::test]
+async fn test_fetches_are_batched_and_spaced() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    for subject in ["Issue 1", "Issue 2", "Issue 3"] {
+        server.add_message("INBOX", MockMessage::new("news@example.com", subject));
+    }
+    let fixture = server.test_account("Gmail")
+        .configure(|account| {
+            account.max_messages_per_fetch = Some(2);
+            account.min_fetch_delay_seconds = Some(600);
+        })
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert_eq!(result.total_emails_processed, 2);
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    assert_eq!(FeedItemOpsGeneric::get_by_feed_id(&pool, &feed_id, None).unwrap().len(), 2);
+
+    // The next fetch has to wait out the delay
+    let error = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap_err();
+    assert!(error.to_string().contains("600s between fetches"));
+}
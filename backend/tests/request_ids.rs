@@ -0,0 +1,89 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+/// The `X-Request-Id` a GET of `uri` is answered with, sending `request_id`
+async fn request_id(app: &axum::Router, uri: &str, request_id: Option<&str>) -> String {
+    let mut request = Request::builder().method(Method::GET).uri(uri);
+    if let Some(request_id) = request_id {
+        request = request.header("X-Request-Id", request_id);
+    }
+    let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    response.headers().get("x-request-id").expect("response has a request ID").to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_every_response_carries_a_request_id() {
+    let app = app(DatabasePool::SQLite(setup_test_db()));
+
+    let generated = request_id(&app, "/api/feeds", None).await;
+    assert!(uuid::Uuid::parse_str(&generated).is_ok(), "{}", generated);
+    assert_ne!(request_id(&app, "/api/feeds", None).await, generated);
+
+    // Kept from a proxy in front, on errors and outside /api too
+    assert_eq!(request_id(&app, "/api/feeds/missing", Some("edge-4f2a")).await, "edge-4f2a");
+    assert_eq!(request_id(&app, "/feeds/missing/rss", Some("edge-4f2b")).await, "edge-4f2b");
+
+    let replaced = request_id(&app, "/api/feeds", Some(&"x".repeat(200))).await;
+    assert!(uuid::Uuid::parse_str(&replaced).is_ok(), "{}", replaced);
+}
+
+/// Log output captured for a test
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_json_log_lines_carry_the_request_id() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_span_list(true)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let app = app(DatabasePool::SQLite(setup_test_db()));
+    request_id(&app, "/api/feeds?token=secret", Some("trace-me")).await;
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let handled = output.lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|line| line["fields"]["message"] == "Handled request")
+        .unwrap_or_else(|| panic!("no request logged in {}", output));
+    assert_eq!(handled["fields"]["status"], 200);
+    let span = &handled["spans"][0];
+    assert_eq!((span["name"].as_str(), span["request_id"].as_str()), (Some("request"), Some("trace-me")));
+    assert_eq!((span["method"].as_str(), span["path"].as_str()), (Some("GET"), Some("/api/feeds")));
+    assert!(!output.contains("secret"), "{}", output);
+}
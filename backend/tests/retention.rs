@@ -0,0 +1,206 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{retention, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+async fn settings(app: &axum::Router, method: Method, body: Option<Value>) -> (StatusCode, Value) {
+    let (status, body) = send(app, method, "/api/settings", body).await;
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+fn retention_of<'a>(settings: &'a Value, table: &str) -> &'a Value {
+    settings["retention"].as_array().unwrap().iter().find(|setting| setting["table"] == table).unwrap()
+}
+
+#[tokio::test]
+async fn test_settings_validate_and_store_retention() {
+    let app = app(setup_test_db());
+
+    let (status, body) = settings(&app, Method::GET, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let tables: Vec<_> = body["retention"].as_array().unwrap().iter().map(|setting| setting["table"].as_str().unwrap()).collect();
+    assert_eq!(tables, ["processing_runs", "rule_matches", "deliveries", "rule_costs"]);
+    assert_eq!(retention_of(&body, "deliveries")["max_rows"], Value::Null);
+
+    let (status, body) = settings(&app, Method::PUT, Some(json!({"retention": [{"table": "access_log", "max_rows": 10}]}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().starts_with("Unknown table 'access_log'"), "{}", body);
+
+    // Nothing is stored when any entry is invalid
+    let (status, _) = settings(&app, Method::PUT, Some(json!({"retention": [
+        {"table": "deliveries", "max_rows": 10},
+        {"table": "rule_costs", "max_age_days": 0},
+    ]}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, body) = settings(&app, Method::GET, None).await;
+    assert_eq!(retention_of(&body, "deliveries")["max_rows"], Value::Null);
+
+    let (status, body) = settings(&app, Method::PUT, Some(json!({"retention": [
+        {"table": "deliveries", "max_rows": 10, "max_age_days": 30},
+    ]}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(retention_of(&body, "deliveries")["max_rows"], 10);
+    assert_eq!(retention_of(&body, "deliveries")["max_age_days"], 30);
+
+    // Updating keeps one policy per table, and null lifts a limit
+    let (_, body) = settings(&app, Method::PUT, Some(json!({"retention": [
+        {"table": "deliveries", "max_rows": null, "max_age_days": 7},
+    ]}))).await;
+    assert_eq!(retention_of(&body, "deliveries")["max_rows"], Value::Null);
+    assert_eq!(retention_of(&body, "deliveries")["max_age_days"], 7);
+}
+
+#[tokio::test]
+async fn test_purge_trims_history_and_counts_rows() {
+    let pool = setup_test_db();
+    let fixture = TestAccount::new("Test Account")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("Feed")))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let (account_id, rule, feed) = (fixture.account_id(), fixture.rule("Newsletters").clone(), fixture.feed("Feed").clone());
+    let runs = {
+        let mut conn = pool.get().unwrap();
+        // Oldest first: two finished runs, one still running
+        let runs: Vec<ProcessingRun> = (0..3).map(|_| ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap()).collect();
+        for run in &runs[..2] {
+            ProcessingRunOps::finish(&mut conn, run.id.as_ref().unwrap(), &ProcessingRunStatus::Completed, 1, 1, None).unwrap();
+        }
+
+        for age_in_days in [1, 45] {
+            let mut new_match = NewRuleMatch::new(
+                rule.id.clone().unwrap(),
+                format!("<match-{}@example.com>", age_in_days),
+                "Subject".to_string(),
+                "news@example.com".to_string(),
+                Utc::now(),
+            );
+            new_match.matched_at = (Utc::now() - Duration::days(age_in_days)).to_rfc3339();
+            RuleMatchOps::create_if_new(&mut conn, &new_match).unwrap();
+        }
+        runs
+    };
+    let run_ids: Vec<String> = runs.iter().map(|run| run.id.clone().unwrap()).collect();
+
+    // An item of the oldest run
+    let item = {
+        let mut conn = pool.get().unwrap();
+        let mut new_item = NewFeedItem::new(feed.id.clone().unwrap(), "Item".to_string(), None, None, None, Utc::now(), None, None, None, None);
+        new_item.processing_run_id = Some(run_ids[0].clone());
+        FeedItemOps::create(&mut conn, &new_item).unwrap()
+    };
+
+    let app = app(pool.clone());
+    let (status, _) = settings(&app, Method::PUT, Some(json!({"retention": [
+        {"table": "processing_runs", "max_rows": 1},
+        {"table": "rule_matches", "max_age_days": 30},
+    ]}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let database = DatabasePool::SQLite(pool.clone());
+    let purged = retention::purge(&database, Utc::now()).unwrap();
+    assert_eq!(purged, [(RetentionTarget::ProcessingRuns, 1), (RetentionTarget::RuleMatches, 1)]);
+
+    {
+        let mut conn = pool.get().unwrap();
+        // The running run is not a candidate, so the newest finished one is kept
+        assert!(ProcessingRunOps::get_by_id(&mut conn, &run_ids[0]).is_err());
+        assert!(ProcessingRunOps::get_by_id(&mut conn, &run_ids[1]).is_ok());
+        assert!(ProcessingRunOps::get_by_id(&mut conn, &run_ids[2]).is_ok());
+        let item = FeedItemOps::get_by_id(&mut conn, item.id.as_ref().unwrap()).unwrap();
+        assert_eq!(item.processing_run_id, None);
+        let matches = RuleMatchOps::get_by_rule_id(&mut conn, rule.id.as_ref().unwrap(), None).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].email_message_id, "<match-1@example.com>");
+    }
+
+    // Nothing more to purge
+    assert!(retention::purge(&database, Utc::now()).unwrap().is_empty());
+
+    let (_, body) = settings(&app, Method::GET, None).await;
+    assert_eq!(retention_of(&body, "processing_runs")["rows_purged"], 1);
+    assert!(retention_of(&body, "processing_runs")["last_purged_at"].is_string());
+
+    let (status, metrics) = send(&app, Method::GET, "/metrics", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(metrics.contains("mail2feed_retention_purged_rows_total{table=\"rule_matches\"} 1"), "{}", metrics);
+    assert!(metrics.contains("mail2feed_retention_purged_rows_total{table=\"deliveries\"} 0"), "{}", metrics);
+}
+
+#[tokio::test]
+async fn test_cleanup_endpoints_apply_default_feed_retention() {
+    let pool = setup_test_db();
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters")
+            .with_feed(["a", "b", "c", "d"].iter().fold(
+                TestFeed::new("Defaults").configure(|feed| (feed.max_items, feed.max_age_days, feed.min_items) = (None, None, None)),
+                |feed, title| feed.with_item(title)))
+            .with_feed(["a", "b", "c", "d"].iter().fold(
+                TestFeed::new("Own limit").configure(|feed| (feed.max_items, feed.min_items) = (Some(3), Some(0))),
+                |feed, title| feed.with_item(title))))
+        .insert(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let defaults_id = fixture.feed("Defaults").id.clone().unwrap();
+    let app = app(pool);
+
+    let (_, body) = settings(&app, Method::GET, None).await;
+    assert_eq!(body["feed_retention"], json!({"max_items": 100, "max_age_days": 30, "min_items": 10}));
+    let (status, _) = settings(&app, Method::PUT, Some(json!({"feed_retention": {"max_items": 0, "max_age_days": null, "min_items": null}}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = settings(&app, Method::PUT, Some(json!({"feed_retention": {"max_items": 2, "max_age_days": null, "min_items": 0}}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["feed_retention"]["max_items"], 2);
+
+    let (status, body) = send(&app, Method::POST, &format!("/api/feeds/{}/cleanup", defaults_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["items_removed"], 2);
+    assert_eq!(body["feeds"][0]["feed_id"], defaults_id.as_str());
+
+    // The feed's own limit wins over the default
+    let (status, body) = send(&app, Method::POST, "/api/cleanup", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["feeds"]["items_removed"], 1);
+    let removed: Vec<(&str, u64)> = body["feeds"]["feeds"].as_array().unwrap().iter()
+        .map(|feed| (feed["title"].as_str().unwrap(), feed["items_removed"].as_u64().unwrap()))
+        .collect();
+    assert!(removed.contains(&("Defaults", 0)) && removed.contains(&("Own limit", 1)), "{:?}", removed);
+    assert!(body["rows_purged"].as_object().unwrap().is_empty());
+
+    let (status, _) = send(&app, Method::POST, "/api/feeds/missing/cleanup", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,152 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::api::types::{JobResponse, JobStartedResponse, JobStatus};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::{EmailRuleOpsGeneric, FeedItemOpsGeneric};
+use mail2feed_backend::testing::{Fixture, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Vec<u8>) {
+    let request = Request::builder().method(method).uri(uri).header("Content-Type", "application/json");
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    (status, hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec())
+}
+
+/// Start a backfill of the fixture's rule and wait for it to finish
+async fn backfill(app: &axum::Router, fixture: &Fixture, request: Value) -> JobResponse {
+    let rule_id = fixture.rule("News").id.clone().unwrap();
+    let (status, body) = send(app, Method::POST, &format!("/api/email-rules/{}/backfill", rule_id), Some(request)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let started: JobStartedResponse = serde_json::from_slice(&body).unwrap();
+
+    for _ in 0..100 {
+        let (_, body) = send(app, Method::GET, &format!("/api/jobs/{}", started.job_id), None).await;
+        let job: JobResponse = serde_json::from_slice(&body).unwrap();
+        if job.finished_at.is_some() {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("Backfill {} did not finish", started.job_id);
+}
+
+fn news_server() -> MockImap {
+    let server = MockImap::start("");
+    for day in 1..=5 {
+        server.add_message("INBOX", MockMessage::new("news@example.com", &format!("Issue {}", day))
+            .date(&format!("Mon, {} Sep 2025 09:00:00 +0000", day)));
+    }
+    server.add_message("INBOX", MockMessage::new("friend@example.com", "Lunch?"));
+    server
+}
+
+fn item_titles(pool: &DatabasePool, fixture: &Fixture) -> Vec<String> {
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let mut titles: Vec<String> = FeedItemOpsGeneric::get_by_feed_id(pool, &feed_id, None).unwrap()
+        .into_iter()
+        .map(|item| item.title)
+        .collect();
+    titles.sort();
+    titles
+}
+
+#[tokio::test]
+async fn test_backfill_imports_every_matching_email() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = news_server();
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News")
+            .from_address("news@example.com")
+            .configure(|rule| rule.post_process_action = "mark_read".to_string())
+            .with_feed(TestFeed::new("News")))
+        .insert(&pool)
+        .unwrap();
+    let app = app(pool.clone());
+
+    let job = backfill(&app, &fixture, json!({ "batch_size": 2 })).await;
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.error);
+    assert_eq!((job.total, job.processed, job.progress), (Some(6), 6, Some(100)));
+    assert_eq!(job.target_id, fixture.rule("News").id);
+    assert_eq!(job.result.unwrap()["items_created"], 5);
+    assert_eq!(item_titles(&pool, &fixture), ["Issue 1", "Issue 2", "Issue 3", "Issue 4", "Issue 5"]);
+
+    // Emails already in the feed are skipped when the backfill is repeated
+    let job = backfill(&app, &fixture, json!({})).await;
+    assert_eq!(job.status, JobStatus::Completed);
+    assert_eq!(job.result.unwrap()["items_created"], 0);
+    assert_eq!(item_titles(&pool, &fixture).len(), 5);
+
+    // The mailbox and the rule's high-water mark are left alone
+    let mailboxes = server.mailboxes();
+    assert!(!mailboxes.sent("STORE"));
+    assert!(mailboxes.sent("UID SEARCH UID 3:*"));
+    drop(mailboxes);
+    let rule = EmailRuleOpsGeneric::get_by_id(&pool, fixture.rule("News").id.as_deref().unwrap()).unwrap();
+    assert_eq!(rule.last_seen_uid, None);
+}
+
+#[tokio::test]
+async fn test_backfill_respects_retention() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = news_server();
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News")
+            .from_address("news@example.com")
+            .with_feed(TestFeed::new("News").configure(|feed| {
+                feed.max_items = Some(2);
+                feed.min_items = Some(0);
+            })))
+        .insert(&pool)
+        .unwrap();
+
+    let job = backfill(&app(pool.clone()), &fixture, json!({})).await;
+    assert_eq!(job.status, JobStatus::Completed, "{:?}", job.error);
+    assert_eq!(job.result.unwrap()["items_created"], 5);
+    assert_eq!(item_titles(&pool, &fixture), ["Issue 4", "Issue 5"]);
+}
+
+#[tokio::test]
+async fn test_invalid_backfills() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .with_rule(TestRule::new("Feedless"))
+        .insert(&pool)
+        .unwrap();
+    let app = app(pool);
+
+    let rule_id = fixture.rule("News").id.clone().unwrap();
+    let (status, _) = send(&app, Method::POST, &format!("/api/email-rules/{}/backfill", rule_id), Some(json!({ "batch_size": 0 }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let feedless = fixture.rule("Feedless").id.clone().unwrap();
+    let (status, _) = send(&app, Method::POST, &format!("/api/email-rules/{}/backfill", feedless), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(&app, Method::POST, "/api/email-rules/missing/backfill", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
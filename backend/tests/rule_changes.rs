@@ -0,0 +1,124 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::changes::RuleChange;
+use mail2feed_backend::background::control::ControlMessage;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+/// App whose control channel is kept so the test can observe what routes send
+fn app() -> (axum::Router, mpsc::UnboundedReceiver<ControlMessage>) {
+    let (control_tx, control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    (api::create_routes(DatabasePool::SQLite(setup_test_db()), background_handle), control_rx)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Value) -> Value {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert!(response.status() == StatusCode::OK || response.status() == StatusCode::CREATED);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn rule_changes(control_rx: &mut mpsc::UnboundedReceiver<ControlMessage>) -> Vec<RuleChange> {
+    let mut changes = Vec::new();
+    while let Ok(message) = control_rx.try_recv() {
+        if let ControlMessage::RuleChanged(change) = message {
+            changes.push(change);
+        }
+    }
+    changes
+}
+
+fn rule_body(account_id: &str, folder: &str, is_active: bool) -> Value {
+    json!({
+        "name": "Newsletters",
+        "imap_account_id": account_id,
+        "folder": folder,
+        "is_active": is_active
+    })
+}
+
+#[tokio::test]
+async fn test_rule_and_feed_edits_notify_background_service() {
+    let (app, mut control_rx) = app();
+
+    let account = send(&app, Method::POST, "/api/imap-accounts", json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    let account_id = account["id"].as_str().unwrap();
+
+    let rule = send(&app, Method::POST, "/api/email-rules", rule_body(account_id, "INBOX", true)).await;
+    let rule_id = rule["id"].as_str().unwrap();
+    assert_eq!(rule_changes(&mut control_rx), vec![RuleChange {
+        account_id: account_id.to_string(),
+        folder: "INBOX".to_string(),
+    }]);
+
+    // Moving the rule re-processes the folder it now reads from
+    send(&app, Method::PUT, &format!("/api/email-rules/{}", rule_id), rule_body(account_id, "Lists", true)).await;
+    let changes = rule_changes(&mut control_rx);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].folder, "Lists");
+
+    send(&app, Method::POST, "/api/feeds", json!({
+        "title": "Newsletters",
+        "email_rule_id": rule_id,
+        "feed_type": "rss",
+        "is_active": true
+    })).await;
+    let changes = rule_changes(&mut control_rx);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].folder, "Lists");
+}
+
+#[tokio::test]
+async fn test_inactive_rules_do_not_trigger_processing() {
+    let (app, mut control_rx) = app();
+
+    let account = send(&app, Method::POST, "/api/imap-accounts", json!({
+        "name": "Test IMAP",
+        "host": "imap.test.com",
+        "port": 993,
+        "username": "test@test.com",
+        "password": "testpass",
+        "use_tls": true
+    })).await;
+    let account_id = account["id"].as_str().unwrap();
+
+    let rule = send(&app, Method::POST, "/api/email-rules", rule_body(account_id, "INBOX", false)).await;
+    send(&app, Method::POST, "/api/feeds", json!({
+        "title": "Paused",
+        "email_rule_id": rule["id"],
+        "feed_type": "rss",
+        "is_active": true
+    })).await;
+
+    assert!(rule_changes(&mut control_rx).is_empty());
+}
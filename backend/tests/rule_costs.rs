@@ -0,0 +1,137 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, String) {
+    let response = app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn create_rule(conn: &mut diesel::SqliteConnection, account_id: &str, name: &str, folder: &str) -> String {
+    EmailRuleOps::create(conn, &NewEmailRule::new(
+        name.to_string(),
+        account_id.to_string(),
+        folder.to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap().id.unwrap()
+}
+
+fn record(conn: &mut diesel::SqliteConnection, run_id: &str, rule_id: &str, folder: &str, fetched: i32, fetch_ms: i64, match_us: i64) {
+    let mut cost = NewRuleCost::new(run_id.to_string(), rule_id.to_string(), folder.to_string());
+    cost.emails_fetched = fetched;
+    cost.evaluations = fetched;
+    cost.matches = fetched / 2;
+    cost.fetch_ms = fetch_ms;
+    cost.match_us = match_us;
+    RuleCostOps::create(conn, &cost).unwrap();
+}
+
+/// Two runs of an account whose rules `Receipts` and `Alerts` share INBOX
+/// and `Lists` reads its own folder; returns the first run's ID
+fn seed(pool: &DbPool) -> String {
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Work".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let account_id = account.id.unwrap();
+    let receipts = create_rule(&mut conn, &account_id, "Receipts", "INBOX");
+    let alerts = create_rule(&mut conn, &account_id, "Alerts", "INBOX");
+    let lists = create_rule(&mut conn, &account_id, "Lists", "Lists");
+
+    let mut run_ids = Vec::new();
+    for _ in 0..2 {
+        let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap();
+        let run_id = run.id.unwrap();
+        record(&mut conn, &run_id, &receipts, "INBOX", 100, 400, 2_000);
+        record(&mut conn, &run_id, &alerts, "INBOX", 100, 350, 1_000);
+        record(&mut conn, &run_id, &lists, "Lists", 10, 50, 100);
+        run_ids.push(run_id);
+    }
+    run_ids.remove(0)
+}
+
+#[tokio::test]
+async fn test_report_ranks_expensive_rules_and_folders() {
+    let pool = setup_test_db();
+    seed(&pool);
+    let app = app(pool);
+
+    let (status, body) = get(&app, "/api/analysis/rule-costs").await;
+    assert_eq!(status, StatusCode::OK);
+    let report: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(report["window_days"], 7);
+
+    let rules: Vec<_> = report["rules"].as_array().unwrap().iter()
+        .map(|rule| (rule["rule_name"].as_str().unwrap(), rule["runs"].as_i64().unwrap(), rule["fetch_ms"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(rules, [("Receipts", 2, 800), ("Alerts", 2, 700), ("Lists", 2, 100)]);
+    assert_eq!(report["rules"][0]["avg_fetch_ms"], 400.0);
+    assert_eq!(report["rules"][0]["avg_match_us"], 20.0);
+
+    // INBOX is fetched once per rule reading it
+    let inbox = &report["folders"][0];
+    assert_eq!(inbox["folder"], "INBOX");
+    assert_eq!(inbox["account_name"], "Work");
+    assert_eq!(inbox["rules"], 2);
+    assert_eq!(inbox["fetches"], 4);
+    assert_eq!(inbox["fetch_ms"], 1500);
+    assert_eq!(report["folders"][1]["folder"], "Lists");
+
+    let (status, body) = get(&app, "/api/analysis/rule-costs?window_days=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("window_days must be a positive number of days"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_run_costs_and_metrics() {
+    let pool = setup_test_db();
+    let run_id = seed(&pool);
+    let app = app(pool);
+
+    let (status, body) = get(&app, &format!("/api/background/runs/{}/rule-costs", run_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let costs: Vec<Value> = serde_json::from_str(&body).unwrap();
+    assert_eq!(costs.len(), 3);
+    assert!(costs.iter().all(|cost| cost["processing_run_id"] == run_id.as_str()));
+
+    assert_eq!(get(&app, "/api/background/runs/missing/rule-costs").await.0, StatusCode::NOT_FOUND);
+
+    let (_, metrics) = get(&app, "/metrics").await;
+    assert!(metrics.contains("# TYPE mail2feed_rule_fetch_milliseconds_total counter"), "{}", metrics);
+    let line = metrics.lines()
+        .find(|line| line.starts_with("mail2feed_rule_evaluations_total") && line.contains("rule=\"Receipts\""))
+        .unwrap();
+    assert!(line.contains("folder=\"INBOX\""), "{}", line);
+    assert!(line.ends_with(" 200"), "{}", line);
+}
@@ -0,0 +1,146 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::NewRuleFeed;
+use mail2feed_backend::db::operations_generic::{FeedItemOpsGeneric, ProcessingIntentOpsGeneric, RuleFeedOpsGeneric};
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: &DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    api::create_routes(pool.clone(), BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    })
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn item_titles(pool: &DatabasePool, feed_id: &Option<String>) -> Vec<String> {
+    let mut titles: Vec<String> = FeedItemOpsGeneric::get_by_feed_id(pool, feed_id.as_deref().unwrap(), None).unwrap()
+        .into_iter()
+        .map(|item| item.title)
+        .collect();
+    titles.sort();
+    titles
+}
+
+#[tokio::test]
+async fn test_rule_delivers_to_every_active_feed_and_post_processes_once() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("UIDPLUS MOVE");
+    let uid = server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly issue"));
+    server.add_message("INBOX", MockMessage::new("deals@example.com", "Big sale"));
+    // The aggregate feeds belong to a rule that matches nothing itself
+    let fixture = server.test_account("Work")
+        .with_rule(TestRule::new("Newsletters")
+            .from_address("news@example.com")
+            .configure(|rule| rule.post_process_actions = Some(json!([{"action": "mark_read"}]).to_string()))
+            .with_feed(TestFeed::new("Newsletters")))
+        .with_rule(TestRule::new("Aggregate").inactive()
+            .with_feed(TestFeed::new("Everything"))
+            .with_feed(TestFeed::new("Paused").configure(|feed| feed.is_active = false)))
+        .insert(&pool)
+        .unwrap();
+    let rule_id = fixture.rule("Newsletters").id.clone().unwrap();
+    for (title, priority) in [("Everything", 5), ("Paused", 10)] {
+        let feed_id = fixture.feed(title).id.clone().unwrap();
+        RuleFeedOpsGeneric::attach(&pool, &NewRuleFeed::new(rule_id.clone(), feed_id, priority)).unwrap();
+    }
+
+    let processor = EmailProcessor::new(fixture.account.clone(), pool.clone());
+    let result = processor.process_account().await.unwrap();
+    assert!(result.errors.is_empty() && result.post_process_failures.is_empty(), "{:?}", result);
+    assert_eq!(result.total_emails_processed, 1);
+    assert_eq!(result.new_feed_items_created, 2);
+    assert_eq!(item_titles(&pool, &fixture.feed("Newsletters").id), ["Weekly issue"]);
+    assert_eq!(item_titles(&pool, &fixture.feed("Everything").id), ["Weekly issue"]);
+    assert!(item_titles(&pool, &fixture.feed("Paused").id).is_empty());
+    assert!(server.mailboxes().message("INBOX", uid).is_seen());
+
+    // One intent per item, none left pending
+    let intents = ProcessingIntentOpsGeneric::get_by_run_id(&pool, result.run_id.as_deref().unwrap()).unwrap();
+    assert_eq!(intents.len(), 2);
+    assert!(intents.iter().all(|intent| intent.status == "applied"), "{:?}", intents);
+
+    // Mail arriving later reaches every feed as well
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Next issue"));
+    let result = processor.process_account().await.unwrap();
+    assert_eq!(result.new_feed_items_created, 2, "{:?}", result);
+    assert_eq!(item_titles(&pool, &fixture.feed("Everything").id), ["Next issue", "Weekly issue"]);
+}
+
+#[tokio::test]
+async fn test_feeds_are_attached_and_detached_through_the_api() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("Newsletters")))
+        .with_rule(TestRule::new("Digests").with_feed(TestFeed::new("Everything")).with_feed(TestFeed::new("Archive")))
+        .insert(&pool)
+        .unwrap();
+    let app = app(&pool);
+    let rule_id = fixture.rule("Newsletters").id.clone().unwrap();
+    let feeds_uri = format!("/api/email-rules/{}/feeds", rule_id);
+    let everything = fixture.feed("Everything").id.clone().unwrap();
+    let archive = fixture.feed("Archive").id.clone().unwrap();
+
+    let (status, body) = send(&app, Method::POST, &feeds_uri, Some(json!({ "feed_id": everything, "priority": 5 }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body, json!({ "feed_id": everything, "feed_title": "Everything", "priority": 5, "attached": true, "is_active": true }));
+    let (status, _) = send(&app, Method::POST, &feeds_uri, Some(json!({ "feed_id": archive, "priority": -1 }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Own feeds have priority 0; higher priorities come first
+    let (status, body) = send(&app, Method::GET, &feeds_uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Vec<(&str, i64)> = body.as_array().unwrap().iter()
+        .map(|feed| (feed["feed_title"].as_str().unwrap(), feed["priority"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(order, [("Everything", 5), ("Newsletters", 0), ("Archive", -1)]);
+
+    // Attaching again changes the priority
+    let (_, body) = send(&app, Method::POST, &feeds_uri, Some(json!({ "feed_id": archive, "priority": 9 }))).await;
+    assert_eq!(body["priority"], 9);
+    let (_, body) = send(&app, Method::GET, &feeds_uri, None).await;
+    assert_eq!(body.as_array().unwrap().len(), 3);
+    assert_eq!(body[0]["feed_title"], "Archive");
+
+    let own = fixture.feed("Newsletters").id.clone().unwrap();
+    let (status, body) = send(&app, Method::POST, &feeds_uri, Some(json!({ "feed_id": own }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, _) = send(&app, Method::POST, &feeds_uri, Some(json!({ "feed_id": "missing" }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, Method::GET, "/api/email-rules/missing/feeds", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&app, Method::DELETE, &format!("{}/{}", feeds_uri, everything), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, Method::DELETE, &format!("{}/{}", feeds_uri, everything), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = send(&app, Method::GET, &feeds_uri, None).await;
+    let titles: Vec<&str> = body.as_array().unwrap().iter().map(|feed| feed["feed_title"].as_str().unwrap()).collect();
+    assert_eq!(titles, ["Archive", "Newsletters"]);
+}
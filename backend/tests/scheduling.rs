@@ -0,0 +1,170 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::background::cleanup::FeedCleanupService;
+use mail2feed_backend::background::clock::{Clock, ManualClock};
+use mail2feed_backend::background::scheduler::EmailScheduler;
+use mail2feed_backend::background::BackgroundConfig;
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::setup_test_db;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+fn create_account(conn: &mut SqliteConnection, max_processing_minutes_per_day: Option<i32>) -> ImapAccount {
+    let mut new_account = NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    );
+    new_account.max_processing_minutes_per_day = max_processing_minutes_per_day;
+    ImapAccountOps::create(conn, &new_account).unwrap()
+}
+
+fn at(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc)
+}
+
+#[tokio::test]
+async fn test_accounts_are_processed_once_per_interval() {
+    let pool = setup_test_db();
+    // Without rules a run finishes without connecting to the server
+    let account = create_account(&mut pool.get().unwrap(), None);
+    let account_id = account.id.unwrap();
+
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let config = BackgroundConfig { per_account_interval_minutes: 30, ..Default::default() };
+    let scheduler = EmailScheduler::with_clock(DatabasePool::SQLite(pool), config, clock.clone()).unwrap();
+
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 1);
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 0);
+    let state = scheduler.get_account_state(&account_id).await.unwrap();
+    assert_eq!(state.stats.last_success, Some(clock.now()));
+    assert_eq!(state.next_allowed_run, clock.now() + 30 * MINUTE);
+
+    clock.advance(29 * MINUTE);
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 0);
+    clock.advance(MINUTE);
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 1);
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_paused_scheduler_starts_no_runs() {
+    let pool = setup_test_db();
+    let account = create_account(&mut pool.get().unwrap(), None);
+    let account_id = account.id.unwrap();
+
+    let scheduler = EmailScheduler::new(DatabasePool::SQLite(pool), BackgroundConfig::default()).unwrap();
+    scheduler.pause();
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 0);
+    assert!(scheduler.process_account_now(&account_id).await.is_err());
+    assert!(scheduler.process_folders_now(&account_id, &["INBOX".to_string()]).await.unwrap().is_none());
+    assert_eq!(scheduler.wait_until_idle(Duration::ZERO).await, 0);
+
+    scheduler.resume();
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 1);
+    assert_eq!(scheduler.in_flight_runs().await, 0);
+}
+
+#[tokio::test]
+async fn test_account_over_processing_quota_waits_for_reset() {
+    let pool = setup_test_db();
+    let account = create_account(&mut pool.get().unwrap(), Some(0));
+    let account_id = account.id.unwrap();
+
+    let clock = Arc::new(ManualClock::new(at("2025-08-23T23:15:00Z")));
+    let scheduler = EmailScheduler::with_clock(DatabasePool::SQLite(pool), BackgroundConfig::default(), clock.clone()).unwrap();
+
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 0);
+    let state = scheduler.get_account_state(&account_id).await.unwrap();
+    assert!(state.stats.last_error.as_deref().unwrap().starts_with("Quota exceeded"));
+    assert_eq!(state.next_allowed_run, clock.now() + 45 * MINUTE);
+
+    clock.advance(44 * MINUTE);
+    assert!(!state.is_due(clock.now()));
+    clock.advance(MINUTE);
+    assert!(state.is_due(clock.now()));
+}
+
+#[tokio::test]
+async fn test_cleanup_judges_item_age_by_clock() {
+    let pool = setup_test_db();
+    let feed = {
+        let mut conn = pool.get().unwrap();
+        let account = create_account(&mut conn, None);
+        let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+            "Test Rule".to_string(),
+            account.id.unwrap(),
+            "INBOX".to_string(),
+            None,
+            None,
+            None,
+            None,
+            true,
+        )).unwrap();
+        let feed = FeedOps::create(&mut conn, &NewFeed::with_retention(
+            "Week Feed".to_string(),
+            None,
+            None,
+            rule.id.unwrap(),
+            "rss".to_string(),
+            true,
+            None,
+            Some(7),
+            Some(0),
+        )).unwrap();
+        FeedItemOps::create(&mut conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            "Old news".to_string(),
+            None,
+            None,
+            None,
+            Utc::now(),
+            None,
+            None,
+            None,
+            None,
+        )).unwrap();
+        feed
+    };
+
+    let clock = Arc::new(ManualClock::new(Utc::now()));
+    let cleanup = FeedCleanupService::with_clock(DatabasePool::SQLite(pool.clone()), clock.clone());
+
+    clock.advance(6 * 24 * 60 * MINUTE);
+    assert_eq!(cleanup.cleanup_feed(&feed).await.unwrap().items_removed, 0);
+    clock.advance(2 * 24 * 60 * MINUTE);
+    assert_eq!(cleanup.cleanup_feed(&feed).await.unwrap().items_removed, 1);
+
+    let mut conn = pool.get().unwrap();
+    assert!(FeedItemOps::get_by_feed_id(&mut conn, feed.id.as_ref().unwrap(), None).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_config_changes_apply_to_running_scheduler() {
+    let pool = setup_test_db();
+    create_account(&mut pool.get().unwrap(), None);
+    let config = BackgroundConfig { max_concurrent_accounts: 3, ..Default::default() };
+    let scheduler = EmailScheduler::new(DatabasePool::SQLite(pool), config).unwrap();
+
+    let new_config = BackgroundConfig { max_concurrent_accounts: 1, global_interval_minutes: 1, ..Default::default() };
+    scheduler.update_config(new_config).unwrap();
+    tokio::task::yield_now().await;
+    assert_eq!(scheduler.config().max_concurrent_accounts, 1);
+    assert_eq!(scheduler.config().global_interval_minutes, 1);
+    assert_eq!(scheduler.in_flight_runs().await, 0);
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 1);
+
+    // An invalid configuration leaves the current one in place
+    let invalid = BackgroundConfig { max_concurrent_accounts: 0, ..Default::default() };
+    assert!(scheduler.update_config(invalid).is_err());
+    assert_eq!(scheduler.config().max_concurrent_accounts, 1);
+}
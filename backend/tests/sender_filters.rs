@@ -0,0 +1,102 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{NewAccountSenderFilter, SenderFilterKind};
+use mail2feed_backend::db::operations_generic::{AccountSenderFilterOpsGeneric, FeedItemOpsGeneric};
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+async fn request(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app.clone()
+        .oneshot(Request::builder().method(method).uri(uri).header("Content-Type", "application/json").body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_manage_account_filters() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Mail").insert(&pool).unwrap();
+    let other = TestAccount::new("Other").insert(&pool).unwrap();
+    let account_id = fixture.account.id.clone().unwrap();
+    let filters = format!("/api/imap-accounts/{}/filters", account_id);
+    let app = app(pool);
+
+    let (status, body) = request(&app, Method::POST, &filters, Some(json!({"kind": "deny", "pattern": "@Spam.Example"}))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!((body["kind"].clone(), body["pattern"].clone()), (json!("deny"), json!("spam.example")));
+    let filter_id = body["id"].as_str().unwrap().to_string();
+
+    let (status, _) = request(&app, Method::POST, &filters, Some(json!({"kind": "deny", "pattern": "spam.example"}))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    for invalid in [json!({"kind": "block", "pattern": "spam.example"}), json!({"kind": "allow", "pattern": "not a sender"})] {
+        let (status, _) = request(&app, Method::POST, &filters, Some(invalid)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, _) = request(&app, Method::POST, "/api/imap-accounts/missing/filters", Some(json!({"kind": "deny", "pattern": "spam.example"}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = request(&app, Method::PUT, &format!("{}/{}", filters, filter_id), Some(json!({"kind": "allow", "pattern": "News@Example.com"}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((body["kind"].clone(), body["pattern"].clone()), (json!("allow"), json!("news@example.com")));
+    let (_, body) = request(&app, Method::GET, &filters, None).await;
+    assert_eq!(body.as_array().unwrap().len(), 1);
+
+    // Filters are only reachable through their own account
+    let other_filter = format!("/api/imap-accounts/{}/filters/{}", other.account.id.clone().unwrap(), filter_id);
+    assert_eq!(request(&app, Method::GET, &other_filter, None).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(request(&app, Method::DELETE, &other_filter, None).await.0, StatusCode::NOT_FOUND);
+
+    assert_eq!(request(&app, Method::DELETE, &format!("{}/{}", filters, filter_id), None).await.0, StatusCode::NO_CONTENT);
+    let (_, body) = request(&app, Method::GET, &filters, None).await;
+    assert_eq!(body, json!([]));
+}
+
+#[tokio::test]
+async fn test_filtered_senders_never_reach_the_rules() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", MockMessage::new("Deals <deals@mail.spam.example>", "Huge savings"));
+    server.add_message("INBOX", MockMessage::new("news@example.com", "Weekly news"));
+    server.add_message("INBOX", MockMessage::new("someone@elsewhere.example", "Hello"));
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("Everything").with_feed(TestFeed::new("Everything")))
+        .insert(&pool)
+        .unwrap();
+    let account_id = fixture.account.id.clone().unwrap();
+    for (kind, pattern) in [(SenderFilterKind::Deny, "spam.example"), (SenderFilterKind::Allow, "example.com"), (SenderFilterKind::Allow, "spam.example")] {
+        AccountSenderFilterOpsGeneric::create(&pool, &NewAccountSenderFilter::new(account_id.clone(), kind, pattern.to_string())).unwrap();
+    }
+
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+    let feed_id = fixture.feed("Everything").id.clone().unwrap();
+    let titles: Vec<String> = FeedItemOpsGeneric::get_by_feed_id(&pool, &feed_id, None).unwrap().into_iter().map(|item| item.title).collect();
+    assert_eq!(titles, ["Weekly news"]);
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// A feed with one item per sender, each `hours_ago` old
+fn create_feed(conn: &mut SqliteConnection, senders: &[(&str, i64)]) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Rule".to_string(),
+        account.id.unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let feed = FeedOps::create(conn, &NewFeed::new(
+        "Newsletters".to_string(),
+        None,
+        None,
+        rule.id.unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+    for (from, hours_ago) in senders {
+        FeedItemOps::create(conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("From {}", from),
+            None,
+            None,
+            None,
+            Utc::now() - Duration::hours(*hours_ago),
+            None,
+            None,
+            Some(from.to_string()),
+            None,
+        )).unwrap();
+    }
+    feed
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_sender_alias_crud_and_validation() {
+    let pool = setup_test_db();
+    let app = app(pool);
+
+    let (status, created) = send(&app, Method::POST, "/api/senders/aliases", Some(json!({
+        "name": "Substack Weekly",
+        "addresses": ["@Mail1.Substack.com", "mail2.substack.com", "mail1.substack.com", " "],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["addresses"], json!(["mail1.substack.com", "mail2.substack.com"]));
+    let id = created["id"].as_str().unwrap().to_string();
+
+    for (body, error) in [
+        (json!({ "name": " ", "addresses": ["example.com"] }), "name must not be empty"),
+        (json!({ "name": "Empty", "addresses": [] }), "addresses must not be empty"),
+        (json!({ "name": "Bad", "addresses": ["not an address"] }), "'not an address' is not an email address or domain"),
+        (json!({ "name": "substack weekly", "addresses": ["example.com"] }), "A sender alias named 'Substack Weekly' already exists"),
+        (json!({ "name": "Other", "addresses": ["MAIL2.substack.com"] }), "'mail2.substack.com' already belongs to sender alias 'Substack Weekly'"),
+    ] {
+        let (status, response) = send(&app, Method::POST, "/api/senders/aliases", Some(body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["error"], error);
+    }
+
+    // A group may keep its own name and members when updated
+    let uri = format!("/api/senders/aliases/{}", id);
+    let (status, updated) = send(&app, Method::PUT, &uri, Some(json!({
+        "name": "Substack Weekly",
+        "addresses": ["mail2.substack.com", "mail3.substack.com"],
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["addresses"], json!(["mail2.substack.com", "mail3.substack.com"]));
+
+    let (status, listed) = send(&app, Method::GET, "/api/senders/aliases", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    let (status, _) = send(&app, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, Method::PUT, &uri, Some(json!({ "name": "Gone", "addresses": ["example.com"] }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_sender_stats_merge_alias_groups() {
+    let pool = setup_test_db();
+    {
+        let mut conn = pool.get().unwrap();
+        create_feed(&mut conn, &[
+            ("Substack <news@mail1.substack.com>", 5),
+            ("news@mail2.substack.com", 1),
+            ("news@mail2.substack.com", 3),
+            ("Someone <someone@example.com>", 2),
+            ("", 4),
+        ]);
+    }
+    let app = app(pool);
+
+    let (status, stats) = send(&app, Method::GET, "/api/senders/stats", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let senders: Vec<(&str, i64)> = stats.as_array().unwrap().iter()
+        .map(|entry| (entry["sender"].as_str().unwrap(), entry["item_count"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(senders, vec![
+        ("news@mail2.substack.com", 2),
+        ("news@mail1.substack.com", 1),
+        ("someone@example.com", 1),
+    ]);
+
+    let (status, alias) = send(&app, Method::POST, "/api/senders/aliases", Some(json!({
+        "name": "Substack",
+        "addresses": ["substack.com"],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, stats) = send(&app, Method::GET, "/api/senders/stats", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let stats = stats.as_array().unwrap();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0]["sender"], "Substack");
+    assert_eq!(stats[0]["alias_id"], alias["id"]);
+    assert_eq!(stats[0]["item_count"], 3);
+    assert_eq!(stats[0]["addresses"], json!(["news@mail1.substack.com", "news@mail2.substack.com"]));
+    assert_eq!(stats[1]["sender"], "someone@example.com");
+    assert!(stats[1]["alias_id"].is_null());
+
+    // The group's newest item (1h old, from mail2) is newer than the other sender's (2h old)
+    assert!(stats[0]["latest_pub_date"].as_str().unwrap() > stats[1]["latest_pub_date"].as_str().unwrap());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{self, BackgroundConfig};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::settings;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, HeaderMap, String) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, headers, String::from_utf8(body.to_vec()).unwrap())
+}
+
+async fn send_json(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let (status, _, body) = send(app, method, uri, body).await;
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+fn setting<'a>(settings: &'a Value, key: &str) -> &'a Value {
+    settings["values"].as_array().unwrap().iter().find(|setting| setting["key"] == key).unwrap()
+}
+
+// One test, since stored settings apply to the whole process
+#[tokio::test]
+async fn test_settings_override_the_environment_at_runtime() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    settings::load(&pool).unwrap();
+    let fixture = TestAccount::new("Work Mail")
+        .with_rule(TestRule::new("Newsletters").with_feed(TestFeed::new("News").with_item("One").with_item("Two")))
+        .insert(&pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let handle = background::initialize_background_service(pool.clone(), BackgroundConfig::default()).await.unwrap();
+    let app = api::create_routes(pool.clone(), handle);
+
+    let (status, body) = send_json(&app, Method::GET, "/api/settings", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let cache = setting(&body, "feed_cache_duration");
+    assert_eq!((&cache["value"], &cache["source"], &cache["env_var"]), (&json!("300"), &json!("default"), &json!("FEED_CACHE_DURATION")));
+
+    let (status, body) = send_json(&app, Method::PUT, "/api/settings", Some(json!({"values": {
+        "feed_cache_duration": "60",
+        "feed_item_limit": " 1 ",
+        "background_max_concurrent_accounts": "7",
+    }}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let limit = setting(&body, "feed_item_limit");
+    assert_eq!((&limit["value"], &limit["source"]), (&json!("1"), &json!("database")));
+
+    // Feeds and the scheduler pick the new values up without a restart
+    let (status, headers, rss) = send(&app, Method::GET, &format!("/feeds/{}/rss", feed_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["cache-control"], "public, max-age=60");
+    assert_eq!(rss.matches("<item>").count(), 1, "{}", rss);
+    let (_, status) = send_json(&app, Method::GET, "/api/background/status", None).await;
+    assert_eq!(status["status"]["config"]["max_concurrent_accounts"], 7);
+
+    // Unknown settings and values that do not fit are refused, changing nothing
+    for values in [json!({"feed_cache_duration": "10", "bogus": "1"}), json!({"feed_item_limit": "0"}), json!({"cors_allowed_origins": "example.com"})] {
+        let (status, body) = send_json(&app, Method::PUT, "/api/settings", Some(json!({"values": values}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+    assert_eq!(settings::feed_cache_duration(), 60);
+
+    // The stored values survive a reload, and null returns a setting to its default
+    settings::load(&pool).unwrap();
+    assert_eq!(settings::feed_item_limit(), 1);
+    let (_, body) = send_json(&app, Method::PUT, "/api/settings", Some(json!({"values": {
+        "feed_cache_duration": null,
+        "background_max_concurrent_accounts": null,
+    }}))).await;
+    assert_eq!(setting(&body, "feed_cache_duration")["source"], "default");
+    let (_, status) = send_json(&app, Method::GET, "/api/background/status", None).await;
+    assert_eq!(status["status"]["config"]["max_concurrent_accounts"], BackgroundConfig::default().max_concurrent_accounts);
+}
@@ -0,0 +1,236 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{operations::*, DbPool};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+use mock_imap::MockImap;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn account(max_feeds: Option<i32>) -> Value {
+    json!({
+        "name": "Newsletters",
+        "host": "imap.example.com",
+        "port": 993,
+        "username": "reader@example.com",
+        "password": "secret",
+        "use_tls": true,
+        "default_post_process_action": "move_to_folder",
+        "default_move_to_folder": "Read",
+        "max_feeds": max_feeds,
+    })
+}
+
+/// A server that is not listening: a port that was free a moment ago
+fn closed_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+#[tokio::test]
+async fn test_finalize_creates_account_rules_and_feeds() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+
+    let (status, created) = send(&app, Method::POST, "/api/setup/finalize", Some(json!({
+        "account": account(Some(2)),
+        "feeds": [
+            { "folder": "Newsletters/Substack", "from_address": "substack.com" },
+            { "folder": "INBOX", "title": "Release notes", "subject_contains": "release", "feed_type": "atom" },
+        ],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let account_id = created["account"]["id"].as_str().unwrap();
+    let rules = created["rules"].as_array().unwrap();
+    let feeds = created["feeds"].as_array().unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(feeds.len(), 2);
+    for (rule, feed) in rules.iter().zip(feeds) {
+        assert_eq!(rule["imap_account_id"], account_id);
+        assert_eq!(rule["post_process_action"], "move_to_folder");
+        assert_eq!(rule["move_to_folder"], "Read");
+        assert_eq!(feed["email_rule_id"], rule["id"]);
+    }
+    assert_eq!(rules[0]["name"], "Newsletters/Substack");
+    assert_eq!(rules[0]["from_address"], "substack.com");
+    assert_eq!(feeds[0]["feed_type"], "rss");
+    assert_eq!(feeds[1]["title"], "Release notes");
+    assert_eq!(feeds[1]["feed_type"], "atom");
+
+    let mut conn = pool.get().unwrap();
+    assert_eq!(EmailRuleOps::get_by_account_id(&mut conn, account_id).unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_finalize_creates_nothing_when_rejected() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+    let two_feeds = json!([{ "folder": "A" }, { "folder": "B" }]);
+
+    let (status, response) = send(&app, Method::POST, "/api/setup/finalize", Some(json!({
+        "account": account(Some(1)),
+        "feeds": two_feeds,
+    }))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(response["error"], "Quota exceeded: account 'Newsletters' allows 1 feeds and has used 0");
+
+    for (feeds, error) in [
+        (json!([]), "feeds must not be empty"),
+        (json!([{ "folder": " " }]), "folder must not be empty"),
+    ] {
+        let (status, response) = send(&app, Method::POST, "/api/setup/finalize", Some(json!({
+            "account": account(None),
+            "feeds": feeds,
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["error"], error);
+    }
+    {
+        let mut conn = pool.get().unwrap();
+        assert!(ImapAccountOps::get_all(&mut conn).unwrap().is_empty());
+        assert!(FeedOps::get_all(&mut conn).unwrap().is_empty());
+    }
+
+    let (status, created) = send(&app, Method::POST, "/api/setup/finalize", Some(json!({
+        "account": account(None),
+        "feeds": two_feeds,
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, duplicate) = send(&app, Method::POST, "/api/setup/finalize", Some(json!({
+        "account": account(None),
+        "feeds": two_feeds,
+    }))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(duplicate["duplicate_of"], created["account"]["id"]);
+}
+
+#[tokio::test]
+async fn test_connection_probe_reports_failed_step() {
+    let pool = setup_test_db();
+    let app = app(pool);
+    let connection = json!({
+        "host": "127.0.0.1",
+        "port": closed_port(),
+        "username": "reader",
+        "password": "secret",
+        "use_tls": false,
+    });
+
+    let (status, probe) = send(&app, Method::POST, "/api/setup/validate-connection", Some(connection.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(probe["success"], false);
+    assert_eq!(probe["failed_step"], "connect");
+    assert_eq!(probe["capabilities"], json!([]));
+
+    let (status, _) = send(&app, Method::POST, "/api/setup/suggest-folders", Some(connection.clone())).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+    let mut pinned = connection;
+    pinned["tls_pin"] = json!("cert-sha256:00");
+    let (status, response) = send(&app, Method::POST, "/api/setup/validate-connection", Some(pinned)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(response["error"], "tls_pin requires use_tls");
+}
+
+fn initial_setup(admin_password: &str, account: Value) -> Value {
+    json!({
+        "admin": { "username": "admin", "password": admin_password },
+        "account": account,
+        "feeds": [{ "folder": "INBOX", "title": "Inbox" }],
+    })
+}
+
+#[tokio::test]
+async fn test_initial_setup_runs_once() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+    let server = MockImap::start("UIDPLUS MOVE");
+    let mut mock_account = account(None);
+    mock_account["host"] = json!("127.0.0.1");
+    mock_account["port"] = json!(server.port);
+    mock_account["use_tls"] = json!(false);
+
+    let (status, state) = send(&app, Method::GET, "/api/setup", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state["initialized"], false);
+    assert_eq!(state["has_admin"], false);
+    assert_eq!(state["database"]["backend"], "sqlite");
+    assert_eq!(state["database"]["status"], "up");
+
+    let (status, response) = send(&app, Method::POST, "/api/setup", Some(initial_setup("short", mock_account.clone()))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(response["error"], "admin password must be 8 to 256 characters long");
+
+    // The account is checked against its server before anything is stored
+    server.refuse("LOGIN");
+    let (status, probe) = send(&app, Method::POST, "/api/setup", Some(initial_setup("correct horse", mock_account.clone()))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(probe["failed_step"], "login");
+    let (_, state) = send(&app, Method::GET, "/api/setup", None).await;
+    assert_eq!(state["initialized"], false);
+    assert_eq!(state["account_count"], 0);
+
+    server.accept_all();
+    let (status, created) = send(&app, Method::POST, "/api/setup", Some(initial_setup("correct horse", mock_account.clone()))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    assert_eq!(created["admin_username"], "admin");
+    assert_eq!(created["feeds"][0]["title"], "Inbox");
+    assert_eq!(created["rules"][0]["imap_account_id"], created["account"]["id"]);
+
+    let (_, state) = send(&app, Method::GET, "/api/setup", None).await;
+    assert_eq!((state["initialized"].clone(), state["has_admin"].clone(), state["account_count"].clone()), (json!(true), json!(true), json!(1)));
+
+    let (status, response) = send(&app, Method::POST, "/api/setup", Some(initial_setup("correct horse", mock_account))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(response["error"], "The instance is already initialized");
+    let mut conn = pool.get().unwrap();
+    assert_eq!(ImapAccountOps::get_all(&mut conn).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_instances_with_accounts_count_as_initialized() {
+    let pool = setup_test_db();
+    let app = app(pool.clone());
+
+    let (status, _) = send(&app, Method::POST, "/api/setup/finalize", Some(json!({
+        "account": account(None),
+        "feeds": [{ "folder": "INBOX" }],
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, state) = send(&app, Method::GET, "/api/setup", None).await;
+    assert_eq!(state["initialized"], true);
+    assert_eq!(state["has_admin"], false);
+    let (status, _) = send(&app, Method::POST, "/api/setup", Some(initial_setup("correct horse", account(None)))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::feed::permalink;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+const KEY: &str = "a signing key of more than 32 bytes";
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// An item of a feed that is not publicly accessible
+fn create_private_item(conn: &mut SqliteConnection) -> (Feed, FeedItem) {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    let mut new_feed = NewFeed::new(
+        "Private Feed".to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    );
+    new_feed.public_access = Some(false);
+    let feed = FeedOps::create(conn, &new_feed).unwrap();
+
+    let item = FeedItemOps::create(conn, &NewFeedItem::new(
+        feed.id.clone().unwrap(),
+        "Issue 42".to_string(),
+        Some("<p>Shared issue body</p>".to_string()),
+        None,
+        None,
+        Utc::now(),
+        None,
+        None,
+        None,
+        None,
+    )).unwrap();
+    (feed, item)
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, String) {
+    let response = app.clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8_lossy(&body).to_string())
+}
+
+async fn share(app: &axum::Router, item: &FeedItem, request: Value) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/feed-items/{}/share", item.id.as_ref().unwrap()))
+                .header("Content-Type", "application/json")
+                .header("Host", "feeds.example.com")
+                .body(Body::from(request.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+// A single test, as it changes the process-wide FEED_SIGNING_KEY setting
+#[tokio::test]
+async fn test_signed_item_links() {
+    let pool = setup_test_db();
+    let (feed, item) = create_private_item(&mut pool.get().unwrap());
+    let app = app(pool);
+    let item_id = item.id.as_ref().unwrap();
+
+    // Without a key links can be neither made nor followed
+    std::env::remove_var("FEED_SIGNING_KEY");
+    assert_eq!(share(&app, &item, json!({})).await.0, StatusCode::BAD_REQUEST);
+    let unsigned = format!("/feed-items/{}/html?sig=abc", item_id);
+    assert_eq!(get(&app, &unsigned).await.0, StatusCode::NOT_FOUND);
+
+    std::env::set_var("FEED_SIGNING_KEY", KEY);
+    let feed_page = format!("/feeds/{}/items/{}", feed.id.as_ref().unwrap(), item_id);
+    assert_eq!(get(&app, &feed_page).await.0, StatusCode::NOT_FOUND);
+
+    let (status, link) = share(&app, &item, json!({ "expires_in_hours": 24 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(link["expires_at"].is_string());
+    let url = link["url"].as_str().unwrap();
+    let path = url.strip_prefix("http://feeds.example.com").unwrap();
+    let (status, page) = get(&app, path).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Shared issue body"));
+
+    // Tampering with the expiry or signature invalidates the link
+    let tampered = path.replace("exp=", "exp=9");
+    assert_eq!(get(&app, &tampered).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(get(&app, &format!("/feed-items/{}/html?sig=abc", item_id)).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(get(&app, &format!("/feed-items/{}/html", item_id)).await.0, StatusCode::FORBIDDEN);
+
+    let expired = permalink::item_path(KEY.as_bytes(), item_id, Some(Utc::now().timestamp() - 60));
+    assert_eq!(get(&app, &expired).await.0, StatusCode::GONE);
+
+    let (status, link) = share(&app, &item, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(link["expires_at"].is_null());
+    assert_eq!(share(&app, &item, json!({ "expires_in_hours": 0 })).await.0, StatusCode::BAD_REQUEST);
+
+    std::env::remove_var("FEED_SIGNING_KEY");
+}
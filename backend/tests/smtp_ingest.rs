@@ -0,0 +1,153 @@
+mod common;
+
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::operations_generic::FeedItemOpsGeneric;
+use mail2feed_backend::smtp::{self, Dialect, SmtpConfig};
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+
+use common::setup_test_db;
+
+const NEWSLETTER: &str = "From: Weekly <weekly@news.example.com>\r\n\
+To: Subscribers <list@news.example.com>\r\n\
+Subject: Issue 42\r\n\
+Message-ID: <issue-42@news.example.com>\r\n\
+Date: Tue, 30 Sep 2025 09:00:00 +0000\r\n\
+\r\n\
+This week in brief.\r\n\
+..and a line that starts with a dot.\r\n";
+
+/// A client speaking to the listener line by line
+struct Client {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl Client {
+    async fn connect(addr: std::net::SocketAddr) -> (Self, String) {
+        let (read, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+        let mut client = Self { reader: BufReader::new(read), writer };
+        let greeting = client.reply().await;
+        (client, greeting)
+    }
+
+    /// The next reply, its continuation lines joined with newlines
+    async fn reply(&mut self) -> String {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).await.unwrap();
+            let line = line.trim_end().to_string();
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line);
+            if last {
+                return lines.join("\n");
+            }
+        }
+    }
+
+    async fn command(&mut self, command: &str) -> String {
+        self.writer.write_all(format!("{}\r\n", command).as_bytes()).await.unwrap();
+        self.reply().await
+    }
+
+    /// Send a message after `DATA` was accepted, dot-stuffed as given
+    async fn data(&mut self, message: &str) {
+        self.writer.write_all(message.as_bytes()).await.unwrap();
+        self.writer.write_all(b".\r\n").await.unwrap();
+    }
+}
+
+async fn spawn_listener(pool: &DatabasePool, dialect: Dialect, max_message_bytes: usize) -> std::net::SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let config = SmtpConfig { listen_addr: addr, dialect, hostname: "feeds.example.com".to_string(), max_message_bytes };
+    tokio::spawn(smtp::serve(listener, pool.clone(), config));
+    addr
+}
+
+fn smtp_account(name: &str, to_address: &str, feed: &str) -> TestAccount {
+    let to_address = to_address.to_string();
+    TestAccount::new(name)
+        .configure(|account| account.protocol = "smtp".to_string())
+        .with_rule(TestRule::new(feed).configure(|rule| rule.to_address = Some(to_address)).with_feed(TestFeed::new(feed)))
+}
+
+fn item_count(pool: &DatabasePool, feed_id: &Option<String>) -> usize {
+    FeedItemOpsGeneric::get_by_feed_id(pool, feed_id.as_deref().unwrap(), None).unwrap().len()
+}
+
+#[tokio::test]
+async fn test_smtp_delivery_creates_an_item_once() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = smtp_account("Newsletters", "news@feeds.example.com", "News").insert(&pool).unwrap();
+    // Rules of polled accounts do not receive mail over SMTP
+    TestAccount::new("Work")
+        .with_rule(TestRule::new("Work").configure(|rule| rule.to_address = Some("work@feeds.example.com".to_string())).with_feed(TestFeed::new("Work")))
+        .insert(&pool)
+        .unwrap();
+    let addr = spawn_listener(&pool, Dialect::Smtp, 4096).await;
+
+    let (mut client, greeting) = Client::connect(addr).await;
+    assert!(greeting.starts_with("220 feeds.example.com ESMTP"), "{}", greeting);
+    assert!(client.command("MAIL FROM:<weekly@news.example.com>").await.starts_with("503"));
+    let hello = client.command("EHLO relay.example.com").await;
+    assert!(hello.ends_with("250 SIZE 4096"), "{}", hello);
+    assert!(client.command("MAIL FROM:<weekly@news.example.com> SIZE=5000").await.starts_with("552"));
+    assert!(client.command("MAIL FROM:<weekly@news.example.com> SIZE=300").await.starts_with("250"));
+    assert!(client.command("RCPT TO:<work@feeds.example.com>").await.starts_with("550"));
+    assert!(client.command("RCPT TO:<stranger@feeds.example.com>").await.starts_with("550"));
+    assert!(client.command("RCPT TO:<News@Feeds.Example.com>").await.starts_with("250"));
+    assert!(client.command("DATA").await.starts_with("354"));
+    client.data(NEWSLETTER).await;
+    assert!(client.reply().await.starts_with("250"));
+
+    let items = FeedItemOpsGeneric::get_by_feed_id(&pool, fixture.feeds[0].id.as_deref().unwrap(), None).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].title, "Issue 42");
+    let body = items[0].email_body.as_deref().unwrap();
+    assert!(body.contains("\n.and a line that starts with a dot."), "{}", body);
+
+    // A redelivered message is accepted without a second item
+    assert!(client.command("MAIL FROM:<weekly@news.example.com>").await.starts_with("250"));
+    assert!(client.command("RCPT TO:<news@feeds.example.com>").await.starts_with("250"));
+    assert!(client.command("DATA").await.starts_with("354"));
+    client.data(NEWSLETTER).await;
+    assert!(client.reply().await.starts_with("250"));
+    assert_eq!(item_count(&pool, &fixture.feeds[0].id), 1);
+
+    // Messages over the limit are refused whatever SIZE promised
+    assert!(client.command("MAIL FROM:<weekly@news.example.com>").await.starts_with("250"));
+    assert!(client.command("RCPT TO:<news@feeds.example.com>").await.starts_with("250"));
+    assert!(client.command("DATA").await.starts_with("354"));
+    client.data(&format!("Subject: Huge\r\n\r\n{}\r\n", "x".repeat(5000))).await;
+    assert!(client.reply().await.starts_with("552"));
+    assert_eq!(item_count(&pool, &fixture.feeds[0].id), 1);
+    assert!(client.command("QUIT").await.starts_with("221"));
+}
+
+#[tokio::test]
+async fn test_lmtp_replies_once_per_recipient() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let news = smtp_account("Newsletters", "news@feeds.example.com", "News").insert(&pool).unwrap();
+    let deals = smtp_account("Deals", "deals@feeds.example.com", "Deals").insert(&pool).unwrap();
+    let addr = spawn_listener(&pool, Dialect::Lmtp, 1024 * 1024).await;
+
+    let (mut client, greeting) = Client::connect(addr).await;
+    assert!(greeting.starts_with("220 feeds.example.com LMTP"), "{}", greeting);
+    assert!(client.command("EHLO relay.example.com").await.starts_with("500"));
+    assert!(client.command("LHLO relay.example.com").await.starts_with("250-feeds.example.com"));
+    assert!(client.command("MAIL FROM:<>").await.starts_with("250"));
+    assert!(client.command("RCPT TO:<news@feeds.example.com>").await.starts_with("250"));
+    assert!(client.command("RCPT TO:<deals@feeds.example.com>").await.starts_with("250"));
+    assert!(client.command("DATA").await.starts_with("354"));
+    client.data(NEWSLETTER).await;
+    assert_eq!(client.reply().await, "250 2.0.0 <news@feeds.example.com> OK");
+    assert_eq!(client.reply().await, "250 2.0.0 <deals@feeds.example.com> OK");
+
+    // Each account matched the message on its own recipient, missing from the To header
+    assert_eq!(item_count(&pool, &news.feeds[0].id), 1);
+    assert_eq!(item_count(&pool, &deals.feeds[0].id), 1);
+}
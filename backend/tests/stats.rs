@@ -0,0 +1,100 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{TimeZone, Utc};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::{NewFeedItem, NewProcessingRun, ProcessingRunStatus};
+use mail2feed_backend::db::operations_generic::{FeedItemOpsGeneric, ProcessingRunOpsGeneric};
+use mail2feed_backend::stats;
+use mail2feed_backend::testing::{TestAccount, TestFeed, TestRule};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+#[tokio::test]
+async fn test_stats_aggregate_items_senders_and_runs() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let fixture = TestAccount::new("Work")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")).with_feed(TestFeed::new("Quiet")))
+        .insert(&pool)
+        .unwrap();
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let account_id = fixture.account_id();
+
+    // Two items on the 9th, one on the 10th, and one long before the window
+    for (day, sender, body) in [(9, "a@example.com", "1234"), (9, "b@example.com", "12"), (10, "a@example.com", "123456"), (1, "c@example.com", "")] {
+        let date = Utc.with_ymd_and_hms(2025, 9, day, 12, 0, 0).unwrap();
+        let item = NewFeedItem::new(feed_id.clone(), format!("Issue {}", day), None, None, None, date,
+            None, None, Some(sender.to_string()), Some(body.to_string()));
+        FeedItemOpsGeneric::create(&pool, &item).unwrap();
+    }
+    for status in [ProcessingRunStatus::Completed, ProcessingRunStatus::Completed, ProcessingRunStatus::Failed] {
+        let run = ProcessingRunOpsGeneric::create(&pool, &NewProcessingRun::new(account_id.clone())).unwrap();
+        ProcessingRunOpsGeneric::finish(&pool, run.id.as_deref().unwrap(), &status, 0, 0, None).unwrap();
+    }
+    ProcessingRunOpsGeneric::create(&pool, &NewProcessingRun::new(account_id.clone())).unwrap();
+
+    let now = Utc.with_ymd_and_hms(2025, 9, 10, 18, 0, 0).unwrap();
+    let stats = stats::collect(&pool, 3, now).unwrap();
+
+    let news = stats.feeds.iter().find(|feed| feed.feed_id == feed_id).unwrap();
+    let per_day: Vec<_> = news.items_per_day.iter().map(|day| (day.date.as_str(), day.items)).collect();
+    assert_eq!(per_day, [("2025-09-08", 0), ("2025-09-09", 2), ("2025-09-10", 1)]);
+    assert_eq!((news.stored_items, news.stored_bytes), (4, 12));
+    let quiet = stats.feeds.iter().find(|feed| feed.title == "Quiet").unwrap();
+    assert!(quiet.items_per_day.iter().all(|day| day.items == 0));
+
+    let senders: Vec<_> = stats.top_senders.iter().map(|sender| (sender.sender.as_str(), sender.items)).collect();
+    assert_eq!(senders, [("a@example.com", 2), ("b@example.com", 1)]);
+    assert_eq!(stats.average_item_bytes, Some(3));
+
+    // Runs are stamped with the real clock, so they fall in a window ending now
+    let stats = stats::collect(&pool, 1, Utc::now()).unwrap();
+    let work = stats.accounts.iter().find(|account| account.account_id == account_id).unwrap();
+    assert_eq!((work.runs, work.failed_runs), (3, 1));
+    assert!((work.error_rate.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_stats_endpoint_checks_the_window() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    TestAccount::new("Idle").insert(&pool).unwrap();
+    let app = app(pool);
+    let get = |uri: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        }
+    };
+
+    let (status, body) = get("/api/stats?days=7").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["days"], 7);
+    assert!(body["storage"]["database_bytes"].as_i64().unwrap() > 0);
+    assert_eq!(body["accounts"][0]["runs"], 0);
+    assert!(body["accounts"][0]["error_rate"].is_null());
+    assert!(body["average_item_bytes"].is_null());
+
+    assert_eq!(get("/api/stats").await.1["days"], stats::DEFAULT_DAYS);
+    for uri in ["/api/stats?days=0", "/api/stats?days=366"] {
+        assert_eq!(get(uri).await.0, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{cleanup::FeedRetention, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// A feed receiving one 1000 byte email a day for the last `days` days
+fn create_feed(conn: &mut SqliteConnection, title: &str, max_items: Option<i32>, days: i64) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        format!("{} account", title),
+        "localhost".to_string(),
+        993,
+        format!("{}@example.com", title),
+        "password".to_string(),
+        true,
+    )).unwrap();
+
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        format!("{} rule", title),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+
+    let mut new_feed = NewFeed::new(
+        title.to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    );
+    new_feed.max_items = max_items;
+    new_feed.max_age_days = None;
+    new_feed.min_items = None;
+    let feed = FeedOps::create(conn, &new_feed).unwrap();
+
+    for day in 0..days {
+        FeedItemOps::create(conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Item {}", day),
+            None,
+            None,
+            None,
+            Utc::now() - Duration::days(day) - Duration::hours(1),
+            None,
+            None,
+            None,
+            Some("x".repeat(1000)),
+        )).unwrap();
+    }
+    feed
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_storage_forecast_projects_budget_and_recommends_retention() {
+    let pool = setup_test_db();
+    // Feeds without limits of their own are not limited by default either
+    FeedRetention { max_items: None, max_age_days: None, min_items: None }
+        .store(&DatabasePool::SQLite(pool.clone()))
+        .unwrap();
+    let open = create_feed(&mut pool.get().unwrap(), "open", None, 60);
+    create_feed(&mut pool.get().unwrap(), "capped", Some(40), 20);
+    let app = app(pool);
+
+    let (status, forecast) = get(&app, "/api/analysis/storage-forecast").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(forecast["window_days"], 30);
+    assert_eq!(forecast["stored_bytes"], 80_000);
+    assert_eq!(forecast["steady_state_bytes"], Value::Null);
+    assert_eq!(forecast["budget_reached_at"], Value::Null);
+
+    // The capped feed is measured over the 20 days it has items for
+    let feed = |title: &str| forecast["feeds"].as_array().unwrap().iter().find(|feed| feed["title"] == title).unwrap().clone();
+    let open_forecast = feed("open");
+    assert_eq!(open_forecast["feed_id"], open.id.clone().unwrap());
+    assert_eq!(open_forecast["average_item_bytes"], 1000);
+    assert_eq!(open_forecast["items_per_day"], 1.0);
+    assert_eq!(open_forecast["steady_state_bytes"], Value::Null);
+    assert_eq!(feed("capped")["steady_state_bytes"], 40_000);
+    assert!((feed("capped")["items_per_day"].as_f64().unwrap() - 1.0).abs() < 0.1);
+
+    let (status, forecast) = get(&app, "/api/analysis/storage-forecast?budget_mb=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(forecast["budget_bytes"], 1024 * 1024);
+    let days = forecast["days_until_budget"].as_i64().unwrap();
+    assert!((900..1000).contains(&days), "{}", days);
+    assert!(forecast["budget_reached_at"].is_string());
+    let open_forecast = forecast["feeds"].as_array().unwrap().iter().find(|feed| feed["title"] == "open").unwrap();
+    assert!(open_forecast["recommended_max_age_days"].as_i64().unwrap() > 0);
+    assert!(open_forecast["recommended_max_items"].as_i64().unwrap() > 0);
+
+    let (status, _) = get(&app, "/api/analysis/storage-forecast?window_days=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
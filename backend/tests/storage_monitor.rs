@@ -0,0 +1,152 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::scheduler::EmailScheduler;
+use mail2feed_backend::background::storage::{self, Safeguard, StorageLimits};
+use mail2feed_backend::background::{BackgroundConfig, BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// A feed keeping at least `min_items`, with `count` items a day apart
+fn create_feed(conn: &mut SqliteConnection, min_items: i32, count: i64) -> Feed {
+    let account = ImapAccountOps::create(conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let mut new_feed = NewFeed::new(
+        "Growing Feed".to_string(),
+        None,
+        None,
+        rule.id.unwrap(),
+        "rss".to_string(),
+        true,
+    );
+    new_feed.min_items = Some(min_items);
+    let feed = FeedOps::create(conn, &new_feed).unwrap();
+
+    for day in 0..count {
+        FeedItemOps::create(conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("Day {}", day),
+            Some("x".repeat(4096)),
+            None,
+            None,
+            Utc::now() - Duration::days(count - day),
+            None,
+            None,
+            None,
+            None,
+        )).unwrap();
+    }
+    feed
+}
+
+fn titles(conn: &mut SqliteConnection, feed: &Feed) -> Vec<String> {
+    FeedItemOps::get_by_feed_id(conn, feed.id.as_ref().unwrap(), None)
+        .unwrap()
+        .into_iter()
+        .map(|item| item.title)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_database_limit_tightens_retention() {
+    let pool = setup_test_db();
+    let feed = create_feed(&mut pool.get().unwrap(), 5, 12);
+    let db_pool = DatabasePool::SQLite(pool.clone());
+
+    let within = StorageLimits { max_database_bytes: Some(i64::MAX), ..Default::default() };
+    let status = storage::enforce(&db_pool, &within).await.unwrap();
+    assert!(status.database_bytes > 12 * 4096);
+    assert!(status.warnings.is_empty());
+    assert_eq!(titles(&mut pool.get().unwrap(), &feed).len(), 12);
+
+    // Each pass over the limit removes the oldest quarter, down to min_items
+    let over = StorageLimits { max_database_bytes: Some(1), ..Default::default() };
+    let status = storage::enforce(&db_pool, &over).await.unwrap();
+    assert!(status.retention_tightened);
+    assert!(!status.ingestion_paused);
+    let remaining = titles(&mut pool.get().unwrap(), &feed);
+    assert_eq!(remaining.len(), 9);
+    assert_eq!(remaining.last().unwrap(), "Day 3");
+
+    for _ in 0..3 {
+        storage::enforce(&db_pool, &over).await.unwrap();
+    }
+    assert_eq!(titles(&mut pool.get().unwrap(), &feed).len(), 5);
+
+    let paused = StorageLimits { safeguard: Safeguard::PauseIngestion, ..over };
+    let status = storage::enforce(&db_pool, &paused).await.unwrap();
+    assert!(status.ingestion_paused);
+    assert_eq!(titles(&mut pool.get().unwrap(), &feed).len(), 5);
+}
+
+// A single test, as it changes the process-wide storage limits
+#[tokio::test]
+async fn test_low_disk_space_pauses_processing_and_shows_in_stats() {
+    let pool = setup_test_db();
+    create_feed(&mut pool.get().unwrap(), 0, 0);
+    let scheduler = EmailScheduler::new(DatabasePool::SQLite(pool.clone()), BackgroundConfig::default()).unwrap();
+    let app = app(pool);
+
+    // No disk has this much free
+    std::env::set_var("STORAGE_MIN_FREE_MB", i64::MAX.to_string());
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 0);
+
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/stats").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let stats: Value = serde_json::from_slice(&body).unwrap();
+    assert!(stats["storage"]["database_bytes"].as_i64().unwrap() > 0);
+    assert!(stats["storage"]["disk_free_bytes"].as_i64().is_some());
+    assert_eq!(stats["storage"]["ingestion_paused"], true);
+    assert_eq!(stats["storage"]["warnings"].as_array().unwrap().len(), 1);
+
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+    assert!(metrics.contains("\nmail2feed_storage_limit_exceeded 1\n"), "{}", metrics);
+    assert!(metrics.contains("\nmail2feed_database_bytes "), "{}", metrics);
+
+    std::env::remove_var("STORAGE_MIN_FREE_MB");
+    assert_eq!(scheduler.process_due_accounts().await.unwrap(), 1);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use chrono::{Duration, Utc};
+use diesel::SqliteConnection;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// A feed with one item per entry of `hours_ago`, titled `<feed> <hours>h`
+fn create_feed(conn: &mut SqliteConnection, rule: &EmailRule, title: &str, hours_ago: &[i64]) -> Feed {
+    let feed = FeedOps::create(conn, &NewFeed::new(
+        title.to_string(),
+        None,
+        None,
+        rule.id.clone().unwrap(),
+        "rss".to_string(),
+        true,
+    )).unwrap();
+    for hours in hours_ago {
+        FeedItemOps::create(conn, &NewFeedItem::new(
+            feed.id.clone().unwrap(),
+            format!("{} {}h", title, hours),
+            None,
+            None,
+            None,
+            Utc::now() - Duration::hours(*hours),
+            None,
+            None,
+            None,
+            None,
+        )).unwrap();
+    }
+    feed
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn titles(page: &Value) -> Vec<String> {
+    page["items"].as_array().unwrap().iter().map(|item| item["title"].as_str().unwrap().to_string()).collect()
+}
+
+/// Follow the cursors from `uri` and return every title in order
+async fn all_titles(app: &axum::Router, uri: &str) -> Vec<String> {
+    let mut titles_seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let page_uri = match &cursor {
+            Some(cursor) => format!("{}&cursor={}", uri, urlencoding::encode(cursor)),
+            None => uri.to_string(),
+        };
+        let (status, page) = get(app, &page_uri).await;
+        assert_eq!(status, StatusCode::OK);
+        titles_seen.extend(titles(&page));
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => return titles_seen,
+        }
+    }
+}
+
+fn setup() -> (DbPool, Feed, Feed, Feed) {
+    let pool = setup_test_db();
+    let mut conn = pool.get().unwrap();
+    let account = ImapAccountOps::create(&mut conn, &NewImapAccount::new(
+        "Test Account".to_string(),
+        "localhost".to_string(),
+        993,
+        "user@example.com".to_string(),
+        "password".to_string(),
+        true,
+    )).unwrap();
+    let rule = EmailRuleOps::create(&mut conn, &NewEmailRule::new(
+        "Test Rule".to_string(),
+        account.id.clone().unwrap(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )).unwrap();
+    let news = create_feed(&mut conn, &rule, "news", &[1, 4, 7]);
+    let blogs = create_feed(&mut conn, &rule, "blogs", &[2, 5]);
+    let spam = create_feed(&mut conn, &rule, "spam", &[3, 6]);
+    drop(conn);
+    (pool, news, blogs, spam)
+}
+
+#[tokio::test]
+async fn test_timeline_pages_through_all_feeds_newest_first() {
+    let (pool, _, _, _) = setup();
+    let app = app(pool);
+
+    let (status, page) = get(&app, "/api/timeline?limit=3").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(titles(&page), ["news 1h", "blogs 2h", "spam 3h"]);
+    assert_eq!(page["items"][1]["feed_title"], "blogs");
+    assert!(page["items"][1]["feed_id"].is_string());
+    assert!(page["next_cursor"].is_string());
+
+    assert_eq!(
+        all_titles(&app, "/api/timeline?limit=2").await,
+        ["news 1h", "blogs 2h", "spam 3h", "news 4h", "blogs 5h", "spam 6h", "news 7h"],
+    );
+}
+
+#[tokio::test]
+async fn test_timeline_filters() {
+    let (pool, news, blogs, spam) = setup();
+    let item_id = |title: &str| {
+        let mut conn = pool.get().unwrap();
+        FeedItemOps::get_by_feed_id(&mut conn, news.id.as_ref().unwrap(), None).unwrap()
+            .into_iter().find(|item| item.title == title).unwrap().id.unwrap()
+    };
+    let read_id = item_id("news 1h");
+    FeedItemOps::update_metadata(&mut pool.get().unwrap(), &read_id, Some(true), None).unwrap();
+    let app = app(pool.clone());
+
+    let feeds = format!("{},{}", news.id.as_ref().unwrap(), blogs.id.as_ref().unwrap());
+    assert_eq!(
+        all_titles(&app, &format!("/api/timeline?limit=2&feeds={}", feeds)).await,
+        ["news 1h", "blogs 2h", "news 4h", "blogs 5h", "news 7h"],
+    );
+    assert_eq!(
+        all_titles(&app, &format!("/api/timeline?limit=2&exclude_feeds={}", spam.id.as_ref().unwrap())).await,
+        ["news 1h", "blogs 2h", "news 4h", "blogs 5h", "news 7h"],
+    );
+    assert_eq!(
+        all_titles(&app, &format!("/api/timeline?limit=10&unread_only=true&feeds={}", news.id.as_ref().unwrap())).await,
+        ["news 4h", "news 7h"],
+    );
+
+    let (status, _) = get(&app, "/api/timeline?cursor=garbage").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/api/timeline?limit=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::{connection::DatabasePool, DbPool};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+// Fingerprints of tests/fixtures/imap-server.crt as printed by
+// `openssl x509 -fingerprint -sha256` and of its DER public key
+const CERTIFICATE_PIN: &str = "cert-sha256:A5:A2:A5:E0:01:87:DE:BD:3E:15:9C:5E:A0:DE:2D:B7:79:A5:E9:68:C3:D9:42:13:46:E1:35:EF:B2:8F:E0:F1";
+const PUBLIC_KEY_HEX: &str = "250f52bb171caf5c6085f611d8e09483419d2e8bdc264dc3925fa718007da323";
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+/// IMAP server with a self-signed certificate that completes STARTTLS and
+/// then hangs up; returns its port
+fn spawn_starttls_server() -> u16 {
+    let identity = native_tls::Identity::from_pkcs8(
+        include_bytes!("fixtures/imap-server.crt"),
+        include_bytes!("fixtures/imap-server.key"),
+    ).unwrap();
+    let acceptor = native_tls::TlsAcceptor::new(identity).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let acceptor = acceptor.clone();
+            std::thread::spawn(move || {
+                stream.write_all(b"* OK IMAP4rev1 test server ready\r\n").unwrap();
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line).unwrap();
+                assert_eq!(line, "a0 STARTTLS\r\n");
+                stream.write_all(b"a0 OK Begin TLS negotiation now\r\n").unwrap();
+                let _ = acceptor.accept(stream);
+            });
+        }
+    });
+    port
+}
+
+/// Upper case hex with colons, as pins are reported
+fn colon_hex(hex: &str) -> String {
+    hex.to_uppercase().as_bytes().chunks(2)
+        .map(|pair| std::str::from_utf8(pair).unwrap())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+async fn request(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Content-Type", "application/json")
+                .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn account(username: &str, port: u16, use_tls: bool, tls_pin: Option<&str>) -> Value {
+    json!({
+        "name": username,
+        "host": "localhost",
+        "port": port,
+        "username": username,
+        "password": "secret",
+        "use_tls": use_tls,
+        "tls_pin": tls_pin
+    })
+}
+
+async fn create_account(app: &axum::Router, account: Value) -> String {
+    let (status, created) = request(app, Method::POST, "/api/imap-accounts", Some(account)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    created["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_fingerprint_endpoint_reports_presented_certificate() {
+    let port = spawn_starttls_server();
+    let app = app(setup_test_db());
+
+    let public_key_pin = format!("pubkey-sha256:{}", PUBLIC_KEY_HEX);
+    let account_id = create_account(&app, account("tofu", port, true, Some(&public_key_pin))).await;
+    let (status, observed) = request(&app, Method::GET, &format!("/api/imap/{}/tls-fingerprint", account_id), None).await;
+    assert_eq!(status, StatusCode::OK, "{}", observed);
+    assert_eq!(observed["certificate_pin"], CERTIFICATE_PIN);
+    assert_eq!(observed["public_key_pin"], format!("pubkey-sha256:{}", colon_hex(PUBLIC_KEY_HEX)));
+    assert_eq!(observed["pin"], public_key_pin);
+    assert_eq!(observed["matches_pin"], true);
+
+    let plain_id = create_account(&app, account("plain", port, false, None)).await;
+    let (status, _) = request(&app, Method::GET, &format!("/api/imap/{}/tls-fingerprint", plain_id), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_pinned_connection_fails_closed_on_mismatch() {
+    let port = spawn_starttls_server();
+    let app = app(setup_test_db());
+
+    let other_pin = format!("pubkey-sha256:{}", "00".repeat(32));
+    let mismatched_id = create_account(&app, account("mismatched", port, true, Some(&other_pin))).await;
+    let (status, result) = request(&app, Method::GET, &format!("/api/imap/{}/test", mismatched_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(result["success"], false);
+    let message = result["message"].as_str().unwrap();
+    assert!(message.contains("does not match the pinned"), "{}", message);
+    assert!(message.contains(&format!("server presented pubkey-sha256:{}", colon_hex(PUBLIC_KEY_HEX))), "{}", message);
+
+    // A matching pin trusts the self-signed certificate; the test server then
+    // hangs up, so the connection gets as far as logging in
+    let matching_id = create_account(&app, account("matching", port, true, Some(CERTIFICATE_PIN))).await;
+    let (_, result) = request(&app, Method::GET, &format!("/api/imap/{}/test", matching_id), None).await;
+    assert!(result["message"].as_str().unwrap().contains("Authentication failed for user 'matching'"), "{}", result);
+
+    // Without a pin the certificate must pass normal validation
+    let unpinned_id = create_account(&app, account("unpinned", port, true, None)).await;
+    let (_, result) = request(&app, Method::GET, &format!("/api/imap/{}/test", unpinned_id), None).await;
+    assert!(result["message"].as_str().unwrap().contains("handshake"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_account_api_validates_pins() {
+    let app = app(setup_test_db());
+
+    let invalid = [
+        account("short", 993, true, Some("cert-sha256:abcd")),
+        account("algorithm", 993, true, Some(&format!("sha1:{}", "ab".repeat(32)))),
+        account("plain", 143, false, Some(CERTIFICATE_PIN)),
+    ];
+    for account in invalid {
+        let (status, body) = request(&app, Method::POST, "/api/imap-accounts", Some(account.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "accepted {}", account);
+        assert!(body["error"].is_string());
+    }
+
+    let bare = "a5a2a5e00187debd3e159c5ea0de2db779a5e968c3d9421346e135efb28fe0f1";
+    let account_id = create_account(&app, account("bare", 993, true, Some(bare))).await;
+    let uri = format!("/api/imap-accounts/{}", account_id);
+    let (_, stored) = request(&app, Method::GET, &uri, None).await;
+    assert_eq!(stored["tls_pin"], bare);
+
+    // Updating without a pin removes it
+    let (status, updated) = request(&app, Method::PUT, &uri, Some(account("bare", 993, true, None))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(updated["tls_pin"].is_null());
+}
+
+#[tokio::test]
+async fn test_account_tls_server_name() {
+    let port = spawn_starttls_server();
+    let app = app(setup_test_db());
+
+    let invalid = [
+        ("imap.example.com", false),
+        ("imap.example.com:993", true),
+        ("10.0.0.5", true),
+    ];
+    for (name, use_tls) in invalid {
+        let mut body = account("invalid", port, use_tls, None);
+        body["tls_server_name"] = json!(name);
+        let (status, response) = request(&app, Method::POST, "/api/imap-accounts", Some(body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "accepted {}", name);
+        assert!(response["error"].is_string());
+    }
+
+    // The connection goes to the host while the handshake names the override
+    let mut proxied = account("proxied", port, true, Some(CERTIFICATE_PIN));
+    proxied["host"] = json!("127.0.0.1");
+    proxied["tls_server_name"] = json!("imap.example.com");
+    let account_id = create_account(&app, proxied).await;
+    let (_, stored) = request(&app, Method::GET, &format!("/api/imap-accounts/{}", account_id), None).await;
+    assert_eq!(stored["tls_server_name"], "imap.example.com");
+    let (_, result) = request(&app, Method::GET, &format!("/api/imap/{}/test", account_id), None).await;
+    assert!(result["message"].as_str().unwrap().contains("Authentication failed for user 'proxied'"), "{}", result);
+}
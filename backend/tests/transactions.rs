@@ -0,0 +1,154 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::Utc;
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{models::*, operations::*, DbPool};
+use mail2feed_backend::testing::TestAccount;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+fn create_account(pool: &DbPool) -> String {
+    TestAccount::new("Test Account").insert(&DatabasePool::SQLite(pool.clone())).unwrap().account_id()
+}
+
+fn new_rule(account_id: &str) -> NewEmailRule {
+    NewEmailRule::new(
+        "Newsletters".to_string(),
+        account_id.to_string(),
+        "INBOX".to_string(),
+        None,
+        None,
+        None,
+        None,
+        true,
+    )
+}
+
+async fn create_rule_with_feed(app: &axum::Router, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/email-rules/with-feed")
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_rule_and_feed_are_created_together() {
+    let pool = setup_test_db();
+    let account_id = create_account(&pool);
+    let app = app(pool.clone());
+
+    let (status, body) = create_rule_with_feed(&app, json!({
+        "rule": {"name": "Newsletters", "imap_account_id": account_id, "folder": "INBOX", "is_active": true},
+        "feed": {"description": "From {{rule.folder}}"},
+    })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["feed"]["title"], "Newsletters");
+    assert_eq!(body["feed"]["feed_type"], "rss");
+    assert_eq!(body["feed"]["email_rule_id"], body["rule"]["id"]);
+
+    let mut conn = pool.get().unwrap();
+    assert_eq!(EmailRuleOps::get_all(&mut conn).unwrap().len(), 1);
+    assert_eq!(FeedOps::get_all(&mut conn).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_feed_creates_no_rule() {
+    let pool = setup_test_db();
+    let account_id = create_account(&pool);
+    let app = app(pool.clone());
+
+    let (status, _) = create_rule_with_feed(&app, json!({
+        "rule": {"name": "Newsletters", "imap_account_id": account_id, "folder": "INBOX", "is_active": true},
+        "feed": {"title": "{{rule.unknown}}"},
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = create_rule_with_feed(&app, json!({
+        "rule": {"name": "Newsletters", "imap_account_id": "missing", "folder": "INBOX", "is_active": true},
+        "feed": {},
+    })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().starts_with("Invalid account ID"), "{}", body);
+
+    let mut conn = pool.get().unwrap();
+    assert!(EmailRuleOps::get_all(&mut conn).unwrap().is_empty());
+    assert!(FeedOps::get_all(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_failed_transaction_is_rolled_back() {
+    let pool = setup_test_db();
+    let account_id = create_account(&pool);
+    let database = DatabasePool::SQLite(pool.clone());
+
+    let result: anyhow::Result<()> = database.transaction(|tx| {
+        let rule = tx.create_email_rule(&new_rule(&account_id))?;
+        tx.create_feed(&NewFeed::new("Feed".to_string(), None, None, rule.id.unwrap(), "rss".to_string(), true))?;
+        anyhow::bail!("giving up")
+    });
+    assert_eq!(result.unwrap_err().to_string(), "giving up");
+
+    let mut conn = pool.get().unwrap();
+    assert!(EmailRuleOps::get_all(&mut conn).unwrap().is_empty());
+    assert!(FeedOps::get_all(&mut conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_items_are_counted_with_their_run() {
+    let pool = setup_test_db();
+    let account_id = create_account(&pool);
+    let database = DatabasePool::SQLite(pool.clone());
+    let (feed, run_id) = {
+        let mut conn = pool.get().unwrap();
+        let rule = EmailRuleOps::create(&mut conn, &new_rule(&account_id)).unwrap();
+        let feed = FeedOps::create(&mut conn, &NewFeed::new("Feed".to_string(), None, None, rule.id.unwrap(), "rss".to_string(), true)).unwrap();
+        let run = ProcessingRunOps::create(&mut conn, &NewProcessingRun::new(account_id.clone())).unwrap();
+        (feed, run.id.unwrap())
+    };
+
+    let new_item = |title: &str| {
+        let mut item = NewFeedItem::new(feed.id.clone().unwrap(), title.to_string(), None, None, None, Utc::now(), None, None, None, None);
+        item.processing_run_id = Some(run_id.clone());
+        item
+    };
+    for title in ["First", "Second"] {
+        database.transaction(|tx| {
+            tx.create_feed_item(&new_item(title))?;
+            tx.count_run_item(&run_id)
+        }).unwrap();
+    }
+    // An item whose count fails is not kept either
+    let result: anyhow::Result<()> = database.transaction(|tx| {
+        tx.create_feed_item(&new_item("Third"))?;
+        tx.count_run_item(&run_id)?;
+        anyhow::bail!("interrupted")
+    });
+    assert!(result.is_err());
+
+    let mut conn = pool.get().unwrap();
+    assert_eq!(ProcessingRunOps::get_by_id(&mut conn, &run_id).unwrap().items_created, 2);
+    assert_eq!(FeedItemOps::get_by_feed_id(&mut conn, feed.id.as_ref().unwrap(), None).unwrap().len(), 2);
+}
@@ -0,0 +1,125 @@
+mod common;
+mod mock_imap;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use mail2feed_backend::api;
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::models::FeedItem;
+use mail2feed_backend::db::operations_generic::{FeedItemOpsGeneric, FeedOpsGeneric};
+use mail2feed_backend::feed::generator::FeedGenerator;
+use mail2feed_backend::imap::processor::EmailProcessor;
+use mail2feed_backend::testing::{Fixture, TestFeed, TestRule};
+use mock_imap::{MockImap, MockMessage};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DatabasePool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(pool, background_handle)
+}
+
+/// Process the server's INBOX into feed `News`; returns its items by title
+async fn process(server: &MockImap, pool: &DatabasePool) -> (Fixture, Vec<FeedItem>) {
+    let fixture = server.test_account("Mock IMAP")
+        .with_rule(TestRule::new("News").with_feed(TestFeed::new("News")))
+        .insert(pool)
+        .unwrap();
+    let result = EmailProcessor::new(fixture.account.clone(), pool.clone()).process_account().await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+    let feed_id = fixture.feed("News").id.clone().unwrap();
+    let mut items = FeedItemOpsGeneric::get_by_feed_id(pool, &feed_id, None).unwrap();
+    items.sort_by(|a, b| a.title.cmp(&b.title));
+    (fixture, items)
+}
+
+async fn unsubscribe(pool: &DatabasePool, item_id: &str) -> (StatusCode, Value) {
+    let response = app(pool.clone())
+        .oneshot(Request::builder()
+            .method(Method::POST)
+            .uri(format!("/api/feed-items/{}/unsubscribe", item_id))
+            .body(Body::empty())
+            .unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_items_keep_unsubscribe_links() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", MockMessage::new("list@example.com", "A header")
+        .header_line("List-Unsubscribe", "<mailto:leave@example.com>, <https://example.com/u/1>")
+        .header_line("List-Unsubscribe-Post", "List-Unsubscribe=One-Click"));
+    server.add_message("INBOX", MockMessage::new("news@example.com", "B footer")
+        .body("This week's news.\r\n\r\nDon't want these emails? Unsubscribe: https://example.com/prefs/2\r\n"));
+    server.add_message("INBOX", MockMessage::new("friend@example.com", "C none"));
+
+    let (fixture, items) = process(&server, &pool).await;
+
+    assert_eq!(items[0].unsubscribe_url.as_deref(), Some("https://example.com/u/1"));
+    assert_eq!(items[0].unsubscribe_mailto.as_deref(), Some("mailto:leave@example.com"));
+    assert!(items[0].unsubscribe_one_click);
+    assert_eq!(items[1].unsubscribe_url.as_deref(), Some("https://example.com/prefs/2"));
+    assert_eq!(items[1].unsubscribe_mailto, None);
+    assert!(!items[1].unsubscribe_one_click);
+    assert_eq!((items[2].unsubscribe_url.as_deref(), items[2].unsubscribe_mailto.as_deref()), (None, None));
+
+    let feed = FeedOpsGeneric::get_by_id(&pool, fixture.feed("News").id.as_ref().unwrap()).unwrap();
+    let rss = FeedGenerator::generate_rss(&feed, &items).unwrap();
+    assert!(rss.contains("xmlns:atom=\"http://www.w3.org/2005/Atom\""));
+    assert!(rss.contains(r#"<atom:link href="https://example.com/u/1" rel="unsubscribe"/>"#), "{}", rss);
+    assert!(rss.contains(r#"<atom:link href="https://example.com/prefs/2" rel="unsubscribe"/>"#));
+    let atom = FeedGenerator::generate_atom(&feed, &items).unwrap();
+    assert_eq!(atom.matches(r#"rel="unsubscribe""#).count(), 2);
+}
+
+#[tokio::test]
+async fn test_unsubscribe_endpoint() {
+    let pool = DatabasePool::SQLite(setup_test_db());
+    let server = MockImap::start("");
+    server.add_message("INBOX", MockMessage::new("list@example.com", "A mailto")
+        .header_line("List-Unsubscribe", "<mailto:leave@example.com>"));
+    server.add_message("INBOX", MockMessage::new("list@example.com", "B link")
+        .header_line("List-Unsubscribe", "<mailto:leave@example.com>, <https://example.com/u/2>"));
+    // Nothing listens on port 1, so the one-click request cannot be delivered
+    server.add_message("INBOX", MockMessage::new("list@example.com", "C one-click")
+        .header_line("List-Unsubscribe", "<https://127.0.0.1:1/u/3>")
+        .header_line("List-Unsubscribe-Post", "List-Unsubscribe=One-Click"));
+    server.add_message("INBOX", MockMessage::new("friend@example.com", "D none"));
+    let (_fixture, items) = process(&server, &pool).await;
+    let ids: Vec<String> = items.iter().map(|item| item.id.clone().unwrap()).collect();
+
+    let (status, body) = unsubscribe(&pool, &ids[0]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["method"], "mailto");
+    assert_eq!(body["url"], "mailto:leave@example.com");
+    assert_eq!(body["unsubscribed"], false);
+
+    let (status, body) = unsubscribe(&pool, &ids[1]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["method"], "link");
+    assert_eq!(body["url"], "https://example.com/u/2");
+
+    let (status, body) = unsubscribe(&pool, &ids[2]).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert!(body["error"].as_str().unwrap().contains("One-click unsubscribe failed"));
+
+    let (status, _) = unsubscribe(&pool, &ids[3]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = unsubscribe(&pool, "missing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use mail2feed_backend::api::{self, version};
+use mail2feed_backend::background::{BackgroundServiceHandle, ServiceController};
+use mail2feed_backend::db::connection::DatabasePool;
+use mail2feed_backend::db::{operations::*, DbPool};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use tower::ServiceExt;
+
+use common::setup_test_db;
+
+fn app(pool: DbPool) -> axum::Router {
+    let (control_tx, _control_rx) = mpsc::unbounded_channel();
+    let background_handle = BackgroundServiceHandle {
+        service: Arc::new(RwLock::new(None)),
+        controller: ServiceController::new(control_tx),
+    };
+    api::create_routes(DatabasePool::SQLite(pool), background_handle)
+}
+
+async fn get_version(app: &axum::Router) -> Value {
+    let request = Request::builder().uri("/api/admin/version").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_fresh_install_reports_no_changes() {
+    let pool = setup_test_db();
+    version::record_run(&DatabasePool::SQLite(pool.clone())).unwrap();
+
+    let body = get_version(&app(pool)).await;
+    assert_eq!(body["version"], version::VERSION);
+    assert!(body["schema_version"].is_string());
+    assert_eq!(body["previous_version"], Value::Null);
+    assert!(body["first_run_at"].is_string());
+    assert_eq!(body["changes"], Value::Array(Vec::new()));
+}
+
+#[tokio::test]
+async fn test_upgrade_lists_changes_since_previous_version() {
+    let pool = setup_test_db();
+    {
+        let mut conn = pool.get().unwrap();
+        AppVersionOps::record_run(&mut conn, "0.0.1", Some("abc1234")).unwrap();
+    }
+    let database = DatabasePool::SQLite(pool.clone());
+    version::record_run(&database).unwrap();
+    // Restarting the same version keeps the upgrade in view
+    version::record_run(&database).unwrap();
+
+    let body = get_version(&app(pool)).await;
+    assert_eq!(body["previous_version"], "0.0.1");
+    let changes = body["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), version::CHANGES.len());
+    assert!(changes.iter().all(|change| change["version"] == version::VERSION && change["kind"] == "behavior"));
+}
@@ -3,7 +3,11 @@ import type {
   ImapAccount, 
   CreateImapAccountRequest, 
   UpdateImapAccountRequest,
-  ConnectionTestResult 
+  ConnectionTestResult,
+  ConnectionDiagnostics,
+  FolderNode,
+  SetupConnectionRequest,
+  TlsFingerprint
 } from '../types'
 
 export const accountsApi = {
@@ -31,6 +35,22 @@ export const accountsApi = {
   testConnection: (id: string) => 
     apiClient.get<ConnectionTestResult>(`/api/imap/${id}/test`),
 
+  // Outcome of each step of connecting to a saved account
+  diagnose: (id: string) => 
+    apiClient.post<ConnectionDiagnostics>(`/api/imap-accounts/${id}/test`, {}),
+
+  // Outcome of each step of connecting with unsaved settings
+  diagnoseSettings: (data: SetupConnectionRequest) => 
+    apiClient.post<ConnectionDiagnostics>('/api/imap-accounts/test', data),
+
+  // Certificate fingerprints presented by the account's server
+  getTlsFingerprint: (id: string) => 
+    apiClient.get<TlsFingerprint>(`/api/imap/${id}/tls-fingerprint`),
+
+  // Folder tree with message and unseen counts, for picking a rule's folder
+  getFolders: (id: string) => 
+    apiClient.get<FolderNode[]>(`/api/imap-accounts/${id}/folders`),
+
   // Process emails for account
   processEmails: (id: string) => 
     apiClient.post<{ message: string }>(`/api/imap/${id}/process`, {}),
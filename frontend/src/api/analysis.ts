@@ -0,0 +1,21 @@
+import { apiClient } from './client'
+import type { RatingReport, RuleCostReport, StorageForecast } from '../types'
+
+export const analysisApi = {
+  // Storage growth per feed projected against a size budget
+  getStorageForecast: (windowDays?: number, budgetMb?: number) => {
+    const params = new URLSearchParams()
+    if (windowDays) params.set('window_days', String(windowDays))
+    if (budgetMb) params.set('budget_mb', String(budgetMb))
+    const query = params.toString()
+    return apiClient.get<StorageForecast>(`/api/analysis/storage-forecast${query ? `?${query}` : ''}`)
+  },
+
+  // Item ratings per sender and rule with suggested rule refinements
+  getRatingReport: () =>
+    apiClient.get<RatingReport>('/api/analysis/ratings'),
+
+  // Most expensive rules and folders over recent runs
+  getRuleCosts: (windowDays?: number) =>
+    apiClient.get<RuleCostReport>(`/api/analysis/rule-costs${windowDays ? `?window_days=${windowDays}` : ''}`),
+}
@@ -3,8 +3,10 @@ import { apiClient } from './client';
 export interface BackgroundConfig {
   global_interval_minutes: number;
   per_account_interval_minutes: number;
+  change_debounce_seconds: number;
   max_concurrent_accounts: number;
   enabled: boolean;
+  catch_up_interrupted: boolean;
   retry: {
     max_attempts: number;
     initial_delay_seconds: number;
@@ -31,6 +33,7 @@ export interface ServiceStatus {
   config: BackgroundConfig;
   accounts_count: number;
   active_processing_count: number;
+  is_paused: boolean;
   total_emails_processed: number;
   total_errors: number;
   uptime_seconds?: number;
@@ -45,6 +48,8 @@ export interface ProcessAccountResponse {
   account_id: string;
   success: boolean;
   message: string;
+  // Job following the processing, see jobsApi
+  job_id?: string;
 }
 
 export interface StartServiceRequest {
@@ -73,6 +78,11 @@ export const backgroundApi = {
     return apiClient.post<ServiceActionResponse>('/api/background/restart', {});
   },
 
+  // Apply a new configuration without restarting the service
+  async updateConfig(config: BackgroundConfig): Promise<BackgroundConfig> {
+    return apiClient.put<BackgroundConfig>('/api/background/config', config);
+  },
+
   // Process a specific account manually
   async processAccount(accountId: string): Promise<ProcessAccountResponse> {
     return apiClient.post<ProcessAccountResponse>(`/api/background/process/${accountId}`, {});
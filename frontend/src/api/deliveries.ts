@@ -0,0 +1,20 @@
+import { apiClient } from './client'
+import type { Delivery } from '../types'
+
+export const deliveriesApi = {
+  // Webhook and chat deliveries that ran out of attempts
+  getFailed: () =>
+    apiClient.get<Delivery[]>('/api/deliveries/failed'),
+
+  // Queue all failed deliveries again
+  retryFailed: () =>
+    apiClient.post<Delivery[]>('/api/deliveries/retry', {}),
+
+  // Queue one delivery again
+  retry: (id: string) =>
+    apiClient.post<Delivery>(`/api/deliveries/${id}/retry`, {}),
+
+  // Discard a delivery
+  delete: (id: string) =>
+    apiClient.delete<void>(`/api/deliveries/${id}`),
+}
@@ -2,13 +2,35 @@ import { apiClient } from './client'
 import type { 
   Feed, 
   FeedItem,
-  FeedItemMetadata,
+  FeedItemsQuery,
+  FeedItemsPage,
+  FeedItemMetadataPage,
   CreateFeedRequest, 
   UpdateFeedRequest,
   UpdateFeedItemRequest,
-  ProcessingStatus
+  BulkFeedItemsRequest,
+  BulkFeedItemsResponse,
+  ProcessingStatus,
+  WebhookTestResult,
+  MergeFeedsRequest,
+  SplitFeedRequest,
+  FeedReorganizationResponse,
+  UnsubscribeResponse,
+  CleanupResult,
+  CleanupReport,
+  JobStartedResponse
 } from '../types'
 
+function itemsSearch(query: FeedItemsQuery): string {
+  const params = new URLSearchParams()
+  if (query.limit) params.set('limit', String(query.limit))
+  if (query.cursor) params.set('cursor', query.cursor)
+  if (query.before) params.set('before', query.before)
+  if (query.after) params.set('after', query.after)
+  const search = params.toString()
+  return search ? `?${search}` : ''
+}
+
 export const feedsApi = {
   // Get all feeds
   getAll: () => 
@@ -30,22 +52,46 @@ export const feedsApi = {
   delete: (id: string) => 
     apiClient.delete<void>(`/api/feeds/${id}`),
 
-  // Get feed items
-  getItems: (id: string, limit?: number) => {
-    const params = limit ? `?limit=${limit}` : ''
-    return apiClient.get<FeedItem[]>(`/api/feeds/${id}/items${params}`)
-  },
+  // Get a page of feed items; pass next_cursor back as cursor for the next page
+  getItems: (id: string, query: FeedItemsQuery = {}) =>
+    apiClient.get<FeedItemsPage>(`/api/feeds/${id}/items${itemsSearch(query)}`),
+
+  // Get a page of feed items metadata for management
+  getItemsMetadata: (id: string, query: FeedItemsQuery = {}) =>
+    apiClient.get<FeedItemMetadataPage>(`/api/feeds/${id}/items/metadata${itemsSearch(query)}`),
+
+  // Enforce the feed's retention now
+  cleanup: (id: string) =>
+    apiClient.post<CleanupResult>(`/api/feeds/${id}/cleanup`, {}),
+
+  // Enforce the retention of every feed and history table now
+  cleanupAll: () =>
+    apiClient.post<CleanupReport>('/api/cleanup', {}),
+
+  // Queue the same cleanup as a job whose result is the report
+  queueCleanupAll: () =>
+    apiClient.post<JobStartedResponse>('/api/cleanup?background=true', {}),
 
-  // Get feed items metadata for management
-  getItemsMetadata: (id: string, limit?: number) => {
-    const params = limit ? `?limit=${limit}` : ''
-    return apiClient.get<FeedItemMetadata[]>(`/api/feeds/${id}/items/metadata${params}`)
-  },
+  // Call the feed's webhook with its newest item
+  testWebhook: (id: string) =>
+    apiClient.post<WebhookTestResult>(`/api/feeds/${id}/webhook/test`, {}),
+
+  // Get a single feed item with its complete body
+  getItem: (itemId: string) =>
+    apiClient.get<FeedItem>(`/api/feed-items/${itemId}`),
 
   // Update feed item (read status, starred status, etc.)
   updateItem: (itemId: string, data: UpdateFeedItemRequest) => 
     apiClient.patch<FeedItem>(`/api/feed-items/${itemId}`, data),
 
+  // Mark, star or delete many items of a feed at once
+  bulkUpdateItems: (feedId: string, data: BulkFeedItemsRequest) =>
+    apiClient.post<BulkFeedItemsResponse>(`/api/feeds/${feedId}/items/bulk`, data),
+
+  // Send the item's one-click unsubscribe request, or get its unsubscribe link
+  unsubscribeItem: (itemId: string) =>
+    apiClient.post<UnsubscribeResponse>(`/api/feed-items/${itemId}/unsubscribe`, {}),
+
   // Get RSS feed content
   getRss: (id: string) => 
     apiClient.get<string>(`/feeds/${id}/rss`),
@@ -54,6 +100,14 @@ export const feedsApi = {
   getAtom: (id: string) => 
     apiClient.get<string>(`/feeds/${id}/atom`),
 
+  // Move every item of one feed into another, which takes over its URLs
+  merge: (data: MergeFeedsRequest) =>
+    apiClient.post<FeedReorganizationResponse>('/api/admin/feeds/merge', data),
+
+  // Move the items of a feed matching a filter into a new feed
+  split: (id: string, data: SplitFeedRequest) =>
+    apiClient.post<FeedReorganizationResponse>(`/api/admin/feeds/${id}/split`, data),
+
   // Process all accounts
   processAll: () => 
     apiClient.post<ProcessingStatus>('/api/imap/process-all', {}),
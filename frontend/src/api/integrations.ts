@@ -0,0 +1,28 @@
+import { apiClient } from './client'
+import type {
+  ChatIntegration,
+  ChatIntegrationRequest,
+  WebhookTestResult
+} from '../types'
+
+export const integrationsApi = {
+  // Get the chat integrations of a feed
+  getByFeed: (feedId: string) =>
+    apiClient.get<ChatIntegration[]>(`/api/feeds/${feedId}/integrations`),
+
+  // Add a Slack, Discord or Matrix integration to a feed
+  create: (feedId: string, data: ChatIntegrationRequest) =>
+    apiClient.post<ChatIntegration>(`/api/feeds/${feedId}/integrations`, data),
+
+  // Update chat integration
+  update: (id: string, data: ChatIntegrationRequest) =>
+    apiClient.put<ChatIntegration>(`/api/chat-integrations/${id}`, data),
+
+  // Delete chat integration
+  delete: (id: string) =>
+    apiClient.delete<void>(`/api/chat-integrations/${id}`),
+
+  // Post the feed's newest item through the integration
+  test: (id: string) =>
+    apiClient.post<WebhookTestResult>(`/api/chat-integrations/${id}/test`, {}),
+}
@@ -0,0 +1,18 @@
+import { apiClient } from './client'
+import type { Job, JobQuery } from '../types'
+
+export const jobsApi = {
+  // Jobs, most recently created first
+  getAll: (query: JobQuery = {}) => {
+    const params = new URLSearchParams(
+      Object.entries(query)
+        .filter(([, value]) => value !== undefined)
+        .map(([key, value]) => [key, String(value)])
+    ).toString()
+    return apiClient.get<Job[]>(`/api/jobs${params ? `?${params}` : ''}`)
+  },
+
+  // A job with its progress and, once completed, its result
+  get: (id: string) =>
+    apiClient.get<Job>(`/api/jobs/${id}`),
+}
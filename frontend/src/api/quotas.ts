@@ -0,0 +1,37 @@
+import { apiClient } from './client'
+import type {
+  AccountQuota,
+  QuotaGroup,
+  QuotaGroupRequest,
+  QuotaReport
+} from '../types'
+
+export const quotasApi = {
+  // Get all quota groups
+  getGroups: () =>
+    apiClient.get<QuotaGroup[]>('/api/quota-groups'),
+
+  // Get quota group by ID
+  getGroup: (id: string) =>
+    apiClient.get<QuotaGroup>(`/api/quota-groups/${id}`),
+
+  // Create quota group
+  createGroup: (data: QuotaGroupRequest) =>
+    apiClient.post<QuotaGroup>('/api/quota-groups', data),
+
+  // Update quota group
+  updateGroup: (id: string, data: QuotaGroupRequest) =>
+    apiClient.put<QuotaGroup>(`/api/quota-groups/${id}`, data),
+
+  // Delete quota group; its accounts keep their own limits
+  deleteGroup: (id: string) =>
+    apiClient.delete<void>(`/api/quota-groups/${id}`),
+
+  // Group limits and usage summed over its accounts
+  getGroupUsage: (id: string) =>
+    apiClient.get<QuotaReport>(`/api/quota-groups/${id}/usage`),
+
+  // Account limits and usage, plus those of its group
+  getAccountQuota: (accountId: string) =>
+    apiClient.get<AccountQuota>(`/api/imap-accounts/${accountId}/quota`),
+}
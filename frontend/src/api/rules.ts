@@ -2,7 +2,15 @@ import { apiClient } from './client'
 import type { 
   EmailRule, 
   CreateEmailRuleRequest, 
-  UpdateEmailRuleRequest 
+  UpdateEmailRuleRequest,
+  CreateRuleWithFeedRequest,
+  RuleWithFeedResponse,
+  RuleStats,
+  RuleMatch,
+  ValidateMatchExpressionRequest,
+  MatchExpressionValidation,
+  BackfillRuleRequest,
+  JobStartedResponse
 } from '../types'
 
 export const rulesApi = {
@@ -18,6 +26,10 @@ export const rulesApi = {
   create: (data: CreateEmailRuleRequest) => 
     apiClient.post<EmailRule>('/api/email-rules', data),
 
+  // Create a rule and its feed in one transaction
+  createWithFeed: (data: CreateRuleWithFeedRequest) =>
+    apiClient.post<RuleWithFeedResponse>('/api/email-rules/with-feed', data),
+
   // Update email rule
   update: (id: string, data: UpdateEmailRuleRequest) => 
     apiClient.put<EmailRule>(`/api/email-rules/${id}`, data),
@@ -26,6 +38,24 @@ export const rulesApi = {
   delete: (id: string) => 
     apiClient.delete<void>(`/api/email-rules/${id}`),
 
+  // Matches recorded for an observe-only rule
+  getStats: (id: string) =>
+    apiClient.get<RuleStats>(`/api/email-rules/${id}/stats`),
+
+  // Emails matched while the rule was observe-only, newest first
+  getPreview: (id: string, limit?: number) => {
+    const params = limit ? `?limit=${limit}` : ''
+    return apiClient.get<RuleMatch[]>(`/api/email-rules/${id}/preview${params}`)
+  },
+
+  // Import the past emails of the rule's folder into its feed in the background
+  backfill: (id: string, data: BackfillRuleRequest = {}) =>
+    apiClient.post<JobStartedResponse>(`/api/email-rules/${id}/backfill`, data),
+
+  // Check a match expression, optionally against a sample email
+  validateExpression: (data: ValidateMatchExpressionRequest) =>
+    apiClient.post<MatchExpressionValidation>('/api/email-rules/validate-expression', data),
+
   // Get rules by account ID
   getByAccountId: (accountId: string) => 
     apiClient.get<EmailRule[]>(`/api/email-rules?account_id=${accountId}`),
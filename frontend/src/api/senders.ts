@@ -0,0 +1,32 @@
+import { apiClient } from './client'
+import type {
+  SenderAlias,
+  SenderAliasRequest,
+  SenderStats
+} from '../types'
+
+export const sendersApi = {
+  // Get all sender alias groups
+  getAliases: () =>
+    apiClient.get<SenderAlias[]>('/api/senders/aliases'),
+
+  // Get sender alias group by ID
+  getAlias: (id: string) =>
+    apiClient.get<SenderAlias>(`/api/senders/aliases/${id}`),
+
+  // Create sender alias group
+  createAlias: (data: SenderAliasRequest) =>
+    apiClient.post<SenderAlias>('/api/senders/aliases', data),
+
+  // Update sender alias group
+  updateAlias: (id: string, data: SenderAliasRequest) =>
+    apiClient.put<SenderAlias>(`/api/senders/aliases/${id}`, data),
+
+  // Delete sender alias group
+  deleteAlias: (id: string) =>
+    apiClient.delete<void>(`/api/senders/aliases/${id}`),
+
+  // Items per sender, alias groups merged
+  getStats: () =>
+    apiClient.get<SenderStats[]>('/api/senders/stats'),
+}
@@ -0,0 +1,12 @@
+import { apiClient } from './client'
+import type { SettingsResponse, UpdateSettingsRequest } from '../types'
+
+export const settingsApi = {
+  // Retention, the public base URL and the runtime settings
+  get: () =>
+    apiClient.get<SettingsResponse>('/api/settings'),
+
+  // Change retention of the tables listed; the others keep theirs
+  update: (data: UpdateSettingsRequest) =>
+    apiClient.put<SettingsResponse>('/api/settings', data),
+}
@@ -0,0 +1,22 @@
+import { apiClient } from './client'
+import type {
+  FolderSuggestion,
+  SetupConnectionRequest,
+  SetupConnectionResult,
+  SetupFinalizeRequest,
+  SetupFinalizeResult
+} from '../types'
+
+export const setupApi = {
+  // Log in with unsaved settings and report the server's capabilities
+  validateConnection: (data: SetupConnectionRequest) =>
+    apiClient.post<SetupConnectionResult>('/api/setup/validate-connection', data),
+
+  // Folders with message counts and newsletter scores
+  suggestFolders: (data: SetupConnectionRequest) =>
+    apiClient.post<FolderSuggestion[]>('/api/setup/suggest-folders', data),
+
+  // Create account, rules and feeds at once
+  finalize: (data: SetupFinalizeRequest) =>
+    apiClient.post<SetupFinalizeResult>('/api/setup/finalize', data),
+}
@@ -0,0 +1,16 @@
+import { apiClient } from './client'
+import type { TimelineQuery, TimelineResponse } from '../types'
+
+export const timelineApi = {
+  // Items across feeds, newest first; pass next_cursor back as cursor for the next page
+  getTimeline: (query: TimelineQuery = {}) => {
+    const params = new URLSearchParams()
+    if (query.limit) params.set('limit', String(query.limit))
+    if (query.cursor) params.set('cursor', query.cursor)
+    if (query.feeds?.length) params.set('feeds', query.feeds.join(','))
+    if (query.exclude_feeds?.length) params.set('exclude_feeds', query.exclude_feeds.join(','))
+    if (query.unread_only) params.set('unread_only', 'true')
+    const search = params.toString()
+    return apiClient.get<TimelineResponse>(`/api/timeline${search ? `?${search}` : ''}`)
+  },
+}
@@ -0,0 +1,8 @@
+import { apiClient } from './client'
+import type { VersionResponse } from '../types'
+
+export const versionApi = {
+  // Running version and what changed since the version that ran before it
+  get: () =>
+    apiClient.get<VersionResponse>('/api/admin/version'),
+}
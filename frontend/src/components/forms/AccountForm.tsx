@@ -3,7 +3,7 @@ import { useNavigate } from 'react-router-dom'
 import { accountsApi } from '../../api/accounts'
 import { useToast } from '../common/Toast'
 import { useAutoSave } from '../../hooks/useAutoSave'
-import type { ImapAccount, CreateImapAccountRequest, UpdateImapAccountRequest } from '../../types'
+import type { ImapAccount, CreateImapAccountRequest, UpdateImapAccountRequest, ConnectionSecurity } from '../../types'
 
 interface AccountFormProps {
   account?: ImapAccount
@@ -11,6 +11,13 @@ interface AccountFormProps {
   onCancel?: () => void
 }
 
+// Implicit TLS on 993 and STARTTLS on 143 are what servers expect there
+const suggestSecurity = (port: number, current: ConnectionSecurity): ConnectionSecurity => {
+  if (port === 993) return 'ssl_tls'
+  if (port === 143) return current === 'none' ? 'none' : 'starttls'
+  return current
+}
+
 export default function AccountForm({ account, onSubmit, onCancel }: AccountFormProps) {
   const navigate = useNavigate()
   const toast = useToast()
@@ -26,6 +33,7 @@ export default function AccountForm({ account, onSubmit, onCancel }: AccountForm
     username: account?.username || '',
     password: account?.password || '',
     use_tls: account?.use_tls ?? true,
+    security: account?.security || 'ssl_tls' as ConnectionSecurity,
     default_post_process_action: account?.default_post_process_action || 'mark_read',
     default_move_to_folder: account?.default_move_to_folder || ''
   })
@@ -39,6 +47,7 @@ export default function AccountForm({ account, onSubmit, onCancel }: AccountForm
         username: account.username,
         password: account.password,
         use_tls: account.use_tls,
+        security: account.security,
         default_post_process_action: account.default_post_process_action,
         default_move_to_folder: account.default_move_to_folder || ''
       })
@@ -181,10 +190,18 @@ export default function AccountForm({ account, onSubmit, onCancel }: AccountForm
     const { name, value, type } = e.target
     const checked = 'checked' in e.target ? e.target.checked : false
     
-    setFormData(prev => ({
-      ...prev,
-      [name]: type === 'checkbox' ? checked : type === 'number' ? parseInt(value) || 0 : value
-    }))
+    setFormData(prev => {
+      const next = {
+        ...prev,
+        [name]: type === 'checkbox' ? checked : type === 'number' ? parseInt(value) || 0 : value
+      }
+      // Suggest the security mode for the port, and keep use_tls in step with it
+      if (name === 'port') {
+        next.security = suggestSecurity(next.port, prev.security)
+      }
+      next.use_tls = next.security !== 'none'
+      return next
+    })
     
     // Clear error when user starts typing
     if (errors[name]) {
@@ -416,24 +433,27 @@ export default function AccountForm({ account, onSubmit, onCancel }: AccountForm
           </div>
         </div>
 
-        {/* Use TLS */}
+        {/* Connection security */}
         <div className="sm:col-span-6">
-          <div className="flex items-center">
-            <input
-              type="checkbox"
-              name="use_tls"
-              id="use_tls"
-              checked={formData.use_tls}
+          <label htmlFor="security" className="block text-sm font-medium text-gray-700">
+            Connection security
+          </label>
+          <div className="mt-1">
+            <select
+              name="security"
+              id="security"
+              value={formData.security}
               onChange={handleChange}
-              aria-describedby="use_tls-description"
-              className="h-4 w-4 text-primary-600 focus:ring-primary-500 border-gray-300 rounded"
-            />
-            <label htmlFor="use_tls" className="ml-2 block text-sm text-gray-900">
-              Use TLS/SSL encryption
-            </label>
+              aria-describedby="security-description"
+              className="block w-full shadow-sm sm:text-sm border-gray-300 rounded-md focus:ring-primary-500 focus:border-primary-500"
+            >
+              <option value="ssl_tls">SSL/TLS (implicit, usually port 993)</option>
+              <option value="starttls">STARTTLS (usually port 143)</option>
+              <option value="none">None (plain text)</option>
+            </select>
           </div>
-          <p id="use_tls-description" className="mt-2 text-sm text-gray-500">
-            Recommended for secure connections. Most email providers require TLS.
+          <p id="security-description" className="mt-2 text-sm text-gray-500">
+            Picked from the port when it changes. Most email providers require TLS; local bridges often use none.
           </p>
         </div>
       </div>
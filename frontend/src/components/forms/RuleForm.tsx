@@ -28,7 +28,8 @@ export default function RuleForm({ rule, onSubmit, onCancel }: RuleFormProps) {
     is_active: rule?.is_active ?? true,
     post_process_action: rule?.post_process_action || '',
     move_to_folder: rule?.move_to_folder || '',
-    inherit_account_defaults: !rule // Default to true for new rules
+    inherit_account_defaults: !rule, // Default to true for new rules
+    observe_only: rule?.observe_only ?? false
   })
 
   // State for manual folder input
@@ -82,7 +83,8 @@ export default function RuleForm({ rule, onSubmit, onCancel }: RuleFormProps) {
         is_active: rule.is_active,
         post_process_action: rule.post_process_action || '',
         move_to_folder: rule.move_to_folder || '',
-        inherit_account_defaults: false // Existing rules don't inherit by default
+        inherit_account_defaults: false, // Existing rules don't inherit by default
+        observe_only: rule.observe_only ?? false
       })
     }
   }, [rule])
@@ -489,7 +491,7 @@ export default function RuleForm({ rule, onSubmit, onCancel }: RuleFormProps) {
               placeholder="important"
             />
             <p className="mt-1 text-xs text-gray-500">
-              Filter emails with this label (Gmail, etc.)
+              Only emails with this Gmail label, e.g. Newsletters or important; ignored on servers without labels
             </p>
           </div>
         </div>
@@ -598,6 +600,26 @@ export default function RuleForm({ rule, onSubmit, onCancel }: RuleFormProps) {
               Inactive rules will not process new emails but existing feeds remain accessible.
             </p>
           </div>
+
+          {/* Observe Only */}
+          <div className="sm:col-span-6">
+            <div className="flex items-center">
+              <input
+                type="checkbox"
+                name="observe_only"
+                id="observe_only"
+                checked={formData.observe_only}
+                onChange={handleChange}
+                className="h-4 w-4 text-primary-600 focus:ring-primary-500 border-gray-300 rounded"
+              />
+              <label htmlFor="observe_only" className="ml-2 block text-sm text-gray-900">
+                Observe only
+              </label>
+            </div>
+            <p className="mt-2 text-sm text-gray-500">
+              Matching emails are recorded in the rule's preview, but no feed items are created and emails are left untouched.
+            </p>
+          </div>
         </div>
       </div>
 
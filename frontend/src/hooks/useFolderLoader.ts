@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback, useRef } from 'react'
 import { accountsApi } from '../api/accounts'
-import type { ConnectionTestResult } from '../types'
+import type { FolderNode } from '../types'
 
 // Fallback folders to use when IMAP fails or is loading
 const FALLBACK_FOLDERS = [
@@ -13,6 +13,13 @@ const FALLBACK_FOLDERS = [
   'Important'
 ]
 
+// Full paths of the selectable folders of a tree, parents first
+const selectableFolders = (nodes: FolderNode[]): string[] =>
+  nodes.flatMap(node => [
+    ...(node.selectable ? [node.name] : []),
+    ...selectableFolders(node.children)
+  ])
+
 // Cache configuration
 const CACHE_EXPIRY_MS = 30 * 60 * 1000 // 30 minutes
 const DEBOUNCE_DELAY_MS = 500
@@ -118,16 +125,16 @@ export const useFolderLoader = (accountId?: string): UseFolderLoaderReturn => {
     setState(prev => ({ ...prev, isLoading: true, error: null }))
 
     try {
-      const response: ConnectionTestResult = await accountsApi.testConnection(id)
+      const tree = await accountsApi.getFolders(id)
       
       // Check if request was aborted
       if (abortControllerRef.current?.signal.aborted) {
         return
       }
 
-      if (response.success && response.folders && response.folders.length > 0) {
+      const folders = selectableFolders(tree)
+      if (folders.length > 0) {
         // Success: use real folders
-        const folders = response.folders
         cacheFolders(id, folders)
         setState(prev => ({
           ...prev,
@@ -136,8 +143,8 @@ export const useFolderLoader = (accountId?: string): UseFolderLoaderReturn => {
           error: null
         }))
       } else {
-        // API succeeded but no folders or connection failed
-        const errorMsg = response.error || 'No folders found or connection failed'
+        // API succeeded but no folders to pick from
+        const errorMsg = 'No folders found'
         console.warn('IMAP folder fetch failed:', errorMsg)
         
         // Try cached folders first, then fallback
@@ -3,12 +3,18 @@ import { useParams, useNavigate, Link } from 'react-router-dom'
 import { feedsApi } from '../api/feeds'
 import type { Feed, FeedItemMetadata } from '../types'
 
+// Items loaded per page
+const PAGE_SIZE = 50
+
 export default function FeedItems() {
   const { id } = useParams<{ id: string }>()
   const navigate = useNavigate()
   const [feed, setFeed] = useState<Feed | null>(null)
   const [items, setItems] = useState<FeedItemMetadata[]>([])
+  const [counts, setCounts] = useState({ total: 0, unread: 0 })
+  const [nextCursor, setNextCursor] = useState<string | undefined>()
   const [loading, setLoading] = useState(true)
+  const [loadingMore, setLoadingMore] = useState(false)
   const [error, setError] = useState<string | null>(null)
 
   useEffect(() => {
@@ -23,10 +29,12 @@ export default function FeedItems() {
       try {
         const [feedData, itemsData] = await Promise.all([
           feedsApi.getById(id),
-          feedsApi.getItemsMetadata(id)
+          feedsApi.getItemsMetadata(id, { limit: PAGE_SIZE })
         ])
         setFeed(feedData)
-        setItems(itemsData)
+        setItems(itemsData.items)
+        setCounts({ total: itemsData.total, unread: itemsData.unread })
+        setNextCursor(itemsData.next_cursor)
       } catch (error) {
         console.error('Failed to load feed items:', error)
         setError(error instanceof Error ? error.message : 'Failed to load feed items')
@@ -38,6 +46,21 @@ export default function FeedItems() {
     loadData()
   }, [id, navigate])
 
+  const loadMore = async () => {
+    if (!id || !nextCursor) return
+    setLoadingMore(true)
+    try {
+      const page = await feedsApi.getItemsMetadata(id, { limit: PAGE_SIZE, cursor: nextCursor })
+      setItems((loaded) => [...loaded, ...page.items])
+      setCounts({ total: page.total, unread: page.unread })
+      setNextCursor(page.next_cursor)
+    } catch (error) {
+      console.error('Failed to load more feed items:', error)
+      setError(error instanceof Error ? error.message : 'Failed to load more feed items')
+    } finally {
+      setLoadingMore(false)
+    }
+  }
 
   const formatBodySize = (size: number | undefined) => {
     if (!size) return 'N/A'
@@ -198,6 +221,17 @@ export default function FeedItems() {
               </li>
             ))}
           </ul>
+          {nextCursor && (
+            <div className="px-4 py-3 sm:px-6 border-t border-gray-200 text-center">
+              <button
+                onClick={loadMore}
+                disabled={loadingMore}
+                className="btn btn-secondary"
+              >
+                {loadingMore ? 'Loading...' : 'Load more'}
+              </button>
+            </div>
+          )}
         </div>
       )}
 
@@ -205,8 +239,8 @@ export default function FeedItems() {
       {items.length > 0 && (
         <div className="bg-gray-50 rounded-lg p-4">
           <div className="flex items-center justify-between text-sm text-gray-600">
-            <span>Total items: {items.length}</span>
-            <span>Showing feed content</span>
+            <span>Total items: {counts.total} ({counts.unread} unread)</span>
+            <span>Showing {items.length} of {counts.total}</span>
           </div>
         </div>
       )}
@@ -223,7 +223,7 @@ export default function Rules() {
               <li><strong>To Address:</strong> Filter emails sent to a specific address</li>
               <li><strong>From Address:</strong> Filter emails from a specific sender</li>
               <li><strong>Subject:</strong> Filter emails containing specific text in the subject</li>
-              <li><strong>Label/Tag:</strong> Filter emails with specific labels (Gmail, etc.)</li>
+              <li><strong>Label/Tag:</strong> Filter emails with a specific Gmail label (ignored on other servers)</li>
             </ul>
             <p className="mt-3">You can combine multiple conditions, and each rule can generate its own RSS/Atom feed.</p>
           </div>
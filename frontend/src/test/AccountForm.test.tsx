@@ -25,6 +25,7 @@ const mockAccount: ImapAccount = {
   username: 'test@example.com',
   password: 'password123',
   use_tls: true,
+  security: 'ssl_tls',
   created_at: '2023-01-01T00:00:00Z',
   updated_at: '2023-01-01T00:00:00Z',
   default_post_process_action: 'do_nothing',
@@ -27,6 +27,7 @@ const mockAccounts: ImapAccount[] = [
     username: 'user@gmail.com',
     password: 'password',
     use_tls: true,
+    security: 'ssl_tls',
     created_at: '2023-01-01T00:00:00Z',
     updated_at: '2023-01-01T00:00:00Z',
     default_post_process_action: 'do_nothing',
@@ -39,6 +40,7 @@ const mockAccounts: ImapAccount[] = [
     username: 'user@outlook.com',
     password: 'password',
     use_tls: true,
+    security: 'ssl_tls',
     created_at: '2023-01-02T00:00:00Z',
     updated_at: '2023-01-02T00:00:00Z',
     default_post_process_action: 'do_nothing',
@@ -26,6 +26,7 @@ function TestComponent() {
             username: 'test@test.com',
             password: 'password',
             use_tls: true,
+            security: 'ssl_tls',
             created_at: new Date().toISOString(),
             updated_at: new Date().toISOString(),
             default_post_process_action: 'do_nothing',
@@ -108,6 +109,7 @@ describe('AppContext', () => {
             username: 'user1@test.com',
             password: 'password1',
             use_tls: true,
+            security: 'ssl_tls',
             created_at: '2023-01-01T00:00:00Z',
             updated_at: '2023-01-01T00:00:00Z',
             default_post_process_action: 'do_nothing',
@@ -120,6 +122,7 @@ describe('AppContext', () => {
             username: 'user2@test.com',
             password: 'password2',
             use_tls: false,
+            security: 'none',
             created_at: '2023-01-01T00:00:00Z',
             updated_at: '2023-01-01T00:00:00Z',
             default_post_process_action: 'mark_read',
@@ -165,6 +168,7 @@ describe('AppContext', () => {
           username: 'test@test.com',
           password: 'password',
           use_tls: true,
+          security: 'ssl_tls',
           created_at: '2023-01-01T00:00:00Z',
           updated_at: '2023-01-01T00:00:00Z',
           default_post_process_action: 'do_nothing',
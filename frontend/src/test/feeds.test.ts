@@ -183,32 +183,45 @@ describe('feedsApi', () => {
 
   describe('getItems', () => {
     it('fetches feed items', async () => {
-      const mockItems = [
-        {
-          id: '1',
-          feed_id: '1',
-          title: 'Item 1',
-          pub_date: '2023-01-01T00:00:00Z',
-          created_at: '2023-01-01T00:00:00Z',
-        }
-      ]
+      const mockPage = {
+        items: [
+          {
+            id: '1',
+            feed_id: '1',
+            title: 'Item 1',
+            pub_date: '2023-01-01T00:00:00Z',
+            created_at: '2023-01-01T00:00:00Z',
+          }
+        ],
+        total: 1,
+        unread: 1,
+      }
 
-      mockClient.get.mockResolvedValueOnce(mockItems)
+      mockClient.get.mockResolvedValueOnce(mockPage)
 
       const result = await feedsApi.getItems('1')
 
       expect(mockClient.get).toHaveBeenCalledWith('/api/feeds/1/items')
-      expect(result).toEqual(mockItems)
+      expect(result).toEqual(mockPage)
     })
 
     it('fetches feed items with limit', async () => {
-      const mockItems: any[] = []
-      mockClient.get.mockResolvedValueOnce(mockItems)
+      mockClient.get.mockResolvedValueOnce({ items: [], total: 0, unread: 0 })
 
-      await feedsApi.getItems('1', 10)
+      await feedsApi.getItems('1', { limit: 10 })
 
       expect(mockClient.get).toHaveBeenCalledWith('/api/feeds/1/items?limit=10')
     })
+
+    it('fetches the next page with the cursor', async () => {
+      mockClient.get.mockResolvedValueOnce({ items: [], total: 0, unread: 0 })
+
+      await feedsApi.getItems('1', { limit: 50, cursor: '0|2023-01-01T00:00:00+00:00|abc' })
+
+      expect(mockClient.get).toHaveBeenCalledWith(
+        '/api/feeds/1/items?limit=50&cursor=0%7C2023-01-01T00%3A00%3A00%2B00%3A00%7Cabc'
+      )
+    })
   })
 
   describe('getRss', () => {
@@ -8,6 +8,10 @@ export interface ApiResponse<T> {
 export type EmailAction = 'do_nothing' | 'mark_read' | 'delete' | 'move_to_folder'
 
 // IMAP Account Types
+export type ConnectionSecurity = 'none' | 'starttls' | 'ssl_tls'
+
+export type AccountProtocol = 'imap' | 'jmap' | 'smtp'
+
 export interface ImapAccount {
   id: string
   name: string
@@ -16,10 +20,25 @@ export interface ImapAccount {
   username: string
   password: string
   use_tls: boolean
+  security: ConnectionSecurity
   created_at: string
   updated_at: string
   default_post_process_action: string
   default_move_to_folder?: string
+  max_bytes_per_second?: number
+  fingerprint?: string
+  tls_pin?: string
+  tls_server_name?: string
+  quota_group_id?: string
+  max_feeds?: number
+  max_items?: number
+  max_processing_minutes_per_day?: number
+  max_connections_per_hour?: number
+  min_fetch_delay_seconds?: number
+  max_messages_per_fetch?: number
+  server_capabilities?: string
+  fetch_strategy?: 'headers' | 'envelope' | 'uid'
+  protocol: AccountProtocol
 }
 
 export interface CreateImapAccountRequest {
@@ -29,13 +48,102 @@ export interface CreateImapAccountRequest {
   username: string
   password: string
   use_tls: boolean
+  security?: ConnectionSecurity
   default_post_process_action?: string
   default_move_to_folder?: string
+  max_bytes_per_second?: number
+  tls_pin?: string
+  tls_server_name?: string
+  quota_group_id?: string
+  max_feeds?: number
+  max_items?: number
+  max_processing_minutes_per_day?: number
+  max_connections_per_hour?: number
+  min_fetch_delay_seconds?: number
+  max_messages_per_fetch?: number
+  protocol?: AccountProtocol
+  allow_duplicate?: boolean
 }
 
-export interface UpdateImapAccountRequest extends CreateImapAccountRequest {}
+export interface UpdateImapAccountRequest extends Omit<CreateImapAccountRequest, 'allow_duplicate'> {}
+
+// Quota Types
+export interface QuotaGroup {
+  id: string
+  name: string
+  max_feeds?: number
+  max_items?: number
+  max_processing_minutes_per_day?: number
+  created_at: string
+  updated_at: string
+}
+
+export interface QuotaGroupRequest {
+  name: string
+  max_feeds?: number
+  max_items?: number
+  max_processing_minutes_per_day?: number
+}
+
+export interface QuotaUsage {
+  feeds: number
+  items: number
+  processing_minutes_today: number
+}
+
+export interface QuotaReport {
+  max_feeds?: number
+  max_items?: number
+  max_processing_minutes_per_day?: number
+  usage: QuotaUsage
+}
+
+export interface AccountQuota {
+  account: QuotaReport
+  group?: QuotaReport
+}
+
+// Sender Types
+export interface SenderAlias {
+  id: string
+  name: string
+  addresses: string[]
+  created_at: string
+  updated_at: string
+}
+
+export interface SenderAliasRequest {
+  name: string
+  addresses: string[]
+}
+
+export interface AccountSenderFilter {
+  id: string
+  imap_account_id: string
+  kind: 'allow' | 'deny'
+  pattern: string
+  created_at: string
+}
+
+export interface SenderFilterRequest {
+  kind: 'allow' | 'deny'
+  pattern: string
+}
+
+export interface SenderStats {
+  sender: string
+  alias_id?: string
+  addresses: string[]
+  item_count: number
+  latest_pub_date?: string
+}
 
 // Email Rule Types
+export type Importance = 'high' | 'normal' | 'low'
+
+export type Rating = 'up' | 'down'
+export type ProcessingOrder = 'newest_first' | 'oldest_first'
+
 export interface EmailRule {
   id: string
   name: string
@@ -50,6 +158,31 @@ export interface EmailRule {
   updated_at: string
   post_process_action: string
   move_to_folder?: string
+  observe_only?: boolean
+  importance?: Importance
+  processing_order: ProcessingOrder
+  last_seen_uid?: number
+  uid_validity?: number
+  post_process_delay_hours?: number
+  match_expression?: string
+  fetch_limit?: number
+  include_seen: boolean
+  include_subfolders: boolean
+  strip_footers: boolean
+  strip_quoted_replies: boolean
+  strip_tracking_pixels: boolean
+  unwrap_tracking_links: boolean
+  // JSON array of PostProcessStep
+  post_process_actions?: string
+}
+
+export type PostProcessStepAction = 'mark_read' | 'add_flag' | 'copy_to_folder' | 'move_to_folder' | 'delete'
+
+export interface PostProcessStep {
+  action: PostProcessStepAction
+  folder?: string
+  flag?: string
+  delay_hours?: number
 }
 
 export interface CreateEmailRuleRequest {
@@ -64,10 +197,145 @@ export interface CreateEmailRuleRequest {
   post_process_action?: string
   move_to_folder?: string
   inherit_account_defaults?: boolean
+  observe_only?: boolean
+  importance?: Importance
+  processing_order?: ProcessingOrder
+  post_process_delay_hours?: number
+  match_expression?: MatchExpression | null
+  fetch_limit?: number
+  include_seen?: boolean
+  include_subfolders?: boolean
+  strip_footers?: boolean
+  strip_quoted_replies?: boolean
+  strip_tracking_pixels?: boolean
+  unwrap_tracking_links?: boolean
+  post_process_actions?: PostProcessStep[] | null
 }
 
 export interface UpdateEmailRuleRequest extends CreateEmailRuleRequest {}
 
+export interface RuleFeedRequest {
+  title?: string
+  description?: string
+  feed_type?: 'rss' | 'atom'
+}
+
+export interface CreateRuleWithFeedRequest {
+  rule: CreateEmailRuleRequest
+  feed: RuleFeedRequest
+}
+
+export interface RuleWithFeedResponse {
+  rule: EmailRule
+  feed: Feed
+}
+
+export type MatchField = 'from' | 'to' | 'subject' | 'body'
+
+export type MatchExpression =
+  | { all: MatchExpression[] }
+  | { any: MatchExpression[] }
+  | { not: MatchExpression }
+  | { regex: { field: MatchField; pattern: string; case_sensitive?: boolean } }
+
+export interface SampleEmail {
+  from?: string
+  to?: string
+  subject?: string
+  body?: string
+}
+
+export interface ValidateMatchExpressionRequest {
+  match_expression: MatchExpression
+  sample?: SampleEmail
+}
+
+export interface MatchExpressionValidation {
+  match_expression: string
+  matches?: boolean
+}
+
+export interface RuleStats {
+  rule_id: string
+  observe_only: boolean
+  match_count: number
+  last_matched_at?: string
+}
+
+export interface BackfillRuleRequest {
+  batch_size?: number
+}
+
+export interface AttachFeedRequest {
+  feed_id: string
+  priority?: number
+}
+
+export interface RuleFeed {
+  feed_id: string
+  feed_title: string
+  priority: number
+  attached: boolean
+  is_active: boolean
+}
+
+export interface TaskStartedResponse {
+  task_id: string
+  message: string
+}
+
+export interface JobStartedResponse {
+  job_id: string
+  message: string
+}
+
+export type ExportFormat = 'epub' | 'html' | 'mbox'
+
+// Result of an export_feed job
+export interface ExportResult {
+  format: ExportFormat
+  filename: string
+  items: number
+  bytes: number
+  download_url: string
+}
+
+export type JobStatus = 'queued' | 'running' | 'completed' | 'failed'
+
+export interface Job {
+  id: string
+  kind: string
+  target_id?: string
+  status: JobStatus
+  // Percentage done; null while a running job's total is unknown
+  progress?: number | null
+  processed: number
+  total?: number | null
+  message?: string
+  error?: string
+  result?: unknown
+  created_at: string
+  started_at?: string
+  finished_at?: string
+}
+
+export interface JobQuery {
+  kind?: string
+  target_id?: string
+  status?: JobStatus
+  limit?: number
+}
+
+export interface RuleMatch {
+  id: string
+  email_rule_id: string
+  email_message_id: string
+  email_subject: string
+  email_from: string
+  email_date: string
+  matched_at: string
+}
+
 // Feed Types
 export interface Feed {
   id: string
@@ -82,8 +350,36 @@ export interface Feed {
   max_items?: number
   max_age_days?: number
   min_items?: number
+  summary_length?: number
+  locale?: string
+  timezone?: string
+  title_template?: string
+  description_template?: string
+  item_limit?: number
+  full_content?: boolean
+  auth_username?: string
+  webhook_url?: string
+  webhook_method?: string
+  webhook_body?: string
+  public_access?: boolean
+  max_pinned?: number
+  auto_titles?: boolean
+  page_css?: string
+  page_header_html?: string
+  page_footer_html?: string
+  page_logo_url?: string
+  append_only: boolean
+  chain_head?: string
+  // Merge emails of the same thread or day into one item
+  digest_mode?: DigestMode
+  // Feeds searched for an email's Message-ID before it is stored; unset uses FEED_DEDUP_SCOPE
+  dedup_scope?: DedupScope
 }
 
+export type DigestMode = 'thread' | 'day'
+
+export type DedupScope = 'feed' | 'account' | 'global'
+
 export interface CreateFeedRequest {
   title: string
   description?: string
@@ -94,10 +390,95 @@ export interface CreateFeedRequest {
   max_items?: number
   max_age_days?: number
   min_items?: number
+  summary_length?: number
+  locale?: string
+  timezone?: string
+  title_template?: string
+  description_template?: string
+  item_limit?: number
+  full_content?: boolean
+  auth_username?: string
+  auth_password?: string
+  webhook_url?: string
+  webhook_method?: string
+  webhook_body?: string
+  public_access?: boolean
+  max_pinned?: number
+  auto_titles?: boolean
+  page_css?: string
+  page_header_html?: string
+  page_footer_html?: string
+  page_logo_url?: string
+  append_only?: boolean
+  digest_mode?: DigestMode
+  dedup_scope?: DedupScope
 }
 
 export interface UpdateFeedRequest extends CreateFeedRequest {}
 
+export interface ChainVerification {
+  feed_id: string
+  append_only: boolean
+  items_checked: number
+  unchained_items: number
+  head?: string
+  valid: boolean
+  problems: string[]
+}
+
+export type ChatPlatform = 'slack' | 'discord' | 'matrix'
+
+export interface ChatIntegration {
+  id: string
+  feed_id: string
+  platform: ChatPlatform
+  webhook_url?: string
+  matrix_homeserver?: string
+  matrix_room_id?: string
+  matrix_access_token?: string
+  message_template?: string
+  rate_limit_per_minute?: number
+  is_active: boolean
+  created_at: string
+  updated_at: string
+}
+
+export interface ChatIntegrationRequest {
+  platform: ChatPlatform
+  webhook_url?: string
+  matrix_homeserver?: string
+  matrix_room_id?: string
+  matrix_access_token?: string
+  message_template?: string
+  rate_limit_per_minute?: number
+  is_active: boolean
+}
+
+export interface WebhookTestResult {
+  status: number
+  item_id?: string
+}
+
+export type DeliveryStatus = 'pending' | 'delivered' | 'failed'
+
+export interface Delivery {
+  id: string
+  feed_id: string
+  item_id?: string
+  kind: 'webhook' | 'chat'
+  queue: string
+  method: string
+  url: string
+  body: string
+  status: DeliveryStatus
+  attempts: number
+  next_attempt_at: string
+  last_error?: string
+  delivered_at?: string
+  created_at: string
+  updated_at: string
+}
+
 // Feed Item Types
 export interface FeedItem {
   id: string
@@ -111,10 +492,35 @@ export interface FeedItem {
   email_subject?: string
   email_from?: string
   email_body?: string
+  email_body_html?: string
+  body_ref?: string
   created_at: string
   is_read?: boolean
   starred?: boolean
+  pinned?: boolean
+  rating?: Rating
   body_size?: number
+  language?: string
+  importance?: Importance
+  category?: string
+  chain_previous?: string
+  chain_hash?: string
+  // Feed the item was first published in, once merged or split into another
+  origin_feed_id?: string
+  // Where the email's list can be unsubscribed from
+  unsubscribe_url?: string
+  unsubscribe_mailto?: string
+  unsubscribe_one_click: boolean
+  in_reply_to?: string
+  email_references?: string
+  // Message-ID of the first email of the item's thread
+  thread_id?: string
+  // Further emails merged into the item by its feed's digest mode, one per line
+  digest_message_ids?: string
+  // Content hash the item's RSS GUID is built from
+  guid?: string
+  // Grows with every new item; what Fever clients page by
+  sync_id?: number
 }
 
 export interface FeedItemMetadata {
@@ -124,13 +530,92 @@ export interface FeedItemMetadata {
   author?: string
   is_read?: boolean
   starred?: boolean
+  pinned?: boolean
+  rating?: Rating
   body_size?: number
   created_at: string
 }
 
+export interface FeedItemsQuery {
+  limit?: number
+  cursor?: string
+  before?: string
+  after?: string
+}
+
+export interface FeedItemsPage {
+  items: FeedItem[]
+  total: number
+  unread: number
+  next_cursor?: string
+}
+
+export interface FeedItemMetadataPage {
+  items: FeedItemMetadata[]
+  total: number
+  unread: number
+  next_cursor?: string
+}
+
+export interface TimelineItem extends FeedItem {
+  feed_title: string
+}
+
+export interface TimelineResponse {
+  items: TimelineItem[]
+  next_cursor?: string
+}
+
+export interface TimelineQuery {
+  limit?: number
+  cursor?: string
+  feeds?: string[]
+  exclude_feeds?: string[]
+  unread_only?: boolean
+}
+
 export interface UpdateFeedItemRequest {
   is_read?: boolean
   starred?: boolean
+  pinned?: boolean
+  // An empty string clears the rating
+  rating?: Rating | ''
+}
+
+export type BulkItemAction = 'mark_read' | 'mark_unread' | 'star' | 'unstar' | 'delete'
+
+// At least one of item_ids, since, until and unread selects the items
+export interface BulkFeedItemsRequest {
+  action: BulkItemAction
+  item_ids?: string[]
+  since?: string
+  until?: string
+  unread?: boolean
+}
+
+export interface BulkFeedItemsResponse {
+  action: BulkItemAction
+  matched: number
+  updated: number
+  // Pinned items a delete left alone
+  skipped: number
+}
+
+export interface ShareFeedItemRequest {
+  expires_in_hours?: number
+}
+
+export interface SharedItemLink {
+  url: string
+  expires_at?: string
+}
+
+export interface UnsubscribeResponse {
+  // 'one_click' when the request was sent; otherwise url has to be opened
+  method: 'one_click' | 'link' | 'mailto'
+  url: string
+  unsubscribed: boolean
+  status?: number
 }
 
 // Processing Types
@@ -146,6 +631,448 @@ export interface ConnectionTestResult {
   folders?: string[]
   error?: string
   message?: string
+  duplicate_of?: string[]
+}
+
+export type DiagnosticStepName = 'dns' | 'tcp' | 'tls' | 'auth' | 'capabilities' | 'folders'
+
+export interface DiagnosticStep {
+  step: DiagnosticStepName
+  status: 'passed' | 'failed' | 'skipped'
+  detail?: string
+  error?: string
+  duration_ms: number
+}
+
+export interface ConnectionDiagnostics {
+  success: boolean
+  failed_step?: DiagnosticStepName
+  steps: DiagnosticStep[]
+}
+
+export interface FolderNode {
+  name: string
+  label: string
+  selectable: boolean
+  messages?: number
+  unseen?: number
+  children: FolderNode[]
+}
+
+export interface TlsFingerprint {
+  certificate_pin: string
+  public_key_pin: string
+  pin?: string
+  matches_pin?: boolean
+}
+
+// Setup Wizard Types
+export interface SetupConnectionRequest {
+  host: string
+  port: number
+  username: string
+  password: string
+  use_tls: boolean
+  security?: ConnectionSecurity
+  tls_pin?: string
+  tls_server_name?: string
+}
+
+export interface SetupConnectionResult {
+  success: boolean
+  failed_step?: 'connect' | 'tls' | 'login' | 'protocol'
+  message: string
+  greeting?: string
+  capabilities: string[]
+  duplicate_of?: string
+}
+
+export interface FolderSuggestion {
+  folder: string
+  messages: number
+  newsletter_score: number
+  top_senders: string[]
+}
+
+export interface SetupFeedRequest {
+  folder: string
+  title?: string
+  from_address?: string
+  to_address?: string
+  subject_contains?: string
+  feed_type?: 'rss' | 'atom'
+}
+
+export interface SetupFinalizeRequest {
+  account: CreateImapAccountRequest
+  feeds: SetupFeedRequest[]
+}
+
+export interface SetupFinalizeResult {
+  account: ImapAccount
+  rules: EmailRule[]
+  feeds: Feed[]
+}
+
+export interface SetupStatus {
+  initialized: boolean
+  has_admin: boolean
+  account_count: number
+  database: {
+    backend: 'sqlite' | 'postgres'
+    status: 'up' | 'down'
+    latency_ms: number
+    error?: string
+  }
+}
+
+export interface InitialSetupRequest {
+  admin: {
+    username: string
+    password: string
+  }
+  account: CreateImapAccountRequest
+  feeds?: SetupFeedRequest[]
+}
+
+export interface InitialSetupResult extends SetupFinalizeResult {
+  admin_username: string
+}
+
+// Import Types
+export type ImportFormat = 'kill_the_newsletter' | 'csv' | 'json'
+
+export interface ImportRequest {
+  format: ImportFormat
+  content: string
+  imap_account_id?: string
+  account?: CreateImapAccountRequest
+  folder?: string
+}
+
+export interface SkippedImport {
+  address: string
+  title: string
+  reason: string
+}
+
+export interface ImportResult {
+  imap_account_id: string
+  account_created: boolean
+  rules: EmailRule[]
+  feeds: Feed[]
+  skipped: SkippedImport[]
+}
+
+// Storage Forecast Types
+export interface FeedForecast {
+  feed_id: string
+  title: string
+  item_count: number
+  unsized_items: number
+  average_item_bytes: number
+  stored_bytes: number
+  items_per_day: number
+  bytes_per_day: number
+  max_items?: number
+  max_age_days?: number
+  steady_state_bytes?: number
+  recommended_max_items?: number
+  recommended_max_age_days?: number
+}
+
+export interface StorageForecast {
+  generated_at: string
+  window_days: number
+  budget_bytes?: number
+  stored_bytes: number
+  bytes_per_day: number
+  steady_state_bytes?: number
+  budget_reached_at?: string
+  days_until_budget?: number
+  feeds: FeedForecast[]
+}
+
+export interface SenderRating {
+  sender: string
+  up: number
+  down: number
+  score: number
+}
+
+export interface RuleRating {
+  rule_id: string
+  rule_name: string
+  up: number
+  down: number
+  score: number
+}
+
+export interface RuleSuggestion {
+  rule_id: string
+  rule_name: string
+  exclude_sender: string
+  up: number
+  down: number
+  reason: string
+}
+
+export interface RuleCost {
+  id: string
+  processing_run_id: string
+  email_rule_id: string
+  folder: string
+  emails_fetched: number
+  evaluations: number
+  matches: number
+  fetch_ms: number
+  match_us: number
+  created_at: string
+}
+
+export interface RuleCostSummary {
+  rule_id: string
+  rule_name: string
+  account_id?: string
+  folder: string
+  runs: number
+  emails_fetched: number
+  evaluations: number
+  matches: number
+  fetch_ms: number
+  match_us: number
+  avg_fetch_ms: number
+  avg_match_us: number
+}
+
+export interface FolderCostSummary {
+  account_id?: string
+  account_name?: string
+  folder: string
+  rules: number
+  fetches: number
+  emails_fetched: number
+  fetch_ms: number
+  avg_fetch_ms: number
+}
+
+export interface RuleCostReport {
+  generated_at: string
+  window_days: number
+  rules: RuleCostSummary[]
+  folders: FolderCostSummary[]
+}
+
+export interface RatingReport {
+  generated_at: string
+  senders: SenderRating[]
+  rules: RuleRating[]
+  suggestions: RuleSuggestion[]
+}
+
+export type Safeguard = 'tighten_retention' | 'pause_ingestion'
+
+export interface StorageStatus {
+  database_bytes: number
+  data_dir: string
+  disk_free_bytes?: number
+  disk_total_bytes?: number
+  max_database_bytes?: number
+  min_free_bytes?: number
+  safeguard: Safeguard
+  retention_tightened: boolean
+  ingestion_paused: boolean
+  warnings: string[]
+}
+
+export interface DayCount {
+  date: string
+  items: number
+}
+
+export interface FeedStats {
+  feed_id: string
+  title: string
+  stored_items: number
+  stored_bytes: number
+  items_per_day: DayCount[]
+}
+
+export interface TopSender {
+  sender: string
+  items: number
+}
+
+export interface AccountStats {
+  account_id: string
+  name: string
+  runs: number
+  failed_runs: number
+  error_rate?: number
+}
+
+export interface StatsResponse {
+  generated_at: string
+  storage: StorageStatus
+  days: number
+  feeds: FeedStats[]
+  top_senders: TopSender[]
+  average_item_bytes?: number
+  accounts: AccountStats[]
+}
+
+export type ChangeKind = 'behavior' | 'breaking' | 'deprecation'
+
+export interface VersionChange {
+  version: string
+  kind: ChangeKind
+  summary: string
+}
+
+// Feed Reorganization Types
+export interface MergeFeedsRequest {
+  source_feed_id: string
+  target_feed_id: string
+  dry_run?: boolean
+}
+
+export interface SplitFeedRequest {
+  title: string
+  email_rule_id?: string
+  sender_domain?: string
+  from_address?: string
+  subject_contains?: string
+  dry_run?: boolean
+}
+
+export interface MovedFeedItem {
+  id: string
+  title: string
+  email_from?: string
+  pub_date: string
+}
+
+export interface FeedReorganizationResponse {
+  dry_run: boolean
+  source_feed_id: string
+  target_feed_id: string | null
+  items_moved: number
+  items: MovedFeedItem[]
+}
+
+export interface VersionResponse {
+  version: string
+  git_hash?: string | null
+  schema_version?: string | null
+  previous_version?: string | null
+  first_run_at?: string | null
+  changes: VersionChange[]
+}
+
+export type RetentionTable = 'processing_runs' | 'rule_matches' | 'deliveries' | 'rule_costs'
+
+export interface RetentionSetting {
+  table: RetentionTable
+  max_rows?: number | null
+  max_age_days?: number | null
+  rows_purged: number
+  last_purged_at?: string | null
+}
+
+// Retention of feeds that leave a limit unset; null limits are off
+export interface FeedRetention {
+  max_items: number | null
+  max_age_days: number | null
+  min_items: number | null
+}
+
+export interface SettingsResponse {
+  retention: RetentionSetting[]
+  public_base_url: string | null
+  feed_retention: FeedRetention
+  notifications: NotificationSettings
+  // null while the Fever API is off
+  fever_username: string | null
+  values: RuntimeSetting[]
+}
+
+export type NotificationChannelKind = 'email' | 'webhook' | 'ntfy' | 'gotify'
+
+export interface NotificationChannel {
+  kind: NotificationChannelKind
+  // Webhook URL, ntfy topic URL or Gotify server URL
+  url?: string | null
+  // ntfy access token or Gotify application token
+  token?: string | null
+  priority?: number | null
+  smtp_host?: string | null
+  smtp_port?: number | null
+  smtp_security?: 'starttls' | 'tls' | 'none'
+  smtp_username?: string | null
+  smtp_password?: string | null
+  email_from?: string | null
+  email_to?: string[]
+}
+
+export interface NotificationSettings {
+  failure_threshold: number
+  alert_on_auth_error: boolean
+  channels: NotificationChannel[]
+}
+
+export interface NotificationTestResult {
+  channel: number
+  kind: NotificationChannelKind
+  delivered: boolean
+  error: string | null
+}
+
+export type SettingSource = 'database' | 'environment' | 'default'
+
+export interface RuntimeSetting {
+  key: string
+  value: string
+  source: SettingSource
+  kind: 'integer' | 'origin_list'
+  env_var: string
+  default: string
+}
+
+export interface RetentionSettingRequest {
+  table: RetentionTable
+  max_rows?: number | null
+  max_age_days?: number | null
+}
+
+export interface UpdateSettingsRequest {
+  retention?: RetentionSettingRequest[]
+  // An empty string removes the stored URL
+  public_base_url?: string
+  feed_retention?: FeedRetention
+  // Replaces every channel
+  notifications?: NotificationSettings
+  // An empty username turns the Fever API off
+  fever?: { username: string; password: string }
+  // null removes the stored value so the environment variable applies again
+  values?: Record<string, string | null>
+}
+
+export interface FeedCleanup {
+  feed_id: string
+  title: string
+  items_removed: number
+}
+
+export interface CleanupResult {
+  feeds_processed: number
+  items_removed: number
+  errors: number
+  feeds: FeedCleanup[]
+}
+
+export interface CleanupReport {
+  feeds: CleanupResult
+  rows_purged: Record<string, number>
 }
 
 // App State Types